@@ -1,7 +1,4 @@
-use std::{
-    ops::Bound,
-    time::Duration,
-};
+use std::time::Duration;
 
 use async_trait::async_trait;
 use bytes::Bytes;
@@ -9,11 +6,14 @@ use common::{
     components::{
         CanonicalizedComponentFunctionPath,
         ComponentId,
+        ComponentPath,
         ExportPath,
         PublicFunctionPath,
     },
     http::ResolvedHostname,
+    paths::FieldPath,
     pause::PauseClient,
+    query::IndexRange,
     runtime::Runtime,
     types::{
         AllowedVisibility,
@@ -31,24 +31,32 @@ use database::{
     Token,
 };
 use file_storage::{
-    FileRangeStream,
+    image_transform::ImageTransform,
+    FileRange,
     FileStream,
 };
 use futures::{
     future::BoxFuture,
     stream::BoxStream,
     FutureExt,
+    StreamExt,
 };
+use futures_async_stream::try_stream;
 use headers::{
     ContentLength,
     ContentType,
+    Range,
 };
 use isolate::{
     HttpActionRequest,
     HttpActionResponseStreamer,
 };
-use keybroker::Identity;
+use keybroker::{
+    Identity,
+    StoreFileConstraints,
+};
 use model::{
+    cors_config::types::CorsConfig,
     file_storage::FileStorageId,
     session_requests::types::SessionRequestIdentifier,
 };
@@ -64,11 +72,14 @@ use value::{
 };
 
 use crate::{
+    file_storage_upload::FileUploadStatus,
     Application,
     FunctionError,
     FunctionReturn,
     RedactedActionError,
     RedactedActionReturn,
+    RedactedMutationBatchError,
+    RedactedMutationBatchReturn,
     RedactedMutationError,
     RedactedMutationReturn,
     RedactedQueryReturn,
@@ -141,6 +152,17 @@ pub trait ApplicationApi: Send + Sync {
         mutation_identifier: Option<SessionRequestIdentifier>,
     ) -> anyhow::Result<Result<RedactedMutationReturn, RedactedMutationError>>;
 
+    /// Execute public mutations on the root app in order in a single
+    /// transaction. Either all of them commit or none do.
+    async fn execute_public_mutation_batch(
+        &self,
+        host: &ResolvedHostname,
+        request_id: RequestId,
+        identity: Identity,
+        mutations: Vec<(ExportPath, Vec<JsonValue>)>,
+        caller: FunctionCaller,
+    ) -> anyhow::Result<Result<RedactedMutationBatchReturn, RedactedMutationBatchError>>;
+
     /// Execute an admin mutation for a particular component for the dashboard.
     async fn execute_admin_mutation(
         &self,
@@ -205,13 +227,20 @@ pub trait ApplicationApi: Send + Sync {
         request_id: RequestId,
     ) -> anyhow::Result<RepeatableTimestamp>;
 
+    /// The deployment's CORS policy for HTTP actions, if it has one.
+    async fn http_action_cors_config(
+        &self,
+        host: &ResolvedHostname,
+        request_id: RequestId,
+    ) -> anyhow::Result<Option<CorsConfig>>;
+
     async fn check_store_file_authorization(
         &self,
         host: &ResolvedHostname,
         request_id: RequestId,
         token: &str,
         validity: Duration,
-    ) -> anyhow::Result<ComponentId>;
+    ) -> anyhow::Result<(ComponentId, StoreFileConstraints)>;
 
     async fn store_file(
         &self,
@@ -222,9 +251,55 @@ pub trait ApplicationApi: Send + Sync {
         content_length: Option<ContentLength>,
         content_type: Option<ContentType>,
         expected_sha256: Option<Sha256Digest>,
+        filename: Option<String>,
+        tags: BTreeMap<String, String>,
         body: BoxStream<'_, anyhow::Result<Bytes>>,
     ) -> anyhow::Result<DeveloperDocumentId>;
 
+    async fn start_file_upload(
+        &self,
+        host: &ResolvedHostname,
+        request_id: RequestId,
+        component: ComponentId,
+        content_type: Option<ContentType>,
+        upload_length: Option<u64>,
+    ) -> anyhow::Result<DeveloperDocumentId>;
+
+    async fn file_upload_status(
+        &self,
+        host: &ResolvedHostname,
+        request_id: RequestId,
+        component: ComponentId,
+        upload_id: DeveloperDocumentId,
+    ) -> anyhow::Result<FileUploadStatus>;
+
+    async fn append_file_upload_chunk(
+        &self,
+        host: &ResolvedHostname,
+        request_id: RequestId,
+        component: ComponentId,
+        upload_id: DeveloperDocumentId,
+        offset: u64,
+        body: BoxStream<'_, anyhow::Result<Bytes>>,
+    ) -> anyhow::Result<u64>;
+
+    async fn finish_file_upload(
+        &self,
+        host: &ResolvedHostname,
+        request_id: RequestId,
+        component: ComponentId,
+        upload_id: DeveloperDocumentId,
+        expected_sha256: Sha256Digest,
+    ) -> anyhow::Result<DeveloperDocumentId>;
+
+    async fn cancel_file_upload(
+        &self,
+        host: &ResolvedHostname,
+        request_id: RequestId,
+        component: ComponentId,
+        upload_id: DeveloperDocumentId,
+    ) -> anyhow::Result<()>;
+
     async fn get_file_range(
         &self,
         host: &ResolvedHostname,
@@ -232,8 +307,8 @@ pub trait ApplicationApi: Send + Sync {
         origin: ConvexOrigin,
         component: ComponentId,
         file_storage_id: FileStorageId,
-        range: (Bound<u64>, Bound<u64>),
-    ) -> anyhow::Result<FileRangeStream>;
+        range: Range,
+    ) -> anyhow::Result<FileRange>;
 
     async fn get_file(
         &self,
@@ -244,6 +319,17 @@ pub trait ApplicationApi: Send + Sync {
         file_storage_id: FileStorageId,
     ) -> anyhow::Result<FileStream>;
 
+    /// Get a file resized or re-encoded with `transform`.
+    async fn get_transformed_file(
+        &self,
+        host: &ResolvedHostname,
+        request_id: RequestId,
+        origin: ConvexOrigin,
+        component: ComponentId,
+        file_storage_id: FileStorageId,
+        transform: ImageTransform,
+    ) -> anyhow::Result<FileStream>;
+
     // Returns a fallible subscription client. The implementation is not required to
     // recover from transient errors with the underlying connection or stream. The
     // client is responsible to Drop the client and create a new one on any system
@@ -255,6 +341,19 @@ pub trait ApplicationApi: Send + Sync {
         &self,
         host: &ResolvedHostname,
     ) -> anyhow::Result<Box<dyn SubscriptionClient>>;
+
+    // Returns a stream that yields the current value of the aggregate over
+    // `index_range` and then a new value each time it changes. The stream ends
+    // after yielding an error.
+    async fn subscribe_live_aggregate(
+        &self,
+        host: &ResolvedHostname,
+        request_id: RequestId,
+        identity: Identity,
+        component_path: ComponentPath,
+        index_range: IndexRange,
+        sum_field: Option<FieldPath>,
+    ) -> anyhow::Result<BoxStream<'static, anyhow::Result<LiveAggregateUpdate>>>;
 }
 
 // Implements ApplicationApi via Application.
@@ -359,6 +458,30 @@ impl<RT: Runtime> ApplicationApi for Application<RT> {
         .await
     }
 
+    async fn execute_public_mutation_batch(
+        &self,
+        _host: &ResolvedHostname,
+        request_id: RequestId,
+        identity: Identity,
+        mutations: Vec<(ExportPath, Vec<JsonValue>)>,
+        caller: FunctionCaller,
+    ) -> anyhow::Result<Result<RedactedMutationBatchReturn, RedactedMutationBatchError>> {
+        anyhow::ensure!(
+            caller.allowed_visibility() == AllowedVisibility::PublicOnly,
+            "This method should not be used by internal callers."
+        );
+        self.mutation_batch_udf(
+            request_id,
+            mutations
+                .into_iter()
+                .map(|(path, args)| (PublicFunctionPath::RootExport(path), args))
+                .collect(),
+            identity,
+            caller,
+        )
+        .await
+    }
+
     async fn execute_admin_mutation(
         &self,
         _host: &ResolvedHostname,
@@ -474,13 +597,21 @@ impl<RT: Runtime> ApplicationApi for Application<RT> {
         .await
     }
 
+    async fn http_action_cors_config(
+        &self,
+        _host: &ResolvedHostname,
+        _request_id: RequestId,
+    ) -> anyhow::Result<Option<CorsConfig>> {
+        self.get_cors_config(Identity::system()).await
+    }
+
     async fn check_store_file_authorization(
         &self,
         _host: &ResolvedHostname,
         _request_id: RequestId,
         token: &str,
         validity: Duration,
-    ) -> anyhow::Result<ComponentId> {
+    ) -> anyhow::Result<(ComponentId, StoreFileConstraints)> {
         self.key_broker()
             .check_store_file_authorization(&self.runtime, token, validity)
     }
@@ -494,6 +625,8 @@ impl<RT: Runtime> ApplicationApi for Application<RT> {
         content_length: Option<ContentLength>,
         content_type: Option<ContentType>,
         expected_sha256: Option<Sha256Digest>,
+        filename: Option<String>,
+        tags: BTreeMap<String, String>,
         body: BoxStream<'_, anyhow::Result<Bytes>>,
     ) -> anyhow::Result<DeveloperDocumentId> {
         self.store_file(
@@ -501,11 +634,70 @@ impl<RT: Runtime> ApplicationApi for Application<RT> {
             content_length,
             content_type,
             expected_sha256,
+            filename,
+            tags,
             body,
         )
         .await
     }
 
+    async fn start_file_upload(
+        &self,
+        _host: &ResolvedHostname,
+        _request_id: RequestId,
+        component: ComponentId,
+        content_type: Option<ContentType>,
+        upload_length: Option<u64>,
+    ) -> anyhow::Result<DeveloperDocumentId> {
+        self.start_file_upload(component, content_type, upload_length)
+            .await
+    }
+
+    async fn file_upload_status(
+        &self,
+        _host: &ResolvedHostname,
+        _request_id: RequestId,
+        component: ComponentId,
+        upload_id: DeveloperDocumentId,
+    ) -> anyhow::Result<FileUploadStatus> {
+        self.file_upload_status(component, upload_id).await
+    }
+
+    async fn append_file_upload_chunk(
+        &self,
+        _host: &ResolvedHostname,
+        _request_id: RequestId,
+        component: ComponentId,
+        upload_id: DeveloperDocumentId,
+        offset: u64,
+        body: BoxStream<'_, anyhow::Result<Bytes>>,
+    ) -> anyhow::Result<u64> {
+        self.append_file_upload_chunk(component, upload_id, offset, body)
+            .await
+    }
+
+    async fn finish_file_upload(
+        &self,
+        _host: &ResolvedHostname,
+        _request_id: RequestId,
+        component: ComponentId,
+        upload_id: DeveloperDocumentId,
+        expected_sha256: Sha256Digest,
+    ) -> anyhow::Result<DeveloperDocumentId> {
+        self.finish_file_upload(component, upload_id, expected_sha256)
+            .await
+    }
+
+    async fn cancel_file_upload(
+        &self,
+        _host: &ResolvedHostname,
+        _request_id: RequestId,
+        component: ComponentId,
+        upload_id: DeveloperDocumentId,
+    ) -> anyhow::Result<()> {
+        self.cancel_file_upload(component, upload_id).await
+    }
+
     async fn get_file_range(
         &self,
         _host: &ResolvedHostname,
@@ -513,8 +705,8 @@ impl<RT: Runtime> ApplicationApi for Application<RT> {
         _origin: ConvexOrigin,
         component: ComponentId,
         file_storage_id: FileStorageId,
-        range: (Bound<u64>, Bound<u64>),
-    ) -> anyhow::Result<FileRangeStream> {
+        range: Range,
+    ) -> anyhow::Result<FileRange> {
         self.get_file_range(component, file_storage_id, range).await
     }
 
@@ -529,6 +721,19 @@ impl<RT: Runtime> ApplicationApi for Application<RT> {
         self.get_file(component, file_storage_id).await
     }
 
+    async fn get_transformed_file(
+        &self,
+        _host: &ResolvedHostname,
+        _request_id: RequestId,
+        _origin: ConvexOrigin,
+        component: ComponentId,
+        file_storage_id: FileStorageId,
+        transform: ImageTransform,
+    ) -> anyhow::Result<FileStream> {
+        self.get_transformed_file(component, file_storage_id, transform)
+            .await
+    }
+
     async fn subscription_client(
         &self,
         _host: &ResolvedHostname,
@@ -537,6 +742,63 @@ impl<RT: Runtime> ApplicationApi for Application<RT> {
             database: self.database.clone(),
         }))
     }
+
+    async fn subscribe_live_aggregate(
+        &self,
+        _host: &ResolvedHostname,
+        _request_id: RequestId,
+        identity: Identity,
+        component_path: ComponentPath,
+        index_range: IndexRange,
+        sum_field: Option<FieldPath>,
+    ) -> anyhow::Result<BoxStream<'static, anyhow::Result<LiveAggregateUpdate>>> {
+        Ok(live_aggregate_updates(
+            self.clone(),
+            identity,
+            component_path,
+            index_range,
+            sum_field,
+        )
+        .boxed())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LiveAggregateUpdate {
+    pub ts: Timestamp,
+    pub count: u64,
+    pub sum: f64,
+}
+
+#[try_stream(ok = LiveAggregateUpdate, error = anyhow::Error)]
+async fn live_aggregate_updates<RT: Runtime>(
+    application: Application<RT>,
+    identity: Identity,
+    component_path: ComponentPath,
+    index_range: IndexRange,
+    sum_field: Option<FieldPath>,
+) {
+    loop {
+        let mut subscription = application
+            .subscribe_live_aggregate(
+                identity.clone(),
+                component_path.clone(),
+                index_range.clone(),
+                sum_field.clone(),
+            )
+            .await?;
+        let mut current = subscription.current();
+        while let Some((ts, value)) = current {
+            yield LiveAggregateUpdate {
+                ts,
+                count: value.count,
+                sum: value.sum,
+            };
+            current = subscription.wait_for_change().await;
+        }
+        // The subscriptions worker couldn't keep the aggregate up to date, so
+        // recompute it from scratch.
+    }
 }
 
 #[async_trait]
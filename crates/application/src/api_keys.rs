@@ -0,0 +1,151 @@
+//! Deployment API keys, for backend services that call public functions.
+//!
+//! Each key authenticates as a user whose subject and custom claims are
+//! configured when the key is created, so functions can check
+//! `ctx.auth.getUserIdentity()` as they would for a logged in user. The issuer
+//! is the deployment's origin followed by `/api_keys`, so functions can tell
+//! services apart from users.
+use std::{
+    collections::BTreeMap,
+    time::{
+        Duration,
+        SystemTime,
+    },
+};
+
+use common::{
+    document::ParsedDocument,
+    runtime::Runtime,
+};
+use database::unauthorized_error;
+use errors::ErrorMetadata;
+use keybroker::{
+    Identity,
+    UserIdentity,
+};
+use model::api_keys::{
+    api_key_hash,
+    types::ApiKey,
+    ApiKeysModel,
+    API_KEY_PREFIX,
+};
+use rand::{
+    distributions::Alphanumeric,
+    Rng,
+};
+use serde_json::Value as JsonValue;
+use value::DeveloperDocumentId;
+
+use crate::Application;
+
+const API_KEY_SECRET_LENGTH: usize = 40;
+
+/// Keys don't expire, so identities from them are valid until the key is
+/// deleted. The expiration only needs to outlast any request or sync session.
+const API_KEY_IDENTITY_TTL: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+/// Claims that are set from the key's subject and issuer, or that mean
+/// something to JWT validation, so can't be custom claims.
+const RESERVED_CLAIMS: &[&str] = &["sub", "iss", "aud", "exp", "iat", "nbf", "jti"];
+
+fn invalid_api_key(msg: String) -> anyhow::Error {
+    ErrorMetadata::bad_request("InvalidApiKey", msg).into()
+}
+
+impl<RT: Runtime> Application<RT> {
+    /// Creates an API key that authenticates as `subject` with
+    /// `custom_claims`. Returns the key's ID and the key, which isn't stored
+    /// and can't be retrieved later.
+    pub async fn create_api_key(
+        &self,
+        identity: Identity,
+        name: String,
+        subject: String,
+        custom_claims: BTreeMap<String, JsonValue>,
+    ) -> anyhow::Result<(String, String)> {
+        anyhow::ensure!(
+            identity.is_admin() || identity.is_system(),
+            unauthorized_error("create_api_key")
+        );
+        if name.is_empty() || subject.is_empty() {
+            return Err(invalid_api_key(
+                "API keys need a name and subject".to_string(),
+            ));
+        }
+        if let Some(claim) = custom_claims
+            .keys()
+            .find(|claim| RESERVED_CLAIMS.contains(&claim.as_str()))
+        {
+            return Err(invalid_api_key(format!("{claim} can't be a custom claim")));
+        }
+        let custom_claims = custom_claims
+            .into_iter()
+            .map(|(claim, value)| anyhow::Ok((claim, serde_json::to_string(&value)?)))
+            .collect::<anyhow::Result<_>>()?;
+        let secret: String = self
+            .runtime
+            .rng()
+            .sample_iter(Alphanumeric)
+            .take(API_KEY_SECRET_LENGTH)
+            .map(char::from)
+            .collect();
+        let key = format!("{API_KEY_PREFIX}{secret}");
+        let mut tx = self.begin(identity).await?;
+        let id = ApiKeysModel::new(&mut tx)
+            .insert(ApiKey {
+                name,
+                key_hash: api_key_hash(&key),
+                subject,
+                custom_claims,
+                created_at: self.runtime.unix_timestamp(),
+            })
+            .await?;
+        self.commit(tx, "create_api_key").await?;
+        Ok((DeveloperDocumentId::from(id).encode(), key))
+    }
+
+    pub async fn list_api_keys(
+        &self,
+        identity: Identity,
+    ) -> anyhow::Result<Vec<ParsedDocument<ApiKey>>> {
+        let mut tx = self.begin(identity).await?;
+        ApiKeysModel::new(&mut tx).list().await
+    }
+
+    /// Stops accepting the key with ID `id`. Returns whether it existed.
+    pub async fn delete_api_key(&self, identity: Identity, id: String) -> anyhow::Result<bool> {
+        let mut tx = self.begin(identity).await?;
+        let deleted = ApiKeysModel::new(&mut tx).delete(&id).await?;
+        if deleted {
+            self.commit(tx, "delete_api_key").await?;
+        }
+        Ok(deleted)
+    }
+
+    /// The identity for an API key. Called by [`Application::authenticate`]
+    /// for ID tokens that start with [`API_KEY_PREFIX`].
+    pub(crate) async fn authenticate_api_key(
+        &self,
+        key: String,
+        system_time: SystemTime,
+    ) -> anyhow::Result<UserIdentity> {
+        let mut tx = self.begin(Identity::system()).await?;
+        let api_key = ApiKeysModel::new(&mut tx)
+            .get_by_key(&key)
+            .await?
+            .ok_or_else(|| {
+                ErrorMetadata::unauthenticated(
+                    "InvalidApiKey",
+                    "The API key is invalid or was deleted",
+                )
+            })?
+            .into_value();
+        Ok(UserIdentity::from_api_key(
+            format!("{}/api_keys", self.convex_origin),
+            api_key.subject,
+            api_key.custom_claims,
+            system_time + API_KEY_IDENTITY_TTL,
+            key,
+        ))
+    }
+}
@@ -157,3 +157,44 @@ pub fn function_run_timer(udf_type: UdfType) -> StatusTimer {
     timer.add_label(udf_type.metric_label());
     timer
 }
+
+pub enum RegionalActionDispatch {
+    /// Ran on the runners for the requested region.
+    Pinned,
+    /// The region has no runners, so the action ran on the default runners.
+    Fallback,
+    /// The region has no runners and `ACTION_REGION_STRICT` is set.
+    Rejected,
+}
+
+register_convex_counter!(
+    APPLICATION_REGIONAL_ACTION_DISPATCH_TOTAL,
+    "Number of Node actions pinned to a region, by how they were dispatched",
+    &["region", "dispatch"]
+);
+pub fn log_regional_action_dispatch(region: &str, dispatch: RegionalActionDispatch) {
+    let dispatch_value = match dispatch {
+        RegionalActionDispatch::Pinned => "pinned",
+        RegionalActionDispatch::Fallback => "fallback",
+        RegionalActionDispatch::Rejected => "rejected",
+    };
+    log_counter_with_labels(
+        &APPLICATION_REGIONAL_ACTION_DISPATCH_TOTAL,
+        1,
+        vec![
+            StaticMetricLabel::new("region", region.to_string()),
+            StaticMetricLabel::new("dispatch", dispatch_value),
+        ],
+    );
+}
+
+register_convex_histogram!(
+    APPLICATION_REGIONAL_ACTION_SECONDS,
+    "Time taken to run a Node action on a region's action runners",
+    &[STATUS_LABEL[0], "region"]
+);
+pub fn regional_action_timer(region: &str) -> StatusTimer {
+    let mut timer = StatusTimer::new(&APPLICATION_REGIONAL_ACTION_SECONDS);
+    timer.add_label(StaticMetricLabel::new("region", region.to_string()));
+    timer
+}
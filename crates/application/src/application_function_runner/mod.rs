@@ -35,6 +35,7 @@ use common::{
     execution_context::ExecutionContext,
     http::fetch::FetchClient,
     knobs::{
+        ACTION_REGION_STRICT,
         APPLICATION_FUNCTION_RUNNER_SEMAPHORE_TIMEOUT,
         APPLICATION_MAX_CONCURRENT_HTTP_ACTIONS,
         APPLICATION_MAX_CONCURRENT_MUTATIONS,
@@ -43,6 +44,7 @@ use common::{
         APPLICATION_MAX_CONCURRENT_V8_ACTIONS,
         BACKEND_ISOLATE_ACTIVE_THREADS_PERCENT,
         ISOLATE_MAX_USER_HEAP_SIZE,
+        MAX_MUTATION_BATCH_SIZE,
         UDF_EXECUTOR_OCC_INITIAL_BACKOFF,
         UDF_EXECUTOR_OCC_MAX_BACKOFF,
         UDF_EXECUTOR_OCC_MAX_RETRIES,
@@ -78,6 +80,8 @@ use common::{
 use database::{
     unauthorized_error,
     Database,
+    HybridSearch,
+    HybridSearchResult,
     Token,
     Transaction,
 };
@@ -126,7 +130,9 @@ use keybroker::{
     InstanceSecret,
     KeyBroker,
 };
+use minitrace::local::LocalSpan;
 use model::{
+    action_checkpoints::ActionCheckpointsModel,
     backend_state::BackendStateModel,
     components::handles::FunctionHandlesModel,
     config::{
@@ -156,9 +162,13 @@ use model::{
         },
         ModuleModel,
     },
-    scheduled_jobs::VirtualSchedulerModel,
+    scheduled_jobs::{
+        types::IdempotencyKey,
+        VirtualSchedulerModel,
+    },
     session_requests::{
         types::{
+            mutation_args_hash,
             SessionRequestIdentifier,
             SessionRequestOutcome,
             SessionRequestRecord,
@@ -169,7 +179,10 @@ use model::{
         types::SourcePackage,
         SourcePackageModel,
     },
-    udf_config::types::UdfConfig,
+    udf_config::{
+        types::UdfConfig,
+        UdfConfigModel,
+    },
 };
 use node_executor::{
     Actions,
@@ -199,9 +212,12 @@ use self::metrics::{
     function_waiter_timer,
     log_occ_retries,
     log_outstanding_functions,
+    log_regional_action_dispatch,
     log_udf_executor_result,
     mutation_timer,
+    regional_action_timer,
     OutstandingFunctionState,
+    RegionalActionDispatch,
     UdfExecutorResult,
 };
 use crate::{
@@ -216,8 +232,12 @@ use crate::{
         ActionCompletion,
         FunctionExecutionLog,
     },
+    metrics::log_function_occ_conflict,
+    replication_worker::read_only_replica_error,
     ActionError,
     ActionReturn,
+    MutationBatchError,
+    MutationBatchReturn,
     MutationError,
     MutationReturn,
     QueryReturn,
@@ -290,6 +310,12 @@ impl<RT: Runtime> FunctionRouter<RT> {
         context: ExecutionContext,
     ) -> anyhow::Result<(Transaction<RT>, FunctionOutcome)> {
         anyhow::ensure!(udf_type == UdfType::Query || udf_type == UdfType::Mutation);
+        LocalSpan::add_properties(|| {
+            [
+                ("udf_type", udf_type.to_string()),
+                ("udf_path", format!("{:?}", path_and_args.path().udf_path)),
+            ]
+        });
         // All queries and mutations are run in the isolate environment.
         let timer = function_total_timer(ModuleEnvironment::Isolate, udf_type);
         let (tx, outcome) = self
@@ -318,6 +344,7 @@ impl<RT: Runtime> FunctionRouter<RT> {
         log_line_sender: mpsc::UnboundedSender<LogLine>,
         context: ExecutionContext,
     ) -> anyhow::Result<ActionOutcome> {
+        LocalSpan::add_property(|| ("udf_path", format!("{:?}", path_and_args.path().udf_path)));
         let (_, outcome) = self
             .function_runner_execute(
                 tx,
@@ -346,6 +373,7 @@ impl<RT: Runtime> FunctionRouter<RT> {
         http_action_metadata: HttpActionMetadata,
         context: ExecutionContext,
     ) -> anyhow::Result<HttpActionOutcome> {
+        LocalSpan::add_property(|| ("http_path", http_action_metadata.routed_path.to_string()));
         let (_, outcome) = self
             .function_runner_execute(
                 tx,
@@ -556,6 +584,13 @@ impl<'a> Drop for RequestGuard<'a> {
     }
 }
 
+/// The mutation a session request called and a hash of its arguments, to
+/// check that retries repeat it.
+struct MutationCall {
+    udf_path: String,
+    args_hash: String,
+}
+
 /// Executes UDFs for backends.
 ///
 /// This struct directly executes http and node actions. Queries, Mutations and
@@ -572,6 +607,8 @@ pub struct ApplicationFunctionRunner<RT: Runtime> {
     analyze_isolate: IsolateClient<RT>,
     http_actions: IsolateClient<RT>,
     node_actions: Actions,
+    /// Action runners for Node actions pinned to a region, by region.
+    regional_node_actions: BTreeMap<String, Actions>,
 
     pub(crate) module_cache: Arc<dyn ModuleLoader<RT>>,
     modules_storage: Arc<dyn Storage>,
@@ -583,6 +620,9 @@ pub struct ApplicationFunctionRunner<RT: Runtime> {
     system_env_vars: BTreeMap<EnvVarName, EnvVarValue>,
     node_action_limiter: Limiter,
     fetch_client: Arc<dyn FetchClient>,
+    /// Whether this deployment is a replica of another deployment, in which
+    /// case mutations are rejected.
+    read_only_replica: bool,
 }
 
 impl<RT: Runtime> ApplicationFunctionRunner<RT> {
@@ -594,12 +634,14 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
         key_broker: KeyBroker,
         function_runner: Arc<dyn FunctionRunner<RT>>,
         node_actions: Actions,
+        regional_node_actions: BTreeMap<String, Actions>,
         file_storage: TransactionalFileStorage<RT>,
         modules_storage: Arc<dyn Storage>,
         module_cache: Arc<dyn ModuleLoader<RT>>,
         function_log: FunctionExecutionLog<RT>,
         system_env_vars: BTreeMap<EnvVarName, EnvVarValue>,
         fetch_client: Arc<dyn FetchClient>,
+        read_only_replica: bool,
     ) -> Self {
         // We limit the isolates to only consume fraction of the available
         // cores leaving the rest for tokio. This is still over-provisioning
@@ -667,6 +709,7 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
             analyze_isolate,
             http_actions,
             node_actions,
+            regional_node_actions,
             module_cache,
             modules_storage,
             file_storage,
@@ -679,6 +722,7 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
                 *APPLICATION_MAX_CONCURRENT_NODE_ACTIONS,
             ),
             fetch_client,
+            read_only_replica,
         }
     }
 
@@ -686,6 +730,9 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
         self.analyze_isolate.shutdown().await?;
         self.http_actions.shutdown().await?;
         self.node_actions.shutdown();
+        for actions in self.regional_node_actions.values() {
+            actions.shutdown();
+        }
         Ok(())
     }
 
@@ -799,6 +846,9 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
         caller: FunctionCaller,
         pause_client: PauseClient,
     ) -> anyhow::Result<Result<MutationReturn, MutationError>> {
+        if self.read_only_replica {
+            anyhow::bail!(read_only_replica_error());
+        }
         if path.is_system() && !(identity.is_admin() || identity.is_system()) {
             anyhow::bail!(unauthorized_error("mutation"));
         }
@@ -812,6 +862,21 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
             },
         };
         let udf_path_string = (!path.is_system()).then_some(path.udf_path().to_string());
+        // Unauthenticated callers all have the same identity, so their mutation
+        // IDs only deduplicate within their session, by request ID.
+        let mutation_identifier = mutation_identifier.map(|mut identifier| {
+            if matches!(identity, Identity::Unknown) {
+                identifier.mutation_id = None;
+            }
+            identifier
+        });
+        let mutation_call = match mutation_identifier {
+            Some(_) => Some(MutationCall {
+                udf_path: path.udf_path().to_string(),
+                args_hash: mutation_args_hash(&arguments)?,
+            }),
+            None => None,
+        };
 
         let mut backoff = Backoff::new(
             *UDF_EXECUTOR_OCC_INITIAL_BACKOFF,
@@ -834,7 +899,7 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
 
             // Return the previous execution's result if the mutation was committed already.
             if let Some(result) = self
-                .check_mutation_status(&mut tx, &mutation_identifier)
+                .check_mutation_status(&mut tx, &mutation_identifier, &mutation_call)
                 .await?
             {
                 return Ok(result);
@@ -867,7 +932,7 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
 
             // Save a CommittedMutation object so we won't rerun this mutation if
             // successful.
-            self.write_mutation_status(&mut tx, &mutation_identifier, &outcome)
+            self.write_mutation_status(&mut tx, &mutation_identifier, &mutation_call, &outcome)
                 .await?;
 
             let stats = tx.take_stats();
@@ -918,6 +983,9 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
                             log_lines,
                         })
                     } else {
+                        if e.is_occ() {
+                            log_function_occ_conflict(&outcome.path.udf_path);
+                        }
                         if e.is_occ()
                             && (backoff.failures() as usize) < *UDF_EXECUTOR_OCC_MAX_RETRIES
                         {
@@ -970,6 +1038,208 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
         }
     }
 
+    /// Runs a batch of mutations in order in a single transaction, so either
+    /// all of them commit or none do. The whole batch is retried on OCC
+    /// errors.
+    #[minitrace::trace]
+    pub async fn retry_mutation_batch(
+        &self,
+        request_id: RequestId,
+        mutations: Vec<(PublicFunctionPath, Vec<JsonValue>)>,
+        identity: Identity,
+        caller: FunctionCaller,
+    ) -> anyhow::Result<Result<MutationBatchReturn, MutationBatchError>> {
+        if self.read_only_replica {
+            anyhow::bail!(read_only_replica_error());
+        }
+        if mutations.len() > *MAX_MUTATION_BATCH_SIZE {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "TooManyMutations",
+                format!(
+                    "A mutation batch can contain at most {} mutations, received {}",
+                    *MAX_MUTATION_BATCH_SIZE,
+                    mutations.len()
+                )
+            ));
+        }
+        let mut parsed_mutations = Vec::with_capacity(mutations.len());
+        for (index, (path, arguments)) in mutations.into_iter().enumerate() {
+            if path.is_system() && !(identity.is_admin() || identity.is_system()) {
+                anyhow::bail!(unauthorized_error("mutation"));
+            }
+            match parse_udf_args(path.udf_path(), arguments) {
+                Ok(arguments) => parsed_mutations.push((path, arguments)),
+                Err(error) => {
+                    return Ok(Err(MutationBatchError {
+                        index: Some(index),
+                        error,
+                        log_lines: vec![].into(),
+                    }))
+                },
+            }
+        }
+
+        let mut backoff = Backoff::new(
+            *UDF_EXECUTOR_OCC_INITIAL_BACKOFF,
+            *UDF_EXECUTOR_OCC_MAX_BACKOFF,
+        );
+        let usage_tracker = FunctionUsageTracker::new();
+        loop {
+            let mut tx = self
+                .database
+                .begin_with_usage(identity.clone(), usage_tracker.clone())
+                .await?;
+            let inert_identity = tx.inert_identity();
+            let mut completed = Vec::with_capacity(parsed_mutations.len());
+            for (index, (path, arguments)) in parsed_mutations.iter().enumerate() {
+                // Every mutation gets its own context so each JS function run
+                // gets a different executionId.
+                let context = ExecutionContext::new(request_id.clone(), &caller);
+                let start = self.runtime.monotonic_now();
+                let result = self
+                    .run_mutation_no_udf_log(
+                        tx,
+                        path.clone(),
+                        arguments.clone(),
+                        caller.allowed_visibility(),
+                        context.clone(),
+                    )
+                    .await;
+                let outcome;
+                (tx, outcome) = match result {
+                    Ok(r) => r,
+                    Err(e) => {
+                        self.function_log.log_mutation_system_error(
+                            &e,
+                            path.debug_into_component_path(),
+                            arguments.clone(),
+                            inert_identity,
+                            start,
+                            caller,
+                            context,
+                        )?;
+                        return Err(e);
+                    },
+                };
+                // Attribute reads and writes to the mutation that made them.
+                let stats = tx.take_stats();
+                if let Err(ref error) = outcome.result {
+                    let error = error.to_owned();
+                    let log_lines = outcome.log_lines.clone();
+                    drop(tx);
+                    self.function_log.log_mutation(
+                        outcome,
+                        stats,
+                        start.elapsed(),
+                        caller,
+                        usage_tracker,
+                        context,
+                    );
+                    return Ok(Err(MutationBatchError {
+                        index: Some(index),
+                        error,
+                        log_lines,
+                    }));
+                }
+                completed.push((outcome, stats, start.elapsed(), context));
+            }
+
+            let ts = match self.database.commit(tx).await {
+                Ok(ts) => ts,
+                Err(e) => {
+                    if e.is_deterministic_user_error() {
+                        let js_error = JsError::from_error(e);
+                        for (mut outcome, stats, execution_time, context) in completed {
+                            outcome.result = Err(js_error.clone());
+                            self.function_log.log_mutation(
+                                outcome,
+                                stats,
+                                execution_time,
+                                caller.clone(),
+                                FunctionUsageTracker::new(),
+                                context,
+                            );
+                        }
+                        return Ok(Err(MutationBatchError {
+                            index: None,
+                            error: js_error,
+                            log_lines: vec![].into(),
+                        }));
+                    }
+                    if e.is_occ() {
+                        for (outcome, ..) in &completed {
+                            log_function_occ_conflict(&outcome.path.udf_path);
+                        }
+                    }
+                    if e.is_occ() && (backoff.failures() as usize) < *UDF_EXECUTOR_OCC_MAX_RETRIES {
+                        let sleep = backoff.fail(&mut self.runtime.rng());
+                        tracing::warn!(
+                            "Optimistic concurrency control failed ({e}), retrying mutation batch \
+                             after {sleep:?}",
+                        );
+                        self.runtime.wait(sleep).await;
+                        continue;
+                    }
+                    for (mut outcome, stats, execution_time, context) in completed {
+                        outcome.result = Err(JsError::from_error_ref(&e));
+                        if e.is_occ() {
+                            self.function_log.log_mutation_occ_error(
+                                outcome,
+                                stats,
+                                execution_time,
+                                caller.clone(),
+                                context,
+                            );
+                        } else {
+                            self.function_log.log_mutation(
+                                outcome,
+                                stats,
+                                execution_time,
+                                caller.clone(),
+                                FunctionUsageTracker::new(),
+                                context,
+                            );
+                        }
+                    }
+                    log_occ_retries(backoff.failures() as usize);
+                    return Err(e);
+                },
+            };
+
+            let num_mutations = completed.len();
+            let mut results = Vec::with_capacity(num_mutations);
+            for (i, (outcome, stats, execution_time, context)) in completed.into_iter().enumerate()
+            {
+                let value = match outcome.result {
+                    Ok(ref value) => value.clone().unpack(),
+                    Err(_) => anyhow::bail!("Mutation in batch failed after committing"),
+                };
+                results.push(MutationReturn {
+                    value,
+                    log_lines: outcome.log_lines.clone(),
+                    ts,
+                });
+                // The batch's writes are committed together, so all of its
+                // usage is attributed to the last mutation.
+                let usage = if i + 1 == num_mutations {
+                    usage_tracker.clone()
+                } else {
+                    FunctionUsageTracker::new()
+                };
+                self.function_log.log_mutation(
+                    outcome,
+                    stats,
+                    execution_time,
+                    caller.clone(),
+                    usage,
+                    context,
+                );
+            }
+            log_occ_retries(backoff.failures() as usize);
+            return Ok(Ok(MutationBatchReturn { results, ts }));
+        }
+    }
+
     /// Attempts to run a mutation once using the given transaction.
     /// The method is not idempotent. It is the caller responsibility to
     /// drive retries as we as log in the UDF log.
@@ -1217,6 +1487,12 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
         };
 
         let component = path_and_args.path().component;
+        let isolate_memory_in_mb: u64 = (path_and_args
+            .max_user_heap_size()
+            .unwrap_or(*ISOLATE_MAX_USER_HEAP_SIZE)
+            / (1 << 20))
+            .try_into()
+            .unwrap();
 
         // We should use table mappings from the same transaction as the output
         // validator was retrieved.
@@ -1257,10 +1533,6 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
                 )
                 .await;
 
-                let memory_in_mb: u64 = (*ISOLATE_MAX_USER_HEAP_SIZE / (1 << 20))
-                    .try_into()
-                    .unwrap();
-
                 let validated_outcome_result = outcome_result.map(|outcome| {
                     ValidatedActionOutcome::new(outcome, returns_validator, &table_mapping)
                 });
@@ -1270,7 +1542,7 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
                     outcome,
                     execution_time: start.elapsed(),
                     environment: ModuleEnvironment::Isolate,
-                    memory_in_mb,
+                    memory_in_mb: isolate_memory_in_mb,
                     context: context.clone(),
                     unix_timestamp,
                     caller: caller.clone(),
@@ -1289,6 +1561,14 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
                     .get_module(&mut tx, module_path.clone())
                     .await?
                     .context("Missing a valid module_version")?;
+                let region = module.analyze_result.as_ref().and_then(|analyzed| {
+                    analyzed
+                        .functions
+                        .iter()
+                        .find(|function| &function.name == path.udf_path.function_name())
+                        .and_then(|function| function.region.as_deref())
+                });
+                let (node_actions, pinned_region) = self.node_actions_for_region(region)?;
                 let _request_guard = self
                     .node_action_limiter
                     .acquire_permit_with_timeout(&self.runtime)
@@ -1357,8 +1637,8 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
                     encoded_parent_trace: EncodedSpan::from_parent().0,
                 };
 
-                let node_outcome_future = self
-                    .node_actions
+                let regional_timer = pinned_region.map(regional_action_timer);
+                let node_outcome_future = node_actions
                     .execute(request, &source_maps, log_line_sender)
                     .boxed();
                 let (mut node_outcome_result, log_lines) = run_function_and_collect_log_lines(
@@ -1377,6 +1657,11 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
                 .await;
 
                 timer.finish();
+                if let Some(regional_timer) = regional_timer
+                    && node_outcome_result.is_ok()
+                {
+                    regional_timer.finish();
+                }
 
                 if let Ok(ref mut node_outcome) = node_outcome_result {
                     if let Ok(ref output) = node_outcome.result {
@@ -1434,9 +1719,7 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
                     execution_time: start.elapsed(),
                     environment: module.environment,
                     memory_in_mb: match module.environment {
-                        ModuleEnvironment::Isolate => (*ISOLATE_MAX_USER_HEAP_SIZE / (1 << 20))
-                            .try_into()
-                            .unwrap(),
+                        ModuleEnvironment::Isolate => isolate_memory_in_mb,
                         // This isn't correct but we don't have a value to use here.
                         ModuleEnvironment::Node => 0,
                         ModuleEnvironment::Invalid => 0,
@@ -1616,6 +1899,60 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
         Ok(Ok(result))
     }
 
+    /// Evaluate the modules of `component`'s functions that set
+    /// `warmInstances` in as many isolates as the most demanding of them asks
+    /// for. Does nothing if none of them do.
+    #[minitrace::trace]
+    pub(crate) async fn warm_up_functions(
+        &self,
+        tx: &mut Transaction<RT>,
+        component: ComponentId,
+    ) -> anyhow::Result<Result<(), JsError>> {
+        let mut entry_points = vec![];
+        let mut instances = 0;
+        for metadata in ModuleModel::new(tx).get_all_metadata(component).await? {
+            if metadata.environment != ModuleEnvironment::Isolate {
+                continue;
+            }
+            let Some(analyze_result) = &metadata.analyze_result else {
+                continue;
+            };
+            let warm_instances = analyze_result
+                .functions
+                .iter()
+                .filter_map(|function| function.warm_instances)
+                .max();
+            if let Some(warm_instances) = warm_instances {
+                entry_points.push(metadata.path.clone());
+                instances = instances.max(warm_instances);
+            }
+        }
+        if entry_points.is_empty() {
+            return Ok(Ok(()));
+        }
+        let Some(udf_config) = UdfConfigModel::new(tx, component.into()).get().await? else {
+            return Ok(Ok(()));
+        };
+        let modules = ModuleModel::new(tx)
+            .get_application_modules(component, self.module_cache.as_ref())
+            .await?
+            .into_iter()
+            .filter(|(_, module)| module.environment == ModuleEnvironment::Isolate)
+            .collect();
+        let mut environment_variables = EnvironmentVariablesModel::new(tx).get_all().await?;
+        environment_variables.extend(self.system_env_vars.clone());
+        self.isolate_functions
+            .function_runner
+            .warm_up(
+                udf_config.into_value(),
+                modules,
+                environment_variables,
+                entry_points,
+                instances,
+            )
+            .await
+    }
+
     #[minitrace::trace]
     fn validate_cron_jobs(
         &self,
@@ -1691,7 +2028,41 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
     }
 
     pub fn enable_actions(&self) -> anyhow::Result<()> {
-        self.node_actions.enable()
+        self.node_actions.enable()?;
+        for actions in self.regional_node_actions.values() {
+            actions.enable()?;
+        }
+        Ok(())
+    }
+
+    /// Picks the action runners for a Node action pinned to `region`, along
+    /// with the region it will run in. Actions pinned to a region without
+    /// runners run on the default runners unless `ACTION_REGION_STRICT` is
+    /// set.
+    fn node_actions_for_region<'a>(
+        &self,
+        region: Option<&'a str>,
+    ) -> anyhow::Result<(&Actions, Option<&'a str>)> {
+        let Some(region) = region else {
+            return Ok((&self.node_actions, None));
+        };
+        if let Some(actions) = self.regional_node_actions.get(region) {
+            log_regional_action_dispatch(region, RegionalActionDispatch::Pinned);
+            return Ok((actions, Some(region)));
+        }
+        if *ACTION_REGION_STRICT {
+            log_regional_action_dispatch(region, RegionalActionDispatch::Rejected);
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "ActionRegionUnavailable",
+                format!(
+                    "This action is pinned to region {region:?}, which has no action runners. \
+                     Configured regions: {:?}",
+                    self.regional_node_actions.keys().collect::<Vec<_>>()
+                ),
+            ));
+        }
+        log_regional_action_dispatch(region, RegionalActionDispatch::Fallback);
+        Ok((&self.node_actions, None))
     }
 
     #[minitrace::trace]
@@ -1770,20 +2141,94 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
         Ok(result)
     }
 
+    /// Runs a query at a historical `ts` within the retention window, which
+    /// may be older than the timestamps [`Self::run_query_at_ts`] accepts. The
+    /// result isn't cached.
+    #[minitrace::trace]
+    pub async fn run_query_at_historical_ts(
+        &self,
+        request_id: RequestId,
+        path: PublicFunctionPath,
+        args: Vec<JsonValue>,
+        identity: Identity,
+        ts: Timestamp,
+        caller: FunctionCaller,
+    ) -> anyhow::Result<QueryReturn> {
+        if path.is_system() && !(identity.is_admin() || identity.is_system()) {
+            anyhow::bail!(unauthorized_error("query"));
+        }
+        let args = match parse_udf_args(path.udf_path(), args) {
+            Ok(arguments) => arguments,
+            Err(js_error) => {
+                return Ok(QueryReturn {
+                    result: Err(js_error),
+                    log_lines: vec![].into(),
+                    token: Token::empty(ts),
+                    journal: QueryJournal::new(),
+                });
+            },
+        };
+        self.cache_manager
+            .get_historical(
+                request_id,
+                path,
+                args,
+                identity,
+                ts,
+                caller,
+                FunctionUsageTracker::new(),
+            )
+            .await
+    }
+
     #[minitrace::trace]
     async fn check_mutation_status(
         &self,
         tx: &mut Transaction<RT>,
         mutation_identifier: &Option<SessionRequestIdentifier>,
+        mutation_call: &Option<MutationCall>,
     ) -> anyhow::Result<Option<Result<MutationReturn, MutationError>>> {
-        let Some(ref identifier) = mutation_identifier else {
+        let (Some(identifier), Some(call)) = (mutation_identifier, mutation_call) else {
             return Ok(None);
         };
         let mutation_status = SessionRequestModel::new(tx)
             .get_session_request_record(identifier, Identity::system())
             .await?;
-        let result = match mutation_status {
-            Some((ts, SessionRequestOutcome::Mutation { result, log_lines })) => {
+        let Some((ts, record)) = mutation_status else {
+            return Ok(None);
+        };
+        // Mutation IDs are chosen by clients, so don't replay another user's
+        // result to a client that picked the same ID.
+        if identifier.mutation_id.is_some() && record.identity != tx.inert_identity() {
+            return Ok(Some(Err(MutationError {
+                error: JsError::from_message(
+                    "This mutation ID was already used by a different user".to_string(),
+                ),
+                log_lines: vec![].into(),
+            })));
+        }
+        // A retry must repeat the original call, or the client would get the
+        // result of a different one.
+        let same_call = record
+            .udf_path
+            .as_ref()
+            .map_or(true, |udf_path| *udf_path == call.udf_path)
+            && record
+                .args_hash
+                .as_ref()
+                .map_or(true, |args_hash| *args_hash == call.args_hash);
+        if !same_call {
+            return Ok(Some(Err(MutationError {
+                error: JsError::from_message(
+                    "This request was already used to run a different mutation or pass different \
+                     arguments. Retries must repeat the original call."
+                        .to_string(),
+                ),
+                log_lines: vec![].into(),
+            })));
+        }
+        let result = match record.outcome {
+            SessionRequestOutcome::Mutation { result, log_lines } => {
                 tracing::info!("Mutation already executed so skipping {:?}", identifier);
                 log_mutation_already_committed();
                 Ok(MutationReturn {
@@ -1792,7 +2237,6 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
                     ts,
                 })
             },
-            None => return Ok(None),
         };
         Ok(Some(result))
     }
@@ -1802,15 +2246,19 @@ impl<RT: Runtime> ApplicationFunctionRunner<RT> {
         &self,
         tx: &mut Transaction<RT>,
         mutation_identifier: &Option<SessionRequestIdentifier>,
+        mutation_call: &Option<MutationCall>,
         outcome: &ValidatedUdfOutcome,
     ) -> anyhow::Result<()> {
-        let Some(ref identifier) = mutation_identifier else {
+        let (Some(identifier), Some(call)) = (mutation_identifier, mutation_call) else {
             return Ok(());
         };
         if let Ok(ref value) = outcome.result {
             let record = SessionRequestRecord {
                 session_id: identifier.session_id,
                 request_id: identifier.request_id,
+                mutation_id: identifier.mutation_id.clone(),
+                udf_path: Some(call.udf_path.clone()),
+                args_hash: Some(call.args_hash.clone()),
                 outcome: SessionRequestOutcome::Mutation {
                     result: value.unpack(),
                     log_lines: outcome.log_lines.clone(),
@@ -1860,7 +2308,8 @@ impl<RT: Runtime> ActionCallbacks for ApplicationFunctionRunner<RT> {
                 },
             )
             .await?
-            .result;
+            .result
+            .map(|value| value.unpack());
         Ok(FunctionResult { result })
     }
 
@@ -1980,6 +2429,8 @@ impl<RT: Runtime> ActionCallbacks for ApplicationFunctionRunner<RT> {
                 },
             )
             .await?;
+        self.file_storage
+            .scan_in_background(self.database.clone(), component.into(), r.1, entry);
         Ok(r)
     }
 
@@ -2020,6 +2471,7 @@ impl<RT: Runtime> ActionCallbacks for ApplicationFunctionRunner<RT> {
         udf_args: Vec<JsonValue>,
         scheduled_ts: UnixTimestamp,
         context: ExecutionContext,
+        idempotency_key: Option<IdempotencyKey>,
     ) -> anyhow::Result<DeveloperDocumentId> {
         let (_ts, virtual_id, _stats) = self
             .database
@@ -2032,6 +2484,7 @@ impl<RT: Runtime> ActionCallbacks for ApplicationFunctionRunner<RT> {
                     let path = scheduled_path.clone();
                     let args = udf_args.clone();
                     let context = context.clone();
+                    let idempotency_key = idempotency_key.clone();
                     async move {
                         let (path, udf_args) = validate_schedule_args(
                             path,
@@ -2043,10 +2496,25 @@ impl<RT: Runtime> ActionCallbacks for ApplicationFunctionRunner<RT> {
                             tx,
                         )
                         .await?;
-                        let virtual_id =
-                            VirtualSchedulerModel::new(tx, scheduling_component.into())
-                                .schedule(path, udf_args, scheduled_ts, context)
-                                .await?;
+                        let mut model = VirtualSchedulerModel::new(tx, scheduling_component.into());
+                        let virtual_id = match idempotency_key {
+                            Some(idempotency_key) => {
+                                model
+                                    .schedule_idempotent(
+                                        path,
+                                        udf_args,
+                                        scheduled_ts,
+                                        context,
+                                        idempotency_key,
+                                    )
+                                    .await?
+                            },
+                            None => {
+                                model
+                                    .schedule(path, udf_args, scheduled_ts, context)
+                                    .await?
+                            },
+                        };
                         Ok(virtual_id)
                     }
                     .into()
@@ -2080,6 +2548,41 @@ impl<RT: Runtime> ActionCallbacks for ApplicationFunctionRunner<RT> {
         Ok(())
     }
 
+    async fn load_action_checkpoint(
+        &self,
+        identity: Identity,
+        job_id: DeveloperDocumentId,
+    ) -> anyhow::Result<Option<JsonValue>> {
+        let mut tx = self.database.begin(identity).await?;
+        ActionCheckpointsModel::new(&mut tx)
+            .get(job_id)
+            .await?
+            .map(|checkpoint| anyhow::Ok(serde_json::from_str(&checkpoint.value)?))
+            .transpose()
+    }
+
+    async fn save_action_checkpoint(
+        &self,
+        identity: Identity,
+        job_id: DeveloperDocumentId,
+        value: JsonValue,
+    ) -> anyhow::Result<()> {
+        let value = serde_json::to_string(&value)?;
+        self.database
+            .execute_with_occ_retries(
+                identity,
+                FunctionUsageTracker::new(),
+                PauseClient::new(),
+                "app_funrun_save_action_checkpoint",
+                |tx| {
+                    let value = value.clone();
+                    async move { ActionCheckpointsModel::new(tx).save(job_id, value).await }.into()
+                },
+            )
+            .await?;
+        Ok(())
+    }
+
     async fn vector_search(
         &self,
         identity: Identity,
@@ -2092,6 +2595,18 @@ impl<RT: Runtime> ActionCallbacks for ApplicationFunctionRunner<RT> {
         self.database.vector_search(identity, query).await
     }
 
+    async fn hybrid_search(
+        &self,
+        identity: Identity,
+        query: JsonValue,
+    ) -> anyhow::Result<(Vec<HybridSearchResult>, FunctionUsageStats)> {
+        let query = HybridSearch::try_from(query).map_err(|e| {
+            let message = e.to_string();
+            e.context(ErrorMetadata::bad_request("InvalidHybridSearch", message))
+        })?;
+        self.database.hybrid_search(identity, query).await
+    }
+
     async fn lookup_function_handle(
         &self,
         identity: Identity,
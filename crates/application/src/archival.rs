@@ -0,0 +1,237 @@
+//! Moves old documents in tables with an archival policy into compressed
+//! segments in file storage. See `model::archival` for how they're recorded
+//! and read back.
+//!
+//! Each segment is uploaded before the transaction that deletes its documents
+//! commits, so a failed commit leaves an unreferenced segment behind. Its
+//! documents are still in the table and are archived again on the next pass.
+use std::{
+    sync::Arc,
+    time::Duration,
+};
+
+use common::{
+    backoff::Backoff,
+    components::{
+        ComponentId,
+        ComponentPath,
+    },
+    errors::report_error,
+    knobs::{
+        ARCHIVAL_INTERVAL,
+        ARCHIVAL_SEGMENT_MAX_DOCUMENTS,
+    },
+    query::{
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+};
+use database::{
+    unauthorized_error,
+    Database,
+    ResolvedQuery,
+};
+use errors::ErrorMetadata;
+use futures::Future;
+use keybroker::Identity;
+use model::archival::{
+    segment::write_segment,
+    types::{
+        ArchivalPolicy,
+        ArchivedSegment,
+    },
+    ArchivalModel,
+};
+use storage::Storage;
+use value::{
+    TableName,
+    TableNamespace,
+};
+
+use crate::Application;
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+pub struct ArchivalWorker<RT: Runtime> {
+    runtime: RT,
+    database: Database<RT>,
+    files_storage: Arc<dyn Storage>,
+}
+
+impl<RT: Runtime> ArchivalWorker<RT> {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        runtime: RT,
+        database: Database<RT>,
+        files_storage: Arc<dyn Storage>,
+    ) -> impl Future<Output = ()> + Send {
+        let worker = Self {
+            runtime,
+            database,
+            files_storage,
+        };
+        async move {
+            let mut backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
+            while let Err(mut e) = worker.run(&mut backoff).await {
+                report_error(&mut e);
+                let delay = backoff.fail(&mut worker.runtime.rng());
+                tracing::error!("ArchivalWorker failed, sleeping {delay:?}");
+                worker.runtime.wait(delay).await;
+            }
+        }
+    }
+
+    async fn run(&self, backoff: &mut Backoff) -> anyhow::Result<()> {
+        tracing::info!("Starting ArchivalWorker");
+        loop {
+            let mut tx = self.database.begin(Identity::system()).await?;
+            let policies = ArchivalModel::new(&mut tx).policies().await?;
+            for policy in policies {
+                let policy = policy.into_value();
+                while self.archive_segment(&policy).await? > 0 {}
+            }
+            backoff.reset();
+            self.runtime.wait(*ARCHIVAL_INTERVAL).await;
+        }
+    }
+
+    /// Archives the oldest documents in the policy's table that are old
+    /// enough, up to a segment's worth. Returns how many were archived.
+    async fn archive_segment(&self, policy: &ArchivalPolicy) -> anyhow::Result<usize> {
+        let now = self.runtime.unix_timestamp();
+        let Some(cutoff) = now.as_system_time().checked_sub(policy.archive_after) else {
+            return Ok(0);
+        };
+        let cutoff_ms = cutoff.duration_since(std::time::UNIX_EPOCH)?.as_millis() as f64;
+        let mut tx = self.database.begin(Identity::system()).await?;
+        let table_mapping = tx.table_mapping().clone();
+        if !table_mapping.is_active(policy.tablet_id) {
+            return Ok(0);
+        }
+        let namespace = table_mapping.tablet_namespace(policy.tablet_id)?;
+        let table_name = table_mapping.tablet_name(policy.tablet_id)?;
+        let mut query_stream = ResolvedQuery::new(
+            &mut tx,
+            namespace,
+            Query::full_table_scan(table_name.clone(), Order::Asc),
+        )?;
+        let mut documents = vec![];
+        while documents.len() < *ARCHIVAL_SEGMENT_MAX_DOCUMENTS
+            && let Some(document) = query_stream.next(&mut tx, None).await?
+        {
+            let Some(creation_time) = document.creation_time() else {
+                continue;
+            };
+            if f64::from(creation_time) >= cutoff_ms {
+                break;
+            }
+            documents.push(document);
+        }
+        if documents.is_empty() {
+            return Ok(0);
+        }
+        let ids: Vec<_> = documents.iter().map(|document| document.id()).collect();
+        let document_count = ids.len();
+        let (storage_key, size) = write_segment(&self.files_storage, documents).await?;
+        for id in &ids {
+            tx.delete_inner(*id).await?;
+        }
+        ArchivalModel::new(&mut tx)
+            .record_segment(
+                ArchivedSegment {
+                    tablet_id: policy.tablet_id,
+                    storage_key,
+                    document_count: document_count as u64,
+                    size,
+                    archived_at: now,
+                },
+                ids.iter().map(|id| id.internal_id()).collect(),
+            )
+            .await?;
+        self.database
+            .commit_with_write_source(tx, "archival_worker")
+            .await?;
+        tracing::info!("Archived {document_count} documents from {table_name}");
+        Ok(document_count)
+    }
+}
+
+/// A table's archival policy and what has been archived from it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TableArchivalStatus {
+    pub component: ComponentPath,
+    pub table_name: TableName,
+    pub archive_after: Duration,
+    pub segments: u64,
+    pub archived_documents: u64,
+    pub archived_bytes: u64,
+}
+
+impl<RT: Runtime> Application<RT> {
+    /// Archives documents in `table_name` once they're `archive_after` old,
+    /// or stops archiving new ones if it's `None`.
+    pub async fn set_archival_policy(
+        &self,
+        identity: Identity,
+        component: ComponentId,
+        table_name: TableName,
+        archive_after: Option<Duration>,
+    ) -> anyhow::Result<()> {
+        if table_name.is_system() {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "InvalidArchivalTable",
+                format!("System table {table_name} can't be archived"),
+            ));
+        }
+        let mut tx = self.begin(identity).await?;
+        let tablet_id = tx
+            .table_mapping()
+            .namespace(TableNamespace::from(component))
+            .id_if_exists(&table_name)
+            .ok_or_else(|| {
+                ErrorMetadata::not_found("TableNotFound", format!("Table {table_name} not found"))
+            })?;
+        ArchivalModel::new(&mut tx)
+            .set_policy(tablet_id, archive_after)
+            .await?;
+        self.commit(tx, "set_archival_policy").await?;
+        Ok(())
+    }
+
+    pub async fn archival_status(
+        &self,
+        identity: Identity,
+    ) -> anyhow::Result<Vec<TableArchivalStatus>> {
+        anyhow::ensure!(
+            identity.is_admin() || identity.is_system(),
+            unauthorized_error("archival_status")
+        );
+        let mut tx = self.begin(identity).await?;
+        let component_paths = tx.all_component_paths();
+        let table_mapping = tx.table_mapping().clone();
+        let mut model = ArchivalModel::new(&mut tx);
+        let mut statuses = vec![];
+        for policy in model.policies().await? {
+            let policy = policy.into_value();
+            if !table_mapping.is_active(policy.tablet_id) {
+                continue;
+            }
+            let segments = model.segments(policy.tablet_id).await?;
+            let namespace = table_mapping.tablet_namespace(policy.tablet_id)?;
+            statuses.push(TableArchivalStatus {
+                component: component_paths
+                    .get(&ComponentId::from(namespace))
+                    .cloned()
+                    .unwrap_or_default(),
+                table_name: table_mapping.tablet_name(policy.tablet_id)?,
+                archive_after: policy.archive_after,
+                segments: segments.len() as u64,
+                archived_documents: segments.iter().map(|s| s.document_count).sum(),
+                archived_bytes: segments.iter().map(|s| s.size).sum(),
+            });
+        }
+        Ok(statuses)
+    }
+}
@@ -0,0 +1,194 @@
+//! Requests snapshot exports on the deployment's backup schedule and deletes
+//! old backups according to its retention policy, so self-hosted deployments
+//! get backups without an external cron job.
+use std::{
+    sync::Arc,
+    time::Duration,
+};
+
+use common::{
+    backoff::Backoff,
+    components::ComponentId,
+    errors::report_error,
+    runtime::Runtime,
+    types::ObjectKey,
+};
+use database::{
+    Database,
+    Transaction,
+};
+use futures::{
+    future::Either,
+    select_biased,
+    Future,
+    FutureExt,
+};
+use keybroker::Identity;
+use model::{
+    backup_schedule::{
+        types::BackupSchedule,
+        BackupScheduleModel,
+    },
+    cron_jobs::next_ts::compute_next_schedule_ts,
+    exports::{
+        types::{
+            Export,
+            ExportFormat,
+            ExportRequestor,
+        },
+        ExportsModel,
+    },
+};
+use storage::Storage;
+use sync_types::Timestamp;
+use value::ResolvedDocumentId;
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+pub struct BackupScheduleWorker<RT: Runtime> {
+    runtime: RT,
+    database: Database<RT>,
+    exports_storage: Arc<dyn Storage>,
+}
+
+impl<RT: Runtime> BackupScheduleWorker<RT> {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        runtime: RT,
+        database: Database<RT>,
+        exports_storage: Arc<dyn Storage>,
+    ) -> impl Future<Output = ()> + Send {
+        let worker = Self {
+            runtime,
+            database,
+            exports_storage,
+        };
+        async move {
+            let mut backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
+            while let Err(mut e) = worker.run(&mut backoff).await {
+                report_error(&mut e);
+                let delay = backoff.fail(&mut worker.runtime.rng());
+                tracing::error!("BackupScheduleWorker failed, sleeping {delay:?}");
+                worker.runtime.wait(delay).await;
+            }
+        }
+    }
+
+    async fn run(&self, backoff: &mut Backoff) -> anyhow::Result<()> {
+        tracing::info!("Starting BackupScheduleWorker");
+        loop {
+            let mut tx = self.database.begin(Identity::system()).await?;
+            let now = self.runtime.generate_timestamp()?;
+            let next_backup_future = match BackupScheduleModel::new(&mut tx).get().await? {
+                None => Either::Right(std::future::pending()),
+                Some(schedule) => {
+                    let schedule = schedule.into_value();
+                    // Rotating in this transaction also subscribes to new backups
+                    // completing, so we delete the oldest one as soon as the limit
+                    // is exceeded.
+                    let object_keys = self.rotate_backups(&mut tx, &schedule, now).await?;
+                    if !object_keys.is_empty() {
+                        self.database
+                            .commit_with_write_source(tx, "backup_schedule_rotation")
+                            .await?;
+                        let num_deleted = object_keys.len();
+                        for object_key in object_keys {
+                            self.exports_storage.delete_object(&object_key).await?;
+                        }
+                        tracing::info!(
+                            "Deleted {num_deleted} scheduled backups outside the retention policy"
+                        );
+                        continue;
+                    }
+                    if schedule.next_backup_ts > now {
+                        Either::Left(self.runtime.wait(schedule.next_backup_ts - now))
+                    } else {
+                        let mut exports_model = ExportsModel::new(&mut tx);
+                        if exports_model.latest_requested().await?.is_none()
+                            && exports_model.latest_in_progress().await?.is_none()
+                        {
+                            let backup_id = self.request_backup(&mut tx, schedule, now).await?;
+                            self.database
+                                .commit_with_write_source(tx, "backup_schedule_worker")
+                                .await?;
+                            tracing::info!("Requested scheduled backup {backup_id}");
+                            backoff.reset();
+                            continue;
+                        }
+                        // There's already an export running, so wait for it to
+                        // finish before requesting the backup.
+                        Either::Right(std::future::pending())
+                    }
+                },
+            };
+            let token = tx.into_token()?;
+            let subscription = self.database.subscribe(token).await?;
+            select_biased! {
+                _ = next_backup_future.fuse() => {},
+                _ = subscription.wait_for_invalidation().fuse() => {},
+            }
+            backoff.reset();
+        }
+    }
+
+    async fn request_backup(
+        &self,
+        tx: &mut Transaction<RT>,
+        mut schedule: BackupSchedule,
+        now: Timestamp,
+    ) -> anyhow::Result<ResolvedDocumentId> {
+        let expiration_ts = schedule
+            .retention_age
+            .and_then(|age| now.add(age).ok())
+            .map_or(i64::MAX as u64, u64::from);
+        let backup_id = ExportsModel::new(tx)
+            .insert_requested(
+                ExportFormat::Zip {
+                    include_storage: schedule.include_storage,
+                },
+                ComponentId::Root,
+                ExportRequestor::ScheduledBackup,
+                Some(expiration_ts),
+            )
+            .await?;
+        // Skip any backups we missed while the deployment was down.
+        let mut next_backup_ts =
+            compute_next_schedule_ts(&schedule.schedule, Some(schedule.next_backup_ts), now)?;
+        while next_backup_ts <= now {
+            next_backup_ts =
+                compute_next_schedule_ts(&schedule.schedule, Some(next_backup_ts), now)?;
+        }
+        schedule.next_backup_ts = next_backup_ts;
+        BackupScheduleModel::new(tx).set(Some(schedule)).await?;
+        Ok(backup_id)
+    }
+
+    /// Deletes the scheduled backups that fall outside the retention policy,
+    /// returning their object keys to delete from storage after committing.
+    /// Backups are kept if scheduled backups are turned off.
+    async fn rotate_backups(
+        &self,
+        tx: &mut Transaction<RT>,
+        schedule: &BackupSchedule,
+        now: Timestamp,
+    ) -> anyhow::Result<Vec<ObjectKey>> {
+        let mut exports_model = ExportsModel::new(tx);
+        let backups = exports_model
+            .list_completed(ExportRequestor::ScheduledBackup)
+            .await?;
+        let mut object_keys = vec![];
+        for (index, backup) in backups.into_iter().enumerate() {
+            let Export::Completed { start_ts, .. } = &*backup else {
+                continue;
+            };
+            if schedule.retains(index, *start_ts, now) {
+                continue;
+            }
+            if let Some(object_key) = exports_model.delete(backup.id().into()).await? {
+                object_keys.push(object_key);
+            }
+        }
+        Ok(object_keys)
+    }
+}
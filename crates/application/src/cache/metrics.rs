@@ -57,6 +57,14 @@ pub fn log_plan_ready() {
     log_counter(&CACHE_PLAN_READY_TOTAL, 1);
 }
 
+register_convex_counter!(
+    CACHE_PLAN_SHARED_READY_TOTAL,
+    "Number of times a cache entry shared between identities was already ready"
+);
+pub fn log_plan_shared_ready() {
+    log_counter(&CACHE_PLAN_SHARED_READY_TOTAL, 1);
+}
+
 register_convex_counter!(
     CACHE_PLAN_PEER_TIMEOUT_TOTAL,
     "Number of times a peer was found to have timed out when computing a cache result"
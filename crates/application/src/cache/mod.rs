@@ -20,6 +20,7 @@ use common::{
     execution_context::ExecutionContext,
     identity::IdentityCacheKey,
     knobs::{
+        DATABASE_UDF_MAX_USER_TIMEOUT,
         DATABASE_UDF_SYSTEM_TIMEOUT,
         DATABASE_UDF_USER_TIMEOUT,
         UDF_CACHE_MAX_SIZE,
@@ -40,6 +41,7 @@ use common::{
 use database::{
     Database,
     Token,
+    Transaction,
 };
 use errors::ErrorMetadataAnyhowExt;
 use futures::{
@@ -63,6 +65,7 @@ use metrics::{
     log_plan_go,
     log_plan_peer_timeout,
     log_plan_ready,
+    log_plan_shared_ready,
     log_plan_wait,
     log_success,
     log_validate_refresh_failed,
@@ -90,8 +93,12 @@ mod metrics;
 // Maximum age of results to tolerate if they're time-dependent.
 pub const MAX_CACHE_AGE: Duration = Duration::from_secs(5);
 
-static TOTAL_QUERY_TIMEOUT: LazyLock<Duration> =
-    LazyLock::new(|| *DATABASE_UDF_USER_TIMEOUT + *DATABASE_UDF_SYSTEM_TIMEOUT);
+// Queries can declare their own user timeout up to the maximum, so allow for
+// that when deciding whether a peer computing the same query is stuck.
+static TOTAL_QUERY_TIMEOUT: LazyLock<Duration> = LazyLock::new(|| {
+    cmp::max(*DATABASE_UDF_USER_TIMEOUT, *DATABASE_UDF_MAX_USER_TIMEOUT)
+        + *DATABASE_UDF_SYSTEM_TIMEOUT
+});
 
 #[derive(Clone)]
 pub struct CacheManager<RT: Runtime> {
@@ -107,7 +114,9 @@ pub struct CacheManager<RT: Runtime> {
 pub struct CacheKey {
     path: PublicFunctionPath,
     args: ConvexArray,
-    identity: IdentityCacheKey,
+    /// `None` for results that didn't read the identity they ran as, which
+    /// are shared by every identity that can use them.
+    identity: Option<IdentityCacheKey>,
     journal: QueryJournal,
     allowed_visibility: AllowedVisibility,
 }
@@ -168,6 +177,7 @@ struct CacheResult {
     outcome: UdfOutcome,
     original_ts: Timestamp,
     token: Token,
+    observed_identity: bool,
 }
 
 impl HeapSize for CacheResult {
@@ -232,6 +242,54 @@ impl<RT: Runtime> CacheManager<RT> {
         Ok(result?.0)
     }
 
+    /// Execute a query at a historical timestamp within the retention window,
+    /// which may be older than the snapshots kept in memory. These results
+    /// are never cached.
+    #[minitrace::trace]
+    pub async fn get_historical(
+        &self,
+        request_id: RequestId,
+        path: PublicFunctionPath,
+        args: ConvexArray,
+        identity: Identity,
+        ts: Timestamp,
+        caller: FunctionCaller,
+        usage_tracker: FunctionUsageTracker,
+    ) -> anyhow::Result<QueryReturn> {
+        let start = self.rt.monotonic_now();
+        let context = ExecutionContext::new(request_id, &caller);
+        let tx = self
+            .database
+            .begin_at_historical_ts(identity.clone(), ts, usage_tracker.clone())
+            .await?;
+        let (result, table_stats) = self
+            .execute_query(
+                tx,
+                path,
+                args,
+                identity,
+                QueryJournal::new(),
+                caller.allowed_visibility(),
+                context.clone(),
+            )
+            .await?;
+        self.udf_execution.log_query(
+            result.outcome.clone(),
+            table_stats,
+            false,
+            start.elapsed(),
+            caller,
+            usage_tracker,
+            context,
+        );
+        Ok(QueryReturn {
+            result: result.outcome.result,
+            log_lines: result.outcome.log_lines,
+            token: result.token,
+            journal: result.outcome.journal,
+        })
+    }
+
     #[minitrace::trace]
     async fn _get(
         &self,
@@ -249,10 +307,18 @@ impl<RT: Runtime> CacheManager<RT> {
         let key = CacheKey {
             path: path.clone(),
             args: args.clone(),
-            identity: identity_cache_key,
+            identity: Some(identity_cache_key),
             journal: journal.unwrap_or_else(QueryJournal::new),
             allowed_visibility: caller.allowed_visibility(),
         };
+        // Results that didn't read the identity can be shared between end
+        // users, but not with admins, whose permissions can change the result
+        // without the function reading the identity.
+        let shared_key =
+            matches!(identity, Identity::Unknown | Identity::User(_)).then(|| CacheKey {
+                identity: None,
+                ..key.clone()
+            });
         let context = ExecutionContext::new(request_id, &caller);
 
         let mut num_attempts = 0;
@@ -269,6 +335,34 @@ impl<RT: Runtime> CacheManager<RT> {
                 "Query execution time out: {elapsed:?}",
             );
 
+            // Step 0: Use a result computed for another identity if it didn't depend
+            // on who ran it.
+            if let Some(ref shared_key) = shared_key
+                && let Some(result) = self.cache.get_ready(shared_key, ts)
+                && let Some(mut cache_result) =
+                    self.validate_cache_result(shared_key, ts, result).await?
+            {
+                log_plan_shared_ready();
+                log_success(num_attempts);
+                cache_result.outcome.identity = identity.clone().into();
+                self.udf_execution.log_query(
+                    cache_result.outcome.clone(),
+                    BTreeMap::new(),
+                    true,
+                    start.elapsed(),
+                    caller,
+                    usage_tracker,
+                    context.clone(),
+                );
+                let result = QueryReturn {
+                    result: cache_result.outcome.result,
+                    log_lines: cache_result.outcome.log_lines,
+                    token: cache_result.token,
+                    journal: cache_result.outcome.journal,
+                };
+                return Ok((result, true));
+            }
+
             // Step 1: Decide what we're going to do this iteration: use a cached value,
             // wait on someone else to run a UDF, or run the UDF ourselves.
             let maybe_op =
@@ -321,7 +415,16 @@ impl<RT: Runtime> CacheManager<RT> {
             // value is in the cache.
             if cache_result.outcome.result.is_ok() {
                 // We do not cache JSErrors
-                waiting_entry_guard.complete(cache_result.clone());
+                match shared_key {
+                    // Peers waiting on us have already been sent the result, so
+                    // only keep the copy every identity can use.
+                    Some(ref shared_key) if !cache_result.observed_identity => {
+                        drop(waiting_entry_guard);
+                        self.cache
+                            .put_ready(shared_key.clone(), cache_result.clone());
+                    },
+                    _ => waiting_entry_guard.complete(cache_result.clone()),
+                }
             } else {
                 drop(waiting_entry_guard);
             }
@@ -339,7 +442,7 @@ impl<RT: Runtime> CacheManager<RT> {
             );
 
             let result = QueryReturn {
-                result: cache_result.outcome.result.map(|r| r.unpack()),
+                result: cache_result.outcome.result,
                 log_lines: cache_result.outcome.log_lines,
                 token: cache_result.token,
                 journal: cache_result.outcome.journal,
@@ -409,74 +512,21 @@ impl<RT: Runtime> CacheManager<RT> {
                 allowed_visibility,
                 context,
             } => {
-                let mut tx = self
+                let tx = self
                     .database
                     .begin_with_ts(identity.clone(), ts, usage_tracker)
                     .await?;
-                // We are validating UDF visibility here as opposed to earlier so the validation
-                // checks are transactional with running the query. This is safe because we will
-                // never serve a result based of a stale visibility check since the data read as
-                // part of the visibility check is part of the ReadSet for this query.
-                let validate_result = ValidatedPathAndArgs::new_with_returns_validator(
-                    allowed_visibility,
-                    &mut tx,
-                    path.clone(),
-                    args.clone(),
-                    UdfType::Query,
-                )
-                .await?;
-
-                let (mut tx, query_outcome) = match validate_result {
-                    Err(js_err) => {
-                        let query_outcome = UdfOutcome::from_error(
-                            js_err,
-                            path.clone().debug_into_component_path(),
-                            args,
-                            identity.into(),
-                            self.rt.clone(),
-                            None,
-                        )?;
-                        (tx, query_outcome)
-                    },
-                    Ok((path_and_args, returns_validator)) => {
-                        let (mut tx, outcome) = self
-                            .function_router
-                            .execute_query_or_mutation(
-                                tx,
-                                path_and_args.clone(),
-                                UdfType::Query,
-                                journal,
-                                context,
-                            )
-                            .await?;
-                        let FunctionOutcome::Query(mut query_outcome) = outcome else {
-                            anyhow::bail!("Received non-query outcome when executing a query")
-                        };
-                        if let Ok(ref json_packed_value) = &query_outcome.result {
-                            let output: ConvexValue = json_packed_value.unpack();
-                            let component = path_and_args.path().component;
-                            let table_mapping = tx.table_mapping().namespace(component.into());
-                            let virtual_system_mapping = tx.virtual_system_mapping();
-                            let returns_validation_error = returns_validator.check_output(
-                                &output,
-                                &table_mapping,
-                                virtual_system_mapping,
-                            );
-                            if let Some(js_err) = returns_validation_error {
-                                query_outcome.result = Err(js_err);
-                            }
-                        }
-                        (tx, query_outcome)
-                    },
-                };
-                let ts = tx.begin_timestamp();
-                let table_stats = tx.take_stats();
-                let token = tx.into_token()?;
-                let result = CacheResult {
-                    outcome: query_outcome,
-                    original_ts: *ts,
-                    token,
-                };
+                let (result, table_stats) = self
+                    .execute_query(
+                        tx,
+                        path,
+                        args,
+                        identity,
+                        journal,
+                        allowed_visibility,
+                        context,
+                    )
+                    .await?;
                 if result.outcome.result.is_ok() {
                     let _: Result<_, _> = sender.try_broadcast(result.clone());
                 }
@@ -487,6 +537,86 @@ impl<RT: Runtime> CacheManager<RT> {
         Ok(Some(r))
     }
 
+    /// Runs the query in `tx` without consulting or updating the cache.
+    async fn execute_query(
+        &self,
+        mut tx: Transaction<RT>,
+        path: PublicFunctionPath,
+        args: ConvexArray,
+        identity: Identity,
+        journal: QueryJournal,
+        allowed_visibility: AllowedVisibility,
+        context: ExecutionContext,
+    ) -> anyhow::Result<(CacheResult, BTreeMap<TableName, TableStats>)> {
+        // We are validating UDF visibility here as opposed to earlier so the validation
+        // checks are transactional with running the query. This is safe because we will
+        // never serve a result based of a stale visibility check since the data read as
+        // part of the visibility check is part of the ReadSet for this query.
+        let validate_result = ValidatedPathAndArgs::new_with_returns_validator(
+            allowed_visibility,
+            &mut tx,
+            path.clone(),
+            args.clone(),
+            UdfType::Query,
+        )
+        .await?;
+
+        let (mut tx, query_outcome) = match validate_result {
+            Err(js_err) => {
+                let query_outcome = UdfOutcome::from_error(
+                    js_err,
+                    path.clone().debug_into_component_path(),
+                    args,
+                    identity.into(),
+                    self.rt.clone(),
+                    None,
+                )?;
+                (tx, query_outcome)
+            },
+            Ok((path_and_args, returns_validator)) => {
+                let (mut tx, outcome) = self
+                    .function_router
+                    .execute_query_or_mutation(
+                        tx,
+                        path_and_args.clone(),
+                        UdfType::Query,
+                        journal,
+                        context,
+                    )
+                    .await?;
+                let FunctionOutcome::Query(mut query_outcome) = outcome else {
+                    anyhow::bail!("Received non-query outcome when executing a query")
+                };
+                if let Ok(ref json_packed_value) = &query_outcome.result {
+                    let output: ConvexValue = json_packed_value.unpack();
+                    let component = path_and_args.path().component;
+                    let table_mapping = tx.table_mapping().namespace(component.into());
+                    let virtual_system_mapping = tx.virtual_system_mapping();
+                    let returns_validation_error = returns_validator.check_output(
+                        &output,
+                        &table_mapping,
+                        virtual_system_mapping,
+                    );
+                    if let Some(js_err) = returns_validation_error {
+                        query_outcome.result = Err(js_err);
+                    }
+                }
+                (tx, query_outcome)
+            },
+        };
+        let ts = tx.begin_timestamp();
+        let table_stats = tx.take_stats();
+        let observed_identity = tx.observed_identity();
+        let token = tx.into_token()?;
+        let result = CacheResult {
+            outcome: query_outcome,
+            original_ts: *ts,
+            token,
+            observed_identity,
+        };
+        Ok((result, table_stats))
+    }
+
     #[minitrace::trace]
     async fn validate_cache_result(
         &self,
@@ -697,6 +827,15 @@ impl Cache {
         Some(op)
     }
 
+    /// Returns the result for `key` if it's ready and not newer than `ts`,
+    /// without waiting on or starting an execution.
+    fn get_ready(&self, key: &CacheKey, ts: Timestamp) -> Option<CacheResult> {
+        match self.inner.lock().cache.get(key) {
+            Some(CacheEntry::Ready(r)) if r.original_ts <= ts => Some(r.clone()),
+            _ => None,
+        }
+    }
+
     fn remove_waiting(&self, key: &CacheKey, entry_id: u64) {
         self.inner.lock().remove_waiting(key, entry_id)
     }
@@ -0,0 +1,113 @@
+//! Reading and updating sharded counters, retuning each counter's number of
+//! shards from how often updates to it conflict.
+use common::{
+    pause::PauseClient,
+    runtime::Runtime,
+};
+use database::unauthorized_error;
+use errors::ErrorMetadataAnyhowExt;
+use keybroker::Identity;
+use model::counters::CounterModel;
+use usage_tracking::FunctionUsageTracker;
+
+use crate::Application;
+
+impl<RT: Runtime> Application<RT> {
+    pub async fn get_counter(&self, identity: Identity, name: String) -> anyhow::Result<i64> {
+        if !identity.is_admin() {
+            anyhow::bail!(unauthorized_error("get_counter"));
+        }
+        let mut tx = self.begin(identity).await?;
+        CounterModel::new(&mut tx).get(&name).await
+    }
+
+    pub async fn add_to_counter(
+        &self,
+        identity: Identity,
+        name: String,
+        delta: i64,
+    ) -> anyhow::Result<()> {
+        if !identity.is_admin() {
+            anyhow::bail!(unauthorized_error("add_to_counter"));
+        }
+        let result = self
+            .database
+            .execute_with_occ_retries(
+                identity,
+                FunctionUsageTracker::new(),
+                PauseClient::new(),
+                "add_to_counter",
+                |tx| async { CounterModel::new(tx).add(&name, delta).await }.into(),
+            )
+            .await;
+        let (_, _, stats) = match result {
+            Ok(r) => r,
+            Err(e) => {
+                if e.is_occ() {
+                    self.counter_tuner.lock().record(&name, true);
+                }
+                return Err(e);
+            },
+        };
+        let num_shards = {
+            let mut tx = self.begin(Identity::system()).await?;
+            CounterModel::new(&mut tx).num_shards(&name).await?
+        };
+        let recommended = {
+            let mut tuner = self.counter_tuner.lock();
+            for _ in 0..stats.retries {
+                tuner.record(&name, true);
+            }
+            tuner.record(&name, false);
+            tuner.recommend(&name, num_shards)
+        };
+        if let Some(num_shards) = recommended {
+            tracing::info!("Changing counter {name} to {num_shards} shards");
+            self.set_counter_shards_inner(&name, num_shards).await?;
+        }
+        Ok(())
+    }
+
+    /// Set the number of shards the counter is split across, which is
+    /// otherwise tuned automatically.
+    pub async fn set_counter_shards(
+        &self,
+        identity: Identity,
+        name: String,
+        num_shards: u32,
+    ) -> anyhow::Result<()> {
+        if !identity.is_admin() {
+            anyhow::bail!(unauthorized_error("set_counter_shards"));
+        }
+        self.set_counter_shards_inner(&name, num_shards).await
+    }
+
+    async fn set_counter_shards_inner(&self, name: &str, num_shards: u32) -> anyhow::Result<()> {
+        self.database
+            .execute_with_occ_retries(
+                Identity::system(),
+                FunctionUsageTracker::new(),
+                PauseClient::new(),
+                "set_counter_shards",
+                |tx| async { CounterModel::new(tx).set_num_shards(name, num_shards).await }.into(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn delete_counter(&self, identity: Identity, name: String) -> anyhow::Result<()> {
+        if !identity.is_admin() {
+            anyhow::bail!(unauthorized_error("delete_counter"));
+        }
+        self.database
+            .execute_with_occ_retries(
+                identity,
+                FunctionUsageTracker::new(),
+                PauseClient::new(),
+                "delete_counter",
+                |tx| async { CounterModel::new(tx).delete(&name).await }.into(),
+            )
+            .await?;
+        Ok(())
+    }
+}
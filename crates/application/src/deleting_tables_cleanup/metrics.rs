@@ -0,0 +1,23 @@
+use metrics::{
+    log_counter,
+    prometheus::VMHistogram,
+    register_convex_counter,
+    register_convex_histogram,
+    Timer,
+};
+
+register_convex_histogram!(
+    DELETING_TABLES_CLEANUP_CHUNK_SECONDS,
+    "Duration of deleting a chunk of revisions from dropped tables"
+);
+pub fn deleting_tables_cleanup_chunk_timer() -> Timer<VMHistogram> {
+    Timer::new(&DELETING_TABLES_CLEANUP_CHUNK_SECONDS)
+}
+
+register_convex_counter!(
+    DELETING_TABLES_CLEANUP_ROWS_TOTAL,
+    "Number of document revisions deleted from dropped tables",
+);
+pub fn log_deleting_tables_cleanup_rows(rows: usize) {
+    log_counter(&DELETING_TABLES_CLEANUP_ROWS_TOTAL, rows as u64)
+}
@@ -0,0 +1,427 @@
+//! Garbage collection for tables in [`TableState::Deleting`].
+//!
+//! Dropping a table only flips its `_tables` entry to `Deleting`; the
+//! document revisions stay in persistence until this worker removes them.
+//! For a large table that can take hours, so the worker keeps its progress
+//! in memory and exposes it (and a pause switch and rate knob) through
+//! [`DeletingTablesCleanupClient`].
+
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
+    num::NonZeroU32,
+    sync::Arc,
+};
+
+use common::{
+    bootstrap_model::tables::{
+        TableMetadata,
+        TableState,
+        TABLES_TABLE,
+    },
+    document::ParsedDocument,
+    errors::report_error,
+    knobs::{
+        DEFAULT_DOCUMENTS_PAGE_SIZE,
+        DELETING_TABLES_CLEANUP_CHUNK_SIZE,
+        DELETING_TABLES_CLEANUP_ROWS_PER_SECOND,
+        INDEX_RETENTION_DELAY,
+        SYSTEM_TABLE_CLEANUP_FREQUENCY,
+    },
+    persistence::{
+        NoopRetentionValidator,
+        Persistence,
+        TimestampRange,
+    },
+    query::{
+        Order,
+        Query,
+    },
+    runtime::{
+        new_rate_limiter,
+        RateLimiter,
+        Runtime,
+        UnixTimestamp,
+    },
+    types::{
+        TableName,
+        Timestamp,
+    },
+};
+use database::{
+    query::{
+        PaginationOptions,
+        TableFilter,
+    },
+    Database,
+    ResolvedQuery,
+};
+use futures::{
+    future,
+    pin_mut,
+    select_biased,
+    Future,
+    FutureExt,
+    TryStreamExt,
+};
+use governor::Quota;
+use keybroker::Identity;
+use parking_lot::Mutex;
+use rand::Rng;
+use tokio::sync::Notify;
+use value::{
+    InternalDocumentId,
+    TableNamespace,
+    TabletId,
+};
+
+use self::metrics::{
+    deleting_tables_cleanup_chunk_timer,
+    log_deleting_tables_cleanup_rows,
+};
+use crate::metrics::log_worker_starting;
+
+mod metrics;
+
+/// Progress of garbage collection for a single dropped tablet.
+#[derive(Clone, Debug)]
+pub struct DeletingTabletStatus {
+    pub namespace: TableNamespace,
+    pub table_name: TableName,
+    /// When the worker first saw the tablet in `Deleting`. Collection starts
+    /// once `INDEX_RETENTION_DELAY` has passed, so no snapshot read can still
+    /// see the table's documents.
+    pub first_observed: UnixTimestamp,
+    /// Revisions deleted from persistence since this process started.
+    pub revisions_deleted: u64,
+    /// Set after a full pass over the document log found nothing left.
+    pub complete: bool,
+}
+
+/// A single scan over the document log, deleting revisions of every
+/// incomplete `Deleting` tablet.
+#[derive(Clone, Debug)]
+pub struct CleanupPassStatus {
+    pub started: UnixTimestamp,
+    pub finished: Option<UnixTimestamp>,
+    /// Timestamp of the last log entry scanned.
+    pub cursor: Timestamp,
+    /// The pass scans the log up to this timestamp.
+    pub target: Timestamp,
+    pub revisions_deleted: u64,
+}
+
+impl CleanupPassStatus {
+    /// Fraction of the document log scanned in this pass.
+    pub fn progress(&self) -> f64 {
+        if self.finished.is_some() || u64::from(self.target) == 0 {
+            return 1.0;
+        }
+        (u64::from(self.cursor) as f64 / u64::from(self.target) as f64).min(1.0)
+    }
+
+    /// Revisions deleted per second over the duration of the pass.
+    pub fn throughput(&self, now: UnixTimestamp) -> f64 {
+        let end = self.finished.unwrap_or(now);
+        match end.checked_sub(self.started) {
+            Some(elapsed) if !elapsed.is_zero() => {
+                self.revisions_deleted as f64 / elapsed.as_secs_f64()
+            },
+            _ => 0.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DeletingTablesCleanupStatus {
+    pub paused: bool,
+    pub rows_per_second: NonZeroU32,
+    pub tablets: BTreeMap<TabletId, DeletingTabletStatus>,
+    /// The pass in progress, or the most recently finished one.
+    pub last_pass: Option<CleanupPassStatus>,
+}
+
+struct CleanupState {
+    status: DeletingTablesCleanupStatus,
+    /// Bumped when `rows_per_second` changes so the worker rebuilds its rate
+    /// limiter.
+    config_version: u64,
+}
+
+/// Handle for inspecting and controlling the worker.
+#[derive(Clone)]
+pub struct DeletingTablesCleanupClient {
+    state: Arc<Mutex<CleanupState>>,
+    config_changed: Arc<Notify>,
+}
+
+impl DeletingTablesCleanupClient {
+    fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(CleanupState {
+                status: DeletingTablesCleanupStatus {
+                    paused: false,
+                    rows_per_second: *DELETING_TABLES_CLEANUP_ROWS_PER_SECOND,
+                    tablets: BTreeMap::new(),
+                    last_pass: None,
+                },
+                config_version: 0,
+            })),
+            config_changed: Arc::new(Notify::new()),
+        }
+    }
+
+    pub fn status(&self) -> DeletingTablesCleanupStatus {
+        self.state.lock().status.clone()
+    }
+
+    /// Pauses or resumes collection and/or changes its rate. Any change also
+    /// wakes the worker so it starts a pass right away instead of waiting for
+    /// the next scheduled run.
+    pub fn update(&self, paused: Option<bool>, rows_per_second: Option<NonZeroU32>) {
+        {
+            let mut state = self.state.lock();
+            if let Some(paused) = paused {
+                state.status.paused = paused;
+            }
+            if let Some(rows_per_second) = rows_per_second {
+                state.status.rows_per_second = rows_per_second;
+                state.config_version += 1;
+            }
+        }
+        self.config_changed.notify_one();
+    }
+
+    fn is_paused(&self) -> bool {
+        self.state.lock().status.paused
+    }
+
+    async fn wait_until_unpaused(&self) {
+        while self.is_paused() {
+            self.config_changed.notified().await;
+        }
+    }
+}
+
+pub struct DeletingTablesCleanupWorker<RT: Runtime> {
+    runtime: RT,
+    database: Database<RT>,
+    persistence: Arc<dyn Persistence>,
+    client: DeletingTablesCleanupClient,
+}
+
+impl<RT: Runtime> DeletingTablesCleanupWorker<RT> {
+    pub(crate) fn new(
+        runtime: RT,
+        database: Database<RT>,
+        persistence: Arc<dyn Persistence>,
+    ) -> (impl Future<Output = ()> + Send, DeletingTablesCleanupClient) {
+        let client = DeletingTablesCleanupClient::new();
+        let mut worker = DeletingTablesCleanupWorker {
+            runtime,
+            database,
+            persistence,
+            client: client.clone(),
+        };
+        let fut = async move {
+            loop {
+                if let Err(e) = worker.run().await {
+                    report_error(&mut e.context("DeletingTablesCleanupWorker died"));
+                }
+            }
+        };
+        (fut, client)
+    }
+
+    async fn run(&mut self) -> anyhow::Result<()> {
+        tracing::info!("Starting DeletingTablesCleanupWorker");
+        loop {
+            // Jitter the wait between runs to even out load. Changing the
+            // configuration cuts the wait short.
+            let delay = SYSTEM_TABLE_CLEANUP_FREQUENCY.mul_f32(self.runtime.rng().gen());
+            select_biased! {
+                _ = self.client.config_changed.notified().fuse() => {},
+                _ = self.runtime.wait(delay).fuse() => {},
+            }
+            self.client.wait_until_unpaused().await;
+
+            let tablets = self.refresh_deleting_tablets().await?;
+            if tablets.is_empty() {
+                continue;
+            }
+            let _status = log_worker_starting("DeletingTablesCleanup");
+            self.cleanup_pass(tablets).await?;
+        }
+    }
+
+    /// Records all tablets in `Deleting` and returns the ones that are ready
+    /// to be collected.
+    async fn refresh_deleting_tablets(&self) -> anyhow::Result<BTreeSet<TabletId>> {
+        let mut deleting = BTreeMap::new();
+        let mut tx = self.database.begin(Identity::system()).await?;
+        let query = Query::full_table_scan(TABLES_TABLE.clone(), Order::Asc);
+        let mut query_stream = ResolvedQuery::new(&mut tx, TableNamespace::Global, query.clone())?;
+        while let Some(document) = query_stream.next(&mut tx, None).await? {
+            if query_stream.is_approaching_data_limit() {
+                let cursor = query_stream.cursor();
+                tx = self.database.begin(Identity::system()).await?;
+                query_stream = ResolvedQuery::new_bounded(
+                    &mut tx,
+                    TableNamespace::Global,
+                    query.clone(),
+                    PaginationOptions::ManualPagination {
+                        start_cursor: cursor,
+                        maximum_rows_read: None,
+                        maximum_bytes_read: None,
+                    },
+                    None,
+                    TableFilter::IncludePrivateSystemTables,
+                )?;
+            }
+            let table: ParsedDocument<TableMetadata> = document.try_into()?;
+            if matches!(table.state, TableState::Deleting) {
+                let tablet_id = TabletId(table.id().internal_id());
+                let table = table.into_value();
+                deleting.insert(tablet_id, (table.namespace, table.name));
+            }
+        }
+
+        let now = self.runtime.unix_timestamp();
+        let mut state = self.client.state.lock();
+        for (tablet_id, (namespace, table_name)) in deleting {
+            state
+                .status
+                .tablets
+                .entry(tablet_id)
+                .or_insert_with(|| DeletingTabletStatus {
+                    namespace,
+                    table_name,
+                    first_observed: now,
+                    revisions_deleted: 0,
+                    complete: false,
+                });
+        }
+        Ok(state
+            .status
+            .tablets
+            .iter()
+            .filter(|(_, status)| {
+                !status.complete
+                    && now
+                        .checked_sub(status.first_observed)
+                        .is_some_and(|age| age >= *INDEX_RETENTION_DELAY)
+            })
+            .map(|(tablet_id, _)| *tablet_id)
+            .collect())
+    }
+
+    async fn cleanup_pass(&self, tablets: BTreeSet<TabletId>) -> anyhow::Result<()> {
+        let target = *self.database.now_ts_for_reads();
+        tracing::info!(
+            "Cleaning up {} deleting tables up to {target:?}",
+            tablets.len()
+        );
+        self.client.state.lock().status.last_pass = Some(CleanupPassStatus {
+            started: self.runtime.unix_timestamp(),
+            finished: None,
+            cursor: Timestamp::MIN,
+            target,
+            revisions_deleted: 0,
+        });
+
+        let (mut rate_limiter, mut config_version) = self.rate_limiter();
+        let reader = self.persistence.reader();
+        let stream = reader
+            .load_documents(
+                TimestampRange::new(..=target)?,
+                Order::Asc,
+                *DEFAULT_DOCUMENTS_PAGE_SIZE,
+                Arc::new(NoopRetentionValidator),
+            )
+            .try_filter(|(_, id, _)| future::ready(tablets.contains(&id.table())));
+        pin_mut!(stream);
+        let mut chunk = Vec::with_capacity(*DELETING_TABLES_CLEANUP_CHUNK_SIZE);
+        while let Some((ts, id, _)) = stream.try_next().await? {
+            chunk.push((ts, id));
+            if chunk.len() >= *DELETING_TABLES_CLEANUP_CHUNK_SIZE {
+                self.delete_chunk(&mut chunk, &mut rate_limiter, &mut config_version)
+                    .await?;
+            }
+        }
+        self.delete_chunk(&mut chunk, &mut rate_limiter, &mut config_version)
+            .await?;
+
+        let now = self.runtime.unix_timestamp();
+        let mut state = self.client.state.lock();
+        for tablet_id in &tablets {
+            if let Some(status) = state.status.tablets.get_mut(tablet_id) {
+                status.complete = true;
+            }
+        }
+        if let Some(pass) = &mut state.status.last_pass {
+            pass.cursor = target;
+            pass.finished = Some(now);
+            tracing::info!(
+                "Finished cleaning up deleting tables, deleted {} revisions",
+                pass.revisions_deleted
+            );
+        }
+        Ok(())
+    }
+
+    fn rate_limiter(&self) -> (RateLimiter<RT>, u64) {
+        let state = self.client.state.lock();
+        (
+            new_rate_limiter(
+                self.runtime.clone(),
+                Quota::per_second(state.status.rows_per_second),
+            ),
+            state.config_version,
+        )
+    }
+
+    async fn delete_chunk(
+        &self,
+        chunk: &mut Vec<(Timestamp, InternalDocumentId)>,
+        rate_limiter: &mut RateLimiter<RT>,
+        config_version: &mut u64,
+    ) -> anyhow::Result<()> {
+        let Some((last_ts, _)) = chunk.last().copied() else {
+            return Ok(());
+        };
+        self.client.wait_until_unpaused().await;
+        if self.client.state.lock().config_version != *config_version {
+            (*rate_limiter, *config_version) = self.rate_limiter();
+        }
+        // Rate limit between deletes rather than across them, like
+        // SystemTableCleanupWorker does.
+        for _ in 0..chunk.len() {
+            while let Err(not_until) = rate_limiter.check() {
+                let delay = not_until.wait_time_from(self.runtime.monotonic_now().into());
+                self.runtime.wait(delay).await;
+            }
+        }
+
+        let _timer = deleting_tables_cleanup_chunk_timer();
+        let mut deleted_by_tablet: BTreeMap<TabletId, u64> = BTreeMap::new();
+        for (_, id) in chunk.iter() {
+            *deleted_by_tablet.entry(id.table()).or_default() += 1;
+        }
+        let deleted = self.persistence.delete(std::mem::take(chunk)).await?;
+        log_deleting_tables_cleanup_rows(deleted);
+
+        let mut state = self.client.state.lock();
+        for (tablet_id, count) in deleted_by_tablet {
+            if let Some(status) = state.status.tablets.get_mut(&tablet_id) {
+                status.revisions_deleted += count;
+            }
+        }
+        if let Some(pass) = &mut state.status.last_pass {
+            pass.cursor = last_ts;
+            pass.revisions_deleted += deleted as u64;
+        }
+        Ok(())
+    }
+}
@@ -191,6 +191,9 @@ impl<RT: Runtime> Application<RT> {
         )?;
         let ctx = TypecheckContext::new(&evaluated_components, &initializer_evaluator);
         let app = ctx.instantiate_root().await?;
+        let args_validator_warnings = self
+            .check_observed_args(&app, &evaluated_components)
+            .await?;
 
         let schema_change = {
             let mut tx = self.begin(Identity::system()).await?;
@@ -226,6 +229,7 @@ impl<RT: Runtime> Application<RT> {
             analysis: evaluated_components,
             app,
             schema_change,
+            args_validator_warnings,
         };
         Ok(resp)
     }
@@ -691,6 +695,10 @@ pub struct StartPushResponse {
     pub app: CheckedComponent,
 
     pub schema_change: SchemaChange,
+
+    /// Functions whose new argument validators would reject arguments they
+    /// were recently called with.
+    pub args_validator_warnings: Vec<String>,
 }
 
 impl From<NodeDependencyJson> for NodeDependency {
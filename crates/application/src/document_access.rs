@@ -0,0 +1,227 @@
+//! Writes sampled document reads to `_document_access` and reports how much of
+//! each table hasn't been read recently, to inform archival and TTL policies.
+//!
+//! Reads are sampled in the transaction's read path (see
+//! `database::document_access`) and only buffered in memory there, so tracking
+//! never adds writes to the transactions doing the reads.
+use std::time::Duration;
+
+use common::{
+    backoff::Backoff,
+    components::{
+        ComponentId,
+        ComponentPath,
+    },
+    errors::report_error,
+    knobs::{
+        DOCUMENT_ACCESS_FLUSH_INTERVAL,
+        DOCUMENT_ACCESS_RESOLUTION,
+        DOCUMENT_ACCESS_SAMPLE_RATE,
+    },
+    pause::PauseClient,
+    runtime::{
+        Runtime,
+        UnixTimestamp,
+    },
+};
+use database::{
+    document_access::{
+        restore_sampled_document_reads,
+        take_sampled_document_reads,
+    },
+    unauthorized_error,
+    Database,
+    TableModel,
+};
+use futures::Future;
+use keybroker::Identity;
+use model::document_access::{
+    types::DocumentAccess,
+    DocumentAccessModel,
+};
+use usage_tracking::FunctionUsageTracker;
+use value::{
+    ResolvedDocumentId,
+    TableName,
+};
+
+use crate::Application;
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// How many sampled reads are written per transaction.
+const FLUSH_BATCH_SIZE: usize = 256;
+
+/// Periodically writes the sampled reads buffered in memory to
+/// `_document_access`.
+pub struct DocumentAccessWorker<RT: Runtime> {
+    runtime: RT,
+    database: Database<RT>,
+}
+
+impl<RT: Runtime> DocumentAccessWorker<RT> {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(runtime: RT, database: Database<RT>) -> impl Future<Output = ()> + Send {
+        let worker = Self { runtime, database };
+        async move {
+            if *DOCUMENT_ACCESS_SAMPLE_RATE <= 0.0 {
+                tracing::info!("Document access tracking is disabled");
+                return;
+            }
+            let mut backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
+            while let Err(mut e) = worker.run(&mut backoff).await {
+                report_error(&mut e);
+                let delay = backoff.fail(&mut worker.runtime.rng());
+                tracing::error!("DocumentAccessWorker failed, sleeping {delay:?}");
+                worker.runtime.wait(delay).await;
+            }
+        }
+    }
+
+    async fn run(&self, backoff: &mut Backoff) -> anyhow::Result<()> {
+        tracing::info!("Starting DocumentAccessWorker");
+        loop {
+            self.runtime.wait(*DOCUMENT_ACCESS_FLUSH_INTERVAL).await;
+            self.flush().await?;
+            backoff.reset();
+        }
+    }
+
+    async fn flush(&self) -> anyhow::Result<()> {
+        let reads: Vec<(ResolvedDocumentId, UnixTimestamp)> =
+            take_sampled_document_reads().into_iter().collect();
+        let mut written = 0;
+        for (i, batch) in reads.chunks(FLUSH_BATCH_SIZE).enumerate() {
+            let result = self
+                .database
+                .execute_with_occ_retries(
+                    Identity::system(),
+                    FunctionUsageTracker::new(),
+                    PauseClient::new(),
+                    "document_access_flush",
+                    |tx| {
+                        async move {
+                            let mut written = 0;
+                            for (id, last_read) in batch {
+                                let access = DocumentAccess {
+                                    tablet_id: id.tablet_id,
+                                    internal_id: id.internal_id(),
+                                    last_read: *last_read,
+                                };
+                                if DocumentAccessModel::new(tx)
+                                    .record_read(access, *DOCUMENT_ACCESS_RESOLUTION)
+                                    .await?
+                                {
+                                    written += 1;
+                                }
+                            }
+                            Ok(written)
+                        }
+                        .into()
+                    },
+                )
+                .await;
+            match result {
+                Ok((_, batch_written, _)) => written += batch_written,
+                Err(e) => {
+                    // Keep the reads that weren't written for the next flush.
+                    restore_sampled_document_reads(
+                        reads[i * FLUSH_BATCH_SIZE..].iter().cloned().collect(),
+                    );
+                    return Err(e);
+                },
+            }
+        }
+        tracing::debug!("Wrote {written} of {} sampled document reads", reads.len());
+        Ok(())
+    }
+}
+
+/// How much of a table has been read since the report's cutoff.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TableColdData {
+    pub component: ComponentPath,
+    pub table_name: TableName,
+    pub documents: u64,
+    /// Documents with at least one sampled read.
+    pub sampled: u64,
+    /// Documents with a sampled read since the cutoff.
+    pub read_since: u64,
+}
+
+impl TableColdData {
+    /// Documents without a sampled read since the cutoff. Reads are sampled,
+    /// so this is an upper bound on the documents that weren't read at all.
+    pub fn cold(&self) -> u64 {
+        self.documents.saturating_sub(self.read_since)
+    }
+}
+
+impl<RT: Runtime> Application<RT> {
+    /// Reports, for each user table, how many of its documents had a sampled
+    /// read at or after `since`.
+    pub async fn cold_data_report(
+        &self,
+        identity: Identity,
+        since: UnixTimestamp,
+    ) -> anyhow::Result<Vec<TableColdData>> {
+        anyhow::ensure!(
+            identity.is_admin() || identity.is_system(),
+            unauthorized_error("cold_data_report")
+        );
+        let mut tx = self.begin(identity).await?;
+        let component_paths = tx.all_component_paths();
+        let tables: Vec<_> = tx
+            .table_mapping()
+            .iter_active_user_tables()
+            .map(|(tablet_id, namespace, _, table_name)| {
+                (tablet_id, ComponentId::from(namespace), table_name.clone())
+            })
+            .collect();
+        let mut report = vec![];
+        for (tablet_id, component_id, table_name) in tables {
+            let documents = TableModel::new(&mut tx).count_tablet(tablet_id).await?;
+            let (sampled, read_since) = DocumentAccessModel::new(&mut tx)
+                .count_reads(tablet_id, since)
+                .await?;
+            report.push(TableColdData {
+                component: component_paths
+                    .get(&component_id)
+                    .cloned()
+                    .unwrap_or_default(),
+                table_name,
+                documents,
+                sampled,
+                read_since,
+            });
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::components::ComponentPath;
+
+    use super::TableColdData;
+
+    #[test]
+    fn test_cold_is_bounded_by_documents() -> anyhow::Result<()> {
+        let table = TableColdData {
+            component: ComponentPath::root(),
+            table_name: "messages".parse()?,
+            documents: 10,
+            sampled: 6,
+            read_since: 4,
+        };
+        assert_eq!(table.cold(), 6);
+        // Access records outlive deleted documents.
+        let table = TableColdData {
+            documents: 2,
+            ..table
+        };
+        assert_eq!(table.cold(), 0);
+        Ok(())
+    }
+}
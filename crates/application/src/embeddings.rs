@@ -0,0 +1,414 @@
+//! Generating embeddings for vector indexes on write. A vector index whose
+//! schema sets `embedFrom` has its vector field filled in from a text field of
+//! the same document: whenever a commit changes the text, the
+//! [`EmbeddingPipeline`] commit hook embeds it with the deployment's
+//! [`EmbeddingProvider`] and writes the result to the vector field, where the
+//! vector index picks it up as usual.
+//!
+//! Embedding happens after the commit, so the vector field lags the text for a
+//! moment after every write. A write that sets the vector field itself is left
+//! alone.
+use std::{
+    borrow::Borrow,
+    collections::{
+        btree_map::Entry,
+        BTreeMap,
+        BTreeSet,
+    },
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use common::{
+    bootstrap_model::schema::SchemaState,
+    document::ResolvedDocument,
+    pause::PauseClient,
+    persistence::PersistenceReader,
+    runtime::Runtime,
+    schemas::DatabaseSchema,
+    types::MaybeValue,
+};
+use database::{
+    commit_hooks::{
+        CommitHook,
+        CommittedWrites,
+    },
+    Database,
+    PatchValue,
+    SchemaModel,
+    UserFacingModel,
+};
+use errors::{
+    ErrorMetadata,
+    ErrorMetadataAnyhowExt,
+};
+use keybroker::Identity;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use url::Url;
+use usage_tracking::FunctionUsageTracker;
+use value::{
+    ConvexArray,
+    ConvexObject,
+    ConvexValue,
+    FieldName,
+    FieldPath,
+    IdentifierFieldName,
+    InternalDocumentId,
+    ResolvedDocumentId,
+    TableNamespace,
+};
+
+/// The most texts sent to the embedding provider in one request.
+pub const MAX_EMBEDDING_BATCH_SIZE: usize = 64;
+
+/// Turns texts into embeddings. Errors tagged as bad requests mean the texts
+/// can never be embedded, and are skipped. Other errors are retried.
+#[async_trait]
+pub trait EmbeddingProvider: Send + Sync {
+    /// Returns one embedding per text, in the same order.
+    async fn embed(&self, texts: Vec<String>) -> anyhow::Result<Vec<Vec<f32>>>;
+}
+
+/// Embeds texts with an OpenAI-compatible `/embeddings` endpoint. Hosted
+/// providers and local model servers (e.g. for ONNX models) commonly expose
+/// this API.
+pub struct HttpEmbeddingProvider {
+    client: reqwest::Client,
+    url: Url,
+    model: String,
+    api_key: Option<String>,
+}
+
+impl HttpEmbeddingProvider {
+    pub fn new(url: Url, model: String, api_key: Option<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url,
+            model,
+            api_key,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+#[async_trait]
+impl EmbeddingProvider for HttpEmbeddingProvider {
+    async fn embed(&self, texts: Vec<String>) -> anyhow::Result<Vec<Vec<f32>>> {
+        let num_texts = texts.len();
+        let mut request = self.client.post(self.url.clone()).json(&EmbeddingRequest {
+            model: &self.model,
+            input: texts,
+        });
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        // Rate limits are worth retrying, but other client errors will fail
+        // the same way every time.
+        if status.is_client_error() && status != reqwest::StatusCode::TOO_MANY_REQUESTS {
+            let body = response.text().await.unwrap_or_default();
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "EmbeddingRequestRejected",
+                format!("The embedding provider rejected the request with {status}: {body}"),
+            ));
+        }
+        let mut response: EmbeddingResponse = response.error_for_status()?.json().await?;
+        anyhow::ensure!(
+            response.data.len() == num_texts,
+            "Embedding provider returned {} embeddings for {num_texts} texts",
+            response.data.len()
+        );
+        response.data.sort_by_key(|data| data.index);
+        Ok(response
+            .data
+            .into_iter()
+            .map(|data| data.embedding)
+            .collect())
+    }
+}
+
+/// A document whose vector field needs to be embedded from its text.
+struct PendingEmbedding<'a> {
+    document: &'a ResolvedDocument,
+    namespace: TableNamespace,
+    text_field: FieldPath,
+    vector_field: FieldPath,
+    dimension: usize,
+}
+
+impl PendingEmbedding<'_> {
+    fn text(&self) -> &str {
+        match self.document.value().get_path(&self.text_field) {
+            Some(ConvexValue::String(text)) => text,
+            _ => unreachable!("Only documents with text are embedded"),
+        }
+    }
+}
+
+/// An embedding to write back to a document, as long as its text still
+/// matches.
+struct EmbeddingUpdate {
+    id: ResolvedDocumentId,
+    namespace: TableNamespace,
+    text_field: FieldPath,
+    text: ConvexValue,
+    vector_field: FieldPath,
+    vector: ConvexValue,
+}
+
+/// Commit hook that embeds the text fields named by `embedFrom` in vector
+/// indexes whenever they change.
+pub struct EmbeddingPipeline<RT: Runtime> {
+    database: Database<RT>,
+    reader: Arc<dyn PersistenceReader>,
+    provider: Arc<dyn EmbeddingProvider>,
+}
+
+impl<RT: Runtime> EmbeddingPipeline<RT> {
+    pub fn new(
+        database: Database<RT>,
+        reader: Arc<dyn PersistenceReader>,
+        provider: Arc<dyn EmbeddingProvider>,
+    ) -> Self {
+        Self {
+            database,
+            reader,
+            provider,
+        }
+    }
+
+    async fn pending_embeddings<'a>(
+        &self,
+        commit: &'a CommittedWrites,
+    ) -> anyhow::Result<Vec<PendingEmbedding<'a>>> {
+        let mut tx = self.database.begin(Identity::system()).await?;
+        let table_mapping = tx.table_mapping().clone();
+        let mut schemas: BTreeMap<TableNamespace, Option<DatabaseSchema>> = BTreeMap::new();
+        let mut pending = vec![];
+        for (_, document) in &commit.writes {
+            let Some(document) = document else {
+                continue;
+            };
+            let tablet_id = document.id().tablet_id;
+            if table_mapping.is_system_tablet(tablet_id) {
+                continue;
+            }
+            // The table may have been deleted since the commit.
+            let (Ok(namespace), Ok(table_name)) = (
+                table_mapping.tablet_namespace(tablet_id),
+                table_mapping.tablet_name(tablet_id),
+            ) else {
+                continue;
+            };
+            let schema = match schemas.entry(namespace) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => entry.insert(
+                    SchemaModel::new(&mut tx, namespace)
+                        .get_by_state(SchemaState::Active)
+                        .await?
+                        .map(|(_, schema)| schema),
+                ),
+            };
+            let Some(table) = schema
+                .as_ref()
+                .and_then(|schema| schema.tables.get(&table_name))
+            else {
+                continue;
+            };
+            for index in table.vector_indexes.values() {
+                let Some(ref text_field) = index.embed_from else {
+                    continue;
+                };
+                let Some(ConvexValue::String(_)) = document.value().get_path(text_field) else {
+                    continue;
+                };
+                pending.push(PendingEmbedding {
+                    document,
+                    namespace,
+                    text_field: text_field.clone(),
+                    vector_field: index.vector_field.clone(),
+                    dimension: index.dimension.into(),
+                });
+            }
+        }
+        if pending.is_empty() {
+            return Ok(pending);
+        }
+
+        // Only embed text that this commit changed, and leave vectors that
+        // the commit wrote itself alone.
+        let ids: BTreeSet<_> = pending
+            .iter()
+            .map(|p| (InternalDocumentId::from(p.document.id()), commit.ts))
+            .collect();
+        let previous_revisions = self
+            .reader
+            .previous_revisions(ids, self.database.retention_validator())
+            .await?;
+        pending.retain(|p| {
+            let value = p.document.value();
+            let previous = previous_revisions
+                .get(&(InternalDocumentId::from(p.document.id()), commit.ts))
+                .and_then(|(_, document)| document.as_ref())
+                .map(|document| document.value());
+            let text_changed = previous.map_or(true, |previous| {
+                previous.get_path(&p.text_field) != value.get_path(&p.text_field)
+            });
+            let vector = value.get_path(&p.vector_field);
+            let vector_written = vector.is_some()
+                && vector != previous.and_then(|previous| previous.get_path(&p.vector_field));
+            text_changed && !vector_written
+        });
+        Ok(pending)
+    }
+
+    async fn embed(
+        &self,
+        pending: &[PendingEmbedding<'_>],
+    ) -> anyhow::Result<Vec<EmbeddingUpdate>> {
+        let mut updates = vec![];
+        for batch in pending.chunks(MAX_EMBEDDING_BATCH_SIZE) {
+            let texts = batch.iter().map(|p| p.text().to_string()).collect();
+            let embeddings = match self.provider.embed(texts).await {
+                Ok(embeddings) => embeddings,
+                Err(e) if e.is_bad_request() => {
+                    tracing::warn!("Skipping {} embeddings: {e:#}", batch.len());
+                    continue;
+                },
+                Err(e) => return Err(e),
+            };
+            anyhow::ensure!(
+                embeddings.len() == batch.len(),
+                "Embedding provider returned {} embeddings for {} texts",
+                embeddings.len(),
+                batch.len()
+            );
+            for (p, embedding) in batch.iter().zip(embeddings) {
+                if embedding.len() != p.dimension {
+                    tracing::warn!(
+                        "Skipping embedding for {}: expected {} dimensions, got {}",
+                        p.vector_field,
+                        p.dimension,
+                        embedding.len()
+                    );
+                    continue;
+                }
+                let vector = ConvexArray::try_from(
+                    embedding
+                        .into_iter()
+                        .map(|x| ConvexValue::Float64(x as f64))
+                        .collect::<Vec<_>>(),
+                )?;
+                updates.push(EmbeddingUpdate {
+                    id: p.document.id(),
+                    namespace: p.namespace,
+                    text_field: p.text_field.clone(),
+                    text: ConvexValue::String(p.text().try_into()?),
+                    vector_field: p.vector_field.clone(),
+                    vector: ConvexValue::Array(vector),
+                });
+            }
+        }
+        Ok(updates)
+    }
+}
+
+/// Sets `value` below `path` in `object`, creating intermediate objects as
+/// needed.
+fn set_path(
+    object: ConvexObject,
+    path: &[IdentifierFieldName],
+    value: ConvexValue,
+) -> anyhow::Result<ConvexObject> {
+    let (first, rest) = path.split_first().expect("Empty FieldPath?");
+    let mut fields: BTreeMap<FieldName, ConvexValue> = object.into();
+    let value = if rest.is_empty() {
+        value
+    } else {
+        let inner = match fields.remove::<str>(first.borrow()) {
+            Some(ConvexValue::Object(inner)) => inner,
+            _ => ConvexObject::empty(),
+        };
+        ConvexValue::Object(set_path(inner, rest, value)?)
+    };
+    fields.insert(first.clone().into(), value);
+    fields.try_into()
+}
+
+#[async_trait]
+impl<RT: Runtime> CommitHook for EmbeddingPipeline<RT> {
+    fn name(&self) -> &'static str {
+        "embedding_pipeline"
+    }
+
+    async fn on_commit(&self, commit: &CommittedWrites) -> anyhow::Result<()> {
+        let pending = self.pending_embeddings(commit).await?;
+        if pending.is_empty() {
+            return Ok(());
+        }
+        let updates = self.embed(&pending).await?;
+        if updates.is_empty() {
+            return Ok(());
+        }
+        self.database
+            .execute_with_occ_retries(
+                Identity::system(),
+                FunctionUsageTracker::new(),
+                PauseClient::new(),
+                "embedding_pipeline",
+                |tx| {
+                    async {
+                        for update in &updates {
+                            let Some(document) = tx.get(update.id).await? else {
+                                continue;
+                            };
+                            // If the text has changed again, a later commit
+                            // will embed the new text.
+                            let value = document.into_value().0;
+                            if value.get_path(&update.text_field) != Some(&update.text) {
+                                continue;
+                            }
+                            let field = update.vector_field.fields()[0].clone();
+                            let updated = set_path(
+                                value,
+                                update.vector_field.fields(),
+                                update.vector.clone(),
+                            )?;
+                            let top_level = updated.get::<str>(field.borrow()).cloned();
+                            let patch = PatchValue::from(BTreeMap::from([(
+                                field.into(),
+                                MaybeValue(top_level),
+                            )]));
+                            UserFacingModel::new(tx, update.namespace)
+                                .patch(update.id.developer_id, patch)
+                                .await?;
+                        }
+                        Ok(())
+                    }
+                    .into()
+                },
+            )
+            .await?;
+        Ok(())
+    }
+}
@@ -23,7 +23,10 @@ use common::{
     self,
     async_compat::TokioAsyncWriteCompatExt,
     backoff::Backoff,
-    bootstrap_model::tables::TABLES_TABLE,
+    bootstrap_model::{
+        schema::SchemaState,
+        tables::TABLES_TABLE,
+    },
     components::{
         ComponentId,
         ComponentName,
@@ -35,7 +38,9 @@ use common::{
     },
     errors::report_error,
     execution_context::ExecutionId,
+    knobs::SNAPSHOT_EXPORT_PROGRESS_INTERVAL,
     runtime::Runtime,
+    schemas::DatabaseSchema,
     types::{
         IndexId,
         ObjectKey,
@@ -48,6 +53,7 @@ use common::{
 use database::{
     Database,
     IndexModel,
+    SchemaModel,
     SystemMetadataModel,
     TableSummary,
     Transaction,
@@ -66,10 +72,19 @@ use keybroker::Identity;
 use mime2ext::mime2ext;
 use model::{
     components::ComponentsModel,
+    export_watermarks::{
+        types::{
+            ExportWatermark,
+            WatermarkMode,
+            WATERMARK_FIELD,
+        },
+        ExportWatermarksModel,
+    },
     exports::{
         types::{
             Export,
             ExportFormat,
+            ExportProgress,
             ExportRequestor,
         },
         ExportsModel,
@@ -112,16 +127,23 @@ use usage_tracking::{
 };
 use value::{
     export::ValueFormat,
+    DeveloperDocumentId,
     TableNamespace,
     TableNumber,
     TabletId,
 };
 
+use self::parquet_table::{
+    ParquetTableSchema,
+    ParquetTableWriter,
+};
 use crate::metrics::{
     export_timer,
     log_worker_starting,
 };
 
+mod parquet_table;
+
 const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
 const MAX_BACKOFF: Duration = Duration::from_secs(900); // 15 minutes
 static AFTER_DOCUMENTS_CLEAN: Bytes = Bytes::from_static("\n".as_bytes());
@@ -141,6 +163,35 @@ check out [the docs](https://docs.convex.dev/database/import-export/export) or
 ask us in [Discord](http://convex.dev/community).
 "#;
 
+static PARQUET_README_MD_CONTENTS: &str = r#"# Welcome to your Convex Parquet export!
+
+This ZIP file contains a snapshot of the tables in your Convex deployment.
+
+Documents for each table are stored in <table_name>/documents.parquet files.
+Columns are derived from your schema: fields with a scalar type get a typed
+column, and other fields are stored as JSON strings. Tables without a schema
+have a single `document` column containing each document as JSON.
+
+This format is meant for loading into analytics tools, and can't be imported
+back into Convex. Use a ZIP export for backups.
+"#;
+
+/// How user table documents are encoded in the snapshot.
+enum TableEncoding {
+    Jsonl,
+    /// Active schemas by namespace, for deriving each table's columns.
+    Parquet(BTreeMap<TableNamespace, DatabaseSchema>),
+}
+
+impl TableEncoding {
+    fn readme(&self) -> &'static str {
+        match self {
+            TableEncoding::Jsonl => README_MD_CONTENTS,
+            TableEncoding::Parquet(_) => PARQUET_README_MD_CONTENTS,
+        }
+    }
+}
+
 pub struct ExportWorker<RT: Runtime> {
     runtime: RT,
     database: Database<RT>,
@@ -169,6 +220,74 @@ impl ComponentTree {
         }
         Ok(Self { id, children })
     }
+
+    /// Namespaces of this component and all of its descendants.
+    fn namespaces(&self) -> Vec<TableNamespace> {
+        let mut namespaces = vec![self.id.into()];
+        for child in self.children.values() {
+            namespaces.extend(child.namespaces());
+        }
+        namespaces
+    }
+}
+
+/// Counts what an export has written and periodically saves it to the
+/// export's document, so subscribed clients see live progress.
+struct ExportProgressTracker<RT: Runtime> {
+    runtime: RT,
+    database: Database<RT>,
+    /// Set when the export has a document to report progress to.
+    export_id: Option<DeveloperDocumentId>,
+    progress: ExportProgress,
+    last_saved: Option<tokio::time::Instant>,
+}
+
+impl<RT: Runtime> ExportProgressTracker<RT> {
+    fn new(runtime: RT, database: Database<RT>, export_id: Option<DeveloperDocumentId>) -> Self {
+        Self {
+            runtime,
+            database,
+            export_id,
+            progress: ExportProgress::default(),
+            last_saved: None,
+        }
+    }
+
+    async fn start_table(&mut self, component_path: &ComponentPath, table_name: &TableName) {
+        self.progress.component_path = component_path.clone();
+        self.progress.current_table = Some(table_name.clone());
+        self.maybe_save().await;
+    }
+
+    async fn record(&mut self, num_documents: u64, num_bytes: u64) {
+        self.progress.documents_written += num_documents;
+        self.progress.bytes_written += num_bytes;
+        self.maybe_save().await;
+    }
+
+    async fn maybe_save(&mut self) {
+        let Some(export_id) = self.export_id else {
+            return;
+        };
+        let now = self.runtime.monotonic_now();
+        if let Some(last_saved) = self.last_saved
+            && now - last_saved < *SNAPSHOT_EXPORT_PROGRESS_INTERVAL
+        {
+            return;
+        }
+        self.last_saved = Some(now);
+        // Ignore errors because progress is only informational, and the next
+        // save will catch up.
+        let _result: anyhow::Result<()> = try {
+            let mut tx = self.database.begin(Identity::system()).await?;
+            ExportsModel::new(&mut tx)
+                .update_progress(export_id, self.progress.clone())
+                .await?;
+            self.database
+                .commit_with_write_source(tx, "export_worker_update_progress")
+                .await?;
+        };
+    }
 }
 
 impl<RT: Runtime> ExportWorker<RT> {
@@ -290,13 +409,46 @@ impl<RT: Runtime> ExportWorker<RT> {
         format: ExportFormat,
         component: ComponentId,
         requestor: ExportRequestor,
+        export_id: Option<DeveloperDocumentId>,
     ) -> anyhow::Result<(Timestamp, ObjectKey, FunctionUsageTracker)> {
         tracing::info!("Beginning snapshot export...");
         let storage = &self.storage;
-        let (ts, tables, component_ids_to_paths, by_id_indexes, system_tables, component_tree) = {
+        let (
+            ts,
+            tables,
+            component_ids_to_paths,
+            by_id_indexes,
+            system_tables,
+            component_tree,
+            table_encoding,
+            watermark,
+        ) = {
             let mut tx = self.database.begin(Identity::system()).await?;
             let by_id_indexes = IndexModel::new(&mut tx).by_id_indexes().await?;
+            let watermark = match export_id {
+                Some(export_id) => {
+                    ExportWatermarksModel::new(&mut tx)
+                        .watermark_for_export(export_id)
+                        .await?
+                },
+                None => None,
+            };
             let component_tree = ComponentTree::new(&mut tx, component).await?;
+            let table_encoding = match format {
+                ExportFormat::Zip { .. } => TableEncoding::Jsonl,
+                ExportFormat::Parquet { .. } => {
+                    let mut schemas = BTreeMap::new();
+                    for namespace in component_tree.namespaces() {
+                        if let Some((_, schema)) = SchemaModel::new(&mut tx, namespace)
+                            .get_by_state(SchemaState::Active)
+                            .await?
+                        {
+                            schemas.insert(namespace, schema);
+                        }
+                    }
+                    TableEncoding::Parquet(schemas)
+                },
+            };
             let snapshot = self.database.snapshot(tx.begin_timestamp())?;
             let tables: BTreeMap<_, _> = snapshot
                 .table_registry
@@ -326,35 +478,44 @@ impl<RT: Runtime> ExportWorker<RT> {
                 by_id_indexes,
                 system_tables,
                 component_tree,
+                table_encoding,
+                watermark,
             )
         };
-        match format {
-            ExportFormat::Zip { include_storage } => {
-                // Start upload.
-                let mut upload = storage.start_upload().await?;
-                let (sender, receiver) = mpsc::channel::<Bytes>(1);
-                let uploader =
-                    upload.try_write_parallel_and_hash(ReceiverStream::new(receiver).map(Ok));
-                let writer = ChannelWriter::new(sender, 5 * (1 << 20));
-                let usage = FunctionUsageTracker::new();
-
-                let zipper = self.construct_zip_snapshot(
-                    writer,
-                    component_tree,
-                    tables.clone(),
-                    &component_ids_to_paths,
-                    ts,
-                    by_id_indexes,
-                    system_tables,
-                    include_storage,
-                    usage.clone(),
-                    requestor,
-                );
-                let (_, ()) = try_join!(uploader, zipper)?;
-                let zip_object_key = upload.complete().await?;
-                Ok((*ts, zip_object_key, usage))
-            },
-        }
+        let mut progress =
+            ExportProgressTracker::new(self.runtime.clone(), self.database.clone(), export_id);
+        let namespaces = component_tree.namespaces();
+        progress.progress.total_documents = tables
+            .values()
+            .filter(|(namespace, ..)| namespaces.contains(namespace))
+            .map(|(.., table_summary)| table_summary.num_values() as u64)
+            .sum();
+        // Both formats are a ZIP of per-table files, and only differ in how user
+        // tables are encoded.
+        let mut upload = storage.start_upload().await?;
+        let (sender, receiver) = mpsc::channel::<Bytes>(1);
+        let uploader = upload.try_write_parallel_and_hash(ReceiverStream::new(receiver).map(Ok));
+        let writer = ChannelWriter::new(sender, 5 * (1 << 20));
+        let usage = FunctionUsageTracker::new();
+
+        let zipper = self.construct_zip_snapshot(
+            writer,
+            component_tree,
+            tables.clone(),
+            &component_ids_to_paths,
+            ts,
+            by_id_indexes,
+            system_tables,
+            format.include_storage(),
+            &table_encoding,
+            watermark.as_ref(),
+            usage.clone(),
+            requestor,
+            &mut progress,
+        );
+        let (_, ()) = try_join!(uploader, zipper)?;
+        let zip_object_key = upload.complete().await?;
+        Ok((*ts, zip_object_key, usage))
     }
 
     #[async_recursion]
@@ -369,8 +530,11 @@ impl<RT: Runtime> ExportWorker<RT> {
         by_id_indexes: &BTreeMap<TabletId, IndexId>,
         system_tables: &BTreeMap<(TableNamespace, TableName), TabletId>,
         include_storage: bool,
+        table_encoding: &'a TableEncoding,
+        watermark: Option<&'a ExportWatermark>,
         usage: FunctionUsageTracker,
         requestor: ExportRequestor,
+        progress: &'a mut ExportProgressTracker<RT>,
     ) -> anyhow::Result<()> {
         let namespace: TableNamespace = component_tree.id.into();
         let component_path = component_ids_to_paths
@@ -417,6 +581,9 @@ impl<RT: Runtime> ExportWorker<RT> {
                 .get(tablet_id)
                 .context("_file_storage.by_id does not exist")?;
 
+            progress
+                .start_table(&component_path, &FILE_STORAGE_VIRTUAL_TABLE)
+                .await;
             // First write metadata to _storage/documents.jsonl
             let mut table_upload = zip_snapshot_upload
                 .start_system_table(path_prefix, FILE_STORAGE_VIRTUAL_TABLE.clone())
@@ -440,6 +607,8 @@ impl<RT: Runtime> ExportWorker<RT> {
                         size: Some(file_storage_entry.size),
                         content_type: file_storage_entry.content_type.clone(),
                         internal_id: Some(file_storage_entry.storage_id.to_string()),
+                        filename: file_storage_entry.filename.clone(),
+                        tags: file_storage_entry.tags.clone(),
                     }))
                     .await?;
             }
@@ -493,32 +662,67 @@ impl<RT: Runtime> ExportWorker<RT> {
                     requestor.usage_tag().to_string(),
                     file_stream.content_length as u64,
                 );
+                let file_size = file_stream.content_length as u64;
                 zip_snapshot_upload
                     .stream_full_file(path, file_stream.stream)
                     .await?;
+                progress.record(1, file_size).await;
             }
         }
 
+        // Noise is added to documents as they're read, and the watermark field
+        // as they're encoded.
+        let watermark_token = watermark
+            .filter(|watermark| watermark.mode == WatermarkMode::Field)
+            .map(|watermark| watermark.token.clone());
         for tablet_id in tablet_ids.iter() {
             let (_, _, table_name, table_summary) =
                 tables.remove(tablet_id).expect("table should have details");
+            progress.start_table(&component_path, &table_name).await;
             let by_id = by_id_indexes
                 .get(tablet_id)
                 .ok_or_else(|| anyhow::anyhow!("no by_id index for {} found", tablet_id))?;
 
-            let mut generated_schema = GeneratedSchema::new(table_summary.inferred_type().into());
-            if ExportContext::is_ambiguous(table_summary.inferred_type()) {
-                let table_iterator = self.database.table_iterator(snapshot_ts, 1000, None);
-                let stream = table_iterator.stream_documents_in_table(*tablet_id, *by_id, None);
-                pin_mut!(stream);
-                while let Some((doc, _ts)) = stream.try_next().await? {
-                    generated_schema.insert(doc.value(), doc.developer_id());
-                }
-            }
-
-            let mut table_upload = zip_snapshot_upload
-                .start_table(path_prefix, table_name.clone(), generated_schema)
-                .await?;
+            let mut table_upload = match table_encoding {
+                TableEncoding::Jsonl => {
+                    let mut generated_schema =
+                        GeneratedSchema::new(table_summary.inferred_type().into());
+                    if ExportContext::is_ambiguous(table_summary.inferred_type()) {
+                        let table_iterator = self.database.table_iterator(snapshot_ts, 1000, None);
+                        let stream =
+                            table_iterator.stream_documents_in_table(*tablet_id, *by_id, None);
+                        pin_mut!(stream);
+                        while let Some((doc, _ts)) = stream.try_next().await? {
+                            generated_schema.insert(doc.value(), doc.developer_id());
+                        }
+                    }
+                    SnapshotTableUpload::Jsonl(
+                        zip_snapshot_upload
+                            .start_table(path_prefix, table_name.clone(), generated_schema)
+                            .await?
+                            .with_watermark(watermark_token.clone()),
+                    )
+                },
+                TableEncoding::Parquet(schemas) => {
+                    // Only use the schema's types if they're enforced, since otherwise
+                    // documents may not match them.
+                    let document_schema = schemas
+                        .get(&namespace)
+                        .filter(|schema| schema.schema_validation)
+                        .and_then(|schema| schema.tables.get(&table_name))
+                        .and_then(|table| table.document_type.as_ref());
+                    SnapshotTableUpload::Parquet(
+                        zip_snapshot_upload
+                            .start_parquet_table(
+                                path_prefix,
+                                table_name.clone(),
+                                ParquetTableSchema::new(document_schema)
+                                    .with_watermark(watermark_token.clone()),
+                            )
+                            .await?,
+                    )
+                },
+            };
 
             let table_iterator = self.database.table_iterator(snapshot_ts, 1000, None);
             let stream = table_iterator.stream_documents_in_table(*tablet_id, *by_id, None);
@@ -526,13 +730,21 @@ impl<RT: Runtime> ExportWorker<RT> {
 
             // Write documents from stream to table uploads
             while let Some((doc, _ts)) = stream.try_next().await? {
+                let doc_size = doc.size() as u64;
                 usage.track_database_egress_size(
                     component_path.clone(),
                     table_name.to_string(),
-                    doc.size() as u64,
+                    doc_size,
                     false,
                 );
+                let doc = match watermark {
+                    Some(watermark) => doc.replace_value(
+                        watermark.apply(doc.developer_id(), doc.value().0.clone())?,
+                    )?,
+                    None => doc,
+                };
                 table_upload.write(doc).await?;
+                progress.record(1, doc_size).await;
             }
             table_upload.complete().await?;
         }
@@ -554,8 +766,11 @@ impl<RT: Runtime> ExportWorker<RT> {
                 by_id_indexes,
                 system_tables,
                 include_storage,
+                table_encoding,
+                watermark,
                 usage.clone(),
                 requestor,
+                progress,
             )
             .await?;
         }
@@ -573,10 +788,14 @@ impl<RT: Runtime> ExportWorker<RT> {
         by_id_indexes: BTreeMap<TabletId, IndexId>,
         system_tables: BTreeMap<(TableNamespace, TableName), TabletId>,
         include_storage: bool,
+        table_encoding: &TableEncoding,
+        watermark: Option<&ExportWatermark>,
         usage: FunctionUsageTracker,
         requestor: ExportRequestor,
+        progress: &mut ExportProgressTracker<RT>,
     ) -> anyhow::Result<()> {
-        let mut zip_snapshot_upload = ZipSnapshotUpload::new(&mut writer).await?;
+        let mut zip_snapshot_upload =
+            ZipSnapshotUpload::new(&mut writer, table_encoding.readme()).await?;
 
         self.write_component(
             "",
@@ -588,8 +807,11 @@ impl<RT: Runtime> ExportWorker<RT> {
             &by_id_indexes,
             &system_tables,
             include_storage,
+            table_encoding,
+            watermark,
             usage,
             requestor,
+            progress,
         )
         .await?;
 
@@ -604,7 +826,12 @@ impl<RT: Runtime> ExportWorker<RT> {
         export: ParsedDocument<Export>,
     ) -> anyhow::Result<()> {
         let (ts, object_key, usage) = self
-            .export_inner(export.format(), export.component(), export.requestor())
+            .export_inner(
+                export.format(),
+                export.component(),
+                export.requestor(),
+                Some(export.id().into()),
+            )
             .await?;
 
         let mut tx = self.database.begin(Identity::system()).await?;
@@ -626,7 +853,7 @@ impl<RT: Runtime> ExportWorker<RT> {
 
         let tag = export.requestor().usage_tag().to_string();
         let call_type = match export.requestor() {
-            ExportRequestor::SnapshotExport => CallType::Export,
+            ExportRequestor::SnapshotExport | ExportRequestor::ScheduledBackup => CallType::Export,
             ExportRequestor::CloudBackup => CallType::CloudBackup,
         };
         // Charge file bandwidth for the upload of the snapshot to exports storage
@@ -657,12 +884,18 @@ pub struct FileStorageZipMetadata {
     pub size: Option<i64>,
     pub content_type: Option<String>,
     pub internal_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filename: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
 }
 
 // 'a is lifetime of entire zip file writer.
 // 'b is lifetime of entry writer for a single table.
 struct ZipSnapshotTableUpload<'a, 'b> {
     entry_writer: EntryStreamWriter<'b, &'a mut ChannelWriter>,
+    /// Added to each document as `WATERMARK_FIELD`.
+    watermark_token: Option<String>,
 }
 
 impl<'a, 'b> ZipSnapshotTableUpload<'a, 'b> {
@@ -675,11 +908,24 @@ impl<'a, 'b> ZipSnapshotTableUpload<'a, 'b> {
         let builder = ZipEntryBuilder::new(source_path.clone(), Compression::Deflate)
             .unix_permissions(ZIP_ENTRY_PERMISSIONS);
         let entry_writer = zip_writer.write_entry_stream(builder.build()).await?;
-        Ok(Self { entry_writer })
+        Ok(Self {
+            entry_writer,
+            watermark_token: None,
+        })
+    }
+
+    fn with_watermark(mut self, watermark_token: Option<String>) -> Self {
+        self.watermark_token = watermark_token;
+        self
     }
 
     async fn write(&mut self, doc: ResolvedDocument) -> anyhow::Result<()> {
-        let json = doc.export(ValueFormat::ConvexCleanJSON);
+        let mut json = doc.export(ValueFormat::ConvexCleanJSON);
+        if let Some(token) = &self.watermark_token
+            && let JsonValue::Object(fields) = &mut json
+        {
+            fields.insert(WATERMARK_FIELD.to_string(), token.clone().into());
+        }
         self.write_json_line(json).await
     }
 
@@ -699,16 +945,76 @@ impl<'a, 'b> ZipSnapshotTableUpload<'a, 'b> {
     }
 }
 
+struct ParquetSnapshotTableUpload<'a, 'b> {
+    entry_writer: EntryStreamWriter<'b, &'a mut ChannelWriter>,
+    table_writer: ParquetTableWriter,
+}
+
+impl<'a, 'b> ParquetSnapshotTableUpload<'a, 'b> {
+    async fn new(
+        zip_writer: &'b mut ZipFileWriter<&'a mut ChannelWriter>,
+        path_prefix: &str,
+        table_name: TableName,
+        schema: ParquetTableSchema,
+    ) -> anyhow::Result<Self> {
+        let source_path = format!("{path_prefix}{table_name}/documents.parquet");
+        let builder = ZipEntryBuilder::new(source_path.clone(), Compression::Deflate)
+            .unix_permissions(ZIP_ENTRY_PERMISSIONS);
+        let entry_writer = zip_writer.write_entry_stream(builder.build()).await?;
+        Ok(Self {
+            entry_writer,
+            table_writer: ParquetTableWriter::new(schema)?,
+        })
+    }
+
+    async fn write(&mut self, doc: ResolvedDocument) -> anyhow::Result<()> {
+        self.table_writer.write(doc)?;
+        let buf = self.table_writer.take_output();
+        if !buf.is_empty() {
+            self.entry_writer.compat_mut_write().write_all(&buf).await?;
+        }
+        Ok(())
+    }
+
+    async fn complete(mut self) -> anyhow::Result<()> {
+        let buf = self.table_writer.finish()?;
+        self.entry_writer.compat_mut_write().write_all(&buf).await?;
+        self.entry_writer.close().await?;
+        Ok(())
+    }
+}
+
+enum SnapshotTableUpload<'a, 'b> {
+    Jsonl(ZipSnapshotTableUpload<'a, 'b>),
+    Parquet(ParquetSnapshotTableUpload<'a, 'b>),
+}
+
+impl<'a, 'b> SnapshotTableUpload<'a, 'b> {
+    async fn write(&mut self, doc: ResolvedDocument) -> anyhow::Result<()> {
+        match self {
+            SnapshotTableUpload::Jsonl(upload) => upload.write(doc).await,
+            SnapshotTableUpload::Parquet(upload) => upload.write(doc).await,
+        }
+    }
+
+    async fn complete(self) -> anyhow::Result<()> {
+        match self {
+            SnapshotTableUpload::Jsonl(upload) => upload.complete().await,
+            SnapshotTableUpload::Parquet(upload) => upload.complete().await,
+        }
+    }
+}
+
 struct ZipSnapshotUpload<'a> {
     writer: ZipFileWriter<&'a mut ChannelWriter>,
 }
 
 impl<'a> ZipSnapshotUpload<'a> {
-    async fn new(out: &'a mut ChannelWriter) -> anyhow::Result<Self> {
+    async fn new(out: &'a mut ChannelWriter, readme: &str) -> anyhow::Result<Self> {
         let writer = ZipFileWriter::new(out);
         let mut zip_snapshot_upload = Self { writer };
         zip_snapshot_upload
-            .write_full_file(format!("README.md"), README_MD_CONTENTS)
+            .write_full_file(format!("README.md"), readme)
             .await?;
         Ok(zip_snapshot_upload)
     }
@@ -752,6 +1058,17 @@ impl<'a> ZipSnapshotUpload<'a> {
         ZipSnapshotTableUpload::new(&mut self.writer, path_prefix, table_name).await
     }
 
+    /// Parquet files carry their own column types, so there's no generated
+    /// schema to write alongside them.
+    async fn start_parquet_table(
+        &mut self,
+        path_prefix: &str,
+        table_name: TableName,
+        schema: ParquetTableSchema,
+    ) -> anyhow::Result<ParquetSnapshotTableUpload<'a, '_>> {
+        ParquetSnapshotTableUpload::new(&mut self.writer, path_prefix, table_name, schema).await
+    }
+
     /// System tables have known shape, so we don't need to serialize it.
     async fn start_system_table(
         &mut self,
@@ -806,6 +1123,10 @@ mod tests {
     };
 
     use anyhow::Context;
+    use arrow_array::{
+        Array,
+        StringArray,
+    };
     use bytes::Bytes;
     use common::{
         components::{
@@ -833,6 +1154,13 @@ mod tests {
     use headers::ContentType;
     use keybroker::Identity;
     use model::{
+        export_watermarks::{
+            types::{
+                ExportWatermark,
+                WatermarkMode,
+            },
+            ExportWatermarksModel,
+        },
         exports::types::{
             ExportFormat,
             ExportRequestor,
@@ -840,6 +1168,7 @@ mod tests {
         file_storage::types::FileStorageEntry,
         test_helpers::DbFixturesWithModel,
     };
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
     use runtime::testing::TestRuntime;
     use serde_json::json;
     use storage::{
@@ -858,7 +1187,10 @@ mod tests {
 
     use super::ExportWorker;
     use crate::{
-        export_worker::README_MD_CONTENTS,
+        export_worker::{
+            PARQUET_README_MD_CONTENTS,
+            README_MD_CONTENTS,
+        },
         test_helpers::ApplicationTestExt,
         Application,
     };
@@ -960,6 +1292,7 @@ mod tests {
                 },
                 ComponentId::Root,
                 ExportRequestor::SnapshotExport,
+                None,
             )
             .await?;
 
@@ -992,6 +1325,144 @@ mod tests {
         Ok(())
     }
 
+    #[convex_macro::test_runtime]
+    async fn test_export_watermark(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let storage: Arc<dyn Storage> = Arc::new(LocalDirStorage::new(rt.clone())?);
+        let file_storage: Arc<dyn Storage> = Arc::new(LocalDirStorage::new(rt.clone())?);
+        let mut export_worker =
+            ExportWorker::new_test(rt, db.clone(), storage.clone(), file_storage);
+
+        let mut tx = db.begin(Identity::system()).await?;
+        UserFacingModel::new_root_for_test(&mut tx)
+            .insert("table_0".parse()?, assert_obj!("price" => 19.99))
+            .await?;
+        let export_id = DeveloperDocumentId::MIN;
+        ExportWatermarksModel::new(&mut tx)
+            .insert_watermark(ExportWatermark {
+                export_id,
+                key_fingerprint: "fingerprint".to_string(),
+                label: "ci".to_string(),
+                mode: WatermarkMode::Field,
+                token: "token".to_string(),
+            })
+            .await?;
+        db.commit(tx).await?;
+
+        let (_, zip_object_key, _) = export_worker
+            .export_inner(
+                ExportFormat::Zip {
+                    include_storage: false,
+                },
+                ComponentId::Root,
+                ExportRequestor::SnapshotExport,
+                Some(export_id),
+            )
+            .await?;
+        let stored_bytes = storage
+            .get(&zip_object_key)
+            .await?
+            .context("object missing from storage")?
+            .collect_as_bytes()
+            .await?;
+        let mut zip_reader = async_zip::read::mem::ZipFileReader::new(&stored_bytes).await?;
+        let index = zip_reader
+            .entries()
+            .iter()
+            .position(|entry| entry.filename() == "table_0/documents.jsonl")
+            .context("table_0 missing from export")?;
+        let entry_reader = zip_reader.entry_reader(index).await?;
+        let contents = String::from_utf8(entry_reader.read_to_end_crc().await?)?;
+        let document: serde_json::Value = serde_json::from_str(contents.trim())?;
+        assert_eq!(document["_watermark"], json!("token"));
+        assert_eq!(document["price"], json!(19.99));
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_export_parquet(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let storage: Arc<dyn Storage> = Arc::new(LocalDirStorage::new(rt.clone())?);
+        let file_storage: Arc<dyn Storage> = Arc::new(LocalDirStorage::new(rt.clone())?);
+        let mut export_worker =
+            ExportWorker::new_test(rt, db.clone(), storage.clone(), file_storage);
+
+        let table: TableName = "table_0".parse()?;
+        let mut tx = db.begin(Identity::system()).await?;
+        let id = UserFacingModel::new_root_for_test(&mut tx)
+            .insert(table, assert_obj!("foo" => 1))
+            .await?;
+        let doc = UserFacingModel::new_root_for_test(&mut tx)
+            .get(id, None)
+            .await?
+            .unwrap();
+        db.commit(tx).await?;
+
+        let (_, zip_object_key, _) = export_worker
+            .export_inner(
+                ExportFormat::Parquet {
+                    include_storage: false,
+                },
+                ComponentId::Root,
+                ExportRequestor::SnapshotExport,
+                None,
+            )
+            .await?;
+
+        let storage_stream = storage
+            .get(&zip_object_key)
+            .await?
+            .context("object missing from storage")?;
+        let stored_bytes = storage_stream.collect_as_bytes().await?;
+        let mut zip_reader = async_zip::read::mem::ZipFileReader::new(&stored_bytes).await?;
+        let mut zip_entries = BTreeMap::new();
+        let filenames: Vec<_> = zip_reader
+            .entries()
+            .into_iter()
+            .map(|entry| entry.filename().to_string())
+            .collect();
+        for (i, filename) in filenames.into_iter().enumerate() {
+            let entry_reader = zip_reader.entry_reader(i).await?;
+            zip_entries.insert(filename, entry_reader.read_to_end_crc().await?);
+        }
+        assert_eq!(
+            zip_entries.keys().collect::<Vec<_>>(),
+            vec![
+                "README.md",
+                "_tables/documents.jsonl",
+                "table_0/documents.parquet"
+            ]
+        );
+        assert_eq!(
+            str::from_utf8(&zip_entries["README.md"])?,
+            PARQUET_README_MD_CONTENTS
+        );
+
+        // Without a schema, each document is stored as JSON.
+        let parquet_bytes = Bytes::from(zip_entries.remove("table_0/documents.parquet").unwrap());
+        let reader = ParquetRecordBatchReaderBuilder::try_new(parquet_bytes)?.build()?;
+        let batches = reader.collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(batches.len(), 1);
+        let batch = &batches[0];
+        assert_eq!(batch.num_rows(), 1);
+        let ids = batch
+            .column_by_name("_id")
+            .context("missing _id column")?
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .context("_id isn't a string column")?;
+        assert_eq!(ids.value(0), doc.id().encode());
+        let documents = batch
+            .column_by_name("document")
+            .context("missing document column")?
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .context("document isn't a string column")?;
+        let document: serde_json::Value = serde_json::from_str(documents.value(0))?;
+        assert_eq!(document["foo"], json!(1));
+        Ok(())
+    }
+
     async fn write_test_data_in_component(
         db: &Database<TestRuntime>,
         component: ComponentId,
@@ -1075,6 +1546,7 @@ mod tests {
                 },
                 ComponentId::Root,
                 ExportRequestor::SnapshotExport,
+                None,
             )
             .await?;
 
@@ -1136,6 +1608,7 @@ mod tests {
                 },
                 child_component,
                 ExportRequestor::SnapshotExport,
+                None,
             )
             .await?;
 
@@ -1230,6 +1703,7 @@ mod tests {
                 },
                 ComponentId::Root,
                 ExportRequestor::SnapshotExport,
+                None,
             )
             .await?;
 
@@ -1293,6 +1767,7 @@ mod tests {
                 },
                 ComponentId::test_user(),
                 ExportRequestor::SnapshotExport,
+                None,
             )
             .await?;
         Ok(())
@@ -0,0 +1,506 @@
+//! Parquet encoding for user tables in snapshot exports.
+//!
+//! Columns are derived from the table's document validator in the active
+//! schema, so typed fields load directly into analytics engines. Values that
+//! don't have a natural columnar representation (objects, arrays, unions of
+//! different types, etc.) are written as clean JSON strings.
+use std::{
+    collections::BTreeMap,
+    mem,
+    sync::Arc,
+};
+
+use arrow_array::{
+    ArrayRef,
+    BinaryArray,
+    BooleanArray,
+    Float64Array,
+    Int64Array,
+    RecordBatch,
+    StringArray,
+};
+use arrow_schema::{
+    DataType,
+    Field,
+    Schema,
+    SchemaRef,
+};
+use common::{
+    document::{
+        ResolvedDocument,
+        CREATION_TIME_FIELD,
+        ID_FIELD,
+    },
+    schemas::{
+        validator::{
+            LiteralValidator,
+            Validator,
+        },
+        DocumentSchema,
+    },
+};
+use model::export_watermarks::types::WATERMARK_FIELD;
+use parquet::{
+    arrow::ArrowWriter,
+    basic::{
+        Compression,
+        ZstdLevel,
+    },
+    file::properties::WriterProperties,
+};
+use value::{
+    export::ValueFormat,
+    ConvexValue,
+    Namespace,
+};
+
+/// Number of rows buffered before they're encoded as a record batch.
+const RECORD_BATCH_ROWS: usize = 1024;
+/// Flush buffered rows early once they're this large, so a batch of large
+/// documents doesn't sit in memory.
+const RECORD_BATCH_BYTES: usize = 8 << 20;
+/// Flush the current row group once the encoder has buffered this much data.
+const ROW_GROUP_BYTES: usize = 64 << 20;
+const MAX_ROW_GROUP_ROWS: usize = 64 * 1024;
+
+/// Column holding the whole document for tables without an enforced schema.
+const DOCUMENT_COLUMN: &str = "document";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ColumnKind {
+    Float64,
+    Int64,
+    Boolean,
+    String,
+    Bytes,
+    /// Clean JSON encoding of an arbitrary Convex value.
+    Json,
+}
+
+impl ColumnKind {
+    fn data_type(&self) -> DataType {
+        match self {
+            ColumnKind::Float64 => DataType::Float64,
+            ColumnKind::Int64 => DataType::Int64,
+            ColumnKind::Boolean => DataType::Boolean,
+            ColumnKind::String | ColumnKind::Json => DataType::Utf8,
+            ColumnKind::Bytes => DataType::Binary,
+        }
+    }
+
+    /// The column type for values matching `validator`, along with whether
+    /// the validator admits `null`. Returns `None` for the kind if the
+    /// validator only admits `null`.
+    fn from_validator(validator: &Validator) -> (Option<Self>, bool) {
+        let kind = match validator {
+            Validator::Null => return (None, true),
+            Validator::Float64 | Validator::Literal(LiteralValidator::Float64(_)) => {
+                ColumnKind::Float64
+            },
+            Validator::Int64 | Validator::Literal(LiteralValidator::Int64(_)) => ColumnKind::Int64,
+            Validator::Boolean | Validator::Literal(LiteralValidator::Boolean(_)) => {
+                ColumnKind::Boolean
+            },
+            Validator::String
+            | Validator::Literal(LiteralValidator::String(_))
+            | Validator::Id(_) => ColumnKind::String,
+            Validator::Bytes => ColumnKind::Bytes,
+            Validator::Union(validators) => {
+                let mut kind = None;
+                let mut nullable = false;
+                for validator in validators {
+                    let (member_kind, member_nullable) = Self::from_validator(validator);
+                    kind = Self::merge(kind, member_kind);
+                    nullable |= member_nullable;
+                }
+                return (kind, nullable);
+            },
+            Validator::Array(_)
+            | Validator::Set(_)
+            | Validator::Record(..)
+            | Validator::Map(..)
+            | Validator::Object(_)
+            | Validator::Any => ColumnKind::Json,
+        };
+        (Some(kind), false)
+    }
+
+    fn merge(left: Option<Self>, right: Option<Self>) -> Option<Self> {
+        match (left, right) {
+            (None, kind) | (kind, None) => kind,
+            (Some(left), Some(right)) if left == right => Some(left),
+            (Some(_), Some(_)) => Some(ColumnKind::Json),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum ColumnSource {
+    Id,
+    CreationTime,
+    Field(String),
+    Document,
+    /// The export's watermark token, which is the same for every row.
+    Watermark(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct ParquetColumn {
+    source: ColumnSource,
+    kind: ColumnKind,
+    nullable: bool,
+}
+
+impl ParquetColumn {
+    fn name(&self) -> &str {
+        match &self.source {
+            ColumnSource::Id => &ID_FIELD,
+            ColumnSource::CreationTime => &CREATION_TIME_FIELD,
+            ColumnSource::Field(name) => name,
+            ColumnSource::Document => DOCUMENT_COLUMN,
+            ColumnSource::Watermark(_) => WATERMARK_FIELD,
+        }
+    }
+
+    fn value<'a>(&self, doc: &'a ResolvedDocument) -> anyhow::Result<Option<ColumnValue<'a>>> {
+        let value = match &self.source {
+            ColumnSource::Id => return Ok(Some(ColumnValue::Id(doc.developer_id().encode()))),
+            ColumnSource::CreationTime => {
+                let creation_time = doc.creation_time().map(f64::from);
+                return Ok(creation_time.map(ColumnValue::CreationTime));
+            },
+            ColumnSource::Field(name) => match doc.value().get(name.as_str()) {
+                None | Some(ConvexValue::Null) => return Ok(None),
+                Some(value) => value,
+            },
+            ColumnSource::Document => {
+                let json = doc.value().0.clone().export(ValueFormat::ConvexCleanJSON);
+                return Ok(Some(ColumnValue::Json(serde_json::to_string(&json)?)));
+            },
+            ColumnSource::Watermark(token) => return Ok(Some(ColumnValue::Json(token.clone()))),
+        };
+        let column_value = match (self.kind, value) {
+            (ColumnKind::Float64, ConvexValue::Float64(f)) => ColumnValue::Float64(*f),
+            (ColumnKind::Int64, ConvexValue::Int64(i)) => ColumnValue::Int64(*i),
+            (ColumnKind::Boolean, ConvexValue::Boolean(b)) => ColumnValue::Boolean(*b),
+            (ColumnKind::String, ConvexValue::String(s)) => ColumnValue::String(s),
+            (ColumnKind::Bytes, ConvexValue::Bytes(b)) => ColumnValue::Bytes(b),
+            (ColumnKind::Json, value) => {
+                let json = value.clone().export(ValueFormat::ConvexCleanJSON);
+                ColumnValue::Json(serde_json::to_string(&json)?)
+            },
+            (kind, value) => anyhow::bail!(
+                "Field {} of {} doesn't match its {kind:?} column: {}",
+                self.name(),
+                doc.developer_id().encode(),
+                value.type_name(),
+            ),
+        };
+        Ok(Some(column_value))
+    }
+}
+
+enum ColumnValue<'a> {
+    Id(String),
+    CreationTime(f64),
+    Float64(f64),
+    Int64(i64),
+    Boolean(bool),
+    String(&'a str),
+    Bytes(&'a [u8]),
+    Json(String),
+}
+
+/// Parquet columns for a single table.
+#[derive(Clone, Debug)]
+pub struct ParquetTableSchema {
+    columns: Vec<ParquetColumn>,
+    arrow_schema: SchemaRef,
+}
+
+impl ParquetTableSchema {
+    /// Derive columns from the table's document validator. Tables without a
+    /// validator get a single JSON column holding the entire document,
+    /// alongside `_id` and `_creationTime`.
+    pub fn new(document_schema: Option<&DocumentSchema>) -> Self {
+        let mut columns = vec![
+            ParquetColumn {
+                source: ColumnSource::Id,
+                kind: ColumnKind::String,
+                nullable: false,
+            },
+            ParquetColumn {
+                source: ColumnSource::CreationTime,
+                kind: ColumnKind::Float64,
+                nullable: false,
+            },
+        ];
+        match document_schema {
+            None | Some(DocumentSchema::Any) => {
+                columns.push(ParquetColumn {
+                    source: ColumnSource::Document,
+                    kind: ColumnKind::Json,
+                    nullable: false,
+                });
+            },
+            Some(DocumentSchema::Union(object_validators)) => {
+                // (kind, nullable, number of variants containing the field)
+                let mut fields: BTreeMap<String, (Option<ColumnKind>, bool, usize)> =
+                    BTreeMap::new();
+                for object_validator in object_validators {
+                    for (field_name, field_validator) in &object_validator.0 {
+                        if field_name.is_system() {
+                            continue;
+                        }
+                        let (kind, nullable) =
+                            ColumnKind::from_validator(field_validator.validator());
+                        let entry = fields
+                            .entry(field_name.to_string())
+                            .or_insert((None, false, 0));
+                        entry.0 = ColumnKind::merge(entry.0, kind);
+                        entry.1 |= nullable || field_validator.is_optional();
+                        entry.2 += 1;
+                    }
+                }
+                for (name, (kind, nullable, variants)) in fields {
+                    columns.push(ParquetColumn {
+                        source: ColumnSource::Field(name),
+                        kind: kind.unwrap_or(ColumnKind::Json),
+                        // Fields missing from some variants of the union are
+                        // absent on documents matching those variants.
+                        nullable: nullable || variants < object_validators.len(),
+                    });
+                }
+            },
+        }
+        Self {
+            arrow_schema: Self::arrow_schema(&columns),
+            columns,
+        }
+    }
+
+    /// Adds a column holding the export's watermark token, if it has one.
+    pub fn with_watermark(mut self, watermark_token: Option<String>) -> Self {
+        if let Some(token) = watermark_token {
+            self.columns.push(ParquetColumn {
+                source: ColumnSource::Watermark(token),
+                kind: ColumnKind::String,
+                nullable: false,
+            });
+            self.arrow_schema = Self::arrow_schema(&self.columns);
+        }
+        self
+    }
+
+    fn arrow_schema(columns: &[ParquetColumn]) -> SchemaRef {
+        Arc::new(Schema::new(
+            columns
+                .iter()
+                .map(|column| Field::new(column.name(), column.kind.data_type(), column.nullable))
+                .collect::<Vec<_>>(),
+        ))
+    }
+
+    fn record_batch(&self, docs: &[ResolvedDocument]) -> anyhow::Result<RecordBatch> {
+        let mut arrays: Vec<ArrayRef> = Vec::with_capacity(self.columns.len());
+        for column in &self.columns {
+            let values = docs
+                .iter()
+                .map(|doc| column.value(doc))
+                .collect::<anyhow::Result<Vec<_>>>()?;
+            let array: ArrayRef = match column.kind {
+                ColumnKind::Float64 => Arc::new(Float64Array::from(
+                    values
+                        .into_iter()
+                        .map(|v| match v {
+                            Some(ColumnValue::Float64(f) | ColumnValue::CreationTime(f)) => Some(f),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>(),
+                )),
+                ColumnKind::Int64 => Arc::new(Int64Array::from(
+                    values
+                        .into_iter()
+                        .map(|v| match v {
+                            Some(ColumnValue::Int64(i)) => Some(i),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>(),
+                )),
+                ColumnKind::Boolean => Arc::new(BooleanArray::from(
+                    values
+                        .into_iter()
+                        .map(|v| match v {
+                            Some(ColumnValue::Boolean(b)) => Some(b),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>(),
+                )),
+                ColumnKind::String | ColumnKind::Json => Arc::new(StringArray::from(
+                    values
+                        .into_iter()
+                        .map(|v| match v {
+                            Some(ColumnValue::String(s)) => Some(s.to_string()),
+                            Some(ColumnValue::Id(s) | ColumnValue::Json(s)) => Some(s),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>(),
+                )),
+                ColumnKind::Bytes => Arc::new(BinaryArray::from(
+                    values
+                        .into_iter()
+                        .map(|v| match v {
+                            Some(ColumnValue::Bytes(b)) => Some(b),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>(),
+                )),
+            };
+            arrays.push(array);
+        }
+        Ok(RecordBatch::try_new(self.arrow_schema.clone(), arrays)?)
+    }
+}
+
+/// Incrementally encodes a table's documents as a Parquet file. Encoded bytes
+/// are buffered in memory until they're taken with `take_output`, so callers
+/// can stream the file out as it's written.
+pub struct ParquetTableWriter {
+    schema: ParquetTableSchema,
+    writer: ArrowWriter<Vec<u8>>,
+    pending: Vec<ResolvedDocument>,
+    pending_bytes: usize,
+}
+
+impl ParquetTableWriter {
+    pub fn new(schema: ParquetTableSchema) -> anyhow::Result<Self> {
+        let properties = WriterProperties::builder()
+            .set_compression(Compression::ZSTD(ZstdLevel::default()))
+            .set_max_row_group_size(MAX_ROW_GROUP_ROWS)
+            .build();
+        let writer =
+            ArrowWriter::try_new(Vec::new(), schema.arrow_schema.clone(), Some(properties))?;
+        Ok(Self {
+            schema,
+            writer,
+            pending: Vec::new(),
+            pending_bytes: 0,
+        })
+    }
+
+    pub fn write(&mut self, doc: ResolvedDocument) -> anyhow::Result<()> {
+        self.pending_bytes += doc.size();
+        self.pending.push(doc);
+        if self.pending.len() >= RECORD_BATCH_ROWS || self.pending_bytes >= RECORD_BATCH_BYTES {
+            self.write_pending()?;
+        }
+        Ok(())
+    }
+
+    /// Take the bytes encoded so far.
+    pub fn take_output(&mut self) -> Vec<u8> {
+        mem::take(self.writer.inner_mut())
+    }
+
+    /// Encode any remaining documents and the file footer, returning the
+    /// bytes that haven't been taken yet.
+    pub fn finish(mut self) -> anyhow::Result<Vec<u8>> {
+        self.write_pending()?;
+        Ok(self.writer.into_inner()?)
+    }
+
+    fn write_pending(&mut self) -> anyhow::Result<()> {
+        if self.pending.is_empty() {
+            return Ok(());
+        }
+        let batch = self.schema.record_batch(&self.pending)?;
+        self.writer.write(&batch)?;
+        self.pending.clear();
+        self.pending_bytes = 0;
+        if self.writer.in_progress_size() >= ROW_GROUP_BYTES {
+            self.writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrow_schema::DataType;
+    use common::{
+        object_validator,
+        schemas::{
+            validator::{
+                FieldValidator,
+                Validator,
+            },
+            DocumentSchema,
+        },
+    };
+
+    use super::ParquetTableSchema;
+
+    #[test]
+    fn test_parquet_schema_from_validator() -> anyhow::Result<()> {
+        let document_schema = DocumentSchema::Union(vec![
+            object_validator!(
+                "count" => FieldValidator::required_field_type(Validator::Int64),
+                "name" => FieldValidator::required_field_type(Validator::String),
+                "score" => FieldValidator::optional_field_type(Validator::Float64),
+                "tags" => FieldValidator::required_field_type(Validator::Array(Box::new(
+                    Validator::String,
+                ))),
+                "maybe" => FieldValidator::required_field_type(Validator::Union(vec![
+                    Validator::Boolean,
+                    Validator::Null,
+                ])),
+            ),
+            object_validator!(
+                "count" => FieldValidator::required_field_type(Validator::String),
+                "name" => FieldValidator::required_field_type(Validator::String),
+            ),
+        ]);
+        let schema = ParquetTableSchema::new(Some(&document_schema));
+        let fields: Vec<_> = schema
+            .arrow_schema
+            .fields()
+            .iter()
+            .map(|f| (f.name().clone(), f.data_type().clone(), f.is_nullable()))
+            .collect();
+        assert_eq!(
+            fields,
+            vec![
+                ("_id".to_string(), DataType::Utf8, false),
+                ("_creationTime".to_string(), DataType::Float64, false),
+                // Int64 in one variant and string in the other.
+                ("count".to_string(), DataType::Utf8, false),
+                ("maybe".to_string(), DataType::Boolean, true),
+                ("name".to_string(), DataType::Utf8, false),
+                ("score".to_string(), DataType::Float64, true),
+                ("tags".to_string(), DataType::Utf8, true),
+            ]
+        );
+
+        let schema = ParquetTableSchema::new(None);
+        let names: Vec<_> = schema
+            .arrow_schema
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect();
+        assert_eq!(names, vec!["_id", "_creationTime", "document"]);
+
+        let schema = ParquetTableSchema::new(None).with_watermark(Some("token".to_string()));
+        let names: Vec<_> = schema
+            .arrow_schema
+            .fields()
+            .iter()
+            .map(|f| f.name().clone())
+            .collect();
+        assert_eq!(
+            names,
+            vec!["_id", "_creationTime", "document", "_watermark"]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+//! Serving images from `_storage` resized or re-encoded, so apps can request
+//! thumbnails from the file-serving route without a separate image CDN. The
+//! first request for a transform of a file does the work and stores the
+//! result, and later requests serve the stored image.
+use std::collections::BTreeMap;
+
+use anyhow::Context;
+use bytes::{
+    Bytes,
+    BytesMut,
+};
+use common::{
+    components::{
+        ComponentId,
+        ComponentPath,
+    },
+    errors::report_error,
+    runtime::Runtime,
+};
+use errors::ErrorMetadata;
+use file_storage::{
+    image_transform::ImageTransform,
+    FileStream,
+};
+use futures::{
+    stream,
+    TryStreamExt,
+};
+use headers::ContentLength;
+use keybroker::Identity;
+use model::file_storage::{
+    transforms::FileStorageTransformModel,
+    types::{
+        FileStorageEntry,
+        FileStorageTransform,
+    },
+    FileStorageId,
+};
+use storage::Storage;
+
+use crate::Application;
+
+impl<RT: Runtime> Application<RT> {
+    /// Stream the file `storage_id` with `transform` applied to it.
+    pub async fn get_transformed_file(
+        &self,
+        component: ComponentId,
+        storage_id: FileStorageId,
+        transform: ImageTransform,
+    ) -> anyhow::Result<FileStream> {
+        self.bail_if_not_running().await?;
+        let file_entry = self.get_file_entry(component, storage_id).await?;
+        let mut tx = self.begin(Identity::system()).await?;
+        let Some(component_path) = tx.get_component_path(component) else {
+            return Err(ErrorMetadata::not_found(
+                "FileNotFound",
+                format!("Component {component:?} not found"),
+            )
+            .into());
+        };
+        let cache_key = transform.cache_key();
+        let cached = FileStorageTransformModel::new(&mut tx)
+            .get(&file_entry.storage_key, &cache_key)
+            .await?;
+        let transformed = match cached {
+            Some(transformed) => transformed.into_value(),
+            None => {
+                self.transform_file(component_path.clone(), &file_entry, &transform, cache_key)
+                    .await?
+            },
+        };
+        let transformed_entry = FileStorageEntry {
+            storage_id: file_entry.storage_id,
+            storage_key: transformed.storage_key,
+            sha256: transformed.sha256,
+            size: transformed.size.try_into()?,
+            content_type: Some(transformed.content_type),
+            // The original's filename would have the wrong extension.
+            filename: None,
+            tags: BTreeMap::new(),
+            // Transforms of a file are only served once the file itself can be.
+            scan_status: file_entry.scan_status,
+        };
+        self.file_storage
+            .transactional_file_storage
+            .get_file_stream(
+                component_path,
+                transformed_entry,
+                self.usage_tracking.clone(),
+            )
+            .await
+    }
+
+    async fn transform_file(
+        &self,
+        component_path: ComponentPath,
+        file_entry: &FileStorageEntry,
+        transform: &ImageTransform,
+        cache_key: String,
+    ) -> anyhow::Result<FileStorageTransform> {
+        ImageTransform::check_source_size(file_entry.size.try_into()?)?;
+        let mut source_stream = self
+            .file_storage
+            .transactional_file_storage
+            .get_file_stream(
+                component_path,
+                file_entry.clone(),
+                self.usage_tracking.clone(),
+            )
+            .await?
+            .stream;
+        let mut source = BytesMut::new();
+        while let Some(chunk) = source_stream
+            .try_next()
+            .await
+            .context("Failed to read file")?
+        {
+            source.extend_from_slice(&chunk);
+        }
+        let (output, format) = transform.apply(source.freeze()).await?;
+
+        let output_entry = self
+            .file_storage
+            .transactional_file_storage
+            .upload_file(
+                Some(ContentLength(output.len() as u64)),
+                Some(format.content_type().parse()?),
+                stream::iter([anyhow::Ok(Bytes::from(output))]),
+                None,
+            )
+            .await?;
+        let transformed = FileStorageTransform {
+            source_key: file_entry.storage_key.clone(),
+            transform: cache_key,
+            storage_key: output_entry.storage_key,
+            sha256: output_entry.sha256,
+            size: output_entry.size.try_into()?,
+            content_type: format.content_type().to_string(),
+        };
+
+        let mut tx = self.begin(Identity::system()).await?;
+        let mut model = FileStorageTransformModel::new(&mut tx);
+        // A concurrent request may have stored the same transform, in which
+        // case serve theirs and throw ours away.
+        if let Some(existing) = model
+            .get(&transformed.source_key, &transformed.transform)
+            .await?
+        {
+            if let Err(mut e) = self
+                .files_storage
+                .delete_object(&transformed.storage_key)
+                .await
+            {
+                report_error(&mut e);
+            }
+            return Ok(existing.into_value());
+        }
+        model.insert(transformed.clone()).await?;
+        self.commit(tx, "file_storage_transform").await?;
+        Ok(transformed)
+    }
+}
@@ -0,0 +1,265 @@
+//! Resumable uploads to `_storage`. A client starts an upload, appends the
+//! file to it in chunks over as many requests as it needs, and finishes it
+//! with the file's checksum. If a request fails partway through, the client
+//! asks for the upload's offset and resumes from there, so a multi-gigabyte
+//! file survives an unreliable network.
+use std::sync::Arc;
+
+use anyhow::Context;
+use bytes::Bytes;
+use common::{
+    components::ComponentId,
+    errors::report_error,
+    runtime::Runtime,
+};
+use database::Transaction;
+use errors::ErrorMetadata;
+use futures::{
+    pin_mut,
+    stream::BoxStream,
+    TryStreamExt,
+};
+use futures_async_stream::try_stream;
+use headers::{
+    ContentLength,
+    ContentType,
+};
+use keybroker::Identity;
+use model::file_storage::{
+    types::FileStorageUploadChunk,
+    uploads::{
+        FileStorageUploadModel,
+        FILE_STORAGE_UPLOADS_TABLE,
+    },
+};
+use storage::{
+    Storage,
+    StorageExt,
+    UploadExt,
+};
+use value::{
+    sha256::Sha256Digest,
+    DeveloperDocumentId,
+    ResolvedDocumentId,
+    TableNamespace,
+};
+
+use crate::Application;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileUploadStatus {
+    /// The number of bytes received so far, which is where the next chunk
+    /// starts.
+    pub offset: u64,
+    /// The file's total size, if the client declared it when starting the
+    /// upload.
+    pub upload_length: Option<u64>,
+}
+
+fn resolve_file_upload_id<RT: Runtime>(
+    tx: &mut Transaction<RT>,
+    upload_id: DeveloperDocumentId,
+) -> anyhow::Result<ResolvedDocumentId> {
+    let table_mapping = tx.table_mapping().namespace(TableNamespace::Global);
+    if table_mapping.name_by_number_if_exists(upload_id.table())
+        != Some(&*FILE_STORAGE_UPLOADS_TABLE)
+    {
+        anyhow::bail!(ErrorMetadata::not_found(
+            "FileUploadNotFound",
+            format!("File upload {upload_id} not found"),
+        ));
+    }
+    upload_id.to_resolved(table_mapping.number_to_tablet())
+}
+
+/// Streams the chunks of an upload in order, checking that each one is still
+/// intact in storage.
+#[try_stream(ok = Bytes, error = anyhow::Error)]
+async fn read_file_upload_chunks(storage: Arc<dyn Storage>, chunks: Vec<FileStorageUploadChunk>) {
+    for chunk in chunks {
+        let stream = storage
+            .get(&chunk.object_key)
+            .await?
+            .with_context(|| format!("Upload chunk {:?} is missing", chunk.object_key))?
+            .stream;
+        let mut hasher = value::sha256::Sha256::new();
+        let mut size = 0;
+        pin_mut!(stream);
+        while let Some(bytes) = stream.try_next().await? {
+            hasher.update(&bytes);
+            size += bytes.len() as u64;
+            yield bytes;
+        }
+        anyhow::ensure!(
+            size == chunk.size && hasher.finalize() == chunk.sha256,
+            "Upload chunk {:?} was corrupted in storage",
+            chunk.object_key
+        );
+    }
+}
+
+impl<RT: Runtime> Application<RT> {
+    /// Start a resumable upload of a file into `component`'s `_storage`.
+    /// `upload_length` is the file's size if the client knows it, in which
+    /// case the upload can't grow past it.
+    pub async fn start_file_upload(
+        &self,
+        component: ComponentId,
+        content_type: Option<ContentType>,
+        upload_length: Option<u64>,
+    ) -> anyhow::Result<DeveloperDocumentId> {
+        self.bail_if_not_running().await?;
+        let mut tx = self.begin(Identity::system()).await?;
+        let upload_id = FileStorageUploadModel::new(&mut tx)
+            .start_upload(
+                component,
+                content_type.map(|ct| ct.to_string()),
+                upload_length,
+            )
+            .await?;
+        self.commit(tx, "file_storage_start_upload").await?;
+        Ok(upload_id.into())
+    }
+
+    pub async fn file_upload_status(
+        &self,
+        component: ComponentId,
+        upload_id: DeveloperDocumentId,
+    ) -> anyhow::Result<FileUploadStatus> {
+        let mut tx = self.begin(Identity::system()).await?;
+        let upload_id = resolve_file_upload_id(&mut tx, upload_id)?;
+        let upload = FileStorageUploadModel::new(&mut tx)
+            .must_get(upload_id, component)
+            .await?;
+        Ok(FileUploadStatus {
+            offset: upload.offset(),
+            upload_length: upload.upload_length,
+        })
+    }
+
+    /// Append a chunk to an upload. `offset` is where the client thinks the
+    /// upload ends, and the chunk is rejected if it doesn't, e.g. because an
+    /// earlier attempt to send it succeeded after all. Returns the new offset.
+    pub async fn append_file_upload_chunk(
+        &self,
+        component: ComponentId,
+        upload_id: DeveloperDocumentId,
+        offset: u64,
+        body: BoxStream<'_, anyhow::Result<Bytes>>,
+    ) -> anyhow::Result<u64> {
+        self.bail_if_not_running().await?;
+        // Check the offset before writing anything so a stale retry fails
+        // fast. It's checked again when the chunk is recorded.
+        let status = self.file_upload_status(component, upload_id).await?;
+        anyhow::ensure!(
+            offset == status.offset,
+            ErrorMetadata::bad_request(
+                "FileUploadOffsetMismatch",
+                format!(
+                    "Chunk starts at offset {offset} but the upload has received {} bytes",
+                    status.offset
+                ),
+            )
+        );
+
+        let storage = &self.files_storage;
+        let mut upload = storage.start_upload().await?;
+        let (size, sha256) = upload.try_write_parallel_and_hash(body).await?;
+        let object_key = upload.complete().await?;
+        let chunk = FileStorageUploadChunk {
+            object_key: object_key.clone(),
+            sha256,
+            size: size as u64,
+        };
+        let result: anyhow::Result<u64> = try {
+            let mut tx = self.begin(Identity::system()).await?;
+            let upload_id = resolve_file_upload_id(&mut tx, upload_id)?;
+            let new_offset = FileStorageUploadModel::new(&mut tx)
+                .record_chunk(upload_id, component, offset, chunk)
+                .await?;
+            self.commit(tx, "file_storage_upload_chunk").await?;
+            new_offset
+        };
+        if result.is_err()
+            && let Err(mut delete_err) = storage.delete_object(&object_key).await
+        {
+            report_error(&mut delete_err);
+        }
+        result
+    }
+
+    /// Assemble an upload's chunks into a file in `_storage`, after checking
+    /// it against the checksum the client computed, and return its storage
+    /// ID.
+    pub async fn finish_file_upload(
+        &self,
+        component: ComponentId,
+        upload_id: DeveloperDocumentId,
+        expected_sha256: Sha256Digest,
+    ) -> anyhow::Result<DeveloperDocumentId> {
+        self.bail_if_not_running().await?;
+        let mut tx = self.begin(Identity::system()).await?;
+        let resolved_id = resolve_file_upload_id(&mut tx, upload_id)?;
+        let upload = FileStorageUploadModel::new(&mut tx)
+            .must_get(resolved_id, component)
+            .await?
+            .into_value();
+        let offset = upload.offset();
+        if let Some(upload_length) = upload.upload_length {
+            anyhow::ensure!(
+                offset == upload_length,
+                ErrorMetadata::bad_request(
+                    "FileUploadIncomplete",
+                    format!("Only {offset} of {upload_length} bytes have been uploaded"),
+                )
+            );
+        }
+        let content_type = upload
+            .content_type
+            .as_ref()
+            .map(|ct| ct.parse())
+            .transpose()?;
+        let chunks = read_file_upload_chunks(self.files_storage.clone(), upload.chunks.clone());
+        let entry = self
+            .file_storage
+            .transactional_file_storage
+            .upload_file(
+                Some(ContentLength(offset)),
+                content_type,
+                chunks,
+                Some(expected_sha256),
+            )
+            .await?;
+
+        let storage_id = self.store_file_entry(component, entry).await?;
+        let mut tx = self.begin(Identity::system()).await?;
+        let mut model = FileStorageUploadModel::new(&mut tx);
+        // A concurrent request may have finished the same upload.
+        if model.get(resolved_id).await?.is_some() {
+            model.delete(resolved_id).await?;
+            self.commit(tx, "file_storage_finish_upload").await?;
+        }
+        for chunk in upload.chunks {
+            self.files_storage.delete_object(&chunk.object_key).await?;
+        }
+        Ok(storage_id)
+    }
+
+    /// Abandon an upload, deleting the chunks received so far.
+    pub async fn cancel_file_upload(
+        &self,
+        component: ComponentId,
+        upload_id: DeveloperDocumentId,
+    ) -> anyhow::Result<()> {
+        let mut tx = self.begin(Identity::system()).await?;
+        let upload_id = resolve_file_upload_id(&mut tx, upload_id)?;
+        let mut model = FileStorageUploadModel::new(&mut tx);
+        let upload = model.must_get(upload_id, component).await?.into_value();
+        model.delete(upload_id).await?;
+        self.commit(tx, "file_storage_cancel_upload").await?;
+        for chunk in upload.chunks {
+            self.files_storage.delete_object(&chunk.object_key).await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,357 @@
+//! Built-in email/password and magic link auth, for apps that don't want to
+//! configure a third-party identity provider.
+//!
+//! Logging in creates an `_auth_sessions` document and returns a session token
+//! that names it. Clients send the token in place of an ID token, and
+//! [`Application::authenticate`] resolves it to the account's identity until
+//! the session expires or is logged out.
+use std::time::SystemTime;
+
+use authentication::passwords::{
+    check_password_length,
+    hash_password,
+    verify_password_if_set,
+};
+use common::{
+    components::{
+        CanonicalizedComponentFunctionPath,
+        ComponentPath,
+    },
+    execution_context::{
+        ExecutionContext,
+        ExecutionId,
+    },
+    knobs::{
+        FIRST_PARTY_AUTH_ENABLED,
+        FIRST_PARTY_AUTH_MAGIC_LINK_FUNCTION,
+        FIRST_PARTY_AUTH_MAGIC_LINK_TTL,
+        FIRST_PARTY_AUTH_SESSION_TTL,
+        FIRST_PARTY_AUTH_SIGNUP_ENABLED,
+    },
+    runtime::Runtime,
+    RequestId,
+};
+use database::Transaction;
+use errors::ErrorMetadata;
+use keybroker::{
+    Identity,
+    UserIdentity,
+};
+use model::{
+    first_party_auth::{
+        types::AuthAccount,
+        FirstPartyAuthModel,
+    },
+    scheduled_jobs::SchedulerModel,
+};
+use rand::{
+    distributions::Alphanumeric,
+    Rng,
+};
+use sha2::{
+    Digest,
+    Sha256,
+};
+use sync_types::CanonicalizedUdfPath;
+use value::{
+    obj,
+    ConvexArray,
+    ConvexValue,
+    DeveloperDocumentId,
+    ResolvedDocumentId,
+    TableNamespace,
+};
+
+use crate::Application;
+
+const MAX_EMAIL_LENGTH: usize = 254;
+const MAGIC_LINK_TOKEN_LENGTH: usize = 43;
+
+/// A session token returned by signing up or logging in.
+#[derive(Clone, Debug)]
+pub struct FirstPartyAuthSession {
+    pub token: String,
+    pub expires_at: SystemTime,
+}
+
+fn check_enabled() -> anyhow::Result<()> {
+    anyhow::ensure!(
+        *FIRST_PARTY_AUTH_ENABLED,
+        ErrorMetadata::bad_request(
+            "FirstPartyAuthDisabled",
+            "First-party auth isn't enabled for this deployment. Set FIRST_PARTY_AUTH_ENABLED to \
+             enable it.",
+        )
+    );
+    Ok(())
+}
+
+/// Lowercases and trims `email`, failing if it isn't an email.
+pub fn normalize_email(email: &str) -> anyhow::Result<String> {
+    let email = email.trim().to_lowercase();
+    let valid = email.len() <= MAX_EMAIL_LENGTH
+        && email
+            .split_once('@')
+            .is_some_and(|(local, domain)| !local.is_empty() && domain.contains('.'));
+    anyhow::ensure!(
+        valid,
+        ErrorMetadata::bad_request("InvalidEmail", format!("{email} isn't a valid email"))
+    );
+    Ok(email)
+}
+
+fn invalid_credentials() -> anyhow::Error {
+    ErrorMetadata::unauthenticated("InvalidCredentials", "Incorrect email or password").into()
+}
+
+fn hash_magic_link_token(token: &str) -> Vec<u8> {
+    Sha256::digest(token.as_bytes()).to_vec()
+}
+
+async fn run_blocking<T: Send + 'static>(
+    f: impl FnOnce() -> anyhow::Result<T> + Send + 'static,
+) -> anyhow::Result<T> {
+    tokio::task::spawn_blocking(f).await?
+}
+
+impl<RT: Runtime> Application<RT> {
+    pub async fn first_party_signup(
+        &self,
+        email: String,
+        password: String,
+    ) -> anyhow::Result<FirstPartyAuthSession> {
+        check_enabled()?;
+        anyhow::ensure!(
+            *FIRST_PARTY_AUTH_SIGNUP_ENABLED,
+            ErrorMetadata::forbidden(
+                "SignupDisabled",
+                "Signing up isn't enabled for this deployment",
+            )
+        );
+        let email = normalize_email(&email)?;
+        check_password_length(&password)?;
+        // Hash before starting the transaction so it isn't held open.
+        let password_hash = run_blocking(move || hash_password(&password)).await?;
+        let mut tx = self.begin(Identity::system()).await?;
+        let now = self.runtime.unix_timestamp();
+        let account_id = FirstPartyAuthModel::new(&mut tx)
+            .insert_account(AuthAccount {
+                email,
+                password_hash: Some(password_hash),
+                magic_link_token_hash: None,
+                magic_link_expires_at: None,
+                created_at: now,
+            })
+            .await?;
+        let session = self.create_first_party_session(&mut tx, account_id).await?;
+        self.commit(tx, "first_party_signup").await?;
+        Ok(session)
+    }
+
+    pub async fn first_party_login(
+        &self,
+        email: String,
+        password: String,
+    ) -> anyhow::Result<FirstPartyAuthSession> {
+        check_enabled()?;
+        let email = normalize_email(&email)?;
+        let account = {
+            let mut tx = self.begin(Identity::system()).await?;
+            FirstPartyAuthModel::new(&mut tx)
+                .account_by_email(&email)
+                .await?
+        };
+        // Verify after the transaction is dropped so it isn't held open. Emails
+        // without a password are checked against a dummy hash, so the response
+        // time doesn't reveal which emails have accounts.
+        let password_hash = account
+            .as_ref()
+            .and_then(|account| account.password_hash.clone());
+        let expected_hash = password_hash.clone();
+        if !run_blocking(move || verify_password_if_set(&password, expected_hash.as_deref()))
+            .await?
+        {
+            return Err(invalid_credentials());
+        }
+        let (Some(account), Some(password_hash)) = (account, password_hash) else {
+            return Err(invalid_credentials());
+        };
+        // The password may have changed, or the account been deleted, while
+        // verifying.
+        let mut tx = self.begin(Identity::system()).await?;
+        let current = FirstPartyAuthModel::new(&mut tx)
+            .account(&DeveloperDocumentId::from(account.id()).encode())
+            .await?;
+        if current.and_then(|current| current.into_value().password_hash) != Some(password_hash) {
+            return Err(invalid_credentials());
+        }
+        let session = self
+            .create_first_party_session(&mut tx, account.id())
+            .await?;
+        self.commit(tx, "first_party_login").await?;
+        Ok(session)
+    }
+
+    /// Ends the session `token` was issued for. Logging out of a session that
+    /// already ended succeeds.
+    pub async fn first_party_logout(&self, token: String) -> anyhow::Result<()> {
+        check_enabled()?;
+        let (session_id, _) = self.app_auth().check_user_session_token(&token)?;
+        let mut tx = self.begin(Identity::system()).await?;
+        if FirstPartyAuthModel::new(&mut tx)
+            .delete_session(&session_id)
+            .await?
+        {
+            self.commit(tx, "first_party_logout").await?;
+        }
+        Ok(())
+    }
+
+    /// Schedules `FIRST_PARTY_AUTH_MAGIC_LINK_FUNCTION` to send the user a
+    /// one-time token, creating an account for the email if there isn't one
+    /// and signing up is enabled.
+    pub async fn request_magic_link(&self, email: String) -> anyhow::Result<()> {
+        check_enabled()?;
+        let email = normalize_email(&email)?;
+        let token: String = self
+            .runtime
+            .rng()
+            .sample_iter(Alphanumeric)
+            .take(MAGIC_LINK_TOKEN_LENGTH)
+            .map(char::from)
+            .collect();
+        let udf_path: CanonicalizedUdfPath = FIRST_PARTY_AUTH_MAGIC_LINK_FUNCTION.parse()?;
+        let mut tx = self.begin(Identity::system()).await?;
+        let now = self.runtime.unix_timestamp();
+        let expires_at = now + *FIRST_PARTY_AUTH_MAGIC_LINK_TTL;
+        let mut model = FirstPartyAuthModel::new(&mut tx);
+        match model.account_by_email(&email).await? {
+            Some(account) => {
+                let id = account.id();
+                let mut account = account.into_value();
+                account.magic_link_token_hash = Some(hash_magic_link_token(&token));
+                account.magic_link_expires_at = Some(expires_at);
+                model.replace_account(id, account).await?;
+            },
+            // Succeed without sending anything, so the response doesn't reveal
+            // which emails have accounts.
+            None if !*FIRST_PARTY_AUTH_SIGNUP_ENABLED => return Ok(()),
+            None => {
+                model
+                    .insert_account(AuthAccount {
+                        email: email.clone(),
+                        password_hash: None,
+                        magic_link_token_hash: Some(hash_magic_link_token(&token)),
+                        magic_link_expires_at: Some(expires_at),
+                        created_at: now,
+                    })
+                    .await?;
+            },
+        }
+        let args = ConvexArray::try_from(vec![ConvexValue::Object(obj!(
+            "email" => email,
+            "token" => token
+        )?)])?;
+        SchedulerModel::new(&mut tx, TableNamespace::root_component())
+            .schedule(
+                CanonicalizedComponentFunctionPath {
+                    component: ComponentPath::root(),
+                    udf_path,
+                },
+                args,
+                now,
+                ExecutionContext::new_from_parts(RequestId::new(), ExecutionId::new(), None, true),
+            )
+            .await?;
+        self.commit(tx, "request_magic_link").await?;
+        Ok(())
+    }
+
+    /// Logs in with a token sent by [`Self::request_magic_link`]. Each token
+    /// can only be used once.
+    pub async fn verify_magic_link(
+        &self,
+        email: String,
+        token: String,
+    ) -> anyhow::Result<FirstPartyAuthSession> {
+        check_enabled()?;
+        let email = normalize_email(&email)?;
+        let invalid_link = || {
+            ErrorMetadata::unauthenticated(
+                "InvalidMagicLink",
+                "The magic link is invalid, expired, or was already used",
+            )
+        };
+        let mut tx = self.begin(Identity::system()).await?;
+        let now = self.runtime.unix_timestamp();
+        let mut model = FirstPartyAuthModel::new(&mut tx);
+        let account = model
+            .account_by_email(&email)
+            .await?
+            .ok_or_else(invalid_link)?;
+        let id = account.id();
+        let mut account = account.into_value();
+        let valid = account.magic_link_token_hash.as_deref()
+            == Some(&hash_magic_link_token(&token)[..])
+            && account
+                .magic_link_expires_at
+                .is_some_and(|expires_at| now < expires_at);
+        anyhow::ensure!(valid, invalid_link());
+        account.magic_link_token_hash = None;
+        account.magic_link_expires_at = None;
+        model.replace_account(id, account).await?;
+        let session = self.create_first_party_session(&mut tx, id).await?;
+        self.commit(tx, "verify_magic_link").await?;
+        Ok(session)
+    }
+
+    async fn create_first_party_session(
+        &self,
+        tx: &mut Transaction<RT>,
+        account_id: ResolvedDocumentId,
+    ) -> anyhow::Result<FirstPartyAuthSession> {
+        let now = self.runtime.unix_timestamp();
+        let expires_at = now + *FIRST_PARTY_AUTH_SESSION_TTL;
+        let session_id = FirstPartyAuthModel::new(tx)
+            .create_session(account_id, expires_at, now)
+            .await?;
+        let token = self.app_auth().issue_user_session_token(
+            DeveloperDocumentId::from(session_id).encode(),
+            expires_at.as_system_time(),
+        )?;
+        Ok(FirstPartyAuthSession {
+            token,
+            expires_at: expires_at.as_system_time(),
+        })
+    }
+
+    /// The identity for a session token, if its session hasn't been logged
+    /// out. Called by [`Application::authenticate`] for ID tokens that are
+    /// session tokens.
+    pub(crate) async fn authenticate_session_token(
+        &self,
+        token: String,
+    ) -> anyhow::Result<UserIdentity> {
+        let (session_id, expiration) = self.app_auth().check_user_session_token(&token)?;
+        let ended = || {
+            ErrorMetadata::unauthenticated(
+                "AuthSessionInvalid",
+                "The session has ended. Log in again.",
+            )
+        };
+        let mut tx = self.begin(Identity::system()).await?;
+        let mut model = FirstPartyAuthModel::new(&mut tx);
+        let session = model.session(&session_id).await?.ok_or_else(ended)?;
+        let account = model
+            .account(&session.account_id)
+            .await?
+            .ok_or_else(ended)?;
+        Ok(UserIdentity::from_session(
+            self.convex_origin.to_string(),
+            session.account_id.clone(),
+            account.email.clone(),
+            expiration,
+            token,
+        ))
+    }
+}
@@ -24,7 +24,11 @@ use common::{
     },
     execution_context::ExecutionContext,
     identity::InertIdentity,
-    knobs::MAX_UDF_EXECUTION,
+    knobs::{
+        FUNCTION_ARGS_SAMPLE_RATE,
+        MAX_UDF_EXECUTION,
+        SLOW_EXECUTION_LOG_MAX_ROWS,
+    },
     log_lines::{
         LogLine,
         LogLines,
@@ -67,12 +71,18 @@ use isolate::{
     UdfOutcome,
 };
 use itertools::Either;
+use model::{
+    slow_executions::types::SlowExecution,
+    usage_meters::types::UsageCounters,
+};
 use parking_lot::Mutex;
+use rand::Rng;
 use serde::Deserialize;
 use serde_json::{
     json,
     Value as JsonValue,
 };
+use sync_types::CanonicalizedUdfPath;
 use tokio::sync::oneshot;
 use url::Url;
 use usage_tracking::{
@@ -88,6 +98,18 @@ use value::{
     },
     sha256::Sha256Digest,
     ConvexArray,
+    Size,
+};
+
+use crate::{
+    metrics::log_function_execution,
+    metrics_rollups::PendingRollups,
+    observed_args::{
+        ObservedArgs,
+        ObservedFunctionArgs,
+    },
+    slow_execution_log::slow_execution,
+    usage_metering::PendingUsage,
 };
 /// A function's execution is summarized by this structure and stored in the
 /// UdfExecutionLog
@@ -132,6 +154,8 @@ pub struct FunctionExecution {
     /// Usage statistics for this instance
     pub usage_stats: AggregatedFunctionUsageStats,
     pub action_memory_used_mb: Option<u64>,
+    /// How big the arguments were. HTTP actions don't have arguments.
+    pub args_size_bytes: Option<u64>,
 
     /// The Convex NPM package version pushed with the module version executed.
     pub udf_server_version: Option<semver::Version>,
@@ -155,7 +179,7 @@ impl HeapSize for FunctionExecution {
 }
 
 impl FunctionExecution {
-    fn identifier(&self) -> UdfIdentifier {
+    pub(crate) fn identifier(&self) -> UdfIdentifier {
         match &self.params {
             UdfParams::Function { identifier, .. } => UdfIdentifier::Function(identifier.clone()),
             UdfParams::Http { identifier, .. } => UdfIdentifier::Http(identifier.clone()),
@@ -536,6 +560,10 @@ impl<RT: Runtime> FunctionExecutionLog<RT> {
             log_waiters: vec![].into(),
             log_manager,
             metrics: Metrics::default(),
+            observed_args: ObservedArgs::default(),
+            pending_rollups: PendingRollups::default(),
+            pending_slow_executions: VecDeque::new(),
+            pending_usage: PendingUsage::default(),
         };
         Self {
             inner: Arc::new(Mutex::new(inner)),
@@ -630,6 +658,9 @@ impl<RT: Runtime> FunctionExecutionLog<RT> {
         if outcome.path.is_system() {
             return;
         }
+        if outcome.result.is_ok() {
+            self.sample_args(&outcome.path, &outcome.arguments);
+        }
         let execution = FunctionExecution {
             params: UdfParams::Function {
                 error: match outcome.result {
@@ -650,6 +681,7 @@ impl<RT: Runtime> FunctionExecutionLog<RT> {
             syscall_trace: outcome.syscall_trace,
             usage_stats: aggregated,
             action_memory_used_mb: None,
+            args_size_bytes: Some(outcome.arguments.size() as u64),
             udf_server_version: outcome.udf_server_version,
             identity: outcome.identity,
             context,
@@ -752,6 +784,9 @@ impl<RT: Runtime> FunctionExecutionLog<RT> {
         if outcome.path.udf_path.is_system() {
             return;
         }
+        if outcome.result.is_ok() {
+            self.sample_args(&outcome.path, &outcome.arguments);
+        }
         let execution = FunctionExecution {
             params: UdfParams::Function {
                 error: match outcome.result {
@@ -772,6 +807,7 @@ impl<RT: Runtime> FunctionExecutionLog<RT> {
             syscall_trace: outcome.syscall_trace,
             usage_stats: aggregated,
             action_memory_used_mb: None,
+            args_size_bytes: Some(outcome.arguments.size() as u64),
             udf_server_version: outcome.udf_server_version,
             identity: outcome.identity,
             context,
@@ -840,6 +876,9 @@ impl<RT: Runtime> FunctionExecutionLog<RT> {
         if outcome.path.udf_path.is_system() {
             return;
         }
+        if outcome.result.is_ok() {
+            self.sample_args(&outcome.path, &outcome.arguments);
+        }
         let execution = FunctionExecution {
             params: UdfParams::Function {
                 error: match outcome.result {
@@ -860,6 +899,7 @@ impl<RT: Runtime> FunctionExecutionLog<RT> {
             syscall_trace: outcome.syscall_trace,
             usage_stats: aggregated,
             action_memory_used_mb: Some(completion.memory_in_mb),
+            args_size_bytes: Some(outcome.arguments.size() as u64),
             udf_server_version: outcome.udf_server_version,
             identity: outcome.identity,
             context: completion.context,
@@ -867,6 +907,65 @@ impl<RT: Runtime> FunctionExecutionLog<RT> {
         self.log_execution(execution, /* send_console_events */ false)
     }
 
+    /// Record a sample of the arguments of a successful call for argument
+    /// shape inference.
+    fn sample_args(&self, path: &CanonicalizedComponentFunctionPath, args: &ConvexArray) {
+        if self
+            .rt
+            .rng()
+            .gen_bool(FUNCTION_ARGS_SAMPLE_RATE.clamp(0.0, 1.0))
+        {
+            self.inner.lock().observed_args.record(path, args);
+        }
+    }
+
+    /// The sampled arguments of each function in `component`.
+    pub fn observed_args(
+        &self,
+        component: &ComponentPath,
+    ) -> BTreeMap<CanonicalizedUdfPath, ObservedFunctionArgs> {
+        self.inner.lock().observed_args.in_component(component)
+    }
+
+    /// Stats for the executions logged since the last call, to be added to
+    /// the stored metrics rollups.
+    pub(crate) fn take_pending_rollups(&self) -> PendingRollups {
+        std::mem::take(&mut self.inner.lock().pending_rollups)
+    }
+
+    /// Put back stats that couldn't be stored, so they're included in the
+    /// next flush.
+    pub(crate) fn restore_pending_rollups(&self, rollups: PendingRollups) {
+        self.inner.lock().pending_rollups.merge(rollups);
+    }
+
+    /// Slow executions logged since the last call, to be written to
+    /// `_slow_executions`, oldest first.
+    pub(crate) fn take_slow_executions(&self) -> Vec<SlowExecution> {
+        std::mem::take(&mut self.inner.lock().pending_slow_executions).into()
+    }
+
+    /// Usage metered since the last call, to be added to `_usage_meters`.
+    pub(crate) fn take_pending_usage(&self) -> PendingUsage {
+        std::mem::take(&mut self.inner.lock().pending_usage)
+    }
+
+    /// Put back usage that couldn't be stored, so it's included in the next
+    /// flush.
+    pub(crate) fn restore_pending_usage(&self, usage: PendingUsage) {
+        self.inner.lock().pending_usage.merge(usage);
+    }
+
+    /// Usage by `function`, or the deployment for `None`, on `day` that
+    /// hasn't been stored yet.
+    pub(crate) fn pending_usage(
+        &self,
+        day: UnixTimestamp,
+        function: &Option<String>,
+    ) -> UsageCounters {
+        self.inner.lock().pending_usage.get(day, function)
+    }
+
     pub fn log_action_progress(
         &self,
         path: CanonicalizedComponentFunctionPath,
@@ -991,6 +1090,7 @@ impl<RT: Runtime> FunctionExecutionLog<RT> {
             environment: ModuleEnvironment::Isolate,
             usage_stats: aggregated,
             action_memory_used_mb: Some(outcome.memory_in_mb()),
+            args_size_bytes: None,
             syscall_trace: outcome.syscall_trace,
             udf_server_version: outcome.udf_server_version,
             identity: outcome.identity,
@@ -1220,6 +1320,10 @@ struct Inner<RT: Runtime> {
     log_manager: Arc<dyn LogSender>,
 
     metrics: Metrics,
+    observed_args: ObservedArgs,
+    pending_rollups: PendingRollups,
+    pending_slow_executions: VecDeque<SlowExecution>,
+    pending_usage: PendingUsage,
 }
 
 impl<RT: Runtime> Inner<RT> {
@@ -1229,6 +1333,21 @@ impl<RT: Runtime> Inner<RT> {
         send_console_events: bool,
     ) -> anyhow::Result<()> {
         self.metrics.append(&execution)?;
+        self.pending_rollups.append(&execution);
+        self.pending_usage.append(&execution);
+        log_function_execution(
+            execution.udf_type,
+            execution.params.identifier_str(),
+            execution.params.is_err(),
+            execution.execution_time,
+        );
+        if let Some(slow_execution) = slow_execution(&execution) {
+            // Only the most recent slow executions are kept anyway.
+            if self.pending_slow_executions.len() >= *SLOW_EXECUTION_LOG_MAX_ROWS {
+                self.pending_slow_executions.pop_front();
+            }
+            self.pending_slow_executions.push_back(slow_execution);
+        }
         let next_time = self.next_time()?;
 
         // Gather log lines
@@ -0,0 +1,79 @@
+//! Keeps isolates warm for functions that set `warmInstances`, so that calls
+//! to latency-critical functions after an idle period don't wait for a new
+//! isolate to compile their modules. Warm-ups rerun after every push and
+//! often enough that the warm isolates don't idle out in between.
+use std::{
+    sync::Arc,
+    time::Duration,
+};
+
+use common::{
+    backoff::Backoff,
+    errors::report_error,
+    knobs::FUNCTION_WARM_UP_INTERVAL,
+    runtime::Runtime,
+};
+use database::Database;
+use futures::{
+    select_biased,
+    Future,
+    FutureExt,
+};
+use keybroker::Identity;
+
+use crate::application_function_runner::ApplicationFunctionRunner;
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+pub struct FunctionWarmUpWorker<RT: Runtime> {
+    runtime: RT,
+    database: Database<RT>,
+    runner: Arc<ApplicationFunctionRunner<RT>>,
+}
+
+impl<RT: Runtime> FunctionWarmUpWorker<RT> {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        runtime: RT,
+        database: Database<RT>,
+        runner: Arc<ApplicationFunctionRunner<RT>>,
+    ) -> impl Future<Output = ()> + Send {
+        let worker = Self {
+            runtime,
+            database,
+            runner,
+        };
+        async move {
+            let mut backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
+            while let Err(mut e) = worker.run(&mut backoff).await {
+                report_error(&mut e);
+                let delay = backoff.fail(&mut worker.runtime.rng());
+                tracing::error!("FunctionWarmUpWorker failed, sleeping {delay:?}");
+                worker.runtime.wait(delay).await;
+            }
+        }
+    }
+
+    async fn run(&self, backoff: &mut Backoff) -> anyhow::Result<()> {
+        tracing::info!("Starting FunctionWarmUpWorker");
+        loop {
+            let mut tx = self.database.begin(Identity::system()).await?;
+            for (component, component_path) in tx.all_component_paths() {
+                // A function that fails to evaluate will fail when it's called
+                // too, so there's nothing to retry until the next push.
+                if let Err(e) = self.runner.warm_up_functions(&mut tx, component).await? {
+                    tracing::warn!("Failed to warm up functions in {component_path:?}: {e}");
+                }
+            }
+            // Reading the modules subscribes to the next push.
+            let token = tx.into_token()?;
+            let subscription = self.database.subscribe(token).await?;
+            select_biased! {
+                _ = subscription.wait_for_invalidation().fuse() => {},
+                _ = self.runtime.wait(*FUNCTION_WARM_UP_INTERVAL).fuse() => {},
+            }
+            backoff.reset();
+        }
+    }
+}
@@ -11,7 +11,6 @@ use std::{
         BTreeMap,
         HashSet,
     },
-    ops::Bound,
     sync::Arc,
     time::{
         Duration,
@@ -23,6 +22,10 @@ use std::{
 use anyhow::Context;
 use authentication::{
     application_auth::ApplicationAuth,
+    oidc_providers::{
+        OidcProviderCache,
+        OidcProviderStatus,
+    },
     validate_id_token,
     Auth0IdToken,
 };
@@ -52,7 +55,9 @@ use common::{
         Resource,
     },
     document::{
+        CreationTime,
         DocumentUpdate,
+        ParsedDocument,
         CREATION_TIME_FIELD_PATH,
     },
     errors::{
@@ -63,6 +68,8 @@ use common::{
     knobs::{
         APPLICATION_MAX_CONCURRENT_UPLOADS,
         MAX_JOBS_CANCEL_BATCH,
+        REPLICATION_STREAM_PAGE_SIZE,
+        REPLICATION_TOKEN_VALIDITY,
         SNAPSHOT_LIST_LIMIT,
     },
     log_lines::LogLines,
@@ -70,6 +77,7 @@ use common::{
     paths::FieldPath,
     pause::PauseClient,
     persistence::Persistence,
+    query::IndexRange,
     query_journal::QueryJournal,
     runtime::{
         Runtime,
@@ -80,6 +88,7 @@ use common::{
     types::{
         env_var_limit_met,
         env_var_name_not_unique,
+        AdminKey,
         ConvexOrigin,
         ConvexSite,
         CursorMs,
@@ -89,6 +98,7 @@ use common::{
         FunctionCaller,
         IndexId,
         IndexName,
+        MemberId,
         ModuleEnvironment,
         NodeDependency,
         ObjectKey,
@@ -103,13 +113,17 @@ use common::{
 };
 use cron_jobs::CronJobExecutor;
 use database::{
+    replication::ReplicatedCommit,
     unauthorized_error,
     BootstrapComponentsModel,
     Database,
     DocumentDeltas,
     FastForwardIndexWorker,
+    HybridSearch,
+    HybridSearchResult,
     IndexModel,
     IndexWorker,
+    LiveAggregateSubscription,
     OccRetryStats,
     SearchIndexWorkers,
     Snapshot,
@@ -121,13 +135,17 @@ use database::{
     Transaction,
     WriteSource,
 };
+use deleting_tables_cleanup::{
+    DeletingTablesCleanupClient,
+    DeletingTablesCleanupWorker,
+};
 use either::Either;
 use errors::{
     ErrorMetadata,
     ErrorMetadataAnyhowExt,
 };
 use file_storage::{
-    FileRangeStream,
+    FileRange,
     FileStorage,
     FileStream,
 };
@@ -140,6 +158,7 @@ use futures::stream::BoxStream;
 use headers::{
     ContentLength,
     ContentType,
+    Range,
 };
 use http_client::{
     cached_http_client_for,
@@ -151,14 +170,20 @@ use isolate::{
     HttpActionRequest,
     HttpActionResponseStreamer,
     HttpActionResult,
+    JsonPackedValue,
     UdfOutcome,
     CONVEX_ORIGIN,
     CONVEX_SITE,
 };
 use keybroker::{
+    admin_key_fingerprint,
+    AdminKeyScope,
     Identity,
     InstanceSecret,
     KeyBroker,
+    ReplicationToken,
+    RetiredAdminKeySecret,
+    StoreFileConstraints,
 };
 use maplit::btreemap;
 use minitrace::{
@@ -168,8 +193,25 @@ use minitrace::{
     Span,
 };
 use model::{
+    admin_keys::{
+        types::{
+            AdminKeyMetadata,
+            AdminKeySecrets,
+            RetiredAdminKeySecret as RetiredAdminKeySecretMetadata,
+        },
+        AdminKeysModel,
+    },
+    api_keys::is_api_key,
+    audit_log::{
+        types::AuditLogEntry,
+        AuditLogModel,
+    },
     auth::AuthInfoModel,
     backend_state::BackendStateModel,
+    backup_schedule::{
+        types::BackupSchedule,
+        BackupScheduleModel,
+    },
     components::{
         config::ComponentConfigModel,
         handles::FunctionHandlesModel,
@@ -186,6 +228,18 @@ use model::{
         },
         ConfigModel,
     },
+    cors_config::{
+        types::CorsConfig,
+        CorsConfigModel,
+    },
+    counters::ShardCountTuner,
+    custom_domains::{
+        types::{
+            CustomDomain,
+            CustomDomainTarget,
+        },
+        CustomDomainsModel,
+    },
     deployment_audit_log::{
         types::DeploymentAuditLogEvent,
         DeploymentAuditLogModel,
@@ -195,6 +249,15 @@ use model::{
         EnvironmentVariablesModel,
         ENVIRONMENT_VARIABLES_TABLE,
     },
+    export_watermarks::{
+        types::{
+            ExportWatermark,
+            ExportWatermarkConfig,
+            WatermarkMode,
+            MAX_EXPORT_WATERMARK_LABEL_LEN,
+        },
+        ExportWatermarksModel,
+    },
     exports::{
         types::{
             Export,
@@ -211,9 +274,17 @@ use model::{
         ExternalPackagesModel,
     },
     file_storage::{
-        types::FileStorageEntry,
+        types::{
+            validate_file_storage_filename,
+            validate_file_storage_tags,
+            FileStorageEntry,
+        },
         FileStorageId,
     },
+    function_limits::{
+        types::FunctionLimits,
+        FunctionLimitsModel,
+    },
     modules::{
         module_versions::{
             AnalyzedModule,
@@ -221,12 +292,35 @@ use model::{
         },
         ModuleModel,
     },
-    scheduled_jobs::SchedulerModel,
+    network_acl::{
+        types::NetworkAclConfig,
+        NetworkAclConfigModel,
+    },
+    operations::{
+        types::{
+            Operation,
+            OperationKind,
+        },
+        OperationsModel,
+    },
+    rate_limit_config::{
+        types::RateLimitConfig,
+        RateLimitConfigModel,
+    },
+    scheduled_jobs::{
+        types::{
+            ScheduledJob,
+            ScheduledJobFilter,
+            ScheduledJobsCursor,
+        },
+        SchedulerModel,
+    },
     session_requests::types::SessionRequestIdentifier,
     snapshot_imports::types::{
         ImportFormat,
         ImportMode,
         ImportRequestor,
+        ImportTableSelection,
     },
     source_packages::{
         types::{
@@ -236,6 +330,7 @@ use model::{
         upload_download::upload_package,
         SourcePackageModel,
     },
+    table_compactions::TableCompactionModel,
     udf_config::{
         types::UdfConfig,
         UdfConfigModel,
@@ -243,7 +338,10 @@ use model::{
 };
 use node_executor::Actions;
 use parking_lot::Mutex;
-use rand::Rng;
+use rand::{
+    distributions::Alphanumeric,
+    Rng,
+};
 use scheduled_jobs::ScheduledJobRunner;
 use schema_worker::SchemaWorker;
 use search::{
@@ -258,6 +356,7 @@ use serde_json::Value as JsonValue;
 use short_future::ShortBoxFuture;
 use snapshot_import::{
     clear_tables,
+    restore_tables,
     start_stored_import,
 };
 use storage::{
@@ -310,6 +409,9 @@ use vector::{
 
 use crate::{
     application_function_runner::ApplicationFunctionRunner,
+    archival::ArchivalWorker,
+    backup_schedule_worker::BackupScheduleWorker,
+    document_access::DocumentAccessWorker,
     export_worker::ExportWorker,
     function_log::{
         FunctionExecutionLog,
@@ -320,32 +422,76 @@ use crate::{
         UdfMetricSummary,
         UdfRate,
     },
+    function_warm_up_worker::FunctionWarmUpWorker,
     log_visibility::LogVisibility,
+    materialized_aggregates::MaterializedAggregateWorker,
+    metrics_rollups::MetricsRollupWorker,
     module_cache::ModuleCache,
+    pii_scan::PiiScanWorker,
     redaction::{
         RedactedJsError,
         RedactedLogLines,
     },
+    replication_worker::{
+        ReplicationConfig,
+        ReplicationWorker,
+    },
+    rust_client_codegen::{
+        generate_rust_client,
+        RustClientFunction,
+    },
+    slow_execution_log::SlowExecutionLogWorker,
     snapshot_import::SnapshotImportWorker,
+    table_compaction_worker::TableCompactionWorker,
+    usage_metering::{
+        UsageMeterWorker,
+        UsageQuotas,
+    },
 };
 
 pub mod api;
+pub mod api_keys;
 pub mod application_function_runner;
+pub mod archival;
+mod backup_schedule_worker;
 mod cache;
+pub mod counters;
 pub mod cron_jobs;
+pub mod deleting_tables_cleanup;
 pub mod deploy_config;
+pub mod document_access;
+pub mod embeddings;
 mod export_worker;
+pub mod file_storage_transform;
+pub mod file_storage_upload;
+pub mod first_party_auth;
 pub mod function_log;
+mod function_warm_up_worker;
+pub mod log_streaming;
 pub mod log_visibility;
+pub mod materialized_aggregates;
 mod metrics;
+mod metrics_rollups;
 mod module_cache;
+pub mod observed_args;
+mod pii_scan;
+pub mod queues;
 pub mod redaction;
+pub mod replication_worker;
+pub mod rust_client_codegen;
 pub mod scheduled_jobs;
 mod schema_worker;
+pub mod search_index_bundle;
+mod slow_execution_log;
 pub mod snapshot_import;
+pub mod storage_gc;
 mod system_table_cleanup;
+mod table_compaction_worker;
 mod table_summary_worker;
+mod usage_metering;
 pub mod valid_identifier;
+pub mod vector_index_compaction;
+pub mod workflows;
 
 #[cfg(any(test, feature = "testing"))]
 pub mod test_helpers;
@@ -376,9 +522,11 @@ pub struct ApplyConfigArgs {
     pub analyze_results: BTreeMap<CanonicalizedModulePath, AnalyzedModule>,
 }
 
+/// The result of a query. The value stays serialized, as it was cached, so
+/// every caller that gets the same cached result shares one payload.
 #[derive(Debug)]
 pub struct QueryReturn {
-    pub result: Result<ConvexValue, JsError>,
+    pub result: Result<JsonPackedValue, JsError>,
     pub log_lines: LogLines,
     pub token: Token,
     pub journal: QueryJournal,
@@ -386,7 +534,7 @@ pub struct QueryReturn {
 
 #[derive(Debug)]
 pub struct RedactedQueryReturn {
-    pub result: Result<ConvexValue, RedactedJsError>,
+    pub result: Result<JsonPackedValue, RedactedJsError>,
     pub log_lines: RedactedLogLines,
     pub token: Token,
     pub journal: SerializedQueryJournal,
@@ -420,6 +568,36 @@ pub struct RedactedMutationError {
     pub log_lines: RedactedLogLines,
 }
 
+/// The results of a mutation batch, which all committed at `ts`.
+#[derive(Debug)]
+pub struct MutationBatchReturn {
+    pub results: Vec<MutationReturn>,
+    pub ts: Timestamp,
+}
+
+#[derive(Debug)]
+pub struct RedactedMutationBatchReturn {
+    pub results: Vec<RedactedMutationReturn>,
+    pub ts: Timestamp,
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("Mutation batch failed: {error}")]
+pub struct MutationBatchError {
+    /// The mutation that failed, or `None` if the batch failed to commit.
+    pub index: Option<usize>,
+    pub error: JsError,
+    pub log_lines: LogLines,
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error("Mutation batch failed: {error}")]
+pub struct RedactedMutationBatchError {
+    pub index: Option<usize>,
+    pub error: RedactedJsError,
+    pub log_lines: RedactedLogLines,
+}
+
 #[derive(Debug)]
 pub struct ActionReturn {
     pub value: ConvexValue,
@@ -490,12 +668,29 @@ pub struct Application<RT: Runtime> {
     schema_worker: Arc<Mutex<Box<dyn SpawnHandle>>>,
     snapshot_import_worker: Arc<Mutex<Box<dyn SpawnHandle>>>,
     export_worker: Arc<Mutex<Box<dyn SpawnHandle>>>,
+    backup_schedule_worker: Arc<Mutex<Box<dyn SpawnHandle>>>,
+    function_warm_up_worker: Arc<Mutex<Box<dyn SpawnHandle>>>,
+    metrics_rollup_worker: Arc<Mutex<Box<dyn SpawnHandle>>>,
+    slow_execution_log_worker: Arc<Mutex<Box<dyn SpawnHandle>>>,
+    usage_meter_worker: Arc<Mutex<Box<dyn SpawnHandle>>>,
+    usage_quotas: Arc<UsageQuotas<RT>>,
+    pii_scan_worker: Arc<Mutex<Box<dyn SpawnHandle>>>,
+    document_access_worker: Arc<Mutex<Box<dyn SpawnHandle>>>,
+    archival_worker: Arc<Mutex<Box<dyn SpawnHandle>>>,
+    materialized_aggregate_worker: Arc<Mutex<Box<dyn SpawnHandle>>>,
+    replication_worker: Option<Arc<Mutex<Box<dyn SpawnHandle>>>>,
     system_table_cleanup_worker: Arc<Mutex<Box<dyn SpawnHandle>>>,
+    deleting_tables_cleanup_worker: Arc<Mutex<Box<dyn SpawnHandle>>>,
+    deleting_tables_cleanup: DeletingTablesCleanupClient,
+    table_compaction_worker: Arc<Mutex<Box<dyn SpawnHandle>>>,
     log_sender: Arc<dyn LogSender>,
     log_visibility: Arc<dyn LogVisibility<RT>>,
     module_cache: ModuleCache<RT>,
     system_env_var_names: HashSet<EnvVarName>,
     app_auth: Arc<ApplicationAuth>,
+    counter_tuner: Arc<Mutex<ShardCountTuner>>,
+    oidc_providers: Arc<OidcProviderCache>,
+    convex_origin: ConvexOrigin,
 }
 
 impl<RT: Runtime> Clone for Application<RT> {
@@ -524,12 +719,29 @@ impl<RT: Runtime> Clone for Application<RT> {
             schema_worker: self.schema_worker.clone(),
             snapshot_import_worker: self.snapshot_import_worker.clone(),
             export_worker: self.export_worker.clone(),
+            backup_schedule_worker: self.backup_schedule_worker.clone(),
+            function_warm_up_worker: self.function_warm_up_worker.clone(),
+            metrics_rollup_worker: self.metrics_rollup_worker.clone(),
+            slow_execution_log_worker: self.slow_execution_log_worker.clone(),
+            usage_meter_worker: self.usage_meter_worker.clone(),
+            usage_quotas: self.usage_quotas.clone(),
+            pii_scan_worker: self.pii_scan_worker.clone(),
+            document_access_worker: self.document_access_worker.clone(),
+            archival_worker: self.archival_worker.clone(),
+            materialized_aggregate_worker: self.materialized_aggregate_worker.clone(),
+            replication_worker: self.replication_worker.clone(),
             system_table_cleanup_worker: self.system_table_cleanup_worker.clone(),
+            deleting_tables_cleanup_worker: self.deleting_tables_cleanup_worker.clone(),
+            deleting_tables_cleanup: self.deleting_tables_cleanup.clone(),
+            table_compaction_worker: self.table_compaction_worker.clone(),
             log_sender: self.log_sender.clone(),
             log_visibility: self.log_visibility.clone(),
             module_cache: self.module_cache.clone(),
             system_env_var_names: self.system_env_var_names.clone(),
             app_auth: self.app_auth.clone(),
+            counter_tuner: self.counter_tuner.clone(),
+            oidc_providers: self.oidc_providers.clone(),
+            convex_origin: self.convex_origin.clone(),
         }
     }
 }
@@ -555,12 +767,14 @@ impl<RT: Runtime> Application<RT> {
         segment_term_metadata_fetcher: Arc<dyn SegmentTermMetadataFetcher>,
         persistence: Arc<dyn Persistence>,
         node_actions: Actions,
+        regional_node_actions: BTreeMap<String, Actions>,
         fetch_client: Arc<dyn FetchClient>,
         log_sender: Arc<dyn LogSender>,
         log_visibility: Arc<dyn LogVisibility<RT>>,
         snapshot_import_pause_client: PauseClient,
         scheduled_jobs_pause_client: PauseClient,
         app_auth: Arc<ApplicationAuth>,
+        replication: Option<ReplicationConfig>,
     ) -> anyhow::Result<Self> {
         let module_cache = ModuleCache::new(runtime.clone(), modules_storage.clone()).await;
         let module_loader = Arc::new(module_cache.clone());
@@ -610,6 +824,23 @@ impl<RT: Runtime> Application<RT> {
             runtime.spawn("system_table_cleanup_worker", system_table_cleanup_worker),
         ));
 
+        let (deleting_tables_cleanup_worker, deleting_tables_cleanup) =
+            DeletingTablesCleanupWorker::new(
+                runtime.clone(),
+                database.clone(),
+                persistence.clone(),
+            );
+        let deleting_tables_cleanup_worker = Arc::new(Mutex::new(runtime.spawn(
+            "deleting_tables_cleanup_worker",
+            deleting_tables_cleanup_worker,
+        )));
+
+        let table_compaction_worker =
+            TableCompactionWorker::new(runtime.clone(), database.clone(), persistence.clone());
+        let table_compaction_worker = Arc::new(Mutex::new(
+            runtime.spawn("table_compaction_worker", table_compaction_worker),
+        ));
+
         let function_log = FunctionExecutionLog::new(
             runtime.clone(),
             database.usage_counter(),
@@ -623,12 +854,14 @@ impl<RT: Runtime> Application<RT> {
             key_broker.clone(),
             function_runner.clone(),
             node_actions,
+            regional_node_actions,
             file_storage.transactional_file_storage.clone(),
             modules_storage.clone(),
             module_loader,
             function_log.clone(),
             system_env_vars.clone(),
-            fetch_client,
+            fetch_client.clone(),
+            replication.is_some(),
         ));
         function_runner.set_action_callbacks(runner.clone());
 
@@ -661,6 +894,64 @@ impl<RT: Runtime> Application<RT> {
         );
         let export_worker = Arc::new(Mutex::new(runtime.spawn("export_worker", export_worker)));
 
+        let backup_schedule_worker =
+            BackupScheduleWorker::new(runtime.clone(), database.clone(), exports_storage.clone());
+        let backup_schedule_worker = Arc::new(Mutex::new(
+            runtime.spawn("backup_schedule_worker", backup_schedule_worker),
+        ));
+
+        let function_warm_up_worker =
+            FunctionWarmUpWorker::new(runtime.clone(), database.clone(), runner.clone());
+        let function_warm_up_worker = Arc::new(Mutex::new(
+            runtime.spawn("function_warm_up_worker", function_warm_up_worker),
+        ));
+
+        let metrics_rollup_worker =
+            MetricsRollupWorker::new(runtime.clone(), database.clone(), function_log.clone());
+        let metrics_rollup_worker = Arc::new(Mutex::new(
+            runtime.spawn("metrics_rollup_worker", metrics_rollup_worker),
+        ));
+
+        let slow_execution_log_worker =
+            SlowExecutionLogWorker::new(runtime.clone(), database.clone(), function_log.clone());
+        let slow_execution_log_worker = Arc::new(Mutex::new(
+            runtime.spawn("slow_execution_log_worker", slow_execution_log_worker),
+        ));
+
+        let usage_meter_worker =
+            UsageMeterWorker::new(runtime.clone(), database.clone(), function_log.clone());
+        let usage_meter_worker = Arc::new(Mutex::new(
+            runtime.spawn("usage_meter_worker", usage_meter_worker),
+        ));
+        let usage_quotas = Arc::new(UsageQuotas::new(
+            runtime.clone(),
+            database.clone(),
+            function_log.clone(),
+        ));
+
+        let pii_scan_worker = PiiScanWorker::new(runtime.clone(), database.clone());
+        let pii_scan_worker = Arc::new(Mutex::new(
+            runtime.spawn("pii_scan_worker", pii_scan_worker),
+        ));
+
+        let document_access_worker = DocumentAccessWorker::new(runtime.clone(), database.clone());
+        let document_access_worker = Arc::new(Mutex::new(
+            runtime.spawn("document_access_worker", document_access_worker),
+        ));
+
+        let archival_worker =
+            ArchivalWorker::new(runtime.clone(), database.clone(), files_storage.clone());
+        let archival_worker = Arc::new(Mutex::new(
+            runtime.spawn("archival_worker", archival_worker),
+        ));
+
+        let materialized_aggregate_worker =
+            MaterializedAggregateWorker::new(runtime.clone(), database.clone());
+        let materialized_aggregate_worker = Arc::new(Mutex::new(runtime.spawn(
+            "materialized_aggregate_worker",
+            materialized_aggregate_worker,
+        )));
+
         let snapshot_import_worker = SnapshotImportWorker::new(
             runtime.clone(),
             database.clone(),
@@ -673,6 +964,14 @@ impl<RT: Runtime> Application<RT> {
             runtime.spawn("snapshot_import_worker", snapshot_import_worker),
         ));
 
+        let replication_worker = replication.map(|config| {
+            let replication_worker =
+                ReplicationWorker::new(runtime.clone(), database.clone(), fetch_client, config);
+            Arc::new(Mutex::new(
+                runtime.spawn("replication_worker", replication_worker),
+            ))
+        });
+
         Ok(Self {
             runtime,
             database,
@@ -696,13 +995,30 @@ impl<RT: Runtime> Application<RT> {
             table_summary_worker,
             schema_worker,
             export_worker,
+            backup_schedule_worker,
+            function_warm_up_worker,
+            metrics_rollup_worker,
+            slow_execution_log_worker,
+            usage_meter_worker,
+            usage_quotas,
+            pii_scan_worker,
+            document_access_worker,
+            archival_worker,
+            materialized_aggregate_worker,
             snapshot_import_worker,
+            replication_worker,
             system_table_cleanup_worker,
+            deleting_tables_cleanup_worker,
+            deleting_tables_cleanup,
+            table_compaction_worker,
             log_sender,
             log_visibility,
             module_cache,
             system_env_var_names: system_env_vars.into_keys().collect(),
             app_auth,
+            counter_tuner: Arc::new(Mutex::new(ShardCountTuner::default())),
+            oidc_providers: Arc::new(OidcProviderCache::default()),
+            convex_origin,
         })
     }
 
@@ -801,6 +1117,42 @@ impl<RT: Runtime> Application<RT> {
             .await
     }
 
+    pub fn issue_replication_token(&self, identity: Identity) -> anyhow::Result<ReplicationToken> {
+        if !identity.is_admin() {
+            anyhow::bail!(unauthorized_error("issue_replication_token"));
+        }
+        Ok(self
+            .key_broker
+            .issue_replication_token(&self.runtime, *REPLICATION_TOKEN_VALIDITY))
+    }
+
+    /// Returns the commits after `cursor` to send to a follower, ending with an
+    /// empty commit at the new cursor, and whether there are more commits to
+    /// read.
+    pub async fn replication_commits(
+        &self,
+        replication_token: &str,
+        cursor: Timestamp,
+    ) -> anyhow::Result<(Vec<ReplicatedCommit>, bool)> {
+        self.key_broker
+            .check_replication_token(&self.runtime, replication_token)?;
+        let deltas = self
+            .database
+            .document_deltas(
+                Identity::system(),
+                Some(cursor),
+                StreamingExportTableFilter {
+                    component_path: Some(ComponentPath::root()),
+                    ..Default::default()
+                },
+                *REPLICATION_STREAM_PAGE_SIZE,
+                *REPLICATION_STREAM_PAGE_SIZE,
+            )
+            .await?;
+        let has_more = deltas.has_more;
+        Ok((ReplicatedCommit::from_document_deltas(deltas), has_more))
+    }
+
     #[minitrace::trace]
     pub async fn list_snapshot(
         &self,
@@ -859,6 +1211,14 @@ impl<RT: Runtime> Application<RT> {
         self.database.vector_search(identity, query).await
     }
 
+    pub async fn hybrid_search(
+        &self,
+        identity: Identity,
+        query: HybridSearch,
+    ) -> anyhow::Result<(Vec<HybridSearchResult>, FunctionUsageStats)> {
+        self.database.hybrid_search(identity, query).await
+    }
+
     pub async fn get_source_code(
         &self,
         identity: Identity,
@@ -895,15 +1255,48 @@ impl<RT: Runtime> Application<RT> {
         Ok(Some(source_map_content.to_owned()))
     }
 
+    /// Source for a typed Rust client for the root component's public
+    /// queries, mutations and actions. See [`rust_client_codegen`].
+    pub async fn generate_rust_client(&self, identity: Identity) -> anyhow::Result<String> {
+        let mut tx = self.begin(identity).await?;
+        let modules = ModuleModel::new(&mut tx)
+            .get_application_metadata(ComponentId::Root)
+            .await?;
+        let mut functions = vec![];
+        for metadata in modules {
+            let metadata = metadata.into_value();
+            let Some(analyze_result) = metadata.analyze_result else {
+                continue;
+            };
+            let module = metadata.path.strip();
+            for function in analyze_result.functions.iter() {
+                if function.visibility != Some(Visibility::Public)
+                    || function.udf_type == UdfType::HttpAction
+                {
+                    continue;
+                }
+                functions.push(RustClientFunction {
+                    module: module.as_str().to_string(),
+                    name: function.name.to_string(),
+                    udf_type: function.udf_type,
+                    args: function.args()?,
+                    returns: function.returns()?,
+                });
+            }
+        }
+        Ok(generate_rust_client(functions))
+    }
+
     pub async fn storage_generate_upload_url(
         &self,
         component: ComponentId,
+        constraints: StoreFileConstraints,
     ) -> anyhow::Result<String> {
         let issued_ts = self.runtime().unix_timestamp();
         let url = self
             .file_storage
             .transactional_file_storage
-            .generate_upload_url(self.key_broker(), issued_ts, component)?;
+            .generate_upload_url(self.key_broker(), issued_ts, component, constraints)?;
 
         Ok(url)
     }
@@ -932,6 +1325,8 @@ impl<RT: Runtime> Application<RT> {
         journal: Option<Option<String>>,
         caller: FunctionCaller,
     ) -> anyhow::Result<RedactedQueryReturn> {
+        identity.ensure_can_run_function(UdfType::Query)?;
+        self.check_usage_quotas(Some(&path)).await?;
         let persistence_version = self.database.persistence_version();
         let block_logging = self
             .log_visibility
@@ -993,6 +1388,69 @@ impl<RT: Runtime> Application<RT> {
         Ok(redacted_query_return)
     }
 
+    /// Runs a query as of a historical `ts` within the retention window, for
+    /// debugging what it returned in the past. Unlike
+    /// [`Self::read_only_udf_at_ts`], `ts` may be older than the snapshots kept
+    /// in memory.
+    pub async fn read_only_udf_at_historical_ts(
+        &self,
+        request_id: RequestId,
+        path: CanonicalizedComponentFunctionPath,
+        args: Vec<JsonValue>,
+        identity: Identity,
+        ts: Timestamp,
+        caller: FunctionCaller,
+    ) -> anyhow::Result<Result<FunctionReturn, FunctionError>> {
+        if !identity.is_admin() {
+            anyhow::bail!(unauthorized_error("read_only_udf_at_historical_ts"));
+        }
+        identity.ensure_can_run_function(UdfType::Query)?;
+        let block_logging = self
+            .log_visibility
+            .should_redact_logs_and_error(
+                &mut self.begin(identity.clone()).await?,
+                identity.clone(),
+                caller.allowed_visibility(),
+            )
+            .await?;
+        let query_return = self
+            .runner
+            .run_query_at_historical_ts(
+                request_id.clone(),
+                PublicFunctionPath::Component(path),
+                args,
+                identity,
+                ts,
+                caller,
+            )
+            .await;
+        match query_return {
+            Ok(query_return) => {
+                let log_lines =
+                    RedactedLogLines::from_log_lines(query_return.log_lines, block_logging);
+                Ok(match query_return.result {
+                    Ok(value) => Ok(FunctionReturn {
+                        value: value.unpack(),
+                        log_lines,
+                    }),
+                    Err(e) => Err(FunctionError {
+                        error: RedactedJsError::from_js_error(e, block_logging, request_id),
+                        log_lines,
+                    }),
+                })
+            },
+            Err(e) if e.is_deterministic_user_error() => Ok(Err(FunctionError {
+                error: RedactedJsError::from_js_error(
+                    JsError::from_error(e),
+                    block_logging,
+                    request_id,
+                ),
+                log_lines: RedactedLogLines::empty(),
+            })),
+            Err(e) => Err(e),
+        }
+    }
+
     #[minitrace::trace]
     pub async fn mutation_udf(
         &self,
@@ -1006,6 +1464,7 @@ impl<RT: Runtime> Application<RT> {
         pause_client: PauseClient,
     ) -> anyhow::Result<Result<RedactedMutationReturn, RedactedMutationError>> {
         identity.ensure_can_run_function(UdfType::Mutation)?;
+        self.check_usage_quotas(Some(&path)).await?;
         let block_logging = self
             .log_visibility
             .should_redact_logs_and_error(
@@ -1059,6 +1518,67 @@ impl<RT: Runtime> Application<RT> {
         Ok(result)
     }
 
+    /// Runs `mutations` in order in a single transaction that commits only if
+    /// all of them succeed.
+    #[minitrace::trace]
+    pub async fn mutation_batch_udf(
+        &self,
+        request_id: RequestId,
+        mutations: Vec<(PublicFunctionPath, Vec<JsonValue>)>,
+        identity: Identity,
+        caller: FunctionCaller,
+    ) -> anyhow::Result<Result<RedactedMutationBatchReturn, RedactedMutationBatchError>> {
+        identity.ensure_can_run_function(UdfType::Mutation)?;
+        for (path, _) in &mutations {
+            self.check_usage_quotas(Some(path)).await?;
+        }
+        let block_logging = self
+            .log_visibility
+            .should_redact_logs_and_error(
+                &mut self.begin(identity.clone()).await?,
+                identity.clone(),
+                caller.allowed_visibility(),
+            )
+            .await?;
+        let result = match self
+            .runner
+            .retry_mutation_batch(request_id.clone(), mutations, identity, caller)
+            .await
+        {
+            Ok(Ok(batch_return)) => Ok(RedactedMutationBatchReturn {
+                results: batch_return
+                    .results
+                    .into_iter()
+                    .map(|mutation_return| RedactedMutationReturn {
+                        value: mutation_return.value,
+                        log_lines: RedactedLogLines::from_log_lines(
+                            mutation_return.log_lines,
+                            block_logging,
+                        ),
+                        ts: mutation_return.ts,
+                    })
+                    .collect(),
+                ts: batch_return.ts,
+            }),
+            Ok(Err(batch_error)) => Err(RedactedMutationBatchError {
+                index: batch_error.index,
+                error: RedactedJsError::from_js_error(batch_error.error, block_logging, request_id),
+                log_lines: RedactedLogLines::from_log_lines(batch_error.log_lines, block_logging),
+            }),
+            Err(e) if e.is_deterministic_user_error() => Err(RedactedMutationBatchError {
+                index: None,
+                error: RedactedJsError::from_js_error(
+                    JsError::from_error(e),
+                    block_logging,
+                    request_id,
+                ),
+                log_lines: RedactedLogLines::empty(),
+            }),
+            Err(e) => anyhow::bail!(e),
+        };
+        Ok(result)
+    }
+
     #[minitrace::trace]
     pub async fn action_udf(
         &self,
@@ -1069,6 +1589,7 @@ impl<RT: Runtime> Application<RT> {
         caller: FunctionCaller,
     ) -> anyhow::Result<Result<RedactedActionReturn, RedactedActionError>> {
         identity.ensure_can_run_function(UdfType::Action)?;
+        self.check_usage_quotas(Some(&name)).await?;
 
         let block_logging = self
             .log_visibility
@@ -1135,6 +1656,7 @@ impl<RT: Runtime> Application<RT> {
         mut response_streamer: HttpActionResponseStreamer,
     ) -> anyhow::Result<()> {
         identity.ensure_can_run_function(UdfType::HttpAction)?;
+        self.check_usage_quotas(None).await?;
         let block_logging = self
             .log_visibility
             .should_redact_logs_and_error(
@@ -1253,7 +1775,10 @@ impl<RT: Runtime> Application<RT> {
                          result, log_lines, ..
                      }| {
                         match result {
-                            Ok(value) => Ok(FunctionReturn { value, log_lines }),
+                            Ok(value) => Ok(FunctionReturn {
+                                value: value.unpack(),
+                                log_lines,
+                            }),
                             Err(error) => Err(FunctionError { error, log_lines }),
                         }
                     },
@@ -1311,6 +1836,41 @@ impl<RT: Runtime> Application<RT> {
         }
     }
 
+    /// Subscribe to the number of documents in `index_range`, and the sum of
+    /// `sum_field` over them. These bypass the deployment's functions, so
+    /// only admins can subscribe.
+    pub async fn subscribe_live_aggregate(
+        &self,
+        identity: Identity,
+        component_path: ComponentPath,
+        index_range: IndexRange,
+        sum_field: Option<FieldPath>,
+    ) -> anyhow::Result<LiveAggregateSubscription> {
+        if !identity.is_admin() && !identity.is_system() {
+            anyhow::bail!(ErrorMetadata::forbidden(
+                "InvalidLiveAggregate",
+                "Only an admin of the deployment can subscribe to live aggregates"
+            ));
+        }
+        let mut tx = self.begin(identity.clone()).await?;
+        let Some((_, component_id)) =
+            BootstrapComponentsModel::new(&mut tx).component_path_to_ids(&component_path)?
+        else {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "ComponentNotFound",
+                format!("Component {component_path} not found")
+            ));
+        };
+        self.database
+            .subscribe_aggregate(
+                identity,
+                TableNamespace::from(component_id),
+                index_range,
+                sum_field,
+            )
+            .await
+    }
+
     pub async fn request_export(
         &self,
         identity: Identity,
@@ -1347,6 +1907,12 @@ impl<RT: Runtime> Application<RT> {
             );
         }
 
+        let key_fingerprint = match &identity {
+            Identity::InstanceAdmin(admin) | Identity::ActingUser(admin, _) => {
+                Some(admin.key_fingerprint())
+            },
+            _ => None,
+        };
         let mut tx = self.begin(identity).await?;
         let mut exports_model = ExportsModel::new(&mut tx);
         let export_requested = exports_model.latest_requested().await?;
@@ -1366,10 +1932,566 @@ impl<RT: Runtime> Application<RT> {
                     )),
             ),
         }?;
+        if let Some(key_fingerprint) = key_fingerprint
+            && let Some(config) = ExportWatermarksModel::new(&mut tx)
+                .config(&key_fingerprint)
+                .await?
+        {
+            let config = config.into_value();
+            let token = self
+                .runtime
+                .rng()
+                .sample_iter(Alphanumeric)
+                .take(32)
+                .map(char::from)
+                .collect();
+            ExportWatermarksModel::new(&mut tx)
+                .insert_watermark(ExportWatermark {
+                    export_id: snapshot_id.into(),
+                    key_fingerprint,
+                    label: config.label,
+                    mode: config.mode,
+                    token,
+                })
+                .await?;
+        }
         self.commit(tx, "request_export").await?;
         Ok(snapshot_id.into())
     }
 
+    pub async fn get_backup_schedule(
+        &self,
+        identity: Identity,
+    ) -> anyhow::Result<Option<BackupSchedule>> {
+        anyhow::ensure!(
+            identity.is_admin() || identity.is_system(),
+            unauthorized_error("get_backup_schedule")
+        );
+        let mut tx = self.begin(identity).await?;
+        Ok(BackupScheduleModel::new(&mut tx)
+            .get()
+            .await?
+            .map(|schedule| schedule.into_value()))
+    }
+
+    /// Sets the schedule `BackupScheduleWorker` requests backups on, or turns
+    /// scheduled backups off if `schedule` is `None`.
+    pub async fn set_backup_schedule(
+        &self,
+        identity: Identity,
+        schedule: Option<BackupSchedule>,
+    ) -> anyhow::Result<()> {
+        let mut tx = self.begin(identity).await?;
+        BackupScheduleModel::new(&mut tx).set(schedule).await?;
+        self.commit(tx, "set_backup_schedule").await?;
+        Ok(())
+    }
+
+    /// The CORS policy the HTTP action router applies, if there is one.
+    pub async fn get_cors_config(&self, identity: Identity) -> anyhow::Result<Option<CorsConfig>> {
+        let mut tx = self.begin(identity).await?;
+        Ok(CorsConfigModel::new(&mut tx)
+            .get()
+            .await?
+            .map(|config| config.into_value()))
+    }
+
+    /// Replaces the CORS policy for HTTP actions, or removes it if `config` is
+    /// `None`.
+    pub async fn set_cors_config(
+        &self,
+        identity: Identity,
+        config: Option<CorsConfig>,
+    ) -> anyhow::Result<()> {
+        let mut tx = self.begin(identity).await?;
+        CorsConfigModel::new(&mut tx).set(config).await?;
+        self.commit(tx, "set_cors_config").await?;
+        Ok(())
+    }
+
+    /// The rate limits applied to public endpoints, if there are any.
+    pub async fn get_rate_limit_config(
+        &self,
+        identity: Identity,
+    ) -> anyhow::Result<Option<RateLimitConfig>> {
+        let mut tx = self.begin(identity).await?;
+        Ok(RateLimitConfigModel::new(&mut tx)
+            .get()
+            .await?
+            .map(|config| config.into_value()))
+    }
+
+    /// Replaces the rate limits applied to public endpoints, or removes them
+    /// if `config` is `None`.
+    pub async fn set_rate_limit_config(
+        &self,
+        identity: Identity,
+        config: Option<RateLimitConfig>,
+    ) -> anyhow::Result<()> {
+        let mut tx = self.begin(identity).await?;
+        RateLimitConfigModel::new(&mut tx).set(config).await?;
+        self.commit(tx, "set_rate_limit_config").await?;
+        Ok(())
+    }
+
+    /// The functions whose timeout or heap limit overrides the defaults.
+    pub async fn list_function_limits(
+        &self,
+        identity: Identity,
+    ) -> anyhow::Result<Vec<FunctionLimits>> {
+        let mut tx = self.begin(identity).await?;
+        Ok(FunctionLimitsModel::new(&mut tx)
+            .list()
+            .await?
+            .into_iter()
+            .map(|limits| limits.into_value())
+            .collect())
+    }
+
+    /// Overrides the timeout and heap limit for `limits.function`. New calls
+    /// pick up the change as soon as it commits.
+    pub async fn set_function_limits(
+        &self,
+        identity: Identity,
+        limits: FunctionLimits,
+    ) -> anyhow::Result<()> {
+        let mut tx = self.begin(identity).await?;
+        FunctionLimitsModel::new(&mut tx).set(limits).await?;
+        self.commit(tx, "set_function_limits").await?;
+        Ok(())
+    }
+
+    /// Removes the overrides for `function`, so it gets the defaults again.
+    pub async fn delete_function_limits(
+        &self,
+        identity: Identity,
+        function: String,
+    ) -> anyhow::Result<()> {
+        let mut tx = self.begin(identity).await?;
+        FunctionLimitsModel::new(&mut tx).delete(&function).await?;
+        self.commit(tx, "delete_function_limits").await?;
+        Ok(())
+    }
+
+    /// The IP and country restrictions applied to public endpoints, if there
+    /// are any.
+    pub async fn get_network_acl_config(
+        &self,
+        identity: Identity,
+    ) -> anyhow::Result<Option<NetworkAclConfig>> {
+        let mut tx = self.begin(identity).await?;
+        Ok(NetworkAclConfigModel::new(&mut tx)
+            .get()
+            .await?
+            .map(|config| config.into_value()))
+    }
+
+    /// Replaces the IP and country restrictions applied to public endpoints,
+    /// or removes them if `config` is `None`.
+    pub async fn set_network_acl_config(
+        &self,
+        identity: Identity,
+        config: Option<NetworkAclConfig>,
+    ) -> anyhow::Result<()> {
+        let mut tx = self.begin(identity).await?;
+        NetworkAclConfigModel::new(&mut tx).set(config).await?;
+        self.commit(tx, "set_network_acl_config").await?;
+        Ok(())
+    }
+
+    pub async fn list_export_watermark_configs(
+        &self,
+        identity: Identity,
+    ) -> anyhow::Result<Vec<ExportWatermarkConfig>> {
+        anyhow::ensure!(
+            identity.is_admin() || identity.is_system(),
+            unauthorized_error("list_export_watermark_configs")
+        );
+        let mut tx = self.begin(identity).await?;
+        ExportWatermarksModel::new(&mut tx).list_configs().await
+    }
+
+    /// Watermarks exports requested with `admin_key` from now on, returning
+    /// the key's fingerprint.
+    pub async fn set_export_watermark_config(
+        &self,
+        identity: Identity,
+        admin_key: &str,
+        label: String,
+        mode: WatermarkMode,
+    ) -> anyhow::Result<String> {
+        let key_fingerprint = match self.key_broker.check_admin_key(admin_key) {
+            Ok(Identity::InstanceAdmin(admin)) => admin.key_fingerprint(),
+            _ => anyhow::bail!(ErrorMetadata::bad_request(
+                "InvalidExportWatermark",
+                "The admin key isn't valid for this deployment",
+            )),
+        };
+        anyhow::ensure!(
+            !label.trim().is_empty() && label.len() <= MAX_EXPORT_WATERMARK_LABEL_LEN,
+            ErrorMetadata::bad_request(
+                "InvalidExportWatermark",
+                format!(
+                    "Watermark labels must be between 1 and {MAX_EXPORT_WATERMARK_LABEL_LEN} \
+                     characters"
+                ),
+            )
+        );
+        let mut tx = self.begin(identity).await?;
+        ExportWatermarksModel::new(&mut tx)
+            .set_config(ExportWatermarkConfig {
+                key_fingerprint: key_fingerprint.clone(),
+                label,
+                mode,
+            })
+            .await?;
+        self.commit(tx, "set_export_watermark_config").await?;
+        Ok(key_fingerprint)
+    }
+
+    /// Stops watermarking exports requested with the key whose fingerprint is
+    /// `key_fingerprint`. Exports that were already watermarked can still be
+    /// traced.
+    pub async fn remove_export_watermark_config(
+        &self,
+        identity: Identity,
+        key_fingerprint: &str,
+    ) -> anyhow::Result<bool> {
+        let mut tx = self.begin(identity).await?;
+        let removed = ExportWatermarksModel::new(&mut tx)
+            .remove_config(key_fingerprint)
+            .await?;
+        self.commit(tx, "remove_export_watermark_config").await?;
+        Ok(removed)
+    }
+
+    /// Finds the export, and so the admin key, that a watermark token found in
+    /// leaked data was given to.
+    pub async fn trace_export_watermark(
+        &self,
+        identity: Identity,
+        token: &str,
+    ) -> anyhow::Result<Option<ExportWatermark>> {
+        anyhow::ensure!(
+            identity.is_admin() || identity.is_system(),
+            unauthorized_error("trace_export_watermark")
+        );
+        let mut tx = self.begin(identity).await?;
+        ExportWatermarksModel::new(&mut tx).trace(token).await
+    }
+
+    /// Records requests the network ACL rejected in the audit log.
+    pub async fn log_network_acl_rejections(
+        &self,
+        events: Vec<DeploymentAuditLogEvent>,
+    ) -> anyhow::Result<()> {
+        let tx = self.begin(Identity::system()).await?;
+        self.commit_with_audit_log_events(tx, events, "log_network_acl_rejections")
+            .await?;
+        Ok(())
+    }
+
+    /// Records an API call made with an admin key in `_audit_log`.
+    pub async fn record_audit_log_entry(&self, entry: AuditLogEntry) -> anyhow::Result<()> {
+        let mut tx = self.begin(Identity::system()).await?;
+        AuditLogModel::new(&mut tx).insert(entry).await?;
+        self.commit(tx, "record_audit_log_entry").await?;
+        Ok(())
+    }
+
+    /// Up to `limit` admin API calls made before `before`, newest first.
+    pub async fn list_audit_log(
+        &self,
+        identity: Identity,
+        before: Option<CreationTime>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<ParsedDocument<AuditLogEntry>>> {
+        anyhow::ensure!(
+            identity.is_admin() || identity.is_system(),
+            unauthorized_error("list_audit_log")
+        );
+        let mut tx = self.begin(identity).await?;
+        AuditLogModel::new(&mut tx).list(before, limit).await
+    }
+
+    /// Issues an admin key with `scope` and records it in `_admin_keys`, so
+    /// it can be listed and revoked. Returns the key and its fingerprint.
+    pub async fn issue_admin_key(
+        &self,
+        identity: Identity,
+        name: String,
+        member_id: MemberId,
+        scope: AdminKeyScope,
+    ) -> anyhow::Result<(AdminKey, String)> {
+        anyhow::ensure!(
+            identity.is_admin() || identity.is_system(),
+            unauthorized_error("issue_admin_key")
+        );
+        let key = self.key_broker.issue_scoped_admin_key(member_id, scope);
+        let key_fingerprint = admin_key_fingerprint(key.as_str());
+        let mut tx = self.begin(identity).await?;
+        AdminKeysModel::new(&mut tx)
+            .record_issued(AdminKeyMetadata {
+                key_fingerprint: key_fingerprint.clone(),
+                name: Some(name),
+                member_id: Some(member_id),
+                scope: Some(scope),
+                created_at: Some(self.runtime.unix_timestamp()),
+                revoked_at: None,
+            })
+            .await?;
+        self.commit(tx, "issue_admin_key").await?;
+        Ok((key, key_fingerprint))
+    }
+
+    pub async fn list_admin_keys(
+        &self,
+        identity: Identity,
+    ) -> anyhow::Result<Vec<AdminKeyMetadata>> {
+        let mut tx = self.begin(identity).await?;
+        AdminKeysModel::new(&mut tx).list().await
+    }
+
+    /// Stops accepting the admin key with fingerprint `key_fingerprint`.
+    /// Returns whether it wasn't already revoked.
+    pub async fn revoke_admin_key(
+        &self,
+        identity: Identity,
+        key_fingerprint: String,
+    ) -> anyhow::Result<bool> {
+        let mut tx = self.begin(identity).await?;
+        let revoked = AdminKeysModel::new(&mut tx)
+            .revoke(key_fingerprint, self.runtime.unix_timestamp())
+            .await?;
+        self.commit(tx, "revoke_admin_key").await?;
+        self.load_admin_keys().await?;
+        Ok(revoked)
+    }
+
+    /// Starts encrypting admin keys with a new secret. Keys encrypted with the
+    /// previous one keep working for `grace_period`.
+    pub async fn rotate_admin_key_secret(
+        &self,
+        identity: Identity,
+        grace_period: Duration,
+    ) -> anyhow::Result<()> {
+        let now = self.runtime.unix_timestamp();
+        let mut tx = self.begin(identity).await?;
+        let mut model = AdminKeysModel::new(&mut tx);
+        let (previous, mut retired) = match model.secrets().await? {
+            Some(secrets) => {
+                let AdminKeySecrets { current, retired } = secrets.into_value();
+                (Some(current), retired)
+            },
+            None => (None, vec![]),
+        };
+        retired.retain(|retired| retired.valid_until > now);
+        retired.push(RetiredAdminKeySecretMetadata {
+            secret: previous,
+            valid_until: now + grace_period,
+        });
+        let current = self
+            .key_broker
+            .encrypt_admin_key_secret(&InstanceSecret::random());
+        model
+            .set_secrets(AdminKeySecrets { current, retired })
+            .await?;
+        self.commit(tx, "rotate_admin_key_secret").await?;
+        self.load_admin_keys().await
+    }
+
+    /// Loads the admin key secrets and revocation list into the key broker.
+    pub async fn load_admin_keys(&self) -> anyhow::Result<()> {
+        let mut tx = self.begin(Identity::system()).await?;
+        let mut model = AdminKeysModel::new(&mut tx);
+        let revoked = model.revoked_fingerprints().await?;
+        let (current, retired) = match model.secrets().await? {
+            Some(secrets) => {
+                let AdminKeySecrets { current, retired } = secrets.into_value();
+                let current = self.key_broker.decrypt_admin_key_secret(&current)?;
+                let retired = retired
+                    .into_iter()
+                    .map(|retired| {
+                        Ok(RetiredAdminKeySecret {
+                            secret: retired
+                                .secret
+                                .map(|secret| self.key_broker.decrypt_admin_key_secret(&secret))
+                                .transpose()?,
+                            valid_until: retired.valid_until.as_system_time(),
+                        })
+                    })
+                    .collect::<anyhow::Result<_>>()?;
+                (Some(current), retired)
+            },
+            None => (None, vec![]),
+        };
+        self.key_broker.set_admin_key_secrets(current, retired)?;
+        self.key_broker.set_revoked_admin_keys(revoked);
+        Ok(())
+    }
+
+    pub async fn list_custom_domains(
+        &self,
+        identity: Identity,
+    ) -> anyhow::Result<Vec<ParsedDocument<CustomDomain>>> {
+        anyhow::ensure!(
+            identity.is_admin() || identity.is_system(),
+            unauthorized_error("list_custom_domains")
+        );
+        let mut tx = self.begin(identity).await?;
+        CustomDomainsModel::new(&mut tx).list().await
+    }
+
+    /// Registers a domain to serve the deployment on. It's served once the
+    /// ACME worker has issued a certificate for it, which requires its DNS to
+    /// point at this backend.
+    pub async fn register_custom_domain(
+        &self,
+        identity: Identity,
+        domain: &str,
+        target: CustomDomainTarget,
+    ) -> anyhow::Result<CustomDomain> {
+        let domain = CustomDomain::new(domain, target)?;
+        let mut tx = self.begin(identity).await?;
+        CustomDomainsModel::new(&mut tx)
+            .register(domain.clone())
+            .await?;
+        self.commit(tx, "register_custom_domain").await?;
+        Ok(domain)
+    }
+
+    pub async fn remove_custom_domain(
+        &self,
+        identity: Identity,
+        domain: &str,
+    ) -> anyhow::Result<()> {
+        let mut tx = self.begin(identity).await?;
+        CustomDomainsModel::new(&mut tx)
+            .remove(&domain.to_ascii_lowercase())
+            .await?;
+        self.commit(tx, "remove_custom_domain").await?;
+        Ok(())
+    }
+
+    /// Stores the outcome of issuing a certificate for a domain, unless the
+    /// domain was removed or re-registered since `id` was read.
+    pub async fn update_custom_domain(
+        &self,
+        id: ResolvedDocumentId,
+        domain: CustomDomain,
+    ) -> anyhow::Result<()> {
+        let mut tx = self.begin(Identity::system()).await?;
+        let current = CustomDomainsModel::new(&mut tx).get(&domain.domain).await?;
+        if current.map(|existing| existing.id()) != Some(id) {
+            return Ok(());
+        }
+        CustomDomainsModel::new(&mut tx).update(id, domain).await?;
+        self.commit(tx, "update_custom_domain").await?;
+        Ok(())
+    }
+
+    /// Completed scheduled backups, newest first.
+    pub async fn list_scheduled_backups(
+        &self,
+        identity: Identity,
+    ) -> anyhow::Result<Vec<ParsedDocument<Export>>> {
+        anyhow::ensure!(
+            identity.is_admin() || identity.is_system(),
+            unauthorized_error("list_scheduled_backups")
+        );
+        let mut tx = self.begin(identity).await?;
+        ExportsModel::new(&mut tx)
+            .list_completed(ExportRequestor::ScheduledBackup)
+            .await
+    }
+
+    /// Deletes a completed or failed export along with its zip file.
+    pub async fn delete_export(
+        &self,
+        identity: Identity,
+        id: DeveloperDocumentId,
+    ) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            identity.is_admin() || identity.is_system(),
+            unauthorized_error("delete_export")
+        );
+        let mut tx = self.begin(identity).await?;
+        let object_key = ExportsModel::new(&mut tx).delete(id).await?;
+        self.commit(tx, "delete_export").await?;
+        if let Some(object_key) = object_key {
+            self.exports_storage.delete_object(&object_key).await?;
+        }
+        Ok(())
+    }
+
+    /// Imports, exports, table compactions and in-flight index builds, newest
+    /// first.
+    pub async fn list_operations(
+        &self,
+        identity: Identity,
+        kind: Option<OperationKind>,
+    ) -> anyhow::Result<Vec<Operation>> {
+        anyhow::ensure!(
+            identity.is_admin() || identity.is_system(),
+            unauthorized_error("list_operations")
+        );
+        let mut tx = self.begin(identity).await?;
+        OperationsModel::new(&mut tx).list(kind).await
+    }
+
+    pub async fn get_operation(
+        &self,
+        identity: Identity,
+        id: DeveloperDocumentId,
+    ) -> anyhow::Result<Option<Operation>> {
+        anyhow::ensure!(
+            identity.is_admin() || identity.is_system(),
+            unauthorized_error("get_operation")
+        );
+        let mut tx = self.begin(identity).await?;
+        OperationsModel::new(&mut tx).get(id).await
+    }
+
+    /// Returns the operation once it differs from `last`, waiting for it to
+    /// change if it hasn't yet. Returns `None` if the operation doesn't exist
+    /// (anymore).
+    pub async fn wait_for_operation_change(
+        &self,
+        identity: Identity,
+        id: DeveloperDocumentId,
+        last: Option<&Operation>,
+    ) -> anyhow::Result<Option<Operation>> {
+        anyhow::ensure!(
+            identity.is_admin() || identity.is_system(),
+            unauthorized_error("wait_for_operation_change")
+        );
+        loop {
+            let mut tx = self.begin(identity.clone()).await?;
+            let operation = OperationsModel::new(&mut tx).get(id).await?;
+            if operation.is_none() || operation.as_ref() != last {
+                return Ok(operation);
+            }
+            let token = tx.into_token()?;
+            let subscription = self.subscribe(token).await?;
+            subscription.wait_for_invalidation().await;
+        }
+    }
+
+    pub async fn cancel_operation(
+        &self,
+        identity: Identity,
+        id: DeveloperDocumentId,
+    ) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            identity.is_admin() || identity.is_system(),
+            unauthorized_error("cancel_operation")
+        );
+        let mut tx = self.begin(identity).await?;
+        OperationsModel::new(&mut tx).cancel(id).await?;
+        self.commit(tx, "cancel_operation").await?;
+        Ok(())
+    }
+
     pub async fn get_zip_export(
         &self,
         identity: Identity,
@@ -2027,6 +3149,7 @@ impl<RT: Runtime> Application<RT> {
         format: ImportFormat,
         mode: ImportMode,
         component_path: ComponentPath,
+        table_selection: ImportTableSelection,
         upload_token: ClientDrivenUploadToken,
         part_tokens: Vec<ClientDrivenUploadPartToken>,
     ) -> anyhow::Result<DeveloperDocumentId> {
@@ -2048,6 +3171,7 @@ impl<RT: Runtime> Application<RT> {
             component_path,
             object_key,
             ImportRequestor::SnapshotImport,
+            table_selection,
         )
         .await
     }
@@ -2131,6 +3255,17 @@ impl<RT: Runtime> Application<RT> {
         clear_tables(self, identity, table_names).await
     }
 
+    // Restore user tables, or just `table`, to their state at `ts`. Returns the
+    // number of documents restored.
+    pub async fn restore_tables(
+        &self,
+        identity: &Identity,
+        ts: Timestamp,
+        table: Option<(ComponentPath, TableName)>,
+    ) -> anyhow::Result<u64> {
+        restore_tables(self, identity, ts, table).await
+    }
+
     pub async fn execute_standalone_module(
         &self,
         request_id: RequestId,
@@ -2140,6 +3275,8 @@ impl<RT: Runtime> Application<RT> {
         caller: FunctionCaller,
         component: ComponentId,
     ) -> anyhow::Result<Result<FunctionReturn, FunctionError>> {
+        // Only queries can be run this way, but they can read any data.
+        identity.ensure_can_run_function(UdfType::Query)?;
         let block_logging = self
             .log_visibility
             .should_redact_logs_and_error(
@@ -2365,6 +3502,26 @@ impl<RT: Runtime> Application<RT> {
         Ok(count)
     }
 
+    pub fn deleting_tables_cleanup(&self) -> &DeletingTablesCleanupClient {
+        &self.deleting_tables_cleanup
+    }
+
+    /// Requests a compaction of the table, returning the ID of the operation
+    /// tracking its progress.
+    pub async fn compact_table(
+        &self,
+        identity: &Identity,
+        table_name: TableName,
+        table_namespace: TableNamespace,
+    ) -> anyhow::Result<DeveloperDocumentId> {
+        let mut tx = self.begin(identity.clone()).await?;
+        let id = TableCompactionModel::new(&mut tx)
+            .request(table_namespace, table_name)
+            .await?;
+        self.commit(tx, "compact_table").await?;
+        Ok(id.into())
+    }
+
     pub async fn delete_component(
         &self,
         identity: &Identity,
@@ -2436,19 +3593,25 @@ impl<RT: Runtime> Application<RT> {
         content_length: Option<ContentLength>,
         content_type: Option<ContentType>,
         expected_sha256: Option<Sha256Digest>,
+        filename: Option<String>,
+        tags: BTreeMap<String, String>,
         body: BoxStream<'_, anyhow::Result<Bytes>>,
     ) -> anyhow::Result<DeveloperDocumentId> {
         self.bail_if_not_running().await?;
+        if let Some(filename) = &filename {
+            validate_file_storage_filename(filename)?;
+        }
+        validate_file_storage_tags(&tags)?;
+        let mut entry = self
+            .file_storage
+            .transactional_file_storage
+            .upload_file(content_length, content_type, body, expected_sha256)
+            .await?;
+        entry.filename = filename;
+        entry.tags = tags;
         let storage_id = self
             .file_storage
-            .store_file(
-                component.into(),
-                content_length,
-                content_type,
-                body,
-                expected_sha256,
-                &self.usage_tracking,
-            )
+            .store_entry(component.into(), entry, &self.usage_tracking)
             .await?;
         Ok(storage_id)
     }
@@ -2527,8 +3690,8 @@ impl<RT: Runtime> Application<RT> {
         &self,
         component: ComponentId,
         storage_id: FileStorageId,
-        bytes_range: (Bound<u64>, Bound<u64>),
-    ) -> anyhow::Result<FileRangeStream> {
+        range: Range,
+    ) -> anyhow::Result<FileRange> {
         self.bail_if_not_running().await?;
         let mut file_storage_tx = self.begin(Identity::system()).await?;
 
@@ -2560,7 +3723,7 @@ impl<RT: Runtime> Application<RT> {
             .get_file_range_stream(
                 component_path,
                 file_entry,
-                bytes_range,
+                range,
                 self.usage_tracking.clone(),
             )
             .await
@@ -2591,6 +3754,14 @@ impl<RT: Runtime> Application<RT> {
                     None => admin_identity,
                 }
             },
+            AuthenticationToken::User(key) if is_api_key(&key) => {
+                Identity::user(self.authenticate_api_key(key, system_time).await?)
+            },
+            AuthenticationToken::User(id_token)
+                if self.app_auth().is_user_session_token(&id_token) =>
+            {
+                Identity::user(self.authenticate_session_token(id_token).await?)
+            },
             AuthenticationToken::User(id_token) => {
                 let mut tx = self.begin(Identity::system()).await?;
                 let auth_infos = AuthInfoModel::new(&mut tx).get().await?;
@@ -2602,6 +3773,7 @@ impl<RT: Runtime> Application<RT> {
                         .into_iter()
                         .map(|auth_info| auth_info.into_value())
                         .collect(),
+                    &self.oidc_providers,
                     system_time,
                 )
                 .await?;
@@ -2612,6 +3784,33 @@ impl<RT: Runtime> Application<RT> {
         Ok(identity)
     }
 
+    /// Reports what's cached for each auth provider in the auth config,
+    /// fetching the keys of any that haven't been used yet.
+    pub async fn auth_provider_status(
+        &self,
+        identity: Identity,
+    ) -> anyhow::Result<Vec<OidcProviderStatus>> {
+        anyhow::ensure!(
+            identity.is_admin() || identity.is_system(),
+            unauthorized_error("auth_provider_status")
+        );
+        let mut tx = self.begin(identity).await?;
+        let auth_infos = AuthInfoModel::new(&mut tx)
+            .get()
+            .await?
+            .into_iter()
+            .map(|auth_info| auth_info.into_value())
+            .collect();
+        Ok(self
+            .oidc_providers
+            .status(
+                auth_infos,
+                cached_http_client_for(ClientPurpose::ProviderMetadata),
+                self.runtime.system_time(),
+            )
+            .await)
+    }
+
     pub async fn validate_component_id(
         &self,
         identity: Identity,
@@ -2710,12 +3909,15 @@ impl<RT: Runtime> Application<RT> {
         Ok(self.function_log.stream_parts(cursor).await)
     }
 
+    /// Cancels every unfinished job in the component matching `filter`, in
+    /// batches, and returns how many were canceled.
     pub async fn cancel_all_jobs(
         &self,
         component_id: ComponentId,
-        path: Option<CanonicalizedComponentFunctionPath>,
+        filter: ScheduledJobFilter,
         identity: Identity,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<usize> {
+        let mut total = 0;
         loop {
             let count = self
                 .execute_with_audit_log_events_and_occ_retries(
@@ -2725,32 +3927,52 @@ impl<RT: Runtime> Application<RT> {
                         Self::_cancel_all_jobs(
                             tx,
                             component_id,
-                            path.clone(),
+                            filter.clone(),
                             *MAX_JOBS_CANCEL_BATCH,
                         )
                         .into()
                     },
                 )
                 .await?;
+            total += count;
             if count < *MAX_JOBS_CANCEL_BATCH {
                 break;
             }
         }
-        Ok(())
+        Ok(total)
     }
 
     async fn _cancel_all_jobs(
         tx: &mut Transaction<RT>,
         component_id: ComponentId,
-        path: Option<CanonicalizedComponentFunctionPath>,
+        filter: ScheduledJobFilter,
         max_jobs: usize,
     ) -> anyhow::Result<(usize, Vec<DeploymentAuditLogEvent>)> {
         let count = SchedulerModel::new(tx, component_id.into())
-            .cancel_all(path, max_jobs)
+            .cancel_matching(filter, max_jobs)
             .await?;
         Ok((count, vec![]))
     }
 
+    /// A page of the unfinished jobs in the component matching `filter`, in
+    /// the order they next run.
+    pub async fn list_scheduled_jobs_page(
+        &self,
+        identity: Identity,
+        component_id: ComponentId,
+        filter: ScheduledJobFilter,
+        cursor: Option<ScheduledJobsCursor>,
+        limit: usize,
+    ) -> anyhow::Result<(
+        Vec<ParsedDocument<ScheduledJob>>,
+        Option<ScheduledJobsCursor>,
+    )> {
+        let mut tx = self.begin(identity).await?;
+        SchedulerModel::new(&mut tx, component_id.into())
+            .list_matching(filter, cursor, limit)
+            .await
+    }
+
     /// Commit a transaction and send audit log events to the log manager if the
     /// transaction commits successfully.
     pub async fn commit_with_audit_log_events(
@@ -2960,7 +4182,21 @@ impl<RT: Runtime> Application<RT> {
         self.search_and_vector_bootstrap_worker.lock().shutdown();
         self.fast_forward_worker.lock().shutdown();
         self.export_worker.lock().shutdown();
+        self.backup_schedule_worker.lock().shutdown();
+        self.function_warm_up_worker.lock().shutdown();
+        self.metrics_rollup_worker.lock().shutdown();
+        self.slow_execution_log_worker.lock().shutdown();
+        self.usage_meter_worker.lock().shutdown();
+        self.pii_scan_worker.lock().shutdown();
+        self.document_access_worker.lock().shutdown();
+        self.archival_worker.lock().shutdown();
+        self.materialized_aggregate_worker.lock().shutdown();
         self.snapshot_import_worker.lock().shutdown();
+        if let Some(replication_worker) = &self.replication_worker {
+            replication_worker.lock().shutdown();
+        }
+        self.deleting_tables_cleanup_worker.lock().shutdown();
+        self.table_compaction_worker.lock().shutdown();
         self.runner.shutdown().await?;
         self.scheduled_job_runner.shutdown();
         self.cron_job_executor.lock().shutdown();
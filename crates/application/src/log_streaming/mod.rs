@@ -0,0 +1,401 @@
+//! Streams function logs and system events to the deployment's log sinks.
+//!
+//! The function log and deployment audit log send events to the
+//! [`LogManagerClient`], and the [`LogManager`] fans them out to a worker for
+//! each sink configured in `_log_sinks`. Sink workers send events in batches,
+//! retrying failed batches with backoff, and record on the sink whether its
+//! last batch was delivered.
+//!
+//! Producing logs never waits on a sink: when the manager or a sink falls
+//! behind, its buffer fills up and new events for it are dropped.
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::Duration,
+};
+
+use common::{
+    backoff::Backoff,
+    errors::report_error,
+    http::fetch::FetchClient,
+    knobs::{
+        ENABLE_LOG_STREAMING,
+        LOG_MANAGER_AGGREGATION_INTERVAL_MILLIS,
+        LOG_MANAGER_EVENT_RECV_BUFFER_SIZE,
+        LOG_SINK_BUFFER_SIZE,
+        LOG_SINK_MAX_ATTEMPTS,
+        LOG_SINK_MAX_BATCH_SIZE,
+    },
+    log_streaming::{
+        LogEvent,
+        LogSender,
+        NoopLogSender,
+        StructuredLogEvent,
+    },
+    runtime::{
+        Runtime,
+        SpawnHandle,
+    },
+};
+use database::{
+    unauthorized_error,
+    Database,
+};
+use futures::{
+    pin_mut,
+    select_biased,
+    FutureExt,
+};
+use keybroker::Identity;
+use model::log_sinks::{
+    types::{
+        LogSink,
+        SinkConfig,
+        SinkStatus,
+        SinkType,
+    },
+    LogSinksModel,
+};
+use parking_lot::Mutex;
+use tokio::sync::mpsc;
+
+use self::sinks::{
+    validate_sink_config,
+    SinkClient,
+};
+use crate::{
+    metrics::{
+        log_streaming_events_dropped,
+        log_streaming_events_sent,
+    },
+    Application,
+};
+
+pub mod sinks;
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// How long a sink has to accept a batch before the attempt fails.
+const SINK_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The [`LogSender`] for a running [`LogManager`].
+pub struct LogManagerClient {
+    sender: mpsc::Sender<LogEvent>,
+    handle: Mutex<Box<dyn SpawnHandle>>,
+}
+
+impl LogSender for LogManagerClient {
+    fn send_logs(&self, logs: Vec<LogEvent>) {
+        for log in logs {
+            if self.sender.try_send(log).is_err() {
+                log_streaming_events_dropped("manager", 1);
+            }
+        }
+    }
+
+    fn shutdown(&self) -> anyhow::Result<()> {
+        self.handle.lock().shutdown();
+        Ok(())
+    }
+}
+
+struct SinkHandle {
+    config: SinkConfig,
+    sender: mpsc::Sender<LogEvent>,
+    handle: Box<dyn SpawnHandle>,
+}
+
+impl Drop for SinkHandle {
+    fn drop(&mut self) {
+        self.handle.shutdown();
+    }
+}
+
+pub struct LogManager<RT: Runtime> {
+    runtime: RT,
+    database: Database<RT>,
+    fetch_client: Arc<dyn FetchClient>,
+    deployment_name: String,
+    events: mpsc::Receiver<LogEvent>,
+    sinks: BTreeMap<SinkType, SinkHandle>,
+}
+
+impl<RT: Runtime> LogManager<RT> {
+    /// Starts streaming logs sent to the returned [`LogSender`] to the
+    /// deployment's sinks, unless log streaming is turned off.
+    pub fn start(
+        runtime: RT,
+        database: Database<RT>,
+        fetch_client: Arc<dyn FetchClient>,
+        deployment_name: String,
+    ) -> Arc<dyn LogSender> {
+        if !*ENABLE_LOG_STREAMING {
+            return Arc::new(NoopLogSender);
+        }
+        let (sender, events) = mpsc::channel(*LOG_MANAGER_EVENT_RECV_BUFFER_SIZE);
+        let mut manager = Self {
+            runtime: runtime.clone(),
+            database,
+            fetch_client,
+            deployment_name,
+            events,
+            sinks: BTreeMap::new(),
+        };
+        let handle = runtime.spawn("log_manager", async move {
+            let mut backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
+            while let Err(mut e) = manager.run(&mut backoff).await {
+                report_error(&mut e);
+                let delay = backoff.fail(&mut manager.runtime.rng());
+                tracing::error!("LogManager failed, sleeping {delay:?}");
+                manager.runtime.wait(delay).await;
+            }
+        });
+        Arc::new(LogManagerClient {
+            sender,
+            handle: Mutex::new(handle),
+        })
+    }
+
+    async fn run(&mut self, backoff: &mut Backoff) -> anyhow::Result<()> {
+        tracing::info!("Starting LogManager");
+        loop {
+            let mut tx = self.database.begin(Identity::system()).await?;
+            let configs = LogSinksModel::new(&mut tx)
+                .list()
+                .await?
+                .into_iter()
+                .map(|sink| sink.into_value().config)
+                .collect();
+            self.update_sinks(configs);
+            let token = tx.into_token()?;
+            let subscription = self.database.subscribe(token).await?;
+            backoff.reset();
+
+            let invalidated = subscription.wait_for_invalidation().fuse();
+            pin_mut!(invalidated);
+            loop {
+                let event = select_biased! {
+                    _ = invalidated => break,
+                    event = self.events.recv().fuse() => event,
+                };
+                let Some(event) = event else {
+                    return Ok(());
+                };
+                self.fan_out(event);
+            }
+        }
+    }
+
+    /// Starts workers for new or reconfigured sinks, and stops the workers for
+    /// sinks that were reconfigured or removed.
+    fn update_sinks(&mut self, configs: Vec<SinkConfig>) {
+        let configs: BTreeMap<_, _> = configs
+            .into_iter()
+            .map(|config| (config.sink_type(), config))
+            .collect();
+        self.sinks
+            .retain(|sink_type, sink| configs.get(sink_type) == Some(&sink.config));
+        for (sink_type, config) in configs {
+            if self.sinks.contains_key(&sink_type) {
+                continue;
+            }
+            let client = match SinkClient::new(
+                config.clone(),
+                self.deployment_name.clone(),
+                self.fetch_client.clone(),
+            ) {
+                Ok(client) => client,
+                Err(e) => {
+                    tracing::error!("Not streaming logs to invalid {sink_type} sink: {e:#}");
+                    continue;
+                },
+            };
+            let (sender, events) = mpsc::channel(*LOG_SINK_BUFFER_SIZE);
+            let worker = SinkWorker {
+                runtime: self.runtime.clone(),
+                database: self.database.clone(),
+                config: config.clone(),
+                client,
+                events,
+                status: None,
+            };
+            tracing::info!("Streaming logs to {sink_type} sink");
+            let handle = self.runtime.spawn("log_sink_worker", worker.run());
+            self.sinks.insert(
+                sink_type,
+                SinkHandle {
+                    config,
+                    sender,
+                    handle,
+                },
+            );
+        }
+    }
+
+    fn fan_out(&self, event: LogEvent) {
+        // Exceptions are for error trackers, and their function execution
+        // records already include the error.
+        if matches!(event.event, StructuredLogEvent::Exception { .. }) {
+            return;
+        }
+        for (sink_type, sink) in &self.sinks {
+            if sink.sender.try_send(event.clone()).is_err() {
+                log_streaming_events_dropped(sink_type.as_str(), 1);
+            }
+        }
+    }
+}
+
+struct SinkWorker<RT: Runtime> {
+    runtime: RT,
+    database: Database<RT>,
+    config: SinkConfig,
+    client: SinkClient,
+    events: mpsc::Receiver<LogEvent>,
+    /// The status last recorded on the sink by this worker.
+    status: Option<SinkStatus>,
+}
+
+impl<RT: Runtime> SinkWorker<RT> {
+    async fn run(mut self) {
+        // Start with a verification event, so a misconfigured sink is marked as
+        // failed without waiting for logs.
+        let mut batch = match LogEvent::default_for_verification(&self.runtime) {
+            Ok(event) => vec![event],
+            Err(mut e) => {
+                report_error(&mut e);
+                vec![]
+            },
+        };
+        loop {
+            if !batch.is_empty() {
+                self.send_batch(batch).await;
+            }
+            batch = match self.next_batch().await {
+                Some(batch) => batch,
+                None => return,
+            };
+        }
+    }
+
+    /// Waits for an event, then collects the events that arrive over the next
+    /// aggregation interval, up to the max batch size.
+    async fn next_batch(&mut self) -> Option<Vec<LogEvent>> {
+        let mut batch = vec![self.events.recv().await?];
+        let mut deadline = self.runtime.wait(Duration::from_millis(
+            *LOG_MANAGER_AGGREGATION_INTERVAL_MILLIS,
+        ));
+        while batch.len() < *LOG_SINK_MAX_BATCH_SIZE {
+            select_biased! {
+                event = self.events.recv().fuse() => match event {
+                    Some(event) => batch.push(event),
+                    None => break,
+                },
+                _ = deadline => break,
+            }
+        }
+        Some(batch)
+    }
+
+    async fn send_batch(&mut self, batch: Vec<LogEvent>) {
+        let sink_type = self.config.sink_type();
+        let mut backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
+        let status = loop {
+            let result = select_biased! {
+                result = self.client.send(&batch).fuse() => result,
+                _ = self.runtime.wait(SINK_REQUEST_TIMEOUT) => {
+                    Err(anyhow::anyhow!("Timed out after {SINK_REQUEST_TIMEOUT:?}"))
+                },
+            };
+            match result {
+                Ok(()) => {
+                    log_streaming_events_sent(sink_type.as_str(), batch.len());
+                    break SinkStatus::Active;
+                },
+                Err(e) if backoff.failures() + 1 < *LOG_SINK_MAX_ATTEMPTS => {
+                    let delay = backoff.fail(&mut self.runtime.rng());
+                    tracing::warn!(
+                        "Failed to send logs to {sink_type} sink, retrying in {delay:?}: {e:#}"
+                    );
+                    self.runtime.wait(delay).await;
+                },
+                Err(e) => {
+                    tracing::error!(
+                        "Dropping {} events after failing to send them to {sink_type} sink: {e:#}",
+                        batch.len()
+                    );
+                    log_streaming_events_dropped(sink_type.as_str(), batch.len());
+                    break SinkStatus::Failed {
+                        reason: format!("{e:#}"),
+                    };
+                },
+            }
+        };
+        if self.status.as_ref() != Some(&status) {
+            if let Err(mut e) = self.set_status(status.clone()).await {
+                report_error(&mut e);
+                return;
+            }
+            self.status = Some(status);
+        }
+    }
+
+    async fn set_status(&self, status: SinkStatus) -> anyhow::Result<()> {
+        let mut tx = self.database.begin(Identity::system()).await?;
+        LogSinksModel::new(&mut tx)
+            .set_status(&self.config, status)
+            .await?;
+        self.database
+            .commit_with_write_source(tx, "log_sink_status")
+            .await?;
+        Ok(())
+    }
+}
+
+impl<RT: Runtime> Application<RT> {
+    /// The deployment's log sinks, with their credentials redacted.
+    pub async fn list_log_sinks(&self, identity: Identity) -> anyhow::Result<Vec<LogSink>> {
+        let mut tx = self.begin(identity).await?;
+        Ok(LogSinksModel::new(&mut tx)
+            .list()
+            .await?
+            .into_iter()
+            .map(|sink| {
+                let sink = sink.into_value();
+                LogSink {
+                    config: sink.config.redacted(),
+                    status: sink.status,
+                }
+            })
+            .collect())
+    }
+
+    /// Starts streaming logs to the sink, replacing any sink of the same type.
+    pub async fn set_log_sink(&self, identity: Identity, config: SinkConfig) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            identity.is_admin() || identity.is_system(),
+            unauthorized_error("set_log_sink")
+        );
+        validate_sink_config(&config)?;
+        let mut tx = self.begin(identity).await?;
+        LogSinksModel::new(&mut tx).set(config).await?;
+        self.commit(tx, "set_log_sink").await?;
+        Ok(())
+    }
+
+    /// Stops streaming logs to the sink of type `sink_type`. Returns whether
+    /// there was one.
+    pub async fn remove_log_sink(
+        &self,
+        identity: Identity,
+        sink_type: SinkType,
+    ) -> anyhow::Result<bool> {
+        let mut tx = self.begin(identity).await?;
+        let removed = LogSinksModel::new(&mut tx).remove(sink_type).await?;
+        if removed {
+            self.commit(tx, "remove_log_sink").await?;
+        }
+        Ok(removed)
+    }
+}
@@ -0,0 +1,435 @@
+//! Formats batches of log events for each kind of sink and sends them.
+//!
+//! Events are formatted as in [`LogEventFormatVersion::V2`], with the fields
+//! each service expects added.
+use std::sync::Arc;
+
+use chrono::{
+    DateTime,
+    SecondsFormat,
+    Utc,
+};
+use common::{
+    http::{
+        fetch::{
+            FetchClient,
+            InternalFetchPurpose,
+        },
+        HttpRequest,
+    },
+    log_lines::LogLevel,
+    log_streaming::{
+        LogEvent,
+        LogEventFormatVersion,
+        StructuredLogEvent,
+    },
+    runtime::UnixTimestamp,
+};
+use errors::ErrorMetadata;
+use http::{
+    header::{
+        AUTHORIZATION,
+        CONTENT_TYPE,
+    },
+    HeaderMap,
+    HeaderValue,
+    Method,
+};
+use model::log_sinks::types::{
+    AxiomConfig,
+    DatadogConfig,
+    SinkConfig,
+    SyslogConfig,
+    SyslogProtocol,
+    WebhookConfig,
+};
+use serde_json::{
+    json,
+    Value as JsonValue,
+};
+use tokio::{
+    io::AsyncWriteExt,
+    net::{
+        TcpStream,
+        UdpSocket,
+    },
+};
+use url::Url;
+
+const AXIOM_API_URL: &str = "https://api.axiom.co";
+
+/// The syslog facility for user-level messages.
+const SYSLOG_FACILITY_USER: u8 = 1;
+
+fn invalid_sink(msg: String) -> anyhow::Error {
+    ErrorMetadata::bad_request("InvalidLogSink", msg).into()
+}
+
+/// Checks the config is complete enough to try sending logs with it.
+pub fn validate_sink_config(config: &SinkConfig) -> anyhow::Result<()> {
+    match config {
+        SinkConfig::Datadog(config) => {
+            if config.api_key.is_empty() {
+                return Err(invalid_sink("Datadog sinks need an API key".to_string()));
+            }
+            datadog_url(&config.site)?;
+        },
+        SinkConfig::Axiom(config) => {
+            if config.api_token.is_empty() || config.dataset_name.is_empty() {
+                return Err(invalid_sink(
+                    "Axiom sinks need an API token and dataset name".to_string(),
+                ));
+            }
+        },
+        SinkConfig::Webhook(config) => {
+            webhook_url(&config.url)?;
+        },
+        SinkConfig::Syslog(config) => {
+            let port = config.address.rsplit_once(':').map(|(_, port)| port);
+            if port.map_or(true, |port| port.parse::<u16>().is_err()) {
+                return Err(invalid_sink(format!(
+                    "Syslog address {} should be a host:port",
+                    config.address
+                )));
+            }
+        },
+    }
+    Ok(())
+}
+
+fn datadog_url(site: &str) -> anyhow::Result<Url> {
+    let valid = !site.is_empty()
+        && site
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-');
+    if !valid {
+        return Err(invalid_sink(format!(
+            "{site:?} isn't a Datadog site, like \"datadoghq.com\""
+        )));
+    }
+    Ok(format!("https://http-intake.logs.{site}/api/v2/logs").parse()?)
+}
+
+fn webhook_url(url: &str) -> anyhow::Result<Url> {
+    let url: Url = url
+        .parse()
+        .map_err(|e| invalid_sink(format!("Invalid webhook URL {url}: {e}")))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(invalid_sink(format!(
+            "Webhook URL {url} should be http or https"
+        )));
+    }
+    Ok(url)
+}
+
+fn rfc3339(timestamp: UnixTimestamp) -> String {
+    DateTime::<Utc>::from(timestamp.as_system_time()).to_rfc3339_opts(SecondsFormat::Millis, true)
+}
+
+fn event_fields(event: LogEvent) -> anyhow::Result<serde_json::Map<String, JsonValue>> {
+    event.to_json_map(LogEventFormatVersion::V2)
+}
+
+fn json_request(url: Url, mut headers: HeaderMap, body: &JsonValue) -> anyhow::Result<HttpRequest> {
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    Ok(HttpRequest {
+        headers,
+        url,
+        method: Method::POST,
+        body: Some(serde_json::to_vec(body)?),
+    })
+}
+
+/// A request to Datadog's log intake API.
+pub fn datadog_request(
+    config: &DatadogConfig,
+    deployment_name: &str,
+    events: &[LogEvent],
+) -> anyhow::Result<HttpRequest> {
+    let service = config.service.as_deref().unwrap_or(deployment_name);
+    let tags = config.tags.join(",");
+    let logs = events
+        .iter()
+        .map(|event| {
+            let mut fields = event_fields(event.clone())?;
+            fields.insert("ddsource".to_string(), json!("convex"));
+            fields.insert("service".to_string(), json!(service));
+            fields.insert("hostname".to_string(), json!(deployment_name));
+            fields.insert("ddtags".to_string(), json!(tags));
+            Ok(JsonValue::Object(fields))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let mut headers = HeaderMap::new();
+    headers.insert("DD-API-KEY", HeaderValue::from_str(&config.api_key)?);
+    json_request(datadog_url(&config.site)?, headers, &JsonValue::Array(logs))
+}
+
+/// A request to Axiom's ingest API.
+pub fn axiom_request(config: &AxiomConfig, events: &[LogEvent]) -> anyhow::Result<HttpRequest> {
+    let logs = events
+        .iter()
+        .map(|event| {
+            let time = rfc3339(event.timestamp);
+            let mut fields = event_fields(event.clone())?;
+            for (key, value) in &config.attributes {
+                fields.insert(key.clone(), json!(value));
+            }
+            fields.insert("_time".to_string(), json!(time));
+            Ok(JsonValue::Object(fields))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let mut url: Url = AXIOM_API_URL.parse()?;
+    url.path_segments_mut()
+        .map_err(|_| anyhow::anyhow!("Axiom URL can't have a path"))?
+        .extend(["v1", "datasets", &config.dataset_name, "ingest"]);
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        AUTHORIZATION,
+        HeaderValue::from_str(&format!("Bearer {}", config.api_token))?,
+    );
+    json_request(url, headers, &JsonValue::Array(logs))
+}
+
+/// A request POSTing the events to a webhook as a JSON array.
+pub fn webhook_request(config: &WebhookConfig, events: &[LogEvent]) -> anyhow::Result<HttpRequest> {
+    let logs = events
+        .iter()
+        .map(|event| Ok(JsonValue::Object(event_fields(event.clone())?)))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    json_request(
+        webhook_url(&config.url)?,
+        HeaderMap::new(),
+        &JsonValue::Array(logs),
+    )
+}
+
+fn syslog_severity(event: &StructuredLogEvent) -> u8 {
+    match event {
+        StructuredLogEvent::Console { log_line, .. } => match log_line.level {
+            LogLevel::Error => 3,
+            LogLevel::Warn => 4,
+            LogLevel::Info | LogLevel::Log => 6,
+            LogLevel::Debug => 7,
+        },
+        StructuredLogEvent::FunctionExecution { error: Some(_), .. }
+        | StructuredLogEvent::Exception { .. } => 3,
+        _ => 6,
+    }
+}
+
+/// An RFC 5424 syslog message, with the event's fields as JSON in the
+/// message and its topic as the message ID.
+pub fn syslog_message(deployment_name: &str, event: &LogEvent) -> anyhow::Result<String> {
+    let priority = SYSLOG_FACILITY_USER * 8 + syslog_severity(&event.event);
+    let timestamp = rfc3339(event.timestamp);
+    let fields = event_fields(event.clone())?;
+    let topic = fields
+        .get("topic")
+        .and_then(|topic| topic.as_str())
+        .unwrap_or("-")
+        .to_string();
+    Ok(format!(
+        "<{priority}>1 {timestamp} {deployment_name} convex - {topic} - {}",
+        JsonValue::Object(fields)
+    ))
+}
+
+pub struct SinkClient {
+    config: SinkConfig,
+    deployment_name: String,
+    fetch_client: Arc<dyn FetchClient>,
+}
+
+impl SinkClient {
+    pub fn new(
+        config: SinkConfig,
+        deployment_name: String,
+        fetch_client: Arc<dyn FetchClient>,
+    ) -> anyhow::Result<Self> {
+        validate_sink_config(&config)?;
+        Ok(Self {
+            config,
+            deployment_name,
+            fetch_client,
+        })
+    }
+
+    pub async fn send(&self, events: &[LogEvent]) -> anyhow::Result<()> {
+        let request = match &self.config {
+            SinkConfig::Datadog(config) => datadog_request(config, &self.deployment_name, events)?,
+            SinkConfig::Axiom(config) => axiom_request(config, events)?,
+            SinkConfig::Webhook(config) => webhook_request(config, events)?,
+            SinkConfig::Syslog(config) => return self.send_syslog(config, events).await,
+        };
+        let response = self
+            .fetch_client
+            .internal_fetch(request.into(), InternalFetchPurpose::LogStreaming)
+            .await?;
+        if !response.status.is_success() {
+            let status = response.status;
+            let body = response
+                .into_http_response()
+                .await?
+                .body
+                .unwrap_or_default();
+            anyhow::bail!(
+                "{} responded with {status}: {}",
+                self.config.sink_type(),
+                String::from_utf8_lossy(&body)
+            );
+        }
+        Ok(())
+    }
+
+    async fn send_syslog(&self, config: &SyslogConfig, events: &[LogEvent]) -> anyhow::Result<()> {
+        let messages = events
+            .iter()
+            .map(|event| syslog_message(&self.deployment_name, event))
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        match config.protocol {
+            SyslogProtocol::Udp => {
+                let socket = UdpSocket::bind("0.0.0.0:0").await?;
+                socket.connect(&config.address).await?;
+                for message in messages {
+                    socket.send(message.as_bytes()).await?;
+                }
+            },
+            SyslogProtocol::Tcp => {
+                let mut stream = TcpStream::connect(&config.address).await?;
+                for message in messages {
+                    stream
+                        .write_all(format!("{} {message}", message.len()).as_bytes())
+                        .await?;
+                }
+                stream.shutdown().await?;
+            },
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{
+        log_lines::{
+            LogLevel,
+            LogLineStructured,
+        },
+        log_streaming::{
+            FunctionEventSource,
+            LogEvent,
+            StructuredLogEvent,
+        },
+        runtime::UnixTimestamp,
+    };
+    use maplit::btreemap;
+    use model::log_sinks::types::{
+        AxiomConfig,
+        DatadogConfig,
+        SinkConfig,
+        SyslogConfig,
+        SyslogProtocol,
+        WebhookConfig,
+    };
+    use serde_json::Value as JsonValue;
+
+    use super::{
+        axiom_request,
+        datadog_request,
+        syslog_message,
+        validate_sink_config,
+    };
+
+    fn console_event(level: LogLevel) -> LogEvent {
+        let timestamp = UnixTimestamp::from_millis(1_700_000_000_000);
+        LogEvent {
+            timestamp,
+            event: StructuredLogEvent::Console {
+                source: FunctionEventSource::new_for_test(),
+                log_line: LogLineStructured::new_developer_log_line(
+                    level,
+                    vec!["hello".to_string()],
+                    timestamp,
+                ),
+            },
+        }
+    }
+
+    #[test]
+    fn test_datadog_request() -> anyhow::Result<()> {
+        let config = DatadogConfig {
+            site: "datadoghq.eu".to_string(),
+            api_key: "key".to_string(),
+            tags: vec!["env:prod".to_string(), "team:web".to_string()],
+            service: None,
+        };
+        let request = datadog_request(&config, "happy-otter-123", &[console_event(LogLevel::Log)])?;
+        assert_eq!(
+            request.url.as_str(),
+            "https://http-intake.logs.datadoghq.eu/api/v2/logs"
+        );
+        assert_eq!(request.headers["DD-API-KEY"], "key");
+        let body: JsonValue = serde_json::from_slice(&request.body.unwrap())?;
+        assert_eq!(body[0]["message"], "hello");
+        assert_eq!(body[0]["service"], "happy-otter-123");
+        assert_eq!(body[0]["ddtags"], "env:prod,team:web");
+        Ok(())
+    }
+
+    #[test]
+    fn test_axiom_request() -> anyhow::Result<()> {
+        let config = AxiomConfig {
+            api_token: "token".to_string(),
+            dataset_name: "convex logs".to_string(),
+            attributes: btreemap! { "region".to_string() => "eu".to_string() },
+        };
+        let request = axiom_request(&config, &[console_event(LogLevel::Log)])?;
+        assert_eq!(
+            request.url.as_str(),
+            "https://api.axiom.co/v1/datasets/convex%20logs/ingest"
+        );
+        let body: JsonValue = serde_json::from_slice(&request.body.unwrap())?;
+        assert_eq!(body[0]["region"], "eu");
+        assert_eq!(body[0]["_time"], "2023-11-14T22:13:20.000Z");
+        Ok(())
+    }
+
+    #[test]
+    fn test_syslog_message() -> anyhow::Result<()> {
+        let message = syslog_message("happy-otter-123", &console_event(LogLevel::Error))?;
+        let (header, fields) = message.split_once(" - console - ").unwrap();
+        assert_eq!(
+            header,
+            "<11>1 2023-11-14T22:13:20.000Z happy-otter-123 convex"
+        );
+        let fields: JsonValue = serde_json::from_str(fields)?;
+        assert_eq!(fields["log_level"], "ERROR");
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_sink_config() {
+        let webhook = |url: &str| {
+            SinkConfig::Webhook(WebhookConfig {
+                url: url.to_string(),
+            })
+        };
+        assert!(validate_sink_config(&webhook("https://logs.example.com/ingest")).is_ok());
+        assert!(validate_sink_config(&webhook("ftp://logs.example.com")).is_err());
+        let syslog = |address: &str| {
+            SinkConfig::Syslog(SyslogConfig {
+                address: address.to_string(),
+                protocol: SyslogProtocol::Udp,
+            })
+        };
+        assert!(validate_sink_config(&syslog("logs.example.com:514")).is_ok());
+        assert!(validate_sink_config(&syslog("logs.example.com")).is_err());
+        let datadog = SinkConfig::Datadog(DatadogConfig {
+            site: "datadoghq.com/evil".to_string(),
+            api_key: "key".to_string(),
+            tags: vec![],
+            service: None,
+        });
+        assert!(validate_sink_config(&datadog).is_err());
+    }
+}
@@ -0,0 +1,222 @@
+//! Backfills materialized aggregates and cleans up deleted ones. See
+//! `database::materialized_aggregates` for how writes keep them up to date.
+//!
+//! The worker counts a new aggregate's existing documents in batches, each in
+//! its own transaction, and deletes the entries of deleted aggregates the same
+//! way. Aggregates on tables that were deleted are deleted too.
+use std::time::Duration;
+
+use common::{
+    backoff::Backoff,
+    components::{
+        ComponentId,
+        ComponentPath,
+    },
+    errors::report_error,
+    knobs::MATERIALIZED_AGGREGATE_BATCH_SIZE,
+    runtime::Runtime,
+};
+use database::{
+    materialized_aggregates::{
+        MaterializedAggregateModel,
+        MaterializedAggregateState,
+    },
+    unauthorized_error,
+    Database,
+};
+use futures::Future;
+use keybroker::Identity;
+use value::{
+    FieldPath,
+    ResolvedDocumentId,
+    TableName,
+    TableNamespace,
+};
+
+use crate::Application;
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+pub struct MaterializedAggregateWorker<RT: Runtime> {
+    runtime: RT,
+    database: Database<RT>,
+}
+
+impl<RT: Runtime> MaterializedAggregateWorker<RT> {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(runtime: RT, database: Database<RT>) -> impl Future<Output = ()> + Send {
+        let worker = Self { runtime, database };
+        async move {
+            let mut backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
+            while let Err(mut e) = worker.run(&mut backoff).await {
+                report_error(&mut e);
+                let delay = backoff.fail(&mut worker.runtime.rng());
+                tracing::error!("MaterializedAggregateWorker failed, sleeping {delay:?}");
+                worker.runtime.wait(delay).await;
+            }
+        }
+    }
+
+    async fn run(&self, backoff: &mut Backoff) -> anyhow::Result<()> {
+        tracing::info!("Starting MaterializedAggregateWorker");
+        loop {
+            let mut tx = self.database.begin(Identity::system()).await?;
+            let table_mapping = tx.table_mapping().clone();
+            let aggregates = MaterializedAggregateModel::new(&mut tx).all().await?;
+            let mut changed = false;
+            for aggregate in aggregates {
+                let id = aggregate.id();
+                if aggregate.state != MaterializedAggregateState::Deleting
+                    && !table_mapping.is_active(aggregate.table_id)
+                {
+                    let mut tx = self.database.begin(Identity::system()).await?;
+                    MaterializedAggregateModel::new(&mut tx)
+                        .mark_deleting(aggregate)
+                        .await?;
+                    self.database
+                        .commit_with_write_source(tx, "materialized_aggregate_worker")
+                        .await?;
+                    changed = true;
+                    continue;
+                }
+                match aggregate.state {
+                    MaterializedAggregateState::Backfilling { .. } => {
+                        while !self.backfill(id).await? {}
+                        tracing::info!("Finished backfilling materialized aggregate {id}");
+                        changed = true;
+                    },
+                    MaterializedAggregateState::Deleting => {
+                        while !self.delete_entries(id).await? {}
+                        changed = true;
+                    },
+                    MaterializedAggregateState::Ready => (),
+                }
+            }
+            backoff.reset();
+            // Our own writes invalidate the token, so look again rather than
+            // subscribing to it.
+            if changed {
+                continue;
+            }
+            let token = tx.into_token()?;
+            let subscription = self.database.subscribe(token).await?;
+            subscription.wait_for_invalidation().await;
+        }
+    }
+
+    async fn backfill(&self, id: ResolvedDocumentId) -> anyhow::Result<bool> {
+        let mut tx = self.database.begin(Identity::system()).await?;
+        let done = MaterializedAggregateModel::new(&mut tx)
+            .backfill(id, *MATERIALIZED_AGGREGATE_BATCH_SIZE)
+            .await?;
+        self.database
+            .commit_with_write_source(tx, "materialized_aggregate_worker")
+            .await?;
+        Ok(done)
+    }
+
+    async fn delete_entries(&self, id: ResolvedDocumentId) -> anyhow::Result<bool> {
+        let mut tx = self.database.begin(Identity::system()).await?;
+        let done = MaterializedAggregateModel::new(&mut tx)
+            .delete_entries(id, *MATERIALIZED_AGGREGATE_BATCH_SIZE)
+            .await?;
+        self.database
+            .commit_with_write_source(tx, "materialized_aggregate_worker")
+            .await?;
+        Ok(done)
+    }
+}
+
+/// A materialized aggregate and whether it's finished backfilling.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MaterializedAggregateStatus {
+    pub component: ComponentPath,
+    pub table_name: TableName,
+    pub name: String,
+    pub key_field: Option<FieldPath>,
+    pub sum_field: Option<FieldPath>,
+    pub ready: bool,
+}
+
+impl<RT: Runtime> Application<RT> {
+    /// Starts maintaining a count of `table_name`'s documents, and the sum of
+    /// `sum_field`, per value of `key_field`. Functions can read it once the
+    /// table's existing documents have been counted.
+    pub async fn create_materialized_aggregate(
+        &self,
+        identity: Identity,
+        component: ComponentId,
+        table_name: TableName,
+        name: String,
+        key_field: Option<FieldPath>,
+        sum_field: Option<FieldPath>,
+    ) -> anyhow::Result<()> {
+        let mut tx = self.begin(identity).await?;
+        MaterializedAggregateModel::new(&mut tx)
+            .create(
+                TableNamespace::from(component),
+                &table_name,
+                name,
+                key_field,
+                sum_field,
+            )
+            .await?;
+        self.commit(tx, "create_materialized_aggregate").await?;
+        Ok(())
+    }
+
+    pub async fn delete_materialized_aggregate(
+        &self,
+        identity: Identity,
+        component: ComponentId,
+        table_name: TableName,
+        name: String,
+    ) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            identity.is_admin() || identity.is_system(),
+            unauthorized_error("delete_materialized_aggregate")
+        );
+        let mut tx = self.begin(identity).await?;
+        MaterializedAggregateModel::new(&mut tx)
+            .delete(TableNamespace::from(component), &table_name, &name)
+            .await?;
+        self.commit(tx, "delete_materialized_aggregate").await?;
+        Ok(())
+    }
+
+    pub async fn materialized_aggregates(
+        &self,
+        identity: Identity,
+    ) -> anyhow::Result<Vec<MaterializedAggregateStatus>> {
+        anyhow::ensure!(
+            identity.is_admin() || identity.is_system(),
+            unauthorized_error("materialized_aggregates")
+        );
+        let mut tx = self.begin(identity).await?;
+        let component_paths = tx.all_component_paths();
+        let table_mapping = tx.table_mapping().clone();
+        let mut statuses = vec![];
+        for aggregate in MaterializedAggregateModel::new(&mut tx).all().await? {
+            let aggregate = aggregate.into_value();
+            if aggregate.state == MaterializedAggregateState::Deleting
+                || !table_mapping.is_active(aggregate.table_id)
+            {
+                continue;
+            }
+            let namespace = table_mapping.tablet_namespace(aggregate.table_id)?;
+            statuses.push(MaterializedAggregateStatus {
+                component: component_paths
+                    .get(&ComponentId::from(namespace))
+                    .cloned()
+                    .unwrap_or_default(),
+                table_name: table_mapping.tablet_name(aggregate.table_id)?,
+                name: aggregate.name,
+                key_field: aggregate.key_field,
+                sum_field: aggregate.sum_field,
+                ready: aggregate.state == MaterializedAggregateState::Ready,
+            });
+        }
+        Ok(statuses)
+    }
+}
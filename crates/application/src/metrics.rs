@@ -1,5 +1,9 @@
 use std::time::Duration;
 
+use common::{
+    components::ComponentPath,
+    types::UdfType,
+};
 use metrics::{
     log_counter_with_labels,
     log_distribution,
@@ -13,6 +17,8 @@ use metrics::{
     STATUS_LABEL,
 };
 use model::source_packages::types::PackageSize;
+use sync_types::CanonicalizedUdfPath;
+use value::TableName;
 
 register_convex_counter!(
     EXTERNAL_DEPS_PACKAGES_TOTAL,
@@ -103,3 +109,105 @@ fn log_worker_status(is_working: bool, name: &'static str) {
         vec![StaticMetricLabel::new("worker", name)],
     )
 }
+
+register_convex_counter!(
+    LOG_STREAMING_EVENTS_SENT_TOTAL,
+    "Number of log events delivered to log sinks",
+    &["sink"],
+);
+pub fn log_streaming_events_sent(sink: &'static str, num_events: usize) {
+    log_counter_with_labels(
+        &LOG_STREAMING_EVENTS_SENT_TOTAL,
+        num_events as u64,
+        vec![StaticMetricLabel::new("sink", sink)],
+    );
+}
+
+register_convex_counter!(
+    LOG_STREAMING_EVENTS_DROPPED_TOTAL,
+    "Number of log events dropped because a buffer was full or a batch couldn't be delivered",
+    &["sink"],
+);
+/// `sink` is "manager" for events dropped before they reach any sink.
+pub fn log_streaming_events_dropped(sink: &'static str, num_events: usize) {
+    log_counter_with_labels(
+        &LOG_STREAMING_EVENTS_DROPPED_TOTAL,
+        num_events as u64,
+        vec![StaticMetricLabel::new("sink", sink)],
+    );
+}
+
+register_convex_counter!(
+    FUNCTION_EXECUTIONS_TOTAL,
+    "Number of function executions, by function path",
+    &["udf_type", "function", "status"],
+);
+register_convex_histogram!(
+    FUNCTION_EXECUTION_SECONDS,
+    "Time taken to execute a function, by function path",
+    &["udf_type", "function"],
+);
+/// `function` is the function's path, or the route for HTTP actions.
+pub fn log_function_execution(
+    udf_type: UdfType,
+    function: String,
+    is_err: bool,
+    execution_time_secs: f64,
+) {
+    log_counter_with_labels(
+        &FUNCTION_EXECUTIONS_TOTAL,
+        1,
+        vec![
+            udf_type.metric_label(),
+            StaticMetricLabel::new("function", function.clone()),
+            StaticMetricLabel::new("status", if is_err { "failure" } else { "success" }),
+        ],
+    );
+    log_distribution_with_labels(
+        &FUNCTION_EXECUTION_SECONDS,
+        execution_time_secs,
+        vec![
+            udf_type.metric_label(),
+            StaticMetricLabel::new("function", function),
+        ],
+    );
+}
+
+register_convex_counter!(
+    FUNCTION_OCC_CONFLICTS_TOTAL,
+    "Number of times a mutation conflicted with a concurrent write, by function path",
+    &["function"],
+);
+pub fn log_function_occ_conflict(udf_path: &CanonicalizedUdfPath) {
+    if udf_path.is_system() {
+        return;
+    }
+    log_counter_with_labels(
+        &FUNCTION_OCC_CONFLICTS_TOTAL,
+        1,
+        vec![StaticMetricLabel::new(
+            "function",
+            udf_path.clone().strip().to_string(),
+        )],
+    );
+}
+
+register_convex_gauge!(
+    TABLE_DOCUMENTS_TOTAL,
+    "Number of documents in each user table",
+    &["component", "table"],
+);
+pub fn log_table_document_counts(counts: Vec<(ComponentPath, TableName, u64)>) {
+    // Start over so that deleted tables don't keep reporting their last count.
+    TABLE_DOCUMENTS_TOTAL.reset();
+    for (component_path, table_name, count) in counts {
+        log_gauge_with_labels(
+            &TABLE_DOCUMENTS_TOTAL,
+            count as f64,
+            vec![
+                StaticMetricLabel::new("component", component_path.to_string()),
+                StaticMetricLabel::new("table", table_name.to_string()),
+            ],
+        );
+    }
+}
@@ -0,0 +1,235 @@
+//! Adds the metrics of function executions to the hourly and daily rollups in
+//! `_metrics_rollups`. Executions are summarized in memory as they're logged
+//! and flushed periodically, and rollups older than their retention period
+//! are deleted.
+use std::{
+    collections::BTreeMap,
+    time::Duration,
+};
+
+use common::{
+    backoff::Backoff,
+    errors::report_error,
+    knobs::{
+        METRICS_ROLLUP_DAILY_RETENTION,
+        METRICS_ROLLUP_FLUSH_INTERVAL,
+        METRICS_ROLLUP_HOURLY_RETENTION,
+    },
+    pause::PauseClient,
+    runtime::{
+        Runtime,
+        UnixTimestamp,
+    },
+};
+use database::Database;
+use futures::Future;
+use keybroker::Identity;
+use model::metrics_rollups::{
+    types::{
+        MetricsRollup,
+        RollupGranularity,
+        RollupKind,
+        RollupStats,
+    },
+    MetricsRollupModel,
+};
+use usage_tracking::FunctionUsageTracker;
+
+use crate::function_log::{
+    FunctionExecution,
+    FunctionExecutionLog,
+};
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// How many hours of stats for a function or table are written per
+/// transaction.
+const FLUSH_BATCH_SIZE: usize = 64;
+/// How many expired rollups are deleted per transaction.
+const DELETE_BATCH_SIZE: usize = 128;
+
+type RollupKey = (UnixTimestamp, RollupKind, String);
+
+/// Stats for executions that haven't been added to the stored rollups yet,
+/// by hour.
+#[derive(Default)]
+pub(crate) struct PendingRollups {
+    stats: BTreeMap<RollupKey, RollupStats>,
+}
+
+impl PendingRollups {
+    pub(crate) fn append(&mut self, execution: &FunctionExecution) {
+        let hour = RollupGranularity::Hour.bucket_start(execution.unix_timestamp);
+        let errors = execution.params.is_err() as u64;
+        let usage = &execution.usage_stats;
+        let mut function_stats = RollupStats {
+            calls: 1,
+            errors,
+            bandwidth_bytes: usage.database_read_bytes
+                + usage.database_write_bytes
+                + usage.storage_read_bytes
+                + usage.storage_write_bytes
+                + usage.vector_index_read_bytes
+                + usage.vector_index_write_bytes,
+            ..Default::default()
+        };
+        function_stats
+            .latency
+            .record(Duration::from_secs_f64(execution.execution_time));
+        for (table_name, table_stats) in &execution.tables_touched {
+            function_stats.rows_read += table_stats.rows_read;
+            function_stats.rows_written += table_stats.rows_written;
+            self.add(
+                (hour, RollupKind::Table, table_name.to_string()),
+                RollupStats {
+                    calls: 1,
+                    errors,
+                    rows_read: table_stats.rows_read,
+                    rows_written: table_stats.rows_written,
+                    ..Default::default()
+                },
+            );
+        }
+        self.add(
+            (
+                hour,
+                RollupKind::Function,
+                execution.identifier().to_string(),
+            ),
+            function_stats,
+        );
+    }
+
+    fn add(&mut self, key: RollupKey, stats: RollupStats) {
+        self.stats.entry(key).or_default().merge(&stats);
+    }
+
+    pub(crate) fn merge(&mut self, other: PendingRollups) {
+        for (key, stats) in other.stats {
+            self.add(key, stats);
+        }
+    }
+}
+
+/// Moves the pending stats into the stored rollups, and deletes expired ones.
+pub struct MetricsRollupWorker<RT: Runtime> {
+    runtime: RT,
+    database: Database<RT>,
+    function_log: FunctionExecutionLog<RT>,
+}
+
+impl<RT: Runtime> MetricsRollupWorker<RT> {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        runtime: RT,
+        database: Database<RT>,
+        function_log: FunctionExecutionLog<RT>,
+    ) -> impl Future<Output = ()> + Send {
+        let worker = Self {
+            runtime,
+            database,
+            function_log,
+        };
+        async move {
+            let mut backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
+            while let Err(mut e) = worker.run(&mut backoff).await {
+                report_error(&mut e);
+                let delay = backoff.fail(&mut worker.runtime.rng());
+                tracing::error!("MetricsRollupWorker failed, sleeping {delay:?}");
+                worker.runtime.wait(delay).await;
+            }
+        }
+    }
+
+    async fn run(&self, backoff: &mut Backoff) -> anyhow::Result<()> {
+        tracing::info!("Starting MetricsRollupWorker");
+        loop {
+            self.runtime.wait(*METRICS_ROLLUP_FLUSH_INTERVAL).await;
+            self.flush().await?;
+            self.delete_expired().await?;
+            backoff.reset();
+        }
+    }
+
+    async fn flush(&self) -> anyhow::Result<()> {
+        let pending = self.function_log.take_pending_rollups();
+        let entries: Vec<_> = pending.stats.into_iter().collect();
+        for (i, batch) in entries.chunks(FLUSH_BATCH_SIZE).enumerate() {
+            let result = self
+                .database
+                .execute_with_occ_retries(
+                    Identity::system(),
+                    FunctionUsageTracker::new(),
+                    PauseClient::new(),
+                    "metrics_rollup_flush",
+                    |tx| {
+                        async move {
+                            for ((hour, kind, name), stats) in batch {
+                                for granularity in [RollupGranularity::Hour, RollupGranularity::Day]
+                                {
+                                    MetricsRollupModel::new(tx)
+                                        .merge(MetricsRollup {
+                                            granularity,
+                                            bucket_start: granularity.bucket_start(*hour),
+                                            kind: *kind,
+                                            name: name.clone(),
+                                            stats: stats.clone(),
+                                        })
+                                        .await?;
+                                }
+                            }
+                            Ok(())
+                        }
+                        .into()
+                    },
+                )
+                .await;
+            if let Err(e) = result {
+                // Keep the stats that weren't written for the next flush.
+                let unwritten = PendingRollups {
+                    stats: entries[i * FLUSH_BATCH_SIZE..].iter().cloned().collect(),
+                };
+                self.function_log.restore_pending_rollups(unwritten);
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn delete_expired(&self) -> anyhow::Result<()> {
+        let now = self.runtime.unix_timestamp();
+        for (granularity, retention) in [
+            (RollupGranularity::Hour, *METRICS_ROLLUP_HOURLY_RETENTION),
+            (RollupGranularity::Day, *METRICS_ROLLUP_DAILY_RETENTION),
+        ] {
+            if now.as_secs_f64() < retention.as_secs_f64() {
+                continue;
+            }
+            let cutoff = now - retention;
+            loop {
+                let (_, deleted, _) = self
+                    .database
+                    .execute_with_occ_retries(
+                        Identity::system(),
+                        FunctionUsageTracker::new(),
+                        PauseClient::new(),
+                        "metrics_rollup_retention",
+                        |tx| {
+                            async move {
+                                MetricsRollupModel::new(tx)
+                                    .delete_before(granularity, cutoff, DELETE_BATCH_SIZE)
+                                    .await
+                            }
+                            .into()
+                        },
+                    )
+                    .await?;
+                if deleted < DELETE_BATCH_SIZE {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+}
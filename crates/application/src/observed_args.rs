@@ -0,0 +1,205 @@
+//! Samples of the arguments functions are actually called with. The function
+//! log records a small fraction of successful calls, which is enough to infer
+//! each function's argument shapes and to warn at push time when a function's
+//! new argument validator would reject the traffic it's been getting.
+use std::collections::{
+    BTreeMap,
+    VecDeque,
+};
+
+use common::{
+    components::{
+        CanonicalizedComponentFunctionPath,
+        ComponentDefinitionPath,
+        ComponentPath,
+    },
+    knobs::FUNCTION_ARGS_MAX_SAMPLES,
+    runtime::Runtime,
+};
+use keybroker::Identity;
+use model::{
+    components::{
+        type_checking::CheckedComponent,
+        types::EvaluatedComponentDefinition,
+    },
+    virtual_system_mapping,
+};
+use shape_inference::{
+    CountedShape,
+    ProdConfigWithOptionalFields,
+};
+use sync_types::CanonicalizedUdfPath;
+use value::{
+    ConvexArray,
+    Size,
+};
+
+use crate::Application;
+
+/// Arguments bigger than this aren't sampled, to bound the memory used.
+const MAX_SAMPLED_ARGS_SIZE: usize = 1 << 16;
+
+#[derive(Clone, Debug)]
+pub struct ObservedFunctionArgs {
+    /// The union of the shapes of every sampled argument.
+    pub shape: CountedShape<ProdConfigWithOptionalFields>,
+    /// The most recently sampled arguments, oldest first.
+    pub recent: VecDeque<ConvexArray>,
+}
+
+#[derive(Default)]
+pub(crate) struct ObservedArgs {
+    functions: BTreeMap<CanonicalizedComponentFunctionPath, ObservedFunctionArgs>,
+}
+
+impl ObservedArgs {
+    pub(crate) fn record(&mut self, path: &CanonicalizedComponentFunctionPath, args: &ConvexArray) {
+        if args.size() > MAX_SAMPLED_ARGS_SIZE {
+            return;
+        }
+        // Functions take a single object of named arguments.
+        let Some(arg) = args.iter().next() else {
+            return;
+        };
+        let observed = self
+            .functions
+            .entry(path.clone())
+            .or_insert_with(|| ObservedFunctionArgs {
+                shape: CountedShape::empty(),
+                recent: VecDeque::new(),
+            });
+        observed.shape = observed.shape.insert_value(arg);
+        observed.recent.push_back(args.clone());
+        while observed.recent.len() > *FUNCTION_ARGS_MAX_SAMPLES {
+            observed.recent.pop_front();
+        }
+    }
+
+    pub(crate) fn in_component(
+        &self,
+        component: &ComponentPath,
+    ) -> BTreeMap<CanonicalizedUdfPath, ObservedFunctionArgs> {
+        self.functions
+            .iter()
+            .filter(|(path, _)| &path.component == component)
+            .map(|(path, observed)| (path.udf_path.clone(), observed.clone()))
+            .collect()
+    }
+}
+
+impl<RT: Runtime> Application<RT> {
+    /// The argument shapes observed for each function in `component`.
+    pub fn observed_args(
+        &self,
+        component: &ComponentPath,
+    ) -> BTreeMap<CanonicalizedUdfPath, ObservedFunctionArgs> {
+        self.function_log.observed_args(component)
+    }
+
+    /// Check the argument validators of the functions being pushed against
+    /// their recently observed arguments, returning a warning for each
+    /// function whose new validator would reject some of them.
+    pub(crate) async fn check_observed_args(
+        &self,
+        app: &CheckedComponent,
+        evaluated_components: &BTreeMap<ComponentDefinitionPath, EvaluatedComponentDefinition>,
+    ) -> anyhow::Result<Vec<String>> {
+        let mut tx = self.begin(Identity::system()).await?;
+        let component_ids: BTreeMap<_, _> = tx
+            .all_component_paths()
+            .into_iter()
+            .map(|(id, path)| (path, id))
+            .collect();
+        let mut warnings = vec![];
+        let mut stack = vec![app];
+        while let Some(component) = stack.pop() {
+            stack.extend(component.child_components.values());
+            // Components that don't exist yet haven't been called.
+            let Some(component_id) = component_ids.get(&component.component_path) else {
+                continue;
+            };
+            let Some(evaluated) = evaluated_components.get(&component.definition_path) else {
+                continue;
+            };
+            let observed = self.observed_args(&component.component_path);
+            if observed.is_empty() {
+                continue;
+            }
+            let table_mapping = tx.table_mapping().namespace((*component_id).into());
+            for (module_path, module) in &evaluated.functions {
+                for function in module.functions.iter() {
+                    let udf_path =
+                        CanonicalizedUdfPath::new(module_path.clone(), function.name.clone());
+                    let Some(observed) = observed.get(&udf_path) else {
+                        continue;
+                    };
+                    let args_validator = function.args()?;
+                    let mut num_rejected = 0;
+                    let mut first_error = None;
+                    for args in &observed.recent {
+                        if let Some(error) = args_validator.check_args(
+                            args,
+                            &table_mapping,
+                            &virtual_system_mapping(),
+                        )? {
+                            num_rejected += 1;
+                            first_error.get_or_insert(error);
+                        }
+                    }
+                    if let Some(error) = first_error {
+                        let path = CanonicalizedComponentFunctionPath {
+                            component: component.component_path.clone(),
+                            udf_path,
+                        };
+                        warnings.push(format!(
+                            "The new argument validator for {path:?} would reject {num_rejected} \
+                             of its {} recently observed calls: {}",
+                            observed.recent.len(),
+                            error.message
+                        ));
+                    }
+                }
+            }
+        }
+        Ok(warnings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::components::{
+        CanonicalizedComponentFunctionPath,
+        ComponentPath,
+    };
+    use value::{
+        assert_obj,
+        ConvexArray,
+        ConvexValue,
+    };
+
+    use super::ObservedArgs;
+
+    #[test]
+    fn test_record_observed_args() -> anyhow::Result<()> {
+        let path = CanonicalizedComponentFunctionPath {
+            component: ComponentPath::root(),
+            udf_path: "messages:send".parse()?,
+        };
+        let mut observed = ObservedArgs::default();
+        for i in 0..100 {
+            let args = ConvexArray::try_from(vec![ConvexValue::Object(
+                assert_obj!("body" => format!("message {i}")),
+            )])?;
+            observed.record(&path, &args);
+        }
+
+        let functions = observed.in_component(&ComponentPath::root());
+        let observed_args = &functions[&path.udf_path];
+        assert_eq!(*observed_args.shape.num_values(), 100);
+        assert_eq!(
+            observed_args.recent.len(),
+            *common::knobs::FUNCTION_ARGS_MAX_SAMPLES
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,377 @@
+//! Opt-in scanner that samples each of the app's tables for fields that look
+//! like they hold personal data (emails, phone numbers and credit card
+//! numbers), storing a report per table in `_pii_reports`. The reports help
+//! decide which fields need masking or encryption; they're heuristics over a
+//! sample, so they can both miss fields and flag ones that aren't PII.
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
+    sync::LazyLock,
+    time::Duration,
+};
+
+use anyhow::Context;
+use common::{
+    backoff::Backoff,
+    components::ComponentId,
+    errors::report_error,
+    knobs::{
+        PII_SCAN_ENABLED,
+        PII_SCAN_INTERVAL,
+        PII_SCAN_SAMPLE_SIZE,
+    },
+    pause::PauseClient,
+    runtime::{
+        Runtime,
+        UnixTimestamp,
+    },
+};
+use database::{
+    Database,
+    IndexModel,
+};
+use futures::{
+    pin_mut,
+    Future,
+    TryStreamExt,
+};
+use keybroker::Identity;
+use model::pii_reports::{
+    types::{
+        PiiField,
+        PiiKind,
+        PiiReport,
+    },
+    PiiReportModel,
+};
+use regex::Regex;
+use usage_tracking::FunctionUsageTracker;
+use value::{
+    ConvexObject,
+    ConvexValue,
+    TableName,
+    TableNamespace,
+};
+
+use crate::Application;
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60 * 60);
+
+static EMAIL_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[^\s@]+@[^\s@]+\.[A-Za-z]{2,}$").unwrap());
+
+/// Whether `s` is 13 to 19 digits, optionally grouped with spaces or dashes,
+/// that pass the Luhn check used by card numbers.
+fn is_credit_card(s: &str) -> bool {
+    if !s
+        .chars()
+        .all(|c| c.is_ascii_digit() || c == ' ' || c == '-')
+    {
+        return false;
+    }
+    let digits: Vec<u32> = s.chars().filter_map(|c| c.to_digit(10)).collect();
+    if !(13..=19).contains(&digits.len()) {
+        return false;
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &digit)| match (i % 2 == 1, digit * 2) {
+            (true, doubled) if doubled > 9 => doubled - 9,
+            (true, doubled) => doubled,
+            (false, _) => digit,
+        })
+        .sum();
+    sum % 10 == 0
+}
+
+/// Whether `s` looks like a phone number: 10 to 15 digits with an
+/// international `+` prefix or the usual separators. Bare runs of digits are
+/// skipped since they're more often IDs or amounts.
+fn is_phone(s: &str) -> bool {
+    let rest = s.strip_prefix('+').unwrap_or(s);
+    if !rest
+        .chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, ' ' | '-' | '.' | '(' | ')'))
+    {
+        return false;
+    }
+    let num_digits = rest.chars().filter(|c| c.is_ascii_digit()).count();
+    (10..=15).contains(&num_digits)
+        && (s.starts_with('+') || rest.chars().any(|c| !c.is_ascii_digit()))
+}
+
+fn classify(s: &str) -> Option<PiiKind> {
+    let s = s.trim();
+    if EMAIL_REGEX.is_match(s) {
+        Some(PiiKind::Email)
+    } else if is_credit_card(s) {
+        Some(PiiKind::CreditCard)
+    } else if is_phone(s) {
+        Some(PiiKind::Phone)
+    } else {
+        None
+    }
+}
+
+#[derive(Default)]
+struct FieldCounts {
+    documents_with_field: u64,
+    matches: BTreeMap<PiiKind, u64>,
+}
+
+/// Counts, for each field path, how many sampled documents had values of
+/// each kind.
+#[derive(Default)]
+struct PiiDetector {
+    documents_sampled: u64,
+    fields: BTreeMap<String, FieldCounts>,
+}
+
+impl PiiDetector {
+    fn add_document(&mut self, document: &ConvexObject) {
+        self.documents_sampled += 1;
+        let mut found = BTreeMap::new();
+        for (field, value) in document.iter() {
+            // Skip `_id` and `_creationTime`.
+            if field.starts_with('_') {
+                continue;
+            }
+            Self::collect(field.to_string(), value, &mut found);
+        }
+        for (field_path, kinds) in found {
+            let counts = self.fields.entry(field_path).or_default();
+            counts.documents_with_field += 1;
+            for kind in kinds {
+                *counts.matches.entry(kind).or_default() += 1;
+            }
+        }
+    }
+
+    fn collect(
+        field_path: String,
+        value: &ConvexValue,
+        found: &mut BTreeMap<String, BTreeSet<PiiKind>>,
+    ) {
+        match value {
+            ConvexValue::String(s) => {
+                let kinds = found.entry(field_path).or_default();
+                if let Some(kind) = classify(s) {
+                    kinds.insert(kind);
+                }
+            },
+            ConvexValue::Array(values) => {
+                for value in values {
+                    Self::collect(field_path.clone(), value, found);
+                }
+            },
+            ConvexValue::Object(object) => {
+                for (field, value) in object.iter() {
+                    Self::collect(format!("{field_path}.{field}"), value, found);
+                }
+            },
+            ConvexValue::Null
+            | ConvexValue::Int64(_)
+            | ConvexValue::Float64(_)
+            | ConvexValue::Boolean(_)
+            | ConvexValue::Bytes(_)
+            | ConvexValue::Set(_)
+            | ConvexValue::Map(_) => {},
+        }
+    }
+
+    fn into_report(self, table_name: TableName, scanned_at: UnixTimestamp) -> PiiReport {
+        let fields = self
+            .fields
+            .into_iter()
+            .flat_map(|(field_path, counts)| {
+                counts
+                    .matches
+                    .into_iter()
+                    .map(move |(kind, matches)| PiiField {
+                        field_path: field_path.clone(),
+                        kind,
+                        matches,
+                        documents_with_field: counts.documents_with_field,
+                    })
+            })
+            .collect();
+        PiiReport {
+            table_name,
+            scanned_at,
+            documents_sampled: self.documents_sampled,
+            fields,
+        }
+    }
+}
+
+/// Periodically rescans the root component's tables when `PII_SCAN_ENABLED`
+/// is set.
+pub struct PiiScanWorker<RT: Runtime> {
+    runtime: RT,
+    database: Database<RT>,
+}
+
+impl<RT: Runtime> PiiScanWorker<RT> {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(runtime: RT, database: Database<RT>) -> impl Future<Output = ()> + Send {
+        let worker = Self { runtime, database };
+        async move {
+            if !*PII_SCAN_ENABLED {
+                return;
+            }
+            let mut backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
+            while let Err(mut e) = worker.run(&mut backoff).await {
+                report_error(&mut e);
+                let delay = backoff.fail(&mut worker.runtime.rng());
+                tracing::error!("PiiScanWorker failed, sleeping {delay:?}");
+                worker.runtime.wait(delay).await;
+            }
+        }
+    }
+
+    async fn run(&self, backoff: &mut Backoff) -> anyhow::Result<()> {
+        tracing::info!("Starting PiiScanWorker");
+        loop {
+            self.scan().await?;
+            backoff.reset();
+            self.runtime.wait(*PII_SCAN_INTERVAL).await;
+        }
+    }
+
+    async fn scan(&self) -> anyhow::Result<()> {
+        let mut tx = self.database.begin(Identity::system()).await?;
+        let by_id_indexes = IndexModel::new(&mut tx).by_id_indexes().await?;
+        let snapshot_ts = tx.begin_timestamp();
+        let tables: Vec<_> = tx
+            .table_mapping()
+            .namespace(TableNamespace::from(ComponentId::Root))
+            .iter_active_user_tables()
+            .map(|(tablet_id, _, table_name)| (tablet_id, table_name.clone()))
+            .collect();
+        drop(tx);
+
+        let sample_size = *PII_SCAN_SAMPLE_SIZE;
+        for (tablet_id, table_name) in &tables {
+            let by_id = by_id_indexes
+                .get(tablet_id)
+                .with_context(|| format!("{table_name}.by_id does not exist"))?;
+            // Document IDs are random, so the first documents by ID are a
+            // uniform sample of the table.
+            let table_iterator =
+                self.database
+                    .table_iterator(snapshot_ts, sample_size.clamp(1, 1000), None);
+            let stream = table_iterator.stream_documents_in_table(*tablet_id, *by_id, None);
+            pin_mut!(stream);
+            let mut detector = PiiDetector::default();
+            while detector.documents_sampled < sample_size as u64 {
+                let Some((doc, _ts)) = stream.try_next().await? else {
+                    break;
+                };
+                detector.add_document(doc.value());
+            }
+            let report = detector.into_report(table_name.clone(), self.runtime.unix_timestamp());
+            let report = &report;
+            self.database
+                .execute_with_occ_retries(
+                    Identity::system(),
+                    FunctionUsageTracker::new(),
+                    PauseClient::new(),
+                    "pii_scan_report",
+                    |tx| async move { PiiReportModel::new(tx).upsert(report.clone()).await }.into(),
+                )
+                .await?;
+        }
+
+        let table_names: BTreeSet<TableName> = tables
+            .into_iter()
+            .map(|(_, table_name)| table_name)
+            .collect();
+        let table_names = &table_names;
+        self.database
+            .execute_with_occ_retries(
+                Identity::system(),
+                FunctionUsageTracker::new(),
+                PauseClient::new(),
+                "pii_scan_retain",
+                |tx| async move { PiiReportModel::new(tx).retain(table_names).await }.into(),
+            )
+            .await?;
+        tracing::info!("Scanned {} tables for PII", table_names.len());
+        Ok(())
+    }
+}
+
+impl<RT: Runtime> Application<RT> {
+    /// The latest PII scan report for each of the root component's tables.
+    /// There are none unless the deployment has `PII_SCAN_ENABLED` set.
+    pub async fn pii_reports(&self, identity: Identity) -> anyhow::Result<Vec<PiiReport>> {
+        let mut tx = self.begin(identity).await?;
+        PiiReportModel::new(&mut tx).reports().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::runtime::UnixTimestamp;
+    use model::pii_reports::types::{
+        PiiField,
+        PiiKind,
+    };
+    use value::assert_obj;
+
+    use super::{
+        classify,
+        PiiDetector,
+    };
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(classify("ada@example.com"), Some(PiiKind::Email));
+        assert_eq!(classify("not an @email"), None);
+        assert_eq!(classify("4111 1111 1111 1111"), Some(PiiKind::CreditCard));
+        // Fails the Luhn check.
+        assert_eq!(classify("4111 1111 1111 1112"), None);
+        assert_eq!(classify("+1 (415) 555-0100"), Some(PiiKind::Phone));
+        assert_eq!(classify("415-555-0100"), Some(PiiKind::Phone));
+        assert_eq!(classify("4155550100"), None);
+        assert_eq!(classify("2024-01-01"), None);
+    }
+
+    #[test]
+    fn test_detector_report() -> anyhow::Result<()> {
+        let mut detector = PiiDetector::default();
+        detector.add_document(&assert_obj!(
+            "name" => "Ada",
+            "contact" => { "email" => "ada@example.com", "phones" => ["+44 20 7946 0000"] },
+        ));
+        detector.add_document(&assert_obj!(
+            "name" => "Grace",
+            "contact" => { "email" => "unknown" },
+        ));
+        let report = detector.into_report("users".parse()?, UnixTimestamp::from_millis(0));
+        assert_eq!(report.documents_sampled, 2);
+        assert_eq!(
+            report.fields,
+            vec![
+                PiiField {
+                    field_path: "contact.email".to_string(),
+                    kind: PiiKind::Email,
+                    matches: 1,
+                    documents_with_field: 2,
+                },
+                PiiField {
+                    field_path: "contact.phones".to_string(),
+                    kind: PiiKind::Phone,
+                    matches: 1,
+                    documents_with_field: 1,
+                },
+            ]
+        );
+        Ok(())
+    }
+}
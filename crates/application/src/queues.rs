@@ -0,0 +1,119 @@
+//! Queues: named message queues delivered to consumer functions through the
+//! scheduler. See [`model::queues`] for how messages are delivered.
+use common::{
+    document::ParsedDocument,
+    runtime::Runtime,
+};
+use keybroker::Identity;
+use model::queues::{
+    types::{
+        QueueConfig,
+        QueueMessage,
+    },
+    QueuesModel,
+};
+use serde_json::Value as JsonValue;
+
+use crate::Application;
+
+/// How many messages are purged or redriven per transaction.
+const QUEUE_BATCH_SIZE: usize = 1000;
+
+impl<RT: Runtime> Application<RT> {
+    pub async fn list_queues(&self, identity: Identity) -> anyhow::Result<Vec<QueueConfig>> {
+        let mut tx = self.begin(identity).await?;
+        Ok(QueuesModel::new(&mut tx)
+            .list()
+            .await?
+            .into_iter()
+            .map(ParsedDocument::into_value)
+            .collect())
+    }
+
+    pub async fn set_queue(&self, identity: Identity, config: QueueConfig) -> anyhow::Result<()> {
+        let mut tx = self.begin(identity).await?;
+        QueuesModel::new(&mut tx).set(config).await?;
+        self.commit(tx, "set_queue").await?;
+        Ok(())
+    }
+
+    /// Removes the queue `name` and then all of its messages.
+    pub async fn delete_queue(&self, identity: Identity, name: String) -> anyhow::Result<()> {
+        let mut tx = self.begin(identity.clone()).await?;
+        QueuesModel::new(&mut tx).delete(&name).await?;
+        self.commit(tx, "delete_queue").await?;
+        self.purge_queue(identity, name).await?;
+        Ok(())
+    }
+
+    /// Adds `body` to the end of the partition `partition_key`, returning the
+    /// message's sequence number.
+    pub async fn enqueue_queue_message(
+        &self,
+        identity: Identity,
+        name: String,
+        partition_key: String,
+        body: JsonValue,
+    ) -> anyhow::Result<u64> {
+        let mut tx = self.begin(identity).await?;
+        let sequence = QueuesModel::new(&mut tx)
+            .enqueue(&name, partition_key, body)
+            .await?;
+        self.commit(tx, "enqueue_queue_message").await?;
+        Ok(sequence)
+    }
+
+    pub async fn list_queue_messages(
+        &self,
+        identity: Identity,
+        name: String,
+        group: Option<String>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<ParsedDocument<QueueMessage>>> {
+        let mut tx = self.begin(identity).await?;
+        QueuesModel::new(&mut tx)
+            .list_messages(&name, group.as_deref(), limit)
+            .await
+    }
+
+    /// Deletes every message in the queue `name`, returning how many there
+    /// were.
+    pub async fn purge_queue(&self, identity: Identity, name: String) -> anyhow::Result<usize> {
+        let mut total = 0;
+        loop {
+            let mut tx = self.begin(identity.clone()).await?;
+            let count = QueuesModel::new(&mut tx)
+                .purge(&name, QUEUE_BATCH_SIZE)
+                .await?;
+            self.commit(tx, "purge_queue").await?;
+            total += count;
+            if count < QUEUE_BATCH_SIZE {
+                break;
+            }
+        }
+        Ok(total)
+    }
+
+    /// Redelivers every dead-lettered message in the queue `name`, or just
+    /// the ones for `group`, returning how many there were.
+    pub async fn redrive_queue(
+        &self,
+        identity: Identity,
+        name: String,
+        group: Option<String>,
+    ) -> anyhow::Result<usize> {
+        let mut total = 0;
+        loop {
+            let mut tx = self.begin(identity.clone()).await?;
+            let count = QueuesModel::new(&mut tx)
+                .redrive(&name, group.as_deref(), QUEUE_BATCH_SIZE)
+                .await?;
+            self.commit(tx, "redrive_queue").await?;
+            total += count;
+            if count < QUEUE_BATCH_SIZE {
+                break;
+            }
+        }
+        Ok(total)
+    }
+}
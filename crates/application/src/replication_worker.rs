@@ -0,0 +1,200 @@
+//! Follower side of leader-to-follower replication.
+//!
+//! A follower deployment tails the leader's `/api/replication/stream`, which
+//! sends one JSON-encoded [`SerializedReplicatedCommit`] per line, and applies
+//! each commit along with its replication cursor in a single transaction. On
+//! any error it reconnects from the last applied cursor, so commits are
+//! applied exactly once and in order.
+use std::{
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::Context;
+use common::{
+    backoff::Backoff,
+    errors::report_error,
+    http::{
+        fetch::{
+            FetchClient,
+            InternalFetchPurpose,
+        },
+        HttpRequestStream,
+    },
+    runtime::Runtime,
+};
+use database::{
+    replication::{
+        ReplicaApplier,
+        ReplicatedCommit,
+        SerializedReplicatedCommit,
+    },
+    Database,
+};
+use errors::ErrorMetadata;
+use futures::{
+    select_biased,
+    stream,
+    Future,
+    FutureExt,
+    TryStreamExt,
+};
+use http::{
+    HeaderMap,
+    HeaderValue,
+    Method,
+};
+use keybroker::Identity;
+use model::replication::ReplicationStateModel;
+use sync_types::Timestamp;
+use url::Url;
+
+/// Header the follower sends its replication token in.
+pub const REPLICATION_TOKEN_HEADER: &str = "Convex-Replication-Token";
+
+/// How often the leader sends an empty commit to advance an idle follower's
+/// cursor.
+pub const REPLICATION_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How long the follower waits for data from the leader before reconnecting.
+const REPLICATION_IDLE_TIMEOUT: Duration = Duration::from_secs(120);
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+#[derive(Clone)]
+pub struct ReplicationConfig {
+    /// Origin of the leader deployment, e.g. `https://happy-animal-123.convex.cloud`.
+    pub leader_url: Url,
+    /// Token issued by the leader's `/api/replication/token`.
+    pub token: String,
+    /// Leader timestamp to start after if nothing has been replicated yet.
+    pub start_ts: Timestamp,
+}
+
+/// The error returned for writes to a read-only replica.
+pub fn read_only_replica_error() -> ErrorMetadata {
+    ErrorMetadata::bad_request(
+        "ReadOnlyReplica",
+        "This deployment is a read-only replica. Run mutations on the leader deployment instead.",
+    )
+}
+
+pub struct ReplicationWorker<RT: Runtime> {
+    runtime: RT,
+    database: Database<RT>,
+    fetch_client: Arc<dyn FetchClient>,
+    config: ReplicationConfig,
+}
+
+impl<RT: Runtime> ReplicationWorker<RT> {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        runtime: RT,
+        database: Database<RT>,
+        fetch_client: Arc<dyn FetchClient>,
+        config: ReplicationConfig,
+    ) -> impl Future<Output = ()> + Send {
+        let worker = Self {
+            runtime,
+            database,
+            fetch_client,
+            config,
+        };
+        async move {
+            tracing::info!(
+                "Starting ReplicationWorker from leader {}",
+                worker.config.leader_url
+            );
+            let mut backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
+            loop {
+                if let Err(mut e) = worker.run(&mut backoff).await {
+                    report_error(&mut e);
+                }
+                let delay = backoff.fail(&mut worker.runtime.rng());
+                tracing::warn!("Replication stream disconnected, reconnecting in {delay:?}");
+                worker.runtime.wait(delay).await;
+            }
+        }
+    }
+
+    async fn run(&self, backoff: &mut Backoff) -> anyhow::Result<()> {
+        let cursor = self.cursor().await?;
+        let mut url = self.config.leader_url.join("/api/replication/stream")?;
+        url.query_pairs_mut()
+            .append_pair("cursor", &u64::from(cursor).to_string());
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            REPLICATION_TOKEN_HEADER,
+            HeaderValue::from_str(&self.config.token)?,
+        );
+        let response = self
+            .fetch_client
+            .internal_fetch(
+                HttpRequestStream {
+                    headers,
+                    url,
+                    method: Method::GET,
+                    body: Box::pin(stream::empty()),
+                },
+                InternalFetchPurpose::Replication,
+            )
+            .await?;
+        if !response.status.is_success() {
+            let status = response.status;
+            let body = response
+                .into_http_response()
+                .await?
+                .body
+                .unwrap_or_default();
+            anyhow::bail!(
+                "Leader replication stream failed with {status}: {}",
+                String::from_utf8_lossy(&body)
+            );
+        }
+        let mut body = response
+            .body
+            .context("Leader replication stream has no body")?;
+        let mut buffer = vec![];
+        loop {
+            let chunk = select_biased! {
+                chunk = body.try_next().fuse() => chunk?,
+                _ = self.runtime.wait(REPLICATION_IDLE_TIMEOUT).fuse() => {
+                    anyhow::bail!("Timed out waiting for the leader's replication stream");
+                },
+            };
+            let Some(chunk) = chunk else {
+                return Ok(());
+            };
+            buffer.extend_from_slice(&chunk);
+            while let Some(newline) = buffer.iter().position(|b| *b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=newline).collect();
+                let commit: SerializedReplicatedCommit = serde_json::from_slice(&line)?;
+                self.apply(commit.try_into()?).await?;
+                backoff.reset();
+            }
+        }
+    }
+
+    async fn cursor(&self) -> anyhow::Result<Timestamp> {
+        let mut tx = self.database.begin(Identity::system()).await?;
+        let state = ReplicationStateModel::new(&mut tx).get().await?;
+        Ok(state.map_or(self.config.start_ts, |state| state.cursor))
+    }
+
+    async fn apply(&self, commit: ReplicatedCommit) -> anyhow::Result<()> {
+        let mut tx = self.database.begin(Identity::system()).await?;
+        if let Some(state) = ReplicationStateModel::new(&mut tx).get().await?
+            && commit.ts <= state.cursor
+        {
+            return Ok(());
+        }
+        let ts = commit.ts;
+        ReplicaApplier::new(&mut tx).apply(commit).await?;
+        ReplicationStateModel::new(&mut tx).set_cursor(ts).await?;
+        self.database
+            .commit_with_write_source(tx, "replication_worker")
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,696 @@
+//! Generates a typed Rust client for a deployment's public functions, so Rust
+//! services calling them over the HTTP API get argument and return types
+//! derived from the functions' validators instead of hand-maintained serde
+//! structs that drift from the deployed code.
+//!
+//! Each module becomes a Rust module with a constant for each function's path,
+//! `{Function}Args` and `{Function}Returns` types, and an async function that
+//! calls it. Functions without validators take and return
+//! `serde_json::Value`.
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
+    fmt::Write,
+};
+
+use common::{
+    schemas::validator::{
+        LiteralValidator,
+        ObjectValidator,
+        Validator,
+    },
+    types::UdfType,
+};
+use model::modules::function_validators::{
+    ArgsValidator,
+    ReturnsValidator,
+};
+
+/// A function to include in the generated client.
+pub struct RustClientFunction {
+    /// The module's path without its extension, e.g. `messages` or
+    /// `folder/file`.
+    pub module: String,
+    pub name: String,
+    pub udf_type: UdfType,
+    pub args: ArgsValidator,
+    pub returns: ReturnsValidator,
+}
+
+const HEADER: &str = r#"// Generated by Convex from the functions deployed to this deployment.
+// Regenerate it with `GET /api/codegen/rust` instead of editing it by hand.
+//
+// Requires the `serde` (with `derive`), `serde_json`, `base64` and `reqwest`
+// (with `json`) crates.
+#![allow(dead_code, unused_imports, clippy::all)]
+
+use ::serde::{
+    de::DeserializeOwned,
+    Deserialize,
+    Serialize,
+};
+
+/// Calls the deployment's functions over its HTTP API.
+#[derive(Clone, Debug)]
+pub struct Client {
+    http: ::reqwest::Client,
+    url: String,
+    authorization: Option<String>,
+}
+
+impl Client {
+    /// A client for the deployment at `url`, like
+    /// `https://happy-animal-123.convex.cloud`.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            http: ::reqwest::Client::new(),
+            url: url.into().trim_end_matches('/').to_string(),
+            authorization: None,
+        }
+    }
+
+    /// Calls functions as the user the token from your auth provider identifies.
+    pub fn with_auth_token(mut self, token: &str) -> Self {
+        self.authorization = Some(format!("Bearer {token}"));
+        self
+    }
+
+    /// Calls functions with a deploy key or admin key.
+    pub fn with_admin_key(mut self, key: &str) -> Self {
+        self.authorization = Some(format!("Convex {key}"));
+        self
+    }
+
+    pub async fn query<A: Serialize, R: DeserializeOwned>(
+        &self,
+        path: &str,
+        args: &A,
+    ) -> Result<R, Error> {
+        self.call("query", path, args).await
+    }
+
+    pub async fn mutation<A: Serialize, R: DeserializeOwned>(
+        &self,
+        path: &str,
+        args: &A,
+    ) -> Result<R, Error> {
+        self.call("mutation", path, args).await
+    }
+
+    pub async fn action<A: Serialize, R: DeserializeOwned>(
+        &self,
+        path: &str,
+        args: &A,
+    ) -> Result<R, Error> {
+        self.call("action", path, args).await
+    }
+
+    async fn call<A: Serialize, R: DeserializeOwned>(
+        &self,
+        kind: &str,
+        path: &str,
+        args: &A,
+    ) -> Result<R, Error> {
+        let mut request = self
+            .http
+            .post(format!("{}/api/{kind}", self.url))
+            .json(&::serde_json::json!({
+                "path": path,
+                "args": args,
+                "format": "convex_encoded_json",
+            }));
+        if let Some(authorization) = &self.authorization {
+            request = request.header("Authorization", authorization);
+        }
+        let response = request.send().await.map_err(Error::Request)?;
+        let status = response.status().as_u16();
+        let body = response.text().await.map_err(Error::Request)?;
+        match ::serde_json::from_str::<FunctionResponse>(&body) {
+            Ok(FunctionResponse::Success { value }) => {
+                ::serde_json::from_value(value).map_err(Error::Decode)
+            },
+            Ok(FunctionResponse::Error {
+                error_message,
+                error_data,
+            }) => Err(Error::Function {
+                message: error_message,
+                data: error_data,
+            }),
+            Err(_) => Err(Error::Http { status, body }),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+enum FunctionResponse {
+    Success {
+        value: ::serde_json::Value,
+    },
+    #[serde(rename_all = "camelCase")]
+    Error {
+        error_message: String,
+        error_data: Option<::serde_json::Value>,
+    },
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// The request couldn't be sent or its response couldn't be read.
+    Request(::reqwest::Error),
+    /// The deployment rejected the request, e.g. because of invalid auth.
+    Http { status: u16, body: String },
+    /// The function threw an error. `data` is set for `ConvexError`s.
+    Function {
+        message: String,
+        data: Option<::serde_json::Value>,
+    },
+    /// The function returned a value that doesn't match the generated types,
+    /// which means this client is out of date.
+    Decode(::serde_json::Error),
+}
+
+impl ::std::fmt::Display for Error {
+    fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+        match self {
+            Error::Request(e) => write!(f, "Request failed: {e}"),
+            Error::Http { status, body } => write!(f, "HTTP {status}: {body}"),
+            Error::Function { message, .. } => write!(f, "{message}"),
+            Error::Decode(e) => write!(f, "Unexpected return value: {e}"),
+        }
+    }
+}
+
+impl ::std::error::Error for Error {}
+
+/// A `v.int64()` value.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Int64(pub i64);
+
+impl Serialize for Int64 {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use ::base64::Engine;
+        use ::serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(
+            "$integer",
+            &::base64::engine::general_purpose::STANDARD.encode(self.0.to_le_bytes()),
+        )?;
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Int64 {
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use ::base64::Engine;
+        #[derive(Deserialize)]
+        struct Encoded {
+            #[serde(rename = "$integer")]
+            integer: String,
+        }
+        let encoded = Encoded::deserialize(deserializer)?;
+        let bytes = ::base64::engine::general_purpose::STANDARD
+            .decode(encoded.integer)
+            .map_err(::serde::de::Error::custom)?;
+        let bytes: [u8; 8] = bytes
+            .try_into()
+            .map_err(|_| ::serde::de::Error::custom("$integer must be 8 bytes"))?;
+        Ok(Int64(i64::from_le_bytes(bytes)))
+    }
+}
+
+/// A `v.bytes()` value.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Bytes(pub Vec<u8>);
+
+impl Serialize for Bytes {
+    fn serialize<S: ::serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use ::base64::Engine;
+        use ::serde::ser::SerializeMap;
+        let mut map = serializer.serialize_map(Some(1))?;
+        map.serialize_entry(
+            "$bytes",
+            &::base64::engine::general_purpose::STANDARD.encode(&self.0),
+        )?;
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for Bytes {
+    fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use ::base64::Engine;
+        #[derive(Deserialize)]
+        struct Encoded {
+            #[serde(rename = "$bytes")]
+            bytes: String,
+        }
+        let encoded = Encoded::deserialize(deserializer)?;
+        ::base64::engine::general_purpose::STANDARD
+            .decode(encoded.bytes)
+            .map(Bytes)
+            .map_err(::serde::de::Error::custom)
+    }
+}
+"#;
+
+const DERIVES: &str = "#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]";
+
+/// Generates the client's source code.
+pub fn generate_rust_client(functions: Vec<RustClientFunction>) -> String {
+    let mut modules: BTreeMap<String, Vec<RustClientFunction>> = BTreeMap::new();
+    for function in functions {
+        if function.udf_type == UdfType::HttpAction {
+            continue;
+        }
+        modules
+            .entry(function.module.clone())
+            .or_default()
+            .push(function);
+    }
+    let mut out = HEADER.to_string();
+    let mut module_names = Names::default();
+    for (module, functions) in modules {
+        let module_name = module_names.claim(snake_case(&module));
+        let mut generator = ModuleGenerator::default();
+        for function in functions {
+            generator.function(&module, function);
+        }
+        writeln!(out, "\n/// Functions in `convex/{module}`.").unwrap();
+        writeln!(out, "pub mod {} {{", escape_ident(&module_name)).unwrap();
+        out.push_str("    use ::serde::{\n        Deserialize,\n        Serialize,\n    };\n\n");
+        out.push_str("    use super::{\n        Bytes,\n        Client,\n        Error,\n        Int64,\n    };\n");
+        for item in generator.items {
+            out.push('\n');
+            for line in item.lines() {
+                if line.is_empty() {
+                    out.push('\n');
+                } else {
+                    writeln!(out, "    {line}").unwrap();
+                }
+            }
+        }
+        out.push_str("}\n");
+    }
+    out
+}
+
+#[derive(Default)]
+struct ModuleGenerator {
+    items: Vec<String>,
+    values: Names,
+    types: Names,
+}
+
+impl ModuleGenerator {
+    fn function(&mut self, module: &str, function: RustClientFunction) {
+        let path = format!("{module}:{}", function.name);
+        let fn_name = self.values.claim(snake_case(&function.name));
+        let const_name = fn_name.to_ascii_uppercase();
+        let type_name = pascal_case(&function.name);
+        let args_type = match &function.args {
+            ArgsValidator::Unvalidated => "::serde_json::Value".to_string(),
+            ArgsValidator::Validated(object) => {
+                let name = self.types.claim(format!("{type_name}Args"));
+                self.object(object, &name);
+                name
+            },
+        };
+        let returns_type = match &function.returns {
+            ReturnsValidator::Unvalidated => "::serde_json::Value".to_string(),
+            ReturnsValidator::Validated(validator) => {
+                let rust_type = self.rust_type(validator, &format!("{type_name}Returns"));
+                if self.types.0.contains(&rust_type) {
+                    rust_type
+                } else {
+                    let name = self.types.claim(format!("{type_name}Returns"));
+                    self.items.push(format!("pub type {name} = {rust_type};\n"));
+                    name
+                }
+            },
+        };
+        let kind = match function.udf_type {
+            UdfType::Query => "query",
+            UdfType::Mutation => "mutation",
+            UdfType::Action => "action",
+            UdfType::HttpAction => unreachable!(),
+        };
+        self.items.push(format!(
+            "pub const {const_name}: &str = {path:?};\n\n/// Calls the `{path}` {kind}.\npub \
+             async fn {}(client: &Client, args: &{args_type}) -> Result<{returns_type}, Error> \
+             {{\n    client.{kind}({const_name}, args).await\n}}\n",
+            escape_ident(&fn_name),
+        ));
+    }
+
+    /// The Rust type for `validator`, naming any struct or enum it needs
+    /// `name`.
+    fn rust_type(&mut self, validator: &Validator, name: &str) -> String {
+        match validator {
+            Validator::Id(_)
+            | Validator::String
+            | Validator::Literal(LiteralValidator::String(_)) => "String".to_string(),
+            Validator::Null => "()".to_string(),
+            Validator::Float64 | Validator::Literal(LiteralValidator::Float64(_)) => {
+                "f64".to_string()
+            },
+            Validator::Int64 | Validator::Literal(LiteralValidator::Int64(_)) => {
+                "Int64".to_string()
+            },
+            Validator::Boolean | Validator::Literal(LiteralValidator::Boolean(_)) => {
+                "bool".to_string()
+            },
+            Validator::Bytes => "Bytes".to_string(),
+            Validator::Array(element) => {
+                format!("Vec<{}>", self.rust_type(element, &format!("{name}Item")))
+            },
+            Validator::Record(_, value) => format!(
+                "::std::collections::BTreeMap<String, {}>",
+                self.rust_type(value, &format!("{name}Value"))
+            ),
+            // Sets and maps are deprecated and have their own encodings.
+            Validator::Set(_) | Validator::Map(..) | Validator::Any => {
+                "::serde_json::Value".to_string()
+            },
+            Validator::Object(object) => {
+                let name = self.types.claim(name.to_string());
+                self.object(object, &name);
+                name
+            },
+            Validator::Union(members) => self.union(members, name),
+        }
+    }
+
+    fn object(&mut self, object: &ObjectValidator, name: &str) {
+        let mut fields = String::new();
+        let mut field_names = Names::default();
+        for (field_name, field) in &object.0 {
+            let field_name: &str = field_name;
+            let rust_name = field_names.claim(snake_case(field_name));
+            let mut rust_type = self.rust_type(
+                field.validator(),
+                &format!("{name}{}", pascal_case(field_name)),
+            );
+            if rust_name != field_name {
+                writeln!(fields, "    #[serde(rename = {field_name:?})]").unwrap();
+            }
+            if field.is_optional() {
+                if !rust_type.starts_with("Option<") {
+                    rust_type = format!("Option<{rust_type}>");
+                }
+                fields
+                    .push_str("    #[serde(default, skip_serializing_if = \"Option::is_none\")]\n");
+            }
+            writeln!(fields, "    pub {}: {rust_type},", escape_ident(&rust_name)).unwrap();
+        }
+        self.items
+            .push(format!("{DERIVES}\npub struct {name} {{\n{fields}}}\n"));
+    }
+
+    fn union(&mut self, members: &[Validator], name: &str) -> String {
+        let non_null: Vec<_> = members
+            .iter()
+            .filter(|member| !matches!(member, Validator::Null))
+            .collect();
+        let nullable = non_null.len() < members.len();
+        let rust_type = match &non_null[..] {
+            [] => return "()".to_string(),
+            [member] => self.rust_type(member, name),
+            _ => {
+                let name = self.types.claim(name.to_string());
+                let string_literals: Option<Vec<&str>> = non_null
+                    .iter()
+                    .map(|member| match member {
+                        Validator::Literal(LiteralValidator::String(s)) => Some(&s[..]),
+                        _ => None,
+                    })
+                    .collect();
+                let enum_body = match string_literals {
+                    Some(literals) => literal_enum(&literals, &name),
+                    None => self.untagged_enum(&non_null, &name),
+                };
+                self.items.push(format!("{DERIVES}\n{enum_body}"));
+                name
+            },
+        };
+        if nullable && !rust_type.starts_with("Option<") {
+            format!("Option<{rust_type}>")
+        } else {
+            rust_type
+        }
+    }
+
+    fn untagged_enum(&mut self, members: &[&Validator], name: &str) -> String {
+        let mut variants = String::new();
+        let mut variant_names = Names::default();
+        for member in members {
+            let variant = variant_names.claim(variant_name(member));
+            let rust_type = self.rust_type(member, &format!("{name}{variant}"));
+            writeln!(variants, "    {variant}({rust_type}),").unwrap();
+        }
+        format!("#[serde(untagged)]\npub enum {name} {{\n{variants}}}\n")
+    }
+}
+
+fn literal_enum(literals: &[&str], name: &str) -> String {
+    let mut variants = String::new();
+    let mut variant_names = Names::default();
+    for literal in literals {
+        let variant = variant_names.claim(pascal_case(literal));
+        writeln!(
+            variants,
+            "    #[serde(rename = {literal:?})]\n    {variant},"
+        )
+        .unwrap();
+    }
+    format!("pub enum {name} {{\n{variants}}}\n")
+}
+
+/// Names an untagged enum variant after its type, or after the literal
+/// value of the first field that has one for objects in a discriminated
+/// union like `v.object({ kind: v.literal("text"), ... })`.
+fn variant_name(validator: &Validator) -> String {
+    match validator {
+        Validator::Object(object) => object
+            .0
+            .values()
+            .find_map(|field| match field.validator() {
+                Validator::Literal(LiteralValidator::String(s)) => Some(pascal_case(s)),
+                _ => None,
+            })
+            .unwrap_or_else(|| "Object".to_string()),
+        Validator::Id(_) => "Id".to_string(),
+        Validator::Null => "Null".to_string(),
+        Validator::Float64 => "Float64".to_string(),
+        Validator::Int64 => "Int64".to_string(),
+        Validator::Boolean => "Boolean".to_string(),
+        Validator::String => "String".to_string(),
+        Validator::Bytes => "Bytes".to_string(),
+        Validator::Literal(_) => "Literal".to_string(),
+        Validator::Array(_) => "Array".to_string(),
+        Validator::Set(_) => "Set".to_string(),
+        Validator::Record(..) => "Record".to_string(),
+        Validator::Map(..) => "Map".to_string(),
+        Validator::Union(_) => "Union".to_string(),
+        Validator::Any => "Any".to_string(),
+    }
+}
+
+/// Hands out unique names, adding a numeric suffix to repeats.
+#[derive(Default)]
+struct Names(BTreeSet<String>);
+
+impl Names {
+    fn claim(&mut self, name: String) -> String {
+        let mut candidate = name.clone();
+        let mut i = 2;
+        while !self.0.insert(candidate.clone()) {
+            candidate = format!("{name}{i}");
+            i += 1;
+        }
+        candidate
+    }
+}
+
+fn words(s: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut current = String::new();
+    let mut prev_lower = false;
+    for c in s.chars() {
+        if !c.is_ascii_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            prev_lower = false;
+            continue;
+        }
+        if c.is_ascii_uppercase() && prev_lower {
+            words.push(std::mem::take(&mut current));
+        }
+        prev_lower = c.is_ascii_lowercase() || c.is_ascii_digit();
+        current.push(c);
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+fn snake_case(s: &str) -> String {
+    let name = words(s)
+        .iter()
+        .map(|word| word.to_ascii_lowercase())
+        .collect::<Vec<_>>()
+        .join("_");
+    let name = if s.starts_with('_') {
+        format!("_{name}")
+    } else {
+        name
+    };
+    match name.chars().next() {
+        None => "value".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("_{name}"),
+        Some(_) => name,
+    }
+}
+
+fn pascal_case(s: &str) -> String {
+    let name: String = words(s)
+        .iter()
+        .map(|word| {
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_ascii_uppercase().to_string() + chars.as_str(),
+                None => String::new(),
+            }
+        })
+        .collect();
+    match name.chars().next() {
+        None => "Value".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("V{name}"),
+        Some(_) if name == "Self" => "Self_".to_string(),
+        Some(_) => name,
+    }
+}
+
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let",
+    "loop", "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return",
+    "static", "struct", "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use",
+    "virtual", "where", "while", "yield",
+];
+
+fn escape_ident(name: &str) -> String {
+    match name {
+        "self" | "super" | "crate" => format!("{name}_"),
+        _ if KEYWORDS.contains(&name) => format!("r#{name}"),
+        _ => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{
+        object_validator,
+        schemas::validator::{
+            FieldValidator,
+            LiteralValidator,
+            Validator,
+        },
+        types::UdfType,
+    };
+    use model::modules::function_validators::{
+        ArgsValidator,
+        ReturnsValidator,
+    };
+
+    use super::{
+        generate_rust_client,
+        pascal_case,
+        snake_case,
+        RustClientFunction,
+    };
+
+    #[test]
+    fn test_names() {
+        assert_eq!(snake_case("listMessages"), "list_messages");
+        assert_eq!(snake_case("folder/someFile"), "folder_some_file");
+        assert_eq!(snake_case("_creationTime"), "_creation_time");
+        assert_eq!(pascal_case("list_messages"), "ListMessages");
+        assert_eq!(pascal_case("in-progress"), "InProgress");
+        assert_eq!(pascal_case("2fa"), "V2fa");
+    }
+
+    #[test]
+    fn test_generate_rust_client() -> anyhow::Result<()> {
+        let status = Validator::Union(vec![
+            Validator::Literal(LiteralValidator::String("sent".to_string().try_into()?)),
+            Validator::Literal(LiteralValidator::String("read".to_string().try_into()?)),
+        ]);
+        let functions = vec![
+            RustClientFunction {
+                module: "messages".to_string(),
+                name: "send".to_string(),
+                udf_type: UdfType::Mutation,
+                args: ArgsValidator::Validated(object_validator!(
+                    "body" => FieldValidator::required_field_type(Validator::String),
+                    "channelId" => FieldValidator::required_field_type(
+                        Validator::Id("channels".parse()?)
+                    ),
+                    "priority" => FieldValidator::optional_field_type(Validator::Int64),
+                )),
+                returns: ReturnsValidator::Validated(Validator::Null),
+            },
+            RustClientFunction {
+                module: "messages".to_string(),
+                name: "list".to_string(),
+                udf_type: UdfType::Query,
+                args: ArgsValidator::Unvalidated,
+                returns: ReturnsValidator::Validated(Validator::Array(Box::new(
+                    Validator::Object(object_validator!(
+                        "body" => FieldValidator::required_field_type(Validator::String),
+                        "status" => FieldValidator::required_field_type(status),
+                        "type" => FieldValidator::required_field_type(Validator::Union(vec![
+                            Validator::String,
+                            Validator::Null,
+                        ])),
+                    )),
+                ))),
+            },
+            RustClientFunction {
+                module: "http".to_string(),
+                name: "default".to_string(),
+                udf_type: UdfType::HttpAction,
+                args: ArgsValidator::Unvalidated,
+                returns: ReturnsValidator::Unvalidated,
+            },
+        ];
+        let source = generate_rust_client(functions);
+        for expected in [
+            "pub mod messages {",
+            "    pub const SEND: &str = \"messages:send\";",
+            "    pub struct SendArgs {\n        pub body: String,\n        #[serde(rename = \
+             \"channelId\")]\n        pub channel_id: String,\n        #[serde(default, \
+             skip_serializing_if = \"Option::is_none\")]\n        pub priority: Option<Int64>,\n",
+            "    pub type SendReturns = ();",
+            "    pub async fn send(client: &Client, args: &SendArgs) -> Result<SendReturns, \
+             Error> {\n        client.mutation(SEND, args).await\n",
+            "    pub async fn list(client: &Client, args: &::serde_json::Value) -> \
+             Result<ListReturns, Error> {\n        client.query(LIST, args).await\n",
+            "    pub type ListReturns = Vec<ListReturnsItem>;",
+            "    pub enum ListReturnsItemStatus {\n        #[serde(rename = \"sent\")]\n        \
+             Sent,\n",
+            "        pub status: ListReturnsItemStatus,",
+            "        pub r#type: Option<String>,",
+        ] {
+            assert!(
+                source.contains(expected),
+                "{expected}\n\nnot in\n\n{source}"
+            );
+        }
+        assert!(!source.contains("pub mod http"));
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+//! Admin operations on scheduled jobs that ran out of attempts, and on the
+//! policy that decides when that happens.
+use common::{
+    document::ParsedDocument,
+    knobs::MAX_JOBS_CANCEL_BATCH,
+    runtime::Runtime,
+};
+use keybroker::Identity;
+use model::{
+    scheduled_job_dead_letters::{
+        types::DeadLetteredJob,
+        DeadLetteredJobsModel,
+    },
+    scheduler_backoff_policy::{
+        types::SchedulerBackoffPolicy,
+        SchedulerBackoffPolicyModel,
+    },
+};
+use value::{
+    DeveloperDocumentId,
+    ResolvedDocumentId,
+};
+
+use crate::Application;
+
+impl<RT: Runtime> Application<RT> {
+    /// Up to `limit` of the most recently dead-lettered jobs, newest first.
+    pub async fn list_dead_lettered_jobs(
+        &self,
+        identity: Identity,
+        limit: usize,
+    ) -> anyhow::Result<Vec<ParsedDocument<DeadLetteredJob>>> {
+        let mut tx = self.begin(identity).await?;
+        DeadLetteredJobsModel::new(&mut tx).list(limit).await
+    }
+
+    /// Schedules a dead-lettered job to run again now and returns the new
+    /// job's id.
+    pub async fn retry_dead_lettered_job(
+        &self,
+        identity: Identity,
+        id: DeveloperDocumentId,
+    ) -> anyhow::Result<ResolvedDocumentId> {
+        let mut tx = self.begin(identity).await?;
+        let job_id = DeadLetteredJobsModel::new(&mut tx).retry(id).await?;
+        self.commit(tx, "retry_dead_lettered_job").await?;
+        Ok(job_id)
+    }
+
+    /// Removes one dead-lettered job, or all of them if `id` is `None`, and
+    /// returns how many were removed.
+    pub async fn purge_dead_lettered_jobs(
+        &self,
+        identity: Identity,
+        id: Option<DeveloperDocumentId>,
+    ) -> anyhow::Result<usize> {
+        if let Some(id) = id {
+            let mut tx = self.begin(identity).await?;
+            DeadLetteredJobsModel::new(&mut tx).delete(id).await?;
+            self.commit(tx, "purge_dead_lettered_jobs").await?;
+            return Ok(1);
+        }
+        let mut total = 0;
+        loop {
+            let mut tx = self.begin(identity.clone()).await?;
+            let count = DeadLetteredJobsModel::new(&mut tx)
+                .purge(*MAX_JOBS_CANCEL_BATCH)
+                .await?;
+            self.commit(tx, "purge_dead_lettered_jobs").await?;
+            total += count;
+            if count < *MAX_JOBS_CANCEL_BATCH {
+                break;
+            }
+        }
+        Ok(total)
+    }
+
+    /// The policy the scheduler retries failed jobs with.
+    pub async fn get_scheduler_backoff_policy(
+        &self,
+        identity: Identity,
+    ) -> anyhow::Result<SchedulerBackoffPolicy> {
+        let mut tx = self.begin(identity).await?;
+        SchedulerBackoffPolicyModel::new(&mut tx).policy().await
+    }
+
+    /// Replaces the scheduler's retry policy, or goes back to the default one
+    /// if `policy` is `None`.
+    pub async fn set_scheduler_backoff_policy(
+        &self,
+        identity: Identity,
+        policy: Option<SchedulerBackoffPolicy>,
+    ) -> anyhow::Result<()> {
+        let mut tx = self.begin(identity).await?;
+        SchedulerBackoffPolicyModel::new(&mut tx)
+            .set(policy)
+            .await?;
+        self.commit(tx, "set_scheduler_backoff_policy").await?;
+        Ok(())
+    }
+}
@@ -70,6 +70,7 @@ use futures_async_stream::try_stream;
 use keybroker::Identity;
 use minitrace::future::FutureExt as _;
 use model::{
+    action_checkpoints::ActionCheckpointsModel,
     backend_state::BackendStateModel,
     modules::ModuleModel,
     scheduled_jobs::{
@@ -84,8 +85,10 @@ use model::{
         SCHEDULED_JOBS_INDEX_BY_COMPLETED_TS,
         SCHEDULED_JOBS_TABLE,
     },
+    scheduler_backoff_policy::SchedulerBackoffPolicyModel,
 };
 use parking_lot::Mutex;
+use serde_json::Value as JsonValue;
 use sync_types::Timestamp;
 use tokio::sync::mpsc;
 use usage_tracking::FunctionUsageTracker;
@@ -96,6 +99,7 @@ use crate::{
     function_log::FunctionExecutionLog,
 };
 
+pub mod dead_letters;
 mod metrics;
 
 pub(crate) const SCHEDULED_JOB_EXECUTED: &str = "scheduled_job_executed";
@@ -458,7 +462,8 @@ impl<RT: Runtime> ScheduledJobContext<RT> {
         }
         let namespace = tx.table_mapping().tablet_namespace(job_id.tablet_id)?;
 
-        let mut backoff = Backoff::new(*SCHEDULED_JOB_INITIAL_BACKOFF, *SCHEDULED_JOB_MAX_BACKOFF);
+        let policy = SchedulerBackoffPolicyModel::new(&mut tx).policy().await?;
+        let mut backoff = Backoff::new(policy.initial_backoff, policy.max_backoff);
         let attempts = &mut job.attempts;
         backoff.set_failures(attempts.count_failures());
         // Only report OCCs that happen repeatedly
@@ -471,6 +476,20 @@ impl<RT: Runtime> ScheduledJobContext<RT> {
         } else {
             attempts.system_errors += 1;
         }
+        attempts.record_error(self.rt.generate_timestamp()?, format!("{system_error:#}"));
+        if policy.is_exhausted(attempts.count_failures()) {
+            tracing::error!(
+                "Scheduled job failed {} times, moving it to the dead letter table",
+                attempts.count_failures()
+            );
+            SchedulerModel::new(&mut tx, namespace)
+                .dead_letter(job_id, job)
+                .await?;
+            self.database
+                .commit_with_write_source(tx, "scheduled_job_dead_letter")
+                .await?;
+            return Ok(());
+        }
         let delay = backoff.fail(&mut self.rt.rng());
         tracing::error!("System error executing job, sleeping {delay:?}");
         job.next_ts = Some(self.rt.generate_timestamp()?.add(delay)?);
@@ -660,9 +679,13 @@ impl<RT: Runtime> ScheduledJobContext<RT> {
         let stats = tx.take_stats();
         let execution_time = start.elapsed();
 
-        if outcome.result.is_ok() {
+        if let Ok(output) = &outcome.result {
             SchedulerModel::new(&mut tx, namespace)
-                .complete(job_id, ScheduledJobState::Success)
+                .complete_with_output(
+                    job_id,
+                    ScheduledJobState::Success,
+                    Some(output.json_value()),
+                )
                 .await?;
             if let Fault::Error(e) = self.pause_client.wait(SCHEDULED_JOB_COMMITTING).await {
                 tracing::info!("Injected error before committing mutation");
@@ -753,18 +776,30 @@ impl<RT: Runtime> ScheduledJobContext<RT> {
                         context.clone(),
                     )
                     .await?;
-                let state = match &completion.outcome.result {
-                    Ok(_) => ScheduledJobState::Success,
-                    Err(e) => ScheduledJobState::Failed(e.to_string()),
+                let (state, output, resumable) = match &completion.outcome.result {
+                    Ok(output) => (ScheduledJobState::Success, Some(output.json_value()), false),
+                    Err(e) => (
+                        ScheduledJobState::Failed(e.to_string()),
+                        None,
+                        is_timeout(e),
+                    ),
                 };
 
-                // Mark the job as completed. Keep trying until we succeed (or
-                // detect the job state has changed). Don't bubble up the error
-                // since otherwise we will lose the original execution logs.
+                // Mark the job as completed, or re-run it from its checkpoint
+                // if it timed out. Keep trying until we succeed (or detect the
+                // job state has changed). Don't bubble up the error since
+                // otherwise we will lose the original execution logs.
                 let mut backoff =
                     Backoff::new(*SCHEDULED_JOB_INITIAL_BACKOFF, *SCHEDULED_JOB_MAX_BACKOFF);
                 while let Err(mut err) = self
-                    .complete_action(job_id, &updated_job, usage_tracker.clone(), state.clone())
+                    .complete_action(
+                        job_id,
+                        &updated_job,
+                        usage_tracker.clone(),
+                        state.clone(),
+                        output.clone(),
+                        resumable,
+                    )
                     .await
                 {
                     let delay = backoff.fail(&mut self.rt.rng());
@@ -778,14 +813,31 @@ impl<RT: Runtime> ScheduledJobContext<RT> {
                 // This case can happen if there is a system error while executing
                 // the action or if backend exits after executing the action but
                 // before updating the state. Since we execute actions at most once,
-                // complete this job and log the error.
-                let message = "Transient error while executing action".to_string();
-                SchedulerModel::new(&mut tx, namespace)
-                    .complete(job_id, ScheduledJobState::Failed(message.clone()))
-                    .await?;
-                self.database
-                    .commit_with_write_source(tx, "scheduled_job_action_error")
-                    .await?;
+                // complete this job and log the error, unless the action saved a
+                // checkpoint to be re-run from.
+                let message = if ActionCheckpointsModel::new(&mut tx)
+                    .resume(job_id.developer_id)
+                    .await?
+                {
+                    SchedulerModel::new(&mut tx, namespace)
+                        .replace(job_id, self.resumed_job(job.clone())?)
+                        .await?;
+                    self.database
+                        .commit_with_write_source(tx, "scheduled_job_action_resume")
+                        .await?;
+                    "Transient error while executing action. Re-running it from its last \
+                     checkpoint."
+                        .to_string()
+                } else {
+                    let message = "Transient error while executing action".to_string();
+                    SchedulerModel::new(&mut tx, namespace)
+                        .complete(job_id, ScheduledJobState::Failed(message.clone()))
+                        .await?;
+                    self.database
+                        .commit_with_write_source(tx, "scheduled_job_action_error")
+                        .await?;
+                    message
+                };
                 // TODO: This is wrong. We don't know the executionId the action has been
                 // started with. We generate a new executionId and use it to log the failures. I
                 // guess the correct behavior here is to store the executionId in the state so
@@ -834,14 +886,25 @@ impl<RT: Runtime> ScheduledJobContext<RT> {
         Ok((new_job.as_ref() == Some(expected_state), tx))
     }
 
+    // Sets a job that's being re-run from its checkpoint back to pending, so
+    // the scheduler picks it up right away.
+    fn resumed_job(&self, mut job: ScheduledJob) -> anyhow::Result<ScheduledJob> {
+        job.state = ScheduledJobState::Pending;
+        job.next_ts = Some(self.rt.generate_timestamp()?);
+        Ok(job)
+    }
+
     // Completes an action in separate transaction. Returns false if the action
-    // state has changed.
+    // state has changed. If `resumable`, the action is re-run from its
+    // checkpoint instead when it has one.
     async fn complete_action(
         &self,
         job_id: ResolvedDocumentId,
         expected_state: &ScheduledJob,
         usage_tracking: FunctionUsageTracker,
         job_state: ScheduledJobState,
+        output: Option<JsonValue>,
+        resumable: bool,
     ) -> anyhow::Result<()> {
         let (success, mut tx) = self
             .new_transaction_for_job_state(job_id, expected_state, usage_tracking)
@@ -852,9 +915,23 @@ impl<RT: Runtime> ScheduledJobContext<RT> {
         }
         let namespace = tx.table_mapping().tablet_namespace(job_id.tablet_id)?;
 
+        if resumable
+            && ActionCheckpointsModel::new(&mut tx)
+                .resume(job_id.developer_id)
+                .await?
+        {
+            SchedulerModel::new(&mut tx, namespace)
+                .replace(job_id, self.resumed_job(expected_state.clone())?)
+                .await?;
+            self.database
+                .commit_with_write_source(tx, "scheduled_job_resume_action")
+                .await?;
+            return Ok(());
+        }
+
         // Remove from the scheduled jobs table
         SchedulerModel::new(&mut tx, namespace)
-            .complete(job_id, job_state)
+            .complete_with_output(job_id, job_state, output)
             .await?;
         self.database
             .commit_with_write_source(tx, "scheduled_job_complete_action")
@@ -863,6 +940,11 @@ impl<RT: Runtime> ScheduledJobContext<RT> {
     }
 }
 
+/// Whether an action failed by running out of time, rather than by throwing.
+fn is_timeout(error: &JsError) -> bool {
+    error.message.contains("execution timed out")
+}
+
 pub struct ScheduledJobGarbageCollector<RT: Runtime> {
     rt: RT,
     database: Database<RT>,
@@ -0,0 +1,707 @@
+//! Bundles of a text or vector index's segments. Building an index on a very
+//! large table can take a deployment a long time, so instead its segments can
+//! be built offline by `build-search-index` from a snapshot export of the
+//! table and imported as a bundle. The deployment then only has to index the
+//! writes since the export.
+//!
+//! A bundle is a ZIP with the index's metadata in `manifest.json` and each of
+//! its segments' objects under `objects/`.
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+};
+
+use anyhow::Context;
+use async_zip::{
+    read::seek::ZipFileReader,
+    write::ZipFileWriter,
+    Compression,
+    ZipEntryBuilder,
+};
+use bytes::Bytes;
+use common::{
+    async_compat::{
+        FuturesAsyncReadCompatExt,
+        TokioAsyncReadCompatExt,
+        TokioAsyncWriteCompatExt,
+    },
+    bootstrap_model::index::{
+        text_index::{
+            FragmentedTextSegment,
+            TextIndexSnapshot,
+            TextIndexSnapshotData,
+            TextIndexState,
+        },
+        vector_index::{
+            FragmentedVectorSegment,
+            VectorIndexSnapshot,
+            VectorIndexSnapshotData,
+            VectorIndexState,
+        },
+        DeveloperIndexConfig,
+        IndexConfig,
+        IndexMetadata,
+        SerializedIndexConfig,
+        TabletIndexMetadata,
+    },
+    components::ComponentId,
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    persistence::RetentionValidator,
+    runtime::Runtime,
+    types::{
+        IndexDescriptor,
+        IndexName,
+        ObjectKey,
+        TableName,
+        Timestamp,
+    },
+};
+use database::{
+    text_index_worker::OfflineTextIndexBuilder,
+    unauthorized_error,
+    vector_index_worker::OfflineVectorIndexBuilder,
+    IndexModel,
+    SystemMetadataModel,
+    Transaction,
+};
+use errors::ErrorMetadata;
+use futures::{
+    io::BufReader,
+    stream::{
+        self,
+        BoxStream,
+    },
+    AsyncBufReadExt,
+    AsyncRead,
+    AsyncReadExt,
+    AsyncWriteExt,
+    FutureExt,
+    StreamExt,
+    TryStreamExt,
+};
+use keybroker::Identity;
+use search::searcher::InProcessSearcher;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::Value as JsonValue;
+use shape_inference::{
+    export_context::GeneratedSchema,
+    ProdConfigWithOptionalFields,
+};
+use storage::{
+    ChannelWriter,
+    LocalDirStorage,
+    Storage,
+};
+use tokio::{
+    io::AsyncWrite,
+    sync::mpsc,
+};
+use tokio_stream::wrappers::ReceiverStream;
+use value::{
+    TableNamespace,
+    TabletId,
+};
+
+use crate::{
+    snapshot_import::{
+        map_zip_error,
+        parse_generated_schema,
+    },
+    Application,
+};
+
+const MANIFEST_PATH: &str = "manifest.json";
+const OBJECTS_PREFIX: &str = "objects/";
+const ZIP_ENTRY_PERMISSIONS: u16 = 0o644;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SerializedSearchIndexManifest {
+    table_name: String,
+    index_descriptor: String,
+    index_config: SerializedIndexConfig,
+}
+
+/// The index a bundle was built for, with its segments as of the snapshot it
+/// was built from.
+#[derive(Clone, Debug)]
+pub struct SearchIndexManifest {
+    pub table_name: TableName,
+    pub index_descriptor: IndexDescriptor,
+    pub index_config: IndexConfig,
+}
+
+impl TryFrom<SearchIndexManifest> for SerializedSearchIndexManifest {
+    type Error = anyhow::Error;
+
+    fn try_from(manifest: SearchIndexManifest) -> anyhow::Result<Self> {
+        Ok(Self {
+            table_name: manifest.table_name.to_string(),
+            index_descriptor: manifest.index_descriptor.to_string(),
+            index_config: manifest.index_config.try_into()?,
+        })
+    }
+}
+
+impl TryFrom<SerializedSearchIndexManifest> for SearchIndexManifest {
+    type Error = anyhow::Error;
+
+    fn try_from(manifest: SerializedSearchIndexManifest) -> anyhow::Result<Self> {
+        Ok(Self {
+            table_name: manifest.table_name.parse()?,
+            index_descriptor: manifest.index_descriptor.parse()?,
+            index_config: manifest.index_config.try_into()?,
+        })
+    }
+}
+
+fn not_backfilled_error() -> ErrorMetadata {
+    ErrorMetadata::bad_request(
+        "SearchIndexNotBackfilled",
+        "Only text and vector indexes that have finished backfilling can be exported",
+    )
+}
+
+/// The snapshot timestamp of a backfilled index and the keys of all of its
+/// segments' objects.
+fn segment_object_keys(config: &IndexConfig) -> anyhow::Result<(Timestamp, Vec<ObjectKey>)> {
+    match config {
+        IndexConfig::Text {
+            on_disk_state:
+                TextIndexState::Backfilled(snapshot) | TextIndexState::SnapshottedAt(snapshot),
+            ..
+        } => {
+            let TextIndexSnapshotData::MultiSegment(segments) = &snapshot.data else {
+                anyhow::bail!(not_backfilled_error());
+            };
+            let keys = segments
+                .iter()
+                .flat_map(|segment| {
+                    [
+                        segment.segment_key.clone(),
+                        segment.id_tracker_key.clone(),
+                        segment.deleted_terms_table_key.clone(),
+                        segment.alive_bitset_key.clone(),
+                    ]
+                })
+                .collect();
+            Ok((snapshot.ts, keys))
+        },
+        IndexConfig::Vector {
+            on_disk_state:
+                VectorIndexState::Backfilled(snapshot) | VectorIndexState::SnapshottedAt(snapshot),
+            ..
+        } => {
+            let VectorIndexSnapshotData::MultiSegment(segments) = &snapshot.data else {
+                anyhow::bail!(not_backfilled_error());
+            };
+            let keys = segments
+                .iter()
+                .flat_map(|segment| {
+                    [
+                        segment.segment_key.clone(),
+                        segment.id_tracker_key.clone(),
+                        segment.deleted_bitset_key.clone(),
+                    ]
+                })
+                .collect();
+            Ok((snapshot.ts, keys))
+        },
+        _ => anyhow::bail!(not_backfilled_error()),
+    }
+}
+
+/// Point a bundled index's segments at the copies of their objects in
+/// `copies`, returning the config of the now backfilled index.
+fn with_copied_segments(
+    config: IndexConfig,
+    copies: &BTreeMap<ObjectKey, ObjectKey>,
+) -> anyhow::Result<IndexConfig> {
+    let copy = |key: &ObjectKey| {
+        copies
+            .get(key)
+            .cloned()
+            .with_context(|| bundle_error(format!("Bundle is missing object {key:?}")))
+    };
+    match config {
+        IndexConfig::Text {
+            developer_config,
+            on_disk_state:
+                TextIndexState::Backfilled(snapshot) | TextIndexState::SnapshottedAt(snapshot),
+        } => {
+            let TextIndexSnapshotData::MultiSegment(segments) = snapshot.data else {
+                anyhow::bail!(not_backfilled_error());
+            };
+            let segments = segments
+                .into_iter()
+                .map(|segment| {
+                    anyhow::Ok(FragmentedTextSegment {
+                        segment_key: copy(&segment.segment_key)?,
+                        id_tracker_key: copy(&segment.id_tracker_key)?,
+                        deleted_terms_table_key: copy(&segment.deleted_terms_table_key)?,
+                        alive_bitset_key: copy(&segment.alive_bitset_key)?,
+                        ..segment
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?;
+            Ok(IndexConfig::Text {
+                developer_config,
+                on_disk_state: TextIndexState::Backfilled(TextIndexSnapshot {
+                    data: TextIndexSnapshotData::MultiSegment(segments),
+                    ts: snapshot.ts,
+                    version: snapshot.version,
+                }),
+            })
+        },
+        IndexConfig::Vector {
+            developer_config,
+            on_disk_state:
+                VectorIndexState::Backfilled(snapshot) | VectorIndexState::SnapshottedAt(snapshot),
+        } => {
+            let VectorIndexSnapshotData::MultiSegment(segments) = snapshot.data else {
+                anyhow::bail!(not_backfilled_error());
+            };
+            let segments = segments
+                .into_iter()
+                .map(|segment| {
+                    anyhow::Ok(FragmentedVectorSegment {
+                        segment_key: copy(&segment.segment_key)?,
+                        id_tracker_key: copy(&segment.id_tracker_key)?,
+                        deleted_bitset_key: copy(&segment.deleted_bitset_key)?,
+                        ..segment
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?;
+            Ok(IndexConfig::Vector {
+                developer_config,
+                on_disk_state: VectorIndexState::Backfilled(VectorIndexSnapshot {
+                    data: VectorIndexSnapshotData::MultiSegment(segments),
+                    ts: snapshot.ts,
+                }),
+            })
+        },
+        _ => anyhow::bail!(not_backfilled_error()),
+    }
+}
+
+fn bundle_error(msg: String) -> ErrorMetadata {
+    ErrorMetadata::bad_request("InvalidSearchIndexBundle", msg)
+}
+
+/// Write a bundle of the index in `manifest`, reading its segments' objects
+/// from `storage`.
+pub async fn write_search_index_bundle<W: AsyncWrite + Unpin>(
+    mut out: W,
+    manifest: SearchIndexManifest,
+    storage: Arc<dyn Storage>,
+) -> anyhow::Result<()> {
+    let (_, object_keys) = segment_object_keys(&manifest.index_config)?;
+    let manifest = SerializedSearchIndexManifest::try_from(manifest)?;
+    let mut writer = ZipFileWriter::new(&mut out);
+
+    let builder = ZipEntryBuilder::new(MANIFEST_PATH.to_string(), Compression::Deflate)
+        .unix_permissions(ZIP_ENTRY_PERMISSIONS);
+    writer
+        .write_entry_whole(builder, &serde_json::to_vec(&manifest)?)
+        .await?;
+    for object_key in object_keys {
+        let mut object = storage
+            .get(&object_key)
+            .await?
+            .with_context(|| format!("Segment object {object_key:?} missing from storage"))?
+            .stream;
+        // Segments are already compressed.
+        let builder = ZipEntryBuilder::new(
+            format!("{OBJECTS_PREFIX}{}", &*object_key),
+            Compression::Stored,
+        )
+        .unix_permissions(ZIP_ENTRY_PERMISSIONS);
+        let mut entry_writer = writer.write_entry_stream(builder.build()).await?;
+        while let Some(chunk) = object.try_next().await? {
+            entry_writer.compat_mut_write().write_all(&chunk).await?;
+        }
+        entry_writer.close().await?;
+    }
+    writer.close().await?;
+    out.compat_write().close().await?;
+    Ok(())
+}
+
+enum OfflineSearchIndexBuilder<RT: Runtime> {
+    Text(OfflineTextIndexBuilder<RT>),
+    Vector(OfflineVectorIndexBuilder<RT>),
+}
+
+/// Build the segments of an index offline from a table's `documents.jsonl`
+/// in a snapshot export taken at `snapshot_ts`, writing them as a bundle to
+/// `out`. `generated_schema` is the table's `generated_schema.jsonl`, if the
+/// export has one.
+pub async fn build_search_index_bundle<RT: Runtime, W: AsyncWrite + Unpin>(
+    runtime: RT,
+    table_name: TableName,
+    index_descriptor: IndexDescriptor,
+    developer_config: DeveloperIndexConfig,
+    snapshot_ts: Timestamp,
+    documents: impl AsyncRead + Unpin,
+    generated_schema: Option<impl AsyncRead + Unpin>,
+    out: W,
+) -> anyhow::Result<()> {
+    let storage: Arc<dyn Storage> = Arc::new(LocalDirStorage::new(runtime.clone())?);
+    let mut builder = match developer_config {
+        DeveloperIndexConfig::Search(developer_config) => {
+            OfflineSearchIndexBuilder::Text(OfflineTextIndexBuilder::new(
+                runtime.clone(),
+                storage.clone(),
+                developer_config,
+                Arc::new(InProcessSearcher::new(runtime.clone()).await?),
+                snapshot_ts,
+            ))
+        },
+        DeveloperIndexConfig::Vector(developer_config) => {
+            OfflineSearchIndexBuilder::Vector(OfflineVectorIndexBuilder::new(
+                runtime.clone(),
+                storage.clone(),
+                developer_config,
+                snapshot_ts,
+            ))
+        },
+        DeveloperIndexConfig::Database(_) => {
+            anyhow::bail!("Only text and vector indexes can be built offline")
+        },
+    };
+
+    let mut generated_schema: Option<GeneratedSchema<ProdConfigWithOptionalFields>> =
+        match generated_schema {
+            Some(reader) => Some(
+                parse_generated_schema("generated_schema.jsonl", BufReader::new(reader)).await?,
+            ),
+            None => None,
+        };
+    let mut reader = BufReader::new(documents);
+    let mut line = String::new();
+    let mut lineno = 1;
+    while reader.read_line(&mut line).await? > 0 {
+        let exported_value: JsonValue = serde_json::from_str(&line)
+            .with_context(|| format!("Invalid JSON on line {lineno} of documents.jsonl"))?;
+        let value = GeneratedSchema::<ProdConfigWithOptionalFields>::apply(
+            &mut generated_schema.as_mut(),
+            exported_value,
+        )
+        .with_context(|| format!("Invalid document on line {lineno} of documents.jsonl"))?;
+        // Segments only refer to documents by their internal ID, so which
+        // tablet they're in doesn't matter.
+        let document = ResolvedDocument::from_database(TabletId::MIN, value)?;
+        match &mut builder {
+            OfflineSearchIndexBuilder::Text(builder) => builder.add_document(document).await?,
+            OfflineSearchIndexBuilder::Vector(builder) => builder.add_document(document).await?,
+        }
+        line.clear();
+        lineno += 1;
+    }
+    let index_config = match builder {
+        OfflineSearchIndexBuilder::Text(builder) => builder.finish().await?,
+        OfflineSearchIndexBuilder::Vector(builder) => builder.finish().await?,
+    };
+    tracing::info!("Built index {table_name}.{index_descriptor} from {lineno} documents");
+    let manifest = SearchIndexManifest {
+        table_name,
+        index_descriptor,
+        index_config,
+    };
+    write_search_index_bundle(out, manifest, storage).await
+}
+
+impl<RT: Runtime> Application<RT> {
+    /// Stream a bundle of a backfilled text or vector index's segments.
+    pub async fn export_search_index(
+        &self,
+        identity: Identity,
+        component: ComponentId,
+        table_name: TableName,
+        index_descriptor: IndexDescriptor,
+    ) -> anyhow::Result<BoxStream<'static, anyhow::Result<Bytes>>> {
+        if !identity.is_admin() {
+            anyhow::bail!(unauthorized_error("export_search_index"));
+        }
+        let index_name = IndexName::new(table_name.clone(), index_descriptor.clone())?;
+        let namespace = TableNamespace::from(component);
+        let mut tx = self.begin(identity).await?;
+        let mut index_model = IndexModel::new(&mut tx);
+        let metadata = match index_model.enabled_index_metadata(namespace, &index_name)? {
+            Some(metadata) => Some(metadata),
+            None => index_model.pending_index_metadata(namespace, &index_name)?,
+        };
+        let Some(metadata) = metadata else {
+            anyhow::bail!(ErrorMetadata::not_found(
+                "IndexNotFound",
+                format!("Index {index_name} not found"),
+            ));
+        };
+        let manifest = SearchIndexManifest {
+            table_name,
+            index_descriptor,
+            index_config: metadata.into_value().config,
+        };
+        // Check up front so the error isn't mid-stream.
+        segment_object_keys(&manifest.index_config)?;
+
+        let (sender, receiver) = mpsc::channel::<Bytes>(1);
+        let writer = ChannelWriter::new(sender, 5 * (1 << 20));
+        let write_bundle = write_search_index_bundle(writer, manifest, self.search_storage.clone())
+            .into_stream()
+            .filter_map(|result| async move { result.err().map(Err) });
+        Ok(stream::select(ReceiverStream::new(receiver).map(Ok), write_bundle).boxed())
+    }
+
+    /// Import the bundle uploaded to `object_key` as the segments of a text
+    /// or vector index that's still backfilling. The index must have the same
+    /// definition as the one the bundle was built for, and the snapshot it
+    /// was built from must still be within the deployment's retention.
+    pub async fn import_search_index(
+        &self,
+        identity: Identity,
+        component: ComponentId,
+        table_name: TableName,
+        index_descriptor: IndexDescriptor,
+        object_key: ObjectKey,
+    ) -> anyhow::Result<()> {
+        if !identity.is_admin() {
+            anyhow::bail!(unauthorized_error("import_search_index"));
+        }
+        let mut reader = self
+            .snapshot_imports_storage
+            .get_reader(&object_key)
+            .await?
+            .compat();
+        let mut zip_reader = ZipFileReader::new(&mut reader)
+            .await
+            .map_err(map_zip_error)?;
+        let filenames: Vec<_> = zip_reader
+            .entries()
+            .iter()
+            .map(|entry| entry.filename().to_string())
+            .collect();
+        let manifest_index = filenames
+            .iter()
+            .position(|filename| filename == MANIFEST_PATH)
+            .with_context(|| bundle_error(format!("Bundle is missing {MANIFEST_PATH}")))?;
+        let mut manifest = vec![];
+        zip_reader
+            .entry_reader(manifest_index)
+            .await
+            .map_err(map_zip_error)?
+            .compat()
+            .read_to_end(&mut manifest)
+            .await?;
+        let manifest: SerializedSearchIndexManifest = serde_json::from_slice(&manifest)
+            .map_err(|e| bundle_error(format!("Invalid {MANIFEST_PATH}: {e}")))?;
+        let manifest = SearchIndexManifest::try_from(manifest)?;
+        let (snapshot_ts, object_keys) = segment_object_keys(&manifest.index_config)?;
+
+        let index_name = IndexName::new(table_name, index_descriptor)?;
+        let namespace = TableNamespace::from(component);
+        let mut tx = self.begin(Identity::system()).await?;
+        self.check_search_index_importable(&mut tx, namespace, &index_name, &manifest, snapshot_ts)
+            .await?;
+
+        let mut copies = BTreeMap::new();
+        for (i, filename) in filenames.iter().enumerate() {
+            let Some(bundled_key) = filename.strip_prefix(OBJECTS_PREFIX) else {
+                continue;
+            };
+            let bundled_key = ObjectKey::try_from(bundled_key.to_string())?;
+            if !object_keys.contains(&bundled_key) {
+                continue;
+            }
+            let mut entry_reader = zip_reader
+                .entry_reader(i)
+                .await
+                .map_err(map_zip_error)?
+                .compat();
+            let mut upload = self.search_storage.start_upload().await?;
+            let mut buf = vec![0u8; 1 << 16];
+            while let bytes_read = entry_reader.read(&mut buf).await?
+                && bytes_read > 0
+            {
+                upload
+                    .write(Bytes::copy_from_slice(&buf[..bytes_read]))
+                    .await?;
+            }
+            copies.insert(bundled_key, upload.complete().await?);
+        }
+        let index_config = with_copied_segments(manifest.index_config.clone(), &copies)?;
+
+        // The index may have changed while the segments were copying.
+        let mut tx = self.begin(Identity::system()).await?;
+        let (id, metadata) = self
+            .check_search_index_importable(&mut tx, namespace, &index_name, &manifest, snapshot_ts)
+            .await?
+            .into_id_and_value();
+        SystemMetadataModel::new_global(&mut tx)
+            .replace(
+                id,
+                IndexMetadata {
+                    name: metadata.name,
+                    config: index_config,
+                }
+                .try_into()?,
+            )
+            .await?;
+        self.commit(tx, "import_search_index").await?;
+        tracing::info!(
+            "Imported {} segment objects into {index_name}",
+            copies.len()
+        );
+        Ok(())
+    }
+
+    /// Returns the metadata of the backfilling index the bundle can be
+    /// imported into.
+    async fn check_search_index_importable(
+        &self,
+        tx: &mut Transaction<RT>,
+        namespace: TableNamespace,
+        index_name: &IndexName,
+        manifest: &SearchIndexManifest,
+        snapshot_ts: Timestamp,
+    ) -> anyhow::Result<ParsedDocument<TabletIndexMetadata>> {
+        let Some(metadata) = IndexModel::new(tx).pending_index_metadata(namespace, index_name)?
+        else {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "IndexNotBackfilling",
+                format!(
+                    "Index {index_name} isn't being backfilled. Push the index's definition \
+                     before importing its segments."
+                ),
+            ));
+        };
+        let config = &metadata.config;
+        anyhow::ensure!(
+            config.is_backfilling(),
+            ErrorMetadata::bad_request(
+                "IndexNotBackfilling",
+                format!("Index {index_name} has already been backfilled"),
+            )
+        );
+        anyhow::ensure!(
+            config.same_config(&manifest.index_config),
+            ErrorMetadata::bad_request(
+                "SearchIndexBundleMismatch",
+                format!(
+                    "The bundle was built for a different definition of {index_name} ({}.{}).",
+                    manifest.table_name, manifest.index_descriptor
+                ),
+            )
+        );
+        // The deployment catches the index up by reading the revisions since
+        // the snapshot, which it only has within its retention.
+        let min_snapshot_ts = self
+            .database
+            .retention_validator()
+            .min_document_snapshot_ts()
+            .await?;
+        anyhow::ensure!(
+            snapshot_ts >= *min_snapshot_ts && snapshot_ts <= *tx.begin_timestamp(),
+            ErrorMetadata::bad_request(
+                "SearchIndexBundleTooOld",
+                format!(
+                    "The bundle was built from a snapshot at {snapshot_ts}, which is outside the \
+                     deployment's retention. Build it from a more recent export."
+                ),
+            )
+        );
+        Ok(metadata)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use common::{
+        bootstrap_model::index::{
+            vector_index::{
+                DeveloperVectorIndexConfig,
+                FragmentedVectorSegment,
+                VectorIndexSnapshot,
+                VectorIndexSnapshotData,
+                VectorIndexState,
+            },
+            IndexConfig,
+        },
+        types::{
+            ObjectKey,
+            Timestamp,
+        },
+    };
+
+    use super::{
+        segment_object_keys,
+        with_copied_segments,
+    };
+
+    fn key(s: &str) -> ObjectKey {
+        ObjectKey::try_from(s.to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_copy_bundled_segments() -> anyhow::Result<()> {
+        let segment = FragmentedVectorSegment {
+            segment_key: key("segment"),
+            id_tracker_key: key("id_tracker"),
+            deleted_bitset_key: key("deleted_bitset"),
+            num_vectors: 10,
+            num_deleted: 0,
+            id: "segment_id".to_string(),
+        };
+        let config = IndexConfig::Vector {
+            developer_config: DeveloperVectorIndexConfig {
+                dimensions: 4u32.try_into()?,
+                vector_field: "embedding".parse()?,
+                filter_fields: Default::default(),
+                distance: Default::default(),
+            },
+            on_disk_state: VectorIndexState::SnapshottedAt(VectorIndexSnapshot {
+                data: VectorIndexSnapshotData::MultiSegment(vec![segment]),
+                ts: Timestamp::must(100),
+            }),
+        };
+        let (ts, keys) = segment_object_keys(&config)?;
+        assert_eq!(ts, Timestamp::must(100));
+        assert_eq!(
+            keys,
+            vec![key("segment"), key("id_tracker"), key("deleted_bitset")]
+        );
+
+        let copies: BTreeMap<_, _> = keys
+            .iter()
+            .map(|k| (k.clone(), key(&format!("copy_of_{}", &**k))))
+            .collect();
+        let IndexConfig::Vector {
+            on_disk_state: VectorIndexState::Backfilled(snapshot),
+            ..
+        } = with_copied_segments(config.clone(), &copies)?
+        else {
+            panic!("Imported index isn't backfilled");
+        };
+        let VectorIndexSnapshotData::MultiSegment(segments) = snapshot.data else {
+            panic!("Unexpected snapshot data");
+        };
+        assert_eq!(segments[0].segment_key, key("copy_of_segment"));
+        assert_eq!(
+            segments[0].deleted_bitset_key,
+            key("copy_of_deleted_bitset")
+        );
+        assert_eq!(segments[0].num_vectors, 10);
+
+        // Every segment object must be in the bundle.
+        assert!(with_copied_segments(config, &BTreeMap::new()).is_err());
+        Ok(())
+    }
+}
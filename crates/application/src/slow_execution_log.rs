@@ -0,0 +1,221 @@
+//! Records function executions that are over the slow execution thresholds in
+//! `_slow_executions`. Slow executions are collected in memory as they're
+//! logged and written periodically, and only the most recent
+//! `SLOW_EXECUTION_LOG_MAX_ROWS` are kept.
+use std::time::Duration;
+
+use common::{
+    backoff::Backoff,
+    errors::report_error,
+    knobs::{
+        SLOW_EXECUTION_LOG_FLUSH_INTERVAL,
+        SLOW_EXECUTION_LOG_MAX_ROWS,
+        SLOW_EXECUTION_ROWS_READ_THRESHOLD,
+        SLOW_EXECUTION_ROWS_WRITTEN_THRESHOLD,
+        SLOW_EXECUTION_THRESHOLD,
+    },
+    pause::PauseClient,
+    runtime::Runtime,
+};
+use database::Database;
+use futures::Future;
+use keybroker::Identity;
+use model::slow_executions::{
+    types::{
+        SlowExecution,
+        SlowExecutionReason,
+        SyscallTiming,
+        TableAccess,
+    },
+    SlowExecutionsModel,
+};
+use usage_tracking::FunctionUsageTracker;
+
+use crate::{
+    function_log::{
+        FunctionExecution,
+        FunctionExecutionLog,
+    },
+    Application,
+};
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// How many slow executions are written or deleted per transaction.
+const BATCH_SIZE: usize = 64;
+
+/// The record for `execution` if it's over any of the thresholds. Cached
+/// query results aren't recorded, since they didn't run again.
+pub(crate) fn slow_execution(execution: &FunctionExecution) -> Option<SlowExecution> {
+    if execution.cached_result {
+        return None;
+    }
+    let tables: Vec<_> = execution
+        .tables_touched
+        .iter()
+        .map(|(table_name, stats)| TableAccess {
+            table: table_name.to_string(),
+            rows_read: stats.rows_read,
+            rows_written: stats.rows_written,
+        })
+        .collect();
+    let rows_read: u64 = tables.iter().map(|table| table.rows_read).sum();
+    let rows_written: u64 = tables.iter().map(|table| table.rows_written).sum();
+
+    let mut reasons = vec![];
+    if let Some(threshold) = *SLOW_EXECUTION_THRESHOLD
+        && execution.execution_time >= threshold.as_secs_f64()
+    {
+        reasons.push(SlowExecutionReason::ExecutionTime);
+    }
+    if *SLOW_EXECUTION_ROWS_READ_THRESHOLD > 0 && rows_read >= *SLOW_EXECUTION_ROWS_READ_THRESHOLD {
+        reasons.push(SlowExecutionReason::RowsRead);
+    }
+    if *SLOW_EXECUTION_ROWS_WRITTEN_THRESHOLD > 0
+        && rows_written >= *SLOW_EXECUTION_ROWS_WRITTEN_THRESHOLD
+    {
+        reasons.push(SlowExecutionReason::RowsWritten);
+    }
+    if reasons.is_empty() {
+        return None;
+    }
+
+    let mut syscalls: Vec<_> = execution
+        .syscall_trace
+        .async_syscalls
+        .iter()
+        .map(|(name, stats)| SyscallTiming {
+            name: name.clone(),
+            invocations: stats.invocations,
+            total_duration_ms: stats.total_duration.as_secs_f64() * 1000.0,
+        })
+        .collect();
+    syscalls.sort_by(|a, b| b.total_duration_ms.total_cmp(&a.total_duration_ms));
+    Some(SlowExecution {
+        udf_type: execution.udf_type,
+        function: execution.params.identifier_str(),
+        started_at: execution.execution_timestamp,
+        reasons,
+        failed: execution.params.is_err(),
+        execution_time_ms: execution.execution_time * 1000.0,
+        args_size_bytes: execution.args_size_bytes,
+        database_read_bytes: execution.usage_stats.database_read_bytes,
+        database_write_bytes: execution.usage_stats.database_write_bytes,
+        syscalls,
+        tables,
+    })
+}
+
+/// Writes the pending slow executions, and deletes the oldest ones over the
+/// cap.
+pub struct SlowExecutionLogWorker<RT: Runtime> {
+    runtime: RT,
+    database: Database<RT>,
+    function_log: FunctionExecutionLog<RT>,
+}
+
+impl<RT: Runtime> SlowExecutionLogWorker<RT> {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        runtime: RT,
+        database: Database<RT>,
+        function_log: FunctionExecutionLog<RT>,
+    ) -> impl Future<Output = ()> + Send {
+        let worker = Self {
+            runtime,
+            database,
+            function_log,
+        };
+        async move {
+            let mut backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
+            while let Err(mut e) = worker.run(&mut backoff).await {
+                report_error(&mut e);
+                let delay = backoff.fail(&mut worker.runtime.rng());
+                tracing::error!("SlowExecutionLogWorker failed, sleeping {delay:?}");
+                worker.runtime.wait(delay).await;
+            }
+        }
+    }
+
+    async fn run(&self, backoff: &mut Backoff) -> anyhow::Result<()> {
+        tracing::info!("Starting SlowExecutionLogWorker");
+        loop {
+            self.runtime.wait(*SLOW_EXECUTION_LOG_FLUSH_INTERVAL).await;
+            if self.flush().await? {
+                self.trim().await?;
+            }
+            backoff.reset();
+        }
+    }
+
+    /// Returns whether any slow executions were written. Executions that
+    /// can't be written are dropped, since the log is best effort.
+    async fn flush(&self) -> anyhow::Result<bool> {
+        let pending = self.function_log.take_slow_executions();
+        for batch in pending.chunks(BATCH_SIZE) {
+            self.database
+                .execute_with_occ_retries(
+                    Identity::system(),
+                    FunctionUsageTracker::new(),
+                    PauseClient::new(),
+                    "slow_execution_log_flush",
+                    |tx| {
+                        async move {
+                            for execution in batch {
+                                SlowExecutionsModel::new(tx)
+                                    .insert(execution.clone())
+                                    .await?;
+                            }
+                            Ok(())
+                        }
+                        .into()
+                    },
+                )
+                .await?;
+        }
+        Ok(!pending.is_empty())
+    }
+
+    async fn trim(&self) -> anyhow::Result<()> {
+        loop {
+            let (_, deleted, _) = self
+                .database
+                .execute_with_occ_retries(
+                    Identity::system(),
+                    FunctionUsageTracker::new(),
+                    PauseClient::new(),
+                    "slow_execution_log_trim",
+                    |tx| {
+                        async move {
+                            SlowExecutionsModel::new(tx)
+                                .trim(*SLOW_EXECUTION_LOG_MAX_ROWS, BATCH_SIZE)
+                                .await
+                        }
+                        .into()
+                    },
+                )
+                .await?;
+            if deleted < BATCH_SIZE {
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl<RT: Runtime> Application<RT> {
+    /// Up to `limit` of the most recent slow executions, newest first.
+    pub async fn list_slow_executions(
+        &self,
+        identity: Identity,
+        limit: usize,
+    ) -> anyhow::Result<Vec<SlowExecution>> {
+        let mut tx = self.begin(identity).await?;
+        Ok(SlowExecutionsModel::new(&mut tx)
+            .list(limit)
+            .await?
+            .into_iter()
+            .map(|execution| execution.into_value())
+            .collect())
+    }
+}
@@ -23,6 +23,11 @@ use async_zip::{
     },
 };
 use bytes::Bytes;
+use chrono::{
+    DateTime,
+    NaiveDate,
+    NaiveDateTime,
+};
 use common::{
     async_compat::{
         FuturesAsyncReadCompatExt,
@@ -53,7 +58,14 @@ use common::{
     },
     pause::PauseClient,
     runtime::Runtime,
-    schemas::DatabaseSchema,
+    schemas::{
+        validator::{
+            LiteralValidator,
+            Validator,
+        },
+        DatabaseSchema,
+        DocumentSchema,
+    },
     types::{
         FieldName,
         MemberId,
@@ -69,6 +81,8 @@ use database::{
     ImportFacingModel,
     IndexModel,
     SchemaModel,
+    Snapshot,
+    StreamingExportTableFilter,
     TableModel,
     Transaction,
     TransactionReadSet,
@@ -116,19 +130,31 @@ use model::{
         DeploymentAuditLogModel,
     },
     file_storage::{
+        types::{
+            validate_file_storage_filename,
+            validate_file_storage_tags,
+        },
         FILE_STORAGE_TABLE,
         FILE_STORAGE_VIRTUAL_TABLE,
     },
     snapshot_imports::{
         types::{
+            CsvColumnType,
             ImportFormat,
             ImportMode,
+            ImportProgress,
             ImportRequestor,
             ImportState,
             ImportTableCheckpoint,
+            ImportTableSelection,
             SnapshotImport,
+            SnapshotImportUpload,
+            SnapshotImportUploadChunk,
         },
         SnapshotImportModel,
+        SnapshotImportUploadModel,
+        SNAPSHOT_IMPORTS_TABLE,
+        SNAPSHOT_IMPORT_UPLOADS_TABLE,
     },
 };
 use regex::Regex;
@@ -146,9 +172,12 @@ use shape_inference::{
     ShapeConfig,
 };
 use storage::{
+    BufferedUpload,
     Storage,
     StorageExt,
+    StorageGetStream,
     StorageObjectReader,
+    Upload,
 };
 use strum::AsRefStr;
 use sync_types::{
@@ -170,6 +199,7 @@ use value::{
     ConvexObject,
     ConvexValue,
     IdentifierFieldName,
+    InternalId,
     ResolvedDocumentId,
     Size,
     TableMapping,
@@ -423,7 +453,10 @@ impl<RT: Runtime> SnapshotImportWorker<RT> {
                         *count += 1;
                     }
                     if !tables_missing_id_field.contains(current_component_table)
-                        && exported_value.get(&**ID_FIELD).is_none()
+                        && (exported_value.get(&**ID_FIELD).is_none()
+                            || snapshot_import
+                                .table_selection
+                                .regenerates_ids(current_table))
                     {
                         tables_missing_id_field.insert(current_component_table.clone());
                     }
@@ -622,7 +655,7 @@ impl<RT: Runtime> SnapshotImportWorker<RT> {
             },
         }
         match self.attempt_perform_import(snapshot_import).await {
-            Ok((ts, num_rows_written)) => {
+            Ok((ts, num_rows_written, id_mapping_object_key)) => {
                 self.database
                     .execute_with_overloaded_retries(
                         Identity::system(),
@@ -633,7 +666,12 @@ impl<RT: Runtime> SnapshotImportWorker<RT> {
                             async {
                                 let mut import_model = SnapshotImportModel::new(tx);
                                 import_model
-                                    .complete_import(import_id, ts, num_rows_written)
+                                    .complete_import(
+                                        import_id,
+                                        ts,
+                                        num_rows_written,
+                                        id_mapping_object_key.clone(),
+                                    )
                                     .await?;
                                 Ok(())
                             }
@@ -699,8 +737,27 @@ impl<RT: Runtime> SnapshotImportWorker<RT> {
     async fn attempt_perform_import(
         &mut self,
         snapshot_import: ParsedDocument<SnapshotImport>,
-    ) -> anyhow::Result<(Timestamp, u64)> {
+    ) -> anyhow::Result<(Timestamp, u64, Option<ObjectKey>)> {
         self.fail_if_too_old(&snapshot_import)?;
+        let mut remapper = ImportIdRemapper::new(
+            snapshot_import.id(),
+            snapshot_import.table_selection.clone(),
+            self.snapshot_imports_storage.clone(),
+        );
+        let prepared_tables = if snapshot_import.table_selection.rewrite_references {
+            let (_, objects) = self.parse_import(snapshot_import.id()).await?;
+            prepare_tables_with_new_ids(
+                &self.database,
+                &Identity::system(),
+                snapshot_import.mode,
+                objects,
+                Some(snapshot_import.id()),
+                &mut remapper,
+            )
+            .await?
+        } else {
+            TableMapping::new()
+        };
         let (initial_schemas, objects) = self.parse_import(snapshot_import.id()).await?;
 
         let usage = FunctionUsageTracker::new();
@@ -715,8 +772,11 @@ impl<RT: Runtime> SnapshotImportWorker<RT> {
             Some(snapshot_import.id()),
             snapshot_import.requestor.clone(),
             &self.usage_tracking,
+            prepared_tables,
+            Some(&mut remapper),
         )
         .await?;
+        let id_mapping_object_key = remapper.finish().await?;
 
         // Truncate list of table names to avoid storing too much data in
         // audit log object.
@@ -764,7 +824,7 @@ impl<RT: Runtime> SnapshotImportWorker<RT> {
             object_attributes.size,
         );
 
-        Ok((ts, total_documents_imported))
+        Ok((ts, total_documents_imported, id_mapping_object_key))
     }
 
     async fn parse_import(
@@ -774,7 +834,7 @@ impl<RT: Runtime> SnapshotImportWorker<RT> {
         SchemasForImport,
         Peekable<BoxStream<'_, anyhow::Result<ImportUnit>>>,
     )> {
-        let (object_key, format, component_path) = {
+        let (object_key, format, component_path, table_selection) = {
             let mut tx = self.database.begin(Identity::system()).await?;
             let mut model = SnapshotImportModel::new(&mut tx);
             let snapshot_import = model.get(import_id).await?.context("import not found")?;
@@ -782,41 +842,18 @@ impl<RT: Runtime> SnapshotImportWorker<RT> {
                 snapshot_import.object_key.clone(),
                 snapshot_import.format.clone(),
                 snapshot_import.component_path.clone(),
+                snapshot_import.table_selection.clone(),
             )
         };
-        let body_stream = move || {
-            let object_key = object_key.clone();
-            async move { self.read_snapshot_import(&object_key).await }
-        };
-        let objects = parse_objects(format.clone(), component_path.clone(), body_stream).boxed();
-
-        // Remapping could be more extensive here, it's just relatively simple to handle
-        // optional types. We do remapping after parsing rather than during parsing
-        // because it seems expensive to read the data for and parse all objects inside
-        // of a transaction, though I haven't explicitly tested the performance.
-        let mut tx = self.database.begin(Identity::system()).await?;
-
-        let initial_schemas = schemas_for_import(&mut tx).await?;
-
-        let mut components_model = BootstrapComponentsModel::new(&mut tx);
-        let (_, component_id) = components_model
-            .must_component_path_to_ids(&component_path)
-            .with_context(|| ImportError::ComponentMissing(component_path))?;
-        let objects = match format {
-            ImportFormat::Csv(table_name) => {
-                remap_empty_string_by_schema(
-                    TableNamespace::from(component_id),
-                    table_name,
-                    &mut tx,
-                    objects,
-                )
-                .await?
-            },
-            _ => objects,
-        }
-        .peekable();
-        drop(tx);
-        Ok((initial_schemas, objects))
+        parse_stored_import(
+            &self.database,
+            &self.snapshot_imports_storage,
+            object_key,
+            format,
+            component_path,
+            table_selection,
+        )
+        .await
     }
 
     pub async fn read_snapshot_import(
@@ -827,6 +864,150 @@ impl<RT: Runtime> SnapshotImportWorker<RT> {
     }
 }
 
+async fn parse_stored_import<'a, RT: Runtime>(
+    database: &Database<RT>,
+    snapshot_imports_storage: &'a Arc<dyn Storage>,
+    object_key: ObjectKey,
+    format: ImportFormat,
+    component_path: ComponentPath,
+    table_selection: ImportTableSelection,
+) -> anyhow::Result<(
+    SchemasForImport,
+    Peekable<BoxStream<'a, anyhow::Result<ImportUnit>>>,
+)> {
+    let body_stream = move || {
+        let object_key = object_key.clone();
+        async move { snapshot_imports_storage.get_reader(&object_key).await }
+    };
+
+    // Remapping could be more extensive here, it's just relatively simple to handle
+    // optional types. We do remapping after parsing rather than during parsing
+    // because it seems expensive to read the data for and parse all objects inside
+    // of a transaction, though I haven't explicitly tested the performance.
+    let mut tx = database.begin(Identity::system()).await?;
+
+    let initial_schemas = schemas_for_import(&mut tx).await?;
+
+    let mut components_model = BootstrapComponentsModel::new(&mut tx);
+    let (_, component_id) = components_model
+        .must_component_path_to_ids(&component_path)
+        .with_context(|| ImportError::ComponentMissing(component_path.clone()))?;
+    let format = match format {
+        ImportFormat::Csv(table_name, column_types) => {
+            let column_types = csv_column_types_with_schema(
+                TableNamespace::from(component_id),
+                &table_name,
+                column_types,
+                &mut tx,
+            )
+            .await?;
+            ImportFormat::Csv(table_name, column_types)
+        },
+        format => format,
+    };
+    let objects = parse_objects(format.clone(), component_path, body_stream).boxed();
+    let objects = match format {
+        ImportFormat::Csv(table_name, _) => {
+            remap_empty_string_by_schema(
+                TableNamespace::from(component_id),
+                table_name,
+                &mut tx,
+                objects,
+            )
+            .await?
+        },
+        _ => objects,
+    };
+    let objects = if table_selection.is_empty() {
+        objects
+    } else {
+        select_tables_for_import(table_selection, objects).boxed()
+    }
+    .peekable();
+    drop(tx);
+    Ok((initial_schemas, objects))
+}
+
+/// Drops the tables that aren't in the import's allowlist and renames the
+/// rest. Tables that get new `_id`s, including all renamed tables, are left
+/// out of `_tables`, so they are created with new table numbers instead of
+/// conflicting with the table they were exported from.
+#[try_stream(ok = ImportUnit, error = anyhow::Error)]
+async fn select_tables_for_import<'a>(
+    table_selection: ImportTableSelection,
+    objects: BoxStream<'a, anyhow::Result<ImportUnit>>,
+) {
+    // The name in the import of the table that the following objects and
+    // storage files belong to.
+    let mut current_table: Option<TableName> = None;
+    let mut imported_tables = BTreeSet::new();
+    pin_mut!(objects);
+    while let Some(unit) = objects.try_next().await? {
+        match unit {
+            ImportUnit::NewTable(component_path, table_name) => {
+                if table_name == *TABLES_TABLE {
+                    yield ImportUnit::NewTable(component_path, table_name.clone());
+                } else if table_selection.includes(&table_name) {
+                    let target_name = table_selection.target_name(&table_name).clone();
+                    anyhow::ensure!(
+                        imported_tables.insert((component_path.clone(), target_name.clone())),
+                        ErrorMetadata::bad_request(
+                            "InvalidTableRename",
+                            format!(
+                                "Import contains more than one table named {target_name}{}",
+                                component_path.in_component_str()
+                            )
+                        )
+                    );
+                    yield ImportUnit::NewTable(component_path, target_name);
+                }
+                current_table = Some(table_name);
+            },
+            ImportUnit::GeneratedSchema(component_path, table_name, generated_schema) => {
+                if table_selection.includes(&table_name) {
+                    yield ImportUnit::GeneratedSchema(
+                        component_path,
+                        table_selection.target_name(&table_name).clone(),
+                        generated_schema,
+                    );
+                }
+            },
+            ImportUnit::Object(object) => {
+                let Some(table_name) = &current_table else {
+                    anyhow::bail!("parse_objects should start with NewTable");
+                };
+                if *table_name == *TABLES_TABLE {
+                    // Invalid names are left for `parse_tables_table` to report.
+                    let listed_table: Option<TableName> = object
+                        .get("name")
+                        .and_then(|name| name.as_str())
+                        .and_then(|name| name.parse().ok());
+                    if let Some(listed_table) = listed_table
+                        && (!table_selection.includes(&listed_table)
+                            || table_selection
+                                .regenerates_ids(table_selection.target_name(&listed_table)))
+                    {
+                        continue;
+                    }
+                    yield ImportUnit::Object(object);
+                } else if !table_selection.includes(table_name) {
+                    continue;
+                } else {
+                    yield ImportUnit::Object(object);
+                }
+            },
+            ImportUnit::StorageFileChunk(storage_id, chunk) => {
+                if current_table
+                    .as_ref()
+                    .map_or(true, |table_name| table_selection.includes(table_name))
+                {
+                    yield ImportUnit::StorageFileChunk(storage_id, chunk);
+                }
+            },
+        }
+    }
+}
+
 #[derive(AsRefStr, Debug, Error)]
 pub enum ImportError {
     #[error("Only deployment admins can import new tables")]
@@ -859,6 +1040,9 @@ pub enum ImportError {
     #[error("CSV row {0} doesn't have all of the fields in the header")]
     CsvRowMissingFields(usize),
 
+    #[error("CSV row {0} has {2:?} in column \"{1}\", which isn't a valid {3}")]
+    CsvInvalidCell(usize, FieldName, String, CsvColumnType),
+
     #[error("Row {0} wasn't valid JSON: {1}")]
     JsonInvalidRow(usize, serde_json::Error),
 
@@ -909,7 +1093,7 @@ static DOCUMENTS_PATTERN: LazyLock<Regex> =
 static STORAGE_FILE_PATTERN: LazyLock<Regex> =
     LazyLock::new(|| Regex::new(r"(.*/)?_storage/([^/.]+)(?:\.[^/]+)?$").unwrap());
 
-fn map_zip_error(e: ZipError) -> anyhow::Error {
+pub(crate) fn map_zip_error(e: ZipError) -> anyhow::Error {
     match e {
         // UpstreamReadError is probably a transient error from S3.
         ZipError::UpstreamReadError(e) => anyhow::Error::from(e),
@@ -963,7 +1147,7 @@ async fn parse_objects<'a, Fut>(
     Fut: Future<Output = anyhow::Result<StorageObjectReader>> + 'a,
 {
     match format {
-        ImportFormat::Csv(table_name) => {
+        ImportFormat::Csv(table_name, column_types) => {
             let reader = stream_body().await?;
             yield ImportUnit::NewTable(component_path, table_name);
             let mut reader = csv_async::AsyncReader::from_reader(reader);
@@ -985,16 +1169,29 @@ async fn parse_objects<'a, Fut>(
             let mut enumerate_rows = reader.records().enumerate();
             while let Some((i, row_r)) = enumerate_rows.next().await {
                 let lineno = i + 1;
-                let parsed_row = row_r
-                    .map_err(map_csv_error)?
-                    .iter()
-                    .map(parse_csv_cell)
-                    .collect::<Vec<JsonValue>>();
+                let row = row_r.map_err(map_csv_error)?;
                 let mut obj = BTreeMap::new();
-                if field_names.len() != parsed_row.len() {
+                if field_names.len() != row.len() {
                     anyhow::bail!(ImportError::CsvRowMissingFields(lineno));
                 }
-                for (field_name, value) in field_names.iter().zip(parsed_row.into_iter()) {
+                for (field_name, cell) in field_names.iter().zip(row.iter()) {
+                    let value = match column_types.get(field_name) {
+                        Some(column_type) => {
+                            match parse_typed_csv_cell(*column_type, cell).ok_or_else(|| {
+                                ImportError::CsvInvalidCell(
+                                    lineno,
+                                    field_name.clone(),
+                                    cell.to_string(),
+                                    *column_type,
+                                )
+                            })? {
+                                Some(value) => value,
+                                // Leave out empty cells.
+                                None => continue,
+                            }
+                        },
+                        None => parse_csv_cell(cell),
+                    };
                     obj.insert(field_name.to_string(), value);
                 }
                 yield ImportUnit::Object(serde_json::to_value(obj)?);
@@ -1277,7 +1474,7 @@ async fn parse_documents_jsonl<'a, R: TokioAsyncRead + Unpin>(
     }
 }
 
-async fn parse_generated_schema<'a, T: ShapeConfig, R: AsyncRead + Unpin>(
+pub(crate) async fn parse_generated_schema<'a, T: ShapeConfig, R: AsyncRead + Unpin>(
     filename: &str,
     mut entry_reader: BufReader<R>,
 ) -> anyhow::Result<GeneratedSchema<T>> {
@@ -1345,12 +1542,127 @@ fn parse_csv_cell(s: &str) -> JsonValue {
     json!(s)
 }
 
+/// Parse a cell in a column with a known type. Returns `None` if the cell
+/// isn't valid for the type, and `Some(None)` if the cell is empty and should
+/// be left out of the document.
+fn parse_typed_csv_cell(column_type: CsvColumnType, s: &str) -> Option<Option<JsonValue>> {
+    if column_type == CsvColumnType::String {
+        return Some(Some(json!(s)));
+    }
+    let s = s.trim();
+    if s.is_empty() {
+        return Some(None);
+    }
+    let value = match column_type {
+        CsvColumnType::String => unreachable!(),
+        CsvColumnType::Number => {
+            let n = s.parse::<f64>().ok().filter(|n| n.is_finite())?;
+            json!(n)
+        },
+        CsvColumnType::Boolean => {
+            if s.eq_ignore_ascii_case("true") {
+                json!(true)
+            } else if s.eq_ignore_ascii_case("false") {
+                json!(false)
+            } else {
+                return None;
+            }
+        },
+        CsvColumnType::Date => json!(parse_csv_date(s)? as f64),
+    };
+    Some(Some(value))
+}
+
+/// Milliseconds since the Unix epoch for an ISO 8601 date or datetime.
+fn parse_csv_date(s: &str) -> Option<i64> {
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(s) {
+        return Some(datetime.timestamp_millis());
+    }
+    for format in [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M",
+    ] {
+        if let Ok(datetime) = NaiveDateTime::parse_from_str(s, format) {
+            return Some(datetime.and_utc().timestamp_millis());
+        }
+    }
+    let date = NaiveDate::parse_from_str(s, "%Y-%m-%d").ok()?;
+    Some(date.and_hms_opt(0, 0, 0)?.and_utc().timestamp_millis())
+}
+
+/// The CSV column type that produces values matching `validator`, if there is
+/// exactly one.
+fn csv_column_type_for_validator(validator: &Validator) -> Option<CsvColumnType> {
+    match validator {
+        Validator::Float64 | Validator::Literal(LiteralValidator::Float64(_)) => {
+            Some(CsvColumnType::Number)
+        },
+        Validator::Boolean | Validator::Literal(LiteralValidator::Boolean(_)) => {
+            Some(CsvColumnType::Boolean)
+        },
+        Validator::String | Validator::Literal(LiteralValidator::String(_)) | Validator::Id(_) => {
+            Some(CsvColumnType::String)
+        },
+        // Empty cells are left out, so `null` members of a union don't matter.
+        Validator::Union(validators) => validators
+            .iter()
+            .filter(|validator| !matches!(validator, Validator::Null))
+            .map(csv_column_type_for_validator)
+            .all_equal_value()
+            .ok()
+            .flatten(),
+        _ => None,
+    }
+}
+
+/// Fill in types for CSV columns that weren't given one from the table's
+/// validator in the active schema, so e.g. a `v.string()` column of zip codes
+/// isn't parsed as numbers.
+async fn csv_column_types_with_schema<RT: Runtime>(
+    namespace: TableNamespace,
+    table_name: &TableName,
+    mut column_types: BTreeMap<FieldName, CsvColumnType>,
+    tx: &mut Transaction<RT>,
+) -> anyhow::Result<BTreeMap<FieldName, CsvColumnType>> {
+    let Some((_, schema)) = SchemaModel::new(tx, namespace)
+        .get_by_state(SchemaState::Active)
+        .await?
+    else {
+        return Ok(column_types);
+    };
+    let Some(DocumentSchema::Union(object_validators)) = schema
+        .tables
+        .get(table_name)
+        .and_then(|table_schema| table_schema.document_type.as_ref())
+    else {
+        return Ok(column_types);
+    };
+    let mut schema_column_types: BTreeMap<FieldName, Vec<Option<CsvColumnType>>> = BTreeMap::new();
+    for object_validator in object_validators {
+        for (field_name, field_validator) in &object_validator.0 {
+            schema_column_types
+                .entry(field_name.clone().into())
+                .or_default()
+                .push(csv_column_type_for_validator(field_validator.validator()));
+        }
+    }
+    for (field_name, types) in schema_column_types {
+        // Only use a type if every variant of the union agrees on it.
+        if let Ok(Some(column_type)) = types.into_iter().all_equal_value() {
+            column_types.entry(field_name).or_insert(column_type);
+        }
+    }
+    Ok(column_types)
+}
+
 pub async fn upload_import_file<RT: Runtime>(
     application: &Application<RT>,
     identity: Identity,
     format: ImportFormat,
     mode: ImportMode,
     component_path: ComponentPath,
+    table_selection: ImportTableSelection,
     body_stream: BoxStream<'_, anyhow::Result<Bytes>>,
 ) -> anyhow::Result<DeveloperDocumentId> {
     if !identity.is_admin() {
@@ -1365,6 +1677,7 @@ pub async fn upload_import_file<RT: Runtime>(
         component_path,
         object_key,
         ImportRequestor::SnapshotImport,
+        table_selection,
     )
     .await
 }
@@ -1377,6 +1690,7 @@ pub async fn start_stored_import<RT: Runtime>(
     component_path: ComponentPath,
     object_key: ObjectKey,
     requestor: ImportRequestor,
+    table_selection: ImportTableSelection,
 ) -> anyhow::Result<DeveloperDocumentId> {
     let (_, id, _) = application
         .database
@@ -1395,6 +1709,7 @@ pub async fn start_stored_import<RT: Runtime>(
                             component_path.clone(),
                             object_key.clone(),
                             requestor.clone(),
+                            table_selection.clone(),
                         )
                         .await
                 }
@@ -1471,115 +1786,879 @@ pub async fn cancel_import<RT: Runtime>(
     Ok(())
 }
 
-fn wrap_import_err(e: anyhow::Error) -> anyhow::Error {
-    let e = e.wrap_error_message(|msg| format!("Hit an error while importing:\n{msg}"));
-    if let Some(import_err) = e.downcast_ref::<ImportError>() {
-        let error_metadata = import_err.error_metadata();
-        e.context(error_metadata)
-    } else {
-        e
-    }
-}
-
-async fn wait_for_import_worker<RT: Runtime>(
+/// Download the id mapping report of a completed import, which lists the new
+/// `_id` of each imported document that didn't keep its id as JSON lines of
+/// `{"componentPath"?, "table", "oldId", "newId"}`.
+pub async fn import_id_mapping<RT: Runtime>(
     application: &Application<RT>,
     identity: Identity,
     import_id: DeveloperDocumentId,
-) -> anyhow::Result<ParsedDocument<SnapshotImport>> {
-    let snapshot_import = loop {
-        let mut tx = application.begin(identity.clone()).await?;
-        let import_id = import_id.to_resolved(
-            tx.table_mapping()
-                .namespace(TableNamespace::Global)
-                .number_to_tablet(),
-        )?;
-        let mut import_model = SnapshotImportModel::new(&mut tx);
-        let snapshot_import =
-            import_model
-                .get(import_id)
-                .await?
-                .context(ErrorMetadata::not_found(
-                    "ImportNotFound",
-                    format!("import {import_id} not found"),
-                ))?;
-        match &snapshot_import.state {
-            ImportState::Uploaded | ImportState::InProgress { .. } => {
-                let token = tx.into_token()?;
-                application.subscribe(token).await?;
-            },
-            ImportState::WaitingForConfirmation { .. }
-            | ImportState::Completed { .. }
-            | ImportState::Failed(..) => {
-                break snapshot_import;
-            },
+) -> anyhow::Result<(StorageGetStream, String)> {
+    if !identity.is_admin() {
+        anyhow::bail!(ImportError::Unauthorized);
+    }
+    let not_found = || {
+        ErrorMetadata::not_found(
+            "ImportIdMappingNotFound",
+            format!("import {} has no id mapping report", import_id.encode()),
+        )
+    };
+    let object_key = {
+        let mut tx = application.begin(identity).await?;
+        let table_mapping = tx.table_mapping().namespace(TableNamespace::Global);
+        if table_mapping.name_by_number_if_exists(import_id.table())
+            != Some(&*SNAPSHOT_IMPORTS_TABLE)
+        {
+            anyhow::bail!(not_found());
         }
+        let import_id = import_id.to_resolved(table_mapping.number_to_tablet())?;
+        let snapshot_import = SnapshotImportModel::new(&mut tx)
+            .get(import_id)
+            .await?
+            .with_context(not_found)?;
+        snapshot_import
+            .into_value()
+            .id_mapping_object_key
+            .with_context(not_found)?
     };
-    Ok(snapshot_import)
+    let stream = application
+        .snapshot_imports_storage
+        .get(&object_key)
+        .await?
+        .with_context(not_found)?;
+    Ok((
+        stream,
+        format!("import_{}_id_mapping.jsonl", import_id.encode()),
+    ))
 }
 
-pub async fn do_import<RT: Runtime>(
+/// Start a resumable upload of an import file. The file is sent in chunks
+/// with `upload_import_chunk`, possibly over several connections, and
+/// `finish_import_upload` starts the import once every chunk has arrived.
+pub async fn start_import_upload<RT: Runtime>(
     application: &Application<RT>,
     identity: Identity,
-    format: ImportFormat,
-    mode: ImportMode,
-    component_path: ComponentPath,
-    body_stream: BoxStream<'_, anyhow::Result<Bytes>>,
-) -> anyhow::Result<u64> {
-    let import_id = upload_import_file(
-        application,
-        identity.clone(),
-        format,
-        mode,
-        component_path,
-        body_stream,
-    )
-    .await?;
-
-    let snapshot_import = wait_for_import_worker(application, identity.clone(), import_id).await?;
-    match &snapshot_import.state {
-        ImportState::Uploaded | ImportState::InProgress { .. } | ImportState::Completed { .. } => {
-            anyhow::bail!("should be WaitingForConfirmation, is {snapshot_import:?}")
-        },
-        ImportState::WaitingForConfirmation { .. } => {},
-        ImportState::Failed(e) => {
-            anyhow::bail!(ErrorMetadata::bad_request("ImportFailed", e.to_string()))
-        },
+) -> anyhow::Result<DeveloperDocumentId> {
+    if !identity.is_admin() {
+        anyhow::bail!(ImportError::Unauthorized);
     }
+    let (_, id, _) = application
+        .database
+        .execute_with_overloaded_retries(
+            identity,
+            FunctionUsageTracker::new(),
+            PauseClient::new(),
+            "snapshot_import_start_upload",
+            |tx| async { SnapshotImportUploadModel::new(tx).start_upload().await }.into(),
+        )
+        .await?;
+    Ok(id.into())
+}
 
-    perform_import(application, identity.clone(), import_id).await?;
+fn resolve_import_upload_id<RT: Runtime>(
+    tx: &mut Transaction<RT>,
+    upload_id: DeveloperDocumentId,
+) -> anyhow::Result<ResolvedDocumentId> {
+    let table_mapping = tx.table_mapping().namespace(TableNamespace::Global);
+    if table_mapping.name_by_number_if_exists(upload_id.table())
+        != Some(&*SNAPSHOT_IMPORT_UPLOADS_TABLE)
+    {
+        anyhow::bail!(ErrorMetadata::not_found(
+            "ImportUploadNotFound",
+            format!("import upload {} not found", upload_id.encode()),
+        ));
+    }
+    upload_id.to_resolved(table_mapping.number_to_tablet())
+}
 
-    let snapshot_import = wait_for_import_worker(application, identity.clone(), import_id).await?;
-    match &snapshot_import.state {
-        ImportState::Uploaded
-        | ImportState::WaitingForConfirmation { .. }
-        | ImportState::InProgress { .. } => {
-            anyhow::bail!("should be done, is {snapshot_import:?}")
-        },
-        ImportState::Completed {
-            ts: _,
-            num_rows_written,
-        } => Ok(*num_rows_written as u64),
-        ImportState::Failed(e) => {
-            anyhow::bail!(ErrorMetadata::bad_request("ImportFailed", e.to_string()))
-        },
+/// The chunks of a resumable upload that have been received so far. A client
+/// resuming an interrupted upload only needs to send the other chunks.
+pub async fn import_upload_status<RT: Runtime>(
+    application: &Application<RT>,
+    identity: Identity,
+    upload_id: DeveloperDocumentId,
+) -> anyhow::Result<Vec<SnapshotImportUploadChunk>> {
+    if !identity.is_admin() {
+        anyhow::bail!(ImportError::Unauthorized);
     }
+    let mut tx = application.begin(identity).await?;
+    let upload_id = resolve_import_upload_id(&mut tx, upload_id)?;
+    let upload = SnapshotImportUploadModel::new(&mut tx)
+        .must_get(upload_id)
+        .await?;
+    Ok(upload.into_value().chunks)
 }
 
-/// Clears tables atomically.
-/// Returns number of documents deleted.
-/// This is implemented as an import of empty tables in Replace mode.
-pub async fn clear_tables<RT: Runtime>(
+/// Store one chunk of a resumable upload after checking it against the
+/// checksum the client computed. Chunks can arrive in any order, and sending
+/// a chunk again replaces it, so a client can retry any chunk whose response
+/// it didn't receive.
+pub async fn upload_import_chunk<RT: Runtime>(
     application: &Application<RT>,
-    identity: &Identity,
-    table_names: Vec<(ComponentPath, TableName)>,
-) -> anyhow::Result<u64> {
-    let usage = FunctionUsageTracker::new();
+    identity: Identity,
+    upload_id: DeveloperDocumentId,
+    index: u32,
+    expected_sha256: Sha256Digest,
+    chunk: Bytes,
+) -> anyhow::Result<()> {
+    if !identity.is_admin() {
+        anyhow::bail!(ImportError::Unauthorized);
+    }
+    let actual_sha256 = value::sha256::Sha256::hash(&chunk);
+    if actual_sha256 != expected_sha256 {
+        anyhow::bail!(ErrorMetadata::bad_request(
+            "Sha256Mismatch",
+            format!(
+                "Sha256 mismatch for chunk {index}. Expected: {} Actual: {}",
+                expected_sha256.as_base64(),
+                actual_sha256.as_base64()
+            ),
+        ));
+    }
+    // Skip the write if this is a retry of a chunk that was already stored.
+    if let Some(existing) = import_upload_status(application, identity.clone(), upload_id)
+        .await?
+        .into_iter()
+        .find(|existing| existing.index == index)
+        && existing.sha256 == actual_sha256
+    {
+        return Ok(());
+    }
 
-    let initial_schemas = {
-        let mut tx = application.begin(identity.clone()).await?;
-        schemas_for_import(&mut tx).await?
+    let storage = &application.snapshot_imports_storage;
+    let size = chunk.len() as i64;
+    let mut upload = storage.start_upload().await?;
+    upload.write(chunk).await?;
+    let object_key = upload.complete().await?;
+    let new_chunk = SnapshotImportUploadChunk {
+        index,
+        object_key: object_key.clone(),
+        sha256: actual_sha256,
+        size,
     };
-
+    let result = application
+        .database
+        .execute_with_overloaded_retries(
+            identity,
+            FunctionUsageTracker::new(),
+            PauseClient::new(),
+            "snapshot_import_upload_chunk",
+            |tx| {
+                let new_chunk = new_chunk.clone();
+                async move {
+                    let upload_id = resolve_import_upload_id(tx, upload_id)?;
+                    SnapshotImportUploadModel::new(tx)
+                        .record_chunk(upload_id, new_chunk)
+                        .await
+                }
+                .into()
+            },
+        )
+        .await;
+    let stale_object_key = match result {
+        Ok((_, replaced, _)) => replaced.map(|replaced| replaced.object_key),
+        Err(e) => {
+            if let Err(mut delete_err) = storage.delete_object(&object_key).await {
+                report_error(&mut delete_err);
+            }
+            return Err(e);
+        },
+    };
+    if let Some(stale_object_key) = stale_object_key {
+        storage.delete_object(&stale_object_key).await?;
+    }
+    Ok(())
+}
+
+/// Streams the chunks of a resumable upload in order, checking that each one
+/// is still intact in storage.
+#[try_stream(ok = Bytes, error = anyhow::Error)]
+async fn read_import_upload_chunks(
+    storage: Arc<dyn Storage>,
+    chunks: Vec<SnapshotImportUploadChunk>,
+) {
+    for chunk in chunks {
+        let stream = storage
+            .get(&chunk.object_key)
+            .await?
+            .with_context(|| format!("chunk {} is missing from storage", chunk.index))?
+            .stream;
+        let mut hasher = value::sha256::Sha256::new();
+        let mut size = 0;
+        pin_mut!(stream);
+        while let Some(bytes) = stream.try_next().await? {
+            hasher.update(&bytes);
+            size += bytes.len() as i64;
+            yield bytes;
+        }
+        anyhow::ensure!(
+            size == chunk.size && hasher.finalize() == chunk.sha256,
+            "chunk {} was corrupted in storage",
+            chunk.index
+        );
+    }
+}
+
+/// Assemble the chunks of a resumable upload into a single file and start
+/// importing it. `num_chunks` is the number of chunks the client split the
+/// file into, so a missing final chunk is caught.
+pub async fn finish_import_upload<RT: Runtime>(
+    application: &Application<RT>,
+    identity: Identity,
+    upload_id: DeveloperDocumentId,
+    num_chunks: u32,
+    format: ImportFormat,
+    mode: ImportMode,
+    component_path: ComponentPath,
+    table_selection: ImportTableSelection,
+) -> anyhow::Result<DeveloperDocumentId> {
+    if !identity.is_admin() {
+        anyhow::bail!(ImportError::Unauthorized);
+    }
+    let chunks = import_upload_status(application, identity.clone(), upload_id).await?;
+    let upload = SnapshotImportUpload {
+        member_id: None,
+        chunks,
+    };
+    let missing_chunks = upload.missing_chunks(num_chunks);
+    if !missing_chunks.is_empty() {
+        anyhow::bail!(ErrorMetadata::bad_request(
+            "ImportUploadIncomplete",
+            format!(
+                "{} of {num_chunks} chunks haven't been uploaded: {}",
+                missing_chunks.len(),
+                missing_chunks.iter().take(10).join(", ")
+            ),
+        ));
+    }
+    if let Some(extra) = upload.chunks.iter().find(|chunk| chunk.index >= num_chunks) {
+        anyhow::bail!(ErrorMetadata::bad_request(
+            "ImportUploadIncomplete",
+            format!(
+                "Chunk {} was uploaded but the upload only has {num_chunks} chunks",
+                extra.index
+            ),
+        ));
+    }
+
+    let storage = application.snapshot_imports_storage.clone();
+    let mut file = storage.start_upload().await?;
+    let mut chunk_stream =
+        read_import_upload_chunks(storage.clone(), upload.chunks.clone()).boxed();
+    file.try_write_parallel(&mut chunk_stream).await?;
+    drop(chunk_stream);
+    let object_key = file.complete().await?;
+
+    let import_id = start_stored_import(
+        application,
+        identity.clone(),
+        format,
+        mode,
+        component_path,
+        object_key,
+        ImportRequestor::SnapshotImport,
+        table_selection,
+    )
+    .await?;
+
+    application
+        .database
+        .execute_with_overloaded_retries(
+            identity,
+            FunctionUsageTracker::new(),
+            PauseClient::new(),
+            "snapshot_import_finish_upload",
+            |tx| {
+                async {
+                    let upload_id = resolve_import_upload_id(tx, upload_id)?;
+                    SnapshotImportUploadModel::new(tx).delete(upload_id).await
+                }
+                .into()
+            },
+        )
+        .await?;
+    for chunk in upload.chunks {
+        storage.delete_object(&chunk.object_key).await?;
+    }
+    Ok(import_id)
+}
+
+fn wrap_import_err(e: anyhow::Error) -> anyhow::Error {
+    let e = e.wrap_error_message(|msg| format!("Hit an error while importing:\n{msg}"));
+    if let Some(import_err) = e.downcast_ref::<ImportError>() {
+        let error_metadata = import_err.error_metadata();
+        e.context(error_metadata)
+    } else {
+        e
+    }
+}
+
+async fn wait_for_import_worker<RT: Runtime>(
+    application: &Application<RT>,
+    identity: Identity,
+    import_id: DeveloperDocumentId,
+) -> anyhow::Result<ParsedDocument<SnapshotImport>> {
+    let snapshot_import = loop {
+        let mut tx = application.begin(identity.clone()).await?;
+        let import_id = import_id.to_resolved(
+            tx.table_mapping()
+                .namespace(TableNamespace::Global)
+                .number_to_tablet(),
+        )?;
+        let mut import_model = SnapshotImportModel::new(&mut tx);
+        let snapshot_import =
+            import_model
+                .get(import_id)
+                .await?
+                .context(ErrorMetadata::not_found(
+                    "ImportNotFound",
+                    format!("import {import_id} not found"),
+                ))?;
+        match &snapshot_import.state {
+            ImportState::Uploaded | ImportState::InProgress { .. } => {
+                let token = tx.into_token()?;
+                application.subscribe(token).await?;
+            },
+            ImportState::WaitingForConfirmation { .. }
+            | ImportState::Completed { .. }
+            | ImportState::Failed(..) => {
+                break snapshot_import;
+            },
+        }
+    };
+    Ok(snapshot_import)
+}
+
+pub async fn do_import<RT: Runtime>(
+    application: &Application<RT>,
+    identity: Identity,
+    format: ImportFormat,
+    mode: ImportMode,
+    component_path: ComponentPath,
+    table_selection: ImportTableSelection,
+    body_stream: BoxStream<'_, anyhow::Result<Bytes>>,
+) -> anyhow::Result<u64> {
+    let import_id = upload_import_file(
+        application,
+        identity.clone(),
+        format,
+        mode,
+        component_path,
+        table_selection,
+        body_stream,
+    )
+    .await?;
+
+    let snapshot_import = wait_for_import_worker(application, identity.clone(), import_id).await?;
+    match &snapshot_import.state {
+        ImportState::Uploaded | ImportState::InProgress { .. } | ImportState::Completed { .. } => {
+            anyhow::bail!("should be WaitingForConfirmation, is {snapshot_import:?}")
+        },
+        ImportState::WaitingForConfirmation { .. } => {},
+        ImportState::Failed(e) => {
+            anyhow::bail!(ErrorMetadata::bad_request("ImportFailed", e.to_string()))
+        },
+    }
+
+    perform_import(application, identity.clone(), import_id).await?;
+
+    let snapshot_import = wait_for_import_worker(application, identity.clone(), import_id).await?;
+    match &snapshot_import.state {
+        ImportState::Uploaded
+        | ImportState::WaitingForConfirmation { .. }
+        | ImportState::InProgress { .. } => {
+            anyhow::bail!("should be done, is {snapshot_import:?}")
+        },
+        ImportState::Completed {
+            ts: _,
+            num_rows_written,
+        } => Ok(*num_rows_written as u64),
+        ImportState::Failed(e) => {
+            anyhow::bail!(ErrorMetadata::bad_request("ImportFailed", e.to_string()))
+        },
+    }
+}
+
+/// Maximum number of conflicts a dry run reports. Any further conflicts only
+/// set `ImportDryRunReport::conflicts_truncated`.
+const MAX_DRY_RUN_CONFLICTS: usize = 100;
+
+#[derive(AsRefStr, Clone, Copy, Debug, Eq, PartialEq)]
+#[strum(serialize_all = "camelCase")]
+pub enum ImportDryRunTableAction {
+    /// The table doesn't exist yet.
+    Create,
+    /// The table exists and its documents would be deleted.
+    Replace,
+    /// The imported documents would be added to the existing table.
+    Append,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ImportDryRunTable {
+    pub component_path: ComponentPath,
+    pub table_name: TableName,
+    pub action: ImportDryRunTableAction,
+    pub documents_to_import: u64,
+    pub existing_documents: u64,
+    pub documents_to_delete: u64,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ImportDryRunConflict {
+    pub component_path: ComponentPath,
+    /// None if the conflict isn't specific to a table, e.g. the file couldn't
+    /// be parsed or a schema would be violated.
+    pub table_name: Option<TableName>,
+    pub row_number: Option<usize>,
+    pub message: String,
+}
+
+/// What an import would do, without having done it.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ImportDryRunReport {
+    pub tables: Vec<ImportDryRunTable>,
+    pub conflicts: Vec<ImportDryRunConflict>,
+    pub conflicts_truncated: bool,
+}
+
+impl ImportDryRunReport {
+    fn add_conflict(&mut self, conflict: ImportDryRunConflict) {
+        if self.conflicts.len() < MAX_DRY_RUN_CONFLICTS {
+            self.conflicts.push(conflict);
+        } else {
+            self.conflicts_truncated = true;
+        }
+    }
+
+    fn add_table(
+        &mut self,
+        snapshot: &Snapshot,
+        mode: ImportMode,
+        component_path: &ComponentPath,
+        namespace: TableNamespace,
+        table_name: &TableName,
+        documents_to_import: u64,
+    ) {
+        let display_table_name = if table_name == &*FILE_STORAGE_TABLE {
+            &*FILE_STORAGE_VIRTUAL_TABLE
+        } else {
+            table_name
+        };
+        // Tables listed in `_tables` are added before their documents are counted.
+        if let Some(table) = self.tables.iter_mut().find(|table| {
+            table.component_path == *component_path && table.table_name == *display_table_name
+        }) {
+            table.documents_to_import += documents_to_import;
+            return;
+        }
+        let existing_documents = snapshot.table_summary(namespace, table_name).num_values() as u64;
+        let exists = snapshot
+            .table_mapping()
+            .namespace(namespace)
+            .name_exists(table_name);
+        let (action, documents_to_delete) = match mode {
+            _ if !exists => (ImportDryRunTableAction::Create, 0),
+            ImportMode::Append => (ImportDryRunTableAction::Append, 0),
+            ImportMode::Replace | ImportMode::RequireEmpty => {
+                (ImportDryRunTableAction::Replace, existing_documents)
+            },
+        };
+        self.tables.push(ImportDryRunTable {
+            component_path: component_path.clone(),
+            table_name: display_table_name.clone(),
+            action,
+            documents_to_import,
+            existing_documents,
+            documents_to_delete,
+        });
+    }
+}
+
+/// Returns the message to report for a conflict, or the error itself if it
+/// isn't the user's fault and the dry run should fail.
+fn dry_run_conflict_message(e: anyhow::Error) -> anyhow::Result<String> {
+    let e = match e.downcast_ref::<ImportError>() {
+        Some(import_err) => {
+            let error_metadata = import_err.error_metadata();
+            e.context(error_metadata)
+        },
+        None => e,
+    };
+    if e.is_bad_request() {
+        Ok(e.user_facing_message())
+    } else {
+        Err(e)
+    }
+}
+
+/// Runs all of an import's validation without writing anything, and reports
+/// which tables it would create or replace along with every conflict that
+/// would make it fail.
+pub async fn dry_run_import<RT: Runtime>(
+    application: &Application<RT>,
+    identity: Identity,
+    format: ImportFormat,
+    mode: ImportMode,
+    component_path: ComponentPath,
+    table_selection: ImportTableSelection,
+    body_stream: BoxStream<'_, anyhow::Result<Bytes>>,
+) -> anyhow::Result<ImportDryRunReport> {
+    if !identity.is_admin() {
+        anyhow::bail!(ImportError::Unauthorized);
+    }
+    let object_key = application.upload_snapshot_import(body_stream).await?;
+    let report = dry_run_stored_import(
+        &application.database,
+        &application.snapshot_imports_storage,
+        &identity,
+        format,
+        mode,
+        component_path,
+        table_selection,
+        object_key.clone(),
+    )
+    .await;
+    if let Err(mut e) = application
+        .snapshot_imports_storage
+        .delete_object(&object_key)
+        .await
+    {
+        report_error(&mut e);
+    }
+    report
+}
+
+async fn dry_run_stored_import<RT: Runtime>(
+    database: &Database<RT>,
+    snapshot_imports_storage: &Arc<dyn Storage>,
+    identity: &Identity,
+    format: ImportFormat,
+    mode: ImportMode,
+    component_path: ComponentPath,
+    table_selection: ImportTableSelection,
+    object_key: ObjectKey,
+) -> anyhow::Result<ImportDryRunReport> {
+    let mut report = ImportDryRunReport::default();
+    let (initial_schemas, objects) = match parse_stored_import(
+        database,
+        snapshot_imports_storage,
+        object_key,
+        format,
+        component_path.clone(),
+        table_selection.clone(),
+    )
+    .await
+    {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            report.add_conflict(ImportDryRunConflict {
+                component_path,
+                table_name: None,
+                row_number: None,
+                message: dry_run_conflict_message(e)?,
+            });
+            return Ok(report);
+        },
+    };
+    pin_mut!(objects);
+
+    let snapshot = database.latest_snapshot()?;
+    // Tables are prepared in a single transaction that is never committed, so
+    // later tables are checked against the table numbers of earlier ones.
+    let mut tx = database.begin(identity.clone()).await?;
+    let mut generated_schemas = BTreeMap::new();
+    let mut table_mapping_for_import = TableMapping::new();
+    let mut renumbered_tables = BTreeMap::new();
+    loop {
+        match objects.as_mut().try_next().await {
+            Ok(None) => break,
+            Ok(Some(ImportUnit::GeneratedSchema(component_path, table_name, generated_schema))) => {
+                generated_schemas.insert((component_path, table_name), generated_schema);
+            },
+            Ok(Some(ImportUnit::NewTable(component_path, table_name))) => {
+                if let Err(e) = dry_run_table(
+                    &mut tx,
+                    &snapshot,
+                    mode,
+                    &component_path,
+                    table_name.clone(),
+                    table_selection.regenerates_ids(&table_name),
+                    objects.as_mut(),
+                    &mut generated_schemas,
+                    &mut table_mapping_for_import,
+                    &mut renumbered_tables,
+                    &mut report,
+                )
+                .await
+                {
+                    report.add_conflict(ImportDryRunConflict {
+                        component_path,
+                        table_name: Some(table_name),
+                        row_number: None,
+                        message: dry_run_conflict_message(e)?,
+                    });
+                }
+            },
+            // The rest of a table that already hit a conflict.
+            Ok(Some(ImportUnit::Object(_) | ImportUnit::StorageFileChunk(..))) => {},
+            Err(e) => {
+                report.add_conflict(ImportDryRunConflict {
+                    component_path: component_path.clone(),
+                    table_name: None,
+                    row_number: None,
+                    message: dry_run_conflict_message(e)?,
+                });
+                break;
+            },
+        }
+    }
+
+    let schema_constraints =
+        ImportSchemaConstraints::new(&table_mapping_for_import, initial_schemas);
+    if let Err(e) = schema_constraints.validate(&mut tx).await {
+        report.add_conflict(ImportDryRunConflict {
+            component_path,
+            table_name: None,
+            row_number: None,
+            message: dry_run_conflict_message(e)?,
+        });
+    }
+    Ok(report)
+}
+
+async fn dry_run_table<RT: Runtime>(
+    tx: &mut Transaction<RT>,
+    snapshot: &Snapshot,
+    mode: ImportMode,
+    component_path: &ComponentPath,
+    mut table_name: TableName,
+    regenerates_ids: bool,
+    mut objects: Pin<&mut Peekable<BoxStream<'_, anyhow::Result<ImportUnit>>>>,
+    generated_schemas: &mut BTreeMap<
+        (ComponentPath, TableName),
+        GeneratedSchema<ProdConfigWithOptionalFields>,
+    >,
+    table_mapping_for_import: &mut TableMapping,
+    renumbered_tables: &mut RenumberedTables,
+    report: &mut ImportDryRunReport,
+) -> anyhow::Result<()> {
+    // Documents that get new ids are checked as if they had no `_id`, since
+    // their new ids don't exist yet.
+    let table_number_from_docs = if regenerates_ids {
+        None
+    } else {
+        table_number_for_import(objects.as_mut()).await
+    };
+    if table_name == *FILE_STORAGE_VIRTUAL_TABLE {
+        table_name = FILE_STORAGE_TABLE.clone();
+    }
+    let (_, component_id) = BootstrapComponentsModel::new(tx)
+        .component_path_to_ids(component_path)?
+        .with_context(|| ImportError::ComponentMissing(component_path.clone()))?;
+    let namespace = TableNamespace::from(component_id);
+
+    if table_name == *TABLES_TABLE {
+        let import_tables = parse_tables_table(objects.as_mut()).await?;
+        let tables_in_import = import_tables
+            .iter()
+            .map(|(table_name, _)| table_name.clone())
+            .collect();
+        let import_tables =
+            order_tables_for_import(tx, namespace, import_tables, &tables_in_import);
+        for (table_name, table_number, new_table_number) in import_tables.iter() {
+            let table_id = dry_run_prepare_table(
+                tx,
+                mode,
+                namespace,
+                table_name,
+                *new_table_number,
+                &tables_in_import,
+            )
+            .await?;
+            table_mapping_for_import.insert(
+                table_id.tablet_id,
+                namespace,
+                table_id.table_number,
+                table_name.clone(),
+            );
+            if table_id.table_number != *table_number && !table_name.is_system() {
+                renumbered_tables.insert((namespace, *table_number), table_id.table_number);
+            }
+            report.add_table(snapshot, mode, component_path, namespace, table_name, 0);
+        }
+        return Ok(());
+    }
+
+    let table_id = match table_mapping_for_import
+        .namespace(namespace)
+        .id_and_number_if_exists(&table_name)
+    {
+        Some(table_id) => table_id,
+        None => {
+            let tables_in_import = table_mapping_for_import
+                .iter()
+                .map(|(_, _, _, table_name)| table_name.clone())
+                .collect();
+            let table_number = table_number_without_conflict(
+                tx,
+                namespace,
+                &table_name,
+                table_number_from_docs,
+                &tables_in_import,
+            );
+            let table_id = dry_run_prepare_table(
+                tx,
+                mode,
+                namespace,
+                &table_name,
+                table_number,
+                &tables_in_import,
+            )
+            .await?;
+            table_mapping_for_import.insert(
+                table_id.tablet_id,
+                namespace,
+                table_id.table_number,
+                table_name.clone(),
+            );
+            table_id
+        },
+    };
+    if let Some(table_number) = table_number_from_docs
+        && table_id.table_number != table_number
+        && !table_name.is_system()
+    {
+        renumbered_tables.insert((namespace, table_number), table_id.table_number);
+    }
+
+    let mut generated_schema =
+        generated_schemas.get_mut(&(component_path.clone(), table_name.clone()));
+    let mut table_mapping_for_schema = tx.table_mapping().clone();
+    table_mapping_for_schema.update(table_mapping_for_import.clone());
+    let mut object_ids = BTreeSet::new();
+    let mut num_objects = 0;
+    while let Some(unit) = objects
+        .as_mut()
+        .try_next_if(|line| {
+            matches!(
+                line,
+                ImportUnit::Object(_) | ImportUnit::StorageFileChunk(..)
+            )
+        })
+        .await?
+    {
+        let ImportUnit::Object(mut exported_value) = unit else {
+            continue;
+        };
+        num_objects += 1;
+        if table_name == *FILE_STORAGE_TABLE {
+            continue;
+        }
+        if regenerates_ids && let JsonValue::Object(fields) = &mut exported_value {
+            fields.remove(&**ID_FIELD);
+        }
+        rewrite_renumbered_ids(renumbered_tables, namespace, &mut exported_value);
+        let row_number = num_objects as usize;
+        let result: anyhow::Result<()> = async {
+            let convex_value = GeneratedSchema::<ProdConfigWithOptionalFields>::apply(
+                &mut generated_schema,
+                exported_value,
+            )
+            .map_err(|e| ImportError::InvalidConvexValue(row_number, e))?;
+            let ConvexValue::Object(convex_object) = convex_value else {
+                anyhow::bail!(ImportError::NotAnObject(row_number));
+            };
+            if let Some(id) = convex_object.get(&**ID_FIELD) {
+                if !object_ids.insert(id.clone()) {
+                    anyhow::bail!(ErrorMetadata::bad_request(
+                        "DuplicateId",
+                        format!("Objects in table \"{table_name}\" have duplicate _id fields")
+                    ));
+                }
+            }
+            ImportFacingModel::new(tx)
+                .check_insert(
+                    table_id,
+                    &table_name,
+                    convex_object,
+                    &table_mapping_for_schema,
+                )
+                .await
+        }
+        .await;
+        if let Err(e) = result {
+            report.add_conflict(ImportDryRunConflict {
+                component_path: component_path.clone(),
+                table_name: Some(table_name.clone()),
+                row_number: Some(row_number),
+                message: dry_run_conflict_message(e)?,
+            });
+        }
+    }
+    report.add_table(
+        snapshot,
+        mode,
+        component_path,
+        namespace,
+        &table_name,
+        num_objects,
+    );
+    Ok(())
+}
+
+/// Like `prepare_table_for_import`, but creates the Hidden table in a
+/// transaction the caller won't commit.
+async fn dry_run_prepare_table<RT: Runtime>(
+    tx: &mut Transaction<RT>,
+    mode: ImportMode,
+    namespace: TableNamespace,
+    table_name: &TableName,
+    table_number: Option<TableNumber>,
+    tables_in_import: &BTreeSet<TableName>,
+) -> anyhow::Result<TabletIdAndTableNumber> {
+    check_table_name_for_import(table_name)?;
+    let existing_active_table_id = tx
+        .table_mapping()
+        .namespace(namespace)
+        .id_and_number_if_exists(table_name);
+    match mode {
+        ImportMode::Append => {
+            if let Some(table_id) = existing_active_table_id {
+                return Ok(table_id);
+            }
+        },
+        ImportMode::RequireEmpty => {
+            if !TableModel::new(tx)
+                .table_is_empty(namespace, table_name)
+                .await?
+            {
+                anyhow::bail!(ImportError::TableExists(table_name.clone()));
+            }
+        },
+        ImportMode::Replace => {},
+    }
+    let table_number = table_number.or(existing_active_table_id.map(|id| id.table_number));
+    TableModel::new(tx)
+        .insert_table_for_import(namespace, table_name, table_number, tables_in_import)
+        .await
+}
+
+/// Clears tables atomically.
+/// Returns number of documents deleted.
+/// This is implemented as an import of empty tables in Replace mode.
+pub async fn clear_tables<RT: Runtime>(
+    application: &Application<RT>,
+    identity: &Identity,
+    table_names: Vec<(ComponentPath, TableName)>,
+) -> anyhow::Result<u64> {
+    let usage = FunctionUsageTracker::new();
+
+    let initial_schemas = {
+        let mut tx = application.begin(identity.clone()).await?;
+        schemas_for_import(&mut tx).await?
+    };
+
     let objects = stream::iter(table_names.into_iter().map(|(component_path, table_name)| {
         anyhow::Ok(ImportUnit::NewTable(component_path, table_name))
     }))
@@ -1596,22 +2675,228 @@ pub async fn clear_tables<RT: Runtime>(
         None,
         ImportRequestor::SnapshotImport,
         &application.usage_tracking,
+        TableMapping::new(),
+        None,
+    )
+    .await?;
+
+    let (_ts, documents_deleted) = finalize_import(
+        &application.database,
+        &application.usage_tracking,
+        identity.clone(),
+        None,
+        initial_schemas,
+        table_mapping_for_import,
+        usage,
+        DeploymentAuditLogEvent::ClearTables,
+        ImportRequestor::SnapshotImport,
     )
     .await?;
+    Ok(documents_deleted)
+}
+
+/// Restores user tables to the state they were in at `ts`, which must be
+/// within the retention window. If `table` is set only that table is
+/// restored, and otherwise every user table in every component is.
+///
+/// Like a snapshot import in `Replace` mode, the documents at `ts` are written
+/// to new Hidden tables that atomically replace the current ones, keeping
+/// their `_id`s, `_creationTime`s and table numbers. Tables that are empty
+/// or don't exist at `ts` are cleared, and file storage isn't restored.
+/// Returns the number of documents restored.
+pub async fn restore_tables<RT: Runtime>(
+    application: &Application<RT>,
+    identity: &Identity,
+    ts: Timestamp,
+    table: Option<(ComponentPath, TableName)>,
+) -> anyhow::Result<u64> {
+    if !identity.is_admin() {
+        anyhow::bail!(ImportError::Unauthorized);
+    }
+    let database = &application.database;
+    // Fails if `ts` is in the future or outside of the retention window.
+    database
+        .begin_at_historical_ts(identity.clone(), ts, FunctionUsageTracker::new())
+        .await?;
+    let usage = FunctionUsageTracker::new();
+
+    let (initial_schemas, mut tables_to_clear) = {
+        let mut tx = application.begin(identity.clone()).await?;
+        let initial_schemas = schemas_for_import(&mut tx).await?;
+        let mut current_tables = BTreeSet::new();
+        let table_mapping = tx.table_mapping().clone();
+        for (_, namespace, _, table_name) in table_mapping.iter_active_user_tables() {
+            let component_path = tx
+                .get_component_path(ComponentId::from(namespace))
+                .with_context(|| format!("component for {namespace:?} not found"))?;
+            current_tables.insert((component_path, table_name.clone()));
+        }
+        if let Some(table) = &table {
+            current_tables.retain(|current_table| current_table == table);
+        }
+        (initial_schemas, current_tables)
+    };
+    let tables_in_import: BTreeSet<_> = tables_to_clear
+        .iter()
+        .map(|(_, table_name)| table_name.clone())
+        .collect();
+    let table_filter = StreamingExportTableFilter {
+        table_name: table.as_ref().map(|(_, table_name)| table_name.clone()),
+        component_path: table
+            .as_ref()
+            .map(|(component_path, _)| component_path.clone()),
+        include_hidden: false,
+        ..Default::default()
+    };
+
+    let mut table_mapping_for_import = TableMapping::new();
+    let mut current_table: Option<(ComponentPath, TableName, TabletIdAndTableNumber)> = None;
+    let mut table_mapping_for_schema = TableMapping::new();
+    let mut objects_to_insert = vec![];
+    let mut objects_to_insert_size = 0;
+    let mut num_restored = 0;
+    let mut cursor = None;
+    loop {
+        let page = database
+            .list_snapshot(
+                identity.clone(),
+                Some(ts),
+                cursor,
+                table_filter.clone(),
+                *TRANSACTION_MAX_NUM_USER_WRITES / 2,
+                *TRANSACTION_MAX_NUM_USER_WRITES / 2,
+            )
+            .await?;
+        for (_, component_path, table_name, document) in page.documents {
+            if current_table
+                .as_ref()
+                .map_or(true, |(current_path, current_name, _)| {
+                    (current_path, current_name) != (&component_path, &table_name)
+                })
+            {
+                if let Some((_, current_name, table_id)) = &current_table {
+                    insert_import_objects(
+                        database,
+                        identity,
+                        std::mem::take(&mut objects_to_insert),
+                        current_name,
+                        *table_id,
+                        &table_mapping_for_schema,
+                        usage.clone(),
+                    )
+                    .await?;
+                    objects_to_insert_size = 0;
+                }
+                let (table_id, component_id, _) = prepare_table_for_import(
+                    database,
+                    identity,
+                    ImportMode::Replace,
+                    &component_path,
+                    &table_name,
+                    Some(document.id().developer_id.table()),
+                    &tables_in_import,
+                    None,
+                )
+                .await?;
+                table_mapping_for_import.insert(
+                    table_id.tablet_id,
+                    component_id.into(),
+                    table_id.table_number,
+                    table_name.clone(),
+                );
+                let mut tx = database.begin(identity.clone()).await?;
+                table_mapping_for_schema = tx.table_mapping().clone();
+                table_mapping_for_schema.update(table_mapping_for_import.clone());
+                tables_to_clear.remove(&(component_path.clone(), table_name.clone()));
+                current_table = Some((component_path, table_name, table_id));
+            }
+            let Some((_, current_name, table_id)) = &current_table else {
+                anyhow::bail!("restored document has no table");
+            };
+            let object = document.into_value().0;
+            objects_to_insert_size += object.size();
+            objects_to_insert.push(object);
+            num_restored += 1;
+            if objects_to_insert_size > *TRANSACTION_MAX_USER_WRITE_SIZE_BYTES / 2
+                || objects_to_insert.len() > *TRANSACTION_MAX_NUM_USER_WRITES / 2
+            {
+                insert_import_objects(
+                    database,
+                    identity,
+                    std::mem::take(&mut objects_to_insert),
+                    current_name,
+                    *table_id,
+                    &table_mapping_for_schema,
+                    usage.clone(),
+                )
+                .await?;
+                objects_to_insert_size = 0;
+            }
+        }
+        if !page.has_more {
+            break;
+        }
+        cursor = page
+            .cursor
+            .map(|cursor| (Some(cursor.tablet_id), cursor.developer_id));
+    }
+    if let Some((_, current_name, table_id)) = &current_table {
+        insert_import_objects(
+            database,
+            identity,
+            objects_to_insert,
+            current_name,
+            *table_id,
+            &table_mapping_for_schema,
+            usage.clone(),
+        )
+        .await?;
+    }
+    // Replacing the tables that had no documents at `ts` with empty tables
+    // clears them.
+    for (component_path, table_name) in tables_to_clear {
+        let (table_id, component_id, _) = prepare_table_for_import(
+            database,
+            identity,
+            ImportMode::Replace,
+            &component_path,
+            &table_name,
+            None,
+            &tables_in_import,
+            None,
+        )
+        .await?;
+        table_mapping_for_import.insert(
+            table_id.tablet_id,
+            component_id.into(),
+            table_id.table_number,
+            table_name,
+        );
+    }
 
-    let (_ts, documents_deleted) = finalize_import(
-        &application.database,
+    let table_names = table_mapping_for_import
+        .iter()
+        .map(|(_, _, _, table_name)| table_name.clone())
+        .take(20)
+        .collect();
+    let table_count = table_mapping_for_import.iter().count() as u64;
+    finalize_import(
+        database,
         &application.usage_tracking,
         identity.clone(),
         None,
         initial_schemas,
         table_mapping_for_import,
         usage,
-        DeploymentAuditLogEvent::ClearTables,
+        DeploymentAuditLogEvent::RestoreTables {
+            table_names,
+            table_count,
+            restore_ts: ts,
+        },
         ImportRequestor::SnapshotImport,
     )
     .await?;
-    Ok(documents_deleted)
+    Ok(num_restored)
 }
 
 async fn best_effort_update_progress_message<RT: Runtime>(
@@ -1622,6 +2907,7 @@ async fn best_effort_update_progress_message<RT: Runtime>(
     component_path: &ComponentPath,
     display_table_name: &TableName,
     num_rows_written: i64,
+    progress: &ImportProgress,
 ) {
     // Ignore errors because it's not worth blocking or retrying if we can't
     // send a nice progress message on the first try.
@@ -1637,6 +2923,9 @@ async fn best_effort_update_progress_message<RT: Runtime>(
                 num_rows_written,
             )
             .await?;
+        import_model
+            .update_progress(import_id, progress.clone())
+            .await?;
         database
             .commit_with_write_source(tx, "snapshot_update_progress_msg")
             .await?;
@@ -1651,6 +2940,7 @@ async fn add_checkpoint_message<RT: Runtime>(
     component_path: &ComponentPath,
     display_table_name: &TableName,
     num_rows_written: i64,
+    progress: &ImportProgress,
 ) -> anyhow::Result<()> {
     database
         .execute_with_overloaded_retries(
@@ -1660,7 +2950,8 @@ async fn add_checkpoint_message<RT: Runtime>(
             "snapshot_import_add_checkpoint_message",
             |tx| {
                 async {
-                    SnapshotImportModel::new(tx)
+                    let mut import_model = SnapshotImportModel::new(tx);
+                    import_model
                         .add_checkpoint_message(
                             import_id,
                             checkpoint_message.clone(),
@@ -1668,6 +2959,9 @@ async fn add_checkpoint_message<RT: Runtime>(
                             display_table_name,
                             num_rows_written,
                         )
+                        .await?;
+                    import_model
+                        .update_progress(import_id, progress.clone())
                         .await
                 }
                 .into()
@@ -1677,6 +2971,283 @@ async fn add_checkpoint_message<RT: Runtime>(
     Ok(())
 }
 
+/// Assigns new `_id`s to the documents of tables that don't keep their ids,
+/// writes each old id -> new id pair to the import's id mapping report, and
+/// optionally rewrites references to the old ids in imported documents.
+///
+/// New ids are derived from the import's id and the old id, so a resumed
+/// import assigns the same ids to the documents it already inserted.
+///
+/// Tables whose table number conflicts with an existing table keep their
+/// documents' internal ids but get a new table number, so their `_id`s and
+/// all references to them are always rewritten.
+struct ImportIdRemapper {
+    seed: String,
+    table_selection: ImportTableSelection,
+    /// The table number of each table with new ids, by its table number in
+    /// the import.
+    tables: BTreeMap<(TableNamespace, TableNumber), TableNumber>,
+    renumbered_tables: RenumberedTables,
+    storage: Arc<dyn Storage>,
+    report: Option<Box<BufferedUpload>>,
+}
+
+impl ImportIdRemapper {
+    fn new(
+        import_id: ResolvedDocumentId,
+        table_selection: ImportTableSelection,
+        storage: Arc<dyn Storage>,
+    ) -> Self {
+        Self {
+            seed: DeveloperDocumentId::from(import_id).encode(),
+            table_selection,
+            tables: BTreeMap::new(),
+            renumbered_tables: BTreeMap::new(),
+            storage,
+            report: None,
+        }
+    }
+
+    fn regenerates_ids(&self, table_name: &TableName) -> bool {
+        self.table_selection.regenerates_ids(table_name)
+    }
+
+    fn add_table(
+        &mut self,
+        namespace: TableNamespace,
+        old_table_number: TableNumber,
+        new_table_number: TableNumber,
+    ) {
+        self.tables
+            .insert((namespace, old_table_number), new_table_number);
+    }
+
+    fn add_renumbered_table(
+        &mut self,
+        namespace: TableNamespace,
+        old_table_number: TableNumber,
+        new_table_number: TableNumber,
+    ) {
+        if self
+            .renumbered_tables
+            .insert((namespace, old_table_number), new_table_number)
+            .is_none()
+        {
+            tracing::info!(
+                "Renumbering imported table {old_table_number} to {new_table_number} to avoid a \
+                 conflict"
+            );
+        }
+    }
+
+    /// The id that replaces `old_id`, if its table was renumbered or, when
+    /// `include_new_ids` is set, its table gets new ids.
+    fn remapped_id(
+        &self,
+        namespace: TableNamespace,
+        old_id: DeveloperDocumentId,
+        include_new_ids: bool,
+    ) -> Option<DeveloperDocumentId> {
+        if let Some(new_id) = renumbered_id(&self.renumbered_tables, namespace, old_id) {
+            return Some(new_id);
+        }
+        if include_new_ids
+            && let Some(new_table_number) = self.tables.get(&(namespace, old_id.table()))
+        {
+            return Some(self.new_id(*new_table_number, old_id));
+        }
+        None
+    }
+
+    fn new_id(
+        &self,
+        new_table_number: TableNumber,
+        old_id: DeveloperDocumentId,
+    ) -> DeveloperDocumentId {
+        let mut hasher = value::sha256::Sha256::new();
+        hasher.update(self.seed.as_bytes());
+        hasher.update(old_id.encode().as_bytes());
+        let digest = hasher.finalize();
+        let mut internal_id = [0; 16];
+        internal_id.copy_from_slice(&digest[..16]);
+        DeveloperDocumentId::new(new_table_number, InternalId::from(internal_id))
+    }
+
+    /// Gives `object` a new `_id` if its table doesn't keep its ids or was
+    /// renumbered, and rewrites its references to documents that got new ids.
+    async fn remap_object(
+        &mut self,
+        namespace: TableNamespace,
+        component_path: &ComponentPath,
+        table_name: &TableName,
+        table_number: TableNumber,
+        object: &mut JsonValue,
+    ) -> anyhow::Result<()> {
+        let JsonValue::Object(fields) = object else {
+            // Reported as `NotAnObject` when the object is inserted.
+            return Ok(());
+        };
+        if self.regenerates_ids(table_name)
+            && let Some(JsonValue::String(old_id)) = fields.get(&**ID_FIELD)
+        {
+            let old_id = DeveloperDocumentId::decode(old_id).map_err(|e| {
+                ErrorMetadata::bad_request(
+                    "InvalidId",
+                    format!("Invalid _id {old_id} in table \"{table_name}\": {e}"),
+                )
+            })?;
+            let new_id = self.new_id(table_number, old_id);
+            self.record(component_path, table_name, old_id, new_id)
+                .await?;
+            fields.insert(
+                String::from(&**ID_FIELD),
+                JsonValue::String(new_id.encode()),
+            );
+        } else if let Some(JsonValue::String(old_id)) = fields.get(&**ID_FIELD)
+            && let Ok(old_id) = DeveloperDocumentId::decode(old_id)
+            && let Some(new_id) = self.remapped_id(namespace, old_id, false)
+        {
+            self.record(component_path, table_name, old_id, new_id)
+                .await?;
+            fields.insert(
+                String::from(&**ID_FIELD),
+                JsonValue::String(new_id.encode()),
+            );
+        }
+        if self.table_selection.rewrite_references || !self.renumbered_tables.is_empty() {
+            for (field, value) in fields.iter_mut() {
+                if field != &**ID_FIELD {
+                    self.rewrite_references(namespace, value);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn rewrite_references(&self, namespace: TableNamespace, value: &mut JsonValue) {
+        match value {
+            JsonValue::String(s) => {
+                if let Ok(old_id) = DeveloperDocumentId::decode(s)
+                    && let Some(new_id) =
+                        self.remapped_id(namespace, old_id, self.table_selection.rewrite_references)
+                {
+                    *s = new_id.encode();
+                }
+            },
+            JsonValue::Array(values) => {
+                for value in values {
+                    self.rewrite_references(namespace, value);
+                }
+            },
+            JsonValue::Object(fields) => {
+                for value in fields.values_mut() {
+                    self.rewrite_references(namespace, value);
+                }
+            },
+            JsonValue::Null | JsonValue::Bool(_) | JsonValue::Number(_) => {},
+        }
+    }
+
+    async fn record(
+        &mut self,
+        component_path: &ComponentPath,
+        table_name: &TableName,
+        old_id: DeveloperDocumentId,
+        new_id: DeveloperDocumentId,
+    ) -> anyhow::Result<()> {
+        if self.report.is_none() {
+            self.report = Some(self.storage.start_upload().await?);
+        }
+        let Some(report) = &mut self.report else {
+            anyhow::bail!("id mapping report wasn't started");
+        };
+        let mut line = json!({
+            "table": table_name.to_string(),
+            "oldId": old_id.encode(),
+            "newId": new_id.encode(),
+        });
+        if !component_path.is_root() {
+            line["componentPath"] = JsonValue::String(component_path.to_string());
+        }
+        let mut line = serde_json::to_vec(&line)?;
+        line.push(b'\n');
+        report.write(line.into()).await
+    }
+
+    /// Uploads the id mapping report, if any documents got new ids.
+    async fn finish(self) -> anyhow::Result<Option<ObjectKey>> {
+        match self.report {
+            Some(report) => Ok(Some(report.complete().await?)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Creates the tables that get new `_id`s before importing any documents, so
+/// references to them can be rewritten in tables that are imported first.
+/// The tables listed in `_tables` are created too, so the new tables' numbers
+/// don't conflict with them.
+async fn prepare_tables_with_new_ids<RT: Runtime>(
+    database: &Database<RT>,
+    identity: &Identity,
+    mode: ImportMode,
+    objects: Peekable<BoxStream<'_, anyhow::Result<ImportUnit>>>,
+    import_id: Option<ResolvedDocumentId>,
+    remapper: &mut ImportIdRemapper,
+) -> anyhow::Result<TableMapping> {
+    pin_mut!(objects);
+    let mut table_mapping_for_import = TableMapping::new();
+    while let Some(unit) = objects.try_next().await? {
+        let ImportUnit::NewTable(component_path, table_name) = unit else {
+            continue;
+        };
+        if table_name == *TABLES_TABLE {
+            table_mapping_for_import.update(
+                import_tables_table(
+                    database,
+                    identity,
+                    mode,
+                    objects.as_mut(),
+                    &component_path,
+                    import_id,
+                    Some(&mut *remapper),
+                )
+                .await?,
+            );
+            continue;
+        }
+        if !remapper.regenerates_ids(&table_name) {
+            continue;
+        }
+        let Some(old_table_number) = table_number_for_import(objects.as_mut()).await else {
+            continue;
+        };
+        let tables_in_import = table_mapping_for_import
+            .iter()
+            .map(|(_, _, _, table_name)| table_name.clone())
+            .collect();
+        let (table_id, component_id, _) = prepare_table_for_import(
+            database,
+            identity,
+            mode,
+            &component_path,
+            &table_name,
+            None,
+            &tables_in_import,
+            import_id,
+        )
+        .await?;
+        table_mapping_for_import.insert(
+            table_id.tablet_id,
+            component_id.into(),
+            table_id.table_number,
+            table_name.clone(),
+        );
+        remapper.add_table(component_id.into(), old_table_number, table_id.table_number);
+    }
+    Ok(table_mapping_for_import)
+}
+
 async fn import_objects<RT: Runtime>(
     database: &Database<RT>,
     file_storage: &FileStorage<RT>,
@@ -1687,12 +3258,14 @@ async fn import_objects<RT: Runtime>(
     import_id: Option<ResolvedDocumentId>,
     requestor: ImportRequestor,
     usage_tracking: &UsageCounter,
+    mut table_mapping_for_import: TableMapping,
+    mut remapper: Option<&mut ImportIdRemapper>,
 ) -> anyhow::Result<(TableMapping, u64)> {
     pin_mut!(objects);
     let mut generated_schemas = BTreeMap::new();
 
-    let mut table_mapping_for_import = TableMapping::new();
     let mut total_num_documents = 0;
+    let mut progress = ImportProgress::default();
 
     while let Some(num_documents) = import_single_table(
         database,
@@ -1706,6 +3279,8 @@ async fn import_objects<RT: Runtime>(
         import_id,
         requestor.clone(),
         usage_tracking,
+        &mut progress,
+        remapper.as_deref_mut(),
     )
     .await?
     {
@@ -1937,6 +3512,102 @@ async fn schemas_for_import<RT: Runtime>(
     Ok(schemas)
 }
 
+/// New table numbers of the tables an import renumbered to avoid conflicts,
+/// by their table numbers in the import.
+type RenumberedTables = BTreeMap<(TableNamespace, TableNumber), TableNumber>;
+
+/// The id that replaces `id` if its table was renumbered. The internal id
+/// stays the same.
+fn renumbered_id(
+    renumbered_tables: &RenumberedTables,
+    namespace: TableNamespace,
+    id: DeveloperDocumentId,
+) -> Option<DeveloperDocumentId> {
+    renumbered_tables
+        .get(&(namespace, id.table()))
+        .map(|table_number| DeveloperDocumentId::new(*table_number, id.internal_id()))
+}
+
+/// Rewrites every id in `value` that belongs to a renumbered table, including
+/// `_id`.
+fn rewrite_renumbered_ids(
+    renumbered_tables: &RenumberedTables,
+    namespace: TableNamespace,
+    value: &mut JsonValue,
+) {
+    if renumbered_tables.is_empty() {
+        return;
+    }
+    match value {
+        JsonValue::String(s) => {
+            if let Ok(id) = DeveloperDocumentId::decode(s)
+                && let Some(new_id) = renumbered_id(renumbered_tables, namespace, id)
+            {
+                *s = new_id.encode();
+            }
+        },
+        JsonValue::Array(values) => {
+            for value in values {
+                rewrite_renumbered_ids(renumbered_tables, namespace, value);
+            }
+        },
+        JsonValue::Object(fields) => {
+            for value in fields.values_mut() {
+                rewrite_renumbered_ids(renumbered_tables, namespace, value);
+            }
+        },
+        JsonValue::Null | JsonValue::Bool(_) | JsonValue::Number(_) => {},
+    }
+}
+
+/// The table number to create `table_name` with, given the number its
+/// documents use in the import. User tables whose number is taken by a table
+/// the import doesn't replace get a new number instead, and
+/// [`ImportIdRemapper`] rewrites their ids and the references to them.
+fn table_number_without_conflict<RT: Runtime>(
+    tx: &mut Transaction<RT>,
+    namespace: TableNamespace,
+    table_name: &TableName,
+    table_number: Option<TableNumber>,
+    tables_in_import: &BTreeSet<TableName>,
+) -> Option<TableNumber> {
+    table_number.filter(|table_number| {
+        table_name.is_system()
+            || !TableModel::new(tx).table_number_conflicts(
+                namespace,
+                table_name,
+                *table_number,
+                tables_in_import,
+            )
+    })
+}
+
+/// Orders `_tables` entries so that tables which keep their table numbers are
+/// created first, and the new numbers given to conflicting tables can't
+/// collide with numbers the import still needs.
+fn order_tables_for_import<RT: Runtime>(
+    tx: &mut Transaction<RT>,
+    namespace: TableNamespace,
+    import_tables: Vec<(TableName, TableNumber)>,
+    tables_in_import: &BTreeSet<TableName>,
+) -> Vec<(TableName, TableNumber, Option<TableNumber>)> {
+    let mut import_tables: Vec<_> = import_tables
+        .into_iter()
+        .map(|(table_name, table_number)| {
+            let new_table_number = table_number_without_conflict(
+                tx,
+                namespace,
+                &table_name,
+                Some(table_number),
+                tables_in_import,
+            );
+            (table_name, table_number, new_table_number)
+        })
+        .collect();
+    import_tables.sort_by_key(|(_, _, new_table_number)| new_table_number.is_none());
+    import_tables
+}
+
 async fn import_tables_table<RT: Runtime>(
     database: &Database<RT>,
     identity: &Identity,
@@ -1944,8 +3615,61 @@ async fn import_tables_table<RT: Runtime>(
     mut objects: Pin<&mut Peekable<BoxStream<'_, anyhow::Result<ImportUnit>>>>,
     component_path: &ComponentPath,
     import_id: Option<ResolvedDocumentId>,
+    mut remapper: Option<&mut ImportIdRemapper>,
 ) -> anyhow::Result<TableMapping> {
     let mut table_mapping_for_import = TableMapping::new();
+    let import_tables = parse_tables_table(objects.as_mut()).await?;
+    let tables_in_import = import_tables
+        .iter()
+        .map(|(table_name, _)| table_name.clone())
+        .collect();
+    let import_tables = {
+        let mut tx = database.begin(identity.clone()).await?;
+        let (_, component_id) = BootstrapComponentsModel::new(&mut tx)
+            .component_path_to_ids(component_path)?
+            .with_context(|| ImportError::ComponentMissing(component_path.clone()))?;
+        order_tables_for_import(
+            &mut tx,
+            component_id.into(),
+            import_tables,
+            &tables_in_import,
+        )
+    };
+    for (table_name, table_number, new_table_number) in import_tables.iter() {
+        let (table_id, component_id, _) = prepare_table_for_import(
+            database,
+            identity,
+            mode,
+            component_path,
+            table_name,
+            *new_table_number,
+            &tables_in_import,
+            import_id,
+        )
+        .await?;
+        table_mapping_for_import.insert(
+            table_id.tablet_id,
+            component_id.into(),
+            table_id.table_number,
+            table_name.clone(),
+        );
+        if table_id.table_number != *table_number
+            && !table_name.is_system()
+            && let Some(remapper) = remapper.as_deref_mut()
+        {
+            remapper.add_renumbered_table(
+                component_id.into(),
+                *table_number,
+                table_id.table_number,
+            );
+        }
+    }
+    Ok(table_mapping_for_import)
+}
+
+async fn parse_tables_table(
+    mut objects: Pin<&mut Peekable<BoxStream<'_, anyhow::Result<ImportUnit>>>>,
+) -> anyhow::Result<Vec<(TableName, TableNumber)>> {
     let mut import_tables: Vec<(TableName, TableNumber)> = vec![];
     let mut lineno = 0;
     while let Some(ImportUnit::Object(exported_value)) = objects
@@ -1981,30 +3705,7 @@ async fn import_tables_table<RT: Runtime>(
             })?;
         import_tables.push((table_name, table_number));
     }
-    let tables_in_import = import_tables
-        .iter()
-        .map(|(table_name, _)| table_name.clone())
-        .collect();
-    for (table_name, table_number) in import_tables.iter() {
-        let (table_id, component_id, _) = prepare_table_for_import(
-            database,
-            identity,
-            mode,
-            component_path,
-            table_name,
-            Some(*table_number),
-            &tables_in_import,
-            import_id,
-        )
-        .await?;
-        table_mapping_for_import.insert(
-            table_id.tablet_id,
-            component_id.into(),
-            table_id.table_number,
-            table_name.clone(),
-        );
-    }
-    Ok(table_mapping_for_import)
+    Ok(import_tables)
 }
 
 async fn import_storage_table<RT: Runtime>(
@@ -2019,6 +3720,7 @@ async fn import_storage_table<RT: Runtime>(
     num_to_skip: u64,
     requestor: ImportRequestor,
     usage_tracking: &UsageCounter,
+    progress: &mut ImportProgress,
 ) -> anyhow::Result<()> {
     let snapshot = database.latest_snapshot()?;
     let namespace = snapshot
@@ -2071,6 +3773,12 @@ async fn import_storage_table<RT: Runtime>(
             .map(CreationTime::try_from)
             .transpose()
             .map_err(|e| ImportError::InvalidConvexValue(lineno, e))?;
+        if let Some(filename) = &metadata.filename {
+            validate_file_storage_filename(filename)
+                .map_err(|e| ImportError::InvalidConvexValue(lineno, e))?;
+        }
+        validate_file_storage_tags(&metadata.tags)
+            .map_err(|e| ImportError::InvalidConvexValue(lineno, e))?;
 
         storage_metadata.insert(
             id,
@@ -2080,6 +3788,8 @@ async fn import_storage_table<RT: Runtime>(
                 sha256,
                 storage_id,
                 creation_time,
+                metadata.filename,
+                metadata.tags,
             ),
         );
     }
@@ -2090,8 +3800,15 @@ async fn import_storage_table<RT: Runtime>(
         // The or_default means a storage file with a valid id will be imported
         // even if it has been explicitly removed from _storage/documents.jsonl,
         // to be robust to manual modifications.
-        let (content_length, content_type, expected_sha256, storage_id, creation_time) =
-            storage_metadata.remove(&id).unwrap_or_default();
+        let (
+            content_length,
+            content_type,
+            expected_sha256,
+            storage_id,
+            creation_time,
+            filename,
+            tags,
+        ) = storage_metadata.remove(&id).unwrap_or_default();
         let file_chunks = objects
             .as_mut()
             .peeking_take_while(move |unit| match unit {
@@ -2112,8 +3829,11 @@ async fn import_storage_table<RT: Runtime>(
         if let Some(storage_id) = storage_id {
             entry.storage_id = storage_id;
         }
+        entry.filename = filename;
+        entry.tags = tags;
         if num_files < num_to_skip {
             num_files += 1;
+            progress.documents_written += 1;
             continue;
         }
         let file_size = entry.size as u64;
@@ -2172,6 +3892,8 @@ async fn import_storage_table<RT: Runtime>(
             file_size,
         );
         num_files += 1;
+        progress.documents_written += 1;
+        progress.bytes_written += file_size;
         if let Some(import_id) = import_id {
             best_effort_update_progress_message(
                 database,
@@ -2185,6 +3907,7 @@ async fn import_storage_table<RT: Runtime>(
                 component_path,
                 &FILE_STORAGE_VIRTUAL_TABLE,
                 num_files as i64,
+                progress,
             )
             .await;
         }
@@ -2202,6 +3925,7 @@ async fn import_storage_table<RT: Runtime>(
             component_path,
             &FILE_STORAGE_VIRTUAL_TABLE,
             num_files as i64,
+            progress,
         )
         .await?;
     }
@@ -2276,6 +4000,8 @@ async fn import_single_table<RT: Runtime>(
     import_id: Option<ResolvedDocumentId>,
     requestor: ImportRequestor,
     usage_tracking: &UsageCounter,
+    progress: &mut ImportProgress,
+    mut remapper: Option<&mut ImportIdRemapper>,
 ) -> anyhow::Result<Option<u64>> {
     while let Some(ImportUnit::GeneratedSchema(component_path, table_name, generated_schema)) =
         objects
@@ -2292,6 +4018,8 @@ async fn import_single_table<RT: Runtime>(
         None => return Ok(None),
     };
     let table_number_from_docs = table_number_for_import(objects.as_mut()).await;
+    progress.component_path = component_and_table.0.clone();
+    progress.current_table = Some(component_and_table.1.clone());
     if let Some(import_id) = import_id {
         best_effort_update_progress_message(
             database,
@@ -2305,6 +4033,7 @@ async fn import_single_table<RT: Runtime>(
             &component_and_table.0,
             &component_and_table.1,
             0,
+            progress,
         )
         .await;
     }
@@ -2324,6 +4053,7 @@ async fn import_single_table<RT: Runtime>(
                 objects.as_mut(),
                 component_path,
                 import_id,
+                remapper.as_deref_mut(),
             )
             .await?,
         );
@@ -2342,6 +4072,9 @@ async fn import_single_table<RT: Runtime>(
             .with_context(|| ImportError::ComponentMissing(component_path.clone()))?;
         component_id
     };
+    let regenerates_ids = remapper
+        .as_ref()
+        .is_some_and(|remapper| remapper.regenerates_ids(table_name));
     let (table_id, num_to_skip) = match table_mapping_for_import
         .namespace(component_id.into())
         .id_and_number_if_exists(table_name)
@@ -2358,13 +4091,28 @@ async fn import_single_table<RT: Runtime>(
             (table_id, num_to_skip)
         },
         None => {
+            let table_number = if regenerates_ids {
+                // Tables with new ids get new table numbers.
+                None
+            } else if remapper.is_some() {
+                let mut tx = database.begin(identity.clone()).await?;
+                table_number_without_conflict(
+                    &mut tx,
+                    component_id.into(),
+                    table_name,
+                    table_number_from_docs,
+                    &tables_in_import,
+                )
+            } else {
+                table_number_from_docs
+            };
             let (table_id, component_id, num_to_skip) = prepare_table_for_import(
                 database,
                 identity,
                 mode,
                 component_path,
                 table_name,
-                table_number_from_docs,
+                table_number,
                 &tables_in_import,
                 import_id,
             )
@@ -2378,6 +4126,19 @@ async fn import_single_table<RT: Runtime>(
             (table_id, num_to_skip)
         },
     };
+    if let Some(remapper) = remapper.as_deref_mut()
+        && let Some(old_table_number) = table_number_from_docs
+    {
+        if regenerates_ids {
+            remapper.add_table(component_id.into(), old_table_number, table_id.table_number);
+        } else if table_id.table_number != old_table_number && !table_name.is_system() {
+            remapper.add_renumbered_table(
+                component_id.into(),
+                old_table_number,
+                table_id.table_number,
+            );
+        }
+    }
 
     if *table_name == *FILE_STORAGE_TABLE {
         import_storage_table(
@@ -2392,6 +4153,7 @@ async fn import_single_table<RT: Runtime>(
             num_to_skip,
             requestor,
             usage_tracking,
+            progress,
         )
         .await?;
         return Ok(Some(0));
@@ -2405,13 +4167,27 @@ async fn import_single_table<RT: Runtime>(
     let mut objects_to_insert = vec![];
     let mut objects_to_insert_size = 0;
     // Peek so we don't pop ImportUnit::NewTable items.
-    while let Some(ImportUnit::Object(exported_value)) = objects
+    while let Some(ImportUnit::Object(mut exported_value)) = objects
         .as_mut()
         .try_next_if(|line| matches!(line, ImportUnit::Object(_)))
         .await?
     {
+        // Skipped objects are remapped too, so a resumed import's id mapping
+        // report still lists them.
+        if let Some(remapper) = remapper.as_deref_mut() {
+            remapper
+                .remap_object(
+                    component_id.into(),
+                    component_path,
+                    table_name,
+                    table_id.table_number,
+                    &mut exported_value,
+                )
+                .await?;
+        }
         if num_objects < num_to_skip {
             num_objects += 1;
+            progress.documents_written += 1;
             continue;
         }
         let row_number = (num_objects + 1) as usize;
@@ -2429,6 +4205,7 @@ async fn import_single_table<RT: Runtime>(
         if objects_to_insert_size > *TRANSACTION_MAX_USER_WRITE_SIZE_BYTES / 2
             || objects_to_insert.len() > *TRANSACTION_MAX_NUM_USER_WRITES / 2
         {
+            let num_inserted = objects_to_insert.len() as u64;
             insert_import_objects(
                 database,
                 identity,
@@ -2439,6 +4216,8 @@ async fn import_single_table<RT: Runtime>(
                 usage.clone(),
             )
             .await?;
+            progress.documents_written += num_inserted;
+            progress.bytes_written += objects_to_insert_size as u64;
             objects_to_insert = Vec::new();
             objects_to_insert_size = 0;
             if let Some(import_id) = import_id {
@@ -2453,6 +4232,7 @@ async fn import_single_table<RT: Runtime>(
                     component_path,
                     table_name,
                     num_objects as i64,
+                    progress,
                 )
                 .await;
             }
@@ -2460,6 +4240,7 @@ async fn import_single_table<RT: Runtime>(
         num_objects += 1;
     }
 
+    let num_remaining = objects_to_insert.len() as u64;
     insert_import_objects(
         database,
         identity,
@@ -2470,6 +4251,8 @@ async fn import_single_table<RT: Runtime>(
         usage,
     )
     .await?;
+    progress.documents_written += num_remaining;
+    progress.bytes_written += objects_to_insert_size as u64;
 
     if let Some(import_id) = import_id {
         add_checkpoint_message(
@@ -2484,6 +4267,7 @@ async fn import_single_table<RT: Runtime>(
             component_path,
             table_name,
             num_objects as i64,
+            progress,
         )
         .await?;
     }
@@ -2551,13 +4335,7 @@ async fn prepare_table_for_import<RT: Runtime>(
     tables_in_import: &BTreeSet<TableName>,
     import_id: Option<ResolvedDocumentId>,
 ) -> anyhow::Result<(TabletIdAndTableNumber, ComponentId, u64)> {
-    anyhow::ensure!(
-        table_name == &*FILE_STORAGE_TABLE || !table_name.is_system(),
-        ErrorMetadata::bad_request(
-            "InvalidTableName",
-            format!("Invalid table name {table_name} starts with metadata prefix '_'")
-        )
-    );
+    check_table_name_for_import(table_name)?;
     let display_table_name = if table_name == &*FILE_STORAGE_TABLE {
         &*FILE_STORAGE_VIRTUAL_TABLE
     } else {
@@ -2664,6 +4442,17 @@ async fn prepare_table_for_import<RT: Runtime>(
     Ok((table_id, component_id, num_to_skip))
 }
 
+fn check_table_name_for_import(table_name: &TableName) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        table_name == &*FILE_STORAGE_TABLE || !table_name.is_system(),
+        ErrorMetadata::bad_request(
+            "InvalidTableName",
+            format!("Invalid table name {table_name} starts with metadata prefix '_'")
+        )
+    );
+    Ok(())
+}
+
 /// Waits for all indexes on a table to be backfilled, which may take a while
 /// for large tables. After the indexes are backfilled, enable them.
 async fn backfill_and_enable_indexes_on_table<RT: Runtime>(
@@ -2859,8 +4648,10 @@ mod tests {
     };
     use maplit::btreemap;
     use model::snapshot_imports::types::{
+        CsvColumnType,
         ImportRequestor,
         ImportState,
+        ImportTableSelection,
     };
     use must_let::must_let;
     use runtime::testing::TestRuntime;
@@ -2880,17 +4671,27 @@ mod tests {
         assert_obj,
         assert_val,
         id_v6::DeveloperDocumentId,
+        sha256::Sha256,
         ConvexObject,
         FieldName,
+        InternalId,
         TableName,
         TableNamespace,
+        TableNumber,
     };
 
     use super::{
         do_import,
+        dry_run_import,
+        finish_import_upload,
         import_objects,
+        import_upload_status,
         parse_documents_jsonl_table_name,
         parse_objects,
+        select_tables_for_import,
+        ImportDryRunReport,
+        ImportDryRunTable,
+        ImportDryRunTableAction,
         ImportFormat,
         ImportMode,
         ImportUnit,
@@ -2899,8 +4700,12 @@ mod tests {
     };
     use crate::{
         snapshot_import::{
+            import_id_mapping,
             parse_storage_filename,
             parse_table_filename,
+            perform_import,
+            start_import_upload,
+            upload_import_chunk,
             upload_import_file,
             wait_for_import_worker,
         },
@@ -3027,6 +4832,63 @@ mod tests {
         assert_eq!(p.next().await, Some(7));
     }
 
+    #[convex_macro::test_runtime]
+    async fn test_select_tables_for_import(_rt: TestRuntime) -> anyhow::Result<()> {
+        let root = ComponentPath::root();
+        let objects = stream::iter(vec![
+            Ok(ImportUnit::NewTable(root.clone(), "_tables".parse()?)),
+            Ok(ImportUnit::Object(json!({"name": "users", "id": 10001}))),
+            Ok(ImportUnit::Object(json!({"name": "posts", "id": 10002}))),
+            Ok(ImportUnit::Object(json!({"name": "logs", "id": 10003}))),
+            Ok(ImportUnit::NewTable(root.clone(), "users".parse()?)),
+            Ok(ImportUnit::Object(json!({"_id": "abc", "name": "alice"}))),
+            Ok(ImportUnit::NewTable(root.clone(), "posts".parse()?)),
+            Ok(ImportUnit::Object(json!({"_id": "def", "title": "hi"}))),
+            Ok(ImportUnit::NewTable(root.clone(), "logs".parse()?)),
+            Ok(ImportUnit::Object(json!({"_id": "ghi", "line": 1}))),
+        ])
+        .boxed();
+        let table_selection = ImportTableSelection::new(
+            Some(["users".parse()?, "posts".parse()?].into()),
+            btreemap! { "users".parse()? => "users_staging".parse()? },
+        )?;
+        let units: Vec<_> = select_tables_for_import(table_selection, objects)
+            .try_collect()
+            .await?;
+        let units: Vec<_> = units
+            .into_iter()
+            .map(|unit| match unit {
+                ImportUnit::NewTable(_, table_name) => json!(table_name.to_string()),
+                ImportUnit::Object(object) => object,
+                unit => panic!("unexpected {unit:?}"),
+            })
+            .collect();
+        // The renamed table is left out of `_tables` so it gets a new table
+        // number. Its `_id`s are kept so they can be mapped to new ones.
+        assert_eq!(
+            units,
+            vec![
+                json!("_tables"),
+                json!({"name": "posts", "id": 10002}),
+                json!("users_staging"),
+                json!({"_id": "abc", "name": "alice"}),
+                json!("posts"),
+                json!({"_id": "def", "title": "hi"}),
+            ]
+        );
+
+        let err = ImportTableSelection::new(
+            None,
+            btreemap! {
+                "users".parse()? => "people".parse()?,
+                "members".parse()? => "people".parse()?,
+            },
+        )
+        .unwrap_err();
+        assert!(err.is_bad_request());
+        Ok(())
+    }
+
     async fn run_parse_objects<RT: Runtime>(
         rt: RT,
         format: ImportFormat,
@@ -3067,8 +4929,12 @@ a,b,c
 1,a string i guess,1.2
 5.10,-100,"a string in quotes"
 "#;
-        let objects =
-            run_parse_objects(rt, ImportFormat::Csv("table".parse().unwrap()), test1).await?;
+        let objects = run_parse_objects(
+            rt,
+            ImportFormat::Csv("table".parse().unwrap(), BTreeMap::new()),
+            test1,
+        )
+        .await?;
         let expected = vec![
             json!({
                 "a": 1.,
@@ -3113,8 +4979,12 @@ _id,value
 a,b,c,d
 "",,"""",""""""
 "#;
-        let objects =
-            run_parse_objects(rt, ImportFormat::Csv("table".parse().unwrap()), test1).await?;
+        let objects = run_parse_objects(
+            rt,
+            ImportFormat::Csv("table".parse().unwrap(), BTreeMap::new()),
+            test1,
+        )
+        .await?;
         let expected = vec![json!({
             "a": "",
             "b": "",
@@ -3125,6 +4995,70 @@ a,b,c,d
         Ok(())
     }
 
+    #[convex_macro::test_runtime]
+    async fn test_csv_column_types(rt: TestRuntime) -> anyhow::Result<()> {
+        let test1 = r#"
+a,b,c,d,e
+1.5,TRUE,2024-01-31,007,
+-2,false,2024-01-31T12:00:00+01:00,1,x
+"#;
+        let column_types = btreemap!(
+            "a".parse()? => CsvColumnType::Number,
+            "b".parse()? => CsvColumnType::Boolean,
+            "c".parse()? => CsvColumnType::Date,
+            "d".parse()? => CsvColumnType::String,
+            "e".parse()? => CsvColumnType::String,
+        );
+        let objects = run_parse_objects(
+            rt.clone(),
+            ImportFormat::Csv("table".parse()?, column_types),
+            test1,
+        )
+        .await?;
+        let expected = vec![
+            json!({
+                "a": 1.5,
+                "b": true,
+                "c": 1706659200000.,
+                "d": "007",
+                "e": "",
+            }),
+            json!({
+                "a": -2.,
+                "b": false,
+                "c": 1706698800000.,
+                "d": "1",
+                "e": "x",
+            }),
+        ];
+        assert_eq!(objects, expected);
+
+        // Empty cells in non-string columns are left out.
+        let test2 = r#"
+a,b
+,x
+"#;
+        let column_types = btreemap!("a".parse()? => CsvColumnType::Number);
+        let objects = run_parse_objects(
+            rt.clone(),
+            ImportFormat::Csv("table".parse()?, column_types),
+            test2,
+        )
+        .await?;
+        assert_eq!(objects, vec![json!({"b": "x"})]);
+
+        let test3 = r#"
+a
+yes
+"#;
+        let column_types = btreemap!("a".parse()? => CsvColumnType::Boolean);
+        let err = run_parse_objects(rt, ImportFormat::Csv("table".parse()?, column_types), test3)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("isn't a valid boolean"), "{err}");
+        Ok(())
+    }
+
     #[convex_macro::test_runtime]
     #[ignore]
     async fn import_huge_csv(rt: TestRuntime) -> anyhow::Result<()> {
@@ -3285,6 +5219,44 @@ a,b
         Ok(())
     }
 
+    #[convex_macro::test_runtime]
+    async fn import_csv_with_schema_uses_column_types(rt: TestRuntime) -> anyhow::Result<()> {
+        let app = Application::new_for_tests(&rt).await?;
+        let table_name = "table1";
+        let test_csv = r#"
+zip,count,active
+02134,3,true
+"#;
+
+        let schema = db_schema!(
+            table_name => DocumentSchema::Union(
+                vec![
+                    object_validator!(
+                        "zip" => FieldValidator::required_field_type(Validator::String),
+                        "count" => FieldValidator::required_field_type(Validator::Float64),
+                        "active" => FieldValidator::required_field_type(Validator::Boolean),
+                    )
+                ]
+            )
+        );
+
+        activate_schema(&app, schema).await?;
+        run_csv_import(&app, table_name, test_csv).await?;
+
+        let objects = load_fields_as_maps(&app, table_name, vec!["zip", "count", "active"]).await?;
+
+        assert_eq!(
+            objects,
+            vec![btreemap!(
+                "zip" => assert_val!("02134"),
+                "count" => assert_val!(3.),
+                "active" => assert_val!(true),
+            )]
+        );
+
+        Ok(())
+    }
+
     #[convex_macro::test_runtime]
     async fn import_validates_against_schema(rt: TestRuntime) -> anyhow::Result<()> {
         let app = Application::new_for_tests(&rt).await?;
@@ -3327,9 +5299,10 @@ a
         let import_id = upload_import_file(
             &app,
             new_admin_id(),
-            ImportFormat::Csv(table_name.parse()?),
+            ImportFormat::Csv(table_name.parse()?, BTreeMap::new()),
             ImportMode::Replace,
             ComponentPath::root(),
+            ImportTableSelection::default(),
             stream_from_str(test_csv),
         )
         .await?;
@@ -3595,6 +5568,8 @@ a
             None,
             ImportRequestor::SnapshotImport,
             &app.usage_tracking,
+            TableMapping::new(),
+            None,
         )
         .await?;
 
@@ -3604,6 +5579,201 @@ a
         Ok(())
     }
 
+    #[convex_macro::test_runtime]
+    async fn test_import_records_progress(rt: TestRuntime) -> anyhow::Result<()> {
+        let app = Application::new_for_tests(&rt).await?;
+        let identity = new_admin_id();
+        let test_csv = r#"
+a,b
+"foo","bar"
+"baz","qux"
+"#;
+        let import_id = upload_import_file(
+            &app,
+            identity.clone(),
+            ImportFormat::Csv("table1".parse()?, BTreeMap::new()),
+            ImportMode::Replace,
+            ComponentPath::root(),
+            ImportTableSelection::default(),
+            stream_from_str(test_csv),
+        )
+        .await?;
+        let snapshot_import = wait_for_import_worker(&app, identity.clone(), import_id).await?;
+        assert_eq!(snapshot_import.progress, None);
+
+        perform_import(&app, identity.clone(), import_id).await?;
+        let snapshot_import = wait_for_import_worker(&app, identity, import_id).await?;
+        must_let!(let Some(progress) = &snapshot_import.progress);
+        assert_eq!(progress.component_path, ComponentPath::root());
+        assert_eq!(progress.current_table, Some("table1".parse()?));
+        assert_eq!(progress.documents_written, 2);
+        assert!(progress.bytes_written > 0);
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_import_regenerates_ids(rt: TestRuntime) -> anyhow::Result<()> {
+        let app = Application::new_for_tests(&rt).await?;
+        let identity = new_admin_id();
+        let table_number = TableNumber::try_from(10001)?;
+        let alice_id = DeveloperDocumentId::new(table_number, InternalId::from([1; 16])).encode();
+        let bob_id = DeveloperDocumentId::new(table_number, InternalId::from([2; 16])).encode();
+        // Alice's reference to Bob is rewritten even though Bob is imported after
+        // her.
+        let test_jsonl = format!(
+            "{}\n{}\n",
+            json!({"_id": alice_id, "name": "alice", "friend": bob_id}),
+            json!({"_id": bob_id, "name": "bob", "friends": [alice_id]}),
+        );
+        let import_id = upload_import_file(
+            &app,
+            identity.clone(),
+            ImportFormat::JsonLines("users".parse()?),
+            ImportMode::Replace,
+            ComponentPath::root(),
+            ImportTableSelection::default().with_id_options(true, true),
+            stream_from_str(&test_jsonl),
+        )
+        .await?;
+        wait_for_import_worker(&app, identity.clone(), import_id).await?;
+        perform_import(&app, identity.clone(), import_id).await?;
+        let snapshot_import = wait_for_import_worker(&app, identity.clone(), import_id).await?;
+        must_let!(let ImportState::Completed { num_rows_written, .. } = snapshot_import.state);
+        assert_eq!(num_rows_written, 2);
+
+        let users =
+            load_fields_as_maps(&app, "users", vec!["_id", "name", "friend", "friends"]).await?;
+        let new_id = |name: &str| -> anyhow::Result<String> {
+            let user = users
+                .iter()
+                .find(|user| user["name"] == assert_val!(name))
+                .context("user not found")?;
+            must_let!(let ConvexValue::String(id) = &user["_id"]);
+            Ok(String::from(id.clone()))
+        };
+        let (new_alice_id, new_bob_id) = (new_id("alice")?, new_id("bob")?);
+        assert_ne!(new_alice_id, alice_id);
+        assert_ne!(new_bob_id, bob_id);
+        for user in &users {
+            if user["name"] == assert_val!("alice") {
+                assert_eq!(user["friend"], assert_val!(new_bob_id.clone()));
+            } else {
+                assert_eq!(user["friends"], assert_val!([new_alice_id.clone()]));
+            }
+        }
+
+        let (report, _) = import_id_mapping(&app, identity, import_id).await?;
+        let report = report.collect_as_bytes().await?;
+        let mapping = std::str::from_utf8(&report)?
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<Vec<JsonValue>, _>>()?;
+        assert_eq!(
+            mapping,
+            vec![
+                json!({"table": "users", "oldId": alice_id, "newId": new_alice_id}),
+                json!({"table": "users", "oldId": bob_id, "newId": new_bob_id}),
+            ]
+        );
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_import_renumbers_conflicting_table(rt: TestRuntime) -> anyhow::Result<()> {
+        let app = Application::new_for_tests(&rt).await?;
+        let identity = new_admin_id();
+        run_csv_import(&app, "existing", "a\n\"foo\"\n").await?;
+        let existing_table_number = {
+            let mut tx = app.begin(identity.clone()).await?;
+            tx.table_mapping()
+                .namespace(TableNamespace::test_user())
+                .id(&"existing".parse()?)?
+                .table_number
+        };
+        // The imported ids use the existing table's number, so "users" gets a new
+        // number and its ids and the references to them are rewritten.
+        let alice_id = DeveloperDocumentId::new(existing_table_number, InternalId::from([1; 16]));
+        let bob_id = DeveloperDocumentId::new(existing_table_number, InternalId::from([2; 16]));
+        let test_jsonl = format!(
+            "{}\n{}\n",
+            json!({"_id": alice_id.encode(), "name": "alice", "friend": bob_id.encode()}),
+            json!({"_id": bob_id.encode(), "name": "bob", "friend": alice_id.encode()}),
+        );
+        let import_id = upload_import_file(
+            &app,
+            identity.clone(),
+            ImportFormat::JsonLines("users".parse()?),
+            ImportMode::Replace,
+            ComponentPath::root(),
+            ImportTableSelection::default(),
+            stream_from_str(&test_jsonl),
+        )
+        .await?;
+        wait_for_import_worker(&app, identity.clone(), import_id).await?;
+        perform_import(&app, identity.clone(), import_id).await?;
+        let snapshot_import = wait_for_import_worker(&app, identity.clone(), import_id).await?;
+        must_let!(let ImportState::Completed { num_rows_written, .. } = snapshot_import.state);
+        assert_eq!(num_rows_written, 2);
+
+        let users_table_number = {
+            let mut tx = app.begin(identity.clone()).await?;
+            tx.table_mapping()
+                .namespace(TableNamespace::test_user())
+                .id(&"users".parse()?)?
+                .table_number
+        };
+        assert_ne!(users_table_number, existing_table_number);
+        let new_alice_id =
+            DeveloperDocumentId::new(users_table_number, alice_id.internal_id()).encode();
+        let new_bob_id =
+            DeveloperDocumentId::new(users_table_number, bob_id.internal_id()).encode();
+        let users = load_fields_as_maps(&app, "users", vec!["_id", "name", "friend"]).await?;
+        assert_eq!(
+            users,
+            vec![
+                btreemap! {
+                    "_id" => assert_val!(new_alice_id.clone()),
+                    "name" => assert_val!("alice"),
+                    "friend" => assert_val!(new_bob_id.clone()),
+                },
+                btreemap! {
+                    "_id" => assert_val!(new_bob_id),
+                    "name" => assert_val!("bob"),
+                    "friend" => assert_val!(new_alice_id),
+                },
+            ]
+        );
+        assert_eq!(
+            load_fields_as_maps(&app, "existing", vec!["a"]).await?,
+            vec![btreemap! {"a" => assert_val!("foo")}]
+        );
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_restore_tables(rt: TestRuntime) -> anyhow::Result<()> {
+        let app = Application::new_for_tests(&rt).await?;
+        run_csv_import(&app, "table1", "a\n\"foo\"\n\"bar\"\n").await?;
+        let ts = *app.database.now_ts_for_reads();
+        let before = load_fields_as_maps(&app, "table1", vec!["_id", "a"]).await?;
+
+        run_csv_import(&app, "table1", "a\n\"baz\"\n").await?;
+        run_csv_import(&app, "table2", "a\n\"qux\"\n").await?;
+        let num_restored = app.restore_tables(&new_admin_id(), ts, None).await?;
+        assert_eq!(num_restored, 2);
+
+        // The restored documents keep their ids, and tables created after `ts`
+        // are cleared.
+        assert_eq!(
+            load_fields_as_maps(&app, "table1", vec!["_id", "a"]).await?,
+            before
+        );
+        assert!(load_fields_as_maps(&app, "table2", vec!["a"])
+            .await?
+            .is_empty());
+        Ok(())
+    }
+
     #[convex_macro::test_runtime]
     async fn test_import_into_component(rt: TestRuntime) -> anyhow::Result<()> {
         let app = Application::new_for_tests(&rt).await?;
@@ -3617,9 +5787,10 @@ a,b
         do_import(
             &app,
             new_admin_id(),
-            ImportFormat::Csv(table_name.clone()),
+            ImportFormat::Csv(table_name.clone(), BTreeMap::new()),
             ImportMode::Replace,
             component_path.clone(),
+            ImportTableSelection::default(),
             stream_from_str(test_csv),
         )
         .await?;
@@ -3644,9 +5815,10 @@ a,b
         let err = do_import(
             &app,
             new_admin_id(),
-            ImportFormat::Csv(table_name.clone()),
+            ImportFormat::Csv(table_name.clone(), BTreeMap::new()),
             ImportMode::Replace,
             component_path.clone(),
+            ImportTableSelection::default(),
             stream_from_str(test_csv),
         )
         .await
@@ -3662,6 +5834,166 @@ a,b
         Ok(())
     }
 
+    #[convex_macro::test_runtime]
+    async fn test_resumable_import_upload(rt: TestRuntime) -> anyhow::Result<()> {
+        let app = &Application::new_for_tests(&rt).await?;
+        let identity = &new_admin_id();
+        let table_name: TableName = "table1".parse()?;
+        let chunks = ["a,b\n", "\"foo\",\"bar\"\n", "\"baz\",\"qux\"\n"];
+        let upload_id = start_import_upload(app, identity.clone()).await?;
+        let upload_chunk = move |index: u32| {
+            let chunk = Bytes::from_static(chunks[index as usize].as_bytes());
+            upload_import_chunk(
+                app,
+                identity.clone(),
+                upload_id,
+                index,
+                Sha256::hash(&chunk),
+                chunk,
+            )
+        };
+
+        let err = upload_import_chunk(
+            app,
+            identity.clone(),
+            upload_id,
+            0,
+            Sha256::hash(b"something else"),
+            Bytes::from_static(chunks[0].as_bytes()),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.short_msg(), "Sha256Mismatch");
+
+        // Chunks can arrive out of order, and retrying one is harmless.
+        upload_chunk(2).await?;
+        upload_chunk(0).await?;
+        upload_chunk(2).await?;
+        let received = import_upload_status(app, identity.clone(), upload_id).await?;
+        assert_eq!(
+            received.iter().map(|chunk| chunk.index).collect::<Vec<_>>(),
+            vec![0, 2]
+        );
+
+        let finish = move || {
+            finish_import_upload(
+                app,
+                identity.clone(),
+                upload_id,
+                3,
+                ImportFormat::Csv(table_name.clone(), BTreeMap::new()),
+                ImportMode::Replace,
+                ComponentPath::root(),
+                ImportTableSelection::default(),
+            )
+        };
+        let err = finish().await.unwrap_err();
+        assert_eq!(err.short_msg(), "ImportUploadIncomplete");
+
+        upload_chunk(1).await?;
+        let import_id = finish().await?;
+        wait_for_import_worker(app, identity.clone(), import_id).await?;
+        perform_import(app, identity.clone(), import_id).await?;
+        let snapshot_import = wait_for_import_worker(app, identity.clone(), import_id).await?;
+        must_let!(let ImportState::Completed { num_rows_written, .. } = snapshot_import.state);
+        assert_eq!(num_rows_written, 2);
+
+        // The upload is cleaned up once the import starts.
+        let err = import_upload_status(app, identity.clone(), upload_id)
+            .await
+            .unwrap_err();
+        assert!(err.is_not_found());
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_dry_run_import_reports_without_writing(rt: TestRuntime) -> anyhow::Result<()> {
+        let app = Application::new_for_tests(&rt).await?;
+        let table_name = "table1";
+        run_csv_import(&app, table_name, "a\n\"foo\"\n\"bar\"\n").await?;
+
+        let report = dry_run_import(
+            &app,
+            new_admin_id(),
+            ImportFormat::Csv(table_name.parse()?, BTreeMap::new()),
+            ImportMode::Replace,
+            ComponentPath::root(),
+            ImportTableSelection::default(),
+            stream_from_str("a\n\"baz\"\n"),
+        )
+        .await?;
+        assert_eq!(
+            report,
+            ImportDryRunReport {
+                tables: vec![ImportDryRunTable {
+                    component_path: ComponentPath::root(),
+                    table_name: table_name.parse()?,
+                    action: ImportDryRunTableAction::Replace,
+                    documents_to_import: 1,
+                    existing_documents: 2,
+                    documents_to_delete: 2,
+                }],
+                conflicts: vec![],
+                conflicts_truncated: false,
+            }
+        );
+
+        let objects = load_fields_as_maps(&app, table_name, vec!["a"]).await?;
+        assert_eq!(
+            objects,
+            vec![
+                btreemap!("a" => assert_val!("foo")),
+                btreemap!("a" => assert_val!("bar")),
+            ]
+        );
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_dry_run_import_reports_schema_conflicts(rt: TestRuntime) -> anyhow::Result<()> {
+        let app = Application::new_for_tests(&rt).await?;
+        let table_name = "table1";
+        let schema = db_schema!(
+            table_name => DocumentSchema::Union(
+                vec![
+                    object_validator!(
+                        "a" => FieldValidator::required_field_type(Validator::Float64),
+                    )
+                ]
+            )
+        );
+        activate_schema(&app, schema).await?;
+
+        let report = dry_run_import(
+            &app,
+            new_admin_id(),
+            ImportFormat::JsonLines(table_name.parse()?),
+            ImportMode::RequireEmpty,
+            ComponentPath::root(),
+            ImportTableSelection::default(),
+            stream_from_str("{\"a\": 1}\n{\"a\": \"two\"}\n"),
+        )
+        .await?;
+        assert_eq!(report.tables.len(), 1);
+        assert_eq!(report.tables[0].action, ImportDryRunTableAction::Create);
+        assert_eq!(report.tables[0].documents_to_import, 2);
+        assert_eq!(report.conflicts.len(), 1, "{report:?}");
+        assert_eq!(report.conflicts[0].row_number, Some(2));
+        assert!(
+            report.conflicts[0]
+                .message
+                .contains("Failed to insert or update a document"),
+            "{}",
+            report.conflicts[0].message
+        );
+
+        let mut tx = app.begin(new_admin_id()).await?;
+        assert!(
+            !TableModel::new(&mut tx).table_exists(ComponentId::Root.into(), &table_name.parse()?)
+        );
+        Ok(())
+    }
+
     async fn activate_schema<RT: Runtime>(
         app: &Application<RT>,
         schema: DatabaseSchema,
@@ -3731,9 +6063,10 @@ a,b
         do_import(
             app,
             new_admin_id(),
-            ImportFormat::Csv(table_name.parse()?),
+            ImportFormat::Csv(table_name.parse()?, BTreeMap::new()),
             ImportMode::Replace,
             ComponentPath::root(),
+            ImportTableSelection::default(),
             stream_from_str(input),
         )
         .await
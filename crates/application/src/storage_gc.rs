@@ -0,0 +1,370 @@
+//! Garbage collection report for `_storage`. Files stay in storage until a
+//! function deletes them, so files whose IDs were never saved or whose
+//! documents were deleted keep costing storage. The report scans the tables
+//! that can hold storage IDs, according to the active schema, and lists the
+//! files that no document references, optionally deleting them.
+use std::{
+    collections::BTreeSet,
+    time::Duration,
+};
+
+use anyhow::Context;
+use common::{
+    bootstrap_model::schema::SchemaState,
+    components::ComponentId,
+    document::ParsedDocument,
+    knobs::STORAGE_GC_MIN_FILE_AGE,
+    pause::PauseClient,
+    runtime::Runtime,
+    schemas::{
+        DatabaseSchema,
+        DocumentSchema,
+    },
+    types::StorageUuid,
+};
+use database::{
+    unauthorized_error,
+    IndexModel,
+    SchemaModel,
+};
+use futures::{
+    pin_mut,
+    TryStreamExt,
+};
+use keybroker::Identity;
+use model::file_storage::{
+    types::FileStorageEntry,
+    FileStorageId,
+    FileStorageModel,
+    FILE_STORAGE_TABLE,
+    FILE_STORAGE_VIRTUAL_TABLE,
+};
+use usage_tracking::FunctionUsageTracker;
+use value::{
+    ConvexValue,
+    DeveloperDocumentId,
+    InternalId,
+    TableName,
+    TableNamespace,
+    TableNumber,
+};
+
+use crate::Application;
+
+/// How many orphaned files are deleted in each transaction.
+const DELETE_BATCH_SIZE: usize = 128;
+
+#[derive(Clone, Debug)]
+pub struct StorageGcOptions {
+    /// Delete the orphaned files after finding them.
+    pub delete: bool,
+    /// Files younger than this aren't considered orphaned, since their
+    /// uploader may not have saved their IDs yet.
+    pub min_file_age: Duration,
+}
+
+impl Default for StorageGcOptions {
+    fn default() -> Self {
+        Self {
+            delete: false,
+            min_file_age: *STORAGE_GC_MIN_FILE_AGE,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OrphanedFile {
+    pub storage_id: DeveloperDocumentId,
+    pub size: i64,
+    pub content_type: Option<String>,
+    pub creation_time: f64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StorageGcReport {
+    /// The tables that were scanned for references to files.
+    pub scanned_tables: Vec<TableName>,
+    pub total_files: u64,
+    pub orphaned_files: Vec<OrphanedFile>,
+    /// The total size of the orphaned files.
+    pub reclaimable_bytes: u64,
+    /// How many orphaned files were deleted, which is zero unless deletion
+    /// was requested. Files that were deleted concurrently aren't counted.
+    pub deleted_files: u64,
+}
+
+/// Whether documents in `table_name` can hold storage IDs. Tables that the
+/// schema doesn't validate might hold anything, so only tables with enforced
+/// document types that have no `v.id("_storage")` are skipped.
+fn may_reference_storage(schema: Option<&DatabaseSchema>, table_name: &TableName) -> bool {
+    let Some(schema) = schema.filter(|schema| schema.schema_validation) else {
+        return true;
+    };
+    let Some(table_definition) = schema.tables.get(table_name) else {
+        return true;
+    };
+    match &table_definition.document_type {
+        None | Some(DocumentSchema::Any) => true,
+        Some(document_type) => document_type
+            .foreign_keys()
+            .any(|table_name| table_name == &*FILE_STORAGE_VIRTUAL_TABLE),
+    }
+}
+
+/// The files referenced by the scanned documents, by ID or by the storage IDs
+/// used before files had document IDs.
+#[derive(Default)]
+struct StorageReferences {
+    ids: BTreeSet<InternalId>,
+    legacy_ids: BTreeSet<StorageUuid>,
+}
+
+impl StorageReferences {
+    fn collect(&mut self, file_storage_table: TableNumber, value: &ConvexValue) {
+        match value {
+            ConvexValue::String(s) => {
+                if let Ok(id) = DeveloperDocumentId::decode(s) {
+                    if id.table() == file_storage_table {
+                        self.ids.insert(id.internal_id());
+                    }
+                } else if let Ok(storage_id) = s.parse::<StorageUuid>() {
+                    self.legacy_ids.insert(storage_id);
+                }
+            },
+            ConvexValue::Array(values) => {
+                for value in values {
+                    self.collect(file_storage_table, value);
+                }
+            },
+            ConvexValue::Set(values) => {
+                for value in values {
+                    self.collect(file_storage_table, value);
+                }
+            },
+            ConvexValue::Map(entries) => {
+                for (key, value) in entries {
+                    self.collect(file_storage_table, key);
+                    self.collect(file_storage_table, value);
+                }
+            },
+            ConvexValue::Object(object) => {
+                for (_, value) in object.iter() {
+                    self.collect(file_storage_table, value);
+                }
+            },
+            ConvexValue::Null
+            | ConvexValue::Int64(_)
+            | ConvexValue::Float64(_)
+            | ConvexValue::Boolean(_)
+            | ConvexValue::Bytes(_) => {},
+        }
+    }
+
+    fn contains(&self, entry: &ParsedDocument<FileStorageEntry>) -> bool {
+        self.ids.contains(&entry.id().internal_id()) || self.legacy_ids.contains(&entry.storage_id)
+    }
+}
+
+impl<RT: Runtime> Application<RT> {
+    /// Find the files in `component`'s `_storage` that no document references.
+    /// Documents are scanned at a single snapshot, so with `delete` set a file
+    /// whose ID is saved in a document while the report runs can still be
+    /// deleted; `min_file_age` keeps this from happening to new uploads.
+    pub async fn storage_gc_report(
+        &self,
+        identity: Identity,
+        component: ComponentId,
+        options: StorageGcOptions,
+    ) -> anyhow::Result<StorageGcReport> {
+        if !identity.is_admin() {
+            anyhow::bail!(unauthorized_error("storage_gc_report"));
+        }
+        if options.delete {
+            self.bail_if_not_running().await?;
+        }
+        let namespace = TableNamespace::from(component);
+        let mut tx = self.begin(identity).await?;
+        let schema = SchemaModel::new(&mut tx, namespace)
+            .get_by_state(SchemaState::Active)
+            .await?
+            .map(|(_id, schema)| schema);
+        let by_id_indexes = IndexModel::new(&mut tx).by_id_indexes().await?;
+        let snapshot_ts = tx.begin_timestamp();
+        let table_mapping = tx.table_mapping().namespace(namespace);
+        let Some(file_storage) = table_mapping.id_and_number_if_exists(&FILE_STORAGE_TABLE) else {
+            return Ok(StorageGcReport::default());
+        };
+        let scanned_tables: Vec<_> = table_mapping
+            .iter_active_user_tables()
+            .filter(|(_, _, table_name)| may_reference_storage(schema.as_ref(), table_name))
+            .map(|(tablet_id, _, table_name)| (tablet_id, table_name.clone()))
+            .collect();
+
+        let mut references = StorageReferences::default();
+        for (tablet_id, table_name) in &scanned_tables {
+            let by_id = by_id_indexes
+                .get(tablet_id)
+                .with_context(|| format!("{table_name}.by_id does not exist"))?;
+            let table_iterator = self.database.table_iterator(snapshot_ts, 1000, None);
+            let stream = table_iterator.stream_documents_in_table(*tablet_id, *by_id, None);
+            pin_mut!(stream);
+            while let Some((doc, _ts)) = stream.try_next().await? {
+                for (_, value) in doc.value().iter() {
+                    references.collect(file_storage.table_number, value);
+                }
+            }
+        }
+
+        let cutoff = self.runtime.unix_timestamp().as_ms_since_epoch()? as f64
+            - options.min_file_age.as_millis() as f64;
+        let mut report = StorageGcReport {
+            scanned_tables: scanned_tables
+                .into_iter()
+                .map(|(_, table_name)| table_name)
+                .collect(),
+            ..Default::default()
+        };
+        let by_id = by_id_indexes
+            .get(&file_storage.tablet_id)
+            .context("_file_storage.by_id does not exist")?;
+        let table_iterator = self.database.table_iterator(snapshot_ts, 1000, None);
+        let stream = table_iterator.stream_documents_in_table(file_storage.tablet_id, *by_id, None);
+        pin_mut!(stream);
+        while let Some((doc, _ts)) = stream.try_next().await? {
+            let entry = ParsedDocument::<FileStorageEntry>::try_from(doc)?;
+            report.total_files += 1;
+            let creation_time = f64::from(
+                entry
+                    .creation_time()
+                    .context("file should have creation time")?,
+            );
+            if references.contains(&entry) || creation_time > cutoff {
+                continue;
+            }
+            report.reclaimable_bytes += entry.size as u64;
+            report.orphaned_files.push(OrphanedFile {
+                storage_id: entry.id().developer_id,
+                size: entry.size,
+                content_type: entry.content_type.clone(),
+                creation_time,
+            });
+        }
+
+        if options.delete {
+            for batch in report.orphaned_files.chunks(DELETE_BATCH_SIZE) {
+                let (_, deleted, _) = self
+                    .database
+                    .execute_with_occ_retries(
+                        Identity::system(),
+                        FunctionUsageTracker::new(),
+                        PauseClient::new(),
+                        "storage_gc_delete",
+                        |tx| {
+                            async move {
+                                let mut deleted = 0;
+                                for file in batch {
+                                    if FileStorageModel::new(tx, namespace)
+                                        .delete_file(
+                                            FileStorageId::DocumentId(file.storage_id),
+                                            Identity::system(),
+                                        )
+                                        .await?
+                                        .is_some()
+                                    {
+                                        deleted += 1;
+                                    }
+                                }
+                                Ok(deleted)
+                            }
+                            .into()
+                        },
+                    )
+                    .await?;
+                report.deleted_files += deleted;
+            }
+            tracing::info!(
+                "Deleted {} orphaned files from {component:?}'s storage",
+                report.deleted_files
+            );
+        }
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{
+        db_schema,
+        object_validator,
+        schemas::{
+            validator::{
+                FieldValidator,
+                Validator,
+            },
+            DocumentSchema,
+        },
+    };
+    use value::{
+        assert_val,
+        DeveloperDocumentId,
+        InternalId,
+        TableNumber,
+    };
+
+    use super::{
+        may_reference_storage,
+        StorageReferences,
+    };
+
+    #[test]
+    fn test_tables_scanned_for_storage_references() -> anyhow::Result<()> {
+        let mut schema = db_schema!(
+            "messages" => DocumentSchema::Union(vec![object_validator!(
+                "body" => FieldValidator::required_field_type(Validator::String),
+                "image" => FieldValidator::optional_field_type(
+                    Validator::Id("_storage".parse()?)
+                ),
+            )]),
+            "users" => DocumentSchema::Union(vec![object_validator!(
+                "name" => FieldValidator::required_field_type(Validator::String),
+            )]),
+            "logs" => DocumentSchema::Any,
+        );
+        assert!(may_reference_storage(Some(&schema), &"messages".parse()?));
+        assert!(!may_reference_storage(Some(&schema), &"users".parse()?));
+        assert!(may_reference_storage(Some(&schema), &"logs".parse()?));
+        assert!(may_reference_storage(Some(&schema), &"other".parse()?));
+        assert!(may_reference_storage(None, &"users".parse()?));
+
+        schema.schema_validation = false;
+        assert!(may_reference_storage(Some(&schema), &"users".parse()?));
+        Ok(())
+    }
+
+    #[test]
+    fn test_collect_storage_references() -> anyhow::Result<()> {
+        let file_storage_table = TableNumber::try_from(7)?;
+        let referenced = DeveloperDocumentId::new(file_storage_table, InternalId::MIN);
+        let other_table = DeveloperDocumentId::new(TableNumber::try_from(8)?, InternalId::MAX);
+        let value = assert_val!({
+            "nested" => [{ "file" => referenced.encode() }],
+            "other" => other_table.encode(),
+            "legacy" => "d4f2c1a0-8b3e-4c5d-9e6f-7a8b9c0d1e2f",
+        });
+        let mut references = StorageReferences::default();
+        references.collect(file_storage_table, &value);
+        assert_eq!(
+            references.ids.into_iter().collect::<Vec<_>>(),
+            vec![InternalId::MIN]
+        );
+        assert_eq!(
+            references
+                .legacy_ids
+                .into_iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>(),
+            vec!["d4f2c1a0-8b3e-4c5d-9e6f-7a8b9c0d1e2f".to_string()]
+        );
+        Ok(())
+    }
+}
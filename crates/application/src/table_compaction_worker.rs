@@ -0,0 +1,385 @@
+//! Runs table compactions requested through the admin API.
+//!
+//! Retention eventually deletes every revision that's no longer visible, but it
+//! works through the whole document log in order and may be far behind for a
+//! single high-churn table. Compacting a table scans just that table's history
+//! up to the oldest snapshot still in retention and deletes its superseded
+//! revisions, tombstones and their index entries right away, saving progress
+//! to the compaction's document after each chunk so it can resume after a
+//! restart.
+use std::{
+    sync::Arc,
+    time::Duration,
+};
+
+use common::{
+    backoff::Backoff,
+    bootstrap_model::index::{
+        database_index::{
+            DatabaseIndexState,
+            IndexedFields,
+        },
+        IndexConfig,
+    },
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    errors::report_error,
+    index::{
+        IndexEntry,
+        SplitKey,
+    },
+    knobs::{
+        DEFAULT_DOCUMENTS_PAGE_SIZE,
+        TABLE_COMPACTION_CHUNK_SIZE,
+        TABLE_COMPACTION_ROWS_PER_SECOND,
+    },
+    persistence::{
+        NoopRetentionValidator,
+        Persistence,
+        TimestampRange,
+    },
+    query::Order,
+    runtime::{
+        new_rate_limiter,
+        RateLimiter,
+        Runtime,
+    },
+    sha256::Sha256,
+    types::{
+        IndexId,
+        Timestamp,
+    },
+};
+use database::{
+    Database,
+    IndexModel,
+};
+use errors::{
+    ErrorMetadata,
+    ErrorMetadataAnyhowExt,
+};
+use futures::{
+    pin_mut,
+    Future,
+    TryStreamExt,
+};
+use governor::Quota;
+use keybroker::Identity;
+use model::table_compactions::{
+    types::{
+        TableCompaction,
+        TableCompactionProgress,
+        TableCompactionState,
+    },
+    TableCompactionModel,
+};
+use value::{
+    InternalDocumentId,
+    ResolvedDocumentId,
+    TabletId,
+};
+
+use crate::metrics::log_worker_starting;
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+type Revision = (Timestamp, InternalDocumentId, Option<ResolvedDocument>);
+
+pub struct TableCompactionWorker<RT: Runtime> {
+    runtime: RT,
+    database: Database<RT>,
+    persistence: Arc<dyn Persistence>,
+    backoff: Backoff,
+}
+
+impl<RT: Runtime> TableCompactionWorker<RT> {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        runtime: RT,
+        database: Database<RT>,
+        persistence: Arc<dyn Persistence>,
+    ) -> impl Future<Output = ()> + Send {
+        let mut worker = Self {
+            runtime,
+            database,
+            persistence,
+            backoff: Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF),
+        };
+        async move {
+            loop {
+                if let Err(mut e) = worker.run().await {
+                    report_error(&mut e);
+                    let delay = worker.backoff.fail(&mut worker.runtime.rng());
+                    tracing::error!("TableCompactionWorker failed, sleeping {delay:?}");
+                    worker.runtime.wait(delay).await;
+                } else {
+                    worker.backoff.reset();
+                }
+            }
+        }
+    }
+
+    /// Runs the oldest unfinished compaction, or waits for one to be
+    /// requested.
+    async fn run(&mut self) -> anyhow::Result<()> {
+        let mut tx = self.database.begin(Identity::system()).await?;
+        if let Some(compaction) = TableCompactionModel::new(&mut tx).next_unfinished().await? {
+            let _status = log_worker_starting("TableCompactionWorker");
+            return self.compact(compaction).await;
+        }
+        let token = tx.into_token()?;
+        let subscription = self.database.subscribe(token).await?;
+        subscription.wait_for_invalidation().await;
+        Ok(())
+    }
+
+    async fn compact(&self, compaction: ParsedDocument<TableCompaction>) -> anyhow::Result<()> {
+        let (id, compaction) = compaction.into_id_and_value();
+        let mut progress = match compaction.state {
+            TableCompactionState::Requested => {
+                // Index entries are kept for less time than documents, so
+                // anything older than both snapshots can go.
+                let retention_validator = self.database.retention_validator();
+                let target_ts = std::cmp::min(
+                    *retention_validator.min_document_snapshot_ts().await?,
+                    *retention_validator.min_snapshot_ts().await?,
+                );
+                let progress = TableCompactionProgress::new(target_ts);
+                tracing::info!("Compacting {} up to {target_ts:?}", compaction.table_name);
+                if !self
+                    .save_progress(id, TableCompactionState::InProgress { progress })
+                    .await?
+                {
+                    return Ok(());
+                }
+                progress
+            },
+            TableCompactionState::InProgress { progress } => progress,
+            _ => return Ok(()),
+        };
+        let result = self
+            .compact_inner(id, compaction.tablet_id, &mut progress)
+            .await;
+        let state = match result {
+            Ok(false) => {
+                tracing::info!("Table compaction {id} was canceled");
+                return Ok(());
+            },
+            Ok(true) => {
+                tracing::info!(
+                    "Finished compacting {}: deleted {} document revisions and {} index entries",
+                    compaction.table_name,
+                    progress.documents_deleted,
+                    progress.index_entries_deleted
+                );
+                TableCompactionState::Completed { progress }
+            },
+            Err(e) if e.is_deterministic_user_error() => TableCompactionState::Failed {
+                error: e.user_facing_message(),
+                progress: Some(progress),
+            },
+            Err(e) => return Err(e),
+        };
+        self.save_progress(id, state).await?;
+        Ok(())
+    }
+
+    /// Returns false if the compaction was canceled before it finished.
+    async fn compact_inner(
+        &self,
+        id: ResolvedDocumentId,
+        tablet_id: TabletId,
+        progress: &mut TableCompactionProgress,
+    ) -> anyhow::Result<bool> {
+        let indexes = self.database_indexes(tablet_id).await?;
+        let mut rate_limiter = new_rate_limiter(
+            self.runtime.clone(),
+            Quota::per_second(*TABLE_COMPACTION_ROWS_PER_SECOND),
+        );
+        let reader = self.persistence.reader();
+        let stream = reader.load_documents_from_table(
+            tablet_id,
+            TimestampRange::new(progress.cursor..progress.target_ts)?,
+            Order::Asc,
+            *DEFAULT_DOCUMENTS_PAGE_SIZE,
+            Arc::new(NoopRetentionValidator),
+        );
+        pin_mut!(stream);
+        let mut chunk = Vec::with_capacity(*TABLE_COMPACTION_CHUNK_SIZE);
+        while let Some(revision) = stream.try_next().await? {
+            chunk.push(revision);
+            if chunk.len() >= *TABLE_COMPACTION_CHUNK_SIZE {
+                self.compact_chunk(
+                    std::mem::take(&mut chunk),
+                    &indexes,
+                    &mut rate_limiter,
+                    progress,
+                )
+                .await?;
+                let state = TableCompactionState::InProgress {
+                    progress: *progress,
+                };
+                if !self.save_progress(id, state).await? {
+                    return Ok(false);
+                }
+            }
+        }
+        self.compact_chunk(chunk, &indexes, &mut rate_limiter, progress)
+            .await?;
+        progress.cursor = progress.target_ts;
+        Ok(true)
+    }
+
+    /// The fields of each database index on the table. Text and vector indexes
+    /// don't keep entries in persistence.
+    async fn database_indexes(
+        &self,
+        tablet_id: TabletId,
+    ) -> anyhow::Result<Vec<(IndexId, IndexedFields)>> {
+        let mut tx = self.database.begin(Identity::system()).await?;
+        anyhow::ensure!(
+            tx.table_mapping().is_active(tablet_id),
+            ErrorMetadata::bad_request(
+                "TableNotFound",
+                "The table was deleted before it could be compacted"
+            )
+        );
+        let mut indexes = vec![];
+        for index in IndexModel::new(&mut tx)
+            .all_indexes_on_table(tablet_id)
+            .await?
+        {
+            let IndexConfig::Database {
+                developer_config,
+                on_disk_state,
+            } = &index.config
+            else {
+                continue;
+            };
+            // A backfill writes entries for the revisions at its snapshot, so
+            // it could race with us deleting them.
+            anyhow::ensure!(
+                !matches!(on_disk_state, DatabaseIndexState::Backfilling(_)),
+                ErrorMetadata::bad_request(
+                    "IndexBackfilling",
+                    format!(
+                        "Index {} is still backfilling. Try compacting the table again once it \
+                         has finished.",
+                        index.name.descriptor()
+                    )
+                )
+            );
+            indexes.push((index.id().internal_id(), developer_config.fields.clone()));
+        }
+        Ok(indexes)
+    }
+
+    /// Deletes the revisions that `chunk`'s revisions replaced, as well as
+    /// tombstones, since no snapshot in retention can see either.
+    async fn compact_chunk(
+        &self,
+        chunk: Vec<Revision>,
+        indexes: &[(IndexId, IndexedFields)],
+        rate_limiter: &mut RateLimiter<RT>,
+        progress: &mut TableCompactionProgress,
+    ) -> anyhow::Result<()> {
+        let Some((last_ts, ..)) = chunk.last() else {
+            return Ok(());
+        };
+        let last_ts = *last_ts;
+        let reader = self.persistence.reader();
+        let persistence_version = reader.version();
+        let prev_revs = reader
+            .previous_revisions(
+                chunk.iter().map(|(ts, id, _)| (*id, *ts)).collect(),
+                Arc::new(NoopRetentionValidator),
+            )
+            .await?;
+        let mut documents = vec![];
+        let mut index_entries = vec![];
+        for (ts, id, maybe_doc) in &chunk {
+            if let Some((prev_ts, Some(prev_rev))) = prev_revs.get(&(*id, *ts)) {
+                documents.push((*prev_ts, *id));
+                for (index_id, fields) in indexes {
+                    let index_key = prev_rev.index_key(fields, persistence_version).into_bytes();
+                    let key_sha256 = Sha256::hash(&index_key).to_vec();
+                    let key = SplitKey::new(index_key.clone().0);
+                    index_entries.push(IndexEntry {
+                        index_id: *index_id,
+                        key_prefix: key.prefix.clone(),
+                        key_suffix: key.suffix.clone(),
+                        key_sha256: key_sha256.clone(),
+                        ts: *prev_ts,
+                        deleted: false,
+                    });
+                    // The revision at `ts` wrote a tombstone for the previous
+                    // key if it moved the document or deleted it.
+                    let next_index_key = maybe_doc
+                        .as_ref()
+                        .map(|doc| doc.index_key(fields, persistence_version).into_bytes());
+                    if next_index_key.as_ref() != Some(&index_key) {
+                        index_entries.push(IndexEntry {
+                            index_id: *index_id,
+                            key_prefix: key.prefix,
+                            key_suffix: key.suffix,
+                            key_sha256,
+                            ts: *ts,
+                            deleted: true,
+                        });
+                    }
+                }
+            }
+            if maybe_doc.is_none() {
+                documents.push((*ts, *id));
+            }
+        }
+        for _ in 0..(documents.len() + index_entries.len()) {
+            while let Err(not_until) = rate_limiter.check() {
+                let delay = not_until.wait_time_from(self.runtime.monotonic_now().into());
+                self.runtime.wait(delay).await;
+            }
+        }
+        // Delete index entries first, so if we fail in between, retention can
+        // still find the documents it needs to compute their keys.
+        let index_entries_deleted = if index_entries.is_empty() {
+            0
+        } else {
+            self.persistence.delete_index_entries(index_entries).await?
+        };
+        let documents_deleted = if documents.is_empty() {
+            0
+        } else {
+            self.persistence.delete(documents).await?
+        };
+        progress.cursor = last_ts;
+        progress.revisions_scanned += chunk.len() as u64;
+        progress.documents_deleted += documents_deleted as u64;
+        progress.index_entries_deleted += index_entries_deleted as u64;
+        Ok(())
+    }
+
+    /// Saves the compaction's new state, returning false instead if it was
+    /// canceled in the meantime.
+    async fn save_progress(
+        &self,
+        id: ResolvedDocumentId,
+        state: TableCompactionState,
+    ) -> anyhow::Result<bool> {
+        let mut tx = self.database.begin(Identity::system()).await?;
+        let mut model = TableCompactionModel::new(&mut tx);
+        let Some(compaction) = model.get(id).await? else {
+            return Ok(false);
+        };
+        if compaction.state.is_finished() {
+            return Ok(false);
+        }
+        model.update_state(id, state).await?;
+        self.database
+            .commit_with_write_source(tx, "table_compaction_worker")
+            .await?;
+        Ok(true)
+    }
+}
@@ -29,7 +29,10 @@ use futures::{
 use parking_lot::Mutex;
 use tokio::sync::oneshot;
 
-use crate::metrics::log_worker_starting;
+use crate::metrics::{
+    log_table_document_counts,
+    log_worker_starting,
+};
 
 pub struct TableSummaryWorker<RT: Runtime> {
     runtime: RT,
@@ -121,6 +124,10 @@ impl<RT: Runtime> TableSummaryWorker<RT> {
             if let Err(mut err) = result {
                 report_error(&mut err);
             }
+            match self.database.get_document_counts().await {
+                Ok(counts) => log_table_document_counts(counts),
+                Err(mut err) => report_error(&mut err),
+            }
             let wait_fut = self.runtime.wait(Duration::from_secs(10)).fuse();
             pin_mut!(wait_fut);
             select_biased! {
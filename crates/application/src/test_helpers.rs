@@ -269,6 +269,7 @@ impl<RT: Runtime> ApplicationTestExt<RT> for Application<RT> {
             segment_term_metadata_fetcher,
             Arc::new(persistence.clone()),
             actions,
+            BTreeMap::new(),
             fetch_client,
             Arc::new(NoopLogSender),
             Arc::new(AllowLogging),
@@ -278,6 +279,7 @@ impl<RT: Runtime> ApplicationTestExt<RT> for Application<RT> {
                 kb.clone(),
                 Arc::new(NullAccessTokenAuth),
             )),
+            None,
         )
         .await?;
 
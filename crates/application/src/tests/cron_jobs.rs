@@ -80,6 +80,8 @@ async fn create_cron_job(
         udf_path: path.udf_path.clone(),
         udf_args: parse_udf_args(&path.udf_path, vec![JsonValue::Object(map)])?,
         cron_schedule: CronSchedule::Interval { seconds: 60 },
+        time_zone: None,
+        jitter: None,
     };
     let original_jobs = cron_model.list().await?;
     let name = test_cron_identifier();
@@ -0,0 +1,168 @@
+use std::{
+    sync::Arc,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use common::{
+    assert_obj,
+    persistence::Persistence,
+    runtime::Runtime,
+    schemas::DatabaseSchema,
+    testing::TestPersistence,
+    types::Timestamp,
+};
+use database::{
+    commit_hooks::{
+        load_commit_hook_checkpoints,
+        CommitHooks,
+    },
+    SchemaModel,
+    TestFacingModel,
+};
+use keybroker::Identity;
+use runtime::testing::TestRuntime;
+use serde_json::json;
+use value::{
+    assert_val,
+    ConvexValue,
+    ResolvedDocumentId,
+    TableNamespace,
+};
+
+use crate::{
+    embeddings::{
+        EmbeddingPipeline,
+        EmbeddingProvider,
+    },
+    test_helpers::{
+        ApplicationFixtureArgs,
+        ApplicationTestExt,
+    },
+    Application,
+};
+
+/// Embeds each text as its length and a constant, so tests can tell which
+/// text a vector came from.
+struct FakeEmbeddingProvider;
+
+#[async_trait]
+impl EmbeddingProvider for FakeEmbeddingProvider {
+    async fn embed(&self, texts: Vec<String>) -> anyhow::Result<Vec<Vec<f32>>> {
+        Ok(texts
+            .into_iter()
+            .map(|text| vec![text.len() as f32, 1.0])
+            .collect())
+    }
+}
+
+#[convex_macro::test_runtime]
+async fn test_embedding_pipeline(rt: TestRuntime) -> anyhow::Result<()> {
+    let tp = TestPersistence::new();
+    let application = Application::new_for_tests_with_args(
+        &rt,
+        ApplicationFixtureArgs {
+            tp: Some(tp.clone()),
+            ..Default::default()
+        },
+    )
+    .await?;
+    let database = application.database().clone();
+
+    let schema = DatabaseSchema::try_from(json!({
+        "tables": [
+            {
+                "tableName": "documents",
+                "indexes": [],
+                "vectorIndexes": [
+                    {
+                        "indexDescriptor": "by_embedding",
+                        "vectorField": "embedding",
+                        "dimensions": 2,
+                        "filterFields": [],
+                        "embedFrom": "text",
+                    },
+                ],
+            },
+        ],
+    }))?;
+    let mut tx = database.begin(Identity::system()).await?;
+    let mut model = SchemaModel::new(&mut tx, TableNamespace::test_user());
+    let (schema_id, _) = model.submit_pending(schema).await?;
+    model.mark_validated(schema_id).await?;
+    model.mark_active(schema_id).await?;
+    database.commit(tx).await?;
+
+    let mut hooks = CommitHooks::new();
+    hooks.register(Arc::new(EmbeddingPipeline::new(
+        database.clone(),
+        tp.reader(),
+        Arc::new(FakeEmbeddingProvider),
+    )))?;
+    let _handle = rt.spawn(
+        "commit_hooks",
+        hooks.start(
+            rt.clone(),
+            Arc::new(tp.clone()),
+            database.retention_validator(),
+            database.clone(),
+        ),
+    );
+    // Wait for the hook to start so it observes the writes below.
+    while load_commit_hook_checkpoints(tp.reader().as_ref())
+        .await?
+        .is_empty()
+    {
+        rt.wait(Duration::from_millis(10)).await;
+    }
+
+    let wait_for_embedding = |id: ResolvedDocumentId, expected: ConvexValue| {
+        let database = database.clone();
+        let rt = rt.clone();
+        async move {
+            loop {
+                let mut tx = database.begin(Identity::system()).await?;
+                let document = tx.get(id).await?.unwrap();
+                if document.value().get("embedding") == Some(&expected) {
+                    return anyhow::Ok(());
+                }
+                rt.wait(Duration::from_millis(10)).await;
+            }
+        }
+    };
+
+    let mut tx = database.begin(Identity::system()).await?;
+    let id = TestFacingModel::new(&mut tx)
+        .insert(&"documents".parse()?, assert_obj!("text" => "hello"))
+        .await?;
+    database.commit(tx).await?;
+    wait_for_embedding(id, assert_val!([5.0, 1.0])).await?;
+
+    // Changing the text embeds it again.
+    let mut tx = database.begin(Identity::system()).await?;
+    TestFacingModel::new(&mut tx)
+        .replace(id, assert_obj!("text" => "hello world"))
+        .await?;
+    database.commit(tx).await?;
+    wait_for_embedding(id, assert_val!([11.0, 1.0])).await?;
+
+    // A write that sets its own vector keeps it.
+    let mut tx = database.begin(Identity::system()).await?;
+    let own_id = TestFacingModel::new(&mut tx)
+        .insert(
+            &"documents".parse()?,
+            assert_obj!("text" => "hi", "embedding" => [0.0, 0.0]),
+        )
+        .await?;
+    let ts: Timestamp = database.commit(tx).await?;
+    while load_commit_hook_checkpoints(tp.reader().as_ref()).await?["embedding_pipeline"] < ts {
+        rt.wait(Duration::from_millis(10)).await;
+    }
+    let mut tx = database.begin(Identity::system()).await?;
+    let document = tx.get(own_id).await?.unwrap();
+    assert_eq!(
+        document.value().get("embedding"),
+        Some(&assert_val!([0.0, 0.0]))
+    );
+    Ok(())
+}
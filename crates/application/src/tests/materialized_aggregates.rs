@@ -0,0 +1,142 @@
+use std::time::Duration;
+
+use common::{
+    assert_obj,
+    components::ComponentId,
+    query::Order,
+};
+use database::{
+    materialized_aggregates::{
+        MaterializedAggregateModel,
+        MaterializedAggregateOrderBy,
+    },
+    UserFacingModel,
+};
+use keybroker::Identity;
+use runtime::testing::TestRuntime;
+use value::{
+    ConvexValue,
+    TableName,
+    TableNamespace,
+};
+
+use crate::{
+    test_helpers::ApplicationTestExt,
+    Application,
+};
+
+#[convex_macro::test_runtime]
+async fn test_materialized_aggregate(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    let database = application.database().clone();
+    let table: TableName = "scores".parse()?;
+
+    // Documents written before the aggregate exists are backfilled.
+    let mut tx = database.begin(Identity::system()).await?;
+    let mut model = UserFacingModel::new_root_for_test(&mut tx);
+    let red = model
+        .insert(table.clone(), assert_obj!("team" => "red", "points" => 3.0))
+        .await?;
+    model
+        .insert(
+            table.clone(),
+            assert_obj!("team" => "blue", "points" => 5.0),
+        )
+        .await?;
+    model
+        .insert(table.clone(), assert_obj!("points" => 100.0))
+        .await?;
+    database.commit(tx).await?;
+
+    application
+        .create_materialized_aggregate(
+            Identity::system(),
+            ComponentId::Root,
+            table.clone(),
+            "by_team".to_string(),
+            Some("team".parse()?),
+            Some("points".parse()?),
+        )
+        .await?;
+    while !application
+        .materialized_aggregates(Identity::system())
+        .await?[0]
+        .ready
+    {
+        rt.wait(Duration::from_millis(10)).await;
+    }
+
+    // Later writes update it in the same transaction.
+    let mut tx = database.begin(Identity::system()).await?;
+    let mut model = UserFacingModel::new_root_for_test(&mut tx);
+    let new_red = model
+        .insert(table.clone(), assert_obj!("team" => "red", "points" => 4.0))
+        .await?;
+    model
+        .replace(red, assert_obj!("team" => "blue", "points" => 10.0))
+        .await?;
+    database.commit(tx).await?;
+
+    let mut tx = database.begin(Identity::system()).await?;
+    let mut model = MaterializedAggregateModel::new(&mut tx);
+    let aggregate = model
+        .get_ready(TableNamespace::root_component(), &table, "by_team")
+        .await?;
+    let red_entry = model
+        .entry(aggregate.id(), ConvexValue::try_from("red")?)
+        .await?
+        .unwrap();
+    assert_eq!((red_entry.count, red_entry.sum), (1, 4.0));
+    let top = model
+        .entries(
+            aggregate.id(),
+            MaterializedAggregateOrderBy::Sum,
+            Order::Desc,
+            10,
+        )
+        .await?;
+    assert_eq!(
+        top.iter()
+            .map(|entry| (entry.key.clone(), entry.count, entry.sum))
+            .collect::<Vec<_>>(),
+        vec![
+            (ConvexValue::try_from("blue")?, 2, 15.0),
+            (ConvexValue::try_from("red")?, 1, 4.0),
+        ]
+    );
+
+    // Deleting the last document with a key deletes its entry.
+    let mut tx = database.begin(Identity::system()).await?;
+    UserFacingModel::new_root_for_test(&mut tx)
+        .delete(new_red)
+        .await?;
+    database.commit(tx).await?;
+    let mut tx = database.begin(Identity::system()).await?;
+    assert_eq!(
+        MaterializedAggregateModel::new(&mut tx)
+            .entry(aggregate.id(), ConvexValue::try_from("red")?)
+            .await?,
+        None
+    );
+
+    application
+        .delete_materialized_aggregate(
+            Identity::system(),
+            ComponentId::Root,
+            table.clone(),
+            "by_team".to_string(),
+        )
+        .await?;
+    loop {
+        let mut tx = database.begin(Identity::system()).await?;
+        if MaterializedAggregateModel::new(&mut tx)
+            .all()
+            .await?
+            .is_empty()
+        {
+            break;
+        }
+        rt.wait(Duration::from_millis(10)).await;
+    }
+    Ok(())
+}
@@ -2,14 +2,18 @@ mod analyze;
 mod auth_config;
 mod components;
 mod cron_jobs;
+mod embeddings;
 mod environment_variables;
+mod materialized_aggregates;
 mod mutation;
 mod occ_retries;
+mod query_cache;
 mod returns_validation;
 mod scheduled_jobs;
 mod schema;
 mod source_package;
 mod storage;
+mod usage_quotas;
 
 const NODE_SOURCE: &str = r#"
 var nodeFunction = () => {};
@@ -176,3 +176,58 @@ async fn test_multiple_inserts_dont_occ(rt: TestRuntime) -> anyhow::Result<()> {
     assert_eq!(result["an"], "object");
     Ok(())
 }
+
+#[convex_macro::test_runtime]
+async fn test_mutation_batch_is_atomic(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    application.load_udf_tests_modules().await?;
+    let path = |udf_path: &str| -> anyhow::Result<PublicFunctionPath> {
+        Ok(PublicFunctionPath::Component(
+            CanonicalizedComponentFunctionPath {
+                component: ComponentPath::test_user(),
+                udf_path: udf_path.parse()?,
+            },
+        ))
+    };
+    let obj = json!({"an": "object"});
+
+    // Later mutations see the writes of earlier ones.
+    let batch_return = application
+        .mutation_batch_udf(
+            RequestId::new(),
+            vec![
+                (path("basic:insertAndCount")?, vec![obj.clone()]),
+                (path("basic:insertAndCount")?, vec![obj.clone()]),
+            ],
+            Identity::system(),
+            FunctionCaller::Action {
+                parent_scheduled_job: None,
+            },
+        )
+        .await??;
+    let counts: Vec<_> = batch_return
+        .results
+        .into_iter()
+        .map(|result| JsonValue::from(result.value))
+        .collect();
+    assert_eq!(counts, vec![json!(1.0), json!(2.0)]);
+
+    // A failing mutation rolls back the whole batch.
+    let batch_error = application
+        .mutation_batch_udf(
+            RequestId::new(),
+            vec![
+                (path("basic:insertAndCount")?, vec![obj.clone()]),
+                (path("custom_errors:mutationThrows")?, vec![json!({})]),
+            ],
+            Identity::system(),
+            FunctionCaller::Action {
+                parent_scheduled_job: None,
+            },
+        )
+        .await?
+        .unwrap_err();
+    assert_eq!(batch_error.index, Some(1));
+    assert_eq!(insert_and_count(&application, PauseClient::new()).await?, 3);
+    Ok(())
+}
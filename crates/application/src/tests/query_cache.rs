@@ -0,0 +1,76 @@
+use common::{
+    components::{
+        CanonicalizedComponentFunctionPath,
+        ComponentPath,
+        PublicFunctionPath,
+    },
+    types::FunctionCaller,
+    RequestId,
+};
+use keybroker::{
+    testing::TestUserIdentity,
+    Identity,
+    UserIdentity,
+};
+use runtime::testing::TestRuntime;
+use serde_json::json;
+use value::ConvexValue;
+
+use crate::{
+    test_helpers::ApplicationTestExt,
+    Application,
+};
+
+async fn run_zero_arg_query(
+    application: &Application<TestRuntime>,
+    name: &str,
+    identity: Identity,
+) -> anyhow::Result<ConvexValue> {
+    let result = application
+        .read_only_udf(
+            RequestId::new(),
+            PublicFunctionPath::Component(CanonicalizedComponentFunctionPath {
+                component: ComponentPath::test_user(),
+                udf_path: name.parse()?,
+            }),
+            vec![json!({})],
+            identity,
+            FunctionCaller::HttpEndpoint,
+        )
+        .await?;
+    Ok(result.result.unwrap().unpack())
+}
+
+#[convex_macro::test_runtime]
+async fn test_query_without_identity_is_shared(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    application.load_udf_tests_modules().await?;
+    let anonymous =
+        run_zero_arg_query(&application, "globals:getRandom", Identity::Unknown).await?;
+    // A different user gets the cached result, since the query doesn't read
+    // the identity.
+    let user = run_zero_arg_query(
+        &application,
+        "globals:getRandom",
+        Identity::user(UserIdentity::test()),
+    )
+    .await?;
+    assert_eq!(anonymous, user);
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_query_with_identity_is_not_shared(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    application.load_udf_tests_modules().await?;
+    let anonymous = run_zero_arg_query(&application, "auth:getName", Identity::Unknown).await?;
+    assert_eq!(anonymous, ConvexValue::Null);
+    let user = run_zero_arg_query(
+        &application,
+        "auth:getName",
+        Identity::user(UserIdentity::test()),
+    )
+    .await?;
+    assert_ne!(user, ConvexValue::Null);
+    Ok(())
+}
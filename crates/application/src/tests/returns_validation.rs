@@ -155,7 +155,7 @@ async fn test_query_output(rt: TestRuntime) -> anyhow::Result<()> {
         "returns_validation:stringOutputReturnsStringQuery",
     )
     .await?;
-    assert!(format!("{}", result.result.unwrap()).contains("hello"));
+    assert!(format!("{}", result.result.unwrap().unpack()).contains("hello"));
     Ok(())
 }
 
@@ -32,7 +32,12 @@ use model::{
         BackendStateModel,
     },
     scheduled_jobs::{
-        types::ScheduledJobState,
+        types::{
+            IdempotencyKey,
+            ScheduledJobFilter,
+            ScheduledJobState,
+            ScheduledJobsCursor,
+        },
         SchedulerModel,
     },
 };
@@ -154,6 +159,132 @@ async fn test_scheduled_jobs_canceled(rt: TestRuntime) -> anyhow::Result<()> {
     Ok(())
 }
 
+#[convex_macro::test_runtime]
+async fn test_scheduled_jobs_list_and_cancel_matching(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    application.load_udf_tests_modules().await?;
+
+    let mut tx = application.begin(Identity::system()).await?;
+    let path = insert_object_path();
+    let (_, component) =
+        BootstrapComponentsModel::new(&mut tx).must_component_path_to_ids(&path.component)?;
+    let mut model = SchedulerModel::new(&mut tx, component.into());
+    let now = rt.unix_timestamp();
+    let mut job_ids = vec![];
+    for minutes in 1..=3 {
+        let job_id = model
+            .schedule(
+                path.clone(),
+                parse_udf_args(&path.udf_path, vec![JsonValue::Object(Default::default())])?,
+                now + Duration::from_secs(60 * minutes),
+                ExecutionContext::new_for_test(),
+            )
+            .await?;
+        job_ids.push(job_id);
+    }
+
+    // Page through every job, two at a time.
+    let (page, cursor) = model
+        .list_matching(ScheduledJobFilter::default(), None, 2)
+        .await?;
+    assert_eq!(
+        page.iter().map(|job| job.id()).collect::<Vec<_>>(),
+        job_ids[..2]
+    );
+    let cursor = ScheduledJobsCursor::decode(&cursor.unwrap().encode())?;
+    let (page, cursor) = model
+        .list_matching(ScheduledJobFilter::default(), Some(cursor), 2)
+        .await?;
+    assert_eq!(
+        page.iter().map(|job| job.id()).collect::<Vec<_>>(),
+        job_ids[2..]
+    );
+    assert!(cursor.is_none());
+
+    // Cancel the jobs after the first one, leaving it pending.
+    let filter = ScheduledJobFilter {
+        path: Some(path.clone()),
+        start_ts: Some(
+            (now + Duration::from_secs(90))
+                .as_system_time()
+                .try_into()?,
+        ),
+        end_ts: None,
+    };
+    assert_eq!(model.cancel_matching(filter.clone(), 10).await?, 2);
+    assert_eq!(model.cancel_matching(filter, 10).await?, 0);
+    assert_eq!(
+        model.check_status(job_ids[0]).await?,
+        Some(ScheduledJobState::Pending)
+    );
+    assert_eq!(
+        model.check_status(job_ids[2]).await?,
+        Some(ScheduledJobState::Canceled)
+    );
+    application.commit_test(tx).await?;
+
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_scheduled_jobs_idempotency_key(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    application.load_udf_tests_modules().await?;
+
+    let mut tx = application.begin(Identity::system()).await?;
+    let path = insert_object_path();
+    let (_, component) =
+        BootstrapComponentsModel::new(&mut tx).must_component_path_to_ids(&path.component)?;
+    let mut model = SchedulerModel::new(&mut tx, component.into());
+    let args = || parse_udf_args(&path.udf_path, vec![JsonValue::Object(Default::default())]);
+    let key = |on_conflict| IdempotencyKey::new("debounced".to_string(), Some(on_conflict));
+
+    let first = model
+        .schedule_idempotent(
+            path.clone(),
+            args()?,
+            rt.unix_timestamp() + Duration::from_secs(60),
+            ExecutionContext::new_for_test(),
+            key("replace")?,
+        )
+        .await?;
+    // Skipping keeps the pending job.
+    let skipped = model
+        .schedule_idempotent(
+            path.clone(),
+            args()?,
+            rt.unix_timestamp() + Duration::from_secs(120),
+            ExecutionContext::new_for_test(),
+            key("skip")?,
+        )
+        .await?;
+    assert_eq!(skipped, first);
+    // Replacing cancels it in favor of the new job.
+    let replaced = model
+        .schedule_idempotent(
+            path.clone(),
+            args()?,
+            rt.unix_timestamp() + Duration::from_secs(180),
+            ExecutionContext::new_for_test(),
+            key("replace")?,
+        )
+        .await?;
+    assert_ne!(replaced, first);
+    assert_eq!(
+        model.check_status(first).await?,
+        Some(ScheduledJobState::Canceled)
+    );
+    assert_eq!(
+        model.check_status(replaced).await?,
+        Some(ScheduledJobState::Pending)
+    );
+    assert!(IdempotencyKey::new(String::new(), None).is_err());
+    assert!(IdempotencyKey::new("debounced".to_string(), Some("merge")).is_err());
+    application.commit_test(tx).await?;
+
+    Ok(())
+}
+
 #[convex_macro::test_runtime]
 async fn test_scheduled_jobs_race_condition(rt: TestRuntime) -> anyhow::Result<()> {
     let application = Application::new_for_tests(&rt).await?;
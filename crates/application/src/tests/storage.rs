@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use common::{
     components::ComponentId,
     types::BackendState,
@@ -23,7 +25,15 @@ pub(crate) async fn test_backend_not_running_cannot_store_file(
         Ok(bytes::Bytes::from(vec![55; 1024 + 1]))
     }));
     let ok_result = app
-        .store_file(ComponentId::Root, None, None, None, file_body)
+        .store_file(
+            ComponentId::Root,
+            None,
+            None,
+            None,
+            None,
+            BTreeMap::new(),
+            file_body,
+        )
         .await;
     assert!(ok_result.is_ok());
 
@@ -36,7 +46,15 @@ pub(crate) async fn test_backend_not_running_cannot_store_file(
         Ok(bytes::Bytes::from(vec![55; 1024 + 1]))
     }));
     let result = app
-        .store_file(ComponentId::Root, None, None, None, file_body)
+        .store_file(
+            ComponentId::Root,
+            None,
+            None,
+            None,
+            None,
+            BTreeMap::new(),
+            file_body,
+        )
         .await;
     assert!(result.is_err());
     let error = result.unwrap_err();
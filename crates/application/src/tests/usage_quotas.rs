@@ -0,0 +1,67 @@
+use common::{
+    components::{
+        CanonicalizedComponentFunctionPath,
+        ComponentPath,
+        PublicFunctionPath,
+    },
+    pause::PauseClient,
+    types::FunctionCaller,
+    RequestId,
+};
+use errors::ErrorMetadataAnyhowExt;
+use keybroker::Identity;
+use model::{
+    usage_meters::types::UsageMetric,
+    usage_quotas::types::{
+        QuotaEnforcement,
+        UsageQuota,
+        UsageQuotaConfig,
+    },
+};
+use runtime::testing::TestRuntime;
+use serde_json::json;
+
+use crate::{
+    test_helpers::ApplicationTestExt,
+    Application,
+};
+
+async fn insert_object(application: &Application<TestRuntime>) -> anyhow::Result<()> {
+    application
+        .mutation_udf(
+            RequestId::new(),
+            PublicFunctionPath::Component(CanonicalizedComponentFunctionPath {
+                component: ComponentPath::test_user(),
+                udf_path: "basic:insertObject".parse()?,
+            }),
+            vec![json!({"an": "object"})],
+            Identity::system(),
+            None,
+            FunctionCaller::Action {
+                parent_scheduled_job: None,
+            },
+            PauseClient::new(),
+        )
+        .await??;
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_usage_quota_rejects_calls(rt: TestRuntime) -> anyhow::Result<()> {
+    let application = Application::new_for_tests(&rt).await?;
+    application.load_udf_tests_modules().await?;
+    let config = UsageQuotaConfig::new(vec![UsageQuota {
+        function: Some("basic:insertObject".to_string()),
+        metric: UsageMetric::Calls,
+        limit: 1,
+        enforcement: QuotaEnforcement::Reject,
+    }])?;
+    application
+        .set_usage_quota_config(Identity::system(), Some(config))
+        .await?;
+
+    insert_object(&application).await?;
+    let err = insert_object(&application).await.unwrap_err();
+    assert_eq!(err.short_msg(), "UsageQuotaExceeded");
+    Ok(())
+}
@@ -0,0 +1,453 @@
+//! Meters each function's usage, and the deployment's, in daily counters in
+//! `_usage_meters`, and enforces the quotas in `_usage_quotas` on them.
+//!
+//! Usage is counted in memory as executions are logged and added to the
+//! stored counters periodically. Quotas are checked before functions run
+//! against the stored counters as of the last reload plus the usage that
+//! hasn't been stored yet, so they're enforced approximately.
+use std::{
+    collections::BTreeMap,
+    num::NonZeroU32,
+    sync::Arc,
+    time::Duration,
+};
+
+use common::{
+    backoff::Backoff,
+    components::PublicFunctionPath,
+    errors::report_error,
+    knobs::{
+        USAGE_METER_FLUSH_INTERVAL,
+        USAGE_METER_RETENTION,
+        USAGE_QUOTA_REFRESH_INTERVAL,
+    },
+    pause::PauseClient,
+    runtime::{
+        new_rate_limiter,
+        RateLimiter,
+        Runtime,
+        UnixTimestamp,
+    },
+    types::{
+        UdfIdentifier,
+        UdfType,
+    },
+};
+use database::Database;
+use errors::ErrorMetadata;
+use futures::Future;
+use governor::Quota;
+use keybroker::Identity;
+use model::{
+    metrics_rollups::types::RollupGranularity,
+    usage_meters::{
+        types::{
+            UsageCounters,
+            UsageMeter,
+        },
+        UsageMetersModel,
+    },
+    usage_quotas::{
+        types::{
+            QuotaEnforcement,
+            UsageQuotaConfig,
+        },
+        UsageQuotasModel,
+    },
+};
+use parking_lot::Mutex;
+use usage_tracking::FunctionUsageTracker;
+
+use crate::{
+    function_log::{
+        FunctionExecution,
+        FunctionExecutionLog,
+    },
+    Application,
+};
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// How many meters are written per transaction.
+const FLUSH_BATCH_SIZE: usize = 64;
+/// How many expired meters are deleted per transaction.
+const DELETE_BATCH_SIZE: usize = 128;
+
+/// The function, or `None` for the whole deployment.
+type MeterKey = (UnixTimestamp, Option<String>);
+
+fn day_start(ts: UnixTimestamp) -> UnixTimestamp {
+    RollupGranularity::Day.bucket_start(ts)
+}
+
+/// Usage that hasn't been added to the stored meters yet, by day.
+#[derive(Default)]
+pub(crate) struct PendingUsage {
+    counters: BTreeMap<MeterKey, UsageCounters>,
+}
+
+impl PendingUsage {
+    /// Counts `execution` against its function and the deployment. System
+    /// functions, like the dashboard's, aren't metered.
+    pub(crate) fn append(&mut self, execution: &FunctionExecution) {
+        if let UdfIdentifier::Function(path) = execution.identifier()
+            && path.udf_path.is_system()
+        {
+            return;
+        }
+        let usage = &execution.usage_stats;
+        let action_compute_ms = match execution.udf_type {
+            UdfType::Action | UdfType::HttpAction => (execution.execution_time * 1000.0) as u64,
+            UdfType::Query | UdfType::Mutation => 0,
+        };
+        let counters = UsageCounters {
+            calls: 1,
+            database_read_bytes: usage.database_read_bytes,
+            database_write_bytes: usage.database_write_bytes,
+            storage_read_bytes: usage.storage_read_bytes,
+            storage_write_bytes: usage.storage_write_bytes,
+            action_compute_ms,
+            vector_searches: usage.vector_searches,
+        };
+        let day = day_start(execution.unix_timestamp);
+        self.add((day, Some(execution.params.identifier_str())), &counters);
+        self.add((day, None), &counters);
+    }
+
+    fn add(&mut self, key: MeterKey, counters: &UsageCounters) {
+        self.counters.entry(key).or_default().merge(counters);
+    }
+
+    pub(crate) fn merge(&mut self, other: PendingUsage) {
+        for (key, counters) in other.counters {
+            self.add(key, &counters);
+        }
+    }
+
+    pub(crate) fn get(&self, day: UnixTimestamp, function: &Option<String>) -> UsageCounters {
+        self.counters
+            .get(&(day, function.clone()))
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
+/// Adds the pending usage to the stored meters, and deletes expired ones.
+pub struct UsageMeterWorker<RT: Runtime> {
+    runtime: RT,
+    database: Database<RT>,
+    function_log: FunctionExecutionLog<RT>,
+}
+
+impl<RT: Runtime> UsageMeterWorker<RT> {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        runtime: RT,
+        database: Database<RT>,
+        function_log: FunctionExecutionLog<RT>,
+    ) -> impl Future<Output = ()> + Send {
+        let worker = Self {
+            runtime,
+            database,
+            function_log,
+        };
+        async move {
+            let mut backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
+            while let Err(mut e) = worker.run(&mut backoff).await {
+                report_error(&mut e);
+                let delay = backoff.fail(&mut worker.runtime.rng());
+                tracing::error!("UsageMeterWorker failed, sleeping {delay:?}");
+                worker.runtime.wait(delay).await;
+            }
+        }
+    }
+
+    async fn run(&self, backoff: &mut Backoff) -> anyhow::Result<()> {
+        tracing::info!("Starting UsageMeterWorker");
+        loop {
+            self.runtime.wait(*USAGE_METER_FLUSH_INTERVAL).await;
+            self.flush().await?;
+            self.delete_expired().await?;
+            backoff.reset();
+        }
+    }
+
+    async fn flush(&self) -> anyhow::Result<()> {
+        let pending = self.function_log.take_pending_usage();
+        let entries: Vec<_> = pending.counters.into_iter().collect();
+        for (i, batch) in entries.chunks(FLUSH_BATCH_SIZE).enumerate() {
+            let result = self
+                .database
+                .execute_with_occ_retries(
+                    Identity::system(),
+                    FunctionUsageTracker::new(),
+                    PauseClient::new(),
+                    "usage_meter_flush",
+                    |tx| {
+                        async move {
+                            for ((day, function), counters) in batch {
+                                UsageMetersModel::new(tx)
+                                    .add(UsageMeter {
+                                        day: *day,
+                                        function: function.clone(),
+                                        counters: counters.clone(),
+                                    })
+                                    .await?;
+                            }
+                            Ok(())
+                        }
+                        .into()
+                    },
+                )
+                .await;
+            if let Err(e) = result {
+                // Keep the usage that wasn't written for the next flush.
+                let unwritten = PendingUsage {
+                    counters: entries[i * FLUSH_BATCH_SIZE..].iter().cloned().collect(),
+                };
+                self.function_log.restore_pending_usage(unwritten);
+                return Err(e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn delete_expired(&self) -> anyhow::Result<()> {
+        let now = self.runtime.unix_timestamp();
+        if now.as_secs_f64() < USAGE_METER_RETENTION.as_secs_f64() {
+            return Ok(());
+        }
+        let cutoff = now - *USAGE_METER_RETENTION;
+        loop {
+            let (_, deleted, _) = self
+                .database
+                .execute_with_occ_retries(
+                    Identity::system(),
+                    FunctionUsageTracker::new(),
+                    PauseClient::new(),
+                    "usage_meter_retention",
+                    |tx| {
+                        async move {
+                            UsageMetersModel::new(tx)
+                                .delete_before(cutoff, DELETE_BATCH_SIZE)
+                                .await
+                        }
+                        .into()
+                    },
+                )
+                .await?;
+            if deleted < DELETE_BATCH_SIZE {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// The deployment's quotas and today's usage, as of when they were loaded.
+struct LoadedQuotas<RT: Runtime> {
+    day: UnixTimestamp,
+    config: UsageQuotaConfig,
+    /// The stored usage for `day`, by function.
+    stored: BTreeMap<Option<String>, UsageCounters>,
+    /// A limiter for each throttled quota, in the same order as the quotas.
+    throttles: Vec<Option<Arc<RateLimiter<RT>>>>,
+}
+
+/// Checks function calls against the deployment's quotas, reloading them
+/// every [`USAGE_QUOTA_REFRESH_INTERVAL`].
+pub struct UsageQuotas<RT: Runtime> {
+    runtime: RT,
+    database: Database<RT>,
+    function_log: FunctionExecutionLog<RT>,
+    loaded: Mutex<Option<(tokio::time::Instant, Option<Arc<LoadedQuotas<RT>>>)>>,
+}
+
+impl<RT: Runtime> UsageQuotas<RT> {
+    pub fn new(
+        runtime: RT,
+        database: Database<RT>,
+        function_log: FunctionExecutionLog<RT>,
+    ) -> Self {
+        Self {
+            runtime,
+            database,
+            function_log,
+            loaded: Mutex::new(None),
+        }
+    }
+
+    async fn load(&self) -> anyhow::Result<Option<Arc<LoadedQuotas<RT>>>> {
+        let now = self.runtime.monotonic_now();
+        let day = day_start(self.runtime.unix_timestamp());
+        if let Some((loaded_at, loaded)) = &*self.loaded.lock()
+            && now.duration_since(*loaded_at) < *USAGE_QUOTA_REFRESH_INTERVAL
+            && loaded.as_ref().map_or(true, |loaded| loaded.day == day)
+        {
+            return Ok(loaded.clone());
+        }
+        let mut tx = self.database.begin(Identity::system()).await?;
+        let config = UsageQuotasModel::new(&mut tx)
+            .get()
+            .await?
+            .map(|config| config.into_value())
+            .filter(|config| !config.quotas.is_empty());
+        let loaded = match config {
+            Some(config) => {
+                let stored = UsageMetersModel::new(&mut tx)
+                    .list(day, day + RollupGranularity::Day.duration())
+                    .await?
+                    .into_iter()
+                    .map(|meter| {
+                        let meter = meter.into_value();
+                        (meter.function, meter.counters)
+                    })
+                    .collect();
+                let previous = self.loaded.lock().take().and_then(|(_, loaded)| loaded);
+                let throttles = match previous {
+                    // Keep the throttles' state if the quotas haven't changed.
+                    Some(previous) if previous.config == config => previous.throttles.clone(),
+                    _ => config
+                        .quotas
+                        .iter()
+                        .map(|quota| match quota.enforcement {
+                            QuotaEnforcement::Reject => None,
+                            QuotaEnforcement::Throttle { calls_per_minute } => {
+                                let calls_per_minute =
+                                    NonZeroU32::new(calls_per_minute).unwrap_or(NonZeroU32::MIN);
+                                Some(Arc::new(new_rate_limiter(
+                                    self.runtime.clone(),
+                                    Quota::per_minute(calls_per_minute),
+                                )))
+                            },
+                        })
+                        .collect(),
+                };
+                Some(Arc::new(LoadedQuotas {
+                    day,
+                    config,
+                    stored,
+                    throttles,
+                }))
+            },
+            None => None,
+        };
+        *self.loaded.lock() = Some((now, loaded.clone()));
+        Ok(loaded)
+    }
+
+    /// Fails with a `RateLimited` error if `function` or the deployment is
+    /// over one of its quotas. `function` is `None` for HTTP actions.
+    pub async fn check(&self, function: Option<String>) -> anyhow::Result<()> {
+        let loaded = match self.load().await {
+            Ok(Some(loaded)) => loaded,
+            Ok(None) => return Ok(()),
+            Err(mut e) => {
+                // Don't take the deployment down with its quotas.
+                report_error(&mut e);
+                return Ok(());
+            },
+        };
+        for (quota, throttle) in loaded.config.quotas.iter().zip(&loaded.throttles) {
+            if quota.function.is_some() && quota.function != function {
+                continue;
+            }
+            let mut used = loaded
+                .stored
+                .get(&quota.function)
+                .cloned()
+                .unwrap_or_default();
+            used.merge(&self.function_log.pending_usage(loaded.day, &quota.function));
+            if used.get(quota.metric) < quota.limit {
+                continue;
+            }
+            let subject = match &quota.function {
+                Some(function) => format!("The function {function}"),
+                None => "This deployment".to_string(),
+            };
+            match quota.enforcement {
+                QuotaEnforcement::Reject => anyhow::bail!(ErrorMetadata::rate_limited(
+                    "UsageQuotaExceeded",
+                    format!(
+                        "{subject} is over its daily quota of {} {}. Calls are rejected until \
+                         midnight UTC.",
+                        quota.limit, quota.metric
+                    ),
+                )),
+                QuotaEnforcement::Throttle { calls_per_minute } => {
+                    if let Some(throttle) = throttle
+                        && throttle.check().is_ok()
+                    {
+                        continue;
+                    }
+                    anyhow::bail!(ErrorMetadata::rate_limited(
+                        "UsageQuotaThrottled",
+                        format!(
+                            "{subject} is over its daily quota of {} {}, so it's limited to \
+                             {calls_per_minute} calls per minute until midnight UTC.",
+                            quota.limit, quota.metric
+                        ),
+                    ))
+                },
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<RT: Runtime> Application<RT> {
+    /// Fails if the function at `path`, or the deployment, is over one of its
+    /// usage quotas. `path` is `None` for HTTP actions.
+    pub(crate) async fn check_usage_quotas(
+        &self,
+        path: Option<&PublicFunctionPath>,
+    ) -> anyhow::Result<()> {
+        let function = match path {
+            Some(path) if path.is_system() => return Ok(()),
+            Some(path) => Some(path.udf_path().clone().strip().to_string()),
+            None => None,
+        };
+        self.usage_quotas.check(function).await
+    }
+
+    /// The metered usage for days starting in `[start, end)`, oldest first.
+    pub async fn list_usage_meters(
+        &self,
+        identity: Identity,
+        start: UnixTimestamp,
+        end: UnixTimestamp,
+    ) -> anyhow::Result<Vec<UsageMeter>> {
+        let mut tx = self.begin(identity).await?;
+        Ok(UsageMetersModel::new(&mut tx)
+            .list(day_start(start), end)
+            .await?
+            .into_iter()
+            .map(|meter| meter.into_value())
+            .collect())
+    }
+
+    /// The deployment's usage quotas, if it has any.
+    pub async fn get_usage_quota_config(
+        &self,
+        identity: Identity,
+    ) -> anyhow::Result<Option<UsageQuotaConfig>> {
+        let mut tx = self.begin(identity).await?;
+        Ok(UsageQuotasModel::new(&mut tx)
+            .get()
+            .await?
+            .map(|config| config.into_value()))
+    }
+
+    /// Replaces the deployment's usage quotas, or removes them if `config` is
+    /// `None`.
+    pub async fn set_usage_quota_config(
+        &self,
+        identity: Identity,
+        config: Option<UsageQuotaConfig>,
+    ) -> anyhow::Result<()> {
+        let mut tx = self.begin(identity).await?;
+        UsageQuotasModel::new(&mut tx).set(config).await?;
+        self.commit(tx, "set_usage_quota_config").await?;
+        Ok(())
+    }
+}
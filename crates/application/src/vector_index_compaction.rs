@@ -0,0 +1,170 @@
+//! Tuning when the compaction worker merges a vector index's segments, and
+//! compacting an index on demand. Each flush of new writes adds a segment to a
+//! vector index, and searches get slower as segments pile up until the
+//! compaction worker merges them. Write-heavy indexes can lower the worker's
+//! thresholds, or request a compaction right after a large batch of writes.
+use common::{
+    bootstrap_model::index::{
+        vector_index::VectorIndexState,
+        IndexConfig,
+    },
+    components::ComponentId,
+    document::ParsedDocument,
+    runtime::Runtime,
+    types::{
+        IndexName,
+        TabletIndexMetadata,
+    },
+};
+use database::{
+    unauthorized_error,
+    CompactionConfigOverrides,
+    IndexModel,
+    IndexWorkerMetadataModel,
+    ManualCompaction,
+    Transaction,
+};
+use errors::ErrorMetadata;
+use keybroker::Identity;
+use value::TableNamespace;
+
+use crate::Application;
+
+/// A vector index's segments and the state of its compaction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VectorIndexCompactionStatus {
+    pub segments: u64,
+    pub vectors: u64,
+    pub deleted_vectors: u64,
+    pub overrides: CompactionConfigOverrides,
+    /// The most recently requested compaction, if any.
+    pub manual_compaction: Option<ManualCompaction>,
+}
+
+fn invalid_compaction_config(msg: impl Into<String>) -> ErrorMetadata {
+    ErrorMetadata::bad_request("InvalidCompactionConfig", msg.into())
+}
+
+fn validate_overrides(overrides: &CompactionConfigOverrides) -> anyhow::Result<()> {
+    // Compacting a single segment just rewrites it, so the worker would
+    // never stop.
+    if let Some(min_compaction_segments) = overrides.min_compaction_segments {
+        anyhow::ensure!(
+            min_compaction_segments >= 2,
+            invalid_compaction_config(format!(
+                "minCompactionSegments must be at least 2, got {min_compaction_segments}"
+            ))
+        );
+    }
+    if let Some(max_deleted_percentage) = overrides.max_deleted_percentage {
+        anyhow::ensure!(
+            max_deleted_percentage > 0.0 && max_deleted_percentage <= 1.0,
+            invalid_compaction_config(format!(
+                "maxDeletedPercentage must be greater than 0 and at most 1, got \
+                 {max_deleted_percentage}"
+            ))
+        );
+    }
+    anyhow::ensure!(
+        overrides.max_segment_size_bytes != Some(0),
+        invalid_compaction_config("maxSegmentSizeBytes must be positive")
+    );
+    Ok(())
+}
+
+/// Finds a vector index by name, whether or not it's been enabled yet.
+fn vector_index<RT: Runtime>(
+    tx: &mut Transaction<RT>,
+    component: ComponentId,
+    index_name: &IndexName,
+) -> anyhow::Result<(ParsedDocument<TabletIndexMetadata>, VectorIndexState)> {
+    let namespace = TableNamespace::from(component);
+    let mut model = IndexModel::new(tx);
+    let index = match model.enabled_index_metadata(namespace, index_name)? {
+        Some(index) => index,
+        None => model
+            .pending_index_metadata(namespace, index_name)?
+            .ok_or_else(|| {
+                ErrorMetadata::not_found("IndexNotFound", format!("Index {index_name} not found"))
+            })?,
+    };
+    let IndexConfig::Vector { on_disk_state, .. } = &index.config else {
+        anyhow::bail!(ErrorMetadata::bad_request(
+            "NotAVectorIndex",
+            format!("Index {index_name} isn't a vector index"),
+        ));
+    };
+    let on_disk_state = on_disk_state.clone();
+    Ok((index, on_disk_state))
+}
+
+impl<RT: Runtime> Application<RT> {
+    /// Overrides the compaction worker's thresholds for a vector index.
+    /// Thresholds left unset in `overrides` go back to the defaults.
+    pub async fn set_vector_index_compaction_config(
+        &self,
+        identity: Identity,
+        component: ComponentId,
+        index_name: IndexName,
+        overrides: CompactionConfigOverrides,
+    ) -> anyhow::Result<()> {
+        validate_overrides(&overrides)?;
+        let mut tx = self.begin(identity).await?;
+        let (index, _) = vector_index(&mut tx, component, &index_name)?;
+        IndexWorkerMetadataModel::new(&mut tx)
+            .set_vector_compaction_overrides(index.id().internal_id(), overrides)
+            .await?;
+        self.commit(tx, "set_vector_index_compaction_config")
+            .await?;
+        Ok(())
+    }
+
+    /// Asks the compaction worker to merge a vector index's segments as far
+    /// as it can, regardless of its thresholds. Progress is reported by
+    /// [`Self::vector_index_compaction_status`].
+    pub async fn compact_vector_index(
+        &self,
+        identity: Identity,
+        component: ComponentId,
+        index_name: IndexName,
+    ) -> anyhow::Result<ManualCompaction> {
+        let mut tx = self.begin(identity).await?;
+        let (index, _) = vector_index(&mut tx, component, &index_name)?;
+        let manual_compaction = IndexWorkerMetadataModel::new(&mut tx)
+            .request_vector_compaction(index.id().internal_id())
+            .await?;
+        self.commit(tx, "compact_vector_index").await?;
+        Ok(manual_compaction)
+    }
+
+    pub async fn vector_index_compaction_status(
+        &self,
+        identity: Identity,
+        component: ComponentId,
+        index_name: IndexName,
+    ) -> anyhow::Result<VectorIndexCompactionStatus> {
+        anyhow::ensure!(
+            identity.is_admin() || identity.is_system(),
+            unauthorized_error("vector_index_compaction_status")
+        );
+        let mut tx = self.begin(identity).await?;
+        let (index, on_disk_state) = vector_index(&mut tx, component, &index_name)?;
+        let segments = on_disk_state.segments()?;
+        let worker_metadata = IndexWorkerMetadataModel::new(&mut tx)
+            .get_metadata(index.id().internal_id())
+            .await?
+            .map(|metadata| metadata.into_value().index_metadata);
+        Ok(VectorIndexCompactionStatus {
+            segments: segments.len() as u64,
+            vectors: segments.iter().map(|s| s.num_vectors as u64).sum(),
+            deleted_vectors: segments.iter().map(|s| s.num_deleted as u64).sum(),
+            overrides: worker_metadata
+                .as_ref()
+                .map(|metadata| metadata.compaction_overrides().clone())
+                .unwrap_or_default(),
+            manual_compaction: worker_metadata
+                .as_ref()
+                .and_then(|metadata| metadata.manual_compaction().cloned()),
+        })
+    }
+}
@@ -0,0 +1,103 @@
+//! Workflows: multi-step orchestrations defined as data and run through the
+//! scheduler. See [`model::workflows`] for how steps advance.
+use common::{
+    document::ParsedDocument,
+    runtime::Runtime,
+};
+use keybroker::Identity;
+use model::workflows::{
+    types::{
+        Workflow,
+        WorkflowDefinition,
+    },
+    WorkflowsModel,
+};
+use serde_json::Value as JsonValue;
+use value::DeveloperDocumentId;
+
+use crate::Application;
+
+impl<RT: Runtime> Application<RT> {
+    pub async fn list_workflow_definitions(
+        &self,
+        identity: Identity,
+    ) -> anyhow::Result<Vec<WorkflowDefinition>> {
+        let mut tx = self.begin(identity).await?;
+        Ok(WorkflowsModel::new(&mut tx)
+            .list_definitions()
+            .await?
+            .into_iter()
+            .map(ParsedDocument::into_value)
+            .collect())
+    }
+
+    pub async fn set_workflow_definition(
+        &self,
+        identity: Identity,
+        definition: WorkflowDefinition,
+    ) -> anyhow::Result<()> {
+        let mut tx = self.begin(identity).await?;
+        WorkflowsModel::new(&mut tx)
+            .set_definition(definition)
+            .await?;
+        self.commit(tx, "set_workflow_definition").await?;
+        Ok(())
+    }
+
+    pub async fn delete_workflow_definition(
+        &self,
+        identity: Identity,
+        name: String,
+    ) -> anyhow::Result<()> {
+        let mut tx = self.begin(identity).await?;
+        WorkflowsModel::new(&mut tx)
+            .delete_definition(&name)
+            .await?;
+        self.commit(tx, "delete_workflow_definition").await?;
+        Ok(())
+    }
+
+    /// Starts the workflow `name` with `input`, scheduling its first step.
+    pub async fn start_workflow(
+        &self,
+        identity: Identity,
+        name: String,
+        input: JsonValue,
+    ) -> anyhow::Result<DeveloperDocumentId> {
+        let mut tx = self.begin(identity).await?;
+        let id = WorkflowsModel::new(&mut tx).start(&name, input).await?;
+        self.commit(tx, "start_workflow").await?;
+        Ok(id)
+    }
+
+    pub async fn get_workflow(
+        &self,
+        identity: Identity,
+        id: DeveloperDocumentId,
+    ) -> anyhow::Result<Option<ParsedDocument<Workflow>>> {
+        let mut tx = self.begin(identity).await?;
+        WorkflowsModel::new(&mut tx).get(id).await
+    }
+
+    /// Up to `limit` of the most recently started workflows, newest first.
+    pub async fn list_workflows(
+        &self,
+        identity: Identity,
+        limit: usize,
+    ) -> anyhow::Result<Vec<ParsedDocument<Workflow>>> {
+        let mut tx = self.begin(identity).await?;
+        WorkflowsModel::new(&mut tx).list(limit).await
+    }
+
+    /// Stops a running workflow and cancels the step it's on.
+    pub async fn cancel_workflow(
+        &self,
+        identity: Identity,
+        id: DeveloperDocumentId,
+    ) -> anyhow::Result<()> {
+        let mut tx = self.begin(identity).await?;
+        WorkflowsModel::new(&mut tx).cancel(id).await?;
+        self.commit(tx, "cancel_workflow").await?;
+        Ok(())
+    }
+}
@@ -1,4 +1,7 @@
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::SystemTime,
+};
 
 use anyhow::Context;
 use errors::ErrorMetadata;
@@ -35,7 +38,7 @@ impl ApplicationAuth {
         admin_key_or_access_token: String,
         instance_name: String,
     ) -> anyhow::Result<Identity> {
-        if self
+        let identity = if self
             .key_broker
             .is_encrypted_admin_key(&admin_key_or_access_token)
         {
@@ -47,6 +50,15 @@ impl ApplicationAuth {
                     "BadAdminKey",
                     "The provided admin key was invalid for this instance",
                 ))
+        } else if self
+            .key_broker
+            .is_admin_session_token(&admin_key_or_access_token)
+        {
+            // A short-lived token exchanged for a deploy key. Its errors, like
+            // expiry, are returned as is so clients know to get a new one.
+            log_deploy_key_use(DeployKeyType::SessionToken);
+            self.key_broker
+                .check_admin_session_token(&admin_key_or_access_token, SystemTime::now())
         } else {
             // assume this is an Access Token
             // Access Tokens are base64 encoded strings
@@ -54,6 +66,39 @@ impl ApplicationAuth {
             self.access_token_auth
                 .is_authorized(&instance_name, &admin_key_or_access_token)
                 .await
+        }?;
+        // Checked here so routes that take a key in the request body reject it
+        // too. System keys are still accepted, since they're only used by the
+        // deployment's own tooling.
+        if identity.is_admin() && self.key_broker.admin_client_certificate_required() {
+            anyhow::bail!(ErrorMetadata::unauthenticated(
+                "ClientCertificateRequired",
+                "Admin keys aren't accepted by this deployment. Connect with a trusted TLS client \
+                 certificate instead.",
+            ));
         }
+        Ok(identity)
+    }
+
+    /// Whether an ID token is actually a session token from first-party auth.
+    pub fn is_user_session_token(&self, token: &str) -> bool {
+        self.key_broker.is_user_session_token(token)
+    }
+
+    /// Returns the session ID and expiry of a first-party auth session token.
+    /// The caller checks the session still exists, since logging out deletes
+    /// it without invalidating the token itself.
+    pub fn check_user_session_token(&self, token: &str) -> anyhow::Result<(String, SystemTime)> {
+        self.key_broker
+            .check_user_session_token(token, SystemTime::now())
+    }
+
+    pub fn issue_user_session_token(
+        &self,
+        session_id: String,
+        expires_at: SystemTime,
+    ) -> anyhow::Result<String> {
+        self.key_broker
+            .issue_user_session_token(session_id, expires_at)
     }
 }
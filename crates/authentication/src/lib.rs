@@ -1,4 +1,5 @@
 #![feature(lazy_cell)]
+#![feature(let_chains)]
 use std::{
     str::FromStr,
     sync::LazyLock,
@@ -28,7 +29,6 @@ use openidconnect::{
     core::{
         CoreIdToken,
         CoreIdTokenVerifier,
-        CoreProviderMetadata,
     },
     http::{
         header::ACCEPT,
@@ -38,7 +38,6 @@ use openidconnect::{
     },
     ClaimsVerificationError,
     ClientId,
-    DiscoveryError,
 };
 use serde::{
     Deserialize,
@@ -47,9 +46,14 @@ use serde::{
 use sync_types::AuthenticationToken;
 use url::Url;
 
+use crate::oidc_providers::OidcProviderCache;
+
 pub mod access_token_auth;
 pub mod application_auth;
 pub mod metrics;
+pub mod oidc_providers;
+pub mod passwords;
+pub mod webauthn;
 
 /// Issuer for API access tokens
 pub static CONVEX_AUTH_URL: LazyLock<Url> =
@@ -105,6 +109,7 @@ pub async fn validate_id_token<F, E>(
     // serve an HTTP response from an identity provider.
     http_client: impl Fn(HttpRequest) -> F + 'static,
     auth_infos: Vec<AuthInfo>,
+    providers: &OidcProviderCache,
     system_time: SystemTime,
 ) -> anyhow::Result<UserIdentity>
 where
@@ -134,60 +139,53 @@ where
             claims.issuer(),
         )
     };
-    // Find the provider matching this token
-    let auth_info = auth_infos
+    // Find the provider matching this token. Some authentication providers
+    // (Auth0, lookin' at you) tell developers that their identity domain
+    // doesn't have a trailing slash, but the OIDC tokens do have one in the
+    // `issuer` field. This is consistent with what the OIDC Discovery response
+    // will contain, but the value entered in the instance config may or may
+    // not have the slash.
+    let issuer_auth_infos: Vec<_> = auth_infos
         .into_iter()
-        .find(|info| {
-            // Some authentication providers (Auth0, lookin' at you) tell developers that
-            // their identity domain doesn't have a trailing slash, but the OIDC tokens do
-            // have one in the `issuer` field. This is consistent with what the OIDC
-            // Discovery response will contain, but the value entered in the instance config
-            // may or may not have the slash.
-            audiences.contains(&info.application_id)
-                && info.domain.trim_end_matches('/') == issuer.trim_end_matches('/')
-        })
-        .context(ErrorMetadata::unauthenticated(
+        .filter(|info| info.domain.trim_end_matches('/') == issuer.trim_end_matches('/'))
+        .collect();
+    anyhow::ensure!(
+        !issuer_auth_infos.is_empty(),
+        ErrorMetadata::unauthenticated(
             "NoAuthProvider",
-            "No auth provider found matching the given token",
-        ))?;
-    // Use the OpenID Connect Discovery protocol to get the public keys for this
-    // provider.
-    // TODO(CX-606): Add an caching layer that respects the HTTP cache headers
-    // in the response.
-    let metadata = CoreProviderMetadata::discover_async(issuer.clone(), http_client)
-        .await
-        .map_err(|e| {
-            let short = "AuthProviderDiscoveryFailed";
-            let long = format!("Auth provider discovery of {} failed", issuer.as_str());
-            match e {
-                DiscoveryError::Response(code, body, _) => {
-                    let long = format!("{long}: {} {}", code, String::from_utf8_lossy(&body));
-                    let Ok(code) = http::StatusCode::from_u16(code.as_u16()) else {
-                        return ErrorMetadata::bad_request(short, long);
-                    };
-                    if let Some(em) =
-                        ErrorMetadata::from_http_status_code(code, short, long.clone())
-                    {
-                        em
-                    } else {
-                        ErrorMetadata::bad_request(short, long)
-                    }
-                },
-                e => {
-                    tracing::error!(
-                        "Error discovering auth provider: {}, {}",
-                        issuer.as_str(),
-                        e
-                    );
-                    ErrorMetadata::bad_request(short, long)
-                },
-            }
+            format!(
+                "No auth provider found matching the given token's issuer {}",
+                issuer.as_str()
+            ),
+        )
+    );
+    // Several providers can share an issuer with different application IDs,
+    // and the token must be for one of them.
+    let auth_info = issuer_auth_infos
+        .into_iter()
+        .find(|info| audiences.contains(&info.application_id))
+        .with_context(|| {
+            ErrorMetadata::unauthenticated(
+                "InvalidAuthAudience",
+                format!(
+                    "The given token's audience {audiences:?} doesn't match the applicationID of \
+                     any auth provider for {}",
+                    issuer.as_str()
+                ),
+            )
         })?;
+    let metadata = providers
+        .metadata(
+            issuer,
+            http_client,
+            token_key_id(&token_str.0).as_deref(),
+            system_time,
+        )
+        .await?;
     // Create a verifier for the provider using this metadata. Set the verifier
-    // to enforce that the issuer and audience match.
-    // Note for posterity: this verifier will reject tokens containing multiple
-    // audiences. It's very uncommon for an identity provider to create a token with
-    // multiple valid audiences, so we don't handle that case yet.
+    // to enforce that the issuer and audience match. Tokens with several
+    // audiences are accepted as long as one of them is the provider's
+    // application ID, which we checked above.
     let verifier = CoreIdTokenVerifier::new_public_client(
         ClientId::new(auth_info.application_id),
         metadata.issuer().clone(),
@@ -195,6 +193,7 @@ where
     )
     .require_issuer_match(true)
     .require_audience_match(true)
+    .set_other_audience_verifier_fn(|_| true)
     .set_time_fn(|| {
         chrono::Utc
             .timestamp_opt(
@@ -212,6 +211,17 @@ where
     ))
 }
 
+/// The `kid` from a JWT's header, which names the key it was signed with.
+fn token_key_id(token: &str) -> Option<String> {
+    #[derive(Deserialize)]
+    struct Header {
+        kid: Option<String>,
+    }
+    let header = token.split('.').next()?;
+    let header = base64::decode_config(header, base64::URL_SAFE_NO_PAD).ok()?;
+    serde_json::from_slice::<Header>(&header).ok()?.kid
+}
+
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Auth0AccessToken(pub String);
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -395,7 +405,11 @@ mod tests {
     use std::{
         convert::Infallible,
         pin::Pin,
-        time::SystemTime,
+        sync::Arc,
+        time::{
+            Duration as StdDuration,
+            SystemTime,
+        },
     };
 
     use chrono::{
@@ -403,6 +417,7 @@ mod tests {
         Utc,
     };
     use common::auth::AuthInfo;
+    use errors::ErrorMetadataAnyhowExt;
     use futures::{
         Future,
         FutureExt,
@@ -442,12 +457,14 @@ mod tests {
         TokenUrl,
         UserInfoUrl,
     };
+    use parking_lot::Mutex;
     use serde::{
         Deserialize,
         Serialize,
     };
 
     use crate::{
+        oidc_providers::OidcProviderCache,
         validate_access_token,
         validate_id_token,
         Auth0AccessToken,
@@ -485,11 +502,8 @@ mod tests {
         }
     }
 
-    #[tokio::test]
-    async fn test_id_token_auth() -> anyhow::Result<()> {
-        let issuer_url = IssuerUrl::new("https://dev-1sfr-rpl.us.auth0.com".to_string()).unwrap();
-        let audience = Audience::new("client-id-123".to_string());
-        let provider_metadata = serde_json::to_string(
+    fn fake_provider_metadata(issuer_url: &IssuerUrl) -> String {
+        serde_json::to_string(
             &CoreProviderMetadata::new(
                 issuer_url.clone(),
                 None,
@@ -529,7 +543,14 @@ mod tests {
                 CoreClaimName::new("picture".to_string()),
             ])),
         )
-        .unwrap();
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_id_token_auth() -> anyhow::Result<()> {
+        let issuer_url = IssuerUrl::new("https://dev-1sfr-rpl.us.auth0.com".to_string()).unwrap();
+        let audience = Audience::new("client-id-123".to_string());
+        let provider_metadata = fake_provider_metadata(&issuer_url);
         let jwks = serde_json::to_string(&CoreJsonWebKeySet::new(vec![
             TEST_SIGNING_KEY.as_verification_key()
         ]))
@@ -558,6 +579,7 @@ mod tests {
                 application_id: (*audience).clone(),
                 domain: issuer_url,
             }],
+            &OidcProviderCache::default(),
             SystemTime::now(),
         )
         .await
@@ -565,6 +587,88 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_id_token_key_rollover() -> anyhow::Result<()> {
+        let issuer_url = IssuerUrl::new("https://rollover.example.com".to_string())?;
+        let audience = Audience::new("client-id-456".to_string());
+        let provider_metadata = fake_provider_metadata(&issuer_url);
+        // The provider doesn't have the key the token is signed with yet.
+        let jwks = Arc::new(Mutex::new(serde_json::to_string(&CoreJsonWebKeySet::new(
+            vec![],
+        ))?));
+        let http_client = {
+            let jwks = jwks.clone();
+            move |request: HttpRequest| {
+                fake_http_client(provider_metadata.clone(), jwks.lock().clone())(request)
+            }
+        };
+        let id_token = CoreIdToken::new(
+            CoreIdTokenClaims::new(
+                issuer_url.clone(),
+                vec![audience.clone()],
+                Utc::now() + Duration::seconds(120),
+                Utc::now(),
+                StandardClaims::new(SubjectIdentifier::new("1234-abcd".to_string())),
+                EmptyAdditionalClaims {},
+            ),
+            &*TEST_SIGNING_KEY,
+            CoreJwsSigningAlgorithm::RsaSsaPkcs1V15Sha256,
+            None,
+            None,
+        )?
+        .to_string();
+        let auth_infos = vec![AuthInfo {
+            application_id: (*audience).clone(),
+            domain: issuer_url.clone(),
+        }];
+        let providers = OidcProviderCache::default();
+        let now = SystemTime::now();
+        let validate = |system_time| {
+            validate_id_token(
+                Auth0IdToken(id_token.clone()),
+                http_client.clone(),
+                auth_infos.clone(),
+                &providers,
+                system_time,
+            )
+        };
+        validate(now).await.unwrap_err();
+
+        // The provider rolls its keys over. Tokens signed with the new key are
+        // accepted once enough time has passed to fetch the keys again.
+        *jwks.lock() = serde_json::to_string(&CoreJsonWebKeySet::new(vec![
+            TEST_SIGNING_KEY.as_verification_key()
+        ]))?;
+        validate(now + StdDuration::from_secs(1)).await.unwrap_err();
+        validate(now + StdDuration::from_secs(60)).await?;
+        let status = providers
+            .status(
+                auth_infos,
+                http_client.clone(),
+                now + StdDuration::from_secs(60),
+            )
+            .await;
+        assert_eq!(status.len(), 1);
+        assert_eq!(status[0].key_ids, vec!["key1".to_string()]);
+        assert_eq!(status[0].last_error, None);
+
+        // Tokens for another application ID on the same issuer are rejected.
+        let err = validate_id_token(
+            Auth0IdToken(id_token),
+            http_client,
+            vec![AuthInfo {
+                application_id: "some-other-client".to_string(),
+                domain: issuer_url,
+            }],
+            &providers,
+            now + StdDuration::from_secs(60),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.short_msg(), "InvalidAuthAudience");
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_access_token_auth() -> anyhow::Result<()> {
         let issuer_url = IssuerUrl::from_url(CONVEX_AUTH_URL.clone());
@@ -9,12 +9,14 @@ register_convex_counter!(pub DEPLOY_KEY_USE_TOTAL, "Count of deploy key uses", &
 pub enum DeployKeyType {
     Legacy,
     AccessToken,
+    SessionToken,
 }
 
 pub fn log_deploy_key_use(key_type: DeployKeyType) {
     let key_type_label = match key_type {
         DeployKeyType::Legacy => "legacy",
         DeployKeyType::AccessToken => "access_token",
+        DeployKeyType::SessionToken => "session_token",
     };
     log_counter_with_labels(
         &DEPLOY_KEY_USE_TOTAL,
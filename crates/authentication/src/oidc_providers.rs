@@ -0,0 +1,240 @@
+//! Caches each auth provider's OpenID Connect discovery document and signing
+//! keys, so validating a token doesn't fetch them every time.
+//!
+//! Cached keys are refreshed once they're `AUTH_PROVIDER_REFRESH_INTERVAL`
+//! old, and as soon as a token is signed with a key the cache doesn't have,
+//! which is how we pick up providers rolling their keys over. If a refresh
+//! fails, the previous keys are kept and the error is reported in
+//! [`OidcProviderStatus`].
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::{
+        Duration,
+        SystemTime,
+    },
+};
+
+use common::{
+    auth::AuthInfo,
+    knobs::{
+        AUTH_PROVIDER_MIN_REFRESH_INTERVAL,
+        AUTH_PROVIDER_REFRESH_INTERVAL,
+    },
+};
+use errors::ErrorMetadata;
+use futures::Future;
+use oauth2::{
+    HttpRequest,
+    HttpResponse,
+};
+use openidconnect::{
+    core::CoreProviderMetadata,
+    DiscoveryError,
+    IssuerUrl,
+    JsonWebKey,
+};
+use parking_lot::RwLock;
+
+#[derive(Default)]
+pub struct OidcProviderCache {
+    /// Keyed by issuer URL, without a trailing slash.
+    providers: RwLock<BTreeMap<String, CachedProvider>>,
+}
+
+#[derive(Clone, Default)]
+struct CachedProvider {
+    metadata: Option<CoreProviderMetadata>,
+    fetched_at: Option<SystemTime>,
+    last_attempt: Option<SystemTime>,
+    last_error: Option<String>,
+}
+
+/// What's cached for a configured provider, to help diagnose auth configs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OidcProviderStatus {
+    pub issuer: String,
+    pub application_id: String,
+    /// When the provider's keys were last fetched successfully.
+    pub fetched_at: Option<SystemTime>,
+    pub last_refresh_attempt: Option<SystemTime>,
+    /// Why the last refresh failed, if it did.
+    pub last_error: Option<String>,
+    /// IDs of the cached signing keys.
+    pub key_ids: Vec<String>,
+}
+
+impl OidcProviderCache {
+    /// The metadata for the provider at `issuer`, including its signing keys.
+    /// Fetches it if it isn't cached, is stale, or doesn't have the key with
+    /// `key_id`.
+    pub async fn metadata<F, E>(
+        &self,
+        issuer: &IssuerUrl,
+        http_client: impl Fn(HttpRequest) -> F + 'static,
+        key_id: Option<&str>,
+        now: SystemTime,
+    ) -> anyhow::Result<CoreProviderMetadata>
+    where
+        F: Future<Output = Result<HttpResponse, E>>,
+        E: std::error::Error + 'static + Send + Sync,
+    {
+        let key = issuer_key(issuer);
+        let cached = self.providers.read().get(&key).cloned();
+        if let Some(cached) = cached
+            && let Some(metadata) = &cached.metadata
+            && !needs_refresh(&cached, metadata, key_id, now)
+        {
+            return Ok(metadata.clone());
+        }
+        let result = discover(issuer, http_client).await;
+        let mut providers = self.providers.write();
+        let provider = providers.entry(key).or_default();
+        provider.last_attempt = Some(now);
+        match result {
+            Ok(metadata) => {
+                provider.metadata = Some(metadata.clone());
+                provider.fetched_at = Some(now);
+                provider.last_error = None;
+                Ok(metadata)
+            },
+            Err(e) => {
+                provider.last_error = Some(e.to_string());
+                match &provider.metadata {
+                    // Keep using the keys we have while the provider is down.
+                    Some(metadata) => {
+                        tracing::warn!("Failed to refresh auth provider {}: {e}", issuer.as_str());
+                        Ok(metadata.clone())
+                    },
+                    None => Err(e),
+                }
+            },
+        }
+    }
+
+    /// Fetches the metadata for each of `auth_infos` that isn't cached yet,
+    /// and reports what's cached for them.
+    pub async fn status<F, E>(
+        &self,
+        auth_infos: Vec<AuthInfo>,
+        http_client: impl Fn(HttpRequest) -> F + 'static,
+        now: SystemTime,
+    ) -> Vec<OidcProviderStatus>
+    where
+        F: Future<Output = Result<HttpResponse, E>>,
+        E: std::error::Error + 'static + Send + Sync,
+    {
+        let http_client = Arc::new(http_client);
+        let mut statuses = vec![];
+        for auth_info in auth_infos {
+            let http_client = http_client.clone();
+            // Errors are recorded in the cache, and reported below.
+            let _ = self
+                .metadata(
+                    &auth_info.domain,
+                    move |request| http_client(request),
+                    None,
+                    now,
+                )
+                .await;
+            let cached = self
+                .providers
+                .read()
+                .get(&issuer_key(&auth_info.domain))
+                .cloned()
+                .unwrap_or_default();
+            statuses.push(OidcProviderStatus {
+                issuer: auth_info.domain.to_string(),
+                application_id: auth_info.application_id,
+                fetched_at: cached.fetched_at,
+                last_refresh_attempt: cached.last_attempt,
+                last_error: cached.last_error,
+                key_ids: cached
+                    .metadata
+                    .map(|metadata| {
+                        metadata
+                            .jwks()
+                            .keys()
+                            .iter()
+                            .filter_map(|key| Some(key.key_id()?.to_string()))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            });
+        }
+        statuses
+    }
+}
+
+fn issuer_key(issuer: &IssuerUrl) -> String {
+    issuer.as_str().trim_end_matches('/').to_string()
+}
+
+fn needs_refresh(
+    cached: &CachedProvider,
+    metadata: &CoreProviderMetadata,
+    key_id: Option<&str>,
+    now: SystemTime,
+) -> bool {
+    let since = |time: Option<SystemTime>| {
+        time.and_then(|time| now.duration_since(time).ok())
+            .unwrap_or(Duration::MAX)
+    };
+    // Don't let a provider that's down, or tokens signed with keys it doesn't
+    // have, cause a fetch on every request.
+    if since(cached.last_attempt) < *AUTH_PROVIDER_MIN_REFRESH_INTERVAL {
+        return false;
+    }
+    if since(cached.fetched_at) >= *AUTH_PROVIDER_REFRESH_INTERVAL {
+        return true;
+    }
+    key_id.is_some_and(|key_id| {
+        !metadata
+            .jwks()
+            .keys()
+            .iter()
+            .any(|key| key.key_id().is_some_and(|id| id.as_str() == key_id))
+    })
+}
+
+/// Uses the OpenID Connect Discovery protocol to get the provider's metadata
+/// and public keys.
+async fn discover<F, E>(
+    issuer: &IssuerUrl,
+    http_client: impl Fn(HttpRequest) -> F + 'static,
+) -> anyhow::Result<CoreProviderMetadata>
+where
+    F: Future<Output = Result<HttpResponse, E>>,
+    E: std::error::Error + 'static + Send + Sync,
+{
+    let metadata = CoreProviderMetadata::discover_async(issuer.clone(), http_client)
+        .await
+        .map_err(|e| {
+            let short = "AuthProviderDiscoveryFailed";
+            let long = format!("Auth provider discovery of {} failed", issuer.as_str());
+            match e {
+                DiscoveryError::Response(code, body, _) => {
+                    let long = format!("{long}: {} {}", code, String::from_utf8_lossy(&body));
+                    let Ok(code) = http::StatusCode::from_u16(code.as_u16()) else {
+                        return ErrorMetadata::bad_request(short, long);
+                    };
+                    if let Some(em) =
+                        ErrorMetadata::from_http_status_code(code, short, long.clone())
+                    {
+                        em
+                    } else {
+                        ErrorMetadata::bad_request(short, long)
+                    }
+                },
+                e => {
+                    tracing::error!(
+                        "Error discovering auth provider: {}, {}",
+                        issuer.as_str(),
+                        e
+                    );
+                    ErrorMetadata::bad_request(short, format!("{long}: {e}"))
+                },
+            }
+        })?;
+    Ok(metadata)
+}
@@ -0,0 +1,122 @@
+//! Password hashing for first-party auth. Hashes are Argon2id PHC strings, so
+//! the parameters are stored with each hash and can be raised later without
+//! invalidating existing passwords.
+use std::sync::LazyLock;
+
+use argon2::{
+    password_hash::{
+        PasswordHash,
+        PasswordHasher,
+        PasswordVerifier,
+        SaltString,
+    },
+    Argon2,
+};
+use errors::ErrorMetadata;
+use ring::rand::{
+    SecureRandom,
+    SystemRandom,
+};
+
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+/// Argon2 accepts much longer passwords, but hashing them is slow enough to be
+/// a denial of service vector.
+pub const MAX_PASSWORD_LENGTH: usize = 256;
+
+const SALT_LENGTH: usize = 16;
+
+/// Checked against when there's no hash to check, so a failed login takes as
+/// long whether or not the account exists.
+static DUMMY_PASSWORD_HASH: LazyLock<String> = LazyLock::new(|| {
+    hash_password("not anyone's password").expect("Failed to hash the dummy password")
+});
+
+pub fn check_password_length(password: &str) -> anyhow::Result<()> {
+    let length = password.chars().count();
+    if length < MIN_PASSWORD_LENGTH {
+        anyhow::bail!(ErrorMetadata::bad_request(
+            "PasswordTooShort",
+            format!("Passwords must be at least {MIN_PASSWORD_LENGTH} characters"),
+        ));
+    }
+    if length > MAX_PASSWORD_LENGTH {
+        anyhow::bail!(ErrorMetadata::bad_request(
+            "PasswordTooLong",
+            format!("Passwords can be at most {MAX_PASSWORD_LENGTH} characters"),
+        ));
+    }
+    Ok(())
+}
+
+/// Hashes `password` with a random salt. This is deliberately slow, so call it
+/// from a blocking thread.
+pub fn hash_password(password: &str) -> anyhow::Result<String> {
+    check_password_length(password)?;
+    let mut salt = [0; SALT_LENGTH];
+    SystemRandom::new()
+        .fill(&mut salt)
+        .map_err(|_| anyhow::anyhow!("Failed to generate a password salt"))?;
+    let salt = SaltString::encode_b64(&salt).map_err(|e| anyhow::anyhow!("{e}"))?;
+    let hash = Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|e| anyhow::anyhow!("Failed to hash password: {e}"))?;
+    Ok(hash.to_string())
+}
+
+/// Whether `password` matches a hash from [`hash_password`].
+pub fn verify_password(password: &str, hash: &str) -> anyhow::Result<bool> {
+    if password.chars().count() > MAX_PASSWORD_LENGTH {
+        return Ok(false);
+    }
+    let hash =
+        PasswordHash::new(hash).map_err(|e| anyhow::anyhow!("Invalid password hash: {e}"))?;
+    Ok(Argon2::default()
+        .verify_password(password.as_bytes(), &hash)
+        .is_ok())
+}
+
+/// Like [`verify_password`], but for accounts that may not exist or have a
+/// password. Without a hash this does the same work and returns false.
+pub fn verify_password_if_set(password: &str, hash: Option<&str>) -> anyhow::Result<bool> {
+    match hash {
+        Some(hash) => verify_password(password, hash),
+        None => {
+            verify_password(password, &DUMMY_PASSWORD_HASH)?;
+            Ok(false)
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use errors::ErrorMetadataAnyhowExt;
+
+    use super::{
+        hash_password,
+        verify_password,
+        verify_password_if_set,
+    };
+
+    #[test]
+    fn test_hash_password() -> anyhow::Result<()> {
+        let hash = hash_password("correct horse")?;
+        assert!(hash.starts_with("$argon2id$"));
+        assert!(verify_password("correct horse", &hash)?);
+        assert!(!verify_password("battery staple", &hash)?);
+        // Each hash has its own salt.
+        assert_ne!(hash_password("correct horse")?, hash);
+
+        let err = hash_password("short").unwrap_err();
+        assert_eq!(err.short_msg(), "PasswordTooShort");
+        Ok(())
+    }
+
+    #[test]
+    fn test_verify_password_if_set() -> anyhow::Result<()> {
+        let hash = hash_password("correct horse")?;
+        assert!(verify_password_if_set("correct horse", Some(&hash))?);
+        assert!(!verify_password_if_set("correct horse", None)?);
+        assert!(!verify_password_if_set("not anyone's password", None)?);
+        Ok(())
+    }
+}
@@ -0,0 +1,496 @@
+//! Relying party verification of WebAuthn (passkey) registrations and
+//! assertions, so apps can offer passkey login without a third-party identity
+//! provider.
+//!
+//! Attestation statements aren't verified, which matches the `"none"`
+//! attestation conveyance passkey providers use by default.
+use std::io::Cursor;
+
+use ciborium::value::Value as CborValue;
+use errors::ErrorMetadata;
+use ring::{
+    digest::{
+        digest,
+        SHA256,
+    },
+    signature::{
+        RsaPublicKeyComponents,
+        UnparsedPublicKey,
+        ECDSA_P256_SHA256_ASN1,
+        ED25519,
+        RSA_PKCS1_2048_8192_SHA256,
+    },
+};
+use serde::Deserialize;
+
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_ATTESTED_CREDENTIAL_DATA: u8 = 0x40;
+
+const COSE_ALG_ES256: i128 = -7;
+const COSE_ALG_EDDSA: i128 = -8;
+const COSE_ALG_RS256: i128 = -257;
+
+fn invalid_response(msg: impl Into<String>) -> anyhow::Error {
+    ErrorMetadata::bad_request("InvalidWebAuthnResponse", msg.into()).into()
+}
+
+/// The app that credentials are scoped to.
+#[derive(Clone, Debug)]
+pub struct RelyingParty {
+    /// Domain like `example.com` that credentials are registered for.
+    pub id: String,
+    /// Origins like `https://example.com` that responses may come from.
+    pub origins: Vec<String>,
+    /// Whether the authenticator must have verified the user, e.g. with a
+    /// PIN or biometric, rather than only checking they're present.
+    pub require_user_verification: bool,
+}
+
+/// A credential that passed registration, to store for later assertions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RegisteredCredential {
+    pub credential_id: Vec<u8>,
+    /// COSE-encoded public key.
+    pub public_key: Vec<u8>,
+    pub sign_count: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CollectedClientData {
+    #[serde(rename = "type")]
+    ty: String,
+    challenge: String,
+    origin: String,
+    #[serde(default)]
+    cross_origin: bool,
+}
+
+impl CollectedClientData {
+    fn parse(client_data_json: &[u8]) -> anyhow::Result<Self> {
+        serde_json::from_slice(client_data_json)
+            .map_err(|e| invalid_response(format!("Invalid clientDataJSON: {e}")))
+    }
+}
+
+/// The challenge a response was signed over, which identifies the ceremony
+/// it belongs to.
+pub fn client_data_challenge(client_data_json: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let client_data = CollectedClientData::parse(client_data_json)?;
+    base64::decode_config(&client_data.challenge, base64::URL_SAFE_NO_PAD)
+        .map_err(|_| invalid_response("Challenge in clientDataJSON isn't base64url"))
+}
+
+fn verify_client_data(
+    rp: &RelyingParty,
+    expected_type: &str,
+    expected_challenge: &[u8],
+    client_data_json: &[u8],
+) -> anyhow::Result<()> {
+    let client_data = CollectedClientData::parse(client_data_json)?;
+    if client_data.ty != expected_type {
+        anyhow::bail!(invalid_response(format!(
+            "Expected a {expected_type} response but got {}",
+            client_data.ty
+        )));
+    }
+    if client_data_challenge(client_data_json)? != expected_challenge {
+        anyhow::bail!(invalid_response("Response is for a different challenge"));
+    }
+    if client_data.cross_origin || !rp.origins.contains(&client_data.origin) {
+        anyhow::bail!(invalid_response(format!(
+            "Origin {} isn't allowed for relying party {}",
+            client_data.origin, rp.id
+        )));
+    }
+    Ok(())
+}
+
+struct AuthenticatorData {
+    rp_id_hash: Vec<u8>,
+    flags: u8,
+    sign_count: u32,
+    /// Credential ID and COSE public key, present in registrations.
+    attested_credential: Option<(Vec<u8>, Vec<u8>)>,
+}
+
+impl AuthenticatorData {
+    fn parse(data: &[u8]) -> anyhow::Result<Self> {
+        let truncated = || invalid_response("authenticatorData is truncated");
+        let header = data.get(..37).ok_or_else(truncated)?;
+        let flags = header[32];
+        let sign_count = u32::from_be_bytes(header[33..37].try_into()?);
+        let attested_credential = if flags & FLAG_ATTESTED_CREDENTIAL_DATA != 0 {
+            // Skip the 16 byte AAGUID.
+            let rest = data.get(53..).ok_or_else(truncated)?;
+            let id_len = u16::from_be_bytes(data.get(53..55).ok_or_else(truncated)?.try_into()?);
+            let credential_id = rest
+                .get(2..2 + id_len as usize)
+                .ok_or_else(truncated)?
+                .to_vec();
+            let key_bytes = &rest[2 + id_len as usize..];
+            // The public key is followed by extensions, so decode it to find
+            // where it ends.
+            let mut cursor = Cursor::new(key_bytes);
+            let _: CborValue = ciborium::de::from_reader(&mut cursor)
+                .map_err(|e| invalid_response(format!("Invalid credential public key: {e}")))?;
+            let public_key = key_bytes[..cursor.position() as usize].to_vec();
+            Some((credential_id, public_key))
+        } else {
+            None
+        };
+        Ok(Self {
+            rp_id_hash: header[..32].to_vec(),
+            flags,
+            sign_count,
+            attested_credential,
+        })
+    }
+
+    fn verify(&self, rp: &RelyingParty) -> anyhow::Result<()> {
+        if self.rp_id_hash != digest(&SHA256, rp.id.as_bytes()).as_ref() {
+            anyhow::bail!(invalid_response(format!(
+                "Response is for a different relying party than {}",
+                rp.id
+            )));
+        }
+        if self.flags & FLAG_USER_PRESENT == 0 {
+            anyhow::bail!(invalid_response(
+                "Authenticator didn't check the user is present"
+            ));
+        }
+        if rp.require_user_verification && self.flags & FLAG_USER_VERIFIED == 0 {
+            anyhow::bail!(invalid_response("Authenticator didn't verify the user"));
+        }
+        Ok(())
+    }
+}
+
+enum CosePublicKey {
+    Es256 { x: Vec<u8>, y: Vec<u8> },
+    Ed25519 { x: Vec<u8> },
+    Rs256 { n: Vec<u8>, e: Vec<u8> },
+}
+
+impl CosePublicKey {
+    fn parse(bytes: &[u8]) -> anyhow::Result<Self> {
+        let value: CborValue = ciborium::de::from_reader(bytes)
+            .map_err(|e| invalid_response(format!("Invalid credential public key: {e}")))?;
+        let CborValue::Map(entries) = value else {
+            anyhow::bail!(invalid_response("Credential public key isn't a COSE key"));
+        };
+        let field = |label: i128| {
+            entries.iter().find_map(|(key, value)| match key {
+                CborValue::Integer(key) if i128::from(*key) == label => Some(value),
+                _ => None,
+            })
+        };
+        let integer = |label: i128| match field(label) {
+            Some(CborValue::Integer(value)) => Some(i128::from(*value)),
+            _ => None,
+        };
+        let bytes = |label: i128| match field(label) {
+            Some(CborValue::Bytes(value)) => Ok(value.clone()),
+            _ => Err(invalid_response(format!(
+                "Credential public key is missing parameter {label}"
+            ))),
+        };
+        match integer(3) {
+            Some(COSE_ALG_ES256) => Ok(Self::Es256 {
+                x: bytes(-2)?,
+                y: bytes(-3)?,
+            }),
+            Some(COSE_ALG_EDDSA) => Ok(Self::Ed25519 { x: bytes(-2)? }),
+            Some(COSE_ALG_RS256) => Ok(Self::Rs256 {
+                n: bytes(-1)?,
+                e: bytes(-2)?,
+            }),
+            alg => Err(invalid_response(format!(
+                "Unsupported credential algorithm {alg:?}. Use ES256, EdDSA or RS256."
+            ))),
+        }
+    }
+
+    fn verify(&self, message: &[u8], signature: &[u8]) -> anyhow::Result<()> {
+        let result = match self {
+            Self::Es256 { x, y } => {
+                let point = [&[0x04], &x[..], &y[..]].concat();
+                UnparsedPublicKey::new(&ECDSA_P256_SHA256_ASN1, point).verify(message, signature)
+            },
+            Self::Ed25519 { x } => UnparsedPublicKey::new(&ED25519, x).verify(message, signature),
+            Self::Rs256 { n, e } => RsaPublicKeyComponents { n, e }.verify(
+                &RSA_PKCS1_2048_8192_SHA256,
+                message,
+                signature,
+            ),
+        };
+        result.map_err(|_| invalid_response("Invalid signature"))
+    }
+}
+
+/// Verifies the response to `navigator.credentials.create()` for
+/// `expected_challenge`.
+pub fn verify_registration(
+    rp: &RelyingParty,
+    expected_challenge: &[u8],
+    client_data_json: &[u8],
+    attestation_object: &[u8],
+) -> anyhow::Result<RegisteredCredential> {
+    verify_client_data(rp, "webauthn.create", expected_challenge, client_data_json)?;
+    let attestation: CborValue = ciborium::de::from_reader(attestation_object)
+        .map_err(|e| invalid_response(format!("Invalid attestationObject: {e}")))?;
+    let auth_data = attestation
+        .as_map()
+        .and_then(|entries| {
+            entries.iter().find_map(|(key, value)| match (key, value) {
+                (CborValue::Text(key), CborValue::Bytes(value)) if key == "authData" => Some(value),
+                _ => None,
+            })
+        })
+        .ok_or_else(|| invalid_response("attestationObject is missing authData"))?;
+    let auth_data = AuthenticatorData::parse(auth_data)?;
+    auth_data.verify(rp)?;
+    let (credential_id, public_key) = auth_data
+        .attested_credential
+        .ok_or_else(|| invalid_response("Registration is missing the credential"))?;
+    CosePublicKey::parse(&public_key)?;
+    Ok(RegisteredCredential {
+        credential_id,
+        public_key,
+        sign_count: auth_data.sign_count,
+    })
+}
+
+/// Verifies the response to `navigator.credentials.get()` for
+/// `expected_challenge`, signed by a credential with `public_key` that was
+/// last seen with `sign_count`. Returns the new signature count to store.
+pub fn verify_assertion(
+    rp: &RelyingParty,
+    expected_challenge: &[u8],
+    public_key: &[u8],
+    sign_count: u32,
+    client_data_json: &[u8],
+    authenticator_data: &[u8],
+    signature: &[u8],
+) -> anyhow::Result<u32> {
+    verify_client_data(rp, "webauthn.get", expected_challenge, client_data_json)?;
+    let auth_data = AuthenticatorData::parse(authenticator_data)?;
+    auth_data.verify(rp)?;
+    let message = [
+        authenticator_data,
+        digest(&SHA256, client_data_json).as_ref(),
+    ]
+    .concat();
+    CosePublicKey::parse(public_key)?.verify(&message, signature)?;
+    // Authenticators that count signatures increase the count on every
+    // assertion, so a count that didn't increase means the credential was
+    // cloned. Passkeys synced between devices always report zero.
+    if (auth_data.sign_count != 0 || sign_count != 0) && auth_data.sign_count <= sign_count {
+        anyhow::bail!(invalid_response(
+            "Signature count didn't increase, so the credential may have been cloned"
+        ));
+    }
+    Ok(auth_data.sign_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use ciborium::value::Value as CborValue;
+    use errors::ErrorMetadataAnyhowExt;
+    use ring::{
+        digest::{
+            digest,
+            SHA256,
+        },
+        rand::SystemRandom,
+        signature::{
+            EcdsaKeyPair,
+            KeyPair,
+            ECDSA_P256_SHA256_ASN1_SIGNING,
+        },
+    };
+    use serde_json::json;
+
+    use super::{
+        verify_assertion,
+        verify_registration,
+        RelyingParty,
+    };
+
+    const CHALLENGE: &[u8] = b"0123456789abcdef0123456789abcdef";
+
+    fn rp() -> RelyingParty {
+        RelyingParty {
+            id: "example.com".to_string(),
+            origins: vec!["https://example.com".to_string()],
+            require_user_verification: false,
+        }
+    }
+
+    fn client_data(ty: &str, challenge: &[u8], origin: &str) -> Vec<u8> {
+        serde_json::to_vec(&json!({
+            "type": ty,
+            "challenge": base64::encode_config(challenge, base64::URL_SAFE_NO_PAD),
+            "origin": origin,
+        }))
+        .unwrap()
+    }
+
+    fn auth_data(rp_id: &str, flags: u8, sign_count: u32, credential: Option<&[u8]>) -> Vec<u8> {
+        let mut data = digest(&SHA256, rp_id.as_bytes()).as_ref().to_vec();
+        data.push(flags);
+        data.extend(sign_count.to_be_bytes());
+        if let Some(public_key) = credential {
+            data.extend([0; 16]);
+            data.extend(4u16.to_be_bytes());
+            data.extend(b"cred");
+            data.extend(public_key);
+        }
+        data
+    }
+
+    struct Authenticator {
+        key_pair: EcdsaKeyPair,
+        rng: SystemRandom,
+    }
+
+    impl Authenticator {
+        fn new() -> Self {
+            let rng = SystemRandom::new();
+            let pkcs8 =
+                EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, &rng).unwrap();
+            let key_pair =
+                EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_ASN1_SIGNING, pkcs8.as_ref(), &rng)
+                    .unwrap();
+            Self { key_pair, rng }
+        }
+
+        fn cose_key(&self) -> Vec<u8> {
+            let point = self.key_pair.public_key().as_ref();
+            let key = CborValue::Map(vec![
+                (1.into(), 2.into()),
+                (3.into(), (-7).into()),
+                ((-1).into(), 1.into()),
+                ((-2).into(), CborValue::Bytes(point[1..33].to_vec())),
+                ((-3).into(), CborValue::Bytes(point[33..].to_vec())),
+            ]);
+            let mut bytes = vec![];
+            ciborium::ser::into_writer(&key, &mut bytes).unwrap();
+            bytes
+        }
+
+        fn attestation_object(&self, rp_id: &str) -> Vec<u8> {
+            let attestation = CborValue::Map(vec![
+                ("fmt".into(), "none".into()),
+                ("attStmt".into(), CborValue::Map(vec![])),
+                (
+                    "authData".into(),
+                    CborValue::Bytes(auth_data(rp_id, 0x41, 0, Some(&self.cose_key()))),
+                ),
+            ]);
+            let mut bytes = vec![];
+            ciborium::ser::into_writer(&attestation, &mut bytes).unwrap();
+            bytes
+        }
+
+        fn sign(&self, authenticator_data: &[u8], client_data_json: &[u8]) -> Vec<u8> {
+            let message = [
+                authenticator_data,
+                digest(&SHA256, client_data_json).as_ref(),
+            ]
+            .concat();
+            self.key_pair
+                .sign(&self.rng, &message)
+                .unwrap()
+                .as_ref()
+                .to_vec()
+        }
+    }
+
+    #[test]
+    fn test_registration_and_assertion() -> anyhow::Result<()> {
+        let authenticator = Authenticator::new();
+        let credential = verify_registration(
+            &rp(),
+            CHALLENGE,
+            &client_data("webauthn.create", CHALLENGE, "https://example.com"),
+            &authenticator.attestation_object("example.com"),
+        )?;
+        assert_eq!(credential.credential_id, b"cred");
+        assert_eq!(credential.public_key, authenticator.cose_key());
+
+        let client_data_json = client_data("webauthn.get", CHALLENGE, "https://example.com");
+        let authenticator_data = auth_data("example.com", 0x01, 5, None);
+        let signature = authenticator.sign(&authenticator_data, &client_data_json);
+        let sign_count = verify_assertion(
+            &rp(),
+            CHALLENGE,
+            &credential.public_key,
+            credential.sign_count,
+            &client_data_json,
+            &authenticator_data,
+            &signature,
+        )?;
+        assert_eq!(sign_count, 5);
+
+        // Replaying the assertion doesn't increase the signature count.
+        let err = verify_assertion(
+            &rp(),
+            CHALLENGE,
+            &credential.public_key,
+            sign_count,
+            &client_data_json,
+            &authenticator_data,
+            &signature,
+        )
+        .unwrap_err();
+        assert_eq!(err.short_msg(), "InvalidWebAuthnResponse");
+
+        let mut tampered = signature.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(verify_assertion(
+            &rp(),
+            CHALLENGE,
+            &credential.public_key,
+            0,
+            &client_data_json,
+            &authenticator_data,
+            &tampered,
+        )
+        .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_registration_rejects_mismatches() {
+        let authenticator = Authenticator::new();
+        let attestation_object = authenticator.attestation_object("example.com");
+        for (client_data_json, attestation_object) in [
+            (
+                client_data("webauthn.create", CHALLENGE, "https://evil.com"),
+                attestation_object.clone(),
+            ),
+            (
+                client_data(
+                    "webauthn.create",
+                    b"another challenge",
+                    "https://example.com",
+                ),
+                attestation_object.clone(),
+            ),
+            (
+                client_data("webauthn.get", CHALLENGE, "https://example.com"),
+                attestation_object.clone(),
+            ),
+            (
+                client_data("webauthn.create", CHALLENGE, "https://example.com"),
+                authenticator.attestation_object("evil.com"),
+            ),
+        ] {
+            let err = verify_registration(&rp(), CHALLENGE, &client_data_json, &attestation_object)
+                .unwrap_err();
+            assert_eq!(err.short_msg(), "InvalidWebAuthnResponse");
+        }
+    }
+}
@@ -250,6 +250,7 @@ mod tests {
         vector_index::{
             DeveloperVectorIndexConfig,
             FragmentedVectorSegment,
+            VectorDistanceMetric,
             VectorIndexBackfillState,
             VectorIndexState,
         },
@@ -287,6 +288,7 @@ mod tests {
                     dimensions: 1536.try_into()?,
                     vector_field: "embedding.field".parse()?,
                     filter_fields: btreeset! { "filter1".parse()?, "filter2".parse()? },
+                    distance: VectorDistanceMetric::Cosine,
                 },
                 on_disk_state: VectorIndexState::Backfilling(VectorIndexBackfillState {
                     cursor: None,
@@ -24,6 +24,7 @@ use super::{
     vector_index::{
         DeveloperVectorIndexConfig,
         VectorDimensions,
+        VectorDistanceMetric,
         VectorIndexBackfillState,
         VectorIndexState,
     },
@@ -32,6 +33,7 @@ use super::{
 use crate::{
     bootstrap_model::index::text_index::{
         DeveloperTextIndexConfig,
+        TextIndexAnalyzer,
         TextIndexBackfillState,
         TextIndexState,
     },
@@ -82,12 +84,14 @@ impl<T: IndexTableIdentifier> IndexMetadata<T> {
         name: GenericIndexName<T>,
         search_field: FieldPath,
         filter_fields: BTreeSet<FieldPath>,
+        analyzer: TextIndexAnalyzer,
     ) -> Self {
         Self::new_text_index(
             name,
             DeveloperTextIndexConfig {
                 search_field,
                 filter_fields,
+                analyzer,
             },
             TextIndexState::Backfilling(TextIndexBackfillState::new()),
         )
@@ -98,6 +102,7 @@ impl<T: IndexTableIdentifier> IndexMetadata<T> {
         vector_field: FieldPath,
         dimensions: VectorDimensions,
         filter_fields: BTreeSet<FieldPath>,
+        distance: VectorDistanceMetric,
     ) -> Self {
         Self {
             name,
@@ -106,6 +111,7 @@ impl<T: IndexTableIdentifier> IndexMetadata<T> {
                     dimensions,
                     vector_field,
                     filter_fields,
+                    distance,
                 },
                 on_disk_state: VectorIndexState::Backfilling(VectorIndexBackfillState {
                     segments: vec![],
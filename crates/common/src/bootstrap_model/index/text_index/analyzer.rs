@@ -0,0 +1,68 @@
+use std::str::FromStr;
+
+use errors::ErrorMetadata;
+
+/// How a text index splits its search field into terms. The same analyzer is
+/// used when indexing documents and when tokenizing queries, so changing it
+/// means rebuilding the index.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    strum::EnumString,
+    strum::Display,
+    strum::EnumIter,
+)]
+#[strum(serialize_all = "snake_case")]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub enum TextIndexAnalyzer {
+    /// Splits on whitespace and punctuation and lowercases each word.
+    #[default]
+    Simple,
+    /// Like `Simple`, but also splits runs of Chinese, Japanese and Korean
+    /// characters into overlapping pairs of characters, since those languages
+    /// don't separate words with spaces.
+    Cjk,
+    // Like `Simple`, but also drops the language's stop words and reduces each
+    // word to its stem, so "running" matches "runs".
+    Danish,
+    Dutch,
+    English,
+    Finnish,
+    French,
+    German,
+    Hungarian,
+    Italian,
+    Norwegian,
+    Portuguese,
+    Russian,
+    Spanish,
+    Swedish,
+}
+
+impl TextIndexAnalyzer {
+    /// Parses the analyzer a developer names in their schema.
+    pub fn parse(analyzer: &str) -> anyhow::Result<Self> {
+        Self::from_str(analyzer).map_err(|_| {
+            anyhow::anyhow!(ErrorMetadata::bad_request(
+                "InvalidTextIndexAnalyzer",
+                format!(
+                    "Unknown search index analyzer {analyzer:?}. Use one of \"simple\", \"cjk\", \
+                     \"danish\", \"dutch\", \"english\", \"finnish\", \"french\", \"german\", \
+                     \"hungarian\", \"italian\", \"norwegian\", \"portuguese\", \"russian\", \
+                     \"spanish\" or \"swedish\"."
+                ),
+            ))
+        })
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
@@ -6,6 +6,7 @@ use serde::{
 };
 use value::codegen_convex_serialization;
 
+use super::TextIndexAnalyzer;
 use crate::paths::FieldPath;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -16,6 +17,9 @@ pub struct DeveloperTextIndexConfig {
 
     /// Other fields to index for equality filtering.
     pub filter_fields: BTreeSet<FieldPath>,
+
+    /// How the search field is split into terms.
+    pub analyzer: TextIndexAnalyzer,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -24,6 +28,10 @@ pub struct DeveloperTextIndexConfig {
 pub struct SerializedDeveloperTextIndexConfig {
     search_field: String,
     filter_fields: Vec<String>,
+    // Omitted for the default analyzer, which indexes created before analyzers
+    // were configurable use.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    analyzer: Option<String>,
 }
 
 impl TryFrom<DeveloperTextIndexConfig> for SerializedDeveloperTextIndexConfig {
@@ -33,6 +41,7 @@ impl TryFrom<DeveloperTextIndexConfig> for SerializedDeveloperTextIndexConfig {
         Ok(Self {
             search_field: config.search_field.into(),
             filter_fields: config.filter_fields.into_iter().map(String::from).collect(),
+            analyzer: (!config.analyzer.is_default()).then(|| config.analyzer.to_string()),
         })
     }
 }
@@ -48,6 +57,11 @@ impl TryFrom<SerializedDeveloperTextIndexConfig> for DeveloperTextIndexConfig {
                 .into_iter()
                 .map(|p| p.parse())
                 .collect::<anyhow::Result<BTreeSet<FieldPath>>>()?,
+            analyzer: config
+                .analyzer
+                .map(|analyzer| TextIndexAnalyzer::parse(&analyzer))
+                .transpose()?
+                .unwrap_or_default(),
         })
     }
 }
@@ -70,6 +84,11 @@ impl TryFrom<pb::searchlight::SearchIndexConfig> for DeveloperTextIndexConfig {
                 .collect::<Result<Vec<_>, _>>()?
                 .into_iter()
                 .collect(),
+            analyzer: proto
+                .analyzer
+                .map(|analyzer| TextIndexAnalyzer::parse(&analyzer))
+                .transpose()?
+                .unwrap_or_default(),
         })
     }
 }
@@ -83,6 +102,7 @@ impl From<DeveloperTextIndexConfig> for pb::searchlight::SearchIndexConfig {
                 .into_iter()
                 .map(|f| f.into())
                 .collect::<Vec<_>>(),
+            analyzer: (!config.analyzer.is_default()).then(|| config.analyzer.to_string()),
         }
     }
 }
@@ -1,9 +1,11 @@
+mod analyzer;
 mod backfill_state;
 mod index_config;
 mod index_snapshot;
 mod index_state;
 
 pub use self::{
+    analyzer::TextIndexAnalyzer,
     backfill_state::{
         TextBackfillCursor,
         TextIndexBackfillState,
@@ -0,0 +1,54 @@
+use std::str::FromStr;
+
+use errors::ErrorMetadata;
+
+/// How a vector index compares vectors. The metric is used when building the
+/// index's segments, so changing it means rebuilding the index.
+///
+/// Every metric scores results so that a higher score is a closer match.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    strum::EnumString,
+    strum::Display,
+    strum::EnumIter,
+)]
+#[strum(serialize_all = "camelCase")]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub enum VectorDistanceMetric {
+    /// The cosine of the angle between the vectors, from -1 to 1.
+    #[default]
+    Cosine,
+    /// The dot product of the vectors, for embeddings trained for
+    /// inner-product retrieval.
+    DotProduct,
+    /// The Euclidean distance between the vectors, negated so closer vectors
+    /// score higher.
+    Euclidean,
+}
+
+impl VectorDistanceMetric {
+    /// Parses the metric a developer names in their schema.
+    pub fn parse(metric: &str) -> anyhow::Result<Self> {
+        Self::from_str(metric).map_err(|_| {
+            anyhow::anyhow!(ErrorMetadata::bad_request(
+                "InvalidVectorDistanceMetric",
+                format!(
+                    "Unknown vector index distance {metric:?}. Use one of \"cosine\", \
+                     \"dotProduct\" or \"euclidean\"."
+                ),
+            ))
+        })
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == Self::default()
+    }
+}
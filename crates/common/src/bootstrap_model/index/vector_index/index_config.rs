@@ -9,7 +9,10 @@ use value::{
     FieldPath,
 };
 
-use super::VectorDimensions;
+use super::{
+    VectorDimensions,
+    VectorDistanceMetric,
+};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 #[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
@@ -22,6 +25,9 @@ pub struct DeveloperVectorIndexConfig {
 
     /// Other fields to index for equality filtering.
     pub filter_fields: BTreeSet<FieldPath>,
+
+    /// How vectors are compared.
+    pub distance: VectorDistanceMetric,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -33,6 +39,10 @@ pub struct SerializedDeveloperVectorIndexConfig {
     dimensions: i64,
     vector_field: String,
     filter_fields: Vec<String>,
+    // Omitted for the default metric, which indexes created before the metric
+    // was configurable use.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    distance: Option<String>,
 }
 
 impl TryFrom<DeveloperVectorIndexConfig> for SerializedDeveloperVectorIndexConfig {
@@ -43,6 +53,7 @@ impl TryFrom<DeveloperVectorIndexConfig> for SerializedDeveloperVectorIndexConfi
             dimensions: u32::from(config.dimensions) as i64,
             vector_field: config.vector_field.into(),
             filter_fields: config.filter_fields.into_iter().map(String::from).collect(),
+            distance: (!config.distance.is_default()).then(|| config.distance.to_string()),
         })
     }
 }
@@ -59,6 +70,11 @@ impl TryFrom<SerializedDeveloperVectorIndexConfig> for DeveloperVectorIndexConfi
                 .into_iter()
                 .map(|p| p.parse())
                 .collect::<anyhow::Result<BTreeSet<FieldPath>>>()?,
+            distance: config
+                .distance
+                .map(|distance| VectorDistanceMetric::parse(&distance))
+                .transpose()?
+                .unwrap_or_default(),
         })
     }
 }
@@ -85,6 +101,11 @@ impl TryFrom<pb::searchlight::VectorIndexConfig> for DeveloperVectorIndexConfig
                 .collect::<Result<Vec<_>, _>>()?
                 .into_iter()
                 .collect(),
+            distance: proto
+                .distance
+                .map(|distance| VectorDistanceMetric::parse(&distance))
+                .transpose()?
+                .unwrap_or_default(),
         })
     }
 }
@@ -99,6 +120,7 @@ impl From<DeveloperVectorIndexConfig> for pb::searchlight::VectorIndexConfig {
                 .into_iter()
                 .map(|f| f.into())
                 .collect::<Vec<_>>(),
+            distance: (!config.distance.is_default()).then(|| config.distance.to_string()),
         }
     }
 }
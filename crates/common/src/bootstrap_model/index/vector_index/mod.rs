@@ -1,5 +1,6 @@
 mod backfill_state;
 mod dimensions;
+mod distance;
 mod index_config;
 mod index_snapshot;
 mod index_state;
@@ -12,6 +13,7 @@ pub use self::{
         MAX_VECTOR_DIMENSIONS,
         MIN_VECTOR_DIMENSIONS,
     },
+    distance::VectorDistanceMetric,
     index_config::{
         DeveloperVectorIndexConfig,
         SerializedDeveloperVectorIndexConfig,
@@ -0,0 +1,412 @@
+//! Fault injection for staging deployments, so teams can check that their
+//! clients retry correctly when the backend fails.
+//!
+//! Faults are only injected if the operator starts the backend with
+//! `ENABLE_FAULT_INJECTION` set, and an admin then configures them. The
+//! configuration lives in memory, so it resets when the backend restarts.
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
+    sync::{
+        Arc,
+        LazyLock,
+    },
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use errors::ErrorMetadata;
+use futures::{
+    future,
+    stream::{
+        self,
+        BoxStream,
+    },
+    StreamExt,
+};
+use parking_lot::RwLock;
+use rand::{
+    Rng,
+    RngCore,
+};
+use serde_json::Value as JsonValue;
+use value::{
+    InternalDocumentId,
+    TabletId,
+};
+
+use crate::{
+    document::ResolvedDocument,
+    index::{
+        IndexEntry,
+        IndexKey,
+    },
+    interval::Interval,
+    knobs::ENABLE_FAULT_INJECTION,
+    persistence::{
+        ConflictStrategy,
+        DocumentStream,
+        IndexStream,
+        Persistence,
+        PersistenceGlobalKey,
+        PersistenceReader,
+        RetentionValidator,
+        TimestampRange,
+    },
+    query::Order,
+    runtime::Runtime,
+    types::{
+        DatabaseIndexUpdate,
+        IndexId,
+        PersistenceVersion,
+        Timestamp,
+    },
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct FaultInjectionConfig {
+    /// The fraction of commits from functions and clients, between 0 and 1,
+    /// that fail with a retryable error instead of being written.
+    pub commit_failure_rate: f64,
+    /// Added to every read from persistence.
+    pub persistence_read_latency: Duration,
+    /// The fraction of function executions, between 0 and 1, whose isolate is
+    /// killed before they run.
+    pub isolate_kill_rate: f64,
+}
+
+static CONFIG: LazyLock<RwLock<FaultInjectionConfig>> =
+    LazyLock::new(|| RwLock::new(FaultInjectionConfig::default()));
+
+/// The faults currently being injected.
+pub fn config() -> FaultInjectionConfig {
+    *CONFIG.read()
+}
+
+/// Replaces the faults being injected. Fails unless the operator enabled fault
+/// injection.
+pub fn configure(config: FaultInjectionConfig) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        *ENABLE_FAULT_INJECTION,
+        ErrorMetadata::bad_request(
+            "FaultInjectionDisabled",
+            "Fault injection is disabled. Restart the backend with ENABLE_FAULT_INJECTION=true to \
+             use it."
+        )
+    );
+    for (name, rate) in [
+        ("commitFailureRate", config.commit_failure_rate),
+        ("isolateKillRate", config.isolate_kill_rate),
+    ] {
+        anyhow::ensure!(
+            (0.0..=1.0).contains(&rate),
+            ErrorMetadata::bad_request(
+                "InvalidFaultInjectionConfig",
+                format!("{name} must be between 0 and 1, not {rate}"),
+            )
+        );
+    }
+    tracing::warn!("Injecting faults: {config:?}");
+    *CONFIG.write() = config;
+    Ok(())
+}
+
+/// Randomly fails a commit with an error clients should retry.
+pub fn maybe_fail_commit(rng: &mut dyn RngCore) -> anyhow::Result<()> {
+    let rate = CONFIG.read().commit_failure_rate;
+    if rate > 0.0 && rng.gen_bool(rate) {
+        anyhow::bail!(ErrorMetadata::overloaded(
+            "InjectedCommitFailure",
+            "This commit was dropped by fault injection. Please retry."
+        ));
+    }
+    Ok(())
+}
+
+/// Randomly decides whether to kill the isolate that's about to run a
+/// function.
+pub fn should_kill_isolate(rng: &mut dyn RngCore) -> bool {
+    let rate = CONFIG.read().isolate_kill_rate;
+    rate > 0.0 && rng.gen_bool(rate)
+}
+
+/// The error for a function whose isolate was killed.
+pub fn isolate_killed_error() -> anyhow::Error {
+    anyhow::anyhow!(ErrorMetadata::overloaded(
+        "InjectedIsolateFailure",
+        "The function's isolate was killed by fault injection. Please retry."
+    ))
+}
+
+/// Wraps persistence to delay reads by `persistence_read_latency`. Backends
+/// only use it if fault injection is enabled.
+pub struct FaultInjectionPersistence<RT: Runtime> {
+    rt: RT,
+    inner: Arc<dyn Persistence>,
+}
+
+impl<RT: Runtime> FaultInjectionPersistence<RT> {
+    pub fn new(rt: RT, inner: Arc<dyn Persistence>) -> Self {
+        Self { rt, inner }
+    }
+}
+
+#[async_trait]
+impl<RT: Runtime> Persistence for FaultInjectionPersistence<RT> {
+    fn is_fresh(&self) -> bool {
+        self.inner.is_fresh()
+    }
+
+    fn reader(&self) -> Arc<dyn PersistenceReader> {
+        Arc::new(FaultInjectionPersistenceReader {
+            rt: self.rt.clone(),
+            inner: self.inner.reader(),
+        })
+    }
+
+    async fn write(
+        &self,
+        documents: Vec<(Timestamp, InternalDocumentId, Option<ResolvedDocument>)>,
+        indexes: BTreeSet<(Timestamp, DatabaseIndexUpdate)>,
+        conflict_strategy: ConflictStrategy,
+    ) -> anyhow::Result<()> {
+        self.inner
+            .write(documents, indexes, conflict_strategy)
+            .await
+    }
+
+    async fn set_read_only(&self, read_only: bool) -> anyhow::Result<()> {
+        self.inner.set_read_only(read_only).await
+    }
+
+    async fn write_persistence_global(
+        &self,
+        key: PersistenceGlobalKey,
+        value: JsonValue,
+    ) -> anyhow::Result<()> {
+        self.inner.write_persistence_global(key, value).await
+    }
+
+    async fn load_index_chunk(
+        &self,
+        cursor: Option<IndexEntry>,
+        chunk_size: usize,
+    ) -> anyhow::Result<Vec<IndexEntry>> {
+        delay_read(&self.rt).await;
+        self.inner.load_index_chunk(cursor, chunk_size).await
+    }
+
+    async fn delete_index_entries(&self, entries: Vec<IndexEntry>) -> anyhow::Result<usize> {
+        self.inner.delete_index_entries(entries).await
+    }
+
+    async fn delete(
+        &self,
+        documents: Vec<(Timestamp, InternalDocumentId)>,
+    ) -> anyhow::Result<usize> {
+        self.inner.delete(documents).await
+    }
+
+    async fn shutdown(&self) -> anyhow::Result<()> {
+        self.inner.shutdown().await
+    }
+}
+
+struct FaultInjectionPersistenceReader<RT: Runtime> {
+    rt: RT,
+    inner: Arc<dyn PersistenceReader>,
+}
+
+async fn delay_read<RT: Runtime>(rt: &RT) {
+    let latency = CONFIG.read().persistence_read_latency;
+    if !latency.is_zero() {
+        rt.wait(latency).await;
+    }
+}
+
+/// Delays the first item of `stream`, which is when the read actually starts.
+fn delay_stream<'a, RT: Runtime, T: Send + 'a>(
+    rt: &RT,
+    stream: BoxStream<'a, T>,
+) -> BoxStream<'a, T> {
+    let latency = CONFIG.read().persistence_read_latency;
+    if latency.is_zero() {
+        return stream;
+    }
+    stream::once(rt.wait(latency))
+        .filter_map(|()| future::ready(None))
+        .chain(stream)
+        .boxed()
+}
+
+#[async_trait]
+impl<RT: Runtime> PersistenceReader for FaultInjectionPersistenceReader<RT> {
+    fn load_documents(
+        &self,
+        range: TimestampRange,
+        order: Order,
+        page_size: u32,
+        retention_validator: Arc<dyn RetentionValidator>,
+    ) -> DocumentStream<'_> {
+        delay_stream(
+            &self.rt,
+            self.inner
+                .load_documents(range, order, page_size, retention_validator),
+        )
+    }
+
+    fn load_documents_from_table(
+        &self,
+        tablet_id: TabletId,
+        range: TimestampRange,
+        order: Order,
+        page_size: u32,
+        retention_validator: Arc<dyn RetentionValidator>,
+    ) -> DocumentStream<'_> {
+        delay_stream(
+            &self.rt,
+            self.inner.load_documents_from_table(
+                tablet_id,
+                range,
+                order,
+                page_size,
+                retention_validator,
+            ),
+        )
+    }
+
+    async fn previous_revisions(
+        &self,
+        ids: BTreeSet<(InternalDocumentId, Timestamp)>,
+        retention_validator: Arc<dyn RetentionValidator>,
+    ) -> anyhow::Result<
+        BTreeMap<(InternalDocumentId, Timestamp), (Timestamp, Option<ResolvedDocument>)>,
+    > {
+        delay_read(&self.rt).await;
+        self.inner
+            .previous_revisions(ids, retention_validator)
+            .await
+    }
+
+    fn index_scan(
+        &self,
+        index_id: IndexId,
+        tablet_id: TabletId,
+        read_timestamp: Timestamp,
+        range: &Interval,
+        order: Order,
+        size_hint: usize,
+        retention_validator: Arc<dyn RetentionValidator>,
+    ) -> IndexStream<'_> {
+        delay_stream(
+            &self.rt,
+            self.inner.index_scan(
+                index_id,
+                tablet_id,
+                read_timestamp,
+                range,
+                order,
+                size_hint,
+                retention_validator,
+            ),
+        )
+    }
+
+    async fn get_persistence_global(
+        &self,
+        key: PersistenceGlobalKey,
+    ) -> anyhow::Result<Option<JsonValue>> {
+        delay_read(&self.rt).await;
+        self.inner.get_persistence_global(key).await
+    }
+
+    async fn index_get(
+        &self,
+        index_id: IndexId,
+        tablet_id: TabletId,
+        read_timestamp: Timestamp,
+        key: IndexKey,
+        retention_validator: Arc<dyn RetentionValidator>,
+    ) -> anyhow::Result<Option<(Timestamp, ResolvedDocument)>> {
+        delay_read(&self.rt).await;
+        self.inner
+            .index_get(
+                index_id,
+                tablet_id,
+                read_timestamp,
+                key,
+                retention_validator,
+            )
+            .await
+    }
+
+    async fn max_ts(&self) -> anyhow::Result<Option<Timestamp>> {
+        delay_read(&self.rt).await;
+        self.inner.max_ts().await
+    }
+
+    fn version(&self) -> PersistenceVersion {
+        self.inner.version()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::Arc,
+        time::Duration,
+    };
+
+    use futures::TryStreamExt;
+
+    use super::{
+        FaultInjectionConfig,
+        FaultInjectionPersistence,
+        CONFIG,
+    };
+    use crate::{
+        persistence::{
+            NoopRetentionValidator,
+            Persistence,
+            TimestampRange,
+        },
+        query::Order,
+        runtime::{
+            testing::TestDriver,
+            Runtime,
+        },
+        testing::TestPersistence,
+    };
+
+    #[test]
+    fn test_persistence_read_latency() -> anyhow::Result<()> {
+        let td = TestDriver::new();
+        let rt = td.rt();
+        td.run_until(async move {
+            let persistence =
+                FaultInjectionPersistence::new(rt.clone(), Arc::new(TestPersistence::new()));
+            let reader = persistence.reader();
+            *CONFIG.write() = FaultInjectionConfig {
+                persistence_read_latency: Duration::from_secs(1),
+                ..Default::default()
+            };
+            let start = rt.monotonic_now();
+            let documents: Vec<_> = reader
+                .load_documents(
+                    TimestampRange::all(),
+                    Order::Asc,
+                    10,
+                    Arc::new(NoopRetentionValidator),
+                )
+                .try_collect()
+                .await?;
+            assert!(documents.is_empty());
+            assert!(reader.max_ts().await?.is_none());
+            assert_eq!(rt.monotonic_now() - start, Duration::from_secs(2));
+            *CONFIG.write() = FaultInjectionConfig::default();
+            Ok(())
+        })
+    }
+}
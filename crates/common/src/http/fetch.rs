@@ -9,6 +9,7 @@ use std::{
     },
 };
 
+use anyhow::Context;
 use async_trait::async_trait;
 use futures::{
     future::BoxFuture,
@@ -22,10 +23,28 @@ use reqwest::{
     Proxy,
     Url,
 };
+use tokio::{
+    io::{
+        AsyncReadExt,
+        AsyncWriteExt,
+    },
+    net::TcpStream,
+};
+use tokio_tungstenite::{
+    tungstenite::{
+        client::IntoClientRequest,
+        protocol::WebSocketConfig,
+    },
+    MaybeTlsStream,
+    WebSocketStream,
+};
 
-use crate::http::{
-    HttpRequestStream,
-    HttpResponseStream,
+use crate::{
+    http::{
+        HttpRequestStream,
+        HttpResponseStream,
+    },
+    knobs::ACTION_WEBSOCKET_MAX_MESSAGE_SIZE,
 };
 
 /// Http client used for fetch syscall.
@@ -45,33 +64,51 @@ pub trait FetchClient: Send + Sync {
         request: HttpRequestStream,
         purpose: InternalFetchPurpose,
     ) -> anyhow::Result<HttpResponseStream>;
+
+    /// Open a websocket connection for the `WebSocket` API, with the same
+    /// restrictions as `fetch`. Returns the connection and the subprotocol
+    /// the server selected, if any.
+    async fn connect_websocket(
+        &self,
+        url: Url,
+        protocols: Vec<String>,
+    ) -> anyhow::Result<(WebSocketConnection, Option<String>)>;
 }
 
+pub type WebSocketConnection = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
 #[derive(Clone)]
 pub struct ProxiedFetchClient {
     http_client: reqwest::Client,
     internal_http_client: reqwest::Client,
+    // reqwest can't open websockets, so we tunnel them through the proxy
+    // ourselves.
+    websocket_proxy: Option<(Url, String)>,
 }
 
 impl ProxiedFetchClient {
     pub fn new(proxy_url: Option<Url>, client_id: String) -> Self {
         let mut builder = reqwest::Client::builder().redirect(redirect::Policy::none());
+        let mut websocket_proxy = None;
         // It's okay to panic on these errors, as they indicate a serious programming
         // error -- building the reqwest client is expected to be infallible.
         if let Some(proxy_url) = proxy_url {
-            let proxy = Proxy::all(proxy_url)
+            let proxy = Proxy::all(proxy_url.clone())
                 .expect("Infallible conversion from URL type to URL type")
                 .custom_http_auth(
                     client_id
+                        .clone()
                         .try_into()
                         .expect("Backend name is not valid ASCII?"),
                 );
             builder = builder.proxy(proxy);
+            websocket_proxy = Some((proxy_url, client_id));
         }
         builder = builder.user_agent("Convex/1.0");
         Self {
             http_client: builder.build().expect("Failed to build reqwest client"),
             internal_http_client: reqwest::Client::new(),
+            websocket_proxy,
         }
     }
 }
@@ -131,6 +168,96 @@ impl FetchClient for ProxiedFetchClient {
         };
         Ok(response)
     }
+
+    async fn connect_websocket(
+        &self,
+        url: Url,
+        protocols: Vec<String>,
+    ) -> anyhow::Result<(WebSocketConnection, Option<String>)> {
+        let host = url.host_str().context("WebSocket URL has no host")?;
+        let port = url
+            .port_or_known_default()
+            .context("WebSocket URL has no port")?;
+        let stream = match &self.websocket_proxy {
+            Some((proxy_url, proxy_auth)) => {
+                connect_through_proxy(proxy_url, proxy_auth, host, port).await?
+            },
+            None => TcpStream::connect((host, port)).await?,
+        };
+        connect_websocket_over(stream, url, protocols).await
+    }
+}
+
+/// Open a tunnel to `host:port` with an HTTP CONNECT request to the proxy.
+async fn connect_through_proxy(
+    proxy_url: &Url,
+    proxy_auth: &str,
+    host: &str,
+    port: u16,
+) -> anyhow::Result<TcpStream> {
+    const MAX_RESPONSE_HEADER_SIZE: usize = 8192;
+
+    let proxy_host = proxy_url.host_str().context("Proxy URL has no host")?;
+    let proxy_port = proxy_url
+        .port_or_known_default()
+        .context("Proxy URL has no port")?;
+    let mut stream = TcpStream::connect((proxy_host, proxy_port)).await?;
+    let request = format!(
+        "CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\nProxy-Authorization: \
+         {proxy_auth}\r\n\r\n"
+    );
+    stream.write_all(request.as_bytes()).await?;
+
+    // Read the response a byte at a time so none of the tunneled stream is
+    // consumed with it.
+    let mut response = vec![];
+    while !response.ends_with(b"\r\n\r\n") {
+        anyhow::ensure!(
+            response.len() < MAX_RESPONSE_HEADER_SIZE,
+            "Proxy response headers too large"
+        );
+        response.push(stream.read_u8().await?);
+    }
+    let status = std::str::from_utf8(&response)?
+        .split_whitespace()
+        .nth(1)
+        .context("Invalid proxy response")?;
+    match status.parse()? {
+        StatusCode::OK => Ok(stream),
+        // See the comment on SSRF in `fetch`.
+        StatusCode::PROXY_AUTHENTICATION_REQUIRED => {
+            anyhow::bail!("Request to {host}:{port} forbidden")
+        },
+        status => anyhow::bail!("Proxy responded to CONNECT with {status}"),
+    }
+}
+
+async fn connect_websocket_over(
+    stream: TcpStream,
+    url: Url,
+    protocols: Vec<String>,
+) -> anyhow::Result<(WebSocketConnection, Option<String>)> {
+    let mut request = url.as_str().into_client_request()?;
+    if !protocols.is_empty() {
+        request
+            .headers_mut()
+            .insert("Sec-WebSocket-Protocol", protocols.join(", ").parse()?);
+    }
+    let config = WebSocketConfig {
+        max_message_size: Some(*ACTION_WEBSOCKET_MAX_MESSAGE_SIZE),
+        max_frame_size: Some(*ACTION_WEBSOCKET_MAX_MESSAGE_SIZE),
+        ..Default::default()
+    };
+    let (connection, response) =
+        tokio_tungstenite::client_async_tls_with_config(request, stream, Some(config), None)
+            .await?;
+    let protocol = response
+        .headers()
+        .get("Sec-WebSocket-Protocol")
+        .map(|protocol| protocol.to_str())
+        .transpose()?
+        .map(|protocol| protocol.to_string());
+    Ok((connection, protocol))
 }
 
 type HandlerFn = Box<
@@ -196,10 +323,25 @@ impl FetchClient for StaticFetchClient {
     ) -> anyhow::Result<HttpResponseStream> {
         self.fetch(request).await
     }
+
+    async fn connect_websocket(
+        &self,
+        url: Url,
+        protocols: Vec<String>,
+    ) -> anyhow::Result<(WebSocketConnection, Option<String>)> {
+        let host = url.host_str().context("WebSocket URL has no host")?;
+        let port = url
+            .port_or_known_default()
+            .context("WebSocket URL has no port")?;
+        let stream = TcpStream::connect((host, port)).await?;
+        connect_websocket_over(stream, url, protocols).await
+    }
 }
 
 pub enum InternalFetchPurpose {
     AccessTokenAuth,
+    Replication,
+    LogStreaming,
 }
 
 #[cfg(test)]
@@ -1,5 +1,6 @@
 use std::{
     borrow::Cow,
+    collections::BTreeMap,
     convert::Infallible,
     fmt,
     future::Future,
@@ -102,8 +103,12 @@ use url::Url;
 use self::metrics::log_http_request;
 use crate::{
     errors::report_error,
-    knobs::HTTP_SERVER_TCP_BACKLOG,
+    knobs::{
+        HTTP_SERVER_TCP_BACKLOG,
+        OTEL_EXPORTER_OTLP_ENDPOINT,
+    },
     metrics::log_client_version_unsupported,
+    minitrace_helpers::get_sampled_span,
     runtime::TaskManager,
     version::{
         ClientVersion,
@@ -818,9 +823,16 @@ pub async fn stats_middleware<RM: RouteMapper>(
         .map(|r| r.as_str().to_owned())
         .unwrap_or("unknown".to_owned());
 
-    // Sampling isn't done here, and should be done upstream
+    // Requests are usually sampled upstream, which passes a `traceparent`. When
+    // this backend exports its own traces, sample the requests without one here.
     let root = match traceparent {
         Some(span_ctx) => Span::root(route.to_owned(), span_ctx),
+        None if OTEL_EXPORTER_OTLP_ENDPOINT.is_some() => get_sampled_span(
+            &resolved_host.instance_name,
+            &route,
+            &mut rand::thread_rng(),
+            BTreeMap::from([("http.method".to_string(), method.to_string())]),
+        ),
         None => Span::noop(),
     };
     let resp = next.run(req).in_span(root).await;
@@ -1,6 +1,7 @@
 use std::str::FromStr;
 
 use anyhow::Result;
+use errors::ErrorMetadata;
 use serde::{
     Deserialize,
     Serialize,
@@ -17,12 +18,16 @@ use crate::{
         IndexRangeExpression,
         Order,
         Query,
+        QueryHints,
         QueryOperator,
         QuerySource,
         Search,
         SearchFilterExpression,
+        TextSearchOptions,
+        MAX_SEARCH_EDIT_DISTANCE,
     },
     types::{
+        IndexDescriptor,
         IndexName,
         MaybeValue,
         TableName,
@@ -107,6 +112,15 @@ impl TryFrom<JsonIndexRangeExpression> for IndexRangeExpression {
     }
 }
 
+impl TryFrom<JsonValue> for IndexRangeExpression {
+    type Error = anyhow::Error;
+
+    fn try_from(value: JsonValue) -> Result<Self> {
+        let json_range_expression: JsonIndexRangeExpression = serde_json::from_value(value)?;
+        json_range_expression.try_into()
+    }
+}
+
 impl From<IndexRangeExpression> for JsonIndexRangeExpression {
     fn from(range_expression: IndexRangeExpression) -> Self {
         match range_expression {
@@ -165,6 +179,10 @@ enum JsonSearchFilterExpression {
     Search {
         field_path: String,
         value: String,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        prefix: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_edit_distance: Option<u8>,
     },
     Eq(JsonFieldPathAndValue),
 }
@@ -174,9 +192,33 @@ impl TryFrom<JsonSearchFilterExpression> for SearchFilterExpression {
 
     fn try_from(json_filter_expression: JsonSearchFilterExpression) -> Result<Self> {
         match json_filter_expression {
-            JsonSearchFilterExpression::Search { field_path, value } => Ok(
-                SearchFilterExpression::Search(FieldPath::from_str(&field_path)?, value),
-            ),
+            JsonSearchFilterExpression::Search {
+                field_path,
+                value,
+                prefix,
+                max_edit_distance,
+            } => {
+                let max_edit_distance = max_edit_distance.unwrap_or(0);
+                anyhow::ensure!(
+                    max_edit_distance <= MAX_SEARCH_EDIT_DISTANCE,
+                    ErrorMetadata::bad_request(
+                        "InvalidSearchOptions",
+                        format!(
+                            "maxEditDistance must be between 0 and {MAX_SEARCH_EDIT_DISTANCE}, \
+                             got {max_edit_distance}."
+                        ),
+                    )
+                );
+                let options = TextSearchOptions {
+                    prefix,
+                    max_edit_distance,
+                };
+                Ok(SearchFilterExpression::Search(
+                    FieldPath::from_str(&field_path)?,
+                    value,
+                    options,
+                ))
+            },
             JsonSearchFilterExpression::Eq(field_and_value) => Ok(SearchFilterExpression::Eq(
                 FieldPath::from_str(&field_and_value.field_path)?,
                 MaybeValue::try_from(field_and_value.value)?.0,
@@ -188,10 +230,13 @@ impl TryFrom<JsonSearchFilterExpression> for SearchFilterExpression {
 impl From<SearchFilterExpression> for JsonSearchFilterExpression {
     fn from(filter_expression: SearchFilterExpression) -> Self {
         match filter_expression {
-            SearchFilterExpression::Search(field_path, value) => {
+            SearchFilterExpression::Search(field_path, value, options) => {
                 JsonSearchFilterExpression::Search {
                     field_path: field_path.into(),
                     value,
+                    prefix: options.prefix,
+                    max_edit_distance: (options.max_edit_distance > 0)
+                        .then_some(options.max_edit_distance),
                 }
             },
             SearchFilterExpression::Eq(field_path, value) => {
@@ -209,6 +254,44 @@ impl From<SearchFilterExpression> for JsonSearchFilterExpression {
 struct JsonQuery {
     pub source: JsonQuerySource,
     pub operators: Vec<JsonQueryOperator>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hints: Option<JsonQueryHints>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonQueryHints {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    index: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    disable_filter_pushdown: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scan_budget: Option<usize>,
+}
+
+impl TryFrom<JsonQueryHints> for QueryHints {
+    type Error = anyhow::Error;
+
+    fn try_from(value: JsonQueryHints) -> Result<Self> {
+        Ok(QueryHints {
+            index: value
+                .index
+                .map(|index| IndexDescriptor::from_str(&index))
+                .transpose()?,
+            disable_filter_pushdown: value.disable_filter_pushdown,
+            scan_budget: value.scan_budget,
+        })
+    }
+}
+
+impl From<QueryHints> for JsonQueryHints {
+    fn from(value: QueryHints) -> Self {
+        JsonQueryHints {
+            index: value.index.map(|index| index.to_string()),
+            disable_filter_pushdown: value.disable_filter_pushdown,
+            scan_budget: value.scan_budget,
+        }
+    }
 }
 
 #[derive(Deserialize, Serialize)]
@@ -312,6 +395,11 @@ impl TryFrom<JsonValue> for Query {
                     })
                 })
                 .collect::<Result<Vec<QueryOperator>>>()?,
+            hints: json_query
+                .hints
+                .map(QueryHints::try_from)
+                .transpose()?
+                .unwrap_or_default(),
         })
     }
 }
@@ -332,6 +420,9 @@ impl TryFrom<Query> for JsonValue {
                     QueryOperator::Limit(n) => JsonQueryOperator::Limit(n),
                 })
                 .collect(),
+            // Omit the hints from queries without any so that they keep the
+            // same fingerprint.
+            hints: (!query.hints.is_empty()).then(|| query.hints.into()),
         };
         Ok(serde_json::to_value(json_query)?)
     }
@@ -87,6 +87,11 @@ pub static HEAP_WORKER_REPORT_INTERVAL_SECONDS: LazyLock<Duration> =
 pub static ACTION_USER_TIMEOUT: LazyLock<Duration> =
     LazyLock::new(|| Duration::from_secs(env_config("ACTIONS_USER_TIMEOUT_SECS", 600)));
 
+/// Upper bound on the user timeout an admin can give a single action in
+/// `_function_limits`.
+pub static ACTION_MAX_USER_TIMEOUT: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(env_config("ACTIONS_MAX_USER_TIMEOUT_SECS", 600)));
+
 /// Max number of rows we will read when calculating document deltas.
 pub static DOCUMENT_DELTAS_LIMIT: LazyLock<usize> =
     LazyLock::new(|| env_config("DOCUMENT_DELTAS_LIMIT", 128));
@@ -97,6 +102,11 @@ pub static DOCUMENT_DELTAS_LIMIT: LazyLock<usize> =
 pub static SNAPSHOT_LIST_LIMIT: LazyLock<usize> =
     LazyLock::new(|| env_config("SNAPSHOT_LIST_LIMIT", 1024));
 
+/// Allows admins to inject faults like failed commits and slow persistence
+/// reads. Only enable this on staging deployments.
+pub static ENABLE_FAULT_INJECTION: LazyLock<bool> =
+    LazyLock::new(|| env_config("ENABLE_FAULT_INJECTION", false));
+
 /// Enables the log streaming worker.
 pub static ENABLE_LOG_STREAMING: LazyLock<bool> =
     LazyLock::new(|| env_config("ENABLE_LOG_STREAMING", true));
@@ -110,10 +120,29 @@ pub static LOG_MANAGER_EVENT_RECV_BUFFER_SIZE: LazyLock<usize> =
 pub static LOG_MANAGER_AGGREGATION_INTERVAL_MILLIS: LazyLock<u64> =
     LazyLock::new(|| env_config("LOG_MANAGER_AGGREGATION_INTERVAL", 5000));
 
+/// The most events buffered for each log sink while it's sending a batch.
+/// Events for a sink are dropped while its buffer is full.
+pub static LOG_SINK_BUFFER_SIZE: LazyLock<usize> =
+    LazyLock::new(|| env_config("LOG_SINK_BUFFER_SIZE", 16384));
+
+/// The most events a log sink sends in one batch.
+pub static LOG_SINK_MAX_BATCH_SIZE: LazyLock<usize> =
+    LazyLock::new(|| env_config("LOG_SINK_MAX_BATCH_SIZE", 1000));
+
+/// How many times a log sink tries to send a batch before dropping it and
+/// marking the sink as failed.
+pub static LOG_SINK_MAX_ATTEMPTS: LazyLock<u32> =
+    LazyLock::new(|| env_config("LOG_SINK_MAX_ATTEMPTS", 5));
+
 /// Max number of times a mutation can retry due to OCC conflicts.
 pub static UDF_EXECUTOR_OCC_MAX_RETRIES: LazyLock<usize> =
     LazyLock::new(|| env_config("UDF_EXECUTOR_OCC_MAX_RETRIES", 4));
 
+/// Max number of mutations that can be submitted in a single mutation batch.
+/// All of them run in one transaction, so they also share its limits.
+pub static MAX_MUTATION_BATCH_SIZE: LazyLock<usize> =
+    LazyLock::new(|| env_config("MAX_MUTATION_BATCH_SIZE", 64));
+
 /// Initial backoff when we encounter an OCC conflict.
 pub static UDF_EXECUTOR_OCC_INITIAL_BACKOFF: LazyLock<Duration> =
     LazyLock::new(|| Duration::from_millis(env_config("UDF_EXECUTOR_OCC_INITIAL_BACKOFF_MS", 10)));
@@ -207,6 +236,16 @@ pub static FUNCTION_MAX_RESULT_SIZE: LazyLock<usize> = LazyLock::new(|| {
     env_config("FUNCTION_MAX_RESULT_SIZE", 1 << 23) // 8 MiB
 });
 
+/// Fraction of successful function calls whose arguments are sampled to infer
+/// the function's argument shapes and check pushed validators against.
+pub static FUNCTION_ARGS_SAMPLE_RATE: LazyLock<f64> =
+    LazyLock::new(|| env_config("FUNCTION_ARGS_SAMPLE_RATE", 0.01));
+
+/// How many of each function's most recently sampled arguments to keep for
+/// checking pushed validators against.
+pub static FUNCTION_ARGS_MAX_SAMPLES: LazyLock<usize> =
+    LazyLock::new(|| env_config("FUNCTION_ARGS_MAX_SAMPLES", 32));
+
 /// When a function exceeds FUNCTION_LIMIT_WARNING_RATIO * a corresponding
 /// limit value, we add a warning log line.
 pub static FUNCTION_LIMIT_WARNING_RATIO: LazyLock<f64> = LazyLock::new(|| {
@@ -262,6 +301,18 @@ pub static SCHEDULED_JOB_MAX_BACKOFF: LazyLock<Duration> = LazyLock::new(|| {
     Duration::from_secs(env_config("SCHEDULED_JOB_MAX_BACKOFF_SECS", 2 * 60 * 60))
 });
 
+/// How many times a scheduled job can hit a system error before it is moved
+/// to the dead letter table instead of being retried again. Zero retries
+/// forever. Admins can override this and the backoff for a deployment.
+pub static SCHEDULED_JOB_MAX_ATTEMPTS: LazyLock<u32> =
+    LazyLock::new(|| env_config("SCHEDULED_JOB_MAX_ATTEMPTS", 25));
+
+/// How many times a scheduled action that saved a checkpoint is re-run after
+/// timing out or crashing. It's also only re-run if it saved a checkpoint
+/// since the last time, so an action that isn't making progress stops early.
+pub static SCHEDULED_ACTION_MAX_RESUMES: LazyLock<u64> =
+    LazyLock::new(|| env_config("SCHEDULED_ACTION_MAX_RESUMES", 100));
+
 /// Initial backoff in milliseconds on a system error from the scheduled job
 /// garbage collector.
 pub static SCHEDULED_JOB_GARBAGE_COLLECTION_INITIAL_BACKOFF: LazyLock<Duration> =
@@ -387,6 +438,12 @@ pub static BACKEND_STARTUP_DELAY: LazyLock<Duration> = LazyLock::new(|| {
     ))
 });
 
+/// Check the `_tables` and `_index` metadata when the database loads, and
+/// refuse to start if it's inconsistent. Disable to start the deployment
+/// anyway, for example to export its data.
+pub static STARTUP_INTEGRITY_CHECK_ENABLED: LazyLock<bool> =
+    LazyLock::new(|| env_config("STARTUP_INTEGRITY_CHECK_ENABLED", true));
+
 /// When to start rejecting new additions to the search memory index.
 pub static TEXT_INDEX_SIZE_HARD_LIMIT: LazyLock<usize> =
     LazyLock::new(|| env_config("SEARCH_INDEX_SIZE_HARD_LIMIT", 100 * (1 << 20))); // 100 MiB
@@ -535,6 +592,34 @@ pub static SYSTEM_TABLE_ROWS_PER_SECOND: LazyLock<NonZeroU32> = LazyLock::new(||
     )
 });
 
+/// Number of document revisions from dropped (`Deleting`) tables deleted from
+/// persistence in a single batch.
+pub static DELETING_TABLES_CLEANUP_CHUNK_SIZE: LazyLock<usize> =
+    LazyLock::new(|| env_config("DELETING_TABLES_CLEANUP_CHUNK_SIZE", 256));
+
+/// Default maximum number of document revisions from dropped tables deleted
+/// per second. Can be changed at runtime through the admin API.
+pub static DELETING_TABLES_CLEANUP_ROWS_PER_SECOND: LazyLock<NonZeroU32> = LazyLock::new(|| {
+    env_config(
+        "DELETING_TABLES_CLEANUP_ROWS_PER_SECOND",
+        NonZeroU32::new(256).unwrap(),
+    )
+});
+
+/// Number of document revisions a table compaction scans before deleting the
+/// expired ones and saving its progress.
+pub static TABLE_COMPACTION_CHUNK_SIZE: LazyLock<usize> =
+    LazyLock::new(|| env_config("TABLE_COMPACTION_CHUNK_SIZE", 256));
+
+/// Maximum number of rows (document revisions and index entries) a table
+/// compaction deletes per second.
+pub static TABLE_COMPACTION_ROWS_PER_SECOND: LazyLock<NonZeroU32> = LazyLock::new(|| {
+    env_config(
+        "TABLE_COMPACTION_ROWS_PER_SECOND",
+        NonZeroU32::new(1024).unwrap(),
+    )
+});
+
 /// Default 6 months, which is approximately how often we deprecate npm
 /// packages. If the npm package is deprecated, the client can't reconnect with
 /// an outstanding mutation. We can potentially reduce this window by changing
@@ -611,6 +696,12 @@ pub static VECTOR_INDEX_WORKER_PAGE_SIZE: LazyLock<usize> =
 pub static DATABASE_UDF_USER_TIMEOUT: LazyLock<Duration> =
     LazyLock::new(|| Duration::from_secs(env_config("DATABASE_UDF_USER_TIMEOUT_SECONDS", 1)));
 
+/// Upper bound on the "user time" timeout an internal query or mutation can
+/// declare for itself with `timeoutMs`, or an admin can give any query or
+/// mutation in `_function_limits`.
+pub static DATABASE_UDF_MAX_USER_TIMEOUT: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(env_config("DATABASE_UDF_MAX_USER_TIMEOUT_SECONDS", 10)));
+
 /// Timeout on the "system time" during a UDF -- i.e. syscalls.
 // The user limits are not very tight, which requires us to have a high
 // syscall timeout. When the database is healthy, we should never have UDF
@@ -667,6 +758,17 @@ pub static REUSE_ISOLATES: LazyLock<bool> = LazyLock::new(|| env_config("REUSE_I
 pub static ISOLATE_IDLE_TIMEOUT: LazyLock<Duration> =
     LazyLock::new(|| Duration::from_secs(env_config("ISOLATE_IDLE_TIMEOUT_SECONDS", 600)));
 
+/// The most isolates a single function can ask to keep warm with its module
+/// evaluated, via its `warmInstances` setting.
+pub static MAX_WARM_INSTANCES_PER_FUNCTION: LazyLock<u32> =
+    LazyLock::new(|| env_config("MAX_WARM_INSTANCES_PER_FUNCTION", 4));
+
+/// How often to re-evaluate the modules of functions with `warmInstances` set.
+/// This should be shorter than `ISOLATE_IDLE_TIMEOUT` so the warm isolates
+/// aren't recreated between warm-ups.
+pub static FUNCTION_WARM_UP_INTERVAL: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(env_config("FUNCTION_WARM_UP_INTERVAL_SECONDS", 300)));
+
 /// The maximum amount of time an isolate can be used before being recreated.
 pub static ISOLATE_MAX_LIFETIME: LazyLock<Duration> =
     LazyLock::new(|| Duration::from_secs(env_config("ISOLATE_MAX_LIFETIME_SECONDS", 60 * 60)));
@@ -732,6 +834,12 @@ pub static APPLICATION_MAX_CONCURRENT_V8_ACTIONS: LazyLock<usize> =
 pub static APPLICATION_MAX_CONCURRENT_NODE_ACTIONS: LazyLock<usize> =
     LazyLock::new(|| env_config("APPLICATION_MAX_CONCURRENT_NODE_ACTIONS", 16));
 
+/// Whether Node actions pinned to a region with no action runners configured
+/// fail, rather than falling back to the default action runners. Enable this
+/// when the region is a data residency requirement rather than a preference.
+pub static ACTION_REGION_STRICT: LazyLock<bool> =
+    LazyLock::new(|| env_config("ACTION_REGION_STRICT", false));
+
 /// Number of threads to execute V8 actions.
 ///
 /// Http actions are not sent through FunctionRunner implementations. This is a
@@ -750,11 +858,189 @@ pub static APPLICATION_MAX_CONCURRENT_HTTP_ACTIONS: LazyLock<usize> = LazyLock::
     )
 });
 
+/// How long an HTTP action streaming Server-Sent Events can go without sending
+/// anything before we send a keep-alive comment, so proxies and clients don't
+/// close the connection as idle.
+pub static HTTP_ACTION_SSE_KEEPALIVE_INTERVAL: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(env_config("HTTP_ACTION_SSE_KEEPALIVE_INTERVAL_SECS", 15))
+});
+
+/// The longest an HTTP action can stream Server-Sent Events before we end the
+/// response. `EventSource` clients reconnect automatically when it ends.
+pub static HTTP_ACTION_SSE_MAX_DURATION: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(env_config("HTTP_ACTION_SSE_MAX_DURATION_SECS", 30 * 60)));
+
+/// The largest request body an HTTP action accepts. Requests that declare a
+/// larger `Content-Length` are rejected with a 413 before the action runs, and
+/// bodies streamed without one are cut off with a 413 once they pass it.
+/// Raise it to accept large webhook payloads.
+pub static HTTP_ACTION_REQUEST_BODY_LIMIT: LazyLock<usize> =
+    LazyLock::new(|| env_config("HTTP_ACTION_REQUEST_BODY_LIMIT_BYTES", 20 << 20));
+
+/// Age at which the ACME worker renews a custom domain's certificate. Let's
+/// Encrypt certificates are valid for 90 days.
+pub static CUSTOM_DOMAIN_CERTIFICATE_RENEWAL_AGE: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(env_config(
+        "CUSTOM_DOMAIN_CERTIFICATE_RENEWAL_AGE_SECS",
+        60 * 24 * 60 * 60,
+    ))
+});
+
+/// How long the ACME worker waits before retrying a custom domain whose
+/// certificate couldn't be issued, e.g. because its DNS doesn't point at the
+/// backend yet.
+pub static CUSTOM_DOMAIN_CERTIFICATE_RETRY_INTERVAL: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(env_config(
+        "CUSTOM_DOMAIN_CERTIFICATE_RETRY_INTERVAL_SECS",
+        60 * 60,
+    ))
+});
+
+/// How long a passkey registration or login has to complete before its
+/// WebAuthn challenge expires.
+pub static WEBAUTHN_CHALLENGE_TTL: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(env_config("WEBAUTHN_CHALLENGE_TTL_SECS", 5 * 60)));
+
+/// How often the HTTP router reloads the deployment's rate limits. Changes to
+/// them take up to this long to apply.
+pub static RATE_LIMIT_CONFIG_REFRESH_INTERVAL: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(env_config("RATE_LIMIT_CONFIG_REFRESH_INTERVAL_SECS", 5)));
+
+/// Whether to rate limit and apply the network ACL by the client IP in
+/// `X-Forwarded-For` rather than the address connecting to the backend. Only
+/// enable this behind a reverse proxy that sets the header, since clients can
+/// set it to anything.
+pub static RATE_LIMIT_TRUST_FORWARDED_FOR: LazyLock<bool> =
+    LazyLock::new(|| env_config("RATE_LIMIT_TRUST_FORWARDED_FOR", false));
+
+/// How often the HTTP router reloads the deployment's network ACL. Changes to
+/// it take up to this long to apply.
+pub static NETWORK_ACL_CONFIG_REFRESH_INTERVAL: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(env_config("NETWORK_ACL_CONFIG_REFRESH_INTERVAL_SECS", 5))
+});
+
+/// Requests rejected by the network ACL are recorded in the audit log at most
+/// once per address in each interval of this length.
+pub static NETWORK_ACL_AUDIT_LOG_INTERVAL: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(env_config("NETWORK_ACL_AUDIT_LOG_INTERVAL_SECS", 60)));
+
+/// The most rejected addresses recorded in the audit log in each
+/// `NETWORK_ACL_AUDIT_LOG_INTERVAL`, so a flood of requests from many
+/// addresses can't flood the audit log too.
+pub static NETWORK_ACL_MAX_AUDIT_LOGS_PER_INTERVAL: LazyLock<usize> =
+    LazyLock::new(|| env_config("NETWORK_ACL_MAX_AUDIT_LOGS_PER_INTERVAL", 100));
+
 /// The maximum number of concurrent package uploads during
 /// `/api/deploy2/start_push`.
 pub static APPLICATION_MAX_CONCURRENT_UPLOADS: LazyLock<usize> =
     LazyLock::new(|| env_config("APPLICATION_MAX_CONCURRENT_UPLOADS", 4));
 
+/// The most shards a sharded counter's value can be split across.
+pub static COUNTER_MAX_SHARDS: LazyLock<u32> =
+    LazyLock::new(|| env_config("COUNTER_MAX_SHARDS", 64));
+
+/// The most shards a rate limit called from a mutation can split its
+/// allowance across.
+pub static RATE_LIMITER_MAX_SHARDS: LazyLock<u32> =
+    LazyLock::new(|| env_config("RATE_LIMITER_MAX_SHARDS", 64));
+
+/// Files younger than this are never reported as orphaned by the storage
+/// garbage collection report, since a client that just uploaded a file may not
+/// have stored its ID in a document yet.
+pub static STORAGE_GC_MIN_FILE_AGE: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(env_config("STORAGE_GC_MIN_FILE_AGE_SECS", 60 * 60)));
+
+/// Whether to periodically sample the app's tables for fields that look like
+/// they hold emails, phone numbers or credit card numbers, and store a report
+/// for each table in `_pii_reports`.
+pub static PII_SCAN_ENABLED: LazyLock<bool> =
+    LazyLock::new(|| env_config("PII_SCAN_ENABLED", false));
+
+/// How often tables are scanned for PII when `PII_SCAN_ENABLED` is set.
+pub static PII_SCAN_INTERVAL: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(env_config("PII_SCAN_INTERVAL_SECS", 24 * 60 * 60)));
+
+/// How many documents of each table a PII scan looks at.
+pub static PII_SCAN_SAMPLE_SIZE: LazyLock<usize> =
+    LazyLock::new(|| env_config("PII_SCAN_SAMPLE_SIZE", 1000));
+
+/// How often the function metrics collected in memory are added to the hourly
+/// and daily rollups in `_metrics_rollups`. Metrics collected since the last
+/// flush are lost if the backend stops.
+pub static METRICS_ROLLUP_FLUSH_INTERVAL: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(env_config("METRICS_ROLLUP_FLUSH_INTERVAL_SECS", 60)));
+
+/// How long hourly metrics rollups are kept.
+pub static METRICS_ROLLUP_HOURLY_RETENTION: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(env_config(
+        "METRICS_ROLLUP_HOURLY_RETENTION_SECS",
+        7 * 24 * 60 * 60,
+    ))
+});
+
+/// How long daily metrics rollups are kept.
+pub static METRICS_ROLLUP_DAILY_RETENTION: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(env_config(
+        "METRICS_ROLLUP_DAILY_RETENTION_SECS",
+        90 * 24 * 60 * 60,
+    ))
+});
+
+/// Function executions that take at least this long are recorded in
+/// `_slow_executions`. Set to 0 to only record executions over the row
+/// thresholds.
+pub static SLOW_EXECUTION_THRESHOLD: LazyLock<Option<Duration>> = LazyLock::new(|| {
+    let millis = env_config("SLOW_EXECUTION_THRESHOLD_MS", 1000);
+    (millis > 0).then(|| Duration::from_millis(millis))
+});
+
+/// Function executions that read at least this many documents are recorded in
+/// `_slow_executions`. Set to 0 to turn off.
+pub static SLOW_EXECUTION_ROWS_READ_THRESHOLD: LazyLock<u64> =
+    LazyLock::new(|| env_config("SLOW_EXECUTION_ROWS_READ_THRESHOLD", 8192));
+
+/// Function executions that write at least this many documents are recorded
+/// in `_slow_executions`. Set to 0 to turn off.
+pub static SLOW_EXECUTION_ROWS_WRITTEN_THRESHOLD: LazyLock<u64> =
+    LazyLock::new(|| env_config("SLOW_EXECUTION_ROWS_WRITTEN_THRESHOLD", 4096));
+
+/// How many of the most recent slow executions are kept in
+/// `_slow_executions`.
+pub static SLOW_EXECUTION_LOG_MAX_ROWS: LazyLock<usize> =
+    LazyLock::new(|| env_config("SLOW_EXECUTION_LOG_MAX_ROWS", 1000));
+
+/// How often slow executions collected in memory are written to
+/// `_slow_executions`.
+pub static SLOW_EXECUTION_LOG_FLUSH_INTERVAL: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(env_config("SLOW_EXECUTION_LOG_FLUSH_INTERVAL_SECS", 10)));
+
+/// How often usage metered in memory is added to the daily counters in
+/// `_usage_meters`.
+pub static USAGE_METER_FLUSH_INTERVAL: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(env_config("USAGE_METER_FLUSH_INTERVAL_SECS", 10)));
+
+/// How long daily usage counters are kept.
+pub static USAGE_METER_RETENTION: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(env_config("USAGE_METER_RETENTION_SECS", 400 * 24 * 60 * 60))
+});
+
+/// How often usage quotas and the usage counted against them are reloaded.
+/// Quotas are enforced approximately, and functions can go over a quota by
+/// about this much usage.
+pub static USAGE_QUOTA_REFRESH_INTERVAL: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(env_config("USAGE_QUOTA_REFRESH_INTERVAL_SECS", 10)));
+
+/// The largest file in storage that can be transformed with the image
+/// parameters on the file-serving route.
+pub static MAX_IMAGE_TRANSFORM_SOURCE_SIZE: LazyLock<u64> =
+    LazyLock::new(|| env_config("MAX_IMAGE_TRANSFORM_SOURCE_SIZE", 20 << 20));
+
+/// The largest width or height, in pixels, of an image transformed on the
+/// file-serving route. Larger sources are rejected, and larger outputs can't be
+/// requested.
+pub static MAX_IMAGE_TRANSFORM_DIMENSION: LazyLock<u32> =
+    LazyLock::new(|| env_config("MAX_IMAGE_TRANSFORM_DIMENSION", 8192));
+
 /// Set a 64MB limit on the heap size.
 pub static ISOLATE_MAX_USER_HEAP_SIZE: LazyLock<usize> =
     LazyLock::new(|| env_config("ISOLATE_MAX_USER_HEAP_SIZE", 1 << 26));
@@ -764,6 +1050,11 @@ pub static ISOLATE_MAX_USER_HEAP_SIZE: LazyLock<usize> =
 pub static ISOLATE_MAX_HEAP_EXTRA_SIZE: LazyLock<usize> =
     LazyLock::new(|| env_config("ISOLATE_MAX_HEAP_EXTRA_SIZE", 1 << 25));
 
+/// Upper bound on the heap size an admin can give a single function in
+/// `_function_limits`. Defaults to 512MB.
+pub static ISOLATE_MAX_FUNCTION_HEAP_SIZE: LazyLock<usize> =
+    LazyLock::new(|| env_config("ISOLATE_MAX_FUNCTION_HEAP_SIZE", 1 << 29));
+
 /// Chunk sizes: 1, 2, 3, ..., MAX_DYNAMIC_SMART_CHUNK_SIZE incrementing by 1.
 /// These chunk sizes allow small (common) batches to be handled in a single
 /// chunk, while limiting the size of a chunk (don't overload the db), and
@@ -819,12 +1110,61 @@ pub static MYSQL_CHUNK_SIZE: LazyLock<usize> =
 pub static MAX_CONCURRENT_ACTION_OPS: LazyLock<usize> =
     LazyLock::new(|| env_config("MAX_CONCURRENT_ACTION_OPS", 8));
 
+/// How many websocket connections an action can have open at once. They're
+/// closed when the action finishes.
+pub static ACTION_MAX_WEBSOCKET_CONNECTIONS: LazyLock<usize> =
+    LazyLock::new(|| env_config("ACTION_MAX_WEBSOCKET_CONNECTIONS", 8));
+
+/// The largest message an action can send or receive over a websocket.
+pub static ACTION_WEBSOCKET_MAX_MESSAGE_SIZE: LazyLock<usize> =
+    LazyLock::new(|| env_config("ACTION_WEBSOCKET_MAX_MESSAGE_SIZE", 16 << 20));
+
 /// Maximum count of transitions within the web socket server message buffer.
 /// When this limit is reached, the web socket worker will temporary stop
 /// computing and sending transition messages to the client.
 pub static SYNC_MAX_SEND_TRANSITION_COUNT: LazyLock<usize> =
     LazyLock::new(|| env_config("SYNC_MAX_SEND_TRANSITION_COUNT", 2));
 
+/// Total size in bytes of the transitions within the web socket server message
+/// buffer at which the web socket worker stops computing new ones, like
+/// `SYNC_MAX_SEND_TRANSITION_COUNT`. A single larger transition is still sent.
+pub static SYNC_MAX_SEND_TRANSITION_SIZE: LazyLock<usize> =
+    LazyLock::new(|| env_config("SYNC_MAX_SEND_TRANSITION_SIZE", 8 << 20));
+
+/// Maximum size in bytes of the web socket server message buffer. Transitions
+/// for slow clients are coalesced rather than buffered, so this is only reached
+/// by a client that isn't reading its mutation and action responses. It gets
+/// disconnected instead of the server buffering them without bound.
+pub static SYNC_MAX_SEND_BUFFER_SIZE: LazyLock<usize> =
+    LazyLock::new(|| env_config("SYNC_MAX_SEND_BUFFER_SIZE", 64 << 20));
+
+/// Minimum time between a sync worker's reruns of invalidated queries. Commits
+/// that invalidate a client's queries within this long of its last update are
+/// handled together at the end of the window, so hot tables with many
+/// subscribers rerun each query once per window rather than once per commit.
+/// Zero disables coalescing.
+pub static SYNC_INVALIDATION_COALESCE_WINDOW: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_millis(env_config("SYNC_INVALIDATION_COALESCE_WINDOW_MS", 0)));
+
+/// Query results at least this many bytes long are sent as patches to the
+/// client's previous result, when the client supports them and the patch is
+/// smaller. Smaller results are always sent whole, since diffing them costs
+/// more than it saves.
+pub static SYNC_QUERY_PATCH_MIN_SIZE: LazyLock<usize> =
+    LazyLock::new(|| env_config("SYNC_QUERY_PATCH_MIN_SIZE", 4096));
+
+/// How long a sync session's queries and identity are kept after its websocket
+/// closes. A client that reconnects within this window resumes the session
+/// rather than resending every query, which matters on flaky mobile networks.
+/// Zero disables resumption.
+pub static SYNC_SESSION_RESUME_GRACE_PERIOD: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(env_config("SYNC_SESSION_RESUME_GRACE_PERIOD_SECS", 60)));
+
+/// Maximum number of closed sync sessions kept for resumption. The sessions
+/// closest to expiring are dropped first.
+pub static SYNC_MAX_RETAINED_SESSIONS: LazyLock<usize> =
+    LazyLock::new(|| env_config("SYNC_MAX_RETAINED_SESSIONS", 1000));
+
 /// Max Axiom sink attributes. This is a knob just in case a user actually hits
 /// the limit but has an Enterprise Axiom plan that lets them use more than the
 /// limit we've configured.
@@ -1085,6 +1425,11 @@ pub static DATABASE_WORKERS_MIN_COMMITS: LazyLock<usize> =
 pub static HTTP_SERVER_TIMEOUT_DURATION: LazyLock<Duration> =
     LazyLock::new(|| Duration::from_secs(env_config("HTTP_SERVER_TIMEOUT_SECONDS", 300)));
 
+/// How long an HTTP query waits for the backend to reach the `minTs` the client
+/// passed in, e.g. the commit timestamp of its last mutation.
+pub static HTTP_QUERY_MIN_TS_MAX_WAIT: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_millis(env_config("HTTP_QUERY_MIN_TS_MAX_WAIT_MS", 2000)));
+
 /// The limit on the request size to /push_config.
 // Schema and code bundle pushes must be less than this.
 pub static MAX_PUSH_BYTES: LazyLock<usize> =
@@ -1124,6 +1469,23 @@ pub static REQUEST_TRACE_SAMPLE_CONFIG: LazyLock<SamplingConfig> = LazyLock::new
     )
 });
 
+/// The OTLP/HTTP endpoint, like "http://localhost:4318", that sampled traces
+/// are exported to. Traces aren't exported if this is unset. Requests without
+/// a `traceparent` header are sampled according to
+/// `REQUEST_TRACE_SAMPLE_CONFIG`.
+pub static OTEL_EXPORTER_OTLP_ENDPOINT: LazyLock<Option<String>> = LazyLock::new(|| {
+    let endpoint: String = env_config("OTEL_EXPORTER_OTLP_ENDPOINT", String::new());
+    if endpoint.is_empty() {
+        None
+    } else {
+        Some(endpoint)
+    }
+});
+
+/// The `service.name` of exported traces.
+pub static OTEL_SERVICE_NAME: LazyLock<String> =
+    LazyLock::new(|| env_config("OTEL_SERVICE_NAME", "convex-backend".to_string()));
+
 /// If true, the backend will check the rate limiter service for capacity under
 /// the "backend_startup" domain keyed by db cluster name.
 pub static STARTUP_RATE_LIMIT_ENABLED: LazyLock<bool> =
@@ -1167,8 +1529,167 @@ pub static EXECUTE_HTTP_ACTIONS_IN_FUNRUN: LazyLock<bool> =
 pub static MAX_IMPORT_AGE: LazyLock<Duration> =
     LazyLock::new(|| Duration::from_secs(env_config("MAX_IMPORT_AGE_SECONDS", 7 * 24 * 60 * 60)));
 
+/// Minimum time between saves of a snapshot export's progress to `_exports`,
+/// where subscribed clients pick it up.
+pub static SNAPSHOT_EXPORT_PROGRESS_INTERVAL: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_millis(env_config("SNAPSHOT_EXPORT_PROGRESS_INTERVAL_MS", 1000))
+});
+
+/// How long replication tokens issued by the leader are valid for.
+pub static REPLICATION_TOKEN_VALIDITY: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(env_config(
+        "REPLICATION_TOKEN_VALIDITY_SECS",
+        30 * 24 * 60 * 60,
+    ))
+});
+
+/// How often the leader's replication stream polls for new commits once a
+/// follower has caught up.
+pub static REPLICATION_STREAM_POLL_INTERVAL: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_millis(env_config("REPLICATION_STREAM_POLL_INTERVAL_MS", 250)));
+
+/// Maximum number of document revisions sent in one page of a replication
+/// stream.
+pub static REPLICATION_STREAM_PAGE_SIZE: LazyLock<usize> =
+    LazyLock::new(|| env_config("REPLICATION_STREAM_PAGE_SIZE", 1000));
+
 /// Max staleness in seconds of a partition loader result before we allow
 /// refreshing. If a request tries to update the partition loader and this
 /// duration has not passed since the last refresh, a stale value will be used.
 pub static PARTITION_LOADER_MAX_STALE_SECS: LazyLock<Duration> =
     LazyLock::new(|| Duration::from_secs(env_config("PARTITION_LOADER_MAX_STALE_SECS", 1)));
+
+/// Whether the SQLite persistence zstd compresses the part of long index keys
+/// past their stored prefix. Existing entries are rewritten to match in the
+/// background after startup.
+pub static INDEX_KEY_BLOCK_COMPRESSION: LazyLock<bool> =
+    LazyLock::new(|| env_config("INDEX_KEY_BLOCK_COMPRESSION", true));
+
+/// Number of index entries checked in each batch when recompressing index
+/// keys after startup.
+pub static INDEX_KEY_RECOMPRESSION_BATCH_SIZE: LazyLock<usize> =
+    LazyLock::new(|| env_config("INDEX_KEY_RECOMPRESSION_BATCH_SIZE", 1000));
+
+/// Pause between batches when recompressing index keys, so the recompression
+/// doesn't hold up writes.
+pub static INDEX_KEY_RECOMPRESSION_BATCH_INTERVAL: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_millis(env_config("INDEX_KEY_RECOMPRESSION_BATCH_INTERVAL_MS", 50))
+});
+
+/// Fraction of user document reads whose time is recorded in
+/// `_document_access`, for reports of data that isn't read anymore. Zero
+/// disables tracking.
+pub static DOCUMENT_ACCESS_SAMPLE_RATE: LazyLock<f64> =
+    LazyLock::new(|| env_config("DOCUMENT_ACCESS_SAMPLE_RATE", 0.0));
+
+/// Maximum number of distinct documents whose sampled reads are buffered in
+/// memory between flushes. Reads of other documents aren't sampled until the
+/// next flush.
+pub static DOCUMENT_ACCESS_MAX_BUFFERED: LazyLock<usize> =
+    LazyLock::new(|| env_config("DOCUMENT_ACCESS_MAX_BUFFERED", 100_000));
+
+/// How often sampled document reads are written to `_document_access`.
+/// Samples since the last flush are lost if the backend stops.
+pub static DOCUMENT_ACCESS_FLUSH_INTERVAL: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(env_config("DOCUMENT_ACCESS_FLUSH_INTERVAL_SECS", 60)));
+
+/// A document's stored last read time is only updated once it's this much
+/// older than a new sample, which bounds how often each document's access
+/// record is rewritten.
+pub static DOCUMENT_ACCESS_RESOLUTION: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(env_config("DOCUMENT_ACCESS_RESOLUTION_SECS", 60 * 60)));
+
+/// How long session tokens exchanged for an admin key are valid if the caller
+/// doesn't ask for a shorter time.
+pub static ADMIN_SESSION_TOKEN_DEFAULT_TTL: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(env_config("ADMIN_SESSION_TOKEN_DEFAULT_TTL_SECS", 60 * 60))
+});
+
+/// The longest a session token exchanged for an admin key can be valid.
+pub static ADMIN_SESSION_TOKEN_MAX_TTL: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(env_config("ADMIN_SESSION_TOKEN_MAX_TTL_SECS", 24 * 60 * 60))
+});
+
+/// How often tables with an archival policy are checked for documents old
+/// enough to archive.
+pub static ARCHIVAL_INTERVAL: LazyLock<Duration> =
+    LazyLock::new(|| Duration::from_secs(env_config("ARCHIVAL_INTERVAL_SECS", 10 * 60)));
+
+/// Maximum number of documents written to each archived segment, and so
+/// deleted from their table in each transaction.
+pub static ARCHIVAL_SEGMENT_MAX_DOCUMENTS: LazyLock<usize> =
+    LazyLock::new(|| env_config("ARCHIVAL_SEGMENT_MAX_DOCUMENTS", 1000));
+
+/// Maximum number of documents counted into a backfilling materialized
+/// aggregate, or entries deleted from a deleted one, in each transaction.
+pub static MATERIALIZED_AGGREGATE_BATCH_SIZE: LazyLock<usize> =
+    LazyLock::new(|| env_config("MATERIALIZED_AGGREGATE_BATCH_SIZE", 500));
+
+/// Comma-separated capabilities granted to this deployment's functions.
+/// Custom native ops registered by an embedding build can only be called if
+/// their capability is listed here, so none can be called by default.
+pub static NATIVE_OP_CAPABILITIES: LazyLock<Vec<String>> = LazyLock::new(|| {
+    env_config("NATIVE_OP_CAPABILITIES", String::new())
+        .split(',')
+        .map(|capability| capability.trim().to_string())
+        .filter(|capability| !capability.is_empty())
+        .collect()
+});
+
+/// How long an auth provider's discovery document and signing keys are cached
+/// before they're fetched again.
+pub static AUTH_PROVIDER_REFRESH_INTERVAL: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(env_config("AUTH_PROVIDER_REFRESH_INTERVAL_SECS", 60 * 60))
+});
+
+/// The least time between fetches of an auth provider's signing keys, however
+/// many tokens arrive signed with keys we don't have.
+pub static AUTH_PROVIDER_MIN_REFRESH_INTERVAL: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(env_config("AUTH_PROVIDER_MIN_REFRESH_INTERVAL_SECS", 30))
+});
+
+/// Whether the deployment's first-party email/password and magic link auth
+/// endpoints are enabled.
+pub static FIRST_PARTY_AUTH_ENABLED: LazyLock<bool> =
+    LazyLock::new(|| env_config("FIRST_PARTY_AUTH_ENABLED", false));
+
+/// Whether new accounts can be created, by signing up or requesting a magic
+/// link for an email without an account. Existing accounts can still log in
+/// when this is off.
+pub static FIRST_PARTY_AUTH_SIGNUP_ENABLED: LazyLock<bool> =
+    LazyLock::new(|| env_config("FIRST_PARTY_AUTH_SIGNUP_ENABLED", true));
+
+/// How many times an hour each email can be used to log in, request a magic
+/// link or verify one, so passwords can't be guessed quickly and inboxes
+/// can't be flooded.
+pub static FIRST_PARTY_AUTH_ATTEMPTS_PER_EMAIL: LazyLock<u32> =
+    LazyLock::new(|| env_config("FIRST_PARTY_AUTH_ATTEMPTS_PER_EMAIL", 10));
+
+/// How many times an hour each client IP can log in, request a magic link or
+/// verify one, across all emails.
+pub static FIRST_PARTY_AUTH_ATTEMPTS_PER_IP: LazyLock<u32> =
+    LazyLock::new(|| env_config("FIRST_PARTY_AUTH_ATTEMPTS_PER_IP", 100));
+
+/// How long a first-party auth session lasts before the user must log in
+/// again.
+pub static FIRST_PARTY_AUTH_SESSION_TTL: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(env_config(
+        "FIRST_PARTY_AUTH_SESSION_TTL_SECS",
+        30 * 24 * 60 * 60,
+    ))
+});
+
+/// How long a magic link can be used for after it's sent.
+pub static FIRST_PARTY_AUTH_MAGIC_LINK_TTL: LazyLock<Duration> = LazyLock::new(|| {
+    Duration::from_secs(env_config("FIRST_PARTY_AUTH_MAGIC_LINK_TTL_SECS", 15 * 60))
+});
+
+/// The action scheduled to send a magic link. It's called with `{ email, token
+/// }` and is expected to email the user a link that passes the token to
+/// `/api/auth/magic_link/verify`.
+pub static FIRST_PARTY_AUTH_MAGIC_LINK_FUNCTION: LazyLock<String> = LazyLock::new(|| {
+    env_config(
+        "FIRST_PARTY_AUTH_MAGIC_LINK_FUNCTION",
+        String::from("auth:sendMagicLink"),
+    )
+});
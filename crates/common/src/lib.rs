@@ -39,6 +39,7 @@ pub mod document;
 pub mod errors;
 pub mod execution_context;
 pub mod ext;
+pub mod fault_injection;
 pub mod floating_point;
 pub mod grpc;
 pub mod heap_size;
@@ -110,6 +110,10 @@ pub enum PersistenceGlobalKey {
     IndexByIdIndex,
     /// Internal id of _index table, for bootstrapping.
     IndexTabletId,
+
+    /// Map from commit hook name to the timestamp of the last commit it has
+    /// acknowledged.
+    CommitHookCheckpoints,
 }
 
 impl From<PersistenceGlobalKey> for String {
@@ -132,6 +136,7 @@ impl From<PersistenceGlobalKey> for String {
             // NB: For compatibility, these are referred to as "table_id"s, not "tablet_id"s.
             PersistenceGlobalKey::TablesTabletId => "tables_table_id".to_string(),
             PersistenceGlobalKey::IndexTabletId => "index_table_id".to_string(),
+            PersistenceGlobalKey::CommitHookCheckpoints => "commit_hook_checkpoints".to_string(),
         }
     }
 }
@@ -150,6 +155,7 @@ impl FromStr for PersistenceGlobalKey {
             "tables_table_id" => Ok(Self::TablesTabletId),
             "index_by_id" => Ok(Self::IndexByIdIndex),
             "index_table_id" => Ok(Self::IndexTabletId),
+            "commit_hook_checkpoints" => Ok(Self::CommitHookCheckpoints),
             _ => anyhow::bail!("unrecognized persistence global key"),
         }
     }
@@ -25,10 +25,12 @@ use sha2::{
 use value::{
     heap_size::HeapSize,
     id_v6::DeveloperDocumentId,
+    sorting::split_trailing_id,
     utils::display_sequence,
     val,
     ConvexObject,
     ConvexValue,
+    TableNumber,
     TabletId,
 };
 
@@ -45,6 +47,7 @@ use crate::{
     paths::FieldPath,
     types::{
         GenericIndexName,
+        IndexDescriptor,
         IndexName,
         MaybeValue,
         TableName,
@@ -70,6 +73,30 @@ pub enum CursorPosition {
     End,
 }
 
+impl CursorPosition {
+    /// Moves the position to the same document in a table with a different
+    /// number. Index keys end with the document's ID, which includes its
+    /// table number, so a position from before a snapshot import changed the
+    /// table's number would otherwise sort in the wrong place.
+    ///
+    /// Index values that are IDs of other tables aren't changed.
+    pub fn with_table_number(self, table_number: TableNumber) -> anyhow::Result<Self> {
+        let CursorPosition::After(key) = self else {
+            return Ok(self);
+        };
+        let (values, id) = split_trailing_id(&key).ok_or_else(|| {
+            anyhow::anyhow!(ErrorMetadata::bad_request(
+                "InvalidCursor",
+                "Cursor position doesn't end with a document ID"
+            ))
+        })?;
+        let mut bytes = values.to_vec();
+        ConvexValue::from(DeveloperDocumentId::new(table_number, id.internal_id()))
+            .write_sort_key(&mut bytes)?;
+        Ok(CursorPosition::After(IndexKeyBytes(bytes)))
+    }
+}
+
 impl HeapSize for CursorPosition {
     fn heap_size(&self) -> usize {
         match self {
@@ -86,6 +113,28 @@ pub struct Cursor {
 
     /// Hashed representation of the query this cursor refers to.
     pub query_fingerprint: QueryFingerprint,
+
+    /// The number of the table whose IDs are in `position` when the cursor
+    /// was issued. Cursors from before this was recorded don't have one.
+    pub table_number: Option<TableNumber>,
+}
+
+impl Cursor {
+    /// Returns the cursor for the same position in its table, which now has
+    /// `table_number`.
+    pub fn for_table(self, table_number: Option<TableNumber>) -> anyhow::Result<Self> {
+        let position = match (self.table_number, table_number) {
+            (Some(issued), Some(current)) if issued != current => {
+                self.position.with_table_number(current)?
+            },
+            _ => self.position,
+        };
+        Ok(Self {
+            position,
+            query_fingerprint: self.query_fingerprint,
+            table_number: table_number.or(self.table_number),
+        })
+    }
 }
 
 impl From<Cursor> for pb::convex_cursor::Cursor {
@@ -93,6 +142,7 @@ impl From<Cursor> for pb::convex_cursor::Cursor {
         Cursor {
             position,
             query_fingerprint,
+            table_number,
         }: Cursor,
     ) -> Self {
         let position = match position {
@@ -104,6 +154,7 @@ impl From<Cursor> for pb::convex_cursor::Cursor {
         Self {
             position: Some(position),
             query_fingerprint: Some(query_fingerprint),
+            table_number: table_number.map(u32::from),
         }
     }
 }
@@ -115,6 +166,7 @@ impl TryFrom<pb::convex_cursor::Cursor> for Cursor {
         pb::convex_cursor::Cursor {
             position,
             query_fingerprint,
+            table_number,
         }: pb::convex_cursor::Cursor,
     ) -> anyhow::Result<Self> {
         let position = position.ok_or_else(|| anyhow::anyhow!("Cursor is missing position"))?;
@@ -128,13 +180,16 @@ impl TryFrom<pb::convex_cursor::Cursor> for Cursor {
             position,
             query_fingerprint: query_fingerprint
                 .ok_or_else(|| anyhow::anyhow!("Missing query_fingerprint"))?,
+            table_number: table_number.map(TableNumber::try_from).transpose()?,
         })
     }
 }
 
 impl HeapSize for Cursor {
     fn heap_size(&self) -> usize {
-        self.position.heap_size() + self.query_fingerprint.heap_size()
+        self.position.heap_size()
+            + self.query_fingerprint.heap_size()
+            + self.table_number.heap_size()
     }
 }
 
@@ -568,10 +623,31 @@ pub fn search_value_to_bytes(value: Option<&ConvexValue>) -> Vec<u8> {
     }
 }
 
+/// The most typos a search query can allow in each word.
+pub const MAX_SEARCH_EDIT_DISTANCE: u8 = 2;
+
+/// How a search filter's text is matched against the indexed words.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct TextSearchOptions {
+    /// Also match words that start with the query's last word, so results
+    /// show up while the user is still typing it.
+    pub prefix: bool,
+    /// The most typos (insertions, deletions or substitutions) allowed in
+    /// each word of the query. Short words allow fewer: words of up to four
+    /// characters must match exactly, and words of up to eight allow one
+    /// typo.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=MAX_SEARCH_EDIT_DISTANCE")
+    )]
+    pub max_edit_distance: u8,
+}
+
 /// Filters to apply while querying a search index.
 #[derive(Clone, Debug, PartialEq)]
 pub enum SearchFilterExpression {
-    Search(FieldPath, String),
+    Search(FieldPath, String, TextSearchOptions),
     Eq(FieldPath, Option<ConvexValue>),
 }
 
@@ -579,14 +655,16 @@ pub enum SearchFilterExpression {
 #[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
 pub enum InternalSearchFilterExpression {
-    Search(FieldPath, String),
+    Search(FieldPath, String, TextSearchOptions),
     Eq(FieldPath, Vec<u8>),
 }
 
 impl SearchFilterExpression {
     pub fn to_internal(self) -> anyhow::Result<InternalSearchFilterExpression> {
         let expression = match self {
-            Self::Search(field, s) => InternalSearchFilterExpression::Search(field, s),
+            Self::Search(field, s, options) => {
+                InternalSearchFilterExpression::Search(field, s, options)
+            },
             Self::Eq(field, v) => {
                 InternalSearchFilterExpression::Eq(field, search_value_to_bytes(v.as_ref()))
             },
@@ -659,6 +737,7 @@ mod proptest {
         IndexRange,
         MaybeValue,
         Query,
+        QueryHints,
         QuerySource,
         Search,
     };
@@ -670,6 +749,7 @@ mod proptest {
             Order,
             QueryOperator,
             SearchFilterExpression,
+            TextSearchOptions,
         },
         types::IndexName,
     };
@@ -702,8 +782,11 @@ mod proptest {
 
         fn arbitrary_with(_args: Self::Parameters) -> Self::Strategy {
             prop_oneof![
-                any::<(FieldPath, String)>()
-                    .prop_map(|(field_path, s)| SearchFilterExpression::Search(field_path, s)),
+                any::<(FieldPath, String, TextSearchOptions)>().prop_map(
+                    |(field_path, s, options)| SearchFilterExpression::Search(
+                        field_path, s, options
+                    )
+                ),
                 any::<(FieldPath, Option<ConvexValue>)>()
                     .prop_map(|(field_path, v)| SearchFilterExpression::Eq(field_path, v)),
             ]
@@ -839,8 +922,13 @@ mod proptest {
             (
                 any::<QuerySource>(),
                 prop::collection::vec(any::<QueryOperator>(), 0..4),
+                any::<QueryHints>(),
             )
-                .prop_map(|(source, operators)| Query { source, operators })
+                .prop_map(|(source, operators, hints)| Query {
+                    source,
+                    operators,
+                    hints,
+                })
         }
     }
 }
@@ -1018,6 +1106,26 @@ pub enum QueryOperator {
     Limit(usize),
 }
 
+/// Hints from the developer that override how a query is planned, for when
+/// the default plan is a poor fit for the shape of the data.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct QueryHints {
+    /// Walk this index of the table instead of scanning it by creation time.
+    /// Only valid for queries that don't specify an index themselves.
+    pub index: Option<IndexDescriptor>,
+    /// Don't narrow the index range with the query's equality filters.
+    pub disable_filter_pushdown: bool,
+    /// Fail the query if it reads more than this many documents.
+    pub scan_budget: Option<usize>,
+}
+
+impl QueryHints {
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
 /// A query, represented as a source and a chain of operators to apply as a lazy
 /// iteration.
 #[derive(Clone, Debug, PartialEq)]
@@ -1026,6 +1134,7 @@ pub struct Query {
     pub source: QuerySource,
     /// The list of operators to apply in order.
     pub operators: Vec<QueryOperator>,
+    pub hints: QueryHints,
 }
 
 impl Query {
@@ -1034,6 +1143,7 @@ impl Query {
         Self {
             source: QuerySource::FullTableScan(FullTableScan { table_name, order }),
             operators: vec![],
+            hints: QueryHints::default(),
         }
     }
 
@@ -1042,6 +1152,7 @@ impl Query {
         Self {
             source: QuerySource::IndexRange(index_range),
             operators: vec![],
+            hints: QueryHints::default(),
         }
     }
 
@@ -1060,6 +1171,7 @@ impl Query {
         Self {
             source: QuerySource::Search(search),
             operators: vec![],
+            hints: QueryHints::default(),
         }
     }
 
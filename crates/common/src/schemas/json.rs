@@ -43,7 +43,11 @@ use crate::{
             search_field_not_unique,
             vector_field_not_unique,
         },
-        vector_index::VectorDimensions,
+        text_index::TextIndexAnalyzer,
+        vector_index::{
+            VectorDimensions,
+            VectorDistanceMetric,
+        },
     },
     json::invalid_json,
     schemas::{
@@ -363,6 +367,10 @@ struct VectorIndexSchemaJson {
     dimensions: Option<u32>,
     dimension: Option<u32>,
     filter_fields: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    distance: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    embed_from: Option<String>,
 }
 
 impl TryFrom<JsonValue> for VectorIndexSchema {
@@ -391,7 +399,27 @@ impl TryFrom<JsonValue> for VectorIndexSchema {
                 None => anyhow::bail!("Missing dimensions field"),
             },
         };
-        Self::new(index_descriptor, vector_field, dimension, filter_fields)
+        let distance = j
+            .distance
+            .map(|distance| VectorDistanceMetric::parse(&distance))
+            .transpose()?
+            .unwrap_or_default();
+        let embed_from = j
+            .embed_from
+            .map(|f| {
+                f.parse().with_context(|| {
+                    index_validation_error::invalid_index_field(&index_descriptor, &f)
+                })
+            })
+            .transpose()?;
+        Ok(Self::new(
+            index_descriptor,
+            vector_field,
+            dimension,
+            filter_fields,
+            distance,
+        )?
+        .with_embed_from(embed_from))
     }
 }
 
@@ -404,6 +432,8 @@ impl TryFrom<VectorIndexSchema> for JsonValue {
             vector_field,
             dimension,
             filter_fields,
+            distance,
+            embed_from,
             ..
         }: VectorIndexSchema,
     ) -> anyhow::Result<Self> {
@@ -416,6 +446,8 @@ impl TryFrom<VectorIndexSchema> for JsonValue {
                 .into_iter()
                 .map(String::from)
                 .collect::<Vec<_>>(),
+            distance: (!distance.is_default()).then(|| distance.to_string()),
+            embed_from: embed_from.map(String::from),
         };
         Ok(serde_json::to_value(vector_index_schema_json)?)
     }
@@ -427,6 +459,8 @@ struct SearchIndexSchemaJson {
     index_descriptor: String,
     search_field: String,
     filter_fields: BTreeSet<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    analyzer: Option<String>,
 }
 
 impl TryFrom<JsonValue> for SearchIndexSchema {
@@ -447,8 +481,13 @@ impl TryFrom<JsonValue> for SearchIndexSchema {
                 })
             })
             .collect::<anyhow::Result<BTreeSet<_>>>()?;
+        let analyzer = j
+            .analyzer
+            .map(|analyzer| TextIndexAnalyzer::parse(&analyzer))
+            .transpose()?
+            .unwrap_or_default();
 
-        Self::new(index_descriptor, search_field, filter_fields)
+        Self::new(index_descriptor, search_field, filter_fields, analyzer)
     }
 }
 
@@ -460,6 +499,7 @@ impl TryFrom<SearchIndexSchema> for JsonValue {
             index_descriptor,
             search_field,
             filter_fields,
+            analyzer,
             ..
         }: SearchIndexSchema,
     ) -> anyhow::Result<Self> {
@@ -470,6 +510,7 @@ impl TryFrom<SearchIndexSchema> for JsonValue {
                 .into_iter()
                 .map(String::from)
                 .collect::<BTreeSet<_>>(),
+            analyzer: (!analyzer.is_default()).then(|| analyzer.to_string()),
         };
         Ok(serde_json::to_value(search_index_json)?)
     }
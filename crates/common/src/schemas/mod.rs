@@ -41,7 +41,11 @@ use crate::{
     bootstrap_model::index::{
         database_index::IndexedFields,
         index_validation_error,
-        vector_index::VectorDimensions,
+        text_index::TextIndexAnalyzer,
+        vector_index::{
+            VectorDimensions,
+            VectorDistanceMetric,
+        },
         MAX_TEXT_INDEX_FILTER_FIELDS_SIZE,
         MAX_VECTOR_INDEX_FILTER_FIELDS_SIZE,
     },
@@ -248,6 +252,7 @@ macro_rules! db_schema_with_vector_indexes {
                                 value::FieldPath::from_str($vector_field)?,
                                 1536u32.try_into()?,
                                 Default::default(),
+                                Default::default(),
                             )?,
                         );
                     )*
@@ -661,6 +666,7 @@ pub struct SearchIndexSchema {
         proptest(strategy = "prop::collection::btree_set(any::<FieldPath>(), 0..8)")
     )]
     pub filter_fields: BTreeSet<FieldPath>,
+    pub analyzer: TextIndexAnalyzer,
 
     // Private field to force all creations to go through the constructor.
     _pd: PhantomData<()>,
@@ -671,6 +677,7 @@ impl SearchIndexSchema {
         index_descriptor: IndexDescriptor,
         search_field: FieldPath,
         filter_fields: BTreeSet<FieldPath>,
+        analyzer: TextIndexAnalyzer,
     ) -> anyhow::Result<Self> {
         if filter_fields.len() > MAX_TEXT_INDEX_FILTER_FIELDS_SIZE {
             anyhow::bail!(index_validation_error::too_many_filter_fields(
@@ -681,6 +688,7 @@ impl SearchIndexSchema {
             index_descriptor,
             search_field,
             filter_fields,
+            analyzer,
             _pd: PhantomData,
         })
     }
@@ -697,6 +705,10 @@ pub struct VectorIndexSchema {
         proptest(strategy = "prop::collection::btree_set(any::<FieldPath>(), 0..8)")
     )]
     pub filter_fields: BTreeSet<FieldPath>,
+    pub distance: VectorDistanceMetric,
+    /// A text field to generate `vector_field` from with the deployment's
+    /// embedding provider whenever it changes.
+    pub embed_from: Option<FieldPath>,
 
     // Private field to force all creations to go through the constructor.
     _pd: PhantomData<()>,
@@ -708,6 +720,7 @@ impl VectorIndexSchema {
         vector_field: FieldPath,
         dimension: VectorDimensions,
         filter_fields: BTreeSet<FieldPath>,
+        distance: VectorDistanceMetric,
     ) -> anyhow::Result<Self> {
         if filter_fields.len() > MAX_VECTOR_INDEX_FILTER_FIELDS_SIZE {
             anyhow::bail!(index_validation_error::too_many_filter_fields(
@@ -719,9 +732,16 @@ impl VectorIndexSchema {
             vector_field,
             dimension,
             filter_fields,
+            distance,
+            embed_from: None,
             _pd: PhantomData,
         })
     }
+
+    pub fn with_embed_from(mut self, embed_from: Option<FieldPath>) -> Self {
+        self.embed_from = embed_from;
+        self
+    }
 }
 
 /// [`DocumentSchema`] corresponds to the `DocumentSchema` TS type in
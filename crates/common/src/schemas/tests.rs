@@ -14,6 +14,10 @@ use value::{
 };
 
 use crate::{
+    bootstrap_model::index::{
+        text_index::TextIndexAnalyzer,
+        vector_index::VectorDistanceMetric,
+    },
     db_schema_with_vector_indexes,
     object_validator,
     schemas::{
@@ -656,6 +660,119 @@ fn test_json_backwards_compatibility() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_search_index_analyzer() -> anyhow::Result<()> {
+    let schema_json = |analyzer: Option<&str>| {
+        json!({
+            "tables": [
+                {
+                    "tableName": "testTable",
+                    "indexes": [],
+                    "searchIndexes": [
+                        {
+                            "indexDescriptor": "by_body",
+                            "searchField": "body",
+                            "filterFields": [],
+                            "analyzer": analyzer,
+                        },
+                    ],
+                },
+            ],
+        })
+    };
+    let analyzer = |schema: DatabaseSchema| {
+        let table = schema.tables.into_values().next().unwrap();
+        table.search_indexes.into_values().next().unwrap().analyzer
+    };
+
+    let schema = DatabaseSchema::try_from(schema_json(None))?;
+    assert_eq!(analyzer(schema), TextIndexAnalyzer::Simple);
+
+    let schema = DatabaseSchema::try_from(schema_json(Some("english")))?;
+    assert_eq!(analyzer(schema.clone()), TextIndexAnalyzer::English);
+    let json = JsonValue::try_from(schema)?;
+    assert_eq!(
+        json["tables"][0]["searchIndexes"][0]["analyzer"],
+        json!("english")
+    );
+
+    let error = DatabaseSchema::try_from(schema_json(Some("klingon"))).unwrap_err();
+    assert!(error.to_string().contains("Unknown search index analyzer"));
+    Ok(())
+}
+
+#[test]
+fn test_vector_index_distance() -> anyhow::Result<()> {
+    let schema_json = |distance: Option<&str>| {
+        json!({
+            "tables": [
+                {
+                    "tableName": "testTable",
+                    "indexes": [],
+                    "vectorIndexes": [
+                        {
+                            "indexDescriptor": "by_embedding",
+                            "vectorField": "embedding",
+                            "dimensions": 1536,
+                            "filterFields": [],
+                            "distance": distance,
+                        },
+                    ],
+                },
+            ],
+        })
+    };
+    let distance = |schema: DatabaseSchema| {
+        let table = schema.tables.into_values().next().unwrap();
+        table.vector_indexes.into_values().next().unwrap().distance
+    };
+
+    let schema = DatabaseSchema::try_from(schema_json(None))?;
+    assert_eq!(distance(schema), VectorDistanceMetric::Cosine);
+
+    let schema = DatabaseSchema::try_from(schema_json(Some("dotProduct")))?;
+    assert_eq!(distance(schema.clone()), VectorDistanceMetric::DotProduct);
+    let json = JsonValue::try_from(schema)?;
+    assert_eq!(
+        json["tables"][0]["vectorIndexes"][0]["distance"],
+        json!("dotProduct")
+    );
+
+    let error = DatabaseSchema::try_from(schema_json(Some("manhattan"))).unwrap_err();
+    assert!(error.to_string().contains("Unknown vector index distance"));
+    Ok(())
+}
+
+#[test]
+fn test_vector_index_embed_from() -> anyhow::Result<()> {
+    let schema = DatabaseSchema::try_from(json!({
+        "tables": [
+            {
+                "tableName": "testTable",
+                "indexes": [],
+                "vectorIndexes": [
+                    {
+                        "indexDescriptor": "by_embedding",
+                        "vectorField": "embedding",
+                        "dimensions": 1536,
+                        "filterFields": [],
+                        "embedFrom": "body",
+                    },
+                ],
+            },
+        ],
+    }))?;
+    let table = schema.tables.values().next().unwrap();
+    let index = table.vector_indexes.values().next().unwrap();
+    assert_eq!(index.embed_from, Some("body".parse()?));
+    let json = JsonValue::try_from(schema)?;
+    assert_eq!(
+        json["tables"][0]["vectorIndexes"][0]["embedFrom"],
+        json!("body")
+    );
+    Ok(())
+}
+
 fn empty_table_mapping() -> NamespacedTableMapping {
     TableMapping::new().namespace(TableNamespace::test_user())
 }
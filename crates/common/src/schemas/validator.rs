@@ -1008,6 +1008,10 @@ impl FieldValidator {
         &self.validator
     }
 
+    pub fn is_optional(&self) -> bool {
+        self.optional
+    }
+
     pub fn required_field_type(validator: Validator) -> Self {
         Self {
             validator,
@@ -27,6 +27,9 @@ pub enum RepeatableReason {
     /// only in tests
     #[cfg(any(test, feature = "testing"))]
     TestOnly,
+    /// ts is the snapshot timestamp of a snapshot export, which was
+    /// repeatable in the deployment it was exported from.
+    SnapshotExport,
     /// only in db-info tool, and only when
     /// non-repeatable reads are directly requested.
     DbInfoManuallyRequested,
@@ -135,6 +135,7 @@ impl LocalSyncState {
             args: vec![Value::Object(args.clone()).into()],
             journal: None,
             component_path: None,
+            server_args: None,
         });
         let message = ClientMessage::ModifyQuerySet {
             base_version,
@@ -234,6 +235,7 @@ impl LocalSyncState {
                 args: vec![Value::Object(local_query.args.clone()).into()],
                 journal: None,
                 component_path: None,
+                server_args: None,
             });
             modifications.push(add)
         }
@@ -326,6 +328,11 @@ impl RemoteQuerySet {
                 StateModification::QueryRemoved { query_id } => {
                     self.remote_query_set.remove(&query_id);
                 },
+                // This client doesn't negotiate patches, so the server never
+                // sends them.
+                StateModification::QueryPatched { .. } => {
+                    return Err("UnexpectedQueryPatch".into());
+                },
             }
         }
         self.version = end_version;
@@ -515,6 +522,7 @@ impl BaseConvexClient {
             udf_path,
             args: vec![Value::Object(args).into()],
             component_path: None,
+            mutation_id: None,
         };
 
         let result_receiver = self.request_manager.track_request(
@@ -657,6 +665,13 @@ impl BaseConvexClient {
             ServerMessage::Ping => {
                 // Do nothing
             },
+            ServerMessage::Connected { .. } => {
+                // This client doesn't resume sessions, so it doesn't ask for
+                // this.
+            },
+            ServerMessage::AggregateUpdated { .. } | ServerMessage::AggregateFailed { .. } => {
+                // This client never subscribes to aggregates.
+            },
         }
         Ok(None)
     }
@@ -586,6 +586,7 @@ pub mod tests {
                 udf_path: UdfPath::from_str("incrementCounter")?,
                 args: vec![json!({})],
                 component_path: None,
+                mutation_id: None,
             }]
         );
 
@@ -719,6 +720,8 @@ pub mod tests {
                     connection_count: 0,
                     last_close_reason: "InitialConnect".to_string(),
                     max_observed_timestamp: None,
+                    protocol_version: 0,
+                    resume: None,
                 },
                 ClientMessage::ModifyQuerySet {
                     base_version: 0,
@@ -729,6 +732,7 @@ pub mod tests {
                         args: vec![json!({})],
                         journal: None,
                         component_path: None,
+                        server_args: None,
                     })]
                 },
             ]
@@ -852,6 +856,8 @@ pub mod tests {
                     connection_count: 0,
                     last_close_reason: "InitialConnect".to_string(),
                     max_observed_timestamp: None,
+                    protocol_version: 0,
+                    resume: None,
                 },
                 ClientMessage::ModifyQuerySet {
                     base_version: 0,
@@ -862,6 +868,7 @@ pub mod tests {
                         args: vec![json!({})],
                         journal: None,
                         component_path: None,
+                        server_args: None,
                     })]
                 },
                 ClientMessage::ModifyQuerySet {
@@ -873,6 +880,7 @@ pub mod tests {
                         args: vec![json!({})],
                         journal: None,
                         component_path: None,
+                        server_args: None,
                     })]
                 },
                 ClientMessage::ModifyQuerySet {
@@ -884,6 +892,7 @@ pub mod tests {
                         args: vec![json!({"hello": "world"})],
                         journal: None,
                         component_path: None,
+                        server_args: None,
                     })]
                 },
             ]
@@ -912,6 +921,8 @@ pub mod tests {
                     connection_count: 0,
                     last_close_reason: "InitialConnect".to_string(),
                     max_observed_timestamp: None,
+                    protocol_version: 0,
+                    resume: None,
                 },
                 ClientMessage::ModifyQuerySet {
                     base_version: 0,
@@ -922,6 +933,7 @@ pub mod tests {
                         args: vec![json!({})],
                         journal: None,
                         component_path: None,
+                        server_args: None,
                     })]
                 },
             ]
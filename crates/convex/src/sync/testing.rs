@@ -83,6 +83,8 @@ impl SyncProtocol for TestProtocolManager {
                 connection_count,
                 last_close_reason: "InitialConnect".to_string(),
                 max_observed_timestamp: None,
+                protocol_version: 0,
+                resume: None,
             })
             .await?;
 
@@ -342,6 +342,10 @@ impl WebSocketInternal {
             connection_count,
             last_close_reason,
             max_observed_timestamp,
+            // This client doesn't apply patches, so it's always sent full query
+            // results.
+            protocol_version: 0,
+            resume: None,
         };
         let msg = Message::Text(
             serde_json::Value::try_from(message)
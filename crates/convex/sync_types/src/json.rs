@@ -16,13 +16,18 @@ use serde_json::{
 
 use crate::{
     types::{
+        Aggregate,
+        AggregateId,
+        AggregateSetModification,
         ClientEvent,
         ErrorPayload,
+        SessionResume,
     },
     AuthenticationToken,
     ClientMessage,
     IdentityVersion,
     LogLinesMessage,
+    PatchOperation,
     Query,
     QueryId,
     QuerySetModification,
@@ -82,6 +87,9 @@ struct QueryJson {
 
     #[serde(skip_serializing_if = "Option::is_none")]
     component_path: Option<String>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    server_args: Option<BTreeMap<String, String>>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -106,6 +114,7 @@ impl TryFrom<QuerySetModification> for JsonValue {
                     args: JsonValue::from(q.args),
                     journal: q.journal,
                     component_path: q.component_path,
+                    server_args: q.server_args,
                 };
                 QuerySetModificationJson::Add(query_json)
             },
@@ -132,6 +141,7 @@ impl TryFrom<JsonValue> for QuerySetModification {
                     args,
                     journal: q.journal,
                     component_path: q.component_path,
+                    server_args: q.server_args,
                 };
                 QuerySetModification::Add(query)
             },
@@ -143,6 +153,73 @@ impl TryFrom<JsonValue> for QuerySetModification {
     }
 }
 
+#[derive(Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AggregateJson {
+    aggregate_id: AggregateId,
+    index_name: String,
+    range: Vec<JsonValue>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sum_field: Option<String>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    component_path: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+#[serde(tag = "type")]
+enum AggregateSetModificationJson {
+    Add(AggregateJson),
+    #[serde(rename_all = "camelCase")]
+    Remove {
+        aggregate_id: AggregateId,
+    },
+}
+
+impl TryFrom<AggregateSetModification> for JsonValue {
+    type Error = anyhow::Error;
+
+    fn try_from(m: AggregateSetModification) -> Result<Self, Self::Error> {
+        let modification_json = match m {
+            AggregateSetModification::Add(a) => AggregateSetModificationJson::Add(AggregateJson {
+                aggregate_id: a.aggregate_id,
+                index_name: a.index_name,
+                range: a.range,
+                sum_field: a.sum_field,
+                component_path: a.component_path,
+            }),
+            AggregateSetModification::Remove { aggregate_id } => {
+                AggregateSetModificationJson::Remove { aggregate_id }
+            },
+        };
+        Ok(serde_json::to_value(modification_json)?)
+    }
+}
+
+impl TryFrom<JsonValue> for AggregateSetModification {
+    type Error = anyhow::Error;
+
+    fn try_from(value: JsonValue) -> Result<Self, Self::Error> {
+        let m: AggregateSetModificationJson = serde_json::from_value(value)?;
+        let result = match m {
+            AggregateSetModificationJson::Add(a) => AggregateSetModification::Add(Aggregate {
+                aggregate_id: a.aggregate_id,
+                index_name: a.index_name,
+                range: a.range,
+                sum_field: a.sum_field,
+                component_path: a.component_path,
+            }),
+            AggregateSetModificationJson::Remove { aggregate_id } => {
+                AggregateSetModification::Remove { aggregate_id }
+            },
+        };
+        Ok(result)
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(tag = "tokenType")]
 enum AuthenticationTokenJson {
@@ -158,6 +235,39 @@ enum AuthenticationTokenJson {
     None,
 }
 
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+struct SessionResumeJson {
+    session_token: String,
+    state_version: JsonValue,
+    query_set_version: u32,
+    identity_version: u32,
+}
+
+impl From<SessionResume> for SessionResumeJson {
+    fn from(resume: SessionResume) -> Self {
+        Self {
+            session_token: resume.session_token,
+            state_version: resume.state_version.into(),
+            query_set_version: resume.query_set_version,
+            identity_version: resume.identity_version,
+        }
+    }
+}
+
+impl TryFrom<SessionResumeJson> for SessionResume {
+    type Error = anyhow::Error;
+
+    fn try_from(resume: SessionResumeJson) -> Result<Self, Self::Error> {
+        Ok(Self {
+            session_token: resume.session_token,
+            state_version: resume.state_version.try_into()?,
+            query_set_version: resume.query_set_version,
+            identity_version: resume.identity_version,
+        })
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug)]
 #[serde(tag = "type")]
 enum ClientMessageJson {
@@ -173,6 +283,13 @@ enum ClientMessageJson {
         #[serde(default)]
         #[serde(skip_serializing_if = "Option::is_none")]
         max_observed_timestamp: Option<String>,
+
+        #[serde(default)]
+        protocol_version: u32,
+
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        resume: Option<SessionResumeJson>,
     },
     #[serde(rename_all = "camelCase")]
     ModifyQuerySet {
@@ -187,6 +304,9 @@ enum ClientMessageJson {
         args: JsonValue,
         #[serde(skip_serializing_if = "Option::is_none")]
         component_path: Option<String>,
+        #[serde(default)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        mutation_id: Option<String>,
     },
     #[serde(rename_all = "camelCase")]
     Action {
@@ -207,6 +327,9 @@ enum ClientMessageJson {
         event_type: String,
         event: JsonValue,
     },
+    ModifyAggregateSet {
+        modifications: Vec<JsonValue>,
+    },
 }
 
 impl TryFrom<ClientMessage> for JsonValue {
@@ -219,11 +342,15 @@ impl TryFrom<ClientMessage> for JsonValue {
                 connection_count,
                 last_close_reason,
                 max_observed_timestamp,
+                protocol_version,
+                resume,
             } => ClientMessageJson::Connect {
                 session_id: format!("{}", session_id.as_hyphenated()),
                 connection_count,
                 last_close_reason: Some(last_close_reason),
                 max_observed_timestamp: max_observed_timestamp.map(|ts| u64_to_string(ts.into())),
+                protocol_version,
+                resume: resume.map(SessionResumeJson::from),
             },
             ClientMessage::ModifyQuerySet {
                 base_version,
@@ -242,11 +369,13 @@ impl TryFrom<ClientMessage> for JsonValue {
                 udf_path,
                 args,
                 component_path,
+                mutation_id,
             } => ClientMessageJson::Mutation {
                 request_id,
                 udf_path: String::from(udf_path),
                 args: JsonValue::Array(args.into_iter().map(JsonValue::from).collect::<Vec<_>>()),
                 component_path,
+                mutation_id,
             },
             ClientMessage::Action {
                 request_id,
@@ -286,6 +415,14 @@ impl TryFrom<ClientMessage> for JsonValue {
             ClientMessage::Event(ClientEvent { event_type, event }) => {
                 ClientMessageJson::Event { event_type, event }
             },
+            ClientMessage::ModifyAggregateSet { modifications } => {
+                ClientMessageJson::ModifyAggregateSet {
+                    modifications: modifications
+                        .into_iter()
+                        .map(JsonValue::try_from)
+                        .collect::<anyhow::Result<Vec<_>>>()?,
+                }
+            },
         };
         let result = serde_json::to_value(s)?;
         Ok(result)
@@ -303,6 +440,8 @@ impl TryFrom<JsonValue> for ClientMessage {
                 connection_count,
                 last_close_reason,
                 max_observed_timestamp,
+                protocol_version,
+                resume,
             } => ClientMessage::Connect {
                 session_id: session_id.parse()?,
                 connection_count,
@@ -312,6 +451,8 @@ impl TryFrom<JsonValue> for ClientMessage {
                     .transpose()?
                     .map(Timestamp::try_from)
                     .transpose()?,
+                protocol_version,
+                resume: resume.map(SessionResume::try_from).transpose()?,
             },
             ClientMessageJson::ModifyQuerySet {
                 base_version,
@@ -330,6 +471,7 @@ impl TryFrom<JsonValue> for ClientMessage {
                 udf_path,
                 args,
                 component_path,
+                mutation_id,
             } => {
                 let json_args: Vec<JsonValue> = serde_json::from_value(args)?;
                 ClientMessage::Mutation {
@@ -337,6 +479,7 @@ impl TryFrom<JsonValue> for ClientMessage {
                     udf_path: udf_path.parse()?,
                     args: json_args,
                     component_path,
+                    mutation_id,
                 }
             },
             ClientMessageJson::Action {
@@ -372,6 +515,14 @@ impl TryFrom<JsonValue> for ClientMessage {
             ClientMessageJson::Event { event_type, event } => {
                 ClientMessage::Event(ClientEvent { event_type, event })
             },
+            ClientMessageJson::ModifyAggregateSet { modifications } => {
+                ClientMessage::ModifyAggregateSet {
+                    modifications: modifications
+                        .into_iter()
+                        .map(AggregateSetModification::try_from)
+                        .collect::<anyhow::Result<_>>()?,
+                }
+            },
         };
         Ok(result)
     }
@@ -425,6 +576,18 @@ impl<V: Into<JsonValue>> From<StateModification<V>> for JsonValue {
                     "journal": journal
                 })
             },
+            StateModification::QueryPatched {
+                query_id,
+                patch,
+                log_lines,
+                journal,
+            } => json!({
+                "type": "QueryPatched",
+                "queryId": query_id,
+                "patch": patch,
+                "logLines": log_lines,
+                "journal": journal
+            }),
             StateModification::QueryFailed {
                 query_id,
                 error_message,
@@ -469,6 +632,13 @@ impl<V: TryFrom<JsonValue, Error = anyhow::Error>> TryFrom<JsonValue> for StateM
                 journal: SerializedQueryJournal,
             },
             #[serde(rename_all = "camelCase")]
+            QueryPatched {
+                query_id: QueryId,
+                patch: Vec<PatchOperation>,
+                log_lines: LogLinesMessage,
+                journal: SerializedQueryJournal,
+            },
+            #[serde(rename_all = "camelCase")]
             QueryFailed {
                 query_id: QueryId,
                 error_message: String,
@@ -493,6 +663,17 @@ impl<V: TryFrom<JsonValue, Error = anyhow::Error>> TryFrom<JsonValue> for StateM
                 log_lines,
                 journal,
             },
+            StateModificationJson::QueryPatched {
+                query_id,
+                patch,
+                log_lines,
+                journal,
+            } => StateModification::QueryPatched {
+                query_id,
+                patch,
+                log_lines,
+                journal,
+            },
             StateModificationJson::QueryFailed {
                 query_id,
                 error_message,
@@ -610,6 +791,39 @@ impl<V: Into<JsonValue>> From<ServerMessage<V>> for JsonValue {
             ServerMessage::Ping {} => json!({
                 "type": "Ping"
             }),
+            ServerMessage::Connected {
+                session_token,
+                resumed,
+            } => json!({
+                "type": "Connected",
+                "sessionToken": session_token,
+                "resumed": resumed,
+            }),
+            ServerMessage::AggregateUpdated {
+                aggregate_id,
+                ts,
+                count,
+                sum,
+            } => {
+                let mut response = json!({
+                    "type": "AggregateUpdated",
+                    "aggregateId": aggregate_id,
+                    "ts": u64_to_string(ts.into()),
+                    "count": count,
+                });
+                if let Some(sum) = sum {
+                    response["sum"] = sum.into();
+                }
+                response
+            },
+            ServerMessage::AggregateFailed {
+                aggregate_id,
+                error_message,
+            } => json!({
+                "type": "AggregateFailed",
+                "aggregateId": aggregate_id,
+                "error": error_message,
+            }),
         }
     }
 }
@@ -655,6 +869,24 @@ impl<V: TryFrom<JsonValue, Error = anyhow::Error>> TryFrom<JsonValue> for Server
             },
             #[serde(rename_all = "camelCase")]
             Ping {},
+            #[serde(rename_all = "camelCase")]
+            Connected {
+                session_token: String,
+                resumed: bool,
+            },
+            #[serde(rename_all = "camelCase")]
+            AggregateUpdated {
+                aggregate_id: AggregateId,
+                ts: String,
+                count: u64,
+                #[serde(default, deserialize_with = "deserialize_some")]
+                sum: Option<JsonValue>,
+            },
+            #[serde(rename_all = "camelCase")]
+            AggregateFailed {
+                aggregate_id: AggregateId,
+                error: String,
+            },
         }
         let s: ServerMessageJson = serde_json::from_value(value)?;
         let result = match s {
@@ -739,6 +971,31 @@ impl<V: TryFrom<JsonValue, Error = anyhow::Error>> TryFrom<JsonValue> for Server
                 base_version,
             },
             ServerMessageJson::Ping {} => ServerMessage::Ping {},
+            ServerMessageJson::Connected {
+                session_token,
+                resumed,
+            } => ServerMessage::Connected {
+                session_token,
+                resumed,
+            },
+            ServerMessageJson::AggregateUpdated {
+                aggregate_id,
+                ts,
+                count,
+                sum,
+            } => ServerMessage::AggregateUpdated {
+                aggregate_id,
+                ts: Timestamp::try_from(string_to_u64(&ts)?)?,
+                count,
+                sum: sum.map(V::try_from).transpose()?,
+            },
+            ServerMessageJson::AggregateFailed {
+                aggregate_id,
+                error,
+            } => ServerMessage::AggregateFailed {
+                aggregate_id,
+                error_message: error,
+            },
         };
         Ok(result)
     }
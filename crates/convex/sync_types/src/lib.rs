@@ -4,6 +4,7 @@ pub mod headers;
 pub mod identifier;
 pub mod json;
 pub mod module_path;
+pub mod patch;
 pub mod path;
 #[cfg(any(test, feature = "testing"))]
 pub mod testing;
@@ -17,8 +18,15 @@ pub use crate::{
         CanonicalizedModulePath,
         ModulePath,
     },
+    patch::{
+        PatchOperation,
+        PatchPathSegment,
+    },
     timestamp::Timestamp,
     types::{
+        Aggregate,
+        AggregateId,
+        AggregateSetModification,
         AuthenticationToken,
         ClientMessage,
         ErrorPayload,
@@ -32,6 +40,7 @@ pub use crate::{
         ServerMessage,
         SessionId,
         SessionRequestSeqNumber,
+        SessionResume,
         StateModification,
         StateVersion,
         UserIdentifier,
@@ -0,0 +1,251 @@
+//! Patches to the JSON encoding of query results, so the server can send a
+//! client only what changed in a result it already has.
+
+use anyhow::Context;
+#[cfg(any(test, feature = "testing"))]
+use proptest::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::Value as JsonValue;
+
+#[cfg(any(test, feature = "testing"))]
+use crate::testing::arb_json;
+
+/// The first sync protocol version where clients can apply
+/// [`crate::StateModification::QueryPatched`]. Older clients are always sent
+/// the full result.
+pub const QUERY_PATCH_PROTOCOL_VERSION: u32 = 1;
+
+/// A step from a JSON value into one of its children: a key of an object or an
+/// index of an array.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+#[serde(untagged)]
+pub enum PatchPathSegment {
+    Key(String),
+    Index(u32),
+}
+
+/// One change to a JSON value. `path` is the steps from the root of the value
+/// to the value the operation applies to.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum PatchOperation {
+    /// Replaces the value at `path`. Keys are never added to objects this way,
+    /// so an object that gains keys is set as a whole and keeps the server's
+    /// key order.
+    Set {
+        path: Vec<PatchPathSegment>,
+        #[cfg_attr(any(test, feature = "testing"), proptest(strategy = "arb_json()"))]
+        value: JsonValue,
+    },
+    /// Removes the key at `path` from its object.
+    Remove { path: Vec<PatchPathSegment> },
+    /// Replaces `delete_count` elements of the array at `path`, starting at
+    /// `start`, with `values`.
+    #[serde(rename_all = "camelCase")]
+    Splice {
+        path: Vec<PatchPathSegment>,
+        start: u32,
+        delete_count: u32,
+        #[cfg_attr(
+            any(test, feature = "testing"),
+            proptest(strategy = "prop::collection::vec(arb_json(), 0..3)")
+        )]
+        values: Vec<JsonValue>,
+    },
+}
+
+/// Computes a patch that turns `old` into `new`.
+///
+/// Arrays are compared by their common prefix and suffix, so inserting,
+/// removing, or changing one element of a long list only sends that element.
+pub fn diff_json(old: &JsonValue, new: &JsonValue) -> Vec<PatchOperation> {
+    let mut patch = vec![];
+    diff_at(old, new, &mut vec![], &mut patch);
+    patch
+}
+
+fn diff_at(
+    old: &JsonValue,
+    new: &JsonValue,
+    path: &mut Vec<PatchPathSegment>,
+    patch: &mut Vec<PatchOperation>,
+) {
+    if old == new {
+        return;
+    }
+    match (old, new) {
+        (JsonValue::Object(old), JsonValue::Object(new))
+            if new.keys().all(|key| old.contains_key(key)) =>
+        {
+            for key in old.keys() {
+                if !new.contains_key(key) {
+                    let mut path = path.clone();
+                    path.push(PatchPathSegment::Key(key.clone()));
+                    patch.push(PatchOperation::Remove { path });
+                }
+            }
+            for (key, new_value) in new {
+                path.push(PatchPathSegment::Key(key.clone()));
+                diff_at(&old[key], new_value, path, patch);
+                path.pop();
+            }
+        },
+        (JsonValue::Array(old), JsonValue::Array(new)) => {
+            let prefix = old.iter().zip(new).take_while(|(o, n)| o == n).count();
+            let suffix = old[prefix..]
+                .iter()
+                .rev()
+                .zip(new[prefix..].iter().rev())
+                .take_while(|(o, n)| o == n)
+                .count();
+            let old_middle = &old[prefix..old.len() - suffix];
+            let new_middle = &new[prefix..new.len() - suffix];
+            if old_middle.len() == new_middle.len() {
+                // Elements changed in place, so only send what changed within
+                // each of them.
+                for (i, (old_value, new_value)) in old_middle.iter().zip(new_middle).enumerate() {
+                    path.push(PatchPathSegment::Index((prefix + i) as u32));
+                    diff_at(old_value, new_value, path, patch);
+                    path.pop();
+                }
+            } else {
+                patch.push(PatchOperation::Splice {
+                    path: path.clone(),
+                    start: prefix as u32,
+                    delete_count: old_middle.len() as u32,
+                    values: new_middle.to_vec(),
+                });
+            }
+        },
+        _ => patch.push(PatchOperation::Set {
+            path: path.clone(),
+            value: new.clone(),
+        }),
+    }
+}
+
+/// Applies `patch` to `value` in order.
+pub fn apply_patch(value: &mut JsonValue, patch: &[PatchOperation]) -> anyhow::Result<()> {
+    for operation in patch {
+        match operation {
+            PatchOperation::Set {
+                path,
+                value: new_value,
+            } => {
+                *value_at(value, path)? = new_value.clone();
+            },
+            PatchOperation::Remove { path } => {
+                let Some((PatchPathSegment::Key(key), parent)) = path.split_last() else {
+                    anyhow::bail!("Invalid patch path {path:?}");
+                };
+                match value_at(value, parent)? {
+                    JsonValue::Object(object) => {
+                        object
+                            .remove(key)
+                            .with_context(|| format!("Invalid patch path {path:?}"))?;
+                    },
+                    _ => anyhow::bail!("Invalid patch path {path:?}"),
+                }
+            },
+            PatchOperation::Splice {
+                path,
+                start,
+                delete_count,
+                values,
+            } => {
+                let JsonValue::Array(array) = value_at(value, path)? else {
+                    anyhow::bail!("Invalid patch path {path:?}");
+                };
+                let start = *start as usize;
+                let end = start + *delete_count as usize;
+                anyhow::ensure!(
+                    end <= array.len(),
+                    "Splice of {start}..{end} is out of bounds of array of length {}",
+                    array.len()
+                );
+                array.splice(start..end, values.iter().cloned());
+            },
+        }
+    }
+    Ok(())
+}
+
+fn value_at<'a>(
+    mut value: &'a mut JsonValue,
+    path: &[PatchPathSegment],
+) -> anyhow::Result<&'a mut JsonValue> {
+    for segment in path {
+        value = match (value, segment) {
+            (JsonValue::Object(object), PatchPathSegment::Key(key)) => object.get_mut(key),
+            (JsonValue::Array(array), PatchPathSegment::Index(i)) => array.get_mut(*i as usize),
+            _ => None,
+        }
+        .with_context(|| format!("Invalid patch path {path:?}"))?;
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+    use serde_json::json;
+
+    use super::{
+        apply_patch,
+        diff_json,
+        PatchOperation,
+        PatchPathSegment,
+    };
+    use crate::testing::arb_json;
+
+    proptest! {
+        #![proptest_config(
+            ProptestConfig { failure_persistence: None, ..ProptestConfig::default() }
+        )]
+
+        #[test]
+        fn test_apply_diff(old in arb_json(), new in arb_json()) {
+            let mut patched = old.clone();
+            apply_patch(&mut patched, &diff_json(&old, &new)).unwrap();
+            assert_eq!(patched, new);
+        }
+    }
+
+    #[test]
+    fn test_diff_changes_one_element() {
+        let old: Vec<_> = (0..100).map(|i| json!({"i": i, "done": false})).collect();
+        let mut new = old.clone();
+        new[50] = json!({"i": 50, "done": true});
+        assert_eq!(
+            diff_json(&json!(old), &json!(new)),
+            vec![PatchOperation::Set {
+                path: vec![
+                    PatchPathSegment::Index(50),
+                    PatchPathSegment::Key("done".to_string())
+                ],
+                value: json!(true),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_diff_inserts_element() {
+        let old: Vec<_> = (0..100).map(|i| json!(i)).collect();
+        let mut new = old.clone();
+        new.insert(0, json!(-1));
+        assert_eq!(
+            diff_json(&json!(old), &json!(new)),
+            vec![PatchOperation::Splice {
+                path: vec![],
+                start: 0,
+                delete_count: 0,
+                values: vec![json!(-1)],
+            }]
+        );
+    }
+}
@@ -20,6 +20,7 @@ use uuid::Uuid;
 #[cfg(any(test, feature = "testing"))]
 use crate::testing::arb_json;
 use crate::{
+    patch::PatchOperation,
     Timestamp,
     UdfPath,
 };
@@ -46,6 +47,28 @@ impl Display for QueryId {
     }
 }
 
+#[derive(
+    Copy, Clone, Debug, Default, Eq, PartialEq, PartialOrd, Ord, Serialize, Deserialize, Hash,
+)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct AggregateId(u32);
+
+impl AggregateId {
+    pub fn new(id: u32) -> Self {
+        AggregateId(id)
+    }
+
+    pub fn get_id(&self) -> u32 {
+        self.0
+    }
+}
+
+impl Display for AggregateId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
 pub type QuerySetVersion = u32;
 pub type IdentityVersion = u32;
 
@@ -91,6 +114,11 @@ pub struct Query {
     /// For internal use by Convex dashboard. Only works with admin auth.
     /// Allows calling a query within a component directly.
     pub component_path: Option<String>,
+
+    /// Top-level arguments the server fills in, from the argument's name to a
+    /// server value like `identity.subject`. Queries like "my messages" can
+    /// then be subscribed to with the same arguments whoever is logged in.
+    pub server_args: Option<BTreeMap<String, String>>,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -100,6 +128,33 @@ pub enum QuerySetModification {
     Remove { query_id: QueryId },
 }
 
+/// A live aggregate over a range of an index: the number of documents in the
+/// range, and optionally the sum of a numeric field over them. Only works with
+/// admin auth.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct Aggregate {
+    pub aggregate_id: AggregateId,
+    /// The full index name, like `messages.by_channel`.
+    pub index_name: String,
+    /// Index range expressions in the same format as the `range` of an index
+    /// range query.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "string_json_args_strategy()")
+    )]
+    pub range: Vec<JsonValue>,
+    pub sum_field: Option<String>,
+    pub component_path: Option<String>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub enum AggregateSetModification {
+    Add(Aggregate),
+    Remove { aggregate_id: AggregateId },
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 #[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
 pub enum ClientMessage {
@@ -108,6 +163,12 @@ pub enum ClientMessage {
         connection_count: u32,
         last_close_reason: String,
         max_observed_timestamp: Option<Timestamp>,
+        /// The newest sync protocol version the client understands. Clients
+        /// from before protocol versions were introduced send 0.
+        protocol_version: u32,
+        /// Set when the client wants to pick up the session from its previous
+        /// connection rather than resending its queries and identity.
+        resume: Option<SessionResume>,
     },
     ModifyQuerySet {
         base_version: QuerySetVersion,
@@ -129,6 +190,10 @@ pub enum ClientMessage {
         /// For internal use by Convex dashboard. Only works with admin auth.
         /// Allows calling a mutation within a component directly.
         component_path: Option<String>,
+        /// A client-chosen ID for the mutation that stays the same when it's
+        /// retried, even from a new session. The mutation runs at most once
+        /// per ID, and retries get the result of the run that committed.
+        mutation_id: Option<String>,
     },
     Action {
         request_id: SessionRequestSeqNumber,
@@ -147,6 +212,33 @@ pub enum ClientMessage {
         token: AuthenticationToken,
     },
     Event(ClientEvent),
+    ModifyAggregateSet {
+        #[cfg_attr(
+            any(test, feature = "testing"),
+            proptest(strategy = "prop::collection::vec(any::<AggregateSetModification>(), 0..2)")
+        )]
+        modifications: Vec<AggregateSetModification>,
+    },
+}
+
+/// The first sync protocol version where the server sends
+/// [`ServerMessage::Connected`] and clients can resume their sessions.
+pub const SESSION_RESUME_PROTOCOL_VERSION: u32 = 2;
+
+/// What a reconnecting client knows about its previous connection's session.
+/// The server only resumes the session if this matches the state it retained,
+/// since otherwise the client and server would disagree on the query set.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct SessionResume {
+    /// The token from the previous connection's `Connected` message.
+    pub session_token: String,
+    /// The end version of the last transition the client received.
+    pub state_version: StateVersion,
+    /// The version of the last query set modification the client sent.
+    pub query_set_version: QuerySetVersion,
+    /// The version of the last identity the client sent.
+    pub identity_version: IdentityVersion,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -267,6 +359,19 @@ pub enum StateModification<V> {
         log_lines: LogLinesMessage,
         journal: SerializedQueryJournal,
     },
+    /// Like `QueryUpdated`, but the result is sent as a patch to the JSON
+    /// encoding of the query's previous result. Only sent to clients that
+    /// negotiated [`crate::patch::QUERY_PATCH_PROTOCOL_VERSION`].
+    QueryPatched {
+        query_id: QueryId,
+        #[cfg_attr(
+            any(test, feature = "testing"),
+            proptest(strategy = "prop::collection::vec(any::<PatchOperation>(), 0..4)")
+        )]
+        patch: Vec<PatchOperation>,
+        log_lines: LogLinesMessage,
+        journal: SerializedQueryJournal,
+    },
     QueryFailed {
         query_id: QueryId,
         error_message: String,
@@ -328,6 +433,28 @@ pub enum ServerMessage<V: 'static> {
         error_message: String,
     },
     Ping,
+    /// Sent in reply to `Connect` to clients that support session resumption.
+    /// The client can present `session_token` when it reconnects to resume
+    /// this session. If `resumed` is true, the session from the token in
+    /// `Connect` was resumed, and the client shouldn't resend its queries or
+    /// identity.
+    Connected {
+        session_token: String,
+        resumed: bool,
+    },
+    /// Sent when an aggregate is added and then whenever its value changes.
+    AggregateUpdated {
+        aggregate_id: AggregateId,
+        ts: Timestamp,
+        count: u64,
+        /// Only set if the aggregate has a `sum_field`.
+        sum: Option<V>,
+    },
+    /// The aggregate failed and has been removed.
+    AggregateFailed {
+        aggregate_id: AggregateId,
+        error_message: String,
+    },
 }
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -354,6 +481,16 @@ impl<V: 'static> ErrorPayload<V> {
             ErrorPayload::ErrorData { message: _, data } => Some(data),
         }
     }
+
+    pub fn map_data<W>(self, f: impl FnOnce(V) -> W) -> ErrorPayload<W> {
+        match self {
+            ErrorPayload::Message(message) => ErrorPayload::Message(message),
+            ErrorPayload::ErrorData { message, data } => ErrorPayload::ErrorData {
+                message,
+                data: f(data),
+            },
+        }
+    }
 }
 
 /// List of log lines from a Convex function execution.
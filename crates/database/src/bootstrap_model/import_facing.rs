@@ -24,6 +24,7 @@ use value::{
 
 use crate::{
     defaults::bootstrap_system_tables,
+    materialized_aggregates::update_materialized_aggregates,
     SchemaModel,
     Transaction,
 };
@@ -53,6 +54,36 @@ impl<'a, RT: Runtime> ImportFacingModel<'a, RT> {
         value: ConvexObject,
         table_mapping_for_schema: &TableMapping,
     ) -> anyhow::Result<DeveloperDocumentId> {
+        let document = self
+            .validate_insert(table_id, table_name, value, table_mapping_for_schema)
+            .await?;
+        let id = document.id();
+        self.tx.apply_validated_write(id, None, Some(document))?;
+
+        Ok(id.into())
+    }
+
+    /// Runs all of the checks that `insert` would, without writing the
+    /// document. Used by import dry runs.
+    pub async fn check_insert(
+        &mut self,
+        table_id: TabletIdAndTableNumber,
+        table_name: &TableName,
+        value: ConvexObject,
+        table_mapping_for_schema: &TableMapping,
+    ) -> anyhow::Result<()> {
+        self.validate_insert(table_id, table_name, value, table_mapping_for_schema)
+            .await?;
+        Ok(())
+    }
+
+    async fn validate_insert(
+        &mut self,
+        table_id: TabletIdAndTableNumber,
+        table_name: &TableName,
+        value: ConvexObject,
+        table_mapping_for_schema: &TableMapping,
+    ) -> anyhow::Result<ResolvedDocument> {
         if self
             .tx
             .virtual_system_mapping()
@@ -120,9 +151,7 @@ impl<'a, RT: Runtime> ImportFacingModel<'a, RT> {
         SchemaModel::new(self.tx, namespace)
             .enforce_with_table_mapping(&document, &table_mapping_for_schema.namespace(namespace))
             .await?;
-        self.tx.apply_validated_write(id, None, Some(document))?;
-
-        Ok(id.into())
+        Ok(document)
     }
 
     #[convex_macro::instrument_future]
@@ -198,6 +227,7 @@ impl<'a, RT: Runtime> ImportFacingModel<'a, RT> {
         SchemaModel::new(self.tx, namespace)
             .enforce_with_table_mapping(&document, &table_mapping_for_schema.namespace(namespace))
             .await?;
+        update_materialized_aggregates(self.tx, id, existing_doc.as_ref(), Some(&document)).await?;
         self.tx
             .apply_validated_write(id, existing_doc, Some(document))?;
 
@@ -236,6 +266,7 @@ impl<'a, RT: Runtime> ImportFacingModel<'a, RT> {
         let id = ResolvedDocumentId::new(table_id.tablet_id, developer_id);
         let existing_doc = self.tx.get(id).await?;
 
+        update_materialized_aggregates(self.tx, id, existing_doc.as_ref(), None).await?;
         self.tx.apply_validated_write(id, existing_doc, None)?;
 
         Ok(())
@@ -430,6 +430,7 @@ impl<'a, RT: Runtime> IndexModel<'a, RT> {
                     index_name.clone(),
                     index_schema.search_field.clone(),
                     index_schema.filter_fields.clone(),
+                    index_schema.analyzer,
                 ))
             }
             for (index_descriptor, index_schema) in &table_schema.vector_indexes {
@@ -439,6 +440,7 @@ impl<'a, RT: Runtime> IndexModel<'a, RT> {
                     index_schema.vector_field.clone(),
                     index_schema.dimension,
                     index_schema.filter_fields.clone(),
+                    index_schema.distance,
                 ));
             }
         }
@@ -958,12 +960,14 @@ impl<'a, RT: Runtime> IndexModel<'a, RT> {
                         DeveloperTextIndexConfig {
                             search_field,
                             filter_fields,
+                            analyzer,
                         },
                     ..
                 } => IndexMetadata::new_backfilling_text_index(
                     index_name,
                     search_field,
                     filter_fields,
+                    analyzer,
                 ),
                 IndexConfig::Vector {
                     developer_config:
@@ -971,6 +975,7 @@ impl<'a, RT: Runtime> IndexModel<'a, RT> {
                             dimensions,
                             vector_field,
                             filter_fields,
+                            distance,
                         },
                     ..
                 } => IndexMetadata::new_backfilling_vector_index(
@@ -978,6 +983,7 @@ impl<'a, RT: Runtime> IndexModel<'a, RT> {
                     vector_field,
                     dimensions,
                     filter_fields,
+                    distance,
                 ),
             };
             SystemMetadataModel::new_global(self.tx)
@@ -27,8 +27,10 @@ use value::{
     obj,
     ConvexObject,
     ConvexValue,
+    FieldName,
     FieldPath,
     InternalId,
+    ResolvedDocumentId,
     TableName,
     TableNamespace,
 };
@@ -72,9 +74,7 @@ impl<'a, RT: Runtime> IndexWorkerMetadataModel<'a, RT> {
     ) -> anyhow::Result<ParsedDocument<IndexWorkerMetadataRecord>> {
         self.get_or_create_metadata(
             id,
-            IndexWorkerMetadata::VectorSearch(IndexWorkerBatchMetadata {
-                fast_forward_ts: Timestamp::default(),
-            }),
+            IndexWorkerMetadata::VectorSearch(IndexWorkerBatchMetadata::default()),
         )
         .await
     }
@@ -85,13 +85,56 @@ impl<'a, RT: Runtime> IndexWorkerMetadataModel<'a, RT> {
     ) -> anyhow::Result<ParsedDocument<IndexWorkerMetadataRecord>> {
         self.get_or_create_metadata(
             id,
-            IndexWorkerMetadata::TextSearch(IndexWorkerBatchMetadata {
-                fast_forward_ts: Timestamp::default(),
-            }),
+            IndexWorkerMetadata::TextSearch(IndexWorkerBatchMetadata::default()),
         )
         .await
     }
 
+    /// Replaces the compaction thresholds a vector index overrides.
+    pub async fn set_vector_compaction_overrides(
+        &mut self,
+        id: IndexId,
+        overrides: CompactionConfigOverrides,
+    ) -> anyhow::Result<()> {
+        let (metadata_id, mut metadata) = self
+            .get_or_create_vector_search(id)
+            .await?
+            .into_id_and_value();
+        *metadata.index_metadata.mut_compaction_overrides() = overrides;
+        self.replace_metadata(metadata_id, metadata).await
+    }
+
+    /// Asks the compaction worker to compact a vector index as far as it can,
+    /// replacing any earlier request.
+    pub async fn request_vector_compaction(
+        &mut self,
+        id: IndexId,
+    ) -> anyhow::Result<ManualCompaction> {
+        let (metadata_id, mut metadata) = self
+            .get_or_create_vector_search(id)
+            .await?
+            .into_id_and_value();
+        let manual_compaction = ManualCompaction {
+            requested_ts: *self.tx.begin_timestamp(),
+            completed_ts: None,
+            segments_compacted: 0,
+        };
+        *metadata.index_metadata.mut_manual_compaction() = Some(manual_compaction.clone());
+        self.replace_metadata(metadata_id, metadata).await?;
+        Ok(manual_compaction)
+    }
+
+    pub async fn replace_metadata(
+        &mut self,
+        id: ResolvedDocumentId,
+        metadata: IndexWorkerMetadataRecord,
+    ) -> anyhow::Result<()> {
+        SystemMetadataModel::new_global(self.tx)
+            .replace(id, metadata.try_into()?)
+            .await?;
+        Ok(())
+    }
+
     pub async fn get_metadata(
         &mut self,
         id: IndexId,
@@ -235,12 +278,38 @@ pub enum IndexWorkerMetadata {
 }
 
 impl IndexWorkerMetadata {
-    pub fn mut_fast_forward_ts(&mut self) -> &mut Timestamp {
-        &mut match self {
+    fn batch_metadata(&self) -> &IndexWorkerBatchMetadata {
+        match self {
+            IndexWorkerMetadata::TextSearch(meta) => meta,
+            IndexWorkerMetadata::VectorSearch(meta) => meta,
+        }
+    }
+
+    fn mut_batch_metadata(&mut self) -> &mut IndexWorkerBatchMetadata {
+        match self {
             IndexWorkerMetadata::TextSearch(ref mut meta) => meta,
             IndexWorkerMetadata::VectorSearch(ref mut meta) => meta,
         }
-        .fast_forward_ts
+    }
+
+    pub fn mut_fast_forward_ts(&mut self) -> &mut Timestamp {
+        &mut self.mut_batch_metadata().fast_forward_ts
+    }
+
+    pub fn compaction_overrides(&self) -> &CompactionConfigOverrides {
+        &self.batch_metadata().compaction_overrides
+    }
+
+    pub fn mut_compaction_overrides(&mut self) -> &mut CompactionConfigOverrides {
+        &mut self.mut_batch_metadata().compaction_overrides
+    }
+
+    pub fn manual_compaction(&self) -> Option<&ManualCompaction> {
+        self.batch_metadata().manual_compaction.as_ref()
+    }
+
+    pub fn mut_manual_compaction(&mut self) -> &mut Option<ManualCompaction> {
+        &mut self.mut_batch_metadata().manual_compaction
     }
 }
 
@@ -289,23 +358,39 @@ impl TryFrom<ConvexObject> for IndexWorkerMetadata {
 /// batches on an ongoing basis.
 ///
 /// For now this is vector and text search.
-#[derive(Debug)]
+#[derive(Debug, Default)]
 #[cfg_attr(
     any(test, feature = "testing"),
     derive(proptest_derive::Arbitrary, Clone, PartialEq)
 )]
 pub struct IndexWorkerBatchMetadata {
     fast_forward_ts: Timestamp,
+    compaction_overrides: CompactionConfigOverrides,
+    manual_compaction: Option<ManualCompaction>,
 }
 
 impl TryFrom<IndexWorkerBatchMetadata> for ConvexObject {
     type Error = anyhow::Error;
 
     fn try_from(value: IndexWorkerBatchMetadata) -> Result<Self, Self::Error> {
-        let ts = ConvexValue::Int64(value.fast_forward_ts.into());
-        obj!(
-            "fast_forward_ts" => ts,
-        )
+        let mut fields: BTreeMap<FieldName, ConvexValue> = BTreeMap::new();
+        fields.insert(
+            "fast_forward_ts".parse()?,
+            ConvexValue::Int64(value.fast_forward_ts.into()),
+        );
+        if value.compaction_overrides != CompactionConfigOverrides::default() {
+            fields.insert(
+                "compaction_overrides".parse()?,
+                ConvexValue::Object(value.compaction_overrides.try_into()?),
+            );
+        }
+        if let Some(manual_compaction) = value.manual_compaction {
+            fields.insert(
+                "manual_compaction".parse()?,
+                ConvexValue::Object(manual_compaction.try_into()?),
+            );
+        }
+        fields.try_into()
     }
 }
 
@@ -320,7 +405,179 @@ impl TryFrom<ConvexObject> for IndexWorkerBatchMetadata {
                 anyhow::bail!("Missing or invalid `fast_forward_ts` field for IndexWorkerMetadata")
             },
         };
-        Ok(IndexWorkerBatchMetadata { fast_forward_ts })
+        let compaction_overrides = match fields.remove("compaction_overrides") {
+            Some(ConvexValue::Object(overrides)) => overrides.try_into()?,
+            None => CompactionConfigOverrides::default(),
+            _ => anyhow::bail!("Invalid `compaction_overrides` field for IndexWorkerMetadata"),
+        };
+        let manual_compaction = match fields.remove("manual_compaction") {
+            Some(ConvexValue::Object(manual_compaction)) => Some(manual_compaction.try_into()?),
+            None => None,
+            _ => anyhow::bail!("Invalid `manual_compaction` field for IndexWorkerMetadata"),
+        };
+        Ok(IndexWorkerBatchMetadata {
+            fast_forward_ts,
+            compaction_overrides,
+            manual_compaction,
+        })
+    }
+}
+
+/// Per-index overrides for the thresholds the compaction worker uses to pick
+/// segments to merge. Unset thresholds use the deployment's defaults.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct CompactionConfigOverrides {
+    /// Don't merge fewer than this many segments at once.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "proptest::option::of(0..=i64::MAX as u64)")
+    )]
+    pub min_compaction_segments: Option<u64>,
+    /// Rewrite a segment once more than this fraction, between 0 and 1, of
+    /// its documents are deleted.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "proptest::option::of(0.0..=1.0)")
+    )]
+    pub max_deleted_percentage: Option<f64>,
+    /// Segments up to this size are merged more eagerly than larger ones.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "proptest::option::of(0..=i64::MAX as u64)")
+    )]
+    pub small_segment_threshold_bytes: Option<u64>,
+    /// The largest segment compaction produces.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "proptest::option::of(0..=i64::MAX as u64)")
+    )]
+    pub max_segment_size_bytes: Option<u64>,
+}
+
+impl TryFrom<CompactionConfigOverrides> for ConvexObject {
+    type Error = anyhow::Error;
+
+    fn try_from(value: CompactionConfigOverrides) -> Result<Self, Self::Error> {
+        let mut fields: BTreeMap<FieldName, ConvexValue> = BTreeMap::new();
+        for (field, bytes) in [
+            ("min_compaction_segments", value.min_compaction_segments),
+            (
+                "small_segment_threshold_bytes",
+                value.small_segment_threshold_bytes,
+            ),
+            ("max_segment_size_bytes", value.max_segment_size_bytes),
+        ] {
+            if let Some(bytes) = bytes {
+                fields.insert(field.parse()?, ConvexValue::Int64(bytes.try_into()?));
+            }
+        }
+        if let Some(percentage) = value.max_deleted_percentage {
+            fields.insert(
+                "max_deleted_percentage".parse()?,
+                ConvexValue::Float64(percentage),
+            );
+        }
+        fields.try_into()
+    }
+}
+
+impl TryFrom<ConvexObject> for CompactionConfigOverrides {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ConvexObject) -> Result<Self, Self::Error> {
+        let mut fields: BTreeMap<_, _> = value.into();
+        let mut remove_u64 = |field: &str| -> anyhow::Result<Option<u64>> {
+            match fields.remove(field) {
+                Some(ConvexValue::Int64(value)) => Ok(Some(value.try_into()?)),
+                None => Ok(None),
+                _ => anyhow::bail!("Invalid `{field}` field for CompactionConfigOverrides"),
+            }
+        };
+        let min_compaction_segments = remove_u64("min_compaction_segments")?;
+        let small_segment_threshold_bytes = remove_u64("small_segment_threshold_bytes")?;
+        let max_segment_size_bytes = remove_u64("max_segment_size_bytes")?;
+        let max_deleted_percentage = match fields.remove("max_deleted_percentage") {
+            Some(ConvexValue::Float64(percentage)) => Some(percentage),
+            None => None,
+            _ => anyhow::bail!(
+                "Invalid `max_deleted_percentage` field for CompactionConfigOverrides"
+            ),
+        };
+        Ok(CompactionConfigOverrides {
+            min_compaction_segments,
+            max_deleted_percentage,
+            small_segment_threshold_bytes,
+            max_segment_size_bytes,
+        })
+    }
+}
+
+/// A compaction requested through the admin API. The compaction worker keeps
+/// merging the index's segments, ignoring its usual thresholds, until no more
+/// can be merged.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct ManualCompaction {
+    pub requested_ts: Timestamp,
+    /// Set once there's nothing left to merge.
+    pub completed_ts: Option<Timestamp>,
+    /// Segments merged since the compaction was requested.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=i64::MAX as u64")
+    )]
+    pub segments_compacted: u64,
+}
+
+impl TryFrom<ManualCompaction> for ConvexObject {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ManualCompaction) -> Result<Self, Self::Error> {
+        let mut fields: BTreeMap<FieldName, ConvexValue> = BTreeMap::new();
+        fields.insert(
+            "requested_ts".parse()?,
+            ConvexValue::Int64(value.requested_ts.into()),
+        );
+        if let Some(completed_ts) = value.completed_ts {
+            fields.insert(
+                "completed_ts".parse()?,
+                ConvexValue::Int64(completed_ts.into()),
+            );
+        }
+        fields.insert(
+            "segments_compacted".parse()?,
+            ConvexValue::Int64(value.segments_compacted.try_into()?),
+        );
+        fields.try_into()
+    }
+}
+
+impl TryFrom<ConvexObject> for ManualCompaction {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ConvexObject) -> Result<Self, Self::Error> {
+        let mut fields: BTreeMap<_, _> = value.into();
+        let requested_ts = match fields.remove("requested_ts") {
+            Some(ConvexValue::Int64(ts)) => Timestamp::try_from(ts)?,
+            _ => anyhow::bail!("Missing or invalid `requested_ts` field for ManualCompaction"),
+        };
+        let completed_ts = match fields.remove("completed_ts") {
+            Some(ConvexValue::Int64(ts)) => Some(Timestamp::try_from(ts)?),
+            None => None,
+            _ => anyhow::bail!("Invalid `completed_ts` field for ManualCompaction"),
+        };
+        let segments_compacted = match fields.remove("segments_compacted") {
+            Some(ConvexValue::Int64(segments)) => segments.try_into()?,
+            _ => {
+                anyhow::bail!("Missing or invalid `segments_compacted` field for ManualCompaction")
+            },
+        };
+        Ok(ManualCompaction {
+            requested_ts,
+            completed_ts,
+            segments_compacted,
+        })
     }
 }
 
@@ -1,6 +1,12 @@
 use std::{
-    collections::BTreeSet,
-    sync::LazyLock,
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
+    sync::{
+        Arc,
+        LazyLock,
+    },
 };
 
 use anyhow::Context;
@@ -38,8 +44,10 @@ use common::{
         TabletIndexName,
     },
     value::TabletIdAndTableNumber,
+    virtual_system_mapping::VirtualSystemDocMapper,
 };
 use errors::ErrorMetadata;
+use maplit::btreemap;
 use value::{
     DeveloperDocumentId,
     FieldPath,
@@ -56,6 +64,12 @@ use crate::{
         SystemIndex,
         SystemTable,
     },
+    virtual_tables::tables_debug::{
+        TablesDebugDocMapper,
+        TABLES_DEBUG_VIRTUAL_INDEX_BY_CREATION_TIME,
+        TABLES_DEBUG_VIRTUAL_INDEX_BY_ID,
+        TABLES_DEBUG_VIRTUAL_TABLE,
+    },
     IndexModel,
     ResolvedQuery,
     SchemaModel,
@@ -94,6 +108,25 @@ impl SystemTable for TablesTable {
         }]
     }
 
+    fn virtual_table(
+        &self,
+    ) -> Option<(
+        &'static TableName,
+        BTreeMap<IndexName, IndexName>,
+        Arc<dyn VirtualSystemDocMapper>,
+    )> {
+        Some((
+            &TABLES_DEBUG_VIRTUAL_TABLE,
+            btreemap! {
+                TABLES_DEBUG_VIRTUAL_INDEX_BY_CREATION_TIME.clone() =>
+                    GenericIndexName::by_creation_time(TABLES_TABLE.clone()),
+                TABLES_DEBUG_VIRTUAL_INDEX_BY_ID.clone() =>
+                    GenericIndexName::by_id(TABLES_TABLE.clone()),
+            },
+            Arc::new(TablesDebugDocMapper),
+        ))
+    }
+
     fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
         ParsedDocument::<TableMetadata>::try_from(document).map(|_| ())
     }
@@ -296,6 +329,20 @@ impl<'a, RT: Runtime> TableModel<'a, RT> {
         Ok(candidate_table_number)
     }
 
+    /// Whether creating `table` with `table_number` for an import would
+    /// conflict with an existing table, so the import has to give it a new
+    /// table number instead.
+    pub fn table_number_conflicts(
+        &mut self,
+        namespace: TableNamespace,
+        table: &TableName,
+        table_number: TableNumber,
+        tables_in_import: &BTreeSet<TableName>,
+    ) -> bool {
+        self.check_can_overwrite(namespace, table, Some(table_number), tables_in_import)
+            .is_err()
+    }
+
     /// Checks for conflicts when replacing table, e.g. snapshot import.
     /// A table with the same name can be replaced with a different table
     /// number, but if a different table has the same table number then we have
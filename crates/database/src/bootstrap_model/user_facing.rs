@@ -430,26 +430,29 @@ pub async fn index_range_batch<RT: Runtime>(
 
     for (batch_key, fetch_result) in fetch_results {
         let virtual_table_version = virtual_table_versions.get(&batch_key).cloned();
-        let result = fetch_result.and_then(|IndexRangeResponse { page, cursor }| {
+        let result: anyhow::Result<_> = try {
+            let IndexRangeResponse { page, cursor } = fetch_result?;
             let developer_results = match virtual_table_version {
-                Some(version) => page
-                    .into_iter()
-                    .map(|(key, doc, ts)| {
+                Some(version) => {
+                    let mut developer_results = Vec::with_capacity(page.len());
+                    for (key, doc, ts) in page {
                         let doc = VirtualTable::new(tx)
-                            .map_system_doc_to_virtual_doc(doc, version.clone())?;
-                        anyhow::Ok((key, doc, ts))
-                    })
-                    .try_collect()?,
+                            .map_system_doc_to_virtual_doc(doc, version.clone())
+                            .await?;
+                        developer_results.push((key, doc, ts));
+                    }
+                    developer_results
+                },
                 None => page
                     .into_iter()
                     .map(|(key, doc, ts)| (key, doc.to_developer(), ts))
                     .collect(),
             };
-            anyhow::Ok(DeveloperIndexRangeResponse {
+            DeveloperIndexRangeResponse {
                 page: developer_results,
                 cursor,
-            })
-        });
+            }
+        };
         results.insert(batch_key, result);
     }
     assert_eq!(results.len(), batch_size);
@@ -0,0 +1,391 @@
+//! Ordered post-commit hooks.
+//!
+//! A [`CommitHook`] is invoked once per committed transaction, strictly after
+//! the committer has made the commit durable in persistence and published it,
+//! and in commit timestamp order. Each hook's progress is checkpointed in
+//! persistence under its [`CommitHook::name`], so delivery resumes from the
+//! last acknowledged commit after a restart.
+//!
+//! Delivery is at-least-once: if the process stops after a hook has handled a
+//! commit but before its checkpoint is written, that commit is delivered again.
+//! Hooks should therefore be idempotent, e.g. by deduplicating on
+//! [`CommittedWrites::ts`].
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use common::{
+    backoff::Backoff,
+    document::ResolvedDocument,
+    errors::report_error,
+    persistence::{
+        Persistence,
+        PersistenceGlobalKey,
+        PersistenceReader,
+        RetentionValidator,
+        TimestampRange,
+    },
+    query::Order,
+    runtime::Runtime,
+    types::Timestamp,
+};
+use futures::{
+    future,
+    Future,
+    TryStreamExt,
+};
+use serde_json::Value as JsonValue;
+use value::InternalDocumentId;
+
+use crate::Database;
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(100);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Number of revisions loaded per page from the document log.
+const DOCUMENTS_PAGE_SIZE: u32 = 256;
+
+/// Persist a hook's checkpoint at least this often while catching up on a
+/// long range of commits, to bound redelivery after a restart.
+const CHECKPOINT_EVERY_N_COMMITS: usize = 128;
+
+/// The writes of a single committed transaction.
+#[derive(Clone, Debug)]
+pub struct CommittedWrites {
+    pub ts: Timestamp,
+    /// Revisions written at `ts`, including writes to system tables. `None`
+    /// means the document was deleted.
+    pub writes: Vec<(InternalDocumentId, Option<ResolvedDocument>)>,
+}
+
+#[async_trait]
+pub trait CommitHook: Send + Sync + 'static {
+    /// Stable identifier for the hook, used as its checkpoint key. Renaming a
+    /// hook restarts its delivery from the latest commit.
+    fn name(&self) -> &'static str;
+
+    /// Called once per committed transaction, in commit timestamp order.
+    /// Returning an error retries the same commit with backoff; later commits
+    /// are not delivered until this one succeeds.
+    async fn on_commit(&self, commit: &CommittedWrites) -> anyhow::Result<()>;
+}
+
+/// Registry of commit hooks. Register hooks at startup and then drive them
+/// with [`CommitHooks::start`].
+#[derive(Default)]
+pub struct CommitHooks {
+    hooks: Vec<Arc<dyn CommitHook>>,
+}
+
+impl CommitHooks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, hook: Arc<dyn CommitHook>) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.hooks.iter().all(|h| h.name() != hook.name()),
+            "Commit hook {} is already registered",
+            hook.name()
+        );
+        self.hooks.push(hook);
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+
+    /// Returns a future that delivers commits to every registered hook. Each
+    /// hook makes progress independently, so a failing hook only delays its
+    /// own deliveries.
+    pub fn start<RT: Runtime>(
+        self,
+        runtime: RT,
+        persistence: Arc<dyn Persistence>,
+        retention_validator: Arc<dyn RetentionValidator>,
+        database: Database<RT>,
+    ) -> impl Future<Output = ()> + Send {
+        let checkpoints = CheckpointWriter {
+            persistence: persistence.clone(),
+            lock: Arc::new(tokio::sync::Mutex::new(())),
+        };
+        let runners = self.hooks.into_iter().map(move |hook| {
+            let runner = CommitHookRunner {
+                runtime: runtime.clone(),
+                reader: persistence.reader(),
+                retention_validator: retention_validator.clone(),
+                database: database.clone(),
+                checkpoints: checkpoints.clone(),
+                backoff: Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF),
+                hook,
+            };
+            runner.go()
+        });
+        async move {
+            future::join_all(runners).await;
+        }
+    }
+}
+
+/// Loads the last acknowledged commit timestamp of each hook.
+pub async fn load_commit_hook_checkpoints(
+    reader: &dyn PersistenceReader,
+) -> anyhow::Result<BTreeMap<String, Timestamp>> {
+    let Some(value) = reader
+        .get_persistence_global(PersistenceGlobalKey::CommitHookCheckpoints)
+        .await?
+    else {
+        return Ok(BTreeMap::new());
+    };
+    let checkpoints: BTreeMap<String, JsonValue> = serde_json::from_value(value)?;
+    checkpoints
+        .into_iter()
+        .map(|(name, ts)| Ok((name, Timestamp::try_from(ts)?)))
+        .collect()
+}
+
+/// All hooks share a single persistence global, so updates are serialized to
+/// avoid losing a concurrent hook's checkpoint.
+#[derive(Clone)]
+struct CheckpointWriter {
+    persistence: Arc<dyn Persistence>,
+    lock: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl CheckpointWriter {
+    async fn write(&self, name: &str, ts: Timestamp) -> anyhow::Result<()> {
+        let _guard = self.lock.lock().await;
+        let mut checkpoints =
+            load_commit_hook_checkpoints(self.persistence.reader().as_ref()).await?;
+        checkpoints.insert(name.to_string(), ts);
+        let value: BTreeMap<String, JsonValue> = checkpoints
+            .into_iter()
+            .map(|(name, ts)| (name, ts.into()))
+            .collect();
+        self.persistence
+            .write_persistence_global(
+                PersistenceGlobalKey::CommitHookCheckpoints,
+                serde_json::to_value(value)?,
+            )
+            .await
+    }
+}
+
+struct CommitHookRunner<RT: Runtime> {
+    runtime: RT,
+    reader: Arc<dyn PersistenceReader>,
+    retention_validator: Arc<dyn RetentionValidator>,
+    database: Database<RT>,
+    checkpoints: CheckpointWriter,
+    backoff: Backoff,
+    hook: Arc<dyn CommitHook>,
+}
+
+impl<RT: Runtime> CommitHookRunner<RT> {
+    async fn go(mut self) {
+        loop {
+            if let Err(e) = self.run().await {
+                let name = self.hook.name();
+                report_error(&mut e.context(format!("Commit hook {name} runner died")));
+                let delay = self.backoff.fail(&mut self.runtime.rng());
+                tracing::error!(
+                    "Commit hook {name} runner died, num_failures: {}. Backing off for {}ms",
+                    self.backoff.failures(),
+                    delay.as_millis()
+                );
+                self.runtime.wait(delay).await;
+            }
+        }
+    }
+
+    async fn run(&mut self) -> anyhow::Result<()> {
+        let name = self.hook.name();
+        let checkpoints = load_commit_hook_checkpoints(self.reader.as_ref()).await?;
+        let mut checkpoint = match checkpoints.get(name) {
+            Some(ts) => *ts,
+            None => {
+                // A newly registered hook starts at the latest published commit.
+                // Everything in the write log has already been durably written.
+                let ts = self.database.log().max_ts();
+                self.checkpoints.write(name, ts).await?;
+                ts
+            },
+        };
+        tracing::info!("Starting commit hook {name} after {checkpoint}");
+        loop {
+            // The committer only appends to the write log after a commit is durable,
+            // and does so in commit order, so every commit up to `upper` can be read
+            // back from persistence.
+            let upper = self.database.log().wait_for_higher_ts(checkpoint).await;
+            checkpoint = self.deliver(checkpoint, upper).await?;
+            self.backoff.reset();
+        }
+    }
+
+    /// Deliver all commits in `(checkpoint, upper]`, returning the new
+    /// checkpoint.
+    async fn deliver(
+        &mut self,
+        checkpoint: Timestamp,
+        upper: Timestamp,
+    ) -> anyhow::Result<Timestamp> {
+        let name = self.hook.name();
+        let range = TimestampRange::new(checkpoint.succ()?..=upper)?;
+        let mut stream = self.reader.load_documents(
+            range,
+            Order::Asc,
+            DOCUMENTS_PAGE_SIZE,
+            self.retention_validator.clone(),
+        );
+        let mut current: Option<CommittedWrites> = None;
+        let mut num_uncheckpointed = 0;
+        while let Some((ts, id, document)) = stream.try_next().await? {
+            if current.as_ref().is_some_and(|commit| commit.ts != ts) {
+                let commit = current.take().expect("checked above");
+                self.invoke(&commit).await;
+                num_uncheckpointed += 1;
+                if num_uncheckpointed >= CHECKPOINT_EVERY_N_COMMITS {
+                    self.checkpoints.write(name, commit.ts).await?;
+                    num_uncheckpointed = 0;
+                }
+            }
+            current
+                .get_or_insert_with(|| CommittedWrites { ts, writes: vec![] })
+                .writes
+                .push((id, document));
+        }
+        if let Some(commit) = current {
+            self.invoke(&commit).await;
+        }
+        self.checkpoints.write(name, upper).await?;
+        Ok(upper)
+    }
+
+    /// Invoke the hook on a single commit, retrying until it succeeds.
+    async fn invoke(&mut self, commit: &CommittedWrites) {
+        let name = self.hook.name();
+        loop {
+            match self.hook.on_commit(commit).await {
+                Ok(()) => {
+                    self.backoff.reset();
+                    return;
+                },
+                Err(e) => {
+                    report_error(
+                        &mut e.context(format!("Commit hook {name} failed at {}", commit.ts)),
+                    );
+                    let delay = self.backoff.fail(&mut self.runtime.rng());
+                    self.runtime.wait(delay).await;
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+    use common::{
+        assert_obj,
+        persistence::NoopRetentionValidator,
+        runtime::Runtime,
+        types::Timestamp,
+    };
+    use keybroker::Identity;
+    use runtime::testing::TestRuntime;
+    use tokio::sync::mpsc;
+
+    use super::{
+        load_commit_hook_checkpoints,
+        CommitHook,
+        CommitHooks,
+        CommittedWrites,
+    };
+    use crate::{
+        test_helpers::DbFixtures,
+        Database,
+        TestFacingModel,
+    };
+
+    struct RecordingHook {
+        sender: mpsc::UnboundedSender<Timestamp>,
+    }
+
+    #[async_trait]
+    impl CommitHook for RecordingHook {
+        fn name(&self) -> &'static str {
+            "recording"
+        }
+
+        async fn on_commit(&self, commit: &CommittedWrites) -> anyhow::Result<()> {
+            anyhow::ensure!(!commit.writes.is_empty());
+            self.sender.send(commit.ts)?;
+            Ok(())
+        }
+    }
+
+    async fn insert(db: &Database<TestRuntime>) -> anyhow::Result<Timestamp> {
+        let mut tx = db.begin(Identity::system()).await?;
+        TestFacingModel::new(&mut tx)
+            .insert(&"table".parse()?, assert_obj!())
+            .await?;
+        db.commit(tx).await
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_commit_hooks_ordered_and_resume(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, tp, .. } = DbFixtures::new(&rt).await?;
+        let start_hooks = |sender| {
+            let mut hooks = CommitHooks::new();
+            hooks.register(Arc::new(RecordingHook { sender }))?;
+            anyhow::Ok(rt.spawn(
+                "commit_hooks",
+                hooks.start(
+                    rt.clone(),
+                    tp.clone(),
+                    Arc::new(NoopRetentionValidator),
+                    db.clone(),
+                ),
+            ))
+        };
+
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let mut handle = start_hooks(sender)?;
+        // Wait for the hook to record its initial checkpoint so it observes
+        // every commit below.
+        while load_commit_hook_checkpoints(tp.reader().as_ref())
+            .await?
+            .is_empty()
+        {
+            rt.wait(std::time::Duration::from_millis(10)).await;
+        }
+        let mut expected = vec![];
+        for _ in 0..3 {
+            expected.push(insert(&db).await?);
+        }
+        let mut delivered = vec![];
+        for _ in 0..3 {
+            delivered.push(receiver.recv().await.unwrap());
+        }
+        assert_eq!(delivered, expected);
+        let last = *expected.last().unwrap();
+        while load_commit_hook_checkpoints(tp.reader().as_ref()).await?["recording"] < last {
+            rt.wait(std::time::Duration::from_millis(10)).await;
+        }
+        handle.shutdown();
+
+        // Commits made while the hook isn't running are delivered once it
+        // restarts from its checkpoint.
+        let ts = insert(&db).await?;
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let _handle = start_hooks(sender)?;
+        assert_eq!(receiver.recv().await.unwrap(), ts);
+        Ok(())
+    }
+}
@@ -5,6 +5,7 @@ use std::{
         BTreeMap,
         BTreeSet,
     },
+    future::Future,
     ops::Bound,
     sync::{
         atomic::{
@@ -26,6 +27,7 @@ use anyhow::{
     Error,
 };
 use async_lru::async_lru::AsyncLru;
+use async_trait::async_trait;
 use cmd_util::env::env_config;
 use common::{
     bootstrap_model::{
@@ -54,8 +56,14 @@ use common::{
         ParsedDocument,
         ResolvedDocument,
     },
+    fault_injection,
+    index::IndexKeyBytes,
     interval::Interval,
-    knobs::DEFAULT_DOCUMENTS_PAGE_SIZE,
+    knobs::{
+        DEFAULT_DOCUMENTS_PAGE_SIZE,
+        STARTUP_INTEGRITY_CHECK_ENABLED,
+    },
+    paths::FieldPath,
     pause::PauseClient,
     persistence::{
         new_idle_repeatable_ts,
@@ -70,7 +78,10 @@ use common::{
         RetentionValidator,
         TimestampRange,
     },
-    query::Order,
+    query::{
+        IndexRange,
+        Order,
+    },
     runtime::{
         RateLimiter,
         Runtime,
@@ -115,6 +126,7 @@ use indexing::{
     backend_in_memory_indexes::{
         BackendInMemoryIndexes,
         DatabaseIndexSnapshot,
+        InMemoryIndexes,
     },
     index_registry::IndexRegistry,
 };
@@ -162,12 +174,20 @@ use crate::{
         SystemIndex,
         DEFAULT_BOOTSTRAP_TABLE_NUMBERS,
     },
+    integrity_check::check_metadata_integrity,
     metrics::{
         self,
         load_indexes_into_memory_timer,
         vector::vector_search_with_retries_timer,
         verify_invariants_timer,
     },
+    query::{
+        fuse_rankings,
+        DeveloperQuery,
+        HybridSearch,
+        HybridSearchResult,
+        TableFilter,
+    },
     retention::LeaderRetentionManager,
     schema_registry::SchemaRegistry,
     search_index_bootstrap::SearchIndexBootstrapWorker,
@@ -178,6 +198,8 @@ use crate::{
     },
     stack_traces::StackTrace,
     subscription::{
+        LiveAggregate,
+        LiveAggregateSubscription,
         Subscription,
         SubscriptionsClient,
         SubscriptionsWorker,
@@ -201,6 +223,7 @@ use crate::{
     BootstrapComponentsModel,
     ComponentRegistry,
     FollowerRetentionManager,
+    IndexModel,
     TableIterator,
     Transaction,
     TransactionReadSet,
@@ -428,12 +451,30 @@ impl DatabaseSnapshot {
         let index_documents =
             Self::load_raw_table_documents(persistence_snapshot, index_by_id, index_tablet_id)
                 .await?;
-        let table_documents = Self::load_table_documents::<TableMetadata>(
-            persistence_snapshot,
-            tables_by_id,
-            tables_tablet_id,
-        )
-        .await?;
+        let table_documents =
+            Self::load_raw_table_documents(persistence_snapshot, tables_by_id, tables_tablet_id)
+                .await?;
+        if *STARTUP_INTEGRITY_CHECK_ENABLED {
+            let problems = check_metadata_integrity(
+                table_documents.values().map(|(_, d)| d),
+                index_documents.values().map(|(_, d)| d),
+            );
+            if !problems.is_empty() {
+                for problem in &problems {
+                    tracing::error!("Integrity check failed: {problem}");
+                }
+                anyhow::bail!(
+                    "Found {} problems with the table and index metadata. Set \
+                     STARTUP_INTEGRITY_CHECK_ENABLED=false to start anyway.\n{}",
+                    problems.len(),
+                    problems.iter().map(|p| p.to_string()).join("\n")
+                );
+            }
+        }
+        let table_documents = table_documents
+            .into_values()
+            .map(|(_, doc)| doc.try_into())
+            .try_collect()?;
 
         let (table_mapping, table_states) = Self::table_mapping_and_states(table_documents);
 
@@ -732,6 +773,24 @@ impl ShutdownSignal {
     }
 }
 
+/// In-memory indexes for a historical transaction, which misses on every
+/// range so that reads always go to persistence.
+struct NoInMemoryIndexes;
+
+#[async_trait]
+impl InMemoryIndexes for NoInMemoryIndexes {
+    async fn range(
+        &self,
+        _index_id: IndexId,
+        _interval: &Interval,
+        _order: Order,
+        _tablet_id: TabletId,
+        _table_name: TableName,
+    ) -> anyhow::Result<Option<Vec<(IndexKeyBytes, Timestamp, ResolvedDocument)>>> {
+        Ok(None)
+    }
+}
+
 #[derive(Clone)]
 pub struct StreamingExportTableFilter {
     pub table_name: Option<TableName>,
@@ -1487,7 +1546,75 @@ impl<RT: Runtime> Database<RT> {
             );
         }
         let snapshot = self.snapshot_manager.lock().snapshot(*repeatable_ts)?;
+        let in_memory_indexes = Arc::new(snapshot.in_memory_indexes.clone());
+        self.begin_at_snapshot(
+            identity,
+            repeatable_ts,
+            snapshot,
+            in_memory_indexes,
+            usage_tracker,
+        )
+    }
 
+    /// Begins a read-only transaction at a historical `ts` within the retention
+    /// window, which may be older than the snapshots kept in memory. Documents
+    /// are read from persistence as of `ts`, but table, index, and schema
+    /// metadata come from the latest snapshot, so tables and indexes deleted
+    /// since `ts` aren't visible. Text and vector searches also read the
+    /// latest indexes.
+    pub async fn begin_at_historical_ts(
+        &self,
+        identity: Identity,
+        ts: Timestamp,
+        usage_tracker: FunctionUsageTracker,
+    ) -> anyhow::Result<Transaction<RT>> {
+        let (latest_ts, earliest_in_memory_ts) = {
+            let snapshot_manager = self.snapshot_manager.lock();
+            (snapshot_manager.latest_ts(), snapshot_manager.earliest_ts())
+        };
+        if ts > *latest_ts {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "HistoricalTimestampInFuture",
+                format!("Timestamp {ts} is after the latest timestamp {latest_ts}"),
+            ));
+        }
+        let repeatable_ts = latest_ts.prior_ts(ts)?;
+        if ts >= earliest_in_memory_ts {
+            return self
+                .begin_with_repeatable_ts(identity, repeatable_ts, usage_tracker)
+                .await;
+        }
+        let min_snapshot_ts = self.retention_manager.min_snapshot_ts().await?;
+        if ts < *min_snapshot_ts {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "HistoricalTimestampOutOfRetention",
+                format!(
+                    "Timestamp {ts} is outside of the retention window. The earliest timestamp \
+                     that can be read is {min_snapshot_ts}"
+                ),
+            ));
+        }
+        let snapshot = self.latest_snapshot()?;
+        // Every index range is read from persistence at `ts`, since the in-memory
+        // indexes only reflect the latest snapshot.
+        self.begin_at_snapshot(
+            identity,
+            repeatable_ts,
+            snapshot,
+            Arc::new(NoInMemoryIndexes),
+            usage_tracker,
+        )
+    }
+
+    fn begin_at_snapshot(
+        &self,
+        identity: Identity,
+        repeatable_ts: RepeatableTimestamp,
+        snapshot: Snapshot,
+        in_memory_indexes: Arc<dyn InMemoryIndexes>,
+        usage_tracker: FunctionUsageTracker,
+    ) -> anyhow::Result<Transaction<RT>> {
+        let latest_ts = self.now_ts_for_reads();
         // TODO: Use `begin_ts` outside of just the "_creationTime".
         let begin_ts = cmp::max(latest_ts.succ()?, self.runtime.generate_timestamp()?);
         let creation_time = CreationTime::try_from(begin_ts)?;
@@ -1496,7 +1623,7 @@ impl<RT: Runtime> Database<RT> {
             snapshot.index_registry.clone(),
             DatabaseIndexSnapshot::new(
                 snapshot.index_registry.clone(),
-                Arc::new(snapshot.in_memory_indexes),
+                in_memory_indexes,
                 snapshot.table_registry.table_mapping().clone(),
                 RepeatablePersistence::new(
                     self.reader.clone(),
@@ -1553,6 +1680,9 @@ impl<RT: Runtime> Database<RT> {
     ) -> anyhow::Result<Timestamp> {
         task::consume_budget().await;
         let readonly = transaction.is_readonly();
+        if !readonly && !transaction.identity().is_system() {
+            fault_injection::maybe_fail_commit(&mut self.runtime.rng())?;
+        }
         let result = self
             .committer
             .commit(transaction, write_source.into())
@@ -1583,6 +1713,51 @@ impl<RT: Runtime> Database<RT> {
         self.subscriptions.subscribe(token).await
     }
 
+    /// Subscribe to the number of documents in `index_range`, and the sum of
+    /// `sum_field` over them. The initial value is computed by scanning the
+    /// range once, after which the subscriptions worker maintains it from the
+    /// write log.
+    pub async fn subscribe_aggregate(
+        &self,
+        identity: Identity,
+        namespace: TableNamespace,
+        index_range: IndexRange,
+        sum_field: Option<FieldPath>,
+    ) -> anyhow::Result<LiveAggregateSubscription> {
+        let mut tx = self.begin(identity).await?;
+        let index_name = index_range.index_name.clone();
+        let stable_index_name = IndexModel::new(&mut tx).stable_index_name(
+            namespace,
+            &index_name,
+            TableFilter::ExcludePrivateSystemTables,
+        )?;
+        let indexed_fields =
+            IndexModel::new(&mut tx).indexed_fields(&stable_index_name, &index_name)?;
+        let tablet_index_name = stable_index_name
+            .tablet_index_name()
+            .context("Index resolved to a missing table")?
+            .clone();
+        let index_id = tx
+            .index
+            .index_registry()
+            .get_enabled(&tablet_index_name)
+            .with_context(|| format!("Index {index_name} is not enabled"))?
+            .id();
+        let aggregate = LiveAggregate {
+            index: tablet_index_name,
+            interval: index_range.compile(indexed_fields.clone())?,
+            fields: indexed_fields,
+            sum_field,
+        };
+        let snapshot_ts = tx.begin_timestamp();
+        let value = aggregate
+            .compute_value(self.table_iterator(snapshot_ts, 1000, None), index_id)
+            .await?;
+        self.subscriptions
+            .subscribe_aggregate(aggregate, *snapshot_ts, value)
+            .await
+    }
+
     fn streaming_export_table_filter(
         table_filter: &StreamingExportTableFilter,
         tablet_id: TabletId,
@@ -2029,12 +2204,28 @@ impl<RT: Runtime> Database<RT> {
         _identity: Identity,
         query: VectorSearch,
     ) -> anyhow::Result<(Vec<PublicVectorSearchQueryResult>, FunctionUsageStats)> {
+        let timer = vector_search_with_retries_timer();
+        let result = self
+            .retry_while_vector_indexes_load(|ts| self.vector_search_at_ts(query.clone(), ts))
+            .await;
+        timer.finish(result.is_ok());
+        result
+    }
+
+    /// Runs `f` at the latest timestamp, retrying at a new timestamp if it
+    /// fails because the in-memory vector indexes haven't loaded yet.
+    async fn retry_while_vector_indexes_load<T, Fut>(
+        &self,
+        f: impl Fn(RepeatableTimestamp) -> Fut,
+    ) -> anyhow::Result<T>
+    where
+        Fut: Future<Output = anyhow::Result<T>>,
+    {
         let mut last_error = None;
         let mut backoff = Backoff::new(INITIAL_VECTOR_BACKOFF, MAX_VECTOR_BACKOFF);
-        let timer = vector_search_with_retries_timer();
         while backoff.failures() < MAX_VECTOR_ATTEMPTS {
             let ts = self.now_ts_for_reads();
-            match self.vector_search_at_ts(query.clone(), ts).await {
+            match f(ts).await {
                 Err(e) => {
                     // If backend hasn't loaded the in-memory index yet, it returns
                     // overloaded. We want to retry those.
@@ -2051,19 +2242,13 @@ impl<RT: Runtime> Database<RT> {
                         self.runtime.wait(delay).await;
                         continue;
                     } else {
-                        timer.finish(false);
                         return Err(e);
                     }
                 },
-                Ok(result) => {
-                    timer.finish(true);
-                    return Ok(result);
-                },
+                Ok(result) => return Ok(result),
             }
         }
-        let last_error = last_error.expect("Exited vector_search() loop without any failure");
-        timer.finish(false);
-        Err(last_error)
+        Err(last_error.expect("Exited vector search retry loop without any failure"))
     }
 
     pub async fn vector_search_at_ts(
@@ -2118,6 +2303,63 @@ impl<RT: Runtime> Database<RT> {
         Ok((results, usage.gather_user_stats()))
     }
 
+    /// Runs a text search and a vector search over the same table at the same
+    /// timestamp, and fuses their results with [`fuse_rankings`].
+    pub async fn hybrid_search(
+        &self,
+        identity: Identity,
+        query: HybridSearch,
+    ) -> anyhow::Result<(Vec<HybridSearchResult>, FunctionUsageStats)> {
+        self.retry_while_vector_indexes_load(|ts| {
+            self.hybrid_search_at_ts(identity.clone(), query.clone(), ts)
+        })
+        .await
+    }
+
+    pub async fn hybrid_search_at_ts(
+        &self,
+        identity: Identity,
+        query: HybridSearch,
+        ts: RepeatableTimestamp,
+    ) -> anyhow::Result<(Vec<HybridSearchResult>, FunctionUsageStats)> {
+        let HybridSearch {
+            component_id,
+            text_query,
+            vector_search,
+            limit,
+            text_weight,
+            vector_weight,
+        } = query;
+        let usage = FunctionUsageTracker::new();
+        let mut rankings = vec![];
+        if vector_weight > 0.0 {
+            let (results, vector_usage) = self.vector_search_at_ts(vector_search, ts).await?;
+            usage.add(vector_usage);
+            rankings.push((vector_weight, results.into_iter().map(|r| r.id).collect()));
+        }
+        if text_weight > 0.0 {
+            let mut tx = self
+                .begin_with_repeatable_ts(identity, ts, usage.clone())
+                .await?;
+            let mut query_stream = DeveloperQuery::new_with_version(
+                &mut tx,
+                TableNamespace::from(component_id),
+                text_query,
+                None,
+                TableFilter::ExcludePrivateSystemTables,
+            )?;
+            let mut ids = vec![];
+            while ids.len() < limit as usize
+                && let Some(document) = query_stream.next(&mut tx, None).await?
+            {
+                ids.push(document.id());
+            }
+            rankings.push((text_weight, ids));
+        }
+        let results = fuse_rankings(rankings, limit as usize);
+        Ok((results, usage.gather_user_stats()))
+    }
+
     pub async fn search_with_compiled_query(
         &self,
         index_id: IndexId,
@@ -2247,3 +2489,12 @@ impl ConflictingReadWithWriteSource {
 pub fn unauthorized_error(op: &'static str) -> ErrorMetadata {
     ErrorMetadata::forbidden("Unauthorized", format!("Operation {op} not permitted"))
 }
+
+/// Fails with `unauthorized_error(op)` unless `identity` is an admin or the
+/// system.
+pub fn require_admin_or_system(identity: &Identity, op: &'static str) -> anyhow::Result<()> {
+    if !(identity.is_admin() || identity.is_system()) {
+        anyhow::bail!(unauthorized_error(op));
+    }
+    Ok(())
+}
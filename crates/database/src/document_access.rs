@@ -0,0 +1,74 @@
+//! Samples of the user documents transactions read, which are periodically
+//! written to `_document_access` to find data that isn't read anymore.
+//!
+//! Sampling happens in the read path, so it only buffers the document's id
+//! and the time in memory: writing a timestamp on every read would turn reads
+//! into writes. The buffer is process-wide because transactions are created
+//! both by the database and by the function runner.
+use std::{
+    collections::BTreeMap,
+    sync::LazyLock,
+};
+
+use common::{
+    knobs::{
+        DOCUMENT_ACCESS_MAX_BUFFERED,
+        DOCUMENT_ACCESS_SAMPLE_RATE,
+    },
+    runtime::{
+        Runtime,
+        UnixTimestamp,
+    },
+};
+use parking_lot::Mutex;
+use rand::Rng;
+use value::{
+    ResolvedDocumentId,
+    TableName,
+};
+
+static SAMPLED_READS: LazyLock<Mutex<BTreeMap<ResolvedDocumentId, UnixTimestamp>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+/// Records a read of the document `id` in `table_name` with probability
+/// `DOCUMENT_ACCESS_SAMPLE_RATE`.
+pub(crate) fn sample_document_read<RT: Runtime>(
+    runtime: &RT,
+    id: ResolvedDocumentId,
+    table_name: &TableName,
+) {
+    let sample_rate = *DOCUMENT_ACCESS_SAMPLE_RATE;
+    if sample_rate <= 0.0 || table_name.is_system() {
+        return;
+    }
+    if !runtime.rng().gen_bool(sample_rate.min(1.0)) {
+        return;
+    }
+    record_document_read(id, runtime.unix_timestamp());
+}
+
+fn record_document_read(id: ResolvedDocumentId, ts: UnixTimestamp) {
+    let mut sampled = SAMPLED_READS.lock();
+    // Reads of documents that are already buffered just move their timestamp
+    // forward, so the buffer is only full once that many distinct documents
+    // are waiting to be written.
+    if sampled.len() >= *DOCUMENT_ACCESS_MAX_BUFFERED && !sampled.contains_key(&id) {
+        return;
+    }
+    let last_read = sampled.entry(id).or_insert(ts);
+    *last_read = (*last_read).max(ts);
+}
+
+/// Takes the sampled reads that haven't been written yet, with the latest
+/// time each document was read.
+pub fn take_sampled_document_reads() -> BTreeMap<ResolvedDocumentId, UnixTimestamp> {
+    std::mem::take(&mut *SAMPLED_READS.lock())
+}
+
+/// Puts back sampled reads that couldn't be written, to retry them with the
+/// next batch.
+pub fn restore_sampled_document_reads(reads: BTreeMap<ResolvedDocumentId, UnixTimestamp>) {
+    for (id, ts) in reads {
+        record_document_read(id, ts);
+    }
+}
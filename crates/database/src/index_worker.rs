@@ -97,6 +97,7 @@ use value::{
 
 use crate::{
     metrics::{
+        log_index_backfill_starting,
         log_index_backfilled,
         log_num_indexes_to_backfill,
         log_worker_starting,
@@ -394,8 +395,10 @@ impl<RT: Runtime> IndexWorker<RT> {
             .map(|(index_id, (index_name, _))| (*index_id, index_name.clone()))
             .collect::<BTreeMap<_, _>>();
 
+        let table_name = table_mapping.tablet_to_name()(tablet_id)?;
         if !needs_backfill.is_empty() {
-            let table_name = table_mapping.tablet_to_name()(tablet_id)?;
+            let _status =
+                log_index_backfill_starting(&table_name, "backfill", needs_backfill.len());
             log::info!(
                 "Starting backfill of {} indexes for {table_name}: {needs_backfill:?}",
                 needs_backfill.len()
@@ -426,6 +429,7 @@ impl<RT: Runtime> IndexWorker<RT> {
             retention.insert(*index_id, (index_name, indexed_fields));
         }
         if let Some(min_begin_ts) = min_begin_ts {
+            let _status = log_index_backfill_starting(&table_name, "retention", retention.len());
             log::info!(
                 "Started running retention for {} indexes: {retention:?}",
                 retention.len()
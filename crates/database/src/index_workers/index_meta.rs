@@ -94,11 +94,14 @@ pub trait SearchIndex: Clone + Debug {
 
     fn estimate_document_size(schema: &Self::Schema, doc: &ResolvedDocument) -> u64;
 
+    /// Build a segment from `documents`. `reader` is used to look up the
+    /// previous revisions of documents in partial builds, and may be `None`
+    /// for complete ones.
     async fn build_disk_index(
         schema: &Self::Schema,
         index_path: &PathBuf,
         documents: DocumentStream<'_>,
-        reader: RepeatablePersistence,
+        reader: Option<RepeatablePersistence>,
         previous_segments: &mut Self::PreviousSegments,
         document_log_lower_bound: Option<Timestamp>,
         build_index_args: Self::BuildIndexArgs,
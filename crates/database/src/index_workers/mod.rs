@@ -1,5 +1,6 @@
 pub mod fast_forward;
 pub mod index_meta;
+pub mod offline_builder;
 pub mod retriable_worker;
 pub mod search_compactor;
 pub mod search_flusher;
@@ -0,0 +1,143 @@
+//! Building text and vector index segments outside of a deployment, from a
+//! table exported from it. The initial build of an index on a very large table
+//! can then happen out-of-band, with the resulting segments imported into the
+//! deployment, which only has to catch up on the writes since the export.
+use std::{
+    mem,
+    sync::Arc,
+};
+
+use common::{
+    bootstrap_model::index::IndexConfig,
+    document::ResolvedDocument,
+    runtime::Runtime,
+    types::{
+        RepeatableReason,
+        RepeatableTimestamp,
+    },
+};
+use futures::{
+    stream,
+    StreamExt,
+};
+use storage::Storage;
+use sync_types::Timestamp;
+use tempfile::TempDir;
+
+use crate::index_workers::{
+    index_meta::{
+        SearchIndex,
+        SearchOnDiskState,
+        SearchSnapshot,
+        SnapshotData,
+    },
+    search_flusher::MultipartBuildType,
+};
+
+/// Builds the segments of an index from the documents in its table as of
+/// `snapshot_ts`, uploading them to `storage` as they fill up.
+pub struct OfflineIndexBuilder<RT: Runtime, T: SearchIndex> {
+    runtime: RT,
+    storage: Arc<dyn Storage>,
+    developer_config: T::DeveloperConfig,
+    schema: T::Schema,
+    build_args: T::BuildIndexArgs,
+    snapshot_ts: RepeatableTimestamp,
+    segment_size_bytes: u64,
+    pending: Vec<ResolvedDocument>,
+    pending_size_bytes: u64,
+    segments: Vec<T::Segment>,
+}
+
+impl<RT: Runtime, T: SearchIndex + 'static> OfflineIndexBuilder<RT, T> {
+    pub(crate) fn with_build_args(
+        runtime: RT,
+        storage: Arc<dyn Storage>,
+        developer_config: T::DeveloperConfig,
+        build_args: T::BuildIndexArgs,
+        snapshot_ts: Timestamp,
+        segment_size_bytes: usize,
+    ) -> Self {
+        let schema = T::new_schema(&developer_config);
+        Self {
+            runtime,
+            storage,
+            developer_config,
+            schema,
+            build_args,
+            snapshot_ts: RepeatableTimestamp::new_validated(
+                snapshot_ts,
+                RepeatableReason::SnapshotExport,
+            ),
+            segment_size_bytes: segment_size_bytes as u64,
+            pending: vec![],
+            pending_size_bytes: 0,
+            segments: vec![],
+        }
+    }
+
+    /// Add a document from the table to the index.
+    pub async fn add_document(&mut self, document: ResolvedDocument) -> anyhow::Result<()> {
+        self.pending_size_bytes += T::estimate_document_size(&self.schema, &document);
+        self.pending.push(document);
+        // Split the index into segments of the same size the backend builds
+        // when backfilling, so it can compact them as usual.
+        if self.pending_size_bytes >= self.segment_size_bytes {
+            self.build_segment().await?;
+        }
+        Ok(())
+    }
+
+    async fn build_segment(&mut self) -> anyhow::Result<()> {
+        let documents = mem::take(&mut self.pending);
+        self.pending_size_bytes = 0;
+        if documents.is_empty() {
+            return Ok(());
+        }
+        let ts = *self.snapshot_ts;
+        let document_stream = stream::iter(
+            documents
+                .into_iter()
+                .map(move |doc| Ok((ts, doc.id_with_table_id(), Some(doc)))),
+        )
+        .boxed();
+        let index_path = TempDir::new()?;
+        // This is a complete build, so there are no previous segments to
+        // update and no deletes to look up.
+        let mut previous_segments =
+            T::download_previous_segments(self.storage.clone(), vec![]).await?;
+        let new_segment = T::build_disk_index(
+            &self.schema,
+            &index_path.path().to_owned(),
+            document_stream,
+            None,
+            &mut previous_segments,
+            None,
+            self.build_args.clone(),
+            MultipartBuildType::IncrementalComplete {
+                cursor: None,
+                backfill_snapshot_ts: self.snapshot_ts,
+            },
+        )
+        .await?;
+        if let Some(new_segment) = new_segment {
+            let segment =
+                T::upload_new_segment(&self.runtime, self.storage.clone(), new_segment).await?;
+            self.segments.push(segment);
+        }
+        Ok(())
+    }
+
+    /// Finish building the index, returning its config with the segments
+    /// built, which is backfilled as of the snapshot timestamp.
+    pub async fn finish(mut self) -> anyhow::Result<IndexConfig> {
+        self.build_segment().await?;
+        T::new_index_config(
+            self.developer_config,
+            SearchOnDiskState::Backfilled(SearchSnapshot {
+                ts: *self.snapshot_ts,
+                data: SnapshotData::MultiSegment(self.segments),
+            }),
+        )
+    }
+}
@@ -13,7 +13,10 @@ use common::{
         VECTOR_INDEX_SIZE_HARD_LIMIT,
     },
     runtime::Runtime,
-    types::TabletIndexName,
+    types::{
+        IndexId,
+        TabletIndexName,
+    },
 };
 use itertools::Itertools;
 use keybroker::Identity;
@@ -26,6 +29,10 @@ use tokio::task;
 use value::ResolvedDocumentId;
 
 use crate::{
+    bootstrap_model::index_workers::{
+        CompactionConfigOverrides,
+        IndexWorkerMetadataModel,
+    },
     index_workers::{
         index_meta::{
             BackfillState,
@@ -91,8 +98,14 @@ impl<RT: Runtime, T: SearchIndex> SearchIndexCompactor<RT, T> {
         for job in to_build {
             task::consume_budget().await;
 
+            let index_id = job.index_id.internal_id();
             let index_name = job.index_name.clone();
+            let is_manual = job.is_manual;
             let total_segments_compacted = self.build_one(job).await?;
+            if is_manual {
+                self.update_manual_compaction(index_id, total_segments_compacted, false)
+                    .await?;
+            }
             metrics.insert(index_name, total_segments_compacted);
         }
 
@@ -105,6 +118,7 @@ impl<RT: Runtime, T: SearchIndex> SearchIndexCompactor<RT, T> {
 
     async fn needs_compaction(&self) -> anyhow::Result<(Vec<CompactionJob<T>>, Token)> {
         let mut to_build = vec![];
+        let mut finished_manual_compactions = vec![];
         let mut tx = self.database.begin(Identity::system()).await?;
 
         // Skip compaction on empty tables.
@@ -114,6 +128,23 @@ impl<RT: Runtime, T: SearchIndex> SearchIndexCompactor<RT, T> {
                 continue;
             };
             let name = index_metadata.name;
+            let worker_metadata = IndexWorkerMetadataModel::new(&mut tx)
+                .get_metadata(index_id.internal_id())
+                .await?;
+            let mut compaction_config = self.config.clone();
+            let mut is_manual = false;
+            if let Some(worker_metadata) = worker_metadata {
+                let worker_metadata = worker_metadata.into_value().index_metadata;
+                compaction_config =
+                    compaction_config.with_overrides(worker_metadata.compaction_overrides());
+                if worker_metadata
+                    .manual_compaction()
+                    .is_some_and(|manual_compaction| manual_compaction.completed_ts.is_none())
+                {
+                    compaction_config = compaction_config.for_manual_compaction();
+                    is_manual = true;
+                }
+            }
 
             let maybe_segments_to_compact = match &config.on_disk_state {
                 SearchOnDiskState::Backfilling(BackfillState {
@@ -127,7 +158,7 @@ impl<RT: Runtime, T: SearchIndex> SearchIndexCompactor<RT, T> {
                         Self::find_segments_to_compact(
                             segments,
                             &config.developer_config,
-                            &self.config,
+                            &compaction_config,
                         )?
                     }
                 },
@@ -141,7 +172,7 @@ impl<RT: Runtime, T: SearchIndex> SearchIndexCompactor<RT, T> {
                 }) => Self::find_segments_to_compact(
                     segments,
                     &config.developer_config,
-                    &self.config,
+                    &compaction_config,
                 )?,
                 _ => continue,
             };
@@ -157,11 +188,52 @@ impl<RT: Runtime, T: SearchIndex> SearchIndexCompactor<RT, T> {
                     on_disk_state: config.on_disk_state,
                     segments_to_compact,
                     compaction_reason,
+                    compaction_config,
+                    is_manual,
                 };
                 to_build.push(job);
+            } else if is_manual {
+                finished_manual_compactions.push(index_id.internal_id());
             }
         }
-        Ok((to_build, tx.into_token()?))
+        let token = tx.into_token()?;
+        for index_id in finished_manual_compactions {
+            tracing::info!(
+                "Finished manual compaction of {:?} index {index_id}",
+                Self::search_type()
+            );
+            self.update_manual_compaction(index_id, 0, true).await?;
+        }
+        Ok((to_build, token))
+    }
+
+    /// Records progress on a compaction requested through
+    /// [`IndexWorkerMetadataModel::request_vector_compaction`].
+    async fn update_manual_compaction(
+        &self,
+        index_id: IndexId,
+        segments_compacted: u64,
+        completed: bool,
+    ) -> anyhow::Result<()> {
+        let mut tx = self.database.begin(Identity::system()).await?;
+        let now = *tx.begin_timestamp();
+        let mut model = IndexWorkerMetadataModel::new(&mut tx);
+        let Some(worker_metadata) = model.get_metadata(index_id).await? else {
+            return Ok(());
+        };
+        let (metadata_id, mut worker_metadata) = worker_metadata.into_id_and_value();
+        let Some(manual_compaction) = worker_metadata.index_metadata.mut_manual_compaction() else {
+            return Ok(());
+        };
+        manual_compaction.segments_compacted += segments_compacted;
+        if completed {
+            manual_compaction.completed_ts = Some(now);
+        }
+        model.replace_metadata(metadata_id, worker_metadata).await?;
+        self.database
+            .commit_with_write_source(tx, "search_index_compactor_manual_compaction")
+            .await?;
+        Ok(())
     }
 
     async fn build_one(&self, job: CompactionJob<T>) -> anyhow::Result<u64> {
@@ -187,7 +259,11 @@ impl<RT: Runtime, T: SearchIndex> SearchIndexCompactor<RT, T> {
         log_compaction_total_segments(total_compacted_segments, Self::search_type());
 
         let new_segment = self
-            .compact(&job.developer_config, segments_to_compact.clone())
+            .compact(
+                &job.developer_config,
+                &job.compaction_config,
+                segments_to_compact.clone(),
+            )
             .await?;
         let stats = new_segment.statistics()?;
 
@@ -363,6 +439,7 @@ impl<RT: Runtime, T: SearchIndex> SearchIndexCompactor<RT, T> {
     async fn compact(
         &self,
         developer_config: &T::DeveloperConfig,
+        compaction_config: &CompactionConfig,
         segments: Vec<T::Segment>,
     ) -> anyhow::Result<T::Segment> {
         let total_segment_size_bytes: u64 = segments
@@ -375,11 +452,11 @@ impl<RT: Runtime, T: SearchIndex> SearchIndexCompactor<RT, T> {
                 })
             })?;
         anyhow::ensure!(
-            total_segment_size_bytes <= self.config.max_segment_size_bytes,
+            total_segment_size_bytes <= compaction_config.max_segment_size_bytes,
             "Trying to compact {} segments with total size {} > our max size of {}, segments: {:?}",
             segments.len(),
             total_segment_size_bytes,
-            self.config.max_segment_size_bytes,
+            compaction_config.max_segment_size_bytes,
             segments
                 .iter()
                 .map(|segment| Self::format(segment, developer_config))
@@ -391,7 +468,7 @@ impl<RT: Runtime, T: SearchIndex> SearchIndexCompactor<RT, T> {
              segments: {:?}",
             segments.len(),
             total_segment_size_bytes,
-            self.config.max_segment_size_bytes,
+            compaction_config.max_segment_size_bytes,
             segments
                 .iter()
                 .map(|segment| Self::format(segment, developer_config))
@@ -435,6 +512,36 @@ impl Default for CompactionConfig {
     }
 }
 
+impl CompactionConfig {
+    /// Applies an index's overrides on top of the worker's defaults.
+    pub fn with_overrides(self, overrides: &CompactionConfigOverrides) -> Self {
+        Self {
+            max_deleted_percentage: overrides
+                .max_deleted_percentage
+                .unwrap_or(self.max_deleted_percentage),
+            small_segment_threshold_bytes: overrides
+                .small_segment_threshold_bytes
+                .unwrap_or(self.small_segment_threshold_bytes),
+            min_compaction_segments: overrides
+                .min_compaction_segments
+                .unwrap_or(self.min_compaction_segments),
+            max_segment_size_bytes: overrides
+                .max_segment_size_bytes
+                .unwrap_or(self.max_segment_size_bytes),
+        }
+    }
+
+    /// A requested compaction merges any two segments that fit together and
+    /// rewrites any large segment with deleted documents.
+    fn for_manual_compaction(self) -> Self {
+        Self {
+            max_deleted_percentage: 0.0,
+            min_compaction_segments: 2,
+            ..self
+        }
+    }
+}
+
 struct CompactionJob<T: SearchIndex> {
     index_id: ResolvedDocumentId,
     index_name: TabletIndexName,
@@ -442,4 +549,6 @@ struct CompactionJob<T: SearchIndex> {
     on_disk_state: SearchOnDiskState<T>,
     segments_to_compact: Vec<T::Segment>,
     compaction_reason: CompactionReason,
+    compaction_config: CompactionConfig,
+    is_manual: bool,
 }
@@ -575,7 +575,7 @@ impl<RT: Runtime, T: SearchIndex + 'static> SearchFlusher<RT, T> {
             &qdrant_schema,
             &index_path,
             documents,
-            persistence,
+            Some(persistence),
             &mut mutable_previous_segments,
             lower_bound_ts,
             build_index_args,
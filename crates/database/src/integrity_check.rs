@@ -0,0 +1,280 @@
+//! Checks the `_tables` and `_index` metadata when the database loads, before
+//! anything builds on it.
+//!
+//! Inconsistent metadata otherwise surfaces much later as an error deep in
+//! `TableModel` or `IndexModel` that doesn't say which table or index is to
+//! blame. Each problem found here names the table or index and how to repair
+//! it.
+//!
+//! Virtual tables don't have their own metadata: they share the table number
+//! of the system table they're backed by, so a table colliding with a virtual
+//! table is caught as a duplicate table number.
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
+    fmt,
+};
+
+use common::{
+    bootstrap_model::{
+        index::TabletIndexMetadata,
+        tables::{
+            TableMetadata,
+            TableState,
+        },
+    },
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+};
+use value::{
+    TableName,
+    TableNamespace,
+    TableNumber,
+    TabletId,
+};
+
+use crate::bootstrap_model::defaults::bootstrap_system_tables;
+
+const RESTORE_FROM_BACKUP: &str = "restore the deployment from a backup, or import a snapshot \
+                                   export into a new deployment with `npx convex import`";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IntegrityProblem {
+    pub problem: String,
+    pub repair: String,
+}
+
+impl fmt::Display for IntegrityProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}. To repair it, {}.", self.problem, self.repair)
+    }
+}
+
+fn problem(problem: String, repair: impl Into<String>) -> IntegrityProblem {
+    IntegrityProblem {
+        problem,
+        repair: repair.into(),
+    }
+}
+
+/// Parses the `_tables` and `_index` documents and checks them for problems,
+/// returning every problem found rather than stopping at the first one.
+pub fn check_metadata_integrity<'a>(
+    table_documents: impl Iterator<Item = &'a ResolvedDocument>,
+    index_documents: impl Iterator<Item = &'a ResolvedDocument>,
+) -> Vec<IntegrityProblem> {
+    let mut problems = vec![];
+    let mut tables = vec![];
+    for document in table_documents {
+        let tablet_id = TabletId(document.id().internal_id());
+        match ParsedDocument::<TableMetadata>::try_from(document.clone()) {
+            Ok(metadata) => tables.push((tablet_id, metadata.into_value())),
+            Err(e) => problems.push(problem(
+                format!("The `_tables` document for table {tablet_id} is invalid: {e:#}"),
+                RESTORE_FROM_BACKUP,
+            )),
+        }
+    }
+    let mut indexes = vec![];
+    for document in index_documents {
+        match TabletIndexMetadata::from_document(document.clone()) {
+            Ok(metadata) => indexes.push(metadata.into_value()),
+            Err(e) => problems.push(problem(
+                format!("The `_index` document {} is invalid: {e:#}", document.id()),
+                RESTORE_FROM_BACKUP,
+            )),
+        }
+    }
+    problems.extend(check_tables_and_indexes(&tables, &indexes));
+    problems
+}
+
+fn check_tables_and_indexes(
+    tables: &[(TabletId, TableMetadata)],
+    indexes: &[TabletIndexMetadata],
+) -> Vec<IntegrityProblem> {
+    let mut problems = vec![];
+    // Hidden tables are being imported, and can share a name and number with
+    // the active table they'll replace, so only active tables must be unique.
+    let active_tables: Vec<_> = tables
+        .iter()
+        .filter(|(_, metadata)| metadata.is_active())
+        .collect();
+
+    for system_table in bootstrap_system_tables() {
+        let exists = active_tables.iter().any(|(_, metadata)| {
+            metadata.namespace == TableNamespace::Global
+                && metadata.name == *system_table.table_name()
+        });
+        if !exists {
+            problems.push(problem(
+                format!(
+                    "Bootstrap system table {} is missing",
+                    system_table.table_name()
+                ),
+                RESTORE_FROM_BACKUP,
+            ));
+        }
+    }
+
+    let mut by_number: BTreeMap<(TableNamespace, TableNumber), &TableName> = BTreeMap::new();
+    let mut by_name: BTreeMap<(TableNamespace, &TableName), TabletId> = BTreeMap::new();
+    for (tablet_id, metadata) in &active_tables {
+        let namespace = metadata.namespace;
+        if let Some(existing) = by_number.insert((namespace, metadata.number), &metadata.name) {
+            let user_table = [existing, &metadata.name]
+                .into_iter()
+                .find(|name| !name.is_system());
+            let repair = match user_table {
+                Some(user_table) => format!(
+                    "export {user_table} with `npx convex export`, then import it again with `npx \
+                     convex import --replace` from a zip with its `_id` fields removed, so it's \
+                     given a new table number"
+                ),
+                None => RESTORE_FROM_BACKUP.to_string(),
+            };
+            problems.push(problem(
+                format!(
+                    "Tables {existing} and {} in {namespace:?} both have table number {}",
+                    metadata.name, metadata.number
+                ),
+                repair,
+            ));
+        }
+        if let Some(existing) = by_name.insert((namespace, &metadata.name), *tablet_id) {
+            problems.push(problem(
+                format!(
+                    "Tables {existing} and {tablet_id} in {namespace:?} are both named {}",
+                    metadata.name
+                ),
+                RESTORE_FROM_BACKUP,
+            ));
+        }
+    }
+
+    let table_names: BTreeMap<TabletId, &TableName> = tables
+        .iter()
+        .filter(|(_, metadata)| metadata.state != TableState::Deleting)
+        .map(|(tablet_id, metadata)| (*tablet_id, &metadata.name))
+        .collect();
+    let mut index_names = BTreeSet::new();
+    for index in indexes {
+        let tablet_id = *index.name.table();
+        let Some(table_name) = table_names.get(&tablet_id) else {
+            problems.push(problem(
+                format!(
+                    "Index {} is defined on table {tablet_id}, which doesn't exist",
+                    index.name.descriptor()
+                ),
+                RESTORE_FROM_BACKUP,
+            ));
+            continue;
+        };
+        let is_enabled = index.config.is_enabled();
+        if !index_names.insert((tablet_id, index.name.descriptor().clone(), is_enabled)) {
+            problems.push(problem(
+                format!(
+                    "Table {table_name} has two {} indexes named {}",
+                    if is_enabled { "enabled" } else { "backfilling" },
+                    index.name.descriptor()
+                ),
+                RESTORE_FROM_BACKUP,
+            ));
+        }
+    }
+    for (tablet_id, table_name) in &table_names {
+        let has_by_id = indexes.iter().any(|index| {
+            index.name.table() == tablet_id && index.name.is_by_id() && index.config.is_enabled()
+        });
+        if !has_by_id {
+            problems.push(problem(
+                format!("Table {table_name} ({tablet_id}) is missing its enabled `by_id` index"),
+                RESTORE_FROM_BACKUP,
+            ));
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{
+        bootstrap_model::{
+            index::{
+                database_index::IndexedFields,
+                IndexMetadata,
+                TabletIndexMetadata,
+            },
+            tables::TableMetadata,
+        },
+        testing::TestIdGenerator,
+        types::GenericIndexName,
+    };
+    use value::{
+        TableName,
+        TableNamespace,
+        TableNumber,
+        TabletId,
+    };
+
+    use super::check_tables_and_indexes;
+    use crate::bootstrap_model::defaults::{
+        bootstrap_system_tables,
+        DEFAULT_BOOTSTRAP_TABLE_NUMBERS,
+    };
+
+    fn by_id_index(tablet_id: TabletId) -> TabletIndexMetadata {
+        IndexMetadata::new_enabled(GenericIndexName::by_id(tablet_id), IndexedFields::by_id())
+    }
+
+    #[test]
+    fn test_check_tables_and_indexes() -> anyhow::Result<()> {
+        let mut id_generator = TestIdGenerator::new();
+        let mut tables: Vec<_> = bootstrap_system_tables()
+            .into_iter()
+            .map(|table| {
+                let name = table.table_name().clone();
+                let number = DEFAULT_BOOTSTRAP_TABLE_NUMBERS[&name];
+                (
+                    TabletId(id_generator.generate_internal()),
+                    TableMetadata::new(TableNamespace::Global, name, number),
+                )
+            })
+            .collect();
+        let mut indexes: Vec<_> = tables
+            .iter()
+            .map(|(tablet_id, _)| by_id_index(*tablet_id))
+            .collect();
+        assert_eq!(check_tables_and_indexes(&tables, &indexes), vec![]);
+
+        // A user table with the same number as a system table.
+        let users: TableName = "users".parse()?;
+        let users_tablet = TabletId(id_generator.generate_internal());
+        let number = tables[0].1.number;
+        tables.push((
+            users_tablet,
+            TableMetadata::new(TableNamespace::Global, users.clone(), number),
+        ));
+        let problems = check_tables_and_indexes(&tables, &indexes);
+        assert_eq!(problems.len(), 2, "{problems:?}");
+        assert!(problems[0].problem.contains("both have table number"));
+        assert!(problems[0].repair.contains("export users"));
+        assert!(problems[1]
+            .problem
+            .contains("missing its enabled `by_id` index"));
+
+        tables.last_mut().unwrap().1.number = TableNumber::try_from(10001)?;
+        indexes.push(by_id_index(users_tablet));
+        // An index on a table that doesn't exist.
+        let missing_tablet = TabletId(id_generator.generate_internal());
+        indexes.push(by_id_index(missing_tablet));
+        let problems = check_tables_and_indexes(&tables, &indexes);
+        assert_eq!(problems.len(), 1, "{problems:?}");
+        assert!(problems[0].problem.contains("which doesn't exist"));
+        Ok(())
+    }
+}
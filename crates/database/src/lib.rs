@@ -19,17 +19,22 @@
 #![feature(try_find)]
 
 mod bootstrap_model;
+pub mod commit_hooks;
 mod committer;
 mod database;
+pub mod document_access;
 mod execution_size;
 mod index_worker;
 mod index_workers;
+mod integrity_check;
+pub mod materialized_aggregates;
 mod metrics;
 pub mod patch;
 pub mod persistence_helpers;
 mod preloaded;
 pub mod query;
 pub mod reads;
+pub mod replication;
 mod retention;
 mod search_index_bootstrap;
 mod snapshot_manager;
@@ -60,6 +65,7 @@ pub use execution_size::FunctionExecutionSize;
 pub use index_worker::IndexWorker;
 pub use index_workers::{
     fast_forward::FastForwardIndexWorker,
+    offline_builder::OfflineIndexBuilder,
     search_worker::SearchIndexWorkers,
 };
 pub use patch::PatchValue;
@@ -116,7 +122,10 @@ pub use self::{
             LegacyIndexDiff,
         },
         index_workers::{
+            CompactionConfigOverrides,
+            IndexWorkerMetadataModel,
             IndexWorkerMetadataTable,
+            ManualCompaction,
             INDEX_DOC_ID_INDEX,
             INDEX_WORKER_METADATA_TABLE,
         },
@@ -142,6 +151,7 @@ pub use self::{
         user_facing::UserFacingModel,
     },
     database::{
+        require_admin_or_system,
         unauthorized_error,
         BootstrapMetadata,
         Database,
@@ -158,9 +168,31 @@ pub use self::{
         IndexWriter,
     },
     query::{
+        aggregate,
+        get_page,
+        hydrate_references,
+        search_facets,
         soft_data_limit,
+        Aggregate,
+        AggregateFunction,
+        AggregateGroup,
         DeveloperQuery,
+        GetPage,
+        GetPageRequest,
+        HybridSearch,
+        HybridSearchJson,
+        HybridSearchRequest,
+        HybridSearchResult,
+        IndexKeyBound,
+        IndexKeyValues,
         ResolvedQuery,
+        SearchFacetCount,
+        MAX_AGGREGATES,
+        MAX_AGGREGATE_GROUPS,
+        MAX_GET_PAGE_ROWS,
+        MAX_HYDRATED_DOCUMENTS,
+        MAX_HYDRATE_DEPTH,
+        MAX_SEARCH_FACETS,
     },
     retention::{
         latest_retention_min_snapshot_ts,
@@ -172,7 +204,12 @@ pub use self::{
         Snapshot,
         TableSummaries,
     },
-    subscription::Subscription,
+    subscription::{
+        LiveAggregate,
+        LiveAggregateSubscription,
+        LiveAggregateValue,
+        Subscription,
+    },
     table_iteration::TableIterator,
     table_summary::{
         TableSummary,
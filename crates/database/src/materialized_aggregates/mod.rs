@@ -0,0 +1,643 @@
+//! Materialized aggregates count and sum a table's documents per key, and are
+//! kept up to date by every write to the table in the same transaction. Their
+//! entries are indexed by key, count and sum, so reading one key's totals or
+//! the top keys of a leaderboard takes a few index lookups rather than a scan
+//! of the table.
+//!
+//! Aggregates created on a table that already has documents are backfilled by
+//! a worker, which counts the existing documents in creation time order.
+//! Writes only maintain an aggregate for documents the worker has already
+//! counted, so each document is counted exactly once.
+use std::sync::LazyLock;
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+        CREATION_TIME_FIELD_PATH,
+    },
+    query::{
+        IndexRange,
+        IndexRangeExpression,
+        Order,
+        Query,
+        QueryOperator,
+    },
+    runtime::Runtime,
+    types::IndexName,
+};
+use errors::ErrorMetadata;
+use value::{
+    ConvexValue,
+    FieldPath,
+    ResolvedDocumentId,
+    TableName,
+    TableNamespace,
+    TabletId,
+};
+
+use crate::{
+    defaults::{
+        system_index,
+        SystemIndex,
+        SystemTable,
+    },
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+
+mod types;
+
+pub use self::types::{
+    invalid_materialized_aggregate,
+    validate_materialized_aggregate_name,
+    MaterializedAggregateConfig,
+    MaterializedAggregateEntry,
+    MaterializedAggregateState,
+    MAX_MATERIALIZED_AGGREGATES_PER_TABLE,
+    MAX_MATERIALIZED_AGGREGATE_LIST_LIMIT,
+    MAX_MATERIALIZED_AGGREGATE_NAME_LEN,
+};
+
+pub static MATERIALIZED_AGGREGATES_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_materialized_aggregates"
+        .parse()
+        .expect("Invalid built-in materialized aggregates table")
+});
+
+pub static MATERIALIZED_AGGREGATE_ENTRIES_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_materialized_aggregate_entries"
+        .parse()
+        .expect("Invalid built-in materialized aggregate entries table")
+});
+
+pub static MATERIALIZED_AGGREGATES_BY_TABLE_AND_NAME: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&MATERIALIZED_AGGREGATES_TABLE, "by_table_id_and_name"));
+pub static MATERIALIZED_AGGREGATE_ENTRIES_BY_KEY: LazyLock<IndexName> = LazyLock::new(|| {
+    system_index(
+        &MATERIALIZED_AGGREGATE_ENTRIES_TABLE,
+        "by_aggregate_id_and_key",
+    )
+});
+pub static MATERIALIZED_AGGREGATE_ENTRIES_BY_COUNT: LazyLock<IndexName> = LazyLock::new(|| {
+    system_index(
+        &MATERIALIZED_AGGREGATE_ENTRIES_TABLE,
+        "by_aggregate_id_and_count",
+    )
+});
+pub static MATERIALIZED_AGGREGATE_ENTRIES_BY_SUM: LazyLock<IndexName> = LazyLock::new(|| {
+    system_index(
+        &MATERIALIZED_AGGREGATE_ENTRIES_TABLE,
+        "by_aggregate_id_and_sum",
+    )
+});
+
+static TABLE_ID_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "tableId".parse().expect("Invalid built-in field"));
+static NAME_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "name".parse().expect("Invalid built-in field"));
+static AGGREGATE_ID_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "aggregateId".parse().expect("Invalid built-in field"));
+static KEY_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "key".parse().expect("Invalid built-in field"));
+static COUNT_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "count".parse().expect("Invalid built-in field"));
+static SUM_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "sum".parse().expect("Invalid built-in field"));
+
+pub struct MaterializedAggregatesTable;
+impl SystemTable for MaterializedAggregatesTable {
+    fn table_name(&self) -> &'static TableName {
+        &MATERIALIZED_AGGREGATES_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![SystemIndex {
+            name: MATERIALIZED_AGGREGATES_BY_TABLE_AND_NAME.clone(),
+            fields: vec![TABLE_ID_FIELD.clone(), NAME_FIELD.clone()]
+                .try_into()
+                .unwrap(),
+        }]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<MaterializedAggregateConfig>::try_from(document).map(|_| ())
+    }
+}
+
+pub struct MaterializedAggregateEntriesTable;
+impl SystemTable for MaterializedAggregateEntriesTable {
+    fn table_name(&self) -> &'static TableName {
+        &MATERIALIZED_AGGREGATE_ENTRIES_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        [
+            (&*MATERIALIZED_AGGREGATE_ENTRIES_BY_KEY, &*KEY_FIELD),
+            (&*MATERIALIZED_AGGREGATE_ENTRIES_BY_COUNT, &*COUNT_FIELD),
+            (&*MATERIALIZED_AGGREGATE_ENTRIES_BY_SUM, &*SUM_FIELD),
+        ]
+        .into_iter()
+        .map(|(name, field)| SystemIndex {
+            name: name.clone(),
+            fields: vec![AGGREGATE_ID_FIELD.clone(), field.clone()]
+                .try_into()
+                .unwrap(),
+        })
+        .collect()
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<MaterializedAggregateEntry>::try_from(document).map(|_| ())
+    }
+}
+
+/// How to order a materialized aggregate's entries.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MaterializedAggregateOrderBy {
+    Key,
+    Count,
+    Sum,
+}
+
+impl MaterializedAggregateOrderBy {
+    fn index_name(self) -> IndexName {
+        match self {
+            Self::Key => MATERIALIZED_AGGREGATE_ENTRIES_BY_KEY.clone(),
+            Self::Count => MATERIALIZED_AGGREGATE_ENTRIES_BY_COUNT.clone(),
+            Self::Sum => MATERIALIZED_AGGREGATE_ENTRIES_BY_SUM.clone(),
+        }
+    }
+}
+
+pub fn materialized_aggregate_not_found(table: &TableName, name: &str) -> ErrorMetadata {
+    ErrorMetadata::not_found(
+        "MaterializedAggregateNotFound",
+        format!("Table {table} has no materialized aggregate named {name:?}"),
+    )
+}
+
+fn aggregate_id_value(id: ResolvedDocumentId) -> anyhow::Result<ConvexValue> {
+    Ok(ConvexValue::String(
+        id.internal_id().to_string().try_into()?,
+    ))
+}
+
+pub struct MaterializedAggregateModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> MaterializedAggregateModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    /// Creates an aggregate over a table's documents, which the backfill
+    /// worker then counts the table's existing documents into.
+    pub async fn create(
+        &mut self,
+        namespace: TableNamespace,
+        table: &TableName,
+        name: String,
+        key_field: Option<FieldPath>,
+        sum_field: Option<FieldPath>,
+    ) -> anyhow::Result<ResolvedDocumentId> {
+        validate_materialized_aggregate_name(&name)?;
+        anyhow::ensure!(
+            !table.is_system(),
+            invalid_materialized_aggregate(format!(
+                "Can't create a materialized aggregate on system table {table}"
+            ))
+        );
+        let Some(table_id) = self
+            .tx
+            .table_mapping()
+            .namespace(namespace)
+            .id_if_exists(table)
+        else {
+            anyhow::bail!(ErrorMetadata::not_found(
+                "TableNotFound",
+                format!("Table {table} not found"),
+            ));
+        };
+        let existing = self.table_aggregates(table_id).await?;
+        if let Some(aggregate) = existing.iter().find(|a| a.name == name) {
+            anyhow::bail!(invalid_materialized_aggregate(match aggregate.state {
+                MaterializedAggregateState::Deleting => format!(
+                    "Materialized aggregate {name:?} on {table} is still being deleted. Try again \
+                     once it's gone."
+                ),
+                _ => format!("Table {table} already has a materialized aggregate named {name:?}"),
+            }));
+        }
+        let live = existing
+            .iter()
+            .filter(|a| a.state != MaterializedAggregateState::Deleting)
+            .count();
+        anyhow::ensure!(
+            live < MAX_MATERIALIZED_AGGREGATES_PER_TABLE,
+            invalid_materialized_aggregate(format!(
+                "Table {table} already has {MAX_MATERIALIZED_AGGREGATES_PER_TABLE} materialized \
+                 aggregates."
+            ))
+        );
+        let config = MaterializedAggregateConfig {
+            table_id,
+            name,
+            key_field,
+            sum_field,
+            state: MaterializedAggregateState::Backfilling { cursor: None },
+        };
+        SystemMetadataModel::new_global(self.tx)
+            .insert(&MATERIALIZED_AGGREGATES_TABLE, config.try_into()?)
+            .await
+    }
+
+    /// Stops maintaining an aggregate. The backfill worker deletes its
+    /// entries in the background.
+    pub async fn delete(
+        &mut self,
+        namespace: TableNamespace,
+        table: &TableName,
+        name: &str,
+    ) -> anyhow::Result<()> {
+        let aggregate = self
+            .get(namespace, table, name)
+            .await?
+            .ok_or_else(|| materialized_aggregate_not_found(table, name))?;
+        self.mark_deleting(aggregate).await
+    }
+
+    pub async fn mark_deleting(
+        &mut self,
+        aggregate: ParsedDocument<MaterializedAggregateConfig>,
+    ) -> anyhow::Result<()> {
+        let (id, mut config) = aggregate.into_id_and_value();
+        config.state = MaterializedAggregateState::Deleting;
+        SystemMetadataModel::new_global(self.tx)
+            .replace(id, config.try_into()?)
+            .await?;
+        Ok(())
+    }
+
+    /// Finds an aggregate by name, unless it's being deleted.
+    pub async fn get(
+        &mut self,
+        namespace: TableNamespace,
+        table: &TableName,
+        name: &str,
+    ) -> anyhow::Result<Option<ParsedDocument<MaterializedAggregateConfig>>> {
+        let Some(table_id) = self
+            .tx
+            .table_mapping()
+            .namespace(namespace)
+            .id_if_exists(table)
+        else {
+            return Ok(None);
+        };
+        if !self.tables_exist() {
+            return Ok(None);
+        }
+        let query = Query::index_range(IndexRange {
+            index_name: MATERIALIZED_AGGREGATES_BY_TABLE_AND_NAME.clone(),
+            range: vec![
+                IndexRangeExpression::Eq(
+                    TABLE_ID_FIELD.clone(),
+                    ConvexValue::String(table_id.to_string().try_into()?).into(),
+                ),
+                IndexRangeExpression::Eq(
+                    NAME_FIELD.clone(),
+                    ConvexValue::String(name.try_into()?).into(),
+                ),
+            ],
+            order: Order::Asc,
+        });
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        let aggregate = query_stream
+            .expect_at_most_one(self.tx)
+            .await?
+            .map(ParsedDocument::<MaterializedAggregateConfig>::try_from)
+            .transpose()?;
+        Ok(aggregate.filter(|a| a.state != MaterializedAggregateState::Deleting))
+    }
+
+    /// Finds an aggregate that functions can read, which must have finished
+    /// backfilling.
+    pub async fn get_ready(
+        &mut self,
+        namespace: TableNamespace,
+        table: &TableName,
+        name: &str,
+    ) -> anyhow::Result<ParsedDocument<MaterializedAggregateConfig>> {
+        let aggregate = self
+            .get(namespace, table, name)
+            .await?
+            .ok_or_else(|| materialized_aggregate_not_found(table, name))?;
+        anyhow::ensure!(
+            aggregate.state == MaterializedAggregateState::Ready,
+            ErrorMetadata::bad_request(
+                "MaterializedAggregateBackfilling",
+                format!(
+                    "Materialized aggregate {name:?} on {table} is still counting the table's \
+                     existing documents. Try again once it's ready."
+                ),
+            )
+        );
+        Ok(aggregate)
+    }
+
+    /// All of the aggregates in the deployment, including the ones being
+    /// deleted.
+    pub async fn all(
+        &mut self,
+    ) -> anyhow::Result<Vec<ParsedDocument<MaterializedAggregateConfig>>> {
+        if !self.tables_exist() {
+            return Ok(vec![]);
+        }
+        let query = Query::full_table_scan(MATERIALIZED_AGGREGATES_TABLE.clone(), Order::Asc);
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        let mut aggregates = vec![];
+        while let Some(document) = query_stream.next(self.tx, None).await? {
+            aggregates.push(document.try_into()?);
+        }
+        Ok(aggregates)
+    }
+
+    /// The aggregates on a table, including the ones being deleted.
+    async fn table_aggregates(
+        &mut self,
+        table_id: TabletId,
+    ) -> anyhow::Result<Vec<ParsedDocument<MaterializedAggregateConfig>>> {
+        let query = Query::index_range(IndexRange {
+            index_name: MATERIALIZED_AGGREGATES_BY_TABLE_AND_NAME.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                TABLE_ID_FIELD.clone(),
+                ConvexValue::String(table_id.to_string().try_into()?).into(),
+            )],
+            order: Order::Asc,
+        });
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        let mut aggregates = vec![];
+        while let Some(document) = query_stream.next(self.tx, None).await? {
+            aggregates.push(document.try_into()?);
+        }
+        Ok(aggregates)
+    }
+
+    fn tables_exist(&mut self) -> bool {
+        self.tx
+            .table_mapping()
+            .namespace(TableNamespace::Global)
+            .id_if_exists(&MATERIALIZED_AGGREGATE_ENTRIES_TABLE)
+            .is_some()
+    }
+
+    /// The count and sum of the documents with `key`, or `None` if there
+    /// aren't any.
+    pub async fn entry(
+        &mut self,
+        aggregate_id: ResolvedDocumentId,
+        key: ConvexValue,
+    ) -> anyhow::Result<Option<MaterializedAggregateEntry>> {
+        self.entry_document(aggregate_id, key)
+            .await?
+            .map(|document| MaterializedAggregateEntry::try_from(document.into_value().0))
+            .transpose()
+    }
+
+    async fn entry_document(
+        &mut self,
+        aggregate_id: ResolvedDocumentId,
+        key: ConvexValue,
+    ) -> anyhow::Result<Option<ResolvedDocument>> {
+        let query = Query::index_range(IndexRange {
+            index_name: MATERIALIZED_AGGREGATE_ENTRIES_BY_KEY.clone(),
+            range: vec![
+                IndexRangeExpression::Eq(
+                    AGGREGATE_ID_FIELD.clone(),
+                    aggregate_id_value(aggregate_id)?.into(),
+                ),
+                IndexRangeExpression::Eq(KEY_FIELD.clone(), key.into()),
+            ],
+            order: Order::Asc,
+        });
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        query_stream.expect_at_most_one(self.tx).await
+    }
+
+    /// Up to `limit` of an aggregate's entries, in order of their key, count
+    /// or sum.
+    pub async fn entries(
+        &mut self,
+        aggregate_id: ResolvedDocumentId,
+        order_by: MaterializedAggregateOrderBy,
+        order: Order,
+        limit: usize,
+    ) -> anyhow::Result<Vec<MaterializedAggregateEntry>> {
+        let mut query = Query::index_range(IndexRange {
+            index_name: order_by.index_name(),
+            range: vec![IndexRangeExpression::Eq(
+                AGGREGATE_ID_FIELD.clone(),
+                aggregate_id_value(aggregate_id)?.into(),
+            )],
+            order,
+        });
+        query.operators.push(QueryOperator::Limit(limit));
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        let mut entries = vec![];
+        while let Some(document) = query_stream.next(self.tx, Some(limit)).await? {
+            entries.push(
+                ParsedDocument::<MaterializedAggregateEntry>::try_from(document)?.into_value(),
+            );
+        }
+        Ok(entries)
+    }
+
+    /// Counts the next batch of a backfilling aggregate's documents, and
+    /// marks it ready once there are none left. Returns whether the
+    /// aggregate is ready.
+    pub async fn backfill(
+        &mut self,
+        aggregate_id: ResolvedDocumentId,
+        batch_size: usize,
+    ) -> anyhow::Result<bool> {
+        let Some(aggregate) = self.tx.get(aggregate_id).await? else {
+            return Ok(true);
+        };
+        let (_, mut config) =
+            ParsedDocument::<MaterializedAggregateConfig>::try_from(aggregate)?.into_id_and_value();
+        let MaterializedAggregateState::Backfilling { cursor } = config.state else {
+            return Ok(true);
+        };
+        let table_mapping = self.tx.table_mapping();
+        let namespace = table_mapping.tablet_namespace(config.table_id)?;
+        let table_name = table_mapping.tablet_name(config.table_id)?;
+        let mut range = vec![];
+        if let Some(cursor) = cursor {
+            range.push(IndexRangeExpression::Gt(
+                CREATION_TIME_FIELD_PATH.clone(),
+                ConvexValue::Float64(cursor.into()),
+            ));
+        }
+        let query = Query::index_range(IndexRange {
+            index_name: IndexName::by_creation_time(table_name),
+            range,
+            order: Order::Asc,
+        });
+        let mut query_stream = ResolvedQuery::new(self.tx, namespace, query)?;
+        let mut new_cursor = cursor;
+        let mut counted = 0;
+        let mut done = true;
+        while let Some(document) = query_stream.next(self.tx, Some(batch_size)).await? {
+            let creation_time = document.creation_time();
+            // Only stop between documents with different creation times, so
+            // every document at or before the cursor has been counted.
+            if counted >= batch_size && creation_time != new_cursor {
+                done = false;
+                break;
+            }
+            if let Some((key, sum)) = config.contribution_unchecked(&document) {
+                self.add_to_entry(aggregate_id, key, 1, sum).await?;
+            }
+            new_cursor = creation_time;
+            counted += 1;
+        }
+        config.state = if done {
+            MaterializedAggregateState::Ready
+        } else {
+            MaterializedAggregateState::Backfilling { cursor: new_cursor }
+        };
+        SystemMetadataModel::new_global(self.tx)
+            .replace(aggregate_id, config.try_into()?)
+            .await?;
+        Ok(done)
+    }
+
+    /// Deletes the next batch of a deleted aggregate's entries, and the
+    /// aggregate itself once they're all gone. Returns whether the aggregate
+    /// is gone.
+    pub async fn delete_entries(
+        &mut self,
+        aggregate_id: ResolvedDocumentId,
+        batch_size: usize,
+    ) -> anyhow::Result<bool> {
+        let mut query = Query::index_range(IndexRange {
+            index_name: MATERIALIZED_AGGREGATE_ENTRIES_BY_KEY.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                AGGREGATE_ID_FIELD.clone(),
+                aggregate_id_value(aggregate_id)?.into(),
+            )],
+            order: Order::Asc,
+        });
+        query.operators.push(QueryOperator::Limit(batch_size));
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        let mut entries = vec![];
+        while let Some(document) = query_stream.next(self.tx, Some(batch_size)).await? {
+            entries.push(document.id());
+        }
+        let done = entries.len() < batch_size;
+        for id in entries {
+            SystemMetadataModel::new_global(self.tx).delete(id).await?;
+        }
+        if done && self.tx.get(aggregate_id).await?.is_some() {
+            SystemMetadataModel::new_global(self.tx)
+                .delete(aggregate_id)
+                .await?;
+        }
+        Ok(done)
+    }
+
+    /// Adds to the count and sum of the documents with `key`, deleting the
+    /// entry once no documents have the key.
+    ///
+    /// Entries are written directly rather than through
+    /// `SystemMetadataModel`, since they're maintained by mutations running
+    /// as any identity.
+    async fn add_to_entry(
+        &mut self,
+        aggregate_id: ResolvedDocumentId,
+        key: ConvexValue,
+        count: i64,
+        sum: f64,
+    ) -> anyhow::Result<()> {
+        match self.entry_document(aggregate_id, key.clone()).await? {
+            Some(old_document) => {
+                let mut entry =
+                    MaterializedAggregateEntry::try_from(old_document.value().0.clone())?;
+                let new_count = entry.count as i64 + count;
+                anyhow::ensure!(
+                    new_count >= 0,
+                    "Materialized aggregate {aggregate_id} has a negative count"
+                );
+                if new_count == 0 {
+                    self.tx
+                        .apply_validated_write(old_document.id(), Some(old_document), None)?;
+                } else {
+                    entry.count = new_count as u64;
+                    entry.sum += sum;
+                    let new_document = old_document.replace_value(entry.try_into()?)?;
+                    self.tx.apply_validated_write(
+                        old_document.id(),
+                        Some(old_document),
+                        Some(new_document),
+                    )?;
+                }
+            },
+            None => {
+                anyhow::ensure!(
+                    count > 0,
+                    "Materialized aggregate {aggregate_id} is missing an entry"
+                );
+                let entry = MaterializedAggregateEntry {
+                    aggregate_id: aggregate_id.internal_id(),
+                    key,
+                    count: count as u64,
+                    sum,
+                };
+                let table_id = self
+                    .tx
+                    .table_mapping()
+                    .namespace(TableNamespace::Global)
+                    .id(&MATERIALIZED_AGGREGATE_ENTRIES_TABLE)?;
+                let id = self.tx.id_generator.generate_resolved(table_id);
+                let creation_time = self.tx.next_creation_time.increment()?;
+                let document = ResolvedDocument::new(id, creation_time, entry.try_into()?)?;
+                self.tx.apply_validated_write(id, None, Some(document))?;
+            },
+        }
+        Ok(())
+    }
+}
+
+/// Updates the materialized aggregates on a document's table for a write,
+/// from `old_document` to `new_document`. Called for every write to a user
+/// table before the write is applied, so errors fail the write.
+pub(crate) async fn update_materialized_aggregates<RT: Runtime>(
+    tx: &mut Transaction<RT>,
+    id: ResolvedDocumentId,
+    old_document: Option<&ResolvedDocument>,
+    new_document: Option<&ResolvedDocument>,
+) -> anyhow::Result<()> {
+    if tx.table_mapping().is_system_tablet(id.tablet_id) {
+        return Ok(());
+    }
+    let mut model = MaterializedAggregateModel::new(tx);
+    if !model.tables_exist() {
+        return Ok(());
+    }
+    for aggregate in model.table_aggregates(id.tablet_id).await? {
+        let old = old_document.and_then(|document| aggregate.contribution(document));
+        let new = new_document.and_then(|document| aggregate.contribution(document));
+        if old == new {
+            continue;
+        }
+        if let Some((key, sum)) = old {
+            model.add_to_entry(aggregate.id(), key, -1, -sum).await?;
+        }
+        if let Some((key, sum)) = new {
+            model.add_to_entry(aggregate.id(), key, 1, sum).await?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,331 @@
+use std::{
+    collections::BTreeMap,
+    str::FromStr,
+};
+
+use common::document::{
+    CreationTime,
+    ResolvedDocument,
+};
+use errors::ErrorMetadata;
+use value::{
+    obj,
+    ConvexObject,
+    ConvexValue,
+    FieldName,
+    FieldPath,
+    InternalId,
+    TabletId,
+};
+
+/// The most materialized aggregates a table can have.
+pub const MAX_MATERIALIZED_AGGREGATES_PER_TABLE: usize = 16;
+
+/// The longest a materialized aggregate's name can be, in bytes.
+pub const MAX_MATERIALIZED_AGGREGATE_NAME_LEN: usize = 64;
+
+/// The most entries a function can list from a materialized aggregate at once.
+pub const MAX_MATERIALIZED_AGGREGATE_LIST_LIMIT: usize = 1000;
+
+/// A count and sum of a table's documents, grouped by the value of
+/// `key_field`, that's kept up to date by every write to the table.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct MaterializedAggregateConfig {
+    pub table_id: TabletId,
+    pub name: String,
+    /// Documents are grouped by their value in this field, and documents
+    /// missing it aren't counted. Without a key field, all of the table's
+    /// documents are counted under a single `null` key.
+    pub key_field: Option<FieldPath>,
+    /// The field whose values are summed. Values that aren't numbers, and
+    /// documents without the field, add nothing to the sum.
+    pub sum_field: Option<FieldPath>,
+    pub state: MaterializedAggregateState,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub enum MaterializedAggregateState {
+    /// The backfill worker is counting the documents that existed when the
+    /// aggregate was created, in creation time order. Writes keep the
+    /// aggregate up to date for documents created at or before `cursor`,
+    /// and the worker counts the rest when it reaches them.
+    Backfilling { cursor: Option<CreationTime> },
+    /// Every document in the table is counted.
+    Ready,
+    /// The aggregate was deleted, and the worker is deleting its entries.
+    /// Writes no longer update it.
+    Deleting,
+}
+
+impl MaterializedAggregateConfig {
+    /// Whether the aggregate is maintained for this document yet.
+    fn includes(&self, document: &ResolvedDocument) -> bool {
+        match self.state {
+            MaterializedAggregateState::Backfilling { cursor } => cursor.is_some_and(|cursor| {
+                document
+                    .creation_time()
+                    .is_some_and(|creation_time| creation_time <= cursor)
+            }),
+            MaterializedAggregateState::Ready => true,
+            MaterializedAggregateState::Deleting => false,
+        }
+    }
+
+    /// The key a document is counted under and what it adds to the sum, or
+    /// `None` if the document isn't counted.
+    pub fn contribution(&self, document: &ResolvedDocument) -> Option<(ConvexValue, f64)> {
+        if !self.includes(document) {
+            return None;
+        }
+        self.contribution_unchecked(document)
+    }
+
+    /// Like [`Self::contribution`], for documents the backfill worker is
+    /// counting for the first time.
+    pub(crate) fn contribution_unchecked(
+        &self,
+        document: &ResolvedDocument,
+    ) -> Option<(ConvexValue, f64)> {
+        let key = match self.key_field {
+            Some(ref key_field) => document.value().get_path(key_field)?.clone(),
+            None => ConvexValue::Null,
+        };
+        let sum = match self
+            .sum_field
+            .as_ref()
+            .and_then(|sum_field| document.value().get_path(sum_field))
+        {
+            Some(ConvexValue::Float64(f)) => *f,
+            Some(ConvexValue::Int64(i)) => *i as f64,
+            _ => 0.0,
+        };
+        Some((key, sum))
+    }
+}
+
+pub fn invalid_materialized_aggregate(msg: impl Into<String>) -> ErrorMetadata {
+    ErrorMetadata::bad_request("InvalidMaterializedAggregate", msg.into())
+}
+
+pub fn validate_materialized_aggregate_name(name: &str) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        !name.is_empty() && name.len() <= MAX_MATERIALIZED_AGGREGATE_NAME_LEN,
+        invalid_materialized_aggregate(format!(
+            "Materialized aggregate names must be between 1 and \
+             {MAX_MATERIALIZED_AGGREGATE_NAME_LEN} bytes long."
+        ))
+    );
+    anyhow::ensure!(
+        name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'),
+        invalid_materialized_aggregate(format!(
+            "Materialized aggregate name {name:?} can only contain letters, numbers and \
+             underscores."
+        ))
+    );
+    Ok(())
+}
+
+impl TryFrom<MaterializedAggregateConfig> for ConvexObject {
+    type Error = anyhow::Error;
+
+    fn try_from(value: MaterializedAggregateConfig) -> Result<Self, Self::Error> {
+        let mut fields: BTreeMap<FieldName, ConvexValue> = BTreeMap::new();
+        fields.insert(
+            "tableId".parse()?,
+            ConvexValue::String(value.table_id.to_string().try_into()?),
+        );
+        fields.insert("name".parse()?, ConvexValue::String(value.name.try_into()?));
+        if let Some(key_field) = value.key_field {
+            fields.insert("keyField".parse()?, key_field.try_into()?);
+        }
+        if let Some(sum_field) = value.sum_field {
+            fields.insert("sumField".parse()?, sum_field.try_into()?);
+        }
+        fields.insert(
+            "state".parse()?,
+            ConvexValue::Object(value.state.try_into()?),
+        );
+        fields.try_into()
+    }
+}
+
+impl TryFrom<ConvexObject> for MaterializedAggregateConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ConvexObject) -> Result<Self, Self::Error> {
+        let mut fields: BTreeMap<_, _> = value.into();
+        let table_id = match fields.remove("tableId") {
+            Some(ConvexValue::String(table_id)) => TabletId::from_str(&table_id)?,
+            _ => {
+                anyhow::bail!("Missing or invalid `tableId` field for MaterializedAggregateConfig")
+            },
+        };
+        let name = match fields.remove("name") {
+            Some(ConvexValue::String(name)) => name.into(),
+            _ => anyhow::bail!("Missing or invalid `name` field for MaterializedAggregateConfig"),
+        };
+        let key_field = fields
+            .remove("keyField")
+            .map(FieldPath::try_from)
+            .transpose()?;
+        let sum_field = fields
+            .remove("sumField")
+            .map(FieldPath::try_from)
+            .transpose()?;
+        let state = match fields.remove("state") {
+            Some(ConvexValue::Object(state)) => state.try_into()?,
+            _ => anyhow::bail!("Missing or invalid `state` field for MaterializedAggregateConfig"),
+        };
+        Ok(MaterializedAggregateConfig {
+            table_id,
+            name,
+            key_field,
+            sum_field,
+            state,
+        })
+    }
+}
+
+impl TryFrom<MaterializedAggregateState> for ConvexObject {
+    type Error = anyhow::Error;
+
+    fn try_from(value: MaterializedAggregateState) -> Result<Self, Self::Error> {
+        match value {
+            MaterializedAggregateState::Backfilling { cursor } => {
+                let mut fields: BTreeMap<FieldName, ConvexValue> = BTreeMap::new();
+                fields.insert(
+                    "type".parse()?,
+                    ConvexValue::String("backfilling".try_into()?),
+                );
+                if let Some(cursor) = cursor {
+                    fields.insert("cursor".parse()?, ConvexValue::Float64(cursor.into()));
+                }
+                fields.try_into()
+            },
+            MaterializedAggregateState::Ready => obj!("type" => "ready"),
+            MaterializedAggregateState::Deleting => obj!("type" => "deleting"),
+        }
+    }
+}
+
+impl TryFrom<ConvexObject> for MaterializedAggregateState {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ConvexObject) -> Result<Self, Self::Error> {
+        let mut fields: BTreeMap<_, _> = value.into();
+        let state_type = match fields.remove("type") {
+            Some(ConvexValue::String(state_type)) => state_type,
+            _ => anyhow::bail!("Missing or invalid `type` field for MaterializedAggregateState"),
+        };
+        Ok(match &*state_type {
+            "backfilling" => {
+                let cursor = match fields.remove("cursor") {
+                    Some(ConvexValue::Float64(cursor)) => Some(CreationTime::try_from(cursor)?),
+                    None => None,
+                    _ => anyhow::bail!("Invalid `cursor` field for MaterializedAggregateState"),
+                };
+                MaterializedAggregateState::Backfilling { cursor }
+            },
+            "ready" => MaterializedAggregateState::Ready,
+            "deleting" => MaterializedAggregateState::Deleting,
+            _ => anyhow::bail!("Invalid MaterializedAggregateState type {state_type}"),
+        })
+    }
+}
+
+/// The count and sum of the documents with one key in a materialized
+/// aggregate.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct MaterializedAggregateEntry {
+    pub aggregate_id: InternalId,
+    pub key: ConvexValue,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "1..=i64::MAX as u64")
+    )]
+    pub count: u64,
+    /// Sums are kept as floats, so sums of large integers can lose
+    /// precision.
+    #[cfg_attr(any(test, feature = "testing"), proptest(strategy = "-1e12..1e12"))]
+    pub sum: f64,
+}
+
+impl TryFrom<MaterializedAggregateEntry> for ConvexObject {
+    type Error = anyhow::Error;
+
+    fn try_from(value: MaterializedAggregateEntry) -> Result<Self, Self::Error> {
+        obj!(
+            "aggregateId" => ConvexValue::String(value.aggregate_id.to_string().try_into()?),
+            "key" => value.key,
+            "count" => ConvexValue::Int64(value.count.try_into()?),
+            "sum" => ConvexValue::Float64(value.sum),
+        )
+    }
+}
+
+impl TryFrom<ConvexObject> for MaterializedAggregateEntry {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ConvexObject) -> Result<Self, Self::Error> {
+        let mut fields: BTreeMap<_, _> = value.into();
+        let aggregate_id = match fields.remove("aggregateId") {
+            Some(ConvexValue::String(aggregate_id)) => InternalId::from_str(&aggregate_id)?,
+            _ => anyhow::bail!(
+                "Missing or invalid `aggregateId` field for MaterializedAggregateEntry"
+            ),
+        };
+        let key = fields
+            .remove("key")
+            .ok_or_else(|| anyhow::anyhow!("Missing `key` field for MaterializedAggregateEntry"))?;
+        let count = match fields.remove("count") {
+            Some(ConvexValue::Int64(count)) => count.try_into()?,
+            _ => anyhow::bail!("Missing or invalid `count` field for MaterializedAggregateEntry"),
+        };
+        let sum = match fields.remove("sum") {
+            Some(ConvexValue::Float64(sum)) => sum,
+            _ => anyhow::bail!("Missing or invalid `sum` field for MaterializedAggregateEntry"),
+        };
+        Ok(MaterializedAggregateEntry {
+            aggregate_id,
+            key,
+            count,
+            sum,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cmd_util::env::env_config;
+    use proptest::prelude::*;
+    use value::ConvexObject;
+
+    use super::{
+        MaterializedAggregateConfig,
+        MaterializedAggregateEntry,
+    };
+
+    proptest! {
+        #![proptest_config(
+            ProptestConfig { cases: 256 * env_config("CONVEX_PROPTEST_MULTIPLIER", 1), failure_persistence: None, ..ProptestConfig::default() }
+        )]
+        #[test]
+        fn test_config_roundtrip(v in any::<MaterializedAggregateConfig>()) {
+            let roundtripped = MaterializedAggregateConfig::try_from(
+                ConvexObject::try_from(v.clone()).unwrap()
+            ).unwrap();
+            assert_eq!(v, roundtripped);
+        }
+
+        #[test]
+        fn test_entry_roundtrip(v in any::<MaterializedAggregateEntry>()) {
+            let roundtripped = MaterializedAggregateEntry::try_from(
+                ConvexObject::try_from(v.clone()).unwrap()
+            ).unwrap();
+            assert_eq!(v, roundtripped);
+        }
+    }
+}
@@ -29,6 +29,7 @@ use prometheus::{
     VMHistogram,
     VMHistogramVec,
 };
+use value::TableName;
 
 use crate::{
     transaction::FinalTransaction,
@@ -61,6 +62,47 @@ pub fn log_index_backfilled() {
     log_counter(&INDEXES_BACKFILLED_TOTAL, 1);
 }
 
+/// Reports indexes on a table as being in a phase of their backfill until
+/// dropped.
+pub struct IndexBackfillStatus {
+    table: String,
+    phase: &'static str,
+}
+
+impl Drop for IndexBackfillStatus {
+    fn drop(&mut self) {
+        log_index_backfills_in_progress(&self.table, self.phase, 0);
+    }
+}
+
+register_convex_gauge!(
+    INDEX_BACKFILLS_IN_PROGRESS_TOTAL,
+    "Number of indexes being backfilled on each table, by backfill phase",
+    &["table", "phase"],
+);
+/// `phase` is "backfill" while index entries are written for existing
+/// documents, then "retention" while old entries are cleaned up.
+pub fn log_index_backfill_starting(
+    table: &TableName,
+    phase: &'static str,
+    num_indexes: usize,
+) -> IndexBackfillStatus {
+    let table = table.to_string();
+    log_index_backfills_in_progress(&table, phase, num_indexes);
+    IndexBackfillStatus { table, phase }
+}
+
+fn log_index_backfills_in_progress(table: &str, phase: &'static str, num_indexes: usize) {
+    log_gauge_with_labels(
+        &INDEX_BACKFILLS_IN_PROGRESS_TOTAL,
+        num_indexes as f64,
+        vec![
+            StaticMetricLabel::new("table", table.to_owned()),
+            StaticMetricLabel::new("phase", phase),
+        ],
+    )
+}
+
 register_convex_histogram!(
     DATABASE_WRITE_TX_READ_INTERVALS_TOTAL,
     "Number of read intervals in a write transaction"
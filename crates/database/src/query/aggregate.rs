@@ -0,0 +1,372 @@
+use std::{
+    collections::BTreeMap,
+    str::FromStr,
+};
+
+use common::{
+    bootstrap_model::index::IndexConfig,
+    query::{
+        Query,
+        QuerySource,
+    },
+    runtime::Runtime,
+    version::Version,
+};
+use errors::ErrorMetadata;
+use indexing::index_registry::index_not_found_error;
+use value::{
+    ConvexValue,
+    FieldPath,
+    TableNamespace,
+};
+
+use super::{
+    DeveloperQuery,
+    TableFilter,
+};
+use crate::{
+    IndexModel,
+    Transaction,
+};
+
+/// The most aggregates a single query can compute.
+pub const MAX_AGGREGATES: usize = 16;
+
+/// The most groups a grouped aggregation can return.
+pub const MAX_AGGREGATE_GROUPS: usize = 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AggregateFunction {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+}
+
+impl FromStr for AggregateFunction {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let function = match s {
+            "count" => Self::Count,
+            "sum" => Self::Sum,
+            "avg" => Self::Avg,
+            "min" => Self::Min,
+            "max" => Self::Max,
+            _ => anyhow::bail!(invalid_aggregate(format!(
+                "Unknown aggregate function {s:?}. Use one of \"count\", \"sum\", \"avg\", \
+                 \"min\" or \"max\"."
+            ))),
+        };
+        Ok(function)
+    }
+}
+
+/// One value computed over the documents in each group.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Aggregate {
+    pub function: AggregateFunction,
+    /// The field to aggregate. Only `Count` can leave it out, which counts
+    /// every document rather than just the ones with the field.
+    pub field: Option<FieldPath>,
+}
+
+/// The aggregates computed over the documents with `key` in the group-by
+/// field. `key` is `None` for documents missing the field, and for the single
+/// group of an ungrouped aggregation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AggregateGroup {
+    pub key: Option<ConvexValue>,
+    pub values: BTreeMap<String, ConvexValue>,
+}
+
+fn invalid_aggregate(msg: impl Into<String>) -> ErrorMetadata {
+    ErrorMetadata::bad_request("InvalidAggregate", msg.into())
+}
+
+enum Accumulator {
+    Count(u64),
+    Sum {
+        int_sum: i64,
+        float_sum: f64,
+        has_float: bool,
+    },
+    Avg {
+        sum: f64,
+        count: u64,
+    },
+    Min(Option<ConvexValue>),
+    Max(Option<ConvexValue>),
+}
+
+impl Accumulator {
+    fn new(function: AggregateFunction) -> Self {
+        match function {
+            AggregateFunction::Count => Self::Count(0),
+            AggregateFunction::Sum => Self::Sum {
+                int_sum: 0,
+                float_sum: 0.0,
+                has_float: false,
+            },
+            AggregateFunction::Avg => Self::Avg { sum: 0.0, count: 0 },
+            AggregateFunction::Min => Self::Min(None),
+            AggregateFunction::Max => Self::Max(None),
+        }
+    }
+
+    /// Adds a document's value, which is `None` if it's missing the field.
+    /// Sums and averages skip values that aren't numbers.
+    fn add(&mut self, name: &str, value: Option<&ConvexValue>) -> anyhow::Result<()> {
+        match (self, value) {
+            (_, None) => (),
+            (Self::Count(count), Some(_)) => *count += 1,
+            (
+                Self::Sum {
+                    int_sum,
+                    float_sum,
+                    has_float,
+                },
+                Some(value),
+            ) => match value {
+                ConvexValue::Int64(i) => {
+                    *int_sum = int_sum.checked_add(*i).ok_or_else(|| {
+                        invalid_aggregate(format!("The sum {name} overflowed a 64-bit integer."))
+                    })?;
+                },
+                ConvexValue::Float64(f) => {
+                    *float_sum += f;
+                    *has_float = true;
+                },
+                _ => (),
+            },
+            (Self::Avg { sum, count }, Some(value)) => match value {
+                ConvexValue::Int64(i) => {
+                    *sum += *i as f64;
+                    *count += 1;
+                },
+                ConvexValue::Float64(f) => {
+                    *sum += f;
+                    *count += 1;
+                },
+                _ => (),
+            },
+            (Self::Min(min), Some(value)) => {
+                if min.as_ref().map_or(true, |min| value < min) {
+                    *min = Some(value.clone());
+                }
+            },
+            (Self::Max(max), Some(value)) => {
+                if max.as_ref().map_or(true, |max| value > max) {
+                    *max = Some(value.clone());
+                }
+            },
+        }
+        Ok(())
+    }
+
+    /// Sums of only integers stay integers, and any float makes the sum a
+    /// float. Averages, minimums and maximums of no values are null.
+    fn finish(self) -> ConvexValue {
+        match self {
+            Self::Count(count) => ConvexValue::Float64(count as f64),
+            Self::Sum {
+                int_sum,
+                float_sum,
+                has_float,
+            } => {
+                if has_float {
+                    ConvexValue::Float64(float_sum + int_sum as f64)
+                } else {
+                    ConvexValue::Int64(int_sum)
+                }
+            },
+            Self::Avg { count: 0, .. } => ConvexValue::Null,
+            Self::Avg { sum, count } => ConvexValue::Float64(sum / count as f64),
+            Self::Min(value) | Self::Max(value) => value.unwrap_or(ConvexValue::Null),
+        }
+    }
+}
+
+/// Computes `aggregates` over a query's results without returning the
+/// documents themselves, optionally grouping them by their value in
+/// `group_by`.
+///
+/// Grouped aggregations must use an index that includes the group-by field,
+/// so the documents are read from index ranges and the transaction's read set
+/// only tracks those ranges. Groups are ordered by their key, with documents
+/// missing the field first.
+pub async fn aggregate<RT: Runtime>(
+    tx: &mut Transaction<RT>,
+    namespace: TableNamespace,
+    query: Query,
+    group_by: Option<FieldPath>,
+    aggregates: BTreeMap<String, Aggregate>,
+    version: Option<Version>,
+    table_filter: TableFilter,
+) -> anyhow::Result<Vec<AggregateGroup>> {
+    anyhow::ensure!(
+        !aggregates.is_empty() && aggregates.len() <= MAX_AGGREGATES,
+        invalid_aggregate(format!(
+            "A query must compute between 1 and {MAX_AGGREGATES} aggregates."
+        ))
+    );
+    for (name, aggregate) in &aggregates {
+        anyhow::ensure!(
+            aggregate.field.is_some() || aggregate.function == AggregateFunction::Count,
+            invalid_aggregate(format!("Aggregate {name} must have a field to aggregate."))
+        );
+    }
+    match query.source {
+        QuerySource::Search(_) => anyhow::bail!(invalid_aggregate(
+            "Queries using `withSearchIndex` can't be aggregated."
+        )),
+        QuerySource::FullTableScan(_) if group_by.is_some() => anyhow::bail!(invalid_aggregate(
+            "Grouped aggregations must use `withIndex` with an index on the group-by field."
+        )),
+        QuerySource::IndexRange(ref index_range) => {
+            if let Some(ref group_by) = group_by {
+                let index_name = &index_range.index_name;
+                let Some(metadata) =
+                    IndexModel::new(tx).enabled_index_metadata(namespace, index_name)?
+                else {
+                    anyhow::bail!(index_not_found_error(index_name));
+                };
+                let IndexConfig::Database {
+                    ref developer_config,
+                    ..
+                } = metadata.config
+                else {
+                    anyhow::bail!(index_not_found_error(index_name));
+                };
+                anyhow::ensure!(
+                    developer_config.fields.contains(group_by),
+                    invalid_aggregate(format!(
+                        "Can't group by {group_by}, which isn't one of the fields of index \
+                         {index_name}."
+                    ))
+                );
+            }
+        },
+        QuerySource::FullTableScan(_) => (),
+    }
+
+    let mut groups: BTreeMap<Option<ConvexValue>, Vec<Accumulator>> = BTreeMap::new();
+    let new_accumulators = || {
+        aggregates
+            .values()
+            .map(|aggregate| Accumulator::new(aggregate.function))
+            .collect::<Vec<_>>()
+    };
+    if group_by.is_none() {
+        // An ungrouped aggregation always has a result, even over no documents.
+        groups.insert(None, new_accumulators());
+    }
+    let mut query_stream =
+        DeveloperQuery::new_with_version(tx, namespace, query, version, table_filter)?;
+    while let Some(document) = query_stream.next(tx, None).await? {
+        let key = group_by
+            .as_ref()
+            .and_then(|field| document.value().get_path(field).cloned());
+        if !groups.contains_key(&key) {
+            anyhow::ensure!(
+                groups.len() < MAX_AGGREGATE_GROUPS,
+                invalid_aggregate(format!(
+                    "Aggregation has more than {MAX_AGGREGATE_GROUPS} groups. Narrow the index \
+                     range, or group by a field with fewer distinct values."
+                ))
+            );
+        }
+        let accumulators = groups.entry(key).or_insert_with(new_accumulators);
+        for ((name, aggregate), accumulator) in aggregates.iter().zip(accumulators.iter_mut()) {
+            let value = match aggregate.field {
+                Some(ref field) => document.value().get_path(field),
+                None => Some(&ConvexValue::Null),
+            };
+            accumulator.add(name, value)?;
+        }
+    }
+    Ok(groups
+        .into_iter()
+        .map(|(key, accumulators)| AggregateGroup {
+            key,
+            values: aggregates
+                .keys()
+                .cloned()
+                .zip(accumulators.into_iter().map(Accumulator::finish))
+                .collect(),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use value::ConvexValue;
+
+    use super::{
+        Accumulator,
+        AggregateFunction,
+    };
+
+    fn accumulate(function: AggregateFunction, values: Vec<ConvexValue>) -> ConvexValue {
+        let mut accumulator = Accumulator::new(function);
+        for value in &values {
+            accumulator.add("test", Some(value)).unwrap();
+        }
+        accumulator.finish()
+    }
+
+    #[test]
+    fn test_accumulators() {
+        let values = vec![
+            ConvexValue::Float64(2.0),
+            ConvexValue::Float64(0.5),
+            ConvexValue::String("skipped".try_into().unwrap()),
+        ];
+        assert_eq!(
+            accumulate(AggregateFunction::Count, values.clone()),
+            ConvexValue::Float64(3.0)
+        );
+        assert_eq!(
+            accumulate(AggregateFunction::Sum, values.clone()),
+            ConvexValue::Float64(2.5)
+        );
+        assert_eq!(
+            accumulate(AggregateFunction::Avg, values.clone()),
+            ConvexValue::Float64(1.25)
+        );
+        assert_eq!(
+            accumulate(AggregateFunction::Min, values.clone()),
+            ConvexValue::Float64(0.5)
+        );
+        // Min and max compare values of different types by the order of
+        // their types, where strings sort after numbers.
+        assert_eq!(
+            accumulate(AggregateFunction::Max, values),
+            ConvexValue::String("skipped".try_into().unwrap())
+        );
+        assert_eq!(
+            accumulate(AggregateFunction::Avg, vec![]),
+            ConvexValue::Null
+        );
+    }
+
+    #[test]
+    fn test_integer_sums() {
+        // Sums of only integers stay integers.
+        assert_eq!(
+            accumulate(
+                AggregateFunction::Sum,
+                vec![ConvexValue::Int64(1), ConvexValue::Int64(2)]
+            ),
+            ConvexValue::Int64(3)
+        );
+        let mut accumulator = Accumulator::new(AggregateFunction::Sum);
+        accumulator
+            .add("test", Some(&ConvexValue::Int64(i64::MAX)))
+            .unwrap();
+        assert!(accumulator
+            .add("test", Some(&ConvexValue::Int64(1)))
+            .is_err());
+    }
+}
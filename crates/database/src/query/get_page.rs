@@ -0,0 +1,198 @@
+use std::marker::PhantomData;
+
+use common::{
+    document::DeveloperDocument,
+    interval::{
+        BinaryKey,
+        End,
+        Interval,
+        Start,
+    },
+    query::Order,
+    runtime::Runtime,
+    types::IndexName,
+    version::Version,
+};
+use errors::ErrorMetadata;
+use value::{
+    values_to_bytes,
+    ConvexValue,
+    TableNamespace,
+};
+
+use super::{
+    index_range::{
+        CursorInterval,
+        IndexRange,
+    },
+    DeveloperQuery,
+    QueryNode,
+    TableFilter,
+    MAX_QUERY_FETCH,
+};
+use crate::{
+    IndexModel,
+    Transaction,
+};
+
+/// The most documents a single page can have.
+pub const MAX_GET_PAGE_ROWS: usize = MAX_QUERY_FETCH;
+
+/// The values of a document's indexed fields, in index order and ending with
+/// `_id`. `None` is a field the document doesn't have.
+pub type IndexKeyValues = Vec<Option<ConvexValue>>;
+
+/// One end of a range of an index. `key` is a prefix of the index's fields,
+/// and the bound includes or excludes every document whose index key starts
+/// with it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IndexKeyBound {
+    pub key: IndexKeyValues,
+    pub inclusive: bool,
+}
+
+pub struct GetPageRequest {
+    pub index_name: IndexName,
+    /// The bound to start reading from in index order, regardless of
+    /// `order`. Leave it out to start at the beginning of the index.
+    pub start: Option<IndexKeyBound>,
+    pub end: Option<IndexKeyBound>,
+    pub order: Order,
+    pub max_rows: usize,
+}
+
+#[derive(Debug)]
+pub struct GetPage {
+    pub page: Vec<DeveloperDocument>,
+    /// The index key of each document in `page`. Pass the last one as an
+    /// exclusive bound to read the next page.
+    pub index_keys: Vec<IndexKeyValues>,
+    /// Whether there are more documents in the range after the page.
+    pub has_more: bool,
+}
+
+fn invalid_get_page(msg: impl Into<String>) -> ErrorMetadata {
+    ErrorMetadata::bad_request("InvalidGetPage", msg.into())
+}
+
+/// Reads a page of an index between two index keys, rather than from a
+/// cursor. Since the bounds are plain values, callers can keep them between
+/// requests, and read any page without reading the pages before it.
+///
+/// The transaction's read set covers the range up to and including the first
+/// document after the page, so `has_more` is invalidated along with the page.
+pub async fn get_page<RT: Runtime>(
+    tx: &mut Transaction<RT>,
+    namespace: TableNamespace,
+    request: GetPageRequest,
+    version: Option<Version>,
+    table_filter: TableFilter,
+) -> anyhow::Result<GetPage> {
+    let GetPageRequest {
+        index_name,
+        start,
+        end,
+        order,
+        max_rows,
+    } = request;
+    anyhow::ensure!(
+        max_rows > 0 && max_rows <= MAX_GET_PAGE_ROWS,
+        invalid_get_page(format!(
+            "A page must have between 1 and {MAX_GET_PAGE_ROWS} documents."
+        ))
+    );
+    let stable_index_name =
+        IndexModel::new(tx).stable_index_name(namespace, &index_name, table_filter)?;
+    let indexed_fields = IndexModel::new(tx).indexed_fields(&stable_index_name, &index_name)?;
+    let num_fields = indexed_fields.iter_with_id().count();
+    for bound in [&start, &end].into_iter().flatten() {
+        anyhow::ensure!(
+            bound.key.len() <= num_fields,
+            invalid_get_page(format!(
+                "Index key has {} values, but {index_name} only has {num_fields} fields.",
+                bound.key.len()
+            ))
+        );
+    }
+
+    let start = match start {
+        None => Start::Included(BinaryKey::min()),
+        Some(IndexKeyBound {
+            key,
+            inclusive: true,
+        }) => Start::Included(values_to_bytes(&key).into()),
+        Some(IndexKeyBound {
+            key,
+            inclusive: false,
+        }) => match End::after_prefix(&values_to_bytes(&key).into()) {
+            End::Excluded(after_key) => Start::Included(after_key),
+            // Nothing sorts after the bound.
+            End::Unbounded => {
+                return Ok(GetPage {
+                    page: vec![],
+                    index_keys: vec![],
+                    has_more: false,
+                })
+            },
+        },
+    };
+    let end = match end {
+        None => End::Unbounded,
+        Some(IndexKeyBound {
+            key,
+            inclusive: true,
+        }) => End::after_prefix(&values_to_bytes(&key).into()),
+        Some(IndexKeyBound {
+            key,
+            inclusive: false,
+        }) => End::Excluded(values_to_bytes(&key).into()),
+    };
+
+    let mut query = DeveloperQuery::<RT> {
+        root: QueryNode::IndexRange(IndexRange::new(
+            namespace,
+            stable_index_name,
+            index_name,
+            Interval { start, end },
+            order,
+            indexed_fields.clone(),
+            CursorInterval {
+                curr_exclusive: None,
+                end_inclusive: None,
+            },
+            None,
+            None,
+            false,
+            version,
+        )),
+        query_fingerprint: None,
+        table_number: None,
+        end_cursor: None,
+        _marker: PhantomData,
+    };
+    let mut page = vec![];
+    let mut index_keys = vec![];
+    while page.len() < max_rows {
+        // Fetch one more than the page so we know if there are more.
+        let Some(document) = query.next(tx, Some(max_rows - page.len() + 1)).await? else {
+            return Ok(GetPage {
+                page,
+                index_keys,
+                has_more: false,
+            });
+        };
+        index_keys.push(
+            indexed_fields
+                .iter_with_id()
+                .map(|field| document.value().get_path(field).cloned())
+                .collect(),
+        );
+        page.push(document);
+    }
+    let has_more = query.next(tx, Some(1)).await?.is_some();
+    Ok(GetPage {
+        page,
+        index_keys,
+        has_more,
+    })
+}
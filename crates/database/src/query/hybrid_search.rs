@@ -0,0 +1,216 @@
+use std::collections::BTreeMap;
+
+use common::{
+    components::ComponentId,
+    query::{
+        Query,
+        QuerySource,
+    },
+};
+use errors::ErrorMetadata;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::{
+    json,
+    Value as JsonValue,
+};
+use value::id_v6::DeveloperDocumentId;
+use vector::{
+    VectorSearch,
+    VectorSearchJson,
+    DEFAULT_VECTOR_LIMIT,
+    MAX_VECTOR_RESULTS,
+};
+
+/// The `k` in reciprocal rank fusion, which a document's rank in each search
+/// is offset by. Larger values flatten the difference between the top results
+/// of a search and the ones just below them.
+pub const HYBRID_SEARCH_RRF_K: f32 = 60.0;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HybridSearchRequest {
+    pub query: JsonValue,
+}
+
+/// A text search and a vector search over the same table, whose results are
+/// fused into one ranking with weighted reciprocal rank fusion.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HybridSearch {
+    pub component_id: ComponentId,
+    /// A query with a `Search` source and no operators.
+    pub text_query: Query,
+    pub vector_search: VectorSearch,
+    /// How many fused results to return. Each search contributes at most this
+    /// many candidates.
+    pub limit: u32,
+    /// How much each search contributes to a result's score. A search with a
+    /// weight of zero isn't run.
+    pub text_weight: f32,
+    pub vector_weight: f32,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HybridSearchJson {
+    component_id: Option<String>,
+    text_query: JsonValue,
+    vector_query: VectorSearchJson,
+    limit: Option<u32>,
+    text_weight: Option<f32>,
+    vector_weight: Option<f32>,
+}
+
+impl HybridSearchJson {
+    /// Inject the component_id into the [HybridSearchJson] and its vector
+    /// query, like [`VectorSearchJson::insert_component_id`].
+    pub fn insert_component_id(&mut self, component_id: ComponentId) {
+        self.component_id = component_id.serialize_to_string();
+        self.vector_query.insert_component_id(component_id);
+    }
+}
+
+fn invalid_hybrid_search(msg: impl Into<String>) -> ErrorMetadata {
+    ErrorMetadata::bad_request("InvalidHybridSearch", msg.into())
+}
+
+impl TryFrom<JsonValue> for HybridSearch {
+    type Error = anyhow::Error;
+
+    fn try_from(value: JsonValue) -> Result<Self, Self::Error> {
+        let search: HybridSearchJson = serde_json::from_value(value)?;
+        let component_id = ComponentId::deserialize_from_string(search.component_id.as_deref())?;
+        let text_query = Query::try_from(search.text_query)?;
+        let QuerySource::Search(ref text_search) = text_query.source else {
+            anyhow::bail!(invalid_hybrid_search(
+                "The text query of a hybrid search must use a search index."
+            ));
+        };
+        anyhow::ensure!(
+            text_query.operators.is_empty(),
+            invalid_hybrid_search(
+                "The text query of a hybrid search can't have filters or a limit."
+            )
+        );
+        let mut vector_search = VectorSearch::try_from(serde_json::to_value(search.vector_query)?)?;
+        anyhow::ensure!(
+            text_search.table == *vector_search.index_name.table(),
+            invalid_hybrid_search(format!(
+                "Search index {} and vector index {} must be on the same table.",
+                text_search.index_name, vector_search.index_name
+            ))
+        );
+
+        let limit = search.limit.unwrap_or(DEFAULT_VECTOR_LIMIT);
+        anyhow::ensure!(
+            limit >= 1 && limit as usize <= MAX_VECTOR_RESULTS,
+            invalid_hybrid_search(format!(
+                "Hybrid searches must fetch between 1 and {MAX_VECTOR_RESULTS} results, requested \
+                 {limit}."
+            ))
+        );
+        vector_search.limit = Some(limit);
+
+        let text_weight = search.text_weight.unwrap_or(1.0);
+        let vector_weight = search.vector_weight.unwrap_or(1.0);
+        anyhow::ensure!(
+            [text_weight, vector_weight]
+                .iter()
+                .all(|weight| weight.is_finite() && *weight >= 0.0)
+                && (text_weight > 0.0 || vector_weight > 0.0),
+            invalid_hybrid_search(
+                "`textWeight` and `vectorWeight` must be non-negative numbers, and at least one \
+                 must be positive."
+            )
+        );
+
+        Ok(Self {
+            component_id,
+            text_query,
+            vector_search,
+            limit,
+            text_weight,
+            vector_weight,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct HybridSearchResult {
+    pub id: DeveloperDocumentId,
+    pub score: f32,
+}
+
+impl From<HybridSearchResult> for JsonValue {
+    fn from(value: HybridSearchResult) -> Self {
+        json!({
+            "_id": String::from(value.id),
+            "_score": value.score,
+        })
+    }
+}
+
+/// Fuses rankings of documents, each ordered from best to worst match, with
+/// weighted reciprocal rank fusion: a document scores `weight / (k + rank)`
+/// for each ranking it appears in, with ranks starting at 1. Returns the top
+/// `limit` documents by their total score.
+pub fn fuse_rankings(
+    rankings: Vec<(f32, Vec<DeveloperDocumentId>)>,
+    limit: usize,
+) -> Vec<HybridSearchResult> {
+    let mut scores: BTreeMap<DeveloperDocumentId, f32> = BTreeMap::new();
+    for (weight, ranking) in rankings {
+        for (i, id) in ranking.into_iter().enumerate() {
+            *scores.entry(id).or_default() += weight / (HYBRID_SEARCH_RRF_K + (i + 1) as f32);
+        }
+    }
+    let mut results: Vec<_> = scores
+        .into_iter()
+        .map(|(id, score)| HybridSearchResult { id, score })
+        .collect();
+    results.sort_by(|a, b| b.score.total_cmp(&a.score));
+    results.truncate(limit);
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use value::{
+        id_v6::DeveloperDocumentId,
+        InternalId,
+        TableNumber,
+    };
+
+    use super::fuse_rankings;
+
+    fn id(i: u8) -> DeveloperDocumentId {
+        DeveloperDocumentId::new(
+            TableNumber::try_from(1000).unwrap(),
+            InternalId::from([i; 16]),
+        )
+    }
+
+    #[test]
+    fn test_fuse_rankings() {
+        // 2 is ranked second by both searches, so it beats 1 and 3, which are
+        // each only ranked first by one.
+        let results = fuse_rankings(
+            vec![(1.0, vec![id(1), id(2)]), (1.0, vec![id(3), id(2)])],
+            2,
+        );
+        let ids: Vec<_> = results.iter().map(|r| r.id).collect();
+        assert_eq!(ids.len(), 2);
+        assert_eq!(ids[0], id(2));
+        assert!((results[0].score - 2.0 / 62.0).abs() < 1e-6);
+
+        // Weighting the second search breaks the tie between 1 and 3.
+        let results = fuse_rankings(
+            vec![(1.0, vec![id(1), id(2)]), (3.0, vec![id(3), id(2)])],
+            3,
+        );
+        let ids: Vec<_> = results.iter().map(|r| r.id).collect();
+        assert_eq!(ids, vec![id(2), id(3), id(1)]);
+    }
+}
@@ -0,0 +1,320 @@
+use std::collections::BTreeMap;
+
+use common::{
+    bootstrap_model::schema::SchemaState,
+    document::ID_FIELD,
+    query::Query,
+    runtime::Runtime,
+    schemas::validator::Validator,
+    types::TableName,
+    version::Version,
+};
+use errors::ErrorMetadata;
+use value::{
+    ConvexArray,
+    ConvexObject,
+    ConvexValue,
+    DeveloperDocumentId,
+    FieldName,
+    Namespace,
+    TableNamespace,
+    TableNumber,
+};
+
+use super::{
+    DeveloperQuery,
+    TableFilter,
+    MAX_QUERY_FETCH,
+};
+use crate::{
+    SchemaModel,
+    Transaction,
+};
+
+/// The most levels of references a single call can hydrate.
+pub const MAX_HYDRATE_DEPTH: usize = 4;
+
+/// The most referenced documents a single call can read.
+pub const MAX_HYDRATED_DOCUMENTS: usize = MAX_QUERY_FETCH;
+
+fn invalid_hydrate(msg: impl Into<String>) -> ErrorMetadata {
+    ErrorMetadata::bad_request("InvalidHydrate", msg.into())
+}
+
+/// The referenced documents read so far, by id. `None` is a reference to a
+/// document that doesn't exist.
+type Fetched = BTreeMap<DeveloperDocumentId, Option<(TableName, ConvexValue)>>;
+
+/// Replaces the `v.id(...)` fields of `documents`, as declared in the active
+/// schema, with the documents they reference, and those documents'
+/// references in turn, up to `depth` levels deep. References to documents
+/// that don't exist become `null`.
+///
+/// Referenced documents are read level by level, so each level costs one
+/// round of reads however many documents reference it, and every read is
+/// part of the transaction's read set. Without an active schema, or for
+/// tables without a document validator, documents are returned unchanged.
+pub async fn hydrate_references<RT: Runtime>(
+    tx: &mut Transaction<RT>,
+    namespace: TableNamespace,
+    documents: Vec<ConvexValue>,
+    depth: usize,
+    version: Option<Version>,
+    table_filter: TableFilter,
+) -> anyhow::Result<Vec<ConvexValue>> {
+    anyhow::ensure!(
+        depth > 0 && depth <= MAX_HYDRATE_DEPTH,
+        invalid_hydrate(format!(
+            "References can be hydrated between 1 and {MAX_HYDRATE_DEPTH} levels deep."
+        ))
+    );
+    let Some((_, schema)) = SchemaModel::new(tx, namespace)
+        .get_by_state(SchemaState::Active)
+        .await?
+    else {
+        return Ok(documents);
+    };
+    let validators: BTreeMap<TableName, Validator> = schema
+        .tables
+        .into_iter()
+        .map(|(table_name, table)| (table_name, table.document_type.into()))
+        .collect();
+
+    let mut roots = Vec::with_capacity(documents.len());
+    for document in documents {
+        let table_name = match document {
+            ConvexValue::Object(ref object) => match object.get(&**ID_FIELD) {
+                Some(ConvexValue::String(id)) => {
+                    DeveloperDocumentId::decode(id).ok().and_then(|id| {
+                        tx.all_tables_number_to_name(namespace, table_filter)(id.table()).ok()
+                    })
+                },
+                _ => None,
+            },
+            _ => None,
+        };
+        let Some(table_name) = table_name else {
+            anyhow::bail!(invalid_hydrate(
+                "Only documents read from the database can be hydrated."
+            ));
+        };
+        roots.push((table_name, document));
+    }
+
+    let mut fetched = Fetched::new();
+    let mut frontier: Vec<(TableName, ConvexValue)> = roots.clone();
+    for _ in 0..depth {
+        let mut references = BTreeMap::new();
+        {
+            let number_to_name = tx.all_tables_number_to_name(namespace, table_filter);
+            for (table_name, document) in &frontier {
+                if let Some(validator) = validators.get(table_name) {
+                    collect_references(validator, document, &number_to_name, &mut references);
+                }
+            }
+        }
+        references.retain(|id, _| !fetched.contains_key(id));
+        anyhow::ensure!(
+            fetched.len() + references.len() <= MAX_HYDRATED_DOCUMENTS,
+            invalid_hydrate(format!(
+                "Hydrating these references would read more than {MAX_HYDRATED_DOCUMENTS} \
+                 documents. Hydrate fewer levels or fewer documents at once."
+            ))
+        );
+        frontier = vec![];
+        for (id, table_name) in references {
+            let mut query = DeveloperQuery::new_with_version(
+                tx,
+                namespace,
+                Query::get(table_name.clone(), id),
+                version.clone(),
+                table_filter,
+            )?;
+            let document = query
+                .next(tx, Some(1))
+                .await?
+                .map(|document| ConvexValue::from(document.into_value().0));
+            if let Some(ref document) = document {
+                frontier.push((table_name.clone(), document.clone()));
+            }
+            fetched.insert(id, document.map(|document| (table_name, document)));
+        }
+        if frontier.is_empty() {
+            break;
+        }
+    }
+
+    roots
+        .iter()
+        .map(|(table_name, document)| {
+            hydrate_document(table_name, document, depth, &validators, &fetched)
+        })
+        .collect()
+}
+
+/// Adds the ids in `value` that `validator` declares as references to
+/// `references`. Strings that aren't ids of the declared table are skipped,
+/// so every branch of a union can be searched.
+fn collect_references(
+    validator: &Validator,
+    value: &ConvexValue,
+    number_to_name: &impl Fn(TableNumber) -> anyhow::Result<TableName>,
+    references: &mut BTreeMap<DeveloperDocumentId, TableName>,
+) {
+    match (validator, value) {
+        (Validator::Id(table_name), ConvexValue::String(s)) => {
+            if let Ok(id) = DeveloperDocumentId::decode(s)
+                && number_to_name(id.table()).is_ok_and(|name| &name == table_name)
+            {
+                references.insert(id, table_name.clone());
+            }
+        },
+        (Validator::Array(element), ConvexValue::Array(values)) => {
+            for value in values {
+                collect_references(element, value, number_to_name, references);
+            }
+        },
+        (Validator::Record(_, element), ConvexValue::Object(object)) => {
+            for (_, value) in object.iter() {
+                collect_references(element, value, number_to_name, references);
+            }
+        },
+        (Validator::Object(fields), ConvexValue::Object(object)) => {
+            for (field, field_validator) in &fields.0 {
+                if field.is_system() {
+                    continue;
+                }
+                if let Some(value) = object.get(&**field) {
+                    collect_references(
+                        field_validator.validator(),
+                        value,
+                        number_to_name,
+                        references,
+                    );
+                }
+            }
+        },
+        (Validator::Union(validators), value) => {
+            for validator in validators {
+                collect_references(validator, value, number_to_name, references);
+            }
+        },
+        _ => {},
+    }
+}
+
+fn hydrate_document(
+    table_name: &TableName,
+    document: &ConvexValue,
+    depth: usize,
+    validators: &BTreeMap<TableName, Validator>,
+    fetched: &Fetched,
+) -> anyhow::Result<ConvexValue> {
+    if depth == 0 {
+        return Ok(document.clone());
+    }
+    let Some(validator) = validators.get(table_name) else {
+        return Ok(document.clone());
+    };
+    Ok(
+        hydrate_value(validator, document, depth, validators, fetched)?
+            .unwrap_or_else(|| document.clone()),
+    )
+}
+
+/// Returns `value` with the references `validator` declares replaced, or
+/// `None` if it has none.
+fn hydrate_value(
+    validator: &Validator,
+    value: &ConvexValue,
+    depth: usize,
+    validators: &BTreeMap<TableName, Validator>,
+    fetched: &Fetched,
+) -> anyhow::Result<Option<ConvexValue>> {
+    let hydrated = match (validator, value) {
+        (Validator::Id(table_name), ConvexValue::String(s)) => {
+            let Ok(id) = DeveloperDocumentId::decode(s) else {
+                return Ok(None);
+            };
+            match fetched.get(&id) {
+                Some(Some((referenced_table, document))) if referenced_table == table_name => {
+                    hydrate_document(table_name, document, depth - 1, validators, fetched)?
+                },
+                Some(None) => ConvexValue::Null,
+                _ => return Ok(None),
+            }
+        },
+        (Validator::Array(element), ConvexValue::Array(values)) => {
+            let mut changed = false;
+            let mut hydrated_values = Vec::with_capacity(values.len());
+            for value in values {
+                match hydrate_value(element, value, depth, validators, fetched)? {
+                    Some(hydrated) => {
+                        changed = true;
+                        hydrated_values.push(hydrated);
+                    },
+                    None => hydrated_values.push(value.clone()),
+                }
+            }
+            if !changed {
+                return Ok(None);
+            }
+            ConvexValue::Array(ConvexArray::try_from(hydrated_values)?)
+        },
+        (Validator::Record(_, element), ConvexValue::Object(object)) => {
+            let mut fields: BTreeMap<FieldName, ConvexValue> = BTreeMap::new();
+            for (field, value) in object.iter() {
+                if let Some(hydrated) = hydrate_value(element, value, depth, validators, fetched)? {
+                    fields.insert(field.clone(), hydrated);
+                }
+            }
+            if fields.is_empty() {
+                return Ok(None);
+            }
+            ConvexValue::Object(with_fields(object, fields)?)
+        },
+        (Validator::Object(field_validators), ConvexValue::Object(object)) => {
+            let mut fields: BTreeMap<FieldName, ConvexValue> = BTreeMap::new();
+            for (field, field_validator) in &field_validators.0 {
+                if field.is_system() {
+                    continue;
+                }
+                if let Some(value) = object.get(&**field)
+                    && let Some(hydrated) = hydrate_value(
+                        field_validator.validator(),
+                        value,
+                        depth,
+                        validators,
+                        fetched,
+                    )?
+                {
+                    fields.insert(field.to_string().parse()?, hydrated);
+                }
+            }
+            if fields.is_empty() {
+                return Ok(None);
+            }
+            ConvexValue::Object(with_fields(object, fields)?)
+        },
+        // The value is hydrated by the first branch with references in it.
+        (Validator::Union(branches), value) => {
+            for branch in branches {
+                if let Some(hydrated) = hydrate_value(branch, value, depth, validators, fetched)? {
+                    return Ok(Some(hydrated));
+                }
+            }
+            return Ok(None);
+        },
+        _ => return Ok(None),
+    };
+    Ok(Some(hydrated))
+}
+
+fn with_fields(
+    object: &ConvexObject,
+    fields: BTreeMap<FieldName, ConvexValue>,
+) -> anyhow::Result<ConvexObject> {
+    let mut all_fields: BTreeMap<FieldName, ConvexValue> = object.clone().into();
+    all_fields.extend(fields);
+    all_fields.try_into()
+}
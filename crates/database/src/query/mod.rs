@@ -29,6 +29,7 @@ use common::{
     runtime::Runtime,
     types::{
         IndexName,
+        StableIndexName,
         TabletIndexName,
         WriteTimestamp,
     },
@@ -42,7 +43,10 @@ use futures::{
 use indexing::backend_in_memory_indexes::BatchKey;
 use maplit::btreemap;
 use minitrace::Event;
-use value::TableNamespace;
+use value::{
+    TableNamespace,
+    TableNumber,
+};
 
 use self::{
     filter::Filter,
@@ -51,6 +55,10 @@ use self::{
         IndexRange,
     },
     limit::Limit,
+    planner::{
+        apply_index_hint,
+        push_down_filters,
+    },
     search_query::SearchQuery,
 };
 use crate::{
@@ -60,12 +68,52 @@ use crate::{
     Transaction,
 };
 
+mod aggregate;
 mod filter;
+mod get_page;
+mod hybrid_search;
+mod hydrate;
 mod index_range;
 mod limit;
+mod planner;
+mod search_facets;
 mod search_query;
 
+pub use aggregate::{
+    aggregate,
+    Aggregate,
+    AggregateFunction,
+    AggregateGroup,
+    MAX_AGGREGATES,
+    MAX_AGGREGATE_GROUPS,
+};
+pub use get_page::{
+    get_page,
+    GetPage,
+    GetPageRequest,
+    IndexKeyBound,
+    IndexKeyValues,
+    MAX_GET_PAGE_ROWS,
+};
+pub use hybrid_search::{
+    fuse_rankings,
+    HybridSearch,
+    HybridSearchJson,
+    HybridSearchRequest,
+    HybridSearchResult,
+    HYBRID_SEARCH_RRF_K,
+};
+pub use hydrate::{
+    hydrate_references,
+    MAX_HYDRATED_DOCUMENTS,
+    MAX_HYDRATE_DEPTH,
+};
 pub use index_range::soft_data_limit;
+pub use search_facets::{
+    search_facets,
+    SearchFacetCount,
+    MAX_SEARCH_FACETS,
+};
 
 // Even in the presence of large prefetch hints, we should never fetch too much
 // data at once.
@@ -143,6 +191,8 @@ pub enum QueryStreamNext {
 pub struct DeveloperQuery<RT: Runtime> {
     root: QueryNode,
     query_fingerprint: Option<QueryFingerprint>,
+    /// The number of the table being queried, for its cursors.
+    table_number: Option<TableNumber>,
     end_cursor: Option<Cursor>,
     _marker: PhantomData<RT>,
 }
@@ -299,11 +349,12 @@ impl<RT: Runtime> DeveloperQuery<RT> {
     pub fn new_bounded(
         tx: &mut Transaction<RT>,
         namespace: TableNamespace,
-        query: Query,
+        mut query: Query,
         pagination_options: PaginationOptions,
         version: Option<Version>,
         table_filter: TableFilter,
     ) -> anyhow::Result<Self> {
+        apply_index_hint(&mut query)?;
         let index_name = match query.source {
             QuerySource::FullTableScan(ref full_table_scan) => {
                 let table_name = full_table_scan.table_name.clone();
@@ -333,6 +384,17 @@ impl<RT: Runtime> DeveloperQuery<RT> {
                 IndexedFields::try_from(Vec::new())?
             },
         };
+        // Cursors record the number of the table whose IDs they contain, so
+        // cursors issued before a snapshot import changed the table's number
+        // can be moved to the same documents in the new table.
+        let table_number = match (&query.source, &stable_index_name) {
+            (QuerySource::Search(_), _) => None,
+            (_, StableIndexName::Physical(tablet_index_name)) => Some(
+                tx.table_mapping()
+                    .tablet_number(*tablet_index_name.table())?,
+            ),
+            (_, StableIndexName::Virtual(..) | StableIndexName::Missing(_)) => None,
+        };
         let should_compute_split_cursor = match &pagination_options {
             PaginationOptions::NoPagination => false,
             PaginationOptions::ManualPagination { .. } => false,
@@ -351,6 +413,10 @@ impl<RT: Runtime> DeveloperQuery<RT> {
                 ..
             } => (*maximum_rows_read, *maximum_bytes_read),
         };
+        let maximum_rows_read = match (maximum_rows_read, query.hints.scan_budget) {
+            (Some(limit), Some(budget)) => Some(limit.min(budget)),
+            (limit, budget) => limit.or(budget),
+        };
         // Fingerprint makes sure that a cursor is only used with the same
         // query. So you can fetch the next page of a query, but if the query
         // changes, we don't start returning bogus results.
@@ -386,7 +452,7 @@ impl<RT: Runtime> DeveloperQuery<RT> {
                     Some(&end_cursor.query_fingerprint) == fingerprint.as_ref(),
                     invalid_cursor()
                 );
-                Some(end_cursor.clone())
+                Some(end_cursor.clone().for_table(table_number)?)
             },
         };
         let cursor_interval = match pagination_options {
@@ -399,10 +465,10 @@ impl<RT: Runtime> DeveloperQuery<RT> {
                 let start_cursor_position = match start_cursor {
                     Some(cursor) => {
                         anyhow::ensure!(
-                            Some(cursor.query_fingerprint) == fingerprint,
+                            Some(&cursor.query_fingerprint) == fingerprint.as_ref(),
                             invalid_cursor()
                         );
-                        Some(cursor.position)
+                        Some(cursor.for_table(table_number)?.position)
                     },
                     None => None,
                 };
@@ -427,7 +493,10 @@ impl<RT: Runtime> DeveloperQuery<RT> {
                 should_compute_split_cursor,
                 version,
             )),
-            QuerySource::IndexRange(index_range) => {
+            QuerySource::IndexRange(mut index_range) => {
+                if !query.hints.disable_filter_pushdown && !index_name.table().is_system() {
+                    push_down_filters(&mut index_range.range, &query.operators, &indexed_fields);
+                }
                 let order = index_range.order;
                 let interval = index_range.compile(indexed_fields.clone())?;
                 QueryNode::IndexRange(IndexRange::new(
@@ -467,6 +536,7 @@ impl<RT: Runtime> DeveloperQuery<RT> {
         Ok(Self {
             root: cur_node,
             query_fingerprint: fingerprint,
+            table_number,
             end_cursor,
             _marker: PhantomData,
         })
@@ -487,6 +557,7 @@ impl<RT: Runtime> DeveloperQuery<RT> {
             Some(position) => Some(Cursor {
                 position,
                 query_fingerprint: self.query_fingerprint.clone()?,
+                table_number: self.table_number,
             }),
             None => None,
         }
@@ -497,6 +568,7 @@ impl<RT: Runtime> DeveloperQuery<RT> {
             Some(position) => Some(Cursor {
                 position,
                 query_fingerprint: self.query_fingerprint.clone()?,
+                table_number: self.table_number,
             }),
             None => None,
         }
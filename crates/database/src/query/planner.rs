@@ -0,0 +1,199 @@
+//! Rewrites a query according to its `QueryHints` and cheap, always-safe
+//! optimizations before it's compiled into a tree of `QueryNode`s.
+use common::{
+    bootstrap_model::index::database_index::IndexedFields,
+    document::ID_FIELD_PATH,
+    query::{
+        Expression,
+        IndexRange,
+        IndexRangeExpression,
+        Query,
+        QueryOperator,
+        QuerySource,
+    },
+    types::IndexName,
+};
+use errors::ErrorMetadata;
+
+/// Replace the source of a query with a walk of the index in its index hint.
+/// Queries that already pick an index can only hint that same index.
+pub fn apply_index_hint(query: &mut Query) -> anyhow::Result<()> {
+    let Some(ref descriptor) = query.hints.index else {
+        return Ok(());
+    };
+    match &query.source {
+        QuerySource::FullTableScan(full_table_scan) => {
+            let index_name =
+                IndexName::new(full_table_scan.table_name.clone(), descriptor.clone())?;
+            query.source = QuerySource::IndexRange(IndexRange {
+                index_name,
+                range: vec![],
+                order: full_table_scan.order,
+            });
+        },
+        QuerySource::IndexRange(index_range) => {
+            anyhow::ensure!(
+                index_range.index_name.descriptor() == descriptor,
+                ErrorMetadata::bad_request(
+                    "ConflictingIndexHint",
+                    format!(
+                        "Query uses index {} but hints index {descriptor}",
+                        index_range.index_name
+                    ),
+                )
+            );
+        },
+        QuerySource::Search(_) => {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "InvalidIndexHint",
+                "Search queries can't hint an index",
+            ));
+        },
+    }
+    Ok(())
+}
+
+/// Narrow the range of an index walk with the equality filters that apply to
+/// the next fields of the index, so pathological filters on indexed fields
+/// don't scan documents they'll throw away. The filters are left in place, so
+/// this never changes the query's results, only how many documents it reads.
+pub fn push_down_filters(
+    range: &mut Vec<IndexRangeExpression>,
+    operators: &[QueryOperator],
+    indexed_fields: &IndexedFields,
+) {
+    if range
+        .iter()
+        .any(|expr| !matches!(expr, IndexRangeExpression::Eq(..)))
+    {
+        return;
+    }
+    // Filters after a limit apply to fewer documents than the range, so
+    // they can't narrow it.
+    let mut conjuncts = vec![];
+    for operator in operators {
+        match operator {
+            QueryOperator::Filter(expr) => collect_conjuncts(expr, &mut conjuncts),
+            QueryOperator::Limit(_) => break,
+        }
+    }
+    // Since the range only has equalities, it must be on a prefix of the
+    // index's fields if it's valid.
+    while let Some(next_field) = indexed_fields.iter_with_id().nth(range.len())
+        && next_field != &*ID_FIELD_PATH
+    {
+        let value = conjuncts.iter().find_map(|expr| match expr {
+            Expression::Eq(l, r) => match (&**l, &**r) {
+                (Expression::Field(field), Expression::Literal(value))
+                | (Expression::Literal(value), Expression::Field(field))
+                    if field == next_field =>
+                {
+                    Some(value.clone())
+                },
+                _ => None,
+            },
+            _ => None,
+        });
+        let Some(value) = value else {
+            break;
+        };
+        range.push(IndexRangeExpression::Eq(next_field.clone(), value));
+    }
+}
+
+fn collect_conjuncts<'a>(expr: &'a Expression, out: &mut Vec<&'a Expression>) {
+    match expr {
+        Expression::And(exprs) => {
+            for expr in exprs {
+                collect_conjuncts(expr, out);
+            }
+        },
+        expr => out.push(expr),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{
+        bootstrap_model::index::database_index::IndexedFields,
+        maybe_val,
+        query::{
+            Expression,
+            IndexRangeExpression,
+            Order,
+            Query,
+            QueryHints,
+            QueryOperator,
+            QuerySource,
+        },
+    };
+
+    use super::{
+        apply_index_hint,
+        push_down_filters,
+    };
+
+    fn eq(field: &str, value: &str) -> anyhow::Result<Expression> {
+        Ok(Expression::Eq(
+            Box::new(Expression::Field(field.parse()?)),
+            Box::new(Expression::Literal(maybe_val!(value))),
+        ))
+    }
+
+    #[test]
+    fn test_push_down_equality_filters() -> anyhow::Result<()> {
+        let indexed_fields = IndexedFields::try_from(vec!["a".parse()?, "b".parse()?])?;
+        let mut range = vec![];
+        let operators = vec![
+            QueryOperator::Filter(Expression::And(vec![eq("b", "2")?, eq("c", "3")?])),
+            QueryOperator::Filter(eq("a", "1")?),
+        ];
+        push_down_filters(&mut range, &operators, &indexed_fields);
+        assert_eq!(
+            range,
+            vec![
+                IndexRangeExpression::Eq("a".parse()?, maybe_val!("1")),
+                IndexRangeExpression::Eq("b".parse()?, maybe_val!("2")),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_push_down_past_limit_or_gap() -> anyhow::Result<()> {
+        let indexed_fields = IndexedFields::try_from(vec!["a".parse()?, "b".parse()?])?;
+        let mut range = vec![];
+        let operators = vec![
+            QueryOperator::Filter(eq("b", "2")?),
+            QueryOperator::Limit(10),
+            QueryOperator::Filter(eq("a", "1")?),
+        ];
+        push_down_filters(&mut range, &operators, &indexed_fields);
+        assert_eq!(range, vec![]);
+
+        let mut range = vec![IndexRangeExpression::Gt("a".parse()?, maybe_val!("0"))];
+        let operators = vec![QueryOperator::Filter(eq("b", "2")?)];
+        push_down_filters(&mut range, &operators, &indexed_fields);
+        assert_eq!(range.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_hint() -> anyhow::Result<()> {
+        let mut query = Query::full_table_scan("messages".parse()?, Order::Desc);
+        query.hints = QueryHints {
+            index: Some("by_channel".parse()?),
+            ..Default::default()
+        };
+        apply_index_hint(&mut query)?;
+        let QuerySource::IndexRange(index_range) = &query.source else {
+            panic!("Expected an index range, got {:?}", query.source);
+        };
+        assert_eq!(index_range.index_name.to_string(), "messages.by_channel");
+        assert_eq!(index_range.order, Order::Desc);
+
+        query.hints.index = Some("by_author".parse()?);
+        assert!(apply_index_hint(&mut query).is_err());
+        Ok(())
+    }
+}
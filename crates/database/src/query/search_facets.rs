@@ -0,0 +1,124 @@
+use std::collections::BTreeMap;
+
+use common::{
+    bootstrap_model::index::IndexConfig,
+    query::{
+        Query,
+        QuerySource,
+    },
+    runtime::Runtime,
+    version::Version,
+};
+use errors::ErrorMetadata;
+use indexing::index_registry::index_not_found_error;
+use search::MAX_CANDIDATE_REVISIONS;
+use value::{
+    ConvexValue,
+    FieldPath,
+    TableNamespace,
+};
+
+use super::{
+    DeveloperQuery,
+    TableFilter,
+};
+use crate::{
+    IndexModel,
+    Transaction,
+};
+
+/// The most fields a single search query can count facets over.
+pub const MAX_SEARCH_FACETS: usize = 16;
+
+/// How many of a search query's results have `value` in a facet field.
+/// `value` is `None` for results missing the field.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchFacetCount {
+    pub value: Option<ConvexValue>,
+    pub count: u64,
+}
+
+fn invalid_search_facets(msg: impl Into<String>) -> ErrorMetadata {
+    ErrorMetadata::bad_request("InvalidSearchFacets", msg.into())
+}
+
+/// Counts a search query's results by their value in each of `fields`, which
+/// must be filter fields of the search index. Counts for each field are
+/// ordered from most to least common.
+///
+/// Like any search query, only the top `MAX_CANDIDATE_REVISIONS` results are
+/// counted.
+pub async fn search_facets<RT: Runtime>(
+    tx: &mut Transaction<RT>,
+    namespace: TableNamespace,
+    query: Query,
+    fields: Vec<FieldPath>,
+    version: Option<Version>,
+    table_filter: TableFilter,
+) -> anyhow::Result<BTreeMap<FieldPath, Vec<SearchFacetCount>>> {
+    let QuerySource::Search(ref search) = query.source else {
+        anyhow::bail!(invalid_search_facets(
+            "Facets can only be counted for queries using `withSearchIndex`."
+        ));
+    };
+    anyhow::ensure!(
+        query.operators.is_empty(),
+        invalid_search_facets("Facets can't be counted for a query with filters or a limit.")
+    );
+    anyhow::ensure!(
+        !fields.is_empty() && fields.len() <= MAX_SEARCH_FACETS,
+        invalid_search_facets(format!(
+            "A query must count facets for between 1 and {MAX_SEARCH_FACETS} fields."
+        ))
+    );
+    let index_name = search.index_name.clone();
+    let Some(metadata) = IndexModel::new(tx).enabled_index_metadata(namespace, &index_name)? else {
+        anyhow::bail!(index_not_found_error(&index_name));
+    };
+    let IndexConfig::Text {
+        ref developer_config,
+        ..
+    } = metadata.config
+    else {
+        anyhow::bail!(invalid_search_facets(format!(
+            "Index {index_name} is not a search index."
+        )));
+    };
+    for field in &fields {
+        anyhow::ensure!(
+            developer_config.filter_fields.contains(field),
+            invalid_search_facets(format!(
+                "Can't count facets for {field}, which isn't one of the filter fields of search \
+                 index {index_name}."
+            ))
+        );
+    }
+
+    let mut counts: BTreeMap<&FieldPath, BTreeMap<Option<ConvexValue>, u64>> = fields
+        .iter()
+        .map(|field| (field, BTreeMap::new()))
+        .collect();
+    let mut query_stream =
+        DeveloperQuery::new_with_version(tx, namespace, query, version, table_filter)?;
+    let mut results = 0;
+    while results < MAX_CANDIDATE_REVISIONS
+        && let Some(document) = query_stream.next(tx, None).await?
+    {
+        results += 1;
+        for (field, field_counts) in counts.iter_mut() {
+            let value = document.value().get_path(field).cloned();
+            *field_counts.entry(value).or_default() += 1;
+        }
+    }
+    Ok(counts
+        .into_iter()
+        .map(|(field, field_counts)| {
+            let mut field_counts: Vec<_> = field_counts
+                .into_iter()
+                .map(|(value, count)| SearchFacetCount { value, count })
+                .collect();
+            field_counts.sort_by(|a, b| b.count.cmp(&a.count));
+            (field.clone(), field_counts)
+        })
+        .collect())
+}
@@ -492,6 +492,7 @@ mod tests {
 
     use common::{
         assert_obj,
+        bootstrap_model::index::text_index::TextIndexAnalyzer,
         document::{
             CreationTime,
             PackedDocument,
@@ -549,6 +550,7 @@ mod tests {
         let search_reads = SearchQueryReads::new(
             vec![TextQueryTermRead {
                 field_path: FieldPath::from_str(field_path)?,
+                analyzer: TextIndexAnalyzer::default(),
                 term: TextQueryTerm::Fuzzy {
                     max_distance: FuzzyDistance::Zero,
                     token: "word".to_string(),
@@ -595,6 +597,7 @@ mod tests {
         let search_reads = SearchQueryReads::new(
             vec![TextQueryTermRead {
                 field_path: FieldPath::from_str(field_path)?,
+                analyzer: TextIndexAnalyzer::default(),
                 term: TextQueryTerm::Fuzzy {
                     max_distance: FuzzyDistance::One,
                     token: "wod".to_string(),
@@ -641,6 +644,7 @@ mod tests {
         let search_reads = SearchQueryReads::new(
             vec![TextQueryTermRead {
                 field_path: FieldPath::from_str(field_path)?,
+                analyzer: TextIndexAnalyzer::default(),
                 term: TextQueryTerm::Fuzzy {
                     max_distance: FuzzyDistance::Two,
                     token: "word".to_string(),
@@ -698,6 +702,7 @@ mod tests {
         let search_reads = SearchQueryReads::new(
             vec![TextQueryTermRead {
                 field_path: FieldPath::from_str(field_path)?,
+                analyzer: TextIndexAnalyzer::default(),
                 term: TextQueryTerm::Fuzzy {
                     max_distance: FuzzyDistance::Zero,
                     token: "word".to_string(),
@@ -744,6 +749,7 @@ mod tests {
         let search_reads = SearchQueryReads::new(
             vec![TextQueryTermRead {
                 field_path: FieldPath::from_str(field_path)?,
+                analyzer: TextIndexAnalyzer::default(),
                 term: TextQueryTerm::Fuzzy {
                     max_distance: FuzzyDistance::One,
                     token: "wrd".to_string(),
@@ -790,6 +796,7 @@ mod tests {
         let search_reads = SearchQueryReads::new(
             vec![TextQueryTermRead {
                 field_path: FieldPath::from_str(field_path)?,
+                analyzer: TextIndexAnalyzer::default(),
                 term: TextQueryTerm::Fuzzy {
                     max_distance: FuzzyDistance::Two,
                     token: "word".to_string(),
@@ -857,6 +864,7 @@ mod tests {
         let search_reads = SearchQueryReads::new(
             vec![TextQueryTermRead {
                 field_path: FieldPath::from_str("textField")?,
+                analyzer: TextIndexAnalyzer::default(),
                 term: TextQueryTerm::Exact("word".to_string()),
             }]
             .into(),
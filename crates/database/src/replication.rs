@@ -0,0 +1,226 @@
+//! Live replication from a leader deployment to a follower.
+//!
+//! The leader serves its commit log as a stream of [`ReplicatedCommit`]s,
+//! built from [`DocumentDeltas`], and the follower applies each one in a
+//! single transaction with [`ReplicaApplier`]. Only user tables in the root
+//! component are replicated, matching streaming export; a follower is
+//! bootstrapped by importing a snapshot export of the leader and then tailing
+//! the log from the export's timestamp.
+use std::collections::BTreeSet;
+
+use common::runtime::Runtime;
+use errors::ErrorMetadata;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::Value as JsonValue;
+use sync_types::Timestamp;
+use value::{
+    ConvexObject,
+    DeveloperDocumentId,
+    ResolvedDocumentId,
+    TableName,
+    TableNamespace,
+    TabletIdAndTableNumber,
+};
+
+use crate::{
+    DocumentDeltas,
+    ImportFacingModel,
+    TableModel,
+    Transaction,
+};
+
+/// A single document write in a [`ReplicatedCommit`].
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplicatedWrite {
+    pub table_name: TableName,
+    pub id: DeveloperDocumentId,
+    /// The document after the write, including its system fields, or `None`
+    /// if the document was deleted.
+    pub document: Option<ConvexObject>,
+}
+
+/// All of the replicated writes the leader committed at `ts`. A commit with no
+/// writes only advances the follower's cursor.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplicatedCommit {
+    pub ts: Timestamp,
+    pub writes: Vec<ReplicatedWrite>,
+}
+
+impl ReplicatedCommit {
+    /// Groups a page of document deltas into commits, followed by an empty
+    /// commit at the page's cursor so followers catch up to it even if the
+    /// last writes weren't replicated.
+    pub fn from_document_deltas(deltas: DocumentDeltas) -> Vec<Self> {
+        let mut commits: Vec<Self> = vec![];
+        for (ts, id, _, table_name, document) in deltas.deltas {
+            let write = ReplicatedWrite {
+                table_name,
+                id,
+                document: document.map(|document| document.into_value().0),
+            };
+            match commits.last_mut() {
+                Some(commit) if commit.ts == ts => commit.writes.push(write),
+                _ => commits.push(Self {
+                    ts,
+                    writes: vec![write],
+                }),
+            }
+        }
+        if commits
+            .last()
+            .map_or(true, |commit| commit.ts < deltas.cursor)
+        {
+            commits.push(Self {
+                ts: deltas.cursor,
+                writes: vec![],
+            });
+        }
+        commits
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedReplicatedCommit {
+    /// Decimal string, since timestamps don't fit in a JSON number.
+    ts: String,
+    writes: Vec<SerializedReplicatedWrite>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SerializedReplicatedWrite {
+    table: String,
+    id: String,
+    /// Convex-encoded JSON, which preserves every value's type.
+    document: Option<JsonValue>,
+}
+
+impl From<ReplicatedCommit> for SerializedReplicatedCommit {
+    fn from(commit: ReplicatedCommit) -> Self {
+        Self {
+            ts: u64::from(commit.ts).to_string(),
+            writes: commit
+                .writes
+                .into_iter()
+                .map(|write| SerializedReplicatedWrite {
+                    table: write.table_name.to_string(),
+                    id: write.id.encode(),
+                    document: write.document.map(JsonValue::from),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<SerializedReplicatedCommit> for ReplicatedCommit {
+    type Error = anyhow::Error;
+
+    fn try_from(commit: SerializedReplicatedCommit) -> anyhow::Result<Self> {
+        Ok(Self {
+            ts: Timestamp::try_from(commit.ts.parse::<u64>()?)?,
+            writes: commit
+                .writes
+                .into_iter()
+                .map(|write| {
+                    anyhow::Ok(ReplicatedWrite {
+                        table_name: write.table.parse()?,
+                        id: DeveloperDocumentId::decode(&write.id)?,
+                        document: write.document.map(ConvexObject::try_from).transpose()?,
+                    })
+                })
+                .try_collect()?,
+        })
+    }
+}
+
+/// Applies a leader's commits to a follower's root component.
+pub struct ReplicaApplier<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> ReplicaApplier<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    /// Applies all of `commit`'s writes in the transaction, so they become
+    /// visible on the follower atomically. Reapplying a commit is a no-op.
+    pub async fn apply(&mut self, commit: ReplicatedCommit) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.tx.identity().is_system(),
+            "Only the system can apply replicated commits"
+        );
+        for write in commit.writes {
+            let table_id = self.table_for_write(&write).await?;
+            match write.document {
+                Some(document) => {
+                    let table_mapping = self.tx.table_mapping().clone();
+                    ImportFacingModel::new(self.tx)
+                        .upsert(table_id, &write.table_name, document, &table_mapping)
+                        .await?;
+                },
+                None => {
+                    let id = ResolvedDocumentId::new(table_id.tablet_id, write.id);
+                    if self.tx.get(id).await?.is_some() {
+                        ImportFacingModel::new(self.tx)
+                            .delete(table_id, &write.table_name, write.id)
+                            .await?;
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// Finds the follower's table for `write`, creating it with the leader's
+    /// table number if it doesn't exist yet.
+    async fn table_for_write(
+        &mut self,
+        write: &ReplicatedWrite,
+    ) -> anyhow::Result<TabletIdAndTableNumber> {
+        let namespace = TableNamespace::root_component();
+        if let Some(table_id) = self
+            .tx
+            .table_mapping()
+            .namespace(namespace)
+            .id_and_number_if_exists(&write.table_name)
+        {
+            anyhow::ensure!(
+                table_id.table_number == write.id.table(),
+                ErrorMetadata::bad_request(
+                    "ReplicaDiverged",
+                    format!(
+                        "Table {} has number {} on the follower but {} on the leader",
+                        write.table_name,
+                        table_id.table_number,
+                        write.id.table()
+                    ),
+                )
+            );
+            return Ok(table_id);
+        }
+        let mut table_model = TableModel::new(self.tx);
+        let table_id = table_model
+            .insert_table_for_import(
+                namespace,
+                &write.table_name,
+                Some(write.id.table()),
+                &BTreeSet::new(),
+            )
+            .await?;
+        table_model
+            .activate_table(
+                table_id.tablet_id,
+                &write.table_name,
+                table_id.table_number,
+                &BTreeSet::new(),
+            )
+            .await?;
+        Ok(table_id)
+    }
+}
@@ -148,7 +148,10 @@ impl IndexesToBootstrap {
                     let vector_index_bootstrap_data = VectorIndexBootstrapData {
                         index_id: index_id.internal_id(),
                         on_disk_state,
-                        memory_index: MemoryVectorIndex::new(WriteTimestamp::Committed(ts.succ()?)),
+                        memory_index: MemoryVectorIndex::new(
+                            WriteTimestamp::Committed(ts.succ()?),
+                            developer_config.distance,
+                        ),
                         qdrant_schema,
                     };
                     if let Some(vector_indexes) =
@@ -556,7 +559,11 @@ mod tests {
 
     use common::{
         bootstrap_model::index::{
-            text_index::TextIndexState,
+            text_index::{
+                TextIndexAnalyzer,
+                TextIndexState,
+            },
+            vector_index::VectorDistanceMetric,
             IndexConfig,
             IndexMetadata,
             TabletIndexMetadata,
@@ -945,6 +952,7 @@ mod tests {
             vector_field,
             (2u32).try_into()?,
             btreeset![filter_field],
+            VectorDistanceMetric::default(),
         );
         Ok(metadata)
     }
@@ -1176,6 +1184,7 @@ mod tests {
             "test.by_text".parse()?,
             "searchField".parse()?,
             btreeset! {"filterField".parse()?},
+            TextIndexAnalyzer::default(),
         );
         IndexModel::new(&mut tx)
             .add_application_index(TableNamespace::test_user(), index)
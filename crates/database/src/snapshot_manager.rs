@@ -389,7 +389,7 @@ impl SnapshotManager {
         snapshot
     }
 
-    fn earliest_ts(&self) -> Timestamp {
+    pub(crate) fn earliest_ts(&self) -> Timestamp {
         self.versions
             .front()
             .map(|(ts, ..)| *ts)
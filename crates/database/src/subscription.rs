@@ -2,11 +2,13 @@
 //! notify subscribers on any changes to these documents.
 
 use std::{
+    cmp,
     collections::{
         BTreeMap,
         BTreeSet,
     },
     future::Future,
+    mem,
     sync::Arc,
 };
 
@@ -15,6 +17,14 @@ use common::{
     bootstrap_model::index::database_index::IndexedFields,
     document::PackedDocument,
     errors::report_error,
+    index::IndexKeyBytes,
+    interval::{
+        End,
+        Interval,
+        Start,
+    },
+    paths::FieldPath,
+    query::CursorPosition,
     runtime::{
         block_in_place,
         Runtime,
@@ -27,12 +37,18 @@ use common::{
         StateChannelSender,
     },
     types::{
+        IndexId,
         PersistenceVersion,
         SubscriberId,
         TabletIndexName,
         Timestamp,
     },
 };
+use errors::ErrorMetadataAnyhowExt;
+use futures::{
+    pin_mut,
+    TryStreamExt,
+};
 use indexing::interval::IntervalMap;
 use minitrace::future::FutureExt as MinitraceFutureExt;
 use parking_lot::Mutex;
@@ -43,14 +59,18 @@ use tokio::sync::{
     mpsc,
     mpsc::error::TrySendError,
     oneshot,
+    watch,
 };
+use value::ConvexValue;
 
 use crate::{
     metrics,
     reads::ReadSet,
+    table_iteration::TableIterator,
     write_log::{
         LogOwner,
         LogReader,
+        PackedDocumentUpdate,
     },
     Token,
 };
@@ -99,6 +119,27 @@ impl SubscriptionsClient {
         rx.await.map_err(|_| metrics::shutdown_error())
     }
 
+    /// Subscribe to `aggregate`, which had `value` at `ts`.
+    pub async fn subscribe_aggregate(
+        &self,
+        aggregate: LiveAggregate,
+        ts: Timestamp,
+        value: LiveAggregateValue,
+    ) -> anyhow::Result<LiveAggregateSubscription> {
+        let (tx, rx) = oneshot::channel();
+        let request = SubscriptionRequest::SubscribeAggregate {
+            aggregate,
+            ts,
+            value,
+            result: tx,
+        };
+        self.sender.clone().try_send(request).map_err(|e| match e {
+            TrySendError::Full(..) => metrics::subscriptions_worker_full_error().into(),
+            TrySendError::Closed(..) => metrics::shutdown_error(),
+        })?;
+        rx.await.map_err(|_| metrics::shutdown_error())
+    }
+
     pub fn shutdown(&self) {
         self.handle.lock().shutdown();
     }
@@ -113,6 +154,15 @@ enum SubscriptionRequest {
         result: oneshot::Sender<Subscription>,
     },
     Cancel(SubscriptionKey),
+    SubscribeAggregate {
+        aggregate: LiveAggregate,
+        // The timestamp `value` was computed at. Unlike `Subscribe`, the worker
+        // catches the aggregate up from here itself.
+        ts: Timestamp,
+        value: LiveAggregateValue,
+        result: oneshot::Sender<LiveAggregateSubscription>,
+    },
+    CancelAggregate(SubscriptionKey),
 }
 
 pub struct SubscriptionsWorker {
@@ -157,6 +207,22 @@ impl SubscriptionsWorker {
                         Some(SubscriptionRequest::Cancel(key)) => {
                             self.subscriptions.remove(key);
                         },
+                        Some(SubscriptionRequest::SubscribeAggregate {
+                            aggregate,
+                            ts,
+                            value,
+                            result,
+                        }) => {
+                            match self.subscriptions.subscribe_aggregate(aggregate, ts, value) {
+                                Ok(s) => {
+                                    let _: Result<_, _> = result.send(s);
+                                },
+                                Err(mut e) => report_error(&mut e),
+                            }
+                        },
+                        Some(SubscriptionRequest::CancelAggregate(key)) => {
+                            self.subscriptions.remove_aggregate(key);
+                        },
                         None => {
                             tracing::info!("All clients have gone away, shutting down subscriptions worker...");
                             break;
@@ -178,6 +244,7 @@ impl SubscriptionsWorker {
 pub struct SubscriptionManager {
     subscribers: Slab<Subscriber>,
     subscriptions: SubscriptionMap,
+    aggregates: Slab<AggregateSubscriber>,
     next_seq: Sequence,
 
     log: LogOwner,
@@ -199,6 +266,17 @@ struct Subscriber {
     seq: Sequence,
 }
 
+struct AggregateSubscriber {
+    aggregate: LiveAggregate,
+    value: LiveAggregateValue,
+    // `value` includes all writes at or before this timestamp. This may be
+    // ahead of `processed_ts` if the subscriber computed its initial value at a
+    // timestamp the worker hasn't processed yet.
+    ts: Timestamp,
+    sender: watch::Sender<LiveAggregateState>,
+    seq: Sequence,
+}
+
 impl SubscriptionManager {
     #[allow(unused)]
     #[cfg(any(test, feature = "testing"))]
@@ -219,6 +297,7 @@ impl SubscriptionManager {
         Self {
             subscribers: Slab::new(),
             subscriptions: SubscriptionMap::new(),
+            aggregates: Slab::new(),
             next_seq: 0,
             log,
             processed_ts,
@@ -270,14 +349,77 @@ impl SubscriptionManager {
         Ok(subscription)
     }
 
+    pub fn subscribe_aggregate(
+        &mut self,
+        aggregate: LiveAggregate,
+        mut ts: Timestamp,
+        mut value: LiveAggregateValue,
+    ) -> anyhow::Result<LiveAggregateSubscription> {
+        // Catch the value up to `processed_ts`, after which `advance_log` keeps
+        // it up to date.
+        if ts < self.processed_ts {
+            let persistence_version = self.persistence_version;
+            let result = self
+                .log
+                .for_each(ts.succ()?, self.processed_ts, |_, writes| {
+                    for (_, update) in writes {
+                        aggregate.apply(&mut value, update, persistence_version);
+                    }
+                });
+            match result {
+                Ok(()) => {},
+                Err(e) if e.is_out_of_retention() => {
+                    return Ok(LiveAggregateSubscription::invalid(self.sender.clone()));
+                },
+                Err(e) => return Err(e),
+            }
+            ts = self.processed_ts;
+        }
+
+        let entry = self.aggregates.vacant_entry();
+        let key = SubscriptionKey {
+            id: entry.key(),
+            seq: self.next_seq,
+        };
+        self.next_seq += 1;
+        let (sender, receiver) = watch::channel(LiveAggregateState::Valid { ts, value });
+        entry.insert(AggregateSubscriber {
+            aggregate,
+            value,
+            ts,
+            sender,
+            seq: key.seq,
+        });
+        Ok(LiveAggregateSubscription {
+            receiver,
+            key: Some(key),
+            sender: self.sender.clone(),
+        })
+    }
+
     pub fn advance_log(&mut self, next_ts: Timestamp) -> anyhow::Result<()> {
         let _timer = metrics::subscriptions_update_timer();
         block_in_place(|| {
             let from_ts = self.processed_ts.succ()?;
 
             let mut to_notify = BTreeSet::new();
-            self.log.for_each(from_ts, next_ts, |_, writes| {
+            let mut changed_aggregates = BTreeSet::new();
+            // Move the aggregates out of `self` so the closure can update them
+            // while `overlapping` borrows `self`.
+            let mut aggregates = mem::take(&mut self.aggregates);
+            let result = self.log.for_each(from_ts, next_ts, |ts, writes| {
                 for (_, document_change) in writes {
+                    for (aggregate_id, subscriber) in &mut aggregates {
+                        if ts > subscriber.ts
+                            && subscriber.aggregate.apply(
+                                &mut subscriber.value,
+                                document_change,
+                                self.persistence_version,
+                            )
+                        {
+                            changed_aggregates.insert(aggregate_id);
+                        }
+                    }
                     // We're applying a mutation to the document so if it already exists
                     // we need to remove it before writing the new version.
                     if let Some(ref old_document) = document_change.old_document {
@@ -289,7 +431,9 @@ impl SubscriptionManager {
                         self.overlapping(new_document, &mut to_notify, self.persistence_version);
                     }
                 }
-            })?;
+            });
+            self.aggregates = aggregates;
+            result?;
 
             // First, do a pass where we advance all of the valid subscriptions.
             for (subscriber_id, subscriber) in &mut self.subscribers {
@@ -301,6 +445,20 @@ impl SubscriptionManager {
             for subscriber_id in to_notify {
                 self._remove(subscriber_id);
             }
+            // Aggregates only wake up their subscribers if the value changed,
+            // but their timestamp always advances.
+            for (aggregate_id, subscriber) in &mut self.aggregates {
+                subscriber.ts = cmp::max(subscriber.ts, next_ts);
+                let state = LiveAggregateState::Valid {
+                    ts: subscriber.ts,
+                    value: subscriber.value,
+                };
+                let changed = changed_aggregates.contains(&aggregate_id);
+                subscriber.sender.send_if_modified(|current| {
+                    *current = state;
+                    changed
+                });
+            }
 
             assert!(self.processed_ts <= next_ts);
             self.processed_ts = next_ts;
@@ -370,6 +528,14 @@ impl SubscriptionManager {
         entry.sender.set(SubscriptionState::Invalid);
         self.subscriptions.remove(id, &entry.reads);
     }
+
+    fn remove_aggregate(&mut self, key: SubscriptionKey) {
+        match self.aggregates.get(key.id) {
+            Some(entry) if entry.seq == key.seq => {},
+            _ => return,
+        }
+        self.aggregates.remove(key.id);
+    }
 }
 
 #[derive(Copy, Clone, Eq, PartialEq, Hash)]
@@ -428,6 +594,173 @@ impl Drop for Subscription {
     }
 }
 
+/// A count of the documents in a range of an index, and optionally the sum of
+/// a numeric field over them, that the subscriptions worker keeps up to date
+/// from the write log. Subscribers are only woken up when the value changes,
+/// without anyone rereading the range.
+#[derive(Clone, Debug)]
+pub struct LiveAggregate {
+    pub index: TabletIndexName,
+    pub fields: IndexedFields,
+    pub interval: Interval,
+    /// Documents where this field is missing or isn't a finite number are
+    /// counted but don't contribute to the sum.
+    pub sum_field: Option<FieldPath>,
+}
+
+impl LiveAggregate {
+    /// Computes the value at the iterator's snapshot by walking the range of
+    /// the index, which isn't subject to transaction read limits.
+    pub async fn compute_value(
+        &self,
+        table_iterator: TableIterator,
+        index_id: IndexId,
+    ) -> anyhow::Result<LiveAggregateValue> {
+        let start = match &self.interval.start {
+            Start::Included(start) => start,
+        };
+        // The cursor is exclusive, so start just before the interval. Any
+        // proper prefix of the start key sorts before it.
+        let cursor = (!start.is_empty())
+            .then(|| CursorPosition::After(IndexKeyBytes(start[..start.len() - 1].to_vec())));
+        let stream = table_iterator.stream_documents_in_table_by_index(
+            *self.index.table(),
+            index_id,
+            self.fields.clone(),
+            cursor,
+        );
+        pin_mut!(stream);
+        let mut value = LiveAggregateValue::default();
+        while let Some((index_key, _, document)) = stream.try_next().await? {
+            if index_key.0[..] < start[..] {
+                continue;
+            }
+            if let End::Excluded(end) = &self.interval.end {
+                if index_key.0[..] >= end[..] {
+                    break;
+                }
+            }
+            value.count += 1;
+            value.sum += self.summand(&PackedDocument::pack(document));
+        }
+        Ok(value)
+    }
+
+    fn contains(&self, document: &PackedDocument, persistence_version: PersistenceVersion) -> bool {
+        *self.index.table() == document.id().tablet_id
+            && self.interval.contains(
+                &document
+                    .index_key(&self.fields, persistence_version)
+                    .into_bytes()
+                    .0,
+            )
+    }
+
+    fn summand(&self, document: &PackedDocument) -> f64 {
+        let Some(ref sum_field) = self.sum_field else {
+            return 0.;
+        };
+        let summand = match document.value().get_path(sum_field) {
+            Some(ConvexValue::Float64(f)) => f,
+            Some(ConvexValue::Int64(i)) => i as f64,
+            _ => return 0.,
+        };
+        if summand.is_finite() {
+            summand
+        } else {
+            0.
+        }
+    }
+
+    /// Applies a write to `value`, returning whether it changed.
+    fn apply(
+        &self,
+        value: &mut LiveAggregateValue,
+        update: &PackedDocumentUpdate,
+        persistence_version: PersistenceVersion,
+    ) -> bool {
+        let mut changed = false;
+        if let Some(ref old_document) = update.old_document {
+            if self.contains(old_document, persistence_version) {
+                value.count = value.count.saturating_sub(1);
+                value.sum -= self.summand(old_document);
+                changed = true;
+            }
+        }
+        if let Some(ref new_document) = update.new_document {
+            if self.contains(new_document, persistence_version) {
+                value.count += 1;
+                value.sum += self.summand(new_document);
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct LiveAggregateValue {
+    pub count: u64,
+    /// Always zero if the aggregate has no `sum_field`.
+    pub sum: f64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum LiveAggregateState {
+    Valid {
+        ts: Timestamp,
+        value: LiveAggregateValue,
+    },
+    Invalid,
+}
+
+/// A subscription to a `LiveAggregate`. The subscription becomes invalid if
+/// the worker can't catch it up from its initial timestamp, in which case the
+/// caller should recompute the value and resubscribe.
+pub struct LiveAggregateSubscription {
+    receiver: watch::Receiver<LiveAggregateState>,
+    key: Option<SubscriptionKey>,
+    sender: mpsc::Sender<SubscriptionRequest>,
+}
+
+impl LiveAggregateSubscription {
+    fn invalid(sender: mpsc::Sender<SubscriptionRequest>) -> Self {
+        let (_, receiver) = watch::channel(LiveAggregateState::Invalid);
+        Self {
+            receiver,
+            key: None,
+            sender,
+        }
+    }
+
+    /// Returns the latest value and the timestamp it's valid at, or `None` if
+    /// the subscription is no longer valid.
+    pub fn current(&self) -> Option<(Timestamp, LiveAggregateValue)> {
+        match *self.receiver.borrow() {
+            LiveAggregateState::Valid { ts, value } => Some((ts, value)),
+            LiveAggregateState::Invalid => None,
+        }
+    }
+
+    /// Waits for the value to change, returning `None` if the subscription is
+    /// no longer valid.
+    pub async fn wait_for_change(&mut self) -> Option<(Timestamp, LiveAggregateValue)> {
+        self.receiver.changed().await.ok()?;
+        self.receiver.borrow_and_update();
+        self.current()
+    }
+}
+
+impl Drop for LiveAggregateSubscription {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            let _: Result<_, _> = self
+                .sender
+                .try_send(SubscriptionRequest::CancelAggregate(key));
+        }
+    }
+}
+
 /// Tracks every subscriber for a given read-set.
 struct SubscriptionMap {
     indexed: BTreeMap<TabletIndexName, (IndexedFields, IntervalMap<SubscriberId>)>,
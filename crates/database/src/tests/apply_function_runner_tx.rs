@@ -251,6 +251,59 @@ async fn test_apply_function_runner_tx_merge_existing_writes(
     Ok(())
 }
 
+#[convex_macro::test_runtime]
+async fn test_apply_function_runner_tx_overwrites_existing_writes(
+    rt: TestRuntime,
+) -> anyhow::Result<()> {
+    let db = new_test_database(rt).await;
+    let mut backend_tx = db.begin_system().await?;
+    // Make writes before initializing funrun transaction
+    let id = UserFacingModel::new_root_for_test(&mut backend_tx)
+        .insert("table".parse()?, obj!("field" => "value")?)
+        .await?;
+    let begin_timestamp = backend_tx.begin_timestamp();
+
+    // Create a new tx as though it were in funrun
+    let mut function_runner_tx = db
+        .begin_with_ts(
+            Identity::system(),
+            *begin_timestamp,
+            FunctionUsageTracker::new(),
+        )
+        .await?;
+    let updates = backend_tx.writes().as_flat()?.clone().into_updates();
+    function_runner_tx.merge_writes(updates)?;
+
+    // Overwrite the document written before funrun started
+    UserFacingModel::new_root_for_test(&mut function_runner_tx)
+        .replace(id, obj!("field" => "new value")?)
+        .await?;
+
+    // Apply reads and writes to the backend_tx
+    let num_intervals = function_runner_tx.reads.num_intervals();
+    let user_tx_size = function_runner_tx.reads.user_tx_size().clone();
+    let system_tx_size = function_runner_tx.reads.system_tx_size().clone();
+    let reads = function_runner_tx.reads.clone().into_read_set();
+    let rows_read_by_tablet = function_runner_tx
+        .stats_by_tablet()
+        .iter()
+        .map(|(table, stats)| (*table, stats.rows_read))
+        .collect();
+    let updates = function_runner_tx.writes.as_flat()?.clone().into_updates();
+    backend_tx.apply_function_runner_tx(
+        *begin_timestamp,
+        reads,
+        num_intervals,
+        user_tx_size,
+        system_tx_size,
+        updates,
+        rows_read_by_tablet,
+    )?;
+
+    assert_transaction_writes_match(&backend_tx, &function_runner_tx)?;
+    Ok(())
+}
+
 #[convex_macro::test_runtime]
 async fn test_apply_function_runner_tx_merge_existing_writes_bad(
     rt: TestRuntime,
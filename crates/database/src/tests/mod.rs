@@ -35,12 +35,14 @@ use common::{
         Persistence,
     },
     query::{
+        Cursor,
         Expression,
         FullTableScan,
         IndexRange,
         IndexRangeExpression,
         Order,
         Query,
+        QueryHints,
         QueryOperator,
         QuerySource,
     },
@@ -105,9 +107,14 @@ use crate::{
         IndexWriter,
     },
     query::{
+        get_page,
+        hydrate_references,
+        GetPageRequest,
+        IndexKeyBound,
         PaginationOptions,
         ResolvedQuery,
         TableFilter,
+        MAX_HYDRATE_DEPTH,
     },
     table_summary::{
         write_snapshot,
@@ -134,6 +141,7 @@ use crate::{
 };
 
 mod randomized_search_tests;
+mod replication_tests;
 mod streaming_export_tests;
 mod usage_tracking;
 mod vector_tests;
@@ -462,6 +470,39 @@ async fn test_creation_time_success(rt: TestRuntime) -> anyhow::Result<()> {
     Ok(())
 }
 
+#[convex_macro::test_runtime]
+async fn test_begin_at_historical_ts(rt: TestRuntime) -> anyhow::Result<()> {
+    let database = new_test_database(rt.clone()).await;
+    let mut tx = database.begin(Identity::system()).await?;
+    let id = TestFacingModel::new(&mut tx)
+        .insert(&"table".parse()?, assert_obj!("x" => 1))
+        .await?;
+    let ts1 = database.commit(tx).await?;
+
+    let mut tx = database.begin(Identity::system()).await?;
+    TestFacingModel::new(&mut tx)
+        .replace(id, assert_obj!("x" => 2))
+        .await?;
+    let ts2 = database.commit(tx).await?;
+
+    let mut tx = database
+        .begin_at_historical_ts(Identity::system(), ts1, FunctionUsageTracker::new())
+        .await?;
+    let doc = tx.get(id).await?.unwrap();
+    assert_eq!(doc.value().get("x"), Some(&assert_val!(1)));
+
+    let err = database
+        .begin_at_historical_ts(
+            Identity::system(),
+            ts2.succ()?.succ()?,
+            FunctionUsageTracker::new(),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(err.short_msg(), "HistoricalTimestampInFuture");
+    Ok(())
+}
+
 #[convex_macro::test_runtime]
 async fn test_id_reuse_across_transactions(rt: TestRuntime) -> anyhow::Result<()> {
     let database = new_test_database(rt).await;
@@ -582,6 +623,7 @@ async fn test_query_filter(rt: TestRuntime) -> anyhow::Result<()> {
             Box::new(Expression::Literal(maybe_val!("eng"))),
             Box::new(Expression::Field("channel".parse()?)),
         ))],
+        hints: QueryHints::default(),
     };
     let results = run_query(database, namespace, query).await?;
     assert_eq!(results, vec![doc1, doc3]);
@@ -628,6 +670,7 @@ async fn test_query_limit(rt: TestRuntime) -> anyhow::Result<()> {
             order: Order::Asc,
         }),
         operators: vec![QueryOperator::Limit(1)],
+        hints: QueryHints::default(),
     };
     let results = run_query(database, namespace, query).await?;
     assert_eq!(results.len(), 1);
@@ -653,6 +696,7 @@ async fn test_full_table_scan_order(rt: TestRuntime) -> anyhow::Result<()> {
             order: Order::Asc,
         }),
         operators: vec![],
+        hints: QueryHints::default(),
     };
     let asc_results = run_query(database.clone(), namespace, asc_query).await?;
     assert_eq!(asc_results, vec![doc1.clone(), doc2.clone()],);
@@ -663,6 +707,7 @@ async fn test_full_table_scan_order(rt: TestRuntime) -> anyhow::Result<()> {
             order: Order::Desc,
         }),
         operators: vec![],
+        hints: QueryHints::default(),
     };
     let desc_results = run_query(database, namespace, desc_query).await?;
     assert_eq!(desc_results, vec![doc2, doc1],);
@@ -764,6 +809,7 @@ where
             order,
         }),
         operators: vec![],
+        hints: QueryHints::default(),
     };
     let actual = run_query(database, namespace, query).await?;
     assert_eq!(actual, expected);
@@ -896,6 +942,93 @@ proptest! {
     }
 }
 
+#[convex_macro::test_runtime]
+async fn test_live_aggregate(rt: TestRuntime) -> anyhow::Result<()> {
+    let DbFixtures {
+        db: database, tp, ..
+    } = DbFixtures::new(&rt).await?;
+    let table_name: TableName = str::parse("messages")?;
+    let namespace = TableNamespace::test_user();
+    let index_name = IndexName::new(table_name.clone(), "a_and_b".parse()?)?;
+
+    let mut tx = database.begin(Identity::system()).await?;
+    let begin_ts = tx.begin_timestamp();
+    IndexModel::new(&mut tx)
+        .add_application_index(
+            namespace,
+            IndexMetadata::new_backfilling(
+                *begin_ts,
+                index_name.clone(),
+                vec![str::parse("a")?, str::parse("b")?].try_into()?,
+            ),
+        )
+        .await?;
+    database.commit(tx).await?;
+
+    let mut tx = database.begin(Identity::system()).await?;
+    let values = insert_documents(&mut tx, table_name.clone()).await?;
+    database.commit(tx).await?;
+
+    let retention_validator = Arc::new(NoopRetentionValidator);
+    IndexWorker::new_terminating(rt, tp, retention_validator, database.clone()).await?;
+    let mut tx = database.begin_system().await?;
+    IndexModel::new(&mut tx)
+        .enable_index_for_testing(namespace, &index_name)
+        .await?;
+    database.commit(tx).await?;
+
+    let index_range = IndexRange {
+        index_name,
+        range: vec![
+            IndexRangeExpression::Gte("a".parse()?, val!(3)),
+            IndexRangeExpression::Lte("a".parse()?, val!(7)),
+        ],
+        order: Order::Asc,
+    };
+    let mut subscription = database
+        .subscribe_aggregate(
+            Identity::system(),
+            namespace,
+            index_range,
+            Some("b".parse()?),
+        )
+        .await?;
+    let b_total = (0..10 * TEST_PREFETCH_HINT).sum::<usize>() as f64;
+    let (_, value) = subscription.current().unwrap();
+    assert_eq!(value.count, 5 * 10 * TEST_PREFETCH_HINT as u64);
+    assert_eq!(value.sum, 5. * b_total);
+
+    // Inserting into the range pushes the new value.
+    let mut tx = database.begin(Identity::system()).await?;
+    TestFacingModel::new(&mut tx)
+        .insert(&table_name, assert_obj!("a" => 5, "b" => 10.5))
+        .await?;
+    let ts = database.commit(tx).await?;
+    let (value_ts, value) = subscription.wait_for_change().await.unwrap();
+    assert!(value_ts >= ts);
+    assert_eq!(value.count, 5 * 10 * TEST_PREFETCH_HINT as u64 + 1);
+    assert_eq!(value.sum, 5. * b_total + 10.5);
+
+    // Writes outside the range don't change the value, and moving a document
+    // out of the range removes it.
+    let mut tx = database.begin(Identity::system()).await?;
+    TestFacingModel::new(&mut tx)
+        .insert(&table_name, assert_obj!("a" => 1, "b" => 100))
+        .await?;
+    database.commit(tx).await?;
+    let moved = &values[3 * 10 * TEST_PREFETCH_HINT + 4];
+    let mut tx = database.begin(Identity::system()).await?;
+    UserFacingModel::new_root_for_test(&mut tx)
+        .replace(moved.id().into(), assert_obj!("a" => 8, "b" => 4))
+        .await?;
+    database.commit(tx).await?;
+    let (_, value) = subscription.wait_for_change().await.unwrap();
+    assert_eq!(value.count, 5 * 10 * TEST_PREFETCH_HINT as u64);
+    assert_eq!(value.sum, 5. * b_total + 6.5);
+
+    Ok(())
+}
+
 #[convex_macro::test_runtime]
 async fn test_query_cursor_reuse(rt: TestRuntime) -> anyhow::Result<()> {
     let database = new_test_database(rt).await;
@@ -957,6 +1090,251 @@ async fn test_query_cursor_reuse(rt: TestRuntime) -> anyhow::Result<()> {
     Ok(())
 }
 
+#[convex_macro::test_runtime]
+async fn test_query_cursor_after_table_number_change(rt: TestRuntime) -> anyhow::Result<()> {
+    let database = new_test_database(rt).await;
+    let namespace = TableNamespace::test_user();
+    let table_name: TableName = "table".parse()?;
+    let mut tx = database.begin(Identity::system()).await?;
+    let mut ids = vec![];
+    for i in 0..3 {
+        ids.push(
+            TestFacingModel::new(&mut tx)
+                .insert(&table_name, assert_obj!("n" => i))
+                .await?,
+        );
+    }
+    database.commit(tx).await?;
+
+    let mut tx = database.begin(Identity::system()).await?;
+    let query = Query::full_table_scan(table_name.clone(), Order::Asc);
+    let mut compiled_query = ResolvedQuery::new_bounded(
+        &mut tx,
+        namespace,
+        query.clone(),
+        PaginationOptions::ManualPagination {
+            start_cursor: None,
+            maximum_rows_read: None,
+            maximum_bytes_read: None,
+        },
+        None,
+        TableFilter::ExcludePrivateSystemTables,
+    )?;
+    compiled_query.next(&mut tx, None).await?;
+    let cursor = compiled_query.cursor().unwrap();
+    let table_number = ids[0].developer_id.table();
+    assert_eq!(cursor.table_number, Some(table_number));
+
+    // Pretend the cursor was issued before an import gave the table its
+    // current number.
+    let old_table_number = table_number.increment()?;
+    let old_cursor = Cursor {
+        position: cursor.position.with_table_number(old_table_number)?,
+        query_fingerprint: cursor.query_fingerprint,
+        table_number: Some(old_table_number),
+    };
+    let mut compiled_query = ResolvedQuery::<TestRuntime>::new_bounded(
+        &mut tx,
+        namespace,
+        query,
+        PaginationOptions::ManualPagination {
+            start_cursor: Some(old_cursor),
+            maximum_rows_read: None,
+            maximum_bytes_read: None,
+        },
+        None,
+        TableFilter::ExcludePrivateSystemTables,
+    )?;
+    let next = compiled_query.next(&mut tx, None).await?.unwrap();
+    assert_eq!(next.id(), ids[1]);
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_get_page(rt: TestRuntime) -> anyhow::Result<()> {
+    let database = new_test_database(rt).await;
+    let namespace = TableNamespace::test_user();
+    let table_name: TableName = "table".parse()?;
+    let mut tx = database.begin(Identity::system()).await?;
+    let mut ids = vec![];
+    for i in 0..5 {
+        ids.push(DeveloperDocumentId::from(
+            TestFacingModel::new(&mut tx)
+                .insert(&table_name, assert_obj!("n" => i))
+                .await?,
+        ));
+    }
+    database.commit(tx).await?;
+
+    let mut tx = database.begin(Identity::system()).await?;
+    let request = |start, order| GetPageRequest {
+        index_name: IndexName::by_creation_time(table_name.clone()),
+        start,
+        end: None,
+        order,
+        max_rows: 2,
+    };
+    let mut pages = vec![];
+    let mut start = None;
+    loop {
+        let page = get_page(
+            &mut tx,
+            namespace,
+            request(start, Order::Asc),
+            None,
+            TableFilter::ExcludePrivateSystemTables,
+        )
+        .await?;
+        pages.push(page.page.iter().map(|doc| doc.id()).collect::<Vec<_>>());
+        if !page.has_more {
+            break;
+        }
+        // Each page starts after the last index key of the previous one.
+        start = Some(IndexKeyBound {
+            key: page.index_keys.last().unwrap().clone(),
+            inclusive: false,
+        });
+    }
+    assert_eq!(
+        pages,
+        vec![vec![ids[0], ids[1]], vec![ids[2], ids[3]], vec![ids[4]]]
+    );
+
+    // A prefix of an index key bounds every document starting with it.
+    let page = get_page(
+        &mut tx,
+        namespace,
+        request(
+            Some(IndexKeyBound {
+                key: vec![],
+                inclusive: true,
+            }),
+            Order::Desc,
+        ),
+        None,
+        TableFilter::ExcludePrivateSystemTables,
+    )
+    .await?;
+    assert_eq!(
+        page.page.iter().map(|doc| doc.id()).collect::<Vec<_>>(),
+        vec![ids[4], ids[3]]
+    );
+    assert!(page.has_more);
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_hydrate_references(rt: TestRuntime) -> anyhow::Result<()> {
+    let database = new_test_database(rt).await;
+    let namespace = TableNamespace::test_user();
+    let users: TableName = "users".parse()?;
+    let posts: TableName = "posts".parse()?;
+    let mut tx = database.begin(Identity::system()).await?;
+    let alice = TestFacingModel::new(&mut tx)
+        .insert(&users, assert_obj!("name" => "alice"))
+        .await?;
+    let alice_id = DeveloperDocumentId::from(alice).encode();
+    let bob = TestFacingModel::new(&mut tx)
+        .insert(
+            &users,
+            assert_obj!("name" => "bob", "invitedBy" => alice_id.clone()),
+        )
+        .await?;
+    let bob_id = DeveloperDocumentId::from(bob).encode();
+    let carol = TestFacingModel::new(&mut tx)
+        .insert(&users, assert_obj!("name" => "carol"))
+        .await?;
+    let carol_id = DeveloperDocumentId::from(carol).encode();
+    tx.delete_inner(carol).await?;
+    let post = TestFacingModel::new(&mut tx)
+        .insert(
+            &posts,
+            assert_obj!(
+                "title" => "hello",
+                "author" => bob_id.clone(),
+                "editors" => array![ConvexValue::try_from(carol_id)?]?,
+            ),
+        )
+        .await?;
+    database.commit(tx).await?;
+
+    let mut tx = database.begin(Identity::system()).await?;
+    let mut schema_model = SchemaModel::new_root_for_test(&mut tx);
+    let db_schema = db_schema!(
+        users.clone() => DocumentSchema::Union(vec![object_validator!(
+            "name" => FieldValidator::required_field_type(Validator::String),
+            "invitedBy" => FieldValidator::optional_field_type(Validator::Id(users.clone())),
+        )]),
+        posts.clone() => DocumentSchema::Union(vec![object_validator!(
+            "title" => FieldValidator::required_field_type(Validator::String),
+            "author" => FieldValidator::required_field_type(Validator::Id(users.clone())),
+            "editors" => FieldValidator::required_field_type(Validator::Array(Box::new(
+                Validator::Id(users.clone())
+            ))),
+        )]),
+    );
+    let (schema_id, _) = schema_model.submit_pending(db_schema).await?;
+    schema_model.mark_validated(schema_id).await?;
+    schema_model.mark_active(schema_id).await?;
+    database.commit(tx).await?;
+
+    let mut tx = database.begin(Identity::system()).await?;
+    let post = ConvexValue::from(
+        UserFacingModel::new(&mut tx, namespace)
+            .get(post.into(), None)
+            .await?
+            .unwrap()
+            .into_value()
+            .0,
+    );
+    // One level replaces the post's references, but not the author's.
+    let hydrated = hydrate_references(
+        &mut tx,
+        namespace,
+        vec![post.clone()],
+        1,
+        None,
+        TableFilter::ExcludePrivateSystemTables,
+    )
+    .await?;
+    must_let!(let ConvexValue::Object(hydrated) = &hydrated[0]);
+    must_let!(let Some(ConvexValue::Object(author)) = hydrated.get("author"));
+    assert_eq!(author.get("name"), Some(&assert_val!("bob")));
+    assert_eq!(author.get("invitedBy"), Some(&assert_val!(alice_id)));
+    // References to deleted documents become null.
+    assert_eq!(
+        hydrated.get("editors"),
+        Some(&array![ConvexValue::Null]?.into())
+    );
+
+    let hydrated = hydrate_references(
+        &mut tx,
+        namespace,
+        vec![post.clone()],
+        2,
+        None,
+        TableFilter::ExcludePrivateSystemTables,
+    )
+    .await?;
+    must_let!(let ConvexValue::Object(hydrated) = &hydrated[0]);
+    must_let!(let Some(ConvexValue::Object(author)) = hydrated.get("author"));
+    must_let!(let Some(ConvexValue::Object(inviter)) = author.get("invitedBy"));
+    assert_eq!(inviter.get("name"), Some(&assert_val!("alice")));
+
+    let err = hydrate_references(
+        &mut tx,
+        namespace,
+        vec![post],
+        MAX_HYDRATE_DEPTH + 1,
+        None,
+        TableFilter::ExcludePrivateSystemTables,
+    )
+    .await
+    .unwrap_err();
+    assert!(err.is_bad_request());
+    Ok(())
+}
+
 #[convex_macro::test_runtime]
 async fn test_too_large_values(rt: TestRuntime) -> anyhow::Result<()> {
     let huge_obj = assert_obj!("huge" => vec![0; 1 << 22]);
@@ -1866,6 +2244,7 @@ async fn test_index_backfill(rt: TestRuntime) -> anyhow::Result<()> {
                 order,
             }),
             operators: vec![],
+            hints: QueryHints::default(),
         };
         let actual = run_query(db.clone(), namespace, query).await?;
         assert_eq!(actual, expected);
@@ -1963,6 +2342,7 @@ async fn test_index_write(rt: TestRuntime) -> anyhow::Result<()> {
                 order,
             }),
             operators: vec![],
+            hints: QueryHints::default(),
         };
         let actual = run_query(database.clone(), namespace, query).await?;
         assert_eq!(actual, expected);
@@ -2238,6 +2618,7 @@ async fn test_query_filter_readset(rt: TestRuntime) -> anyhow::Result<()> {
             Box::new(Expression::Literal(maybe_val!("eng"))),
             Box::new(Expression::Field("channel".parse()?)),
         ))],
+        hints: QueryHints::default(),
     };
     let mut tx = database.begin(Identity::system()).await?;
     let mut query_stream = ResolvedQuery::new(&mut tx, namespace, query)?;
@@ -2331,6 +2712,7 @@ async fn test_query_readset_empty_query(rt: TestRuntime) -> anyhow::Result<()> {
             order: Order::Asc,
         }),
         operators: vec![],
+        hints: QueryHints::default(),
     };
     let mut tx = database.begin(Identity::system()).await?;
     let mut query_stream = ResolvedQuery::new(&mut tx, namespace, query)?;
@@ -16,7 +16,10 @@ use async_trait::async_trait;
 use cmd_util::env::env_config;
 use common::{
     bootstrap_model::index::{
-        text_index::FragmentedTextSegment,
+        text_index::{
+            FragmentedTextSegment,
+            TextIndexAnalyzer,
+        },
         vector_index::FragmentedVectorSegment,
         IndexMetadata,
     },
@@ -26,11 +29,13 @@ use common::{
     query::{
         CursorPosition,
         Query,
+        QueryHints,
         QueryOperator,
         QuerySource,
         Search,
         SearchFilterExpression,
         SearchVersion,
+        TextSearchOptions,
     },
     types::{
         IndexName,
@@ -143,6 +148,14 @@ impl Scenario {
     }
 
     async fn new_with_searcher(rt: TestRuntime, searcher: impl Searcher) -> anyhow::Result<Self> {
+        Self::new_with_analyzer(rt, searcher, TextIndexAnalyzer::default()).await
+    }
+
+    async fn new_with_analyzer(
+        rt: TestRuntime,
+        searcher: impl Searcher,
+        analyzer: TextIndexAnalyzer,
+    ) -> anyhow::Result<Self> {
         let DbFixtures {
             db: database,
             search_storage,
@@ -169,6 +182,7 @@ impl Scenario {
             "test.by_text".parse()?,
             "searchField".parse()?,
             btreeset! {"filterField".parse()?},
+            analyzer,
         );
         IndexModel::new(&mut tx)
             .add_application_index(namespace, index)
@@ -279,10 +293,29 @@ impl Scenario {
         filter: Option<String>,
         ts: Option<Timestamp>,
         version: SearchVersion,
+    ) -> anyhow::Result<Vec<(ResolvedDocumentId, f64)>> {
+        self._query_with_options(
+            query_string,
+            filter,
+            ts,
+            version,
+            TextSearchOptions::default(),
+        )
+        .await
+    }
+
+    async fn _query_with_options<S: Into<String>>(
+        &self,
+        query_string: S,
+        filter: Option<String>,
+        ts: Option<Timestamp>,
+        version: SearchVersion,
+        options: TextSearchOptions,
     ) -> anyhow::Result<Vec<(ResolvedDocumentId, f64)>> {
         let mut filters = vec![SearchFilterExpression::Search(
             "searchField".parse()?,
             query_string.into(),
+            options,
         )];
         if let Some(filter_field) = filter {
             filters.push(SearchFilterExpression::Eq(
@@ -298,6 +331,7 @@ impl Scenario {
         let query = Query {
             source: QuerySource::Search(search),
             operators: vec![QueryOperator::Limit(MAX_CANDIDATE_REVISIONS)],
+            hints: QueryHints::default(),
         };
 
         let mut tx = if let Some(ts) = ts {
@@ -923,6 +957,72 @@ async fn test_fuzzy_disk(rt: TestRuntime) -> anyhow::Result<()> {
     Ok(())
 }
 
+#[convex_macro::test_runtime]
+async fn test_search_options(rt: TestRuntime) -> anyhow::Result<()> {
+    let mut scenario = Scenario::new(rt).await?;
+    scenario
+        ._patch("key1", "bartholomew wuz here", "test")
+        .await?;
+    scenario.backfill().await?;
+    scenario
+        ._patch("key2", "the quick brow fox", "test")
+        .await?;
+
+    let query = |query_string: &'static str, prefix: bool, max_edit_distance: u8| {
+        let options = TextSearchOptions {
+            prefix,
+            max_edit_distance,
+        };
+        scenario._query_with_options(query_string, None, None, SearchVersion::V1, options)
+    };
+    // Without options, V1 only matches whole words exactly.
+    assert_eq!(query("brown", false, 0).await?.len(), 0);
+    assert_eq!(query("bro", false, 0).await?.len(), 0);
+    assert_eq!(query("barth", false, 0).await?.len(), 0);
+
+    // Prefixes match in both the memory and disk indexes.
+    assert_eq!(query("bro", true, 0).await?.len(), 1);
+    assert_eq!(query("barth", true, 0).await?.len(), 1);
+    // Only the last word is a prefix.
+    assert_eq!(query("barth fox", true, 0).await?.len(), 1);
+
+    // Typos match up to the query's edit distance.
+    assert_eq!(query("brown", false, 1).await?.len(), 1);
+    assert_eq!(query("batholmew", false, 1).await?.len(), 0);
+    assert_eq!(query("batholmew", false, 2).await?.len(), 1);
+    // Short words have to match exactly.
+    assert_eq!(query("fax", false, 2).await?.len(), 0);
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_search_analyzer(rt: TestRuntime) -> anyhow::Result<()> {
+    let searcher = InProcessSearcher::new(rt.clone()).await?;
+    let mut scenario =
+        Scenario::new_with_analyzer(rt, searcher, TextIndexAnalyzer::English).await?;
+    scenario
+        ._patch("key1", "the foxes were running", "test")
+        .await?;
+    scenario.backfill().await?;
+    scenario._patch("key2", "a fox runs", "test").await?;
+
+    // Documents and queries are both stemmed, in the memory and disk indexes.
+    let results = scenario
+        ._query_with_scores("run", None, None, SearchVersion::V1)
+        .await?;
+    assert_eq!(results.len(), 2);
+    let results = scenario
+        ._query_with_scores("foxes", None, None, SearchVersion::V1)
+        .await?;
+    assert_eq!(results.len(), 2);
+    // Stop words aren't indexed.
+    let results = scenario
+        ._query_with_scores("the", None, None, SearchVersion::V1)
+        .await?;
+    assert_eq!(results.len(), 0);
+    Ok(())
+}
+
 // Previous regression
 #[convex_macro::test_runtime]
 async fn test_fuzzy_disk_snapshot_shortlist_ids_valid_with_empty_memory_index(
@@ -0,0 +1,96 @@
+use common::assert_obj;
+use keybroker::Identity;
+use runtime::testing::TestRuntime;
+use sync_types::Timestamp;
+
+use crate::{
+    database::StreamingExportTableFilter,
+    replication::{
+        ReplicaApplier,
+        ReplicatedCommit,
+        SerializedReplicatedCommit,
+    },
+    test_helpers::DbFixtures,
+    Database,
+    UserFacingModel,
+};
+
+async fn replicate(
+    leader: &Database<TestRuntime>,
+    follower: &Database<TestRuntime>,
+    cursor: Option<Timestamp>,
+) -> anyhow::Result<Timestamp> {
+    let deltas = leader
+        .document_deltas(
+            Identity::system(),
+            cursor,
+            StreamingExportTableFilter::default(),
+            200,
+            200,
+        )
+        .await?;
+    let mut cursor = cursor;
+    for commit in ReplicatedCommit::from_document_deltas(deltas) {
+        // Round trip through the wire format.
+        let serialized = serde_json::to_string(&SerializedReplicatedCommit::from(commit))?;
+        let commit = ReplicatedCommit::try_from(
+            serde_json::from_str::<SerializedReplicatedCommit>(&serialized)?,
+        )?;
+        cursor = Some(commit.ts);
+        let mut tx = follower.begin(Identity::system()).await?;
+        ReplicaApplier::new(&mut tx).apply(commit).await?;
+        follower.commit(tx).await?;
+    }
+    Ok(cursor.unwrap())
+}
+
+#[convex_macro::test_runtime]
+async fn test_replicate_inserts_and_deletes(rt: TestRuntime) -> anyhow::Result<()> {
+    let leader = DbFixtures::new(&rt).await?.db;
+    let follower = DbFixtures::new(&rt).await?.db;
+
+    let mut tx = leader.begin(Identity::system()).await?;
+    let doc1 = UserFacingModel::new_root_for_test(&mut tx)
+        .insert("table1".parse()?, assert_obj!("x" => 1))
+        .await?;
+    let doc2 = UserFacingModel::new_root_for_test(&mut tx)
+        .insert("table2".parse()?, assert_obj!("y" => "hi"))
+        .await?;
+    let leader_doc1 = UserFacingModel::new_root_for_test(&mut tx)
+        .get(doc1, None)
+        .await?
+        .unwrap();
+    leader.commit(tx).await?;
+    let cursor = replicate(&leader, &follower, None).await?;
+
+    let mut tx = follower.begin(Identity::system()).await?;
+    let follower_doc1 = UserFacingModel::new_root_for_test(&mut tx)
+        .get(doc1, None)
+        .await?
+        .unwrap();
+    assert_eq!(follower_doc1.into_value(), leader_doc1.into_value());
+    assert!(UserFacingModel::new_root_for_test(&mut tx)
+        .get(doc2, None)
+        .await?
+        .is_some());
+
+    let mut tx = leader.begin(Identity::system()).await?;
+    UserFacingModel::new_root_for_test(&mut tx)
+        .delete(doc2)
+        .await?;
+    leader.commit(tx).await?;
+    // Replaying from the start is idempotent.
+    replicate(&leader, &follower, None).await?;
+    replicate(&leader, &follower, Some(cursor)).await?;
+
+    let mut tx = follower.begin(Identity::system()).await?;
+    assert!(UserFacingModel::new_root_for_test(&mut tx)
+        .get(doc1, None)
+        .await?
+        .is_some());
+    assert!(UserFacingModel::new_root_for_test(&mut tx)
+        .get(doc2, None)
+        .await?
+        .is_none());
+    Ok(())
+}
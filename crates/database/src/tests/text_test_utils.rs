@@ -5,6 +5,7 @@ use common::{
     bootstrap_model::index::{
         text_index::{
             FragmentedTextSegment,
+            TextIndexAnalyzer,
             TextIndexSnapshot,
             TextIndexSnapshotData,
             TextIndexState,
@@ -20,10 +21,12 @@ use common::{
     persistence::PersistenceReader,
     query::{
         Query,
+        QueryHints,
         QueryOperator,
         QuerySource,
         Search,
         SearchFilterExpression,
+        TextSearchOptions,
     },
     runtime::testing::TestRuntime,
     types::{
@@ -321,6 +324,7 @@ impl TextFixtures {
         let filters = vec![SearchFilterExpression::Search(
             SEARCH_FIELD.parse()?,
             query_string.into(),
+            TextSearchOptions::default(),
         )];
         let search = Search {
             table: index_name.table().clone(),
@@ -331,6 +335,7 @@ impl TextFixtures {
         let query = Query {
             source: QuerySource::Search(search),
             operators: vec![QueryOperator::Limit(MAX_CANDIDATE_REVISIONS)],
+            hints: QueryHints::default(),
         };
         let mut query_stream = ResolvedQuery::new_with_version(
             &mut tx,
@@ -365,6 +370,7 @@ pub fn backfilling_text_index() -> anyhow::Result<IndexMetadata<TableName>> {
         index_name,
         search_field,
         btreeset![filter_field],
+        TextIndexAnalyzer::default(),
     );
     Ok(metadata)
 }
@@ -7,6 +7,7 @@ use common::{
         text_index::FragmentedTextSegment,
         vector_index::{
             FragmentedVectorSegment,
+            VectorDistanceMetric,
             VectorIndexBackfillState,
             VectorIndexSnapshot,
             VectorIndexSnapshotData,
@@ -446,6 +447,7 @@ fn new_backfilling_vector_index() -> anyhow::Result<IndexMetadata<TableName>> {
         vector_field,
         (2u32).try_into()?,
         btreeset![filter_field],
+        VectorDistanceMetric::default(),
     );
     Ok(metadata)
 }
@@ -11,6 +11,7 @@ use common::{
     bootstrap_model::index::{
         vector_index::{
             DeveloperVectorIndexConfig,
+            VectorDistanceMetric,
             VectorIndexBackfillState,
             VectorIndexSnapshot,
             VectorIndexSnapshotData,
@@ -64,6 +65,7 @@ use value::{
 };
 use vector::{
     cosine_similarity,
+    vector_score,
     PublicVectorSearchQueryResult,
     VectorSearch,
     VectorSearchExpression,
@@ -151,6 +153,15 @@ impl<RT: Runtime> Scenario<RT> {
     }
 
     async fn add_vector_index(&self, should_backfill: bool) -> anyhow::Result<()> {
+        self.add_vector_index_with_distance(should_backfill, VectorDistanceMetric::default())
+            .await
+    }
+
+    async fn add_vector_index_with_distance(
+        &self,
+        should_backfill: bool,
+        distance: VectorDistanceMetric,
+    ) -> anyhow::Result<()> {
         let table_name: TableName = TABLE_NAME.parse()?;
         let mut tx = self.database.begin(Identity::system()).await?;
         let namespace = TableNamespace::test_user();
@@ -162,6 +173,7 @@ impl<RT: Runtime> Scenario<RT> {
             INDEXED_FIELD.parse()?,
             DIMENSIONS.try_into()?,
             FILTER_FIELDS.iter().map(|f| f.parse()).try_collect()?,
+            distance,
         );
         IndexModel::new(&mut tx)
             .add_application_index(namespace, index)
@@ -960,6 +972,64 @@ async fn test_recall_multi_segment(rt: TestRuntime) -> anyhow::Result<()> {
     Ok(())
 }
 
+#[convex_macro::test_runtime]
+async fn test_recall_with_distance_metrics(rt: TestRuntime) -> anyhow::Result<()> {
+    for distance in [
+        VectorDistanceMetric::Cosine,
+        VectorDistanceMetric::DotProduct,
+        VectorDistanceMetric::Euclidean,
+    ] {
+        let scenario = Scenario::new(rt.clone(), ScenarioIndexState::None).await?;
+        scenario
+            .add_vector_index_with_distance(true, distance)
+            .await?;
+        let mut tx = scenario.database.begin(Identity::system()).await?;
+        let table_number = tx
+            .table_mapping()
+            .namespace(TABLE_NAMESPACE)
+            .name_to_number_user_input()(TABLE_NAME.parse()?)?;
+
+        let mut rng = rt.rng();
+        let mut by_id = BTreeMap::new();
+        for _ in 0..100 {
+            let vector = random_vector(&mut rng);
+            let obj = assert_obj!(INDEXED_FIELD => vector_to_value(vector.clone()));
+            let id = UserFacingModel::new_root_for_test(&mut tx)
+                .insert(TABLE_NAME.parse()?, obj)
+                .await?;
+            by_id.insert(id.internal_id(), vector);
+        }
+        scenario.database.commit(tx).await?;
+
+        let limit = 10u32;
+
+        let query = random_vector(&mut rng);
+        let mut expected: Vec<_> = by_id
+            .iter()
+            .map(|(id, vector)| PublicVectorSearchQueryResult {
+                id: DeveloperDocumentId::new(table_number, *id),
+                score: vector_score(distance, &query, vector),
+            })
+            .collect();
+        expected.sort_by(|a, b| a.cmp(b).reverse());
+        expected.truncate(limit as usize);
+
+        // Search the memory index and then, once it's backfilled, the disk
+        // index.
+        for _ in 0..2 {
+            let results = scenario
+                .search_with_limit(query.clone(), btreeset![], Some(limit))
+                .await?;
+
+            assert_eq!(results, expected, "{distance}");
+
+            scenario.backfill().await?;
+        }
+    }
+
+    Ok(())
+}
+
 proptest! {
     #![proptest_config(ProptestConfig {
         cases: 32 * env_config("CONVEX_PROPTEST_MULTIPLIER", 1),
@@ -1,5 +1,8 @@
 use crate::{
-    index_workers::writer::SearchIndexMetadataWriter,
+    index_workers::{
+        offline_builder::OfflineIndexBuilder,
+        writer::SearchIndexMetadataWriter,
+    },
     text_index_worker::text_meta::TextSearchIndex,
 };
 
@@ -9,4 +12,5 @@ pub mod flusher;
 mod text_meta;
 
 pub type TextIndexMetadataWriter<RT> = SearchIndexMetadataWriter<RT, TextSearchIndex>;
+pub type OfflineTextIndexBuilder<RT> = OfflineIndexBuilder<RT, TextSearchIndex>;
 pub use text_meta::BuildTextIndexArgs;
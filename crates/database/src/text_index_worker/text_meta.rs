@@ -28,6 +28,7 @@ use common::{
         ParsedDocument,
         ResolvedDocument,
     },
+    knobs::SEARCH_INDEX_SIZE_SOFT_LIMIT,
     persistence::{
         DocumentStream,
         RepeatablePersistence,
@@ -79,6 +80,7 @@ use crate::{
             SegmentType,
             SnapshotData,
         },
+        offline_builder::OfflineIndexBuilder,
         search_flusher::MultipartBuildType,
     },
     Snapshot,
@@ -112,6 +114,28 @@ pub struct BuildTextIndexArgs {
     pub segment_term_metadata_fetcher: Arc<dyn SegmentTermMetadataFetcher>,
 }
 
+impl<RT: Runtime> OfflineIndexBuilder<RT, TextSearchIndex> {
+    pub fn new(
+        runtime: RT,
+        storage: Arc<dyn Storage>,
+        developer_config: DeveloperTextIndexConfig,
+        segment_term_metadata_fetcher: Arc<dyn SegmentTermMetadataFetcher>,
+        snapshot_ts: Timestamp,
+    ) -> Self {
+        Self::with_build_args(
+            runtime,
+            storage.clone(),
+            developer_config,
+            BuildTextIndexArgs {
+                search_storage: storage,
+                segment_term_metadata_fetcher,
+            },
+            snapshot_ts,
+            *SEARCH_INDEX_SIZE_SOFT_LIMIT,
+        )
+    }
+}
+
 #[async_trait]
 impl SearchIndex for TextSearchIndex {
     type BuildIndexArgs = BuildTextIndexArgs;
@@ -210,7 +234,7 @@ impl SearchIndex for TextSearchIndex {
         schema: &Self::Schema,
         index_path: &PathBuf,
         documents: DocumentStream<'_>,
-        reader: RepeatablePersistence,
+        reader: Option<RepeatablePersistence>,
         previous_segments: &mut Self::PreviousSegments,
         lower_bound_ts: Option<Timestamp>,
         BuildTextIndexArgs {
@@ -220,7 +244,12 @@ impl SearchIndex for TextSearchIndex {
         multipart_build_type: MultipartBuildType,
     ) -> anyhow::Result<Option<Self::NewSegment>> {
         let revision_stream = match multipart_build_type {
-            MultipartBuildType::Partial(_) => Box::pin(stream_revision_pairs(documents, &reader)),
+            MultipartBuildType::Partial(_) => {
+                let reader = reader
+                    .as_ref()
+                    .context("Partial builds need persistence to read previous revisions")?;
+                Box::pin(stream_revision_pairs(documents, reader))
+            },
             // Create a fake revision stream for complete builds because we are building from
             // scratch so we don't need to look up previous revisions. We know there are no deletes.
             MultipartBuildType::IncrementalComplete { .. } => documents
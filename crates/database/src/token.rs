@@ -41,7 +41,10 @@ impl Token {
     ) -> Self {
         use std::time::Duration;
 
-        use common::types::TabletIndexName;
+        use common::{
+            bootstrap_model::index::text_index::TextIndexAnalyzer,
+            types::TabletIndexName,
+        };
         use pb::searchlight::TextQueryTerm;
         use search::{
             QueryReads,
@@ -55,7 +58,11 @@ impl Token {
         let mut text_queries: WithHeapSize<Vec<TextQueryTermRead>> = WithHeapSize::default();
 
         for term in terms {
-            text_queries.push(TextQueryTermRead::new(field_path.clone(), term));
+            text_queries.push(TextQueryTermRead::new(
+                field_path.clone(),
+                TextIndexAnalyzer::default(),
+                term,
+            ));
         }
 
         let query_reads = QueryReads::new(text_queries, WithHeapSize::default());
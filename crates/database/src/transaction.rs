@@ -109,7 +109,9 @@ use crate::{
         },
     },
     committer::table_dependency_sort_key,
+    document_access::sample_document_read,
     execution_size::FunctionExecutionSize,
+    materialized_aggregates::update_materialized_aggregates,
     metrics,
     patch::PatchValue,
     preloaded::PreloadedIndexRange,
@@ -150,6 +152,10 @@ pub const DEFAULT_PAGE_SIZE: usize = 512;
 pub const MAX_PAGE_SIZE: usize = 1024;
 pub struct Transaction<RT: Runtime> {
     pub(crate) identity: Identity,
+    /// Whether a function running in this transaction has read its user
+    /// identity. Results of transactions that haven't don't depend on who
+    /// ran them.
+    pub(crate) observed_identity: bool,
     pub(crate) id_generator: TransactionIdGenerator,
 
     pub(crate) next_creation_time: CreationTime,
@@ -222,6 +228,7 @@ impl<RT: Runtime> Transaction<RT> {
     ) -> Self {
         Self {
             identity,
+            observed_identity: false,
             reads: TransactionReadSet::new(),
             writes: NestedWrites::new(Writes::new()),
             id_generator,
@@ -322,6 +329,17 @@ impl<RT: Runtime> Transaction<RT> {
         }
     }
 
+    /// Like [`Self::user_identity`], for when the result of the transaction
+    /// depends on it, as it does when a function reads `ctx.auth`.
+    pub fn observe_user_identity(&mut self) -> Option<UserIdentityAttributes> {
+        self.observed_identity = true;
+        self.user_identity()
+    }
+
+    pub fn observed_identity(&self) -> bool {
+        self.observed_identity
+    }
+
     pub fn authentication_token(&self) -> AuthenticationToken {
         self.identity.clone().into()
     }
@@ -453,9 +471,11 @@ impl<RT: Runtime> Transaction<RT> {
 
     // Checks that if this transaction already has some writes, they are included
     // in the given `updates`. This means the passed in `updates` are a superset
-    // of the existing `updates` on this transaction, and that the merged-in
-    // writes cannot modify documents already written to in this transaction.
-    // In most scenarios this transaction will have no writes.
+    // of the existing `updates` on this transaction. The merged-in writes may
+    // change documents already written to in this transaction, e.g. when a
+    // mutation batch runs several mutations in one transaction, as long as they
+    // start from the same document. In most scenarios this transaction will
+    // have no writes.
     pub fn merge_writes(
         &mut self,
         updates: OrdMap<ResolvedDocumentId, DocumentUpdate>,
@@ -473,15 +493,22 @@ impl<RT: Runtime> Transaction<RT> {
 
         let mut preserved_update_count = 0;
         for (id, update) in updates {
-            // Ensure that the existing update matches, and that
-            // that the merged-in writes didn't otherwise modify documents
-            // already written to in this transaction.
+            // Ensure that the existing update is preserved, either as is or
+            // followed by a later write to the same document.
             if let Some(existing_update) = existing_updates.get(&id) {
+                preserved_update_count += 1;
+                if existing_update == &update {
+                    continue;
+                }
                 anyhow::ensure!(
-                    existing_update == &update,
+                    existing_update.old_document == update.old_document,
                     "Conflicting updates for document {id}"
                 );
-                preserved_update_count += 1;
+                self.apply_validated_write(
+                    id,
+                    existing_update.new_document.clone(),
+                    update.new_document,
+                )?;
                 continue;
             }
 
@@ -559,6 +586,7 @@ impl<RT: Runtime> Transaction<RT> {
         SchemaModel::new(self, namespace)
             .enforce(&new_document)
             .await?;
+        update_materialized_aggregates(self, id, Some(&old_document), Some(&new_document)).await?;
 
         self.apply_validated_write(id, Some(old_document), Some(new_document.clone()))?;
         Ok(new_document)
@@ -597,6 +625,7 @@ impl<RT: Runtime> Transaction<RT> {
         SchemaModel::new(self, namespace)
             .enforce(&new_document)
             .await?;
+        update_materialized_aggregates(self, id, Some(&old_document), Some(&new_document)).await?;
 
         self.apply_validated_write(
             new_document.id(),
@@ -622,6 +651,7 @@ impl<RT: Runtime> Transaction<RT> {
                     format!("Delete on nonexistent document ID {id}"),
                 ))?;
 
+        update_materialized_aggregates(self, id, Some(&document), None).await?;
         self.apply_validated_write(document.id(), Some(document.clone()), None)?;
         Ok(document)
     }
@@ -892,6 +922,7 @@ impl<RT: Runtime> Transaction<RT> {
                 let component_path = self
                     .component_path_for_document_id(doc.id())?
                     .unwrap_or_default();
+                sample_document_read(&self.runtime, doc.id(), &table_name);
                 self.reads.record_read_document(
                     component_path,
                     table_name,
@@ -998,6 +1029,7 @@ impl<RT: Runtime> Transaction<RT> {
             .table_mapping()
             .tablet_namespace(document_id.tablet_id)?;
         SchemaModel::new(self, namespace).enforce(&document).await?;
+        update_materialized_aggregates(self, document_id, None, Some(&document)).await?;
         self.apply_validated_write(document_id, None, Some(document))?;
         Ok(document_id)
     }
@@ -1030,6 +1062,7 @@ impl<RT: Runtime> Transaction<RT> {
         let component_path = self
             .component_path_for_document_id(document.id())?
             .unwrap_or_default();
+        sample_document_read(&self.runtime, document.id(), table_name);
         self.reads.record_read_document(
             component_path,
             table_name.clone(),
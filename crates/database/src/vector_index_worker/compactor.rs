@@ -89,6 +89,10 @@ mod tests {
     use vector::VectorSearch;
 
     use crate::{
+        bootstrap_model::index_workers::{
+            CompactionConfigOverrides,
+            IndexWorkerMetadataModel,
+        },
         tests::vector_test_utils::{
             VectorFixtures,
             VECTOR_SIZE_BYTES,
@@ -170,6 +174,91 @@ mod tests {
         Ok(())
     }
 
+    #[convex_macro::test_runtime]
+    async fn manual_compaction_merges_segments_below_min_compaction_segments(
+        rt: TestRuntime,
+    ) -> anyhow::Result<()> {
+        let fixtures = VectorFixtures::new(rt.clone()).await?;
+        let index_data = fixtures.enabled_vector_index().await?;
+        let index_id = index_data.index_id.internal_id();
+
+        for _ in 0..2 {
+            fixtures
+                .add_document_vec_array(index_data.index_name.table(), [3f64, 4f64])
+                .await?;
+            fixtures.backfill().await?;
+        }
+        let compactor = fixtures.new_compactor().await?;
+        let (metrics, _) = compactor.step().await?;
+        assert!(metrics.is_empty());
+
+        let mut tx = fixtures.db.begin_system().await?;
+        IndexWorkerMetadataModel::new(&mut tx)
+            .request_vector_compaction(index_id)
+            .await?;
+        fixtures.db.commit(tx).await?;
+
+        let (metrics, _) = compactor.step().await?;
+        assert_eq!(
+            metrics,
+            btreemap! { index_data.resolved_index_name.clone() => 2 }
+        );
+        let segments = fixtures
+            .get_segments_metadata(index_data.index_name.clone())
+            .await?;
+        assert_eq!(segments.len(), 1);
+
+        // With nothing left to merge, the next step marks the request done.
+        let (metrics, _) = compactor.step().await?;
+        assert!(metrics.is_empty());
+        let mut tx = fixtures.db.begin_system().await?;
+        let manual_compaction = IndexWorkerMetadataModel::new(&mut tx)
+            .get_metadata(index_id)
+            .await?
+            .unwrap()
+            .into_value()
+            .index_metadata
+            .manual_compaction()
+            .cloned()
+            .unwrap();
+        assert!(manual_compaction.completed_ts.is_some());
+        assert_eq!(manual_compaction.segments_compacted, 2);
+
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn compaction_overrides_lower_min_compaction_segments(
+        rt: TestRuntime,
+    ) -> anyhow::Result<()> {
+        let fixtures = VectorFixtures::new(rt.clone()).await?;
+        let index_data = fixtures.enabled_vector_index().await?;
+
+        for _ in 0..2 {
+            fixtures
+                .add_document_vec_array(index_data.index_name.table(), [3f64, 4f64])
+                .await?;
+            fixtures.backfill().await?;
+        }
+        let mut tx = fixtures.db.begin_system().await?;
+        IndexWorkerMetadataModel::new(&mut tx)
+            .set_vector_compaction_overrides(
+                index_data.index_id.internal_id(),
+                CompactionConfigOverrides {
+                    min_compaction_segments: Some(2),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        fixtures.db.commit(tx).await?;
+
+        let compactor = fixtures.new_compactor().await?;
+        let (metrics, _) = compactor.step().await?;
+        assert_eq!(metrics, btreemap! { index_data.resolved_index_name => 2 });
+
+        Ok(())
+    }
+
     #[convex_macro::test_runtime]
     async fn compact_with_enabled_index_multiple_large_segments_compacts_them(
         rt: TestRuntime,
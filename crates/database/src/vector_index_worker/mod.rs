@@ -1,6 +1,13 @@
+use crate::{
+    index_workers::offline_builder::OfflineIndexBuilder,
+    vector_index_worker::vector_meta::VectorSearchIndex,
+};
+
 pub mod compactor;
 pub mod fast_forward;
 pub mod flusher;
 mod vector_meta;
 
 pub use vector_meta::BuildVectorIndexArgs;
+
+pub type OfflineVectorIndexBuilder<RT> = OfflineIndexBuilder<RT, VectorSearchIndex>;
@@ -22,6 +22,10 @@ use common::{
         ParsedDocument,
         ResolvedDocument,
     },
+    knobs::{
+        MULTI_SEGMENT_FULL_SCAN_THRESHOLD_KB,
+        VECTOR_INDEX_SIZE_SOFT_LIMIT,
+    },
     persistence::{
         DocumentStream,
         RepeatablePersistence,
@@ -61,6 +65,7 @@ use crate::{
             SegmentType,
             SnapshotData,
         },
+        offline_builder::OfflineIndexBuilder,
         search_flusher::MultipartBuildType,
     },
     Snapshot,
@@ -131,6 +136,26 @@ pub struct BuildVectorIndexArgs {
     pub full_scan_threshold_bytes: usize,
 }
 
+impl<RT: Runtime> OfflineIndexBuilder<RT, VectorSearchIndex> {
+    pub fn new(
+        runtime: RT,
+        storage: Arc<dyn Storage>,
+        developer_config: DeveloperVectorIndexConfig,
+        snapshot_ts: Timestamp,
+    ) -> Self {
+        Self::with_build_args(
+            runtime,
+            storage,
+            developer_config,
+            BuildVectorIndexArgs {
+                full_scan_threshold_bytes: *MULTI_SEGMENT_FULL_SCAN_THRESHOLD_KB,
+            },
+            snapshot_ts,
+            *VECTOR_INDEX_SIZE_SOFT_LIMIT,
+        )
+    }
+}
+
 #[async_trait]
 impl SearchIndex for VectorSearchIndex {
     type BuildIndexArgs = BuildVectorIndexArgs;
@@ -206,7 +231,7 @@ impl SearchIndex for VectorSearchIndex {
         schema: &Self::Schema,
         index_path: &PathBuf,
         documents: DocumentStream<'_>,
-        _reader: RepeatablePersistence,
+        _reader: Option<RepeatablePersistence>,
         previous_segments: &mut Self::PreviousSegments,
         _document_log_lower_bound: Option<Timestamp>,
         BuildVectorIndexArgs {
@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use common::{
     document::{
         DeveloperDocument,
@@ -8,12 +10,21 @@ use common::{
     version::Version,
 };
 use value::{
+    ConvexObject,
+    ConvexValue,
     DeveloperDocumentId,
     ResolvedDocumentId,
     TableNamespace,
+    TabletId,
+};
+
+use crate::{
+    virtual_tables::tables_debug::DOCUMENT_COUNT_FIELD,
+    TableModel,
+    Transaction,
 };
 
-use crate::Transaction;
+pub mod tables_debug;
 
 pub struct VirtualTable<'a, RT: Runtime> {
     tx: &'a mut Transaction<RT>,
@@ -47,28 +58,51 @@ impl<'a, RT: Runtime> VirtualTable<'a, RT> {
         // `get_inner` doesn't count as bandwidth. It's the caller's
         // responsibility to count bandwidth.
         let result = self.tx.get_inner(id_, system_table_name).await?;
-        let table_mapping = self.tx.table_mapping().clone();
         match result {
             Some((doc, ts)) => {
-                let doc = self.tx.virtual_system_mapping().system_to_virtual_doc(
-                    doc,
-                    &table_mapping,
-                    version,
-                )?;
+                let doc = self.map_system_doc_to_virtual_doc(doc, version).await?;
                 Ok(Some((doc, ts)))
             },
             None => Ok(None),
         }
     }
 
-    pub fn map_system_doc_to_virtual_doc(
+    pub async fn map_system_doc_to_virtual_doc(
         &mut self,
         doc: ResolvedDocument,
         version: Option<Version>,
     ) -> anyhow::Result<DeveloperDocument> {
+        let system_tablet_id = doc.id().tablet_id;
         let table_mapping = self.tx.table_mapping().clone();
-        self.tx
-            .virtual_system_mapping()
-            .system_to_virtual_doc(doc, &table_mapping, version)
+        let virtual_doc =
+            self.tx
+                .virtual_system_mapping()
+                .system_to_virtual_doc(doc, &table_mapping, version)?;
+        if self.tx.bootstrap_tables().is_tables_table(system_tablet_id) {
+            // The `_tables_debug` mapper can't count documents on its own, so
+            // fill in the count for the described tablet here.
+            return self.add_document_count(virtual_doc).await;
+        }
+        Ok(virtual_doc)
+    }
+
+    async fn add_document_count(
+        &mut self,
+        doc: DeveloperDocument,
+    ) -> anyhow::Result<DeveloperDocument> {
+        let tablet_id = TabletId(doc.internal_id());
+        let count = TableModel::new(self.tx).count_tablet(tablet_id).await?;
+        let id = doc.id();
+        let creation_time = doc.creation_time();
+        let mut fields: BTreeMap<_, _> = doc.into_value().0.into();
+        fields.insert(
+            DOCUMENT_COUNT_FIELD.clone(),
+            ConvexValue::Float64(count as f64),
+        );
+        Ok(DeveloperDocument::new(
+            id,
+            creation_time,
+            ConvexObject::try_from(fields)?,
+        ))
     }
 }
@@ -0,0 +1,236 @@
+use std::{
+    collections::BTreeMap,
+    sync::LazyLock,
+};
+
+use common::{
+    bootstrap_model::tables::{
+        TableMetadata,
+        TableState,
+    },
+    document::{
+        DeveloperDocument,
+        ParsedDocument,
+        ResolvedDocument,
+        CREATION_TIME_FIELD,
+        ID_FIELD,
+    },
+    types::{
+        GenericIndexName,
+        IndexName,
+        TableName,
+    },
+    version::Version,
+    virtual_system_mapping::{
+        VirtualSystemDocMapper,
+        VirtualSystemMapping,
+    },
+};
+use value::{
+    val,
+    ConvexObject,
+    ConvexValue,
+    FieldName,
+    TableMapping,
+    TableNamespace,
+    TabletId,
+};
+
+pub static TABLES_DEBUG_VIRTUAL_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_tables_debug"
+        .parse()
+        .expect("_tables_debug is not a valid virtual table name")
+});
+pub static TABLES_DEBUG_VIRTUAL_INDEX_BY_ID: LazyLock<IndexName> =
+    LazyLock::new(|| GenericIndexName::by_id(TABLES_DEBUG_VIRTUAL_TABLE.clone()));
+pub static TABLES_DEBUG_VIRTUAL_INDEX_BY_CREATION_TIME: LazyLock<IndexName> =
+    LazyLock::new(|| GenericIndexName::by_creation_time(TABLES_DEBUG_VIRTUAL_TABLE.clone()));
+
+/// Field filled in by `VirtualTable` after mapping, since counting documents
+/// requires the transaction.
+pub static DOCUMENT_COUNT_FIELD: LazyLock<FieldName> = LazyLock::new(|| {
+    "documentCount"
+        .parse()
+        .expect("Invalid documentCount field")
+});
+
+/// Maps `_tables` documents to `_tables_debug`, surfacing how each tablet is
+/// wired into the table mapping. Useful for diagnosing snapshot import edge
+/// cases, e.g. a Hidden tablet whose table number collides with an Active
+/// table of a different name.
+pub struct TablesDebugDocMapper;
+
+impl VirtualSystemDocMapper for TablesDebugDocMapper {
+    fn system_to_virtual_doc(
+        &self,
+        virtual_system_mapping: &VirtualSystemMapping,
+        doc: ResolvedDocument,
+        table_mapping: &TableMapping,
+        _version: Version,
+    ) -> anyhow::Result<DeveloperDocument> {
+        // `_tables` documents have the same internal id as the tablet they
+        // describe.
+        let tablet_id = TabletId(doc.id().internal_id());
+        let metadata: ParsedDocument<TableMetadata> = doc.clone().try_into()?;
+        let metadata: TableMetadata = metadata.into_value();
+
+        let namespaced_mapping = table_mapping.namespace(metadata.namespace);
+        let number_owner = namespaced_mapping.number_to_tablet()(metadata.number)
+            .ok()
+            .filter(|owner| *owner != tablet_id);
+        let public_table = PublicTableDebugInfo {
+            tablet_id,
+            name: metadata.name,
+            table_number: u32::from(metadata.number),
+            state: metadata.state,
+            namespace: metadata.namespace,
+            in_table_mapping: table_mapping.tablet_id_exists(tablet_id),
+            is_active: table_mapping.is_active(tablet_id),
+            conflicting_tablet_id: number_owner,
+        };
+        let mut public_table_resolved: ConvexObject = public_table.try_into()?;
+
+        let virtual_developer_id =
+            virtual_system_mapping.system_resolved_id_to_virtual_developer_id(doc.id())?;
+
+        let mut fields: BTreeMap<_, _> = public_table_resolved.into();
+        fields.insert(ID_FIELD.to_owned().into(), virtual_developer_id.into());
+        if let Some(t) = doc.creation_time() {
+            fields.insert(
+                CREATION_TIME_FIELD.to_owned().into(),
+                ConvexValue::from(f64::from(t)),
+            );
+        }
+        public_table_resolved = fields.try_into()?;
+
+        let public_doc = DeveloperDocument::new(
+            virtual_developer_id,
+            doc.creation_time(),
+            public_table_resolved,
+        );
+        Ok(public_doc)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct PublicTableDebugInfo {
+    tablet_id: TabletId,
+    name: TableName,
+    table_number: u32,
+    state: TableState,
+    namespace: TableNamespace,
+    /// Whether the tablet can be looked up by id in the table mapping. Tablets
+    /// in `Deleting` are removed from the mapping.
+    in_table_mapping: bool,
+    /// Whether the tablet is the one that its table number resolves to.
+    is_active: bool,
+    /// Another tablet that currently owns this table number in the same
+    /// namespace, if any.
+    conflicting_tablet_id: Option<TabletId>,
+}
+
+impl TryFrom<PublicTableDebugInfo> for ConvexObject {
+    type Error = anyhow::Error;
+
+    fn try_from(info: PublicTableDebugInfo) -> Result<Self, Self::Error> {
+        let mut obj: BTreeMap<FieldName, ConvexValue> = BTreeMap::new();
+        obj.insert("tabletId".parse()?, info.tablet_id.to_string().try_into()?);
+        obj.insert("name".parse()?, String::from(info.name).try_into()?);
+        obj.insert(
+            "tableNumber".parse()?,
+            ConvexValue::Float64(info.table_number as f64),
+        );
+        let state = match info.state {
+            TableState::Active => "active",
+            TableState::Hidden => "hidden",
+            TableState::Deleting => "deleting",
+        };
+        obj.insert("state".parse()?, val!(state));
+        obj.insert(
+            "componentId".parse()?,
+            match info.namespace {
+                TableNamespace::Global => val!(null),
+                TableNamespace::ByComponent(id) => val!(id.to_string()),
+            },
+        );
+        obj.insert(
+            "inTableMapping".parse()?,
+            ConvexValue::Boolean(info.in_table_mapping),
+        );
+        obj.insert("isActive".parse()?, ConvexValue::Boolean(info.is_active));
+        obj.insert(
+            "conflictingTabletId".parse()?,
+            match info.conflicting_tablet_id {
+                None => val!(null),
+                Some(tablet_id) => val!(tablet_id.to_string()),
+            },
+        );
+        ConvexObject::try_from(obj)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{
+        bootstrap_model::tables::TABLES_TABLE,
+        version::Version,
+        virtual_system_mapping::{
+            VirtualSystemDocMapper,
+            VirtualSystemMapping,
+        },
+    };
+    use runtime::testing::TestRuntime;
+    use value::{
+        ConvexValue,
+        TableNamespace,
+    };
+
+    use super::TablesDebugDocMapper;
+    use crate::{
+        test_helpers::new_tx,
+        TableModel,
+    };
+
+    #[convex_macro::test_runtime]
+    async fn test_tables_debug_doc(rt: TestRuntime) -> anyhow::Result<()> {
+        let mut tx = new_tx(rt).await?;
+        let namespace = TableNamespace::test_user();
+        let table_name = "my_table".parse()?;
+        TableModel::new(&mut tx)
+            .insert_table_metadata(namespace, &table_name)
+            .await?;
+        let table_id = tx.table_mapping().namespace(namespace).id(&table_name)?;
+        let table_doc_id = tx
+            .bootstrap_tables()
+            .table_resolved_doc_id(table_id.tablet_id);
+        let table_doc = tx.get(table_doc_id).await?.unwrap();
+        assert_eq!(
+            tx.table_mapping().tablet_name(table_doc_id.tablet_id)?,
+            *TABLES_TABLE
+        );
+
+        let table_mapping = tx.table_mapping().clone();
+        let doc = TablesDebugDocMapper.system_to_virtual_doc(
+            &VirtualSystemMapping::default(),
+            table_doc,
+            &table_mapping,
+            Version::parse("1.16.0")?,
+        )?;
+        let value = doc.into_value();
+        assert_eq!(
+            value.get("tabletId"),
+            Some(&ConvexValue::try_from(table_id.tablet_id.to_string())?)
+        );
+        assert_eq!(value.get("name"), Some(&ConvexValue::try_from("my_table")?));
+        assert_eq!(
+            value.get("tableNumber"),
+            Some(&ConvexValue::Float64(
+                u32::from(table_id.table_number) as f64
+            ))
+        );
+        assert_eq!(value.get("state"), Some(&ConvexValue::try_from("active")?));
+        assert_eq!(value.get("isActive"), Some(&ConvexValue::Boolean(true)));
+        assert_eq!(value.get("conflictingTabletId"), Some(&ConvexValue::Null));
+        Ok(())
+    }
+}
@@ -365,6 +365,19 @@ impl LogReader {
             inner.refresh_token(token, max_ts)
         })
     }
+
+    pub fn max_ts(&self) -> Timestamp {
+        block_in_place(|| self.inner.read().max_ts())
+    }
+
+    /// Blocks until the log has advanced past the given timestamp.
+    pub async fn wait_for_higher_ts(&self, target_ts: Timestamp) -> Timestamp {
+        let fut = block_in_place(|| self.inner.write().wait_for_higher_ts(target_ts));
+        fut.await;
+        let result = block_in_place(|| self.inner.read().max_ts());
+        assert!(result > target_ts);
+        result
+    }
 }
 
 /// LogWriter can append to the log.
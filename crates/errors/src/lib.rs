@@ -48,6 +48,7 @@ pub enum ErrorCode {
     NotFound,
     ClientDisconnect,
     RateLimited,
+    PayloadTooLarge,
 
     Overloaded,
     RejectedBeforeExecution,
@@ -139,6 +140,22 @@ impl ErrorMetadata {
         }
     }
 
+    /// Payload too large. Maps to 413 in HTTP.
+    ///
+    /// The short_msg should be a CapitalCamelCased describing the error (eg
+    /// RequestBodyTooLarge). The msg should be a descriptive message targeted
+    /// toward the developer, including the limit that was exceeded.
+    pub fn payload_too_large(
+        short_msg: impl Into<Cow<'static, str>>,
+        msg: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        Self {
+            code: ErrorCode::PayloadTooLarge,
+            short_msg: short_msg.into(),
+            msg: msg.into(),
+        }
+    }
+
     /// Client disconnected the connection.
     pub fn client_disconnect() -> Self {
         Self {
@@ -351,6 +368,10 @@ impl ErrorMetadata {
         self.code == ErrorCode::MisdirectedRequest
     }
 
+    pub fn is_payload_too_large(&self) -> bool {
+        self.code == ErrorCode::PayloadTooLarge
+    }
+
     /// Return true if this error is deterministically caused by user. If so,
     /// we can propagate it into JS out of a syscall, and cache it if it is the
     /// full UDF result.
@@ -358,6 +379,7 @@ impl ErrorMetadata {
         match self.code {
             ErrorCode::BadRequest
             | ErrorCode::PaginationLimit
+            | ErrorCode::PayloadTooLarge
             | ErrorCode::Unauthenticated
             | ErrorCode::Forbidden => true,
             ErrorCode::OperationalInternalServerError
@@ -383,6 +405,7 @@ impl ErrorMetadata {
             ErrorCode::BadRequest
             | ErrorCode::NotFound
             | ErrorCode::PaginationLimit
+            | ErrorCode::PayloadTooLarge
             | ErrorCode::Unauthenticated
             | ErrorCode::Forbidden
             | ErrorCode::MisdirectedRequest => Some((sentry::Level::Info, None)),
@@ -401,6 +424,7 @@ impl ErrorMetadata {
         match self.code {
             ErrorCode::BadRequest
             | ErrorCode::PaginationLimit
+            | ErrorCode::PayloadTooLarge
             | ErrorCode::Unauthenticated
             | ErrorCode::Forbidden
             | ErrorCode::ClientDisconnect
@@ -430,6 +454,7 @@ impl ErrorMetadata {
             ErrorCode::OCC => Some(&crate::metrics::COMMIT_RACE_TOTAL),
             ErrorCode::NotFound => None,
             ErrorCode::PaginationLimit => None,
+            ErrorCode::PayloadTooLarge => None,
             ErrorCode::OutOfRetention => None,
             ErrorCode::Overloaded => None,
             ErrorCode::RejectedBeforeExecution => None,
@@ -453,7 +478,7 @@ impl ErrorMetadata {
             ErrorCode::OperationalInternalServerError => Some(CloseCode::Error),
             // These ones are client errors - so no close code - the client
             // will handle and close the connection instead.
-            ErrorCode::BadRequest | ErrorCode::Unauthenticated => None,
+            ErrorCode::BadRequest | ErrorCode::PayloadTooLarge | ErrorCode::Unauthenticated => None,
         }?;
         // According to the WebSocket protocol specification (RFC 6455), the reason
         // string (if present) is limited to 123 bytes. This is because the
@@ -479,6 +504,7 @@ impl ErrorCode {
             ErrorCode::Forbidden => StatusCode::FORBIDDEN,
             ErrorCode::NotFound => StatusCode::NOT_FOUND,
             ErrorCode::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ErrorCode::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
             ErrorCode::OperationalInternalServerError => StatusCode::INTERNAL_SERVER_ERROR,
             ErrorCode::OCC
             | ErrorCode::OutOfRetention
@@ -501,6 +527,7 @@ impl ErrorCode {
             },
             ErrorCode::OCC => tonic::Code::ResourceExhausted,
             ErrorCode::PaginationLimit => tonic::Code::InvalidArgument,
+            ErrorCode::PayloadTooLarge => tonic::Code::InvalidArgument,
             ErrorCode::OutOfRetention => tonic::Code::OutOfRange,
             ErrorCode::OperationalInternalServerError => tonic::Code::Internal,
             ErrorCode::MisdirectedRequest => tonic::Code::FailedPrecondition,
@@ -513,6 +540,7 @@ impl ErrorCode {
             StatusCode::FORBIDDEN => Some(ErrorCode::Forbidden),
             StatusCode::NOT_FOUND => Some(ErrorCode::NotFound),
             StatusCode::TOO_MANY_REQUESTS => Some(ErrorCode::RateLimited),
+            StatusCode::PAYLOAD_TOO_LARGE => Some(ErrorCode::PayloadTooLarge),
             StatusCode::MISDIRECTED_REQUEST => Some(ErrorCode::MisdirectedRequest),
             // Tries to categorize in one of the above more specific 4xx codes first,
             // otherwise categorizes as a general 4xx via BadRequest
@@ -536,6 +564,7 @@ pub trait ErrorMetadataAnyhowExt {
     fn should_report_to_sentry(&self) -> Option<(sentry::Level, Option<f64>)>;
     fn is_deterministic_user_error(&self) -> bool;
     fn is_misdirected_request(&self) -> bool;
+    fn is_payload_too_large(&self) -> bool;
     fn user_facing_message(&self) -> String;
     fn short_msg(&self) -> &str;
     fn msg(&self) -> &str;
@@ -630,6 +659,14 @@ impl ErrorMetadataAnyhowExt for anyhow::Error {
         false
     }
 
+    /// Returns true if error is tagged as PayloadTooLarge
+    fn is_payload_too_large(&self) -> bool {
+        if let Some(e) = self.downcast_ref::<ErrorMetadata>() {
+            return e.is_payload_too_large();
+        }
+        false
+    }
+
     /// Returns the level at which the given error should report to sentry
     /// INFO -> it's a client-at-fault error
     /// WARNING -> it's a server-at-fault error that is expected
@@ -816,6 +853,7 @@ mod proptest {
                 ErrorCode::Unauthenticated => ErrorMetadata::unauthenticated("un", "auth"),
                 ErrorCode::Forbidden => ErrorMetadata::forbidden("for", "bidden"),
                 ErrorCode::RateLimited => ErrorMetadata::rate_limited("too", "many requests"),
+                ErrorCode::PayloadTooLarge => ErrorMetadata::payload_too_large("too", "large"),
                 ErrorCode::Overloaded => ErrorMetadata::overloaded("overloaded", "error"),
                 ErrorCode::RejectedBeforeExecution => {
                     ErrorMetadata::rejected_before_execution("rejected_before_execution", "error")
@@ -39,6 +39,8 @@ impl TestUsageEventLogger {
             recent_database_egress_size: std::mem::take(&mut state.recent_database_egress_size),
             recent_vector_ingress_size: std::mem::take(&mut state.recent_vector_ingress_size),
             recent_vector_egress_size: std::mem::take(&mut state.recent_vector_egress_size),
+            recent_websocket_ingress_size: std::mem::take(&mut state.recent_websocket_ingress_size),
+            recent_websocket_egress_size: std::mem::take(&mut state.recent_websocket_egress_size),
         }
     }
 }
@@ -85,6 +87,9 @@ pub struct UsageCounterState {
     pub recent_database_egress_size: BTreeMap<TableName, u64>,
     pub recent_vector_ingress_size: BTreeMap<TableName, u64>,
     pub recent_vector_egress_size: BTreeMap<TableName, u64>,
+
+    pub recent_websocket_ingress_size: u64,
+    pub recent_websocket_egress_size: u64,
 }
 
 impl UsageCounterState {
@@ -174,6 +179,12 @@ impl UsageCounterState {
                     .entry(table_name)
                     .or_default() += egress;
             },
+            UsageEvent::WebSocketBandwidth {
+                ingress, egress, ..
+            } => {
+                self.recent_websocket_ingress_size += ingress;
+                self.recent_websocket_egress_size += egress;
+            },
             UsageEvent::CurrentVectorStorage { tables: _ } => todo!(),
             UsageEvent::CurrentDatabaseStorage { tables: _ } => todo!(),
             UsageEvent::CurrentFileStorage {
@@ -98,6 +98,15 @@ pub enum UsageEvent {
         ingress: u64,
         egress: u64,
     },
+    /// Bandwidth from websocket connections opened by a single action
+    /// invocation. Ingress is data received from the server.
+    WebSocketBandwidth {
+        id: String,
+        component_path: Option<String>,
+        udf_id: String,
+        ingress: u64,
+        egress: u64,
+    },
 
     // Current* events record the current storage state as of a time, they're not incremental
     // deltas. So a new Current* value should replace the previous value. If a tables Vec is
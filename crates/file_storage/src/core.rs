@@ -11,6 +11,7 @@ use common::{
         ComponentId,
         ComponentPath,
     },
+    document::ResolvedDocument,
     runtime::{
         Runtime,
         UnixTimestamp,
@@ -18,6 +19,7 @@ use common::{
     sha256::Sha256Digest,
     types::{
         ConvexOrigin,
+        ObjectKey,
         StorageUuid,
     },
 };
@@ -35,17 +37,25 @@ use headers::{
     ContentLength,
     ContentRange,
     ContentType,
+    Range,
 };
 use keybroker::{
     Identity,
     KeyBroker,
+    StoreFileConstraints,
 };
 use maplit::btreemap;
-use model::file_storage::{
-    types::FileStorageEntry,
-    BatchKey,
-    FileStorageId,
-    FileStorageModel,
+use model::{
+    archival::segment::read_segment,
+    file_storage::{
+        types::{
+            FileScanStatus,
+            FileStorageEntry,
+        },
+        BatchKey,
+        FileStorageId,
+        FileStorageModel,
+    },
 };
 use storage::{
     Storage,
@@ -60,6 +70,7 @@ use usage_tracking::{
 use value::{
     id_v6::DeveloperDocumentId,
     TableNamespace,
+    TabletId,
 };
 
 use crate::{
@@ -68,6 +79,7 @@ use crate::{
         log_get_file_chunk_size,
         GetFileType,
     },
+    FileRange,
     FileRangeStream,
     FileStorage,
     FileStream,
@@ -76,12 +88,33 @@ use crate::{
 
 const MAX_CHUNK_SIZE: usize = 32 * 1024;
 
+/// Resolve the first range in a `Range` header against a file of `size` bytes,
+/// returning the inclusive start and end of the bytes to serve. Ranges that
+/// run past the end of the file are truncated, and `None` means the range
+/// starts past the end of the file.
+pub(crate) fn resolve_byte_range(range: &Range, size: u64) -> Option<(u64, u64)> {
+    let (start, end) = range.satisfiable_ranges(size).next()?;
+    let start = match start {
+        Bound::Included(start) => start,
+        Bound::Excluded(start) => start.checked_add(1)?,
+        Bound::Unbounded => 0,
+    };
+    let last_byte = size.checked_sub(1)?;
+    let end = match end {
+        Bound::Included(end) => end.min(last_byte),
+        Bound::Excluded(end) => end.checked_sub(1)?.min(last_byte),
+        Bound::Unbounded => last_byte,
+    };
+    (start <= end).then_some((start, end))
+}
+
 impl<RT: Runtime> TransactionalFileStorage<RT> {
     pub fn new(rt: RT, storage: Arc<dyn Storage>, convex_origin: ConvexOrigin) -> Self {
         Self {
             rt,
             storage,
             convex_origin,
+            scanner: None,
         }
     }
 
@@ -90,8 +123,14 @@ impl<RT: Runtime> TransactionalFileStorage<RT> {
         key_broker: &KeyBroker,
         issued_ts: UnixTimestamp,
         component: ComponentId,
+        constraints: StoreFileConstraints,
     ) -> anyhow::Result<String> {
-        let token = key_broker.issue_store_file_authorization(&self.rt, issued_ts, component)?;
+        let token = key_broker.issue_store_file_authorization(
+            &self.rt,
+            issued_ts,
+            component,
+            constraints,
+        )?;
         let origin = &self.convex_origin;
 
         Ok(format!("{origin}/api/storage/upload?token={token}"))
@@ -205,6 +244,7 @@ impl<RT: Runtime> TransactionalFileStorage<RT> {
             sha256,
             content_length: result.content_length,
             content_type: result.content_type,
+            filename: result.filename,
             stream: result.stream,
         })
     }
@@ -213,17 +253,24 @@ impl<RT: Runtime> TransactionalFileStorage<RT> {
         &self,
         component_path: ComponentPath,
         file: FileStorageEntry,
-        bytes_range: (Bound<u64>, Bound<u64>),
+        range: Range,
         usage_tracker: impl StorageUsageTracker + Clone + 'static,
-    ) -> anyhow::Result<FileRangeStream> {
-        self.file_stream(
-            component_path,
-            file,
-            bytes_range,
-            usage_tracker,
-            GetFileType::Range,
-        )
-        .await
+    ) -> anyhow::Result<FileRange> {
+        file.check_servable()?;
+        let size = file.size as u64;
+        let Some((start, end)) = resolve_byte_range(&range, size) else {
+            return Ok(FileRange::Unsatisfiable { size });
+        };
+        let stream = self
+            .file_stream(
+                component_path,
+                file,
+                (Bound::Included(start), Bound::Included(end)),
+                usage_tracker,
+                GetFileType::Range,
+            )
+            .await?;
+        Ok(FileRange::Satisfiable(stream))
     }
 
     async fn file_stream(
@@ -234,12 +281,16 @@ impl<RT: Runtime> TransactionalFileStorage<RT> {
         usage_tracker: impl StorageUsageTracker + Clone + 'static,
         get_file_type: GetFileType,
     ) -> anyhow::Result<FileRangeStream> {
+        file.check_servable()?;
         let FileStorageEntry {
             storage_id,
             storage_key,
             sha256,
             size,
             content_type,
+            filename,
+            tags: _,
+            scan_status: _,
         } = file;
 
         let content_type = content_type.as_ref().map(|ct| ct.parse()).transpose()?;
@@ -265,6 +316,7 @@ impl<RT: Runtime> TransactionalFileStorage<RT> {
             content_length,
             content_range,
             content_type,
+            filename,
             stream: Self::track_stream_usage(component_path, stream, get_file_type, call_tracker),
         })
     }
@@ -326,6 +378,17 @@ impl<RT: Runtime> TransactionalFileStorage<RT> {
         Ok(did_delete)
     }
 
+    /// Reads the documents in an archived segment of the table with
+    /// `tablet_id`. Segments are kept in the same storage as files, without
+    /// `_storage` entries.
+    pub async fn read_archived_segment(
+        &self,
+        tablet_id: TabletId,
+        storage_key: &ObjectKey,
+    ) -> anyhow::Result<Vec<ResolvedDocument>> {
+        read_segment(&self.storage, tablet_id, storage_key).await
+    }
+
     /// `upload_file` just uploads a file to storage. It does not save the file
     /// in the _file_storage system table and it does not count towards
     /// usage. The caller is responsible to call `store_file_entry` to
@@ -371,6 +434,9 @@ impl<RT: Runtime> TransactionalFileStorage<RT> {
             sha256: actual_sha256,
             size: size.try_into()?,
             content_type: content_type.map(|ct| ct.to_string()),
+            filename: None,
+            tags: BTreeMap::new(),
+            scan_status: None,
         };
 
         Ok(entry)
@@ -379,13 +445,18 @@ impl<RT: Runtime> TransactionalFileStorage<RT> {
     /// Stores a file entry generated by upload_file(). The caller is
     /// responsible to track usage. If you are outside of the
     /// isolate environment, it is recommended to use FileStorage::store_file
-    /// that performs all necessary steps instead.
+    /// that performs all necessary steps instead. If a scanner is configured,
+    /// the file isn't served until the caller starts `scan_in_background` after
+    /// committing and the scan finds it clean.
     pub async fn store_file_entry(
         &self,
         tx: &mut Transaction<RT>,
         namespace: TableNamespace,
-        entry: FileStorageEntry,
+        mut entry: FileStorageEntry,
     ) -> anyhow::Result<DeveloperDocumentId> {
+        if self.is_scanning() {
+            entry.scan_status = Some(FileScanStatus::Pending);
+        }
         let system_doc_id = FileStorageModel::new(tx, namespace)
             .store_file(entry)
             .await?;
@@ -436,12 +507,18 @@ impl<RT: Runtime> FileStorage<RT> {
         let mut tx = self.database.begin(Identity::system()).await?;
         let virtual_id = self
             .transactional_file_storage
-            .store_file_entry(&mut tx, namespace, entry)
+            .store_file_entry(&mut tx, namespace, entry.clone())
             .await?;
         let component_path = tx.must_component_path(ComponentId::from(namespace))?;
         self.database
             .commit_with_write_source(tx, "file_storage_store_file")
             .await?;
+        self.transactional_file_storage.scan_in_background(
+            self.database.clone(),
+            namespace,
+            virtual_id,
+            entry,
+        );
 
         usage_tracker
             .track_storage_call(
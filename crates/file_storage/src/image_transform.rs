@@ -0,0 +1,257 @@
+//! Resizing and re-encoding images in file storage on the fly, so the
+//! file-serving route can return thumbnails with `?w=&h=&fit=&format=`.
+use std::{
+    fmt,
+    io::Cursor,
+    str::FromStr,
+};
+
+use anyhow::Context;
+use bytes::Bytes;
+use common::knobs::{
+    MAX_IMAGE_TRANSFORM_DIMENSION,
+    MAX_IMAGE_TRANSFORM_SOURCE_SIZE,
+};
+use errors::ErrorMetadata;
+use image::{
+    imageops::FilterType,
+    DynamicImage,
+    ImageReader,
+    Limits,
+};
+
+/// How to fit an image into the requested width and height.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ImageFit {
+    /// Scale the image to fit within the box, preserving its aspect ratio.
+    #[default]
+    Contain,
+    /// Scale the image to cover the box, preserving its aspect ratio, and crop
+    /// what's outside it.
+    Cover,
+    /// Stretch the image to exactly the box.
+    Fill,
+}
+
+impl FromStr for ImageFit {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "contain" => Ok(Self::Contain),
+            "cover" => Ok(Self::Cover),
+            "fill" => Ok(Self::Fill),
+            _ => anyhow::bail!(ErrorMetadata::bad_request(
+                "InvalidImageFit",
+                format!("Invalid image fit {s:?}. Expected \"contain\", \"cover\" or \"fill\"."),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ImageFit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Contain => write!(f, "contain"),
+            Self::Cover => write!(f, "cover"),
+            Self::Fill => write!(f, "fill"),
+        }
+    }
+}
+
+/// The format to encode a transformed image in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageFormat {
+    Jpeg,
+    Png,
+    Webp,
+}
+
+impl ImageFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Jpeg => "image/jpeg",
+            Self::Png => "image/png",
+            Self::Webp => "image/webp",
+        }
+    }
+
+    fn from_source(format: image::ImageFormat) -> Self {
+        match format {
+            image::ImageFormat::Jpeg => Self::Jpeg,
+            image::ImageFormat::WebP => Self::Webp,
+            _ => Self::Png,
+        }
+    }
+}
+
+impl From<ImageFormat> for image::ImageFormat {
+    fn from(format: ImageFormat) -> Self {
+        match format {
+            ImageFormat::Jpeg => image::ImageFormat::Jpeg,
+            ImageFormat::Png => image::ImageFormat::Png,
+            ImageFormat::Webp => image::ImageFormat::WebP,
+        }
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "jpeg" | "jpg" => Ok(Self::Jpeg),
+            "png" => Ok(Self::Png),
+            "webp" => Ok(Self::Webp),
+            _ => anyhow::bail!(ErrorMetadata::bad_request(
+                "InvalidImageFormat",
+                format!("Invalid image format {s:?}. Expected \"jpeg\", \"png\" or \"webp\"."),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ImageFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Jpeg => write!(f, "jpeg"),
+            Self::Png => write!(f, "png"),
+            Self::Webp => write!(f, "webp"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ImageTransform {
+    width: Option<u32>,
+    height: Option<u32>,
+    fit: ImageFit,
+    /// Defaults to the source image's format if it's one we can encode, and
+    /// PNG otherwise.
+    format: Option<ImageFormat>,
+}
+
+fn invalid_image(e: impl fmt::Display) -> ErrorMetadata {
+    ErrorMetadata::bad_request(
+        "InvalidImage",
+        format!("The file can't be transformed because it isn't a supported image: {e}"),
+    )
+}
+
+impl ImageTransform {
+    pub fn new(
+        width: Option<u32>,
+        height: Option<u32>,
+        fit: Option<ImageFit>,
+        format: Option<ImageFormat>,
+    ) -> anyhow::Result<Self> {
+        for dimension in [width, height].into_iter().flatten() {
+            anyhow::ensure!(
+                (1..=*MAX_IMAGE_TRANSFORM_DIMENSION).contains(&dimension),
+                ErrorMetadata::bad_request(
+                    "InvalidImageDimension",
+                    format!(
+                        "Image width and height must be between 1 and {}, not {dimension}",
+                        *MAX_IMAGE_TRANSFORM_DIMENSION
+                    ),
+                )
+            );
+        }
+        Ok(Self {
+            width,
+            height,
+            fit: fit.unwrap_or_default(),
+            format,
+        })
+    }
+
+    /// Check that a file of `size` bytes isn't too large to transform, which
+    /// is worth doing before reading it from storage.
+    pub fn check_source_size(size: u64) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            size <= *MAX_IMAGE_TRANSFORM_SOURCE_SIZE,
+            ErrorMetadata::bad_request(
+                "ImageTooLarge",
+                format!(
+                    "Images larger than {} bytes can't be transformed",
+                    *MAX_IMAGE_TRANSFORM_SOURCE_SIZE
+                ),
+            )
+        );
+        Ok(())
+    }
+
+    /// A canonical description of the transform, which together with the
+    /// source file identifies its output.
+    pub fn cache_key(&self) -> String {
+        let dimension = |d: Option<u32>| d.map_or("auto".to_string(), |d| d.to_string());
+        format!(
+            "w={},h={},fit={},format={}",
+            dimension(self.width),
+            dimension(self.height),
+            self.fit,
+            self.format
+                .map_or("auto".to_string(), |format| format.to_string())
+        )
+    }
+
+    fn resize(&self, image: DynamicImage) -> DynamicImage {
+        let (width, height) = (self.width, self.height);
+        if width.is_none() && height.is_none() {
+            return image;
+        }
+        match (self.fit, width, height) {
+            (ImageFit::Cover, Some(width), Some(height)) => {
+                image.resize_to_fill(width, height, FilterType::Lanczos3)
+            },
+            (ImageFit::Fill, ..) => image.resize_exact(
+                width.unwrap_or(image.width()),
+                height.unwrap_or(image.height()),
+                FilterType::Lanczos3,
+            ),
+            // With only one dimension, covering the box is the same as
+            // containing the image in it.
+            (ImageFit::Contain | ImageFit::Cover, ..) => image.resize(
+                width.unwrap_or(u32::MAX),
+                height.unwrap_or(u32::MAX),
+                FilterType::Lanczos3,
+            ),
+        }
+    }
+
+    /// Decode, resize and re-encode `source`, returning the encoded image and
+    /// its format. This is CPU-bound, so it runs on a blocking thread.
+    pub async fn apply(&self, source: Bytes) -> anyhow::Result<(Vec<u8>, ImageFormat)> {
+        Self::check_source_size(source.len() as u64)?;
+        let transform = self.clone();
+        tokio::task::spawn_blocking(move || transform.apply_blocking(&source)).await?
+    }
+
+    fn apply_blocking(&self, source: &[u8]) -> anyhow::Result<(Vec<u8>, ImageFormat)> {
+        let mut reader = ImageReader::new(Cursor::new(source))
+            .with_guessed_format()
+            .context("Failed to read image")?;
+        let source_format = reader
+            .format()
+            .with_context(|| invalid_image("unrecognized format"))?;
+        let mut limits = Limits::default();
+        limits.max_image_width = Some(*MAX_IMAGE_TRANSFORM_DIMENSION);
+        limits.max_image_height = Some(*MAX_IMAGE_TRANSFORM_DIMENSION);
+        reader.limits(limits);
+        let image = reader.decode().map_err(invalid_image)?;
+
+        let format = self
+            .format
+            .unwrap_or_else(|| ImageFormat::from_source(source_format));
+        let mut image = self.resize(image);
+        if format == ImageFormat::Jpeg {
+            // JPEG doesn't support transparency.
+            image = DynamicImage::ImageRgb8(image.to_rgb8());
+        }
+        let mut output = Cursor::new(Vec::new());
+        image
+            .write_to(&mut output, format.into())
+            .context("Failed to encode image")?;
+        Ok((output.into_inner(), format))
+    }
+}
@@ -1,6 +1,9 @@
 #![feature(lazy_cell)]
 #![feature(let_chains)]
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::Duration,
+};
 
 use common::{
     runtime::Runtime,
@@ -8,16 +11,22 @@ use common::{
     types::ConvexOrigin,
 };
 use database::Database;
+use errors::ErrorMetadata;
 use futures::stream::BoxStream;
 use headers::{
     ContentLength,
     ContentRange,
     ContentType,
 };
+use keybroker::StoreFileConstraints;
+use scanning::FileScanner;
+use serde::Deserialize;
 use storage::Storage;
 
 mod core;
+pub mod image_transform;
 mod metrics;
+pub mod scanning;
 #[cfg(test)]
 mod tests;
 
@@ -25,6 +34,8 @@ pub struct FileStream {
     pub sha256: Sha256Digest,
     pub content_length: ContentLength,
     pub content_type: Option<ContentType>,
+    /// The filename the file was stored with, if any.
+    pub filename: Option<String>,
     pub stream: BoxStream<'static, futures::io::Result<bytes::Bytes>>,
 }
 
@@ -32,9 +43,65 @@ pub struct FileRangeStream {
     pub content_length: ContentLength,
     pub content_range: ContentRange,
     pub content_type: Option<ContentType>,
+    pub filename: Option<String>,
     pub stream: BoxStream<'static, futures::io::Result<bytes::Bytes>>,
 }
 
+/// The response to a `Range` request for a file.
+pub enum FileRange {
+    Satisfiable(FileRangeStream),
+    /// None of the requested bytes are in the file, which is `size` bytes
+    /// long.
+    Unsatisfiable {
+        size: u64,
+    },
+}
+
+/// The options to `storage.generateUploadUrl()`, which limit what can be
+/// uploaded with the URL.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadUrlOptions {
+    pub max_bytes: Option<u64>,
+    #[serde(default)]
+    pub allowed_content_types: Vec<String>,
+    pub expires_in_ms: Option<u64>,
+}
+
+impl TryFrom<UploadUrlOptions> for StoreFileConstraints {
+    type Error = anyhow::Error;
+
+    fn try_from(options: UploadUrlOptions) -> anyhow::Result<Self> {
+        let invalid = |msg: String| ErrorMetadata::bad_request("InvalidUploadUrlOptions", msg);
+        anyhow::ensure!(
+            options.max_bytes != Some(0),
+            invalid("maxBytes must be positive".to_string())
+        );
+        anyhow::ensure!(
+            options.expires_in_ms != Some(0),
+            invalid("expiresInMs must be positive".to_string())
+        );
+        for content_type in &options.allowed_content_types {
+            let valid = content_type
+                .split_once('/')
+                .is_some_and(|(type_, subtype)| {
+                    !type_.is_empty() && !subtype.is_empty() && !content_type.contains(';')
+                });
+            anyhow::ensure!(
+                valid,
+                invalid(format!(
+                    "Invalid content type {content_type:?} in allowedContentTypes"
+                ))
+            );
+        }
+        Ok(StoreFileConstraints {
+            max_bytes: options.max_bytes,
+            allowed_content_types: options.allowed_content_types,
+            expires_in: options.expires_in_ms.map(Duration::from_millis),
+        })
+    }
+}
+
 #[derive(Clone)]
 pub struct FileStorage<RT: Runtime> {
     pub database: Database<RT>,
@@ -46,4 +113,5 @@ pub struct TransactionalFileStorage<RT: Runtime> {
     rt: RT,
     storage: Arc<dyn Storage>,
     convex_origin: ConvexOrigin,
+    scanner: Option<Arc<dyn FileScanner>>,
 }
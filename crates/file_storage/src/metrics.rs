@@ -46,3 +46,12 @@ pub fn log_get_file_chunk_size(size_bytes: u64, get_file_type: GetFileType) {
         vec![get_file_type.tag()],
     );
 }
+
+register_convex_histogram!(
+    FILE_SCAN_SECONDS,
+    "Duration of scanning an uploaded file and recording the verdict",
+    &STATUS_LABEL
+);
+pub fn file_scan_timer() -> StatusTimer {
+    StatusTimer::new(&FILE_SCAN_SECONDS)
+}
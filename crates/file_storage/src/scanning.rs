@@ -0,0 +1,173 @@
+//! Scanning files after they're uploaded, e.g. for malware, for apps with
+//! compliance requirements. While a scanner is configured, new files are
+//! stored with a pending `scanStatus` and aren't served until the scanner
+//! marks them clean. Files the scanner rejects are quarantined: they stay in
+//! storage so they can be inspected, but they're never served.
+use std::sync::Arc;
+
+use anyhow::Context;
+use async_trait::async_trait;
+use bytes::Bytes;
+use common::{
+    pause::PauseClient,
+    runtime::Runtime,
+};
+use database::Database;
+use futures::stream::BoxStream;
+use keybroker::Identity;
+use model::file_storage::{
+    types::{
+        FileScanStatus,
+        FileStorageEntry,
+    },
+    FileStorageId,
+    FileStorageModel,
+};
+use serde::Deserialize;
+use storage::StorageExt;
+use url::Url;
+use usage_tracking::FunctionUsageTracker;
+use value::{
+    id_v6::DeveloperDocumentId,
+    TableNamespace,
+};
+
+use crate::{
+    metrics::file_scan_timer,
+    TransactionalFileStorage,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FileScanVerdict {
+    Clean,
+    Quarantine,
+}
+
+impl From<FileScanVerdict> for FileScanStatus {
+    fn from(verdict: FileScanVerdict) -> Self {
+        match verdict {
+            FileScanVerdict::Clean => FileScanStatus::Clean,
+            FileScanVerdict::Quarantine => FileScanStatus::Quarantined,
+        }
+    }
+}
+
+/// Checks a newly uploaded file. An error leaves the file pending, so files
+/// are never served without a verdict.
+#[async_trait]
+pub trait FileScanner: Send + Sync {
+    async fn scan(
+        &self,
+        entry: &FileStorageEntry,
+        contents: BoxStream<'static, futures::io::Result<Bytes>>,
+    ) -> anyhow::Result<FileScanVerdict>;
+}
+
+/// Scans files by POSTing their contents to an external service, which
+/// responds with `{"verdict": "clean"}` or `{"verdict": "quarantine"}`.
+pub struct WebhookFileScanner {
+    client: reqwest::Client,
+    url: Url,
+}
+
+impl WebhookFileScanner {
+    pub fn new(url: Url) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct WebhookResponse {
+    verdict: FileScanVerdict,
+}
+
+#[async_trait]
+impl FileScanner for WebhookFileScanner {
+    async fn scan(
+        &self,
+        entry: &FileStorageEntry,
+        contents: BoxStream<'static, futures::io::Result<Bytes>>,
+    ) -> anyhow::Result<FileScanVerdict> {
+        let mut request = self
+            .client
+            .post(self.url.clone())
+            .header("Convex-Storage-Id", entry.storage_id.to_string())
+            .header("Convex-Sha256", entry.sha256.as_base64())
+            .body(reqwest::Body::wrap_stream(contents));
+        if let Some(content_type) = &entry.content_type {
+            request = request.header(reqwest::header::CONTENT_TYPE, content_type);
+        }
+        let response: WebhookResponse = request.send().await?.error_for_status()?.json().await?;
+        Ok(response.verdict)
+    }
+}
+
+impl<RT: Runtime> TransactionalFileStorage<RT> {
+    /// Scan files stored from now on with `scanner` before serving them.
+    pub fn with_scanner(mut self, scanner: Arc<dyn FileScanner>) -> Self {
+        self.scanner = Some(scanner);
+        self
+    }
+
+    pub(crate) fn is_scanning(&self) -> bool {
+        self.scanner.is_some()
+    }
+
+    /// Scan a file that was just stored, recording the verdict in its
+    /// `_storage` document. Does nothing if no scanner is configured.
+    pub fn scan_in_background(
+        &self,
+        database: Database<RT>,
+        namespace: TableNamespace,
+        storage_id: DeveloperDocumentId,
+        entry: FileStorageEntry,
+    ) {
+        let Some(scanner) = self.scanner.clone() else {
+            return;
+        };
+        let storage = self.storage.clone();
+        self.rt.spawn("file_scan", async move {
+            let timer = file_scan_timer();
+            let result = async {
+                let contents = storage
+                    .get(&entry.storage_key.to_string().try_into()?)
+                    .await?
+                    .with_context(|| format!("object {:?} not found", entry.storage_key))?;
+                let status = FileScanStatus::from(scanner.scan(&entry, contents.stream).await?);
+                database
+                    .execute_with_occ_retries(
+                        Identity::system(),
+                        FunctionUsageTracker::new(),
+                        PauseClient::new(),
+                        "file_storage_set_scan_status",
+                        |tx| {
+                            async {
+                                FileStorageModel::new(tx, namespace)
+                                    .set_scan_status(FileStorageId::DocumentId(storage_id), status)
+                                    .await
+                            }
+                            .into()
+                        },
+                    )
+                    .await?;
+                anyhow::Ok(status)
+            }
+            .await;
+            match result {
+                Ok(status) => {
+                    timer.finish();
+                    if status == FileScanStatus::Quarantined {
+                        tracing::warn!("Quarantined file {storage_id}");
+                    }
+                },
+                Err(e) => {
+                    tracing::error!("Failed to scan file {storage_id}, leaving it pending: {e:#}");
+                },
+            }
+        });
+    }
+}
@@ -1,6 +1,12 @@
-use std::sync::Arc;
+use std::{
+    sync::Arc,
+    time::Duration,
+};
 
+use async_trait::async_trait;
+use bytes::Bytes;
 use common::{
+    components::ComponentPath,
     runtime::Runtime,
     sha256::Sha256,
 };
@@ -14,21 +20,49 @@ use database::{
 use errors::{
     ErrorCode,
     ErrorMetadata,
+    ErrorMetadataAnyhowExt,
 };
 use events::usage::NoOpUsageEventLogger;
-use futures::stream;
+use futures::stream::{
+    self,
+    BoxStream,
+};
+use headers::{
+    Header,
+    HeaderValue,
+    Range,
+};
 use keybroker::Identity;
 use model::{
-    file_storage::FileStorageId,
+    file_storage::{
+        types::{
+            FileScanStatus,
+            FileStorageEntry,
+        },
+        FileStorageId,
+    },
     test_helpers::DbFixturesWithModel,
 };
 use runtime::testing::TestRuntime;
 use storage::LocalDirStorage;
+use tokio::sync::Semaphore;
 use usage_tracking::UsageCounter;
 use value::TableNamespace;
 
 use super::FileStorage;
-use crate::TransactionalFileStorage;
+use crate::{
+    core::resolve_byte_range,
+    image_transform::{
+        ImageFit,
+        ImageFormat,
+        ImageTransform,
+    },
+    scanning::{
+        FileScanVerdict,
+        FileScanner,
+    },
+    TransactionalFileStorage,
+};
 
 fn setup_file_storage(
     rt: TestRuntime,
@@ -84,3 +118,149 @@ async fn test_store_file_sha_mismatch(rt: TestRuntime) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Returns `verdict` for every file, once the test lets it.
+struct TestScanner {
+    verdict: FileScanVerdict,
+    release: Arc<Semaphore>,
+}
+
+#[async_trait]
+impl FileScanner for TestScanner {
+    async fn scan(
+        &self,
+        _entry: &FileStorageEntry,
+        _contents: BoxStream<'static, futures::io::Result<Bytes>>,
+    ) -> anyhow::Result<FileScanVerdict> {
+        self.release.acquire().await?.forget();
+        Ok(self.verdict)
+    }
+}
+
+#[convex_macro::test_runtime]
+async fn test_file_scan_quarantine(rt: TestRuntime) -> anyhow::Result<()> {
+    let database = DbFixtures::new_with_model(&rt).await?.db;
+    let mut file_storage = setup_file_storage(rt.clone(), &database)?;
+    let release = Arc::new(Semaphore::new(0));
+    file_storage.transactional_file_storage =
+        file_storage
+            .transactional_file_storage
+            .with_scanner(Arc::new(TestScanner {
+                verdict: FileScanVerdict::Quarantine,
+                release: release.clone(),
+            }));
+    let usage_tracker = UsageCounter::new(Arc::new(NoOpUsageEventLogger));
+    let storage_id = file_storage
+        .store_file(
+            TableNamespace::test_user(),
+            None,
+            None,
+            stream::iter([Ok(b"suspicious".to_vec())]),
+            None,
+            &usage_tracker,
+        )
+        .await?;
+    let get_entry = || async {
+        let mut tx = database.begin(Identity::system()).await?;
+        file_storage
+            .transactional_file_storage
+            .get_file_entry(
+                &mut tx,
+                TableNamespace::test_user(),
+                FileStorageId::DocumentId(storage_id),
+            )
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("file not found"))
+    };
+
+    // The file can't be served until the scan finishes.
+    let entry = get_entry().await?;
+    assert_eq!(entry.scan_status, Some(FileScanStatus::Pending));
+    let Err(err) = file_storage
+        .transactional_file_storage
+        .get_file_stream(ComponentPath::test_user(), entry, usage_tracker.clone())
+        .await
+    else {
+        anyhow::bail!("pending file was served");
+    };
+    assert_eq!(err.short_msg(), "FileScanPending");
+
+    release.add_permits(1);
+    let entry = loop {
+        let entry = get_entry().await?;
+        if entry.scan_status != Some(FileScanStatus::Pending) {
+            break entry;
+        }
+        rt.wait(Duration::from_millis(10)).await;
+    };
+    assert_eq!(entry.scan_status, Some(FileScanStatus::Quarantined));
+    let Err(err) = file_storage
+        .transactional_file_storage
+        .get_file_stream(ComponentPath::test_user(), entry, usage_tracker)
+        .await
+    else {
+        anyhow::bail!("quarantined file was served");
+    };
+    assert_eq!(err.short_msg(), "FileQuarantined");
+    Ok(())
+}
+
+#[test]
+fn test_resolve_byte_range() -> anyhow::Result<()> {
+    let resolve = |header: &'static str, size| -> anyhow::Result<Option<(u64, u64)>> {
+        let range = Range::decode(&mut [HeaderValue::from_static(header)].iter())?;
+        Ok(resolve_byte_range(&range, size))
+    };
+    assert_eq!(resolve("bytes=0-99", 1000)?, Some((0, 99)));
+    assert_eq!(resolve("bytes=500-", 1000)?, Some((500, 999)));
+    // Suffix ranges count back from the end of the file.
+    assert_eq!(resolve("bytes=-100", 1000)?, Some((900, 999)));
+    // Ranges running past the end of the file are truncated.
+    assert_eq!(resolve("bytes=900-2000", 1000)?, Some((900, 999)));
+    assert_eq!(resolve("bytes=1000-", 1000)?, None);
+    assert_eq!(resolve("bytes=0-", 0)?, None);
+    Ok(())
+}
+
+fn encode_test_image(width: u32, height: u32) -> anyhow::Result<Bytes> {
+    let image = image::DynamicImage::ImageRgba8(image::RgbaImage::new(width, height));
+    let mut png = std::io::Cursor::new(Vec::new());
+    image.write_to(&mut png, image::ImageFormat::Png)?;
+    Ok(png.into_inner().into())
+}
+
+#[convex_macro::test_runtime]
+async fn test_image_transform(_rt: TestRuntime) -> anyhow::Result<()> {
+    let source = encode_test_image(40, 20)?;
+    let dimensions = |output: &[u8]| -> anyhow::Result<(u32, u32)> {
+        let image = image::load_from_memory(output)?;
+        Ok((image.width(), image.height()))
+    };
+
+    // Containing preserves the aspect ratio, and the format defaults to the
+    // source's.
+    let transform = ImageTransform::new(Some(10), None, None, None)?;
+    let (output, format) = transform.apply(source.clone()).await?;
+    assert_eq!(format, ImageFormat::Png);
+    assert_eq!(dimensions(&output)?, (10, 5));
+
+    let transform = ImageTransform::new(
+        Some(10),
+        Some(10),
+        Some(ImageFit::Cover),
+        Some(ImageFormat::Webp),
+    )?;
+    assert_eq!(transform.cache_key(), "w=10,h=10,fit=cover,format=webp");
+    let (output, format) = transform.apply(source.clone()).await?;
+    assert_eq!(format, ImageFormat::Webp);
+    assert_eq!(dimensions(&output)?, (10, 10));
+
+    let err = ImageTransform::new(Some(0), None, None, None).unwrap_err();
+    assert!(err.is_bad_request());
+    let err = ImageTransform::new(None, None, None, None)?
+        .apply(Bytes::from_static(b"not an image"))
+        .await
+        .unwrap_err();
+    assert_eq!(err.short_msg(), "InvalidImage");
+    Ok(())
+}
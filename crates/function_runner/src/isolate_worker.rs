@@ -196,6 +196,26 @@ impl<RT: Runtime> IsolateWorker<RT> for FunctionRunnerIsolateWorker<RT> {
                 let _ = response.send(r);
                 "Analyze".to_string()
             },
+            RequestType::WarmUp {
+                udf_config,
+                modules,
+                environment_variables,
+                entry_points,
+                response,
+            } => {
+                let r = AnalyzeEnvironment::warm_up::<RT>(
+                    client_id,
+                    isolate,
+                    isolate_clean,
+                    udf_config,
+                    modules,
+                    environment_variables,
+                    entry_points,
+                )
+                .await;
+                let _ = response.send(r);
+                "WarmUp".to_string()
+            },
             RequestType::HttpAction {
                 request,
                 environment_data,
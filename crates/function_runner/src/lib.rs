@@ -93,6 +93,17 @@ pub trait FunctionRunner<RT: Runtime>: Send + Sync + 'static {
         environment_variables: BTreeMap<EnvVarName, EnvVarValue>,
     ) -> anyhow::Result<Result<BTreeMap<CanonicalizedModulePath, AnalyzedModule>, JsError>>;
 
+    /// Evaluate `entry_points` in `instances` isolates at once, so that many
+    /// isolates are ready to run their functions without recompiling them.
+    async fn warm_up(
+        &self,
+        udf_config: UdfConfig,
+        modules: BTreeMap<CanonicalizedModulePath, ModuleConfig>,
+        environment_variables: BTreeMap<EnvVarName, EnvVarValue>,
+        entry_points: Vec<CanonicalizedModulePath>,
+        instances: u32,
+    ) -> anyhow::Result<Result<(), JsError>>;
+
     /// Set the action callbacks. Only used for InProcessFunctionRunner to break
     /// a reference cycle between ApplicationFunctionRunner and dyn
     /// FunctionRunner.
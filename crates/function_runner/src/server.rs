@@ -61,6 +61,7 @@ use database::{
 };
 use errors::ErrorMetadataAnyhowExt;
 use file_storage::TransactionalFileStorage;
+use futures::future;
 use isolate::{
     client::{
         initialize_v8,
@@ -790,6 +791,50 @@ impl<RT: Runtime> FunctionRunner<RT> for InProcessFunctionRunner<RT> {
             })
     }
 
+    #[minitrace::trace]
+    async fn warm_up(
+        &self,
+        udf_config: UdfConfig,
+        modules: BTreeMap<CanonicalizedModulePath, ModuleConfig>,
+        environment_variables: BTreeMap<EnvVarName, EnvVarValue>,
+        entry_points: Vec<CanonicalizedModulePath>,
+        instances: u32,
+    ) -> anyhow::Result<Result<(), JsError>> {
+        anyhow::ensure!(
+            modules
+                .values()
+                .all(|m| m.environment == ModuleEnvironment::Isolate),
+            "Can only warm up Isolate modules"
+        );
+        // Send the requests together so they're in flight at the same time, and
+        // the scheduler gives each one its own isolate.
+        let mut receivers = Vec::with_capacity(instances as usize);
+        for _ in 0..instances {
+            let (tx, rx) = oneshot::channel();
+            let request = IsolateRequestType::WarmUp {
+                udf_config: udf_config.clone(),
+                modules: modules.clone(),
+                environment_variables: environment_variables.clone(),
+                entry_points: entry_points.clone(),
+                response: tx,
+            };
+            self.server.send_request(IsolateRequest::new(
+                self.instance_name.clone(),
+                request,
+                EncodedSpan::from_parent(),
+            ))?;
+            receivers.push(FunctionRunnerCore::<RT, InstanceStorage>::receive_response(
+                rx,
+            ));
+        }
+        for result in future::try_join_all(receivers).await? {
+            if let Err(e) = result? {
+                return Ok(Err(e));
+            }
+        }
+        Ok(Ok(()))
+    }
+
     /// This fn should be called on startup. All `run_function` calls will fail
     /// if actions callbacks are not set.
     fn set_action_callbacks(&self, action_callbacks: Arc<dyn ActionCallbacks>) {
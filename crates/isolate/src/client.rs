@@ -75,6 +75,7 @@ use common::{
 };
 use database::{
     shutdown_error,
+    HybridSearchResult,
     Transaction,
 };
 use deno_core::{
@@ -124,6 +125,7 @@ use model::{
         ModuleSource,
         SourceMap,
     },
+    scheduled_jobs::types::IdempotencyKey,
     udf_config::types::UdfConfig,
 };
 use parking_lot::Mutex;
@@ -356,6 +358,7 @@ pub trait ActionCallbacks: Send + Sync {
         udf_args: Vec<JsonValue>,
         scheduled_ts: UnixTimestamp,
         context: ExecutionContext,
+        idempotency_key: Option<IdempotencyKey>,
     ) -> anyhow::Result<DeveloperDocumentId>;
 
     async fn cancel_job(
@@ -364,6 +367,21 @@ pub trait ActionCallbacks: Send + Sync {
         virtual_id: DeveloperDocumentId,
     ) -> anyhow::Result<()>;
 
+    // Checkpoints for the scheduled action `job_id`, which is re-run from its
+    // last checkpoint if it times out or crashes.
+    async fn load_action_checkpoint(
+        &self,
+        identity: Identity,
+        job_id: DeveloperDocumentId,
+    ) -> anyhow::Result<Option<JsonValue>>;
+
+    async fn save_action_checkpoint(
+        &self,
+        identity: Identity,
+        job_id: DeveloperDocumentId,
+        value: JsonValue,
+    ) -> anyhow::Result<()>;
+
     // Vector Search
     async fn vector_search(
         &self,
@@ -371,6 +389,12 @@ pub trait ActionCallbacks: Send + Sync {
         query: JsonValue,
     ) -> anyhow::Result<(Vec<PublicVectorSearchQueryResult>, FunctionUsageStats)>;
 
+    async fn hybrid_search(
+        &self,
+        identity: Identity,
+        query: JsonValue,
+    ) -> anyhow::Result<(Vec<HybridSearchResult>, FunctionUsageStats)>;
+
     // Components
     async fn lookup_function_handle(
         &self,
@@ -479,6 +503,13 @@ pub enum RequestType<RT: Runtime> {
             anyhow::Result<Result<BTreeMap<CanonicalizedModulePath, AnalyzedModule>, JsError>>,
         >,
     },
+    WarmUp {
+        udf_config: UdfConfig,
+        modules: BTreeMap<CanonicalizedModulePath, ModuleConfig>,
+        environment_variables: BTreeMap<EnvVarName, EnvVarValue>,
+        entry_points: Vec<CanonicalizedModulePath>,
+        response: oneshot::Sender<anyhow::Result<Result<(), JsError>>>,
+    },
     EvaluateSchema {
         schema_bundle: ModuleSource,
         source_map: Option<SourceMap>,
@@ -545,6 +576,9 @@ impl<RT: Runtime> Request<RT> {
             RequestType::Analyze { response, .. } => {
                 let _ = response.send(Err(error));
             },
+            RequestType::WarmUp { response, .. } => {
+                let _ = response.send(Err(error));
+            },
             RequestType::EvaluateSchema { response, .. } => {
                 let _ = response.send(Err(error));
             },
@@ -577,6 +611,9 @@ impl<RT: Runtime> Request<RT> {
             RequestType::Analyze { response, .. } => {
                 let _ = response.send(Err(error));
             },
+            RequestType::WarmUp { response, .. } => {
+                let _ = response.send(Err(error));
+            },
             RequestType::EvaluateSchema { response, .. } => {
                 let _ = response.send(Err(error));
             },
@@ -1697,6 +1734,10 @@ pub(crate) fn should_recreate_isolate<RT: Runtime>(
         metrics::log_recreate_isolate("env_disabled");
         return true;
     }
+    if isolate.heap_limit_raised() {
+        metrics::log_recreate_isolate("heap_limit_raised");
+        return true;
+    }
     if let Err(e) = isolate.check_isolate_clean() {
         tracing::error!(
             "Restarting Isolate {}: {e:?}, last request: {last_executed:?}",
@@ -1888,6 +1929,26 @@ impl<RT: Runtime> IsolateWorker<RT> for BackendIsolateWorker<RT> {
                 let _ = response.send(r);
                 "Analyze".to_string()
             },
+            RequestType::WarmUp {
+                udf_config,
+                modules,
+                environment_variables,
+                entry_points,
+                response,
+            } => {
+                let r = AnalyzeEnvironment::warm_up::<RT>(
+                    client_id,
+                    isolate,
+                    isolate_clean,
+                    udf_config,
+                    modules,
+                    environment_variables,
+                    entry_points,
+                )
+                .await;
+                let _ = response.send(r);
+                "WarmUp".to_string()
+            },
             RequestType::EvaluateSchema {
                 schema_bundle,
                 source_map,
@@ -1,5 +1,7 @@
 #![allow(non_snake_case)]
 
+use std::collections::BTreeMap;
+
 use anyhow::Context;
 use common::{
     bootstrap_model::components::handles::FunctionHandle,
@@ -12,6 +14,10 @@ use common::{
         UnixTimestamp,
     },
 };
+use database::{
+    HybridSearchJson,
+    HybridSearchRequest,
+};
 use errors::{
     ErrorMetadata,
     ErrorMetadataAnyhowExt,
@@ -22,6 +28,7 @@ use model::{
         handles::function_handle_not_found,
     },
     file_storage::FileStorageId,
+    scheduled_jobs::types::IdempotencyKey,
 };
 use serde::{
     Deserialize,
@@ -40,11 +47,13 @@ use vector::{
 use super::task_executor::TaskExecutor;
 use crate::{
     environment::helpers::{
+        parse_upload_url_options,
         with_argument_error,
         ArgName,
     },
     helpers::UdfArgsJson,
     metrics::async_syscall_timer,
+    native_ops::run_native_op,
 };
 
 impl<RT: Runtime> TaskExecutor<RT> {
@@ -63,7 +72,10 @@ impl<RT: Runtime> TaskExecutor<RT> {
                 "1.0/actions/action" => self.async_syscall_actions_runAction(args).await?,
                 "1.0/actions/schedule" => self.async_syscall_schedule(args).await?,
                 "1.0/actions/cancel_job" => self.async_syscall_cancel_job(args).await?,
+                "1.0/actions/loadCheckpoint" => self.async_syscall_loadCheckpoint(args).await?,
+                "1.0/actions/saveCheckpoint" => self.async_syscall_saveCheckpoint(args).await?,
                 "1.0/actions/vectorSearch" => self.async_syscall_vectorSearch(args).await?,
+                "1.0/actions/hybridSearch" => self.async_syscall_hybridSearch(args).await?,
                 "1.0/getUserIdentity" => self.async_syscall_getUserIdentity(args).await?,
                 "1.0/storageDelete" => self.async_syscall_storageDelete(args).await?,
                 "1.0/storageGetMetadata" => self.async_syscall_storageGetMetadata(args).await?,
@@ -72,6 +84,7 @@ impl<RT: Runtime> TaskExecutor<RT> {
                 },
                 "1.0/storageGetUrl" => self.async_syscall_storageGetUrl(args).await?,
                 "1.0/createFunctionHandle" => self.async_syscall_createFunctionHandle(args).await?,
+                "1.0/native" => run_native_op(args, true).await?,
                 _ => {
                     anyhow::bail!(ErrorMetadata::bad_request(
                         "UnknownAsyncOperation",
@@ -241,6 +254,8 @@ impl<RT: Runtime> TaskExecutor<RT> {
             function_handle: Option<String>,
             ts: f64,
             args: UdfArgsJson,
+            idempotency_key: Option<String>,
+            on_conflict: Option<String>,
         }
 
         let ScheduleArgs {
@@ -249,7 +264,12 @@ impl<RT: Runtime> TaskExecutor<RT> {
             function_handle,
             ts,
             args,
+            idempotency_key,
+            on_conflict,
         }: ScheduleArgs = with_argument_error("scheduler", || Ok(serde_json::from_value(args)?))?;
+        let idempotency_key = idempotency_key
+            .map(|key| IdempotencyKey::new(key, on_conflict.as_deref()))
+            .transpose()?;
         let path = match function_handle {
             Some(h) => {
                 let handle: FunctionHandle = with_argument_error("scheduler", || h.parse())?;
@@ -272,6 +292,7 @@ impl<RT: Runtime> TaskExecutor<RT> {
                 args.into_arg_vec(),
                 scheduled_ts,
                 self.context.clone(),
+                idempotency_key,
             )
             .await?;
 
@@ -297,6 +318,45 @@ impl<RT: Runtime> TaskExecutor<RT> {
         Ok(JsonValue::Null)
     }
 
+    /// The scheduled job running this action. Only actions run directly by
+    /// the scheduler are re-run from a checkpoint, so `runAction` calls made
+    /// from them can't save one.
+    fn checkpoint_job_id(&self) -> anyhow::Result<DeveloperDocumentId> {
+        match self.context.parent_scheduled_job {
+            Some(job_id) if self.context.is_root() => Ok(job_id),
+            _ => anyhow::bail!(ErrorMetadata::bad_request(
+                "ActionCheckpointNotScheduled",
+                "Checkpoints can only be used by actions run by the scheduler."
+            )),
+        }
+    }
+
+    #[convex_macro::instrument_future]
+    async fn async_syscall_loadCheckpoint(&self, _args: JsonValue) -> anyhow::Result<JsonValue> {
+        let job_id = self.checkpoint_job_id()?;
+        let value = self
+            .action_callbacks
+            .load_action_checkpoint(self.identity.clone(), job_id)
+            .await?;
+        Ok(value.unwrap_or(JsonValue::Null))
+    }
+
+    #[convex_macro::instrument_future]
+    async fn async_syscall_saveCheckpoint(&self, args: JsonValue) -> anyhow::Result<JsonValue> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct SaveCheckpointArgs {
+            value: JsonValue,
+        }
+        let SaveCheckpointArgs { value } =
+            with_argument_error("saveCheckpoint", || Ok(serde_json::from_value(args)?))?;
+        let job_id = self.checkpoint_job_id()?;
+        self.action_callbacks
+            .save_action_checkpoint(self.identity.clone(), job_id, value)
+            .await?;
+        Ok(JsonValue::Null)
+    }
+
     #[convex_macro::instrument_future]
     async fn async_syscall_vectorSearch(&self, args: JsonValue) -> anyhow::Result<JsonValue> {
         let VectorSearchRequest { query } = serde_json::from_value(args)?;
@@ -316,6 +376,25 @@ impl<RT: Runtime> TaskExecutor<RT> {
         Ok(json!({ "results": results }))
     }
 
+    #[convex_macro::instrument_future]
+    async fn async_syscall_hybridSearch(&self, args: JsonValue) -> anyhow::Result<JsonValue> {
+        let HybridSearchRequest { query } = serde_json::from_value(args)?;
+        let component_id = self.component_id();
+        let mut hybrid_search_query: HybridSearchJson = serde_json::from_value(query)?;
+        hybrid_search_query.insert_component_id(component_id);
+
+        let (results, usage_stats) = self
+            .action_callbacks
+            .hybrid_search(
+                self.identity.clone(),
+                serde_json::to_value(hybrid_search_query)?,
+            )
+            .await?;
+        self.usage_tracker.add(usage_stats);
+        let results: Vec<_> = results.into_iter().map(JsonValue::from).collect();
+        Ok(json!({ "results": results }))
+    }
+
     #[convex_macro::instrument_future]
     async fn async_syscall_getUserIdentity(&self, _args: JsonValue) -> anyhow::Result<JsonValue> {
         self.user_identity()
@@ -323,13 +402,17 @@ impl<RT: Runtime> TaskExecutor<RT> {
 
     async fn async_syscall_storageGenerateUploadUrl(
         &self,
-        _args: JsonValue,
+        args: JsonValue,
     ) -> anyhow::Result<JsonValue> {
+        let constraints = parse_upload_url_options(args)?;
         let issued_ts = self.rt.unix_timestamp();
         let component = self.component_id();
-        let postUrl =
-            self.file_storage
-                .generate_upload_url(&self.key_broker, issued_ts, component)?;
+        let postUrl = self.file_storage.generate_upload_url(
+            &self.key_broker,
+            issued_ts,
+            component,
+            constraints,
+        )?;
         Ok(serde_json::to_value(postUrl)?)
     }
 
@@ -389,6 +472,8 @@ impl<RT: Runtime> TaskExecutor<RT> {
             sha256: String,
             size: i64,
             content_type: Option<String>,
+            filename: Option<String>,
+            tags: BTreeMap<String, String>,
         }
         let file_metadata = self
             .action_callbacks
@@ -402,6 +487,8 @@ impl<RT: Runtime> TaskExecutor<RT> {
                     sha256: entry.sha256.as_hex(),
                     size: entry.size,
                     content_type: entry.content_type,
+                    filename: entry.filename,
+                    tags: entry.tags,
                 }
             });
         Ok(serde_json::to_value(file_metadata)?)
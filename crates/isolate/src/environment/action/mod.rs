@@ -8,11 +8,13 @@ mod syscall;
 mod task;
 mod task_executor;
 mod task_order;
+mod websocket;
 
 use std::{
     cmp::Ordering,
     collections::BTreeMap,
     sync::Arc,
+    time::Duration,
 };
 
 use anyhow::anyhow;
@@ -28,6 +30,7 @@ use common::{
         ACTION_USER_TIMEOUT,
         FUNCTION_MAX_ARGS_SIZE,
         FUNCTION_MAX_RESULT_SIZE,
+        ISOLATE_MAX_USER_HEAP_SIZE,
         V8_ACTION_SYSTEM_TIMEOUT,
     },
     log_lines::{
@@ -235,6 +238,10 @@ pub struct ActionEnvironment<RT: Runtime> {
     phase: ActionPhase<RT>,
     syscall_trace: Arc<Mutex<SyscallTrace>>,
     heap_stats: SharedIsolateHeapStats,
+
+    // Set from the action's `ValidatedPathAndArgs` before it starts running.
+    user_timeout: Duration,
+    max_user_heap_size: usize,
 }
 
 impl<RT: Runtime> ActionEnvironment<RT> {
@@ -248,7 +255,7 @@ impl<RT: Runtime> ActionEnvironment<RT> {
             module_loader,
         }: EnvironmentData<RT>,
         identity: Identity,
-        transaction: Transaction<RT>,
+        mut transaction: Transaction<RT>,
         action_callbacks: Arc<dyn ActionCallbacks>,
         fetch_client: Arc<dyn FetchClient>,
         log_line_sender: mpsc::UnboundedSender<LogLine>,
@@ -260,6 +267,10 @@ impl<RT: Runtime> ActionEnvironment<RT> {
         let (task_retval_sender, task_responses) = mpsc::unbounded_channel();
         let resources = Arc::new(Mutex::new(BTreeMap::new()));
         let function_handles = Arc::new(Mutex::new(BTreeMap::new()));
+        // The component can't be deleted while its action is starting.
+        let component_path = transaction
+            .get_component_path(component)
+            .unwrap_or_default();
         let task_executor = TaskExecutor {
             rt: rt.clone(),
             identity: identity.clone(),
@@ -275,7 +286,9 @@ impl<RT: Runtime> ActionEnvironment<RT> {
             context,
             resources: resources.clone(),
             component_id: component,
+            component_path,
             function_handles: function_handles.clone(),
+            websockets: Arc::new(Default::default()),
         };
         let (pending_task_sender, pending_task_receiver) = spsc::unbounded_channel();
         let running_tasks = rt.spawn("task_executor", task_executor.go(pending_task_receiver));
@@ -302,6 +315,9 @@ impl<RT: Runtime> ActionEnvironment<RT> {
             ),
             syscall_trace,
             heap_stats,
+
+            user_timeout: *ACTION_USER_TIMEOUT,
+            max_user_heap_size: *ISOLATE_MAX_USER_HEAP_SIZE,
         }
     }
 
@@ -623,6 +639,12 @@ impl<RT: Runtime> ActionEnvironment<RT> {
     ) -> anyhow::Result<ActionOutcome> {
         let client_id = Arc::new(client_id);
         let start_unix_timestamp = self.rt.unix_timestamp();
+        if let Some(user_timeout) = request_params.path_and_args.user_timeout() {
+            self.user_timeout = user_timeout;
+        }
+        if let Some(max_user_heap_size) = request_params.path_and_args.max_user_heap_size() {
+            self.max_user_heap_size = max_user_heap_size;
+        }
 
         // See Isolate::with_context for an explanation of this setup code. We can't use
         // that method directly since we want an `await` below, and passing in a
@@ -1368,7 +1390,11 @@ impl<RT: Runtime> IsolateEnvironment<RT> for ActionEnvironment<RT> {
     }
 
     fn user_timeout(&self) -> std::time::Duration {
-        *ACTION_USER_TIMEOUT
+        self.user_timeout
+    }
+
+    fn max_user_heap_size(&self) -> usize {
+        self.max_user_heap_size
     }
 
     fn system_timeout(&self) -> std::time::Duration {
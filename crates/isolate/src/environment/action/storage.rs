@@ -1,4 +1,7 @@
-use std::str::FromStr;
+use std::{
+    collections::BTreeMap,
+    str::FromStr,
+};
 
 use anyhow::Context;
 use common::{
@@ -19,7 +22,13 @@ use headers::{
     Header,
     HeaderValue,
 };
-use model::file_storage::FileStorageId;
+use model::file_storage::{
+    types::{
+        validate_file_storage_filename,
+        validate_file_storage_tags,
+    },
+    FileStorageId,
+};
 use usage_tracking::StorageUsageTracker;
 use value::id_v6::DeveloperDocumentId;
 
@@ -45,7 +54,13 @@ impl<RT: Runtime> TaskExecutor<RT> {
         content_type: Option<String>,
         content_length: Option<String>,
         digest: Option<String>,
+        filename: Option<String>,
+        tags: BTreeMap<String, String>,
     ) -> anyhow::Result<DeveloperDocumentId> {
+        if let Some(filename) = &filename {
+            validate_file_storage_filename(filename)?;
+        }
+        validate_file_storage_tags(&tags)?;
         let content_length = content_length
             .map(|c| -> anyhow::Result<headers::ContentLength> {
                 Ok(headers::ContentLength(c.parse()?))
@@ -67,7 +82,7 @@ impl<RT: Runtime> TaskExecutor<RT> {
             .transpose()
             .map_err(|e| ErrorMetadata::bad_request("InvalidDigestHeader", e.to_string()))?;
 
-        let entry = self
+        let mut entry = self
             .file_storage
             .upload_file(
                 content_length,
@@ -76,6 +91,8 @@ impl<RT: Runtime> TaskExecutor<RT> {
                 digest,
             )
             .await?;
+        entry.filename = filename;
+        entry.tags = tags;
         let storage_id = entry.storage_id.clone();
         let size = entry.size;
         let sha256 = entry.sha256.clone();
@@ -59,6 +59,12 @@ impl TaskRequestEnum {
             TaskRequestEnum::AsyncOp(AsyncOpRequest::StorageStore { .. }) => TaskType::StorageStore,
             TaskRequestEnum::AsyncOp(AsyncOpRequest::StorageGet { .. }) => TaskType::StorageGet,
             TaskRequestEnum::AsyncOp(AsyncOpRequest::SendStream { .. }) => TaskType::SendStream,
+            TaskRequestEnum::AsyncOp(AsyncOpRequest::StreamReserve { .. }) => {
+                TaskType::StreamReserve
+            },
+            TaskRequestEnum::AsyncOp(AsyncOpRequest::WebSocketConnect { .. }) => {
+                TaskType::WebSocketConnect
+            },
         }
     }
 
@@ -85,6 +91,8 @@ pub enum TaskType {
     StorageStore,
     StorageGet,
     SendStream,
+    StreamReserve,
+    WebSocketConnect,
 }
 
 fn syscall_display_name(syscall: &str) -> String {
@@ -104,7 +112,8 @@ impl TaskType {
             TaskType::ParseMultiPart => "formData".to_string(),
             TaskType::StorageStore => "storage.store".to_string(),
             TaskType::StorageGet => "storage.get".to_string(),
-            TaskType::SendStream => "ReadableStream".to_string(),
+            TaskType::SendStream | TaskType::StreamReserve => "ReadableStream".to_string(),
+            TaskType::WebSocketConnect => "WebSocket".to_string(),
             // Sleeps cannot actually be dangling, but we handle it just in case.
             TaskType::Sleep => "setTimeout".to_string(),
         }
@@ -130,6 +139,8 @@ pub enum TaskResponseEnum {
     Sleep(UnixTimestamp),
     StorageStore(DeveloperDocumentId),
     StorageGet(Option<FileResponse>),
+    StreamReserve,
+    WebSocketConnect(WebSocketConnectResponse),
 }
 
 impl TaskResponseEnum {
@@ -144,6 +155,8 @@ impl TaskResponseEnum {
             Self::Sleep(_) => serde_v8::to_v8(scope, ())?,
             Self::StorageStore(storage_id) => serde_v8::to_v8(scope, storage_id.to_string())?,
             Self::StorageGet(file_response) => serde_v8::to_v8(scope, file_response)?,
+            Self::StreamReserve => serde_v8::to_v8(scope, ())?,
+            Self::WebSocketConnect(response) => serde_v8::to_v8(scope, response)?,
         };
         Ok(value_v8)
     }
@@ -157,6 +170,14 @@ pub struct FileResponse {
     pub content_type: Option<String>,
 }
 
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct WebSocketConnectResponse {
+    pub incoming_stream_id: uuid::Uuid,
+    /// The subprotocol the server selected, or empty if it didn't.
+    pub protocol: String,
+}
+
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct FormPart {
@@ -9,6 +9,7 @@ use common::{
     components::{
         CanonicalizedComponentFunctionPath,
         ComponentId,
+        ComponentPath,
         Reference,
         Resource,
     },
@@ -52,6 +53,7 @@ use crate::{
                 TaskResponseEnum,
             },
             task_order::TaskOrder,
+            websocket::WebSocketConnections,
         },
         helpers::SyscallTrace,
         AsyncOpRequest,
@@ -78,7 +80,11 @@ pub struct TaskExecutor<RT: Runtime> {
     pub context: ExecutionContext,
     pub resources: Arc<Mutex<BTreeMap<Reference, Resource>>>,
     pub component_id: ComponentId,
+    /// The path of the action's component, for attributing usage that isn't
+    /// tied to a document.
+    pub component_path: ComponentPath,
     pub function_handles: Arc<Mutex<BTreeMap<CanonicalizedComponentFunctionPath, FunctionHandle>>>,
+    pub websockets: Arc<WebSocketConnections>,
 }
 
 impl<RT: Runtime> TaskExecutor<RT> {
@@ -152,8 +158,17 @@ impl<RT: Runtime> TaskExecutor<RT> {
                 content_type,
                 content_length,
                 digest,
+                filename,
+                tags,
             }) => self
-                .run_storage_store(body_stream, content_type, content_length, digest)
+                .run_storage_store(
+                    body_stream,
+                    content_type,
+                    content_length,
+                    digest,
+                    filename,
+                    tags,
+                )
                 .await
                 .map(TaskResponseEnum::StorageStore),
             TaskRequestEnum::AsyncOp(AsyncOpRequest::StorageGet {
@@ -163,6 +178,20 @@ impl<RT: Runtime> TaskExecutor<RT> {
                 self.run_storage_get(task_id, storage_id, stream_id).await;
                 return task_id;
             },
+            TaskRequestEnum::AsyncOp(AsyncOpRequest::StreamReserve { capacity, bytes }) => capacity
+                .reserve(bytes)
+                .await
+                .map(|()| TaskResponseEnum::StreamReserve),
+            TaskRequestEnum::AsyncOp(AsyncOpRequest::WebSocketConnect {
+                url,
+                protocols,
+                incoming_stream_id,
+                outgoing,
+            }) => {
+                self.run_websocket_connect(task_id, url, protocols, incoming_stream_id, outgoing)
+                    .await;
+                return task_id;
+            },
         };
         let _ = self
             .task_retval_sender
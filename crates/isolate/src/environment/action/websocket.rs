@@ -0,0 +1,319 @@
+//! Websocket connections for the `WebSocket` API. Messages are passed between
+//! JavaScript and the connection as stream chunks, each holding one message
+//! framed by `encode_frame`.
+use std::sync::{
+    atomic::{
+        AtomicUsize,
+        Ordering,
+    },
+    Arc,
+};
+
+use bytes::{
+    BufMut,
+    Bytes,
+    BytesMut,
+};
+use common::{
+    components::ComponentPath,
+    http::fetch::WebSocketConnection,
+    knobs::{
+        ACTION_MAX_WEBSOCKET_CONNECTIONS,
+        ACTION_WEBSOCKET_MAX_MESSAGE_SIZE,
+    },
+    runtime::{
+        Runtime,
+        SpawnHandle,
+    },
+    sync::spsc,
+};
+use errors::ErrorMetadata;
+use futures::{
+    select_biased,
+    FutureExt,
+    SinkExt,
+    StreamExt,
+};
+use parking_lot::Mutex;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::{
+    self,
+    protocol::{
+        frame::coding::CloseCode,
+        CloseFrame,
+    },
+    Message,
+};
+use url::Url;
+use usage_tracking::FunctionUsageTracker;
+
+use super::task_executor::TaskExecutor;
+use crate::environment::action::task::{
+    TaskId,
+    TaskResponse,
+    TaskResponseEnum,
+    WebSocketConnectResponse,
+};
+
+const FRAME_TEXT: u8 = 0;
+const FRAME_BINARY: u8 = 1;
+const FRAME_CLOSE: u8 = 2;
+
+/// The websocket connections an action has opened. They're closed when the
+/// action finishes and its `TaskExecutor` is dropped.
+#[derive(Default)]
+pub struct WebSocketConnections {
+    handles: Mutex<Vec<Box<dyn SpawnHandle>>>,
+    open: Arc<AtomicUsize>,
+}
+
+impl Drop for WebSocketConnections {
+    fn drop(&mut self) {
+        for handle in self.handles.get_mut() {
+            handle.shutdown();
+        }
+    }
+}
+
+struct OpenConnectionGuard(Arc<AtomicUsize>);
+
+impl Drop for OpenConnectionGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl<RT: Runtime> TaskExecutor<RT> {
+    #[convex_macro::instrument_future]
+    pub async fn run_websocket_connect(
+        &self,
+        task_id: TaskId,
+        url: Url,
+        protocols: Vec<String>,
+        incoming_stream_id: uuid::Uuid,
+        outgoing: spsc::UnboundedReceiver<anyhow::Result<Bytes>>,
+    ) {
+        let result = self
+            .run_websocket_connect_inner(url, protocols, incoming_stream_id, outgoing)
+            .await;
+        _ = self.task_retval_sender.send(TaskResponse::TaskDone {
+            task_id,
+            variant: result.map(TaskResponseEnum::WebSocketConnect),
+        });
+    }
+
+    async fn run_websocket_connect_inner(
+        &self,
+        url: Url,
+        protocols: Vec<String>,
+        incoming_stream_id: uuid::Uuid,
+        outgoing: spsc::UnboundedReceiver<anyhow::Result<Bytes>>,
+    ) -> anyhow::Result<WebSocketConnectResponse> {
+        let open = self.websockets.open.fetch_add(1, Ordering::SeqCst) + 1;
+        let guard = OpenConnectionGuard(self.websockets.open.clone());
+        anyhow::ensure!(
+            open <= *ACTION_MAX_WEBSOCKET_CONNECTIONS,
+            ErrorMetadata::bad_request(
+                "TooManyWebSockets",
+                format!(
+                    "Actions can have at most {} open WebSocket connections",
+                    *ACTION_MAX_WEBSOCKET_CONNECTIONS
+                ),
+            )
+        );
+        // Only log origin because query params might contain some PII.
+        let origin = url.origin().unicode_serialization();
+        let (connection, protocol) = self
+            .fetch_client
+            .connect_websocket(url, protocols)
+            .await
+            .map_err(|e| ErrorMetadata::bad_request("WebSocketFailed", format!("{e:#}")))?;
+        tracing::info!("Opened WebSocket to origin: {origin}");
+
+        // The connection runs outside of the task so it doesn't count against
+        // the action's concurrent ops.
+        let pump = pump_messages(
+            connection,
+            outgoing,
+            incoming_stream_id,
+            self.task_retval_sender.clone(),
+            self.usage_tracker.clone(),
+            self.component_path.clone(),
+        );
+        let handle = self.rt.spawn("action_websocket", async move {
+            let _guard = guard;
+            pump.await;
+        });
+        self.websockets.handles.lock().push(handle);
+        Ok(WebSocketConnectResponse {
+            incoming_stream_id,
+            protocol: protocol.unwrap_or_default(),
+        })
+    }
+}
+
+enum Event {
+    Incoming(Option<Result<Message, tungstenite::Error>>),
+    Outgoing(Option<anyhow::Result<Bytes>>),
+}
+
+/// Forward messages between the connection and JavaScript until both sides
+/// are closed.
+async fn pump_messages(
+    connection: WebSocketConnection,
+    mut outgoing: spsc::UnboundedReceiver<anyhow::Result<Bytes>>,
+    stream_id: uuid::Uuid,
+    task_retval_sender: mpsc::UnboundedSender<TaskResponse>,
+    usage_tracker: FunctionUsageTracker,
+    component_path: ComponentPath,
+) {
+    let (mut sink, mut source) = connection.split();
+    let send_chunk = |chunk: anyhow::Result<Option<Bytes>>| {
+        _ = task_retval_sender.send(TaskResponse::StreamExtend { stream_id, chunk });
+    };
+    let forward_incoming = |message: Message| {
+        usage_tracker.track_websocket_bandwidth(component_path.clone(), message.len() as u64, 0);
+        if let Some(frame) = encode_frame(message) {
+            send_chunk(Ok(Some(frame)));
+        }
+    };
+    loop {
+        let event = select_biased! {
+            message = source.next().fuse() => Event::Incoming(message),
+            chunk = outgoing.recv().fuse() => Event::Outgoing(chunk),
+        };
+        match event {
+            Event::Incoming(None) => break,
+            Event::Incoming(Some(Ok(message))) => forward_incoming(message),
+            Event::Incoming(Some(Err(e))) => {
+                send_chunk(Err(ErrorMetadata::bad_request(
+                    "WebSocketFailed",
+                    e.to_string(),
+                )
+                .into()));
+                return;
+            },
+            Event::Outgoing(Some(Ok(chunk))) => {
+                let message = match decode_frame(chunk) {
+                    Ok(message) => message,
+                    Err(e) => {
+                        send_chunk(Err(e));
+                        return;
+                    },
+                };
+                usage_tracker.track_websocket_bandwidth(
+                    component_path.clone(),
+                    0,
+                    message.len() as u64,
+                );
+                if let Err(e) = sink.send(message).await {
+                    send_chunk(Err(ErrorMetadata::bad_request(
+                        "WebSocketFailed",
+                        e.to_string(),
+                    )
+                    .into()));
+                    return;
+                }
+            },
+            Event::Outgoing(_) => {
+                // JavaScript is done sending, so finish closing the connection
+                // and pass on anything the server sends until then.
+                _ = sink.close().await;
+                while let Some(Ok(message)) = source.next().await {
+                    forward_incoming(message);
+                }
+                break;
+            },
+        }
+    }
+    send_chunk(Ok(None));
+}
+
+/// Frame a message for JavaScript, or `None` for control messages that
+/// tungstenite handles itself.
+fn encode_frame(message: Message) -> Option<Bytes> {
+    let (kind, payload) = match message {
+        Message::Text(text) => (FRAME_TEXT, Bytes::from(text)),
+        Message::Binary(data) => (FRAME_BINARY, Bytes::from(data)),
+        Message::Close(close_frame) => {
+            let mut payload = BytesMut::new();
+            if let Some(CloseFrame { code, reason }) = close_frame {
+                payload.put_u16(code.into());
+                payload.put_slice(reason.as_bytes());
+            }
+            (FRAME_CLOSE, payload.freeze())
+        },
+        Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => return None,
+    };
+    let mut frame = BytesMut::with_capacity(payload.len() + 1);
+    frame.put_u8(kind);
+    frame.put(payload);
+    Some(frame.freeze())
+}
+
+fn decode_frame(frame: Bytes) -> anyhow::Result<Message> {
+    let Some((&kind, payload)) = frame.split_first() else {
+        anyhow::bail!("empty WebSocket frame");
+    };
+    anyhow::ensure!(
+        payload.len() <= *ACTION_WEBSOCKET_MAX_MESSAGE_SIZE,
+        ErrorMetadata::bad_request(
+            "WebSocketMessageTooLarge",
+            format!(
+                "WebSocket messages can be at most {} bytes",
+                *ACTION_WEBSOCKET_MAX_MESSAGE_SIZE
+            ),
+        )
+    );
+    let message = match kind {
+        FRAME_TEXT => Message::Text(String::from_utf8(payload.to_vec())?),
+        FRAME_BINARY => Message::Binary(payload.to_vec()),
+        FRAME_CLOSE => {
+            let close_frame = match payload {
+                [] => None,
+                [high, low, reason @ ..] => Some(CloseFrame {
+                    code: CloseCode::from(u16::from_be_bytes([*high, *low])),
+                    reason: String::from_utf8(reason.to_vec())?.into(),
+                }),
+                _ => anyhow::bail!("invalid WebSocket close frame"),
+            };
+            Message::Close(close_frame)
+        },
+        _ => anyhow::bail!("unknown WebSocket frame kind {kind}"),
+    };
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_tungstenite::tungstenite::{
+        protocol::{
+            frame::coding::CloseCode,
+            CloseFrame,
+        },
+        Message,
+    };
+
+    use super::{
+        decode_frame,
+        encode_frame,
+    };
+
+    #[test]
+    fn test_frame_roundtrips() -> anyhow::Result<()> {
+        for message in [
+            Message::Text("hello".to_string()),
+            Message::Binary(vec![0, 1, 2]),
+            Message::Close(None),
+            Message::Close(Some(CloseFrame {
+                code: CloseCode::Normal,
+                reason: "bye".into(),
+            })),
+        ] {
+            let frame = encode_frame(message.clone()).unwrap();
+            assert_eq!(decode_frame(frame)?, message);
+        }
+        assert!(encode_frame(Message::Ping(vec![])).is_none());
+        Ok(())
+    }
+}
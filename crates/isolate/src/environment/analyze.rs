@@ -6,6 +6,7 @@ use std::{
     path::Path,
     str::FromStr,
     sync::Arc,
+    time::Duration,
 };
 
 use anyhow::{
@@ -15,8 +16,10 @@ use anyhow::{
 use common::{
     errors::JsError,
     knobs::{
+        DATABASE_UDF_MAX_USER_TIMEOUT,
         DATABASE_UDF_SYSTEM_TIMEOUT,
         ISOLATE_ANALYZE_USER_TIMEOUT,
+        MAX_WARM_INSTANCES_PER_FUNCTION,
     },
     log_lines::LogLevel,
     runtime::{
@@ -63,6 +66,7 @@ use model::{
             AnalyzedSourcePosition,
             FullModuleSource,
             MappedModule,
+            RequiredClaims,
             Visibility,
         },
         user_error::ModuleNotFoundError,
@@ -253,26 +257,7 @@ impl AnalyzeEnvironment {
                 .all(|m| m.environment == ModuleEnvironment::Isolate),
             "Isolate environment can only analyze Isolate modules"
         );
-        let rng = ChaCha12Rng::from_seed(udf_config.import_phase_rng_seed);
-        let unix_timestamp = udf_config.import_phase_unix_timestamp;
-        let environment = AnalyzeEnvironment {
-            modules: modules
-                .into_iter()
-                .map(|(path, module)| {
-                    (
-                        path,
-                        FullModuleSource {
-                            source: module.source,
-                            source_map: module.source_map,
-                        },
-                    )
-                })
-                .collect(),
-            source_maps_cache: BTreeMap::new(),
-            rng,
-            unix_timestamp,
-            environment_variables,
-        };
+        let environment = AnalyzeEnvironment::new(udf_config, modules, environment_variables);
         let client_id = Arc::new(client_id);
         let (handle, state) = isolate.start_request(client_id, environment).await?;
         let mut handle_scope = isolate.handle_scope();
@@ -303,6 +288,96 @@ impl AnalyzeEnvironment {
         result
     }
 
+    /// Evaluate `entry_points` and their imports in a fresh context, leaving
+    /// the isolate clean for reuse. The scheduler hands a client's requests to
+    /// the isolates it used most recently, so this keeps them from idling out
+    /// and primes V8's caches for the modules before real requests arrive.
+    #[minitrace::trace]
+    pub async fn warm_up<RT: Runtime>(
+        client_id: String,
+        isolate: &mut Isolate<RT>,
+        isolate_clean: &mut bool,
+        udf_config: UdfConfig,
+        modules: BTreeMap<CanonicalizedModulePath, ModuleConfig>,
+        environment_variables: BTreeMap<EnvVarName, EnvVarValue>,
+        entry_points: Vec<CanonicalizedModulePath>,
+    ) -> anyhow::Result<Result<(), JsError>> {
+        anyhow::ensure!(
+            modules
+                .values()
+                .all(|m| m.environment == ModuleEnvironment::Isolate),
+            "Isolate environment can only warm up Isolate modules"
+        );
+        let environment = AnalyzeEnvironment::new(udf_config, modules, environment_variables);
+        let client_id = Arc::new(client_id);
+        let (handle, state) = isolate.start_request(client_id, environment).await?;
+        let mut handle_scope = isolate.handle_scope();
+        let v8_context = v8::Context::new(&mut handle_scope);
+        let mut context_scope = v8::ContextScope::new(&mut handle_scope, v8_context);
+        let mut isolate_context =
+            RequestScope::new(&mut context_scope, handle.clone(), state, false).await?;
+        let handle = isolate_context.handle();
+        let result = Self::run_warm_up(&mut isolate_context, entry_points).await;
+
+        // See `analyze` for why the checkpoint is needed before reuse.
+        isolate_context.scope.perform_microtask_checkpoint();
+        *isolate_clean = true;
+        drop(isolate_context);
+
+        if let Err(e) = handle.take_termination_error()? {
+            return Ok(Err(e));
+        }
+        result
+    }
+
+    fn new(
+        udf_config: UdfConfig,
+        modules: BTreeMap<CanonicalizedModulePath, ModuleConfig>,
+        environment_variables: BTreeMap<EnvVarName, EnvVarValue>,
+    ) -> Self {
+        let rng = ChaCha12Rng::from_seed(udf_config.import_phase_rng_seed);
+        let unix_timestamp = udf_config.import_phase_unix_timestamp;
+        AnalyzeEnvironment {
+            modules: modules
+                .into_iter()
+                .map(|(path, module)| {
+                    (
+                        path,
+                        FullModuleSource {
+                            source: module.source,
+                            source_map: module.source_map,
+                        },
+                    )
+                })
+                .collect(),
+            source_maps_cache: BTreeMap::new(),
+            rng,
+            unix_timestamp,
+            environment_variables,
+        }
+    }
+
+    async fn run_warm_up<RT: Runtime>(
+        isolate: &mut RequestScope<'_, '_, RT, Self>,
+        entry_points: Vec<CanonicalizedModulePath>,
+    ) -> anyhow::Result<Result<(), JsError>> {
+        let mut v8_scope = isolate.scope();
+        let mut scope = RequestScope::<RT, Self>::enter(&mut v8_scope);
+        for path in entry_points {
+            let module_specifier = module_specifier_from_path(&path)?;
+            if let Err(e) = scope.eval_module(&module_specifier).await {
+                if let Some(e) = e.downcast_ref::<ModuleNotFoundError>() {
+                    return Ok(Err(JsError::from_message(format!("{e}"))));
+                }
+                return match e.downcast::<JsError>() {
+                    Ok(e) => Ok(Err(e)),
+                    Err(e) => Err(e),
+                };
+            }
+        }
+        Ok(Ok(()))
+    }
+
     fn get_source_map(
         &mut self,
         path: &CanonicalizedModulePath,
@@ -569,6 +644,132 @@ fn parse_returns_validator<'s, RT: Runtime>(
     };
     Ok(Ok(returns))
 }
+
+/// Reads the function's `timeoutMs` override, which only internal queries and
+/// mutations may set, bounded by `DATABASE_UDF_MAX_USER_TIMEOUT`.
+fn parse_timeout<RT: Runtime>(
+    scope: &mut ExecutionScope<RT, AnalyzeEnvironment>,
+    function: v8::Local<v8::Function>,
+    udf_type: UdfType,
+    visibility: &Option<Visibility>,
+    function_identifier_for_error: String,
+) -> anyhow::Result<Result<Option<Duration>, JsError>> {
+    let timeout_ms_str = strings::timeoutMs.create(scope)?;
+    let timeout_ms = match function.get(scope, timeout_ms_str.into()) {
+        Some(value) if value.is_number() => value.number_value(scope).unwrap_or(f64::NAN),
+        Some(value) if value.is_undefined() => return Ok(Ok(None)),
+        Some(_) => {
+            let message = format!("{function_identifier_for_error}.timeoutMs is not a number.");
+            return Ok(Err(JsError::from_message(message)));
+        },
+        None => return Ok(Ok(None)),
+    };
+    if udf_type == UdfType::Action || *visibility != Some(Visibility::Internal) {
+        let message = format!(
+            "{function_identifier_for_error} can't set timeoutMs. Only internal queries and \
+             mutations can override their timeout."
+        );
+        return Ok(Err(JsError::from_message(message)));
+    }
+    let max_timeout_ms = DATABASE_UDF_MAX_USER_TIMEOUT.as_millis() as f64;
+    if !(timeout_ms.is_finite() && timeout_ms > 0.0 && timeout_ms <= max_timeout_ms) {
+        let message = format!(
+            "{function_identifier_for_error}.timeoutMs must be between 1 and {max_timeout_ms}, \
+             but is {timeout_ms}."
+        );
+        return Ok(Err(JsError::from_message(message)));
+    }
+    Ok(Ok(Some(Duration::from_millis(timeout_ms.ceil() as u64))))
+}
+
+/// Reads the function's `warmInstances` setting, which only queries and
+/// mutations may set, bounded by `MAX_WARM_INSTANCES_PER_FUNCTION`.
+fn parse_warm_instances<RT: Runtime>(
+    scope: &mut ExecutionScope<RT, AnalyzeEnvironment>,
+    function: v8::Local<v8::Function>,
+    udf_type: UdfType,
+    function_identifier_for_error: String,
+) -> anyhow::Result<Result<Option<u32>, JsError>> {
+    let warm_instances_str = strings::warmInstances.create(scope)?;
+    let warm_instances = match function.get(scope, warm_instances_str.into()) {
+        Some(value) if value.is_number() => value.number_value(scope).unwrap_or(f64::NAN),
+        Some(value) if value.is_undefined() => return Ok(Ok(None)),
+        Some(_) => {
+            let message = format!("{function_identifier_for_error}.warmInstances is not a number.");
+            return Ok(Err(JsError::from_message(message)));
+        },
+        None => return Ok(Ok(None)),
+    };
+    if udf_type == UdfType::Action {
+        let message = format!(
+            "{function_identifier_for_error} can't set warmInstances. Only queries and mutations \
+             can be kept warm."
+        );
+        return Ok(Err(JsError::from_message(message)));
+    }
+    let max_warm_instances = *MAX_WARM_INSTANCES_PER_FUNCTION;
+    if !(warm_instances.fract() == 0.0
+        && warm_instances >= 1.0
+        && warm_instances <= max_warm_instances as f64)
+    {
+        let message = format!(
+            "{function_identifier_for_error}.warmInstances must be an integer between 1 and \
+             {max_warm_instances}, but is {warm_instances}."
+        );
+        return Ok(Err(JsError::from_message(message)));
+    }
+    Ok(Ok(Some(warm_instances as u32)))
+}
+
+/// Reads the function's `requiredClaims`, the identity claims non-admin
+/// callers must have.
+fn parse_required_claims<RT: Runtime>(
+    scope: &mut ExecutionScope<RT, AnalyzeEnvironment>,
+    function: v8::Local<v8::Function>,
+    function_identifier_for_error: String,
+) -> anyhow::Result<Result<Option<RequiredClaims>, JsError>> {
+    let required_claims_str = strings::requiredClaims.create(scope)?;
+    let value = match function.get(scope, required_claims_str.into()) {
+        Some(value) if value.is_undefined() => return Ok(Ok(None)),
+        Some(value) => value,
+        None => return Ok(Ok(None)),
+    };
+    let claims_json = match v8::json::stringify(scope, value) {
+        Some(claims_str) => {
+            let claims_str = helpers::to_rust_string(scope, &claims_str)?;
+            serde_json::from_str::<JsonValue>(&claims_str).unwrap_or(JsonValue::Null)
+        },
+        None => JsonValue::Null,
+    };
+    match RequiredClaims::try_from(claims_json) {
+        Ok(claims) => Ok(Ok(Some(claims))),
+        Err(e) => {
+            let message = format!("{function_identifier_for_error}: {e}.");
+            Ok(Err(JsError::from_message(message)))
+        },
+    }
+}
+
+/// Rejects a `region` setting, since only Node actions run on regional action
+/// runners.
+fn check_no_region<RT: Runtime>(
+    scope: &mut ExecutionScope<RT, AnalyzeEnvironment>,
+    function: v8::Local<v8::Function>,
+    function_identifier_for_error: String,
+) -> anyhow::Result<Result<(), JsError>> {
+    let region_str = strings::region.create(scope)?;
+    match function.get(scope, region_str.into()) {
+        Some(value) if !value.is_undefined() => {
+            let message = format!(
+                "{function_identifier_for_error} can't set region. Only Node.js actions can be \
+                 pinned to a region. Add \"use node\" to the top of the file to run it in Node.js."
+            );
+            Ok(Err(JsError::from_message(message)))
+        },
+        _ => Ok(Ok(())),
+    }
+}
+
 #[minitrace::trace]
 fn udf_analyze<RT: Runtime>(
     scope: &mut ExecutionScope<RT, AnalyzeEnvironment>,
@@ -648,6 +849,23 @@ fn udf_analyze<RT: Runtime>(
             },
         };
 
+        let timeout = parse_timeout(
+            scope,
+            function,
+            udf_type,
+            &visibility,
+            format!("{module_path:?}:{property_name}"),
+        )??;
+        let warm_instances = parse_warm_instances(
+            scope,
+            function,
+            udf_type,
+            format!("{module_path:?}:{property_name}"),
+        )??;
+        check_no_region(scope, function, format!("{module_path:?}:{property_name}"))??;
+        let required_claims =
+            parse_required_claims(scope, function, format!("{module_path:?}:{property_name}"))??;
+
         let handler_str = strings::_handler.create(scope)?;
         let handler = match function.get(scope, handler_str.into()) {
             Some(handler_value) if handler_value.is_function() => {
@@ -696,28 +914,38 @@ fn udf_analyze<RT: Runtime>(
             && fn_canon_path.as_str() == module_path.as_str()
         {
             // Source map is valid; proceed with mapping in original source map
-            functions.push(AnalyzedFunction::new(
-                canonicalized_name.clone(),
-                Some(AnalyzedSourcePosition {
-                    path: fn_canon_path,
-                    start_lineno: token.get_src_line(),
-                    start_col: token.get_src_col(),
-                }),
-                udf_type,
-                visibility.clone(),
-                args.clone(),
-                returns.clone(),
-            )?);
+            functions.push(
+                AnalyzedFunction::new(
+                    canonicalized_name.clone(),
+                    Some(AnalyzedSourcePosition {
+                        path: fn_canon_path,
+                        start_lineno: token.get_src_line(),
+                        start_col: token.get_src_col(),
+                    }),
+                    udf_type,
+                    visibility.clone(),
+                    args.clone(),
+                    returns.clone(),
+                )?
+                .with_timeout(timeout)
+                .with_warm_instances(warm_instances)
+                .with_required_claims(required_claims.clone()),
+            );
         } else {
             // If there is no valid source map, push a function without a position
-            functions.push(AnalyzedFunction::new(
-                canonicalized_name.clone(),
-                None,
-                udf_type,
-                visibility.clone(),
-                args.clone(),
-                returns.clone(),
-            )?);
+            functions.push(
+                AnalyzedFunction::new(
+                    canonicalized_name.clone(),
+                    None,
+                    udf_type,
+                    visibility.clone(),
+                    args.clone(),
+                    returns.clone(),
+                )?
+                .with_timeout(timeout)
+                .with_warm_instances(warm_instances)
+                .with_required_claims(required_claims.clone()),
+            );
 
             // Log reason for fallback
             if fn_canon_path.as_str() != module_path.as_str() {
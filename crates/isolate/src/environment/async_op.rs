@@ -1,4 +1,7 @@
-use std::fmt;
+use std::{
+    collections::BTreeMap,
+    fmt,
+};
 
 use common::{
     http::HttpRequestStream,
@@ -6,6 +9,9 @@ use common::{
     sync::spsc,
 };
 use futures::stream::BoxStream;
+use url::Url;
+
+use crate::request_scope::StreamCapacity;
 
 pub enum AsyncOpRequest {
     Fetch {
@@ -25,6 +31,8 @@ pub enum AsyncOpRequest {
         content_type: Option<String>,
         content_length: Option<String>,
         digest: Option<String>,
+        filename: Option<String>,
+        tags: BTreeMap<String, String>,
     },
     StorageGet {
         storage_id: String,
@@ -34,6 +42,16 @@ pub enum AsyncOpRequest {
         stream: Option<BoxStream<'static, anyhow::Result<bytes::Bytes>>>,
         stream_id: uuid::Uuid,
     },
+    StreamReserve {
+        capacity: StreamCapacity,
+        bytes: usize,
+    },
+    WebSocketConnect {
+        url: Url,
+        protocols: Vec<String>,
+        incoming_stream_id: uuid::Uuid,
+        outgoing: spsc::UnboundedReceiver<anyhow::Result<bytes::Bytes>>,
+    },
 }
 
 impl AsyncOpRequest {
@@ -43,7 +61,8 @@ impl AsyncOpRequest {
             Self::ParseMultiPart { .. } => "FormParse",
             Self::Sleep { .. } => "Sleep",
             Self::StorageStore { .. } | Self::StorageGet { .. } => "Storage",
-            Self::SendStream { .. } => "Stream",
+            Self::SendStream { .. } | Self::StreamReserve { .. } => "Stream",
+            Self::WebSocketConnect { .. } => "WebSocket",
         }
     }
 
@@ -54,7 +73,8 @@ impl AsyncOpRequest {
             Self::Sleep { name, .. } => name.to_string(),
             Self::StorageStore { .. } => "storage.store()".to_string(),
             Self::StorageGet { .. } => "storage.get()".to_string(),
-            Self::SendStream { .. } => "stream".to_string(),
+            Self::SendStream { .. } | Self::StreamReserve { .. } => "stream".to_string(),
+            Self::WebSocketConnect { .. } => "WebSocket".to_string(),
         }
     }
 }
@@ -33,6 +33,12 @@ impl JsonPackedValue {
     }
 }
 
+impl From<JsonPackedValue> for JsonValue {
+    fn from(value: JsonPackedValue) -> Self {
+        value.json_value()
+    }
+}
+
 impl HeapSize for JsonPackedValue {
     fn heap_size(&self) -> usize {
         self.0.len()
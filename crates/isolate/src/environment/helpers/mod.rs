@@ -3,6 +3,7 @@ pub mod module_loader;
 mod outcome;
 pub mod permit;
 mod promise;
+pub mod seeded_random;
 pub mod syscall_error;
 mod syscall_stats;
 mod syscall_trace;
@@ -14,6 +15,8 @@ use deno_core::{
     v8,
 };
 use errors::ErrorMetadata;
+use file_storage::UploadUrlOptions;
+use keybroker::StoreFileConstraints;
 use serde_json::Value as JsonValue;
 
 pub use self::{
@@ -54,6 +57,15 @@ pub fn with_argument_error<T>(
     })
 }
 
+/// Parse the arguments to `storage.generateUploadUrl()`, which are the same
+/// for queries, mutations and actions.
+pub fn parse_upload_url_options(args: JsonValue) -> anyhow::Result<StoreFileConstraints> {
+    with_argument_error("storage.generateUploadUrl", || {
+        let options: UploadUrlOptions = serde_json::from_value(args)?;
+        options.try_into()
+    })
+}
+
 #[derive(Eq, PartialEq, Debug)]
 pub enum Phase {
     Importing,
@@ -0,0 +1,81 @@
+//! Deterministic randomness for `1.0/seededRandom`.
+//!
+//! Unlike `Math.random()`, which is seeded from system entropy and makes a
+//! query uncacheable, these values are a pure function of the inputs below, so
+//! a query that uses them stays deterministic and cacheable. The derivation is
+//! part of the public API and must not change:
+//!
+//! 1. The seed is SHA-256 over, in order:
+//!    - the ASCII bytes `convex-seeded-random-v1` followed by a zero byte,
+//!    - the length (u64, big-endian) and bytes of the function's arguments
+//!      serialized as Convex JSON,
+//!    - the length (u64, big-endian) and UTF-8 bytes of the caller's key,
+//!    - a zero byte if no period was given, or a one byte followed by
+//!      `floor(execution_time_ms / period_ms)` (u64, big-endian).
+//! 2. The seed initializes a ChaCha12 stream (`rand_chacha::ChaCha12Rng`).
+//! 3. Each value is `(next_u64() >> 11) * 2^-53`, a float in `[0, 1)`.
+use rand::{
+    RngCore,
+    SeedableRng,
+};
+use rand_chacha::ChaCha12Rng;
+use sha2::{
+    Digest,
+    Sha256,
+};
+
+const DOMAIN: &[u8] = b"convex-seeded-random-v1\0";
+
+/// Maximum number of values returned from a single syscall.
+pub const MAX_SEEDED_RANDOM_VALUES: usize = 8192;
+
+pub fn seeded_rng(arguments_json: &str, key: &str, period_index: Option<u64>) -> ChaCha12Rng {
+    let mut hasher = Sha256::new();
+    hasher.update(DOMAIN);
+    hasher.update((arguments_json.len() as u64).to_be_bytes());
+    hasher.update(arguments_json.as_bytes());
+    hasher.update((key.len() as u64).to_be_bytes());
+    hasher.update(key.as_bytes());
+    match period_index {
+        None => hasher.update([0]),
+        Some(index) => {
+            hasher.update([1]);
+            hasher.update(index.to_be_bytes());
+        },
+    }
+    ChaCha12Rng::from_seed(hasher.finalize().into())
+}
+
+pub fn next_f64(rng: &mut ChaCha12Rng) -> f64 {
+    (rng.next_u64() >> 11) as f64 * (1.0 / (1u64 << 53) as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        next_f64,
+        seeded_rng,
+    };
+
+    fn values(arguments_json: &str, key: &str, period_index: Option<u64>) -> Vec<f64> {
+        let mut rng = seeded_rng(arguments_json, key, period_index);
+        (0..4).map(|_| next_f64(&mut rng)).collect()
+    }
+
+    #[test]
+    fn test_seeded_random_is_deterministic() {
+        let a = values("[{}]", "featured", None);
+        assert_eq!(a, values("[{}]", "featured", None));
+        assert!(a.iter().all(|v| (0.0..1.0).contains(v)));
+
+        assert_ne!(a, values("[{\"page\":1}]", "featured", None));
+        assert_ne!(a, values("[{}]", "other", None));
+        assert_ne!(a, values("[{}]", "featured", Some(0)));
+        assert_ne!(
+            values("[{}]", "featured", Some(0)),
+            values("[{}]", "featured", Some(1))
+        );
+        // Length prefixes keep the argument and key boundary unambiguous.
+        assert_ne!(values("[{}]a", "", None), values("[{}]", "a", None));
+    }
+}
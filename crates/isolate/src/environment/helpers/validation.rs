@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use anyhow::Context;
 use common::{
     components::{
@@ -9,6 +11,10 @@ use common::{
     },
     errors::JsError,
     identity::InertIdentity,
+    knobs::{
+        ACTION_MAX_USER_TIMEOUT,
+        DATABASE_UDF_MAX_USER_TIMEOUT,
+    },
     log_lines::LogLines,
     query_journal::QueryJournal,
     runtime::{
@@ -34,6 +40,7 @@ use keybroker::Identity;
 use model::{
     backend_state::BackendStateModel,
     components::ComponentsModel,
+    function_limits::FunctionLimitsModel,
     modules::{
         function_validators::ReturnsValidator,
         module_versions::{
@@ -207,6 +214,11 @@ pub struct ValidatedPathAndArgs {
     args: ConvexArray,
     // Not set for system modules.
     npm_version: Option<Version>,
+    /// The function's own user timeout, if it overrides the default.
+    user_timeout: Option<Duration>,
+    /// The function's own heap limit, if an admin raised it above
+    /// `ISOLATE_MAX_USER_HEAP_SIZE`.
+    max_user_heap_size: Option<usize>,
 }
 
 #[cfg(any(test, feature = "testing"))]
@@ -233,6 +245,8 @@ impl Arbitrary for ValidatedPathAndArgs {
                 },
                 args,
                 npm_version: None,
+                user_timeout: None,
+                max_user_heap_size: None,
             }
         })
     }
@@ -294,6 +308,8 @@ impl ValidatedPathAndArgs {
                         path,
                         args,
                         npm_version: None,
+                        user_timeout: None,
+                        max_user_heap_size: None,
                     },
                     ReturnsValidator::Unvalidated,
                 ))
@@ -370,7 +386,10 @@ impl ValidatedPathAndArgs {
             analyzed_function,
             udf_version,
         )? {
-            Ok(validated_udf_path_and_args) => {
+            Ok(mut validated_udf_path_and_args) => {
+                validated_udf_path_and_args
+                    .apply_function_limits(tx, expected_udf_type)
+                    .await?;
                 Ok(Ok((validated_udf_path_and_args, returns_validator)))
             },
             Err(js_err) => Ok(Err(js_err)),
@@ -409,6 +428,30 @@ impl ValidatedPathAndArgs {
                 },
             },
         };
+        // Like visibility, required claims only restrict callers from outside
+        // the deployment, not functions calling each other.
+        if allowed_visibility == AllowedVisibility::PublicOnly
+            && let Some(required_claims) = analyzed_function.required_claims()?
+        {
+            let identity_claims = match identity {
+                Identity::InstanceAdmin(_) | Identity::System(_) => None,
+                Identity::User(user) => Some(JsonValue::try_from(user.attributes.clone())?),
+                Identity::ActingUser(_, attributes) => {
+                    Some(JsonValue::try_from(attributes.clone())?)
+                },
+                Identity::Unknown => Some(JsonValue::Null),
+            };
+            if let Some(identity_claims) = identity_claims
+                && let Some(claim) = required_claims.first_missing(&identity_claims)
+            {
+                return Ok(Err(JsError::from_message(format!(
+                    "Unauthorized: calling {}{} requires the caller's {claim:?} claim to match \
+                     its requiredClaims.",
+                    path.udf_path,
+                    path.clone().for_logging().component.in_component_str(),
+                ))));
+            }
+        }
         if expected_udf_type != analyzed_function.udf_type {
             return Ok(Err(JsError::from_message(format!(
                 "Trying to execute {}{} as {}, but it is defined as {}.",
@@ -443,9 +486,40 @@ impl ValidatedPathAndArgs {
             path,
             args,
             npm_version: Some(version),
+            user_timeout: analyzed_function.timeout,
+            max_user_heap_size: None,
         }))
     }
 
+    /// Applies an admin's overrides from `_function_limits`, which take
+    /// precedence over the function's own `timeoutMs`. Only functions in the
+    /// root component can be overridden.
+    async fn apply_function_limits<RT: Runtime>(
+        &mut self,
+        tx: &mut Transaction<RT>,
+        udf_type: UdfType,
+    ) -> anyhow::Result<()> {
+        if !self.path.component.is_root() {
+            return Ok(());
+        }
+        let function = self.path.udf_path.clone().strip().to_string();
+        let Some(limits) = FunctionLimitsModel::new(tx).get(&function).await? else {
+            return Ok(());
+        };
+        let limits = limits.into_value();
+        if let Some(user_timeout) = limits.user_timeout {
+            let max_user_timeout = match udf_type {
+                UdfType::Action | UdfType::HttpAction => *ACTION_MAX_USER_TIMEOUT,
+                UdfType::Query | UdfType::Mutation => *DATABASE_UDF_MAX_USER_TIMEOUT,
+            };
+            self.user_timeout = Some(user_timeout.min(max_user_timeout));
+        }
+        if let Some(max_user_heap_size) = limits.max_user_heap_size {
+            self.max_user_heap_size = Some(max_user_heap_size.try_into()?);
+        }
+        Ok(())
+    }
+
     #[cfg(any(test, feature = "testing"))]
     pub fn new_for_tests(
         udf_path: CanonicalizedUdfPath,
@@ -476,6 +550,8 @@ impl ValidatedPathAndArgs {
             },
             args,
             npm_version,
+            user_timeout: None,
+            max_user_heap_size: None,
         }
     }
 
@@ -491,6 +567,14 @@ impl ValidatedPathAndArgs {
         &self.npm_version
     }
 
+    pub fn user_timeout(&self) -> Option<Duration> {
+        self.user_timeout
+    }
+
+    pub fn max_user_heap_size(&self) -> Option<usize> {
+        self.max_user_heap_size
+    }
+
     pub fn from_proto(
         pb::common::ValidatedPathAndArgs {
             path,
@@ -498,6 +582,8 @@ impl ValidatedPathAndArgs {
             npm_version,
             component_path,
             component_id,
+            user_timeout_ms,
+            max_user_heap_size,
         }: pb::common::ValidatedPathAndArgs,
     ) -> anyhow::Result<Self> {
         let args_json: JsonValue =
@@ -516,6 +602,8 @@ impl ValidatedPathAndArgs {
             },
             args,
             npm_version: npm_version.map(|v| Version::parse(&v)).transpose()?,
+            user_timeout: user_timeout_ms.map(Duration::from_millis),
+            max_user_heap_size: max_user_heap_size.map(usize::try_from).transpose()?,
         })
     }
 }
@@ -528,6 +616,8 @@ impl TryFrom<ValidatedPathAndArgs> for pb::common::ValidatedPathAndArgs {
             path,
             args,
             npm_version,
+            user_timeout,
+            max_user_heap_size,
         }: ValidatedPathAndArgs,
     ) -> anyhow::Result<Self> {
         let args_json = JsonValue::from(args);
@@ -541,6 +631,10 @@ impl TryFrom<ValidatedPathAndArgs> for pb::common::ValidatedPathAndArgs {
             npm_version: npm_version.map(|v| v.to_string()),
             component_path,
             component_id: path.component.serialize_to_string(),
+            user_timeout_ms: user_timeout
+                .map(|timeout| u64::try_from(timeout.as_millis()))
+                .transpose()?,
+            max_user_heap_size: max_user_heap_size.map(u64::try_from).transpose()?,
         })
     }
 }
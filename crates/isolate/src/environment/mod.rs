@@ -19,6 +19,7 @@ pub mod warnings;
 
 use common::{
     errors::JsError,
+    knobs::ISOLATE_MAX_USER_HEAP_SIZE,
     log_lines::LogLevel,
     runtime::{
         Runtime,
@@ -95,6 +96,11 @@ pub trait IsolateEnvironment<RT: Runtime>: 'static {
 
     fn user_timeout(&self) -> Duration;
     fn system_timeout(&self) -> Duration;
+
+    /// How large the user's heap can grow while this environment runs.
+    fn max_user_heap_size(&self) -> usize {
+        *ISOLATE_MAX_USER_HEAP_SIZE
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -6,6 +6,10 @@ use std::{
 };
 
 use anyhow::Context;
+use authentication::webauthn::{
+    self,
+    RelyingParty,
+};
 use common::{
     bootstrap_model::components::handles::FunctionHandle,
     components::{
@@ -16,15 +20,21 @@ use common::{
         ResolvedComponentFunctionPath,
         Resource,
     },
-    document::DeveloperDocument,
+    document::{
+        DeveloperDocument,
+        ResolvedDocument,
+    },
     execution_context::ExecutionContext,
     knobs::{
+        MAX_JOBS_CANCEL_BATCH,
         MAX_REACTOR_CALL_DEPTH,
         MAX_SYSCALL_BATCH_SIZE,
+        WEBAUTHN_CHALLENGE_TTL,
     },
     query::{
         Cursor,
         CursorPosition,
+        Order,
         Query,
     },
     query_journal::QueryJournal,
@@ -34,6 +44,9 @@ use common::{
     },
     types::{
         AllowedVisibility,
+        IndexName,
+        MaybeValue,
+        ObjectKey,
         PersistenceVersion,
         UdfType,
     },
@@ -41,15 +54,31 @@ use common::{
     version::Version,
 };
 use database::{
+    aggregate,
+    get_page,
+    hydrate_references,
+    materialized_aggregates::{
+        invalid_materialized_aggregate,
+        MaterializedAggregateModel,
+        MaterializedAggregateOrderBy,
+        MAX_MATERIALIZED_AGGREGATE_LIST_LIMIT,
+    },
     query::{
         query_batch_next,
         PaginationOptions,
         TableFilter,
     },
+    search_facets,
     soft_data_limit,
+    Aggregate,
+    AggregateGroup,
     BootstrapComponentsModel,
     DeveloperQuery,
+    GetPage,
+    GetPageRequest,
+    IndexKeyBound,
     PatchValue,
+    SearchFacetCount,
     Transaction,
     UserFacingModel,
 };
@@ -59,8 +88,12 @@ use errors::{
     ErrorMetadataAnyhowExt,
 };
 use itertools::Itertools;
-use keybroker::KeyBroker;
+use keybroker::{
+    KeyBroker,
+    StoreFileConstraints,
+};
 use model::{
+    archival::ArchivalModel,
     components::{
         auth::propagate_component_auth,
         handles::FunctionHandlesModel,
@@ -71,9 +104,31 @@ use model::{
         BatchKey,
         FileStorageId,
     },
-    scheduled_jobs::VirtualSchedulerModel,
+    queues::QueuesModel,
+    rate_limiter::{
+        types::RateLimit,
+        RateLimiterModel,
+    },
+    scheduled_jobs::{
+        types::{
+            IdempotencyKey,
+            ScheduledJobFilter,
+            ScheduledJobsCursor,
+        },
+        SchedulerModel,
+        VirtualSchedulerModel,
+    },
     virtual_system_mapping,
+    webauthn::{
+        types::{
+            WebAuthnCeremony,
+            WebAuthnChallenge,
+            WebAuthnCredential,
+        },
+        WebAuthnModel,
+    },
 };
+use rand::RngCore;
 use serde::{
     Deserialize,
     Serialize,
@@ -82,12 +137,15 @@ use serde_json::{
     json,
     Value as JsonValue,
 };
+use sync_types::Timestamp;
 use value::{
     heap_size::HeapSize,
     id_v6::DeveloperDocumentId,
     ConvexArray,
     ConvexObject,
+    FieldPath,
     TableName,
+    TabletId,
 };
 
 use super::DatabaseUdfEnvironment;
@@ -96,6 +154,7 @@ use crate::{
     environment::{
         action::parse_name_or_reference,
         helpers::{
+            parse_upload_url_options,
             parse_version,
             syscall_error::clone_error_for_batch,
             validation::validate_schedule_args,
@@ -106,6 +165,7 @@ use crate::{
     helpers::UdfArgsJson,
     isolate2::client::QueryId,
     metrics::async_syscall_timer,
+    native_ops::run_native_op,
     FunctionOutcome,
     UdfOutcome,
     ValidatedPathAndArgs,
@@ -140,6 +200,52 @@ pub fn system_table_guard(name: &TableName, expect_system_table: bool) -> anyhow
     Ok(())
 }
 
+/// Length of WebAuthn challenges in bytes. The spec requires at least 16.
+const WEBAUTHN_CHALLENGE_LEN: usize = 32;
+
+/// Which scheduled functions `1.0/listScheduledJobs` and
+/// `1.0/cancelScheduledJobs` apply to.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ScheduledJobFilterArgs {
+    name: Option<String>,
+    reference: Option<String>,
+    function_handle: Option<String>,
+    /// Milliseconds since the epoch, inclusive.
+    scheduled_after: Option<f64>,
+    /// Milliseconds since the epoch, exclusive.
+    scheduled_before: Option<f64>,
+}
+
+/// The relying party a WebAuthn credential is scoped to. Apps pass these to
+/// each `finish*` syscall since they depend on where the app is served.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WebAuthnRelyingPartyArgs {
+    rp_id: String,
+    origins: Vec<String>,
+    #[serde(default)]
+    require_user_verification: bool,
+}
+
+impl From<WebAuthnRelyingPartyArgs> for RelyingParty {
+    fn from(args: WebAuthnRelyingPartyArgs) -> Self {
+        Self {
+            id: args.rp_id,
+            origins: args.origins,
+            require_user_verification: args.require_user_verification,
+        }
+    }
+}
+
+fn encode_base64url(bytes: &[u8]) -> String {
+    base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+}
+
+fn decode_base64url(s: &str) -> anyhow::Result<Vec<u8>> {
+    Ok(base64::decode_config(s, base64::URL_SAFE_NO_PAD)?)
+}
+
 /// A batch of async syscalls that can run "in parallel", where they actually
 /// execute in a batch for determinism, but as far as the js promises are
 /// concerned, they're running in parallel.
@@ -294,7 +400,10 @@ pub trait AsyncSyscallProvider<RT: Runtime> {
         scheduled_ts: UnixTimestamp,
     ) -> anyhow::Result<(CanonicalizedComponentFunctionPath, ConvexArray)>;
 
-    fn file_storage_generate_upload_url(&self) -> anyhow::Result<String>;
+    fn file_storage_generate_upload_url(
+        &self,
+        constraints: StoreFileConstraints,
+    ) -> anyhow::Result<String>;
     async fn file_storage_get_url_batch(
         &mut self,
         storage_ids: BTreeMap<BatchKey, FileStorageId>,
@@ -304,6 +413,11 @@ pub trait AsyncSyscallProvider<RT: Runtime> {
         &mut self,
         storage_id: FileStorageId,
     ) -> anyhow::Result<Option<FileStorageEntry>>;
+    async fn file_storage_read_archived_segment(
+        &mut self,
+        tablet_id: TabletId,
+        storage_key: ObjectKey,
+    ) -> anyhow::Result<Vec<ResolvedDocument>>;
 
     async fn run_udf(
         &mut self,
@@ -408,12 +522,18 @@ impl<RT: Runtime> AsyncSyscallProvider<RT> for DatabaseUdfEnvironment<RT> {
         .await
     }
 
-    fn file_storage_generate_upload_url(&self) -> anyhow::Result<String> {
+    fn file_storage_generate_upload_url(
+        &self,
+        constraints: StoreFileConstraints,
+    ) -> anyhow::Result<String> {
         let issued_ts = self.phase.unix_timestamp()?;
         let component = self.component()?;
-        let post_url =
-            self.file_storage
-                .generate_upload_url(&self.key_broker, issued_ts, component)?;
+        let post_url = self.file_storage.generate_upload_url(
+            &self.key_broker,
+            issued_ts,
+            component,
+            constraints,
+        )?;
         Ok(post_url)
     }
 
@@ -461,6 +581,16 @@ impl<RT: Runtime> AsyncSyscallProvider<RT> for DatabaseUdfEnvironment<RT> {
             .await
     }
 
+    async fn file_storage_read_archived_segment(
+        &mut self,
+        tablet_id: TabletId,
+        storage_key: ObjectKey,
+    ) -> anyhow::Result<Vec<ResolvedDocument>> {
+        self.file_storage
+            .read_archived_segment(tablet_id, &storage_key)
+            .await
+    }
+
     #[minitrace::trace]
     async fn run_udf(
         &mut self,
@@ -657,6 +787,17 @@ impl<RT: Runtime, P: AsyncSyscallProvider<RT>> DatabaseSyscallsV1<RT, P> {
                     "1.0/replace" => Box::pin(Self::replace(provider, args)).await,
                     "1.0/remove" => Box::pin(Self::remove(provider, args)).await,
                     "1.0/queryPage" => Box::pin(Self::query_page(provider, args)).await,
+                    "1.0/searchFacets" => Box::pin(Self::search_facets(provider, args)).await,
+                    "1.0/aggregate" => Box::pin(Self::aggregate(provider, args)).await,
+                    "1.0/getPage" => Box::pin(Self::get_page(provider, args)).await,
+                    "1.0/hydrate" => Box::pin(Self::hydrate(provider, args)).await,
+                    "1.0/materializedAggregate/get" => {
+                        Box::pin(Self::materialized_aggregate_get(provider, args)).await
+                    },
+                    "1.0/materializedAggregate/list" => {
+                        Box::pin(Self::materialized_aggregate_list(provider, args)).await
+                    },
+                    "1.0/getArchived" => Box::pin(Self::get_archived(provider, args)).await,
                     // Auth
                     "1.0/getUserIdentity" => {
                         Box::pin(Self::get_user_identity(provider, args)).await
@@ -672,6 +813,12 @@ impl<RT: Runtime, P: AsyncSyscallProvider<RT>> DatabaseSyscallsV1<RT, P> {
                     // Scheduling
                     "1.0/schedule" => Box::pin(Self::schedule(provider, args)).await,
                     "1.0/cancel_job" => Box::pin(Self::cancel_job(provider, args)).await,
+                    "1.0/listScheduledJobs" => {
+                        Box::pin(Self::list_scheduled_jobs(provider, args)).await
+                    },
+                    "1.0/cancelScheduledJobs" => {
+                        Box::pin(Self::cancel_scheduled_jobs(provider, args)).await
+                    },
 
                     // Components
                     "1.0/runUdf" => Box::pin(Self::run_udf(provider, args)).await,
@@ -679,6 +826,32 @@ impl<RT: Runtime, P: AsyncSyscallProvider<RT>> DatabaseSyscallsV1<RT, P> {
                         Box::pin(Self::create_function_handle(provider, args)).await
                     },
 
+                    // WebAuthn
+                    "1.0/webauthn/startRegistration" => {
+                        Box::pin(Self::webauthn_start_registration(provider, args)).await
+                    },
+                    "1.0/webauthn/finishRegistration" => {
+                        Box::pin(Self::webauthn_finish_registration(provider, args)).await
+                    },
+                    "1.0/webauthn/startAuthentication" => {
+                        Box::pin(Self::webauthn_start_authentication(provider, args)).await
+                    },
+                    "1.0/webauthn/finishAuthentication" => {
+                        Box::pin(Self::webauthn_finish_authentication(provider, args)).await
+                    },
+
+                    // Rate limiting
+                    "1.0/rateLimiter/limit" => Box::pin(Self::rate_limit(provider, args)).await,
+                    "1.0/rateLimiter/reset" => {
+                        Box::pin(Self::reset_rate_limit(provider, args)).await
+                    },
+
+                    // Queues
+                    "1.0/queue/enqueue" => Box::pin(Self::enqueue(provider, args)).await,
+
+                    // Custom native ops
+                    "1.0/native" => Box::pin(run_native_op(args, false)).await,
+
                     #[cfg(test)]
                     "slowSyscall" => {
                         std::thread::sleep(std::time::Duration::from_secs(1));
@@ -732,11 +905,410 @@ impl<RT: Runtime, P: AsyncSyscallProvider<RT>> DatabaseSyscallsV1<RT, P> {
         Ok(ConvexValue::from(result).into())
     }
 
+    #[convex_macro::instrument_future]
+    async fn search_facets(provider: &mut P, args: JsonValue) -> anyhow::Result<JsonValue> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct SearchFacetsArgs {
+            query: JsonValue,
+            facets: Vec<String>,
+            #[serde(default)]
+            version: Option<String>,
+        }
+        let (query, fields, version) = with_argument_error("facets", || {
+            let args: SearchFacetsArgs = serde_json::from_value(args)?;
+            let query = Query::try_from(args.query).context(ArgName("query"))?;
+            let fields = args
+                .facets
+                .iter()
+                .map(|field| field.parse::<FieldPath>())
+                .collect::<anyhow::Result<Vec<_>>>()
+                .context(ArgName("facets"))?;
+            Ok((query, fields, args.version))
+        })?;
+        let version = parse_version(version)?;
+        let table_filter = provider.table_filter();
+        let component = provider.component()?;
+        let tx = provider.tx()?;
+        let facets =
+            search_facets(tx, component.into(), query, fields, version, table_filter).await?;
+
+        let mut result = serde_json::Map::new();
+        for (field, counts) in facets {
+            let counts = counts
+                .into_iter()
+                .map(|SearchFacetCount { value, count }| {
+                    let mut entry = serde_json::Map::new();
+                    // Results missing the field are counted without a value,
+                    // which becomes `undefined` in JavaScript.
+                    if let Some(value) = value {
+                        entry.insert("value".to_string(), value.into());
+                    }
+                    entry.insert("count".to_string(), json!(count));
+                    JsonValue::Object(entry)
+                })
+                .collect();
+            result.insert(field.to_string(), JsonValue::Array(counts));
+        }
+        Ok(JsonValue::Object(result))
+    }
+
+    #[convex_macro::instrument_future]
+    async fn aggregate(provider: &mut P, args: JsonValue) -> anyhow::Result<JsonValue> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct AggregateJson {
+            op: String,
+            field: Option<String>,
+        }
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct AggregateArgs {
+            query: JsonValue,
+            group_by: Option<String>,
+            aggregates: BTreeMap<String, AggregateJson>,
+            #[serde(default)]
+            version: Option<String>,
+        }
+        let (query, group_by, aggregates, version) = with_argument_error("aggregate", || {
+            let args: AggregateArgs = serde_json::from_value(args)?;
+            let query = Query::try_from(args.query).context(ArgName("query"))?;
+            let group_by = args
+                .group_by
+                .map(|field| field.parse::<FieldPath>())
+                .transpose()
+                .context(ArgName("groupBy"))?;
+            let aggregates = args
+                .aggregates
+                .into_iter()
+                .map(|(name, AggregateJson { op, field })| {
+                    let aggregate = Aggregate {
+                        function: op.parse()?,
+                        field: field.map(|field| field.parse()).transpose()?,
+                    };
+                    anyhow::Ok((name, aggregate))
+                })
+                .collect::<anyhow::Result<BTreeMap<_, _>>>()
+                .context(ArgName("aggregates"))?;
+            Ok((query, group_by, aggregates, args.version))
+        })?;
+        let version = parse_version(version)?;
+        let table_filter = provider.table_filter();
+        let component = provider.component()?;
+        let tx = provider.tx()?;
+        let groups = aggregate(
+            tx,
+            component.into(),
+            query,
+            group_by,
+            aggregates,
+            version,
+            table_filter,
+        )
+        .await?;
+
+        let groups = groups
+            .into_iter()
+            .map(|AggregateGroup { key, values }| {
+                let mut group = serde_json::Map::new();
+                // Documents missing the group-by field are grouped without a
+                // key, which becomes `undefined` in JavaScript.
+                if let Some(key) = key {
+                    group.insert("group".to_string(), key.into());
+                }
+                let values = values
+                    .into_iter()
+                    .map(|(name, value)| (name, JsonValue::from(value)))
+                    .collect();
+                group.insert("values".to_string(), JsonValue::Object(values));
+                JsonValue::Object(group)
+            })
+            .collect();
+        Ok(JsonValue::Array(groups))
+    }
+
+    /// Reads a page of an index between two index keys. Index keys are
+    /// arrays of the indexed fields' values ending with `_id`, where missing
+    /// fields are `{ "$undefined": null }`.
+    #[convex_macro::instrument_future]
+    async fn get_page(provider: &mut P, args: JsonValue) -> anyhow::Result<JsonValue> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct IndexKeyBoundJson {
+            key: Vec<JsonValue>,
+            inclusive: bool,
+        }
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct GetPageArgs {
+            table: String,
+            index: String,
+            start: Option<IndexKeyBoundJson>,
+            end: Option<IndexKeyBoundJson>,
+            order: String,
+            max_rows: usize,
+            #[serde(default)]
+            version: Option<String>,
+        }
+        let parse_bound = |bound: IndexKeyBoundJson| {
+            let key = bound
+                .key
+                .into_iter()
+                .map(|value| anyhow::Ok(MaybeValue::try_from(value)?.0))
+                .collect::<anyhow::Result<_>>()?;
+            anyhow::Ok(IndexKeyBound {
+                key,
+                inclusive: bound.inclusive,
+            })
+        };
+        let (request, version) = with_argument_error("getPage", || {
+            let args: GetPageArgs = serde_json::from_value(args)?;
+            let index_name: IndexName = format!("{}.{}", args.table, args.index)
+                .parse()
+                .context(ArgName("index"))?;
+            let start = args
+                .start
+                .map(parse_bound)
+                .transpose()
+                .context(ArgName("start"))?;
+            let end = args
+                .end
+                .map(parse_bound)
+                .transpose()
+                .context(ArgName("end"))?;
+            let order = match &args.order[..] {
+                "asc" => Order::Asc,
+                "desc" => Order::Desc,
+                _ => Err(anyhow::anyhow!("Invalid order {:?}", args.order))
+                    .context(ArgName("order"))?,
+            };
+            let request = GetPageRequest {
+                index_name,
+                start,
+                end,
+                order,
+                max_rows: args.max_rows,
+            };
+            Ok((request, args.version))
+        })?;
+        let version = parse_version(version)?;
+        let table_filter = provider.table_filter();
+        let component = provider.component()?;
+        let tx = provider.tx()?;
+        let GetPage {
+            page,
+            index_keys,
+            has_more,
+        } = get_page(tx, component.into(), request, version, table_filter).await?;
+        let page: Vec<JsonValue> = page
+            .into_iter()
+            .map(|doc| ConvexValue::from(doc.into_value().0).into())
+            .collect();
+        let index_keys: Vec<JsonValue> = index_keys
+            .into_iter()
+            .map(|key| {
+                JsonValue::Array(
+                    key.into_iter()
+                        .map(|value| JsonValue::from(MaybeValue(value)))
+                        .collect(),
+                )
+            })
+            .collect();
+        Ok(json!({
+            "page": page,
+            "indexKeys": index_keys,
+            "hasMore": has_more,
+        }))
+    }
+
+    /// Replaces the references declared with `v.id(...)` in the schema in
+    /// `documents` with the documents they reference, up to `depth` levels
+    /// deep.
+    #[convex_macro::instrument_future]
+    async fn hydrate(provider: &mut P, args: JsonValue) -> anyhow::Result<JsonValue> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct HydrateArgs {
+            documents: Vec<JsonValue>,
+            depth: usize,
+            #[serde(default)]
+            version: Option<String>,
+        }
+        let (documents, depth, version) = with_argument_error("hydrate", || {
+            let args: HydrateArgs = serde_json::from_value(args)?;
+            let documents = args
+                .documents
+                .into_iter()
+                .map(ConvexValue::try_from)
+                .collect::<anyhow::Result<Vec<_>>>()
+                .context(ArgName("documents"))?;
+            Ok((documents, args.depth, args.version))
+        })?;
+        let version = parse_version(version)?;
+        let table_filter = provider.table_filter();
+        let component = provider.component()?;
+        let tx = provider.tx()?;
+        let documents = hydrate_references(
+            tx,
+            component.into(),
+            documents,
+            depth,
+            version,
+            table_filter,
+        )
+        .await?;
+        Ok(JsonValue::Array(
+            documents.into_iter().map(JsonValue::from).collect(),
+        ))
+    }
+
+    /// Reads the count and sum of the documents with one key in a
+    /// materialized aggregate, which is zero for keys no documents have.
+    #[convex_macro::instrument_future]
+    async fn materialized_aggregate_get(
+        provider: &mut P,
+        args: JsonValue,
+    ) -> anyhow::Result<JsonValue> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct MaterializedAggregateGetArgs {
+            table: String,
+            name: String,
+            key: JsonValue,
+        }
+        let (table, name, key) = with_argument_error("materializedAggregate.get", || {
+            let args: MaterializedAggregateGetArgs = serde_json::from_value(args)?;
+            let table: TableName = args.table.parse().context(ArgName("table"))?;
+            let key = ConvexValue::try_from(args.key).context(ArgName("key"))?;
+            Ok((table, args.name, key))
+        })?;
+        let component = provider.component()?;
+        let tx = provider.tx()?;
+        let mut model = MaterializedAggregateModel::new(tx);
+        let aggregate = model.get_ready(component.into(), &table, &name).await?;
+        let (count, sum) = match model.entry(aggregate.id(), key).await? {
+            Some(entry) => (entry.count, entry.sum),
+            None => (0, 0.0),
+        };
+        Ok(json!({ "count": count, "sum": sum }))
+    }
+
+    /// Lists a materialized aggregate's keys in order of their key, count or
+    /// sum, reading only as many entries as `limit`.
+    #[convex_macro::instrument_future]
+    async fn materialized_aggregate_list(
+        provider: &mut P,
+        args: JsonValue,
+    ) -> anyhow::Result<JsonValue> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct MaterializedAggregateListArgs {
+            table: String,
+            name: String,
+            order_by: String,
+            order: String,
+            limit: usize,
+        }
+        let (table, name, order_by, order, limit) =
+            with_argument_error("materializedAggregate.list", || {
+                let args: MaterializedAggregateListArgs = serde_json::from_value(args)?;
+                let table: TableName = args.table.parse().context(ArgName("table"))?;
+                let order_by = match &args.order_by[..] {
+                    "key" => MaterializedAggregateOrderBy::Key,
+                    "count" => MaterializedAggregateOrderBy::Count,
+                    "sum" => MaterializedAggregateOrderBy::Sum,
+                    _ => anyhow::bail!(invalid_materialized_aggregate(format!(
+                        "Invalid orderBy {:?}. Use \"key\", \"count\" or \"sum\".",
+                        args.order_by
+                    ))),
+                };
+                let order = match &args.order[..] {
+                    "asc" => Order::Asc,
+                    "desc" => Order::Desc,
+                    _ => anyhow::bail!(invalid_materialized_aggregate(format!(
+                        "Invalid order {:?}. Use \"asc\" or \"desc\".",
+                        args.order
+                    ))),
+                };
+                anyhow::ensure!(
+                    args.limit > 0 && args.limit <= MAX_MATERIALIZED_AGGREGATE_LIST_LIMIT,
+                    invalid_materialized_aggregate(format!(
+                        "limit must be between 1 and {MAX_MATERIALIZED_AGGREGATE_LIST_LIMIT}"
+                    ))
+                );
+                Ok((table, args.name, order_by, order, args.limit))
+            })?;
+        let component = provider.component()?;
+        let tx = provider.tx()?;
+        let mut model = MaterializedAggregateModel::new(tx);
+        let aggregate = model.get_ready(component.into(), &table, &name).await?;
+        let entries = model
+            .entries(aggregate.id(), order_by, order, limit)
+            .await?
+            .into_iter()
+            .map(|entry| {
+                json!({
+                    "key": JsonValue::from(entry.key),
+                    "count": entry.count,
+                    "sum": entry.sum,
+                })
+            })
+            .collect();
+        Ok(JsonValue::Array(entries))
+    }
+
+    /// Reads a document that was moved out of its table by an archival policy.
+    /// This fetches the document's whole segment from file storage, so it's
+    /// much slower than `db.get` and only happens when a function opts in.
+    #[convex_macro::instrument_future]
+    async fn get_archived(provider: &mut P, args: JsonValue) -> anyhow::Result<JsonValue> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct GetArchivedArgs {
+            id: String,
+        }
+        let id = with_argument_error("db.get", || {
+            let args: GetArchivedArgs = serde_json::from_value(args)?;
+            DeveloperDocumentId::decode(&args.id).context(ArgName("id"))
+        })?;
+        let component = provider.component()?;
+        let table_filter = provider.table_filter();
+        let tx = provider.tx()?;
+        let Ok(table_name) =
+            tx.all_tables_number_to_name(component.into(), table_filter)(id.table())
+        else {
+            return Ok(JsonValue::Null);
+        };
+        system_table_guard(&table_name, false)?;
+        let Some(tablet_id) = tx
+            .table_mapping()
+            .namespace(component.into())
+            .id_if_exists(&table_name)
+        else {
+            return Ok(JsonValue::Null);
+        };
+        let Some(storage_key) = ArchivalModel::new(tx)
+            .segment_for_document(tablet_id, id.internal_id())
+            .await?
+        else {
+            return Ok(JsonValue::Null);
+        };
+        let document = provider
+            .file_storage_read_archived_segment(tablet_id, storage_key)
+            .await?
+            .into_iter()
+            .find(|document| document.developer_id() == id);
+        let value = match document {
+            Some(document) => document.into_value().0.into(),
+            None => ConvexValue::Null,
+        };
+        Ok(value.into())
+    }
+
     #[convex_macro::instrument_future]
     async fn get_user_identity(provider: &mut P, _args: JsonValue) -> anyhow::Result<JsonValue> {
-        // TODO: Somehow make the Transaction aware of the dependency on the user.
         let tx = provider.tx()?;
-        let user_identity = tx.user_identity();
+        let user_identity = tx.observe_user_identity();
         if let Some(user_identity) = user_identity {
             return user_identity.try_into();
         }
@@ -747,9 +1319,10 @@ impl<RT: Runtime, P: AsyncSyscallProvider<RT>> DatabaseSyscallsV1<RT, P> {
     #[convex_macro::instrument_future]
     async fn storage_generate_upload_url(
         provider: &mut P,
-        _args: JsonValue,
+        args: JsonValue,
     ) -> anyhow::Result<JsonValue> {
-        let post_url = provider.file_storage_generate_upload_url()?;
+        let constraints = parse_upload_url_options(args)?;
+        let post_url = provider.file_storage_generate_upload_url(constraints)?;
         Ok(serde_json::to_value(post_url)?)
     }
 
@@ -827,6 +1400,8 @@ impl<RT: Runtime, P: AsyncSyscallProvider<RT>> DatabaseSyscallsV1<RT, P> {
             sha256: String,
             size: i64,
             content_type: Option<String>,
+            filename: Option<String>,
+            tags: BTreeMap<String, String>,
         }
         let file_metadata = provider.file_storage_get_entry(storage_id).await?.map(
             |FileStorageEntry {
@@ -835,6 +1410,9 @@ impl<RT: Runtime, P: AsyncSyscallProvider<RT>> DatabaseSyscallsV1<RT, P> {
                  sha256,
                  size,
                  content_type,
+                 filename,
+                 tags,
+                 scan_status: _,
              }| {
                 FileMetadataJson {
                     storage_id: storage_id.to_string(),
@@ -842,6 +1420,8 @@ impl<RT: Runtime, P: AsyncSyscallProvider<RT>> DatabaseSyscallsV1<RT, P> {
                     sha256: sha256.as_hex(),
                     size,
                     content_type,
+                    filename,
+                    tags,
                 }
             },
         );
@@ -858,6 +1438,8 @@ impl<RT: Runtime, P: AsyncSyscallProvider<RT>> DatabaseSyscallsV1<RT, P> {
             function_handle: Option<String>,
             ts: f64,
             args: UdfArgsJson,
+            idempotency_key: Option<String>,
+            on_conflict: Option<String>,
         }
 
         let ScheduleArgs {
@@ -866,8 +1448,71 @@ impl<RT: Runtime, P: AsyncSyscallProvider<RT>> DatabaseSyscallsV1<RT, P> {
             function_handle,
             ts,
             args,
+            idempotency_key,
+            on_conflict,
         }: ScheduleArgs = with_argument_error("scheduler", || Ok(serde_json::from_value(args)?))?;
+        let idempotency_key = idempotency_key
+            .map(|key| IdempotencyKey::new(key, on_conflict.as_deref()))
+            .transpose()?;
+
+        let path =
+            Self::resolve_scheduled_function(provider, name, reference, function_handle).await?;
+
+        let scheduling_component = provider.component()?;
 
+        let scheduled_ts = UnixTimestamp::from_secs_f64(ts);
+        let (path, udf_args) = provider
+            .validate_schedule_args(path, args.into_arg_vec(), scheduled_ts)
+            .await?;
+
+        let context = provider.context().clone();
+        let tx = provider.tx()?;
+        let mut model = VirtualSchedulerModel::new(tx, scheduling_component.into());
+        let virtual_id = match idempotency_key {
+            Some(idempotency_key) => {
+                model
+                    .schedule_idempotent(path, udf_args, scheduled_ts, context, idempotency_key)
+                    .await?
+            },
+            None => {
+                model
+                    .schedule(path, udf_args, scheduled_ts, context)
+                    .await?
+            },
+        };
+
+        Ok(JsonValue::from(virtual_id))
+    }
+
+    #[convex_macro::instrument_future]
+    async fn cancel_job(provider: &mut P, args: JsonValue) -> anyhow::Result<JsonValue> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct CancelJobArgs {
+            id: String,
+        }
+        let component = provider.component()?;
+        let tx = provider.tx()?;
+
+        let virtual_id_v6 = with_argument_error("db.cancel_job", || {
+            let args: CancelJobArgs = serde_json::from_value(args)?;
+            let id = DeveloperDocumentId::decode(&args.id).context(ArgName("id"))?;
+            Ok(id)
+        })?;
+
+        VirtualSchedulerModel::new(tx, component.into())
+            .cancel(virtual_id_v6)
+            .await?;
+
+        Ok(JsonValue::Null)
+    }
+
+    async fn resolve_scheduled_function(
+        provider: &mut P,
+        name: Option<String>,
+        reference: Option<String>,
+        function_handle: Option<String>,
+    ) -> anyhow::Result<CanonicalizedComponentFunctionPath> {
         let path = match function_handle {
             Some(h) => {
                 let handle: FunctionHandle = with_argument_error("scheduler", || h.parse())?;
@@ -892,46 +1537,410 @@ impl<RT: Runtime, P: AsyncSyscallProvider<RT>> DatabaseSyscallsV1<RT, P> {
                 }
             },
         };
+        Ok(path)
+    }
 
-        let scheduling_component = provider.component()?;
+    /// Builds the filter for the bulk scheduler syscalls, which only filter
+    /// by function if one of `name`, `reference` or `functionHandle` is set.
+    async fn scheduled_job_filter(
+        provider: &mut P,
+        args: ScheduledJobFilterArgs,
+    ) -> anyhow::Result<ScheduledJobFilter> {
+        let ScheduledJobFilterArgs {
+            name,
+            reference,
+            function_handle,
+            scheduled_after,
+            scheduled_before,
+        } = args;
+        let path = if name.is_some() || reference.is_some() || function_handle.is_some() {
+            Some(
+                Self::resolve_scheduled_function(provider, name, reference, function_handle)
+                    .await?,
+            )
+        } else {
+            None
+        };
+        let ms_to_ts = |ms: f64, arg_name: &'static str| {
+            with_argument_error("scheduler", || {
+                let ts: Timestamp = UnixTimestamp::from_millis(ms as u64)
+                    .as_system_time()
+                    .try_into()
+                    .context(ArgName(arg_name))?;
+                Ok(ts)
+            })
+        };
+        Ok(ScheduledJobFilter {
+            path,
+            start_ts: scheduled_after
+                .map(|ms| ms_to_ts(ms, "scheduledAfter"))
+                .transpose()?,
+            end_ts: scheduled_before
+                .map(|ms| ms_to_ts(ms, "scheduledBefore"))
+                .transpose()?,
+        })
+    }
 
-        let scheduled_ts = UnixTimestamp::from_secs_f64(ts);
-        let (path, udf_args) = provider
-            .validate_schedule_args(path, args.into_arg_vec(), scheduled_ts)
+    /// Lists a page of the component's scheduled functions that haven't
+    /// finished, in the order they next run.
+    #[convex_macro::instrument_future]
+    async fn list_scheduled_jobs(provider: &mut P, args: JsonValue) -> anyhow::Result<JsonValue> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct ListScheduledJobsArgs {
+            #[serde(flatten)]
+            filter: ScheduledJobFilterArgs,
+            cursor: Option<String>,
+            num_items: Option<usize>,
+        }
+        let ListScheduledJobsArgs {
+            filter,
+            cursor,
+            num_items,
+        }: ListScheduledJobsArgs =
+            with_argument_error("scheduler", || Ok(serde_json::from_value(args)?))?;
+        let cursor = cursor
+            .as_deref()
+            .map(ScheduledJobsCursor::decode)
+            .transpose()?;
+        let num_items = num_items
+            .unwrap_or(*MAX_JOBS_CANCEL_BATCH)
+            .clamp(1, *MAX_JOBS_CANCEL_BATCH);
+        let filter = Self::scheduled_job_filter(provider, filter).await?;
+
+        let component = provider.component()?;
+        let tx = provider.tx()?;
+        let (page, cursor) = VirtualSchedulerModel::new(tx, component.into())
+            .list_matching(filter, cursor, num_items)
             .await?;
 
-        let context = provider.context().clone();
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct ListScheduledJobsResult {
+            page: Vec<JsonValue>,
+            is_done: bool,
+            continue_cursor: Option<String>,
+        }
+        let result = ListScheduledJobsResult {
+            page: page
+                .into_iter()
+                .map(|doc| ConvexValue::from(doc.into_value().0).into())
+                .collect(),
+            is_done: cursor.is_none(),
+            continue_cursor: cursor.map(|cursor| cursor.encode()),
+        };
+        Ok(serde_json::to_value(result)?)
+    }
+
+    /// Cancels up to a batch of the component's scheduled functions that
+    /// haven't finished. Callers keep calling until `isDone`.
+    #[convex_macro::instrument_future]
+    async fn cancel_scheduled_jobs(provider: &mut P, args: JsonValue) -> anyhow::Result<JsonValue> {
+        let filter: ScheduledJobFilterArgs =
+            with_argument_error("scheduler", || Ok(serde_json::from_value(args)?))?;
+        let filter = Self::scheduled_job_filter(provider, filter).await?;
+
+        let component = provider.component()?;
         let tx = provider.tx()?;
-        let virtual_id = VirtualSchedulerModel::new(tx, scheduling_component.into())
-            .schedule(path, udf_args, scheduled_ts, context)
+        let canceled = SchedulerModel::new(tx, component.into())
+            .cancel_matching(filter, *MAX_JOBS_CANCEL_BATCH)
             .await?;
 
-        Ok(JsonValue::from(virtual_id))
+        #[derive(Serialize)]
+        #[serde(rename_all = "camelCase")]
+        struct CancelScheduledJobsResult {
+            canceled: usize,
+            is_done: bool,
+        }
+        let result = CancelScheduledJobsResult {
+            canceled,
+            is_done: canceled < *MAX_JOBS_CANCEL_BATCH,
+        };
+        Ok(serde_json::to_value(result)?)
     }
 
+    /// Issues a challenge for registering a passkey to `userId`.
     #[convex_macro::instrument_future]
-    async fn cancel_job(provider: &mut P, args: JsonValue) -> anyhow::Result<JsonValue> {
+    async fn webauthn_start_registration(
+        provider: &mut P,
+        args: JsonValue,
+    ) -> anyhow::Result<JsonValue> {
         #[derive(Deserialize)]
         #[serde(rename_all = "camelCase")]
-        struct CancelJobArgs {
-            id: String,
+        struct StartRegistrationArgs {
+            user_id: String,
         }
-        let component = provider.component()?;
+        let user_id = with_argument_error("webauthn.startRegistration", || {
+            let args: StartRegistrationArgs = serde_json::from_value(args)?;
+            Ok(args.user_id)
+        })?;
+        let challenge =
+            Self::webauthn_start_ceremony(provider, WebAuthnCeremony::Registration, Some(user_id))
+                .await?;
+        Ok(json!({ "challenge": challenge }))
+    }
+
+    /// Verifies a new passkey and stores it for the user its challenge was
+    /// issued to.
+    #[convex_macro::instrument_future]
+    async fn webauthn_finish_registration(
+        provider: &mut P,
+        args: JsonValue,
+    ) -> anyhow::Result<JsonValue> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct FinishRegistrationArgs {
+            #[serde(flatten)]
+            relying_party: WebAuthnRelyingPartyArgs,
+            #[serde(rename = "clientDataJSON")]
+            client_data_json: String,
+            attestation_object: String,
+        }
+        let (relying_party, client_data_json, attestation_object) =
+            with_argument_error("webauthn.finishRegistration", || {
+                let args: FinishRegistrationArgs = serde_json::from_value(args)?;
+                Ok((
+                    RelyingParty::from(args.relying_party),
+                    decode_base64url(&args.client_data_json).context(ArgName("clientDataJSON"))?,
+                    decode_base64url(&args.attestation_object)
+                        .context(ArgName("attestationObject"))?,
+                ))
+            })?;
+        let challenge = webauthn::client_data_challenge(&client_data_json)?;
+        let now = provider.unix_timestamp()?;
         let tx = provider.tx()?;
+        let challenge = WebAuthnModel::new(tx)
+            .take_challenge(&challenge, WebAuthnCeremony::Registration, now)
+            .await?;
+        let user_id = challenge
+            .user_id
+            .context("Registration challenge is missing its user")?;
+        let registered = webauthn::verify_registration(
+            &relying_party,
+            &challenge.challenge,
+            &client_data_json,
+            &attestation_object,
+        )?;
+        let credential_id = encode_base64url(&registered.credential_id);
+        WebAuthnModel::new(tx)
+            .insert_credential(WebAuthnCredential {
+                credential_id: registered.credential_id,
+                user_id: user_id.clone(),
+                public_key: registered.public_key,
+                sign_count: registered.sign_count,
+                created_at: now,
+                last_used_at: None,
+            })
+            .await?;
+        Ok(json!({ "userId": user_id, "credentialId": credential_id }))
+    }
 
-        let virtual_id_v6 = with_argument_error("db.cancel_job", || {
-            let args: CancelJobArgs = serde_json::from_value(args)?;
-            let id = DeveloperDocumentId::decode(&args.id).context(ArgName("id"))?;
-            Ok(id)
+    /// Issues a challenge for logging in with a passkey. If `userId` is
+    /// given, also returns the IDs of that user's passkeys so the browser can
+    /// offer just those.
+    #[convex_macro::instrument_future]
+    async fn webauthn_start_authentication(
+        provider: &mut P,
+        args: JsonValue,
+    ) -> anyhow::Result<JsonValue> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct StartAuthenticationArgs {
+            user_id: Option<String>,
+        }
+        let user_id = with_argument_error("webauthn.startAuthentication", || {
+            let args: StartAuthenticationArgs = serde_json::from_value(args)?;
+            Ok(args.user_id)
         })?;
+        let allow_credentials = match &user_id {
+            Some(user_id) => WebAuthnModel::new(provider.tx()?)
+                .credentials_for_user(user_id)
+                .await?
+                .into_iter()
+                .map(|credential| encode_base64url(&credential.credential_id))
+                .collect(),
+            None => vec![],
+        };
+        let challenge =
+            Self::webauthn_start_ceremony(provider, WebAuthnCeremony::Authentication, user_id)
+                .await?;
+        Ok(json!({ "challenge": challenge, "allowCredentials": allow_credentials }))
+    }
 
-        VirtualSchedulerModel::new(tx, component.into())
-            .cancel(virtual_id_v6)
+    /// Verifies a passkey login, returning the user the passkey belongs to.
+    #[convex_macro::instrument_future]
+    async fn webauthn_finish_authentication(
+        provider: &mut P,
+        args: JsonValue,
+    ) -> anyhow::Result<JsonValue> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct FinishAuthenticationArgs {
+            #[serde(flatten)]
+            relying_party: WebAuthnRelyingPartyArgs,
+            credential_id: String,
+            #[serde(rename = "clientDataJSON")]
+            client_data_json: String,
+            authenticator_data: String,
+            signature: String,
+        }
+        let (relying_party, credential_id, client_data_json, authenticator_data, signature) =
+            with_argument_error("webauthn.finishAuthentication", || {
+                let args: FinishAuthenticationArgs = serde_json::from_value(args)?;
+                Ok((
+                    RelyingParty::from(args.relying_party),
+                    decode_base64url(&args.credential_id).context(ArgName("credentialId"))?,
+                    decode_base64url(&args.client_data_json).context(ArgName("clientDataJSON"))?,
+                    decode_base64url(&args.authenticator_data)
+                        .context(ArgName("authenticatorData"))?,
+                    decode_base64url(&args.signature).context(ArgName("signature"))?,
+                ))
+            })?;
+        let challenge = webauthn::client_data_challenge(&client_data_json)?;
+        let now = provider.unix_timestamp()?;
+        let tx = provider.tx()?;
+        let challenge = WebAuthnModel::new(tx)
+            .take_challenge(&challenge, WebAuthnCeremony::Authentication, now)
+            .await?;
+        let Some(credential) = WebAuthnModel::new(tx).credential(&credential_id).await? else {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "WebAuthnCredentialNotFound",
+                "This passkey isn't registered",
+            ));
+        };
+        if let Some(user_id) = &challenge.user_id
+            && *user_id != credential.user_id
+        {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "WebAuthnCredentialNotFound",
+                "This passkey isn't registered to the user logging in",
+            ));
+        }
+        let sign_count = webauthn::verify_assertion(
+            &relying_party,
+            &challenge.challenge,
+            &credential.public_key,
+            credential.sign_count,
+            &client_data_json,
+            &authenticator_data,
+            &signature,
+        )?;
+        let user_id = credential.user_id.clone();
+        WebAuthnModel::new(tx)
+            .record_assertion(credential, sign_count, now)
             .await?;
+        Ok(json!({
+            "userId": user_id,
+            "credentialId": encode_base64url(&credential_id),
+        }))
+    }
 
+    async fn webauthn_start_ceremony(
+        provider: &mut P,
+        ceremony: WebAuthnCeremony,
+        user_id: Option<String>,
+    ) -> anyhow::Result<String> {
+        let mut challenge = vec![0; WEBAUTHN_CHALLENGE_LEN];
+        provider.rt().rng().fill_bytes(&mut challenge);
+        let now = provider.unix_timestamp()?;
+        let encoded = encode_base64url(&challenge);
+        WebAuthnModel::new(provider.tx()?)
+            .start_ceremony(
+                WebAuthnChallenge {
+                    challenge,
+                    ceremony,
+                    user_id,
+                    expires_at: now + *WEBAUTHN_CHALLENGE_TTL,
+                },
+                now,
+            )
+            .await?;
+        Ok(encoded)
+    }
+
+    /// Takes requests from a key's allowance under a rate limit, returning
+    /// whether they're allowed and if not, how many milliseconds until they
+    /// would be.
+    #[convex_macro::instrument_future]
+    async fn rate_limit(provider: &mut P, args: JsonValue) -> anyhow::Result<JsonValue> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct RateLimitArgs {
+            name: String,
+            #[serde(default)]
+            key: String,
+            kind: String,
+            rate: u64,
+            period: u64,
+            capacity: Option<u64>,
+            shards: Option<u32>,
+            count: Option<u64>,
+        }
+        let (name, key, limit, count) = with_argument_error("rateLimiter.limit", || {
+            let args: RateLimitArgs = serde_json::from_value(args)?;
+            let limit = RateLimit::new(
+                args.kind.parse().context(ArgName("kind"))?,
+                args.rate,
+                Duration::from_millis(args.period),
+                args.capacity,
+                args.shards,
+            )?;
+            Ok((args.name, args.key, limit, args.count.unwrap_or(1)))
+        })?;
+        let now = provider.unix_timestamp()?;
+        let tx = provider.tx()?;
+        let result = RateLimiterModel::new(tx)
+            .limit(&name, &key, &limit, count, now)
+            .await?;
+        Ok(match result {
+            Ok(()) => json!({ "ok": true, "retryAfter": null }),
+            Err(retry_after) => json!({
+                "ok": false,
+                "retryAfter": retry_after.as_millis() as u64,
+            }),
+        })
+    }
+
+    #[convex_macro::instrument_future]
+    async fn reset_rate_limit(provider: &mut P, args: JsonValue) -> anyhow::Result<JsonValue> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct ResetRateLimitArgs {
+            name: String,
+            #[serde(default)]
+            key: String,
+        }
+        let (name, key) = with_argument_error("rateLimiter.reset", || {
+            let args: ResetRateLimitArgs = serde_json::from_value(args)?;
+            Ok((args.name, args.key))
+        })?;
+        let tx = provider.tx()?;
+        RateLimiterModel::new(tx).reset(&name, &key).await?;
         Ok(JsonValue::Null)
     }
 
+    #[convex_macro::instrument_future]
+    async fn enqueue(provider: &mut P, args: JsonValue) -> anyhow::Result<JsonValue> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct EnqueueArgs {
+            queue: String,
+            #[serde(default)]
+            partition_key: String,
+            body: JsonValue,
+        }
+        let EnqueueArgs {
+            queue,
+            partition_key,
+            body,
+        } = with_argument_error("queue.enqueue", || Ok(serde_json::from_value(args)?))?;
+        let tx = provider.tx()?;
+        let sequence = QueuesModel::new(tx)
+            .enqueue(&queue, partition_key, body)
+            .await?;
+        Ok(json!({ "sequence": sequence }))
+    }
+
     #[minitrace::trace]
     #[convex_macro::instrument_future]
     async fn insert(provider: &mut P, args: JsonValue) -> anyhow::Result<JsonValue> {
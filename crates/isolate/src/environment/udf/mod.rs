@@ -33,6 +33,7 @@ use std::{
         Arc,
         LazyLock,
     },
+    time::Duration,
 };
 
 use anyhow::anyhow;
@@ -44,6 +45,7 @@ use common::{
         DATABASE_UDF_USER_TIMEOUT,
         FUNCTION_MAX_ARGS_SIZE,
         FUNCTION_MAX_RESULT_SIZE,
+        ISOLATE_MAX_USER_HEAP_SIZE,
         TRANSACTION_MAX_NUM_SCHEDULED,
         TRANSACTION_MAX_NUM_USER_WRITES,
         TRANSACTION_MAX_READ_SET_INTERVALS,
@@ -181,6 +183,8 @@ pub struct DatabaseUdfEnvironment<RT: Runtime> {
     arguments: ConvexArray,
     identity: InertIdentity,
     udf_server_version: Option<semver::Version>,
+    user_timeout: Duration,
+    max_user_heap_size: usize,
 
     phase: UdfPhase<RT>,
     file_storage: TransactionalFileStorage<RT>,
@@ -302,7 +306,11 @@ impl<RT: Runtime> IsolateEnvironment<RT> for DatabaseUdfEnvironment<RT> {
     }
 
     fn user_timeout(&self) -> std::time::Duration {
-        *DATABASE_UDF_USER_TIMEOUT
+        self.user_timeout
+    }
+
+    fn max_user_heap_size(&self) -> usize {
+        self.max_user_heap_size
     }
 
     fn system_timeout(&self) -> std::time::Duration {
@@ -333,6 +341,12 @@ impl<RT: Runtime> DatabaseUdfEnvironment<RT> {
         udf_callback: Box<dyn UdfCallback<RT>>,
     ) -> Self {
         let persistence_version = transaction.persistence_version();
+        let user_timeout = path_and_args
+            .user_timeout()
+            .unwrap_or(*DATABASE_UDF_USER_TIMEOUT);
+        let max_user_heap_size = path_and_args
+            .max_user_heap_size()
+            .unwrap_or(*ISOLATE_MAX_USER_HEAP_SIZE);
         let (path, arguments, udf_server_version) = path_and_args.consume();
         let component = path.component;
         Self {
@@ -342,6 +356,8 @@ impl<RT: Runtime> DatabaseUdfEnvironment<RT> {
             arguments,
             identity,
             udf_server_version,
+            user_timeout,
+            max_user_heap_size,
 
             phase: UdfPhase::new(
                 transaction,
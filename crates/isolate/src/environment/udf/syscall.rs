@@ -1,12 +1,16 @@
 #![allow(non_snake_case)]
 
-use std::str::FromStr;
+use std::{
+    str::FromStr,
+    time::Duration,
+};
 
 use anyhow::Context;
 use common::{
     query::Query,
     runtime::Runtime,
     static_span,
+    types::Timestamp,
     version::Version,
 };
 use database::{
@@ -15,6 +19,7 @@ use database::{
 };
 use errors::ErrorMetadata;
 use model::virtual_system_mapping;
+use rand_chacha::ChaCha12Rng;
 use serde::{
     Deserialize,
     Serialize,
@@ -26,6 +31,7 @@ use serde_json::{
 use value::{
     id_v6::DeveloperDocumentId,
     identifier::Identifier,
+    json_serialize,
     ConvexValue,
     InternalId,
     TableName,
@@ -39,6 +45,10 @@ use super::{
 };
 use crate::environment::helpers::{
     parse_version,
+    seeded_random::{
+        self,
+        MAX_SEEDED_RANDOM_VALUES,
+    },
     with_argument_error,
     ArgName,
 };
@@ -50,6 +60,14 @@ pub trait SyscallProvider<RT: Runtime> {
     fn lookup_virtual_table(&mut self, name: &TableName) -> anyhow::Result<Option<TableNumber>>;
     fn component_argument(&self, name: &str) -> anyhow::Result<Option<ConvexValue>>;
 
+    /// RNG derived from the function's arguments, `key`, and, if `period` is
+    /// set, the execution time truncated to `period`. See
+    /// [`seeded_random`] for the derivation.
+    fn seeded_rng(&mut self, key: &str, period: Option<Duration>) -> anyhow::Result<ChaCha12Rng>;
+
+    /// The timestamp of the snapshot the function's transaction reads from.
+    fn begin_timestamp(&mut self) -> anyhow::Result<Timestamp>;
+
     fn start_query(&mut self, query: Query, version: Option<Version>) -> anyhow::Result<u32>;
     fn cleanup_query(&mut self, query_id: u32) -> bool;
 }
@@ -87,6 +105,28 @@ impl<RT: Runtime> SyscallProvider<RT> for DatabaseUdfEnvironment<RT> {
         Ok(result)
     }
 
+    fn seeded_rng(&mut self, key: &str, period: Option<Duration>) -> anyhow::Result<ChaCha12Rng> {
+        let period_index = match period {
+            // Reading the execution time marks the query as time-dependent, so its
+            // cached result expires the same way as one that calls `Date.now()`.
+            Some(period) => {
+                let now_ms = self.phase.unix_timestamp()?.as_ms_since_epoch()?;
+                Some(now_ms / period.as_millis() as u64)
+            },
+            None => None,
+        };
+        let arguments_json = json_serialize(self.arguments.clone())?;
+        Ok(seeded_random::seeded_rng(
+            &arguments_json,
+            key,
+            period_index,
+        ))
+    }
+
+    fn begin_timestamp(&mut self) -> anyhow::Result<Timestamp> {
+        Ok(*self.phase.tx()?.begin_timestamp())
+    }
+
     fn start_query(&mut self, query: Query, version: Option<Version>) -> anyhow::Result<u32> {
         let table_filter = SyscallProvider::<RT>::table_filter(self);
         let component = self.component()?;
@@ -115,6 +155,8 @@ pub fn syscall_impl<RT: Runtime, P: SyscallProvider<RT>>(
         "1.0/queryStream" => syscall_query_stream(provider, args),
         "1.0/db/normalizeId" => syscall_normalize_id(provider, args),
         "1.0/componentArgument" => syscall_component_argument(provider, args),
+        "1.0/seededRandom" => syscall_seeded_random(provider, args),
+        "1.0/transactionTimestamp" => syscall_transaction_timestamp(provider, args),
 
         #[cfg(any(test, feature = "testing"))]
         "throwSystemError" => anyhow::bail!("I can't go for that."),
@@ -209,6 +251,54 @@ fn syscall_component_argument<RT: Runtime, P: SyscallProvider<RT>>(
     Ok(result)
 }
 
+fn syscall_seeded_random<RT: Runtime, P: SyscallProvider<RT>>(
+    provider: &mut P,
+    args: JsonValue,
+) -> anyhow::Result<JsonValue> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct SeededRandomArgs {
+        #[serde(default)]
+        key: String,
+        count: usize,
+        period_ms: Option<u64>,
+    }
+    let (key, count, period) = with_argument_error("seededRandom", || {
+        let SeededRandomArgs {
+            key,
+            count,
+            period_ms,
+        } = serde_json::from_value(args)?;
+        anyhow::ensure!(
+            count <= MAX_SEEDED_RANDOM_VALUES,
+            anyhow::anyhow!("must be at most {MAX_SEEDED_RANDOM_VALUES}").context(ArgName("count"))
+        );
+        let period = match period_ms {
+            Some(0) => {
+                anyhow::bail!(anyhow::anyhow!("must be positive").context(ArgName("periodMs")))
+            },
+            Some(ms) => Some(Duration::from_millis(ms)),
+            None => None,
+        };
+        Ok((key, count, period))
+    })?;
+    let mut rng = provider.seeded_rng(&key, period)?;
+    let values: Vec<f64> = (0..count)
+        .map(|_| seeded_random::next_f64(&mut rng))
+        .collect();
+    Ok(json!({ "values": values }))
+}
+
+fn syscall_transaction_timestamp<RT: Runtime, P: SyscallProvider<RT>>(
+    provider: &mut P,
+    _args: JsonValue,
+) -> anyhow::Result<JsonValue> {
+    let ts = provider.begin_timestamp()?;
+    // Timestamps don't fit in a JS number, so return them as an int64, which
+    // has the same encoding as the `ts` in mutation responses.
+    Ok(json!({ "ts": JsonValue::from(ConvexValue::Int64(ts.into())) }))
+}
+
 fn syscall_query_stream<RT: Runtime, P: SyscallProvider<RT>>(
     provider: &mut P,
     args: JsonValue,
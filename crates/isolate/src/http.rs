@@ -1,5 +1,9 @@
-use std::str::FromStr;
+use std::{
+    pin::Pin,
+    str::FromStr,
+};
 
+use bytes::Bytes;
 use common::{
     http::{
         HttpRequestStream,
@@ -8,7 +12,11 @@ use common::{
     },
     sync::spsc,
 };
-use futures::stream::BoxStream;
+use futures::{
+    stream::BoxStream,
+    Stream,
+    TryStreamExt,
+};
 use headers::{
     HeaderMap,
     HeaderName,
@@ -25,10 +33,23 @@ use url::Url;
 
 use crate::{
     ops::OpProvider,
-    request_scope::StreamListener,
+    request_scope::{
+        StreamCapacity,
+        StreamListener,
+    },
     HttpActionRequestHead,
 };
 
+/// Fails JavaScript's pending writes to a stream once its reader is dropped,
+/// e.g. because the request failed, instead of leaving them waiting forever.
+struct CloseOnDrop(StreamCapacity);
+
+impl Drop for CloseOnDrop {
+    fn drop(&mut self) {
+        self.0.close();
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct HttpRequestV8 {
@@ -48,15 +69,23 @@ impl HttpRequestV8 {
             header_map.append(HeaderName::from_str(name.as_str())?, value.parse()?);
         }
         let (body_sender, body_receiver) = spsc::unbounded_channel();
+        let mut body: Pin<Box<dyn Stream<Item = anyhow::Result<Bytes>> + Sync + Send>> =
+            Box::pin(body_receiver.into_stream());
         match self.stream_id {
             Some(stream_id) => {
+                if let Some(capacity) = provider.stream_capacity(stream_id)? {
+                    // Let JavaScript write more of the body only as the client
+                    // sends it, so large uploads aren't buffered in memory.
+                    let reader = CloseOnDrop(capacity);
+                    body = Box::pin(body.inspect_ok(move |chunk| reader.0.release(chunk.len())));
+                }
                 provider.new_stream_listener(stream_id, StreamListener::RustStream(body_sender))?;
             },
             None => drop(body_sender),
         };
 
         Ok(HttpRequestStream {
-            body: Box::pin(body_receiver.into_stream()),
+            body,
             headers: header_map,
             url: Url::parse(&self.url)?,
             method: Method::from_str(&self.method)?,
@@ -1,20 +1,37 @@
 use core::fmt;
+use std::{
+    pin::Pin,
+    time::Duration,
+};
 
 use bytes::Bytes;
 use common::{
     http::normalize_header_map,
+    runtime::Runtime,
     types::{
         HttpActionRoute,
         RoutableMethod,
     },
 };
-use futures::stream::BoxStream;
+use futures::{
+    future::FusedFuture,
+    select_biased,
+    stream::{
+        self,
+        BoxStream,
+        Fuse,
+    },
+    StreamExt,
+};
 use headers::{
     HeaderMap,
     HeaderValue,
 };
 use http::{
-    header::CONTENT_TYPE,
+    header::{
+        CACHE_CONTROL,
+        CONTENT_TYPE,
+    },
     Method,
     StatusCode,
 };
@@ -308,3 +325,178 @@ impl HttpActionResponseStreamer {
         self.sha256.finalize()
     }
 }
+
+/// A comment line, which `EventSource` clients ignore.
+const EVENT_STREAM_KEEPALIVE: &[u8] = b": keep-alive\n\n";
+
+/// Whether the response is a stream of Server-Sent Events.
+pub fn is_event_stream(head: &HttpActionResponseHead) -> bool {
+    head.headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<mime::Mime>().ok())
+        .is_some_and(|mime| mime.essence_str() == mime::TEXT_EVENT_STREAM.essence_str())
+}
+
+/// Adds headers that stop caches and proxies from buffering a stream of
+/// Server-Sent Events, unless the HTTP action set them itself.
+pub fn prepare_event_stream_head(head: &mut HttpActionResponseHead) {
+    head.headers
+        .entry(CACHE_CONTROL)
+        .or_insert(HeaderValue::from_static("no-cache"));
+    head.headers
+        .entry("x-accel-buffering")
+        .or_insert(HeaderValue::from_static("no"));
+}
+
+/// Wraps the body of a stream of Server-Sent Events, sending a keep-alive
+/// comment whenever it's been idle for `keepalive_interval` and ending it after
+/// `max_duration`.
+///
+/// Comments are only sent between events, so an HTTP action that stops
+/// partway through writing an event doesn't have the comment spliced into it.
+pub fn event_stream_body<RT: Runtime>(
+    rt: RT,
+    body: BoxStream<'static, anyhow::Result<Bytes>>,
+    keepalive_interval: Duration,
+    max_duration: Duration,
+) -> BoxStream<'static, anyhow::Result<Bytes>> {
+    struct State<RT: Runtime> {
+        rt: RT,
+        body: Fuse<BoxStream<'static, anyhow::Result<Bytes>>>,
+        deadline: Pin<Box<dyn FusedFuture<Output = ()> + Send>>,
+        // The last few bytes sent, to tell whether we're between events.
+        tail: Vec<u8>,
+    }
+    let state = State {
+        deadline: rt.wait(max_duration),
+        rt,
+        body: body.fuse(),
+        tail: vec![],
+    };
+    stream::unfold(state, move |mut state| async move {
+        loop {
+            let mut keepalive = state.rt.wait(keepalive_interval);
+            select_biased! {
+                chunk = state.body.next() => {
+                    let chunk = chunk?;
+                    if let Ok(bytes) = &chunk {
+                        state.tail.extend_from_slice(bytes);
+                        let start = state.tail.len().saturating_sub(4);
+                        state.tail.drain(..start);
+                    }
+                    return Some((chunk, state));
+                },
+                _ = &mut state.deadline => {
+                    tracing::info!(
+                        "Ending Server-Sent Events stream after {max_duration:?}"
+                    );
+                    return None;
+                },
+                _ = keepalive => {
+                    if at_event_boundary(&state.tail) {
+                        let keepalive = Bytes::from_static(EVENT_STREAM_KEEPALIVE);
+                        return Some((Ok(keepalive), state));
+                    }
+                },
+            }
+        }
+    })
+    .boxed()
+}
+
+fn at_event_boundary(tail: &[u8]) -> bool {
+    tail.is_empty()
+        || tail.ends_with(b"\n\n")
+        || tail.ends_with(b"\r\r")
+        || tail.ends_with(b"\r\n\r\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bytes::Bytes;
+    use futures::{
+        pin_mut,
+        poll,
+        stream,
+        StreamExt,
+        TryStreamExt,
+    };
+    use headers::HeaderMap;
+    use http::{
+        header::CONTENT_TYPE,
+        StatusCode,
+    };
+    use runtime::testing::TestRuntime;
+    use tokio::sync::mpsc;
+    use tokio_stream::wrappers::UnboundedReceiverStream;
+
+    use super::{
+        event_stream_body,
+        is_event_stream,
+        HttpActionResponseHead,
+    };
+
+    #[test]
+    fn test_is_event_stream() -> anyhow::Result<()> {
+        let mut head = HttpActionResponseHead {
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+        };
+        assert!(!is_event_stream(&head));
+        head.headers
+            .insert(CONTENT_TYPE, "text/event-stream; charset=utf-8".parse()?);
+        assert!(is_event_stream(&head));
+        head.headers.insert(CONTENT_TYPE, "text/plain".parse()?);
+        assert!(!is_event_stream(&head));
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_event_stream_keepalive(rt: TestRuntime) -> anyhow::Result<()> {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let body = UnboundedReceiverStream::new(receiver).map(Ok).boxed();
+        let mut body = event_stream_body(
+            rt.clone(),
+            body,
+            Duration::from_secs(10),
+            Duration::from_secs(65),
+        );
+        // Idle streams get keep-alives, even before the first event.
+        assert_eq!(body.try_next().await?.unwrap(), ": keep-alive\n\n");
+        sender.send(Bytes::from("data: a\n\n"))?;
+        assert_eq!(body.try_next().await?.unwrap(), "data: a\n\n");
+        assert_eq!(body.try_next().await?.unwrap(), ": keep-alive\n\n");
+
+        // Nothing is sent in the middle of an event.
+        sender.send(Bytes::from("data: "))?;
+        assert_eq!(body.try_next().await?.unwrap(), "data: ");
+        let next = body.next();
+        pin_mut!(next);
+        assert!(poll!(&mut next).is_pending());
+        rt.advance_time(Duration::from_secs(30)).await;
+        assert!(poll!(&mut next).is_pending());
+        sender.send(Bytes::from("b\n\n"))?;
+        assert_eq!(next.await.unwrap()?, "b\n\n");
+
+        // The stream ends after the max duration, even if the action is still
+        // running.
+        let rest: Vec<_> = body.try_collect().await?;
+        assert_eq!(rest, vec![Bytes::from(": keep-alive\n\n")]);
+        drop(sender);
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_event_stream_ends_with_body(rt: TestRuntime) -> anyhow::Result<()> {
+        let body = stream::iter([Ok(Bytes::from("data: a\n\n"))]).boxed();
+        let chunks: Vec<_> =
+            event_stream_body(rt, body, Duration::from_secs(10), Duration::from_secs(60))
+                .try_collect()
+                .await?;
+        assert_eq!(chunks, vec![Bytes::from("data: a\n\n")]);
+        Ok(())
+    }
+}
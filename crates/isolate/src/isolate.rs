@@ -7,6 +7,7 @@ use std::{
 };
 
 use common::{
+    fault_injection,
     knobs::{
         ISOLATE_MAX_HEAP_EXTRA_SIZE,
         ISOLATE_MAX_USER_HEAP_SIZE,
@@ -182,6 +183,8 @@ impl<RT: Runtime> Isolate<RT> {
         // we'll take back in the `Isolate`'s destructor.
         let heap_context = Box::new(HeapContext {
             handle: handle.clone(),
+            extra_heap_allowance: 0,
+            heap_limit_raised: false,
         });
         let heap_ctx_ptr = Box::into_raw(heap_context);
         v8_isolate.add_near_heap_limit_callback(
@@ -270,13 +273,33 @@ impl<RT: Runtime> Isolate<RT> {
         Ok(())
     }
 
+    /// Whether a request with a larger `max_user_heap_size` raised the heap
+    /// limit, in which case the isolate shouldn't be reused by functions with
+    /// the default limit.
+    pub fn heap_limit_raised(&self) -> bool {
+        // Safety: the heap limit callback only runs on this isolate's thread,
+        // so it can't be running while we hold `&self`.
+        unsafe { (*self.heap_ctx_ptr).heap_limit_raised }
+    }
+
     pub async fn start_request<E: IsolateEnvironment<RT>>(
         &mut self,
         client_id: Arc<String>,
         environment: E,
     ) -> anyhow::Result<(IsolateHandle, RequestState<RT, E>)> {
         self.check_isolate_clean()?;
+        // Safety: see `heap_limit_raised`.
+        let heap_ctx = unsafe { &mut *self.heap_ctx_ptr };
+        heap_ctx.extra_heap_allowance = environment
+            .max_user_heap_size()
+            .saturating_sub(*ISOLATE_MAX_USER_HEAP_SIZE);
         let context_handle = self.handle.new_context_created();
+        if fault_injection::should_kill_isolate(&mut self.rt.rng()) {
+            tracing::warn!("Killing isolate for fault injection");
+            context_handle.terminate(TerminationReason::SystemError(Some(
+                fault_injection::isolate_killed_error(),
+            )));
+        }
         let mut user_timeout = environment.user_timeout();
         if let Some(max_user_timeout) = self.max_user_timeout {
             // We apply the minimum between the timeout from the environment
@@ -334,6 +357,10 @@ impl<RT: Runtime> Drop for Isolate<RT> {
 
 struct HeapContext {
     handle: IsolateHandle,
+    // How much further the current request may grow the heap past the
+    // isolate's limit before it runs out of memory.
+    extra_heap_allowance: usize,
+    heap_limit_raised: bool,
 }
 
 extern "C" fn near_heap_limit_callback(
@@ -342,6 +369,11 @@ extern "C" fn near_heap_limit_callback(
     _initial_heap_limit: usize,
 ) -> usize {
     let heap_ctx = unsafe { &mut *(data as *mut HeapContext) };
+    if heap_ctx.extra_heap_allowance > 0 {
+        let allowance = std::mem::take(&mut heap_ctx.extra_heap_allowance);
+        heap_ctx.heap_limit_raised = true;
+        return current_heap_limit + allowance;
+    }
     heap_ctx.handle.terminate(TerminationReason::OutOfMemory);
 
     // Double heap limit to avoid a hard OOM.
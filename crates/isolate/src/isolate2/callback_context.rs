@@ -459,6 +459,7 @@ mod op_provider {
         isolate2::client::PendingAsyncOp,
         ops::OpProvider,
         request_scope::{
+            StreamCapacity,
             StreamListener,
             TextDecoderResource,
         },
@@ -546,6 +547,16 @@ mod op_provider {
             self.update_stream_listeners()
         }
 
+        fn limit_stream(&mut self, id: Uuid, max_buffered_bytes: u32) -> anyhow::Result<()> {
+            let state = self.context_state()?;
+            state.limit_stream(id, max_buffered_bytes)
+        }
+
+        fn stream_capacity(&mut self, id: Uuid) -> anyhow::Result<Option<StreamCapacity>> {
+            let state = self.context_state()?;
+            state.stream_capacity(id)
+        }
+
         fn new_stream_listener(
             &mut self,
             stream_id: Uuid,
@@ -33,6 +33,7 @@ use crate::{
     ops::CryptoOps,
     request_scope::{
         ReadableStream,
+        StreamCapacity,
         StreamListener,
         TextDecoderResource,
     },
@@ -133,6 +134,23 @@ impl ContextState {
         Ok(id)
     }
 
+    pub fn limit_stream(&mut self, id: Uuid, max_buffered_bytes: u32) -> anyhow::Result<()> {
+        self.streams.mutate(&id, |stream| -> anyhow::Result<()> {
+            let Some(Ok(stream)) = stream else {
+                anyhow::bail!("unrecognized stream id {id}");
+            };
+            stream.capacity = Some(StreamCapacity::new(max_buffered_bytes));
+            Ok(())
+        })
+    }
+
+    pub fn stream_capacity(&self, id: Uuid) -> anyhow::Result<Option<StreamCapacity>> {
+        match self.streams.get(&id) {
+            Some(Ok(stream)) => Ok(stream.capacity.clone()),
+            _ => anyhow::bail!("unrecognized stream id {id}"),
+        }
+    }
+
     pub fn extend_stream(
         &mut self,
         id: Uuid,
@@ -144,7 +162,7 @@ impl ContextState {
             None => None,
         };
         self.streams.mutate(&id, |stream| -> anyhow::Result<()> {
-            let Some(Ok(ReadableStream { parts, done })) = stream else {
+            let Some(Ok(ReadableStream { parts, done, .. })) = stream else {
                 anyhow::bail!("unrecognized stream id {id}");
             };
             if *done {
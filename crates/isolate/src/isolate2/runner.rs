@@ -17,6 +17,7 @@ use common::{
         ResolvedComponentFunctionPath,
         Resource,
     },
+    document::ResolvedDocument,
     errors::JsError,
     execution_context::ExecutionContext,
     log_lines::{
@@ -34,7 +35,9 @@ use common::{
         spsc,
     },
     types::{
+        ObjectKey,
         PersistenceVersion,
+        Timestamp,
         UdfType,
     },
     version::Version,
@@ -45,7 +48,10 @@ use database::{
     Transaction,
 };
 use errors::ErrorMetadata;
-use keybroker::KeyBroker;
+use keybroker::{
+    KeyBroker,
+    StoreFileConstraints,
+};
 use model::{
     config::module_loader::ModuleLoader,
     environment_variables::{
@@ -83,6 +89,7 @@ use value::{
     TableName,
     TableNamespace,
     TableNumber,
+    TabletId,
     TabletIdAndTableNumber,
 };
 
@@ -326,6 +333,18 @@ impl<RT: Runtime> SyscallProvider<RT> for UdfEnvironment<RT> {
         todo!();
     }
 
+    fn seeded_rng(
+        &mut self,
+        _key: &str,
+        _period: Option<std::time::Duration>,
+    ) -> anyhow::Result<ChaCha12Rng> {
+        todo!();
+    }
+
+    fn begin_timestamp(&mut self) -> anyhow::Result<Timestamp> {
+        todo!();
+    }
+
     fn start_query(&mut self, query: Query, version: Option<Version>) -> anyhow::Result<QueryId> {
         self.check_executing()?;
         let query_id = self.shared.start_query(query, version);
@@ -906,7 +925,10 @@ impl<'a, RT: Runtime> AsyncSyscallProvider<RT> for Isolate2SyscallProvider<'a, R
         validate_schedule_args(path, args, scheduled_ts, self.unix_timestamp, self.tx).await
     }
 
-    fn file_storage_generate_upload_url(&self) -> anyhow::Result<String> {
+    fn file_storage_generate_upload_url(
+        &self,
+        _constraints: StoreFileConstraints,
+    ) -> anyhow::Result<String> {
         todo!()
     }
 
@@ -928,6 +950,14 @@ impl<'a, RT: Runtime> AsyncSyscallProvider<RT> for Isolate2SyscallProvider<'a, R
         todo!()
     }
 
+    async fn file_storage_read_archived_segment(
+        &mut self,
+        _tablet_id: TabletId,
+        _storage_key: ObjectKey,
+    ) -> anyhow::Result<Vec<ResolvedDocument>> {
+        todo!()
+    }
+
     fn insert_query(&mut self, query_id: QueryId, query: DeveloperQuery<RT>) {
         self.shared.insert_query(query_id, query)
     }
@@ -27,6 +27,7 @@ pub mod isolate;
 pub mod isolate2;
 pub mod metrics;
 pub mod module_map;
+pub mod native_ops;
 mod ops;
 mod request_scope;
 pub mod strings;
@@ -87,6 +88,9 @@ pub use self::{
         UdfArgsJson,
     },
     http_action::{
+        event_stream_body,
+        is_event_stream,
+        prepare_event_stream_head,
         HttpActionRequest,
         HttpActionRequestHead,
         HttpActionResponseHead,
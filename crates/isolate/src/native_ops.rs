@@ -0,0 +1,226 @@
+//! Custom native ops, so embedding builds can expose in-house Rust code (e.g.
+//! a proprietary codec) to functions without forking this crate.
+//!
+//! Ops are registered once at startup with [`register_native_op`] and called
+//! from JS with `callNativeOp` in `convex/server`, which goes through the
+//! `1.0/native` async syscall. Each op needs a capability, and a deployment
+//! only grants the capabilities listed in `NATIVE_OP_CAPABILITIES`.
+use std::{
+    collections::BTreeMap,
+    sync::{
+        Arc,
+        LazyLock,
+    },
+};
+
+use anyhow::Context;
+use common::{
+    knobs::NATIVE_OP_CAPABILITIES,
+    schemas::validator::Validator,
+    virtual_system_mapping::VirtualSystemMapping,
+};
+use errors::ErrorMetadata;
+use futures::{
+    future::BoxFuture,
+    Future,
+    FutureExt,
+};
+use parking_lot::RwLock;
+use serde::Deserialize;
+use serde_json::Value as JsonValue;
+use value::{
+    ConvexValue,
+    TableMapping,
+    TableNamespace,
+};
+
+use crate::environment::helpers::{
+    with_argument_error,
+    ArgName,
+};
+
+type NativeOpHandler =
+    Arc<dyn Fn(ConvexValue) -> BoxFuture<'static, anyhow::Result<ConvexValue>> + Send + Sync>;
+
+#[derive(Clone)]
+pub struct NativeOp {
+    name: String,
+    capability: String,
+    args: Validator,
+    deterministic: bool,
+    handler: NativeOpHandler,
+}
+
+impl NativeOp {
+    /// An op called `name` that can be called by deployments granted
+    /// `capability`, with arguments matching `args`. Ops can only be called
+    /// from actions unless they're marked
+    /// [`deterministic`](Self::deterministic).
+    pub fn new<F, Fut>(name: &str, capability: &str, args: Validator, handler: F) -> Self
+    where
+        F: Fn(ConvexValue) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = anyhow::Result<ConvexValue>> + Send + 'static,
+    {
+        Self {
+            name: name.to_string(),
+            capability: capability.to_string(),
+            args,
+            deterministic: false,
+            handler: Arc::new(move |args| handler(args).boxed()),
+        }
+    }
+
+    /// Allows the op to be called from queries and mutations, which must be
+    /// deterministic. Only mark ops whose result depends on nothing but their
+    /// arguments, and which have no side effects.
+    pub fn deterministic(mut self) -> Self {
+        self.deterministic = true;
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn capability(&self) -> &str {
+        &self.capability
+    }
+}
+
+static NATIVE_OPS: LazyLock<RwLock<BTreeMap<String, NativeOp>>> =
+    LazyLock::new(|| RwLock::new(BTreeMap::new()));
+
+/// Makes `op` callable from functions in deployments granted its capability.
+/// Fails if an op with the same name is already registered.
+pub fn register_native_op(op: NativeOp) -> anyhow::Result<()> {
+    anyhow::ensure!(!op.name.is_empty(), "Native op names can't be empty");
+    let mut ops = NATIVE_OPS.write();
+    anyhow::ensure!(
+        !ops.contains_key(&op.name),
+        "Native op {} is already registered",
+        op.name
+    );
+    tracing::info!(
+        "Registered native op {} with capability {}",
+        op.name,
+        op.capability
+    );
+    ops.insert(op.name.clone(), op);
+    Ok(())
+}
+
+/// The ops registered with [`register_native_op`].
+pub fn registered_native_ops() -> Vec<NativeOp> {
+    NATIVE_OPS.read().values().cloned().collect()
+}
+
+/// Runs the `1.0/native` syscall, calling the op named in `args`.
+/// `from_action` is false for queries and mutations, which can only call
+/// deterministic ops.
+pub async fn run_native_op(args: JsonValue, from_action: bool) -> anyhow::Result<JsonValue> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct NativeOpArgs {
+        name: String,
+        args: JsonValue,
+    }
+    let (name, args) = with_argument_error("callNativeOp", || {
+        let NativeOpArgs { name, args } = serde_json::from_value(args)?;
+        let args = ConvexValue::try_from(args).context(ArgName("args"))?;
+        Ok((name, args))
+    })?;
+    let Some(op) = NATIVE_OPS.read().get(&name).cloned() else {
+        anyhow::bail!(ErrorMetadata::bad_request(
+            "NativeOpNotFound",
+            format!("No native op named {name} is registered"),
+        ));
+    };
+    anyhow::ensure!(
+        NATIVE_OP_CAPABILITIES.contains(&op.capability),
+        ErrorMetadata::forbidden(
+            "NativeOpNotEnabled",
+            format!(
+                "Native op {name} needs the {} capability, which this deployment doesn't have. \
+                 Add it to NATIVE_OP_CAPABILITIES to allow it.",
+                op.capability
+            ),
+        )
+    );
+    anyhow::ensure!(
+        from_action || op.deterministic,
+        ErrorMetadata::bad_request(
+            "NativeOpNotDeterministic",
+            format!("Native op {name} can only be called from actions"),
+        )
+    );
+    // Ops take plain values, so IDs aren't checked against any tables.
+    let table_mapping = TableMapping::new().namespace(TableNamespace::Global);
+    if let Err(e) = op
+        .args
+        .check_value(&args, &table_mapping, &VirtualSystemMapping::default())
+    {
+        anyhow::bail!(ErrorMetadata::bad_request(
+            "InvalidNativeOpArgs",
+            format!("Invalid arguments for native op {name}: {e}"),
+        ));
+    }
+    let result = (op.handler)(args).await?;
+    Ok(JsonValue::from(result))
+}
+
+#[cfg(test)]
+mod tests {
+    use common::schemas::validator::{
+        FieldValidator,
+        ObjectValidator,
+        Validator,
+    };
+    use errors::ErrorMetadataAnyhowExt;
+    use maplit::btreemap;
+    use runtime::testing::TestRuntime;
+    use serde_json::json;
+    use value::ConvexValue;
+
+    use super::{
+        register_native_op,
+        run_native_op,
+        NativeOp,
+    };
+
+    #[convex_macro::test_runtime]
+    async fn test_native_op_gating(_rt: TestRuntime) -> anyhow::Result<()> {
+        let args = Validator::Object(ObjectValidator(btreemap! {
+            "s".parse()? => FieldValidator::required_field_type(Validator::String),
+        }));
+        register_native_op(NativeOp::new(
+            "test/reverse",
+            "test",
+            args.clone(),
+            |args| async move {
+                let ConvexValue::Object(args) = args else {
+                    anyhow::bail!("Expected an object");
+                };
+                let Some(ConvexValue::String(s)) = args.get("s") else {
+                    anyhow::bail!("Expected a string");
+                };
+                ConvexValue::try_from(s.chars().rev().collect::<String>())
+            },
+        ))?;
+        let duplicate = NativeOp::new("test/reverse", "test", args, |args| async { Ok(args) });
+        assert!(register_native_op(duplicate).is_err());
+
+        let err = run_native_op(json!({ "name": "test/missing", "args": {} }), true)
+            .await
+            .unwrap_err();
+        assert_eq!(err.short_msg(), "NativeOpNotFound");
+        // Tests don't grant any capabilities.
+        let err = run_native_op(
+            json!({ "name": "test/reverse", "args": { "s": "abc" } }),
+            true,
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.short_msg(), "NativeOpNotEnabled");
+        Ok(())
+    }
+}
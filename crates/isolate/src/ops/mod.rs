@@ -17,6 +17,7 @@ mod text;
 mod time;
 mod validate_args;
 mod validate_returns;
+mod websocket;
 
 use std::{
     collections::BTreeMap,
@@ -109,8 +110,10 @@ use self::{
     },
     stream::{
         async_op_stream_read_part,
+        async_op_stream_reserve,
         op_stream_create,
         op_stream_extend,
+        op_stream_limit,
     },
     text::{
         op_atob,
@@ -128,6 +131,7 @@ use self::{
         op_now,
     },
     validate_args::op_validate_args,
+    websocket::async_op_websocket_connect,
 };
 pub use self::{
     crypto::CryptoOps,
@@ -143,6 +147,7 @@ use crate::{
     metrics,
     request_scope::{
         ReadableStream,
+        StreamCapacity,
         StreamListener,
         TextDecoderResource,
     },
@@ -183,6 +188,8 @@ pub trait OpProvider<'b> {
         stream_id: Uuid,
         listener: StreamListener,
     ) -> anyhow::Result<()>;
+    fn limit_stream(&mut self, id: Uuid, max_buffered_bytes: u32) -> anyhow::Result<()>;
+    fn stream_capacity(&mut self, id: Uuid) -> anyhow::Result<Option<StreamCapacity>>;
 
     fn create_text_decoder(&mut self, decoder: TextDecoderResource) -> anyhow::Result<Uuid>;
     fn get_text_decoder(&mut self, uuid: &Uuid) -> anyhow::Result<&mut TextDecoderResource>;
@@ -272,7 +279,7 @@ impl<'a, 'b: 'a, RT: Runtime, E: IsolateEnvironment<RT>> OpProvider<'b>
             None => None,
         };
         state.streams.mutate(&id, |stream| -> anyhow::Result<()> {
-            let Some(Ok(ReadableStream { parts, done })) = stream else {
+            let Some(Ok(ReadableStream { parts, done, .. })) = stream else {
                 anyhow::bail!("unrecognized stream id {id}");
             };
             if *done {
@@ -290,6 +297,14 @@ impl<'a, 'b: 'a, RT: Runtime, E: IsolateEnvironment<RT>> OpProvider<'b>
         Ok(())
     }
 
+    fn limit_stream(&mut self, id: Uuid, max_buffered_bytes: u32) -> anyhow::Result<()> {
+        self.state_mut()?.limit_stream(id, max_buffered_bytes)
+    }
+
+    fn stream_capacity(&mut self, id: Uuid) -> anyhow::Result<Option<StreamCapacity>> {
+        self.state_mut()?.stream_capacity(id)
+    }
+
     fn new_stream_listener(
         &mut self,
         stream_id: Uuid,
@@ -373,6 +388,7 @@ pub fn run_op<'b, P: OpProvider<'b>>(
         "headers/normalizeName" => op_headers_normalize_name(provider, args, rv)?,
         "stream/create" => op_stream_create(provider, args, rv)?,
         "stream/extend" => op_stream_extend(provider, args, rv)?,
+        "stream/limit" => op_stream_limit(provider, args, rv)?,
         "textEncoder/encode" => op_text_encoder_encode(provider, args, rv)?,
         "textEncoder/encodeInto" => op_text_encoder_encode_into(provider, args, rv)?,
         "textEncoder/decodeSingle" => op_text_encoder_decode_single(provider, args, rv)?,
@@ -444,6 +460,8 @@ pub fn start_async_op<'b, P: OpProvider<'b>>(
         "storage/store" => async_op_storage_store(provider, args, resolver)?,
         "storage/get" => async_op_storage_get(provider, args, resolver)?,
         "stream/readPart" => async_op_stream_read_part(provider, args, resolver)?,
+        "stream/reserve" => async_op_stream_reserve(provider, args, resolver)?,
+        "websocket/connect" => async_op_websocket_connect(provider, args, resolver)?,
         _ => {
             anyhow::bail!(ErrorMetadata::bad_request(
                 "UnknownAsyncOperation",
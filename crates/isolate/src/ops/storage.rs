@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use common::sync::spsc;
 use deno_core::{
     serde_v8,
@@ -29,6 +31,8 @@ pub fn async_op_storage_store<'b, P: OpProvider<'b>>(
     let content_type = content_type.filter(|ct| !ct.is_empty());
     let content_length = serde_v8::from_v8(provider.scope(), args.get(3))?;
     let digest = serde_v8::from_v8(provider.scope(), args.get(4))?;
+    let filename = serde_v8::from_v8(provider.scope(), args.get(5))?;
+    let tags: Option<BTreeMap<String, String>> = serde_v8::from_v8(provider.scope(), args.get(6))?;
 
     provider.start_async_op(
         AsyncOpRequest::StorageStore {
@@ -36,6 +40,8 @@ pub fn async_op_storage_store<'b, P: OpProvider<'b>>(
             content_type,
             content_length,
             digest,
+            filename,
+            tags: tags.unwrap_or_default(),
         },
         resolver,
     )
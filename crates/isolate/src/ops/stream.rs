@@ -17,6 +17,7 @@ use super::OpProvider;
 use crate::{
     environment::{
         helpers::resolve_promise,
+        AsyncOpRequest,
         IsolateEnvironment,
     },
     execution_scope::ExecutionScope,
@@ -32,6 +33,19 @@ pub fn async_op_stream_read_part<'b, P: OpProvider<'b>>(
     provider.new_stream_listener(stream_id, StreamListener::JsPromise(resolver))
 }
 
+pub fn async_op_stream_reserve<'b, P: OpProvider<'b>>(
+    provider: &mut P,
+    args: v8::FunctionCallbackArguments,
+    resolver: v8::Global<v8::PromiseResolver>,
+) -> anyhow::Result<()> {
+    let stream_id = serde_v8::from_v8(provider.scope(), args.get(1))?;
+    let bytes = serde_v8::from_v8(provider.scope(), args.get(2))?;
+    let capacity = provider
+        .stream_capacity(stream_id)?
+        .with_context(|| format!("stream {stream_id} has no limit"))?;
+    provider.start_async_op(AsyncOpRequest::StreamReserve { capacity, bytes }, resolver)
+}
+
 #[convex_macro::v8_op]
 pub fn op_stream_create<'b, P: OpProvider<'b>>(provider: &mut P) -> anyhow::Result<Uuid> {
     provider.create_stream()
@@ -47,6 +61,18 @@ pub fn op_stream_extend<'b, P: OpProvider<'b>>(
     provider.extend_stream(id, bytes.map(|b| b.into_vec().into()), new_done)
 }
 
+/// Limit how many bytes JavaScript can write to the stream before its reader
+/// catches up.
+#[convex_macro::v8_op]
+pub fn op_stream_limit<'b, P: OpProvider<'b>>(
+    provider: &mut P,
+    id: Uuid,
+    max_buffered_bytes: u32,
+) -> anyhow::Result<()> {
+    anyhow::ensure!(max_buffered_bytes > 0, "stream limit must be positive");
+    provider.limit_stream(id, max_buffered_bytes)
+}
+
 impl<'a, 'b: 'a, RT: Runtime, E: IsolateEnvironment<RT>> ExecutionScope<'a, 'b, RT, E> {
     pub fn error_stream(&mut self, id: uuid::Uuid, error: anyhow::Error) -> anyhow::Result<()> {
         let state = self.state_mut()?;
@@ -0,0 +1,66 @@
+use anyhow::Context;
+use common::sync::spsc;
+use deno_core::{
+    serde_v8,
+    v8,
+};
+use serde::Deserialize;
+use url::Url;
+use uuid::Uuid;
+
+use super::OpProvider;
+use crate::{
+    environment::{
+        helpers::{
+            with_argument_error,
+            ArgName,
+        },
+        AsyncOpRequest,
+    },
+    request_scope::StreamListener,
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct WebSocketConnectArgs {
+    url: String,
+    protocols: Vec<String>,
+    /// Messages to send, framed as in `environment::action::websocket`.
+    outgoing_stream_id: Uuid,
+}
+
+pub fn async_op_websocket_connect<'b, P: OpProvider<'b>>(
+    provider: &mut P,
+    args: v8::FunctionCallbackArguments,
+    resolver: v8::Global<v8::PromiseResolver>,
+) -> anyhow::Result<()> {
+    let WebSocketConnectArgs {
+        url,
+        protocols,
+        outgoing_stream_id,
+    } = serde_v8::from_v8(provider.scope(), args.get(1))?;
+    let url = with_argument_error("WebSocket", || {
+        let url: Url = url.parse().context(ArgName("url"))?;
+        anyhow::ensure!(
+            matches!(url.scheme(), "ws" | "wss"),
+            "URL scheme must be ws or wss, not {}",
+            url.scheme()
+        );
+        Ok(url)
+    })?;
+    let (outgoing_sender, outgoing) = spsc::unbounded_channel();
+    provider.new_stream_listener(
+        outgoing_stream_id,
+        StreamListener::RustStream(outgoing_sender),
+    )?;
+    let incoming_stream_id = provider.create_stream()?;
+    provider.start_async_op(
+        AsyncOpRequest::WebSocketConnect {
+            url,
+            protocols,
+            incoming_stream_id,
+            outgoing,
+        },
+        resolver,
+    )
+}
@@ -4,6 +4,7 @@ use std::{
         VecDeque,
     },
     marker::PhantomData,
+    sync::Arc,
 };
 
 use anyhow::anyhow;
@@ -110,6 +111,9 @@ pub struct TextDecoderResource {
 pub struct ReadableStream {
     pub parts: WithHeapSize<VecDeque<uuid::Uuid>>,
     pub done: bool,
+    /// Set for streams that JavaScript shouldn't write to faster than Rust
+    /// consumes them.
+    pub capacity: Option<StreamCapacity>,
 }
 
 impl HeapSize for ReadableStream {
@@ -118,6 +122,48 @@ impl HeapSize for ReadableStream {
     }
 }
 
+/// Bounds how many bytes of a stream can be buffered between JavaScript and
+/// Rust. JavaScript reserves capacity for each chunk before extending the
+/// stream, and the Rust consumer releases it as it reads the chunk.
+#[derive(Clone, Debug)]
+pub struct StreamCapacity {
+    semaphore: Arc<tokio::sync::Semaphore>,
+    max_buffered_bytes: usize,
+}
+
+impl StreamCapacity {
+    pub fn new(max_buffered_bytes: u32) -> Self {
+        let max_buffered_bytes = max_buffered_bytes as usize;
+        Self {
+            semaphore: Arc::new(tokio::sync::Semaphore::new(max_buffered_bytes)),
+            max_buffered_bytes,
+        }
+    }
+
+    /// Wait until a chunk of `bytes` fits in the buffer. Chunks larger than
+    /// the buffer wait for it to empty, rather than forever.
+    pub async fn reserve(&self, bytes: usize) -> anyhow::Result<()> {
+        let permits = bytes.min(self.max_buffered_bytes) as u32;
+        self.semaphore
+            .acquire_many(permits)
+            .await
+            .map_err(|_| anyhow!("Stream was closed by its reader"))?
+            .forget();
+        Ok(())
+    }
+
+    /// Release the capacity reserved for a chunk of `bytes` that was read.
+    pub fn release(&self, bytes: usize) {
+        self.semaphore
+            .add_permits(bytes.min(self.max_buffered_bytes));
+    }
+
+    /// Fail current and future reservations, when the reader goes away.
+    pub fn close(&self) {
+        self.semaphore.close();
+    }
+}
+
 pub enum StreamListener {
     JsPromise(v8::Global<v8::PromiseResolver>),
     RustStream(spsc::UnboundedSender<anyhow::Result<bytes::Bytes>>),
@@ -145,6 +191,23 @@ impl<RT: Runtime, E: IsolateEnvironment<RT>> RequestState<RT, E> {
         Ok(uuid)
     }
 
+    pub fn limit_stream(&mut self, id: uuid::Uuid, max_buffered_bytes: u32) -> anyhow::Result<()> {
+        self.streams.mutate(&id, |stream| -> anyhow::Result<()> {
+            let Some(Ok(stream)) = stream else {
+                anyhow::bail!("unrecognized stream id {id}");
+            };
+            stream.capacity = Some(StreamCapacity::new(max_buffered_bytes));
+            Ok(())
+        })
+    }
+
+    pub fn stream_capacity(&self, id: uuid::Uuid) -> anyhow::Result<Option<StreamCapacity>> {
+        match self.streams.get(&id) {
+            Some(Ok(stream)) => Ok(stream.capacity.clone()),
+            _ => anyhow::bail!("unrecognized stream id {id}"),
+        }
+    }
+
     pub fn create_text_decoder(
         &mut self,
         decoder: TextDecoderResource,
@@ -82,7 +82,11 @@ declare_strings!(
     lookup,
     op,
     path,
+    region,
+    requiredClaims,
     runRequest,
     setup,
     syscall,
+    timeoutMs,
+    warmInstances,
 );
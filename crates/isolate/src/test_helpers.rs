@@ -63,6 +63,8 @@ use database::{
     vector_index_worker::flusher::backfill_vector_indexes,
     Database,
     FollowerRetentionManager,
+    HybridSearch,
+    HybridSearchResult,
     IndexModel,
     IndexWorker,
     Transaction,
@@ -82,6 +84,7 @@ use keybroker::{
 };
 use maplit::btreemap;
 use model::{
+    action_checkpoints::ActionCheckpointsModel,
     components::handles::FunctionHandlesModel,
     config::{
         module_loader::{
@@ -98,7 +101,10 @@ use model::{
         types::FileStorageEntry,
         FileStorageId,
     },
-    scheduled_jobs::VirtualSchedulerModel,
+    scheduled_jobs::{
+        types::IdempotencyKey,
+        VirtualSchedulerModel,
+    },
     source_packages::{
         types::SourcePackage,
         upload_download::upload_package,
@@ -1347,6 +1353,7 @@ impl<RT: Runtime, P: Persistence + Clone> ActionCallbacks for UdfTest<RT, P> {
         udf_args: Vec<JsonValue>,
         scheduled_ts: UnixTimestamp,
         context: ExecutionContext,
+        idempotency_key: Option<IdempotencyKey>,
     ) -> anyhow::Result<DeveloperDocumentId> {
         let mut tx: database::Transaction<RT> = self.database.begin(identity).await?;
         let (scheduled_path, udf_args) = validate_schedule_args(
@@ -1360,9 +1367,25 @@ impl<RT: Runtime, P: Persistence + Clone> ActionCallbacks for UdfTest<RT, P> {
         )
         .await?;
 
-        let virtual_id = VirtualSchedulerModel::new(&mut tx, scheduling_component.into())
-            .schedule(scheduled_path, udf_args, scheduled_ts, context)
-            .await?;
+        let mut model = VirtualSchedulerModel::new(&mut tx, scheduling_component.into());
+        let virtual_id = match idempotency_key {
+            Some(idempotency_key) => {
+                model
+                    .schedule_idempotent(
+                        scheduled_path,
+                        udf_args,
+                        scheduled_ts,
+                        context,
+                        idempotency_key,
+                    )
+                    .await?
+            },
+            None => {
+                model
+                    .schedule(scheduled_path, udf_args, scheduled_ts, context)
+                    .await?
+            },
+        };
         self.database.commit(tx).await?;
 
         Ok(virtual_id)
@@ -1381,6 +1404,33 @@ impl<RT: Runtime, P: Persistence + Clone> ActionCallbacks for UdfTest<RT, P> {
         Ok(())
     }
 
+    async fn load_action_checkpoint(
+        &self,
+        identity: Identity,
+        job_id: DeveloperDocumentId,
+    ) -> anyhow::Result<Option<JsonValue>> {
+        let mut tx = self.database.begin(identity).await?;
+        ActionCheckpointsModel::new(&mut tx)
+            .get(job_id)
+            .await?
+            .map(|checkpoint| anyhow::Ok(serde_json::from_str(&checkpoint.value)?))
+            .transpose()
+    }
+
+    async fn save_action_checkpoint(
+        &self,
+        identity: Identity,
+        job_id: DeveloperDocumentId,
+        value: JsonValue,
+    ) -> anyhow::Result<()> {
+        let mut tx = self.database.begin(identity).await?;
+        ActionCheckpointsModel::new(&mut tx)
+            .save(job_id, serde_json::to_string(&value)?)
+            .await?;
+        self.database.commit(tx).await?;
+        Ok(())
+    }
+
     async fn vector_search(
         &self,
         identity: Identity,
@@ -1390,6 +1440,15 @@ impl<RT: Runtime, P: Persistence + Clone> ActionCallbacks for UdfTest<RT, P> {
         self.database.vector_search(identity, query).await
     }
 
+    async fn hybrid_search(
+        &self,
+        identity: Identity,
+        query: JsonValue,
+    ) -> anyhow::Result<(Vec<HybridSearchResult>, FunctionUsageStats)> {
+        let query = HybridSearch::try_from(query)?;
+        self.database.hybrid_search(identity, query).await
+    }
+
     async fn lookup_function_handle(
         &self,
         identity: Identity,
@@ -1,6 +1,7 @@
 use std::{
     collections::BTreeMap,
     str::FromStr,
+    time::Duration,
 };
 
 use common::{
@@ -28,6 +29,7 @@ use model::{
         module_versions::{
             AnalyzedFunction,
             AnalyzedSourcePosition,
+            RequiredClaims,
             Visibility,
         },
         ModuleModel,
@@ -36,6 +38,7 @@ use model::{
 };
 use pretty_assertions::assert_eq;
 use runtime::testing::TestRuntime;
+use serde_json::json;
 use value::{
     assert_obj,
     ConvexArray,
@@ -173,15 +176,21 @@ async fn test_analyze_module(rt: TestRuntime) -> anyhow::Result<()> {
         CronIdentifier::from_str("weekly re-engagement email")? => CronSpec {
             udf_path: "crons.js:addOne".parse()?,
             udf_args: args.clone(),
-            cron_schedule: CronSchedule::Weekly { day_of_week: 2, hour_utc: 17, minute_utc: 30 }},
+            cron_schedule: CronSchedule::Weekly { day_of_week: 2, hour_utc: 17, minute_utc: 30 },
+            time_zone: None,
+            jitter: None },
         CronIdentifier::from_str("add one every hour")? => CronSpec {
             udf_path: "crons.js:addOne".parse()?,
             udf_args: args.clone(),
-            cron_schedule: CronSchedule::Interval{ seconds: 3600 * 24 * 7 } },
+            cron_schedule: CronSchedule::Interval{ seconds: 3600 * 24 * 7 },
+            time_zone: None,
+            jitter: None },
         CronIdentifier::from_str("clear presence data")? => CronSpec {
             udf_path: "crons.js:addOne".parse()?,
             udf_args: args,
-            cron_schedule: CronSchedule::Interval{ seconds: 300} },
+            cron_schedule: CronSchedule::Interval{ seconds: 300},
+            time_zone: None,
+            jitter: None },
         ).into()),
     );
 
@@ -393,6 +402,39 @@ async fn test_analyze_internal_function(rt: TestRuntime) -> anyhow::Result<()> {
                 ArgsValidator::Unvalidated,
                 ReturnsValidator::Unvalidated,
             )?,
+            AnalyzedFunction::new(
+                "slowInternalQuery".parse()?,
+                // Don't check line numbers since those change on every `convex/server`
+                // change.
+                analyzed_module.functions[4].pos.clone(),
+                UdfType::Query,
+                Some(Visibility::Internal),
+                ArgsValidator::Unvalidated,
+                ReturnsValidator::Unvalidated,
+            )?
+            .with_timeout(Some(Duration::from_secs(5))),
+            AnalyzedFunction::new(
+                "warmQuery".parse()?,
+                // Don't check line numbers since those change on every `convex/server`
+                // change.
+                analyzed_module.functions[5].pos.clone(),
+                UdfType::Query,
+                Some(Visibility::Public),
+                ArgsValidator::Unvalidated,
+                ReturnsValidator::Unvalidated,
+            )?
+            .with_warm_instances(Some(2)),
+            AnalyzedFunction::new(
+                "adminMutation".parse()?,
+                // Don't check line numbers since those change on every `convex/server`
+                // change.
+                analyzed_module.functions[6].pos.clone(),
+                UdfType::Mutation,
+                Some(Visibility::Public),
+                ArgsValidator::Unvalidated,
+                ReturnsValidator::Unvalidated,
+            )?
+            .with_required_claims(Some(RequiredClaims::try_from(json!({ "role": "admin" }))?)),
         ],
     );
     let source_mapped = analyzed_module.source_mapped.unwrap();
@@ -447,6 +489,45 @@ async fn test_analyze_internal_function(rt: TestRuntime) -> anyhow::Result<()> {
                 ArgsValidator::Unvalidated,
                 ReturnsValidator::Unvalidated,
             )?,
+            AnalyzedFunction::new(
+                "slowInternalQuery".parse()?,
+                Some(AnalyzedSourcePosition {
+                    path: "internal.js".parse()?,
+                    start_lineno: 32,
+                    start_col: analyzed_module.functions[4].pos.as_ref().unwrap().start_col,
+                }),
+                UdfType::Query,
+                Some(Visibility::Internal),
+                ArgsValidator::Unvalidated,
+                ReturnsValidator::Unvalidated,
+            )?
+            .with_timeout(Some(Duration::from_secs(5))),
+            AnalyzedFunction::new(
+                "warmQuery".parse()?,
+                Some(AnalyzedSourcePosition {
+                    path: "internal.js".parse()?,
+                    start_lineno: 39,
+                    start_col: analyzed_module.functions[5].pos.as_ref().unwrap().start_col,
+                }),
+                UdfType::Query,
+                Some(Visibility::Public),
+                ArgsValidator::Unvalidated,
+                ReturnsValidator::Unvalidated,
+            )?
+            .with_warm_instances(Some(2)),
+            AnalyzedFunction::new(
+                "adminMutation".parse()?,
+                Some(AnalyzedSourcePosition {
+                    path: "internal.js".parse()?,
+                    start_lineno: 46,
+                    start_col: analyzed_module.functions[6].pos.as_ref().unwrap().start_col,
+                }),
+                UdfType::Mutation,
+                Some(Visibility::Public),
+                ArgsValidator::Unvalidated,
+                ReturnsValidator::Unvalidated,
+            )?
+            .with_required_claims(Some(RequiredClaims::try_from(json!({ "role": "admin" }))?)),
         ],
     );
     Ok(())
@@ -488,6 +569,28 @@ async fn test_analyze_developer_errors(rt: TestRuntime) -> anyhow::Result<()> {
             "async function test(){}; await test();",
             "Top-level awaits in source files are unsupported",
         ),
+        // Only internal queries and mutations can override their timeout.
+        (
+            "export const q = Object.assign(() => {}, { isQuery: true, isPublic: true, timeoutMs: \
+             5000 });",
+            "can't set timeoutMs",
+        ),
+        // Actions can't be kept warm, and queries can only ask for a few instances.
+        (
+            "export const a = Object.assign(() => {}, { isAction: true, isPublic: true, \
+             warmInstances: 1 });",
+            "can't set warmInstances",
+        ),
+        (
+            "export const q = Object.assign(() => {}, { isQuery: true, isPublic: true, \
+             warmInstances: 1000 });",
+            "warmInstances must be an integer",
+        ),
+        (
+            "export const m = Object.assign(() => {}, { isMutation: true, isPublic: true, \
+             requiredClaims: { role: ['admin'] } });",
+            "requiredClaims.role must be a string, number or boolean",
+        ),
     ];
 
     for (source, expected_error) in cases {
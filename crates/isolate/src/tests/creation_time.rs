@@ -64,3 +64,23 @@ async fn test_creation_time_between_system_time(rt: TestRuntime) -> anyhow::Resu
     })
     .await
 }
+
+#[convex_macro::test_runtime]
+async fn test_transaction_timestamps_are_monotonic(rt: TestRuntime) -> anyhow::Result<()> {
+    UdfTest::run_test_with_isolate(rt, async move |t: UdfTestType| {
+        let mut prev_ts = None;
+        for _ in 0..3 {
+            must_let!(let ConvexValue::Int64(ts) = t
+                .mutation("creationTime:insertAndReadTimestamp", assert_obj!())
+                .await?);
+            // Each mutation reads from a snapshot that includes the previous
+            // one's commit.
+            if let Some(prev_ts) = prev_ts {
+                assert!(prev_ts < ts);
+            }
+            prev_ts = Some(ts);
+        }
+        Ok(())
+    })
+    .await
+}
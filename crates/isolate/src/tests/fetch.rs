@@ -115,6 +115,13 @@ async fn test_fetch_basic(rt: ProdRuntime) -> anyhow::Result<()> {
                 response
             }),
         )
+        .route(
+            "/count_bytes",
+            post(|req: Request<Body>| async {
+                let bytes = req.into_body().collect().await.unwrap().to_bytes();
+                bytes.len().to_string()
+            }),
+        )
         .route("/assets/hello.txt", get(redirect_handler))
         .route("/post_redirect_to_get", post(redirect_handler))
         .route("/a/b/c", get(redirect_handler))
@@ -18,8 +18,10 @@ use common::{
     version::Version,
 };
 use keybroker::{
+    testing::TestUserIdentity,
     AdminIdentity,
     Identity,
+    UserIdentity,
 };
 use model::{
     config::ConfigModel,
@@ -198,3 +200,53 @@ async fn test_udf_visibility(rt: TestRuntime) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[convex_macro::test_runtime]
+async fn test_required_claims(rt: TestRuntime) -> anyhow::Result<()> {
+    let t = UdfTest::default(rt).await?;
+    let admin_mutation = PublicFunctionPath::Component(CanonicalizedComponentFunctionPath {
+        component: ComponentPath::test_user(),
+        udf_path: CanonicalizedUdfPath::from_str("internal.js:adminMutation")?,
+    });
+    let validate = |identity: Identity, allowed_visibility: AllowedVisibility| {
+        let database = t.database.clone();
+        let path = admin_mutation.clone();
+        async move {
+            let mut tx = database.begin(identity).await?;
+            ValidatedPathAndArgs::new(
+                allowed_visibility,
+                &mut tx,
+                path,
+                ConvexArray::empty(),
+                UdfType::Mutation,
+            )
+            .await
+        }
+    };
+
+    let mut member = UserIdentity::test();
+    member
+        .attributes
+        .custom_claims
+        .insert("role".to_string(), "\"member\"".to_string());
+    must_let!(let Ok(Err(js_error)) =
+        validate(Identity::user(member.clone()), AllowedVisibility::PublicOnly).await);
+    assert!(js_error.message.starts_with("Unauthorized"), "{js_error:?}");
+    must_let!(let Ok(Err(_)) = validate(Identity::Unknown, AllowedVisibility::PublicOnly).await);
+    // Other functions can still call it.
+    must_let!(let Ok(Ok(_)) = validate(Identity::user(member), AllowedVisibility::All).await);
+
+    let mut admin = UserIdentity::test();
+    admin
+        .attributes
+        .custom_claims
+        .insert("role".to_string(), "[\"member\", \"admin\"]".to_string());
+    must_let!(let Ok(Ok(_)) =
+        validate(Identity::user(admin), AllowedVisibility::PublicOnly).await);
+    let instance_admin = Identity::InstanceAdmin(AdminIdentity::new_for_test_only(
+        "happy-animal-123".to_string(),
+        MemberId(123),
+    ));
+    must_let!(let Ok(Ok(_)) = validate(instance_admin, AllowedVisibility::PublicOnly).await);
+    Ok(())
+}
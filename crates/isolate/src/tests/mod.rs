@@ -32,3 +32,4 @@ mod unicode;
 mod user_error;
 mod values;
 mod vector_search;
+mod websocket;
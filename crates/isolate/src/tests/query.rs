@@ -318,6 +318,69 @@ async fn test_index_ranges(rt: TestRuntime) -> anyhow::Result<()> {
     }).await
 }
 
+#[convex_macro::test_runtime]
+async fn test_aggregate(rt: TestRuntime) -> anyhow::Result<()> {
+    UdfTest::run_test_with_isolate2(rt, async move |t: UdfTestType| {
+        add_index(&t).await?;
+        t.backfill_indexes().await?;
+        t.mutation("indexing:insert", assert_obj!("a" => 1, "b" => 1))
+            .await?;
+        t.mutation("indexing:insert", assert_obj!("a" => 1, "b" => 2))
+            .await?;
+        t.mutation("indexing:insertMissingField", assert_obj!("a" => 2))
+            .await?;
+        t.mutation("indexing:insert", assert_obj!("a" => 2, "b" => 5))
+            .await?;
+
+        let groups = t.query("indexing:aggregateByA", assert_obj!()).await?;
+        assert_eq!(
+            groups,
+            assert_val!([
+                {
+                    "group" => 1.0,
+                    "values" => {
+                        "avgB" => 1.5,
+                        "count" => 2.0,
+                        "maxB" => 2.0,
+                        "sumB" => 3.0,
+                        "withB" => 2.0,
+                    },
+                },
+                {
+                    "group" => 2.0,
+                    "values" => {
+                        "avgB" => 5.0,
+                        "count" => 2.0,
+                        "maxB" => 5.0,
+                        "sumB" => 5.0,
+                        "withB" => 1.0,
+                    },
+                },
+            ])
+        );
+
+        // Ungrouped aggregations have a single result, even over no documents.
+        let groups = t
+            .query("indexing:aggregateRange", assert_obj!("a" => 2))
+            .await?;
+        assert_eq!(groups, assert_val!([{ "values" => { "minB" => 5.0 } }]));
+        let groups = t
+            .query("indexing:aggregateRange", assert_obj!("a" => 3))
+            .await?;
+        assert_eq!(groups, assert_val!([{ "values" => { "minB" => null } }]));
+
+        let error = t
+            .query_js_error("indexing:aggregateByNonIndexedField", assert_obj!())
+            .await?;
+        assert_contains(
+            &error,
+            "Grouped aggregations must use `withIndex` with an index on the group-by field.",
+        );
+        Ok(())
+    })
+    .await
+}
+
 #[convex_macro::test_runtime]
 async fn test_index_range_errors(rt: TestRuntime) -> anyhow::Result<()> {
     async fn assert_error_contains(
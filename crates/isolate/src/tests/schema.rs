@@ -1,4 +1,5 @@
 use common::{
+    bootstrap_model::index::text_index::TextIndexAnalyzer,
     object_validator,
     runtime::Runtime,
     schemas::{
@@ -288,7 +289,8 @@ async fn test_eval_schema(rt: TestRuntime) -> anyhow::Result<()> {
                 search_index.clone() => SearchIndexSchema::new(
                   search_index,
                   "title".parse()?,
-                  btreeset!{"is_deleted".parse()?, "workspace_id".parse()?}
+                  btreeset!{"is_deleted".parse()?, "workspace_id".parse()?},
+                  TextIndexAnalyzer::default(),
                 )?
                },
                vector_indexes: btreemap!(),
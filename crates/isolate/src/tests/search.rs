@@ -5,7 +5,10 @@ use std::{
 
 use common::{
     assert_obj,
-    bootstrap_model::index::IndexMetadata,
+    bootstrap_model::index::{
+        text_index::TextIndexAnalyzer,
+        IndexMetadata,
+    },
     testing::{
         assert_contains,
         TestPersistence,
@@ -23,6 +26,7 @@ use search::{
     MAX_QUERY_TERMS,
 };
 use value::{
+    assert_val,
     ConvexArray,
     TableName,
 };
@@ -40,6 +44,7 @@ async fn add_text_index(t: &UdfTest<TestRuntime, TestPersistence>) -> anyhow::Re
         "messages.by_body".parse()?,
         "body".parse()?,
         btreeset! { "filterField".parse()?},
+        TextIndexAnalyzer::default(),
     ))
     .await
 }
@@ -220,6 +225,32 @@ async fn test_search_for_pending_document(rt: TestRuntime) -> anyhow::Result<()>
     }).await
 }
 
+#[convex_macro::test_runtime]
+async fn test_search_facets(rt: TestRuntime) -> anyhow::Result<()> {
+    UdfTest::run_test_with_isolate2(rt, async move |t: UdfTestType| {
+        add_and_backfill_text_index(&t).await?;
+        t.mutation("search:populateFacets", assert_obj!()).await?;
+
+        // Only the results matching the search are counted, and results
+        // missing the field are counted without a value.
+        let facets = t
+            .query("search:searchFacets", assert_obj!("query" => "a"))
+            .await?;
+        assert_eq!(
+            facets,
+            assert_val!({
+                "filterField" => [
+                    {"value" => "news", "count" => 2.0},
+                    {"count" => 1.0},
+                    {"value" => "sports", "count" => 1.0},
+                ],
+            })
+        );
+        Ok(())
+    })
+    .await
+}
+
 /// Tests for all of the search error cases.
 
 #[convex_macro::test_runtime]
@@ -408,3 +439,20 @@ async fn test_search_query_scanned_too_many_documents(rt: TestRuntime) -> anyhow
         Ok(())
     }).await
 }
+
+#[convex_macro::test_runtime]
+async fn test_facets_on_non_filter_field(rt: TestRuntime) -> anyhow::Result<()> {
+    UdfTest::run_test_with_isolate2(rt, async move |t: UdfTestType| {
+        add_and_backfill_text_index(&t).await?;
+        let e = t
+            .query_js_error("search:facetsOnNonFilterField", assert_obj!())
+            .await?;
+        assert_contains(
+            &e,
+            "Can't count facets for body, which isn't one of the filter fields of search index \
+             messages.by_body.",
+        );
+        Ok(())
+    })
+    .await
+}
@@ -1,7 +1,11 @@
 use common::{
     assert_obj,
     bootstrap_model::index::{
-        vector_index::VectorDimensions,
+        text_index::TextIndexAnalyzer,
+        vector_index::{
+            VectorDimensions,
+            VectorDistanceMetric,
+        },
         IndexMetadata,
     },
     testing::{
@@ -31,6 +35,7 @@ async fn add_vector_index(t: &UdfTest<TestRuntime, TestPersistence>) -> anyhow::
         "vector".parse()?,
         VectorDimensions::try_from(4)?,
         btreeset! { "filterA".parse()?, "filterB".parse()? },
+        VectorDistanceMetric::default(),
     );
     IndexModel::new(&mut tx)
         .add_application_index(TableNamespace::test_user(), index)
@@ -49,6 +54,23 @@ async fn add_and_backfill_vector_index(
     Ok(())
 }
 
+async fn add_and_backfill_hybrid_indexes(
+    t: &UdfTest<TestRuntime, TestPersistence>,
+) -> anyhow::Result<()> {
+    add_vector_index(t).await?;
+    t.add_index(IndexMetadata::new_backfilling_text_index(
+        "vectorTable.text".parse()?,
+        "text".parse()?,
+        btreeset! { "filterA".parse()? },
+        TextIndexAnalyzer::default(),
+    ))
+    .await?;
+    t.backfill_text_indexes().await?;
+    t.backfill_vector_indexes().await?;
+
+    Ok(())
+}
+
 #[convex_macro::test_runtime]
 async fn test_multi_field_filter(rt: TestRuntime) -> anyhow::Result<()> {
     common::testing::init_test_logging();
@@ -121,3 +143,47 @@ async fn test_no_filter(rt: TestRuntime) -> anyhow::Result<()> {
     assert_eq!(String::from(r), "success".to_string());
     Ok(())
 }
+
+#[convex_macro::test_runtime]
+async fn test_hybrid_search(rt: TestRuntime) -> anyhow::Result<()> {
+    common::testing::init_test_logging();
+
+    let t = action_udf_test(rt).await?;
+
+    add_and_backfill_hybrid_indexes(&t).await?;
+    t.mutation("vector_search:populate", assert_obj!()).await?;
+
+    must_let!(let ConvexValue::String(r) = t.action("vector_search:hybridSearch", assert_obj!()).await?);
+    assert_eq!(String::from(r), "success".to_string());
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_hybrid_search_with_filters(rt: TestRuntime) -> anyhow::Result<()> {
+    common::testing::init_test_logging();
+
+    let t = action_udf_test(rt).await?;
+
+    add_and_backfill_hybrid_indexes(&t).await?;
+    t.mutation("vector_search:populate", assert_obj!()).await?;
+
+    must_let!(let ConvexValue::String(r) = t.action("vector_search:hybridSearchWithFilters", assert_obj!()).await?);
+    assert_eq!(String::from(r), "success".to_string());
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_hybrid_search_invalid_weights(rt: TestRuntime) -> anyhow::Result<()> {
+    common::testing::init_test_logging();
+
+    let t = action_udf_test(rt).await?;
+
+    add_and_backfill_hybrid_indexes(&t).await?;
+    t.mutation("vector_search:populate", assert_obj!()).await?;
+
+    let error = t
+        .action_js_error("vector_search:hybridSearchInvalidWeights", assert_obj!())
+        .await?;
+    assert_contains(&error, "at least one must be positive");
+    Ok(())
+}
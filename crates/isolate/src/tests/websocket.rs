@@ -0,0 +1,73 @@
+use common::{
+    assert_obj,
+    runtime::Runtime,
+    testing::assert_contains,
+};
+use futures::{
+    SinkExt,
+    StreamExt,
+};
+use must_let::must_let;
+use runtime::{
+    prod::ProdRuntime,
+    testing::TestRuntime,
+};
+use tokio::net::TcpListener;
+use tokio_tungstenite::tungstenite::handshake::server::{
+    Request,
+    Response,
+};
+use value::ConvexValue;
+
+use crate::test_helpers::UdfTest;
+
+/// Echo text and binary messages back, accepting the "chat" subprotocol.
+async fn serve_echo(listener: TcpListener) {
+    while let Ok((stream, _)) = listener.accept().await {
+        let accept_chat = |_: &Request, mut response: Response| {
+            response
+                .headers_mut()
+                .insert("sec-websocket-protocol", "chat".parse().unwrap());
+            Ok(response)
+        };
+        let Ok(mut ws) = tokio_tungstenite::accept_hdr_async(stream, accept_chat).await else {
+            continue;
+        };
+        while let Some(Ok(message)) = ws.next().await {
+            if (message.is_text() || message.is_binary()) && ws.send(message).await.is_err() {
+                break;
+            }
+        }
+    }
+}
+
+#[convex_macro::test_runtime]
+async fn test_websocket_not_allowed_in_queries(rt: TestRuntime) -> anyhow::Result<()> {
+    let t = UdfTest::default(rt).await?;
+    assert_contains(
+        &t.query_js_error("websocket:fromQuery", assert_obj!())
+            .await?,
+        "Can't use WebSocket in queries and mutations. Please consider using an action.",
+    );
+    Ok(())
+}
+
+#[convex_macro::prod_rt_test]
+async fn test_websocket_echo(rt: ProdRuntime) -> anyhow::Result<()> {
+    let listener = TcpListener::bind("127.0.0.1:4548").await?;
+    rt.spawn("test_websocket_server", serve_echo(listener));
+    let t = UdfTest::default(rt).await?;
+
+    must_let!(let ConvexValue::String(r) = t.action("websocket:echo", assert_obj!()).await?);
+    assert_eq!(
+        String::from(r),
+        r#"{"protocol":"chat","text":"hello","binary":[1,2,3],"code":1000,"reason":"done","wasClean":true,"readyState":3}"#,
+    );
+
+    must_let!(let ConvexValue::String(r) = t.action("websocket:invalidUrl", assert_obj!()).await?);
+    assert_eq!(String::from(r), "SyntaxError,SyntaxError,SyntaxError");
+
+    must_let!(let ConvexValue::String(r) = t.action("websocket:connectionRefused", assert_obj!()).await?);
+    assert_eq!(String::from(r), "1006 false");
+    Ok(())
+}
@@ -2,11 +2,12 @@ use std::env;
 
 use common::types::MemberId;
 use keybroker::{
+    AdminKeyScope,
     InstanceSecret,
     KeyBroker,
 };
 
-const USAGE: &str = "USAGE: ./generate_key <instance_name> <instance_secret> [member_id]";
+const USAGE: &str = "USAGE: ./generate_key <instance_name> <instance_secret> [member_id] [scope]";
 
 fn main() -> anyhow::Result<()> {
     let instance_name = env::args().nth(1).ok_or_else(|| anyhow::anyhow!(USAGE))?;
@@ -16,10 +17,14 @@ fn main() -> anyhow::Result<()> {
         .unwrap_or_else(|| "0".to_owned())
         .parse::<u64>()
         .map_err(|_| anyhow::anyhow!(USAGE))?;
+    let scope: AdminKeyScope = env::args()
+        .nth(4)
+        .unwrap_or_else(|| "full".to_owned())
+        .parse()?;
     let instance_secret = InstanceSecret::try_from(&instance_secret_s[..])?;
 
     let broker = KeyBroker::new(&instance_name[..], instance_secret)?;
-    let admin_key = broker.issue_admin_key(MemberId(member_id));
+    let admin_key = broker.issue_scoped_admin_key(MemberId(member_id), scope);
     println!("Admin Key:\n{}", admin_key.as_str());
     let system_key = broker.issue_system_key();
     println!("System key:\n{}", system_key.as_str());
@@ -1,7 +1,13 @@
 use core::panic;
 use std::{
-    collections::BTreeMap,
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
     fmt,
+    iter,
+    str::FromStr,
+    sync::Arc,
     time::{
         Duration,
         SystemTime,
@@ -27,6 +33,7 @@ use common::{
         Runtime,
         UnixTimestamp,
     },
+    sha256::Sha256,
     types::{
         format_admin_key,
         remove_type_prefix_from_instance_name,
@@ -38,6 +45,7 @@ use common::{
         TeamId,
         UdfType,
     },
+    value::TableNumber,
 };
 use errors::ErrorMetadata;
 use openidconnect::{
@@ -47,6 +55,7 @@ use openidconnect::{
     },
     Nonce,
 };
+use parking_lot::RwLock;
 use pb::{
     convex_actions::ActionCallbackToken as ActionCallbackTokenProto,
     convex_cursor::{
@@ -65,7 +74,11 @@ use pb::{
             StoreFile as StoreFileProto,
         },
         AdminKey as AdminKeyProto,
+        AdminKeySecret as AdminKeySecretProto,
+        AdminSessionToken as AdminSessionTokenProto,
+        ReplicationToken as ReplicationTokenProto,
         StorageToken as StorageTokenProto,
+        UserSessionToken as UserSessionTokenProto,
     },
     convex_query_journal::InstanceQueryJournal as InstanceQueryJournalProto,
 };
@@ -91,6 +104,7 @@ use crate::{
     encryptor::Encryptor,
     metrics::{
         log_actions_token_expired,
+        log_replication_token_expired,
         log_store_file_auth_expired,
     },
     secret::InstanceSecret,
@@ -98,9 +112,18 @@ use crate::{
 
 const ACTION_KEY_VERSION: u8 = 2;
 const ADMIN_KEY_VERSION: u8 = 1;
+const SCOPED_ADMIN_KEY_VERSION: u8 = 2;
+const ADMIN_KEY_SECRET_VERSION: u8 = 1;
+// Session tokens are sent as admin keys, so their version can't be one an admin
+// key could have.
+const ADMIN_SESSION_TOKEN_VERSION: u8 = 3;
 const CURSOR_VERSION: u8 = 7;
 const STORE_FILE_AUTHZ_VERSION: u8 = 1;
 const QUERY_JOURNAL_VERSION: u8 = 7;
+const REPLICATION_TOKEN_VERSION: u8 = 3;
+// User session tokens are sent as ID tokens, but use a version no admin key or
+// admin session token has so they can't be mistaken for one another.
+const USER_SESSION_TOKEN_VERSION: u8 = 4;
 
 // Max delay from transaction start time -> key being issued that is tolerable.
 const MAX_TS_DELAY: Duration = Duration::from_secs(15);
@@ -109,6 +132,56 @@ const MAX_TS_DELAY: Duration = Duration::from_secs(15);
 pub struct KeyBroker {
     instance_name: String,
     encryptor: Encryptor,
+    admin_keys: Arc<RwLock<AdminKeyRing>>,
+    client_certificates: Arc<RwLock<AdminClientCertificates>>,
+}
+
+/// Admins who authenticate with a TLS client certificate instead of an admin
+/// key, shared by all clones of a `KeyBroker`.
+#[derive(Default)]
+struct AdminClientCertificates {
+    /// Member each trusted certificate authenticates as and what it may do,
+    /// by the common name of the certificate's subject.
+    principals: BTreeMap<String, (MemberId, AdminKeyScope)>,
+    /// Whether admin keys are rejected, so admins must use a certificate.
+    required: bool,
+}
+
+/// The secrets admin keys are checked against and the keys that have been
+/// revoked, shared by all clones of a `KeyBroker`. Admin keys, and the session
+/// tokens exchanged for them, are encrypted with the instance secret until
+/// it's rotated, and other tokens like cursors always are.
+#[derive(Default)]
+struct AdminKeyRing {
+    /// The secret new admin keys are encrypted with, or `None` for the
+    /// instance secret.
+    current: Option<Encryptor>,
+    /// Secrets replaced by `current`, and until when keys encrypted with them
+    /// are still accepted.
+    retired: Vec<(Encryptor, SystemTime)>,
+    /// Fingerprints of revoked keys, as returned by [`admin_key_fingerprint`].
+    revoked: BTreeSet<String>,
+}
+
+/// A secret admin keys were encrypted with before the instance secret was
+/// rotated. `None` is the instance secret itself.
+pub struct RetiredAdminKeySecret {
+    pub secret: Option<InstanceSecret>,
+    pub valid_until: SystemTime,
+}
+
+/// Identifies an admin key without revealing it, so it can be stored in
+/// config and shown to other admins. Ignores the "prod:"-style prefix the
+/// dashboard and CLI add to the instance name.
+pub fn admin_key_fingerprint(key: &str) -> String {
+    let key = match split_admin_key(key) {
+        Some((instance_name, encrypted_part)) => format_admin_key(
+            remove_type_prefix_from_instance_name(instance_name),
+            encrypted_part,
+        ),
+        None => key.to_string(),
+    };
+    Sha256::hash(key.as_bytes()).as_hex()[..32].to_string()
 }
 
 // This enum encodes a successful authentication decision, and its nontrivial
@@ -131,9 +204,7 @@ pub enum Identity {
 impl From<Identity> for AuthenticationToken {
     fn from(i: Identity) -> Self {
         match i {
-            Identity::User(identity) => {
-                AuthenticationToken::User(identity.original_token.to_string())
-            },
+            Identity::User(identity) => AuthenticationToken::User(identity.original_token),
             Identity::ActingUser(identity, user) => {
                 AuthenticationToken::Admin(identity.key, Some(user))
             },
@@ -198,13 +269,17 @@ impl Identity {
     }
 
     pub fn ensure_can_run_function(&self, udf_type: UdfType) -> anyhow::Result<()> {
-        // Everyone can run queries.
-        if udf_type == UdfType::Query {
-            return Ok(());
-        }
         match self {
             Identity::InstanceAdmin(admin_identity) | Identity::ActingUser(admin_identity, _) => {
-                if admin_identity.is_read_only() {
+                // Queries only need to read data, while other functions can
+                // also write it.
+                let allowed = if udf_type == UdfType::Query {
+                    admin_identity.scope.allows(AdminPermission::ReadData)
+                } else {
+                    admin_identity.scope.allows(AdminPermission::WriteData)
+                        && !admin_identity.is_read_only()
+                };
+                if !allowed {
                     anyhow::bail!(ErrorMetadata::forbidden(
                         "Unauthorized",
                         format!("You do not have permission to run {udf_type} functions.")
@@ -353,8 +428,9 @@ pub struct UserIdentity {
     pub issuer: String,
     pub expiration: SystemTime,
     pub attributes: UserIdentityAttributes,
-    // The original token this user identity was created from.
-    pub original_token: CoreIdToken,
+    // The original token this user identity was created from: an OpenID
+    // Connect ID token, or a session token from first-party auth.
+    pub original_token: String,
 }
 
 #[cfg(any(test, feature = "testing"))]
@@ -387,7 +463,7 @@ impl From<UserIdentity> for pb::convex_identity::UserIdentity {
             issuer: Some(issuer),
             expiration: Some(expiration.into()),
             attributes: Some(attributes.into()),
-            original_token: Some(original_token.to_string()),
+            original_token: Some(original_token),
         }
     }
 }
@@ -458,7 +534,7 @@ impl UserIdentity {
             subject: subject.clone(),
             issuer: issuer.clone(),
             expiration: claims.expiration().into(),
-            original_token: token,
+            original_token: token.to_string(),
             attributes: UserIdentityAttributes {
                 token_identifier: UserIdentifier::construct(&issuer, &subject),
                 subject: Some(subject),
@@ -509,8 +585,7 @@ impl UserIdentity {
             .try_into()?;
         let original_token = msg
             .original_token
-            .ok_or_else(|| anyhow::anyhow!("Missing original_token"))?
-            .parse()?;
+            .ok_or_else(|| anyhow::anyhow!("Missing original_token"))?;
         Ok(Self {
             subject,
             issuer,
@@ -520,6 +595,54 @@ impl UserIdentity {
         })
     }
 
+    /// The identity of a user signed in with the deployment's first-party
+    /// auth, with `token` being their session token.
+    pub fn from_session(
+        issuer: String,
+        subject: String,
+        email: String,
+        expiration: SystemTime,
+        token: String,
+    ) -> Self {
+        UserIdentity {
+            attributes: UserIdentityAttributes {
+                token_identifier: UserIdentifier::construct(&issuer, &subject),
+                subject: Some(subject.clone()),
+                issuer: Some(issuer.clone()),
+                email: Some(email),
+                ..Default::default()
+            },
+            subject,
+            issuer,
+            expiration,
+            original_token: token,
+        }
+    }
+
+    /// The identity for a deployment API key, which has no email or profile,
+    /// just the subject and claims configured for the key.
+    pub fn from_api_key(
+        issuer: String,
+        subject: String,
+        custom_claims: BTreeMap<String, String>,
+        expiration: SystemTime,
+        key: String,
+    ) -> Self {
+        UserIdentity {
+            attributes: UserIdentityAttributes {
+                token_identifier: UserIdentifier::construct(&issuer, &subject),
+                subject: Some(subject.clone()),
+                issuer: Some(issuer.clone()),
+                custom_claims,
+                ..Default::default()
+            },
+            subject,
+            issuer,
+            expiration,
+            original_token: key,
+        }
+    }
+
     pub fn is_expired(&self, current_time: SystemTime) -> bool {
         current_time >= self.expiration
     }
@@ -532,6 +655,124 @@ pub enum AdminIdentityPrincipal {
     Team(TeamId),
 }
 
+/// What an admin key may be used for. Keys issued before scopes existed are
+/// `Full`, or `ReadOnly` if they were issued as read-only keys.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub enum AdminKeyScope {
+    Full,
+    /// Can do anything a full key can, except write data.
+    ReadOnly,
+    /// Can push code, schemas and environment variables, but not run
+    /// functions or read data. Meant for CI pipelines.
+    DeployOnly,
+    /// Can run queries and read data from the dashboard routes.
+    DataRead,
+    /// Can run any function, import and delete data, and manage file storage,
+    /// which actions it runs may use.
+    DataWrite,
+    /// Can only manage file storage.
+    StorageOnly,
+}
+
+/// What an admin route or function call needs its key's scope to allow.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AdminPermission {
+    Deploy,
+    ReadData,
+    WriteData,
+    Storage,
+    /// Deployment settings like CORS, rate limits and backups, which only
+    /// unscoped keys can change.
+    Manage,
+}
+
+impl AdminKeyScope {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Full => "full",
+            Self::ReadOnly => "read-only",
+            Self::DeployOnly => "deploy-only",
+            Self::DataRead => "data-read",
+            Self::DataWrite => "data-write",
+            Self::StorageOnly => "storage-only",
+        }
+    }
+
+    /// Read-only keys can't write data even through routes their scope
+    /// allows.
+    pub fn is_read_only(&self) -> bool {
+        matches!(self, Self::ReadOnly | Self::DataRead)
+    }
+
+    pub fn allows(&self, permission: AdminPermission) -> bool {
+        match self {
+            Self::Full | Self::ReadOnly => true,
+            Self::DeployOnly => permission == AdminPermission::Deploy,
+            Self::DataRead => permission == AdminPermission::ReadData,
+            Self::DataWrite => matches!(
+                permission,
+                AdminPermission::ReadData | AdminPermission::WriteData | AdminPermission::Storage
+            ),
+            Self::StorageOnly => permission == AdminPermission::Storage,
+        }
+    }
+
+    /// Whether everything `other` allows is allowed by this scope too, so a
+    /// session token limited to `other` can be issued for a key with this
+    /// scope.
+    pub fn includes(&self, other: AdminKeyScope) -> bool {
+        let permissions = [
+            AdminPermission::Deploy,
+            AdminPermission::ReadData,
+            AdminPermission::WriteData,
+            AdminPermission::Storage,
+            AdminPermission::Manage,
+        ];
+        permissions
+            .into_iter()
+            .all(|permission| !other.allows(permission) || self.allows(permission))
+            && (!self.is_read_only() || other.is_read_only())
+    }
+
+    /// The scope of a key or identity without a `scope` field.
+    fn from_is_read_only(is_read_only: bool) -> Self {
+        if is_read_only {
+            Self::ReadOnly
+        } else {
+            Self::Full
+        }
+    }
+}
+
+impl FromStr for AdminKeyScope {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "full" => Ok(Self::Full),
+            "read-only" => Ok(Self::ReadOnly),
+            "deploy-only" => Ok(Self::DeployOnly),
+            "data-read" => Ok(Self::DataRead),
+            "data-write" => Ok(Self::DataWrite),
+            "storage-only" => Ok(Self::StorageOnly),
+            _ => anyhow::bail!(ErrorMetadata::bad_request(
+                "InvalidAdminKeyScope",
+                format!(
+                    "Invalid admin key scope {s:?}. Expected one of \"full\", \"read-only\", \
+                     \"deploy-only\", \"data-read\", \"data-write\" or \"storage-only\"."
+                ),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for AdminKeyScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 // Token indicating the possessor has authenticated as the admin for an
 // instance.
 #[derive(Clone, PartialEq, Eq, Hash)]
@@ -539,11 +780,11 @@ pub struct AdminIdentity {
     instance_name: String,
     principal: AdminIdentityPrincipal,
     key: String,
-    // is_read_only being true implies that this identity should not be able to write data.
+    // A read only scope implies that this identity should not be able to write data.
     // At the function level, read only admins are allowed to run queries but not mutations and
     // actions. At the database level, they are allowed to read data from user and system tables
     // but not write to them.
-    is_read_only: bool,
+    scope: AdminKeyScope,
 }
 
 impl From<AdminIdentity> for pb::convex_identity::AdminIdentity {
@@ -552,7 +793,7 @@ impl From<AdminIdentity> for pb::convex_identity::AdminIdentity {
             instance_name,
             principal,
             key,
-            is_read_only,
+            scope,
         }: AdminIdentity,
     ) -> Self {
         Self {
@@ -566,7 +807,8 @@ impl From<AdminIdentity> for pb::convex_identity::AdminIdentity {
                 ),
             },
             key: Some(key),
-            is_read_only,
+            is_read_only: scope.is_read_only(),
+            scope: Some(scope.to_string()),
         }
     }
 }
@@ -586,12 +828,15 @@ impl AdminIdentity {
             None => anyhow::bail!("Missing principal"),
         };
         let key = msg.key.ok_or_else(|| anyhow::anyhow!("Missing key"))?;
-        let is_read_only: bool = msg.is_read_only;
+        let scope = match msg.scope {
+            Some(scope) => scope.parse()?,
+            None => AdminKeyScope::from_is_read_only(msg.is_read_only),
+        };
         Ok(Self {
             instance_name,
             principal,
             key,
-            is_read_only,
+            scope,
         })
     }
 
@@ -605,7 +850,7 @@ impl AdminIdentity {
             instance_name,
             principal,
             key: access_token,
-            is_read_only,
+            scope: AdminKeyScope::from_is_read_only(is_read_only),
         }
     }
 
@@ -613,12 +858,22 @@ impl AdminIdentity {
         &self.principal
     }
 
+    /// Identifies the admin key without revealing it, so it can be stored in
+    /// config and shown to other admins.
+    pub fn key_fingerprint(&self) -> String {
+        admin_key_fingerprint(&self.key)
+    }
+
     // is_read_only being true implies that this identity should not be able to
     // write data. At the function level, read only admins are allowed to run
     // queries but not mutations and actions. At the database level, they are
     // allowed to read data from user and system tables but not write to them.
     pub fn is_read_only(&self) -> bool {
-        self.is_read_only
+        self.scope.is_read_only()
+    }
+
+    pub fn scope(&self) -> AdminKeyScope {
+        self.scope
     }
 }
 
@@ -634,7 +889,7 @@ impl Arbitrary for AdminIdentity {
             instance_name: "fake-instance-name".to_string(),
             principal,
             key,
-            is_read_only: false,
+            scope: AdminKeyScope::Full,
         })
     }
 }
@@ -646,7 +901,7 @@ impl AdminIdentity {
             instance_name,
             principal: AdminIdentityPrincipal::Member(member_id),
             key: "chocolate-charlies-cupcake".to_string(),
-            is_read_only: false,
+            scope: AdminKeyScope::Full,
         }
     }
 
@@ -677,9 +932,72 @@ impl fmt::Debug for SystemIdentity {
 /// Encrypted authorization to store a file
 #[derive(Debug, derive_more::Display)]
 pub struct StoreFileAuthorization(String);
+
+/// Limits on the file a `StoreFileAuthorization` can store, which the backend
+/// checks when the file is uploaded.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct StoreFileConstraints {
+    pub max_bytes: Option<u64>,
+    /// Content types, like `image/png` or `image/*`, that the file may have.
+    /// Empty if any content type is allowed.
+    pub allowed_content_types: Vec<String>,
+    /// How long after issuing the authorization expires, if sooner than
+    /// usual.
+    pub expires_in: Option<Duration>,
+}
+
+impl StoreFileConstraints {
+    pub fn check_size(&self, size: u64) -> anyhow::Result<()> {
+        if let Some(max_bytes) = self.max_bytes {
+            anyhow::ensure!(
+                size <= max_bytes,
+                ErrorMetadata::bad_request(
+                    "StorageFileTooLarge",
+                    format!("This upload URL only allows files of up to {max_bytes} bytes"),
+                )
+            );
+        }
+        Ok(())
+    }
+
+    pub fn check_content_type(&self, content_type: Option<&str>) -> anyhow::Result<()> {
+        if self.allowed_content_types.is_empty() {
+            return Ok(());
+        }
+        // Ignore parameters like `; charset=utf-8`.
+        let essence = content_type
+            .and_then(|content_type| content_type.split(';').next())
+            .map(|essence| essence.trim().to_ascii_lowercase());
+        let allowed = essence.as_deref().is_some_and(|essence| {
+            self.allowed_content_types.iter().any(|allowed| {
+                let allowed = allowed.to_ascii_lowercase();
+                match allowed.strip_suffix("/*") {
+                    Some(prefix) => essence
+                        .split_once('/')
+                        .is_some_and(|(type_, _)| type_ == prefix),
+                    None => essence == allowed,
+                }
+            })
+        });
+        if !allowed {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "StorageContentTypeNotAllowed",
+                format!(
+                    "This upload URL only allows files with content type {}",
+                    self.allowed_content_types.join(", ")
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
 /// Encrypted authorization to get a file
 #[derive(Debug, derive_more::Display)]
 pub struct GetFileAuthorization(String);
+/// Encrypted authorization for a follower deployment to stream this
+/// deployment's commits.
+#[derive(Debug, derive_more::Display)]
+pub struct ReplicationToken(String);
 
 pub fn cursor_parse_error() -> ErrorMetadata {
     ErrorMetadata::bad_request("InvalidCursor", "Failed to parse cursor")
@@ -690,9 +1008,101 @@ impl KeyBroker {
         Ok(Self {
             instance_name: instance_name.to_owned(),
             encryptor: Encryptor::new(instance_secret)?,
+            admin_keys: Arc::new(RwLock::new(AdminKeyRing::default())),
+            client_certificates: Arc::new(RwLock::new(AdminClientCertificates::default())),
         })
     }
 
+    /// Sets the secret new admin keys are encrypted with, `None` for the
+    /// instance secret, and the older secrets whose keys are still accepted
+    /// for a while.
+    pub fn set_admin_key_secrets(
+        &self,
+        current: Option<InstanceSecret>,
+        retired: Vec<RetiredAdminKeySecret>,
+    ) -> anyhow::Result<()> {
+        let encryptor = |secret: Option<InstanceSecret>| match secret {
+            Some(secret) => Encryptor::new(secret),
+            None => Ok(self.encryptor.clone()),
+        };
+        let current = current.map(Encryptor::new).transpose()?;
+        let retired = retired
+            .into_iter()
+            .map(|retired| Ok((encryptor(retired.secret)?, retired.valid_until)))
+            .collect::<anyhow::Result<_>>()?;
+        let mut admin_keys = self.admin_keys.write();
+        admin_keys.current = current;
+        admin_keys.retired = retired;
+        Ok(())
+    }
+
+    /// Sets the fingerprints of admin keys that are no longer accepted.
+    pub fn set_revoked_admin_keys(&self, fingerprints: BTreeSet<String>) {
+        self.admin_keys.write().revoked = fingerprints;
+    }
+
+    /// Sets the members that TLS client certificates authenticate as and
+    /// their scopes, by the common name of the certificate's subject, and
+    /// whether admin keys are rejected in favor of certificates.
+    pub fn set_admin_client_certificates(
+        &self,
+        principals: BTreeMap<String, (MemberId, AdminKeyScope)>,
+        required: bool,
+    ) {
+        *self.client_certificates.write() = AdminClientCertificates {
+            principals,
+            required,
+        };
+    }
+
+    /// Whether admins must authenticate with a TLS client certificate rather
+    /// than an admin key.
+    pub fn admin_client_certificate_required(&self) -> bool {
+        self.client_certificates.read().required
+    }
+
+    /// Checks the subject common name of a client certificate that the TLS
+    /// handshake already verified against the trusted CAs.
+    pub fn check_admin_client_certificate(&self, common_name: &str) -> anyhow::Result<Identity> {
+        let Some((member_id, scope)) = self
+            .client_certificates
+            .read()
+            .principals
+            .get(common_name)
+            .copied()
+        else {
+            anyhow::bail!(ErrorMetadata::unauthenticated(
+                "UnknownClientCertificate",
+                format!("The client certificate for {common_name} isn't mapped to an admin"),
+            ));
+        };
+        Ok(Identity::InstanceAdmin(AdminIdentity {
+            instance_name: self.instance_name.clone(),
+            principal: AdminIdentityPrincipal::Member(member_id),
+            // Not a valid admin key, so it can't be replayed as one.
+            key: format!("client-certificate:{common_name}"),
+            scope,
+        }))
+    }
+
+    /// Encrypts a rotated admin key secret with the instance secret, so it can
+    /// be stored in the database.
+    pub fn encrypt_admin_key_secret(&self, secret: &InstanceSecret) -> String {
+        self.encryptor.encode_proto(
+            ADMIN_KEY_SECRET_VERSION,
+            AdminKeySecretProto {
+                secret: secret.as_bytes().to_vec(),
+            },
+        )
+    }
+
+    pub fn decrypt_admin_key_secret(&self, encrypted: &str) -> anyhow::Result<InstanceSecret> {
+        let AdminKeySecretProto { secret } = self
+            .encryptor
+            .decode_proto(ADMIN_KEY_SECRET_VERSION, encrypted)?;
+        InstanceSecret::try_from(secret)
+    }
+
     pub fn dev() -> Self {
         Self::new(
             crate::DEV_INSTANCE_NAME,
@@ -710,15 +1120,211 @@ impl KeyBroker {
     }
 
     pub fn issue_admin_key(&self, member_id: MemberId) -> AdminKey {
-        AdminKey::new(self.issue_key(Some(member_id), false))
+        AdminKey::new(self.issue_key(Some(member_id), AdminKeyScope::Full))
     }
 
     pub fn issue_read_only_admin_key(&self, member_id: MemberId) -> AdminKey {
-        AdminKey::new(self.issue_key(Some(member_id), true))
+        AdminKey::new(self.issue_key(Some(member_id), AdminKeyScope::ReadOnly))
+    }
+
+    /// Issues an admin key that can only be used for what `scope` allows, like
+    /// a deploy-only key for CI that can't read production data.
+    pub fn issue_scoped_admin_key(&self, member_id: MemberId, scope: AdminKeyScope) -> AdminKey {
+        AdminKey::new(self.issue_key(Some(member_id), scope))
+    }
+
+    /// Exchanges the admin key `admin` authenticated with for a token that's
+    /// accepted in its place until `expires_at`, limited to `scope` if given.
+    /// The token is encrypted with the same secret as the key, so it stops
+    /// being accepted when the key does after the secret is rotated.
+    pub fn issue_admin_session_token(
+        &self,
+        admin: &AdminIdentity,
+        scope: Option<AdminKeyScope>,
+        expires_at: SystemTime,
+    ) -> anyhow::Result<AdminKey> {
+        let AdminIdentityPrincipal::Member(member_id) = &admin.principal else {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "InvalidSessionTokenParent",
+                "Session tokens can only be issued for admin keys",
+            ));
+        };
+        if self.is_admin_session_token(&admin.key) {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "InvalidSessionTokenParent",
+                "Session tokens can't be exchanged for other session tokens",
+            ));
+        }
+        let scope = scope.unwrap_or(admin.scope);
+        if !admin.scope.includes(scope) {
+            anyhow::bail!(ErrorMetadata::forbidden(
+                "AdminKeyScope",
+                format!(
+                    "A session token with scope {scope} can't be issued for a key with scope {}",
+                    admin.scope
+                ),
+            ));
+        }
+        let since_epoch = |time: SystemTime| {
+            time.duration_since(SystemTime::UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+        };
+        let proto = AdminSessionTokenProto {
+            member_id: member_id.0,
+            scope: scope.to_string(),
+            issued_s: since_epoch(SystemTime::now())?,
+            expires_s: since_epoch(expires_at)?,
+            key_fingerprint: admin.key_fingerprint(),
+        };
+        let parent_encrypted_part =
+            split_admin_key(&admin.key).map_or(admin.key.as_str(), |(_, key)| key);
+        let encrypted_part = self
+            .with_admin_key_secrets(|encryptor| {
+                Self::decode_admin_key_with(encryptor, parent_encrypted_part)?;
+                Ok(encryptor.encode_proto(ADMIN_SESSION_TOKEN_VERSION, proto.clone()))
+            })
+            // Admins who didn't authenticate with a key, like with a client
+            // certificate, get a token for the current secret.
+            .unwrap_or_else(|_| {
+                let admin_keys = self.admin_keys.read();
+                admin_keys
+                    .current
+                    .as_ref()
+                    .unwrap_or(&self.encryptor)
+                    .encode_proto(ADMIN_SESSION_TOKEN_VERSION, proto)
+            });
+        Ok(AdminKey::new(format_admin_key(
+            &self.instance_name,
+            &encrypted_part,
+        )))
+    }
+
+    fn decode_admin_session_token(
+        &self,
+        token: &str,
+    ) -> anyhow::Result<(Option<&str>, AdminSessionTokenProto)> {
+        let (instance_name, encrypted_part) = split_admin_key(token)
+            .map(|(name, token)| (Some(remove_type_prefix_from_instance_name(name)), token))
+            .unwrap_or((None, token));
+        let proto = self.with_admin_key_secrets(|encryptor| {
+            encryptor.decode_proto(ADMIN_SESSION_TOKEN_VERSION, encrypted_part)
+        })?;
+        Ok((instance_name, proto))
+    }
+
+    pub fn is_admin_session_token(&self, token: &str) -> bool {
+        self.decode_admin_session_token(token).is_ok()
+    }
+
+    /// Issues a token for the first-party auth session with `session_id`,
+    /// which is accepted in place of an ID token until `expires_at`.
+    pub fn issue_user_session_token(
+        &self,
+        session_id: String,
+        expires_at: SystemTime,
+    ) -> anyhow::Result<String> {
+        let since_epoch = |time: SystemTime| {
+            time.duration_since(SystemTime::UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+        };
+        let proto = UserSessionTokenProto {
+            session_id,
+            issued_s: since_epoch(SystemTime::now())?,
+            expires_s: since_epoch(expires_at)?,
+        };
+        Ok(self
+            .encryptor
+            .encode_proto(USER_SESSION_TOKEN_VERSION, proto))
+    }
+
+    pub fn is_user_session_token(&self, token: &str) -> bool {
+        self.encryptor
+            .decode_proto::<UserSessionTokenProto>(USER_SESSION_TOKEN_VERSION, token)
+            .is_ok()
+    }
+
+    /// Returns the session ID and expiry of a user session token. The caller
+    /// checks that the session hasn't been ended since.
+    pub fn check_user_session_token(
+        &self,
+        token: &str,
+        now: SystemTime,
+    ) -> anyhow::Result<(String, SystemTime)> {
+        let UserSessionTokenProto {
+            session_id,
+            issued_s,
+            expires_s,
+        } = self
+            .encryptor
+            .decode_proto(USER_SESSION_TOKEN_VERSION, token)
+            .context(ErrorMetadata::unauthenticated(
+                "AuthSessionInvalid",
+                "Couldn't decode the session token",
+            ))?;
+        anyhow::ensure!(issued_s != 0, "Proto missing issued_s");
+        let now_s = now.duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+        if expires_s <= now_s {
+            anyhow::bail!(ErrorMetadata::unauthenticated(
+                "AuthSessionExpired",
+                "The session has expired. Log in again.",
+            ));
+        }
+        Ok((
+            session_id,
+            SystemTime::UNIX_EPOCH + Duration::from_secs(expires_s),
+        ))
+    }
+
+    pub fn check_admin_session_token(
+        &self,
+        token: &str,
+        now: SystemTime,
+    ) -> anyhow::Result<Identity> {
+        let (
+            instance_name,
+            AdminSessionTokenProto {
+                member_id,
+                scope,
+                issued_s,
+                expires_s,
+                key_fingerprint,
+            },
+        ) = self
+            .decode_admin_session_token(token)
+            .context(ErrorMetadata::unauthenticated(
+                "SessionTokenInvalid",
+                "Couldn't decode the session token",
+            ))?;
+        if instance_name != Some(self.instance_name.as_str()) {
+            anyhow::bail!(ErrorMetadata::unauthenticated(
+                "SessionTokenInvalid",
+                format!("Session token is for invalid instance {instance_name:?}"),
+            ));
+        }
+        anyhow::ensure!(issued_s != 0, "Proto missing issued_s");
+        let now_s = now.duration_since(SystemTime::UNIX_EPOCH)?.as_secs();
+        if expires_s <= now_s {
+            anyhow::bail!(ErrorMetadata::unauthenticated(
+                "SessionTokenExpired",
+                "The session token has expired. Exchange the admin key for a new one.",
+            ));
+        }
+        if self.admin_keys.read().revoked.contains(&key_fingerprint) {
+            anyhow::bail!(ErrorMetadata::unauthenticated(
+                "AdminKeyRevoked",
+                "The deploy key this session token was issued for has been revoked.",
+            ));
+        }
+        Ok(Identity::InstanceAdmin(AdminIdentity {
+            instance_name: self.instance_name.clone(),
+            principal: AdminIdentityPrincipal::Member(MemberId(member_id)),
+            key: token.to_string(),
+            scope: scope.parse()?,
+        }))
     }
 
     pub fn issue_system_key(&self) -> SystemKey {
-        SystemKey::new(self.issue_key(None, false))
+        SystemKey::new(self.issue_key(None, AdminKeyScope::Full))
     }
 
     pub fn issue_store_file_authorization<RT: Runtime>(
@@ -726,27 +1332,91 @@ impl KeyBroker {
         rt: &RT,
         issued: UnixTimestamp,
         component: ComponentId,
+        constraints: StoreFileConstraints,
     ) -> anyhow::Result<StoreFileAuthorization> {
         let now = rt.unix_timestamp();
         if (now - issued) > MAX_TS_DELAY {
             anyhow::bail!("Could not issue authorization. Issued TS too far in past.");
         }
         let component_str = component.serialize_to_string();
+        let StoreFileConstraints {
+            max_bytes,
+            allowed_content_types,
+            expires_in,
+        } = constraints;
+        // Round up so a short expiry doesn't expire the authorization as
+        // soon as it's issued.
+        let expires_s = expires_in.map(|expires_in| {
+            issued.as_secs() + expires_in.as_secs() + u64::from(expires_in.subsec_nanos() > 0)
+        });
         Ok(StoreFileAuthorization(self.encryptor.encode_proto(
             STORE_FILE_AUTHZ_VERSION,
             StorageTokenProto {
                 instance_name: self.instance_name.clone(),
                 issued_s: issued.as_secs(),
-                authorization_type: Some(AuthorizationTypeProto::StoreFile(StoreFileProto {})),
+                authorization_type: Some(AuthorizationTypeProto::StoreFile(StoreFileProto {
+                    max_bytes,
+                    allowed_content_types,
+                    expires_s,
+                })),
                 component_id: component_str,
             },
         )))
     }
 
+    pub fn issue_replication_token<RT: Runtime>(
+        &self,
+        rt: &RT,
+        validity: Duration,
+    ) -> ReplicationToken {
+        let issued_s = rt.unix_timestamp().as_secs();
+        ReplicationToken(self.encryptor.encode_proto(
+            REPLICATION_TOKEN_VERSION,
+            ReplicationTokenProto {
+                instance_name: self.instance_name.clone(),
+                issued_s,
+                expires_s: issued_s + validity.as_secs(),
+            },
+        ))
+    }
+
+    pub fn check_replication_token<RT: Runtime>(
+        &self,
+        rt: &RT,
+        replication_token: &str,
+    ) -> anyhow::Result<()> {
+        let ReplicationTokenProto {
+            instance_name,
+            issued_s,
+            expires_s,
+        } = self
+            .encryptor
+            .decode_proto(REPLICATION_TOKEN_VERSION, replication_token)
+            .context(ErrorMetadata::unauthenticated(
+                "ReplicationTokenInvalid",
+                "Couldn't decode the replication token",
+            ))?;
+        if instance_name != self.instance_name {
+            anyhow::bail!(ErrorMetadata::unauthenticated(
+                "ReplicationTokenInvalid",
+                format!("Replication token is for invalid instance {instance_name}"),
+            ));
+        }
+        anyhow::ensure!(issued_s != 0, "Proto missing issued_s");
+        if expires_s <= rt.unix_timestamp().as_secs() {
+            log_replication_token_expired();
+            anyhow::bail!(ErrorMetadata::unauthenticated(
+                "ReplicationTokenExpired",
+                "Replication token expired",
+            ));
+        }
+        Ok(())
+    }
+
     /// Private helper method to generate an admin key.
     /// If `member_id` is None, it generates a system key, otherwise
     /// an admin key for the given user.
-    fn issue_key(&self, member_id: Option<MemberId>, is_read_only: bool) -> String {
+    fn issue_key(&self, member_id: Option<MemberId>, scope: AdminKeyScope) -> String {
         let now = SystemTime::now();
         let since_epoch = now
             .duration_since(SystemTime::UNIX_EPOCH)
@@ -756,40 +1426,103 @@ impl KeyBroker {
             Some(member_id) => AdminIdentityProto::MemberId(member_id.0),
             None => AdminIdentityProto::System(()),
         };
+        // Full and read-only keys keep the old version so that they stay
+        // usable with backends that predate scopes.
+        let (version, scope_field) = match scope {
+            AdminKeyScope::Full | AdminKeyScope::ReadOnly => (ADMIN_KEY_VERSION, None),
+            _ => (SCOPED_ADMIN_KEY_VERSION, Some(scope.to_string())),
+        };
         let proto = AdminKeyProto {
             instance_name: None,
             issued_s: since_epoch.as_secs(),
             identity: Some(identity),
-            is_read_only,
+            is_read_only: scope.is_read_only(),
+            scope: scope_field,
         };
-        format_admin_key(
-            &self.instance_name,
-            &self.encryptor.encode_proto(ADMIN_KEY_VERSION, proto),
-        )
+        let admin_keys = self.admin_keys.read();
+        let encryptor = admin_keys.current.as_ref().unwrap_or(&self.encryptor);
+        format_admin_key(&self.instance_name, &encryptor.encode_proto(version, proto))
+    }
+
+    /// Calls `f` with the current admin key secret and then each retired one
+    /// that's still valid, until it succeeds.
+    fn with_admin_key_secrets<T>(
+        &self,
+        mut f: impl FnMut(&Encryptor) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        let admin_keys = self.admin_keys.read();
+        let now = SystemTime::now();
+        let retired = admin_keys
+            .retired
+            .iter()
+            .filter(|(_, valid_until)| now < *valid_until)
+            .map(|(encryptor, _)| encryptor);
+        let mut result = Err(anyhow::anyhow!("No admin key secrets"));
+        for encryptor in
+            iter::once(admin_keys.current.as_ref().unwrap_or(&self.encryptor)).chain(retired)
+        {
+            result = f(encryptor);
+            if result.is_ok() {
+                break;
+            }
+        }
+        result
+    }
+
+    /// Decodes the encrypted part of an admin key with the current admin key
+    /// secret or a retired one that's still valid.
+    fn decode_admin_key(
+        &self,
+        encrypted_part: &str,
+    ) -> anyhow::Result<(AdminKeyProto, AdminKeyScope)> {
+        self.with_admin_key_secrets(|encryptor| {
+            Self::decode_admin_key_with(encryptor, encrypted_part)
+        })
+    }
+
+    /// Decodes the encrypted part of an admin key of either version, returning
+    /// its scope.
+    fn decode_admin_key_with(
+        encryptor: &Encryptor,
+        encrypted_part: &str,
+    ) -> anyhow::Result<(AdminKeyProto, AdminKeyScope)> {
+        if let Ok(proto) =
+            encryptor.decode_proto::<AdminKeyProto>(SCOPED_ADMIN_KEY_VERSION, encrypted_part)
+        {
+            let scope = proto
+                .scope
+                .as_deref()
+                .context("Scoped admin key missing scope")?
+                .parse()?;
+            return Ok((proto, scope));
+        }
+        let proto: AdminKeyProto = encryptor.decode_proto(ADMIN_KEY_VERSION, encrypted_part)?;
+        anyhow::ensure!(proto.scope.is_none(), "Unscoped admin key has a scope");
+        let scope = AdminKeyScope::from_is_read_only(proto.is_read_only);
+        Ok((proto, scope))
     }
 
     pub fn is_encrypted_admin_key(&self, key: &str) -> bool {
         let (_, encrypted_part) = split_admin_key(key)
             .map(|(name, key)| (Some(remove_type_prefix_from_instance_name(name)), key))
             .unwrap_or((None, key));
-        let admin_key: Result<AdminKeyProto, _> = self
-            .encryptor
-            .decode_proto(ADMIN_KEY_VERSION, encrypted_part);
-        admin_key.is_ok()
+        self.decode_admin_key(encrypted_part).is_ok()
     }
 
     pub fn check_admin_key(&self, key: &str) -> anyhow::Result<Identity> {
         let (instance_name, encrypted_part) = split_admin_key(key)
             .map(|(name, key)| (Some(remove_type_prefix_from_instance_name(name)), key))
             .unwrap_or((None, key));
-        let AdminKeyProto {
-            instance_name: instance_name_from_encrypted_part,
-            issued_s,
-            identity,
-            is_read_only,
-        } = self
-            .encryptor
-            .decode_proto(ADMIN_KEY_VERSION, encrypted_part)
+        let (
+            AdminKeyProto {
+                instance_name: instance_name_from_encrypted_part,
+                issued_s,
+                identity,
+                ..
+            },
+            scope,
+        ) = self
+            .decode_admin_key(encrypted_part)
             .with_context(|| format!("Couldn't decode the AdminKeyProto {}", key))?;
         let instance_name = instance_name
             .or(instance_name_from_encrypted_part.as_deref())
@@ -802,13 +1535,24 @@ impl KeyBroker {
         }
         anyhow::ensure!(issued_s != 0, "Proto missing issued_s");
         let identity = identity.context("Proto missing identity")?;
+        if self
+            .admin_keys
+            .read()
+            .revoked
+            .contains(&admin_key_fingerprint(key))
+        {
+            anyhow::bail!(ErrorMetadata::unauthenticated(
+                "AdminKeyRevoked",
+                "This deploy key has been revoked.",
+            ));
+        }
 
         Ok(match identity {
             AdminIdentityProto::MemberId(member_id) => Identity::InstanceAdmin(AdminIdentity {
                 instance_name: self.instance_name.clone(),
                 principal: AdminIdentityPrincipal::Member(MemberId(member_id)),
                 key: key.to_string(),
-                is_read_only,
+                scope,
             }),
             AdminIdentityProto::System(()) => Identity::system(),
         })
@@ -819,7 +1563,7 @@ impl KeyBroker {
         rt: &RT,
         store_file_authorization: &str,
         validity: Duration,
-    ) -> anyhow::Result<ComponentId> {
+    ) -> anyhow::Result<(ComponentId, StoreFileConstraints)> {
         let StorageTokenProto {
             instance_name,
             issued_s,
@@ -850,18 +1594,36 @@ impl KeyBroker {
             ));
         }
 
-        let Some(AuthorizationTypeProto::StoreFile(StoreFileProto {})) = authorization_type else {
+        let Some(AuthorizationTypeProto::StoreFile(StoreFileProto {
+            max_bytes,
+            allowed_content_types,
+            expires_s,
+        })) = authorization_type
+        else {
             anyhow::bail!(ErrorMetadata::unauthenticated(
                 "InvalidStorageToken",
                 "Storage token is for invalid instance {instance_name}"
             ));
         };
+        if expires_s.is_some_and(|expires_s| expires_s <= now) {
+            log_store_file_auth_expired();
+            anyhow::bail!(ErrorMetadata::unauthenticated(
+                "StorageTokenExpired",
+                "Store File Authorization expired",
+            ));
+        }
 
         let component = ComponentId::deserialize_from_string(component_id.as_deref()).context(
             ErrorMetadata::unauthenticated("InvalidStorageToken", "Invalid component ID"),
         )?;
+        let constraints = StoreFileConstraints {
+            max_bytes,
+            allowed_content_types,
+            expires_in: expires_s
+                .map(|expires_s| Duration::from_secs(expires_s.saturating_sub(issued_s))),
+        };
 
-        Ok(component)
+        Ok((component, constraints))
     }
 
     fn cursor_to_proto(&self, cursor: &Cursor) -> InstanceCursorProto {
@@ -875,6 +1637,7 @@ impl KeyBroker {
             instance_name: self.instance_name.clone(),
             position: Some(position),
             query_fingerprint: cursor.query_fingerprint.clone(),
+            table_number: cursor.table_number.map(u32::from),
         }
     }
 
@@ -899,6 +1662,11 @@ impl KeyBroker {
         Ok(Cursor {
             position: cursor_position,
             query_fingerprint: proto.query_fingerprint,
+            table_number: proto
+                .table_number
+                .map(TableNumber::try_from)
+                .transpose()
+                .with_context(cursor_parse_error)?,
         })
     }
 
@@ -1036,12 +1804,18 @@ mod tests {
         query_journal::QueryJournal,
         runtime::Runtime,
         types::{
+            split_admin_key,
             MemberId,
             PersistenceVersion,
             TableName,
+            UdfType,
+        },
+        value::{
+            DeveloperDocumentId,
+            TableNumber,
         },
-        value::DeveloperDocumentId,
     };
+    use errors::ErrorMetadataAnyhowExt;
     use pb::convex_keys::{
         admin_key::Identity as AdminIdentityProto,
         AdminKey as AdminKeyProto,
@@ -1051,13 +1825,20 @@ mod tests {
     use runtime::testing::TestDriver;
 
     use super::{
+        admin_key_fingerprint,
         AdminKey,
+        AdminKeyScope,
+        AdminPermission,
         KeyBroker,
+        RetiredAdminKeySecret,
+        StoreFileConstraints,
         ADMIN_KEY_VERSION,
     };
     use crate::{
         AdminIdentity,
+        AdminIdentityPrincipal,
         Identity,
+        InstanceSecret,
     };
 
     #[test]
@@ -1091,6 +1872,218 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_scoped_admin_keys() -> anyhow::Result<()> {
+        let kb = KeyBroker::dev();
+        let key = kb.issue_scoped_admin_key(MemberId(0), AdminKeyScope::DeployOnly);
+        let identity = kb.check_admin_key(key.as_str())?;
+        let Identity::InstanceAdmin(ref admin) = identity else {
+            panic!("Expected an admin identity, got {identity:?}");
+        };
+        assert_eq!(admin.scope(), AdminKeyScope::DeployOnly);
+        assert!(admin.scope().allows(AdminPermission::Deploy));
+        assert!(!admin.scope().allows(AdminPermission::ReadData));
+        assert!(identity.ensure_can_run_function(UdfType::Query).is_err());
+        // Backends that predate scopes can't decode scoped keys, rather than
+        // treating them as full admin keys.
+        let (_, encrypted_part) = split_admin_key(key.as_str()).unwrap();
+        assert!(kb
+            .encryptor
+            .decode_proto::<AdminKeyProto>(ADMIN_KEY_VERSION, encrypted_part)
+            .is_err());
+
+        let key = kb.issue_scoped_admin_key(MemberId(0), AdminKeyScope::DataRead);
+        let identity = kb.check_admin_key(key.as_str())?;
+        identity.ensure_can_run_function(UdfType::Query)?;
+        assert!(identity.ensure_can_run_function(UdfType::Mutation).is_err());
+
+        // Unscoped keys keep the version older backends accept.
+        let key = kb.issue_read_only_admin_key(MemberId(0));
+        let (_, encrypted_part) = split_admin_key(key.as_str()).unwrap();
+        kb.encryptor
+            .decode_proto::<AdminKeyProto>(ADMIN_KEY_VERSION, encrypted_part)?;
+        let identity = kb.check_admin_key(key.as_str())?;
+        let Identity::InstanceAdmin(admin) = identity else {
+            panic!("Expected an admin identity, got {identity:?}");
+        };
+        assert_eq!(admin.scope(), AdminKeyScope::ReadOnly);
+        Ok(())
+    }
+
+    #[test]
+    fn test_admin_key_rotation() -> anyhow::Result<()> {
+        let kb = KeyBroker::dev();
+        let old_key = kb.issue_admin_key(MemberId(0));
+
+        let secret = InstanceSecret::random();
+        let encrypted = kb.encrypt_admin_key_secret(&secret);
+        assert_eq!(kb.decrypt_admin_key_secret(&encrypted)?, secret);
+        kb.set_admin_key_secrets(
+            Some(secret),
+            vec![RetiredAdminKeySecret {
+                secret: None,
+                valid_until: SystemTime::now() + Duration::from_secs(3600),
+            }],
+        )?;
+        let new_key = kb.issue_admin_key(MemberId(0));
+        kb.check_admin_key(old_key.as_str())?;
+        kb.check_admin_key(new_key.as_str())?;
+
+        // Once the grace period ends, only keys from the new secret work.
+        kb.set_admin_key_secrets(
+            Some(secret),
+            vec![RetiredAdminKeySecret {
+                secret: None,
+                valid_until: SystemTime::now(),
+            }],
+        )?;
+        assert!(kb.check_admin_key(old_key.as_str()).is_err());
+        kb.check_admin_key(new_key.as_str())?;
+
+        kb.set_revoked_admin_keys([admin_key_fingerprint(new_key.as_str())].into());
+        assert!(kb.check_admin_key(new_key.as_str()).is_err());
+        // Revocation applies however the key is prefixed.
+        assert!(kb
+            .check_admin_key(&format!("prod:{}", new_key.as_str()))
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_admin_session_token_rotation() -> anyhow::Result<()> {
+        let kb = KeyBroker::dev();
+        let old_key = kb.issue_admin_key(MemberId(0));
+        let Identity::InstanceAdmin(old_admin) = kb.check_admin_key(old_key.as_str())? else {
+            panic!("Expected an admin identity");
+        };
+        let expires_at = SystemTime::now() + Duration::from_secs(7200);
+        let old_token = kb.issue_admin_session_token(&old_admin, None, expires_at)?;
+
+        let secret = InstanceSecret::random();
+        kb.set_admin_key_secrets(
+            Some(secret),
+            vec![RetiredAdminKeySecret {
+                secret: None,
+                valid_until: SystemTime::now() + Duration::from_secs(3600),
+            }],
+        )?;
+        // Tokens for keys from the retired secret are accepted during the grace
+        // period, and new ones can still be issued for them.
+        kb.check_admin_session_token(old_token.as_str(), SystemTime::now())?;
+        let grace_token = kb.issue_admin_session_token(&old_admin, None, expires_at)?;
+        kb.check_admin_session_token(grace_token.as_str(), SystemTime::now())?;
+        let new_key = kb.issue_admin_key(MemberId(0));
+        let Identity::InstanceAdmin(new_admin) = kb.check_admin_key(new_key.as_str())? else {
+            panic!("Expected an admin identity");
+        };
+        let new_token = kb.issue_admin_session_token(&new_admin, None, expires_at)?;
+
+        // Once it ends, tokens stop being accepted along with their keys, even
+        // if they haven't expired.
+        kb.set_admin_key_secrets(
+            Some(secret),
+            vec![RetiredAdminKeySecret {
+                secret: None,
+                valid_until: SystemTime::now(),
+            }],
+        )?;
+        assert!(kb
+            .check_admin_session_token(old_token.as_str(), SystemTime::now())
+            .is_err());
+        assert!(kb
+            .check_admin_session_token(grace_token.as_str(), SystemTime::now())
+            .is_err());
+        kb.check_admin_session_token(new_token.as_str(), SystemTime::now())?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_admin_client_certificates() -> anyhow::Result<()> {
+        let kb = KeyBroker::dev();
+        assert!(kb.check_admin_client_certificate("ci").is_err());
+        kb.set_admin_client_certificates(
+            [("ci".to_string(), (MemberId(7), AdminKeyScope::DeployOnly))].into(),
+            true,
+        );
+        assert!(kb.admin_client_certificate_required());
+        let Identity::InstanceAdmin(admin) = kb.check_admin_client_certificate("ci")? else {
+            panic!("Expected an admin identity");
+        };
+        assert_eq!(
+            admin.principal(),
+            &AdminIdentityPrincipal::Member(MemberId(7))
+        );
+        assert_eq!(admin.scope(), AdminKeyScope::DeployOnly);
+        let err = kb.check_admin_client_certificate("other").unwrap_err();
+        assert_eq!(err.short_msg(), "UnknownClientCertificate");
+        Ok(())
+    }
+
+    #[test]
+    fn test_admin_session_token() -> anyhow::Result<()> {
+        let kb = KeyBroker::dev();
+        let key = kb.issue_scoped_admin_key(MemberId(3), AdminKeyScope::DataWrite);
+        let Identity::InstanceAdmin(admin) = kb.check_admin_key(key.as_str())? else {
+            panic!("Expected an admin identity");
+        };
+        let now = SystemTime::now();
+        let token = kb.issue_admin_session_token(
+            &admin,
+            Some(AdminKeyScope::DataRead),
+            now + Duration::from_secs(60),
+        )?;
+        assert!(kb.is_admin_session_token(token.as_str()));
+        assert!(!kb.is_encrypted_admin_key(token.as_str()));
+        let Identity::InstanceAdmin(session) = kb.check_admin_session_token(token.as_str(), now)?
+        else {
+            panic!("Expected an admin identity");
+        };
+        assert_eq!(
+            session.principal(),
+            &AdminIdentityPrincipal::Member(MemberId(3))
+        );
+        assert_eq!(session.scope(), AdminKeyScope::DataRead);
+
+        // Expired tokens are rejected.
+        let err = kb
+            .check_admin_session_token(token.as_str(), now + Duration::from_secs(61))
+            .unwrap_err();
+        assert_eq!(err.short_msg(), "SessionTokenExpired");
+
+        // Tokens can't be broader than their key, or be exchanged themselves.
+        assert!(kb
+            .issue_admin_session_token(&admin, Some(AdminKeyScope::Full), now)
+            .is_err());
+        assert!(kb.issue_admin_session_token(&session, None, now).is_err());
+
+        // Revoking the key revokes its tokens.
+        kb.set_revoked_admin_keys([admin_key_fingerprint(key.as_str())].into());
+        let err = kb
+            .check_admin_session_token(token.as_str(), now)
+            .unwrap_err();
+        assert_eq!(err.short_msg(), "AdminKeyRevoked");
+        Ok(())
+    }
+
+    #[test]
+    fn test_user_session_token() -> anyhow::Result<()> {
+        let kb = KeyBroker::dev();
+        let now = SystemTime::now();
+        let token =
+            kb.issue_user_session_token("session1".to_string(), now + Duration::from_secs(60))?;
+        assert!(kb.is_user_session_token(&token));
+        assert!(!kb.is_admin_session_token(&token));
+        assert!(!kb.is_encrypted_admin_key(&token));
+        let (session_id, _) = kb.check_user_session_token(&token, now)?;
+        assert_eq!(session_id, "session1");
+
+        let err = kb
+            .check_user_session_token(&token, now + Duration::from_secs(61))
+            .unwrap_err();
+        assert_eq!(err.short_msg(), "AuthSessionExpired");
+        Ok(())
+    }
+
     fn old_issue_key(kb: &KeyBroker, member_id: Option<MemberId>) -> String {
         let now = SystemTime::now();
         let since_epoch = now
@@ -1106,6 +2099,7 @@ mod tests {
             issued_s: since_epoch.as_secs(),
             identity: Some(identity),
             is_read_only: false,
+            scope: None,
         };
         kb.encryptor.encode_proto(ADMIN_KEY_VERSION, proto)
     }
@@ -1123,10 +2117,79 @@ mod tests {
         let kb = KeyBroker::dev();
         let td = TestDriver::new();
         let now = td.rt().unix_timestamp();
-        let key = kb.issue_store_file_authorization(&td.rt(), now, ComponentId::test_user())?;
-        let component =
+        let key = kb.issue_store_file_authorization(
+            &td.rt(),
+            now,
+            ComponentId::test_user(),
+            StoreFileConstraints::default(),
+        )?;
+        let (component, constraints) =
             kb.check_store_file_authorization(&td.rt(), &key.to_string(), Duration::from_secs(60))?;
         assert_eq!(component, ComponentId::test_user());
+        assert_eq!(constraints, StoreFileConstraints::default());
+        Ok(())
+    }
+
+    #[test]
+    fn test_store_file_constraints() -> anyhow::Result<()> {
+        let kb = KeyBroker::dev();
+        let td = TestDriver::new();
+        let now = td.rt().unix_timestamp();
+        let constraints = StoreFileConstraints {
+            max_bytes: Some(1024),
+            allowed_content_types: vec!["image/*".to_string(), "application/pdf".to_string()],
+            expires_in: Some(Duration::from_secs(30)),
+        };
+        let key = kb.issue_store_file_authorization(
+            &td.rt(),
+            now,
+            ComponentId::test_user(),
+            constraints.clone(),
+        )?;
+        let (_, checked) =
+            kb.check_store_file_authorization(&td.rt(), &key.to_string(), Duration::from_secs(60))?;
+        assert_eq!(checked, constraints);
+
+        checked.check_size(1024)?;
+        checked.check_size(1025).unwrap_err();
+        checked.check_content_type(Some("image/png"))?;
+        checked.check_content_type(Some("Application/PDF; charset=binary"))?;
+        checked.check_content_type(Some("text/plain")).unwrap_err();
+        checked.check_content_type(None).unwrap_err();
+
+        let expired = kb.issue_store_file_authorization(
+            &td.rt(),
+            now,
+            ComponentId::test_user(),
+            StoreFileConstraints {
+                expires_in: Some(Duration::ZERO),
+                ..Default::default()
+            },
+        )?;
+        kb.check_store_file_authorization(&td.rt(), &expired.to_string(), Duration::from_secs(60))
+            .unwrap_err();
+        Ok(())
+    }
+
+    #[test]
+    fn test_replication_token() -> anyhow::Result<()> {
+        let kb = KeyBroker::dev();
+        let td = TestDriver::new();
+        let token = kb.issue_replication_token(&td.rt(), Duration::from_secs(60));
+        kb.check_replication_token(&td.rt(), &token.to_string())?;
+
+        let expired = kb.issue_replication_token(&td.rt(), Duration::ZERO);
+        kb.check_replication_token(&td.rt(), &expired.to_string())
+            .unwrap_err();
+
+        let other_kb = KeyBroker::local_dev("other-instance");
+        other_kb
+            .check_replication_token(&td.rt(), &token.to_string())
+            .unwrap_err();
+        // Admin keys aren't replication tokens.
+        let admin_key = kb.issue_admin_key(MemberId(0));
+        kb.check_replication_token(&td.rt(), admin_key.as_str())
+            .unwrap_err();
         Ok(())
     }
 
@@ -1135,8 +2198,13 @@ mod tests {
         let kb = KeyBroker::dev();
         let td = TestDriver::new();
         let hour_ago = td.rt().unix_timestamp() - Duration::from_secs(3600);
-        kb.issue_store_file_authorization(&td.rt(), hour_ago, ComponentId::test_user())
-            .unwrap_err();
+        kb.issue_store_file_authorization(
+            &td.rt(),
+            hour_ago,
+            ComponentId::test_user(),
+            StoreFileConstraints::default(),
+        )
+        .unwrap_err();
         Ok(())
     }
 
@@ -1146,6 +2214,7 @@ mod tests {
         let cursor = Cursor {
             position: CursorPosition::End,
             query_fingerprint: vec![],
+            table_number: None,
         };
         let encrypted = kb.encrypt_cursor(&cursor, PersistenceVersion::default());
         let echoed = kb.decrypt_cursor(encrypted, PersistenceVersion::default())?;
@@ -1184,10 +2253,11 @@ mod tests {
                 IndexKey::new(vec![100.into()], DeveloperDocumentId::MIN).into_bytes(),
             ),
             query_fingerprint: query.fingerprint(&IndexedFields::creation_time())?,
+            table_number: Some(TableNumber::MIN),
         });
         let serialized_journal_with_cursor =
             kb.encrypt_query_journal(&journal_with_cursor, PersistenceVersion::default());
-        assert_eq!(serialized_journal_with_cursor.unwrap().len(), 252);
+        assert_eq!(serialized_journal_with_cursor.unwrap().len(), 256);
         Ok(())
     }
 
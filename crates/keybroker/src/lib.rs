@@ -14,12 +14,18 @@ pub use sync_types::UserIdentityAttributes;
 
 pub use self::{
     broker::{
+        admin_key_fingerprint,
         AdminIdentity,
         AdminIdentityPrincipal,
+        AdminKeyScope,
+        AdminPermission,
         GetFileAuthorization,
         Identity,
         KeyBroker,
+        ReplicationToken,
+        RetiredAdminKeySecret,
         StoreFileAuthorization,
+        StoreFileConstraints,
         SystemKey,
         UserIdentity,
     },
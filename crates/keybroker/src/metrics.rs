@@ -18,3 +18,11 @@ register_convex_counter!(
 pub fn log_actions_token_expired() {
     log_counter(&KEYBROKER_ACTIONS_TOKEN_EXPIRED_TOTAL, 1);
 }
+
+register_convex_counter!(
+    KEYBROKER_REPLICATION_TOKEN_EXPIRED_TOTAL,
+    "Number of times a replication token was rejected because it was expired"
+);
+pub fn log_replication_token_expired() {
+    log_counter(&KEYBROKER_REPLICATION_TOKEN_EXPIRED_TOTAL, 1);
+}
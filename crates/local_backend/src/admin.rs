@@ -1,12 +1,20 @@
-use anyhow::Context;
 use authentication::application_auth::ApplicationAuth;
 use common::types::MemberId;
-use errors::ErrorMetadata;
+use errors::{
+    ErrorMetadata,
+    ErrorMetadataAnyhowExt,
+};
 use keybroker::{
     AdminIdentityPrincipal,
+    AdminPermission,
     Identity,
 };
 
+use crate::{
+    admin_key_scope::current_required_permission,
+    audit_log::record_audited_admin,
+};
+
 pub async fn must_be_admin_from_key_with_write_access(
     app_auth: &ApplicationAuth,
     instance_name: String,
@@ -29,10 +37,19 @@ async fn must_be_admin_from_key_internal(
     admin_key_or_access_token: String,
     needs_write_access: bool,
 ) -> anyhow::Result<Identity> {
-    let identity = app_auth
+    let identity = match app_auth
         .check_key(admin_key_or_access_token, instance_name.clone())
         .await
-        .context(bad_admin_key_error(Some(instance_name)))?;
+    {
+        Ok(identity) => identity,
+        // The key may be fine, but this deployment only accepts certificates.
+        Err(e) if e.short_msg() == "ClientCertificateRequired" => return Err(e),
+        Err(e) => return Err(e.context(bad_admin_key_error(Some(instance_name)))),
+    };
+    record_audited_admin(&identity);
+    // Keys in the request body are out of reach of
+    // `admin_key_scope_middleware`, so check the route's permission here.
+    must_have_admin_permission(&identity, current_required_permission())?;
     if needs_write_access {
         must_be_admin_with_write_access(&identity)?;
     }
@@ -53,6 +70,7 @@ fn must_be_admin_internal(
     identity: &Identity,
     needs_write_access: bool,
 ) -> anyhow::Result<AdminIdentityPrincipal> {
+    record_audited_admin(identity);
     if let Identity::InstanceAdmin(admin_identity) = identity {
         if needs_write_access && admin_identity.is_read_only() {
             return Err(read_only_admin_key_error().into());
@@ -75,6 +93,7 @@ fn must_be_admin_member_internal(
     identity: &Identity,
     needs_write_access: bool,
 ) -> anyhow::Result<MemberId> {
+    record_audited_admin(identity);
     if let Identity::InstanceAdmin(admin_identity) = identity {
         if let AdminIdentityPrincipal::Member(member_id) = admin_identity.principal() {
             if needs_write_access && admin_identity.is_read_only() {
@@ -89,6 +108,28 @@ fn must_be_admin_member_internal(
     }
 }
 
+/// Checks that an admin identity's key scope allows `permission`. Other
+/// identities are left to the caller.
+pub fn must_have_admin_permission(
+    identity: &Identity,
+    permission: AdminPermission,
+) -> anyhow::Result<()> {
+    if let Identity::InstanceAdmin(admin_identity) | Identity::ActingUser(admin_identity, _) =
+        identity
+    {
+        let scope = admin_identity.scope();
+        if !scope.allows(permission) {
+            anyhow::bail!(ErrorMetadata::forbidden(
+                "AdminKeyScope",
+                format!(
+                    "This {scope} deploy key doesn't have permission to perform this operation."
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
 pub fn bad_admin_key_error(instance_name: Option<String>) -> ErrorMetadata {
     let msg = match instance_name {
         Some(name) => format!(
@@ -0,0 +1,408 @@
+//! Enforces admin key scopes on the admin API, so that e.g. a deploy-only key
+//! held by CI can push code but can't read production data.
+//!
+//! Each route needs an [`AdminPermission`], and routes that aren't listed in
+//! [`required_permission`] need `AdminPermission::Manage`, which only unscoped
+//! keys have. Running functions is checked separately by
+//! `Identity::ensure_can_run_function`, which covers the public API and sync
+//! as well.
+//!
+//! Session tokens exchanged for a key carry a scope too, no broader than the
+//! key's, and are checked the same way, as are client certificates on the
+//! admin mTLS port.
+//!
+//! Handlers that take keys in the request body, like the CLI's, check them
+//! against the same permission with [`current_required_permission`].
+use std::time::SystemTime;
+
+use axum::{
+    extract::{
+        MatchedPath,
+        Request,
+        State,
+    },
+    middleware::Next,
+    response::{
+        IntoResponse,
+        Response,
+    },
+    RequestExt,
+};
+use common::http::HttpResponseError;
+use keybroker::AdminPermission;
+use sync_types::AuthenticationToken;
+
+use crate::{
+    admin::must_have_admin_permission,
+    admin_mtls::AdminClientCertificate,
+    authentication::ExtractAuthenticationToken,
+    LocalAppState,
+};
+
+tokio::task_local! {
+    /// The permission the route being handled needs, for handlers that check
+    /// keys passed in the request body.
+    static REQUIRED_PERMISSION: AdminPermission;
+}
+
+/// The permission the route being handled needs, or `AdminPermission::Manage`
+/// outside of `admin_key_scope_middleware`.
+pub fn current_required_permission() -> AdminPermission {
+    REQUIRED_PERMISSION
+        .try_with(|permission| *permission)
+        .unwrap_or(AdminPermission::Manage)
+}
+
+pub async fn admin_key_scope_middleware(
+    State(st): State<LocalAppState>,
+    matched_path: Option<MatchedPath>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let route = matched_path
+        .as_ref()
+        .map_or(req.uri().path(), |path| path.as_str());
+    let permission = required_permission(route);
+    // Any key can be exchanged for a session token, which is limited to what
+    // the key allows.
+    let exempt = route == "/api/admin_keys/session_token";
+    if !exempt && let Err(e) = check_header_scope(&st, &mut req, permission).await {
+        return HttpResponseError::from(e).into_response();
+    }
+    REQUIRED_PERMISSION.scope(permission, next.run(req)).await
+}
+
+async fn check_header_scope(
+    st: &LocalAppState,
+    req: &mut Request,
+    permission: AdminPermission,
+) -> anyhow::Result<()> {
+    // Only keys and session tokens issued by the key broker, and client
+    // certificates, have scopes. Access tokens, and credentials that don't
+    // check out, are left to the handler.
+    let key_broker = st.application.key_broker();
+    let identity = match req.extract_parts::<ExtractAuthenticationToken>().await {
+        Ok(ExtractAuthenticationToken(AuthenticationToken::Admin(key, _))) => {
+            if key_broker.is_admin_session_token(&key) {
+                key_broker.check_admin_session_token(&key, SystemTime::now())
+            } else {
+                key_broker.check_admin_key(&key)
+            }
+        },
+        // Certificates are only used when no token is presented, like in
+        // `ExtractIdentity`.
+        Ok(ExtractAuthenticationToken(AuthenticationToken::None)) => {
+            match req.extensions().get::<AdminClientCertificate>() {
+                Some(AdminClientCertificate(common_name)) => {
+                    key_broker.check_admin_client_certificate(common_name)
+                },
+                None => return Ok(()),
+            }
+        },
+        _ => return Ok(()),
+    };
+    let Ok(identity) = identity else {
+        return Ok(());
+    };
+    must_have_admin_permission(&identity, permission)
+}
+
+/// The permission an admin key needs to call `route`, a path as registered in
+/// the router.
+fn required_permission(route: &str) -> AdminPermission {
+    let Some(route) = route.strip_prefix("/api") else {
+        return AdminPermission::Manage;
+    };
+    match route {
+        "/push_config"
+        | "/prepare_schema"
+        | "/get_config"
+        | "/get_config_hashes"
+        | "/schema_state/:schema_id"
+        | "/update_environment_variables"
+        | "/get_indexes"
+        | "/get_source_code"
+        | "/codegen/rust" => AdminPermission::Deploy,
+        _ if route.starts_with("/deploy2/") => AdminPermission::Deploy,
+        "/run_query_at_ts"
+        | "/run_test_function"
+        | "/shapes2"
+        | "/observed_args_shapes"
+        | "/stream_udf_execution"
+        | "/stream_function_logs"
+        | "/import/id_mapping"
+        | "/export/request/zip"
+        | "/export/zip/:id"
+        | "/export/backups"
+        | "/export/search_index"
+        | "/list_tables"
+        | "/list_functions"
+        | "/list_scheduled_jobs"
+        | "/scheduled_jobs"
+        | "/scheduled_jobs/dead_letter" => AdminPermission::ReadData,
+        _ if route.starts_with("/app_metrics/") => AdminPermission::ReadData,
+        "/prepare_import"
+        | "/perform_import"
+        | "/cancel_import"
+        | "/restore_tables"
+        | "/delete_tables"
+        | "/deleting_tables_cleanup"
+        | "/compact_table"
+        | "/cancel_all_jobs"
+        | "/cancel_job"
+        | "/scheduled_jobs/dead_letter/retry"
+        | "/scheduled_jobs/dead_letter/purge" => AdminPermission::WriteData,
+        "/storage_gc_report"
+        | "/actions/storage_generate_upload_url"
+        | "/actions/storage_get_url"
+        | "/actions/storage_get_metadata"
+        | "/actions/storage_delete" => AdminPermission::Storage,
+        // Callbacks from actions carry the key of whoever ran the action,
+        // which was already checked when it started.
+        _ if route.starts_with("/import/")
+            || route == "/import"
+            || route.starts_with("/counters/")
+            || route.starts_with("/actions/") =>
+        {
+            AdminPermission::WriteData
+        },
+        _ => AdminPermission::Manage,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum_extra::headers::authorization::Credentials;
+    use common::types::MemberId;
+    use http::{
+        Request,
+        StatusCode,
+    };
+    use keybroker::{
+        AdminKeyScope,
+        AdminPermission,
+    };
+    use runtime::prod::ProdRuntime;
+    use serde_json::json;
+
+    use super::required_permission;
+    use crate::{
+        admin_mtls::AdminClientCertificate,
+        test_helpers::setup_backend_for_test,
+    };
+
+    #[test]
+    fn test_required_permission() {
+        assert_eq!(
+            required_permission("/api/deploy2/start_push"),
+            AdminPermission::Deploy
+        );
+        assert_eq!(
+            required_permission("/api/import/id_mapping"),
+            AdminPermission::ReadData
+        );
+        assert_eq!(
+            required_permission("/api/run_test_function"),
+            AdminPermission::ReadData
+        );
+        assert_eq!(
+            required_permission("/api/import/start_upload"),
+            AdminPermission::WriteData
+        );
+        assert_eq!(
+            required_permission("/api/export/backups/schedule"),
+            AdminPermission::Manage
+        );
+        assert_eq!(
+            required_permission("/api/cors_config"),
+            AdminPermission::Manage
+        );
+        for route in [
+            "/api/storage_gc_report",
+            "/api/actions/storage_generate_upload_url",
+            "/api/actions/storage_get_url",
+            "/api/actions/storage_get_metadata",
+            "/api/actions/storage_delete",
+        ] {
+            assert_eq!(required_permission(route), AdminPermission::Storage);
+        }
+        assert_eq!(
+            required_permission("/api/actions/query"),
+            AdminPermission::WriteData
+        );
+    }
+
+    #[convex_macro::prod_rt_test]
+    async fn test_deploy_only_key(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        let key = backend
+            .st
+            .application
+            .key_broker()
+            .issue_scoped_admin_key(MemberId(2), AdminKeyScope::DeployOnly);
+        let header = key.as_header()?;
+
+        let body = json!({"changes": [{"name": "name1", "value": "value1"}]});
+        let req = Request::builder()
+            .uri("/api/update_environment_variables")
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .header("Authorization", header.0.encode())
+            .body(axum::body::Body::from(serde_json::to_vec(&body)?))?;
+        backend.expect_success::<serde_json::Value>(req).await?;
+
+        let req = Request::builder()
+            .uri("/api/shapes2")
+            .method("GET")
+            .header("Authorization", header.0.encode())
+            .body(axum::body::Body::empty())?;
+        backend
+            .expect_error(req, StatusCode::FORBIDDEN, "AdminKeyScope")
+            .await?;
+
+        let body = json!({"path": "sync:accountBalance", "args": {}});
+        let req = Request::builder()
+            .uri("/api/query")
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .header("Authorization", header.0.encode())
+            .body(axum::body::Body::from(serde_json::to_vec(&body)?))?;
+        backend
+            .expect_error(req, StatusCode::FORBIDDEN, "Unauthorized")
+            .await?;
+
+        // The test function runner takes the key in the body, and runs queries
+        // that can read any data.
+        let body = json!({
+            "adminKey": key.as_str(),
+            "bundle": {
+                "path": "test.js",
+                "source": "export default () => null;",
+            },
+            "args": {},
+            "format": "json",
+        });
+        let req = Request::builder()
+            .uri("/api/run_test_function")
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .body(axum::body::Body::from(serde_json::to_vec(&body)?))?;
+        backend
+            .expect_error(req, StatusCode::FORBIDDEN, "AdminKeyScope")
+            .await?;
+        Ok(())
+    }
+
+    #[convex_macro::prod_rt_test]
+    async fn test_scoped_key_routes(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        let key_broker = backend.st.application.key_broker().clone();
+        let storage_gc_report = |scope| -> anyhow::Result<Request<axum::body::Body>> {
+            let header = key_broker
+                .issue_scoped_admin_key(MemberId(2), scope)
+                .as_header()?;
+            Ok(Request::builder()
+                .uri("/api/storage_gc_report")
+                .method("POST")
+                .header("Content-Type", "application/json")
+                .header("Authorization", header.0.encode())
+                .body(axum::body::Body::from(serde_json::to_vec(&json!({}))?))?)
+        };
+        // Keys in the body are checked against the route's permission too.
+        let report_push_completed = |scope| -> anyhow::Result<Request<axum::body::Body>> {
+            let key = key_broker.issue_scoped_admin_key(MemberId(2), scope);
+            Ok(Request::builder()
+                .uri("/api/deploy2/report_push_completed")
+                .method("POST")
+                .header("Content-Type", "application/json")
+                .body(axum::body::Body::from(serde_json::to_vec(&json!({
+                    "adminKey": key.as_str(),
+                    "spans": [],
+                }))?))?)
+        };
+
+        backend
+            .expect_success::<serde_json::Value>(storage_gc_report(AdminKeyScope::StorageOnly)?)
+            .await?;
+        backend
+            .expect_success::<serde_json::Value>(storage_gc_report(AdminKeyScope::DataWrite)?)
+            .await?;
+        for scope in [AdminKeyScope::DeployOnly, AdminKeyScope::DataRead] {
+            backend
+                .expect_error(
+                    storage_gc_report(scope)?,
+                    StatusCode::FORBIDDEN,
+                    "AdminKeyScope",
+                )
+                .await?;
+        }
+
+        backend
+            .expect_success::<()>(report_push_completed(AdminKeyScope::DeployOnly)?)
+            .await?;
+        for scope in [
+            AdminKeyScope::DataRead,
+            AdminKeyScope::DataWrite,
+            AdminKeyScope::StorageOnly,
+        ] {
+            backend
+                .expect_error(
+                    report_push_completed(scope)?,
+                    StatusCode::FORBIDDEN,
+                    "AdminKeyScope",
+                )
+                .await?;
+        }
+
+        // Storage callbacks from actions need storage access.
+        let header = key_broker
+            .issue_scoped_admin_key(MemberId(2), AdminKeyScope::DeployOnly)
+            .as_header()?;
+        let req = Request::builder()
+            .uri("/api/actions/storage_delete")
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .header("Authorization", header.0.encode())
+            .body(axum::body::Body::from(serde_json::to_vec(
+                &json!({"storageId": "kg2ah2rb3f4vqfpk3syqdgbrg96zw1z2"}),
+            )?))?;
+        backend
+            .expect_error(req, StatusCode::FORBIDDEN, "AdminKeyScope")
+            .await?;
+        Ok(())
+    }
+
+    #[convex_macro::prod_rt_test]
+    async fn test_client_certificate_scope(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        backend
+            .st
+            .application
+            .key_broker()
+            .set_admin_client_certificates(
+                [("ci".to_string(), (MemberId(2), AdminKeyScope::DataRead))].into(),
+                false,
+            );
+
+        let mut req = Request::builder()
+            .uri("/api/shapes2")
+            .method("GET")
+            .body(axum::body::Body::empty())?;
+        req.extensions_mut()
+            .insert(AdminClientCertificate("ci".to_string()));
+        backend.expect_success::<serde_json::Value>(req).await?;
+
+        let body = json!({"changes": [{"name": "name1", "value": "value1"}]});
+        let mut req = Request::builder()
+            .uri("/api/update_environment_variables")
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .body(axum::body::Body::from(serde_json::to_vec(&body)?))?;
+        req.extensions_mut()
+            .insert(AdminClientCertificate("ci".to_string()));
+        backend
+            .expect_error(req, StatusCode::FORBIDDEN, "AdminKeyScope")
+            .await?;
+        Ok(())
+    }
+}
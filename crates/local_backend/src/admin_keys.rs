@@ -0,0 +1,461 @@
+//! Issues admin keys that can be listed and revoked individually, and rotates
+//! the secret admin keys are encrypted with.
+//!
+//! Keys from `generate_key` aren't listed, but can still be revoked by
+//! fingerprint, which the audit log records for every call.
+//!
+//! Any key can also be exchanged for a short-lived session token, so
+//! dashboards and scripts don't need to keep the key around.
+use std::time::{
+    Duration,
+    SystemTime,
+};
+
+use axum::{
+    extract::State,
+    response::IntoResponse,
+};
+use common::{
+    http::{
+        extract::Json,
+        HttpResponseError,
+    },
+    knobs::{
+        ADMIN_SESSION_TOKEN_DEFAULT_TTL,
+        ADMIN_SESSION_TOKEN_MAX_TTL,
+    },
+    types::MemberId,
+};
+use errors::ErrorMetadata;
+use http::StatusCode;
+use keybroker::{
+    admin_key_fingerprint,
+    AdminIdentityPrincipal,
+    AdminKeyScope,
+    Identity,
+};
+use model::admin_keys::types::AdminKeyMetadata;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    admin::{
+        must_be_admin,
+        must_be_admin_with_write_access,
+    },
+    authentication::ExtractIdentity,
+    LocalAppState,
+};
+
+const DEFAULT_ROTATION_GRACE_PERIOD: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AdminKeyJson {
+    key_fingerprint: String,
+    name: Option<String>,
+    member_id: Option<u64>,
+    scope: Option<String>,
+    created_at_ms: Option<u64>,
+    revoked_at_ms: Option<u64>,
+}
+
+impl TryFrom<AdminKeyMetadata> for AdminKeyJson {
+    type Error = anyhow::Error;
+
+    fn try_from(metadata: AdminKeyMetadata) -> anyhow::Result<Self> {
+        Ok(Self {
+            key_fingerprint: metadata.key_fingerprint,
+            name: metadata.name,
+            member_id: metadata.member_id.map(|member_id| member_id.0),
+            scope: metadata.scope.map(|scope| scope.to_string()),
+            created_at_ms: metadata
+                .created_at
+                .map(|created_at| created_at.as_ms_since_epoch())
+                .transpose()?,
+            revoked_at_ms: metadata
+                .revoked_at
+                .map(|revoked_at| revoked_at.as_ms_since_epoch())
+                .transpose()?,
+        })
+    }
+}
+
+/// Lists keys issued through `POST /api/admin_keys` and revoked keys.
+pub async fn list_admin_keys(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let keys = st
+        .application
+        .list_admin_keys(identity)
+        .await?
+        .into_iter()
+        .map(AdminKeyJson::try_from)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(Json(keys))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueAdminKeyArgs {
+    /// What the key is for, like "github-actions".
+    name: String,
+    /// One of the `AdminKeyScope`s, like "deploy-only". Defaults to "full".
+    scope: Option<String>,
+    /// The member the key acts as. Defaults to the caller.
+    member_id: Option<u64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueAdminKeyResponse {
+    admin_key: String,
+    key_fingerprint: String,
+}
+
+pub async fn issue_admin_key(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(IssueAdminKeyArgs {
+        name,
+        scope,
+        member_id,
+    }): Json<IssueAdminKeyArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    let principal = must_be_admin_with_write_access(&identity)?;
+    let scope = match scope {
+        Some(scope) => scope.parse().map_err(|_| {
+            anyhow::anyhow!(ErrorMetadata::bad_request(
+                "InvalidAdminKeyScope",
+                format!("Invalid admin key scope {scope}"),
+            ))
+        })?,
+        None => AdminKeyScope::Full,
+    };
+    let member_id = match (member_id, principal) {
+        (Some(member_id), _) => MemberId(member_id),
+        (None, AdminIdentityPrincipal::Member(member_id)) => member_id,
+        (None, AdminIdentityPrincipal::Team(_)) => {
+            return Err(anyhow::anyhow!(ErrorMetadata::bad_request(
+                "MissingMemberId",
+                "Keys issued with a team token need a memberId",
+            ))
+            .into())
+        },
+    };
+    let (admin_key, key_fingerprint) = st
+        .application
+        .issue_admin_key(identity, name, member_id, scope)
+        .await?;
+    Ok(Json(IssueAdminKeyResponse {
+        admin_key: admin_key.as_string(),
+        key_fingerprint,
+    }))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevokeAdminKeyArgs {
+    /// The fingerprint from `GET /api/admin_keys` or the audit log.
+    key_fingerprint: Option<String>,
+    /// The key itself, for keys whose fingerprint isn't known.
+    admin_key: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RevokeAdminKeyResponse {
+    /// False if the key had already been revoked.
+    revoked: bool,
+}
+
+pub async fn revoke_admin_key(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(RevokeAdminKeyArgs {
+        key_fingerprint,
+        admin_key,
+    }): Json<RevokeAdminKeyArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let key_fingerprint = match (key_fingerprint, admin_key) {
+        (Some(key_fingerprint), None) => key_fingerprint,
+        (None, Some(admin_key)) => admin_key_fingerprint(&admin_key),
+        _ => {
+            return Err(anyhow::anyhow!(ErrorMetadata::bad_request(
+                "InvalidRevokeAdminKeyArgs",
+                "Pass exactly one of keyFingerprint and adminKey",
+            ))
+            .into())
+        },
+    };
+    let revoked = st
+        .application
+        .revoke_admin_key(identity, key_fingerprint)
+        .await?;
+    Ok(Json(RevokeAdminKeyResponse { revoked }))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RotateAdminKeySecretArgs {
+    /// How long keys issued before the rotation keep working. Defaults to a
+    /// day.
+    grace_period_secs: Option<u64>,
+}
+
+/// Starts encrypting new admin keys with a fresh secret. Existing keys,
+/// including the one making this call, stop working after the grace period.
+pub async fn rotate_admin_key_secret(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(RotateAdminKeySecretArgs { grace_period_secs }): Json<RotateAdminKeySecretArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let grace_period = grace_period_secs
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_ROTATION_GRACE_PERIOD);
+    st.application
+        .rotate_admin_key_secret(identity, grace_period)
+        .await?;
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueSessionTokenArgs {
+    /// How long the token is valid. Defaults to
+    /// `ADMIN_SESSION_TOKEN_DEFAULT_TTL`, and can't be longer than
+    /// `ADMIN_SESSION_TOKEN_MAX_TTL`.
+    ttl_secs: Option<u64>,
+    /// Limits the token to one of the `AdminKeyScope`s, which must not allow
+    /// anything the key doesn't. Defaults to the key's scope.
+    scope: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueSessionTokenResponse {
+    /// Sent in place of the admin key, e.g. `Authorization: Convex <token>`.
+    token: String,
+    expires_at_ms: u64,
+}
+
+/// Exchanges the admin key the request was made with for a session token.
+/// Revoking the key revokes its tokens too.
+pub async fn issue_admin_session_token(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(IssueSessionTokenArgs { ttl_secs, scope }): Json<IssueSessionTokenArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let Identity::InstanceAdmin(admin) = &identity else {
+        return Err(anyhow::anyhow!(ErrorMetadata::bad_request(
+            "InvalidSessionTokenParent",
+            "Session tokens can only be issued for admin keys",
+        ))
+        .into());
+    };
+    let ttl = ttl_secs
+        .map(Duration::from_secs)
+        .unwrap_or(*ADMIN_SESSION_TOKEN_DEFAULT_TTL);
+    if ttl.is_zero() || ttl > *ADMIN_SESSION_TOKEN_MAX_TTL {
+        return Err(anyhow::anyhow!(ErrorMetadata::bad_request(
+            "InvalidSessionTokenTtl",
+            format!(
+                "ttlSecs must be between 1 and {}",
+                ADMIN_SESSION_TOKEN_MAX_TTL.as_secs()
+            ),
+        ))
+        .into());
+    }
+    let scope = scope
+        .map(|scope| scope.parse::<AdminKeyScope>())
+        .transpose()?;
+    let expires_at = SystemTime::now() + ttl;
+    let token = st
+        .application
+        .key_broker()
+        .issue_admin_session_token(admin, scope, expires_at)?;
+    Ok(Json(IssueSessionTokenResponse {
+        token: token.as_string(),
+        expires_at_ms: expires_at
+            .duration_since(SystemTime::UNIX_EPOCH)?
+            .as_millis() as u64,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum_extra::headers::authorization::Credentials;
+    use common::types::AdminKey;
+    use http::{
+        HeaderValue,
+        Request,
+        StatusCode,
+    };
+    use runtime::prod::ProdRuntime;
+    use serde_json::{
+        json,
+        Value as JsonValue,
+    };
+
+    use crate::test_helpers::setup_backend_for_test;
+
+    fn post(
+        uri: &str,
+        body: JsonValue,
+        authorization: HeaderValue,
+    ) -> anyhow::Result<Request<axum::body::Body>> {
+        Ok(Request::builder()
+            .uri(uri)
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .header("Authorization", authorization)
+            .body(axum::body::Body::from(serde_json::to_vec(&body)?))?)
+    }
+
+    #[convex_macro::prod_rt_test]
+    async fn test_issue_and_revoke_admin_key(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        let req = post(
+            "/api/admin_keys",
+            json!({"name": "ci", "scope": "deploy-only"}),
+            backend.admin_auth_header.0.encode(),
+        )?;
+        let issued: JsonValue = backend.expect_success(req).await?;
+        let key = AdminKey::new(issued["adminKey"].as_str().unwrap().to_string());
+        let key_header = key.as_header()?.0.encode();
+
+        let body = json!({"changes": [{"name": "name1", "value": "value1"}]});
+        let req = post(
+            "/api/update_environment_variables",
+            body.clone(),
+            key_header.clone(),
+        )?;
+        backend.expect_success::<JsonValue>(req).await?;
+
+        let req = post(
+            "/api/admin_keys/revoke",
+            json!({"keyFingerprint": issued["keyFingerprint"]}),
+            backend.admin_auth_header.0.encode(),
+        )?;
+        let revoked: JsonValue = backend.expect_success(req).await?;
+        assert_eq!(revoked, json!({"revoked": true}));
+
+        let req = post("/api/update_environment_variables", body, key_header)?;
+        backend
+            .expect_error(req, StatusCode::UNAUTHORIZED, "BadAdminKey")
+            .await?;
+        Ok(())
+    }
+
+    #[convex_macro::prod_rt_test]
+    async fn test_revoke_admin_key_after_rotation(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        let req = post(
+            "/api/admin_keys",
+            json!({"name": "ci", "scope": "deploy-only"}),
+            backend.admin_auth_header.0.encode(),
+        )?;
+        let issued: JsonValue = backend.expect_success(req).await?;
+        let key = AdminKey::new(issued["adminKey"].as_str().unwrap().to_string());
+        let key_header = key.as_header()?.0.encode();
+
+        let req = post(
+            "/api/admin_keys/rotate_secret",
+            json!({"gracePeriodSecs": 3600}),
+            backend.admin_auth_header.0.encode(),
+        )?;
+        backend.expect_success::<JsonValue>(req).await?;
+
+        // Keys from the retired secret keep working during the grace period.
+        let body = json!({"changes": [{"name": "name1", "value": "value1"}]});
+        let req = post(
+            "/api/update_environment_variables",
+            body.clone(),
+            key_header.clone(),
+        )?;
+        backend.expect_success::<JsonValue>(req).await?;
+
+        // Revoking one doesn't wait for the grace period to end.
+        let req = post(
+            "/api/admin_keys/revoke",
+            json!({"adminKey": key.as_str()}),
+            backend.admin_auth_header.0.encode(),
+        )?;
+        let revoked: JsonValue = backend.expect_success(req).await?;
+        assert_eq!(revoked, json!({"revoked": true}));
+
+        let req = post(
+            "/api/update_environment_variables",
+            body.clone(),
+            key_header,
+        )?;
+        backend
+            .expect_error(req, StatusCode::UNAUTHORIZED, "BadAdminKey")
+            .await?;
+        let req = post(
+            "/api/update_environment_variables",
+            body,
+            backend.admin_auth_header.0.encode(),
+        )?;
+        backend.expect_success::<JsonValue>(req).await?;
+        Ok(())
+    }
+
+    #[convex_macro::prod_rt_test]
+    async fn test_admin_session_token(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        let req = post(
+            "/api/admin_keys/session_token",
+            json!({"ttlSecs": 60, "scope": "deploy-only"}),
+            backend.admin_auth_header.0.encode(),
+        )?;
+        let issued: JsonValue = backend.expect_success(req).await?;
+        let token = AdminKey::new(issued["token"].as_str().unwrap().to_string());
+        let token_header = token.as_header()?.0.encode();
+
+        let body = json!({"changes": [{"name": "name1", "value": "value1"}]});
+        let req = post(
+            "/api/update_environment_variables",
+            body,
+            token_header.clone(),
+        )?;
+        backend.expect_success::<JsonValue>(req).await?;
+
+        // The token is limited to its scope.
+        let req = post(
+            "/api/admin_keys",
+            json!({"name": "ci"}),
+            token_header.clone(),
+        )?;
+        backend
+            .expect_error(req, StatusCode::FORBIDDEN, "AdminKeyScope")
+            .await?;
+
+        // Tokens can't be exchanged for other tokens.
+        let req = post(
+            "/api/admin_keys/session_token",
+            json!({"scope": "full"}),
+            token_header,
+        )?;
+        backend
+            .expect_error(req, StatusCode::BAD_REQUEST, "InvalidSessionTokenParent")
+            .await?;
+
+        let req = post(
+            "/api/admin_keys/session_token",
+            json!({"ttlSecs": 365 * 24 * 60 * 60}),
+            backend.admin_auth_header.0.encode(),
+        )?;
+        backend
+            .expect_error(req, StatusCode::BAD_REQUEST, "InvalidSessionTokenTtl")
+            .await?;
+        Ok(())
+    }
+}
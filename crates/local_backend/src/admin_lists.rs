@@ -0,0 +1,470 @@
+//! Paginated listings of a deployment's tables, functions and scheduled jobs
+//! for dashboards and scripts.
+//!
+//! Every listing takes the same query parameters: `sort` (one of the fields
+//! the listing can be sorted by, defaulting to the first), `order` (`asc` or
+//! `desc`), `filter` (a case-insensitive substring of the name or path),
+//! `limit`, and the `cursor` from the previous page. Items are ordered by the
+//! sort field and then by their name or ID, so pages don't skip or repeat
+//! items when others are added or removed between requests.
+//!
+//! Log sinks are configured outside this backend, so they aren't listed here.
+use std::cmp::Ordering;
+
+use axum::{
+    extract::State,
+    response::IntoResponse,
+};
+use common::{
+    components::ComponentId,
+    http::{
+        extract::{
+            Json,
+            Query,
+        },
+        HttpResponseError,
+    },
+};
+use errors::ErrorMetadata;
+use model::{
+    modules::{
+        module_versions::Visibility,
+        ModuleModel,
+    },
+    scheduled_jobs::{
+        types::ScheduledJobState,
+        SchedulerModel,
+    },
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use sync_types::CanonicalizedUdfPath;
+use value::{
+    DeveloperDocumentId,
+    TableNamespace,
+};
+
+use crate::{
+    admin::must_be_admin,
+    authentication::ExtractIdentity,
+    LocalAppState,
+};
+
+const DEFAULT_PAGE_SIZE: usize = 100;
+const MAX_PAGE_SIZE: usize = 1000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListArgs {
+    component_id: Option<String>,
+    sort: Option<String>,
+    order: Option<SortOrder>,
+    filter: Option<String>,
+    limit: Option<usize>,
+    /// The `cursor` from the previous page.
+    cursor: Option<String>,
+}
+
+/// The value an item is sorted by. All of a listing's items have the same
+/// variant for a given sort field.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(untagged)]
+enum SortValue {
+    Int(i64),
+    String(String),
+}
+
+/// Where the previous page ended. The sort and order are included so a cursor
+/// can't be used with a different sort.
+#[derive(Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+struct ListCursor {
+    sort: String,
+    order: SortOrder,
+    value: SortValue,
+    id: String,
+}
+
+impl ListCursor {
+    fn encode(&self) -> anyhow::Result<String> {
+        Ok(base64::encode_config(
+            serde_json::to_vec(self)?,
+            base64::URL_SAFE_NO_PAD,
+        ))
+    }
+
+    fn decode(cursor: &str) -> anyhow::Result<Self> {
+        base64::decode_config(cursor, base64::URL_SAFE_NO_PAD)
+            .ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .ok_or_else(|| {
+                ErrorMetadata::bad_request("InvalidListCursor", format!("Invalid cursor {cursor}"))
+                    .into()
+            })
+    }
+}
+
+trait ListItem: Serialize {
+    /// The fields the listing can be sorted by. The first is the default.
+    const SORT_FIELDS: &'static [&'static str];
+
+    /// Unique within the listing, to order items with the same sort value.
+    fn id(&self) -> &str;
+
+    fn sort_value(&self, sort: &str) -> SortValue;
+
+    /// The text `filter` is matched against.
+    fn filter_text(&self) -> &str;
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListPage<T> {
+    items: Vec<T>,
+    /// Pass this as `cursor` to get the next page, or `null` if this is the
+    /// last one.
+    cursor: Option<String>,
+}
+
+fn paginate<T: ListItem>(mut items: Vec<T>, args: &ListArgs) -> anyhow::Result<ListPage<T>> {
+    let sort = match &args.sort {
+        Some(sort) if T::SORT_FIELDS.contains(&sort.as_str()) => sort.clone(),
+        Some(sort) => anyhow::bail!(ErrorMetadata::bad_request(
+            "InvalidListSort",
+            format!(
+                "Can't sort by {sort}. Sort by one of: {}",
+                T::SORT_FIELDS.join(", ")
+            ),
+        )),
+        None => T::SORT_FIELDS[0].to_string(),
+    };
+    let order = args.order.unwrap_or_default();
+    let limit = args
+        .limit
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .clamp(1, MAX_PAGE_SIZE);
+    let after = args.cursor.as_deref().map(ListCursor::decode).transpose()?;
+    if let Some(after) = &after {
+        anyhow::ensure!(
+            after.sort == sort && after.order == order,
+            ErrorMetadata::bad_request(
+                "InvalidListCursor",
+                "The cursor is from a listing with a different sort or order",
+            )
+        );
+    }
+    let filter = args.filter.as_ref().map(|filter| filter.to_lowercase());
+    let compare = |a: (&SortValue, &str), b: (&SortValue, &str)| match order {
+        SortOrder::Asc => a.cmp(&b),
+        SortOrder::Desc => b.cmp(&a),
+    };
+
+    let mut keyed: Vec<_> = items
+        .drain(..)
+        .filter(|item| {
+            filter.as_ref().map_or(true, |filter| {
+                item.filter_text().to_lowercase().contains(filter)
+            })
+        })
+        .map(|item| (item.sort_value(&sort), item))
+        .filter(|(value, item)| {
+            after.as_ref().map_or(true, |after| {
+                compare((value, item.id()), (&after.value, after.id.as_str())) == Ordering::Greater
+            })
+        })
+        .collect();
+    keyed.sort_by(|(a, a_item), (b, b_item)| compare((a, a_item.id()), (b, b_item.id())));
+
+    let has_more = keyed.len() > limit;
+    keyed.truncate(limit);
+    let cursor = match keyed.last() {
+        Some((value, item)) if has_more => Some(
+            ListCursor {
+                sort,
+                order,
+                value: value.clone(),
+                id: item.id().to_string(),
+            }
+            .encode()?,
+        ),
+        _ => None,
+    };
+    Ok(ListPage {
+        items: keyed.into_iter().map(|(_, item)| item).collect(),
+        cursor,
+    })
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableJson {
+    name: String,
+    table_number: u32,
+    document_count: u64,
+    size_bytes: u64,
+}
+
+impl ListItem for TableJson {
+    const SORT_FIELDS: &'static [&'static str] =
+        &["name", "tableNumber", "documentCount", "sizeBytes"];
+
+    fn id(&self) -> &str {
+        &self.name
+    }
+
+    fn sort_value(&self, sort: &str) -> SortValue {
+        match sort {
+            "tableNumber" => SortValue::Int(self.table_number.into()),
+            "documentCount" => SortValue::Int(self.document_count as i64),
+            "sizeBytes" => SortValue::Int(self.size_bytes as i64),
+            _ => SortValue::String(self.name.clone()),
+        }
+    }
+
+    fn filter_text(&self) -> &str {
+        &self.name
+    }
+}
+
+/// Lists the component's user tables with their approximate sizes.
+pub async fn list_tables(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Query(args): Query<ListArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let component = ComponentId::deserialize_from_string(args.component_id.as_deref())?;
+    let namespace = TableNamespace::from(component);
+    let snapshot = st.application.latest_snapshot()?;
+    let tables = snapshot
+        .table_mapping()
+        .iter_active_user_tables()
+        .filter(|(_, table_namespace, ..)| *table_namespace == namespace)
+        .map(|(_, _, table_number, table_name)| {
+            let summary = snapshot.table_summary(namespace, table_name);
+            TableJson {
+                name: table_name.to_string(),
+                table_number: table_number.into(),
+                document_count: summary.num_values() as u64,
+                size_bytes: summary.total_size() as u64,
+            }
+        })
+        .collect();
+    Ok(Json(paginate(tables, &args)?))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionJson {
+    /// Like `messages:list`.
+    path: String,
+    udf_type: String,
+    /// `public` or `internal`, or `null` for functions from old versions of
+    /// the `convex` package.
+    visibility: Option<String>,
+}
+
+impl ListItem for FunctionJson {
+    const SORT_FIELDS: &'static [&'static str] = &["path", "udfType"];
+
+    fn id(&self) -> &str {
+        &self.path
+    }
+
+    fn sort_value(&self, sort: &str) -> SortValue {
+        match sort {
+            "udfType" => SortValue::String(self.udf_type.clone()),
+            _ => SortValue::String(self.path.clone()),
+        }
+    }
+
+    fn filter_text(&self) -> &str {
+        &self.path
+    }
+}
+
+/// Lists the functions in the component's deployed modules.
+pub async fn list_functions(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Query(args): Query<ListArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let component = ComponentId::deserialize_from_string(args.component_id.as_deref())?;
+    let mut tx = st.application.begin(identity).await?;
+    let mut functions = vec![];
+    for module in ModuleModel::new(&mut tx)
+        .get_application_metadata(component)
+        .await?
+    {
+        let module = module.into_value();
+        let Some(analyze_result) = module.analyze_result else {
+            continue;
+        };
+        for function in analyze_result.functions.iter() {
+            let path = CanonicalizedUdfPath::new(module.path.clone(), function.name.clone());
+            functions.push(FunctionJson {
+                path: path.to_string(),
+                udf_type: function.udf_type.to_string(),
+                visibility: function.visibility.as_ref().map(|visibility| {
+                    match visibility {
+                        Visibility::Public => "public",
+                        Visibility::Internal => "internal",
+                    }
+                    .to_string()
+                }),
+            });
+        }
+    }
+    Ok(Json(paginate(functions, &args)?))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledJobJson {
+    id: String,
+    udf_path: String,
+    /// `pending`, `inProgress`, `success`, `failed` or `canceled`.
+    state: String,
+    scheduled_time_ms: i64,
+    completed_time_ms: Option<i64>,
+}
+
+impl ListItem for ScheduledJobJson {
+    const SORT_FIELDS: &'static [&'static str] = &["scheduledTime", "udfPath", "state"];
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn sort_value(&self, sort: &str) -> SortValue {
+        match sort {
+            "udfPath" => SortValue::String(self.udf_path.clone()),
+            "state" => SortValue::String(self.state.clone()),
+            _ => SortValue::Int(self.scheduled_time_ms),
+        }
+    }
+
+    fn filter_text(&self) -> &str {
+        &self.udf_path
+    }
+}
+
+/// Lists the component's scheduled jobs, including completed ones that haven't
+/// been cleaned up yet.
+pub async fn list_scheduled_jobs(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Query(args): Query<ListArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let component = ComponentId::deserialize_from_string(args.component_id.as_deref())?;
+    let mut tx = st.application.begin(identity).await?;
+    let jobs = SchedulerModel::new(&mut tx, component.into())
+        .list()
+        .await?
+        .into_iter()
+        .map(|job| {
+            let (id, job) = job.into_id_and_value();
+            let state = match job.state {
+                ScheduledJobState::Pending => "pending",
+                ScheduledJobState::InProgress => "inProgress",
+                ScheduledJobState::Success => "success",
+                ScheduledJobState::Failed(_) => "failed",
+                ScheduledJobState::Canceled => "canceled",
+            };
+            ScheduledJobJson {
+                id: DeveloperDocumentId::from(id).encode(),
+                udf_path: job.path.udf_path.to_string(),
+                state: state.to_string(),
+                scheduled_time_ms: i64::from(job.original_scheduled_ts) / 1_000_000,
+                completed_time_ms: job.completed_ts.map(|ts| i64::from(ts) / 1_000_000),
+            }
+        })
+        .collect();
+    Ok(Json(paginate(jobs, &args)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use errors::ErrorMetadataAnyhowExt;
+
+    use super::{
+        paginate,
+        ListArgs,
+        SortOrder,
+        TableJson,
+    };
+
+    fn table(name: &str, document_count: u64) -> TableJson {
+        TableJson {
+            name: name.to_string(),
+            table_number: 1,
+            document_count,
+            size_bytes: 0,
+        }
+    }
+
+    fn names(tables: &[TableJson]) -> Vec<&str> {
+        tables.iter().map(|table| table.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_paginate() -> anyhow::Result<()> {
+        let tables = || {
+            vec![
+                table("users", 10),
+                table("messages", 30),
+                table("channels", 10),
+                table("user_settings", 5),
+            ]
+        };
+        let mut args = ListArgs {
+            component_id: None,
+            sort: Some("documentCount".to_string()),
+            order: Some(SortOrder::Desc),
+            filter: None,
+            limit: Some(2),
+            cursor: None,
+        };
+        let page = paginate(tables(), &args)?;
+        assert_eq!(names(&page.items), vec!["messages", "users"]);
+
+        // Items added before the cursor don't shift the next page.
+        let mut more_tables = tables();
+        more_tables.push(table("audit", 50));
+        args.cursor = page.cursor;
+        let page = paginate(more_tables, &args)?;
+        assert_eq!(names(&page.items), vec!["channels", "user_settings"]);
+        assert!(page.cursor.is_none());
+
+        let args = ListArgs {
+            filter: Some("USER".to_string()),
+            sort: None,
+            order: None,
+            limit: None,
+            cursor: None,
+            ..args
+        };
+        let page = paginate(tables(), &args)?;
+        assert_eq!(names(&page.items), vec!["user_settings", "users"]);
+
+        let args = ListArgs {
+            sort: Some("udfType".to_string()),
+            ..args
+        };
+        let err = paginate(tables(), &args).err().unwrap();
+        assert_eq!(err.short_msg(), "InvalidListSort");
+        Ok(())
+    }
+}
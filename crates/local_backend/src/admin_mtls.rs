@@ -0,0 +1,240 @@
+//! Serves the deployment on `--admin-mtls-port`, where admins authenticate
+//! with a TLS client certificate instead of an admin key.
+//!
+//! The handshake only succeeds for client certificates issued by one of the
+//! CAs in `--admin-mtls-client-ca`. Requests without an `Authorization` header
+//! then authenticate as the admin that the subject's common name is mapped to
+//! by `--admin-mtls-principal`, with that principal's scope. Requests with one
+//! authenticate with the token instead. With `--require-admin-mtls`, admin
+//! keys are rejected on every port.
+use std::{
+    fs,
+    io,
+    net::SocketAddr,
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::Context;
+use axum::{
+    body::Body,
+    extract::ConnectInfo,
+    Router,
+};
+use http::Request;
+use hyper::body::Incoming;
+use hyper_util::{
+    rt::{
+        TokioExecutor,
+        TokioIo,
+    },
+    server::conn::auto::Builder,
+    service::TowerToHyperService,
+};
+use tokio::net::{
+    TcpListener,
+    TcpStream,
+};
+use tokio_rustls::{
+    rustls::{
+        pki_types::CertificateDer,
+        server::WebPkiClientVerifier,
+        RootCertStore,
+        ServerConfig,
+    },
+    TlsAcceptor,
+};
+use tower::ServiceExt;
+
+use crate::config::LocalConfig;
+
+#[derive(Clone, Debug)]
+pub struct AdminMtlsConfig {
+    pub bind_address: ([u8; 4], u16),
+    /// PEM file with the server's certificate chain.
+    pub cert: PathBuf,
+    /// PEM file with the server's private key.
+    pub key: PathBuf,
+    /// PEM file with the CAs client certificates must be issued by.
+    pub client_ca: PathBuf,
+}
+
+/// The subject common name of the verified client certificate a request was
+/// made with, set as a request extension by the mTLS listener.
+#[derive(Clone, Debug)]
+pub struct AdminClientCertificate(pub String);
+
+fn read_certs(path: &PathBuf) -> anyhow::Result<Vec<CertificateDer<'static>>> {
+    let pem = fs::read(path).with_context(|| format!("Couldn't read {}", path.display()))?;
+    let certs = rustls_pemfile::certs(&mut &pem[..]).collect::<Result<Vec<_>, _>>()?;
+    anyhow::ensure!(!certs.is_empty(), "No certificates in {}", path.display());
+    Ok(certs)
+}
+
+fn server_config(config: &AdminMtlsConfig) -> anyhow::Result<ServerConfig> {
+    let mut roots = RootCertStore::empty();
+    for ca in read_certs(&config.client_ca)? {
+        roots.add(ca)?;
+    }
+    let verifier = WebPkiClientVerifier::builder(Arc::new(roots)).build()?;
+    let key_pem =
+        fs::read(&config.key).with_context(|| format!("Couldn't read {}", config.key.display()))?;
+    let private_key = rustls_pemfile::private_key(&mut &key_pem[..])?
+        .with_context(|| format!("No private key in {}", config.key.display()))?;
+    let mut server_config = ServerConfig::builder()
+        .with_client_cert_verifier(verifier)
+        .with_single_cert(read_certs(&config.cert)?, private_key)?;
+    server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(server_config)
+}
+
+fn common_name(certificate: &CertificateDer) -> anyhow::Result<String> {
+    let (_, certificate) = x509_parser::parse_x509_certificate(certificate)
+        .map_err(|e| anyhow::anyhow!("Invalid client certificate: {e}"))?;
+    let common_name = certificate
+        .subject()
+        .iter_common_name()
+        .next()
+        .context("Client certificate subject has no common name")?;
+    Ok(common_name.as_str()?.to_string())
+}
+
+/// Serves `router` on `--admin-mtls-port`, if it's set.
+pub async fn serve_admin_mtls(
+    config: &LocalConfig,
+    router: Router,
+    mut shutdown_rx: async_broadcast::Receiver<()>,
+) -> anyhow::Result<()> {
+    let Some(mtls_config) = config.admin_mtls() else {
+        return Ok(());
+    };
+    let acceptor = TlsAcceptor::from(Arc::new(server_config(&mtls_config)?));
+    let addr = SocketAddr::from(mtls_config.bind_address);
+    let listener = TcpListener::bind(addr).await?;
+    tracing::info!("Serving admin mTLS at {addr}...");
+    loop {
+        let (tcp_stream, remote_addr) = tokio::select! {
+            conn = listener.accept() => match conn {
+                Ok(conn) => conn,
+                Err(e) => {
+                    tracing::error!("Failed to accept admin mTLS connection: {e}");
+                    if !matches!(
+                        e.kind(),
+                        io::ErrorKind::ConnectionAborted | io::ErrorKind::ConnectionReset
+                    ) {
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                    }
+                    continue;
+                },
+            },
+            _ = shutdown_rx.recv() => break,
+        };
+        let acceptor = acceptor.clone();
+        let router = router.clone();
+        tokio::spawn(async move {
+            if let Err(e) = serve_connection(acceptor, router, tcp_stream, remote_addr).await {
+                tracing::debug!("Admin mTLS connection from {remote_addr} failed: {e:#}");
+            }
+        });
+    }
+    tracing::info!("Shut down admin mTLS");
+    Ok(())
+}
+
+async fn serve_connection(
+    acceptor: TlsAcceptor,
+    router: Router,
+    tcp_stream: TcpStream,
+    remote_addr: SocketAddr,
+) -> anyhow::Result<()> {
+    tcp_stream.set_nodelay(true)?;
+    // The handshake fails without a client certificate from a trusted CA.
+    let tls_stream = acceptor.accept(tcp_stream).await?;
+    let certificate = tls_stream
+        .get_ref()
+        .1
+        .peer_certificates()
+        .and_then(|chain| chain.first())
+        .context("Missing client certificate")?;
+    let client_certificate = AdminClientCertificate(common_name(certificate)?);
+    let service = router.map_request(move |mut request: Request<Incoming>| {
+        request.extensions_mut().insert(ConnectInfo(remote_addr));
+        request.extensions_mut().insert(client_certificate.clone());
+        request.map(Body::new)
+    });
+    Builder::new(TokioExecutor::new())
+        .serve_connection_with_upgrades(TokioIo::new(tls_stream), TowerToHyperService::new(service))
+        .await
+        .map_err(|e| anyhow::anyhow!(e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use common::types::MemberId;
+    use http::{
+        Request,
+        StatusCode,
+    };
+    use rcgen::{
+        CertificateParams,
+        DistinguishedName,
+        DnType,
+        KeyPair,
+    };
+    use runtime::prod::ProdRuntime;
+    use serde_json::json;
+
+    use super::common_name;
+    use crate::test_helpers::setup_backend_for_test;
+
+    #[test]
+    fn test_common_name() -> anyhow::Result<()> {
+        let mut params = CertificateParams::new(vec![])?;
+        params.distinguished_name = DistinguishedName::new();
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "ci-pipeline");
+        params
+            .distinguished_name
+            .push(DnType::OrganizationName, "Acme");
+        let certificate = params.self_signed(&KeyPair::generate()?)?;
+        assert_eq!(common_name(certificate.der())?, "ci-pipeline");
+
+        params.distinguished_name = DistinguishedName::new();
+        let certificate = params.self_signed(&KeyPair::generate()?)?;
+        assert!(common_name(certificate.der()).is_err());
+        Ok(())
+    }
+
+    #[convex_macro::prod_rt_test]
+    async fn test_require_admin_mtls_rejects_body_keys(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        let key_broker = backend.st.application.key_broker();
+        let admin_key = key_broker.issue_admin_key(MemberId(2));
+        let body = serde_json::to_vec(&json!({"adminKey": admin_key.as_str()}))?;
+        let get_config = || {
+            Request::builder()
+                .uri("/api/get_config")
+                .method("POST")
+                .header("Content-Type", "application/json")
+                .body(axum::body::Body::from(body.clone()))
+        };
+        backend
+            .expect_success::<serde_json::Value>(get_config()?)
+            .await?;
+
+        key_broker.set_admin_client_certificates(BTreeMap::new(), true);
+        backend
+            .expect_error(
+                get_config()?,
+                StatusCode::UNAUTHORIZED,
+                "ClientCertificateRequired",
+            )
+            .await?;
+        Ok(())
+    }
+}
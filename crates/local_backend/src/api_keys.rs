@@ -0,0 +1,198 @@
+//! Manages API keys that backend services send as a bearer token to call
+//! public functions with an identity, instead of minting user JWTs.
+use std::collections::BTreeMap;
+
+use axum::{
+    extract::State,
+    response::IntoResponse,
+};
+use common::{
+    document::ParsedDocument,
+    http::{
+        extract::Json,
+        HttpResponseError,
+    },
+};
+use model::api_keys::types::ApiKey;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::Value as JsonValue;
+use value::DeveloperDocumentId;
+
+use crate::{
+    admin::{
+        must_be_admin,
+        must_be_admin_with_write_access,
+    },
+    authentication::ExtractIdentity,
+    LocalAppState,
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiKeyJson {
+    id: String,
+    name: String,
+    subject: String,
+    custom_claims: BTreeMap<String, JsonValue>,
+    created_at_ms: u64,
+}
+
+impl TryFrom<ParsedDocument<ApiKey>> for ApiKeyJson {
+    type Error = anyhow::Error;
+
+    fn try_from(key: ParsedDocument<ApiKey>) -> anyhow::Result<Self> {
+        let (id, key) = key.into_id_and_value();
+        Ok(Self {
+            id: DeveloperDocumentId::from(id).encode(),
+            name: key.name,
+            subject: key.subject,
+            custom_claims: key
+                .custom_claims
+                .into_iter()
+                .map(|(claim, value)| anyhow::Ok((claim, serde_json::from_str(&value)?)))
+                .collect::<anyhow::Result<_>>()?,
+            created_at_ms: key.created_at.as_ms_since_epoch()?,
+        })
+    }
+}
+
+pub async fn list_api_keys(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let keys = st
+        .application
+        .list_api_keys(identity)
+        .await?
+        .into_iter()
+        .map(ApiKeyJson::try_from)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(Json(keys))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateApiKeyArgs {
+    /// What the key is for, like "billing-service".
+    name: String,
+    /// The identity's subject for calls made with the key.
+    subject: String,
+    /// Extra claims on the identity, like `{"role": "billing"}`.
+    #[serde(default)]
+    custom_claims: BTreeMap<String, JsonValue>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateApiKeyResponse {
+    id: String,
+    /// Only returned once. Send it as `Authorization: Bearer <apiKey>`.
+    api_key: String,
+}
+
+pub async fn create_api_key(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(CreateApiKeyArgs {
+        name,
+        subject,
+        custom_claims,
+    }): Json<CreateApiKeyArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let (id, api_key) = st
+        .application
+        .create_api_key(identity, name, subject, custom_claims)
+        .await?;
+    Ok(Json(CreateApiKeyResponse { id, api_key }))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteApiKeyArgs {
+    id: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteApiKeyResponse {
+    /// False if there was no key with the ID.
+    deleted: bool,
+}
+
+pub async fn delete_api_key(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(DeleteApiKeyArgs { id }): Json<DeleteApiKeyArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let deleted = st.application.delete_api_key(identity, id).await?;
+    Ok(Json(DeleteApiKeyResponse { deleted }))
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{
+        Request,
+        StatusCode,
+    };
+    use runtime::prod::ProdRuntime;
+    use serde_json::{
+        json,
+        Value as JsonValue,
+    };
+
+    use crate::test_helpers::setup_backend_for_test;
+
+    #[convex_macro::prod_rt_test]
+    async fn test_create_and_delete_api_key(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        let post = |uri: &str, body: JsonValue| {
+            Request::builder()
+                .uri(uri)
+                .method("POST")
+                .header("Content-Type", "application/json")
+                .header("Authorization", backend.admin_auth_header.0.encode())
+                .body(axum::body::Body::from(serde_json::to_vec(&body).unwrap()))
+        };
+        let req = post(
+            "/api/api_keys",
+            json!({
+                "name": "billing",
+                "subject": "billing-service",
+                "customClaims": {"role": "billing"},
+            }),
+        )?;
+        let created: JsonValue = backend.expect_success(req).await?;
+        assert!(created["apiKey"]
+            .as_str()
+            .unwrap()
+            .starts_with("convex_api_key_"));
+
+        let req = Request::builder()
+            .uri("/api/api_keys")
+            .method("GET")
+            .header("Authorization", backend.admin_auth_header.0.encode())
+            .body(axum::body::Body::empty())?;
+        let keys: JsonValue = backend.expect_success(req).await?;
+        assert_eq!(keys[0]["subject"], "billing-service");
+        assert_eq!(keys[0]["customClaims"], json!({"role": "billing"}));
+
+        let req = post(
+            "/api/api_keys",
+            json!({"name": "bad", "subject": "s", "customClaims": {"sub": "x"}}),
+        )?;
+        backend
+            .expect_error(req, StatusCode::BAD_REQUEST, "InvalidApiKey")
+            .await?;
+
+        let req = post("/api/api_keys/delete", json!({"id": created["id"]}))?;
+        let deleted: JsonValue = backend.expect_success(req).await?;
+        assert_eq!(deleted, json!({"deleted": true}));
+        Ok(())
+    }
+}
@@ -0,0 +1,157 @@
+use std::time::Duration;
+
+use application::{
+    archival::TableArchivalStatus,
+    valid_identifier::ValidIdentifier,
+};
+use axum::{
+    extract::State,
+    response::IntoResponse,
+};
+use common::{
+    components::ComponentId,
+    http::{
+        extract::Json,
+        HttpResponseError,
+    },
+};
+use http::StatusCode;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use value::TableName;
+
+use crate::{
+    admin::{
+        must_be_admin,
+        must_be_admin_with_write_access,
+    },
+    authentication::ExtractIdentity,
+    LocalAppState,
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetArchivalPolicyArgs {
+    component_id: Option<String>,
+    table_name: String,
+    /// Documents are archived once they're this old. `None` stops archiving,
+    /// but leaves already archived documents where they are.
+    archive_after_secs: Option<u64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableArchivalStatusJson {
+    component_path: String,
+    table_name: String,
+    archive_after_secs: u64,
+    segments: u64,
+    archived_documents: u64,
+    archived_bytes: u64,
+}
+
+impl From<TableArchivalStatus> for TableArchivalStatusJson {
+    fn from(status: TableArchivalStatus) -> Self {
+        Self {
+            component_path: String::from(status.component),
+            table_name: status.table_name.to_string(),
+            archive_after_secs: status.archive_after.as_secs(),
+            segments: status.segments,
+            archived_documents: status.archived_documents,
+            archived_bytes: status.archived_bytes,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivalStatusResponse {
+    tables: Vec<TableArchivalStatusJson>,
+}
+
+/// Lists the tables with an archival policy and how much has been archived
+/// from each.
+pub async fn get_archival_status(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let tables = st
+        .application
+        .archival_status(identity)
+        .await?
+        .into_iter()
+        .map(TableArchivalStatusJson::from)
+        .collect();
+    Ok(Json(ArchivalStatusResponse { tables }))
+}
+
+/// Sets or clears the archival policy for a table. Archived documents are only
+/// readable with `db.get(id, { includeArchived: true })`.
+pub async fn set_archival_policy(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(SetArchivalPolicyArgs {
+        component_id,
+        table_name,
+        archive_after_secs,
+    }): Json<SetArchivalPolicyArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let table_name = table_name.parse::<ValidIdentifier<TableName>>()?.0;
+    let component = ComponentId::deserialize_from_string(component_id.as_deref())?;
+    st.application
+        .set_archival_policy(
+            identity,
+            component,
+            table_name,
+            archive_after_secs.map(Duration::from_secs),
+        )
+        .await?;
+    Ok(StatusCode::OK)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum_extra::headers::authorization::Credentials;
+    use http::{
+        Request,
+        StatusCode,
+    };
+    use runtime::prod::ProdRuntime;
+    use serde_json::{
+        json,
+        Value as JsonValue,
+    };
+
+    use crate::test_helpers::setup_backend_for_test;
+
+    #[convex_macro::prod_rt_test]
+    async fn test_set_archival_policy_requires_table(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        let req = Request::builder()
+            .uri("/api/archival/set_policy")
+            .method("POST")
+            .header("Authorization", backend.admin_auth_header.0.encode())
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_vec(&json!({
+                "tableName": "logs",
+                "archiveAfterSecs": 86400,
+            }))?))?;
+        backend
+            .expect_error(req, StatusCode::NOT_FOUND, "TableNotFound")
+            .await?;
+
+        let req = Request::builder()
+            .uri("/api/archival")
+            .method("GET")
+            .header("Authorization", backend.admin_auth_header.0.encode())
+            .body(Body::empty())?;
+        let status: JsonValue = backend.expect_success(req).await?;
+        assert_eq!(status, json!({ "tables": [] }));
+        Ok(())
+    }
+}
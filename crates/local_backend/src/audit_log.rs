@@ -0,0 +1,317 @@
+//! Records API calls made with admin keys, like deploys, imports and
+//! environment variable changes, in the `_audit_log` system table, and lists
+//! them for admins.
+//!
+//! Calls are attributed to the admin that `must_be_admin*` established for
+//! them, so keys passed in the request body, like the CLI's, are covered as
+//! well as `Authorization` headers.
+//!
+//! Reads (`GET` requests) aren't recorded, since the dashboard polls them
+//! constantly, except for the ones in [`AUDITED_READ_ROUTES`] that hand out
+//! deployment data.
+use std::{
+    collections::BTreeSet,
+    sync::Arc,
+};
+
+use axum::{
+    extract::{
+        MatchedPath,
+        Request,
+        State,
+    },
+    middleware::Next,
+    response::{
+        IntoResponse,
+        Response,
+    },
+    RequestExt,
+};
+use common::{
+    document::CreationTime,
+    errors::report_error,
+    http::{
+        extract::{
+            Json,
+            Query,
+        },
+        HttpResponseError,
+    },
+    runtime::Runtime,
+};
+use errors::ErrorMetadata;
+use http::{
+    header::CONTENT_LENGTH,
+    Method,
+};
+use keybroker::{
+    AdminIdentity,
+    AdminIdentityPrincipal,
+    Identity,
+};
+use model::audit_log::types::AuditLogEntry;
+use parking_lot::Mutex;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use sync_types::AuthenticationToken;
+
+use crate::{
+    admin::must_be_admin,
+    authentication::{
+        ExtractAuthenticationToken,
+        ExtractIdentity,
+    },
+    LocalAppState,
+};
+
+const DEFAULT_AUDIT_LOG_PAGE_SIZE: usize = 100;
+const MAX_AUDIT_LOG_PAGE_SIZE: usize = 1000;
+
+/// Reads that hand out deployment data in bulk, which are recorded like
+/// writes.
+const AUDITED_READ_ROUTES: &[&str] = &[
+    "/api/export/zip/:id",
+    "/api/export/search_index",
+    "/api/replication/stream",
+    "/api/get_source_code",
+];
+
+tokio::task_local! {
+    /// The admin that authenticated the request `audit_log_middleware` is
+    /// handling.
+    static AUDITED_ADMIN: Arc<Mutex<Option<AdminIdentity>>>;
+}
+
+/// Attributes the request being handled to `identity` in the audit log, if
+/// it's an admin. Called by `must_be_admin*` once they've established the
+/// caller, whether or not it turns out to be allowed.
+pub fn record_audited_admin(identity: &Identity) {
+    if let Identity::InstanceAdmin(admin) | Identity::ActingUser(admin, _) = identity {
+        // Not every caller is handling a request, e.g. in tests.
+        let _ = AUDITED_ADMIN.try_with(|audited| {
+            audited.lock().get_or_insert_with(|| admin.clone());
+        });
+    }
+}
+
+pub async fn audit_log_middleware(
+    State(st): State<LocalAppState>,
+    matched_path: Option<MatchedPath>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let route = matched_path.map_or_else(
+        || req.uri().path().to_string(),
+        |path| path.as_str().to_string(),
+    );
+    if matches!(*req.method(), Method::GET | Method::HEAD | Method::OPTIONS)
+        && !AUDITED_READ_ROUTES.contains(&route.as_str())
+    {
+        return next.run(req).await;
+    }
+    // Handlers that don't check for an admin themselves are attributed to the
+    // key in the `Authorization` header, if any.
+    let header_token = match req.extract_parts::<ExtractAuthenticationToken>().await {
+        Ok(ExtractAuthenticationToken(token @ AuthenticationToken::Admin(..))) => Some(token),
+        _ => None,
+    };
+    let runtime = st.application.runtime().clone();
+    let query_params: BTreeSet<String> = req
+        .uri()
+        .query()
+        .map(|query| {
+            url::form_urlencoded::parse(query.as_bytes())
+                .map(|(name, _)| name.into_owned())
+                .collect()
+        })
+        .unwrap_or_default();
+    let body_bytes = req
+        .headers()
+        .get(CONTENT_LENGTH)
+        .and_then(|length| length.to_str().ok()?.parse().ok());
+    let mut entry = AuditLogEntry {
+        member_id: None,
+        key_fingerprint: String::new(),
+        timestamp: runtime.unix_timestamp(),
+        method: req.method().to_string(),
+        route,
+        path: req.uri().path().to_string(),
+        query_params: query_params.into_iter().collect(),
+        body_bytes,
+        status: 0,
+    };
+
+    let audited_admin = Arc::new(Mutex::new(None));
+    let response = AUDITED_ADMIN
+        .scope(audited_admin.clone(), next.run(req))
+        .await;
+
+    let audited_admin = audited_admin.lock().take();
+    let admin = match (audited_admin, header_token) {
+        (Some(admin), _) => admin,
+        // Credentials that don't check out aren't recorded.
+        (None, Some(token)) => match st
+            .application
+            .authenticate(token, runtime.system_time())
+            .await
+        {
+            Ok(Identity::InstanceAdmin(admin) | Identity::ActingUser(admin, _)) => admin,
+            _ => return response,
+        },
+        (None, None) => return response,
+    };
+    entry.member_id = match admin.principal() {
+        AdminIdentityPrincipal::Member(member_id) => Some(*member_id),
+        AdminIdentityPrincipal::Team(_) => None,
+    };
+    entry.key_fingerprint = admin.key_fingerprint();
+    entry.status = response.status().as_u16();
+    let application = st.application.clone();
+    runtime.spawn("record_audit_log_entry", async move {
+        if let Err(mut e) = application.record_audit_log_entry(entry).await {
+            report_error(&mut e);
+        }
+    });
+    response
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListAuditLogArgs {
+    /// The `cursor` from the previous page.
+    cursor: Option<f64>,
+    limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLogEntryJson {
+    member_id: Option<u64>,
+    key_fingerprint: String,
+    timestamp_ms: u64,
+    method: String,
+    route: String,
+    path: String,
+    query_params: Vec<String>,
+    body_bytes: Option<u64>,
+    status: u16,
+}
+
+impl TryFrom<AuditLogEntry> for AuditLogEntryJson {
+    type Error = anyhow::Error;
+
+    fn try_from(entry: AuditLogEntry) -> anyhow::Result<Self> {
+        Ok(Self {
+            member_id: entry.member_id.map(|member_id| member_id.0),
+            key_fingerprint: entry.key_fingerprint,
+            timestamp_ms: entry.timestamp.as_ms_since_epoch()?,
+            method: entry.method,
+            route: entry.route,
+            path: entry.path,
+            query_params: entry.query_params,
+            body_bytes: entry.body_bytes,
+            status: entry.status,
+        })
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListAuditLogResponse {
+    entries: Vec<AuditLogEntryJson>,
+    /// Pass this as `cursor` to get the next page, or `null` if this is the
+    /// last one.
+    cursor: Option<f64>,
+}
+
+/// Lists admin API calls, newest first.
+pub async fn list_audit_log(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Query(ListAuditLogArgs { cursor, limit }): Query<ListAuditLogArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let before = cursor
+        .map(|cursor| {
+            CreationTime::try_from(cursor).map_err(|_| {
+                anyhow::anyhow!(ErrorMetadata::bad_request(
+                    "InvalidAuditLogCursor",
+                    format!("Invalid audit log cursor {cursor}"),
+                ))
+            })
+        })
+        .transpose()?;
+    let limit = limit
+        .unwrap_or(DEFAULT_AUDIT_LOG_PAGE_SIZE)
+        .clamp(1, MAX_AUDIT_LOG_PAGE_SIZE);
+    let entries = st
+        .application
+        .list_audit_log(identity, before, limit)
+        .await?;
+    let cursor = if entries.len() == limit {
+        entries
+            .last()
+            .and_then(|entry| entry.creation_time())
+            .map(f64::from)
+    } else {
+        None
+    };
+    let entries = entries
+        .into_iter()
+        .map(|entry| entry.into_value().try_into())
+        .collect::<anyhow::Result<_>>()?;
+    Ok(Json(ListAuditLogResponse { entries, cursor }))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum::body::Body;
+    use common::types::MemberId;
+    use http::Request;
+    use keybroker::Identity;
+    use runtime::prod::ProdRuntime;
+    use serde_json::json;
+
+    use crate::test_helpers::setup_backend_for_test;
+
+    #[convex_macro::prod_rt_test]
+    async fn test_body_admin_key_audited(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        let admin_key = backend
+            .st
+            .application
+            .key_broker()
+            .issue_admin_key(MemberId(7));
+        // The CLI passes its key in the body rather than a header.
+        let req = Request::builder()
+            .uri("/api/deploy2/report_push_completed")
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_vec(&json!({
+                "adminKey": admin_key.as_str(),
+                "spans": [],
+            }))?))?;
+        backend.expect_success::<()>(req).await?;
+
+        // Entries are written in the background.
+        let entry = loop {
+            let entries = backend
+                .st
+                .application
+                .list_audit_log(Identity::system(), None, 10)
+                .await?;
+            if let Some(entry) = entries.into_iter().next() {
+                break entry.into_value();
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        };
+        assert_eq!(entry.route, "/api/deploy2/report_push_completed");
+        assert_eq!(entry.member_id, Some(MemberId(7)));
+        assert_eq!(entry.status, 200);
+        Ok(())
+    }
+}
@@ -0,0 +1,78 @@
+use std::time::SystemTime;
+
+use authentication::oidc_providers::OidcProviderStatus;
+use axum::{
+    extract::State,
+    response::IntoResponse,
+};
+use common::http::{
+    extract::Json,
+    HttpResponseError,
+};
+use serde::Serialize;
+
+use crate::{
+    admin::must_be_admin,
+    authentication::ExtractIdentity,
+    LocalAppState,
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthProviderStatusJson {
+    issuer: String,
+    #[serde(rename = "applicationID")]
+    application_id: String,
+    /// When the provider's signing keys were last fetched successfully.
+    last_fetched_ms: Option<u64>,
+    last_refresh_attempt_ms: Option<u64>,
+    /// Why the last refresh failed. Tokens are still checked against the keys
+    /// from the last successful fetch, if there was one.
+    last_error: Option<String>,
+    key_ids: Vec<String>,
+}
+
+fn to_ms(time: Option<SystemTime>) -> Option<u64> {
+    Some(
+        time?
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .ok()?
+            .as_millis() as u64,
+    )
+}
+
+impl From<OidcProviderStatus> for AuthProviderStatusJson {
+    fn from(status: OidcProviderStatus) -> Self {
+        Self {
+            issuer: status.issuer,
+            application_id: status.application_id,
+            last_fetched_ms: to_ms(status.fetched_at),
+            last_refresh_attempt_ms: to_ms(status.last_refresh_attempt),
+            last_error: status.last_error,
+            key_ids: status.key_ids,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthProvidersResponse {
+    providers: Vec<AuthProviderStatusJson>,
+}
+
+/// Reports whether the signing keys for each auth provider in the auth config
+/// could be fetched, to help diagnose broken auth configs.
+pub async fn get_auth_providers(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let providers = st
+        .application
+        .auth_provider_status(identity)
+        .await?
+        .into_iter()
+        .map(AuthProviderStatusJson::from)
+        .collect();
+    Ok(Json(AuthProvidersResponse { providers }))
+}
@@ -33,6 +33,7 @@ use sync_types::{
 };
 
 use crate::{
+    admin_mtls::AdminClientCertificate,
     LocalAppState,
     RouterState,
 };
@@ -120,6 +121,18 @@ where
         let token: AuthenticationToken =
             parts.extract::<ExtractAuthenticationToken>().await?.into();
         let st = LocalAppState::from_ref(st);
+        // A token presented on the admin mTLS port takes precedence over the
+        // client certificate, so an admin can still use a narrower key or act
+        // as a user. The certificate only authenticates requests without one.
+        if let (AuthenticationToken::None, Some(AdminClientCertificate(common_name))) =
+            (&token, parts.extensions.get::<AdminClientCertificate>())
+        {
+            return Ok(Self(
+                st.application
+                    .key_broker()
+                    .check_admin_client_certificate(common_name)?,
+            ));
+        }
 
         Ok(Self(
             st.application
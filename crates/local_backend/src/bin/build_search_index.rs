@@ -0,0 +1,159 @@
+//! Builds a text or vector index's segments from a snapshot export of its
+//! table, for importing into the deployment with `/api/import/search_index`
+//! instead of having the deployment backfill the index itself.
+//!
+//! Extract the export's ZIP and point `--documents` at the table's
+//! `documents.jsonl`, and `--generated-schema` at its `generated_schema.jsonl`
+//! if there is one. `--snapshot-ts` is the export's snapshot timestamp.
+use std::{
+    collections::BTreeSet,
+    path::PathBuf,
+};
+
+use anyhow::Context;
+use application::search_index_bundle::build_search_index_bundle;
+use clap::Parser;
+use cmd_util::env::config_service;
+use common::{
+    async_compat::TokioAsyncReadCompatExt,
+    bootstrap_model::index::{
+        text_index::{
+            DeveloperTextIndexConfig,
+            TextIndexAnalyzer,
+        },
+        vector_index::{
+            DeveloperVectorIndexConfig,
+            VectorDistanceMetric,
+        },
+        DeveloperIndexConfig,
+    },
+    errors::MainError,
+    types::{
+        IndexDescriptor,
+        TableName,
+    },
+};
+use runtime::prod::ProdRuntime;
+use sync_types::Timestamp;
+use value::FieldPath;
+
+#[derive(Parser, Clone)]
+struct BuildSearchIndexArgs {
+    /// The table's `documents.jsonl` from an extracted snapshot export.
+    #[clap(long)]
+    documents: PathBuf,
+
+    /// The table's `generated_schema.jsonl`, if the export has one.
+    #[clap(long)]
+    generated_schema: Option<PathBuf>,
+
+    /// The snapshot timestamp of the export.
+    #[clap(long)]
+    snapshot_ts: u64,
+
+    #[clap(long)]
+    table_name: TableName,
+
+    #[clap(long)]
+    index_name: IndexDescriptor,
+
+    /// The field to index for full text search.
+    #[clap(
+        long,
+        conflicts_with = "vector_field",
+        required_unless_present = "vector_field"
+    )]
+    search_field: Option<FieldPath>,
+
+    /// How `--search-field` is split into terms, which must match the
+    /// index's definition.
+    #[clap(long, requires = "search_field")]
+    analyzer: Option<TextIndexAnalyzer>,
+
+    /// The field to index for vector search.
+    #[clap(long, requires = "dimensions")]
+    vector_field: Option<FieldPath>,
+
+    /// The dimensions of the vectors in `--vector-field`.
+    #[clap(long)]
+    dimensions: Option<u32>,
+
+    /// How vectors in `--vector-field` are compared, which must match the
+    /// index's definition.
+    #[clap(long, requires = "vector_field")]
+    distance: Option<VectorDistanceMetric>,
+
+    /// Fields to index for filtering, which must match the index's definition.
+    #[clap(long = "filter-field")]
+    filter_fields: Vec<FieldPath>,
+
+    /// Where to write the bundle of the index's segments.
+    #[clap(long)]
+    output: PathBuf,
+}
+
+impl BuildSearchIndexArgs {
+    fn developer_config(&self) -> anyhow::Result<DeveloperIndexConfig> {
+        let filter_fields: BTreeSet<_> = self.filter_fields.iter().cloned().collect();
+        match (&self.search_field, &self.vector_field, self.dimensions) {
+            (Some(search_field), None, _) => {
+                Ok(DeveloperIndexConfig::Search(DeveloperTextIndexConfig {
+                    search_field: search_field.clone(),
+                    filter_fields,
+                    analyzer: self.analyzer.unwrap_or_default(),
+                }))
+            },
+            (None, Some(vector_field), Some(dimensions)) => {
+                Ok(DeveloperIndexConfig::Vector(DeveloperVectorIndexConfig {
+                    dimensions: dimensions.try_into()?,
+                    vector_field: vector_field.clone(),
+                    filter_fields,
+                    distance: self.distance.unwrap_or_default(),
+                }))
+            },
+            _ => anyhow::bail!("Specify either --search-field or --vector-field and --dimensions"),
+        }
+    }
+}
+
+fn main() -> Result<(), MainError> {
+    let _guard = config_service();
+    let args = BuildSearchIndexArgs::parse();
+    let tokio = ProdRuntime::init_tokio()?;
+    let runtime = ProdRuntime::new(&tokio);
+    let runtime_ = runtime.clone();
+    runtime.block_on("main", async move { build(runtime_, args).await })?;
+    Ok(())
+}
+
+async fn build(runtime: ProdRuntime, args: BuildSearchIndexArgs) -> anyhow::Result<()> {
+    let developer_config = args.developer_config()?;
+    let documents = tokio::fs::File::open(&args.documents)
+        .await
+        .with_context(|| format!("Failed to open {}", args.documents.display()))?;
+    let generated_schema = match &args.generated_schema {
+        Some(path) => Some(
+            tokio::fs::File::open(path)
+                .await
+                .with_context(|| format!("Failed to open {}", path.display()))?
+                .compat(),
+        ),
+        None => None,
+    };
+    let output = tokio::fs::File::create(&args.output)
+        .await
+        .with_context(|| format!("Failed to create {}", args.output.display()))?;
+    build_search_index_bundle(
+        runtime,
+        args.table_name,
+        args.index_name,
+        developer_config,
+        Timestamp::try_from(args.snapshot_ts)?,
+        documents.compat(),
+        generated_schema,
+        output,
+    )
+    .await?;
+    tracing::info!("Wrote {}", args.output.display());
+    Ok(())
+}
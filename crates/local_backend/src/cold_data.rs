@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+use application::document_access::TableColdData;
+use axum::{
+    extract::State,
+    response::IntoResponse,
+};
+use common::{
+    http::{
+        extract::{
+            Json,
+            Query,
+        },
+        HttpResponseError,
+    },
+    knobs::DOCUMENT_ACCESS_SAMPLE_RATE,
+    runtime::UnixTimestamp,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    admin::must_be_admin,
+    authentication::ExtractIdentity,
+    LocalAppState,
+};
+
+const DEFAULT_COLD_AFTER: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColdDataReportArgs {
+    /// Documents not read for this long are cold. Defaults to 30 days.
+    cold_after_secs: Option<u64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableColdDataJson {
+    component_path: String,
+    table_name: String,
+    documents: u64,
+    sampled_documents: u64,
+    recently_read_documents: u64,
+    cold_documents: u64,
+}
+
+impl From<TableColdData> for TableColdDataJson {
+    fn from(table: TableColdData) -> Self {
+        Self {
+            cold_documents: table.cold(),
+            component_path: String::from(table.component),
+            table_name: table.table_name.to_string(),
+            documents: table.documents,
+            sampled_documents: table.sampled,
+            recently_read_documents: table.read_since,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColdDataReportResponse {
+    /// The fraction of reads that are sampled. Zero if tracking is disabled,
+    /// in which case every document is reported as cold.
+    sample_rate: f64,
+    /// Documents last read before this time are cold.
+    cold_before_ms: u64,
+    tables: Vec<TableColdDataJson>,
+}
+
+/// Reports how many documents in each table haven't been read recently, from
+/// the reads sampled with `DOCUMENT_ACCESS_SAMPLE_RATE`.
+pub async fn get_cold_data_report(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Query(ColdDataReportArgs { cold_after_secs }): Query<ColdDataReportArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let cold_after = cold_after_secs
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_COLD_AFTER);
+    let now = st.application.runtime().unix_timestamp();
+    let since = if now.as_secs_f64() < cold_after.as_secs_f64() {
+        UnixTimestamp::from_millis(0)
+    } else {
+        now - cold_after
+    };
+    let tables = st
+        .application
+        .cold_data_report(identity, since)
+        .await?
+        .into_iter()
+        .map(TableColdDataJson::from)
+        .collect();
+    Ok(Json(ColdDataReportResponse {
+        sample_rate: *DOCUMENT_ACCESS_SAMPLE_RATE,
+        cold_before_ms: since.as_ms_since_epoch()?,
+        tables,
+    }))
+}
@@ -1,22 +1,50 @@
 use std::{
+    collections::BTreeMap,
     fmt,
     path::PathBuf,
+    sync::Arc,
 };
 
-use clap::Parser;
-use common::types::{
-    ConvexOrigin,
-    ConvexSite,
+use ::storage::backend::{
+    azure::AzureBlobBackend,
+    s3::{
+        S3Backend,
+        S3Credentials,
+    },
+    StorageBackend,
+    StorageConfig,
+};
+use anyhow::Context;
+use application::replication_worker::ReplicationConfig;
+use clap::{
+    Parser,
+    ValueEnum,
+};
+use common::{
+    runtime::Runtime,
+    types::{
+        ConvexOrigin,
+        ConvexSite,
+        MemberId,
+    },
 };
 use keybroker::{
+    AdminKeyScope,
     InstanceSecret,
     KeyBroker,
     DEV_INSTANCE_NAME,
     DEV_SECRET,
 };
 use metrics::SERVER_VERSION_STR;
+use model::modules::module_versions::is_valid_action_region;
+use sync_types::Timestamp;
 use url::Url;
 
+use crate::{
+    admin_mtls::AdminMtlsConfig,
+    custom_domains::acme::AcmeConfig,
+};
+
 #[derive(Parser, Clone)]
 #[clap(version = &**SERVER_VERSION_STR, author = "Convex, Inc. <no-reply@convex.dev>")]
 pub struct LocalConfig {
@@ -45,6 +73,90 @@ pub struct LocalConfig {
     #[clap(long)]
     pub convex_http_proxy: Option<Url>,
 
+    /// Webhook that uploaded files are POSTed to for scanning. Files aren't
+    /// served until the webhook responds with `{"verdict": "clean"}`.
+    #[clap(long)]
+    pub file_scan_webhook_url: Option<Url>,
+
+    /// OpenAI-compatible `/embeddings` endpoint that vector indexes with
+    /// `embedFrom` generate their vectors with, e.g.
+    /// `https://api.openai.com/v1/embeddings`. Embeddings aren't generated if
+    /// unset.
+    #[clap(long)]
+    pub embedding_url: Option<Url>,
+
+    /// Model to request from `--embedding-url`.
+    #[clap(long, default_value = "text-embedding-3-small")]
+    pub embedding_model: String,
+
+    /// Bearer token sent to `--embedding-url`.
+    #[clap(long, env = "CONVEX_EMBEDDING_API_KEY", hide_env_values = true)]
+    pub embedding_api_key: Option<String>,
+
+    /// Regions to start separate Node action runners for. Actions that set
+    /// `region` to one of these run on that region's runners.
+    #[clap(long, value_delimiter = ',', value_parser = parse_action_region)]
+    pub action_regions: Vec<String>,
+
+    /// Header a CDN or load balancer in front of the backend sets to the
+    /// client's country, like `CF-IPCountry`, for blocking countries with the
+    /// network ACL. Countries can't be blocked if unset.
+    #[clap(long)]
+    pub geoip_country_header: Option<http::HeaderName>,
+
+    /// Port to serve custom domains registered through `/api/custom_domains`
+    /// on over HTTPS, usually 443. Custom domains aren't served if unset.
+    #[clap(long)]
+    custom_domains_https_port: Option<u16>,
+
+    /// Port to answer ACME HTTP-01 challenges on and redirect plain HTTP
+    /// requests to custom domains from. ACME providers validate domains on
+    /// port 80.
+    #[clap(long, default_value = "80")]
+    custom_domains_http_port: u16,
+
+    /// ACME directory that certificates for custom domains are issued from.
+    #[clap(long, default_value = "https://acme-v02.api.letsencrypt.org/directory")]
+    acme_directory_url: Url,
+
+    /// Email the ACME provider sends certificate expiry notices to.
+    #[clap(long)]
+    acme_contact_email: Option<String>,
+
+    /// Port to serve the deployment on over HTTPS, authenticating admins by
+    /// TLS client certificate. Requires `--admin-mtls-cert`,
+    /// `--admin-mtls-key` and `--admin-mtls-client-ca`.
+    #[clap(
+        long,
+        requires_all = ["admin_mtls_cert", "admin_mtls_key", "admin_mtls_client_ca"]
+    )]
+    admin_mtls_port: Option<u16>,
+
+    /// PEM file with the server certificate chain for `--admin-mtls-port`.
+    #[clap(long)]
+    admin_mtls_cert: Option<PathBuf>,
+
+    /// PEM file with the private key of `--admin-mtls-cert`.
+    #[clap(long)]
+    admin_mtls_key: Option<PathBuf>,
+
+    /// PEM file with the CA certificates that client certificates must be
+    /// issued by.
+    #[clap(long)]
+    admin_mtls_client_ca: Option<PathBuf>,
+
+    /// Maps the subject common name of a client certificate to the member ID
+    /// it authenticates as and, optionally, an admin key scope, like
+    /// `ci-pipeline=42:deploy-only`. The scope defaults to `data-read`. Can be
+    /// repeated.
+    #[clap(long, value_parser = parse_admin_mtls_principal)]
+    admin_mtls_principal: Vec<(String, (MemberId, AdminKeyScope))>,
+
+    /// Reject admin keys, so admins can only authenticate with a client
+    /// certificate on `--admin-mtls-port`.
+    #[clap(long, requires = "admin_mtls_port")]
+    pub require_admin_mtls: bool,
+
     #[clap(long, requires = "instance_secret")]
     pub instance_name: Option<String>,
 
@@ -54,6 +166,51 @@ pub struct LocalConfig {
     /// Which directory should local storage use
     #[clap(long, default_value = "convex_local_storage")]
     local_storage: String,
+
+    /// Where to keep exports, modules, search indexes and user files.
+    #[clap(long, value_enum, default_value_t = StorageBackendKind::Local)]
+    storage_backend: StorageBackendKind,
+
+    /// S3 or GCS bucket, or Azure Blob Storage container, for storage.
+    #[clap(long)]
+    storage_bucket: Option<String>,
+
+    /// Prefix for every object key in the bucket, so deployments can share
+    /// one.
+    #[clap(long, default_value = "")]
+    storage_prefix: String,
+
+    /// AWS region of the S3 bucket.
+    #[clap(long, env = "AWS_REGION", default_value = "us-east-1")]
+    storage_region: String,
+
+    /// Endpoint of an S3-compatible object store like MinIO, or of an Azure
+    /// Blob Storage emulator.
+    #[clap(long)]
+    storage_endpoint_url: Option<Url>,
+
+    /// S3 access key ID, GCS HMAC access ID or Azure storage account name.
+    #[clap(long, env = "STORAGE_ACCESS_KEY_ID")]
+    storage_access_key_id: Option<String>,
+
+    /// S3 secret access key, GCS HMAC secret or Azure storage account key.
+    #[clap(long, env = "STORAGE_SECRET_ACCESS_KEY", hide_env_values = true)]
+    storage_secret_access_key: Option<String>,
+
+    /// Origin of a leader deployment to replicate. This deployment becomes a
+    /// read-only replica of the leader.
+    #[clap(long, requires = "replication_token")]
+    pub replication_leader_url: Option<Url>,
+
+    /// Replication token issued by the leader's `/api/replication/token`.
+    #[clap(long, requires = "replication_leader_url")]
+    pub replication_token: Option<String>,
+
+    /// Leader timestamp to start replicating after, e.g. the timestamp of the
+    /// snapshot export this deployment was bootstrapped from. Only used until
+    /// the first commit is replicated.
+    #[clap(long, default_value = "0")]
+    replication_start_ts: u64,
 }
 
 impl fmt::Debug for LocalConfig {
@@ -62,10 +219,57 @@ impl fmt::Debug for LocalConfig {
             .field("convex_origin", &self.convex_origin)
             .field("convex_site", &self.convex_site)
             .field("instance_name", &self.instance_name)
+            .field("storage_backend", &self.storage_backend)
+            .field("storage_bucket", &self.storage_bucket)
+            .field("replication_leader_url", &self.replication_leader_url)
+            .field("custom_domains_https_port", &self.custom_domains_https_port)
+            .field("admin_mtls_port", &self.admin_mtls_port)
+            .field("require_admin_mtls", &self.require_admin_mtls)
+            .field("action_regions", &self.action_regions)
+            .field("geoip_country_header", &self.geoip_country_header)
             .finish()
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum StorageBackendKind {
+    /// Subdirectories of `--local-storage`.
+    Local,
+    /// Amazon S3 or an S3-compatible object store.
+    S3,
+    /// Google Cloud Storage, through its S3-compatible XML API.
+    Gcs,
+    /// Azure Blob Storage.
+    Azure,
+}
+
+fn parse_admin_mtls_principal(
+    principal: &str,
+) -> anyhow::Result<(String, (MemberId, AdminKeyScope))> {
+    let (common_name, member) = principal.rsplit_once('=').context(
+        "Expected <common name>=<member ID>[:<scope>], like \"ci-pipeline=42:deploy-only\"",
+    )?;
+    // Certificates can't be revoked as easily as keys, so they only get more
+    // than read access to data when it's asked for.
+    let (member_id, scope) = match member.split_once(':') {
+        Some((member_id, scope)) => (member_id, scope.parse()?),
+        None => (member, AdminKeyScope::DataRead),
+    };
+    Ok((
+        common_name.to_string(),
+        (MemberId(member_id.parse()?), scope),
+    ))
+}
+
+fn parse_action_region(region: &str) -> anyhow::Result<String> {
+    anyhow::ensure!(
+        is_valid_action_region(region),
+        "Invalid action region {region:?}. Regions are lowercase letters, digits and dashes, like \
+         \"eu-west-1\"."
+    );
+    Ok(region.to_string())
+}
+
 impl LocalConfig {
     pub fn http_bind_address(&self) -> ([u8; 4], u16) {
         (self.interface.octets(), self.port)
@@ -75,6 +279,43 @@ impl LocalConfig {
         Some((self.interface.octets(), self.site_proxy_port))
     }
 
+    pub fn custom_domains_https_bind_address(&self) -> Option<([u8; 4], u16)> {
+        self.custom_domains_https_port
+            .map(|port| (self.interface.octets(), port))
+    }
+
+    pub fn custom_domains_http_bind_address(&self) -> ([u8; 4], u16) {
+        (self.interface.octets(), self.custom_domains_http_port)
+    }
+
+    pub fn admin_mtls(&self) -> Option<AdminMtlsConfig> {
+        let (Some(port), Some(cert), Some(key), Some(client_ca)) = (
+            self.admin_mtls_port,
+            self.admin_mtls_cert.clone(),
+            self.admin_mtls_key.clone(),
+            self.admin_mtls_client_ca.clone(),
+        ) else {
+            return None;
+        };
+        Some(AdminMtlsConfig {
+            bind_address: (self.interface.octets(), port),
+            cert,
+            key,
+            client_ca,
+        })
+    }
+
+    pub fn admin_mtls_principals(&self) -> BTreeMap<String, (MemberId, AdminKeyScope)> {
+        self.admin_mtls_principal.iter().cloned().collect()
+    }
+
+    pub fn acme(&self) -> AcmeConfig {
+        AcmeConfig {
+            directory_url: self.acme_directory_url.clone(),
+            contact_email: self.acme_contact_email.clone(),
+        }
+    }
+
     pub fn convex_origin_url(&self) -> ConvexOrigin {
         self.convex_origin
             .clone()
@@ -108,14 +349,80 @@ impl LocalConfig {
         )
     }
 
-    pub fn storage_dir(&self) -> PathBuf {
-        self.local_storage.clone().into()
+    pub fn replication(&self) -> anyhow::Result<Option<ReplicationConfig>> {
+        let (Some(leader_url), Some(token)) = (
+            self.replication_leader_url.clone(),
+            self.replication_token.clone(),
+        ) else {
+            return Ok(None);
+        };
+        Ok(Some(ReplicationConfig {
+            leader_url,
+            token,
+            start_ts: Timestamp::try_from(self.replication_start_ts)?,
+        }))
+    }
+
+    pub fn storage<RT: Runtime>(&self, rt: RT) -> anyhow::Result<StorageConfig> {
+        let backend: Arc<dyn StorageBackend> = match self.storage_backend {
+            StorageBackendKind::Local => {
+                return Ok(StorageConfig::LocalDir(self.local_storage.clone().into()));
+            },
+            StorageBackendKind::S3 => {
+                let (bucket, credentials) = self.object_storage_credentials()?;
+                Arc::new(S3Backend::new(
+                    rt,
+                    bucket,
+                    self.storage_region.clone(),
+                    self.storage_endpoint_url.clone(),
+                    credentials,
+                )?)
+            },
+            StorageBackendKind::Gcs => {
+                let (bucket, credentials) = self.object_storage_credentials()?;
+                Arc::new(S3Backend::new_gcs(rt, bucket, credentials)?)
+            },
+            StorageBackendKind::Azure => {
+                let (container, credentials) = self.object_storage_credentials()?;
+                Arc::new(AzureBlobBackend::new(
+                    rt,
+                    credentials.access_key_id,
+                    container,
+                    &credentials.secret_access_key,
+                    self.storage_endpoint_url.clone(),
+                )?)
+            },
+        };
+        Ok(StorageConfig::Backend {
+            backend,
+            prefix: self.storage_prefix.clone(),
+        })
+    }
+
+    fn object_storage_credentials(&self) -> anyhow::Result<(String, S3Credentials)> {
+        let bucket = self
+            .storage_bucket
+            .clone()
+            .context("--storage-bucket is required for object storage")?;
+        let access_key_id = self
+            .storage_access_key_id
+            .clone()
+            .context("--storage-access-key-id is required for object storage")?;
+        let secret_access_key = self
+            .storage_secret_access_key
+            .clone()
+            .context("--storage-secret-access-key is required for object storage")?;
+        Ok((
+            bucket,
+            S3Credentials {
+                access_key_id,
+                secret_access_key,
+            },
+        ))
     }
 
     #[cfg(test)]
     pub fn new_for_test() -> anyhow::Result<Self> {
-        use anyhow::Context;
-
         let tempdir_handle = tempfile::tempdir()?;
         let db_path = tempdir_handle.path().join("convex_local_backend.sqlite3");
         // Easiest way to get a config object with defaults is to parse from cmd line
@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+use axum::{
+    extract::State,
+    response::IntoResponse,
+};
+use common::http::{
+    extract::Json,
+    HttpResponseError,
+};
+use http::StatusCode;
+use model::cors_config::types::CorsConfig;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    admin::{
+        must_be_admin,
+        must_be_admin_with_write_access,
+    },
+    authentication::ExtractIdentity,
+    LocalAppState,
+};
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CorsConfigJson {
+    /// Origins like `"https://example.com"` or `"https://*.example.com"`, or
+    /// `"*"` for any origin.
+    allowed_origins: Vec<String>,
+    /// Methods preflight requests may ask for. Any method if empty.
+    #[serde(default)]
+    allowed_methods: Vec<String>,
+    /// Request headers preflight requests may ask for, or `"*"` for any.
+    #[serde(default)]
+    allowed_headers: Vec<String>,
+    max_age_secs: Option<u64>,
+}
+
+impl From<CorsConfig> for CorsConfigJson {
+    fn from(config: CorsConfig) -> Self {
+        Self {
+            allowed_origins: config.allowed_origins,
+            allowed_methods: config.allowed_methods,
+            allowed_headers: config.allowed_headers,
+            max_age_secs: config.max_age.map(|max_age| max_age.as_secs()),
+        }
+    }
+}
+
+/// Returns the CORS policy applied to HTTP actions, or `null` if there isn't
+/// one.
+pub async fn get_cors_config(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let config = st
+        .application
+        .get_cors_config(identity)
+        .await?
+        .map(CorsConfigJson::from);
+    Ok(Json(config))
+}
+
+/// Replaces the CORS policy applied to HTTP actions. A `null` body removes
+/// it, leaving HTTP actions to set their own CORS headers.
+pub async fn set_cors_config(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(args): Json<Option<CorsConfigJson>>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let config = args
+        .map(|args| {
+            CorsConfig::new(
+                args.allowed_origins,
+                args.allowed_methods,
+                args.allowed_headers,
+                args.max_age_secs.map(Duration::from_secs),
+            )
+        })
+        .transpose()?;
+    st.application.set_cors_config(identity, config).await?;
+    Ok(StatusCode::OK)
+}
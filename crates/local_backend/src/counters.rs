@@ -0,0 +1,88 @@
+use axum::{
+    extract::State,
+    response::IntoResponse,
+};
+use common::http::{
+    extract::{
+        Json,
+        Path,
+    },
+    HttpResponseError,
+};
+use http::StatusCode;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    admin::{
+        must_be_admin,
+        must_be_admin_with_write_access,
+    },
+    authentication::ExtractIdentity,
+    LocalAppState,
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CounterResponse {
+    value: i64,
+}
+
+pub async fn get_counter(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Path(name): Path<String>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let value = st.application.get_counter(identity, name).await?;
+    Ok(Json(CounterResponse { value }))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddToCounterArgs {
+    delta: i64,
+}
+
+pub async fn add_to_counter(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Path(name): Path<String>,
+    Json(AddToCounterArgs { delta }): Json<AddToCounterArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    st.application.add_to_counter(identity, name, delta).await?;
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetCounterShardsArgs {
+    num_shards: u32,
+}
+
+/// Override the counter's automatically tuned number of shards.
+pub async fn set_counter_shards(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Path(name): Path<String>,
+    Json(SetCounterShardsArgs { num_shards }): Json<SetCounterShardsArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    st.application
+        .set_counter_shards(identity, name, num_shards)
+        .await?;
+    Ok(StatusCode::OK)
+}
+
+pub async fn delete_counter(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Path(name): Path<String>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    st.application.delete_counter(identity, name).await?;
+    Ok(StatusCode::OK)
+}
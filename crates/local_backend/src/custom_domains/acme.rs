@@ -0,0 +1,446 @@
+//! Issues and renews certificates for custom domains with the ACME protocol,
+//! answering HTTP-01 challenges from `serve_acme_challenges`.
+use std::{
+    collections::BTreeMap,
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::Context;
+use application::Application;
+use axum::{
+    extract::{
+        Request,
+        State,
+    },
+    response::{
+        IntoResponse,
+        Redirect,
+    },
+    routing::get,
+    Router,
+};
+use common::{
+    backoff::Backoff,
+    errors::report_error,
+    http::{
+        extract::Path,
+        ConvexHttpService,
+        HttpResponseError,
+        NoopRouteMapper,
+    },
+    knobs::{
+        CUSTOM_DOMAIN_CERTIFICATE_RENEWAL_AGE,
+        CUSTOM_DOMAIN_CERTIFICATE_RETRY_INTERVAL,
+    },
+    runtime::{
+        Runtime,
+        UnixTimestamp,
+    },
+};
+use errors::ErrorMetadata;
+use futures::{
+    future::Either,
+    select_biased,
+    Future,
+    FutureExt,
+};
+use http::{
+    header::HOST,
+    StatusCode,
+};
+use instant_acme::{
+    Account,
+    AuthorizationStatus,
+    ChallengeType,
+    Identifier,
+    NewAccount,
+    NewOrder,
+    Order,
+    OrderStatus,
+};
+use keybroker::Identity;
+use model::custom_domains::{
+    types::{
+        CustomDomain,
+        DomainCertificate,
+    },
+    CustomDomainsModel,
+};
+use parking_lot::Mutex;
+use rcgen::{
+    CertificateParams,
+    DistinguishedName,
+    KeyPair,
+};
+use runtime::prod::ProdRuntime;
+use url::Url;
+use value::ResolvedDocumentId;
+
+use super::tls::CustomDomainCerts;
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// How many times to poll the ACME server for an order to be validated or its
+/// certificate to be issued.
+const MAX_ORDER_POLLS: usize = 10;
+
+#[derive(Clone)]
+pub struct AcmeConfig {
+    pub directory_url: Url,
+    pub contact_email: Option<String>,
+}
+
+/// Key authorizations for pending HTTP-01 challenges, by token.
+#[derive(Clone, Default)]
+pub struct AcmeChallenges(Arc<Mutex<BTreeMap<String, String>>>);
+
+impl AcmeChallenges {
+    fn get(&self, token: &str) -> Option<String> {
+        self.0.lock().get(token).cloned()
+    }
+
+    fn insert(&self, token: String, key_authorization: String) {
+        self.0.lock().insert(token, key_authorization);
+    }
+
+    fn remove(&self, token: &str) {
+        self.0.lock().remove(token);
+    }
+}
+
+pub struct AcmeWorker {
+    application: Application<ProdRuntime>,
+    config: AcmeConfig,
+    certs: Arc<CustomDomainCerts>,
+    challenges: AcmeChallenges,
+    account: Option<Account>,
+}
+
+impl AcmeWorker {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(
+        application: Application<ProdRuntime>,
+        config: AcmeConfig,
+        certs: Arc<CustomDomainCerts>,
+        challenges: AcmeChallenges,
+    ) -> impl Future<Output = ()> + Send {
+        let mut worker = Self {
+            application,
+            config,
+            certs,
+            challenges,
+            account: None,
+        };
+        async move {
+            let mut backoff = Backoff::new(INITIAL_BACKOFF, MAX_BACKOFF);
+            while let Err(mut e) = worker.run(&mut backoff).await {
+                report_error(&mut e);
+                let runtime = worker.application.runtime();
+                let delay = backoff.fail(&mut runtime.rng());
+                tracing::error!("AcmeWorker failed, sleeping {delay:?}");
+                runtime.wait(delay).await;
+            }
+        }
+    }
+
+    async fn run(&mut self, backoff: &mut Backoff) -> anyhow::Result<()> {
+        tracing::info!("Starting AcmeWorker");
+        let runtime = self.application.runtime();
+        loop {
+            let mut tx = self.application.begin(Identity::system()).await?;
+            let domains = CustomDomainsModel::new(&mut tx).list().await?;
+            let token = tx.into_token()?;
+            self.certs.update(&domains);
+
+            let now = runtime.unix_timestamp();
+            let mut next_attempt = None;
+            let mut attempted = false;
+            for domain in domains {
+                let (id, domain) = domain.into_id_and_value();
+                match next_attempt_in(&domain, now) {
+                    Some(delay) => {
+                        next_attempt = Some(next_attempt.map_or(delay, |d: Duration| d.min(delay)));
+                    },
+                    None => {
+                        self.attempt(id, domain, now).await?;
+                        attempted = true;
+                    },
+                }
+            }
+            if attempted {
+                // Reload so newly issued certificates are served right away.
+                backoff.reset();
+                continue;
+            }
+
+            let next_attempt_future = match next_attempt {
+                Some(delay) => Either::Left(runtime.wait(delay)),
+                None => Either::Right(std::future::pending()),
+            };
+            let subscription = self.application.subscribe(token).await?;
+            select_biased! {
+                _ = next_attempt_future.fuse() => {},
+                _ = subscription.wait_for_invalidation().fuse() => {},
+            }
+            backoff.reset();
+        }
+    }
+
+    async fn attempt(
+        &mut self,
+        id: ResolvedDocumentId,
+        mut domain: CustomDomain,
+        now: UnixTimestamp,
+    ) -> anyhow::Result<()> {
+        tracing::info!("Requesting a certificate for {}", domain.domain);
+        match self.issue_certificate(&domain.domain, now).await {
+            Ok(certificate) => {
+                tracing::info!("Issued a certificate for {}", domain.domain);
+                domain.certificate = Some(certificate);
+                domain.last_error = None;
+            },
+            Err(e) => {
+                // Keep serving the previous certificate, if there is one, until
+                // the retry.
+                tracing::warn!("Failed to issue a certificate for {}: {e:#}", domain.domain);
+                domain.last_error = Some(format!("{e:#}"));
+            },
+        }
+        domain.last_attempt = Some(now);
+        self.application.update_custom_domain(id, domain).await
+    }
+
+    async fn account(&mut self) -> anyhow::Result<Account> {
+        if let Some(account) = &self.account {
+            return Ok(account.clone());
+        }
+        let contact: Vec<_> = self
+            .config
+            .contact_email
+            .iter()
+            .map(|email| format!("mailto:{email}"))
+            .collect();
+        let contact: Vec<_> = contact.iter().map(String::as_str).collect();
+        let (account, _) = Account::create(
+            &NewAccount {
+                contact: &contact,
+                terms_of_service_agreed: true,
+                only_return_existing: false,
+            },
+            self.config.directory_url.as_str(),
+            None,
+        )
+        .await
+        .context("Failed to create ACME account")?;
+        self.account = Some(account.clone());
+        Ok(account)
+    }
+
+    async fn issue_certificate(
+        &mut self,
+        domain: &str,
+        now: UnixTimestamp,
+    ) -> anyhow::Result<DomainCertificate> {
+        let account = self.account().await?;
+        let mut order = account
+            .new_order(&NewOrder {
+                identifiers: &[Identifier::Dns(domain.to_string())],
+            })
+            .await?;
+        let mut tokens = vec![];
+        let result = self.complete_order(&mut order, domain, &mut tokens).await;
+        for token in tokens {
+            self.challenges.remove(&token);
+        }
+        let (certificate_chain_pem, private_key_pem) = result?;
+        Ok(DomainCertificate {
+            certificate_chain_pem,
+            private_key_pem,
+            issued_at: now,
+        })
+    }
+
+    /// Answers the order's challenges, adding their tokens to `tokens`, and
+    /// returns the issued certificate chain and its private key.
+    async fn complete_order(
+        &self,
+        order: &mut Order,
+        domain: &str,
+        tokens: &mut Vec<String>,
+    ) -> anyhow::Result<(String, String)> {
+        let runtime = self.application.runtime();
+        for authorization in order.authorizations().await? {
+            match authorization.status {
+                AuthorizationStatus::Pending => {},
+                AuthorizationStatus::Valid => continue,
+                status => anyhow::bail!("Authorization for {domain} is {status:?}"),
+            }
+            let challenge = authorization
+                .challenges
+                .iter()
+                .find(|challenge| challenge.r#type == ChallengeType::Http01)
+                .context("ACME server didn't offer an HTTP-01 challenge")?;
+            self.challenges.insert(
+                challenge.token.clone(),
+                order.key_authorization(challenge).as_str().to_string(),
+            );
+            tokens.push(challenge.token.clone());
+            order.set_challenge_ready(&challenge.url).await?;
+        }
+
+        let mut delay = Duration::from_millis(250);
+        let mut status = order.state().status;
+        for _ in 0..MAX_ORDER_POLLS {
+            if !matches!(status, OrderStatus::Pending | OrderStatus::Processing) {
+                break;
+            }
+            runtime.wait(delay).await;
+            delay = (delay * 2).min(Duration::from_secs(10));
+            status = order.refresh().await?.status;
+        }
+        anyhow::ensure!(
+            status == OrderStatus::Ready,
+            "ACME order for {domain} is {status:?}. Check that {domain} resolves to this backend \
+             and that --custom-domains-http-port is reachable on port 80."
+        );
+
+        let key_pair = KeyPair::generate()?;
+        let mut params = CertificateParams::new(vec![domain.to_string()])?;
+        params.distinguished_name = DistinguishedName::new();
+        let csr = params.serialize_request(&key_pair)?;
+        order.finalize(csr.der()).await?;
+        for _ in 0..MAX_ORDER_POLLS {
+            if let Some(certificate_chain_pem) = order.certificate().await? {
+                return Ok((certificate_chain_pem, key_pair.serialize_pem()));
+            }
+            runtime.wait(Duration::from_secs(1)).await;
+        }
+        anyhow::bail!("Timed out waiting for the certificate for {domain}")
+    }
+}
+
+/// How long until a certificate should be requested for `domain`, or `None`
+/// if it's due now.
+fn next_attempt_in(domain: &CustomDomain, now: UnixTimestamp) -> Option<Duration> {
+    let due = match (&domain.certificate, &domain.last_error, domain.last_attempt) {
+        (_, Some(_), Some(last_attempt)) => {
+            last_attempt + *CUSTOM_DOMAIN_CERTIFICATE_RETRY_INTERVAL
+        },
+        (Some(certificate), ..) => certificate.issued_at + *CUSTOM_DOMAIN_CERTIFICATE_RENEWAL_AGE,
+        (None, ..) => return None,
+    };
+    due.checked_sub(now).filter(|delay| !delay.is_zero())
+}
+
+/// Answers ACME HTTP-01 challenges and redirects everything else to HTTPS.
+pub async fn serve_acme_challenges(
+    addr: ([u8; 4], u16),
+    challenges: AcmeChallenges,
+    mut shutdown_rx: async_broadcast::Receiver<()>,
+) -> anyhow::Result<()> {
+    async fn challenge(
+        State(challenges): State<AcmeChallenges>,
+        Path(token): Path<String>,
+    ) -> Result<impl IntoResponse, HttpResponseError> {
+        let key_authorization = challenges.get(&token).ok_or_else(|| {
+            anyhow::anyhow!(ErrorMetadata::not_found(
+                "AcmeChallengeNotFound",
+                "No pending ACME challenge has this token",
+            ))
+        })?;
+        Ok(key_authorization)
+    }
+
+    async fn redirect_to_https(request: Request) -> impl IntoResponse {
+        let Some(host) = request
+            .headers()
+            .get(HOST)
+            .and_then(|host| host.to_str().ok())
+        else {
+            return StatusCode::BAD_REQUEST.into_response();
+        };
+        let host = host.split(':').next().unwrap_or(host);
+        let path_and_query = request
+            .uri()
+            .path_and_query()
+            .map_or("/", |path_and_query| path_and_query.as_str());
+        Redirect::permanent(&format!("https://{host}{path_and_query}")).into_response()
+    }
+
+    let router = Router::new()
+        .route("/.well-known/acme-challenge/:token", get(challenge))
+        .with_state(challenges)
+        .fallback(redirect_to_https);
+    let mut service = ConvexHttpService::new(
+        router,
+        "custom_domains_http",
+        "unknown".to_string(),
+        16,
+        Duration::from_secs(125),
+        NoopRouteMapper,
+    );
+    // This port is public, so don't expose `/metrics`.
+    service.set_meta_routes_enabled(false);
+    service
+        .serve(addr.into(), async move {
+            let _ = shutdown_rx.recv().await;
+        })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use common::{
+        knobs::{
+            CUSTOM_DOMAIN_CERTIFICATE_RENEWAL_AGE,
+            CUSTOM_DOMAIN_CERTIFICATE_RETRY_INTERVAL,
+        },
+        runtime::UnixTimestamp,
+    };
+    use model::custom_domains::types::{
+        CustomDomain,
+        CustomDomainTarget,
+        DomainCertificate,
+    };
+
+    use super::next_attempt_in;
+
+    #[test]
+    fn test_next_attempt_in() -> anyhow::Result<()> {
+        let now = UnixTimestamp::from_millis(1_700_000_000_000);
+        let mut domain = CustomDomain::new("api.example.com", CustomDomainTarget::Cloud)?;
+        assert_eq!(next_attempt_in(&domain, now), None);
+
+        domain.last_attempt = Some(now);
+        domain.last_error = Some("DNS doesn't resolve".to_string());
+        assert_eq!(
+            next_attempt_in(&domain, now),
+            Some(*CUSTOM_DOMAIN_CERTIFICATE_RETRY_INTERVAL)
+        );
+        assert_eq!(
+            next_attempt_in(&domain, now + *CUSTOM_DOMAIN_CERTIFICATE_RETRY_INTERVAL),
+            None
+        );
+
+        domain.last_error = None;
+        domain.certificate = Some(DomainCertificate {
+            certificate_chain_pem: "chain".to_string(),
+            private_key_pem: "key".to_string(),
+            issued_at: now,
+        });
+        let later = now + Duration::from_secs(60);
+        assert_eq!(
+            next_attempt_in(&domain, later),
+            Some(*CUSTOM_DOMAIN_CERTIFICATE_RENEWAL_AGE - Duration::from_secs(60))
+        );
+        assert_eq!(
+            next_attempt_in(&domain, now + *CUSTOM_DOMAIN_CERTIFICATE_RENEWAL_AGE),
+            None
+        );
+        Ok(())
+    }
+}
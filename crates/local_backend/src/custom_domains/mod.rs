@@ -0,0 +1,176 @@
+//! Serves the deployment on domains registered through
+//! `/api/custom_domains`, terminating TLS with certificates the ACME worker
+//! issues for them.
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    response::IntoResponse,
+    Router,
+};
+use common::{
+    http::{
+        extract::{
+            Json,
+            Path,
+        },
+        HttpResponseError,
+    },
+    runtime::Runtime,
+};
+use futures::future;
+use http::StatusCode;
+use model::custom_domains::types::{
+    CustomDomain,
+    CustomDomainTarget,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    admin::{
+        must_be_admin,
+        must_be_admin_with_write_access,
+    },
+    authentication::ExtractIdentity,
+    config::LocalConfig,
+    custom_domains::{
+        acme::{
+            serve_acme_challenges,
+            AcmeChallenges,
+            AcmeWorker,
+        },
+        tls::{
+            serve_tls,
+            CustomDomainCerts,
+        },
+    },
+    LocalAppState,
+};
+
+pub mod acme;
+pub mod tls;
+
+/// Issues certificates for custom domains and serves `router` on them, if
+/// `--custom-domains-https-port` is set.
+pub async fn serve_custom_domains(
+    config: &LocalConfig,
+    st: LocalAppState,
+    router: Router,
+    shutdown_rx: async_broadcast::Receiver<()>,
+) -> anyhow::Result<()> {
+    let Some(https_addr) = config.custom_domains_https_bind_address() else {
+        return Ok(());
+    };
+    let certs = Arc::new(CustomDomainCerts::default());
+    let challenges = AcmeChallenges::default();
+    let runtime = st.application.runtime();
+    let mut acme_worker = runtime.spawn(
+        "acme_worker",
+        AcmeWorker::new(
+            st.application.clone(),
+            config.acme(),
+            certs.clone(),
+            challenges.clone(),
+        ),
+    );
+    let result = future::try_join(
+        serve_acme_challenges(
+            config.custom_domains_http_bind_address(),
+            challenges,
+            shutdown_rx.clone(),
+        ),
+        serve_tls(https_addr.into(), router, certs, shutdown_rx),
+    )
+    .await;
+    acme_worker.shutdown();
+    result?;
+    Ok(())
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CustomDomainJson {
+    domain: String,
+    /// `"cloud"` for the deployment's API or `"site"` for HTTP actions.
+    target: String,
+    /// One of `"pending"`, `"active"` or `"failed"`. Failed domains keep
+    /// serving their previous certificate, if they have one.
+    status: &'static str,
+    certificate_issued_at_ms: Option<u64>,
+    last_error: Option<String>,
+}
+
+impl TryFrom<CustomDomain> for CustomDomainJson {
+    type Error = anyhow::Error;
+
+    fn try_from(domain: CustomDomain) -> anyhow::Result<Self> {
+        let status = match (&domain.certificate, &domain.last_error) {
+            (_, Some(_)) => "failed",
+            (Some(_), None) => "active",
+            (None, None) => "pending",
+        };
+        Ok(Self {
+            domain: domain.domain,
+            target: domain.target.to_string(),
+            status,
+            certificate_issued_at_ms: domain
+                .certificate
+                .map(|certificate| certificate.issued_at.as_ms_since_epoch())
+                .transpose()?,
+            last_error: domain.last_error,
+        })
+    }
+}
+
+pub async fn list_custom_domains(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let domains = st
+        .application
+        .list_custom_domains(identity)
+        .await?
+        .into_iter()
+        .map(|domain| CustomDomainJson::try_from(domain.into_value()))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(Json(domains))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegisterCustomDomainArgs {
+    domain: String,
+    target: String,
+}
+
+/// Registers a domain to serve the deployment on. Its DNS must point at this
+/// backend for the certificate to be issued.
+pub async fn register_custom_domain(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(RegisterCustomDomainArgs { domain, target }): Json<RegisterCustomDomainArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let target: CustomDomainTarget = target.parse()?;
+    let domain = st
+        .application
+        .register_custom_domain(identity, &domain, target)
+        .await?;
+    Ok(Json(CustomDomainJson::try_from(domain)?))
+}
+
+pub async fn remove_custom_domain(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Path(domain): Path<String>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    st.application
+        .remove_custom_domain(identity, &domain)
+        .await?;
+    Ok(StatusCode::OK)
+}
@@ -0,0 +1,237 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    io,
+    net::SocketAddr,
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::Context;
+use axum::{
+    body::Body,
+    extract::ConnectInfo,
+    Router,
+};
+use common::document::ParsedDocument;
+use http::Request;
+use hyper::body::Incoming;
+use hyper_util::{
+    rt::{
+        TokioExecutor,
+        TokioIo,
+    },
+    server::conn::auto::Builder,
+    service::TowerToHyperService,
+};
+use model::custom_domains::types::{
+    CustomDomain,
+    CustomDomainTarget,
+    DomainCertificate,
+};
+use parking_lot::RwLock;
+use tokio::net::{
+    TcpListener,
+    TcpStream,
+};
+use tokio_rustls::{
+    rustls::{
+        crypto::ring::sign::any_supported_type,
+        server::{
+            ClientHello,
+            ResolvesServerCert,
+        },
+        sign::CertifiedKey,
+        ServerConfig,
+    },
+    TlsAcceptor,
+};
+use tower::ServiceExt;
+
+struct ServedDomain {
+    target: CustomDomainTarget,
+    key: Arc<CertifiedKey>,
+}
+
+/// Certificates of the custom domains being served, by domain. The ACME
+/// worker refreshes these whenever `_custom_domains` changes.
+#[derive(Default)]
+pub struct CustomDomainCerts {
+    domains: RwLock<BTreeMap<String, ServedDomain>>,
+}
+
+impl CustomDomainCerts {
+    /// Serves the domains that have a certificate and stops serving the rest.
+    pub fn update(&self, domains: &[ParsedDocument<CustomDomain>]) {
+        let mut served = BTreeMap::new();
+        for domain in domains {
+            let Some(certificate) = &domain.certificate else {
+                continue;
+            };
+            match certified_key(certificate) {
+                Ok(key) => {
+                    served.insert(
+                        domain.domain.clone(),
+                        ServedDomain {
+                            target: domain.target,
+                            key: Arc::new(key),
+                        },
+                    );
+                },
+                Err(e) => tracing::error!("Invalid certificate for {}: {e:#}", domain.domain),
+            }
+        }
+        *self.domains.write() = served;
+    }
+
+    fn target(&self, domain: &str) -> Option<CustomDomainTarget> {
+        self.domains.read().get(domain).map(|served| served.target)
+    }
+}
+
+impl fmt::Debug for CustomDomainCerts {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.domains.read().keys()).finish()
+    }
+}
+
+impl ResolvesServerCert for CustomDomainCerts {
+    fn resolve(&self, client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        let domain = client_hello.server_name()?.to_ascii_lowercase();
+        self.domains
+            .read()
+            .get(&domain)
+            .map(|served| served.key.clone())
+    }
+}
+
+fn certified_key(certificate: &DomainCertificate) -> anyhow::Result<CertifiedKey> {
+    let chain = rustls_pemfile::certs(&mut certificate.certificate_chain_pem.as_bytes())
+        .collect::<Result<Vec<_>, _>>()?;
+    anyhow::ensure!(!chain.is_empty(), "Certificate chain is empty");
+    let private_key = rustls_pemfile::private_key(&mut certificate.private_key_pem.as_bytes())?
+        .context("Missing private key")?;
+    Ok(CertifiedKey::new(chain, any_supported_type(&private_key)?))
+}
+
+/// Serves HTTP actions from the root of `site` domains, like the deployment's
+/// `.convex.site` URL. `cloud` domains are served like the deployment's own
+/// origin.
+fn route_request(target: CustomDomainTarget, mut request: Request<Body>) -> Request<Body> {
+    if target == CustomDomainTarget::Site {
+        let path_and_query = request
+            .uri()
+            .path_and_query()
+            .map_or("/", |path_and_query| path_and_query.as_str());
+        if let Ok(uri) = format!("/http{path_and_query}").parse() {
+            *request.uri_mut() = uri;
+        }
+    }
+    request
+}
+
+/// Terminates TLS for custom domains, picking the certificate by SNI, and
+/// routes their requests to `router`.
+pub async fn serve_tls(
+    addr: SocketAddr,
+    router: Router,
+    certs: Arc<CustomDomainCerts>,
+    mut shutdown_rx: async_broadcast::Receiver<()>,
+) -> anyhow::Result<()> {
+    let mut config = ServerConfig::builder()
+        .with_no_client_auth()
+        .with_cert_resolver(certs.clone());
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+    let listener = TcpListener::bind(addr).await?;
+    tracing::info!("Serving custom domains at {addr}...");
+    loop {
+        let (tcp_stream, remote_addr) = tokio::select! {
+            conn = listener.accept() => match conn {
+                Ok(conn) => conn,
+                Err(e) => {
+                    tracing::error!("Failed to accept custom domain connection: {e}");
+                    if !matches!(
+                        e.kind(),
+                        io::ErrorKind::ConnectionAborted | io::ErrorKind::ConnectionReset
+                    ) {
+                        // Probably out of file descriptors, so give connections
+                        // a chance to close.
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                    }
+                    continue;
+                },
+            },
+            _ = shutdown_rx.recv() => break,
+        };
+        let acceptor = acceptor.clone();
+        let router = router.clone();
+        let certs = certs.clone();
+        tokio::spawn(async move {
+            if let Err(e) =
+                serve_connection(acceptor, router, &certs, tcp_stream, remote_addr).await
+            {
+                tracing::debug!("Custom domain connection from {remote_addr} failed: {e:#}");
+            }
+        });
+    }
+    tracing::info!("Shut down custom domains");
+    Ok(())
+}
+
+async fn serve_connection(
+    acceptor: TlsAcceptor,
+    router: Router,
+    certs: &CustomDomainCerts,
+    tcp_stream: TcpStream,
+    remote_addr: SocketAddr,
+) -> anyhow::Result<()> {
+    tcp_stream.set_nodelay(true)?;
+    // The handshake fails for server names without a certificate, so this only
+    // serves domains that are registered.
+    let tls_stream = acceptor.accept(tcp_stream).await?;
+    let domain = tls_stream
+        .get_ref()
+        .1
+        .server_name()
+        .context("Missing SNI server name")?
+        .to_ascii_lowercase();
+    let target = certs
+        .target(&domain)
+        .with_context(|| format!("{domain} is no longer served"))?;
+    let service = router.map_request(move |mut request: Request<Incoming>| {
+        request.extensions_mut().insert(ConnectInfo(remote_addr));
+        route_request(target, request.map(Body::new))
+    });
+    Builder::new(TokioExecutor::new())
+        .serve_connection_with_upgrades(TokioIo::new(tls_stream), TowerToHyperService::new(service))
+        .await
+        .map_err(|e| anyhow::anyhow!(e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use http::Request;
+    use model::custom_domains::types::CustomDomainTarget;
+
+    use super::route_request;
+
+    #[test]
+    fn test_route_request() -> anyhow::Result<()> {
+        let request =
+            Request::get("https://hooks.example.com/stripe?event=1").body(Body::empty())?;
+        let routed = route_request(CustomDomainTarget::Site, request);
+        assert_eq!(routed.uri(), "/http/stripe?event=1");
+
+        let request = Request::get("https://hooks.example.com").body(Body::empty())?;
+        let routed = route_request(CustomDomainTarget::Site, request);
+        assert_eq!(routed.uri(), "/http/");
+
+        let request = Request::get("/api/sync").body(Body::empty())?;
+        let routed = route_request(CustomDomainTarget::Cloud, request);
+        assert_eq!(routed.uri(), "/api/sync");
+        Ok(())
+    }
+}
@@ -31,3 +31,120 @@ impl Header for ContentDispositionAttachment {
         values.extend(std::iter::once(encoded));
     }
 }
+
+/// The filename of a file in storage, which a `Content-Disposition` header
+/// sets when uploading and which is served back as an inline disposition so
+/// browsers save the file under its name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContentDispositionFilename(pub String);
+
+impl Header for ContentDispositionFilename {
+    fn name() -> &'static HeaderName {
+        &CONTENT_DISPOSITION
+    }
+
+    fn decode<'i, I>(values: &mut I) -> Result<Self, axum_extra::headers::Error>
+    where
+        I: Iterator<Item = &'i HeaderValue>,
+    {
+        let value = values
+            .next()
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(axum_extra::headers::Error::invalid)?;
+        let mut filename = None;
+        for param in value.split(';').skip(1) {
+            let Some((name, param_value)) = param.trim().split_once('=') else {
+                continue;
+            };
+            match name.trim().to_ascii_lowercase().as_str() {
+                // RFC 6266 says the extended form takes precedence.
+                "filename*" => {
+                    let encoded = param_value
+                        .strip_prefix("UTF-8''")
+                        .or_else(|| param_value.strip_prefix("utf-8''"))
+                        .ok_or_else(axum_extra::headers::Error::invalid)?;
+                    let decoded = urlencoding::decode(encoded)
+                        .map_err(|_| axum_extra::headers::Error::invalid())?;
+                    return Ok(Self(decoded.into_owned()));
+                },
+                "filename" => {
+                    let param_value = param_value.trim();
+                    filename = Some(
+                        param_value
+                            .strip_prefix('"')
+                            .and_then(|v| v.strip_suffix('"'))
+                            .unwrap_or(param_value)
+                            .to_string(),
+                    );
+                },
+                _ => {},
+            }
+        }
+        filename
+            .map(Self)
+            .ok_or_else(axum_extra::headers::Error::invalid)
+    }
+
+    fn encode<E>(&self, values: &mut E)
+    where
+        E: Extend<HeaderValue>,
+    {
+        // Older clients only understand the quoted ASCII form.
+        let fallback: String = self
+            .0
+            .chars()
+            .map(|c| {
+                if c.is_ascii() && !c.is_ascii_control() && c != '"' && c != '\\' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let value = format!(
+            "inline; filename=\"{fallback}\"; filename*=UTF-8''{}",
+            urlencoding::encode(&self.0)
+        );
+        let encoded = HeaderValue::from_str(&value)
+            .map_err(|_| axum_extra::headers::Error::invalid())
+            .unwrap();
+        values.extend(std::iter::once(encoded));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use axum_extra::headers::{
+        Header,
+        HeaderValue,
+    };
+
+    use super::ContentDispositionFilename;
+
+    fn decode(value: &'static str) -> Option<ContentDispositionFilename> {
+        ContentDispositionFilename::decode(&mut std::iter::once(&HeaderValue::from_static(value)))
+            .ok()
+    }
+
+    #[test]
+    fn test_content_disposition_filename() {
+        assert_eq!(
+            decode("attachment; filename=\"report.pdf\""),
+            Some(ContentDispositionFilename("report.pdf".to_string()))
+        );
+        assert_eq!(
+            decode("inline; filename=\"fallback.txt\"; filename*=UTF-8''r%C3%A9sum%C3%A9.txt"),
+            Some(ContentDispositionFilename("résumé.txt".to_string()))
+        );
+        assert_eq!(decode("attachment"), None);
+
+        let mut values = vec![];
+        ContentDispositionFilename("résumé.txt".to_string()).encode(&mut values);
+        assert_eq!(
+            values[0],
+            "inline; filename=\"r_sum_.txt\"; filename*=UTF-8''r%C3%A9sum%C3%A9.txt"
+        );
+        let roundtripped = ContentDispositionFilename::decode(&mut values.iter()).unwrap();
+        assert_eq!(roundtripped.0, "résumé.txt");
+    }
+}
@@ -1,5 +1,8 @@
+use std::num::NonZeroU32;
+
 use anyhow::Context;
 use application::{
+    deleting_tables_cleanup::DeletingTablesCleanupStatus,
     deploy_config::ModuleJson,
     valid_identifier::ValidIdentifier,
 };
@@ -9,7 +12,11 @@ use axum::{
     response::IntoResponse,
 };
 use common::{
-    components::ComponentId,
+    components::{
+        CanonicalizedComponentFunctionPath,
+        ComponentId,
+        ComponentPath,
+    },
     http::{
         extract::{
             Json,
@@ -19,6 +26,10 @@ use common::{
         ExtractRequestId,
         HttpResponseError,
     },
+    runtime::{
+        Runtime,
+        UnixTimestamp,
+    },
     shapes::{
         dashboard_shape_json,
         reduced::ReducedShape,
@@ -27,7 +38,10 @@ use common::{
 };
 use database::IndexModel;
 use errors::ErrorMetadata;
-use http::StatusCode;
+use http::{
+    header::CONTENT_TYPE,
+    StatusCode,
+};
 use isolate::UdfArgsJson;
 use model::{
     config::types::ModuleConfig,
@@ -37,6 +51,7 @@ use serde::{
     Deserialize,
     Serialize,
 };
+use sync_types::Timestamp;
 use value::{
     TableName,
     TableNamespace,
@@ -44,11 +59,13 @@ use value::{
 
 use crate::{
     admin::{
+        must_be_admin,
         must_be_admin_from_key,
         must_be_admin_member,
         must_be_admin_member_with_write_access,
     },
     authentication::ExtractIdentity,
+    parse::parse_udf_path,
     public_api::{
         export_value,
         UdfResponse,
@@ -64,6 +81,20 @@ pub struct DeleteTableArgs {
     component_id: Option<String>,
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompactTableArgs {
+    table_name: String,
+    component_id: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CompactTableResponse {
+    /// Poll `/api/operations/{operation_id}` for the compaction's progress.
+    operation_id: String,
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DeleteComponentArgs {
@@ -104,6 +135,41 @@ pub async fn shapes2(
     Ok(Json(out))
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObservedArgsShape {
+    shape: serde_json::Value,
+    num_samples: u64,
+}
+
+/// The argument shapes inferred from a sample of each function's recent
+/// successful calls, keyed by function path.
+#[debug_handler]
+pub async fn observed_args_shapes(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Query(ShapesArgs { component }): Query<ShapesArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    let mut out = serde_json::Map::new();
+
+    must_be_admin_member(&identity)?;
+    let component = ComponentId::deserialize_from_string(component.as_deref())?;
+    let mut tx = st.application.begin(identity.clone()).await?;
+    let component_path = tx.must_component_path(component)?;
+    let mapping = tx.table_mapping().namespace(component.into());
+
+    for (udf_path, observed) in st.application.observed_args(&component_path) {
+        let shape = ReducedShape::from_type(&observed.shape, &mapping.table_number_exists());
+        let shape = dashboard_shape_json(&shape, &mapping, &virtual_system_mapping())?;
+        let json = serde_json::to_value(ObservedArgsShape {
+            shape,
+            num_samples: *observed.shape.num_values(),
+        })?;
+        out.insert(udf_path.to_string(), json);
+    }
+    Ok(Json(out))
+}
+
 #[debug_handler]
 pub async fn delete_tables(
     State(st): State<LocalAppState>,
@@ -127,6 +193,31 @@ pub async fn delete_tables(
     Ok(StatusCode::OK)
 }
 
+/// Starts deleting the table's old document revisions and index entries that
+/// are out of retention, without waiting for retention to get to them.
+#[debug_handler]
+pub async fn compact_table(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(CompactTableArgs {
+        table_name,
+        component_id,
+    }): Json<CompactTableArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_member_with_write_access(&identity)?;
+    let table_name = table_name.parse::<ValidIdentifier<TableName>>()?.0;
+    let table_namespace = TableNamespace::from(ComponentId::deserialize_from_string(
+        component_id.as_deref(),
+    )?);
+    let operation_id = st
+        .application
+        .compact_table(&identity, table_name, table_namespace)
+        .await?;
+    Ok(Json(CompactTableResponse {
+        operation_id: operation_id.to_string(),
+    }))
+}
+
 #[debug_handler]
 pub async fn delete_component(
     State(st): State<LocalAppState>,
@@ -141,6 +232,102 @@ pub async fn delete_component(
     Ok(StatusCode::OK)
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeletingTableCleanupResponse {
+    tablet_id: String,
+    table_name: String,
+    component_id: Option<String>,
+    revisions_deleted: u64,
+    complete: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DeletingTablesCleanupResponse {
+    paused: bool,
+    rows_per_second: u32,
+    tables: Vec<DeletingTableCleanupResponse>,
+    /// Fraction of the document log scanned by the current (or last) pass.
+    progress: Option<f64>,
+    /// Revisions deleted per second by the current (or last) pass.
+    throughput: Option<f64>,
+    pass_in_progress: bool,
+}
+
+impl DeletingTablesCleanupResponse {
+    fn new(status: DeletingTablesCleanupStatus, now: UnixTimestamp) -> Self {
+        Self {
+            paused: status.paused,
+            rows_per_second: status.rows_per_second.get(),
+            tables: status
+                .tablets
+                .into_iter()
+                .map(|(tablet_id, tablet)| DeletingTableCleanupResponse {
+                    tablet_id: tablet_id.to_string(),
+                    table_name: tablet.table_name.to_string(),
+                    component_id: ComponentId::from(tablet.namespace).serialize_to_string(),
+                    revisions_deleted: tablet.revisions_deleted,
+                    complete: tablet.complete,
+                })
+                .collect(),
+            progress: status.last_pass.as_ref().map(|pass| pass.progress()),
+            throughput: status.last_pass.as_ref().map(|pass| pass.throughput(now)),
+            pass_in_progress: status
+                .last_pass
+                .as_ref()
+                .is_some_and(|pass| pass.finished.is_none()),
+        }
+    }
+}
+
+/// Reports progress of garbage collecting dropped tables.
+#[debug_handler]
+pub async fn get_deleting_tables_cleanup(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_member(&identity)?;
+    let status = st.application.deleting_tables_cleanup().status();
+    let now = st.application.runtime().unix_timestamp();
+    Ok(Json(DeletingTablesCleanupResponse::new(status, now)))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateDeletingTablesCleanupArgs {
+    paused: Option<bool>,
+    rows_per_second: Option<u32>,
+}
+
+/// Pauses, resumes, or changes the rate of garbage collecting dropped tables.
+#[debug_handler]
+pub async fn update_deleting_tables_cleanup(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(UpdateDeletingTablesCleanupArgs {
+        paused,
+        rows_per_second,
+    }): Json<UpdateDeletingTablesCleanupArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_member_with_write_access(&identity)?;
+    let rows_per_second = rows_per_second
+        .map(|rows_per_second| {
+            NonZeroU32::new(rows_per_second).context(ErrorMetadata::bad_request(
+                "InvalidRowsPerSecond",
+                "rowsPerSecond must be greater than zero",
+            ))
+        })
+        .transpose()?;
+    let cleanup = st.application.deleting_tables_cleanup();
+    cleanup.update(paused, rows_per_second);
+    let now = st.application.runtime().unix_timestamp();
+    Ok(Json(DeletingTablesCleanupResponse::new(
+        cleanup.status(),
+        now,
+    )))
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GetIndexesArgs {
@@ -199,6 +386,17 @@ pub async fn get_source_code(
     Ok(Json(source_code))
 }
 
+/// Returns the source of a typed Rust client for the deployment's public
+/// functions.
+pub async fn generate_rust_client(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let source = st.application.generate_rust_client(identity).await?;
+    Ok(([(CONTENT_TYPE, "text/plain; charset=utf-8")], source))
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RunTestFunctionArgs {
@@ -248,3 +446,63 @@ pub async fn run_test_function(
     };
     Ok(Json(response))
 }
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunQueryAtTsArgs {
+    path: String,
+    args: UdfArgsJson,
+    component_path: Option<String>,
+    /// Nanoseconds since the Unix epoch as a decimal string.
+    ts: String,
+    format: Option<String>,
+}
+
+/// Runs a query as it would have run at a past timestamp within the
+/// retention window, for `npx convex run --at`.
+pub async fn run_query_at_ts(
+    State(st): State<LocalAppState>,
+    ExtractRequestId(request_id): ExtractRequestId,
+    ExtractClientVersion(client_version): ExtractClientVersion,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(req): Json<RunQueryAtTsArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let ts = req
+        .ts
+        .parse::<u64>()
+        .ok()
+        .and_then(|ts| Timestamp::try_from(ts).ok())
+        .ok_or_else(|| {
+            anyhow::anyhow!(ErrorMetadata::bad_request(
+                "InvalidTimestamp",
+                format!("Invalid timestamp {}", req.ts),
+            ))
+        })?;
+    let path = CanonicalizedComponentFunctionPath {
+        component: ComponentPath::deserialize(req.component_path.as_deref())?,
+        udf_path: parse_udf_path(&req.path)?,
+    };
+    let udf_return = st
+        .application
+        .read_only_udf_at_historical_ts(
+            request_id,
+            path,
+            req.args.into_arg_vec(),
+            identity,
+            ts,
+            FunctionCaller::HttpApi(client_version.clone()),
+        )
+        .await?;
+    let value_format = req.format.as_ref().map(|f| f.parse()).transpose()?;
+    let response = match udf_return {
+        Ok(result) => UdfResponse::Success {
+            value: export_value(result.value, value_format, client_version)?,
+            log_lines: result.log_lines,
+        },
+        Err(error) => {
+            UdfResponse::error(error.error, error.log_lines, value_format, client_version)?
+        },
+    };
+    Ok(Json(response))
+}
@@ -95,6 +95,7 @@ impl TryFrom<StartPushResponse> for SerializedStartPushResponse {
                 .collect::<anyhow::Result<_>>()?,
             app: value.app.try_into()?,
             schema_change: value.schema_change.try_into()?,
+            args_validator_warnings: value.args_validator_warnings,
         })
     }
 }
@@ -135,6 +136,7 @@ impl TryFrom<SerializedStartPushResponse> for StartPushResponse {
                 .collect::<anyhow::Result<_>>()?,
             app: value.app.try_into()?,
             schema_change: value.schema_change.try_into()?,
+            args_validator_warnings: value.args_validator_warnings,
         })
     }
 }
@@ -157,6 +159,10 @@ pub struct SerializedStartPushResponse {
 
     // Schema changes.
     schema_change: SerializedSchemaChange,
+
+    // Functions whose new argument validators reject recently observed calls.
+    #[serde(default)]
+    args_validator_warnings: Vec<String>,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use axum::response::IntoResponse;
+use common::{
+    fault_injection::{
+        self,
+        FaultInjectionConfig,
+    },
+    http::{
+        extract::Json,
+        HttpResponseError,
+    },
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    admin::{
+        must_be_admin,
+        must_be_admin_with_write_access,
+    },
+    authentication::ExtractIdentity,
+};
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FaultInjectionConfigJson {
+    #[serde(default)]
+    commit_failure_rate: f64,
+    #[serde(default)]
+    persistence_read_latency_ms: u64,
+    #[serde(default)]
+    isolate_kill_rate: f64,
+}
+
+impl From<FaultInjectionConfig> for FaultInjectionConfigJson {
+    fn from(config: FaultInjectionConfig) -> Self {
+        Self {
+            commit_failure_rate: config.commit_failure_rate,
+            persistence_read_latency_ms: config.persistence_read_latency.as_millis() as u64,
+            isolate_kill_rate: config.isolate_kill_rate,
+        }
+    }
+}
+
+impl From<FaultInjectionConfigJson> for FaultInjectionConfig {
+    fn from(config: FaultInjectionConfigJson) -> Self {
+        Self {
+            commit_failure_rate: config.commit_failure_rate,
+            persistence_read_latency: Duration::from_millis(config.persistence_read_latency_ms),
+            isolate_kill_rate: config.isolate_kill_rate,
+        }
+    }
+}
+
+pub async fn get_fault_injection(
+    ExtractIdentity(identity): ExtractIdentity,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    Ok(Json(FaultInjectionConfigJson::from(
+        fault_injection::config(),
+    )))
+}
+
+/// Replace the faults being injected. Omitted fields turn that fault off, so
+/// an empty object stops injecting faults.
+pub async fn set_fault_injection(
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(args): Json<FaultInjectionConfigJson>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    fault_injection::configure(args.into())?;
+    Ok(Json(FaultInjectionConfigJson::from(
+        fault_injection::config(),
+    )))
+}
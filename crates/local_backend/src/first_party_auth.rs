@@ -0,0 +1,350 @@
+use std::{
+    net::{
+        IpAddr,
+        SocketAddr,
+    },
+    num::NonZeroU32,
+    time::{
+        Duration,
+        SystemTime,
+    },
+};
+
+use application::first_party_auth::{
+    normalize_email,
+    FirstPartyAuthSession,
+};
+use axum::{
+    extract::{
+        ConnectInfo,
+        State,
+    },
+    response::{
+        IntoResponse,
+        Response,
+    },
+};
+use common::{
+    http::{
+        extract::Json,
+        HttpResponseError,
+    },
+    runtime::{
+        new_keyed_rate_limiter,
+        GovernorInstant,
+        KeyedRateLimiter,
+        Runtime,
+    },
+};
+use errors::ErrorMetadata;
+use governor::Quota;
+use http::{
+    HeaderMap,
+    StatusCode,
+};
+use parking_lot::Mutex;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use sync_types::AuthenticationToken;
+
+use crate::{
+    authentication::ExtractAuthenticationToken,
+    rate_limits::{
+        client_ip,
+        rate_limited_response,
+    },
+    LocalAppState,
+};
+
+/// How often buckets that have refilled are forgotten.
+const THROTTLE_RETAIN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Limits how often each email and client IP can log in or use magic links,
+/// on top of the deployment's configurable rate limits.
+pub struct FirstPartyAuthThrottle<RT: Runtime> {
+    runtime: RT,
+    by_email: KeyedRateLimiter<String, RT>,
+    by_ip: KeyedRateLimiter<IpAddr, RT>,
+    retained_at: Mutex<Option<tokio::time::Instant>>,
+}
+
+impl<RT: Runtime> FirstPartyAuthThrottle<RT> {
+    /// Allows `attempts_per_email` and `attempts_per_ip` attempts an hour.
+    pub fn new(runtime: RT, attempts_per_email: u32, attempts_per_ip: u32) -> Self {
+        let quota =
+            |attempts| Quota::per_hour(NonZeroU32::new(attempts).unwrap_or(NonZeroU32::MIN));
+        Self {
+            by_email: new_keyed_rate_limiter(runtime.clone(), quota(attempts_per_email)),
+            by_ip: new_keyed_rate_limiter(runtime.clone(), quota(attempts_per_ip)),
+            runtime,
+            retained_at: Mutex::new(None),
+        }
+    }
+
+    /// Takes an attempt from `email`'s and `ip`'s allowances, or returns how
+    /// long until the attempt would be allowed. Invalid emails are only
+    /// limited by IP, since they're rejected anyway.
+    pub fn check(&self, email: &str, ip: Option<IpAddr>) -> Result<(), Duration> {
+        let now = self.runtime.monotonic_now();
+        {
+            let mut retained_at = self.retained_at.lock();
+            if retained_at.map_or(true, |at| {
+                now.duration_since(at) >= THROTTLE_RETAIN_INTERVAL
+            }) {
+                self.by_email.retain_recent();
+                self.by_ip.retain_recent();
+                *retained_at = Some(now);
+            }
+        }
+        let mut retry_after = None;
+        if let Some(ip) = ip
+            && let Err(not_until) = self.by_ip.check_key(&ip)
+        {
+            retry_after =
+                retry_after.max(Some(not_until.wait_time_from(GovernorInstant::from(now))));
+        }
+        if let Ok(email) = normalize_email(email)
+            && let Err(not_until) = self.by_email.check_key(&email)
+        {
+            retry_after =
+                retry_after.max(Some(not_until.wait_time_from(GovernorInstant::from(now))));
+        }
+        match retry_after {
+            Some(wait) => Err(wait),
+            None => Ok(()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasswordCredentials {
+    email: String,
+    password: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MagicLinkRequest {
+    email: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MagicLinkVerification {
+    email: String,
+    token: String,
+}
+
+/// A session token to send as the auth token, e.g. with
+/// `ConvexClient.setAuth`, until it expires.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionResponse {
+    token: String,
+    expires_at_ms: u64,
+}
+
+impl TryFrom<FirstPartyAuthSession> for SessionResponse {
+    type Error = anyhow::Error;
+
+    fn try_from(session: FirstPartyAuthSession) -> anyhow::Result<Self> {
+        Ok(Self {
+            token: session.token,
+            expires_at_ms: session
+                .expires_at
+                .duration_since(SystemTime::UNIX_EPOCH)?
+                .as_millis() as u64,
+        })
+    }
+}
+
+/// Creates an account with an email and password, and logs in to it.
+pub async fn signup(
+    State(st): State<LocalAppState>,
+    Json(PasswordCredentials { email, password }): Json<PasswordCredentials>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    let session = st.application.first_party_signup(email, password).await?;
+    Ok(Json(SessionResponse::try_from(session)?))
+}
+
+pub async fn login(
+    State(st): State<LocalAppState>,
+    remote_addr: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    Json(PasswordCredentials { email, password }): Json<PasswordCredentials>,
+) -> Result<Response, HttpResponseError> {
+    if let Err(wait) = check_throttle(&st, &email, remote_addr, &headers) {
+        return Ok(rate_limited_response(wait));
+    }
+    let session = st.application.first_party_login(email, password).await?;
+    Ok(Json(SessionResponse::try_from(session)?).into_response())
+}
+
+/// Ends the session whose token is in the `Authorization` header.
+pub async fn logout(
+    State(st): State<LocalAppState>,
+    ExtractAuthenticationToken(token): ExtractAuthenticationToken,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    let AuthenticationToken::User(token) = token else {
+        return Err(anyhow::anyhow!(ErrorMetadata::unauthenticated(
+            "AuthSessionInvalid",
+            "Log out with the session token as a bearer token",
+        ))
+        .into());
+    };
+    st.application.first_party_logout(token).await?;
+    Ok(StatusCode::OK)
+}
+
+/// Emails the user a magic link by scheduling the function configured with
+/// `FIRST_PARTY_AUTH_MAGIC_LINK_FUNCTION`.
+pub async fn request_magic_link(
+    State(st): State<LocalAppState>,
+    remote_addr: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    Json(MagicLinkRequest { email }): Json<MagicLinkRequest>,
+) -> Result<Response, HttpResponseError> {
+    if let Err(wait) = check_throttle(&st, &email, remote_addr, &headers) {
+        return Ok(rate_limited_response(wait));
+    }
+    st.application.request_magic_link(email).await?;
+    Ok(StatusCode::OK.into_response())
+}
+
+pub async fn verify_magic_link(
+    State(st): State<LocalAppState>,
+    remote_addr: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    Json(MagicLinkVerification { email, token }): Json<MagicLinkVerification>,
+) -> Result<Response, HttpResponseError> {
+    if let Err(wait) = check_throttle(&st, &email, remote_addr, &headers) {
+        return Ok(rate_limited_response(wait));
+    }
+    let session = st.application.verify_magic_link(email, token).await?;
+    Ok(Json(SessionResponse::try_from(session)?).into_response())
+}
+
+fn check_throttle(
+    st: &LocalAppState,
+    email: &str,
+    remote_addr: Option<ConnectInfo<SocketAddr>>,
+    headers: &HeaderMap,
+) -> Result<(), Duration> {
+    let ip = client_ip(remote_addr.map(|connect_info| connect_info.0), headers);
+    st.first_party_auth_throttle.check(email, ip)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{
+            IpAddr,
+            Ipv4Addr,
+        },
+        time::Duration,
+    };
+
+    use axum::body::Body;
+    use common::knobs::FIRST_PARTY_AUTH_ATTEMPTS_PER_EMAIL;
+    use http::{
+        Request,
+        StatusCode,
+    };
+    use runtime::{
+        prod::ProdRuntime,
+        testing::TestRuntime,
+    };
+    use serde_json::json;
+
+    use super::FirstPartyAuthThrottle;
+    use crate::test_helpers::setup_backend_for_test;
+
+    fn ip(last: u8) -> Option<IpAddr> {
+        Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, last)))
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_first_party_auth_throttle(rt: TestRuntime) -> anyhow::Result<()> {
+        let throttle = FirstPartyAuthThrottle::new(rt.clone(), 2, 3);
+
+        throttle.check("ada@example.com", ip(1)).unwrap();
+        // Emails are throttled however they're written.
+        throttle.check(" Ada@Example.com", ip(2)).unwrap();
+        let wait = throttle.check("ada@example.com", ip(3)).unwrap_err();
+        assert!(wait <= Duration::from_secs(30 * 60));
+        throttle.check("grace@example.com", ip(3)).unwrap();
+
+        // Each IP is limited across emails.
+        throttle.check("alan@example.com", ip(1)).unwrap();
+        throttle.check("edsger@example.com", ip(1)).unwrap();
+        assert!(throttle.check("barbara@example.com", ip(1)).is_err());
+        throttle.check("barbara@example.com", ip(4)).unwrap();
+        // Invalid emails still count against the IP.
+        assert!(throttle.check("not an email", ip(1)).is_err());
+        throttle.check("not an email", None).unwrap();
+
+        rt.wait(Duration::from_secs(30 * 60)).await;
+        throttle.check("ada@example.com", ip(1)).unwrap();
+        Ok(())
+    }
+
+    #[convex_macro::prod_rt_test]
+    async fn test_login_throttled_per_email(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        let login = |email: &str| -> anyhow::Result<Request<Body>> {
+            Ok(Request::builder()
+                .uri("/api/auth/login")
+                .method("POST")
+                .header("Content-Type", "application/json")
+                .body(Body::from(serde_json::to_vec(&json!({
+                    "email": email,
+                    "password": "correct horse",
+                }))?))?)
+        };
+        for _ in 0..*FIRST_PARTY_AUTH_ATTEMPTS_PER_EMAIL {
+            backend
+                .expect_error(
+                    login("ada@example.com")?,
+                    StatusCode::BAD_REQUEST,
+                    "FirstPartyAuthDisabled",
+                )
+                .await?;
+        }
+        backend
+            .expect_error(
+                login("ada@example.com")?,
+                StatusCode::TOO_MANY_REQUESTS,
+                "RateLimited",
+            )
+            .await?;
+        // Other emails aren't affected.
+        backend
+            .expect_error(
+                login("grace@example.com")?,
+                StatusCode::BAD_REQUEST,
+                "FirstPartyAuthDisabled",
+            )
+            .await?;
+        Ok(())
+    }
+
+    #[convex_macro::prod_rt_test]
+    async fn test_first_party_auth_disabled_by_default(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        let req = Request::builder()
+            .uri("/api/auth/signup")
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_vec(&json!({
+                "email": "ada@example.com",
+                "password": "correct horse",
+            }))?))?;
+        backend
+            .expect_error(req, StatusCode::BAD_REQUEST, "FirstPartyAuthDisabled")
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use axum::{
+    extract::State,
+    response::IntoResponse,
+};
+use common::http::{
+    extract::Json,
+    HttpResponseError,
+};
+use errors::ErrorMetadata;
+use http::StatusCode;
+use model::function_limits::types::FunctionLimits;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use sync_types::CanonicalizedUdfPath;
+
+use crate::{
+    admin::{
+        must_be_admin,
+        must_be_admin_with_write_access,
+    },
+    authentication::ExtractIdentity,
+    LocalAppState,
+};
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionLimitsJson {
+    /// The function's path in the root component, like `"actions:heavy"`.
+    function: String,
+    /// Replaces the default timeout for the function's type.
+    timeout_ms: Option<u64>,
+    /// Replaces the default V8 heap limit. Node actions aren't affected.
+    max_heap_size_bytes: Option<u64>,
+}
+
+impl From<FunctionLimits> for FunctionLimitsJson {
+    fn from(limits: FunctionLimits) -> Self {
+        Self {
+            function: limits.function,
+            timeout_ms: limits
+                .user_timeout
+                .map(|timeout| timeout.as_millis() as u64),
+            max_heap_size_bytes: limits.max_user_heap_size,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListFunctionLimitsResponse {
+    limits: Vec<FunctionLimitsJson>,
+}
+
+/// Returns the functions whose timeout or heap limit overrides the
+/// deployment's defaults.
+pub async fn list_function_limits(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let limits = st
+        .application
+        .list_function_limits(identity)
+        .await?
+        .into_iter()
+        .map(FunctionLimitsJson::from)
+        .collect();
+    Ok(Json(ListFunctionLimitsResponse { limits }))
+}
+
+/// Overrides a function's timeout and heap limit. Leaving both out removes
+/// the overrides.
+pub async fn set_function_limits(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(args): Json<FunctionLimitsJson>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let function = args
+        .function
+        .parse::<CanonicalizedUdfPath>()
+        .context(ErrorMetadata::bad_request(
+            "InvalidFunctionLimits",
+            format!("{:?} isn't a valid function path.", args.function),
+        ))?
+        .strip()
+        .to_string();
+    if args.timeout_ms.is_none() && args.max_heap_size_bytes.is_none() {
+        st.application
+            .delete_function_limits(identity, function)
+            .await?;
+    } else {
+        let limits = FunctionLimits::new(
+            function,
+            args.timeout_ms.map(Duration::from_millis),
+            args.max_heap_size_bytes,
+        )?;
+        st.application.set_function_limits(identity, limits).await?;
+    }
+    Ok(StatusCode::OK)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use http::{
+        Request,
+        StatusCode,
+    };
+    use runtime::prod::ProdRuntime;
+    use serde_json::{
+        json,
+        Value as JsonValue,
+    };
+
+    use crate::test_helpers::setup_backend_for_test;
+
+    #[convex_macro::prod_rt_test]
+    async fn test_function_limits(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        let post = |body: JsonValue| {
+            Request::builder()
+                .uri("/api/function_limits")
+                .method("POST")
+                .header("Content-Type", "application/json")
+                .header("Authorization", backend.admin_auth_header.0.encode())
+                .body(Body::from(serde_json::to_vec(&body).unwrap()))
+        };
+        let list = || {
+            Request::builder()
+                .uri("/api/function_limits")
+                .method("GET")
+                .header("Authorization", backend.admin_auth_header.0.encode())
+                .body(Body::empty())
+        };
+
+        let req = post(json!({"function": "actions.js:heavy", "timeoutMs": 0}))?;
+        backend
+            .expect_error(req, StatusCode::BAD_REQUEST, "InvalidFunctionLimits")
+            .await?;
+
+        let req = post(json!({"function": "actions.js:heavy", "timeoutMs": 300_000}))?;
+        backend.expect_success::<JsonValue>(req).await?;
+        let listed: JsonValue = backend.expect_success(list()?).await?;
+        assert_eq!(
+            listed,
+            json!({"limits": [{
+                "function": "actions:heavy",
+                "timeoutMs": 300_000,
+                "maxHeapSizeBytes": null,
+            }]})
+        );
+
+        let req = post(json!({"function": "actions:heavy"}))?;
+        backend.expect_success::<JsonValue>(req).await?;
+        let listed: JsonValue = backend.expect_success(list()?).await?;
+        assert_eq!(listed, json!({"limits": []}));
+        Ok(())
+    }
+}
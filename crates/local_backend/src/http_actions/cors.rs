@@ -0,0 +1,234 @@
+//! Applies the deployment's CORS policy to HTTP actions. Preflight requests
+//! are answered without running the action, and responses to other requests
+//! from allowed origins get `Access-Control-Allow-Origin`, unless the action
+//! set it itself.
+use futures::{
+    stream,
+    StreamExt,
+};
+use http::{
+    header::{
+        ACCESS_CONTROL_ALLOW_HEADERS,
+        ACCESS_CONTROL_ALLOW_METHODS,
+        ACCESS_CONTROL_ALLOW_ORIGIN,
+        ACCESS_CONTROL_MAX_AGE,
+        ACCESS_CONTROL_REQUEST_HEADERS,
+        ACCESS_CONTROL_REQUEST_METHOD,
+        ORIGIN,
+        VARY,
+    },
+    HeaderMap,
+    HeaderValue,
+    Method,
+    StatusCode,
+};
+use model::cors_config::types::CorsConfig;
+
+use super::HttpActionResponse;
+
+/// The response to a preflight request, or `None` if the request isn't one.
+/// Disallowed preflights get an empty response, so the browser blocks the
+/// actual request.
+pub fn preflight_response(
+    config: &CorsConfig,
+    method: &Method,
+    headers: &HeaderMap,
+) -> Option<HttpActionResponse> {
+    if *method != Method::OPTIONS {
+        return None;
+    }
+    let origin = headers.get(ORIGIN)?;
+    let requested_method = headers.get(ACCESS_CONTROL_REQUEST_METHOD)?;
+    let mut response_headers = HeaderMap::new();
+    response_headers.insert(VARY, HeaderValue::from_static("origin"));
+    let requested_headers: Vec<_> = headers
+        .get_all(ACCESS_CONTROL_REQUEST_HEADERS)
+        .iter()
+        .filter_map(|h| h.to_str().ok())
+        .flat_map(|h| h.split(','))
+        .map(str::trim)
+        .filter(|h| !h.is_empty())
+        .collect();
+    let allow_origin = allowed_origin_header(config, origin).filter(|_| {
+        requested_method
+            .to_str()
+            .is_ok_and(|m| config.allows_method(m))
+            && requested_headers.iter().all(|h| config.allows_header(h))
+    });
+    if let Some(allow_origin) = allow_origin {
+        response_headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, allow_origin);
+        let allow_methods = if config.allowed_methods.is_empty() {
+            Ok(requested_method.clone())
+        } else {
+            HeaderValue::from_str(&config.allowed_methods.join(", "))
+        };
+        if let Ok(allow_methods) = allow_methods {
+            response_headers.insert(ACCESS_CONTROL_ALLOW_METHODS, allow_methods);
+        }
+        if !requested_headers.is_empty()
+            && let Ok(allow_headers) = HeaderValue::from_str(&requested_headers.join(", "))
+        {
+            response_headers.insert(ACCESS_CONTROL_ALLOW_HEADERS, allow_headers);
+        }
+        if let Some(max_age) = config.max_age {
+            response_headers.insert(ACCESS_CONTROL_MAX_AGE, max_age.as_secs().into());
+        }
+    }
+    Some(HttpActionResponse {
+        body: stream::empty().boxed(),
+        status: StatusCode::NO_CONTENT,
+        headers: response_headers,
+    })
+}
+
+/// Lets the request's origin read `response`, if the policy allows it.
+pub fn apply_to_response(
+    config: &CorsConfig,
+    request_headers: &HeaderMap,
+    response: &mut HttpActionResponse,
+) {
+    if response.headers.contains_key(ACCESS_CONTROL_ALLOW_ORIGIN) {
+        return;
+    }
+    response
+        .headers
+        .append(VARY, HeaderValue::from_static("origin"));
+    if let Some(allow_origin) = request_headers
+        .get(ORIGIN)
+        .and_then(|origin| allowed_origin_header(config, origin))
+    {
+        response
+            .headers
+            .insert(ACCESS_CONTROL_ALLOW_ORIGIN, allow_origin);
+    }
+}
+
+fn allowed_origin_header(config: &CorsConfig, origin: &HeaderValue) -> Option<HeaderValue> {
+    if config.allows_any_origin() {
+        return Some(HeaderValue::from_static("*"));
+    }
+    let origin_str = origin.to_str().ok()?;
+    config.allows_origin(origin_str).then(|| origin.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::{
+        stream,
+        StreamExt,
+    };
+    use http::{
+        header::{
+            ACCESS_CONTROL_ALLOW_HEADERS,
+            ACCESS_CONTROL_ALLOW_METHODS,
+            ACCESS_CONTROL_ALLOW_ORIGIN,
+            ACCESS_CONTROL_MAX_AGE,
+            ACCESS_CONTROL_REQUEST_HEADERS,
+            ACCESS_CONTROL_REQUEST_METHOD,
+            ORIGIN,
+        },
+        HeaderMap,
+        HeaderValue,
+        Method,
+        StatusCode,
+    };
+    use model::cors_config::types::CorsConfig;
+
+    use super::{
+        apply_to_response,
+        preflight_response,
+    };
+    use crate::http_actions::HttpActionResponse;
+
+    fn config() -> CorsConfig {
+        CorsConfig::new(
+            vec!["https://example.com".to_string()],
+            vec!["GET".to_string(), "POST".to_string()],
+            vec!["content-type".to_string()],
+            Some(Duration::from_secs(600)),
+        )
+        .unwrap()
+    }
+
+    fn preflight(origin: &'static str, method: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(ORIGIN, HeaderValue::from_static(origin));
+        headers.insert(
+            ACCESS_CONTROL_REQUEST_METHOD,
+            HeaderValue::from_static(method),
+        );
+        headers.insert(
+            ACCESS_CONTROL_REQUEST_HEADERS,
+            HeaderValue::from_static("Content-Type"),
+        );
+        headers
+    }
+
+    #[test]
+    fn test_preflight() {
+        let config = config();
+        let response = preflight_response(
+            &config,
+            &Method::OPTIONS,
+            &preflight("https://example.com", "POST"),
+        )
+        .unwrap();
+        assert_eq!(response.status, StatusCode::NO_CONTENT);
+        assert_eq!(
+            response.headers[ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://example.com"
+        );
+        assert_eq!(response.headers[ACCESS_CONTROL_ALLOW_METHODS], "GET, POST");
+        assert_eq!(
+            response.headers[ACCESS_CONTROL_ALLOW_HEADERS],
+            "Content-Type"
+        );
+        assert_eq!(response.headers[ACCESS_CONTROL_MAX_AGE], "600");
+
+        for (origin, method) in [
+            ("https://evil.com", "POST"),
+            ("https://example.com", "DELETE"),
+        ] {
+            let response =
+                preflight_response(&config, &Method::OPTIONS, &preflight(origin, method)).unwrap();
+            assert!(!response.headers.contains_key(ACCESS_CONTROL_ALLOW_ORIGIN));
+        }
+
+        // Plain OPTIONS requests go to the action.
+        let mut headers = preflight("https://example.com", "POST");
+        headers.remove(ACCESS_CONTROL_REQUEST_METHOD);
+        assert!(preflight_response(&config, &Method::OPTIONS, &headers).is_none());
+    }
+
+    #[test]
+    fn test_apply_to_response() {
+        let config = config();
+        let response = |headers: HeaderMap| HttpActionResponse {
+            body: stream::empty().boxed(),
+            status: StatusCode::OK,
+            headers,
+        };
+        let mut request_headers = HeaderMap::new();
+        request_headers.insert(ORIGIN, HeaderValue::from_static("https://example.com"));
+
+        let mut allowed = response(HeaderMap::new());
+        apply_to_response(&config, &request_headers, &mut allowed);
+        assert_eq!(
+            allowed.headers[ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://example.com"
+        );
+
+        let mut own_headers = HeaderMap::new();
+        own_headers.insert(ACCESS_CONTROL_ALLOW_ORIGIN, HeaderValue::from_static("*"));
+        let mut overridden = response(own_headers);
+        apply_to_response(&config, &request_headers, &mut overridden);
+        assert_eq!(overridden.headers[ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+
+        request_headers.insert(ORIGIN, HeaderValue::from_static("https://evil.com"));
+        let mut disallowed = response(HeaderMap::new());
+        apply_to_response(&config, &request_headers, &mut disallowed);
+        assert!(!disallowed.headers.contains_key(ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+}
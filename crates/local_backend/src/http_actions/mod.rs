@@ -1,4 +1,10 @@
-use std::sync::Arc;
+use std::sync::{
+    atomic::{
+        AtomicBool,
+        Ordering,
+    },
+    Arc,
+};
 
 use anyhow::Context;
 use application::api::ApplicationApi;
@@ -32,9 +38,15 @@ use common::{
         OriginalHttpUri,
         ResolvedHostname,
     },
+    knobs::{
+        HTTP_ACTION_REQUEST_BODY_LIMIT,
+        HTTP_ACTION_SSE_KEEPALIVE_INTERVAL,
+        HTTP_ACTION_SSE_MAX_DURATION,
+    },
     types::FunctionCaller,
     RequestId,
 };
+use errors::ErrorMetadata;
 use futures::{
     stream::{
         BoxStream,
@@ -46,12 +58,18 @@ use futures::{
 };
 use futures_async_stream::try_stream;
 use http::{
-    header::FORWARDED,
+    header::{
+        CONTENT_LENGTH,
+        FORWARDED,
+    },
     HeaderMap,
     Method,
     StatusCode,
 };
 use isolate::{
+    event_stream_body,
+    is_event_stream,
+    prepare_event_stream_head,
     HttpActionRequest,
     HttpActionRequestHead,
     HttpActionResponsePart,
@@ -67,7 +85,14 @@ use crate::{
     RouterState,
 };
 
-pub struct ExtractHttpRequestMetadata(pub HttpActionRequest);
+mod cors;
+mod rpc;
+
+use rpc::RpcProtocol;
+
+/// The request to pass to the HTTP action, and whether its body was cut off
+/// for being larger than `HTTP_ACTION_REQUEST_BODY_LIMIT`.
+pub struct ExtractHttpRequestMetadata(pub HttpActionRequest, pub Arc<AtomicBool>);
 
 const X_FORWARDED_PROTO: &str = "x-forwarded-proto";
 
@@ -127,27 +152,81 @@ impl FromRequest<RouterState, axum::body::Body> for ExtractHttpRequestMetadata {
         // Construct the URL we provide in the HTTP request object.
         let url = Url::parse(&format!("{scheme}://{host}{uri}")).context("Invalid URL")?;
 
+        let body_too_large = Arc::new(AtomicBool::new(false));
         if method == Method::GET || method == Method::OPTIONS || method == Method::HEAD {
-            return Ok(ExtractHttpRequestMetadata(HttpActionRequest {
+            return Ok(ExtractHttpRequestMetadata(
+                HttpActionRequest {
+                    head: HttpActionRequestHead {
+                        headers,
+                        url,
+                        method,
+                    },
+                    body: None,
+                },
+                body_too_large,
+            ));
+        }
+
+        // Reject bodies we know are too large up front. The rest are counted as
+        // they stream into the action, since `DefaultBodyLimit` doesn't apply to
+        // raw body streams.
+        let limit = *HTTP_ACTION_REQUEST_BODY_LIMIT;
+        let content_length = headers
+            .get(CONTENT_LENGTH)
+            .and_then(|h| h.to_str().ok())
+            .and_then(|h| h.parse::<u64>().ok());
+        if content_length.is_some_and(|len| len > limit as u64) {
+            return Err(anyhow::anyhow!(request_body_too_large(limit)).into());
+        }
+        let body = req
+            .into_body()
+            .into_data_stream()
+            .map_err(anyhow::Error::from)
+            .boxed();
+
+        Ok(ExtractHttpRequestMetadata(
+            HttpActionRequest {
                 head: HttpActionRequestHead {
                     headers,
                     url,
                     method,
                 },
-                body: None,
-            }));
-        }
+                body: Some(limit_body(body, limit, body_too_large.clone())),
+            },
+            body_too_large,
+        ))
+    }
+}
 
-        let body = req.into_body();
+fn request_body_too_large(limit: usize) -> ErrorMetadata {
+    ErrorMetadata::payload_too_large(
+        "RequestBodyTooLarge",
+        format!(
+            "HTTP action request bodies can be at most {limit} bytes. Self-hosted deployments can \
+             raise this limit with HTTP_ACTION_REQUEST_BODY_LIMIT_BYTES."
+        ),
+    )
+}
 
-        Ok(ExtractHttpRequestMetadata(HttpActionRequest {
-            head: HttpActionRequestHead {
-                headers,
-                url,
-                method,
-            },
-            body: Some(Box::pin(body.into_data_stream().map_err(|e| e.into()))),
-        }))
+/// Fails `body` once it has streamed more than `limit` bytes, setting
+/// `too_large` so the handler can respond with a 413 whatever the action did
+/// with the error.
+#[try_stream(ok = Bytes, error = anyhow::Error, boxed)]
+async fn limit_body(
+    body: BoxStream<'static, anyhow::Result<Bytes>>,
+    limit: usize,
+    too_large: Arc<AtomicBool>,
+) {
+    let mut total = 0;
+    #[for_await]
+    for chunk in body {
+        let chunk = chunk?;
+        total += chunk.len();
+        if total > limit {
+            too_large.store(true, Ordering::SeqCst);
+            return Err(anyhow::anyhow!(request_body_too_large(limit)));
+        }
+        yield chunk;
     }
 }
 
@@ -158,8 +237,39 @@ pub async fn http_any_method(
     TryExtractIdentity(identity_result): TryExtractIdentity,
     ExtractRequestId(request_id): ExtractRequestId,
     ExtractResolvedHostname(host): ExtractResolvedHostname,
-    ExtractHttpRequestMetadata(http_request_metadata): ExtractHttpRequestMetadata,
+    ExtractHttpRequestMetadata(http_request_metadata, body_too_large): ExtractHttpRequestMetadata,
 ) -> Result<impl IntoResponse, HttpResponseError> {
+    let cors_config = st
+        .api
+        .http_action_cors_config(&host, request_id.clone())
+        .await?;
+    let request_headers = http_request_metadata.head.headers.clone();
+    let with_cors = |mut response: HttpActionResponse| {
+        if let Some(config) = &cors_config {
+            cors::apply_to_response(config, &request_headers, &mut response);
+        }
+        response
+    };
+    if let Some(config) = &cors_config
+        && let Some(response) = cors::preflight_response(
+            config,
+            &http_request_metadata.head.method,
+            &http_request_metadata.head.headers,
+        )
+    {
+        return Ok(response);
+    }
+    let rpc_protocol = RpcProtocol::from_request(
+        &http_request_metadata.head.method,
+        &http_request_metadata.head.headers,
+    );
+    let http_request_metadata = match rpc_protocol {
+        Some(protocol) => match rpc::decode_request(protocol, http_request_metadata).await {
+            Ok(request) => request,
+            Err(e) => return Ok(with_cors(rpc::error_response(protocol, e))),
+        },
+        None => http_request_metadata,
+    };
     let mut http_response_stream = stream_http_response(
         host,
         request_id,
@@ -167,23 +277,48 @@ pub async fn http_any_method(
         identity_result,
         st.api.clone(),
     );
-    let head = http_response_stream.try_next().await?;
-    let Some(HttpActionResponsePart::Head(response_head)) = head else {
+    let head = http_response_stream.try_next().await;
+    // The action may have caught the error from reading its body and
+    // responded anyway, but the client should still learn its request was
+    // too large.
+    if body_too_large.load(Ordering::SeqCst) {
+        return Err(
+            anyhow::anyhow!(request_body_too_large(*HTTP_ACTION_REQUEST_BODY_LIMIT)).into(),
+        );
+    }
+    let head = head?;
+    let Some(HttpActionResponsePart::Head(mut response_head)) = head else {
         return Err(anyhow::anyhow!("Did not receive HTTP response head first").into());
     };
-    let body = http_response_stream.map(|p| match p {
-        Ok(HttpActionResponsePart::BodyChunk(bytes)) => Ok(bytes),
-        Err(e) => Err(e),
-        _ => Err(anyhow::anyhow!(
-            "Unexpected element in HTTP response stream"
-        )),
-    });
+    let mut body = http_response_stream
+        .map(|p| match p {
+            Ok(HttpActionResponsePart::BodyChunk(bytes)) => Ok(bytes),
+            Err(e) => Err(e),
+            _ => Err(anyhow::anyhow!(
+                "Unexpected element in HTTP response stream"
+            )),
+        })
+        .boxed();
+    if is_event_stream(&response_head) {
+        prepare_event_stream_head(&mut response_head);
+        body = event_stream_body(
+            st.runtime.clone(),
+            body,
+            *HTTP_ACTION_SSE_KEEPALIVE_INTERVAL,
+            *HTTP_ACTION_SSE_MAX_DURATION,
+        );
+    }
 
-    Ok(HttpActionResponse {
+    let response = HttpActionResponse {
         status: response_head.status,
         headers: response_head.headers,
-        body: Box::pin(body),
-    })
+        body,
+    };
+    let response = match rpc_protocol {
+        Some(protocol) => rpc::encode_response(protocol, response),
+        None => response,
+    };
+    Ok(with_cors(response))
 }
 
 #[try_stream(ok=HttpActionResponsePart, error=anyhow::Error, boxed)]
@@ -262,3 +397,44 @@ impl IntoResponse for HttpActionResponse {
         (status, headers, body).into_response()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{
+            AtomicBool,
+            Ordering,
+        },
+        Arc,
+    };
+
+    use axum::body::Bytes;
+    use errors::ErrorMetadataAnyhowExt;
+    use futures::{
+        stream,
+        StreamExt,
+        TryStreamExt,
+    };
+
+    use super::limit_body;
+
+    #[tokio::test]
+    async fn test_limit_body() -> anyhow::Result<()> {
+        let chunks =
+            || stream::iter(["abc", "def"].map(|c| Ok::<_, anyhow::Error>(Bytes::from(c)))).boxed();
+
+        let too_large = Arc::new(AtomicBool::new(false));
+        let body: Vec<_> = limit_body(chunks(), 6, too_large.clone())
+            .try_collect()
+            .await?;
+        assert_eq!(body, vec![Bytes::from("abc"), Bytes::from("def")]);
+        assert!(!too_large.load(Ordering::SeqCst));
+
+        let mut body = limit_body(chunks(), 5, too_large.clone());
+        assert_eq!(body.try_next().await?, Some(Bytes::from("abc")));
+        let err = body.try_next().await.unwrap_err();
+        assert!(err.is_payload_too_large());
+        assert!(too_large.load(Ordering::SeqCst));
+        Ok(())
+    }
+}
@@ -0,0 +1,829 @@
+//! Terminates gRPC-web and Connect requests to HTTP actions, so they can serve
+//! typed RPC endpoints without implementing either protocol's framing.
+//!
+//! The action sees each request as a single message with the content type
+//! `application/proto` or `application/json`, and responds with a single
+//! message the same way. Its response's HTTP status is translated to an RPC
+//! status code, unless it sets the `grpc-status` header (and optionally
+//! `grpc-message`) to pick one itself. To stream several messages, the action
+//! can instead respond with an enveloped content type like
+//! `application/connect+proto` and write length-prefixed messages itself, and
+//! we add the end-of-stream trailers.
+//!
+//! Client streaming and compressed messages aren't supported.
+use bytes::{
+    Buf,
+    BufMut,
+    Bytes,
+    BytesMut,
+};
+use common::knobs::HTTP_ACTION_REQUEST_BODY_LIMIT;
+use errors::ErrorMetadataAnyhowExt;
+use futures::{
+    stream::BoxStream,
+    StreamExt,
+    TryStreamExt,
+};
+use futures_async_stream::try_stream;
+use http::{
+    header::{
+        CONTENT_ENCODING,
+        CONTENT_LENGTH,
+        CONTENT_TYPE,
+    },
+    HeaderMap,
+    HeaderValue,
+    Method,
+    StatusCode,
+};
+use isolate::HttpActionRequest;
+use serde_json::json;
+
+use super::HttpActionResponse;
+
+const GRPC_STATUS: &str = "grpc-status";
+const GRPC_MESSAGE: &str = "grpc-message";
+const GRPC_ENCODING: &str = "grpc-encoding";
+const CONNECT_PROTOCOL_VERSION: &str = "connect-protocol-version";
+const CONNECT_CONTENT_ENCODING: &str = "connect-content-encoding";
+
+/// Envelope flags.
+const FLAG_COMPRESSED: u8 = 0x01;
+const FLAG_CONNECT_END_STREAM: u8 = 0x02;
+const FLAG_GRPC_WEB_TRAILERS: u8 = 0x80;
+
+const ENVELOPE_PREFIX_LEN: usize = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RpcCodec {
+    Proto,
+    Json,
+}
+
+impl RpcCodec {
+    fn name(self) -> &'static str {
+        match self {
+            RpcCodec::Proto => "proto",
+            RpcCodec::Json => "json",
+        }
+    }
+
+    /// The content type of a single message, which is what HTTP actions see.
+    fn message_content_type(self) -> &'static str {
+        match self {
+            RpcCodec::Proto => "application/proto",
+            RpcCodec::Json => "application/json",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RpcProtocol {
+    /// gRPC-web, with base64-encoded bodies if `text`.
+    GrpcWeb { codec: RpcCodec, text: bool },
+    /// Connect's unary protocol, where messages aren't enveloped.
+    ConnectUnary { codec: RpcCodec },
+    /// Connect's streaming protocol.
+    ConnectStreaming { codec: RpcCodec },
+}
+
+impl RpcProtocol {
+    /// Detects an RPC from its method and content type. Connect's unary
+    /// protocol uses plain content types, so it also needs the
+    /// `Connect-Protocol-Version` header that Connect clients send.
+    pub fn from_request(method: &Method, headers: &HeaderMap) -> Option<Self> {
+        if method != Method::POST {
+            return None;
+        }
+        let content_type = headers.get(CONTENT_TYPE)?.to_str().ok()?;
+        let content_type = content_type.split(';').next()?.trim().to_ascii_lowercase();
+        let protocol = match content_type.as_str() {
+            "application/grpc-web" | "application/grpc-web+proto" => Self::GrpcWeb {
+                codec: RpcCodec::Proto,
+                text: false,
+            },
+            "application/grpc-web+json" => Self::GrpcWeb {
+                codec: RpcCodec::Json,
+                text: false,
+            },
+            "application/grpc-web-text" | "application/grpc-web-text+proto" => Self::GrpcWeb {
+                codec: RpcCodec::Proto,
+                text: true,
+            },
+            "application/grpc-web-text+json" => Self::GrpcWeb {
+                codec: RpcCodec::Json,
+                text: true,
+            },
+            "application/connect+proto" => Self::ConnectStreaming {
+                codec: RpcCodec::Proto,
+            },
+            "application/connect+json" => Self::ConnectStreaming {
+                codec: RpcCodec::Json,
+            },
+            "application/proto" | "application/json"
+                if headers.contains_key(CONNECT_PROTOCOL_VERSION) =>
+            {
+                Self::ConnectUnary {
+                    codec: if content_type == "application/proto" {
+                        RpcCodec::Proto
+                    } else {
+                        RpcCodec::Json
+                    },
+                }
+            },
+            _ => return None,
+        };
+        Some(protocol)
+    }
+
+    fn codec(self) -> RpcCodec {
+        match self {
+            Self::GrpcWeb { codec, .. }
+            | Self::ConnectUnary { codec }
+            | Self::ConnectStreaming { codec } => codec,
+        }
+    }
+
+    fn response_content_type(self) -> String {
+        match self {
+            Self::GrpcWeb { codec, text: false } => {
+                format!("application/grpc-web+{}", codec.name())
+            },
+            Self::GrpcWeb { codec, text: true } => {
+                format!("application/grpc-web-text+{}", codec.name())
+            },
+            Self::ConnectUnary { codec } => codec.message_content_type().to_string(),
+            Self::ConnectStreaming { codec } => format!("application/connect+{}", codec.name()),
+        }
+    }
+}
+
+/// The status codes shared by gRPC and Connect.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RpcCode {
+    Ok = 0,
+    Canceled = 1,
+    Unknown = 2,
+    InvalidArgument = 3,
+    DeadlineExceeded = 4,
+    NotFound = 5,
+    AlreadyExists = 6,
+    PermissionDenied = 7,
+    ResourceExhausted = 8,
+    FailedPrecondition = 9,
+    Aborted = 10,
+    OutOfRange = 11,
+    Unimplemented = 12,
+    Internal = 13,
+    Unavailable = 14,
+    DataLoss = 15,
+    Unauthenticated = 16,
+}
+
+impl RpcCode {
+    const ALL: [RpcCode; 17] = [
+        RpcCode::Ok,
+        RpcCode::Canceled,
+        RpcCode::Unknown,
+        RpcCode::InvalidArgument,
+        RpcCode::DeadlineExceeded,
+        RpcCode::NotFound,
+        RpcCode::AlreadyExists,
+        RpcCode::PermissionDenied,
+        RpcCode::ResourceExhausted,
+        RpcCode::FailedPrecondition,
+        RpcCode::Aborted,
+        RpcCode::OutOfRange,
+        RpcCode::Unimplemented,
+        RpcCode::Internal,
+        RpcCode::Unavailable,
+        RpcCode::DataLoss,
+        RpcCode::Unauthenticated,
+    ];
+
+    fn from_grpc_status(status: &str) -> Option<Self> {
+        let status: usize = status.trim().parse().ok()?;
+        Self::ALL.get(status).copied()
+    }
+
+    /// The code for an HTTP action response that didn't set `grpc-status`.
+    fn from_http_status(status: StatusCode) -> Self {
+        if status.is_success() {
+            return RpcCode::Ok;
+        }
+        match status.as_u16() {
+            400 => RpcCode::InvalidArgument,
+            401 => RpcCode::Unauthenticated,
+            403 => RpcCode::PermissionDenied,
+            // HTTP actions respond with a 404 when no route matches.
+            404 => RpcCode::Unimplemented,
+            408 | 504 => RpcCode::DeadlineExceeded,
+            409 => RpcCode::Aborted,
+            412 => RpcCode::FailedPrecondition,
+            429 => RpcCode::ResourceExhausted,
+            500 => RpcCode::Internal,
+            501 => RpcCode::Unimplemented,
+            502 | 503 => RpcCode::Unavailable,
+            _ => RpcCode::Unknown,
+        }
+    }
+
+    /// Connect's name for the code.
+    fn name(self) -> &'static str {
+        match self {
+            RpcCode::Ok => "ok",
+            RpcCode::Canceled => "canceled",
+            RpcCode::Unknown => "unknown",
+            RpcCode::InvalidArgument => "invalid_argument",
+            RpcCode::DeadlineExceeded => "deadline_exceeded",
+            RpcCode::NotFound => "not_found",
+            RpcCode::AlreadyExists => "already_exists",
+            RpcCode::PermissionDenied => "permission_denied",
+            RpcCode::ResourceExhausted => "resource_exhausted",
+            RpcCode::FailedPrecondition => "failed_precondition",
+            RpcCode::Aborted => "aborted",
+            RpcCode::OutOfRange => "out_of_range",
+            RpcCode::Unimplemented => "unimplemented",
+            RpcCode::Internal => "internal",
+            RpcCode::Unavailable => "unavailable",
+            RpcCode::DataLoss => "data_loss",
+            RpcCode::Unauthenticated => "unauthenticated",
+        }
+    }
+
+    /// The HTTP status of a Connect unary error response with this code.
+    fn connect_http_status(self) -> StatusCode {
+        match self {
+            RpcCode::Ok => StatusCode::OK,
+            RpcCode::Canceled => StatusCode::from_u16(499).expect("valid status code"),
+            RpcCode::InvalidArgument | RpcCode::FailedPrecondition | RpcCode::OutOfRange => {
+                StatusCode::BAD_REQUEST
+            },
+            RpcCode::DeadlineExceeded => StatusCode::GATEWAY_TIMEOUT,
+            RpcCode::NotFound => StatusCode::NOT_FOUND,
+            RpcCode::AlreadyExists | RpcCode::Aborted => StatusCode::CONFLICT,
+            RpcCode::PermissionDenied => StatusCode::FORBIDDEN,
+            RpcCode::ResourceExhausted => StatusCode::TOO_MANY_REQUESTS,
+            RpcCode::Unimplemented => StatusCode::NOT_IMPLEMENTED,
+            RpcCode::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            RpcCode::Unauthenticated => StatusCode::UNAUTHORIZED,
+            RpcCode::Unknown | RpcCode::Internal | RpcCode::DataLoss => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RpcError {
+    pub code: RpcCode,
+    pub message: String,
+}
+
+impl RpcError {
+    fn new(code: RpcCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Unwraps the request's message so the HTTP action sees it as a plain
+/// request body.
+pub async fn decode_request(
+    protocol: RpcProtocol,
+    mut request: HttpActionRequest,
+) -> Result<HttpActionRequest, RpcError> {
+    let headers = &mut request.head.headers;
+    let encoding_header = match protocol {
+        RpcProtocol::GrpcWeb { .. } => GRPC_ENCODING,
+        RpcProtocol::ConnectUnary { .. } => CONTENT_ENCODING.as_str(),
+        RpcProtocol::ConnectStreaming { .. } => CONNECT_CONTENT_ENCODING,
+    };
+    if let Some(encoding) = headers.get(encoding_header) {
+        if encoding != "identity" {
+            return Err(RpcError::new(
+                RpcCode::Unimplemented,
+                format!("Unsupported compression: {encoding:?}"),
+            ));
+        }
+    }
+    if let RpcProtocol::ConnectUnary { .. } = protocol {
+        return Ok(request);
+    }
+    let mut body = match request.body.take() {
+        Some(body) => read_body(body).await?,
+        None => BytesMut::new(),
+    };
+    if let RpcProtocol::GrpcWeb { text: true, .. } = protocol {
+        body = base64::decode(strip_whitespace(&body))
+            .map_err(|e| {
+                RpcError::new(
+                    RpcCode::InvalidArgument,
+                    format!("Invalid base64 body: {e}"),
+                )
+            })?
+            .as_slice()
+            .into();
+    }
+    let message = decode_single_envelope(body.freeze())?;
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static(protocol.codec().message_content_type()),
+    );
+    headers.remove(CONTENT_LENGTH);
+    request.body = Some(futures::stream::once(async move { Ok(message) }).boxed());
+    Ok(request)
+}
+
+/// Translates the HTTP action's response into the RPC protocol's response.
+pub fn encode_response(protocol: RpcProtocol, response: HttpActionResponse) -> HttpActionResponse {
+    let HttpActionResponse {
+        body,
+        status,
+        mut headers,
+    } = response;
+    let code = headers
+        .remove(GRPC_STATUS)
+        .and_then(|value| RpcCode::from_grpc_status(value.to_str().ok()?))
+        .unwrap_or_else(|| RpcCode::from_http_status(status));
+    let message = headers
+        .remove(GRPC_MESSAGE)
+        .and_then(|value| value.to_str().ok().map(percent_decode));
+    headers.remove(CONTENT_LENGTH);
+    if let RpcProtocol::ConnectUnary { .. } = protocol {
+        if code == RpcCode::Ok {
+            return HttpActionResponse {
+                body,
+                status,
+                headers,
+            };
+        }
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        return HttpActionResponse {
+            body: connect_unary_error_body(body, code, message, status),
+            status: code.connect_http_status(),
+            headers,
+        };
+    }
+    let enveloped = code == RpcCode::Ok
+        && headers
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(is_enveloped_content_type);
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_str(&protocol.response_content_type())
+            .expect("content types are valid header values"),
+    );
+    let body = enveloped_body(protocol, body, enveloped, code, message, status);
+    let body = match protocol {
+        RpcProtocol::GrpcWeb { text: true, .. } => base64_body(body),
+        _ => body,
+    };
+    HttpActionResponse {
+        body,
+        status: StatusCode::OK,
+        headers,
+    }
+}
+
+/// The response for a request that failed before reaching the HTTP action.
+pub fn error_response(protocol: RpcProtocol, error: RpcError) -> HttpActionResponse {
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        GRPC_STATUS,
+        HeaderValue::from_str(&(error.code as u8).to_string()).expect("valid header value"),
+    );
+    if let Ok(message) = HeaderValue::from_str(&percent_encode(&error.message)) {
+        headers.insert(GRPC_MESSAGE, message);
+    }
+    encode_response(
+        protocol,
+        HttpActionResponse {
+            body: futures::stream::empty().boxed(),
+            status: StatusCode::OK,
+            headers,
+        },
+    )
+}
+
+fn is_enveloped_content_type(content_type: &str) -> bool {
+    let content_type = content_type.trim().to_ascii_lowercase();
+    content_type.starts_with("application/grpc") || content_type.starts_with("application/connect+")
+}
+
+async fn read_body(
+    mut body: BoxStream<'static, anyhow::Result<Bytes>>,
+) -> Result<BytesMut, RpcError> {
+    let limit = *HTTP_ACTION_REQUEST_BODY_LIMIT;
+    let too_large = || {
+        RpcError::new(
+            RpcCode::ResourceExhausted,
+            format!("Request message is larger than {limit} bytes"),
+        )
+    };
+    let mut buf = BytesMut::new();
+    while let Some(chunk) = body.try_next().await.map_err(|e| {
+        if e.is_payload_too_large() {
+            return too_large();
+        }
+        RpcError::new(
+            RpcCode::Canceled,
+            format!("Failed to read request body: {e}"),
+        )
+    })? {
+        if buf.len() + chunk.len() > limit {
+            return Err(too_large());
+        }
+        buf.extend_from_slice(&chunk);
+    }
+    Ok(buf)
+}
+
+fn strip_whitespace(body: &[u8]) -> Vec<u8> {
+    body.iter()
+        .copied()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect()
+}
+
+fn decode_single_envelope(mut body: Bytes) -> Result<Bytes, RpcError> {
+    let mut messages = vec![];
+    while !body.is_empty() {
+        if body.len() < ENVELOPE_PREFIX_LEN {
+            return Err(RpcError::new(
+                RpcCode::InvalidArgument,
+                "Truncated message envelope",
+            ));
+        }
+        let flags = body[0];
+        let len = u32::from_be_bytes([body[1], body[2], body[3], body[4]]) as usize;
+        if body.len() - ENVELOPE_PREFIX_LEN < len {
+            return Err(RpcError::new(
+                RpcCode::InvalidArgument,
+                "Truncated message envelope",
+            ));
+        }
+        if flags & FLAG_COMPRESSED != 0 {
+            return Err(RpcError::new(
+                RpcCode::Unimplemented,
+                "Compressed messages aren't supported",
+            ));
+        }
+        body.advance(ENVELOPE_PREFIX_LEN);
+        messages.push(body.split_to(len));
+    }
+    match messages.len() {
+        1 => Ok(messages.pop().expect("just checked the length")),
+        0 => Err(RpcError::new(
+            RpcCode::InvalidArgument,
+            "Request is missing a message",
+        )),
+        _ => Err(RpcError::new(
+            RpcCode::Unimplemented,
+            "Client streaming isn't supported",
+        )),
+    }
+}
+
+fn envelope(flags: u8, message: &[u8]) -> Bytes {
+    let mut buf = BytesMut::with_capacity(ENVELOPE_PREFIX_LEN + message.len());
+    buf.put_u8(flags);
+    buf.put_u32(message.len() as u32);
+    buf.put_slice(message);
+    buf.freeze()
+}
+
+/// The error message for a failed response, preferring `grpc-message`.
+fn error_message(message: Option<String>, body: &[u8], status: StatusCode) -> String {
+    if let Some(message) = message {
+        return message;
+    }
+    let body = String::from_utf8_lossy(body).trim().to_string();
+    if !body.is_empty() {
+        return body;
+    }
+    status
+        .canonical_reason()
+        .unwrap_or("HTTP action failed")
+        .to_string()
+}
+
+#[try_stream(ok = Bytes, error = anyhow::Error, boxed)]
+async fn connect_unary_error_body(
+    body: BoxStream<'static, anyhow::Result<Bytes>>,
+    code: RpcCode,
+    message: Option<String>,
+    status: StatusCode,
+) {
+    let body = match read_body(body).await {
+        Ok(body) => body,
+        Err(e) => BytesMut::from(e.message.as_bytes()),
+    };
+    let error = json!({
+        "code": code.name(),
+        "message": error_message(message, &body, status),
+    });
+    yield Bytes::from(error.to_string());
+}
+
+#[try_stream(ok = Bytes, error = anyhow::Error, boxed)]
+async fn enveloped_body(
+    protocol: RpcProtocol,
+    body: BoxStream<'static, anyhow::Result<Bytes>>,
+    enveloped: bool,
+    code: RpcCode,
+    message: Option<String>,
+    status: StatusCode,
+) {
+    let mut error = None;
+    if enveloped {
+        // The action is streaming messages it enveloped itself.
+        #[for_await]
+        for chunk in body {
+            match chunk {
+                Ok(chunk) => yield chunk,
+                Err(e) => {
+                    tracing::warn!("HTTP action failed while streaming an RPC response: {e:#}");
+                    error = Some(RpcError::new(
+                        RpcCode::Internal,
+                        "HTTP action failed while streaming its response",
+                    ));
+                    break;
+                },
+            }
+        }
+    } else {
+        match read_body(body).await {
+            Ok(body) if code == RpcCode::Ok => yield envelope(0, &body),
+            Ok(body) => error = Some(RpcError::new(code, error_message(message, &body, status))),
+            Err(e) => error = Some(RpcError::new(RpcCode::Internal, e.message)),
+        }
+    }
+    yield end_of_stream(protocol, error);
+}
+
+fn end_of_stream(protocol: RpcProtocol, error: Option<RpcError>) -> Bytes {
+    match protocol {
+        RpcProtocol::GrpcWeb { .. } => {
+            let (code, message) = match &error {
+                Some(error) => (error.code, error.message.as_str()),
+                None => (RpcCode::Ok, ""),
+            };
+            let mut trailers = format!("{GRPC_STATUS}:{}\r\n", code as u8);
+            if !message.is_empty() {
+                trailers += &format!("{GRPC_MESSAGE}:{}\r\n", percent_encode(message));
+            }
+            envelope(FLAG_GRPC_WEB_TRAILERS, trailers.as_bytes())
+        },
+        RpcProtocol::ConnectUnary { .. } | RpcProtocol::ConnectStreaming { .. } => {
+            let end_stream = match error {
+                Some(error) => json!({
+                    "error": { "code": error.code.name(), "message": error.message },
+                }),
+                None => json!({}),
+            };
+            envelope(FLAG_CONNECT_END_STREAM, end_stream.to_string().as_bytes())
+        },
+    }
+}
+
+/// Base64-encodes the body for gRPC-web's text mode, only splitting it on
+/// 3-byte boundaries so padding only ever appears at the end.
+#[try_stream(ok = Bytes, error = anyhow::Error, boxed)]
+async fn base64_body(body: BoxStream<'static, anyhow::Result<Bytes>>) {
+    let mut pending = BytesMut::new();
+    #[for_await]
+    for chunk in body {
+        pending.extend_from_slice(&chunk?);
+        let aligned = pending.len() - pending.len() % 3;
+        if aligned > 0 {
+            yield Bytes::from(base64::encode(pending.split_to(aligned)));
+        }
+    }
+    if !pending.is_empty() {
+        yield Bytes::from(base64::encode(pending));
+    }
+}
+
+fn percent_encode(message: &str) -> String {
+    urlencoding::encode(message).into_owned()
+}
+
+fn percent_decode(message: &str) -> String {
+    urlencoding::decode(message)
+        .map(|message| message.into_owned())
+        .unwrap_or_else(|_| message.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+    use futures::{
+        stream,
+        StreamExt,
+        TryStreamExt,
+    };
+    use http::{
+        header::CONTENT_TYPE,
+        HeaderMap,
+        HeaderValue,
+        Method,
+        StatusCode,
+    };
+    use isolate::{
+        HttpActionRequest,
+        HttpActionRequestHead,
+    };
+
+    use super::{
+        decode_request,
+        encode_response,
+        envelope,
+        RpcCode,
+        RpcCodec,
+        RpcProtocol,
+    };
+    use crate::http_actions::HttpActionResponse;
+
+    fn headers(content_type: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+        headers
+    }
+
+    fn request(content_type: &'static str, body: Bytes) -> HttpActionRequest {
+        HttpActionRequest {
+            head: HttpActionRequestHead {
+                headers: headers(content_type),
+                url: "https://example.convex.site/rpc.Greeter/Greet"
+                    .parse()
+                    .unwrap(),
+                method: Method::POST,
+            },
+            body: Some(stream::once(async move { Ok(body) }).boxed()),
+        }
+    }
+
+    fn response(status: StatusCode, headers: HeaderMap, body: &'static str) -> HttpActionResponse {
+        HttpActionResponse {
+            body: stream::once(async move { Ok(Bytes::from(body)) }).boxed(),
+            status,
+            headers,
+        }
+    }
+
+    async fn body_bytes(response: HttpActionResponse) -> anyhow::Result<Vec<u8>> {
+        let chunks: Vec<Bytes> = response.body.try_collect().await?;
+        Ok(chunks.concat())
+    }
+
+    #[test]
+    fn test_detect_protocol() {
+        let post = Method::POST;
+        assert_eq!(
+            RpcProtocol::from_request(&post, &headers("application/grpc-web+proto")),
+            Some(RpcProtocol::GrpcWeb {
+                codec: RpcCodec::Proto,
+                text: false
+            })
+        );
+        assert_eq!(
+            RpcProtocol::from_request(&post, &headers("application/grpc-web-text")),
+            Some(RpcProtocol::GrpcWeb {
+                codec: RpcCodec::Proto,
+                text: true
+            })
+        );
+        assert_eq!(
+            RpcProtocol::from_request(&post, &headers("application/connect+json")),
+            Some(RpcProtocol::ConnectStreaming {
+                codec: RpcCodec::Json
+            })
+        );
+        // Plain JSON requests are only Connect ones if they say so.
+        assert_eq!(
+            RpcProtocol::from_request(&post, &headers("application/json")),
+            None
+        );
+        let mut connect_headers = headers("application/json; charset=utf-8");
+        connect_headers.insert("connect-protocol-version", HeaderValue::from_static("1"));
+        assert_eq!(
+            RpcProtocol::from_request(&post, &connect_headers),
+            Some(RpcProtocol::ConnectUnary {
+                codec: RpcCodec::Json
+            })
+        );
+        assert_eq!(
+            RpcProtocol::from_request(&Method::GET, &headers("application/grpc-web")),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_grpc_web_unary() -> anyhow::Result<()> {
+        let protocol = RpcProtocol::GrpcWeb {
+            codec: RpcCodec::Proto,
+            text: false,
+        };
+        let request = request("application/grpc-web+proto", envelope(0, b"hello"));
+        let mut request = decode_request(protocol, request).await.unwrap();
+        assert_eq!(
+            request.head.headers.get(CONTENT_TYPE).unwrap(),
+            "application/proto"
+        );
+        let body: Vec<Bytes> = request.body.take().unwrap().try_collect().await?;
+        assert_eq!(body.concat(), b"hello");
+
+        let response = encode_response(
+            protocol,
+            response(StatusCode::OK, headers("application/proto"), "world"),
+        );
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(
+            response.headers.get(CONTENT_TYPE).unwrap(),
+            "application/grpc-web+proto"
+        );
+        let mut expected = envelope(0, b"world").to_vec();
+        expected.extend_from_slice(&envelope(0x80, b"grpc-status:0\r\n"));
+        assert_eq!(body_bytes(response).await?, expected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_grpc_web_text_error() -> anyhow::Result<()> {
+        let protocol = RpcProtocol::GrpcWeb {
+            codec: RpcCodec::Proto,
+            text: true,
+        };
+        let body = base64::encode(envelope(0, b"hello"));
+        let request = request("application/grpc-web-text", body.into());
+        assert!(decode_request(protocol, request).await.is_ok());
+
+        let mut response_headers = headers("text/plain");
+        response_headers.insert("grpc-status", HeaderValue::from_static("5"));
+        let response = encode_response(
+            protocol,
+            response(StatusCode::OK, response_headers, "no such user"),
+        );
+        assert!(response.headers.get("grpc-status").is_none());
+        let body = base64::decode(body_bytes(response).await?)?;
+        assert_eq!(
+            body,
+            envelope(0x80, b"grpc-status:5\r\ngrpc-message:no%20such%20user\r\n")
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_connect() -> anyhow::Result<()> {
+        let unary = RpcProtocol::ConnectUnary {
+            codec: RpcCodec::Json,
+        };
+        let response = encode_response(
+            unary,
+            response(StatusCode::FORBIDDEN, headers("text/plain"), "go away"),
+        );
+        assert_eq!(response.status, StatusCode::FORBIDDEN);
+        assert_eq!(
+            serde_json::from_slice::<serde_json::Value>(&body_bytes(response).await?)?,
+            serde_json::json!({ "code": "permission_denied", "message": "go away" })
+        );
+
+        let streaming = RpcProtocol::ConnectStreaming {
+            codec: RpcCodec::Json,
+        };
+        // Messages the action enveloped itself are streamed through.
+        let response = encode_response(
+            streaming,
+            response(
+                StatusCode::OK,
+                headers("application/connect+json"),
+                "\0\0\0\0\x02{}\0\0\0\0\x02{}",
+            ),
+        );
+        let mut expected = b"\0\0\0\0\x02{}\0\0\0\0\x02{}".to_vec();
+        expected.extend_from_slice(&envelope(0x02, b"{}"));
+        assert_eq!(body_bytes(response).await?, expected);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_rejects_client_streaming() -> anyhow::Result<()> {
+        let protocol = RpcProtocol::ConnectStreaming {
+            codec: RpcCodec::Proto,
+        };
+        let mut body = envelope(0, b"a").to_vec();
+        body.extend_from_slice(&envelope(0, b"b"));
+        let streaming = request("application/connect+proto", body.into());
+        let error = decode_request(protocol, streaming).await.err().unwrap();
+        assert_eq!(error.code, RpcCode::Unimplemented);
+
+        let compressed = request("application/connect+proto", envelope(1, b"a"));
+        let error = decode_request(protocol, compressed).await.err().unwrap();
+        assert_eq!(error.code, RpcCode::Unimplemented);
+        Ok(())
+    }
+}
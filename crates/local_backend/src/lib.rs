@@ -8,6 +8,8 @@
 #![feature(exhaustive_patterns)]
 
 use std::{
+    cmp,
+    collections::BTreeMap,
     sync::Arc,
     time::Duration,
 };
@@ -16,24 +18,33 @@ use ::authentication::{
     access_token_auth::NullAccessTokenAuth,
     application_auth::ApplicationAuth,
 };
-use ::storage::{
-    LocalDirStorage,
-    StorageUseCase,
-};
+use ::storage::StorageUseCase;
 use application::{
     api::ApplicationApi,
+    embeddings::{
+        EmbeddingPipeline,
+        HttpEmbeddingProvider,
+    },
+    log_streaming::LogManager,
     log_visibility::AllowLogging,
     Application,
 };
 use common::{
+    fault_injection::FaultInjectionPersistence,
     http::{
         fetch::ProxiedFetchClient,
         RouteMapper,
     },
-    knobs::ACTION_USER_TIMEOUT,
-    log_streaming::NoopLogSender,
+    knobs::{
+        ACTION_MAX_USER_TIMEOUT,
+        ACTION_USER_TIMEOUT,
+        ENABLE_FAULT_INJECTION,
+        FIRST_PARTY_AUTH_ATTEMPTS_PER_EMAIL,
+        FIRST_PARTY_AUTH_ATTEMPTS_PER_IP,
+    },
     pause::PauseClient,
     persistence::Persistence,
+    runtime::Runtime,
     types::{
         ConvexOrigin,
         ConvexSite,
@@ -41,14 +52,17 @@ use common::{
 };
 use config::LocalConfig;
 use database::{
+    commit_hooks::CommitHooks,
     Database,
     ShutdownSignal,
 };
 use events::usage::NoOpUsageEventLogger;
 use file_storage::{
+    scanning::WebhookFileScanner,
     FileStorage,
     TransactionalFileStorage,
 };
+use first_party_auth::FirstPartyAuthThrottle;
 use function_runner::{
     server::{
         InProcessFunctionRunner,
@@ -60,6 +74,10 @@ use model::{
     initialize_application_system_tables,
     virtual_system_mapping,
 };
+use network_acl::{
+    GeoIpProvider,
+    HeaderGeoIp,
+};
 use node_executor::{
     local::LocalNodeExecutor,
     Actions,
@@ -71,30 +89,63 @@ use search::{
     SegmentTermMetadataFetcher,
 };
 use serde::Serialize;
+use sync::RetainedSessions;
 
 pub mod admin;
+pub mod admin_key_scope;
+pub mod admin_keys;
+pub mod admin_lists;
+pub mod admin_mtls;
+pub mod api_keys;
 mod app_metrics;
+pub mod archival;
 mod args_structs;
+pub mod audit_log;
+pub mod auth_providers;
 pub mod authentication;
+pub mod cold_data;
 pub mod config;
+pub mod cors_config;
+pub mod counters;
+pub mod custom_domains;
 pub mod custom_headers;
 pub mod dashboard;
 pub mod deploy_config;
 pub mod deploy_config2;
 pub mod environment_variables;
+pub mod fault_injection;
+pub mod first_party_auth;
+pub mod function_limits;
 pub mod http_actions;
+pub mod log_sinks;
 pub mod logs;
+pub mod materialized_aggregates;
+pub mod network_acl;
+pub mod network_acl_config;
 pub mod node_action_callbacks;
+pub mod operations;
 pub mod parse;
+pub mod pii_reports;
 pub mod proxy;
 pub mod public_api;
+pub mod queues;
+pub mod rate_limit_config;
+pub mod rate_limits;
+pub mod replication;
 pub mod router;
 pub mod scheduling;
 pub mod schema;
+pub mod search_index_bundle;
+pub mod slow_executions;
 pub mod snapshot_export;
 pub mod snapshot_import;
 pub mod storage;
+pub mod storage_gc;
 pub mod subs;
+pub mod trace_export;
+pub mod usage;
+pub mod vector_index_compaction;
+pub mod workflows;
 
 #[cfg(test)]
 mod test_helpers;
@@ -110,6 +161,9 @@ pub struct LocalAppState {
     pub instance_name: String,
     pub application: Application<ProdRuntime>,
     pub zombify_rx: async_broadcast::Receiver<()>,
+    // Where the network ACL looks up which country requests came from.
+    pub geo_ip: Option<Arc<dyn GeoIpProvider>>,
+    pub first_party_auth_throttle: Arc<FirstPartyAuthThrottle<ProdRuntime>>,
 }
 
 impl LocalAppState {
@@ -128,6 +182,8 @@ impl Clone for LocalAppState {
             instance_name: self.instance_name.clone(),
             application: self.application.clone(),
             zombify_rx: self.zombify_rx.clone(),
+            geo_ip: self.geo_ip.clone(),
+            first_party_auth_throttle: self.first_party_auth_throttle.clone(),
         }
     }
 }
@@ -139,6 +195,8 @@ impl Clone for LocalAppState {
 pub struct RouterState {
     pub api: Arc<dyn ApplicationApi>,
     pub runtime: ProdRuntime,
+    /// Closed sync sessions that reconnecting clients can resume.
+    pub sync_sessions: Arc<RetainedSessions>,
 }
 
 #[derive(Serialize)]
@@ -151,7 +209,15 @@ pub async fn make_app(
     zombify_rx: async_broadcast::Receiver<()>,
     preempt_tx: ShutdownSignal,
 ) -> anyhow::Result<LocalAppState> {
+    let persistence: Arc<dyn Persistence> = if *ENABLE_FAULT_INJECTION {
+        tracing::warn!("Fault injection is enabled. Don't use this deployment in production!");
+        Arc::new(FaultInjectionPersistence::new(runtime.clone(), persistence))
+    } else {
+        persistence
+    };
     let key_broker = config.key_broker()?;
+    key_broker
+        .set_admin_client_certificates(config.admin_mtls_principals(), config.require_admin_mtls);
     let in_process_searcher = InProcessSearcher::new(runtime.clone()).await?;
     let searcher: Arc<dyn Searcher> = Arc::new(in_process_searcher.clone());
     // TODO(CX-6572) Separate `SegmentMetadataFetcher` from `SearcherImpl`
@@ -167,50 +233,51 @@ pub async fn make_app(
     )
     .await?;
     initialize_application_system_tables(&database).await?;
-    let files_storage = Arc::new(LocalDirStorage::for_use_case(
-        runtime.clone(),
-        &config.storage_dir().to_string_lossy(),
-        StorageUseCase::Files,
-    )?);
-    let modules_storage = Arc::new(LocalDirStorage::for_use_case(
-        runtime.clone(),
-        &config.storage_dir().to_string_lossy(),
-        StorageUseCase::Modules,
-    )?);
-    let search_storage = Arc::new(LocalDirStorage::for_use_case(
-        runtime.clone(),
-        &config.storage_dir().to_string_lossy(),
-        StorageUseCase::SearchIndexes,
-    )?);
+    let storage = config.storage(runtime.clone())?;
+    let files_storage = storage.storage_for_use_case(runtime.clone(), StorageUseCase::Files)?;
+    let modules_storage = storage.storage_for_use_case(runtime.clone(), StorageUseCase::Modules)?;
+    let search_storage =
+        storage.storage_for_use_case(runtime.clone(), StorageUseCase::SearchIndexes)?;
     // Search storage needs to be set for Database to be fully initialized
     database.set_search_storage(search_storage.clone());
-    let exports_storage = Arc::new(LocalDirStorage::for_use_case(
-        runtime.clone(),
-        &config.storage_dir().to_string_lossy(),
-        StorageUseCase::Exports,
-    )?);
-    let snapshot_imports_storage = Arc::new(LocalDirStorage::for_use_case(
-        runtime.clone(),
-        &config.storage_dir().to_string_lossy(),
-        StorageUseCase::SnapshotImports,
-    )?);
+    let exports_storage = storage.storage_for_use_case(runtime.clone(), StorageUseCase::Exports)?;
+    let snapshot_imports_storage =
+        storage.storage_for_use_case(runtime.clone(), StorageUseCase::SnapshotImports)?;
 
+    let mut transactional_file_storage = TransactionalFileStorage::new(
+        runtime.clone(),
+        files_storage.clone(),
+        config.convex_origin_url(),
+    );
+    if let Some(url) = config.file_scan_webhook_url.clone() {
+        transactional_file_storage =
+            transactional_file_storage.with_scanner(Arc::new(WebhookFileScanner::new(url)));
+    }
     let file_storage = FileStorage {
-        transactional_file_storage: TransactionalFileStorage::new(
-            runtime.clone(),
-            files_storage.clone(),
-            config.convex_origin_url(),
-        ),
+        transactional_file_storage,
         database: database.clone(),
     };
 
-    let node_process_timeout = *ACTION_USER_TIMEOUT + Duration::from_secs(5);
+    // Leave room for actions whose timeout was raised in `_function_limits`.
+    let node_process_timeout =
+        cmp::max(*ACTION_USER_TIMEOUT, *ACTION_MAX_USER_TIMEOUT) + Duration::from_secs(5);
     let node_executor = Arc::new(LocalNodeExecutor::new(node_process_timeout)?);
     let actions = Actions::new(
         node_executor,
         config.convex_origin_url(),
         *ACTION_USER_TIMEOUT,
     );
+    // Each region gets its own pool of Node processes, so pinned actions don't
+    // queue behind the default runners.
+    let regional_actions: BTreeMap<_, _> = config
+        .action_regions
+        .iter()
+        .map(|region| {
+            let executor = Arc::new(LocalNodeExecutor::new(node_process_timeout)?);
+            let actions = Actions::new(executor, config.convex_origin_url(), *ACTION_USER_TIMEOUT);
+            anyhow::Ok((region.clone(), actions))
+        })
+        .try_collect()?;
 
     #[cfg(not(debug_assertions))]
     if config.convex_http_proxy.is_none() {
@@ -238,6 +305,34 @@ pub async fn make_app(
         )
         .await?,
     );
+    let log_sender = LogManager::start(
+        runtime.clone(),
+        database.clone(),
+        fetch_client.clone(),
+        config.name(),
+    );
+    if let Some(url) = config.embedding_url.clone() {
+        let provider = Arc::new(HttpEmbeddingProvider::new(
+            url,
+            config.embedding_model.clone(),
+            config.embedding_api_key.clone(),
+        ));
+        let mut commit_hooks = CommitHooks::new();
+        commit_hooks.register(Arc::new(EmbeddingPipeline::new(
+            database.clone(),
+            persistence.reader(),
+            provider,
+        )))?;
+        runtime.spawn(
+            "commit_hooks",
+            commit_hooks.start(
+                runtime.clone(),
+                persistence.clone(),
+                database.retention_validator(),
+                database.clone(),
+            ),
+        );
+    }
     let application = Application::new(
         runtime.clone(),
         database.clone(),
@@ -258,8 +353,9 @@ pub async fn make_app(
         segment_metadata_fetcher.clone(),
         persistence,
         actions,
+        regional_actions,
         fetch_client,
-        Arc::new(NoopLogSender),
+        log_sender,
         Arc::new(AllowLogging),
         PauseClient::new(),
         PauseClient::new(),
@@ -267,18 +363,31 @@ pub async fn make_app(
             key_broker.clone(),
             Arc::new(NullAccessTokenAuth),
         )),
+        config.replication()?,
     )
     .await?;
+    // Revocations and rotated secrets are persisted, so they survive restarts.
+    application.load_admin_keys().await?;
 
     let origin = config.convex_origin_url();
     let instance_name = config.name().clone();
 
+    let first_party_auth_throttle = Arc::new(FirstPartyAuthThrottle::new(
+        application.runtime().clone(),
+        *FIRST_PARTY_AUTH_ATTEMPTS_PER_EMAIL,
+        *FIRST_PARTY_AUTH_ATTEMPTS_PER_IP,
+    ));
     let app_state = LocalAppState {
         origin,
         site_origin: config.convex_site_url(),
         instance_name,
         application,
         zombify_rx,
+        geo_ip: config
+            .geoip_country_header
+            .clone()
+            .map(|header| Arc::new(HeaderGeoIp::new(header)) as Arc<dyn GeoIpProvider>),
+        first_party_auth_throttle,
     };
 
     Ok(app_state)
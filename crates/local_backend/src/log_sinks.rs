@@ -0,0 +1,169 @@
+//! Configures the sinks that function logs and system events are streamed to.
+use axum::{
+    extract::State,
+    response::IntoResponse,
+};
+use common::http::{
+    extract::Json,
+    HttpResponseError,
+};
+use errors::ErrorMetadata;
+use http::StatusCode;
+use model::log_sinks::types::{
+    SerializedSinkConfig,
+    SinkStatus,
+    SinkType,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    admin::{
+        must_be_admin,
+        must_be_admin_with_write_access,
+    },
+    authentication::ExtractIdentity,
+    LocalAppState,
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LogSinkJson {
+    /// The sink's config, with its credentials redacted.
+    config: SerializedSinkConfig,
+    /// "pending", "active" or "failed".
+    status: &'static str,
+    /// Why the last batch of logs couldn't be delivered, if it failed.
+    failure_reason: Option<String>,
+}
+
+pub async fn list_log_sinks(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let sinks: Vec<_> = st
+        .application
+        .list_log_sinks(identity)
+        .await?
+        .into_iter()
+        .map(|sink| {
+            let (status, failure_reason) = match sink.status {
+                SinkStatus::Pending => ("pending", None),
+                SinkStatus::Active => ("active", None),
+                SinkStatus::Failed { reason } => ("failed", Some(reason)),
+            };
+            LogSinkJson {
+                config: sink.config.into(),
+                status,
+                failure_reason,
+            }
+        })
+        .collect();
+    Ok(Json(sinks))
+}
+
+/// Adds a sink, like `{"type": "webhook", "url": "https://..."}`, replacing
+/// any sink of the same type.
+pub async fn set_log_sink(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(config): Json<SerializedSinkConfig>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    st.application.set_log_sink(identity, config.into()).await?;
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoveLogSinkArgs {
+    /// "datadog", "axiom", "webhook" or "syslog".
+    #[serde(rename = "type")]
+    sink_type: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoveLogSinkResponse {
+    /// False if there was no sink of the type.
+    removed: bool,
+}
+
+pub async fn remove_log_sink(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(RemoveLogSinkArgs { sink_type }): Json<RemoveLogSinkArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let sink_type: SinkType = sink_type.parse().map_err(|e: anyhow::Error| {
+        e.context(ErrorMetadata::bad_request(
+            "InvalidLogSink",
+            "The sink type should be datadog, axiom, webhook or syslog",
+        ))
+    })?;
+    let removed = st.application.remove_log_sink(identity, sink_type).await?;
+    Ok(Json(RemoveLogSinkResponse { removed }))
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{
+        Request,
+        StatusCode,
+    };
+    use runtime::prod::ProdRuntime;
+    use serde_json::{
+        json,
+        Value as JsonValue,
+    };
+
+    use crate::test_helpers::setup_backend_for_test;
+
+    #[convex_macro::prod_rt_test]
+    async fn test_set_and_remove_log_sink(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        let post = |uri: &str, body: JsonValue| {
+            Request::builder()
+                .uri(uri)
+                .method("POST")
+                .header("Content-Type", "application/json")
+                .header("Authorization", backend.admin_auth_header.0.encode())
+                .body(axum::body::Body::from(serde_json::to_vec(&body).unwrap()))
+        };
+        let req = post(
+            "/api/log_sinks",
+            json!({
+                "type": "datadog",
+                "site": "datadoghq.com",
+                "apiKey": "secret",
+                "tags": ["env:test"],
+            }),
+        )?;
+        backend.expect_success::<JsonValue>(req).await?;
+
+        let req = Request::builder()
+            .uri("/api/log_sinks")
+            .method("GET")
+            .header("Authorization", backend.admin_auth_header.0.encode())
+            .body(axum::body::Body::empty())?;
+        let sinks: JsonValue = backend.expect_success(req).await?;
+        assert_eq!(sinks[0]["config"]["type"], "datadog");
+        assert_eq!(sinks[0]["config"]["apiKey"], "***");
+
+        let req = post(
+            "/api/log_sinks",
+            json!({"type": "webhook", "url": "not a url"}),
+        )?;
+        backend
+            .expect_error(req, StatusCode::BAD_REQUEST, "InvalidLogSink")
+            .await?;
+
+        let req = post("/api/log_sinks/delete", json!({"type": "datadog"}))?;
+        let removed: JsonValue = backend.expect_success(req).await?;
+        assert_eq!(removed, json!({"removed": true}));
+        Ok(())
+    }
+}
@@ -9,8 +9,15 @@ use anyhow::anyhow;
 use clap::Parser;
 use cmd_util::env::config_service;
 use common::{
-    errors::MainError,
+    errors::{
+        report_error,
+        MainError,
+    },
     http::ConvexHttpService,
+    knobs::{
+        INDEX_KEY_RECOMPRESSION_BATCH_INTERVAL,
+        INDEX_KEY_RECOMPRESSION_BATCH_SIZE,
+    },
     runtime::Runtime,
     version::SERVER_VERSION_STR,
 };
@@ -23,10 +30,13 @@ use futures::{
     FutureExt,
 };
 use local_backend::{
+    admin_mtls::serve_admin_mtls,
     config::LocalConfig,
+    custom_domains::serve_custom_domains,
     make_app,
     proxy::dev_site_proxy,
     router::router,
+    trace_export::start_trace_export,
     HttpActionRouteMapper,
     MAX_CONCURRENT_REQUESTS,
 };
@@ -44,6 +54,8 @@ fn main() -> Result<(), MainError> {
 
     sodiumoxide::init().map_err(|()| anyhow!("sodiumoxide initialization failed"))?;
 
+    start_trace_export()?;
+
     let tokio = ProdRuntime::init_tokio()?;
     let runtime = ProdRuntime::new(&tokio);
 
@@ -70,12 +82,42 @@ async fn run_server(runtime: ProdRuntime, config: LocalConfig) -> anyhow::Result
     Ok(())
 }
 
+/// Rewrites index entries whose keys aren't compressed the way
+/// `INDEX_KEY_BLOCK_COMPRESSION` says, a batch at a time.
+async fn recompress_index_entries(runtime: ProdRuntime, persistence: SqlitePersistence) {
+    let mut cursor = None;
+    let mut rewritten = 0;
+    loop {
+        match persistence.recompress_index_entries(cursor, *INDEX_KEY_RECOMPRESSION_BATCH_SIZE) {
+            Ok((count, next_cursor)) => {
+                rewritten += count;
+                cursor = next_cursor;
+            },
+            Err(mut e) => {
+                report_error(&mut e);
+                return;
+            },
+        }
+        if cursor.is_none() {
+            break;
+        }
+        runtime.wait(*INDEX_KEY_RECOMPRESSION_BATCH_INTERVAL).await;
+    }
+    if rewritten > 0 {
+        tracing::info!("Recompressed {rewritten} index entries");
+    }
+}
+
 async fn run_server_inner(runtime: ProdRuntime, config: LocalConfig) -> anyhow::Result<()> {
     // Used to receive fatal errors from the database or /preempt endpoint.
     let (preempt_tx, mut preempt_rx) = async_broadcast::broadcast(1);
     // Use to signal to the http service to stop.
     let (shutdown_tx, shutdown_rx) = async_broadcast::broadcast(1);
     let persistence = SqlitePersistence::new(&config.db_spec, false)?;
+    runtime.spawn(
+        "index_key_recompression",
+        recompress_index_entries(runtime.clone(), persistence.clone()),
+    );
     let st = make_app(
         runtime.clone(),
         config.clone(),
@@ -85,6 +127,9 @@ async fn run_server_inner(runtime: ProdRuntime, config: LocalConfig) -> anyhow::
     )
     .await?;
     let router = router(st.clone());
+    let custom_domains_future =
+        serve_custom_domains(&config, st.clone(), router.clone(), shutdown_rx.clone());
+    let admin_mtls_future = serve_admin_mtls(&config, router.clone(), shutdown_rx.clone());
     let mut shutdown_rx_ = shutdown_rx.clone();
     let http_service = ConvexHttpService::new(
         router,
@@ -103,7 +148,13 @@ async fn run_server_inner(runtime: ProdRuntime, config: LocalConfig) -> anyhow::
         shutdown_rx,
     );
 
-    let serve_future = future::try_join(serve_http_future, proxy_future).fuse();
+    let serve_future = future::try_join4(
+        serve_http_future,
+        proxy_future,
+        custom_domains_future,
+        admin_mtls_future,
+    )
+    .fuse();
     futures::pin_mut!(serve_future);
 
     let preempt_future = async move { preempt_rx.recv().await }.fuse();
@@ -138,6 +189,8 @@ async fn run_server_inner(runtime: ProdRuntime, config: LocalConfig) -> anyhow::
         // Next, shutdown all of our asynchronous workers.
         tracing::info!("Shutting down application...");
         st.shutdown().await?;
+        // Report the spans that haven't been exported yet.
+        minitrace::flush();
 
         Ok::<_, anyhow::Error>(())
     }
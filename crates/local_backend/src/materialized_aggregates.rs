@@ -0,0 +1,189 @@
+use application::{
+    materialized_aggregates::MaterializedAggregateStatus,
+    valid_identifier::ValidIdentifier,
+};
+use axum::{
+    extract::State,
+    response::IntoResponse,
+};
+use common::{
+    components::ComponentId,
+    http::{
+        extract::Json,
+        HttpResponseError,
+    },
+};
+use http::StatusCode;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use value::{
+    FieldPath,
+    TableName,
+};
+
+use crate::{
+    admin::{
+        must_be_admin,
+        must_be_admin_with_write_access,
+    },
+    authentication::ExtractIdentity,
+    LocalAppState,
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateMaterializedAggregateArgs {
+    component_id: Option<String>,
+    table_name: String,
+    name: String,
+    /// Documents are counted per value of this field. Leave it out to count
+    /// the whole table.
+    key_field: Option<String>,
+    /// Numeric field to sum alongside the count.
+    sum_field: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteMaterializedAggregateArgs {
+    component_id: Option<String>,
+    table_name: String,
+    name: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaterializedAggregateJson {
+    component_path: String,
+    table_name: String,
+    name: String,
+    key_field: Option<String>,
+    sum_field: Option<String>,
+    /// False while the table's existing documents are being counted.
+    ready: bool,
+}
+
+impl From<MaterializedAggregateStatus> for MaterializedAggregateJson {
+    fn from(status: MaterializedAggregateStatus) -> Self {
+        Self {
+            component_path: String::from(status.component),
+            table_name: status.table_name.to_string(),
+            name: status.name,
+            key_field: status.key_field.map(String::from),
+            sum_field: status.sum_field.map(String::from),
+            ready: status.ready,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListMaterializedAggregatesResponse {
+    aggregates: Vec<MaterializedAggregateJson>,
+}
+
+pub async fn list_materialized_aggregates(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let aggregates = st
+        .application
+        .materialized_aggregates(identity)
+        .await?
+        .into_iter()
+        .map(MaterializedAggregateJson::from)
+        .collect();
+    Ok(Json(ListMaterializedAggregatesResponse { aggregates }))
+}
+
+/// Creates a materialized aggregate, which is backfilled in the background.
+/// Functions can read it with `materializedAggregate` from `convex/server`
+/// once it's ready.
+pub async fn create_materialized_aggregate(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(CreateMaterializedAggregateArgs {
+        component_id,
+        table_name,
+        name,
+        key_field,
+        sum_field,
+    }): Json<CreateMaterializedAggregateArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let table_name = table_name.parse::<ValidIdentifier<TableName>>()?.0;
+    let component = ComponentId::deserialize_from_string(component_id.as_deref())?;
+    let key_field = key_field.map(|f| f.parse::<FieldPath>()).transpose()?;
+    let sum_field = sum_field.map(|f| f.parse::<FieldPath>()).transpose()?;
+    st.application
+        .create_materialized_aggregate(identity, component, table_name, name, key_field, sum_field)
+        .await?;
+    Ok(StatusCode::OK)
+}
+
+pub async fn delete_materialized_aggregate(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(DeleteMaterializedAggregateArgs {
+        component_id,
+        table_name,
+        name,
+    }): Json<DeleteMaterializedAggregateArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let table_name = table_name.parse::<ValidIdentifier<TableName>>()?.0;
+    let component = ComponentId::deserialize_from_string(component_id.as_deref())?;
+    st.application
+        .delete_materialized_aggregate(identity, component, table_name, name)
+        .await?;
+    Ok(StatusCode::OK)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum_extra::headers::authorization::Credentials;
+    use http::{
+        Request,
+        StatusCode,
+    };
+    use runtime::prod::ProdRuntime;
+    use serde_json::{
+        json,
+        Value as JsonValue,
+    };
+
+    use crate::test_helpers::setup_backend_for_test;
+
+    #[convex_macro::prod_rt_test]
+    async fn test_create_materialized_aggregate_requires_table(
+        rt: ProdRuntime,
+    ) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        let req = Request::builder()
+            .uri("/api/materialized_aggregates/create")
+            .method("POST")
+            .header("Authorization", backend.admin_auth_header.0.encode())
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_vec(&json!({
+                "tableName": "scores",
+                "name": "by_team",
+                "keyField": "team",
+            }))?))?;
+        backend
+            .expect_error(req, StatusCode::NOT_FOUND, "TableNotFound")
+            .await?;
+
+        let req = Request::builder()
+            .uri("/api/materialized_aggregates")
+            .method("GET")
+            .header("Authorization", backend.admin_auth_header.0.encode())
+            .body(Body::empty())?;
+        let aggregates: JsonValue = backend.expect_success(req).await?;
+        assert_eq!(aggregates, json!({ "aggregates": [] }));
+        Ok(())
+    }
+}
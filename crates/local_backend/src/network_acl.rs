@@ -0,0 +1,291 @@
+//! Enforces the deployment's network ACL on public endpoints, rejecting
+//! requests from denied addresses or blocked countries with a 403 before
+//! they're authenticated or rate limited. Rejections are recorded in the
+//! deployment audit log.
+//!
+//! The admin API isn't covered, so a deployment can always fix an ACL that
+//! locks out its own clients.
+use std::{
+    collections::BTreeSet,
+    net::{
+        IpAddr,
+        SocketAddr,
+    },
+    sync::Arc,
+};
+
+use application::Application;
+use axum::{
+    extract::{
+        ConnectInfo,
+        OriginalUri,
+        Request,
+        State,
+    },
+    middleware::Next,
+    response::{
+        IntoResponse,
+        Response,
+    },
+};
+use common::{
+    errors::report_error,
+    http::HttpResponseError,
+    knobs::{
+        NETWORK_ACL_AUDIT_LOG_INTERVAL,
+        NETWORK_ACL_CONFIG_REFRESH_INTERVAL,
+        NETWORK_ACL_MAX_AUDIT_LOGS_PER_INTERVAL,
+    },
+    runtime::Runtime,
+};
+use errors::ErrorMetadata;
+use http::{
+    HeaderMap,
+    HeaderName,
+};
+use keybroker::Identity;
+use model::{
+    deployment_audit_log::types::DeploymentAuditLogEvent,
+    network_acl::types::{
+        NetworkAclConfig,
+        NetworkAclRejection,
+    },
+};
+use parking_lot::Mutex;
+
+use crate::rate_limits::client_ip;
+
+/// Looks up which country a request came from, for blocking countries.
+pub trait GeoIpProvider: Send + Sync {
+    /// The ISO 3166-1 alpha-2 code, like `"US"`, of the country `ip` is in,
+    /// or `None` if it isn't known.
+    fn country(&self, ip: IpAddr, headers: &HeaderMap) -> Option<String>;
+}
+
+/// Trusts a country header set by a CDN or load balancer in front of the
+/// backend, like Cloudflare's `CF-IPCountry`. Only use this if clients can't
+/// reach the backend without going through it, since they can set the header
+/// to anything.
+pub struct HeaderGeoIp {
+    header: HeaderName,
+}
+
+impl HeaderGeoIp {
+    pub fn new(header: HeaderName) -> Self {
+        Self { header }
+    }
+}
+
+impl GeoIpProvider for HeaderGeoIp {
+    fn country(&self, _ip: IpAddr, headers: &HeaderMap) -> Option<String> {
+        let country = headers.get(&self.header)?.to_str().ok()?.trim();
+        (country.len() == 2 && country.chars().all(|c| c.is_ascii_alphabetic()))
+            .then(|| country.to_ascii_uppercase())
+    }
+}
+
+/// Limits how many rejections are written to the audit log, so a flood of
+/// rejected requests doesn't turn into a flood of writes.
+#[derive(Default)]
+struct AuditLogThrottle {
+    interval_start: Option<tokio::time::Instant>,
+    logged: BTreeSet<IpAddr>,
+}
+
+impl AuditLogThrottle {
+    fn should_log(&mut self, ip: IpAddr, now: tokio::time::Instant) -> bool {
+        if self.interval_start.map_or(true, |start| {
+            now.duration_since(start) >= *NETWORK_ACL_AUDIT_LOG_INTERVAL
+        }) {
+            self.interval_start = Some(now);
+            self.logged.clear();
+        }
+        if self.logged.len() >= *NETWORK_ACL_MAX_AUDIT_LOGS_PER_INTERVAL {
+            return false;
+        }
+        self.logged.insert(ip)
+    }
+}
+
+/// The deployment's current network ACL, reloaded every
+/// [`NETWORK_ACL_CONFIG_REFRESH_INTERVAL`].
+pub struct NetworkAcls<RT: Runtime> {
+    application: Application<RT>,
+    geo_ip: Option<Arc<dyn GeoIpProvider>>,
+    loaded: Mutex<Option<(tokio::time::Instant, Option<Arc<NetworkAclConfig>>)>>,
+    audit_log_throttle: Mutex<AuditLogThrottle>,
+}
+
+impl<RT: Runtime> NetworkAcls<RT> {
+    pub fn new(application: Application<RT>, geo_ip: Option<Arc<dyn GeoIpProvider>>) -> Self {
+        Self {
+            application,
+            geo_ip,
+            loaded: Mutex::new(None),
+            audit_log_throttle: Mutex::new(AuditLogThrottle::default()),
+        }
+    }
+
+    async fn config(&self) -> Option<Arc<NetworkAclConfig>> {
+        let now = self.application.runtime().monotonic_now();
+        if let Some((loaded_at, config)) = &*self.loaded.lock()
+            && now.duration_since(*loaded_at) < *NETWORK_ACL_CONFIG_REFRESH_INTERVAL
+        {
+            return config.clone();
+        }
+        match self
+            .application
+            .get_network_acl_config(Identity::system())
+            .await
+        {
+            Ok(config) => {
+                let config = config.map(Arc::new);
+                *self.loaded.lock() = Some((now, config.clone()));
+                config
+            },
+            Err(e) => {
+                // Keep applying the last ACL we loaded rather than letting
+                // every request through.
+                tracing::error!("Failed to load network ACL: {e:#}");
+                self.loaded
+                    .lock()
+                    .as_ref()
+                    .and_then(|(_, config)| config.clone())
+            },
+        }
+    }
+
+    fn log_rejection(
+        &self,
+        ip: IpAddr,
+        country: Option<String>,
+        reason: NetworkAclRejection,
+        path: &str,
+    ) {
+        let runtime = self.application.runtime();
+        if !self
+            .audit_log_throttle
+            .lock()
+            .should_log(ip, runtime.monotonic_now())
+        {
+            return;
+        }
+        let event = DeploymentAuditLogEvent::RejectNetworkRequest {
+            ip: ip.to_string(),
+            country,
+            reason,
+            path: path.to_string(),
+        };
+        let application = self.application.clone();
+        runtime.spawn("network_acl_audit_log", async move {
+            if let Err(mut e) = application.log_network_acl_rejections(vec![event]).await {
+                report_error(&mut e);
+            }
+        });
+    }
+}
+
+pub async fn network_acl_middleware<RT: Runtime>(
+    State(acls): State<Arc<NetworkAcls<RT>>>,
+    remote_addr: Option<ConnectInfo<SocketAddr>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    // We don't know where requests came from in tests.
+    if let Some(config) = acls.config().await
+        && let Some(ip) = client_ip(
+            remote_addr.map(|connect_info| connect_info.0),
+            req.headers(),
+        )
+    {
+        let country = acls
+            .geo_ip
+            .as_ref()
+            .and_then(|geo_ip| geo_ip.country(ip, req.headers()));
+        if let Err(reason) = config.check(ip, country.as_deref()) {
+            let path = req
+                .extensions()
+                .get::<OriginalUri>()
+                .map_or(req.uri().path(), |uri| uri.path());
+            acls.log_rejection(ip, country, reason, path);
+            return network_acl_rejected_response();
+        }
+    }
+    next.run(req).await
+}
+
+fn network_acl_rejected_response() -> Response {
+    HttpResponseError::from(anyhow::anyhow!(ErrorMetadata::forbidden(
+        "NetworkAclRejected",
+        "Requests from this network aren't allowed to reach this deployment",
+    )))
+    .into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{
+            IpAddr,
+            Ipv4Addr,
+        },
+        time::Duration,
+    };
+
+    use common::knobs::{
+        NETWORK_ACL_AUDIT_LOG_INTERVAL,
+        NETWORK_ACL_MAX_AUDIT_LOGS_PER_INTERVAL,
+    };
+    use http::{
+        HeaderMap,
+        HeaderName,
+        HeaderValue,
+        StatusCode,
+    };
+
+    use super::{
+        network_acl_rejected_response,
+        AuditLogThrottle,
+        GeoIpProvider,
+        HeaderGeoIp,
+    };
+
+    fn ip(n: u32) -> IpAddr {
+        IpAddr::V4(Ipv4Addr::from(n))
+    }
+
+    #[test]
+    fn test_header_geo_ip() {
+        let geo_ip = HeaderGeoIp::new(HeaderName::from_static("cf-ipcountry"));
+        let mut headers = HeaderMap::new();
+        assert_eq!(geo_ip.country(ip(1), &headers), None);
+        headers.insert("cf-ipcountry", HeaderValue::from_static("de"));
+        assert_eq!(geo_ip.country(ip(1), &headers), Some("DE".to_string()));
+        headers.insert("cf-ipcountry", HeaderValue::from_static("unknown"));
+        assert_eq!(geo_ip.country(ip(1), &headers), None);
+    }
+
+    #[test]
+    fn test_audit_log_throttle() {
+        let mut throttle = AuditLogThrottle::default();
+        let now = tokio::time::Instant::now();
+        assert!(throttle.should_log(ip(1), now));
+        assert!(!throttle.should_log(ip(1), now + Duration::from_secs(1)));
+        for n in 2..=*NETWORK_ACL_MAX_AUDIT_LOGS_PER_INTERVAL as u32 {
+            assert!(throttle.should_log(ip(n), now));
+        }
+        assert!(!throttle.should_log(ip(u32::MAX), now));
+
+        let later = now + *NETWORK_ACL_AUDIT_LOG_INTERVAL;
+        assert!(throttle.should_log(ip(1), later));
+        assert!(throttle.should_log(ip(u32::MAX), later));
+    }
+
+    #[test]
+    fn test_network_acl_rejected_response() {
+        assert_eq!(
+            network_acl_rejected_response().status(),
+            StatusCode::FORBIDDEN
+        );
+    }
+}
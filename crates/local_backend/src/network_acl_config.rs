@@ -0,0 +1,91 @@
+use axum::{
+    extract::State,
+    response::IntoResponse,
+};
+use common::http::{
+    extract::Json,
+    HttpResponseError,
+};
+use http::StatusCode;
+use model::network_acl::types::NetworkAclConfig;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    admin::{
+        must_be_admin,
+        must_be_admin_with_write_access,
+    },
+    authentication::ExtractIdentity,
+    LocalAppState,
+};
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NetworkAclConfigJson {
+    /// CIDR ranges like `"10.0.0.0/8"`. If nonempty, only these are allowed.
+    #[serde(default)]
+    allow: Vec<String>,
+    /// CIDR ranges to reject, even if they're also allowed.
+    #[serde(default)]
+    deny: Vec<String>,
+    /// Two letter ISO 3166-1 country codes, like `"US"`.
+    #[serde(default)]
+    blocked_countries: Vec<String>,
+}
+
+impl From<NetworkAclConfig> for NetworkAclConfigJson {
+    fn from(config: NetworkAclConfig) -> Self {
+        Self {
+            allow: config.allow.iter().map(ToString::to_string).collect(),
+            deny: config.deny.iter().map(ToString::to_string).collect(),
+            blocked_countries: config.blocked_countries.into_iter().collect(),
+        }
+    }
+}
+
+/// Returns the IP and country restrictions for public endpoints, or `null` if
+/// there aren't any.
+pub async fn get_network_acl_config(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let config = st
+        .application
+        .get_network_acl_config(identity)
+        .await?
+        .map(NetworkAclConfigJson::from);
+    Ok(Json(config))
+}
+
+/// Replaces the IP and country restrictions for public endpoints. A `null`
+/// body removes them.
+pub async fn set_network_acl_config(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(args): Json<Option<NetworkAclConfigJson>>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let config = args
+        .map(|args| {
+            let parse_ranges = |ranges: Vec<String>| {
+                ranges
+                    .iter()
+                    .map(|range| range.parse())
+                    .collect::<anyhow::Result<_>>()
+            };
+            NetworkAclConfig::new(
+                parse_ranges(args.allow)?,
+                parse_ranges(args.deny)?,
+                args.blocked_countries,
+            )
+        })
+        .transpose()?;
+    st.application
+        .set_network_acl_config(identity, config)
+        .await?;
+    Ok(StatusCode::OK)
+}
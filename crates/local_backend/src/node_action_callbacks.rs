@@ -43,7 +43,13 @@ use common::{
     },
     RequestId,
 };
+use database::{
+    HybridSearch,
+    HybridSearchJson,
+    HybridSearchRequest,
+};
 use errors::ErrorMetadata;
+use file_storage::UploadUrlOptions;
 use http::HeaderMap;
 use isolate::{
     ActionCallbacks,
@@ -51,6 +57,7 @@ use isolate::{
 };
 use keybroker::Identity;
 use minitrace::future::FutureExt;
+use model::scheduled_jobs::types::IdempotencyKey;
 use serde::{
     Deserialize,
     Serialize,
@@ -63,7 +70,10 @@ use sync_types::{
     AuthenticationToken,
     CanonicalizedUdfPath,
 };
-use usage_tracking::FunctionUsageTracker;
+use usage_tracking::{
+    FunctionUsageStats,
+    FunctionUsageTracker,
+};
 use value::{
     export::ValueFormat,
     id_v6::DeveloperDocumentId,
@@ -137,7 +147,7 @@ pub async fn internal_query_post(
     let value_format = Some(ValueFormat::ConvexEncodedJSON);
     let response = match udf_return.result {
         Ok(value) => UdfResponse::Success {
-            value: export_value(value, value_format, client_version)?,
+            value: export_value(value.unpack(), value_format, client_version)?,
             log_lines: udf_return.log_lines,
         },
         Err(error) => {
@@ -271,6 +281,8 @@ pub struct ScheduleJobRequest {
     udf_path: Option<String>,
     udf_args: UdfArgsJson,
     scheduled_ts: f64,
+    idempotency_key: Option<String>,
+    on_conflict: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -305,6 +317,10 @@ pub async fn schedule_job(
             anyhow::anyhow!(ErrorMetadata::bad_request("InvalidUdfPath", e.to_string()))
         })?;
     let udf_args = req.udf_args.into_arg_vec();
+    let idempotency_key = req
+        .idempotency_key
+        .map(|key| IdempotencyKey::new(key, req.on_conflict.as_deref()))
+        .transpose()?;
     let job_id = st
         .application
         .runner()
@@ -315,6 +331,7 @@ pub async fn schedule_job(
             udf_args,
             scheduled_ts,
             context,
+            idempotency_key,
         )
         .await?;
     Ok(Json(ScheduleJobResponse {
@@ -413,12 +430,69 @@ pub async fn vector_search(
         .application
         .vector_search(identity.clone(), query)
         .await?;
+    track_search_usage(
+        &st,
+        identity,
+        component_id,
+        action_name,
+        context,
+        usage_stats,
+    )
+    .await?;
 
-    // This is a workaround. The correct way to track usage is to return in the
-    // response, and then Node.js should aggregate it and then send it back to
-    // the backend alongside the action result, which is how Funrun actions
-    // work. Since we don't have that pipeline working in Node.js/Typescript, we
-    // report vector usage directly here.
+    let results: Vec<_> = results.into_iter().map(JsonValue::from).collect();
+    Ok(Json(json!({ "results": results })))
+}
+
+#[debug_handler]
+pub async fn hybrid_search(
+    State(st): State<LocalAppState>,
+    ExtractActionIdentity {
+        identity,
+        component_id,
+    }: ExtractActionIdentity,
+    ExtractActionName(action_name): ExtractActionName,
+    ExtractExecutionContext(context): ExtractExecutionContext,
+    Json(req): Json<HybridSearchRequest>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    let HybridSearchRequest { query } = req;
+    let mut query: HybridSearchJson = serde_json::from_value(query)?;
+    query.insert_component_id(component_id);
+    let query = HybridSearch::try_from(serde_json::to_value(query)?).map_err(|e| {
+        let message = e.to_string();
+        e.context(ErrorMetadata::bad_request("InvalidHybridSearch", message))
+    })?;
+    let (results, usage_stats) = st
+        .application
+        .hybrid_search(identity.clone(), query)
+        .await?;
+    track_search_usage(
+        &st,
+        identity,
+        component_id,
+        action_name,
+        context,
+        usage_stats,
+    )
+    .await?;
+
+    let results: Vec<_> = results.into_iter().map(JsonValue::from).collect();
+    Ok(Json(json!({ "results": results })))
+}
+
+// This is a workaround. The correct way to track usage is to return in the
+// response, and then Node.js should aggregate it and then send it back to
+// the backend alongside the action result, which is how Funrun actions
+// work. Since we don't have that pipeline working in Node.js/Typescript, we
+// report search usage directly here.
+async fn track_search_usage(
+    st: &LocalAppState,
+    identity: Identity,
+    component_id: ComponentId,
+    action_name: Option<String>,
+    context: ExecutionContext,
+    usage_stats: FunctionUsageStats,
+) -> anyhow::Result<()> {
     if let Some(action_name) = action_name {
         let usage = FunctionUsageTracker::new();
         usage.add(usage_stats);
@@ -443,9 +517,7 @@ pub async fn vector_search(
             usage.gather_user_stats(),
         );
     }
-
-    let results: Vec<_> = results.into_iter().map(JsonValue::from).collect();
-    Ok(Json(json!({ "results": results })))
+    Ok(())
 }
 
 #[debug_handler]
@@ -455,10 +527,11 @@ pub async fn storage_generate_upload_url(
         identity: _,
         component_id,
     }: ExtractActionIdentity,
+    Json(options): Json<UploadUrlOptions>,
 ) -> Result<impl IntoResponse, HttpResponseError> {
     let url = st
         .application
-        .storage_generate_upload_url(component_id)
+        .storage_generate_upload_url(component_id, options.try_into()?)
         .await?;
     Ok(Json(json!({ "url": url })))
 }
@@ -0,0 +1,207 @@
+//! Polling and streaming endpoints for long-running admin operations (imports,
+//! exports, index builds and table compactions). The per-feature status
+//! endpoints still work, but these report every kind of operation in the same
+//! shape.
+use anyhow::Context;
+use application::Application;
+use axum::{
+    body::Body,
+    extract::State,
+    response::IntoResponse,
+};
+use common::http::{
+    extract::{
+        Json,
+        Path,
+        Query,
+    },
+    HttpResponseError,
+};
+use errors::ErrorMetadata;
+use futures_async_stream::try_stream;
+use http::{
+    header::CONTENT_TYPE,
+    StatusCode,
+};
+use keybroker::Identity;
+use model::operations::types::{
+    Operation,
+    OperationKind,
+};
+use runtime::prod::ProdRuntime;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use value::DeveloperDocumentId;
+
+use crate::{
+    admin::{
+        must_be_admin,
+        must_be_admin_with_write_access,
+    },
+    authentication::ExtractIdentity,
+    LocalAppState,
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedOperationProgress {
+    completed: u64,
+    total: Option<u64>,
+    message: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedOperation {
+    id: String,
+    kind: String,
+    creation_time: Option<f64>,
+    state: String,
+    progress: SerializedOperationProgress,
+    errors: Vec<String>,
+    cancelable: bool,
+}
+
+impl From<Operation> for SerializedOperation {
+    fn from(operation: Operation) -> Self {
+        Self {
+            id: operation.id.to_string(),
+            kind: operation.kind.to_string(),
+            creation_time: operation.creation_time.map(f64::from),
+            state: operation.state.to_string(),
+            progress: SerializedOperationProgress {
+                completed: operation.progress.completed,
+                total: operation.progress.total,
+                message: operation.progress.message,
+            },
+            errors: operation.errors,
+            cancelable: operation.cancelable,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct ListOperationsArgs {
+    /// Only list operations of this kind, e.g. `snapshotImport`.
+    kind: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct ListOperationsResponse {
+    operations: Vec<SerializedOperation>,
+}
+
+#[derive(Deserialize)]
+pub struct OperationPath {
+    id: String,
+}
+
+fn parse_operation_id(id: &str) -> anyhow::Result<DeveloperDocumentId> {
+    DeveloperDocumentId::decode(id).context(ErrorMetadata::bad_request(
+        "InvalidOperationId",
+        format!("Invalid operation id {id}"),
+    ))
+}
+
+fn operation_not_found(id: DeveloperDocumentId) -> ErrorMetadata {
+    ErrorMetadata::not_found("OperationNotFound", format!("Operation {id} not found"))
+}
+
+/// Lists imports, exports, table compactions and in-flight index builds,
+/// newest first.
+pub async fn list_operations(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Query(ListOperationsArgs { kind }): Query<ListOperationsArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let kind = kind
+        .map(|kind| {
+            kind.parse::<OperationKind>()
+                .context(ErrorMetadata::bad_request(
+                    "InvalidOperationKind",
+                    format!("Invalid operation kind {kind}"),
+                ))
+        })
+        .transpose()?;
+    let operations = st
+        .application
+        .list_operations(identity, kind)
+        .await?
+        .into_iter()
+        .map(SerializedOperation::from)
+        .collect();
+    Ok(Json(ListOperationsResponse { operations }))
+}
+
+pub async fn get_operation(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Path(OperationPath { id }): Path<OperationPath>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let id = parse_operation_id(&id)?;
+    let operation = st
+        .application
+        .get_operation(identity, id)
+        .await?
+        .with_context(|| operation_not_found(id))?;
+    Ok(Json(SerializedOperation::from(operation)))
+}
+
+/// Streams the operation as newline-delimited JSON, with a line each time it
+/// changes, until it succeeds, fails or is canceled.
+pub async fn stream_operation(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Path(OperationPath { id }): Path<OperationPath>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let id = parse_operation_id(&id)?;
+    // Read the operation before responding so that a missing operation fails
+    // the request instead of the stream.
+    let operation = st
+        .application
+        .get_operation(identity.clone(), id)
+        .await?
+        .with_context(|| operation_not_found(id))?;
+    Ok((
+        [(CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(operation_stream(st.application, identity, operation)),
+    ))
+}
+
+#[try_stream(ok = Vec<u8>, error = anyhow::Error, boxed)]
+async fn operation_stream(
+    application: Application<ProdRuntime>,
+    identity: Identity,
+    operation: Operation,
+) {
+    let mut operation = operation;
+    loop {
+        let mut line = serde_json::to_vec(&SerializedOperation::from(operation.clone()))?;
+        line.push(b'\n');
+        yield line;
+        if operation.state.is_terminal() {
+            break;
+        }
+        let id = operation.id;
+        operation = application
+            .wait_for_operation_change(identity.clone(), id, Some(&operation))
+            .await?
+            .with_context(|| operation_not_found(id))?;
+    }
+}
+
+pub async fn cancel_operation(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Path(OperationPath { id }): Path<OperationPath>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let id = parse_operation_id(&id)?;
+    st.application.cancel_operation(identity, id).await?;
+    Ok(StatusCode::OK)
+}
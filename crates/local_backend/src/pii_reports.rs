@@ -0,0 +1,75 @@
+use axum::{
+    extract::State,
+    response::IntoResponse,
+};
+use common::http::{
+    extract::Json,
+    HttpResponseError,
+};
+use model::pii_reports::types::PiiReport;
+use serde::Serialize;
+
+use crate::{
+    admin::must_be_admin,
+    authentication::ExtractIdentity,
+    LocalAppState,
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PiiReportJson {
+    table_name: String,
+    scanned_at_ms: u64,
+    documents_sampled: u64,
+    fields: Vec<PiiFieldJson>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PiiFieldJson {
+    field_path: String,
+    /// `"email"`, `"phone"` or `"creditCard"`.
+    kind: String,
+    matches: u64,
+    documents_with_field: u64,
+}
+
+impl TryFrom<PiiReport> for PiiReportJson {
+    type Error = anyhow::Error;
+
+    fn try_from(report: PiiReport) -> anyhow::Result<Self> {
+        Ok(Self {
+            table_name: report.table_name.to_string(),
+            scanned_at_ms: report.scanned_at.as_ms_since_epoch()?,
+            documents_sampled: report.documents_sampled,
+            fields: report
+                .fields
+                .into_iter()
+                .map(|field| PiiFieldJson {
+                    field_path: field.field_path,
+                    kind: field.kind.to_string(),
+                    matches: field.matches,
+                    documents_with_field: field.documents_with_field,
+                })
+                .collect(),
+        })
+    }
+}
+
+/// Lists the fields in each table that the latest PII scan found emails,
+/// phone numbers or credit card numbers in. Scans only run on deployments
+/// with `PII_SCAN_ENABLED` set.
+pub async fn get_pii_reports(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let reports = st
+        .application
+        .pii_reports(identity)
+        .await?
+        .into_iter()
+        .map(PiiReportJson::try_from)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(Json(reports))
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use application::{
     api::ExecuteQueryTimestamp,
     redaction::{
@@ -10,10 +12,12 @@ use axum::{
     response::IntoResponse,
 };
 use common::{
+    backoff::Backoff,
     components::{
         CanonicalizedComponentFunctionPath,
         ComponentPath,
     },
+    execution_context::RequestId,
     http::{
         extract::{
             Json,
@@ -24,7 +28,10 @@ use common::{
         ExtractRequestId,
         ExtractResolvedHostname,
         HttpResponseError,
+        ResolvedHostname,
     },
+    knobs::HTTP_QUERY_MIN_TS_MAX_WAIT,
+    runtime::Runtime,
     types::FunctionCaller,
     version::ClientVersion,
 };
@@ -51,6 +58,9 @@ use crate::{
     RouterState,
 };
 
+const INITIAL_MIN_TS_BACKOFF: Duration = Duration::from_millis(5);
+const MAX_MIN_TS_BACKOFF: Duration = Duration::from_millis(100);
+
 #[derive(Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct UdfPostRequest {
@@ -58,6 +68,9 @@ pub struct UdfPostRequest {
     pub args: UdfArgsJson,
 
     pub format: Option<String>,
+    /// Only used by queries. See `read_timestamp`.
+    #[serde(default)]
+    pub min_ts: Option<SerializedTs>,
 }
 
 #[derive(Serialize)]
@@ -76,7 +89,7 @@ pub struct UdfPostWithTsRequest {
     pub format: Option<String>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug)]
 pub struct SerializedTs(String);
 
 impl From<Timestamp> for SerializedTs {
@@ -100,11 +113,15 @@ impl TryFrom<SerializedTs> for Timestamp {
 }
 
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct UdfArgsQuery {
     pub path: String,
     pub args: UdfArgsJson,
 
     pub format: Option<String>,
+    /// See `read_timestamp`.
+    #[serde(default)]
+    pub min_ts: Option<SerializedTs>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -325,6 +342,44 @@ pub fn export_value(
     Ok(value.export(format))
 }
 
+/// Picks the timestamp a query reads at. `min_ts` is a token from an earlier
+/// response, like a mutation's commit timestamp, and guarantees the query
+/// observes everything up to it. This gives stateless HTTP clients
+/// read-your-writes, waiting up to `HTTP_QUERY_MIN_TS_MAX_WAIT` for this
+/// backend to catch up.
+async fn read_timestamp(
+    st: &RouterState,
+    host: &ResolvedHostname,
+    request_id: RequestId,
+    min_ts: Option<SerializedTs>,
+) -> anyhow::Result<ExecuteQueryTimestamp> {
+    let Some(min_ts) = min_ts else {
+        return Ok(ExecuteQueryTimestamp::Latest);
+    };
+    let min_ts = Timestamp::try_from(min_ts).map_err(|e| {
+        anyhow::anyhow!(ErrorMetadata::bad_request(
+            "InvalidMinTs",
+            format!("Invalid minTs: {e}"),
+        ))
+    })?;
+    let deadline = st.runtime.monotonic_now() + *HTTP_QUERY_MIN_TS_MAX_WAIT;
+    let mut backoff = Backoff::new(INITIAL_MIN_TS_BACKOFF, MAX_MIN_TS_BACKOFF);
+    loop {
+        let ts = *st.api.latest_timestamp(host, request_id.clone()).await?;
+        if ts >= min_ts {
+            return Ok(ExecuteQueryTimestamp::At(ts));
+        }
+        if st.runtime.monotonic_now() >= deadline {
+            anyhow::bail!(ErrorMetadata::overloaded(
+                "MinTsNotReached",
+                format!("Timed out waiting for the deployment to reach minTs {min_ts}"),
+            ));
+        }
+        let delay = backoff.fail(&mut st.runtime.rng());
+        st.runtime.wait(delay).await;
+    }
+}
+
 #[minitrace::trace(properties = { "udf_type": "query"})]
 pub async fn public_query_get(
     State(st): State<RouterState>,
@@ -345,6 +400,7 @@ pub async fn public_query_get(
         .api
         .authenticate(&host, request_id.clone(), auth_token)
         .await?;
+    let ts = read_timestamp(&st, &host, request_id.clone(), req.min_ts).await?;
     let query_result = st
         .api
         .execute_public_query(
@@ -354,7 +410,7 @@ pub async fn public_query_get(
             export_path,
             args,
             FunctionCaller::HttpApi(client_version.clone()),
-            ExecuteQueryTimestamp::Latest,
+            ts,
             journal,
         )
         .await?;
@@ -362,7 +418,7 @@ pub async fn public_query_get(
     let log_lines = query_result.log_lines;
     let response = match query_result.result {
         Ok(value) => UdfResponse::Success {
-            value: export_value(value, value_format, client_version)?,
+            value: export_value(value.unpack(), value_format, client_version)?,
             log_lines,
         },
         Err(error) => UdfResponse::error(error, log_lines, value_format, client_version)?,
@@ -389,6 +445,7 @@ pub async fn public_query_post(
         .api
         .authenticate(&host, request_id.clone(), auth_token)
         .await?;
+    let ts = read_timestamp(&st, &host, request_id.clone(), req.min_ts).await?;
     let query_return = st
         .api
         .execute_public_query(
@@ -398,14 +455,14 @@ pub async fn public_query_post(
             udf_path,
             req.args.into_arg_vec(),
             FunctionCaller::HttpApi(client_version.clone()),
-            ExecuteQueryTimestamp::Latest,
+            ts,
             journal,
         )
         .await?;
     let value_format = req.format.as_ref().map(|f| f.parse()).transpose()?;
     let response = match query_return.result {
         Ok(value) => UdfResponse::Success {
-            value: export_value(value, value_format, client_version)?,
+            value: export_value(value.unpack(), value_format, client_version)?,
             log_lines: query_return.log_lines,
         },
         Err(error) => {
@@ -460,7 +517,7 @@ pub async fn public_query_at_ts_post(
     let value_format = req.format.as_ref().map(|f| f.parse()).transpose()?;
     let response = match query_return.result {
         Ok(value) => UdfResponse::Success {
-            value: export_value(value, value_format, client_version)?,
+            value: export_value(value.unpack(), value_format, client_version)?,
             log_lines: query_return.log_lines,
         },
         Err(error) => {
@@ -471,8 +528,12 @@ pub async fn public_query_at_ts_post(
 }
 
 #[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct QueryBatchArgs {
     queries: Vec<UdfPostRequest>,
+    /// See `read_timestamp`.
+    #[serde(default)]
+    min_ts: Option<SerializedTs>,
 }
 
 #[derive(Serialize)]
@@ -490,7 +551,12 @@ pub async fn public_query_batch_post(
 ) -> Result<impl IntoResponse, HttpResponseError> {
     let mut results = vec![];
     // All queries execute at the same timestamp.
-    let ts = st.api.latest_timestamp(&host, request_id.clone()).await?;
+    let ts = match read_timestamp(&st, &host, request_id.clone(), req_batch.min_ts).await? {
+        ExecuteQueryTimestamp::At(ts) => ts,
+        ExecuteQueryTimestamp::Latest => {
+            *st.api.latest_timestamp(&host, request_id.clone()).await?
+        },
+    };
     let identity = st
         .api
         .authenticate(&host, request_id.clone(), auth_token)
@@ -507,13 +573,13 @@ pub async fn public_query_batch_post(
                 export_path,
                 req.args.into_arg_vec(),
                 FunctionCaller::HttpApi(client_version.clone()),
-                ExecuteQueryTimestamp::At(*ts),
+                ExecuteQueryTimestamp::At(ts),
                 None,
             )
             .await?;
         let response = match udf_return.result {
             Ok(value) => UdfResponse::Success {
-                value: export_value(value, value_format, client_version.clone())?,
+                value: export_value(value.unpack(), value_format, client_version.clone())?,
                 log_lines: udf_return.log_lines,
             },
             Err(error) => UdfResponse::error(
@@ -528,6 +594,16 @@ pub async fn public_query_batch_post(
     Ok(Json(QueryBatchResponse { results }))
 }
 
+#[derive(Serialize)]
+pub struct MutationResponse {
+    #[serde(flatten)]
+    response: UdfResponse,
+    /// Commit timestamp of a successful mutation, which can be passed as
+    /// `minTs` to later queries to read this mutation's writes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ts: Option<SerializedTs>,
+}
+
 #[minitrace::trace(properties = { "udf_type": "mutation"})]
 pub async fn public_mutation_post(
     State(st): State<RouterState>,
@@ -560,16 +636,131 @@ pub async fn public_mutation_post(
         .await?;
     let value_format = req.format.as_ref().map(|f| f.parse()).transpose()?;
     let response = match udf_result {
-        Ok(write_return) => UdfResponse::Success {
-            value: export_value(write_return.value, value_format, client_version)?,
-            log_lines: write_return.log_lines,
+        Ok(write_return) => MutationResponse {
+            response: UdfResponse::Success {
+                value: export_value(write_return.value, value_format, client_version)?,
+                log_lines: write_return.log_lines,
+            },
+            ts: Some(write_return.ts.into()),
+        },
+        Err(write_error) => MutationResponse {
+            response: UdfResponse::error(
+                write_error.error,
+                write_error.log_lines,
+                value_format,
+                client_version,
+            )?,
+            ts: None,
+        },
+    };
+    Ok(Json(response))
+}
+
+#[derive(Deserialize)]
+pub struct MutationBatchArgs {
+    mutations: Vec<UdfPostRequest>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "status")]
+#[serde(rename_all = "camelCase")]
+pub enum MutationBatchResponse {
+    #[serde(rename_all = "camelCase")]
+    Success {
+        results: Vec<UdfResponse>,
+        ts: SerializedTs,
+    },
+    /// None of the batch's mutations were committed.
+    #[serde(rename_all = "camelCase")]
+    Error {
+        /// The mutation that failed, or `None` if the batch failed to commit.
+        #[serde(skip_serializing_if = "Option::is_none")]
+        failed_index: Option<usize>,
+        error_message: String,
+
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error_data: Option<JsonValue>,
+
+        #[serde(skip_serializing_if = "RedactedLogLines::is_empty")]
+        log_lines: RedactedLogLines,
+    },
+}
+
+/// Runs mutations in order in a single transaction, so either all of them
+/// commit or none do.
+#[minitrace::trace(properties = { "udf_type": "mutation"})]
+pub async fn public_mutation_batch_post(
+    State(st): State<RouterState>,
+    ExtractResolvedHostname(host): ExtractResolvedHostname,
+    ExtractRequestId(request_id): ExtractRequestId,
+    ExtractAuthenticationToken(auth_token): ExtractAuthenticationToken,
+    ExtractClientVersion(client_version): ExtractClientVersion,
+    Json(req_batch): Json<MutationBatchArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    let mut mutations = vec![];
+    let mut value_formats = vec![];
+    for req in req_batch.mutations {
+        value_formats.push(req.format.as_ref().map(|f| f.parse()).transpose()?);
+        mutations.push((parse_export_path(&req.path)?, req.args.into_arg_vec()));
+    }
+    let identity = st
+        .api
+        .authenticate(&host, request_id.clone(), auth_token)
+        .await?;
+    let batch_result = st
+        .api
+        .execute_public_mutation_batch(
+            &host,
+            request_id,
+            identity,
+            mutations,
+            FunctionCaller::HttpApi(client_version.clone()),
+        )
+        .await?;
+    let response = match batch_result {
+        Ok(batch_return) => MutationBatchResponse::Success {
+            results: batch_return
+                .results
+                .into_iter()
+                .zip(value_formats)
+                .map(|(write_return, value_format)| {
+                    Ok(UdfResponse::Success {
+                        value: export_value(
+                            write_return.value,
+                            value_format,
+                            client_version.clone(),
+                        )?,
+                        log_lines: write_return.log_lines,
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?,
+            ts: batch_return.ts.into(),
+        },
+        Err(batch_error) => {
+            let value_format = batch_error
+                .index
+                .and_then(|index| value_formats.get(index).copied())
+                .flatten();
+            let UdfResponse::Error {
+                error_message,
+                error_data,
+                log_lines,
+            } = UdfResponse::error(
+                batch_error.error,
+                batch_error.log_lines,
+                value_format,
+                client_version,
+            )?
+            else {
+                return Err(anyhow::anyhow!("UdfResponse::error returned a success").into());
+            };
+            MutationBatchResponse::Error {
+                failed_index: batch_error.index,
+                error_message,
+                error_data,
+                log_lines,
+            }
         },
-        Err(write_error) => UdfResponse::error(
-            write_error.error,
-            write_error.log_lines,
-            value_format,
-            client_version,
-        )?,
     };
     Ok(Json(response))
 }
@@ -662,7 +853,11 @@ mod tests {
             .body(body)?;
         match expected {
             Ok(expected) => {
-                let result: JsonValue = backend.expect_success(req).await?;
+                let mut result: JsonValue = backend.expect_success(req).await?;
+                if uri == "/api/mutation" {
+                    let ts = result.as_object_mut().unwrap().remove("ts");
+                    assert!(ts.is_some_and(|ts| ts.is_string()));
+                }
                 assert_eq!(
                     result,
                     json!({
@@ -786,4 +981,82 @@ mod tests {
         )
         .await
     }
+
+    #[convex_macro::prod_rt_test]
+    async fn test_http_query_reads_mutation_ts(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        backend.st.application.load_udf_tests_modules().await?;
+        let post = |uri: &str, body: JsonValue| -> anyhow::Result<Request<Body>> {
+            Ok(Request::builder()
+                .uri(uri)
+                .method("POST")
+                .header("Content-Type", "application/json")
+                .header("Host", "localhost")
+                .body(Body::from(serde_json::to_vec(&body)?))?)
+        };
+        let result: JsonValue = backend
+            .expect_success(post(
+                "/api/mutation",
+                json!({"path": "basic:insertObject", "args": {"foo": "bar"}}),
+            )?)
+            .await?;
+        assert_eq!(result["status"], "success");
+        let ts = result["ts"].clone();
+
+        let result: JsonValue = backend
+            .expect_success(post(
+                "/api/query",
+                json!({"path": "basic:count", "args": {}, "minTs": ts}),
+            )?)
+            .await?;
+        assert_eq!(result["value"], json!(1.0));
+
+        let result: JsonValue = backend
+            .expect_success(post(
+                "/api/query_batch",
+                json!({"queries": [{"path": "basic:count", "args": {}}], "minTs": ts}),
+            )?)
+            .await?;
+        assert_eq!(result["results"][0]["value"], json!(1.0));
+
+        backend
+            .expect_error(
+                post(
+                    "/api/query",
+                    json!({"path": "basic:count", "args": {}, "minTs": "invalid"}),
+                )?,
+                StatusCode::BAD_REQUEST,
+                "InvalidMinTs",
+            )
+            .await?;
+        Ok(())
+    }
+
+    #[convex_macro::prod_rt_test]
+    async fn test_http_mutation_batch(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        backend.st.application.load_udf_tests_modules().await?;
+        let json_body = json!({
+            "mutations": [
+                {"path": "values:intMutation", "args": {}},
+                {"path": "values:intMutation", "args": {}, "format": "json"},
+            ],
+        });
+        let req = Request::builder()
+            .uri("/api/mutation_batch")
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .header("Host", "localhost")
+            .body(Body::from(serde_json::to_vec(&json_body)?))?;
+        let result: JsonValue = backend.expect_success(req).await?;
+        assert_eq!(result["status"], "success");
+        assert_eq!(
+            result["results"],
+            json!([
+                {"status": "success", "value": "1"},
+                {"status": "success", "value": "1"},
+            ])
+        );
+        Ok(())
+    }
 }
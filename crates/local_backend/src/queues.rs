@@ -0,0 +1,393 @@
+//! Defines queues, enqueues messages, and inspects and redrives what's
+//! waiting in them.
+use std::time::Duration;
+
+use anyhow::Context;
+use axum::{
+    extract::State,
+    response::IntoResponse,
+};
+use common::{
+    document::ParsedDocument,
+    http::{
+        extract::{
+            Json,
+            Query,
+        },
+        HttpResponseError,
+    },
+};
+use errors::ErrorMetadata;
+use http::StatusCode;
+use model::queues::types::{
+    QueueConfig,
+    QueueConsumer,
+    QueueMessage,
+    QueueMessageState,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::Value as JsonValue;
+use sync_types::CanonicalizedUdfPath;
+use value::DeveloperDocumentId;
+
+use crate::{
+    admin::{
+        must_be_admin,
+        must_be_admin_with_write_access,
+    },
+    authentication::ExtractIdentity,
+    LocalAppState,
+};
+
+const DEFAULT_QUEUE_MESSAGES_LIMIT: usize = 100;
+const MAX_QUEUE_MESSAGES_LIMIT: usize = 1000;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueJson {
+    name: String,
+    /// Every message is delivered to each group.
+    consumers: Vec<QueueConsumerJson>,
+    /// How long to wait after a failed delivery before retrying it. Defaults
+    /// to 30 seconds.
+    visibility_timeout_ms: Option<u64>,
+    /// How many times a message is delivered to a group before it's
+    /// dead-lettered. Defaults to 5.
+    max_deliveries: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueConsumerJson {
+    group: String,
+    /// A mutation or action in the root component, like `"emails:send"`.
+    function: String,
+}
+
+impl From<QueueConfig> for QueueJson {
+    fn from(config: QueueConfig) -> Self {
+        Self {
+            name: config.name,
+            consumers: config
+                .consumers
+                .into_iter()
+                .map(|consumer| QueueConsumerJson {
+                    group: consumer.group,
+                    function: consumer.function.strip().to_string(),
+                })
+                .collect(),
+            visibility_timeout_ms: Some(config.visibility_timeout.as_millis() as u64),
+            max_deliveries: Some(config.max_deliveries),
+        }
+    }
+}
+
+impl TryFrom<QueueJson> for QueueConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(queue: QueueJson) -> anyhow::Result<Self> {
+        let consumers = queue
+            .consumers
+            .into_iter()
+            .map(|consumer| {
+                let function = consumer.function.parse::<CanonicalizedUdfPath>().context(
+                    ErrorMetadata::bad_request(
+                        "InvalidQueue",
+                        format!("{:?} isn't a valid function path.", consumer.function),
+                    ),
+                )?;
+                anyhow::Ok(QueueConsumer {
+                    group: consumer.group,
+                    function,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+        QueueConfig::new(
+            queue.name,
+            consumers,
+            queue.visibility_timeout_ms.map(Duration::from_millis),
+            queue.max_deliveries,
+        )
+    }
+}
+
+pub async fn list_queues(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let queues: Vec<_> = st
+        .application
+        .list_queues(identity)
+        .await?
+        .into_iter()
+        .map(QueueJson::from)
+        .collect();
+    Ok(Json(queues))
+}
+
+/// Creates or replaces the queue with the same name.
+pub async fn set_queue(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(args): Json<QueueJson>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let config = QueueConfig::try_from(args)?;
+    st.application.set_queue(identity, config).await?;
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueNameArgs {
+    name: String,
+}
+
+/// Removes a queue along with every message in it.
+pub async fn delete_queue(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(QueueNameArgs { name }): Json<QueueNameArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    st.application.delete_queue(identity, name).await?;
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnqueueQueueMessageArgs {
+    name: String,
+    /// Messages with the same partition key are delivered one at a time, in
+    /// order.
+    #[serde(default)]
+    partition_key: String,
+    body: JsonValue,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EnqueueQueueMessageResponse {
+    sequence: u64,
+}
+
+pub async fn enqueue_queue_message(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(EnqueueQueueMessageArgs {
+        name,
+        partition_key,
+        body,
+    }): Json<EnqueueQueueMessageArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let sequence = st
+        .application
+        .enqueue_queue_message(identity, name, partition_key, body)
+        .await?;
+    Ok(Json(EnqueueQueueMessageResponse { sequence }))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueMessageJson {
+    id: String,
+    group: String,
+    partition_key: String,
+    sequence: u64,
+    body: JsonValue,
+    /// `"pending"`, `"inFlight"` or `"deadLettered"`.
+    state: String,
+    deliveries: u32,
+    last_error: Option<String>,
+}
+
+impl TryFrom<ParsedDocument<QueueMessage>> for QueueMessageJson {
+    type Error = anyhow::Error;
+
+    fn try_from(message: ParsedDocument<QueueMessage>) -> anyhow::Result<Self> {
+        let (id, message) = message.into_id_and_value();
+        let state = match message.state {
+            QueueMessageState::Pending => "pending",
+            QueueMessageState::InFlight => "inFlight",
+            QueueMessageState::DeadLettered => "deadLettered",
+        };
+        Ok(Self {
+            id: DeveloperDocumentId::from(id).encode(),
+            group: message.group,
+            partition_key: message.partition_key,
+            sequence: message.sequence,
+            body: serde_json::from_str(&message.body)?,
+            state: state.to_string(),
+            deliveries: message.deliveries,
+            last_error: message.last_error,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListQueueMessagesArgs {
+    name: String,
+    /// Only returns messages for this consumer group.
+    group: Option<String>,
+    limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListQueueMessagesResponse {
+    /// Ordered by group, partition key and sequence number.
+    messages: Vec<QueueMessageJson>,
+}
+
+pub async fn list_queue_messages(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Query(ListQueueMessagesArgs { name, group, limit }): Query<ListQueueMessagesArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let limit = limit
+        .unwrap_or(DEFAULT_QUEUE_MESSAGES_LIMIT)
+        .clamp(1, MAX_QUEUE_MESSAGES_LIMIT);
+    let messages = st
+        .application
+        .list_queue_messages(identity, name, group, limit)
+        .await?
+        .into_iter()
+        .map(QueueMessageJson::try_from)
+        .collect::<anyhow::Result<_>>()?;
+    Ok(Json(ListQueueMessagesResponse { messages }))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PurgeQueueResponse {
+    purged: usize,
+}
+
+/// Deletes every message in a queue without delivering it.
+pub async fn purge_queue(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(QueueNameArgs { name }): Json<QueueNameArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let purged = st.application.purge_queue(identity, name).await?;
+    Ok(Json(PurgeQueueResponse { purged }))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedriveQueueArgs {
+    name: String,
+    /// Only redrives messages for this consumer group.
+    group: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RedriveQueueResponse {
+    redriven: usize,
+}
+
+/// Delivers a queue's dead-lettered messages again.
+pub async fn redrive_queue(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(RedriveQueueArgs { name, group }): Json<RedriveQueueArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let redriven = st.application.redrive_queue(identity, name, group).await?;
+    Ok(Json(RedriveQueueResponse { redriven }))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use http::{
+        Request,
+        StatusCode,
+    };
+    use runtime::prod::ProdRuntime;
+    use serde_json::{
+        json,
+        Value as JsonValue,
+    };
+
+    use crate::test_helpers::setup_backend_for_test;
+
+    #[convex_macro::prod_rt_test]
+    async fn test_queues(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        let post = |uri: &str, body: JsonValue| {
+            Request::builder()
+                .uri(uri)
+                .method("POST")
+                .header("Content-Type", "application/json")
+                .header("Authorization", backend.admin_auth_header.0.encode())
+                .body(Body::from(serde_json::to_vec(&body).unwrap()))
+        };
+        let get = |uri: &str| {
+            Request::builder()
+                .uri(uri)
+                .method("GET")
+                .header("Authorization", backend.admin_auth_header.0.encode())
+                .body(Body::empty())
+        };
+
+        let req = post(
+            "/api/queues/enqueue",
+            json!({"name": "emails", "body": {"to": "a@example.com"}}),
+        )?;
+        backend
+            .expect_error(req, StatusCode::NOT_FOUND, "QueueNotFound")
+            .await?;
+
+        let req = post("/api/queues", json!({"name": "emails", "consumers": []}))?;
+        backend
+            .expect_error(req, StatusCode::BAD_REQUEST, "InvalidQueue")
+            .await?;
+
+        let queue = json!({
+            "name": "emails",
+            "consumers": [{"group": "send", "function": "emails:send"}],
+            "visibilityTimeoutMs": 60_000,
+            "maxDeliveries": 3,
+        });
+        let req = post("/api/queues", queue.clone())?;
+        backend.expect_success::<JsonValue>(req).await?;
+        let queues: JsonValue = backend.expect_success(get("/api/queues")?).await?;
+        assert_eq!(queues, json!([queue]));
+
+        for to in ["a@example.com", "b@example.com"] {
+            let req = post(
+                "/api/queues/enqueue",
+                json!({"name": "emails", "partitionKey": "user1", "body": {"to": to}}),
+            )?;
+            backend.expect_success::<JsonValue>(req).await?;
+        }
+        let listed: JsonValue = backend
+            .expect_success(get("/api/queues/messages?name=emails")?)
+            .await?;
+        let messages = listed["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0]["state"], "inFlight");
+        assert_eq!(messages[0]["body"], json!({"to": "a@example.com"}));
+        assert_eq!(messages[1]["state"], "pending");
+
+        let req = post("/api/queues/purge", json!({"name": "emails"}))?;
+        let purged: JsonValue = backend.expect_success(req).await?;
+        assert_eq!(purged, json!({"purged": 2}));
+
+        let req = post("/api/queues/delete", json!({"name": "emails"}))?;
+        backend.expect_success::<JsonValue>(req).await?;
+        let queues: JsonValue = backend.expect_success(get("/api/queues")?).await?;
+        assert_eq!(queues, json!([]));
+        Ok(())
+    }
+}
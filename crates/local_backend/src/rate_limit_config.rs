@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+use axum::{
+    extract::State,
+    response::IntoResponse,
+};
+use common::http::{
+    extract::Json,
+    HttpResponseError,
+};
+use http::StatusCode;
+use model::rate_limit_config::types::{
+    RateLimitConfig,
+    RateLimitRule,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    admin::{
+        must_be_admin,
+        must_be_admin_with_write_access,
+    },
+    authentication::ExtractIdentity,
+    LocalAppState,
+};
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitConfigJson {
+    rules: Vec<RateLimitRuleJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimitRuleJson {
+    /// Paths the rule applies to, like `"/api/mutation"` or `"/http/"`.
+    path_prefix: String,
+    /// `"ip"`, `"identity"` or `"route"`.
+    key: String,
+    requests: u32,
+    period_ms: u64,
+}
+
+impl From<RateLimitConfig> for RateLimitConfigJson {
+    fn from(config: RateLimitConfig) -> Self {
+        Self {
+            rules: config
+                .rules
+                .into_iter()
+                .map(|rule| RateLimitRuleJson {
+                    path_prefix: rule.path_prefix,
+                    key: rule.key.to_string(),
+                    requests: rule.requests,
+                    period_ms: rule.period.as_millis() as u64,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Returns the rate limits for public endpoints, or `null` if there aren't
+/// any.
+pub async fn get_rate_limit_config(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let config = st
+        .application
+        .get_rate_limit_config(identity)
+        .await?
+        .map(RateLimitConfigJson::from);
+    Ok(Json(config))
+}
+
+/// Replaces the rate limits for public endpoints. A `null` body removes them.
+pub async fn set_rate_limit_config(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(args): Json<Option<RateLimitConfigJson>>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let config = args
+        .map(|args| {
+            let rules = args
+                .rules
+                .into_iter()
+                .map(|rule| {
+                    anyhow::Ok(RateLimitRule {
+                        path_prefix: rule.path_prefix,
+                        key: rule.key.parse()?,
+                        requests: rule.requests,
+                        period: Duration::from_millis(rule.period_ms),
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?;
+            RateLimitConfig::new(rules)
+        })
+        .transpose()?;
+    st.application
+        .set_rate_limit_config(identity, config)
+        .await?;
+    Ok(StatusCode::OK)
+}
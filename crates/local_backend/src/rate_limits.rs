@@ -0,0 +1,336 @@
+//! Enforces the deployment's rate limits on public endpoints, rejecting
+//! requests over a limit with a 429 and a `Retry-After` header.
+use std::{
+    net::{
+        IpAddr,
+        SocketAddr,
+    },
+    num::NonZeroU32,
+    sync::Arc,
+    time::Duration,
+};
+
+use application::Application;
+use axum::{
+    extract::{
+        ConnectInfo,
+        OriginalUri,
+        Request,
+        State,
+    },
+    middleware::Next,
+    response::{
+        IntoResponse,
+        Response,
+    },
+};
+use common::{
+    http::HttpResponseError,
+    knobs::{
+        RATE_LIMIT_CONFIG_REFRESH_INTERVAL,
+        RATE_LIMIT_TRUST_FORWARDED_FOR,
+    },
+    runtime::{
+        new_keyed_rate_limiter,
+        GovernorInstant,
+        KeyedRateLimiter,
+        Runtime,
+    },
+};
+use errors::ErrorMetadata;
+use governor::Quota;
+use http::{
+    header::{
+        AUTHORIZATION,
+        RETRY_AFTER,
+    },
+    HeaderMap,
+    HeaderValue,
+};
+use keybroker::Identity;
+use model::rate_limit_config::types::{
+    RateLimitConfig,
+    RateLimitKey,
+};
+use parking_lot::Mutex;
+use sha2::{
+    Digest,
+    Sha256,
+};
+
+/// Who a request counts against for each kind of rule.
+#[derive(Clone, Debug, Default)]
+pub struct RequestClient {
+    pub ip: Option<IpAddr>,
+    /// Hash of the request's `Authorization` header.
+    pub credentials: Option<[u8; 32]>,
+}
+
+/// The address a request came from, which is the one our reverse proxy saw
+/// if `RATE_LIMIT_TRUST_FORWARDED_FOR` is set.
+pub fn client_ip(remote_addr: Option<SocketAddr>, headers: &HeaderMap) -> Option<IpAddr> {
+    let forwarded_ip = if *RATE_LIMIT_TRUST_FORWARDED_FOR {
+        // The last address is the one our proxy saw the request come from.
+        headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .last()
+            .and_then(|ip| ip.trim().parse().ok())
+    } else {
+        None
+    };
+    forwarded_ip.or(remote_addr.map(|addr| addr.ip()))
+}
+
+impl RequestClient {
+    fn from_request(remote_addr: Option<SocketAddr>, headers: &HeaderMap) -> Self {
+        Self {
+            ip: client_ip(remote_addr, headers),
+            credentials: headers
+                .get(AUTHORIZATION)
+                .map(|value| Sha256::digest(value.as_bytes()).into()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+enum Subject {
+    Ip(IpAddr),
+    Credentials([u8; 32]),
+    Route,
+}
+
+/// A token bucket per subject for each of the deployment's rules.
+pub struct RuleLimiters<RT: Runtime> {
+    runtime: RT,
+    config: RateLimitConfig,
+    limiters: Vec<KeyedRateLimiter<Subject, RT>>,
+}
+
+impl<RT: Runtime> RuleLimiters<RT> {
+    pub fn new(runtime: RT, config: RateLimitConfig) -> Self {
+        let limiters = config
+            .rules
+            .iter()
+            .map(|rule| {
+                let requests = NonZeroU32::new(rule.requests).unwrap_or(NonZeroU32::MIN);
+                let replenish_interval =
+                    (rule.period / requests.get()).max(Duration::from_nanos(1));
+                let quota = Quota::with_period(replenish_interval)
+                    .expect("Replenish interval is nonzero")
+                    .allow_burst(requests);
+                new_keyed_rate_limiter(runtime.clone(), quota)
+            })
+            .collect();
+        Self {
+            runtime,
+            config,
+            limiters,
+        }
+    }
+
+    /// Takes a token from every bucket `path` and `client` fall into, or
+    /// returns how long until the request would be allowed.
+    pub fn check(&self, path: &str, client: &RequestClient) -> Result<(), Duration> {
+        let mut retry_after = None;
+        for (rule, limiter) in self.config.rules.iter().zip(&self.limiters) {
+            if !path.starts_with(&rule.path_prefix) {
+                continue;
+            }
+            let subject = match (rule.key, client.credentials, client.ip) {
+                (RateLimitKey::Route, ..) => Subject::Route,
+                (RateLimitKey::Identity, Some(credentials), _) => Subject::Credentials(credentials),
+                (RateLimitKey::Identity | RateLimitKey::Ip, _, Some(ip)) => Subject::Ip(ip),
+                // We don't know where the request came from, e.g. in tests.
+                (RateLimitKey::Identity | RateLimitKey::Ip, _, None) => continue,
+            };
+            if let Err(not_until) = limiter.check_key(&subject) {
+                let wait =
+                    not_until.wait_time_from(GovernorInstant::from(self.runtime.monotonic_now()));
+                retry_after = retry_after.max(Some(wait));
+            }
+        }
+        match retry_after {
+            Some(wait) => Err(wait),
+            None => Ok(()),
+        }
+    }
+
+    /// Forgets subjects whose buckets have refilled, so idle clients don't
+    /// take up memory.
+    fn retain_recent(&self) {
+        for limiter in &self.limiters {
+            limiter.retain_recent();
+        }
+    }
+}
+
+/// The limiters for the deployment's current rate limits, reloaded every
+/// [`RATE_LIMIT_CONFIG_REFRESH_INTERVAL`]. Buckets are kept across reloads
+/// unless the rules changed.
+pub struct RateLimits<RT: Runtime> {
+    application: Application<RT>,
+    loaded: Mutex<Option<(tokio::time::Instant, Option<Arc<RuleLimiters<RT>>>)>>,
+}
+
+impl<RT: Runtime> RateLimits<RT> {
+    pub fn new(application: Application<RT>) -> Self {
+        Self {
+            application,
+            loaded: Mutex::new(None),
+        }
+    }
+
+    async fn limiters(&self) -> anyhow::Result<Option<Arc<RuleLimiters<RT>>>> {
+        let runtime = self.application.runtime();
+        let now = runtime.monotonic_now();
+        if let Some((loaded_at, limiters)) = &*self.loaded.lock()
+            && now.duration_since(*loaded_at) < *RATE_LIMIT_CONFIG_REFRESH_INTERVAL
+        {
+            return Ok(limiters.clone());
+        }
+        let config = self
+            .application
+            .get_rate_limit_config(Identity::system())
+            .await?;
+        let mut loaded = self.loaded.lock();
+        let limiters = match (loaded.take(), config) {
+            (Some((_, Some(existing))), Some(config)) if existing.config == config => {
+                existing.retain_recent();
+                Some(existing)
+            },
+            (_, config) => config.map(|config| Arc::new(RuleLimiters::new(runtime, config))),
+        };
+        *loaded = Some((now, limiters.clone()));
+        Ok(limiters)
+    }
+}
+
+pub async fn rate_limit_middleware<RT: Runtime>(
+    State(rate_limits): State<Arc<RateLimits<RT>>>,
+    remote_addr: Option<ConnectInfo<SocketAddr>>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let limiters = match rate_limits.limiters().await {
+        Ok(limiters) => limiters,
+        Err(e) => {
+            // Don't take the deployment down with its rate limits.
+            tracing::error!("Failed to load rate limits: {e:#}");
+            None
+        },
+    };
+    if let Some(limiters) = limiters {
+        let path = req
+            .extensions()
+            .get::<OriginalUri>()
+            .map_or(req.uri().path(), |uri| uri.path());
+        let client = RequestClient::from_request(
+            remote_addr.map(|connect_info| connect_info.0),
+            req.headers(),
+        );
+        if let Err(wait) = limiters.check(path, &client) {
+            return rate_limited_response(wait);
+        }
+    }
+    next.run(req).await
+}
+
+pub(crate) fn rate_limited_response(wait: Duration) -> Response {
+    // Round up so clients that wait exactly this long are allowed.
+    let retry_after_secs = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+    let mut response = HttpResponseError::from(anyhow::anyhow!(ErrorMetadata::rate_limited(
+        "RateLimited",
+        format!("Too many requests. Try again in {retry_after_secs} seconds."),
+    )))
+    .into_response();
+    response
+        .headers_mut()
+        .insert(RETRY_AFTER, HeaderValue::from(retry_after_secs));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        net::{
+            IpAddr,
+            Ipv4Addr,
+        },
+        time::Duration,
+    };
+
+    use common::runtime::Runtime;
+    use http::{
+        header::RETRY_AFTER,
+        StatusCode,
+    };
+    use model::rate_limit_config::types::{
+        RateLimitConfig,
+        RateLimitKey,
+        RateLimitRule,
+    };
+    use runtime::testing::TestRuntime;
+
+    use super::{
+        rate_limited_response,
+        RequestClient,
+        RuleLimiters,
+    };
+
+    fn client(ip: u8, credentials: Option<u8>) -> RequestClient {
+        RequestClient {
+            ip: Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, ip))),
+            credentials: credentials.map(|c| [c; 32]),
+        }
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_rule_limiters(rt: TestRuntime) -> anyhow::Result<()> {
+        let config = RateLimitConfig::new(vec![
+            RateLimitRule {
+                path_prefix: "/api/mutation".to_string(),
+                key: RateLimitKey::Ip,
+                requests: 2,
+                period: Duration::from_secs(60),
+            },
+            RateLimitRule {
+                path_prefix: "/http/".to_string(),
+                key: RateLimitKey::Identity,
+                requests: 1,
+                period: Duration::from_secs(10),
+            },
+        ])?;
+        let limiters = RuleLimiters::new(rt.clone(), config);
+
+        limiters.check("/api/mutation", &client(1, None)).unwrap();
+        limiters.check("/api/mutation", &client(1, None)).unwrap();
+        let wait = limiters
+            .check("/api/mutation", &client(1, None))
+            .unwrap_err();
+        assert!(wait <= Duration::from_secs(30));
+        // Other IPs and paths have their own allowance.
+        limiters.check("/api/mutation", &client(2, None)).unwrap();
+        limiters.check("/api/query", &client(1, None)).unwrap();
+
+        // Identity rules key on credentials, falling back to the IP.
+        limiters.check("/http/hook", &client(1, Some(1))).unwrap();
+        limiters.check("/http/hook", &client(1, Some(2))).unwrap();
+        limiters.check("/http/hook", &client(1, None)).unwrap();
+        assert!(limiters.check("/http/hook", &client(3, Some(1))).is_err());
+        assert!(limiters.check("/http/hook", &client(1, None)).is_err());
+
+        rt.wait(Duration::from_secs(10)).await;
+        limiters.check("/http/hook", &client(3, Some(1))).unwrap();
+        Ok(())
+    }
+
+    #[test]
+    fn test_rate_limited_response() {
+        let response = rate_limited_response(Duration::from_millis(1500));
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[RETRY_AFTER], "2");
+    }
+}
@@ -0,0 +1,141 @@
+use application::{
+    replication_worker::{
+        REPLICATION_HEARTBEAT_INTERVAL,
+        REPLICATION_TOKEN_HEADER,
+    },
+    Application,
+};
+use axum::{
+    body::Body,
+    extract::State,
+    response::IntoResponse,
+};
+use common::{
+    http::{
+        extract::{
+            Json,
+            Query,
+        },
+        HttpResponseError,
+    },
+    knobs::REPLICATION_STREAM_POLL_INTERVAL,
+    runtime::Runtime,
+};
+use database::replication::{
+    ReplicatedCommit,
+    SerializedReplicatedCommit,
+};
+use errors::ErrorMetadata;
+use futures_async_stream::try_stream;
+use http::{
+    header::CONTENT_TYPE,
+    HeaderMap,
+};
+use runtime::prod::ProdRuntime;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use sync_types::Timestamp;
+
+use crate::{
+    admin::must_be_admin_with_write_access,
+    authentication::ExtractIdentity,
+    LocalAppState,
+};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReplicationTokenResponse {
+    token: String,
+}
+
+/// Issues a token that lets a follower deployment stream this deployment's
+/// commits with `/api/replication/stream`.
+pub async fn issue_replication_token(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let token = st.application.issue_replication_token(identity)?;
+    Ok(Json(ReplicationTokenResponse {
+        token: token.to_string(),
+    }))
+}
+
+#[derive(Deserialize)]
+pub struct ReplicationStreamArgs {
+    /// Send commits after this timestamp.
+    cursor: String,
+}
+
+/// Streams commits to user tables in the root component as newline-delimited
+/// JSON, starting after `cursor`. The stream stays open and sends new commits
+/// as they happen, with an empty commit every so often to advance the
+/// follower's cursor while the deployment is idle.
+pub async fn stream_replication(
+    State(st): State<LocalAppState>,
+    headers: HeaderMap,
+    Query(ReplicationStreamArgs { cursor }): Query<ReplicationStreamArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    let token = headers
+        .get(REPLICATION_TOKEN_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| {
+            anyhow::anyhow!(ErrorMetadata::unauthenticated(
+                "MissingReplicationToken",
+                format!("Missing {REPLICATION_TOKEN_HEADER} header"),
+            ))
+        })?
+        .to_string();
+    let cursor = cursor
+        .parse::<u64>()
+        .ok()
+        .and_then(|cursor| Timestamp::try_from(cursor).ok())
+        .ok_or_else(|| {
+            anyhow::anyhow!(ErrorMetadata::bad_request(
+                "InvalidReplicationCursor",
+                format!("Invalid replication cursor {cursor}"),
+            ))
+        })?;
+    // Read the first page before responding so that an invalid token or cursor
+    // fails the request instead of the stream.
+    let first_page = st.application.replication_commits(&token, cursor).await?;
+    Ok((
+        [(CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(replication_stream(st.application, token, first_page)),
+    ))
+}
+
+#[try_stream(ok = Vec<u8>, error = anyhow::Error, boxed)]
+async fn replication_stream(
+    application: Application<ProdRuntime>,
+    token: String,
+    first_page: (Vec<ReplicatedCommit>, bool),
+) {
+    let runtime = application.runtime();
+    let mut last_sent = None;
+    let (mut commits, mut has_more) = first_page;
+    loop {
+        let mut cursor = None;
+        for commit in commits {
+            cursor = Some(commit.ts);
+            let now = runtime.monotonic_now();
+            if commit.writes.is_empty()
+                && let Some(last_sent) = last_sent
+                && now - last_sent < REPLICATION_HEARTBEAT_INTERVAL
+            {
+                continue;
+            }
+            let mut line = serde_json::to_vec(&SerializedReplicatedCommit::from(commit))?;
+            line.push(b'\n');
+            last_sent = Some(now);
+            yield line;
+        }
+        if !has_more {
+            runtime.wait(*REPLICATION_STREAM_POLL_INTERVAL).await;
+        }
+        let cursor = cursor.ok_or_else(|| anyhow::anyhow!("Replication page has no commits"))?;
+        (commits, has_more) = application.replication_commits(&token, cursor).await?;
+    }
+}
@@ -12,6 +12,7 @@ use axum::{
         State,
     },
     routing::{
+        delete,
         get,
         post,
     },
@@ -23,6 +24,7 @@ use common::{
         CONVEX_CLIENT_HEADER,
     },
     knobs::{
+        HTTP_ACTION_REQUEST_BODY_LIMIT,
         MAX_BACKEND_PUBLIC_API_REQUEST_SIZE,
         MAX_BACKEND_RPC_REQUEST_SIZE,
         MAX_PUSH_BYTES,
@@ -38,8 +40,8 @@ use http::{
     Method,
     StatusCode,
 };
-use isolate::HTTP_ACTION_BODY_LIMIT;
 use metrics::SERVER_VERSION_STR;
+use sync::RetainedSessions;
 use tower::ServiceBuilder;
 use tower_http::{
     cors::{
@@ -50,19 +52,68 @@ use tower_http::{
 };
 
 use crate::{
+    admin_key_scope::admin_key_scope_middleware,
+    admin_keys::{
+        issue_admin_key,
+        issue_admin_session_token,
+        list_admin_keys,
+        revoke_admin_key,
+        rotate_admin_key_secret,
+    },
+    admin_lists::{
+        list_functions,
+        list_scheduled_jobs,
+        list_tables,
+    },
+    api_keys::{
+        create_api_key,
+        delete_api_key,
+        list_api_keys,
+    },
     app_metrics::{
         cache_hit_percentage,
         latency_percentiles,
         table_rate,
         udf_rate,
     },
+    archival::{
+        get_archival_status,
+        set_archival_policy,
+    },
+    audit_log::{
+        audit_log_middleware,
+        list_audit_log,
+    },
+    auth_providers::get_auth_providers,
+    cold_data::get_cold_data_report,
+    cors_config::{
+        get_cors_config,
+        set_cors_config,
+    },
+    counters::{
+        add_to_counter,
+        delete_counter,
+        get_counter,
+        set_counter_shards,
+    },
+    custom_domains::{
+        list_custom_domains,
+        register_custom_domain,
+        remove_custom_domain,
+    },
     dashboard::{
+        compact_table,
         delete_component,
         delete_tables,
+        generate_rust_client,
+        get_deleting_tables_cleanup,
         get_indexes,
         get_source_code,
+        observed_args_shapes,
+        run_query_at_ts,
         run_test_function,
         shapes2,
+        update_deleting_tables_cleanup,
     },
     deploy_config::{
         get_config,
@@ -71,15 +122,49 @@ use crate::{
     },
     deploy_config2,
     environment_variables::update_environment_variables,
+    fault_injection::{
+        get_fault_injection,
+        set_fault_injection,
+    },
+    first_party_auth::{
+        login,
+        logout,
+        request_magic_link,
+        signup,
+        verify_magic_link,
+    },
+    function_limits::{
+        list_function_limits,
+        set_function_limits,
+    },
     http_actions::http_action_handler,
+    log_sinks::{
+        list_log_sinks,
+        remove_log_sink,
+        set_log_sink,
+    },
     logs::{
         stream_function_logs,
         stream_udf_execution,
     },
+    materialized_aggregates::{
+        create_materialized_aggregate,
+        delete_materialized_aggregate,
+        list_materialized_aggregates,
+    },
+    network_acl::{
+        network_acl_middleware,
+        NetworkAcls,
+    },
+    network_acl_config::{
+        get_network_acl_config,
+        set_network_acl_config,
+    },
     node_action_callbacks::{
         action_callbacks_middleware,
         cancel_developer_job,
         create_function_handle,
+        hybrid_search,
         internal_action_post,
         internal_mutation_post,
         internal_query_post,
@@ -90,46 +175,126 @@ use crate::{
         storage_get_url,
         vector_search,
     },
+    operations::{
+        cancel_operation,
+        get_operation,
+        list_operations,
+        stream_operation,
+    },
+    pii_reports::get_pii_reports,
     public_api::{
         public_action_post,
         public_function_post,
         public_function_post_with_path,
         public_get_query_ts,
+        public_mutation_batch_post,
         public_mutation_post,
         public_query_at_ts_post,
         public_query_batch_post,
         public_query_get,
         public_query_post,
     },
+    queues::{
+        delete_queue,
+        enqueue_queue_message,
+        list_queue_messages,
+        list_queues,
+        purge_queue,
+        redrive_queue,
+        set_queue,
+    },
+    rate_limit_config::{
+        get_rate_limit_config,
+        set_rate_limit_config,
+    },
+    rate_limits::{
+        rate_limit_middleware,
+        RateLimits,
+    },
+    replication::{
+        issue_replication_token,
+        stream_replication,
+    },
     scheduling::{
         cancel_all_jobs,
         cancel_job,
+        get_scheduler_backoff_policy,
+        list_dead_lettered_jobs,
+        list_unfinished_jobs,
+        purge_dead_lettered_jobs,
+        retry_dead_lettered_job,
+        set_scheduler_backoff_policy,
     },
     schema::{
         prepare_schema,
         schema_state,
     },
+    search_index_bundle::{
+        export_search_index,
+        import_search_index,
+    },
+    slow_executions::list_slow_executions,
     snapshot_export::{
+        delete_export,
+        get_backup_schedule,
         get_zip_export,
+        list_backups,
+        list_export_watermarks,
+        remove_export_watermark,
         request_zip_export,
+        set_backup_schedule,
+        set_export_watermark,
+        trace_export_watermark,
     },
     snapshot_import::{
         cancel_import,
         import,
+        import_chunk,
+        import_finish,
         import_finish_upload,
+        import_id_mapping,
+        import_start,
         import_start_upload,
         import_upload_part,
         perform_import,
         prepare_import,
+        restore_tables,
     },
     storage::{
+        storage_cancel_upload,
+        storage_finish_upload,
         storage_get,
+        storage_start_upload,
         storage_upload,
+        storage_upload_chunk,
+        storage_upload_status,
+        UPLOAD_LENGTH_HEADER,
+        UPLOAD_OFFSET_HEADER,
     },
+    storage_gc::storage_gc_report,
     subs::{
         sync,
         sync_client_version_url,
     },
+    trace_export::set_trace_sampling,
+    usage::{
+        get_usage_quotas,
+        list_usage,
+        set_usage_quotas,
+    },
+    vector_index_compaction::{
+        compact_vector_index,
+        get_vector_index_compaction_status,
+        set_vector_index_compaction_config,
+    },
+    workflows::{
+        cancel_workflow,
+        delete_workflow_definition,
+        list_workflow_definitions,
+        list_workflows,
+        set_workflow_definition,
+        start_workflow,
+    },
     LocalAppState,
     RouterState,
 };
@@ -182,6 +347,7 @@ pub fn router(st: LocalAppState) -> Router {
                 .layer(RequestDecompressionLayer::new())
                 .layer(DefaultBodyLimit::max(*MAX_PUSH_BYTES)),
         )
+        .route("/run_query_at_ts", post(run_query_at_ts))
         .route("/get_config", post(get_config))
         .route("/get_config_hashes", post(get_config_hashes))
         .route("/schema_state/:schema_id", get(schema_state))
@@ -192,7 +358,63 @@ pub fn router(st: LocalAppState) -> Router {
 
     let snapshot_export_routes = Router::new()
         .route("/request/zip", post(request_zip_export))
-        .route("/zip/:id", get(get_zip_export));
+        .route("/zip/:id", get(get_zip_export))
+        .route("/delete/:id", post(delete_export))
+        .route("/backups", get(list_backups))
+        .route(
+            "/backups/schedule",
+            get(get_backup_schedule).post(set_backup_schedule),
+        )
+        .route(
+            "/watermarks",
+            get(list_export_watermarks).post(set_export_watermark),
+        )
+        .route("/watermarks/remove", post(remove_export_watermark))
+        .route("/watermarks/trace", get(trace_export_watermark))
+        .route("/search_index", get(export_search_index));
+
+    let operations_routes = Router::new()
+        .route("/", get(list_operations))
+        .route("/:id", get(get_operation))
+        .route("/:id/stream", get(stream_operation))
+        .route("/:id/cancel", post(cancel_operation));
+
+    let counter_routes = Router::new()
+        .route("/:name", get(get_counter).delete(delete_counter))
+        .route("/:name/add", post(add_to_counter))
+        .route("/:name/shards", post(set_counter_shards));
+
+    let custom_domain_routes = Router::new()
+        .route("/", get(list_custom_domains).post(register_custom_domain))
+        .route("/:domain", delete(remove_custom_domain));
+
+    let replication_routes = Router::new()
+        .route("/token", post(issue_replication_token))
+        .route("/stream", get(stream_replication));
+
+    // Rate limits apply to the public endpoints, inside the CORS layer so that
+    // browsers can read 429s.
+    let rate_limit_layer = axum::middleware::from_fn_with_state(
+        Arc::new(RateLimits::new(st.application.clone())),
+        rate_limit_middleware,
+    );
+    // The network ACL is checked before rate limits, so rejected requests
+    // don't use up anyone's allowance.
+    let network_acl_layer = axum::middleware::from_fn_with_state(
+        Arc::new(NetworkAcls::new(st.application.clone(), st.geo_ip.clone())),
+        network_acl_middleware,
+    );
+
+    // First-party auth is called by end users rather than admins, so it's
+    // covered by the same limits and ACL as the public endpoints.
+    let first_party_auth_routes = Router::new()
+        .route("/signup", post(signup))
+        .route("/login", post(login))
+        .route("/logout", post(logout))
+        .route("/magic_link", post(request_magic_link))
+        .route("/magic_link/verify", post(verify_magic_link))
+        .layer(rate_limit_layer.clone())
+        .layer(network_acl_layer.clone());
 
     let api_routes = Router::new()
         .merge(cli_routes)
@@ -204,23 +426,139 @@ pub fn router(st: LocalAppState) -> Router {
                 add_extension::<LocalAppState, _>,
             )),
         )
-        .nest("/export", snapshot_export_routes);
+        .nest("/export", snapshot_export_routes)
+        .nest("/operations", operations_routes)
+        .nest("/counters", counter_routes)
+        .route("/storage_gc_report", post(storage_gc_report))
+        .route("/pii_reports", get(get_pii_reports))
+        .route("/cold_data_report", get(get_cold_data_report))
+        .route("/archival", get(get_archival_status))
+        .route("/archival/set_policy", post(set_archival_policy))
+        .route(
+            "/vector_index_compaction",
+            get(get_vector_index_compaction_status).post(set_vector_index_compaction_config),
+        )
+        .route(
+            "/vector_index_compaction/compact",
+            post(compact_vector_index),
+        )
+        .route(
+            "/materialized_aggregates",
+            get(list_materialized_aggregates),
+        )
+        .route(
+            "/materialized_aggregates/create",
+            post(create_materialized_aggregate),
+        )
+        .route(
+            "/materialized_aggregates/delete",
+            post(delete_materialized_aggregate),
+        )
+        .route(
+            "/fault_injection",
+            get(get_fault_injection).post(set_fault_injection),
+        )
+        .route("/cors_config", get(get_cors_config).post(set_cors_config))
+        .route(
+            "/rate_limit_config",
+            get(get_rate_limit_config).post(set_rate_limit_config),
+        )
+        .route(
+            "/function_limits",
+            get(list_function_limits).post(set_function_limits),
+        )
+        .route(
+            "/network_acl_config",
+            get(get_network_acl_config).post(set_network_acl_config),
+        )
+        .nest("/custom_domains", custom_domain_routes)
+        .nest("/replication", replication_routes)
+        .route("/audit_log", get(list_audit_log))
+        .route("/list_tables", get(list_tables))
+        .route("/list_functions", get(list_functions))
+        .route("/list_scheduled_jobs", get(list_scheduled_jobs))
+        .route("/scheduled_jobs", get(list_unfinished_jobs))
+        .route("/scheduled_jobs/dead_letter", get(list_dead_lettered_jobs))
+        .route(
+            "/scheduled_jobs/dead_letter/retry",
+            post(retry_dead_lettered_job),
+        )
+        .route(
+            "/scheduled_jobs/dead_letter/purge",
+            post(purge_dead_lettered_jobs),
+        )
+        .route(
+            "/scheduler/backoff_policy",
+            get(get_scheduler_backoff_policy).post(set_scheduler_backoff_policy),
+        )
+        .route("/auth/providers", get(get_auth_providers))
+        .nest("/auth", first_party_auth_routes)
+        .route("/admin_keys", get(list_admin_keys).post(issue_admin_key))
+        .route("/admin_keys/revoke", post(revoke_admin_key))
+        .route("/admin_keys/rotate_secret", post(rotate_admin_key_secret))
+        .route("/admin_keys/session_token", post(issue_admin_session_token))
+        .route("/api_keys", get(list_api_keys).post(create_api_key))
+        .route("/api_keys/delete", post(delete_api_key))
+        .route("/log_sinks", get(list_log_sinks).post(set_log_sink))
+        .route("/log_sinks/delete", post(remove_log_sink))
+        .route("/trace_sampling", post(set_trace_sampling))
+        .route("/slow_executions", get(list_slow_executions))
+        .route("/usage", get(list_usage))
+        .route(
+            "/usage_quotas",
+            get(get_usage_quotas).post(set_usage_quotas),
+        )
+        .route(
+            "/workflow_definitions",
+            get(list_workflow_definitions).post(set_workflow_definition),
+        )
+        .route(
+            "/workflow_definitions/delete",
+            post(delete_workflow_definition),
+        )
+        .route("/workflows", get(list_workflows))
+        .route("/workflows/start", post(start_workflow))
+        .route("/workflows/cancel", post(cancel_workflow))
+        .route("/queues", get(list_queues).post(set_queue))
+        .route("/queues/delete", post(delete_queue))
+        .route("/queues/enqueue", post(enqueue_queue_message))
+        .route("/queues/messages", get(list_queue_messages))
+        .route("/queues/purge", post(purge_queue))
+        .route("/queues/redrive", post(redrive_queue))
+        .layer(axum::middleware::from_fn_with_state(
+            st.clone(),
+            admin_key_scope_middleware,
+        ))
+        // Outside the scope check, so calls rejected for their key's scope
+        // are recorded too.
+        .layer(axum::middleware::from_fn_with_state(
+            st.clone(),
+            audit_log_middleware,
+        ));
 
     // Endpoints migrated to use the RouterState trait instead of application.
     let migrated_api_routes = Router::new()
         .merge(browser_routes)
         .merge(public_api_routes())
-        .nest("/storage", storage_api_routes());
+        .nest("/storage", storage_api_routes())
+        .layer(rate_limit_layer.clone())
+        .layer(network_acl_layer.clone());
     let migrated = Router::new()
         .nest("/api", migrated_api_routes)
         .layer(cors())
         // Order matters. Layers only apply to routes above them.
         // Notably, any layers added here won't apply to common routes
         // added inside `serve_http`
-        .nest("/http/", http_action_routes())
+        .nest(
+            "/http/",
+            http_action_routes()
+                .layer(rate_limit_layer)
+                .layer(network_acl_layer),
+        )
         .with_state(RouterState {
             api: Arc::new(st.application.clone()),
             runtime: st.application.runtime().clone(),
+            sync_sessions: Arc::new(RetainedSessions::default()),
         });
 
     let instance_name = st.instance_name.clone();
@@ -245,6 +583,7 @@ pub fn public_api_routes() -> Router<RouterState> {
         .route("/query_ts", post(public_get_query_ts))
         .route("/query_batch", post(public_query_batch_post))
         .route("/mutation", post(public_mutation_post))
+        .route("/mutation_batch", post(public_mutation_batch_post))
         .route("/action", post(public_action_post))
         .route("/function", post(public_function_post))
         .route("/run/*rest", post(public_function_post_with_path))
@@ -254,6 +593,14 @@ pub fn public_api_routes() -> Router<RouterState> {
 pub fn storage_api_routes() -> Router<RouterState> {
     Router::new()
         .route("/upload", post(storage_upload))
+        .route("/uploads", post(storage_start_upload))
+        .route(
+            "/uploads/:upload_id",
+            get(storage_upload_status)
+                .patch(storage_upload_chunk)
+                .delete(storage_cancel_upload),
+        )
+        .route("/uploads/:upload_id/finish", post(storage_finish_upload))
         .route("/:storage_id", get(storage_get))
 }
 
@@ -271,6 +618,7 @@ where
         .route("/action", post(internal_action_post))
         .route("/schedule_job", post(schedule_job))
         .route("/vector_search", post(vector_search))
+        .route("/hybrid_search", post(hybrid_search))
         .route("/cancel_job", post(cancel_developer_job))
         .route("/create_function_handle", post(create_function_handle))
         // file storage endpoints
@@ -293,16 +641,22 @@ where
         .route("/import/start_upload", post(import_start_upload))
         .route("/import/upload_part", post(import_upload_part))
         .route("/import/finish_upload", post(import_finish_upload))
+        .route("/import/start", post(import_start))
+        .route("/import/chunk", post(import_chunk))
+        .route("/import/finish", post(import_finish))
+        .route("/import/id_mapping", get(import_id_mapping))
+        .route("/import/search_index", post(import_search_index))
         .route("/prepare_import", post(prepare_import))
         .route("/perform_import", post(perform_import))
         .route("/cancel_import", post(cancel_import))
+        .route("/restore_tables", post(restore_tables))
 }
 
 pub fn http_action_routes() -> Router<RouterState> {
     Router::new()
         .route("/*rest", http_action_handler())
         .route("/", http_action_handler())
-        .layer(DefaultBodyLimit::max(HTTP_ACTION_BODY_LIMIT))
+        .layer(DefaultBodyLimit::max(*HTTP_ACTION_REQUEST_BODY_LIMIT))
 }
 
 pub fn app_metrics_routes<S>() -> Router<S>
@@ -327,17 +681,26 @@ where
 {
     Router::new()
         .route("/shapes2", get(shapes2))
+        .route("/observed_args_shapes", get(observed_args_shapes))
         .route("/get_indexes", get(get_indexes))
         .route("/delete_tables", post(delete_tables))
+        .route("/deleting_tables_cleanup", get(get_deleting_tables_cleanup))
+        .route(
+            "/deleting_tables_cleanup",
+            post(update_deleting_tables_cleanup),
+        )
+        .route("/compact_table", post(compact_table))
         .route("/delete_component", post(delete_component))
         .route("/get_source_code", get(get_source_code))
+        .route("/codegen/rust", get(generate_rust_client))
         // Metrics routes
         .nest("/app_metrics", app_metrics_routes())
 }
 
 pub fn cors() -> CorsLayer {
     CorsLayer::new()
-        .allow_headers(vec![CONTENT_TYPE, "sentry-trace".parse().unwrap(), "baggage".parse().unwrap(), CONVEX_CLIENT_HEADER, AUTHORIZATION])
+        .allow_headers(vec![CONTENT_TYPE, "sentry-trace".parse().unwrap(), "baggage".parse().unwrap(), CONVEX_CLIENT_HEADER, AUTHORIZATION, UPLOAD_OFFSET_HEADER, UPLOAD_LENGTH_HEADER, "digest".parse().unwrap()])
+        .expose_headers(vec![UPLOAD_OFFSET_HEADER, UPLOAD_LENGTH_HEADER])
         .allow_credentials(true)
         .allow_methods(vec![
             Method::GET,
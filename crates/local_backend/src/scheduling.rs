@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use anyhow::Context;
 use axum::{
     debug_handler,
@@ -10,30 +12,58 @@ use common::{
         ComponentId,
         ComponentPath,
     },
+    document::ParsedDocument,
     http::{
-        extract::Json,
+        extract::{
+            Json,
+            Query,
+        },
         HttpResponseError,
     },
 };
 use errors::ErrorMetadata;
 use http::StatusCode;
-use model::scheduled_jobs::{
-    SchedulerModel,
-    SCHEDULED_JOBS_TABLE,
+use model::{
+    scheduled_job_dead_letters::types::DeadLetteredJob,
+    scheduled_jobs::{
+        types::{
+            ScheduledJobAttemptError,
+            ScheduledJobFilter,
+            ScheduledJobState,
+            ScheduledJobsCursor,
+        },
+        SchedulerModel,
+        SCHEDULED_JOBS_TABLE,
+    },
+    scheduler_backoff_policy::types::SchedulerBackoffPolicy,
 };
 use serde::{
     Deserialize,
     Serialize,
 };
-use value::TableNamespace;
+use serde_json::Value as JsonValue;
+use sync_types::Timestamp;
+use value::{
+    DeveloperDocumentId,
+    TableNamespace,
+};
 
 use crate::{
-    admin::must_be_admin_member_with_write_access,
+    admin::{
+        must_be_admin,
+        must_be_admin_member_with_write_access,
+        must_be_admin_with_write_access,
+    },
     authentication::ExtractIdentity,
     parse::parse_document_id,
     LocalAppState,
 };
 
+const DEFAULT_DEAD_LETTERED_JOBS_LIMIT: usize = 100;
+const MAX_DEAD_LETTERED_JOBS_LIMIT: usize = 1000;
+const DEFAULT_UNFINISHED_JOBS_LIMIT: usize = 100;
+const MAX_UNFINISHED_JOBS_LIMIT: usize = 1000;
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct CancelAllJobsRequest {
@@ -44,40 +74,171 @@ pub struct CancelAllJobsRequest {
     /// happen if a function is scheduled from a different component.
     pub component_path: Option<String>,
     pub udf_path: Option<String>,
+    /// Only cancel jobs that next run at or after this time, in milliseconds
+    /// since the epoch.
+    pub start_time: Option<i64>,
+    /// Only cancel jobs that next run before this time.
+    pub end_time: Option<i64>,
 }
 
-#[debug_handler]
-pub async fn cancel_all_jobs(
-    State(st): State<LocalAppState>,
-    ExtractIdentity(identity): ExtractIdentity,
-    Json(CancelAllJobsRequest {
-        component_id,
-        udf_path,
-        component_path,
-    }): Json<CancelAllJobsRequest>,
-) -> Result<impl IntoResponse, HttpResponseError> {
-    must_be_admin_member_with_write_access(&identity)?;
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelAllJobsResponse {
+    canceled: usize,
+}
 
+fn scheduled_job_filter(
+    component_path: Option<&str>,
+    udf_path: Option<String>,
+    start_time: Option<i64>,
+    end_time: Option<i64>,
+) -> anyhow::Result<ScheduledJobFilter> {
     let udf_path = udf_path
         .map(|p| p.parse())
         .transpose()
         .context(ErrorMetadata::bad_request(
             "InvaildUdfPath",
-            "CancelAllJobs requires an optional canonicalized UdfPath",
+            "Scheduled jobs can only be filtered by a canonicalized UdfPath",
         ))?;
-    let component_id = ComponentId::deserialize_from_string(component_id.as_deref())?;
     let path = match udf_path {
         None => None,
         Some(udf_path) => Some(CanonicalizedComponentFunctionPath {
-            component: ComponentPath::deserialize(component_path.as_deref())?,
+            component: ComponentPath::deserialize(component_path)?,
             udf_path,
         }),
     };
-    st.application
-        .cancel_all_jobs(component_id, path, identity)
+    let ms_to_ts = |ms: i64| {
+        ms.checked_mul(1_000_000)
+            .and_then(|nanos| Timestamp::try_from(nanos).ok())
+            .context(ErrorMetadata::bad_request(
+                "InvalidScheduledTime",
+                format!("{ms} isn't a valid time in milliseconds since the epoch."),
+            ))
+    };
+    Ok(ScheduledJobFilter {
+        path,
+        start_ts: start_time.map(ms_to_ts).transpose()?,
+        end_ts: end_time.map(ms_to_ts).transpose()?,
+    })
+}
+
+/// Cancels the component's unfinished jobs, optionally only those for one
+/// function or that next run in a time range.
+#[debug_handler]
+pub async fn cancel_all_jobs(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(CancelAllJobsRequest {
+        component_id,
+        udf_path,
+        component_path,
+        start_time,
+        end_time,
+    }): Json<CancelAllJobsRequest>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_member_with_write_access(&identity)?;
+
+    let filter = scheduled_job_filter(component_path.as_deref(), udf_path, start_time, end_time)?;
+    let component_id = ComponentId::deserialize_from_string(component_id.as_deref())?;
+    let canceled = st
+        .application
+        .cancel_all_jobs(component_id, filter, identity)
         .await?;
 
-    Ok(StatusCode::OK)
+    Ok(Json(CancelAllJobsResponse { canceled }))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListUnfinishedJobsArgs {
+    component_id: Option<String>,
+    component_path: Option<String>,
+    udf_path: Option<String>,
+    /// Milliseconds since the epoch, inclusive.
+    start_time: Option<i64>,
+    /// Milliseconds since the epoch, exclusive.
+    end_time: Option<i64>,
+    /// The `cursor` from the previous page.
+    cursor: Option<String>,
+    limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UnfinishedJobJson {
+    id: String,
+    component_path: String,
+    udf_path: String,
+    args: JsonValue,
+    /// `pending` or `inProgress`.
+    state: String,
+    /// Milliseconds since the epoch.
+    scheduled_time: f64,
+    next_time: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListUnfinishedJobsResponse {
+    /// In the order they next run.
+    jobs: Vec<UnfinishedJobJson>,
+    /// Unset once there are no more jobs.
+    cursor: Option<String>,
+}
+
+/// Returns a page of the component's jobs that haven't finished yet,
+/// optionally only those for one function or that next run in a time range.
+pub async fn list_unfinished_jobs(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Query(args): Query<ListUnfinishedJobsArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let filter = scheduled_job_filter(
+        args.component_path.as_deref(),
+        args.udf_path,
+        args.start_time,
+        args.end_time,
+    )?;
+    let component_id = ComponentId::deserialize_from_string(args.component_id.as_deref())?;
+    let cursor = args
+        .cursor
+        .as_deref()
+        .map(ScheduledJobsCursor::decode)
+        .transpose()?;
+    let limit = args
+        .limit
+        .unwrap_or(DEFAULT_UNFINISHED_JOBS_LIMIT)
+        .clamp(1, MAX_UNFINISHED_JOBS_LIMIT);
+    let (jobs, cursor) = st
+        .application
+        .list_scheduled_jobs_page(identity, component_id, filter, cursor, limit)
+        .await?;
+    let jobs: Vec<_> = jobs
+        .into_iter()
+        .map(|job| {
+            let (id, job) = job.into_id_and_value();
+            let state = match job.state {
+                ScheduledJobState::InProgress => "inProgress",
+                _ => "pending",
+            };
+            anyhow::Ok(UnfinishedJobJson {
+                id: DeveloperDocumentId::from(id).encode(),
+                args: JsonValue::from(job.udf_args()?),
+                component_path: String::from(job.path.component),
+                udf_path: String::from(job.path.udf_path),
+                state: state.to_string(),
+                scheduled_time: i64::from(job.original_scheduled_ts) as f64 / 1_000_000.0,
+                next_time: job
+                    .next_ts
+                    .map_or(0.0, |ts| i64::from(ts) as f64 / 1_000_000.0),
+            })
+        })
+        .try_collect()?;
+    Ok(Json(ListUnfinishedJobsResponse {
+        jobs,
+        cursor: cursor.map(|cursor| cursor.encode()),
+    }))
 }
 
 #[derive(Deserialize, Serialize)]
@@ -116,3 +277,324 @@ pub async fn cancel_job(
 
     Ok(StatusCode::OK)
 }
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeadLetteredJobJson {
+    id: String,
+    /// The component whose `_scheduled_jobs` table the job was in.
+    component_id: Option<String>,
+    /// The job's id in that table, where it's marked as failed.
+    job_id: String,
+    component_path: String,
+    udf_path: String,
+    args: JsonValue,
+    /// Milliseconds since the epoch.
+    scheduled_time: f64,
+    dead_lettered_time: Option<f64>,
+    system_errors: u32,
+    occ_errors: u32,
+    /// The most recent failed attempts, oldest first.
+    errors: Vec<AttemptErrorJson>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AttemptErrorJson {
+    /// Milliseconds since the epoch.
+    time: f64,
+    error: String,
+}
+
+impl From<ScheduledJobAttemptError> for AttemptErrorJson {
+    fn from(error: ScheduledJobAttemptError) -> Self {
+        Self {
+            time: error.ts as f64 / 1_000_000.0,
+            error: error.error,
+        }
+    }
+}
+
+impl From<ParsedDocument<DeadLetteredJob>> for DeadLetteredJobJson {
+    fn from(job: ParsedDocument<DeadLetteredJob>) -> Self {
+        let dead_lettered_time = job.creation_time().map(f64::from);
+        let (id, job) = job.into_id_and_value();
+        Self {
+            id: DeveloperDocumentId::from(id).encode(),
+            component_id: job.component.serialize_to_string(),
+            job_id: job.job_id.encode(),
+            component_path: String::from(job.path.component),
+            udf_path: String::from(job.path.udf_path),
+            args: JsonValue::from(job.udf_args),
+            scheduled_time: i64::from(job.original_scheduled_ts) as f64 / 1_000_000.0,
+            dead_lettered_time,
+            system_errors: job.attempts.system_errors,
+            occ_errors: job.attempts.occ_errors,
+            errors: job
+                .attempts
+                .errors
+                .into_iter()
+                .map(AttemptErrorJson::from)
+                .collect(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListDeadLetteredJobsArgs {
+    limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListDeadLetteredJobsResponse {
+    /// Newest first.
+    jobs: Vec<DeadLetteredJobJson>,
+}
+
+/// Returns the scheduled jobs that ran out of attempts, newest first.
+pub async fn list_dead_lettered_jobs(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Query(ListDeadLetteredJobsArgs { limit }): Query<ListDeadLetteredJobsArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let limit = limit
+        .unwrap_or(DEFAULT_DEAD_LETTERED_JOBS_LIMIT)
+        .clamp(1, MAX_DEAD_LETTERED_JOBS_LIMIT);
+    let jobs = st
+        .application
+        .list_dead_lettered_jobs(identity, limit)
+        .await?
+        .into_iter()
+        .map(DeadLetteredJobJson::from)
+        .collect();
+    Ok(Json(ListDeadLetteredJobsResponse { jobs }))
+}
+
+fn parse_dead_lettered_job_id(id: &str) -> anyhow::Result<DeveloperDocumentId> {
+    DeveloperDocumentId::decode(id).context(ErrorMetadata::bad_request(
+        "InvalidDeadLetteredJobId",
+        format!("{id:?} isn't a valid dead-lettered job ID."),
+    ))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryDeadLetteredJobArgs {
+    id: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetryDeadLetteredJobResponse {
+    /// The new job in the `_scheduled_jobs` table of the job's component.
+    job_id: String,
+}
+
+/// Schedules a dead-lettered job to run again now.
+pub async fn retry_dead_lettered_job(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(RetryDeadLetteredJobArgs { id }): Json<RetryDeadLetteredJobArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let id = parse_dead_lettered_job_id(&id)?;
+    let job_id = st.application.retry_dead_lettered_job(identity, id).await?;
+    Ok(Json(RetryDeadLetteredJobResponse {
+        job_id: DeveloperDocumentId::from(job_id).encode(),
+    }))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PurgeDeadLetteredJobsArgs {
+    /// Purges every dead-lettered job if unset.
+    id: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PurgeDeadLetteredJobsResponse {
+    purged: usize,
+}
+
+/// Removes dead-lettered jobs without running them again.
+pub async fn purge_dead_lettered_jobs(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(PurgeDeadLetteredJobsArgs { id }): Json<PurgeDeadLetteredJobsArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let id = id.as_deref().map(parse_dead_lettered_job_id).transpose()?;
+    let purged = st
+        .application
+        .purge_dead_lettered_jobs(identity, id)
+        .await?;
+    Ok(Json(PurgeDeadLetteredJobsResponse { purged }))
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SchedulerBackoffPolicyJson {
+    initial_backoff_ms: u64,
+    max_backoff_ms: u64,
+    /// How many failed attempts before a job is dead-lettered. Unset retries
+    /// forever.
+    max_attempts: Option<u32>,
+}
+
+impl From<SchedulerBackoffPolicy> for SchedulerBackoffPolicyJson {
+    fn from(policy: SchedulerBackoffPolicy) -> Self {
+        Self {
+            initial_backoff_ms: policy.initial_backoff.as_millis() as u64,
+            max_backoff_ms: policy.max_backoff.as_millis() as u64,
+            max_attempts: policy.max_attempts,
+        }
+    }
+}
+
+/// Returns how the scheduler retries jobs that hit system errors.
+pub async fn get_scheduler_backoff_policy(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let policy = st
+        .application
+        .get_scheduler_backoff_policy(identity)
+        .await?;
+    Ok(Json(SchedulerBackoffPolicyJson::from(policy)))
+}
+
+/// Replaces the scheduler's retry policy. A `null` body goes back to the
+/// default policy.
+pub async fn set_scheduler_backoff_policy(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(policy): Json<Option<SchedulerBackoffPolicyJson>>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let policy = policy
+        .map(|policy| {
+            SchedulerBackoffPolicy::new(
+                Duration::from_millis(policy.initial_backoff_ms),
+                Duration::from_millis(policy.max_backoff_ms),
+                policy.max_attempts,
+            )
+        })
+        .transpose()?;
+    st.application
+        .set_scheduler_backoff_policy(identity, policy)
+        .await?;
+    Ok(StatusCode::OK)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use http::{
+        Request,
+        StatusCode,
+    };
+    use runtime::prod::ProdRuntime;
+    use serde_json::{
+        json,
+        Value as JsonValue,
+    };
+
+    use crate::test_helpers::setup_backend_for_test;
+
+    #[convex_macro::prod_rt_test]
+    async fn test_dead_letter_and_backoff_policy(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        let post = |uri: &str, body: JsonValue| {
+            Request::builder()
+                .uri(uri)
+                .method("POST")
+                .header("Content-Type", "application/json")
+                .header("Authorization", backend.admin_auth_header.0.encode())
+                .body(Body::from(serde_json::to_vec(&body).unwrap()))
+        };
+        let get = |uri: &str| {
+            Request::builder()
+                .uri(uri)
+                .method("GET")
+                .header("Authorization", backend.admin_auth_header.0.encode())
+                .body(Body::empty())
+        };
+
+        let listed: JsonValue = backend
+            .expect_success(get("/api/scheduled_jobs/dead_letter")?)
+            .await?;
+        assert_eq!(listed, json!({"jobs": []}));
+        let req = post("/api/scheduled_jobs/dead_letter/purge", json!({}))?;
+        let purged: JsonValue = backend.expect_success(req).await?;
+        assert_eq!(purged, json!({"purged": 0}));
+        let req = post(
+            "/api/scheduled_jobs/dead_letter/retry",
+            json!({"id": "nope"}),
+        )?;
+        backend
+            .expect_error(req, StatusCode::BAD_REQUEST, "InvalidDeadLetteredJobId")
+            .await?;
+
+        let req = post(
+            "/api/scheduler/backoff_policy",
+            json!({"initialBackoffMs": 0, "maxBackoffMs": 1000, "maxAttempts": 5}),
+        )?;
+        backend
+            .expect_error(
+                req,
+                StatusCode::BAD_REQUEST,
+                "InvalidSchedulerBackoffPolicy",
+            )
+            .await?;
+        let policy = json!({"initialBackoffMs": 100, "maxBackoffMs": 60_000, "maxAttempts": 5});
+        let req = post("/api/scheduler/backoff_policy", policy.clone())?;
+        backend.expect_success::<JsonValue>(req).await?;
+        let stored: JsonValue = backend
+            .expect_success(get("/api/scheduler/backoff_policy")?)
+            .await?;
+        assert_eq!(stored, policy);
+        Ok(())
+    }
+
+    #[convex_macro::prod_rt_test]
+    async fn test_list_and_cancel_unfinished_jobs(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        let get = |uri: &str| {
+            Request::builder()
+                .uri(uri)
+                .method("GET")
+                .header("Authorization", backend.admin_auth_header.0.encode())
+                .body(Body::empty())
+        };
+
+        let listed: JsonValue = backend
+            .expect_success(get("/api/scheduled_jobs?udfPath=jobs:run&limit=10")?)
+            .await?;
+        assert_eq!(listed, json!({"jobs": [], "cursor": null}));
+        backend
+            .expect_error(
+                get("/api/scheduled_jobs?cursor=nope")?,
+                StatusCode::BAD_REQUEST,
+                "InvalidScheduledJobsCursor",
+            )
+            .await?;
+
+        let req = Request::builder()
+            .uri("/api/cancel_all_jobs")
+            .method("POST")
+            .header("Content-Type", "application/json")
+            .header("Authorization", backend.admin_auth_header.0.encode())
+            .body(Body::from(serde_json::to_vec(&json!({
+                "startTime": 0,
+                "endTime": 1_700_000_000_000i64,
+            }))?))?;
+        let canceled: JsonValue = backend.expect_success(req).await?;
+        assert_eq!(canceled, json!({"canceled": 0}));
+        Ok(())
+    }
+}
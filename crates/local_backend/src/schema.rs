@@ -150,6 +150,7 @@ impl TryFrom<IndexMetadata<TableName>> for IndexMetadataResponse {
                     DeveloperTextIndexConfig {
                         search_field,
                         filter_fields,
+                        analyzer,
                     },
             } => {
                 let backfill_state = match on_disk_state {
@@ -165,10 +166,18 @@ impl TryFrom<IndexMetadata<TableName>> for IndexMetadataResponse {
                 IndexMetadataResponse {
                     table,
                     name,
-                    fields: json!({
-                        "searchField":  String::from(search_field),
-                        "filterFields": filter_fields.into_iter().map(String::from).collect::<Vec<_>>()
-                    }),
+                    fields: {
+                        let filter_fields: Vec<_> =
+                            filter_fields.into_iter().map(String::from).collect();
+                        let mut fields = json!({
+                            "searchField": String::from(search_field),
+                            "filterFields": filter_fields,
+                        });
+                        if !analyzer.is_default() {
+                            fields["analyzer"] = analyzer.to_string().into();
+                        }
+                        fields
+                    },
                     backfill: BackfillResponse {
                         state: backfill_state,
                     },
@@ -180,6 +189,7 @@ impl TryFrom<IndexMetadata<TableName>> for IndexMetadataResponse {
                         dimensions,
                         vector_field,
                         filter_fields,
+                        distance,
                     },
                 on_disk_state,
             } => {
@@ -192,11 +202,19 @@ impl TryFrom<IndexMetadata<TableName>> for IndexMetadataResponse {
                 IndexMetadataResponse {
                     table,
                     name,
-                    fields: json!({
-                        "dimensions": u32::from(dimensions),
-                        "vectorField": String::from(vector_field),
-                        "filterFields": filter_fields.into_iter().map(String::from).collect::<Vec<_>>()
-                    }),
+                    fields: {
+                        let filter_fields: Vec<_> =
+                            filter_fields.into_iter().map(String::from).collect();
+                        let mut fields = json!({
+                            "dimensions": u32::from(dimensions),
+                            "vectorField": String::from(vector_field),
+                            "filterFields": filter_fields,
+                        });
+                        if !distance.is_default() {
+                            fields["distance"] = distance.to_string().into();
+                        }
+                        fields
+                    },
                     backfill: BackfillResponse {
                         state: backfill_state,
                     },
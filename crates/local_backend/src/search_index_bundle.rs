@@ -0,0 +1,101 @@
+use axum::{
+    body::Body,
+    extract::State,
+    response::IntoResponse,
+};
+use axum_extra::TypedHeader;
+use common::{
+    components::ComponentId,
+    http::{
+        extract::Query,
+        HttpResponseError,
+    },
+    types::{
+        IndexDescriptor,
+        TableName,
+    },
+};
+use errors::ErrorMetadata;
+use futures::{
+    StreamExt,
+    TryStreamExt,
+};
+use http::StatusCode;
+use serde::Deserialize;
+
+use crate::{
+    admin::must_be_admin_with_write_access,
+    authentication::ExtractIdentity,
+    custom_headers::ContentDispositionAttachment,
+    LocalAppState,
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchIndexBundleArgs {
+    component: Option<String>,
+    table_name: String,
+    index_name: String,
+}
+
+impl SearchIndexBundleArgs {
+    fn parse(self) -> anyhow::Result<(ComponentId, TableName, IndexDescriptor)> {
+        let component = ComponentId::deserialize_from_string(self.component.as_deref())?;
+        let table_name: TableName = self.table_name.parse().map_err(|e| {
+            ErrorMetadata::bad_request(
+                "InvalidTableName",
+                format!("invalid table name {}: {e}", self.table_name),
+            )
+        })?;
+        let index_descriptor: IndexDescriptor = self.index_name.parse().map_err(|e| {
+            ErrorMetadata::bad_request(
+                "InvalidIndexName",
+                format!("invalid index name {}: {e}", self.index_name),
+            )
+        })?;
+        Ok((component, table_name, index_descriptor))
+    }
+}
+
+/// Download a bundle of a backfilled text or vector index's segments.
+pub async fn export_search_index(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Query(args): Query<SearchIndexBundleArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let (component, table_name, index_descriptor) = args.parse()?;
+    let filename = format!("{table_name}.{index_descriptor}.zip");
+    let stream = st
+        .application
+        .export_search_index(identity, component, table_name, index_descriptor)
+        .await?;
+    Ok((
+        TypedHeader(ContentDispositionAttachment(filename)),
+        Body::from_stream(stream),
+    ))
+}
+
+/// Import a bundle, e.g. one built by `build-search-index`, as the segments
+/// of an index that's still backfilling.
+pub async fn import_search_index(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Query(args): Query<SearchIndexBundleArgs>,
+    body: Body,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let (component, table_name, index_descriptor) = args.parse()?;
+    let body_stream = body.into_data_stream().map_err(anyhow::Error::from).boxed();
+    let object_key = st.application.upload_snapshot_import(body_stream).await?;
+    st.application
+        .import_search_index(
+            identity,
+            component,
+            table_name,
+            index_descriptor,
+            object_key,
+        )
+        .await?;
+    Ok(StatusCode::OK)
+}
@@ -0,0 +1,84 @@
+//! Lists the function executions that were over the slow execution
+//! thresholds, like `SLOW_EXECUTION_THRESHOLD_MS`.
+use axum::{
+    extract::State,
+    response::IntoResponse,
+};
+use common::http::{
+    extract::{
+        Json,
+        Query,
+    },
+    HttpResponseError,
+};
+use model::slow_executions::types::SerializedSlowExecution;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    admin::must_be_admin,
+    authentication::ExtractIdentity,
+    LocalAppState,
+};
+
+const DEFAULT_SLOW_EXECUTIONS_LIMIT: usize = 100;
+const MAX_SLOW_EXECUTIONS_LIMIT: usize = 1000;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListSlowExecutionsArgs {
+    limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListSlowExecutionsResponse {
+    /// Newest first.
+    executions: Vec<SerializedSlowExecution>,
+}
+
+pub async fn list_slow_executions(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Query(ListSlowExecutionsArgs { limit }): Query<ListSlowExecutionsArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let limit = limit
+        .unwrap_or(DEFAULT_SLOW_EXECUTIONS_LIMIT)
+        .clamp(1, MAX_SLOW_EXECUTIONS_LIMIT);
+    let executions = st
+        .application
+        .list_slow_executions(identity, limit)
+        .await?
+        .into_iter()
+        .map(SerializedSlowExecution::try_from)
+        .collect::<anyhow::Result<_>>()?;
+    Ok(Json(ListSlowExecutionsResponse { executions }))
+}
+
+#[cfg(test)]
+mod tests {
+    use http::Request;
+    use runtime::prod::ProdRuntime;
+    use serde_json::{
+        json,
+        Value as JsonValue,
+    };
+
+    use crate::test_helpers::setup_backend_for_test;
+
+    #[convex_macro::prod_rt_test]
+    async fn test_list_slow_executions(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        let req = Request::builder()
+            .uri("/api/slow_executions?limit=10")
+            .method("GET")
+            .header("Authorization", backend.admin_auth_header.0.encode())
+            .body(axum::body::Body::empty())?;
+        let response: JsonValue = backend.expect_success(req).await?;
+        assert_eq!(response, json!({"executions": []}));
+        Ok(())
+    }
+}
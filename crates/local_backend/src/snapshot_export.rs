@@ -18,26 +18,40 @@ use common::{
     components::ComponentId,
     http::{
         extract::{
+            Json,
             Path,
             Query,
         },
         HttpResponseError,
     },
+    runtime::Runtime,
 };
 use either::Either;
 use errors::ErrorMetadata;
 use http::StatusCode;
-use model::exports::types::{
-    ExportFormat,
-    ExportRequestor,
+use model::{
+    backup_schedule::types::BackupSchedule,
+    cron_jobs::types::SerializedCronSchedule,
+    export_watermarks::types::ExportWatermarkConfig,
+    exports::types::{
+        Export,
+        ExportFormat,
+        ExportRequestor,
+    },
+};
+use serde::{
+    Deserialize,
+    Serialize,
 };
-use serde::Deserialize;
 use storage::StorageGetStream;
 use sync_types::Timestamp;
 use value::DeveloperDocumentId;
 
 use crate::{
-    admin::must_be_admin_with_write_access,
+    admin::{
+        must_be_admin,
+        must_be_admin_with_write_access,
+    },
     authentication::ExtractIdentity,
     custom_headers::ContentDispositionAttachment,
     LocalAppState,
@@ -46,12 +60,22 @@ use crate::{
 // Export GETs are immutable. Browser can cache for a long time.
 const MAX_CACHE_AGE: Duration = Duration::from_secs(60 * 60 * 24 * 30);
 
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum RequestedExportFormat {
+    #[default]
+    Zip,
+    Parquet,
+}
+
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct RequestZipExport {
     #[serde(default)]
     pub include_storage: bool,
     pub component: Option<String>,
+    #[serde(default)]
+    pub format: RequestedExportFormat,
 }
 
 #[minitrace::trace]
@@ -61,14 +85,19 @@ pub async fn request_zip_export(
     Query(RequestZipExport {
         include_storage,
         component,
+        format,
     }): Query<RequestZipExport>,
 ) -> Result<impl IntoResponse, HttpResponseError> {
     must_be_admin_with_write_access(&identity)?;
     let component = ComponentId::deserialize_from_string(component.as_deref())?;
+    let format = match format {
+        RequestedExportFormat::Zip => ExportFormat::Zip { include_storage },
+        RequestedExportFormat::Parquet => ExportFormat::Parquet { include_storage },
+    };
     st.application
         .request_export(
             identity,
-            ExportFormat::Zip { include_storage },
+            format,
             component,
             ExportRequestor::SnapshotExport,
             None,
@@ -118,3 +147,288 @@ pub async fn get_zip_export(
         Body::from_stream(stream),
     ))
 }
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupScheduleResponse {
+    schedule: SerializedCronSchedule,
+    include_storage: bool,
+    retention_count: Option<u64>,
+    retention_age_secs: Option<u64>,
+    /// Decimal string, since timestamps don't fit in a JSON number.
+    next_backup_ts: String,
+}
+
+impl TryFrom<BackupSchedule> for BackupScheduleResponse {
+    type Error = anyhow::Error;
+
+    fn try_from(schedule: BackupSchedule) -> anyhow::Result<Self> {
+        Ok(Self {
+            schedule: schedule.schedule.try_into()?,
+            include_storage: schedule.include_storage,
+            retention_count: schedule.retention_count,
+            retention_age_secs: schedule.retention_age.map(|age| age.as_secs()),
+            next_backup_ts: u64::from(schedule.next_backup_ts).to_string(),
+        })
+    }
+}
+
+/// Returns the deployment's backup schedule, or `null` if scheduled backups
+/// are off.
+pub async fn get_backup_schedule(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let schedule = st
+        .application
+        .get_backup_schedule(identity)
+        .await?
+        .map(BackupScheduleResponse::try_from)
+        .transpose()?;
+    Ok(Json(schedule))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetBackupScheduleArgs {
+    /// When to take backups, e.g. `{"type": "daily", "hourUTC": 3,
+    /// "minuteUTC": 0}`. Turns scheduled backups off if missing.
+    schedule: Option<SerializedCronSchedule>,
+    #[serde(default)]
+    include_storage: bool,
+    /// Keep at most this many of the latest backups.
+    retention_count: Option<u64>,
+    /// Delete backups older than this.
+    retention_age_secs: Option<u64>,
+}
+
+pub async fn set_backup_schedule(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(SetBackupScheduleArgs {
+        schedule,
+        include_storage,
+        retention_count,
+        retention_age_secs,
+    }): Json<SetBackupScheduleArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let schedule = match schedule {
+        Some(schedule) => Some(BackupSchedule::new(
+            schedule.try_into().context(ErrorMetadata::bad_request(
+                "InvalidBackupSchedule",
+                "Invalid backup schedule",
+            ))?,
+            include_storage,
+            retention_count,
+            retention_age_secs.map(Duration::from_secs),
+            st.application.runtime().generate_timestamp()?,
+        )?),
+        None => None,
+    };
+    st.application
+        .set_backup_schedule(identity, schedule)
+        .await?;
+    Ok(StatusCode::OK)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupResponse {
+    id: String,
+    /// The snapshot timestamp, which can also be used to download the backup
+    /// with `/api/export/zip/:id`.
+    start_ts: String,
+    complete_ts: String,
+    include_storage: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListBackupsResponse {
+    backups: Vec<BackupResponse>,
+}
+
+/// Lists completed scheduled backups, newest first. Download them with
+/// `/api/export/zip/:id`.
+pub async fn list_backups(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let backups = st
+        .application
+        .list_scheduled_backups(identity)
+        .await?
+        .into_iter()
+        .filter_map(|backup| {
+            let id = DeveloperDocumentId::from(backup.id()).encode();
+            match backup.into_value() {
+                Export::Completed {
+                    start_ts,
+                    complete_ts,
+                    format,
+                    ..
+                } => Some(BackupResponse {
+                    id,
+                    start_ts: u64::from(start_ts).to_string(),
+                    complete_ts: u64::from(complete_ts).to_string(),
+                    include_storage: format.include_storage(),
+                }),
+                Export::Requested { .. } | Export::InProgress { .. } | Export::Failed { .. } => {
+                    None
+                },
+            }
+        })
+        .collect();
+    Ok(Json(ListBackupsResponse { backups }))
+}
+
+/// Deletes a completed or failed export, like a scheduled backup, along with
+/// its zip file.
+pub async fn delete_export(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Path(ZipExportRequest { id }): Path<ZipExportRequest>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let id: DeveloperDocumentId = id.parse().context(ErrorMetadata::bad_request(
+        "BadSnapshotId",
+        "Snapshot Id did not parse to an ID.",
+    ))?;
+    st.application.delete_export(identity, id).await?;
+    Ok(StatusCode::OK)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportWatermarkConfigResponse {
+    key_fingerprint: String,
+    label: String,
+    mode: String,
+}
+
+impl From<ExportWatermarkConfig> for ExportWatermarkConfigResponse {
+    fn from(config: ExportWatermarkConfig) -> Self {
+        Self {
+            key_fingerprint: config.key_fingerprint,
+            label: config.label,
+            mode: config.mode.to_string(),
+        }
+    }
+}
+
+/// Lists the admin keys whose exports are watermarked.
+pub async fn list_export_watermarks(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let configs: Vec<_> = st
+        .application
+        .list_export_watermark_configs(identity)
+        .await?
+        .into_iter()
+        .map(ExportWatermarkConfigResponse::from)
+        .collect();
+    Ok(Json(configs))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetExportWatermarkArgs {
+    /// The admin key whose exports to watermark. Only its fingerprint is
+    /// stored.
+    admin_key: String,
+    /// Who the key was issued to, reported when tracing a watermark.
+    label: String,
+    /// `"field"` or `"noise"`.
+    mode: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetExportWatermarkResponse {
+    key_fingerprint: String,
+}
+
+/// Watermarks exports requested with an admin key from now on, so data
+/// leaked from them can be traced back to the key.
+pub async fn set_export_watermark(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(SetExportWatermarkArgs {
+        admin_key,
+        label,
+        mode,
+    }): Json<SetExportWatermarkArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let key_fingerprint = st
+        .application
+        .set_export_watermark_config(identity, &admin_key, label, mode.parse()?)
+        .await?;
+    Ok(Json(SetExportWatermarkResponse { key_fingerprint }))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoveExportWatermarkArgs {
+    key_fingerprint: String,
+}
+
+pub async fn remove_export_watermark(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(RemoveExportWatermarkArgs { key_fingerprint }): Json<RemoveExportWatermarkArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let removed = st
+        .application
+        .remove_export_watermark_config(identity, &key_fingerprint)
+        .await?;
+    if !removed {
+        return Err(anyhow::anyhow!(ErrorMetadata::not_found(
+            "ExportWatermarkNotFound",
+            format!("Exports requested with key {key_fingerprint} aren't watermarked"),
+        ))
+        .into());
+    }
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize)]
+pub struct TraceExportWatermarkArgs {
+    token: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceExportWatermarkResponse {
+    export_id: String,
+    key_fingerprint: String,
+    label: String,
+    mode: String,
+}
+
+/// Finds the export, and the admin key that requested it, that a watermark
+/// token was given to. Returns `null` if no export has the token.
+pub async fn trace_export_watermark(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Query(TraceExportWatermarkArgs { token }): Query<TraceExportWatermarkArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let watermark = st
+        .application
+        .trace_export_watermark(identity, &token)
+        .await?
+        .map(|watermark| TraceExportWatermarkResponse {
+            export_id: watermark.export_id.encode(),
+            key_fingerprint: watermark.key_fingerprint,
+            label: watermark.label,
+            mode: watermark.mode.to_string(),
+        });
+    Ok(Json(watermark))
+}
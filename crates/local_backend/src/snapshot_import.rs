@@ -1,10 +1,18 @@
-use std::str::FromStr;
+use std::{
+    collections::{
+        BTreeMap,
+        BTreeSet,
+    },
+    str::FromStr,
+};
 
 use anyhow::Context;
 use application::snapshot_import::{
     self,
     do_import,
+    dry_run_import,
     upload_import_file,
+    ImportDryRunReport,
 };
 use axum::{
     body::Body,
@@ -12,6 +20,11 @@ use axum::{
     extract::State,
     response::IntoResponse,
 };
+use axum_extra::{
+    headers::ContentLength,
+    typed_header::TypedHeaderRejection,
+    TypedHeader,
+};
 use common::{
     components::ComponentPath,
     http::{
@@ -21,6 +34,7 @@ use common::{
         },
         HttpResponseError,
     },
+    sha256::DigestHeader,
 };
 use errors::ErrorMetadata;
 use futures::{
@@ -28,8 +42,10 @@ use futures::{
     TryStreamExt,
 };
 use model::snapshot_imports::types::{
+    CsvColumnType,
     ImportFormat,
     ImportMode,
+    ImportTableSelection,
 };
 use serde::{
     Deserialize,
@@ -38,15 +54,19 @@ use serde::{
 use storage::{
     ClientDrivenUploadPartToken,
     ClientDrivenUploadToken,
+    StorageGetStream,
 };
+use sync_types::Timestamp;
 use value::{
     id_v6::DeveloperDocumentId,
+    FieldName,
     TableName,
 };
 
 use crate::{
     admin::must_be_admin_with_write_access,
     authentication::ExtractIdentity,
+    custom_headers::ContentDispositionAttachment,
     LocalAppState,
 };
 
@@ -58,6 +78,28 @@ pub struct ImportQueryArgs {
     format: ImportFormatArg,
     #[serde(default)]
     mode: ImportMode,
+    /// JSON object mapping CSV column names to `CsvColumnType`s, e.g.
+    /// `{"age": "number", "signedUpAt": "date"}`.
+    csv_column_types: Option<String>,
+    /// JSON array of the tables to import, e.g. `["users", "_storage"]`.
+    /// Other tables in the import are skipped.
+    tables: Option<String>,
+    /// JSON object mapping tables in the import to the names to import them
+    /// as, e.g. `{"users": "users_staging"}`.
+    table_renames: Option<String>,
+    /// Give the imported documents new `_id`s instead of keeping the ones in
+    /// the import. Documents in renamed tables always get new `_id`s. The old
+    /// and new ids are listed in the import's id mapping report.
+    #[serde(default)]
+    regenerate_ids: bool,
+    /// Rewrite references to documents that get new `_id`s throughout the
+    /// imported tables. This reads the import an extra time.
+    #[serde(default)]
+    rewrite_references: bool,
+    /// Validate the import and report what it would do without writing
+    /// anything.
+    #[serde(default)]
+    dry_run: bool,
 }
 
 #[derive(Deserialize)]
@@ -90,9 +132,78 @@ struct ImportResponse {
     num_written: u64,
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportDryRunResponse {
+    tables: Vec<ImportDryRunTableResponse>,
+    conflicts: Vec<ImportDryRunConflictResponse>,
+    conflicts_truncated: bool,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportDryRunTableResponse {
+    component_path: String,
+    table_name: String,
+    action: String,
+    documents_to_import: u64,
+    existing_documents: u64,
+    documents_to_delete: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportDryRunConflictResponse {
+    component_path: String,
+    table_name: Option<String>,
+    row_number: Option<usize>,
+    message: String,
+}
+
+impl From<ImportDryRunReport> for ImportDryRunResponse {
+    fn from(report: ImportDryRunReport) -> Self {
+        Self {
+            tables: report
+                .tables
+                .into_iter()
+                .map(|table| ImportDryRunTableResponse {
+                    component_path: String::from(table.component_path),
+                    table_name: table.table_name.to_string(),
+                    action: table.action.as_ref().to_string(),
+                    documents_to_import: table.documents_to_import,
+                    existing_documents: table.existing_documents,
+                    documents_to_delete: table.documents_to_delete,
+                })
+                .collect(),
+            conflicts: report
+                .conflicts
+                .into_iter()
+                .map(|conflict| ImportDryRunConflictResponse {
+                    component_path: String::from(conflict.component_path),
+                    table_name: conflict.table_name.map(|table_name| table_name.to_string()),
+                    row_number: conflict.row_number,
+                    message: conflict.message,
+                })
+                .collect(),
+            conflicts_truncated: report.conflicts_truncated,
+        }
+    }
+}
+
+fn reject_dry_run(dry_run: bool) -> anyhow::Result<()> {
+    if dry_run {
+        anyhow::bail!(ErrorMetadata::bad_request(
+            "InvalidDryRun",
+            "Dry runs are only supported for single-request imports",
+        ));
+    }
+    Ok(())
+}
+
 fn parse_format_arg(
     table_name: Option<String>,
     format: ImportFormatArg,
+    csv_column_types: Option<String>,
 ) -> anyhow::Result<ImportFormat> {
     let table_name = table_name
         .map(|table_name| {
@@ -104,6 +215,12 @@ fn parse_format_arg(
             })
         })
         .transpose()?;
+    if csv_column_types.is_some() && format != ImportFormatArg::Csv {
+        anyhow::bail!(ErrorMetadata::bad_request(
+            "InvalidCsvColumnTypes",
+            "Column types are only supported for CSV imports",
+        ));
+    }
     let inner_format = match format {
         ImportFormatArg::Zip => {
             if table_name.is_some() {
@@ -114,9 +231,13 @@ fn parse_format_arg(
             }
             ImportFormat::Zip
         },
-        ImportFormatArg::Csv => ImportFormat::Csv(table_name.context(
-            ErrorMetadata::bad_request("InvalidName", "CSV import requires table name"),
-        )?),
+        ImportFormatArg::Csv => ImportFormat::Csv(
+            table_name.context(ErrorMetadata::bad_request(
+                "InvalidName",
+                "CSV import requires table name",
+            ))?,
+            parse_csv_column_types(csv_column_types)?,
+        ),
         ImportFormatArg::JsonArray => ImportFormat::JsonArray(table_name.context(
             ErrorMetadata::bad_request("InvalidName", "JSON import requires table name"),
         )?),
@@ -127,6 +248,78 @@ fn parse_format_arg(
     Ok(inner_format)
 }
 
+fn parse_csv_column_types(
+    csv_column_types: Option<String>,
+) -> anyhow::Result<BTreeMap<FieldName, CsvColumnType>> {
+    let Some(csv_column_types) = csv_column_types else {
+        return Ok(BTreeMap::new());
+    };
+    let column_types: BTreeMap<String, CsvColumnType> = serde_json::from_str(&csv_column_types)
+        .map_err(|e| {
+            ErrorMetadata::bad_request(
+                "InvalidCsvColumnTypes",
+                format!("invalid CSV column types {csv_column_types}: {e}"),
+            )
+        })?;
+    column_types
+        .into_iter()
+        .map(|(column, column_type)| {
+            let field_name = column.parse().map_err(|e| {
+                ErrorMetadata::bad_request(
+                    "InvalidCsvColumnTypes",
+                    format!("CSV column {column:?} isn't a valid field name: {e}"),
+                )
+            })?;
+            Ok((field_name, column_type))
+        })
+        .collect()
+}
+
+fn parse_table_selection(
+    tables: Option<String>,
+    table_renames: Option<String>,
+) -> anyhow::Result<ImportTableSelection> {
+    let parse_table_name = |table_name: String| {
+        TableName::from_str(&table_name).map_err(|e| {
+            ErrorMetadata::bad_request(
+                "ImportInvalidName",
+                format!("invalid table name {table_name}: {e}"),
+            )
+        })
+    };
+    let allowlist = tables
+        .map(|tables| {
+            let tables: Vec<String> = serde_json::from_str(&tables).map_err(|e| {
+                ErrorMetadata::bad_request(
+                    "InvalidImportTables",
+                    format!("invalid tables {tables}: {e}"),
+                )
+            })?;
+            tables
+                .into_iter()
+                .map(parse_table_name)
+                .collect::<anyhow::Result<BTreeSet<_>>>()
+        })
+        .transpose()?;
+    let renames = match table_renames {
+        Some(table_renames) => {
+            let renames: BTreeMap<String, String> =
+                serde_json::from_str(&table_renames).map_err(|e| {
+                    ErrorMetadata::bad_request(
+                        "InvalidTableRename",
+                        format!("invalid table renames {table_renames}: {e}"),
+                    )
+                })?;
+            renames
+                .into_iter()
+                .map(|(from, to)| Ok((parse_table_name(from)?, parse_table_name(to)?)))
+                .collect::<anyhow::Result<_>>()?
+        },
+        None => BTreeMap::new(),
+    };
+    ImportTableSelection::new(allowlist, renames)
+}
+
 pub async fn import(
     State(st): State<LocalAppState>,
     ExtractIdentity(identity): ExtractIdentity,
@@ -135,26 +328,48 @@ pub async fn import(
         component_path,
         format,
         mode,
+        csv_column_types,
+        tables,
+        table_renames,
+        regenerate_ids,
+        rewrite_references,
+        dry_run,
     }): Query<ImportQueryArgs>,
     stream: Body,
 ) -> Result<impl IntoResponse, HttpResponseError> {
     must_be_admin_with_write_access(&identity)?;
-    let format = parse_format_arg(table_name, format)?;
+    let format = parse_format_arg(table_name, format, csv_column_types)?;
+    let table_selection = parse_table_selection(tables, table_renames)?
+        .with_id_options(regenerate_ids, rewrite_references);
     let component_path = ComponentPath::deserialize(component_path.as_deref())?;
     let body_stream = stream
         .into_data_stream()
         .map_err(anyhow::Error::from)
         .boxed();
+    if dry_run {
+        let report = dry_run_import(
+            &st.application,
+            identity,
+            format,
+            mode,
+            component_path,
+            table_selection,
+            body_stream,
+        )
+        .await?;
+        return Ok(Json(ImportDryRunResponse::from(report)).into_response());
+    }
     let num_written = do_import(
         &st.application,
         identity,
         format,
         mode,
         component_path,
+        table_selection,
         body_stream,
     )
     .await?;
-    Ok(Json(ImportResponse { num_written }))
+    Ok(Json(ImportResponse { num_written }).into_response())
 }
 
 #[derive(Serialize)]
@@ -219,13 +434,22 @@ pub async fn import_finish_upload(
                 component_path,
                 format,
                 mode,
+                csv_column_types,
+                tables,
+                table_renames,
+                regenerate_ids,
+                rewrite_references,
+                dry_run,
             },
         upload_token,
         part_tokens,
     }): Json<ImportFinishUploadArgs>,
 ) -> Result<impl IntoResponse, HttpResponseError> {
     must_be_admin_with_write_access(&identity)?;
-    let format = parse_format_arg(table_name, format)?;
+    reject_dry_run(dry_run)?;
+    let format = parse_format_arg(table_name, format, csv_column_types)?;
+    let table_selection = parse_table_selection(tables, table_renames)?
+        .with_id_options(regenerate_ids, rewrite_references);
     let component_path = ComponentPath::deserialize(component_path.as_deref())?;
     let import_id = st
         .application
@@ -234,6 +458,7 @@ pub async fn import_finish_upload(
             format,
             mode,
             component_path,
+            table_selection,
             ClientDrivenUploadToken(upload_token),
             part_tokens
                 .into_iter()
@@ -246,6 +471,164 @@ pub async fn import_finish_upload(
     }))
 }
 
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportStartArgs {
+    /// Resume this upload instead of starting a new one.
+    upload_id: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportStartResponse {
+    upload_id: String,
+    /// The chunks already received, which don't need to be sent again.
+    chunks: Vec<ImportUploadChunkResponse>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportUploadChunkResponse {
+    index: u32,
+    sha256: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportChunkArgs {
+    upload_id: String,
+    chunk_index: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportFinishArgs {
+    import: ImportQueryArgs,
+
+    upload_id: String,
+    num_chunks: u32,
+}
+
+fn parse_upload_id(upload_id: &str) -> anyhow::Result<DeveloperDocumentId> {
+    DeveloperDocumentId::decode(upload_id).context(ErrorMetadata::bad_request(
+        "InvalidImportUpload",
+        format!("invalid import upload id {upload_id}"),
+    ))
+}
+
+/// Start a resumable, chunked upload, or look up how far an interrupted one
+/// got when `uploadId` is passed.
+pub async fn import_start(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(ImportStartArgs { upload_id }): Json<ImportStartArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let upload_id = match upload_id {
+        Some(upload_id) => parse_upload_id(&upload_id)?,
+        None => snapshot_import::start_import_upload(&st.application, identity.clone()).await?,
+    };
+    let chunks = snapshot_import::import_upload_status(&st.application, identity, upload_id)
+        .await?
+        .into_iter()
+        .map(|chunk| ImportUploadChunkResponse {
+            index: chunk.index,
+            sha256: chunk.sha256.as_base64(),
+        })
+        .collect();
+    Ok(Json(ImportStartResponse {
+        upload_id: upload_id.encode(),
+        chunks,
+    }))
+}
+
+/// Upload one chunk of a resumable upload. The body must match the
+/// `Digest: sha-256=<base64>` header.
+pub async fn import_chunk(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Query(ImportChunkArgs {
+        upload_id,
+        chunk_index,
+    }): Query<ImportChunkArgs>,
+    sha256: Result<TypedHeader<DigestHeader>, TypedHeaderRejection>,
+    body_stream: Body,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let upload_id = parse_upload_id(&upload_id)?;
+    let Ok(TypedHeader(DigestHeader(sha256))) = sha256 else {
+        return Err(anyhow::anyhow!(ErrorMetadata::bad_request(
+            "InvalidImportChunk",
+            "Import chunks need a `Digest: sha-256=<base64>` header",
+        ))
+        .into());
+    };
+    let body_bytes = body_stream
+        .into_data_stream()
+        .map_ok(|chunk| chunk.to_vec())
+        .try_concat()
+        .await
+        .context(ErrorMetadata::bad_request(
+            "ImportFailed",
+            "failed to read request body",
+        ))?;
+    snapshot_import::upload_import_chunk(
+        &st.application,
+        identity,
+        upload_id,
+        chunk_index,
+        sha256,
+        body_bytes.into(),
+    )
+    .await?;
+    Ok(())
+}
+
+/// Assemble a resumable upload and prepare the import, like `/prepare_import`.
+pub async fn import_finish(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(ImportFinishArgs {
+        import:
+            ImportQueryArgs {
+                table_name,
+                component_path,
+                format,
+                mode,
+                csv_column_types,
+                tables,
+                table_renames,
+                regenerate_ids,
+                rewrite_references,
+                dry_run,
+            },
+        upload_id,
+        num_chunks,
+    }): Json<ImportFinishArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    reject_dry_run(dry_run)?;
+    let upload_id = parse_upload_id(&upload_id)?;
+    let format = parse_format_arg(table_name, format, csv_column_types)?;
+    let table_selection = parse_table_selection(tables, table_renames)?
+        .with_id_options(regenerate_ids, rewrite_references);
+    let component_path = ComponentPath::deserialize(component_path.as_deref())?;
+    let import_id = snapshot_import::finish_import_upload(
+        &st.application,
+        identity,
+        upload_id,
+        num_chunks,
+        format,
+        mode,
+        component_path,
+        table_selection,
+    )
+    .await?;
+    Ok(Json(PrepareImportResponse {
+        import_id: import_id.encode(),
+    }))
+}
+
 #[derive(Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct PrepareImportResponse {
@@ -260,11 +643,20 @@ pub async fn prepare_import(
         component_path,
         format,
         mode,
+        csv_column_types,
+        tables,
+        table_renames,
+        regenerate_ids,
+        rewrite_references,
+        dry_run,
     }): Query<ImportQueryArgs>,
     stream: Body,
 ) -> Result<impl IntoResponse, HttpResponseError> {
     must_be_admin_with_write_access(&identity)?;
-    let format = parse_format_arg(table_name, format)?;
+    reject_dry_run(dry_run)?;
+    let format = parse_format_arg(table_name, format, csv_column_types)?;
+    let table_selection = parse_table_selection(tables, table_renames)?
+        .with_id_options(regenerate_ids, rewrite_references);
     let component_path = ComponentPath::deserialize(component_path.as_deref())?;
     let body_stream = stream
         .into_data_stream()
@@ -276,6 +668,7 @@ pub async fn prepare_import(
         format,
         mode,
         component_path,
+        table_selection,
         body_stream,
     )
     .await?;
@@ -322,3 +715,89 @@ pub async fn cancel_import(
     snapshot_import::cancel_import(&st.application, identity, import_id).await?;
     Ok(())
 }
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportIdMappingArgs {
+    pub import_id: String,
+}
+
+/// Download the id mapping report of a completed import, which maps the old
+/// `_id` of each document that got a new one to its new `_id`.
+pub async fn import_id_mapping(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Query(ImportIdMappingArgs { import_id }): Query<ImportIdMappingArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let import_id = DeveloperDocumentId::decode(&import_id).context(ErrorMetadata::bad_request(
+        "InvalidImport",
+        format!("invalid import id {import_id}"),
+    ))?;
+    let (
+        StorageGetStream {
+            content_length,
+            stream,
+        },
+        filename,
+    ) = snapshot_import::import_id_mapping(&st.application, identity, import_id).await?;
+    Ok((
+        TypedHeader(ContentLength(content_length as u64)),
+        TypedHeader(ContentDispositionAttachment(filename)),
+        Body::from_stream(stream),
+    ))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreTablesArgs {
+    /// Nanoseconds since the Unix epoch as a decimal string.
+    ts: String,
+    /// Restore only this table. Otherwise every user table is restored.
+    table_name: Option<String>,
+    component_path: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RestoreTablesResponse {
+    num_restored: u64,
+}
+
+/// Restore user tables to their state at a past timestamp within the
+/// retention window, replacing their current documents.
+pub async fn restore_tables(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(RestoreTablesArgs {
+        ts,
+        table_name,
+        component_path,
+    }): Json<RestoreTablesArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let ts = ts
+        .parse::<u64>()
+        .ok()
+        .and_then(|ts| Timestamp::try_from(ts).ok())
+        .ok_or_else(|| {
+            anyhow::anyhow!(ErrorMetadata::bad_request(
+                "InvalidTimestamp",
+                format!("Invalid timestamp {ts}"),
+            ))
+        })?;
+    let component_path = ComponentPath::deserialize(component_path.as_deref())?;
+    let table = table_name
+        .map(|table_name| {
+            let table_name = TableName::from_str(&table_name).map_err(|e| {
+                ErrorMetadata::bad_request(
+                    "InvalidTableName",
+                    format!("invalid table name {table_name}: {e}"),
+                )
+            })?;
+            anyhow::Ok((component_path, table_name))
+        })
+        .transpose()?;
+    let num_restored = st.application.restore_tables(&identity, ts, table).await?;
+    Ok(Json(RestoreTablesResponse { num_restored }))
+}
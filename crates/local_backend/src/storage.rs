@@ -1,11 +1,15 @@
 use std::{
-    ops::Bound,
+    collections::BTreeMap,
     time::Duration,
 };
 
 use anyhow::Context;
+use application::file_storage_upload::FileUploadStatus;
 use axum::{
-    body::Body,
+    body::{
+        Body,
+        Bytes,
+    },
     debug_handler,
     extract::{
         Host,
@@ -21,6 +25,7 @@ use axum_extra::{
         AcceptRanges,
         CacheControl,
         ContentLength,
+        ContentRange,
         ContentType,
         Header,
         Range,
@@ -47,24 +52,51 @@ use common::{
 };
 use errors::ErrorMetadata;
 use file_storage::{
+    image_transform::ImageTransform,
+    FileRange,
     FileRangeStream,
     FileStream,
 };
-use futures::StreamExt;
-use http::StatusCode;
+use futures::{
+    stream::BoxStream,
+    StreamExt,
+};
+use http::{
+    HeaderMap,
+    HeaderName,
+    StatusCode,
+};
+use keybroker::StoreFileConstraints;
 use model::file_storage::FileStorageId;
 use serde::{
     Deserialize,
     Serialize,
 };
+use value::DeveloperDocumentId;
 
-use crate::RouterState;
+use crate::{
+    custom_headers::ContentDispositionFilename,
+    RouterState,
+};
 
 // Storage GETs are immutable. Browser can cache for a long time.
 const MAX_CACHE_AGE: Duration = Duration::from_secs(60 * 60 * 24 * 30);
 
 const STORE_FILE_AUTHORIZATION_VALIDITY: Duration = Duration::from_secs(60 * 60);
 
+/// A resumable upload has to start within `STORE_FILE_AUTHORIZATION_VALIDITY`
+/// of its upload URL being generated, but large files can take much longer
+/// than that to send, so the same token keeps working for the rest of it.
+const RESUMABLE_UPLOAD_AUTHORIZATION_VALIDITY: Duration = Duration::from_secs(60 * 60 * 24);
+
+/// The number of bytes of a resumable upload received so far.
+pub const UPLOAD_OFFSET_HEADER: HeaderName = HeaderName::from_static("upload-offset");
+/// The total size of a resumable upload's file, if the client knows it.
+pub const UPLOAD_LENGTH_HEADER: HeaderName = HeaderName::from_static("upload-length");
+/// Tags to set on an uploaded file, as a URL-encoded query string like
+/// `kind=avatar&user=abc`.
+pub const STORAGE_TAGS_HEADER: HeaderName = HeaderName::from_static("convex-storage-tags");
+
 fn map_header_err<T: Header>(
     r: Result<TypedHeader<T>, TypedHeaderRejection>,
 ) -> anyhow::Result<Option<T>> {
@@ -78,11 +110,70 @@ fn map_header_err<T: Header>(
     })
 }
 
+fn parse_u64_header(headers: &HeaderMap, name: &HeaderName) -> anyhow::Result<Option<u64>> {
+    headers
+        .get(name)
+        .map(|value| {
+            value
+                .to_str()
+                .ok()
+                .and_then(|value| value.parse().ok())
+                .context(ErrorMetadata::bad_request(
+                    "BadHeader",
+                    format!("Bad header for {name}: expected a number of bytes"),
+                ))
+        })
+        .transpose()
+}
+
+fn parse_storage_tags(headers: &HeaderMap) -> anyhow::Result<BTreeMap<String, String>> {
+    let Some(value) = headers.get(STORAGE_TAGS_HEADER) else {
+        return Ok(BTreeMap::new());
+    };
+    let bad_header = || {
+        ErrorMetadata::bad_request(
+            "BadHeader",
+            format!(
+                "Bad header for {STORAGE_TAGS_HEADER}: expected URL-encoded tags like \
+                 \"kind=avatar&user=abc\""
+            ),
+        )
+    };
+    let value = value.to_str().with_context(bad_header)?;
+    let mut tags = BTreeMap::new();
+    for (key, value) in url::form_urlencoded::parse(value.as_bytes()) {
+        anyhow::ensure!(
+            tags.insert(key.into_owned(), value.into_owned()).is_none(),
+            bad_header()
+        );
+    }
+    Ok(tags)
+}
+
 #[derive(Deserialize)]
 pub struct QueryParams {
     token: String,
 }
 
+/// Fail the upload once its body is larger than the upload URL allows, since
+/// clients can leave out `Content-Length` or send the wrong one.
+fn limit_upload_size(
+    body: BoxStream<'static, anyhow::Result<Bytes>>,
+    constraints: StoreFileConstraints,
+) -> BoxStream<'static, anyhow::Result<Bytes>> {
+    if constraints.max_bytes.is_none() {
+        return body;
+    }
+    let mut size = 0;
+    body.map(move |chunk| {
+        let chunk = chunk?;
+        size += chunk.len() as u64;
+        constraints.check_size(size)?;
+        Ok(chunk)
+    })
+    .boxed()
+}
+
 #[debug_handler]
 pub async fn storage_upload(
     State(st): State<RouterState>,
@@ -90,12 +181,14 @@ pub async fn storage_upload(
     content_type: Result<TypedHeader<ContentType>, TypedHeaderRejection>,
     content_length: Result<TypedHeader<ContentLength>, TypedHeaderRejection>,
     sha256: Result<TypedHeader<DigestHeader>, TypedHeaderRejection>,
+    filename: Result<TypedHeader<ContentDispositionFilename>, TypedHeaderRejection>,
+    headers: HeaderMap,
     ExtractResolvedHostname(host): ExtractResolvedHostname,
     Host(original_host): Host,
     ExtractRequestId(request_id): ExtractRequestId,
     body: Body,
 ) -> Result<impl IntoResponse, HttpResponseError> {
-    let component = st
+    let (component, constraints) = st
         .api
         .check_store_file_authorization(
             &host,
@@ -106,11 +199,18 @@ pub async fn storage_upload(
         .await?;
     let content_length = map_header_err(content_length)?;
     let content_type = map_header_err(content_type)?;
+    constraints.check_content_type(content_type.as_ref().map(|ct| ct.to_string()).as_deref())?;
+    if let Some(ContentLength(content_length)) = content_length {
+        constraints.check_size(content_length)?;
+    }
     let sha256 = map_header_err(sha256)?.map(|dh| dh.0);
+    let filename = map_header_err(filename)?.map(|f| f.0);
+    let tags = parse_storage_tags(&headers)?;
     let body = body
         .into_data_stream()
         .map(|r| r.context("Error parsing body"))
         .boxed();
+    let body = limit_upload_size(body, constraints);
     let origin = original_host.into();
     let storage_id = st
         .api
@@ -122,6 +222,8 @@ pub async fn storage_upload(
             content_length,
             content_type,
             sha256,
+            filename,
+            tags,
             body,
         )
         .await?;
@@ -139,13 +241,33 @@ pub async fn storage_upload(
 #[derive(Deserialize)]
 pub struct GetQueryParams {
     component: Option<String>,
+    // Image transform parameters.
+    w: Option<u32>,
+    h: Option<u32>,
+    fit: Option<String>,
+    format: Option<String>,
+}
+
+impl GetQueryParams {
+    fn image_transform(&self) -> anyhow::Result<Option<ImageTransform>> {
+        if self.w.is_none() && self.h.is_none() && self.fit.is_none() && self.format.is_none() {
+            return Ok(None);
+        }
+        let transform = ImageTransform::new(
+            self.w,
+            self.h,
+            self.fit.as_deref().map(str::parse).transpose()?,
+            self.format.as_deref().map(str::parse).transpose()?,
+        )?;
+        Ok(Some(transform))
+    }
 }
 
 #[debug_handler]
 pub async fn storage_get(
     State(st): State<RouterState>,
     Path(uuid): Path<String>,
-    Query(GetQueryParams { component }): Query<GetQueryParams>,
+    Query(params): Query<GetQueryParams>,
     range: Result<TypedHeader<Range>, TypedHeaderRejection>,
     ExtractResolvedHostname(host): ExtractResolvedHostname,
     Host(original_host): Host,
@@ -156,43 +278,80 @@ pub async fn storage_get(
         format!("Invalid storage path: \"{uuid}\". Please use `storage.getUrl()` to generate a valid URL to retrieve files. See https://docs.convex.dev/file-storage/serve-files for more details"),
     ))?;
     let file_storage_id = FileStorageId::LegacyStorageId(storage_uuid);
-    let component = ComponentId::deserialize_from_string(component.as_deref())?;
+    let component = ComponentId::deserialize_from_string(params.component.as_deref())?;
     let origin = original_host.into();
 
     // TODO(CX-3065) figure out deterministic repeatable tokens
 
-    if let Ok(range_header) = range {
-        let ranges: Vec<(Bound<u64>, Bound<u64>)> = range_header
-            .satisfiable_ranges(
-                u64::MAX, /* technically, we should pass in the length of the file to protect
-                           * against inputs that are too large, but it's a
-                           * bit tricky to get at this point */
+    // Transformed images are generated whole, so they don't support ranges.
+    if let Some(transform) = params.image_transform()? {
+        let FileStream {
+            sha256,
+            content_type,
+            content_length,
+            filename,
+            stream,
+        } = st
+            .api
+            .get_transformed_file(
+                &host,
+                request_id,
+                origin,
+                component,
+                file_storage_id,
+                transform,
             )
-            .collect();
-        // Convex only supports a single range because underlying AWS S3 only supports
-        // a single range
-        if ranges.len() != 1 {
-            // It's always allowable to return the whole file
-            // so we could do that here too.
-            return Ok(StatusCode::RANGE_NOT_SATISFIABLE.into_response());
-        }
-        let range = ranges[0];
+            .await?;
+        return Ok((
+            TypedHeader(DigestHeader(sha256)),
+            content_type.map(TypedHeader),
+            TypedHeader(content_length),
+            filename.map(|f| TypedHeader(ContentDispositionFilename(f))),
+            TypedHeader(
+                CacheControl::new()
+                    .with_private()
+                    .with_max_age(MAX_CACHE_AGE),
+            ),
+            Body::from_stream(stream),
+        )
+            .into_response());
+    }
 
+    // Convex only serves a single range because underlying AWS S3 only supports
+    // a single range. It's always allowable to return the whole file instead,
+    // so do that when there are several.
+    let range = range
+        .ok()
+        .filter(|TypedHeader(range)| range.satisfiable_ranges(u64::MAX).count() == 1);
+    if let Some(TypedHeader(range)) = range {
+        let file_range = st
+            .api
+            .get_file_range(&host, request_id, origin, component, file_storage_id, range)
+            .await?;
         let FileRangeStream {
             content_length,
             content_range,
             content_type,
+            filename,
             stream,
-        } = st
-            .api
-            .get_file_range(&host, request_id, origin, component, file_storage_id, range)
-            .await?;
+        } = match file_range {
+            FileRange::Satisfiable(stream) => stream,
+            FileRange::Unsatisfiable { size } => {
+                return Ok((
+                    StatusCode::RANGE_NOT_SATISFIABLE,
+                    TypedHeader(ContentRange::unsatisfied_bytes(size)),
+                    TypedHeader(AcceptRanges::bytes()),
+                )
+                    .into_response());
+            },
+        };
 
         return Ok((
             StatusCode::PARTIAL_CONTENT,
             content_type.map(TypedHeader),
             TypedHeader(content_range),
             TypedHeader(content_length),
+            filename.map(|f| TypedHeader(ContentDispositionFilename(f))),
             TypedHeader(
                 CacheControl::new()
                     .with_private()
@@ -208,6 +367,7 @@ pub async fn storage_get(
         sha256,
         content_type,
         content_length,
+        filename,
         stream,
     } = st
         .api
@@ -217,6 +377,7 @@ pub async fn storage_get(
         TypedHeader(DigestHeader(sha256)),
         content_type.map(TypedHeader),
         TypedHeader(content_length),
+        filename.map(|f| TypedHeader(ContentDispositionFilename(f))),
         TypedHeader(
             CacheControl::new()
                 .with_private()
@@ -227,3 +388,234 @@ pub async fn storage_get(
     )
         .into_response())
 }
+
+#[derive(Deserialize)]
+pub struct FileUploadPath {
+    upload_id: String,
+}
+
+fn parse_upload_id(upload_id: &str) -> anyhow::Result<DeveloperDocumentId> {
+    DeveloperDocumentId::decode(upload_id).context(ErrorMetadata::bad_request(
+        "InvalidUploadId",
+        format!("Invalid upload id {upload_id}"),
+    ))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct FileUploadStatusResponse {
+    offset: u64,
+    upload_length: Option<u64>,
+}
+
+fn file_upload_status_response(status: FileUploadStatus) -> impl IntoResponse {
+    let mut headers = HeaderMap::new();
+    headers.insert(UPLOAD_OFFSET_HEADER, status.offset.into());
+    if let Some(upload_length) = status.upload_length {
+        headers.insert(UPLOAD_LENGTH_HEADER, upload_length.into());
+    }
+    (
+        headers,
+        TypedHeader(CacheControl::new().with_no_store()),
+        Json(FileUploadStatusResponse {
+            offset: status.offset,
+            upload_length: status.upload_length,
+        }),
+    )
+}
+
+/// Start a resumable upload with the token from an upload URL. The client
+/// then appends the file in chunks with `storage_upload_chunk` and finishes
+/// with `storage_finish_upload`, passing the same token to each.
+#[debug_handler]
+pub async fn storage_start_upload(
+    State(st): State<RouterState>,
+    Query(QueryParams { token }): Query<QueryParams>,
+    content_type: Result<TypedHeader<ContentType>, TypedHeaderRejection>,
+    headers: HeaderMap,
+    ExtractResolvedHostname(host): ExtractResolvedHostname,
+    ExtractRequestId(request_id): ExtractRequestId,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    let (component, constraints) = st
+        .api
+        .check_store_file_authorization(
+            &host,
+            request_id.clone(),
+            &token,
+            STORE_FILE_AUTHORIZATION_VALIDITY,
+        )
+        .await?;
+    let content_type = map_header_err(content_type)?;
+    constraints.check_content_type(content_type.as_ref().map(|ct| ct.to_string()).as_deref())?;
+    let upload_length = parse_u64_header(&headers, &UPLOAD_LENGTH_HEADER)?;
+    // The upload's chunks can't add up to more than its length, so enforcing
+    // the size limit on the length covers the whole upload.
+    if constraints.max_bytes.is_some() {
+        let upload_length = upload_length.context(ErrorMetadata::bad_request(
+            "MissingUploadLength",
+            format!("This upload URL requires the {UPLOAD_LENGTH_HEADER} header"),
+        ))?;
+        constraints.check_size(upload_length)?;
+    }
+    let upload_id = st
+        .api
+        .start_file_upload(&host, request_id, component, content_type, upload_length)
+        .await?;
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Response {
+        upload_id: String,
+    }
+    Ok((
+        StatusCode::CREATED,
+        Json(Response {
+            upload_id: upload_id.to_string(),
+        }),
+    ))
+}
+
+/// The upload's offset, which is where a client resuming it should
+/// continue from.
+#[debug_handler]
+pub async fn storage_upload_status(
+    State(st): State<RouterState>,
+    Path(FileUploadPath { upload_id }): Path<FileUploadPath>,
+    Query(QueryParams { token }): Query<QueryParams>,
+    ExtractResolvedHostname(host): ExtractResolvedHostname,
+    ExtractRequestId(request_id): ExtractRequestId,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    let (component, _) = st
+        .api
+        .check_store_file_authorization(
+            &host,
+            request_id.clone(),
+            &token,
+            RESUMABLE_UPLOAD_AUTHORIZATION_VALIDITY,
+        )
+        .await?;
+    let upload_id = parse_upload_id(&upload_id)?;
+    let status = st
+        .api
+        .file_upload_status(&host, request_id, component, upload_id)
+        .await?;
+    Ok(file_upload_status_response(status))
+}
+
+/// Append the body to the upload. The `Upload-Offset` header must match the
+/// upload's current offset.
+#[debug_handler]
+pub async fn storage_upload_chunk(
+    State(st): State<RouterState>,
+    Path(FileUploadPath { upload_id }): Path<FileUploadPath>,
+    Query(QueryParams { token }): Query<QueryParams>,
+    headers: HeaderMap,
+    ExtractResolvedHostname(host): ExtractResolvedHostname,
+    ExtractRequestId(request_id): ExtractRequestId,
+    body: Body,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    let (component, _) = st
+        .api
+        .check_store_file_authorization(
+            &host,
+            request_id.clone(),
+            &token,
+            RESUMABLE_UPLOAD_AUTHORIZATION_VALIDITY,
+        )
+        .await?;
+    let upload_id = parse_upload_id(&upload_id)?;
+    let offset =
+        parse_u64_header(&headers, &UPLOAD_OFFSET_HEADER)?.context(ErrorMetadata::bad_request(
+            "MissingUploadOffset",
+            format!("Missing {UPLOAD_OFFSET_HEADER} header"),
+        ))?;
+    let body = body
+        .into_data_stream()
+        .map(|r| r.context("Error parsing body"))
+        .boxed();
+    let offset = st
+        .api
+        .append_file_upload_chunk(
+            &host,
+            request_id.clone(),
+            component,
+            upload_id,
+            offset,
+            body,
+        )
+        .await?;
+    let status = st
+        .api
+        .file_upload_status(&host, request_id, component, upload_id)
+        .await?;
+    Ok(file_upload_status_response(FileUploadStatus {
+        offset,
+        ..status
+    }))
+}
+
+/// Store the uploaded file after checking it against the `Digest` header,
+/// and return its storage ID.
+#[debug_handler]
+pub async fn storage_finish_upload(
+    State(st): State<RouterState>,
+    Path(FileUploadPath { upload_id }): Path<FileUploadPath>,
+    Query(QueryParams { token }): Query<QueryParams>,
+    sha256: Result<TypedHeader<DigestHeader>, TypedHeaderRejection>,
+    ExtractResolvedHostname(host): ExtractResolvedHostname,
+    ExtractRequestId(request_id): ExtractRequestId,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    let (component, _) = st
+        .api
+        .check_store_file_authorization(
+            &host,
+            request_id.clone(),
+            &token,
+            RESUMABLE_UPLOAD_AUTHORIZATION_VALIDITY,
+        )
+        .await?;
+    let upload_id = parse_upload_id(&upload_id)?;
+    let sha256 = map_header_err(sha256)?
+        .context(ErrorMetadata::bad_request(
+            "MissingDigest",
+            "Finishing an upload requires a Digest header with the file's sha-256",
+        ))?
+        .0;
+    let storage_id = st
+        .api
+        .finish_file_upload(&host, request_id, component, upload_id, sha256)
+        .await?;
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Response {
+        storage_id: String,
+    }
+    Ok(Json(Response {
+        storage_id: storage_id.to_string(),
+    }))
+}
+
+#[debug_handler]
+pub async fn storage_cancel_upload(
+    State(st): State<RouterState>,
+    Path(FileUploadPath { upload_id }): Path<FileUploadPath>,
+    Query(QueryParams { token }): Query<QueryParams>,
+    ExtractResolvedHostname(host): ExtractResolvedHostname,
+    ExtractRequestId(request_id): ExtractRequestId,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    let (component, _) = st
+        .api
+        .check_store_file_authorization(
+            &host,
+            request_id.clone(),
+            &token,
+            RESUMABLE_UPLOAD_AUTHORIZATION_VALIDITY,
+        )
+        .await?;
+    let upload_id = parse_upload_id(&upload_id)?;
+    st.api
+        .cancel_file_upload(&host, request_id, component, upload_id)
+        .await?;
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,111 @@
+use std::time::Duration;
+
+use application::storage_gc::{
+    StorageGcOptions,
+    StorageGcReport,
+};
+use axum::{
+    extract::State,
+    response::IntoResponse,
+};
+use common::{
+    components::ComponentId,
+    http::{
+        extract::Json,
+        HttpResponseError,
+    },
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    admin::{
+        must_be_admin,
+        must_be_admin_with_write_access,
+    },
+    authentication::ExtractIdentity,
+    LocalAppState,
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageGcArgs {
+    component: Option<String>,
+    /// Delete the orphaned files instead of only reporting them.
+    #[serde(default)]
+    delete: bool,
+    min_file_age_secs: Option<u64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OrphanedFileJson {
+    storage_id: String,
+    size: i64,
+    content_type: Option<String>,
+    creation_time: f64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageGcResponse {
+    scanned_tables: Vec<String>,
+    total_files: u64,
+    orphaned_files: Vec<OrphanedFileJson>,
+    reclaimable_bytes: u64,
+    deleted_files: u64,
+}
+
+impl From<StorageGcReport> for StorageGcResponse {
+    fn from(report: StorageGcReport) -> Self {
+        Self {
+            scanned_tables: report
+                .scanned_tables
+                .into_iter()
+                .map(|table_name| table_name.to_string())
+                .collect(),
+            total_files: report.total_files,
+            orphaned_files: report
+                .orphaned_files
+                .into_iter()
+                .map(|file| OrphanedFileJson {
+                    storage_id: file.storage_id.encode(),
+                    size: file.size,
+                    content_type: file.content_type,
+                    creation_time: file.creation_time,
+                })
+                .collect(),
+            reclaimable_bytes: report.reclaimable_bytes,
+            deleted_files: report.deleted_files,
+        }
+    }
+}
+
+/// Report the files in storage that no document references, and how much
+/// space deleting them would reclaim. With `delete` set, also delete them.
+pub async fn storage_gc_report(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(args): Json<StorageGcArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    if args.delete {
+        must_be_admin_with_write_access(&identity)?;
+    } else {
+        must_be_admin(&identity)?;
+    }
+    let component = ComponentId::deserialize_from_string(args.component.as_deref())?;
+    let mut options = StorageGcOptions {
+        delete: args.delete,
+        ..Default::default()
+    };
+    if let Some(min_file_age_secs) = args.min_file_age_secs {
+        options.min_file_age = Duration::from_secs(min_file_age_secs);
+    }
+    let report = st
+        .application
+        .storage_gc_report(identity, component, options)
+        .await?;
+    Ok(Json(StorageGcResponse::from(report)))
+}
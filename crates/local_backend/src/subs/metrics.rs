@@ -72,6 +72,9 @@ pub fn log_websocket_message_out(message: &ServerMessage, delay: Duration) {
         ServerMessage::AuthError { .. } => "AuthError",
         ServerMessage::FatalError { .. } => "FatalError",
         ServerMessage::Ping { .. } => "Ping",
+        ServerMessage::Connected { .. } => "Connected",
+        ServerMessage::AggregateUpdated { .. } => "AggregateUpdated",
+        ServerMessage::AggregateFailed { .. } => "AggregateFailed",
     };
     let labels = vec![StaticMetricLabel::new("endpoint", endpoint)];
     log_distribution_with_labels(
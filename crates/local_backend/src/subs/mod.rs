@@ -47,7 +47,8 @@ use runtime::prod::ProdRuntime;
 use sentry::SentryFutureExt;
 use serde_json::Value as JsonValue;
 use sync::{
-    worker::measurable_unbounded_channel,
+    outgoing_channel,
+    serialize_server_message,
     ServerMessage,
     SyncWorker,
     SyncWorkerConfig,
@@ -189,7 +190,7 @@ async fn run_sync_socket(
         Ok(())
     };
 
-    let (server_tx, mut server_rx) = measurable_unbounded_channel();
+    let (server_tx, mut server_rx) = outgoing_channel();
     let send_messages = async {
         let _send_message_drop_token = DebugSyncSocketDropToken::new("send_message");
         let mut ping_ticker = tokio::time::interval(HEARTBEAT_INTERVAL);
@@ -215,7 +216,7 @@ async fn run_sync_socket(
                     };
                     let delay = st.runtime.monotonic_now() - send_time;
                     log_websocket_message_out(&message, delay);
-                    let serialized = serde_json::to_string(&JsonValue::from(message))?;
+                    let serialized = serialize_server_message(message)?;
                     if tx.send(Message::Text(serialized)).await.is_err() {
                         break 'top;
                     }
@@ -232,6 +233,7 @@ async fn run_sync_socket(
             st.runtime.clone(),
             host,
             config.clone(),
+            st.sync_sessions.clone(),
             client_rx,
             server_tx,
         );
@@ -277,7 +279,7 @@ async fn run_sync_socket(
             // Only do a best-effort send of the final application message.
             if let Some(final_message) = final_message {
                 let r: anyhow::Result<_> = try {
-                    let serialized = serde_json::to_string(&JsonValue::from(final_message))?;
+                    let serialized = serialize_server_message(final_message)?;
                     socket.send(Message::Text(serialized)).await?;
                 };
                 if let Err(mut e) = r {
@@ -326,7 +328,10 @@ async fn run_sync_socket(
 }
 
 fn new_sync_worker_config(client_version: ClientVersion) -> anyhow::Result<SyncWorkerConfig> {
-    Ok(SyncWorkerConfig { client_version })
+    Ok(SyncWorkerConfig {
+        client_version,
+        ..Default::default()
+    })
 }
 
 pub async fn sync_client_version_url(
@@ -0,0 +1,124 @@
+//! Exports sampled traces of requests, function executions and database
+//! operations to an OpenTelemetry collector, like Jaeger or Tempo, over
+//! OTLP/HTTP.
+use std::borrow::Cow;
+
+use anyhow::Context;
+use axum::response::IntoResponse;
+use common::{
+    http::{
+        extract::Json,
+        HttpResponseError,
+    },
+    knobs::{
+        OTEL_EXPORTER_OTLP_ENDPOINT,
+        OTEL_SERVICE_NAME,
+    },
+    minitrace_helpers::{
+        set_sampling_config,
+        SamplingConfig,
+    },
+};
+use errors::ErrorMetadata;
+use http::StatusCode;
+use minitrace_opentelemetry::OpenTelemetryReporter;
+use opentelemetry::{
+    trace::SpanKind,
+    InstrumentationLibrary,
+    KeyValue,
+};
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::Resource;
+use serde::Deserialize;
+
+use crate::{
+    admin::must_be_admin_with_write_access,
+    authentication::ExtractIdentity,
+};
+
+/// Starts exporting traces if `OTEL_EXPORTER_OTLP_ENDPOINT` is set. Spans are
+/// reported in the background, and [`minitrace::flush`] reports the rest.
+pub fn start_trace_export() -> anyhow::Result<()> {
+    let Some(endpoint) = OTEL_EXPORTER_OTLP_ENDPOINT.as_ref() else {
+        return Ok(());
+    };
+    let exporter = opentelemetry_otlp::new_exporter()
+        .http()
+        .with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')))
+        .build_span_exporter()
+        .context("Failed to build OTLP span exporter")?;
+    let reporter = OpenTelemetryReporter::new(
+        exporter,
+        SpanKind::Server,
+        Cow::Owned(Resource::new([KeyValue::new(
+            "service.name",
+            OTEL_SERVICE_NAME.clone(),
+        )])),
+        InstrumentationLibrary::new("convex-backend", None::<&str>, None::<&str>, None),
+    );
+    minitrace::set_reporter(reporter, minitrace::collector::Config::default());
+    tracing::info!("Exporting traces to {endpoint}");
+    Ok(())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetTraceSamplingArgs {
+    /// Either a JSON config like `{"routeOverrides": [{"routeRegexp":
+    /// "/api/query", "fraction": 0.5}], "defaultFraction": 0.01}`, or the
+    /// `REQUEST_TRACE_SAMPLE_CONFIG` format, like `/api/query=0.5,0.01`.
+    config: String,
+}
+
+/// Changes which requests are traced until the backend restarts, overriding
+/// `REQUEST_TRACE_SAMPLE_CONFIG`.
+pub async fn set_trace_sampling(
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(SetTraceSamplingArgs { config }): Json<SetTraceSamplingArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    config.parse::<SamplingConfig>().map_err(|e| {
+        e.context(ErrorMetadata::bad_request(
+            "InvalidTraceSamplingConfig",
+            "The trace sampling config is invalid",
+        ))
+    })?;
+    set_sampling_config(&config);
+    Ok(StatusCode::OK)
+}
+
+#[cfg(test)]
+mod tests {
+    use http::{
+        Request,
+        StatusCode,
+    };
+    use runtime::prod::ProdRuntime;
+    use serde_json::{
+        json,
+        Value as JsonValue,
+    };
+
+    use crate::test_helpers::setup_backend_for_test;
+
+    #[convex_macro::prod_rt_test]
+    async fn test_set_trace_sampling(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        let post = |body: JsonValue| {
+            Request::builder()
+                .uri("/api/trace_sampling")
+                .method("POST")
+                .header("Content-Type", "application/json")
+                .header("Authorization", backend.admin_auth_header.0.encode())
+                .body(axum::body::Body::from(serde_json::to_vec(&body).unwrap()))
+        };
+        let req = post(json!({"config": "/api/query=2.0=1.0"}))?;
+        backend
+            .expect_error(req, StatusCode::BAD_REQUEST, "InvalidTraceSamplingConfig")
+            .await?;
+
+        let req = post(json!({"config": r#"{"defaultFraction": 0.0}"#}))?;
+        backend.expect_success::<JsonValue>(req).await?;
+        Ok(())
+    }
+}
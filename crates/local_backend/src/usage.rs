@@ -0,0 +1,285 @@
+//! Reports the deployment's metered usage and configures its usage quotas.
+use axum::{
+    extract::State,
+    response::IntoResponse,
+};
+use common::http::{
+    extract::{
+        Json,
+        Query,
+    },
+    HttpResponseError,
+};
+use errors::ErrorMetadata;
+use http::StatusCode;
+use model::{
+    metrics_rollups::types::RollupGranularity,
+    usage_meters::types::UsageMeter,
+    usage_quotas::types::{
+        QuotaEnforcement,
+        UsageQuota,
+        UsageQuotaConfig,
+    },
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    admin::{
+        must_be_admin,
+        must_be_admin_with_write_access,
+    },
+    authentication::ExtractIdentity,
+    LocalAppState,
+};
+
+const DEFAULT_USAGE_DAYS: u64 = 30;
+const MAX_USAGE_DAYS: u64 = 400;
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListUsageArgs {
+    /// How many days of usage to return, including today.
+    days: Option<u64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageMeterJson {
+    /// Midnight UTC at the start of the day, in milliseconds since the epoch.
+    day: u64,
+    /// `null` for the whole deployment's usage.
+    function: Option<String>,
+    calls: u64,
+    database_read_bytes: u64,
+    database_write_bytes: u64,
+    storage_read_bytes: u64,
+    storage_write_bytes: u64,
+    action_compute_ms: u64,
+    vector_searches: u64,
+}
+
+impl TryFrom<UsageMeter> for UsageMeterJson {
+    type Error = anyhow::Error;
+
+    fn try_from(meter: UsageMeter) -> anyhow::Result<Self> {
+        let counters = meter.counters;
+        Ok(Self {
+            day: meter.day.as_ms_since_epoch()?,
+            function: meter.function,
+            calls: counters.calls,
+            database_read_bytes: counters.database_read_bytes,
+            database_write_bytes: counters.database_write_bytes,
+            storage_read_bytes: counters.storage_read_bytes,
+            storage_write_bytes: counters.storage_write_bytes,
+            action_compute_ms: counters.action_compute_ms,
+            vector_searches: counters.vector_searches,
+        })
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListUsageResponse {
+    /// Oldest first.
+    meters: Vec<UsageMeterJson>,
+}
+
+/// Returns each day's usage by function and for the whole deployment. Usage
+/// from the last few seconds may not be included yet.
+pub async fn list_usage(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Query(ListUsageArgs { days }): Query<ListUsageArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let days = days.unwrap_or(DEFAULT_USAGE_DAYS).clamp(1, MAX_USAGE_DAYS);
+    let day = RollupGranularity::Day;
+    let today = day.bucket_start(st.application.runtime().unix_timestamp());
+    let start = today - day.duration() * (days - 1) as u32;
+    let meters = st
+        .application
+        .list_usage_meters(identity, start, today + day.duration())
+        .await?
+        .into_iter()
+        .map(UsageMeterJson::try_from)
+        .collect::<anyhow::Result<_>>()?;
+    Ok(Json(ListUsageResponse { meters }))
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageQuotaConfigJson {
+    quotas: Vec<UsageQuotaJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageQuotaJson {
+    /// The function's path, like `"messages:send"`, or `null` to limit the
+    /// whole deployment.
+    function: Option<String>,
+    /// `"calls"`, `"databaseBandwidthBytes"`, `"storageBandwidthBytes"`,
+    /// `"actionComputeMs"` or `"vectorSearches"`.
+    metric: String,
+    /// The most usage allowed per day, which starts at midnight UTC.
+    limit: u64,
+    /// `"reject"` or `"throttle"`.
+    enforcement: String,
+    /// How many calls a minute are allowed once a throttled quota is used
+    /// up.
+    throttled_calls_per_minute: Option<u32>,
+}
+
+impl From<UsageQuotaConfig> for UsageQuotaConfigJson {
+    fn from(config: UsageQuotaConfig) -> Self {
+        Self {
+            quotas: config
+                .quotas
+                .into_iter()
+                .map(|quota| {
+                    let (enforcement, throttled_calls_per_minute) = match quota.enforcement {
+                        QuotaEnforcement::Reject => ("reject", None),
+                        QuotaEnforcement::Throttle { calls_per_minute } => {
+                            ("throttle", Some(calls_per_minute))
+                        },
+                    };
+                    UsageQuotaJson {
+                        function: quota.function,
+                        metric: quota.metric.to_string(),
+                        limit: quota.limit,
+                        enforcement: enforcement.to_string(),
+                        throttled_calls_per_minute,
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<UsageQuotaJson> for UsageQuota {
+    type Error = anyhow::Error;
+
+    fn try_from(quota: UsageQuotaJson) -> anyhow::Result<Self> {
+        let enforcement = match (quota.enforcement.as_str(), quota.throttled_calls_per_minute) {
+            ("reject", None) => QuotaEnforcement::Reject,
+            ("throttle", Some(calls_per_minute)) => QuotaEnforcement::Throttle { calls_per_minute },
+            _ => anyhow::bail!(ErrorMetadata::bad_request(
+                "InvalidUsageQuotaConfig",
+                "A quota's enforcement must be \"reject\", or \"throttle\" with \
+                 throttledCallsPerMinute set.",
+            )),
+        };
+        Ok(Self {
+            function: quota.function,
+            metric: quota.metric.parse()?,
+            limit: quota.limit,
+            enforcement,
+        })
+    }
+}
+
+/// Returns the deployment's usage quotas, or `null` if there aren't any.
+pub async fn get_usage_quotas(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let config = st
+        .application
+        .get_usage_quota_config(identity)
+        .await?
+        .map(UsageQuotaConfigJson::from);
+    Ok(Json(config))
+}
+
+/// Replaces the deployment's usage quotas. A `null` body removes them.
+pub async fn set_usage_quotas(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(args): Json<Option<UsageQuotaConfigJson>>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let config = args
+        .map(|args| {
+            let quotas = args
+                .quotas
+                .into_iter()
+                .map(UsageQuota::try_from)
+                .collect::<anyhow::Result<_>>()?;
+            UsageQuotaConfig::new(quotas)
+        })
+        .transpose()?;
+    st.application
+        .set_usage_quota_config(identity, config)
+        .await?;
+    Ok(StatusCode::OK)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use http::{
+        Request,
+        StatusCode,
+    };
+    use runtime::prod::ProdRuntime;
+    use serde_json::{
+        json,
+        Value as JsonValue,
+    };
+
+    use crate::test_helpers::setup_backend_for_test;
+
+    #[convex_macro::prod_rt_test]
+    async fn test_usage_quotas(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        let post = |body: JsonValue| {
+            Request::builder()
+                .uri("/api/usage_quotas")
+                .method("POST")
+                .header("Content-Type", "application/json")
+                .header("Authorization", backend.admin_auth_header.0.encode())
+                .body(Body::from(serde_json::to_vec(&body).unwrap()))
+        };
+        let get = || {
+            Request::builder()
+                .uri("/api/usage_quotas")
+                .method("GET")
+                .header("Authorization", backend.admin_auth_header.0.encode())
+                .body(Body::empty())
+        };
+
+        let req = post(json!({"quotas": [{
+            "function": null,
+            "metric": "calls",
+            "limit": 10,
+            "enforcement": "throttle",
+        }]}))?;
+        backend
+            .expect_error(req, StatusCode::BAD_REQUEST, "InvalidUsageQuotaConfig")
+            .await?;
+
+        let config = json!({"quotas": [{
+            "function": "messages:send",
+            "metric": "databaseBandwidthBytes",
+            "limit": 1_000_000,
+            "enforcement": "throttle",
+            "throttledCallsPerMinute": 10,
+        }]});
+        backend
+            .expect_success::<JsonValue>(post(config.clone())?)
+            .await?;
+        let stored: JsonValue = backend.expect_success(get()?).await?;
+        assert_eq!(stored, config);
+
+        backend
+            .expect_success::<JsonValue>(post(JsonValue::Null)?)
+            .await?;
+        let stored: JsonValue = backend.expect_success(get()?).await?;
+        assert_eq!(stored, JsonValue::Null);
+        Ok(())
+    }
+}
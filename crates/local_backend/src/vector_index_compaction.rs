@@ -0,0 +1,246 @@
+use std::str::FromStr;
+
+use application::vector_index_compaction::VectorIndexCompactionStatus;
+use axum::{
+    extract::State,
+    response::IntoResponse,
+};
+use common::{
+    components::ComponentId,
+    http::{
+        extract::{
+            Json,
+            Query,
+        },
+        HttpResponseError,
+    },
+    types::{
+        IndexName,
+        Timestamp,
+    },
+};
+use database::{
+    CompactionConfigOverrides,
+    ManualCompaction,
+};
+use http::StatusCode;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    admin::{
+        must_be_admin,
+        must_be_admin_with_write_access,
+    },
+    authentication::ExtractIdentity,
+    LocalAppState,
+};
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VectorIndexArgs {
+    component_id: Option<String>,
+    /// The index's table and name, e.g. `documents.by_embedding`.
+    index_name: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetVectorIndexCompactionConfigArgs {
+    component_id: Option<String>,
+    index_name: String,
+    /// Thresholds that aren't set use the compaction worker's defaults.
+    #[serde(flatten)]
+    overrides: CompactionConfigOverridesJson,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CompactionConfigOverridesJson {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    min_compaction_segments: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_deleted_percentage: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    small_segment_threshold_bytes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_segment_size_bytes: Option<u64>,
+}
+
+impl From<CompactionConfigOverridesJson> for CompactionConfigOverrides {
+    fn from(overrides: CompactionConfigOverridesJson) -> Self {
+        Self {
+            min_compaction_segments: overrides.min_compaction_segments,
+            max_deleted_percentage: overrides.max_deleted_percentage,
+            small_segment_threshold_bytes: overrides.small_segment_threshold_bytes,
+            max_segment_size_bytes: overrides.max_segment_size_bytes,
+        }
+    }
+}
+
+impl From<CompactionConfigOverrides> for CompactionConfigOverridesJson {
+    fn from(overrides: CompactionConfigOverrides) -> Self {
+        Self {
+            min_compaction_segments: overrides.min_compaction_segments,
+            max_deleted_percentage: overrides.max_deleted_percentage,
+            small_segment_threshold_bytes: overrides.small_segment_threshold_bytes,
+            max_segment_size_bytes: overrides.max_segment_size_bytes,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManualCompactionJson {
+    /// Milliseconds since the epoch.
+    requested_at: u64,
+    completed_at: Option<u64>,
+    segments_compacted: u64,
+}
+
+fn timestamp_ms(ts: Timestamp) -> u64 {
+    u64::from(ts) / 1_000_000
+}
+
+impl From<ManualCompaction> for ManualCompactionJson {
+    fn from(compaction: ManualCompaction) -> Self {
+        Self {
+            requested_at: timestamp_ms(compaction.requested_ts),
+            completed_at: compaction.completed_ts.map(timestamp_ms),
+            segments_compacted: compaction.segments_compacted,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VectorIndexCompactionStatusJson {
+    segments: u64,
+    vectors: u64,
+    deleted_vectors: u64,
+    overrides: CompactionConfigOverridesJson,
+    manual_compaction: Option<ManualCompactionJson>,
+}
+
+impl From<VectorIndexCompactionStatus> for VectorIndexCompactionStatusJson {
+    fn from(status: VectorIndexCompactionStatus) -> Self {
+        Self {
+            segments: status.segments,
+            vectors: status.vectors,
+            deleted_vectors: status.deleted_vectors,
+            overrides: status.overrides.into(),
+            manual_compaction: status.manual_compaction.map(ManualCompactionJson::from),
+        }
+    }
+}
+
+fn parse_vector_index_args(
+    component_id: Option<String>,
+    index_name: String,
+) -> anyhow::Result<(ComponentId, IndexName)> {
+    let component = ComponentId::deserialize_from_string(component_id.as_deref())?;
+    let index_name = IndexName::from_str(&index_name)?;
+    Ok((component, index_name))
+}
+
+/// Reports how many segments a vector index has, the compaction thresholds it
+/// overrides, and the progress of the last compaction requested for it.
+pub async fn get_vector_index_compaction_status(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Query(VectorIndexArgs {
+        component_id,
+        index_name,
+    }): Query<VectorIndexArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let (component, index_name) = parse_vector_index_args(component_id, index_name)?;
+    let status = st
+        .application
+        .vector_index_compaction_status(identity, component, index_name)
+        .await?;
+    Ok(Json(VectorIndexCompactionStatusJson::from(status)))
+}
+
+/// Overrides when the compaction worker merges a vector index's segments.
+pub async fn set_vector_index_compaction_config(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(SetVectorIndexCompactionConfigArgs {
+        component_id,
+        index_name,
+        overrides,
+    }): Json<SetVectorIndexCompactionConfigArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let (component, index_name) = parse_vector_index_args(component_id, index_name)?;
+    st.application
+        .set_vector_index_compaction_config(identity, component, index_name, overrides.into())
+        .await?;
+    Ok(StatusCode::OK)
+}
+
+/// Asks the compaction worker to merge a vector index's segments as far as it
+/// can. Poll the status endpoint to see when it's done.
+pub async fn compact_vector_index(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(VectorIndexArgs {
+        component_id,
+        index_name,
+    }): Json<VectorIndexArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let (component, index_name) = parse_vector_index_args(component_id, index_name)?;
+    let manual_compaction = st
+        .application
+        .compact_vector_index(identity, component, index_name)
+        .await?;
+    Ok(Json(ManualCompactionJson::from(manual_compaction)))
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum_extra::headers::authorization::Credentials;
+    use http::{
+        Request,
+        StatusCode,
+    };
+    use runtime::prod::ProdRuntime;
+    use serde_json::json;
+
+    use crate::test_helpers::setup_backend_for_test;
+
+    #[convex_macro::prod_rt_test]
+    async fn test_vector_index_compaction_requires_index(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        let req = Request::builder()
+            .uri("/api/vector_index_compaction/compact")
+            .method("POST")
+            .header("Authorization", backend.admin_auth_header.0.encode())
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_vec(&json!({
+                "indexName": "documents.by_embedding",
+            }))?))?;
+        backend
+            .expect_error(req, StatusCode::NOT_FOUND, "IndexNotFound")
+            .await?;
+
+        let req = Request::builder()
+            .uri("/api/vector_index_compaction")
+            .method("POST")
+            .header("Authorization", backend.admin_auth_header.0.encode())
+            .header("Content-Type", "application/json")
+            .body(Body::from(serde_json::to_vec(&json!({
+                "indexName": "documents.by_embedding",
+                "minCompactionSegments": 1,
+            }))?))?;
+        backend
+            .expect_error(req, StatusCode::BAD_REQUEST, "InvalidCompactionConfig")
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,433 @@
+//! Defines workflows, starts them, and reports their state.
+use std::time::Duration;
+
+use anyhow::Context;
+use axum::{
+    extract::State,
+    response::IntoResponse,
+};
+use common::{
+    document::ParsedDocument,
+    http::{
+        extract::{
+            Json,
+            Query,
+        },
+        HttpResponseError,
+    },
+};
+use errors::ErrorMetadata;
+use http::StatusCode;
+use model::workflows::types::{
+    Workflow,
+    WorkflowBranch,
+    WorkflowDefinition,
+    WorkflowState,
+    WorkflowStep,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::Value as JsonValue;
+use sync_types::CanonicalizedUdfPath;
+use value::DeveloperDocumentId;
+
+use crate::{
+    admin::{
+        must_be_admin,
+        must_be_admin_with_write_access,
+    },
+    authentication::ExtractIdentity,
+    LocalAppState,
+};
+
+const DEFAULT_WORKFLOWS_LIMIT: usize = 100;
+const MAX_WORKFLOWS_LIMIT: usize = 1000;
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowDefinitionJson {
+    name: String,
+    /// The workflow starts at the first step.
+    steps: Vec<WorkflowStepJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowStepJson {
+    name: String,
+    /// A mutation or action in the root component, like `"emails:send"`.
+    function: String,
+    /// Defaults to 1, which doesn't retry.
+    max_attempts: Option<u32>,
+    delay_ms: Option<u64>,
+    retry_delay_ms: Option<u64>,
+    #[serde(default)]
+    branches: Vec<WorkflowBranchJson>,
+    next: Option<String>,
+    on_failure: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowBranchJson {
+    /// Goes to `next` when the step's function returns this value.
+    when: JsonValue,
+    next: String,
+}
+
+impl TryFrom<WorkflowDefinition> for WorkflowDefinitionJson {
+    type Error = anyhow::Error;
+
+    fn try_from(definition: WorkflowDefinition) -> anyhow::Result<Self> {
+        let steps = definition
+            .steps
+            .into_iter()
+            .map(|step| {
+                anyhow::Ok(WorkflowStepJson {
+                    name: step.name,
+                    function: step.function.strip().to_string(),
+                    max_attempts: Some(step.max_attempts),
+                    delay_ms: step.delay.map(|delay| delay.as_millis() as u64),
+                    retry_delay_ms: step.retry_delay.map(|delay| delay.as_millis() as u64),
+                    branches: step
+                        .branches
+                        .into_iter()
+                        .map(|branch| {
+                            anyhow::Ok(WorkflowBranchJson {
+                                when: serde_json::from_str(&branch.when)?,
+                                next: branch.next,
+                            })
+                        })
+                        .collect::<anyhow::Result<_>>()?,
+                    next: step.next,
+                    on_failure: step.on_failure,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self {
+            name: definition.name,
+            steps,
+        })
+    }
+}
+
+impl TryFrom<WorkflowDefinitionJson> for WorkflowDefinition {
+    type Error = anyhow::Error;
+
+    fn try_from(definition: WorkflowDefinitionJson) -> anyhow::Result<Self> {
+        let steps = definition
+            .steps
+            .into_iter()
+            .map(|step| {
+                let function = step.function.parse::<CanonicalizedUdfPath>().context(
+                    ErrorMetadata::bad_request(
+                        "InvalidWorkflowDefinition",
+                        format!("{:?} isn't a valid function path.", step.function),
+                    ),
+                )?;
+                anyhow::Ok(WorkflowStep {
+                    name: step.name,
+                    function,
+                    max_attempts: step.max_attempts.unwrap_or(1),
+                    delay: step.delay_ms.map(Duration::from_millis),
+                    retry_delay: step.retry_delay_ms.map(Duration::from_millis),
+                    branches: step
+                        .branches
+                        .into_iter()
+                        .map(|branch| {
+                            anyhow::Ok(WorkflowBranch {
+                                when: serde_json::to_string(&branch.when)?,
+                                next: branch.next,
+                            })
+                        })
+                        .collect::<anyhow::Result<_>>()?,
+                    next: step.next,
+                    on_failure: step.on_failure,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+        WorkflowDefinition::new(definition.name, steps)
+    }
+}
+
+pub async fn list_workflow_definitions(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let definitions = st
+        .application
+        .list_workflow_definitions(identity)
+        .await?
+        .into_iter()
+        .map(WorkflowDefinitionJson::try_from)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(Json(definitions))
+}
+
+/// Creates or replaces the definition with the same name.
+pub async fn set_workflow_definition(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(args): Json<WorkflowDefinitionJson>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let definition = WorkflowDefinition::try_from(args)?;
+    st.application
+        .set_workflow_definition(identity, definition)
+        .await?;
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteWorkflowDefinitionArgs {
+    name: String,
+}
+
+pub async fn delete_workflow_definition(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(DeleteWorkflowDefinitionArgs { name }): Json<DeleteWorkflowDefinitionArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    st.application
+        .delete_workflow_definition(identity, name)
+        .await?;
+    Ok(StatusCode::OK)
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartWorkflowArgs {
+    name: String,
+    /// Passed to every step as `input`.
+    #[serde(default)]
+    input: JsonValue,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartWorkflowResponse {
+    id: String,
+}
+
+pub async fn start_workflow(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(StartWorkflowArgs { name, input }): Json<StartWorkflowArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let id = st.application.start_workflow(identity, name, input).await?;
+    Ok(Json(StartWorkflowResponse { id: id.encode() }))
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkflowJson {
+    id: String,
+    definition: String,
+    input: JsonValue,
+    /// `"running"`, `"succeeded"`, `"failed"` or `"canceled"`.
+    state: String,
+    /// Why the workflow failed.
+    error: Option<String>,
+    current_step: Option<String>,
+    attempt: u32,
+    /// The result of the last step that succeeded.
+    output: JsonValue,
+    last_error: Option<String>,
+    steps_run: u64,
+}
+
+impl TryFrom<ParsedDocument<Workflow>> for WorkflowJson {
+    type Error = anyhow::Error;
+
+    fn try_from(workflow: ParsedDocument<Workflow>) -> anyhow::Result<Self> {
+        let (id, workflow) = workflow.into_id_and_value();
+        let (state, error) = match workflow.state {
+            WorkflowState::Running => ("running", None),
+            WorkflowState::Succeeded => ("succeeded", None),
+            WorkflowState::Failed(error) => ("failed", Some(error)),
+            WorkflowState::Canceled => ("canceled", None),
+        };
+        Ok(Self {
+            id: DeveloperDocumentId::from(id).encode(),
+            definition: workflow.definition,
+            input: serde_json::from_str(&workflow.input)?,
+            state: state.to_string(),
+            error,
+            current_step: workflow.current_step,
+            attempt: workflow.attempt,
+            output: match workflow.output {
+                Some(output) => serde_json::from_str(&output)?,
+                None => JsonValue::Null,
+            },
+            last_error: workflow.last_error,
+            steps_run: workflow.steps_run,
+        })
+    }
+}
+
+fn parse_workflow_id(id: &str) -> anyhow::Result<DeveloperDocumentId> {
+    DeveloperDocumentId::decode(id).context(ErrorMetadata::bad_request(
+        "InvalidWorkflowId",
+        format!("{id:?} isn't a valid workflow ID."),
+    ))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListWorkflowsArgs {
+    /// Only returns this workflow.
+    id: Option<String>,
+    limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ListWorkflowsResponse {
+    /// Newest first.
+    workflows: Vec<WorkflowJson>,
+}
+
+/// Returns the most recently started workflows, or the one with `id`.
+pub async fn list_workflows(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Query(ListWorkflowsArgs { id, limit }): Query<ListWorkflowsArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin(&identity)?;
+    let workflows = match id {
+        Some(id) => {
+            let id = parse_workflow_id(&id)?;
+            st.application
+                .get_workflow(identity, id)
+                .await?
+                .into_iter()
+                .collect()
+        },
+        None => {
+            let limit = limit
+                .unwrap_or(DEFAULT_WORKFLOWS_LIMIT)
+                .clamp(1, MAX_WORKFLOWS_LIMIT);
+            st.application.list_workflows(identity, limit).await?
+        },
+    };
+    let workflows = workflows
+        .into_iter()
+        .map(WorkflowJson::try_from)
+        .collect::<anyhow::Result<_>>()?;
+    Ok(Json(ListWorkflowsResponse { workflows }))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancelWorkflowArgs {
+    id: String,
+}
+
+pub async fn cancel_workflow(
+    State(st): State<LocalAppState>,
+    ExtractIdentity(identity): ExtractIdentity,
+    Json(CancelWorkflowArgs { id }): Json<CancelWorkflowArgs>,
+) -> Result<impl IntoResponse, HttpResponseError> {
+    must_be_admin_with_write_access(&identity)?;
+    let id = parse_workflow_id(&id)?;
+    st.application.cancel_workflow(identity, id).await?;
+    Ok(StatusCode::OK)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use http::{
+        Request,
+        StatusCode,
+    };
+    use runtime::prod::ProdRuntime;
+    use serde_json::{
+        json,
+        Value as JsonValue,
+    };
+
+    use crate::test_helpers::setup_backend_for_test;
+
+    #[convex_macro::prod_rt_test]
+    async fn test_workflows(rt: ProdRuntime) -> anyhow::Result<()> {
+        let backend = setup_backend_for_test(rt).await?;
+        let post = |uri: &str, body: JsonValue| {
+            Request::builder()
+                .uri(uri)
+                .method("POST")
+                .header("Content-Type", "application/json")
+                .header("Authorization", backend.admin_auth_header.0.encode())
+                .body(Body::from(serde_json::to_vec(&body).unwrap()))
+        };
+        let get = |uri: &str| {
+            Request::builder()
+                .uri(uri)
+                .method("GET")
+                .header("Authorization", backend.admin_auth_header.0.encode())
+                .body(Body::empty())
+        };
+
+        let req = post(
+            "/api/workflow_definitions",
+            json!({"name": "backfill", "steps": [
+                {"name": "fetch", "function": "backfill:fetch", "next": "store"},
+            ]}),
+        )?;
+        backend
+            .expect_error(req, StatusCode::BAD_REQUEST, "InvalidWorkflowDefinition")
+            .await?;
+
+        let definition = json!({"name": "backfill", "steps": [{
+            "name": "fetch",
+            "function": "backfill:fetch",
+            "maxAttempts": 3,
+            "delayMs": 60_000,
+            "retryDelayMs": 1000,
+            "branches": [{"when": "done", "next": "notify"}],
+            "next": "fetch",
+            "onFailure": null,
+        }, {
+            "name": "notify",
+            "function": "backfill:notify",
+            "maxAttempts": 1,
+            "delayMs": null,
+            "retryDelayMs": null,
+            "branches": [],
+            "next": null,
+            "onFailure": null,
+        }]});
+        let req = post("/api/workflow_definitions", definition.clone())?;
+        backend.expect_success::<JsonValue>(req).await?;
+        let definitions: JsonValue = backend
+            .expect_success(get("/api/workflow_definitions")?)
+            .await?;
+        assert_eq!(definitions, json!([definition]));
+
+        let req = post(
+            "/api/workflows/start",
+            json!({"name": "backfill", "input": {"cursor": null}}),
+        )?;
+        let started: JsonValue = backend.expect_success(req).await?;
+        let id = started["id"].as_str().unwrap();
+        let listed: JsonValue = backend
+            .expect_success(get(&format!("/api/workflows?id={id}"))?)
+            .await?;
+        let workflow = &listed["workflows"][0];
+        assert_eq!(workflow["state"], "running");
+        assert_eq!(workflow["currentStep"], "fetch");
+        assert_eq!(workflow["attempt"], 1);
+
+        let req = post("/api/workflows/cancel", json!({"id": id}))?;
+        backend.expect_success::<JsonValue>(req).await?;
+        let listed: JsonValue = backend.expect_success(get("/api/workflows")?).await?;
+        assert_eq!(listed["workflows"][0]["state"], "canceled");
+        Ok(())
+    }
+}
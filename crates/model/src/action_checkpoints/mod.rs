@@ -0,0 +1,228 @@
+//! Checkpoints saved by scheduled actions, so an action that times out or
+//! whose process crashes is re-run from its last checkpoint instead of
+//! failing.
+use std::sync::LazyLock;
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+        CREATION_TIME_FIELD_PATH,
+    },
+    knobs::SCHEDULED_ACTION_MAX_RESUMES,
+    query::{
+        IndexRange,
+        IndexRangeExpression,
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+    types::IndexName,
+};
+use database::{
+    defaults::system_index,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use errors::ErrorMetadata;
+use value::{
+    ConvexValue,
+    DeveloperDocumentId,
+    FieldPath,
+    TableName,
+    TableNamespace,
+};
+
+use self::types::ActionCheckpoint;
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+/// The largest checkpoint an action can save, in bytes of JSON.
+pub const MAX_ACTION_CHECKPOINT_SIZE: usize = 1 << 19;
+
+pub static ACTION_CHECKPOINTS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_action_checkpoints"
+        .parse()
+        .expect("Invalid built-in action_checkpoints table")
+});
+
+pub static ACTION_CHECKPOINTS_INDEX_BY_JOB_ID: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&ACTION_CHECKPOINTS_TABLE, "by_job_id"));
+
+static JOB_ID_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "jobId".parse().expect("invalid jobId field"));
+
+pub struct ActionCheckpointsTable;
+impl SystemTable for ActionCheckpointsTable {
+    fn table_name(&self) -> &'static TableName {
+        &ACTION_CHECKPOINTS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![SystemIndex {
+            name: ACTION_CHECKPOINTS_INDEX_BY_JOB_ID.clone(),
+            fields: vec![JOB_ID_FIELD.clone(), CREATION_TIME_FIELD_PATH.clone()]
+                .try_into()
+                .unwrap(),
+        }]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<ActionCheckpoint>::try_from(document).map(|_| ())
+    }
+}
+
+/// Checkpoints are only written on behalf of the scheduler and the action
+/// that owns them, so this model doesn't check the identity.
+pub struct ActionCheckpointsModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> ActionCheckpointsModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    pub async fn get(
+        &mut self,
+        job_id: DeveloperDocumentId,
+    ) -> anyhow::Result<Option<ParsedDocument<ActionCheckpoint>>> {
+        let index_range = IndexRange {
+            index_name: ACTION_CHECKPOINTS_INDEX_BY_JOB_ID.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                JOB_ID_FIELD.clone(),
+                ConvexValue::try_from(job_id.encode())?.into(),
+            )],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        query_stream
+            .expect_at_most_one(self.tx)
+            .await?
+            .map(ParsedDocument::try_from)
+            .transpose()
+    }
+
+    /// Replaces the job's checkpoint with `value`, which is JSON.
+    pub async fn save(&mut self, job_id: DeveloperDocumentId, value: String) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            value.len() <= MAX_ACTION_CHECKPOINT_SIZE,
+            ErrorMetadata::bad_request(
+                "ActionCheckpointTooLarge",
+                format!(
+                    "Checkpoint is {} bytes, but the maximum is {MAX_ACTION_CHECKPOINT_SIZE} \
+                     bytes.",
+                    value.len()
+                ),
+            )
+        );
+        let saved_ts = *self.tx.begin_timestamp();
+        match self.get(job_id).await? {
+            Some(existing) => {
+                let (id, existing) = existing.into_id_and_value();
+                let checkpoint = ActionCheckpoint {
+                    value,
+                    saved_ts,
+                    progressed: true,
+                    ..existing
+                };
+                SystemMetadataModel::new_global(self.tx)
+                    .replace(id, checkpoint.try_into()?)
+                    .await?;
+            },
+            None => {
+                let checkpoint = ActionCheckpoint {
+                    job_id,
+                    value,
+                    saved_ts,
+                    resumes: 0,
+                    progressed: true,
+                };
+                SystemMetadataModel::new_global(self.tx)
+                    .insert(&ACTION_CHECKPOINTS_TABLE, checkpoint.try_into()?)
+                    .await?;
+            },
+        }
+        Ok(())
+    }
+
+    /// Records that the job is being re-run from its checkpoint. Returns
+    /// false if it shouldn't be: it never saved a checkpoint, it hasn't
+    /// saved one since it was last re-run, or it has been re-run
+    /// `SCHEDULED_ACTION_MAX_RESUMES` times already.
+    pub async fn resume(&mut self, job_id: DeveloperDocumentId) -> anyhow::Result<bool> {
+        let Some(existing) = self.get(job_id).await? else {
+            return Ok(false);
+        };
+        let (id, checkpoint) = existing.into_id_and_value();
+        if !checkpoint.progressed || checkpoint.resumes >= *SCHEDULED_ACTION_MAX_RESUMES {
+            return Ok(false);
+        }
+        let checkpoint = ActionCheckpoint {
+            resumes: checkpoint.resumes + 1,
+            progressed: false,
+            ..checkpoint
+        };
+        SystemMetadataModel::new_global(self.tx)
+            .replace(id, checkpoint.try_into()?)
+            .await?;
+        Ok(true)
+    }
+
+    pub async fn delete(&mut self, job_id: DeveloperDocumentId) -> anyhow::Result<()> {
+        if let Some(existing) = self.get(job_id).await? {
+            SystemMetadataModel::new_global(self.tx)
+                .delete(existing.id())
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::knobs::SCHEDULED_ACTION_MAX_RESUMES;
+    use database::test_helpers::DbFixtures;
+    use keybroker::Identity;
+    use runtime::testing::TestRuntime;
+    use value::DeveloperDocumentId;
+
+    use super::ActionCheckpointsModel;
+    use crate::test_helpers::DbFixturesWithModel;
+
+    #[convex_macro::test_runtime]
+    async fn test_action_checkpoints(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let job_id = DeveloperDocumentId::MIN;
+        let mut tx = db.begin(Identity::system()).await?;
+        let mut model = ActionCheckpointsModel::new(&mut tx);
+        assert!(!model.resume(job_id).await?);
+
+        model.save(job_id, "1".to_string()).await?;
+        assert!(model.resume(job_id).await?);
+        // It can't be re-run again until it saves another checkpoint.
+        assert!(!model.resume(job_id).await?);
+        model.save(job_id, "2".to_string()).await?;
+        let checkpoint = model.get(job_id).await?.unwrap().into_value();
+        assert_eq!(checkpoint.value, "2");
+        assert_eq!(checkpoint.resumes, 1);
+        for _ in 1..*SCHEDULED_ACTION_MAX_RESUMES {
+            assert!(model.resume(job_id).await?);
+            model.save(job_id, "3".to_string()).await?;
+        }
+        assert!(!model.resume(job_id).await?);
+
+        model.delete(job_id).await?;
+        assert!(model.get(job_id).await?.is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,72 @@
+use common::types::Timestamp;
+#[cfg(any(test, feature = "testing"))]
+use proptest::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use value::{
+    codegen_convex_serialization,
+    DeveloperDocumentId,
+};
+
+/// The progress a scheduled action saved so it can pick up where it left off
+/// if it times out or its process crashes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct ActionCheckpoint {
+    /// The `_scheduled_jobs` document for the action.
+    pub job_id: DeveloperDocumentId,
+    /// The JSON-encoded value the action last saved.
+    pub value: String,
+    pub saved_ts: Timestamp,
+    /// How many times the action has been re-run from a checkpoint.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=i64::MAX as u64")
+    )]
+    pub resumes: u64,
+    /// Whether the action saved a checkpoint since it was last re-run. An
+    /// action that keeps failing without making progress isn't re-run again.
+    pub progressed: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedActionCheckpoint {
+    job_id: String,
+    value: String,
+    saved_ts: i64,
+    resumes: i64,
+    progressed: bool,
+}
+
+impl TryFrom<ActionCheckpoint> for SerializedActionCheckpoint {
+    type Error = anyhow::Error;
+
+    fn try_from(checkpoint: ActionCheckpoint) -> anyhow::Result<Self> {
+        Ok(Self {
+            job_id: checkpoint.job_id.encode(),
+            value: checkpoint.value,
+            saved_ts: checkpoint.saved_ts.into(),
+            resumes: checkpoint.resumes.try_into()?,
+            progressed: checkpoint.progressed,
+        })
+    }
+}
+
+impl TryFrom<SerializedActionCheckpoint> for ActionCheckpoint {
+    type Error = anyhow::Error;
+
+    fn try_from(checkpoint: SerializedActionCheckpoint) -> anyhow::Result<Self> {
+        Ok(Self {
+            job_id: DeveloperDocumentId::decode(&checkpoint.job_id)?,
+            value: checkpoint.value,
+            saved_ts: checkpoint.saved_ts.try_into()?,
+            resumes: checkpoint.resumes.try_into()?,
+            progressed: checkpoint.progressed,
+        })
+    }
+}
+
+codegen_convex_serialization!(ActionCheckpoint, SerializedActionCheckpoint);
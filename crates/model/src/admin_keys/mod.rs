@@ -0,0 +1,279 @@
+use std::{
+    collections::BTreeSet,
+    sync::LazyLock,
+};
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    query::{
+        IndexRange,
+        IndexRangeExpression,
+        Order,
+        Query,
+    },
+    runtime::{
+        Runtime,
+        UnixTimestamp,
+    },
+    types::IndexName,
+};
+use database::{
+    defaults::system_index,
+    require_admin_or_system,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use value::{
+    ConvexValue,
+    FieldPath,
+    TableName,
+    TableNamespace,
+};
+
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+use types::{
+    AdminKeyMetadata,
+    AdminKeySecrets,
+};
+
+pub static ADMIN_KEYS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_admin_keys"
+        .parse()
+        .expect("Invalid built-in admin_keys table")
+});
+
+pub static ADMIN_KEYS_BY_KEY_FINGERPRINT_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&ADMIN_KEYS_TABLE, "by_key_fingerprint"));
+static KEY_FINGERPRINT_FIELD: LazyLock<FieldPath> = LazyLock::new(|| {
+    "keyFingerprint"
+        .parse()
+        .expect("invalid keyFingerprint field")
+});
+
+pub static ADMIN_KEY_SECRETS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_admin_key_secrets"
+        .parse()
+        .expect("Invalid built-in admin_key_secrets table")
+});
+
+pub struct AdminKeysTable;
+impl SystemTable for AdminKeysTable {
+    fn table_name(&self) -> &'static TableName {
+        &ADMIN_KEYS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![SystemIndex {
+            name: ADMIN_KEYS_BY_KEY_FINGERPRINT_INDEX.clone(),
+            fields: vec![KEY_FINGERPRINT_FIELD.clone()].try_into().unwrap(),
+        }]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<AdminKeyMetadata>::try_from(document).map(|_| ())
+    }
+}
+
+pub struct AdminKeySecretsTable;
+impl SystemTable for AdminKeySecretsTable {
+    fn table_name(&self) -> &'static TableName {
+        &ADMIN_KEY_SECRETS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<AdminKeySecrets>::try_from(document).map(|_| ())
+    }
+}
+
+/// Admin keys issued through the admin API, the revocation list, and the
+/// secrets keys are encrypted with once the instance secret has been rotated,
+/// which has at most one row.
+pub struct AdminKeysModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> AdminKeysModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    pub async fn get(
+        &mut self,
+        key_fingerprint: &str,
+    ) -> anyhow::Result<Option<ParsedDocument<AdminKeyMetadata>>> {
+        let index_range = IndexRange {
+            index_name: ADMIN_KEYS_BY_KEY_FINGERPRINT_INDEX.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                KEY_FINGERPRINT_FIELD.clone(),
+                ConvexValue::try_from(key_fingerprint.to_string())?.into(),
+            )],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        query_stream
+            .expect_at_most_one(self.tx)
+            .await?
+            .map(ParsedDocument::try_from)
+            .transpose()
+    }
+
+    pub async fn list(&mut self) -> anyhow::Result<Vec<AdminKeyMetadata>> {
+        require_admin_or_system(self.tx.identity(), "list_admin_keys")?;
+        let query = Query::full_table_scan(ADMIN_KEYS_TABLE.clone(), Order::Asc);
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        let mut keys = vec![];
+        while let Some(document) = query_stream.next(self.tx, None).await? {
+            let key: ParsedDocument<AdminKeyMetadata> = document.try_into()?;
+            keys.push(key.into_value());
+        }
+        Ok(keys)
+    }
+
+    pub async fn revoked_fingerprints(&mut self) -> anyhow::Result<BTreeSet<String>> {
+        Ok(self
+            .list()
+            .await?
+            .into_iter()
+            .filter(|key| key.revoked_at.is_some())
+            .map(|key| key.key_fingerprint)
+            .collect())
+    }
+
+    pub async fn record_issued(&mut self, metadata: AdminKeyMetadata) -> anyhow::Result<()> {
+        require_admin_or_system(self.tx.identity(), "record_issued_admin_key")?;
+        SystemMetadataModel::new_global(self.tx)
+            .insert(&ADMIN_KEYS_TABLE, metadata.try_into()?)
+            .await?;
+        Ok(())
+    }
+
+    /// Adds the key to the revocation list, whether or not it was issued
+    /// through the admin API. Returns whether it wasn't already revoked.
+    pub async fn revoke(
+        &mut self,
+        key_fingerprint: String,
+        now: UnixTimestamp,
+    ) -> anyhow::Result<bool> {
+        require_admin_or_system(self.tx.identity(), "revoke_admin_key")?;
+        match self.get(&key_fingerprint).await? {
+            Some(existing) if existing.revoked_at.is_some() => Ok(false),
+            Some(existing) => {
+                let (id, metadata) = existing.into_id_and_value();
+                let metadata = AdminKeyMetadata {
+                    revoked_at: Some(now),
+                    ..metadata
+                };
+                SystemMetadataModel::new_global(self.tx)
+                    .replace(id, metadata.try_into()?)
+                    .await?;
+                Ok(true)
+            },
+            None => {
+                let metadata = AdminKeyMetadata {
+                    key_fingerprint,
+                    name: None,
+                    member_id: None,
+                    scope: None,
+                    created_at: None,
+                    revoked_at: Some(now),
+                };
+                SystemMetadataModel::new_global(self.tx)
+                    .insert(&ADMIN_KEYS_TABLE, metadata.try_into()?)
+                    .await?;
+                Ok(true)
+            },
+        }
+    }
+
+    pub async fn secrets(&mut self) -> anyhow::Result<Option<ParsedDocument<AdminKeySecrets>>> {
+        let query = Query::full_table_scan(ADMIN_KEY_SECRETS_TABLE.clone(), Order::Asc);
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        query_stream
+            .expect_at_most_one(self.tx)
+            .await?
+            .map(|document| document.try_into())
+            .transpose()
+    }
+
+    pub async fn set_secrets(&mut self, secrets: AdminKeySecrets) -> anyhow::Result<()> {
+        require_admin_or_system(self.tx.identity(), "set_admin_key_secrets")?;
+        match self.secrets().await? {
+            Some(existing) => {
+                SystemMetadataModel::new_global(self.tx)
+                    .replace(existing.id(), secrets.try_into()?)
+                    .await?;
+            },
+            None => {
+                SystemMetadataModel::new_global(self.tx)
+                    .insert(&ADMIN_KEY_SECRETS_TABLE, secrets.try_into()?)
+                    .await?;
+            },
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::runtime::UnixTimestamp;
+    use database::test_helpers::DbFixtures;
+    use keybroker::Identity;
+    use maplit::btreeset;
+    use runtime::testing::TestRuntime;
+
+    use super::{
+        types::AdminKeyMetadata,
+        AdminKeysModel,
+    };
+    use crate::test_helpers::DbFixturesWithModel;
+
+    #[convex_macro::test_runtime]
+    async fn test_revoke_admin_keys(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin(Identity::system()).await?;
+        let mut model = AdminKeysModel::new(&mut tx);
+        model
+            .record_issued(AdminKeyMetadata {
+                key_fingerprint: "issued".to_string(),
+                name: Some("ci".to_string()),
+                member_id: None,
+                scope: None,
+                created_at: Some(UnixTimestamp::from_millis(1000)),
+                revoked_at: None,
+            })
+            .await?;
+        assert!(model.revoked_fingerprints().await?.is_empty());
+
+        let now = UnixTimestamp::from_millis(2000);
+        assert!(model.revoke("issued".to_string(), now).await?);
+        assert!(!model.revoke("issued".to_string(), now).await?);
+        // Keys that weren't issued through the API can be revoked too.
+        assert!(model.revoke("leaked".to_string(), now).await?);
+        assert_eq!(
+            model.revoked_fingerprints().await?,
+            btreeset! {"issued".to_string(), "leaked".to_string()}
+        );
+        let issued = model.get("issued").await?.unwrap();
+        assert_eq!(issued.name.as_deref(), Some("ci"));
+        assert_eq!(issued.revoked_at, Some(now));
+        Ok(())
+    }
+}
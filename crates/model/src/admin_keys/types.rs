@@ -0,0 +1,175 @@
+use common::{
+    runtime::UnixTimestamp,
+    types::MemberId,
+};
+use keybroker::AdminKeyScope;
+#[cfg(any(test, feature = "testing"))]
+use proptest::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use value::codegen_convex_serialization;
+
+/// An admin key issued through the admin API, or one that was revoked without
+/// having been issued that way, like a key from `generate_key`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct AdminKeyMetadata {
+    /// `keybroker::admin_key_fingerprint` of the key.
+    pub key_fingerprint: String,
+    /// What the key is for, like "github-actions". `None` for keys that were
+    /// only revoked.
+    pub name: Option<String>,
+    pub member_id: Option<MemberId>,
+    pub scope: Option<AdminKeyScope>,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "proptest::option::of((0..=i64::MAX as u64 / \
+                             1_000_000).prop_map(UnixTimestamp::from_millis))")
+    )]
+    pub created_at: Option<UnixTimestamp>,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "proptest::option::of((0..=i64::MAX as u64 / \
+                             1_000_000).prop_map(UnixTimestamp::from_millis))")
+    )]
+    pub revoked_at: Option<UnixTimestamp>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedAdminKeyMetadata {
+    key_fingerprint: String,
+    name: Option<String>,
+    member_id: Option<i64>,
+    scope: Option<String>,
+    created_at_ms: Option<i64>,
+    revoked_at_ms: Option<i64>,
+}
+
+fn timestamp_to_ms(timestamp: Option<UnixTimestamp>) -> anyhow::Result<Option<i64>> {
+    timestamp
+        .map(|timestamp| anyhow::Ok(timestamp.as_ms_since_epoch()?.try_into()?))
+        .transpose()
+}
+
+fn ms_to_timestamp(ms: Option<i64>) -> anyhow::Result<Option<UnixTimestamp>> {
+    ms.map(|ms| anyhow::Ok(UnixTimestamp::from_millis(ms.try_into()?)))
+        .transpose()
+}
+
+impl TryFrom<AdminKeyMetadata> for SerializedAdminKeyMetadata {
+    type Error = anyhow::Error;
+
+    fn try_from(metadata: AdminKeyMetadata) -> anyhow::Result<Self> {
+        Ok(Self {
+            key_fingerprint: metadata.key_fingerprint,
+            name: metadata.name,
+            member_id: metadata.member_id.map(|member_id| member_id.0 as i64),
+            scope: metadata.scope.map(|scope| scope.to_string()),
+            created_at_ms: timestamp_to_ms(metadata.created_at)?,
+            revoked_at_ms: timestamp_to_ms(metadata.revoked_at)?,
+        })
+    }
+}
+
+impl TryFrom<SerializedAdminKeyMetadata> for AdminKeyMetadata {
+    type Error = anyhow::Error;
+
+    fn try_from(metadata: SerializedAdminKeyMetadata) -> anyhow::Result<Self> {
+        Ok(Self {
+            key_fingerprint: metadata.key_fingerprint,
+            name: metadata.name,
+            member_id: metadata
+                .member_id
+                .map(|member_id| MemberId(member_id as u64)),
+            scope: metadata.scope.map(|scope| scope.parse()).transpose()?,
+            created_at: ms_to_timestamp(metadata.created_at_ms)?,
+            revoked_at: ms_to_timestamp(metadata.revoked_at_ms)?,
+        })
+    }
+}
+
+codegen_convex_serialization!(AdminKeyMetadata, SerializedAdminKeyMetadata);
+
+/// The secrets admin keys are encrypted with after the instance secret has
+/// been rotated, each encrypted with the instance secret.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct AdminKeySecrets {
+    /// The secret new keys are encrypted with.
+    pub current: String,
+    pub retired: Vec<RetiredAdminKeySecret>,
+}
+
+/// A secret that keys are still accepted from until `valid_until`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct RetiredAdminKeySecret {
+    /// `None` for the instance secret.
+    pub secret: Option<String>,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(
+            strategy = "(0..=i64::MAX as u64 / 1_000_000).prop_map(UnixTimestamp::from_millis)"
+        )
+    )]
+    pub valid_until: UnixTimestamp,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedRetiredAdminKeySecret {
+    secret: Option<String>,
+    valid_until_ms: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedAdminKeySecrets {
+    current: String,
+    retired: Vec<SerializedRetiredAdminKeySecret>,
+}
+
+impl TryFrom<AdminKeySecrets> for SerializedAdminKeySecrets {
+    type Error = anyhow::Error;
+
+    fn try_from(secrets: AdminKeySecrets) -> anyhow::Result<Self> {
+        Ok(Self {
+            current: secrets.current,
+            retired: secrets
+                .retired
+                .into_iter()
+                .map(|retired| {
+                    Ok(SerializedRetiredAdminKeySecret {
+                        secret: retired.secret,
+                        valid_until_ms: retired.valid_until.as_ms_since_epoch()?.try_into()?,
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?,
+        })
+    }
+}
+
+impl TryFrom<SerializedAdminKeySecrets> for AdminKeySecrets {
+    type Error = anyhow::Error;
+
+    fn try_from(secrets: SerializedAdminKeySecrets) -> anyhow::Result<Self> {
+        Ok(Self {
+            current: secrets.current,
+            retired: secrets
+                .retired
+                .into_iter()
+                .map(|retired| {
+                    Ok(RetiredAdminKeySecret {
+                        secret: retired.secret,
+                        valid_until: UnixTimestamp::from_millis(retired.valid_until_ms.try_into()?),
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?,
+        })
+    }
+}
+
+codegen_convex_serialization!(AdminKeySecrets, SerializedAdminKeySecrets);
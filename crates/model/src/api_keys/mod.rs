@@ -0,0 +1,216 @@
+use std::sync::LazyLock;
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    query::{
+        IndexRange,
+        IndexRangeExpression,
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+    types::IndexName,
+};
+use database::{
+    defaults::system_index,
+    require_admin_or_system,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use value::{
+    sha256::Sha256,
+    ConvexValue,
+    DeveloperDocumentId,
+    FieldPath,
+    ResolvedDocumentId,
+    TableName,
+    TableNamespace,
+};
+
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+use types::ApiKey;
+
+/// API keys start with this, so they can be told apart from ID tokens.
+pub const API_KEY_PREFIX: &str = "convex_api_key_";
+
+pub static API_KEYS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_api_keys"
+        .parse()
+        .expect("Invalid built-in api_keys table")
+});
+
+pub static API_KEYS_BY_KEY_HASH_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&API_KEYS_TABLE, "by_key_hash"));
+static KEY_HASH_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "keyHash".parse().expect("invalid keyHash field"));
+
+pub fn is_api_key(token: &str) -> bool {
+    token.starts_with(API_KEY_PREFIX)
+}
+
+pub fn api_key_hash(key: &str) -> String {
+    Sha256::hash(key.as_bytes()).as_hex()
+}
+
+pub struct ApiKeysTable;
+impl SystemTable for ApiKeysTable {
+    fn table_name(&self) -> &'static TableName {
+        &API_KEYS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![SystemIndex {
+            name: API_KEYS_BY_KEY_HASH_INDEX.clone(),
+            fields: vec![KEY_HASH_FIELD.clone()].try_into().unwrap(),
+        }]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<ApiKey>::try_from(document).map(|_| ())
+    }
+}
+
+/// API keys for machine-to-machine callers of public functions. Admins manage
+/// them, and the system identity looks them up to authenticate requests.
+pub struct ApiKeysModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> ApiKeysModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    /// The metadata for `key`, if it's an API key that hasn't been deleted.
+    pub async fn get_by_key(
+        &mut self,
+        key: &str,
+    ) -> anyhow::Result<Option<ParsedDocument<ApiKey>>> {
+        require_admin_or_system(self.tx.identity(), "get_api_key")?;
+        let index_range = IndexRange {
+            index_name: API_KEYS_BY_KEY_HASH_INDEX.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                KEY_HASH_FIELD.clone(),
+                ConvexValue::try_from(api_key_hash(key))?.into(),
+            )],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        query_stream
+            .expect_at_most_one(self.tx)
+            .await?
+            .map(ParsedDocument::try_from)
+            .transpose()
+    }
+
+    pub async fn list(&mut self) -> anyhow::Result<Vec<ParsedDocument<ApiKey>>> {
+        require_admin_or_system(self.tx.identity(), "list_api_keys")?;
+        let query = Query::full_table_scan(API_KEYS_TABLE.clone(), Order::Asc);
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        let mut keys = vec![];
+        while let Some(document) = query_stream.next(self.tx, None).await? {
+            keys.push(document.try_into()?);
+        }
+        Ok(keys)
+    }
+
+    pub async fn insert(&mut self, key: ApiKey) -> anyhow::Result<ResolvedDocumentId> {
+        require_admin_or_system(self.tx.identity(), "insert_api_key")?;
+        SystemMetadataModel::new_global(self.tx)
+            .insert(&API_KEYS_TABLE, key.try_into()?)
+            .await
+    }
+
+    /// Deletes the key with document ID `id`, so it stops being accepted.
+    /// Returns whether it existed.
+    pub async fn delete(&mut self, id: &str) -> anyhow::Result<bool> {
+        require_admin_or_system(self.tx.identity(), "delete_api_key")?;
+        let Ok(id) = DeveloperDocumentId::decode(id) else {
+            return Ok(false);
+        };
+        let Ok(table) = self
+            .tx
+            .table_mapping()
+            .namespace(TableNamespace::Global)
+            .id(&API_KEYS_TABLE)
+        else {
+            return Ok(false);
+        };
+        if id.table() != table.table_number {
+            return Ok(false);
+        }
+        let id = ResolvedDocumentId::new(table.tablet_id, id);
+        if self.tx.get(id).await?.is_none() {
+            return Ok(false);
+        }
+        SystemMetadataModel::new_global(self.tx).delete(id).await?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::runtime::Runtime;
+    use database::test_helpers::DbFixtures;
+    use keybroker::Identity;
+    use maplit::btreemap;
+    use runtime::testing::TestRuntime;
+    use value::DeveloperDocumentId;
+
+    use super::{
+        api_key_hash,
+        types::ApiKey,
+        ApiKeysModel,
+    };
+    use crate::test_helpers::DbFixturesWithModel;
+
+    #[convex_macro::test_runtime]
+    async fn test_api_keys(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin(Identity::system()).await?;
+        let key = ApiKey {
+            name: "billing".to_string(),
+            key_hash: api_key_hash("convex_api_key_secret"),
+            subject: "billing-service".to_string(),
+            custom_claims: btreemap! { "role".to_string() => "\"billing\"".to_string() },
+            created_at: rt.unix_timestamp(),
+        };
+        let id = ApiKeysModel::new(&mut tx).insert(key.clone()).await?;
+        let stored = ApiKeysModel::new(&mut tx)
+            .get_by_key("convex_api_key_secret")
+            .await?
+            .unwrap();
+        assert_eq!(stored.into_value(), key);
+        assert!(ApiKeysModel::new(&mut tx)
+            .get_by_key("convex_api_key_other")
+            .await?
+            .is_none());
+        assert_eq!(ApiKeysModel::new(&mut tx).list().await?.len(), 1);
+
+        let id = DeveloperDocumentId::from(id).encode();
+        assert!(ApiKeysModel::new(&mut tx).delete(&id).await?);
+        assert!(!ApiKeysModel::new(&mut tx).delete(&id).await?);
+        assert!(ApiKeysModel::new(&mut tx)
+            .get_by_key("convex_api_key_secret")
+            .await?
+            .is_none());
+
+        let mut tx = db.begin(Identity::Unknown).await?;
+        assert!(ApiKeysModel::new(&mut tx).list().await.is_err());
+        Ok(())
+    }
+}
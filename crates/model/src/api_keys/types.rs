@@ -0,0 +1,75 @@
+use std::collections::BTreeMap;
+
+use common::runtime::UnixTimestamp;
+#[cfg(any(test, feature = "testing"))]
+use proptest::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use value::codegen_convex_serialization;
+
+/// A key that backend services send in place of an ID token to call public
+/// functions as a user with a fixed subject and claims.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct ApiKey {
+    /// What the key is for, like "billing-service".
+    pub name: String,
+    /// [`super::api_key_hash`] of the key. The key itself isn't stored.
+    pub key_hash: String,
+    /// `ctx.auth.getUserIdentity().subject` for calls made with the key.
+    pub subject: String,
+    /// Claim name to JSON-encoded value, as in
+    /// `UserIdentityAttributes::custom_claims`.
+    pub custom_claims: BTreeMap<String, String>,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(
+            strategy = "(0..=i64::MAX as u64 / 1_000_000).prop_map(UnixTimestamp::from_millis)"
+        )
+    )]
+    pub created_at: UnixTimestamp,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedApiKey {
+    name: String,
+    key_hash: String,
+    subject: String,
+    /// Claim names can be URLs, which aren't valid field names, so the claims
+    /// are stored as a JSON object.
+    custom_claims: String,
+    created_at_ms: i64,
+}
+
+impl TryFrom<ApiKey> for SerializedApiKey {
+    type Error = anyhow::Error;
+
+    fn try_from(key: ApiKey) -> anyhow::Result<Self> {
+        Ok(Self {
+            name: key.name,
+            key_hash: key.key_hash,
+            subject: key.subject,
+            custom_claims: serde_json::to_string(&key.custom_claims)?,
+            created_at_ms: key.created_at.as_ms_since_epoch()?.try_into()?,
+        })
+    }
+}
+
+impl TryFrom<SerializedApiKey> for ApiKey {
+    type Error = anyhow::Error;
+
+    fn try_from(key: SerializedApiKey) -> anyhow::Result<Self> {
+        Ok(Self {
+            name: key.name,
+            key_hash: key.key_hash,
+            subject: key.subject,
+            custom_claims: serde_json::from_str(&key.custom_claims)?,
+            created_at: UnixTimestamp::from_millis(key.created_at_ms.try_into()?),
+        })
+    }
+}
+
+codegen_convex_serialization!(ApiKey, SerializedApiKey);
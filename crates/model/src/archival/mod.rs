@@ -0,0 +1,382 @@
+//! Archival of old documents in append-heavy tables, like logs. Tables with a
+//! policy have their documents moved into compressed segments in file storage
+//! once they're old enough, which removes them from the table and its
+//! indexes. Each archived document keeps a small pointer to its segment, so
+//! functions that opt in can still read it by ID, at the cost of fetching the
+//! segment.
+use std::{
+    sync::LazyLock,
+    time::Duration,
+};
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    query::{
+        IndexRange,
+        IndexRangeExpression,
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+    types::{
+        IndexName,
+        ObjectKey,
+    },
+};
+use database::{
+    defaults::system_index,
+    unauthorized_error,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use value::{
+    ConvexValue,
+    FieldPath,
+    InternalId,
+    TableName,
+    TableNamespace,
+    TabletId,
+};
+
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod segment;
+pub mod types;
+
+use types::{
+    ArchivalPolicy,
+    ArchivedDocument,
+    ArchivedSegment,
+};
+
+pub static ARCHIVAL_POLICIES_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_archival_policies"
+        .parse()
+        .expect("Invalid built-in archival_policies table")
+});
+pub static ARCHIVED_SEGMENTS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_archived_segments"
+        .parse()
+        .expect("Invalid built-in archived_segments table")
+});
+pub static ARCHIVED_DOCUMENTS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_archived_documents"
+        .parse()
+        .expect("Invalid built-in archived_documents table")
+});
+
+pub static ARCHIVAL_POLICIES_BY_TABLE_ID_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&ARCHIVAL_POLICIES_TABLE, "by_table_id"));
+pub static ARCHIVED_SEGMENTS_BY_TABLE_ID_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&ARCHIVED_SEGMENTS_TABLE, "by_table_id"));
+pub static ARCHIVED_DOCUMENTS_BY_DOCUMENT_ID_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&ARCHIVED_DOCUMENTS_TABLE, "by_document_id"));
+
+static TABLE_ID_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "tableId".parse().expect("invalid tableId field"));
+static DOCUMENT_ID_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "documentId".parse().expect("invalid documentId field"));
+
+pub struct ArchivalPoliciesTable;
+impl SystemTable for ArchivalPoliciesTable {
+    fn table_name(&self) -> &'static TableName {
+        &ARCHIVAL_POLICIES_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![SystemIndex {
+            name: ARCHIVAL_POLICIES_BY_TABLE_ID_INDEX.clone(),
+            fields: vec![TABLE_ID_FIELD.clone()].try_into().unwrap(),
+        }]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<ArchivalPolicy>::try_from(document).map(|_| ())
+    }
+}
+
+pub struct ArchivedSegmentsTable;
+impl SystemTable for ArchivedSegmentsTable {
+    fn table_name(&self) -> &'static TableName {
+        &ARCHIVED_SEGMENTS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![SystemIndex {
+            name: ARCHIVED_SEGMENTS_BY_TABLE_ID_INDEX.clone(),
+            fields: vec![TABLE_ID_FIELD.clone()].try_into().unwrap(),
+        }]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<ArchivedSegment>::try_from(document).map(|_| ())
+    }
+}
+
+pub struct ArchivedDocumentsTable;
+impl SystemTable for ArchivedDocumentsTable {
+    fn table_name(&self) -> &'static TableName {
+        &ARCHIVED_DOCUMENTS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![SystemIndex {
+            name: ARCHIVED_DOCUMENTS_BY_DOCUMENT_ID_INDEX.clone(),
+            fields: vec![DOCUMENT_ID_FIELD.clone()].try_into().unwrap(),
+        }]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<ArchivedDocument>::try_from(document).map(|_| ())
+    }
+}
+
+pub struct ArchivalModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> ArchivalModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    pub async fn policies(&mut self) -> anyhow::Result<Vec<ParsedDocument<ArchivalPolicy>>> {
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::full_table_scan(ARCHIVAL_POLICIES_TABLE.clone(), Order::Asc),
+        )?;
+        let mut policies = vec![];
+        while let Some(document) = query_stream.next(self.tx, None).await? {
+            policies.push(document.try_into()?);
+        }
+        Ok(policies)
+    }
+
+    pub async fn policy(
+        &mut self,
+        tablet_id: TabletId,
+    ) -> anyhow::Result<Option<ParsedDocument<ArchivalPolicy>>> {
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(by_table_id(
+                &ARCHIVAL_POLICIES_BY_TABLE_ID_INDEX,
+                tablet_id,
+            )?),
+        )?;
+        query_stream
+            .expect_at_most_one(self.tx)
+            .await?
+            .map(ParsedDocument::try_from)
+            .transpose()
+    }
+
+    /// Archives documents in the table with `tablet_id` once they're
+    /// `archive_after` old, or stops archiving them if `archive_after` is
+    /// `None`. Documents that were already archived stay archived.
+    pub async fn set_policy(
+        &mut self,
+        tablet_id: TabletId,
+        archive_after: Option<Duration>,
+    ) -> anyhow::Result<()> {
+        if !(self.tx.identity().is_system() || self.tx.identity().is_admin()) {
+            anyhow::bail!(unauthorized_error("set_archival_policy"));
+        }
+        let existing = self.policy(tablet_id).await?;
+        match (existing, archive_after) {
+            (Some(existing), Some(archive_after)) => {
+                let policy = ArchivalPolicy {
+                    tablet_id,
+                    archive_after,
+                };
+                SystemMetadataModel::new_global(self.tx)
+                    .replace(existing.id(), policy.try_into()?)
+                    .await?;
+            },
+            (None, Some(archive_after)) => {
+                let policy = ArchivalPolicy {
+                    tablet_id,
+                    archive_after,
+                };
+                SystemMetadataModel::new_global(self.tx)
+                    .insert(&ARCHIVAL_POLICIES_TABLE, policy.try_into()?)
+                    .await?;
+            },
+            (Some(existing), None) => {
+                SystemMetadataModel::new_global(self.tx)
+                    .delete(existing.id())
+                    .await?;
+            },
+            (None, None) => {},
+        }
+        Ok(())
+    }
+
+    /// Records a segment written to file storage, along with a pointer to it
+    /// for each of `document_ids`. The caller deletes the documents from
+    /// their table in the same transaction.
+    pub async fn record_segment(
+        &mut self,
+        segment: ArchivedSegment,
+        document_ids: Vec<InternalId>,
+    ) -> anyhow::Result<()> {
+        if !self.tx.identity().is_system() {
+            anyhow::bail!(unauthorized_error("record_archived_segment"));
+        }
+        let tablet_id = segment.tablet_id;
+        let storage_key = segment.storage_key.clone();
+        SystemMetadataModel::new_global(self.tx)
+            .insert(&ARCHIVED_SEGMENTS_TABLE, segment.try_into()?)
+            .await?;
+        for internal_id in document_ids {
+            let document = ArchivedDocument {
+                tablet_id,
+                internal_id,
+                storage_key: storage_key.clone(),
+            };
+            SystemMetadataModel::new_global(self.tx)
+                .insert(&ARCHIVED_DOCUMENTS_TABLE, document.try_into()?)
+                .await?;
+        }
+        Ok(())
+    }
+
+    pub async fn segments(
+        &mut self,
+        tablet_id: TabletId,
+    ) -> anyhow::Result<Vec<ParsedDocument<ArchivedSegment>>> {
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(by_table_id(
+                &ARCHIVED_SEGMENTS_BY_TABLE_ID_INDEX,
+                tablet_id,
+            )?),
+        )?;
+        let mut segments = vec![];
+        while let Some(document) = query_stream.next(self.tx, None).await? {
+            segments.push(document.try_into()?);
+        }
+        Ok(segments)
+    }
+
+    /// The key of the segment holding the archived document with
+    /// `internal_id` in the table with `tablet_id`, if it was archived.
+    pub async fn segment_for_document(
+        &mut self,
+        tablet_id: TabletId,
+        internal_id: InternalId,
+    ) -> anyhow::Result<Option<ObjectKey>> {
+        let index_range = IndexRange {
+            index_name: ARCHIVED_DOCUMENTS_BY_DOCUMENT_ID_INDEX.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                DOCUMENT_ID_FIELD.clone(),
+                ConvexValue::try_from(internal_id.to_string())?.into(),
+            )],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        let Some(document) = query_stream.expect_at_most_one(self.tx).await? else {
+            return Ok(None);
+        };
+        let archived: ParsedDocument<ArchivedDocument> = document.try_into()?;
+        Ok((archived.tablet_id == tablet_id).then(|| archived.into_value().storage_key))
+    }
+}
+
+fn by_table_id(index_name: &IndexName, tablet_id: TabletId) -> anyhow::Result<IndexRange> {
+    Ok(IndexRange {
+        index_name: index_name.clone(),
+        range: vec![IndexRangeExpression::Eq(
+            TABLE_ID_FIELD.clone(),
+            ConvexValue::try_from(tablet_id.to_string())?.into(),
+        )],
+        order: Order::Asc,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use common::{
+        runtime::UnixTimestamp,
+        types::ObjectKey,
+    };
+    use database::test_helpers::DbFixtures;
+    use keybroker::Identity;
+    use runtime::testing::TestRuntime;
+    use value::{
+        InternalId,
+        TabletId,
+    };
+
+    use super::{
+        types::ArchivedSegment,
+        ArchivalModel,
+    };
+    use crate::test_helpers::DbFixturesWithModel;
+
+    #[convex_macro::test_runtime]
+    async fn test_policies_and_segments(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let tablet_id = TabletId(InternalId([1; 16]));
+        let storage_key = ObjectKey::try_from("segment-1")?;
+
+        let mut tx = db.begin(Identity::system()).await?;
+        let mut model = ArchivalModel::new(&mut tx);
+        model
+            .set_policy(tablet_id, Some(Duration::from_secs(60)))
+            .await?;
+        model
+            .set_policy(tablet_id, Some(Duration::from_secs(120)))
+            .await?;
+        model
+            .record_segment(
+                ArchivedSegment {
+                    tablet_id,
+                    storage_key: storage_key.clone(),
+                    document_count: 2,
+                    size: 100,
+                    archived_at: UnixTimestamp::from_millis(1000),
+                },
+                vec![InternalId([2; 16]), InternalId([3; 16])],
+            )
+            .await?;
+        db.commit(tx).await?;
+
+        let mut tx = db.begin(Identity::system()).await?;
+        let mut model = ArchivalModel::new(&mut tx);
+        let policies = model.policies().await?;
+        assert_eq!(policies.len(), 1);
+        assert_eq!(policies[0].archive_after, Duration::from_secs(120));
+        assert_eq!(model.segments(tablet_id).await?.len(), 1);
+        assert_eq!(
+            model
+                .segment_for_document(tablet_id, InternalId([3; 16]))
+                .await?,
+            Some(storage_key)
+        );
+        assert_eq!(
+            model
+                .segment_for_document(tablet_id, InternalId([4; 16]))
+                .await?,
+            None
+        );
+        model.set_policy(tablet_id, None).await?;
+        assert!(model.policies().await?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,119 @@
+//! Archived segments are zstd-compressed newline-delimited JSON, with one
+//! document per line in the internal JSON encoding, including its system
+//! fields. They're immutable once written, and live in file storage without
+//! a `_storage` entry.
+use std::{
+    io::Write,
+    sync::Arc,
+};
+
+use anyhow::Context;
+use bytes::Bytes;
+use common::{
+    document::ResolvedDocument,
+    types::ObjectKey,
+};
+use futures::TryStreamExt;
+use storage::{
+    Storage,
+    StorageExt,
+    Upload,
+};
+use value::{
+    json_deserialize,
+    json_serialize,
+    ConvexValue,
+    TabletId,
+};
+
+const COMPRESSION_LEVEL: i32 = 3;
+
+pub fn encode_segment(documents: Vec<ResolvedDocument>) -> anyhow::Result<Vec<u8>> {
+    let mut encoder = zstd::Encoder::new(Vec::new(), COMPRESSION_LEVEL)?;
+    for document in documents {
+        let line = json_serialize(ConvexValue::Object(document.into_value().0))?;
+        encoder.write_all(line.as_bytes())?;
+        encoder.write_all(b"\n")?;
+    }
+    Ok(encoder.finish()?)
+}
+
+/// Decodes a segment written by [`encode_segment`] for the table with
+/// `tablet_id`.
+pub fn decode_segment(tablet_id: TabletId, bytes: &[u8]) -> anyhow::Result<Vec<ResolvedDocument>> {
+    let decoded = zstd::decode_all(bytes)?;
+    std::str::from_utf8(&decoded)?
+        .lines()
+        .map(|line| ResolvedDocument::from_database(tablet_id, json_deserialize(line)?))
+        .collect()
+}
+
+/// Writes `documents` to a new segment in `storage`, returning its key and
+/// compressed size.
+pub async fn write_segment(
+    storage: &Arc<dyn Storage>,
+    documents: Vec<ResolvedDocument>,
+) -> anyhow::Result<(ObjectKey, u64)> {
+    let bytes = encode_segment(documents)?;
+    let size = bytes.len() as u64;
+    let mut upload = storage.start_upload().await?;
+    upload.write(Bytes::from(bytes)).await?;
+    Ok((upload.complete().await?, size))
+}
+
+/// Fetches and decodes the segment at `storage_key`, for the table with
+/// `tablet_id`.
+pub async fn read_segment(
+    storage: &Arc<dyn Storage>,
+    tablet_id: TabletId,
+    storage_key: &ObjectKey,
+) -> anyhow::Result<Vec<ResolvedDocument>> {
+    let stream = storage
+        .get(storage_key)
+        .await?
+        .with_context(|| format!("Archived segment {storage_key:?} is missing"))?;
+    let bytes = stream
+        .stream
+        .try_fold(Vec::new(), |mut bytes, chunk| async move {
+            bytes.extend_from_slice(&chunk);
+            Ok::<_, std::io::Error>(bytes)
+        })
+        .await?;
+    decode_segment(tablet_id, &bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{
+        assert_obj,
+        document::{
+            CreationTime,
+            ResolvedDocument,
+        },
+        testing::TestIdGenerator,
+    };
+
+    use super::{
+        decode_segment,
+        encode_segment,
+    };
+
+    #[test]
+    fn test_segment_roundtrips() -> anyhow::Result<()> {
+        let mut id_generator = TestIdGenerator::new();
+        let table_name = "logs".parse()?;
+        let documents = (0..3)
+            .map(|i| {
+                ResolvedDocument::new(
+                    id_generator.user_generate(&table_name),
+                    CreationTime::try_from(1000.0 + i as f64)?,
+                    assert_obj!("line" => format!("line {i}")),
+                )
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let tablet_id = documents[0].id().tablet_id;
+        let bytes = encode_segment(documents.clone())?;
+        assert_eq!(decode_segment(tablet_id, &bytes)?, documents);
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+use std::time::Duration;
+
+use common::{
+    runtime::UnixTimestamp,
+    types::ObjectKey,
+};
+#[cfg(any(test, feature = "testing"))]
+use proptest::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use value::{
+    codegen_convex_serialization,
+    InternalId,
+    TabletId,
+};
+
+/// Documents in the table with `tablet_id` are moved to archived segments
+/// once they're `archive_after` old, by creation time.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct ArchivalPolicy {
+    pub tablet_id: TabletId,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "(0..=u32::MAX as u64).prop_map(Duration::from_millis)")
+    )]
+    pub archive_after: Duration,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedArchivalPolicy {
+    table_id: String,
+    archive_after_ms: i64,
+}
+
+impl TryFrom<ArchivalPolicy> for SerializedArchivalPolicy {
+    type Error = anyhow::Error;
+
+    fn try_from(policy: ArchivalPolicy) -> anyhow::Result<Self> {
+        Ok(Self {
+            table_id: policy.tablet_id.to_string(),
+            archive_after_ms: policy.archive_after.as_millis().try_into()?,
+        })
+    }
+}
+
+impl TryFrom<SerializedArchivalPolicy> for ArchivalPolicy {
+    type Error = anyhow::Error;
+
+    fn try_from(policy: SerializedArchivalPolicy) -> anyhow::Result<Self> {
+        Ok(Self {
+            tablet_id: policy.table_id.parse()?,
+            archive_after: Duration::from_millis(policy.archive_after_ms.try_into()?),
+        })
+    }
+}
+
+codegen_convex_serialization!(ArchivalPolicy, SerializedArchivalPolicy);
+
+/// A compressed batch of documents moved out of the table with `tablet_id`
+/// into file storage. See `segment.rs` for the format.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct ArchivedSegment {
+    pub tablet_id: TabletId,
+    pub storage_key: ObjectKey,
+    pub document_count: u64,
+    /// Compressed size in bytes.
+    pub size: u64,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(
+            strategy = "(0..=i64::MAX as u64 / 1_000_000).prop_map(UnixTimestamp::from_millis)"
+        )
+    )]
+    pub archived_at: UnixTimestamp,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedArchivedSegment {
+    table_id: String,
+    storage_key: String,
+    document_count: i64,
+    size: i64,
+    archived_at_ms: i64,
+}
+
+impl TryFrom<ArchivedSegment> for SerializedArchivedSegment {
+    type Error = anyhow::Error;
+
+    fn try_from(segment: ArchivedSegment) -> anyhow::Result<Self> {
+        Ok(Self {
+            table_id: segment.tablet_id.to_string(),
+            storage_key: segment.storage_key.into(),
+            document_count: segment.document_count.try_into()?,
+            size: segment.size.try_into()?,
+            archived_at_ms: segment.archived_at.as_ms_since_epoch()?.try_into()?,
+        })
+    }
+}
+
+impl TryFrom<SerializedArchivedSegment> for ArchivedSegment {
+    type Error = anyhow::Error;
+
+    fn try_from(segment: SerializedArchivedSegment) -> anyhow::Result<Self> {
+        Ok(Self {
+            tablet_id: segment.table_id.parse()?,
+            storage_key: segment.storage_key.try_into()?,
+            document_count: segment.document_count.try_into()?,
+            size: segment.size.try_into()?,
+            archived_at: UnixTimestamp::from_millis(segment.archived_at_ms.try_into()?),
+        })
+    }
+}
+
+codegen_convex_serialization!(ArchivedSegment, SerializedArchivedSegment);
+
+/// Points an archived document at the segment holding it, so it can be read
+/// by ID without scanning segments.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct ArchivedDocument {
+    pub tablet_id: TabletId,
+    pub internal_id: InternalId,
+    pub storage_key: ObjectKey,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedArchivedDocument {
+    table_id: String,
+    document_id: String,
+    storage_key: String,
+}
+
+impl From<ArchivedDocument> for SerializedArchivedDocument {
+    fn from(document: ArchivedDocument) -> Self {
+        Self {
+            table_id: document.tablet_id.to_string(),
+            document_id: document.internal_id.to_string(),
+            storage_key: document.storage_key.into(),
+        }
+    }
+}
+
+impl TryFrom<SerializedArchivedDocument> for ArchivedDocument {
+    type Error = anyhow::Error;
+
+    fn try_from(document: SerializedArchivedDocument) -> anyhow::Result<Self> {
+        Ok(Self {
+            tablet_id: document.table_id.parse()?,
+            internal_id: document.document_id.parse()?,
+            storage_key: document.storage_key.try_into()?,
+        })
+    }
+}
+
+codegen_convex_serialization!(ArchivedDocument, SerializedArchivedDocument);
@@ -0,0 +1,180 @@
+use std::sync::LazyLock;
+
+use common::{
+    document::{
+        CreationTime,
+        ParsedDocument,
+        ResolvedDocument,
+        CREATION_TIME_FIELD_PATH,
+    },
+    query::{
+        IndexRange,
+        IndexRangeExpression,
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+    types::IndexName,
+};
+use database::{
+    unauthorized_error,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use value::{
+    ConvexValue,
+    TableName,
+    TableNamespace,
+};
+
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+use types::AuditLogEntry;
+
+pub static AUDIT_LOG_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_audit_log"
+        .parse()
+        .expect("Invalid built-in audit_log table")
+});
+
+static AUDIT_LOG_BY_CREATION_TIME_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| IndexName::by_creation_time(AUDIT_LOG_TABLE.clone()));
+
+pub struct AuditLogTable;
+impl SystemTable for AuditLogTable {
+    fn table_name(&self) -> &'static TableName {
+        &AUDIT_LOG_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<AuditLogEntry>::try_from(document).map(|_| ())
+    }
+}
+
+/// Records of API calls made with admin keys.
+pub struct AuditLogModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> AuditLogModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    pub async fn insert(&mut self, entry: AuditLogEntry) -> anyhow::Result<()> {
+        if !self.tx.identity().is_system() {
+            anyhow::bail!(unauthorized_error("insert_audit_log_entry"));
+        }
+        SystemMetadataModel::new_global(self.tx)
+            .insert(&AUDIT_LOG_TABLE, entry.try_into()?)
+            .await?;
+        Ok(())
+    }
+
+    /// Up to `limit` entries created before `before`, newest first.
+    pub async fn list(
+        &mut self,
+        before: Option<CreationTime>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<ParsedDocument<AuditLogEntry>>> {
+        if !(self.tx.identity().is_system() || self.tx.identity().is_admin()) {
+            anyhow::bail!(unauthorized_error("list_audit_log"));
+        }
+        let index_range = IndexRange {
+            index_name: AUDIT_LOG_BY_CREATION_TIME_INDEX.clone(),
+            range: before
+                .map(|before| {
+                    IndexRangeExpression::Lt(
+                        CREATION_TIME_FIELD_PATH.clone(),
+                        ConvexValue::from(f64::from(before)),
+                    )
+                })
+                .into_iter()
+                .collect(),
+            order: Order::Desc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        let mut entries = vec![];
+        while entries.len() < limit
+            && let Some(document) = query_stream.next(self.tx, None).await?
+        {
+            entries.push(document.try_into()?);
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{
+        runtime::UnixTimestamp,
+        types::MemberId,
+    };
+    use database::test_helpers::DbFixtures;
+    use keybroker::Identity;
+    use runtime::testing::TestRuntime;
+
+    use super::{
+        types::AuditLogEntry,
+        AuditLogModel,
+    };
+    use crate::test_helpers::DbFixturesWithModel;
+
+    fn entry(path: &str) -> AuditLogEntry {
+        AuditLogEntry {
+            member_id: Some(MemberId(1)),
+            key_fingerprint: "abc".to_string(),
+            timestamp: UnixTimestamp::from_millis(1000),
+            method: "POST".to_string(),
+            route: "/api/update_environment_variables".to_string(),
+            path: path.to_string(),
+            query_params: vec![],
+            body_bytes: Some(10),
+            status: 200,
+        }
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_audit_log_pagination(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin(Identity::system()).await?;
+        for path in ["a", "b", "c"] {
+            AuditLogModel::new(&mut tx).insert(entry(path)).await?;
+        }
+        db.commit(tx).await?;
+
+        let mut tx = db.begin(Identity::system()).await?;
+        let first_page = AuditLogModel::new(&mut tx).list(None, 2).await?;
+        assert_eq!(
+            first_page
+                .iter()
+                .map(|entry| entry.path.as_str())
+                .collect::<Vec<_>>(),
+            vec!["c", "b"]
+        );
+        let cursor = first_page.last().unwrap().creation_time();
+        let second_page = AuditLogModel::new(&mut tx).list(cursor, 2).await?;
+        assert_eq!(
+            second_page
+                .into_iter()
+                .map(|entry| entry.into_value())
+                .collect::<Vec<_>>(),
+            vec![entry("a")]
+        );
+        Ok(())
+    }
+}
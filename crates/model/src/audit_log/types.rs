@@ -0,0 +1,96 @@
+use common::{
+    runtime::UnixTimestamp,
+    types::MemberId,
+};
+#[cfg(any(test, feature = "testing"))]
+use proptest::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use value::codegen_convex_serialization;
+
+/// An API call made with an admin key, recorded in `_audit_log` so
+/// self-hosted deployments can show who changed what.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct AuditLogEntry {
+    /// The member the admin key was issued to, if it was issued to a member
+    /// rather than a team.
+    pub member_id: Option<MemberId>,
+    /// `AdminIdentity::key_fingerprint` of the admin key.
+    pub key_fingerprint: String,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(
+            strategy = "(0..=i64::MAX as u64 / 1_000_000).prop_map(UnixTimestamp::from_millis)"
+        )
+    )]
+    pub timestamp: UnixTimestamp,
+    pub method: String,
+    /// The route that handled the call, like `/api/export/zip/:id`.
+    pub route: String,
+    pub path: String,
+    /// Names of the call's query parameters. Their values and the request
+    /// body aren't recorded, since they can hold secrets like environment
+    /// variable values.
+    pub query_params: Vec<String>,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "proptest::option::of(0..=i64::MAX as u64)")
+    )]
+    pub body_bytes: Option<u64>,
+    pub status: u16,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedAuditLogEntry {
+    member_id: Option<i64>,
+    key_fingerprint: String,
+    timestamp_ms: i64,
+    method: String,
+    route: String,
+    path: String,
+    query_params: Vec<String>,
+    body_bytes: Option<i64>,
+    status: i64,
+}
+
+impl TryFrom<AuditLogEntry> for SerializedAuditLogEntry {
+    type Error = anyhow::Error;
+
+    fn try_from(entry: AuditLogEntry) -> anyhow::Result<Self> {
+        Ok(Self {
+            member_id: entry.member_id.map(|member_id| member_id.0 as i64),
+            key_fingerprint: entry.key_fingerprint,
+            timestamp_ms: entry.timestamp.as_ms_since_epoch()?.try_into()?,
+            method: entry.method,
+            route: entry.route,
+            path: entry.path,
+            query_params: entry.query_params,
+            body_bytes: entry.body_bytes.map(i64::try_from).transpose()?,
+            status: entry.status.into(),
+        })
+    }
+}
+
+impl TryFrom<SerializedAuditLogEntry> for AuditLogEntry {
+    type Error = anyhow::Error;
+
+    fn try_from(entry: SerializedAuditLogEntry) -> anyhow::Result<Self> {
+        Ok(Self {
+            member_id: entry.member_id.map(|member_id| MemberId(member_id as u64)),
+            key_fingerprint: entry.key_fingerprint,
+            timestamp: UnixTimestamp::from_millis(entry.timestamp_ms.try_into()?),
+            method: entry.method,
+            route: entry.route,
+            path: entry.path,
+            query_params: entry.query_params,
+            body_bytes: entry.body_bytes.map(u64::try_from).transpose()?,
+            status: entry.status.try_into()?,
+        })
+    }
+}
+
+codegen_convex_serialization!(AuditLogEntry, SerializedAuditLogEntry);
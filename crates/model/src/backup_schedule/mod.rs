@@ -0,0 +1,167 @@
+use std::sync::LazyLock;
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    query::{
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+};
+use database::{
+    unauthorized_error,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use value::{
+    TableName,
+    TableNamespace,
+};
+
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+use types::BackupSchedule;
+
+pub static BACKUP_SCHEDULE_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_backup_schedule"
+        .parse()
+        .expect("Invalid built-in backup_schedule table")
+});
+
+pub struct BackupScheduleTable;
+impl SystemTable for BackupScheduleTable {
+    fn table_name(&self) -> &'static TableName {
+        &BACKUP_SCHEDULE_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<BackupSchedule>::try_from(document).map(|_| ())
+    }
+}
+
+/// The deployment's automatic backup schedule, which has at most one row.
+pub struct BackupScheduleModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> BackupScheduleModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    pub async fn get(&mut self) -> anyhow::Result<Option<ParsedDocument<BackupSchedule>>> {
+        let query = Query::full_table_scan(BACKUP_SCHEDULE_TABLE.clone(), Order::Asc);
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        query_stream
+            .expect_at_most_one(self.tx)
+            .await?
+            .map(|document| document.try_into())
+            .transpose()
+    }
+
+    /// Replaces the backup schedule, or turns off scheduled backups if
+    /// `schedule` is `None`. Existing backups are kept either way.
+    pub async fn set(&mut self, schedule: Option<BackupSchedule>) -> anyhow::Result<()> {
+        if !(self.tx.identity().is_system() || self.tx.identity().is_admin()) {
+            anyhow::bail!(unauthorized_error("set_backup_schedule"));
+        }
+        let existing = self.get().await?;
+        match (existing, schedule) {
+            (Some(existing), Some(schedule)) => {
+                SystemMetadataModel::new_global(self.tx)
+                    .replace(existing.id(), schedule.try_into()?)
+                    .await?;
+            },
+            (Some(existing), None) => {
+                SystemMetadataModel::new_global(self.tx)
+                    .delete(existing.id())
+                    .await?;
+            },
+            (None, Some(schedule)) => {
+                SystemMetadataModel::new_global(self.tx)
+                    .insert(&BACKUP_SCHEDULE_TABLE, schedule.try_into()?)
+                    .await?;
+            },
+            (None, None) => {},
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use database::test_helpers::DbFixtures;
+    use keybroker::Identity;
+    use runtime::testing::TestRuntime;
+    use sync_types::Timestamp;
+
+    use super::{
+        types::BackupSchedule,
+        BackupScheduleModel,
+    };
+    use crate::{
+        cron_jobs::types::CronSchedule,
+        test_helpers::DbFixturesWithModel,
+    };
+
+    fn daily_schedule() -> BackupSchedule {
+        BackupSchedule {
+            schedule: CronSchedule::Daily {
+                hour_utc: 3,
+                minute_utc: 0,
+            },
+            include_storage: false,
+            retention_count: Some(2),
+            retention_age: Some(Duration::from_secs(100)),
+            next_backup_ts: Timestamp::must(1000),
+        }
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_set_schedule(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin(Identity::system()).await?;
+        assert!(BackupScheduleModel::new(&mut tx).get().await?.is_none());
+        BackupScheduleModel::new(&mut tx)
+            .set(Some(daily_schedule()))
+            .await?;
+        let schedule = BackupScheduleModel::new(&mut tx)
+            .get()
+            .await?
+            .unwrap()
+            .into_value();
+        assert_eq!(schedule, daily_schedule());
+        BackupScheduleModel::new(&mut tx).set(None).await?;
+        assert!(BackupScheduleModel::new(&mut tx).get().await?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_retains() {
+        let schedule = daily_schedule();
+        let now = Timestamp::try_from(Duration::from_secs(1000).as_nanos() as u64).unwrap();
+        let recent = Timestamp::try_from(Duration::from_secs(950).as_nanos() as u64).unwrap();
+        let old = Timestamp::try_from(Duration::from_secs(850).as_nanos() as u64).unwrap();
+        assert!(schedule.retains(0, recent, now));
+        assert!(schedule.retains(1, recent, now));
+        // Too many newer backups.
+        assert!(!schedule.retains(2, recent, now));
+        // Too old.
+        assert!(!schedule.retains(0, old, now));
+    }
+}
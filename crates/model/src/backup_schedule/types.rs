@@ -0,0 +1,148 @@
+use std::time::Duration;
+
+use anyhow::Context;
+use errors::ErrorMetadata;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use sync_types::Timestamp;
+use value::codegen_convex_serialization;
+
+use crate::cron_jobs::{
+    next_ts::compute_next_schedule_ts,
+    types::{
+        CronSchedule,
+        SerializedCronSchedule,
+    },
+};
+
+/// A deployment's automatic backup configuration. Backups are snapshot
+/// exports of the root component requested with
+/// [`ExportRequestor::ScheduledBackup`](crate::exports::types::ExportRequestor).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct BackupSchedule {
+    pub schedule: CronSchedule,
+    pub include_storage: bool,
+    /// Keep at most this many of the most recent backups.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "proptest::option::of(0..=i64::MAX as u64)")
+    )]
+    pub retention_count: Option<u64>,
+    /// Delete backups that started more than this long ago.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(
+            strategy = "proptest::option::of(proptest::strategy::Strategy::prop_map(
+            0..=i64::MAX as u64,
+            Duration::from_secs
+        ))"
+        )
+    )]
+    pub retention_age: Option<Duration>,
+    /// When the next backup will be requested.
+    pub next_backup_ts: Timestamp,
+}
+
+impl BackupSchedule {
+    /// A new schedule whose first backup is at the schedule's next time after
+    /// `now`.
+    pub fn new(
+        schedule: CronSchedule,
+        include_storage: bool,
+        retention_count: Option<u64>,
+        retention_age: Option<Duration>,
+        now: Timestamp,
+    ) -> anyhow::Result<Self> {
+        if let CronSchedule::Interval { seconds } = schedule {
+            anyhow::ensure!(
+                seconds > 0,
+                ErrorMetadata::bad_request(
+                    "InvalidBackupSchedule",
+                    "The backup interval must be positive"
+                )
+            );
+        }
+        anyhow::ensure!(
+            retention_count != Some(0),
+            ErrorMetadata::bad_request(
+                "InvalidBackupRetention",
+                "The backup retention count must be positive"
+            )
+        );
+        let next_backup_ts = compute_next_schedule_ts(&schedule, None, now).context(
+            ErrorMetadata::bad_request("InvalidBackupSchedule", "Invalid backup schedule"),
+        )?;
+        Ok(Self {
+            schedule,
+            include_storage,
+            retention_count,
+            retention_age,
+            next_backup_ts,
+        })
+    }
+
+    /// Whether a backup that started at `start_ts` should be kept, given it's
+    /// the `index`th most recent one.
+    pub fn retains(&self, index: usize, start_ts: Timestamp, now: Timestamp) -> bool {
+        if let Some(retention_count) = self.retention_count
+            && index as u64 >= retention_count
+        {
+            return false;
+        }
+        if let Some(retention_age) = self.retention_age
+            && now.secs_since_f64(start_ts) > retention_age.as_secs_f64()
+        {
+            return false;
+        }
+        true
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedBackupSchedule {
+    schedule: SerializedCronSchedule,
+    include_storage: bool,
+    retention_count: Option<i64>,
+    retention_age_secs: Option<i64>,
+    next_backup_ts: i64,
+}
+
+impl TryFrom<BackupSchedule> for SerializedBackupSchedule {
+    type Error = anyhow::Error;
+
+    fn try_from(schedule: BackupSchedule) -> anyhow::Result<Self> {
+        Ok(Self {
+            schedule: schedule.schedule.try_into()?,
+            include_storage: schedule.include_storage,
+            retention_count: schedule.retention_count.map(i64::try_from).transpose()?,
+            retention_age_secs: schedule
+                .retention_age
+                .map(|age| i64::try_from(age.as_secs()))
+                .transpose()?,
+            next_backup_ts: schedule.next_backup_ts.into(),
+        })
+    }
+}
+
+impl TryFrom<SerializedBackupSchedule> for BackupSchedule {
+    type Error = anyhow::Error;
+
+    fn try_from(schedule: SerializedBackupSchedule) -> anyhow::Result<Self> {
+        Ok(Self {
+            schedule: schedule.schedule.try_into()?,
+            include_storage: schedule.include_storage,
+            retention_count: schedule.retention_count.map(u64::try_from).transpose()?,
+            retention_age: schedule
+                .retention_age_secs
+                .map(|secs| anyhow::Ok(Duration::from_secs(u64::try_from(secs)?)))
+                .transpose()?,
+            next_backup_ts: schedule.next_backup_ts.try_into()?,
+        })
+    }
+}
+
+codegen_convex_serialization!(BackupSchedule, SerializedBackupSchedule);
@@ -10,7 +10,10 @@ use std::{
 use common::{
     bootstrap_model::index::{
         database_index::DatabaseIndexState,
-        text_index::TextIndexState,
+        text_index::{
+            TextIndexAnalyzer,
+            TextIndexState,
+        },
         vector_index::VectorIndexState,
         IndexConfig,
     },
@@ -82,6 +85,7 @@ macro_rules! db_schema_with_search_indexes {
                                 index_name.descriptor().clone(),
                                 field_path.try_into()?,
                                 BTreeSet::new(),
+                                TextIndexAnalyzer::default(),
                             )?,
                         );
                     )*
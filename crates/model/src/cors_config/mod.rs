@@ -0,0 +1,145 @@
+use std::sync::LazyLock;
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    query::{
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+};
+use database::{
+    unauthorized_error,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use value::{
+    TableName,
+    TableNamespace,
+};
+
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+use types::CorsConfig;
+
+pub static CORS_CONFIG_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_cors_config"
+        .parse()
+        .expect("Invalid built-in cors_config table")
+});
+
+pub struct CorsConfigTable;
+impl SystemTable for CorsConfigTable {
+    fn table_name(&self) -> &'static TableName {
+        &CORS_CONFIG_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<CorsConfig>::try_from(document).map(|_| ())
+    }
+}
+
+/// The deployment's CORS policy for HTTP actions, which has at most one row.
+pub struct CorsConfigModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> CorsConfigModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    pub async fn get(&mut self) -> anyhow::Result<Option<ParsedDocument<CorsConfig>>> {
+        let query = Query::full_table_scan(CORS_CONFIG_TABLE.clone(), Order::Asc);
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        query_stream
+            .expect_at_most_one(self.tx)
+            .await?
+            .map(|document| document.try_into())
+            .transpose()
+    }
+
+    /// Replaces the CORS policy, or removes it if `config` is `None` so HTTP
+    /// actions set their own CORS headers again.
+    pub async fn set(&mut self, config: Option<CorsConfig>) -> anyhow::Result<()> {
+        if !(self.tx.identity().is_system() || self.tx.identity().is_admin()) {
+            anyhow::bail!(unauthorized_error("set_cors_config"));
+        }
+        let existing = self.get().await?;
+        match (existing, config) {
+            (Some(existing), Some(config)) => {
+                SystemMetadataModel::new_global(self.tx)
+                    .replace(existing.id(), config.try_into()?)
+                    .await?;
+            },
+            (Some(existing), None) => {
+                SystemMetadataModel::new_global(self.tx)
+                    .delete(existing.id())
+                    .await?;
+            },
+            (None, Some(config)) => {
+                SystemMetadataModel::new_global(self.tx)
+                    .insert(&CORS_CONFIG_TABLE, config.try_into()?)
+                    .await?;
+            },
+            (None, None) => {},
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use database::test_helpers::DbFixtures;
+    use keybroker::Identity;
+    use runtime::testing::TestRuntime;
+
+    use super::{
+        types::CorsConfig,
+        CorsConfigModel,
+    };
+    use crate::test_helpers::DbFixturesWithModel;
+
+    #[convex_macro::test_runtime]
+    async fn test_set_cors_config(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin(Identity::system()).await?;
+        assert!(CorsConfigModel::new(&mut tx).get().await?.is_none());
+        let config = CorsConfig::new(
+            vec!["https://example.com".to_string()],
+            vec!["POST".to_string()],
+            vec!["content-type".to_string()],
+            Some(Duration::from_secs(600)),
+        )?;
+        CorsConfigModel::new(&mut tx)
+            .set(Some(config.clone()))
+            .await?;
+        let stored = CorsConfigModel::new(&mut tx)
+            .get()
+            .await?
+            .unwrap()
+            .into_value();
+        assert_eq!(stored, config);
+        CorsConfigModel::new(&mut tx).set(None).await?;
+        assert!(CorsConfigModel::new(&mut tx).get().await?.is_none());
+
+        let mut tx = db.begin(Identity::Unknown).await?;
+        assert!(CorsConfigModel::new(&mut tx).set(None).await.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,262 @@
+use std::time::Duration;
+
+use errors::ErrorMetadata;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use value::codegen_convex_serialization;
+
+/// A deployment's CORS policy, which the HTTP action router applies to every
+/// HTTP action so they don't each have to set CORS headers and answer
+/// preflight requests themselves.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct CorsConfig {
+    /// Origins like `https://example.com` that may call HTTP actions. An
+    /// origin can start its host with `*.` to allow any subdomain, and `*`
+    /// allows every origin.
+    pub allowed_origins: Vec<String>,
+    /// Methods preflight requests may ask for, in upper case. Empty allows
+    /// any method.
+    pub allowed_methods: Vec<String>,
+    /// Request headers preflight requests may ask for, in lower case. `*`
+    /// allows any header.
+    pub allowed_headers: Vec<String>,
+    /// How long browsers may cache the result of a preflight request.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(
+            strategy = "proptest::option::of(proptest::strategy::Strategy::prop_map(
+            0..=i64::MAX as u64,
+            Duration::from_secs
+        ))"
+        )
+    )]
+    pub max_age: Option<Duration>,
+}
+
+impl CorsConfig {
+    pub fn new(
+        allowed_origins: Vec<String>,
+        allowed_methods: Vec<String>,
+        allowed_headers: Vec<String>,
+        max_age: Option<Duration>,
+    ) -> anyhow::Result<Self> {
+        for origin in &allowed_origins {
+            anyhow::ensure!(
+                is_valid_origin(origin),
+                ErrorMetadata::bad_request(
+                    "InvalidCorsOrigin",
+                    format!(
+                        "Invalid allowed origin {origin:?}. Origins look like \
+                         \"https://example.com\" or \"https://*.example.com\", with no path, or \
+                         are \"*\"."
+                    )
+                )
+            );
+        }
+        for (kind, values) in [("method", &allowed_methods), ("header", &allowed_headers)] {
+            for value in values {
+                anyhow::ensure!(
+                    is_token(value) || (kind == "header" && value == "*"),
+                    ErrorMetadata::bad_request(
+                        "InvalidCorsConfig",
+                        format!("Invalid allowed {kind} {value:?}")
+                    )
+                );
+            }
+        }
+        Ok(Self {
+            allowed_origins,
+            allowed_methods: allowed_methods
+                .into_iter()
+                .map(|method| method.to_ascii_uppercase())
+                .collect(),
+            allowed_headers: allowed_headers
+                .into_iter()
+                .map(|header| header.to_ascii_lowercase())
+                .collect(),
+            max_age,
+        })
+    }
+
+    /// Whether a request from `origin`, as sent in the `Origin` header, may
+    /// read the response.
+    pub fn allows_origin(&self, origin: &str) -> bool {
+        self.allowed_origins.iter().any(|allowed| {
+            if allowed == "*" {
+                return true;
+            }
+            match allowed.split_once("://*.") {
+                Some((scheme, domain)) => origin
+                    .strip_prefix(scheme)
+                    .and_then(|rest| rest.strip_prefix("://"))
+                    .and_then(|host| host.strip_suffix(domain))
+                    .is_some_and(|subdomain| {
+                        subdomain.len() > 1 && subdomain.ends_with('.') && !subdomain.contains('/')
+                    }),
+                None => allowed.eq_ignore_ascii_case(origin),
+            }
+        })
+    }
+
+    /// Whether every origin is allowed, so responses can say `*` instead of
+    /// echoing the request's origin.
+    pub fn allows_any_origin(&self) -> bool {
+        self.allowed_origins.iter().any(|allowed| allowed == "*")
+    }
+
+    pub fn allows_method(&self, method: &str) -> bool {
+        self.allowed_methods.is_empty()
+            || self
+                .allowed_methods
+                .iter()
+                .any(|allowed| allowed.eq_ignore_ascii_case(method))
+    }
+
+    pub fn allows_header(&self, header: &str) -> bool {
+        self.allowed_headers
+            .iter()
+            .any(|allowed| allowed == "*" || allowed.eq_ignore_ascii_case(header))
+    }
+}
+
+fn is_valid_origin(origin: &str) -> bool {
+    if origin == "*" {
+        return true;
+    }
+    let Some(host) = origin
+        .strip_prefix("https://")
+        .or_else(|| origin.strip_prefix("http://"))
+    else {
+        return false;
+    };
+    let host = host.strip_prefix("*.").unwrap_or(host);
+    !host.is_empty()
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':' | '[' | ']'))
+}
+
+/// An HTTP token, which methods and header names must be.
+fn is_token(value: &str) -> bool {
+    !value.is_empty()
+        && value.bytes().all(|b| {
+            b.is_ascii_alphanumeric()
+                || matches!(
+                    b,
+                    b'!' | b'#'
+                        | b'$'
+                        | b'%'
+                        | b'&'
+                        | b'\''
+                        | b'*'
+                        | b'+'
+                        | b'-'
+                        | b'.'
+                        | b'^'
+                        | b'_'
+                        | b'`'
+                        | b'|'
+                        | b'~'
+                )
+        })
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedCorsConfig {
+    allowed_origins: Vec<String>,
+    allowed_methods: Vec<String>,
+    allowed_headers: Vec<String>,
+    max_age_secs: Option<i64>,
+}
+
+impl TryFrom<CorsConfig> for SerializedCorsConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(config: CorsConfig) -> anyhow::Result<Self> {
+        Ok(Self {
+            allowed_origins: config.allowed_origins,
+            allowed_methods: config.allowed_methods,
+            allowed_headers: config.allowed_headers,
+            max_age_secs: config
+                .max_age
+                .map(|max_age| i64::try_from(max_age.as_secs()))
+                .transpose()?,
+        })
+    }
+}
+
+impl TryFrom<SerializedCorsConfig> for CorsConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(config: SerializedCorsConfig) -> anyhow::Result<Self> {
+        Ok(Self {
+            allowed_origins: config.allowed_origins,
+            allowed_methods: config.allowed_methods,
+            allowed_headers: config.allowed_headers,
+            max_age: config
+                .max_age_secs
+                .map(|secs| anyhow::Ok(Duration::from_secs(u64::try_from(secs)?)))
+                .transpose()?,
+        })
+    }
+}
+
+codegen_convex_serialization!(CorsConfig, SerializedCorsConfig);
+
+#[cfg(test)]
+mod tests {
+    use errors::ErrorMetadataAnyhowExt;
+
+    use super::CorsConfig;
+
+    fn with_origins(origins: &[&str]) -> CorsConfig {
+        CorsConfig::new(
+            origins.iter().map(|o| o.to_string()).collect(),
+            vec!["get".to_string(), "POST".to_string()],
+            vec!["Content-Type".to_string()],
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_allows_origin() {
+        let config = with_origins(&["https://example.com", "https://*.preview.dev"]);
+        assert!(config.allows_origin("https://example.com"));
+        assert!(!config.allows_origin("http://example.com"));
+        assert!(!config.allows_origin("https://example.com.evil.com"));
+        assert!(config.allows_origin("https://pr-1.preview.dev"));
+        assert!(config.allows_origin("https://a.b.preview.dev"));
+        assert!(!config.allows_origin("https://preview.dev"));
+        assert!(!config.allows_origin("https://evilpreview.dev"));
+        assert!(!config.allows_any_origin());
+
+        let config = with_origins(&["*"]);
+        assert!(config.allows_origin("http://localhost:3000"));
+        assert!(config.allows_any_origin());
+    }
+
+    #[test]
+    fn test_normalizes_methods_and_headers() {
+        let config = with_origins(&["*"]);
+        assert_eq!(config.allowed_methods, vec!["GET", "POST"]);
+        assert!(config.allows_method("get"));
+        assert!(!config.allows_method("DELETE"));
+        assert!(config.allows_header("content-type"));
+        assert!(!config.allows_header("authorization"));
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        for origin in ["example.com", "https://example.com/", "https://"] {
+            let err = CorsConfig::new(vec![origin.to_string()], vec![], vec![], None).unwrap_err();
+            assert_eq!(err.short_msg(), "InvalidCorsOrigin");
+        }
+        let err = CorsConfig::new(vec![], vec!["GE T".to_string()], vec![], None).unwrap_err();
+        assert_eq!(err.short_msg(), "InvalidCorsConfig");
+    }
+}
@@ -0,0 +1,418 @@
+//! Sharded counters, for counts that many concurrent mutations update. A
+//! counter stored in a single document makes every concurrent increment
+//! conflict with the others, so instead its value is split across shards and
+//! each increment updates a random one. Reading the counter sums its shards.
+//!
+//! [`ShardCountTuner`] picks each counter's number of shards from how often
+//! increments to it conflict, so idle counters stay cheap to read and hot ones
+//! spread out.
+use std::{
+    collections::BTreeMap,
+    sync::LazyLock,
+};
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    knobs::COUNTER_MAX_SHARDS,
+    query::{
+        IndexRange,
+        IndexRangeExpression,
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+    types::IndexName,
+};
+use database::{
+    defaults::system_index,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use errors::ErrorMetadata;
+use rand::Rng;
+use value::{
+    ConvexValue,
+    FieldPath,
+    TableName,
+    TableNamespace,
+};
+
+use self::types::{
+    CounterMetadata,
+    CounterShard,
+};
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+pub static COUNTERS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_counters"
+        .parse()
+        .expect("Invalid built-in counters table")
+});
+
+pub static COUNTER_SHARDS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_counter_shards"
+        .parse()
+        .expect("Invalid built-in counter shards table")
+});
+
+pub static COUNTERS_BY_NAME_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&COUNTERS_TABLE, "by_name"));
+
+pub static COUNTER_SHARDS_BY_NAME_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&COUNTER_SHARDS_TABLE, "by_name_and_shard"));
+
+static NAME_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "name".parse().expect("invalid name field"));
+static SHARD_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "shard".parse().expect("invalid shard field"));
+
+pub struct CountersTable;
+impl SystemTable for CountersTable {
+    fn table_name(&self) -> &'static TableName {
+        &COUNTERS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![SystemIndex {
+            name: COUNTERS_BY_NAME_INDEX.clone(),
+            fields: vec![NAME_FIELD.clone()].try_into().unwrap(),
+        }]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<CounterMetadata>::try_from(document).map(|_| ())
+    }
+}
+
+pub struct CounterShardsTable;
+impl SystemTable for CounterShardsTable {
+    fn table_name(&self) -> &'static TableName {
+        &COUNTER_SHARDS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![SystemIndex {
+            name: COUNTER_SHARDS_BY_NAME_INDEX.clone(),
+            fields: vec![NAME_FIELD.clone(), SHARD_FIELD.clone()]
+                .try_into()
+                .unwrap(),
+        }]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<CounterShard>::try_from(document).map(|_| ())
+    }
+}
+
+pub struct CounterModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> CounterModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    async fn metadata(
+        &mut self,
+        name: &str,
+    ) -> anyhow::Result<Option<ParsedDocument<CounterMetadata>>> {
+        let index_range = IndexRange {
+            index_name: COUNTERS_BY_NAME_INDEX.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                NAME_FIELD.clone(),
+                ConvexValue::try_from(name.to_string())?.into(),
+            )],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        query_stream
+            .expect_at_most_one(self.tx)
+            .await?
+            .map(ParsedDocument::try_from)
+            .transpose()
+    }
+
+    pub async fn num_shards(&mut self, name: &str) -> anyhow::Result<u32> {
+        Ok(self
+            .metadata(name)
+            .await?
+            .map_or(1, |metadata| metadata.num_shards))
+    }
+
+    async fn shards(
+        &mut self,
+        name: &str,
+        shard: Option<u32>,
+    ) -> anyhow::Result<Vec<ParsedDocument<CounterShard>>> {
+        let mut range = vec![IndexRangeExpression::Eq(
+            NAME_FIELD.clone(),
+            ConvexValue::try_from(name.to_string())?.into(),
+        )];
+        if let Some(shard) = shard {
+            range.push(IndexRangeExpression::Eq(
+                SHARD_FIELD.clone(),
+                ConvexValue::from(i64::from(shard)).into(),
+            ));
+        }
+        let index_range = IndexRange {
+            index_name: COUNTER_SHARDS_BY_NAME_INDEX.clone(),
+            range,
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        let mut shards = vec![];
+        while let Some(doc) = query_stream.next(self.tx, None).await? {
+            shards.push(doc.try_into()?);
+        }
+        Ok(shards)
+    }
+
+    /// The counter's value, which is zero for counters that have never been
+    /// added to.
+    pub async fn get(&mut self, name: &str) -> anyhow::Result<i64> {
+        let mut total: i64 = 0;
+        for shard in self.shards(name, None).await? {
+            total = total
+                .checked_add(shard.value)
+                .ok_or_else(|| counter_overflow_error(name))?;
+        }
+        Ok(total)
+    }
+
+    /// Add `delta` to one of the counter's shards, picked at random.
+    pub async fn add(&mut self, name: &str, delta: i64) -> anyhow::Result<()> {
+        let num_shards = self.num_shards(name).await?;
+        let shard = self.tx.runtime().rng().gen_range(0..num_shards);
+        self.add_to_shard(name, shard, delta).await
+    }
+
+    async fn add_to_shard(&mut self, name: &str, shard: u32, delta: i64) -> anyhow::Result<()> {
+        match self.shards(name, Some(shard)).await?.pop() {
+            Some(existing) => {
+                let (id, mut counter_shard) = existing.into_id_and_value();
+                counter_shard.value = counter_shard
+                    .value
+                    .checked_add(delta)
+                    .ok_or_else(|| counter_overflow_error(name))?;
+                SystemMetadataModel::new_global(self.tx)
+                    .replace(id, counter_shard.try_into()?)
+                    .await?;
+            },
+            None => {
+                let counter_shard = CounterShard {
+                    name: name.to_string(),
+                    shard,
+                    value: delta,
+                };
+                SystemMetadataModel::new_global(self.tx)
+                    .insert(&COUNTER_SHARDS_TABLE, counter_shard.try_into()?)
+                    .await?;
+            },
+        }
+        Ok(())
+    }
+
+    /// Change the number of shards the counter's value is split across,
+    /// moving the value of any shards that no longer exist into the first.
+    /// This reads and may write every shard, so it conflicts with all
+    /// concurrent increments.
+    pub async fn set_num_shards(&mut self, name: &str, num_shards: u32) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            (1..=*COUNTER_MAX_SHARDS).contains(&num_shards),
+            ErrorMetadata::bad_request(
+                "InvalidCounterShards",
+                format!(
+                    "A counter must have between 1 and {} shards",
+                    *COUNTER_MAX_SHARDS
+                ),
+            )
+        );
+        let mut removed_total: i64 = 0;
+        for shard in self.shards(name, None).await? {
+            if shard.shard >= num_shards {
+                removed_total = removed_total
+                    .checked_add(shard.value)
+                    .ok_or_else(|| counter_overflow_error(name))?;
+                SystemMetadataModel::new_global(self.tx)
+                    .delete(shard.id())
+                    .await?;
+            }
+        }
+        if removed_total != 0 {
+            self.add_to_shard(name, 0, removed_total).await?;
+        }
+        let metadata = CounterMetadata {
+            name: name.to_string(),
+            num_shards,
+        };
+        match self.metadata(name).await? {
+            Some(existing) => {
+                SystemMetadataModel::new_global(self.tx)
+                    .replace(existing.id(), metadata.try_into()?)
+                    .await?;
+            },
+            None => {
+                SystemMetadataModel::new_global(self.tx)
+                    .insert(&COUNTERS_TABLE, metadata.try_into()?)
+                    .await?;
+            },
+        }
+        Ok(())
+    }
+
+    /// Delete the counter, resetting it to zero.
+    pub async fn delete(&mut self, name: &str) -> anyhow::Result<()> {
+        for shard in self.shards(name, None).await? {
+            SystemMetadataModel::new_global(self.tx)
+                .delete(shard.id())
+                .await?;
+        }
+        if let Some(metadata) = self.metadata(name).await? {
+            SystemMetadataModel::new_global(self.tx)
+                .delete(metadata.id())
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+fn counter_overflow_error(name: &str) -> ErrorMetadata {
+    ErrorMetadata::bad_request(
+        "CounterOverflow",
+        format!("Counter {name} would overflow a 64-bit integer"),
+    )
+}
+
+/// The number of increments to a counter to look at before deciding whether
+/// to change its number of shards.
+const TUNING_WINDOW: u32 = 100;
+/// Add shards when more than this fraction of increments conflict.
+const GROW_CONFLICT_RATE: f64 = 0.1;
+
+#[derive(Default)]
+struct ConflictStats {
+    attempts: u32,
+    conflicts: u32,
+}
+
+/// Tracks how often increments to each counter conflict, recommending more
+/// shards for counters that conflict a lot and fewer for counters that never
+/// conflict. Counts are kept in memory, since writing them would be its own
+/// hotspot.
+#[derive(Default)]
+pub struct ShardCountTuner {
+    stats: BTreeMap<String, ConflictStats>,
+}
+
+impl ShardCountTuner {
+    /// Record an attempt to add to the counter `name` and whether it failed
+    /// with an OCC conflict.
+    pub fn record(&mut self, name: &str, conflicted: bool) {
+        let stats = self.stats.entry(name.to_string()).or_default();
+        stats.attempts += 1;
+        if conflicted {
+            stats.conflicts += 1;
+        }
+    }
+
+    /// The number of shards the counter should change to from `num_shards`,
+    /// if it's seen enough increments to tell.
+    pub fn recommend(&mut self, name: &str, num_shards: u32) -> Option<u32> {
+        let stats = self.stats.get(name)?;
+        let conflict_rate = stats.conflicts as f64 / stats.attempts as f64;
+        // React to a burst of conflicts before the window fills up.
+        let conflicting =
+            stats.conflicts >= TUNING_WINDOW / 10 && conflict_rate > GROW_CONFLICT_RATE;
+        if !conflicting && stats.attempts < TUNING_WINDOW {
+            return None;
+        }
+        self.stats.remove(name);
+        let recommended = if conflict_rate > GROW_CONFLICT_RATE {
+            num_shards.saturating_mul(2).min(*COUNTER_MAX_SHARDS)
+        } else if conflict_rate == 0.0 {
+            (num_shards / 2).max(1)
+        } else {
+            num_shards
+        };
+        (recommended != num_shards).then_some(recommended)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use database::test_helpers::DbFixtures;
+    use runtime::testing::TestRuntime;
+
+    use super::{
+        CounterModel,
+        ShardCountTuner,
+        TUNING_WINDOW,
+    };
+    use crate::test_helpers::DbFixturesWithModel;
+
+    #[convex_macro::test_runtime]
+    async fn test_sharded_counter(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin_system().await?;
+        let mut model = CounterModel::new(&mut tx);
+        assert_eq!(model.get("likes").await?, 0);
+
+        model.set_num_shards("likes", 8).await?;
+        for _ in 0..20 {
+            model.add("likes", 2).await?;
+        }
+        model.add("likes", -5).await?;
+        assert_eq!(model.get("likes").await?, 35);
+        assert_eq!(model.get("dislikes").await?, 0);
+
+        // Shrinking keeps the value.
+        model.set_num_shards("likes", 1).await?;
+        assert_eq!(model.num_shards("likes").await?, 1);
+        assert_eq!(model.get("likes").await?, 35);
+
+        model.delete("likes").await?;
+        assert_eq!(model.get("likes").await?, 0);
+        assert_eq!(model.num_shards("likes").await?, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_shard_count_tuner() {
+        let mut tuner = ShardCountTuner::default();
+        for i in 0..TUNING_WINDOW {
+            tuner.record("hot", i % 2 == 0);
+            if let Some(num_shards) = tuner.recommend("hot", 4) {
+                assert_eq!(num_shards, 8);
+                break;
+            }
+        }
+        assert!(tuner.stats.get("hot").is_none());
+
+        for _ in 0..TUNING_WINDOW - 1 {
+            tuner.record("cold", false);
+            assert_eq!(tuner.recommend("cold", 4), None);
+        }
+        tuner.record("cold", false);
+        assert_eq!(tuner.recommend("cold", 4), Some(2));
+        tuner.record("cold", false);
+        assert_eq!(tuner.recommend("cold", 1), None);
+    }
+}
@@ -0,0 +1,92 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use value::codegen_convex_serialization;
+
+/// The configuration of a sharded counter. Counters without a document here
+/// have a single shard.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct CounterMetadata {
+    pub name: String,
+    #[cfg_attr(any(test, feature = "testing"), proptest(strategy = "1..=1024u32"))]
+    pub num_shards: u32,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SerializedCounterMetadata {
+    name: String,
+    num_shards: i64,
+}
+
+impl TryFrom<CounterMetadata> for SerializedCounterMetadata {
+    type Error = anyhow::Error;
+
+    fn try_from(metadata: CounterMetadata) -> anyhow::Result<Self> {
+        Ok(SerializedCounterMetadata {
+            name: metadata.name,
+            num_shards: metadata.num_shards.into(),
+        })
+    }
+}
+
+impl TryFrom<SerializedCounterMetadata> for CounterMetadata {
+    type Error = anyhow::Error;
+
+    fn try_from(metadata: SerializedCounterMetadata) -> anyhow::Result<Self> {
+        let num_shards = metadata.num_shards.try_into()?;
+        anyhow::ensure!(num_shards > 0, "Counter {} has no shards", metadata.name);
+        Ok(CounterMetadata {
+            name: metadata.name,
+            num_shards,
+        })
+    }
+}
+
+codegen_convex_serialization!(CounterMetadata, SerializedCounterMetadata);
+
+/// One of the sub-counters a counter's value is split across. Concurrent
+/// increments usually land on different shards, so they don't conflict.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct CounterShard {
+    pub name: String,
+    pub shard: u32,
+    pub value: i64,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SerializedCounterShard {
+    name: String,
+    shard: i64,
+    value: i64,
+}
+
+impl TryFrom<CounterShard> for SerializedCounterShard {
+    type Error = anyhow::Error;
+
+    fn try_from(shard: CounterShard) -> anyhow::Result<Self> {
+        Ok(SerializedCounterShard {
+            name: shard.name,
+            shard: shard.shard.into(),
+            value: shard.value,
+        })
+    }
+}
+
+impl TryFrom<SerializedCounterShard> for CounterShard {
+    type Error = anyhow::Error;
+
+    fn try_from(shard: SerializedCounterShard) -> anyhow::Result<Self> {
+        Ok(CounterShard {
+            name: shard.name,
+            shard: shard.shard.try_into()?,
+            value: shard.value,
+        })
+    }
+}
+
+codegen_convex_serialization!(CounterShard, SerializedCounterShard);
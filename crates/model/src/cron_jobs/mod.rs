@@ -216,7 +216,10 @@ impl<'a, RT: Runtime> CronModel<'a, RT> {
         new_cron_spec: CronSpec,
     ) -> anyhow::Result<()> {
         let (job_id, mut cron_job) = cron_job.into_id_and_value();
-        if new_cron_spec.cron_schedule != cron_job.cron_spec.cron_schedule {
+        if new_cron_spec.cron_schedule != cron_job.cron_spec.cron_schedule
+            || new_cron_spec.time_zone != cron_job.cron_spec.time_zone
+            || new_cron_spec.jitter != cron_job.cron_spec.jitter
+        {
             let now = self.runtime().generate_timestamp()?;
             cron_job.next_ts = compute_next_ts(&new_cron_spec, cron_job.prev_ts, now)?;
         }
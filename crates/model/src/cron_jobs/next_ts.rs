@@ -2,23 +2,79 @@ use std::time::Duration;
 
 use anyhow::Context;
 use chrono::{
+    DateTime,
+    LocalResult,
     TimeZone,
     Utc,
 };
+use chrono_tz::Tz;
 use saffron::Cron;
 use sync_types::Timestamp;
+use value::sha256::Sha256;
 
 use super::types::{
     CronSchedule,
     CronSpec,
 };
 
+/// How many times a schedule is stepped past local times that don't map to a
+/// later instant before giving up.
+const MAX_LOCAL_TIME_STEPS: usize = 8;
+
 pub fn compute_next_ts(
     cron_spec: &CronSpec,
     prev_ts: Option<Timestamp>,
     now: Timestamp,
 ) -> anyhow::Result<Timestamp> {
-    let cron: Cron = match cron_spec.cron_schedule.clone() {
+    let time_zone = match &cron_spec.time_zone {
+        Some(time_zone) => time_zone
+            .parse::<Tz>()
+            .map_err(|e| anyhow::anyhow!("Invalid time zone {time_zone:?}: {e}"))?,
+        None => Tz::UTC,
+    };
+    let Some(offset) = jitter_offset(cron_spec) else {
+        return compute_next_schedule_ts_in(&cron_spec.cron_schedule, time_zone, prev_ts, now);
+    };
+    // Every run is shifted by the same offset, so compute the schedule from
+    // the unshifted previous run to keep the runs from drifting.
+    let prev_ts = prev_ts.map(|prev_ts| prev_ts.sub(offset)).transpose()?;
+    compute_next_schedule_ts_in(&cron_spec.cron_schedule, time_zone, prev_ts, now)?.add(offset)
+}
+
+/// A stable offset between zero and the spec's jitter, derived from the
+/// function and arguments so that jobs on the same schedule are spread out
+/// but each one still runs at a predictable time.
+fn jitter_offset(cron_spec: &CronSpec) -> Option<Duration> {
+    let jitter = cron_spec.jitter?;
+    let mut hasher = Sha256::new();
+    hasher.update(cron_spec.udf_path.to_string().as_bytes());
+    hasher.update(cron_spec.udf_args.to_string().as_bytes());
+    let digest = hasher.finalize();
+    let seed = u64::from_le_bytes(digest[..8].try_into().expect("digest is 32 bytes"));
+    let jitter_ms = u64::try_from(jitter.as_millis()).ok()?.max(1);
+    Some(Duration::from_millis(seed % jitter_ms))
+}
+
+/// Computes the first time `cron_schedule` fires after `prev_ts`, or after
+/// `now` if it hasn't fired yet.
+pub fn compute_next_schedule_ts(
+    cron_schedule: &CronSchedule,
+    prev_ts: Option<Timestamp>,
+    now: Timestamp,
+) -> anyhow::Result<Timestamp> {
+    compute_next_schedule_ts_in(cron_schedule, Tz::UTC, prev_ts, now)
+}
+
+/// Like `compute_next_schedule_ts`, but with the schedule's hours, minutes and
+/// days in `time_zone`. Local times skipped by a daylight saving change run
+/// an hour later, and local times that happen twice run the first time.
+fn compute_next_schedule_ts_in(
+    cron_schedule: &CronSchedule,
+    time_zone: Tz,
+    prev_ts: Option<Timestamp>,
+    now: Timestamp,
+) -> anyhow::Result<Timestamp> {
+    let cron: Cron = match cron_schedule.clone() {
         CronSchedule::Interval { seconds } => {
             let next_ts = match prev_ts {
                 Some(prev_ts) => prev_ts.add(Duration::from_secs(seconds as u64))?,
@@ -56,9 +112,24 @@ pub fn compute_next_ts(
     let prev_ts = prev_ts.unwrap_or(now);
     let prev_ts_nanos: i64 = prev_ts.into();
     let prev_ts_utc = Utc.timestamp_nanos(prev_ts_nanos);
-    let next_ts_utc = match cron.next_after(prev_ts_utc) {
-        Some(next_ts_utc) => next_ts_utc,
-        None => return Err(anyhow::anyhow!("Could not compute next timestamp for cron")),
+    // Saffron only understands UTC, so step through the schedule in local
+    // wall-clock time written as if it were UTC and convert each candidate.
+    let mut local_ts = Utc.from_utc_datetime(&prev_ts_utc.with_timezone(&time_zone).naive_local());
+    let mut next_ts_utc = None;
+    for _ in 0..MAX_LOCAL_TIME_STEPS {
+        let Some(next_local_ts) = cron.next_after(local_ts) else {
+            break;
+        };
+        if let Some(candidate) = local_to_utc(time_zone, next_local_ts)
+            && candidate > prev_ts_utc
+        {
+            next_ts_utc = Some(candidate);
+            break;
+        }
+        local_ts = next_local_ts;
+    }
+    let Some(next_ts_utc) = next_ts_utc else {
+        return Err(anyhow::anyhow!("Could not compute next timestamp for cron"));
     };
     let next_ts_nanos = next_ts_utc
         .timestamp_nanos_opt()
@@ -67,9 +138,24 @@ pub fn compute_next_ts(
     Ok(next_ts)
 }
 
+fn local_to_utc(time_zone: Tz, local_ts: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let naive = local_ts.naive_utc();
+    let local = match time_zone.from_local_datetime(&naive) {
+        LocalResult::Single(ts) => ts,
+        LocalResult::Ambiguous(earliest, _) => earliest,
+        LocalResult::None => time_zone
+            .from_local_datetime(&(naive + chrono::Duration::hours(1)))
+            .earliest()?,
+    };
+    Some(local.with_timezone(&Utc))
+}
+
 #[cfg(test)]
 mod tests {
-    use std::str::FromStr;
+    use std::{
+        str::FromStr,
+        time::Duration,
+    };
 
     use sync_types::{
         Timestamp,
@@ -92,6 +178,8 @@ mod tests {
             udf_path: UdfPath::from_str("test").unwrap().canonicalize(),
             udf_args: ConvexArray::try_from(vec![]).unwrap(),
             cron_schedule: CronSchedule::Interval { seconds: 60 },
+            time_zone: None,
+            jitter: None,
         };
 
         // Mar 01 2023 08:35:00 UTC
@@ -114,6 +202,8 @@ mod tests {
             udf_path: UdfPath::from_str("test").unwrap().canonicalize(),
             udf_args: ConvexArray::try_from(vec![]).unwrap(),
             cron_schedule: CronSchedule::Hourly { minute_utc: 5 },
+            time_zone: None,
+            jitter: None,
         };
 
         // Mar 01 2023 08:35:00 UTC
@@ -141,6 +231,8 @@ mod tests {
                 hour_utc: 8,
                 minute_utc: 30,
             },
+            time_zone: None,
+            jitter: None,
         };
 
         // Feb 28 2023 08:35:00 UTC
@@ -169,6 +261,8 @@ mod tests {
                 hour_utc: 12,
                 minute_utc: 30,
             },
+            time_zone: None,
+            jitter: None,
         };
 
         // Feb 28 2023 08:35:00 UTC
@@ -197,6 +291,8 @@ mod tests {
                 hour_utc: 12,
                 minute_utc: 30,
             },
+            time_zone: None,
+            jitter: None,
         };
 
         // Feb 28 2023 08:35:00 UTC
@@ -226,6 +322,8 @@ mod tests {
             cron_schedule: CronSchedule::Cron {
                 cron_expr: "0 12 * * 1,5".to_string(),
             },
+            time_zone: None,
+            jitter: None,
         };
 
         // Feb 28 2023 08:35:00 UTC
@@ -249,10 +347,78 @@ mod tests {
             cron_schedule: CronSchedule::Cron {
                 cron_expr: "0 12 * * 7".to_string(),
             },
+            time_zone: None,
+            jitter: None,
         };
         result = compute_next_ts(&cron_spec, prev_ts, now);
         assert!(result.is_err());
         assert!(format!("{:?}", result.unwrap_err())
             .contains("Cron Schedule: Cron parsing from Saffron failed"));
     }
+
+    #[test]
+    fn test_compute_next_ts_time_zone() {
+        // Every day at 09:00 in New York, across the start of daylight saving time
+        let cron_spec = CronSpec {
+            udf_path: UdfPath::from_str("test").unwrap().canonicalize(),
+            udf_args: ConvexArray::try_from(vec![]).unwrap(),
+            cron_schedule: CronSchedule::Daily {
+                hour_utc: 9,
+                minute_utc: 0,
+            },
+            time_zone: Some("America/New_York".to_string()),
+            jitter: None,
+        };
+
+        // Mar 11 2023 12:00:00 UTC (07:00 EST)
+        let now = Timestamp::try_from(i64::pow(10, 9) * 1678536000).unwrap();
+        let first = compute_next_ts(&cron_spec, None, now).unwrap();
+        // Mar 11 2023 14:00:00 UTC (09:00 EST)
+        let expected = Timestamp::try_from(i64::pow(10, 9) * 1678543200).unwrap();
+        assert_eq!(first, expected);
+
+        let second = compute_next_ts(&cron_spec, Some(first), now).unwrap();
+        // Mar 12 2023 13:00:00 UTC (09:00 EDT)
+        let expected = Timestamp::try_from(i64::pow(10, 9) * 1678626000).unwrap();
+        assert_eq!(second, expected);
+
+        // 02:30 doesn't exist in New York on Mar 12 2023, so it runs an hour later
+        let cron_spec = CronSpec {
+            cron_schedule: CronSchedule::Daily {
+                hour_utc: 2,
+                minute_utc: 30,
+            },
+            ..cron_spec
+        };
+        // Mar 12 2023 05:00:00 UTC (00:00 EST)
+        let now = Timestamp::try_from(i64::pow(10, 9) * 1678597200).unwrap();
+        let result = compute_next_ts(&cron_spec, None, now).unwrap();
+        // Mar 12 2023 07:30:00 UTC (03:30 EDT)
+        let expected = Timestamp::try_from(i64::pow(10, 9) * 1678606200).unwrap();
+        assert_eq!(result, expected);
+    }
+
+    #[test]
+    fn test_compute_next_ts_jitter() {
+        // Every hour on the hour, offset by up to ten minutes
+        let cron_spec = CronSpec {
+            udf_path: UdfPath::from_str("test").unwrap().canonicalize(),
+            udf_args: ConvexArray::try_from(vec![]).unwrap(),
+            cron_schedule: CronSchedule::Hourly { minute_utc: 0 },
+            time_zone: None,
+            jitter: Some(Duration::from_secs(600)),
+        };
+
+        // Mar 01 2023 08:35:00 UTC
+        let now = Timestamp::try_from(i64::pow(10, 9) * 1677659700).unwrap();
+        // Mar 01 2023 09:00:00 UTC
+        let hour = Timestamp::try_from(i64::pow(10, 9) * 1677661200).unwrap();
+        let first = compute_next_ts(&cron_spec, None, now).unwrap();
+        assert!(first >= hour);
+        assert!(first < hour.add(Duration::from_secs(600)).unwrap());
+
+        // Later runs keep the same offset instead of drifting.
+        let second = compute_next_ts(&cron_spec, Some(first), now).unwrap();
+        assert_eq!(second, first.add(Duration::from_secs(3600)).unwrap());
+    }
 }
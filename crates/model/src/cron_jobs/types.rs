@@ -4,12 +4,14 @@ use std::{
     mem,
     ops::Deref,
     str::FromStr,
+    time::Duration,
 };
 
 use anyhow::{
     bail,
     Context,
 };
+use chrono_tz::Tz;
 use common::{
     log_lines::RawLogLines,
     types::Timestamp,
@@ -43,8 +45,15 @@ pub enum CronValidationError {
     SecondsMinutesHours,
     #[error("Interval must be an integer greater than 0")]
     InvalidIntervalValue,
+    #[error("Unknown time zone {0:?}. Use an IANA time zone like \"America/New_York\".")]
+    InvalidTimeZone(String),
+    #[error("Jitter must be an integer number of seconds from 1 to 86400")]
+    InvalidJitter,
 }
 
+/// The most a cron job's runs can be offset by its jitter.
+pub const MAX_CRON_JITTER: Duration = Duration::from_secs(24 * 60 * 60);
+
 #[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
 pub struct CronJob {
@@ -172,11 +181,29 @@ pub struct CronSpec {
     )]
     pub udf_args: ConvexArray,
     pub cron_schedule: CronSchedule,
+    /// The IANA time zone, like `America/New_York`, that the schedule's
+    /// hours, minutes and days are in, so a daily job keeps its local time
+    /// across daylight saving changes. UTC if unset. Intervals ignore it.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "proptest::option::of(\"UTC|America/New_York|Asia/Kolkata\")")
+    )]
+    pub time_zone: Option<String>,
+    /// Offsets every run by the same amount, between zero and this, so jobs
+    /// on the same schedule don't all start at once.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "proptest::option::of((1..=86_400u64).prop_map(Duration::from_secs))")
+    )]
+    pub jitter: Option<Duration>,
 }
 
 impl HeapSize for CronSpec {
     fn heap_size(&self) -> usize {
-        self.udf_args.heap_size() + self.cron_schedule.heap_size() + self.udf_path.heap_size()
+        self.udf_args.heap_size()
+            + self.cron_schedule.heap_size()
+            + self.udf_path.heap_size()
+            + self.time_zone.heap_size()
     }
 }
 
@@ -187,6 +214,10 @@ pub struct SerializedCronSpec {
     #[serde(with = "serde_bytes")]
     udf_args: Option<Vec<u8>>,
     cron_schedule: SerializedCronSchedule,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    time_zone: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    jitter_seconds: Option<i64>,
 }
 
 impl TryFrom<CronSpec> for SerializedCronSpec {
@@ -201,6 +232,11 @@ impl TryFrom<CronSpec> for SerializedCronSpec {
             udf_path: String::from(spec.udf_path),
             udf_args: Some(udf_args_bytes),
             cron_schedule: spec.cron_schedule.try_into()?,
+            time_zone: spec.time_zone,
+            jitter_seconds: spec
+                .jitter
+                .map(|jitter| i64::try_from(jitter.as_secs()))
+                .transpose()?,
         })
     }
 }
@@ -222,6 +258,11 @@ impl TryFrom<SerializedCronSpec> for CronSpec {
             udf_path,
             udf_args,
             cron_schedule,
+            time_zone: value.time_zone,
+            jitter: value
+                .jitter_seconds
+                .map(|seconds| anyhow::Ok(Duration::from_secs(u64::try_from(seconds)?)))
+                .transpose()?,
         })
     }
 }
@@ -305,6 +346,8 @@ impl TryFrom<JsonValue> for CronSpec {
             name: String,
             args: JsonValue,
             schedule: ScheduleJson,
+            time_zone: Option<String>,
+            jitter_seconds: Option<i64>,
         }
         let j: CronSpecJson = serde_json::from_value(value.clone())
             .with_context(|| CronValidationError::InvalidJson)?;
@@ -436,12 +479,32 @@ impl TryFrom<JsonValue> for CronSpec {
             },
         };
 
+        if let Some(time_zone) = &j.time_zone {
+            if time_zone.parse::<Tz>().is_err() {
+                anyhow::bail!(CronValidationError::InvalidTimeZone(time_zone.clone()));
+            }
+        }
+        let jitter = j
+            .jitter_seconds
+            .map(|seconds| {
+                let jitter = Duration::from_secs(
+                    u64::try_from(seconds).map_err(|_| CronValidationError::InvalidJitter)?,
+                );
+                if jitter.is_zero() || jitter > MAX_CRON_JITTER {
+                    anyhow::bail!(CronValidationError::InvalidJitter);
+                }
+                Ok(jitter)
+            })
+            .transpose()?;
+
         let udf_path: UdfPath = j.name.parse()?;
         let udf_path_canonicalized = udf_path.canonicalize();
         Ok(Self {
             udf_path: udf_path_canonicalized,
             udf_args: ConvexArray::try_from(j.args)?,
             cron_schedule: schedule,
+            time_zone: j.time_zone,
+            jitter,
         })
     }
 }
@@ -498,7 +561,7 @@ impl HeapSize for CronSchedule {
 
 #[derive(Serialize, Deserialize)]
 #[serde(rename_all = "camelCase", tag = "type")]
-enum SerializedCronSchedule {
+pub enum SerializedCronSchedule {
     Interval {
         seconds: i64,
     },
@@ -1026,6 +1089,8 @@ impl TryFrom<ConvexObject> for CronJobLogLines {
 
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use cmd_util::env::env_config;
     use proptest::prelude::*;
     use sync_types::testing::assert_roundtrips;
@@ -1041,6 +1106,8 @@ mod tests {
         CronJobLogLines,
         CronJobResult,
         CronJobStatus,
+        CronSpec,
+        CronValidationError,
     };
 
     proptest! {
@@ -1086,4 +1153,32 @@ mod tests {
         );
         assert_roundtrips::<_, CronJob>(cron_job_obj);
     }
+
+    #[test]
+    fn test_cron_spec_time_zone_and_jitter() -> anyhow::Result<()> {
+        let spec = |time_zone: &str, jitter_seconds: i64| {
+            CronSpec::try_from(serde_json::json!({
+                "name": "crons.js:addOne",
+                "args": [{}],
+                "schedule": {"type": "daily", "hourUTC": 9, "minuteUTC": 0},
+                "timeZone": time_zone,
+                "jitterSeconds": jitter_seconds,
+            }))
+        };
+        let parsed = spec("America/New_York", 60)?;
+        assert_eq!(parsed.time_zone.as_deref(), Some("America/New_York"));
+        assert_eq!(parsed.jitter, Some(Duration::from_secs(60)));
+
+        let err = spec("America/Gotham", 60).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<CronValidationError>(),
+            Some(CronValidationError::InvalidTimeZone(_))
+        ));
+        let err = spec("UTC", 0).unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<CronValidationError>(),
+            Some(CronValidationError::InvalidJitter)
+        ));
+        Ok(())
+    }
 }
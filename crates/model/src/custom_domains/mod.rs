@@ -0,0 +1,222 @@
+use std::sync::LazyLock;
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    query::{
+        IndexRange,
+        IndexRangeExpression,
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+    types::IndexName,
+};
+use database::{
+    defaults::system_index,
+    require_admin_or_system,
+    unauthorized_error,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use errors::ErrorMetadata;
+use value::{
+    ConvexValue,
+    FieldPath,
+    ResolvedDocumentId,
+    TableName,
+    TableNamespace,
+};
+
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+use types::CustomDomain;
+
+pub static CUSTOM_DOMAINS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_custom_domains"
+        .parse()
+        .expect("Invalid built-in custom_domains table")
+});
+
+pub static CUSTOM_DOMAINS_BY_DOMAIN_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&CUSTOM_DOMAINS_TABLE, "by_domain"));
+static DOMAIN_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "domain".parse().expect("invalid domain field"));
+
+pub struct CustomDomainsTable;
+impl SystemTable for CustomDomainsTable {
+    fn table_name(&self) -> &'static TableName {
+        &CUSTOM_DOMAINS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![SystemIndex {
+            name: CUSTOM_DOMAINS_BY_DOMAIN_INDEX.clone(),
+            fields: vec![DOMAIN_FIELD.clone()].try_into().unwrap(),
+        }]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<CustomDomain>::try_from(document).map(|_| ())
+    }
+}
+
+pub struct CustomDomainsModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> CustomDomainsModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    pub async fn list(&mut self) -> anyhow::Result<Vec<ParsedDocument<CustomDomain>>> {
+        let query = Query::full_table_scan(CUSTOM_DOMAINS_TABLE.clone(), Order::Asc);
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        let mut domains = vec![];
+        while let Some(document) = query_stream.next(self.tx, None).await? {
+            domains.push(document.try_into()?);
+        }
+        Ok(domains)
+    }
+
+    pub async fn get(
+        &mut self,
+        domain: &str,
+    ) -> anyhow::Result<Option<ParsedDocument<CustomDomain>>> {
+        let index_range = IndexRange {
+            index_name: CUSTOM_DOMAINS_BY_DOMAIN_INDEX.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                DOMAIN_FIELD.clone(),
+                ConvexValue::try_from(domain.to_string())?.into(),
+            )],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        query_stream
+            .expect_at_most_one(self.tx)
+            .await?
+            .map(ParsedDocument::try_from)
+            .transpose()
+    }
+
+    /// Registers a domain, which is served once the ACME worker has issued
+    /// its certificate.
+    pub async fn register(&mut self, domain: CustomDomain) -> anyhow::Result<ResolvedDocumentId> {
+        require_admin_or_system(self.tx.identity(), "register_custom_domain")?;
+        if self.get(&domain.domain).await?.is_some() {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "CustomDomainAlreadyRegistered",
+                format!("{} is already registered", domain.domain),
+            ));
+        }
+        SystemMetadataModel::new_global(self.tx)
+            .insert(&CUSTOM_DOMAINS_TABLE, domain.try_into()?)
+            .await
+    }
+
+    /// Stops serving `domain` and deletes its certificate.
+    pub async fn remove(&mut self, domain: &str) -> anyhow::Result<()> {
+        require_admin_or_system(self.tx.identity(), "remove_custom_domain")?;
+        let Some(existing) = self.get(domain).await? else {
+            anyhow::bail!(ErrorMetadata::not_found(
+                "CustomDomainNotFound",
+                format!("{domain} isn't registered"),
+            ));
+        };
+        SystemMetadataModel::new_global(self.tx)
+            .delete(existing.id())
+            .await?;
+        Ok(())
+    }
+
+    /// Records the outcome of issuing a certificate. Only the ACME worker
+    /// calls this, as the system user.
+    pub async fn update(
+        &mut self,
+        id: ResolvedDocumentId,
+        domain: CustomDomain,
+    ) -> anyhow::Result<()> {
+        if !self.tx.identity().is_system() {
+            anyhow::bail!(unauthorized_error("update_custom_domain"));
+        }
+        SystemMetadataModel::new_global(self.tx)
+            .replace(id, domain.try_into()?)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::runtime::Runtime;
+    use database::test_helpers::DbFixtures;
+    use errors::ErrorMetadataAnyhowExt;
+    use keybroker::Identity;
+    use runtime::testing::TestRuntime;
+
+    use super::{
+        types::{
+            CustomDomain,
+            CustomDomainTarget,
+            DomainCertificate,
+        },
+        CustomDomainsModel,
+    };
+    use crate::test_helpers::DbFixturesWithModel;
+
+    #[convex_macro::test_runtime]
+    async fn test_custom_domains(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin(Identity::system()).await?;
+        let domain = CustomDomain::new("api.example.com", CustomDomainTarget::Cloud)?;
+        let id = CustomDomainsModel::new(&mut tx)
+            .register(domain.clone())
+            .await?;
+        let err = CustomDomainsModel::new(&mut tx)
+            .register(domain.clone())
+            .await
+            .unwrap_err();
+        assert_eq!(err.short_msg(), "CustomDomainAlreadyRegistered");
+
+        let mut issued = domain.clone();
+        issued.certificate = Some(DomainCertificate {
+            certificate_chain_pem: "chain".to_string(),
+            private_key_pem: "key".to_string(),
+            issued_at: rt.unix_timestamp(),
+        });
+        CustomDomainsModel::new(&mut tx)
+            .update(id, issued.clone())
+            .await?;
+        let stored = CustomDomainsModel::new(&mut tx)
+            .get("api.example.com")
+            .await?
+            .unwrap();
+        assert_eq!(stored.into_value(), issued);
+        assert_eq!(CustomDomainsModel::new(&mut tx).list().await?.len(), 1);
+
+        CustomDomainsModel::new(&mut tx)
+            .remove("api.example.com")
+            .await?;
+        assert!(CustomDomainsModel::new(&mut tx).list().await?.is_empty());
+
+        let mut tx = db.begin(Identity::Unknown).await?;
+        assert!(CustomDomainsModel::new(&mut tx)
+            .register(domain)
+            .await
+            .is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,246 @@
+use std::{
+    fmt,
+    str::FromStr,
+};
+
+use common::runtime::UnixTimestamp;
+use errors::ErrorMetadata;
+#[cfg(any(test, feature = "testing"))]
+use proptest::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use value::codegen_convex_serialization;
+
+/// A domain the deployment is served on in addition to its own URLs, with the
+/// TLS certificate issued for it over ACME.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct CustomDomain {
+    /// Lower-case host name like `api.example.com`.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(regex = "[a-z0-9]{1,10}\\.[a-z]{2,6}")
+    )]
+    pub domain: String,
+    pub target: CustomDomainTarget,
+    pub certificate: Option<DomainCertificate>,
+    /// Why the last attempt to issue a certificate failed, if it did.
+    pub last_error: Option<String>,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "proptest::option::of((0..=i64::MAX as u64 / 1_000_000)
+            .prop_map(UnixTimestamp::from_millis))")
+    )]
+    pub last_attempt: Option<UnixTimestamp>,
+}
+
+impl CustomDomain {
+    pub fn new(domain: &str, target: CustomDomainTarget) -> anyhow::Result<Self> {
+        let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+        anyhow::ensure!(
+            is_valid_domain(&domain),
+            ErrorMetadata::bad_request(
+                "InvalidCustomDomain",
+                format!(
+                    "Invalid domain {domain:?}. Custom domains are host names like \
+                     \"api.example.com\", without a scheme, port or path."
+                )
+            )
+        );
+        Ok(Self {
+            domain,
+            target,
+            certificate: None,
+            last_error: None,
+            last_attempt: None,
+        })
+    }
+}
+
+/// What requests to a custom domain are routed to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub enum CustomDomainTarget {
+    /// The deployment's API, including the sync websocket, like its
+    /// `.convex.cloud` URL.
+    Cloud,
+    /// HTTP actions, served from the root of the domain like the deployment's
+    /// `.convex.site` URL.
+    Site,
+}
+
+impl CustomDomainTarget {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cloud => "cloud",
+            Self::Site => "site",
+        }
+    }
+}
+
+impl FromStr for CustomDomainTarget {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "cloud" => Ok(Self::Cloud),
+            "site" => Ok(Self::Site),
+            _ => anyhow::bail!(ErrorMetadata::bad_request(
+                "InvalidCustomDomainTarget",
+                format!("Invalid custom domain target {s:?}. Use \"cloud\" or \"site\"."),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for CustomDomainTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct DomainCertificate {
+    /// The leaf certificate followed by its intermediates.
+    pub certificate_chain_pem: String,
+    pub private_key_pem: String,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(
+            strategy = "(0..=i64::MAX as u64 / 1_000_000).prop_map(UnixTimestamp::from_millis)"
+        )
+    )]
+    pub issued_at: UnixTimestamp,
+}
+
+impl fmt::Debug for DomainCertificate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DomainCertificate")
+            .field("issued_at", &self.issued_at)
+            .finish_non_exhaustive()
+    }
+}
+
+fn is_valid_domain(domain: &str) -> bool {
+    let labels: Vec<_> = domain.split('.').collect();
+    domain.len() <= 253
+        && labels.len() >= 2
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .bytes()
+                    .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+        })
+        // The last label is a TLD, which is never all digits, so this rejects IP
+        // addresses.
+        && !labels[labels.len() - 1].bytes().all(|b| b.is_ascii_digit())
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedDomainCertificate {
+    certificate_chain_pem: String,
+    private_key_pem: String,
+    issued_at_ms: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedCustomDomain {
+    domain: String,
+    target: String,
+    certificate: Option<SerializedDomainCertificate>,
+    last_error: Option<String>,
+    last_attempt_ms: Option<i64>,
+}
+
+impl TryFrom<CustomDomain> for SerializedCustomDomain {
+    type Error = anyhow::Error;
+
+    fn try_from(domain: CustomDomain) -> anyhow::Result<Self> {
+        Ok(Self {
+            domain: domain.domain,
+            target: domain.target.to_string(),
+            certificate: domain
+                .certificate
+                .map(|certificate| {
+                    anyhow::Ok(SerializedDomainCertificate {
+                        certificate_chain_pem: certificate.certificate_chain_pem,
+                        private_key_pem: certificate.private_key_pem,
+                        issued_at_ms: certificate.issued_at.as_ms_since_epoch()?.try_into()?,
+                    })
+                })
+                .transpose()?,
+            last_error: domain.last_error,
+            last_attempt_ms: domain
+                .last_attempt
+                .map(|ts| anyhow::Ok(ts.as_ms_since_epoch()?.try_into()?))
+                .transpose()?,
+        })
+    }
+}
+
+impl TryFrom<SerializedCustomDomain> for CustomDomain {
+    type Error = anyhow::Error;
+
+    fn try_from(domain: SerializedCustomDomain) -> anyhow::Result<Self> {
+        Ok(Self {
+            domain: domain.domain,
+            target: domain.target.parse()?,
+            certificate: domain
+                .certificate
+                .map(|certificate| {
+                    anyhow::Ok(DomainCertificate {
+                        certificate_chain_pem: certificate.certificate_chain_pem,
+                        private_key_pem: certificate.private_key_pem,
+                        issued_at: UnixTimestamp::from_millis(certificate.issued_at_ms.try_into()?),
+                    })
+                })
+                .transpose()?,
+            last_error: domain.last_error,
+            last_attempt: domain
+                .last_attempt_ms
+                .map(|ms| anyhow::Ok(UnixTimestamp::from_millis(ms.try_into()?)))
+                .transpose()?,
+        })
+    }
+}
+
+codegen_convex_serialization!(CustomDomain, SerializedCustomDomain);
+
+#[cfg(test)]
+mod tests {
+    use errors::ErrorMetadataAnyhowExt;
+
+    use super::{
+        CustomDomain,
+        CustomDomainTarget,
+    };
+
+    #[test]
+    fn test_new_custom_domain() -> anyhow::Result<()> {
+        let domain = CustomDomain::new("API.Example.com.", CustomDomainTarget::Cloud)?;
+        assert_eq!(domain.domain, "api.example.com");
+        assert!(CustomDomain::new("my-app.co.uk", CustomDomainTarget::Site).is_ok());
+        for invalid in [
+            "localhost",
+            "https://example.com",
+            "example.com:443",
+            "example.com/path",
+            "-bad.example.com",
+            "a..example.com",
+            "*.example.com",
+            "127.0.0.1",
+        ] {
+            let err = CustomDomain::new(invalid, CustomDomainTarget::Site).unwrap_err();
+            assert_eq!(err.short_msg(), "InvalidCustomDomain", "{invalid}");
+        }
+        Ok(())
+    }
+}
@@ -31,10 +31,12 @@ use serde::{
     Serialize,
 };
 use serde_json::Value as JsonValue;
+use sync_types::Timestamp;
 use value::{
     codegen_convex_serialization,
     obj,
     remove_int64,
+    remove_nullable_string,
     remove_object,
     remove_string,
     remove_vec,
@@ -53,6 +55,7 @@ use crate::{
     },
     config::types::ConfigDiff,
     environment_variables::types::EnvVarName,
+    network_acl::types::NetworkAclRejection,
     snapshot_imports::types::{
         ImportFormat,
         ImportMode,
@@ -155,6 +158,20 @@ pub enum DeploymentAuditLogEvent {
         import_mode: ImportMode,
         import_format: ImportFormat,
     },
+    RestoreTables {
+        table_names: Vec<TableName>,
+        table_count: u64,
+        restore_ts: Timestamp,
+    },
+    /// A request to a public endpoint was rejected by the deployment's network
+    /// ACL. Repeated rejections of the same address are only logged
+    /// periodically.
+    RejectNetworkRequest {
+        ip: String,
+        country: Option<String>,
+        reason: NetworkAclRejection,
+        path: String,
+    },
 }
 
 impl From<LegacyIndexDiff> for DeploymentAuditLogEvent {
@@ -206,6 +223,8 @@ impl DeploymentAuditLogEvent {
             DeploymentAuditLogEvent::ChangeDeploymentState { .. } => "change_deployment_state",
             DeploymentAuditLogEvent::SnapshotImport { .. } => "snapshot_import",
             DeploymentAuditLogEvent::ClearTables => "clear_tables",
+            DeploymentAuditLogEvent::RestoreTables { .. } => "restore_tables",
+            DeploymentAuditLogEvent::RejectNetworkRequest { .. } => "reject_network_request",
         }
     }
 
@@ -291,6 +310,36 @@ impl DeploymentAuditLogEvent {
                 )
             },
             DeploymentAuditLogEvent::ClearTables => obj!(),
+            DeploymentAuditLogEvent::RestoreTables {
+                table_names,
+                table_count,
+                restore_ts,
+            } => {
+                let table_names: Vec<_> = table_names
+                    .into_iter()
+                    .map(|table_name| {
+                        anyhow::Ok(ConvexValue::String(table_name.to_string().try_into()?))
+                    })
+                    .try_collect()?;
+                obj!(
+                    "table_names" => table_names,
+                    "table_count" => table_count as i64,
+                    "restore_ts" => i64::from(restore_ts)
+                )
+            },
+            DeploymentAuditLogEvent::RejectNetworkRequest {
+                ip,
+                country,
+                reason,
+                path,
+            } => {
+                obj!(
+                    "ip" => ip,
+                    "country" => ConvexValue::try_from(country)?,
+                    "reason" => reason.as_str(),
+                    "path" => path
+                )
+            },
         }
     }
 
@@ -390,6 +439,23 @@ impl TryFrom<ConvexObject> for DeploymentAuditLogEvent {
                     import_format: remove_object(&mut fields, "import_format")?,
                 }
             },
+            "restore_tables" => {
+                let table_names = remove_vec_of_strings(&mut fields, "table_names")?
+                    .iter()
+                    .map(|s| TableName::from_str(s))
+                    .try_collect()?;
+                DeploymentAuditLogEvent::RestoreTables {
+                    table_names,
+                    table_count: remove_int64(&mut fields, "table_count")? as u64,
+                    restore_ts: remove_int64(&mut fields, "restore_ts")?.try_into()?,
+                }
+            },
+            "reject_network_request" => DeploymentAuditLogEvent::RejectNetworkRequest {
+                ip: remove_string(&mut fields, "ip")?,
+                country: remove_nullable_string(&mut fields, "country")?,
+                reason: remove_string(&mut fields, "reason")?.parse()?,
+                path: remove_string(&mut fields, "path")?,
+            },
             _ => anyhow::bail!("action {action} unrecognized"),
         };
         Ok(event)
@@ -0,0 +1,249 @@
+//! Approximate last read times of user documents, from sampled reads. They're
+//! written out-of-band by the application's document access worker and are
+//! used to report how much of each table hasn't been read recently.
+use std::{
+    sync::LazyLock,
+    time::Duration,
+};
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    query::{
+        IndexRange,
+        IndexRangeExpression,
+        Order,
+        Query,
+    },
+    runtime::{
+        Runtime,
+        UnixTimestamp,
+    },
+    types::IndexName,
+};
+use database::{
+    defaults::system_index,
+    unauthorized_error,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use value::{
+    ConvexValue,
+    FieldPath,
+    InternalId,
+    TableName,
+    TableNamespace,
+    TabletId,
+};
+
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+use types::DocumentAccess;
+
+pub static DOCUMENT_ACCESS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_document_access"
+        .parse()
+        .expect("Invalid built-in document_access table")
+});
+
+pub static DOCUMENT_ACCESS_BY_DOCUMENT_ID_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&DOCUMENT_ACCESS_TABLE, "by_document_id"));
+pub static DOCUMENT_ACCESS_BY_TABLE_AND_LAST_READ_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&DOCUMENT_ACCESS_TABLE, "by_table_and_last_read"));
+
+static TABLE_ID_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "tableId".parse().expect("invalid tableId field"));
+static DOCUMENT_ID_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "documentId".parse().expect("invalid documentId field"));
+static LAST_READ_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "lastReadMs".parse().expect("invalid lastReadMs field"));
+
+pub struct DocumentAccessTable;
+impl SystemTable for DocumentAccessTable {
+    fn table_name(&self) -> &'static TableName {
+        &DOCUMENT_ACCESS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![
+            SystemIndex {
+                name: DOCUMENT_ACCESS_BY_DOCUMENT_ID_INDEX.clone(),
+                fields: vec![DOCUMENT_ID_FIELD.clone()].try_into().unwrap(),
+            },
+            SystemIndex {
+                name: DOCUMENT_ACCESS_BY_TABLE_AND_LAST_READ_INDEX.clone(),
+                fields: vec![TABLE_ID_FIELD.clone(), LAST_READ_FIELD.clone()]
+                    .try_into()
+                    .unwrap(),
+            },
+        ]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<DocumentAccess>::try_from(document).map(|_| ())
+    }
+}
+
+pub struct DocumentAccessModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> DocumentAccessModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    pub async fn get(
+        &mut self,
+        internal_id: InternalId,
+    ) -> anyhow::Result<Option<ParsedDocument<DocumentAccess>>> {
+        let index_range = IndexRange {
+            index_name: DOCUMENT_ACCESS_BY_DOCUMENT_ID_INDEX.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                DOCUMENT_ID_FIELD.clone(),
+                ConvexValue::try_from(internal_id.to_string())?.into(),
+            )],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        query_stream
+            .expect_at_most_one(self.tx)
+            .await?
+            .map(ParsedDocument::try_from)
+            .transpose()
+    }
+
+    /// Records a sampled read. The stored time is only moved forward if it's
+    /// at least `resolution` older, so frequently read documents aren't
+    /// rewritten on every flush. Returns whether anything was written.
+    pub async fn record_read(
+        &mut self,
+        access: DocumentAccess,
+        resolution: Duration,
+    ) -> anyhow::Result<bool> {
+        if !self.tx.identity().is_system() {
+            anyhow::bail!(unauthorized_error("record_document_access"));
+        }
+        match self.get(access.internal_id).await? {
+            Some(existing) => {
+                if access.last_read < existing.last_read + resolution {
+                    return Ok(false);
+                }
+                SystemMetadataModel::new_global(self.tx)
+                    .replace(existing.id(), access.try_into()?)
+                    .await?;
+            },
+            None => {
+                SystemMetadataModel::new_global(self.tx)
+                    .insert(&DOCUMENT_ACCESS_TABLE, access.try_into()?)
+                    .await?;
+            },
+        }
+        Ok(true)
+    }
+
+    /// The number of documents in the table with `tablet_id` that have been
+    /// sampled, and the number of those that were last read at or after
+    /// `since`.
+    pub async fn count_reads(
+        &mut self,
+        tablet_id: TabletId,
+        since: UnixTimestamp,
+    ) -> anyhow::Result<(u64, u64)> {
+        if !(self.tx.identity().is_system() || self.tx.identity().is_admin()) {
+            anyhow::bail!(unauthorized_error("count_document_reads"));
+        }
+        let index_range = IndexRange {
+            index_name: DOCUMENT_ACCESS_BY_TABLE_AND_LAST_READ_INDEX.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                TABLE_ID_FIELD.clone(),
+                ConvexValue::try_from(tablet_id.to_string())?.into(),
+            )],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        let mut sampled = 0;
+        let mut read_since = 0;
+        while let Some(document) = query_stream.next(self.tx, None).await? {
+            let access: ParsedDocument<DocumentAccess> = document.try_into()?;
+            sampled += 1;
+            if access.last_read >= since {
+                read_since += 1;
+            }
+        }
+        Ok((sampled, read_since))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use common::runtime::UnixTimestamp;
+    use database::test_helpers::DbFixtures;
+    use keybroker::Identity;
+    use runtime::testing::TestRuntime;
+    use value::{
+        InternalId,
+        TabletId,
+    };
+
+    use super::{
+        types::DocumentAccess,
+        DocumentAccessModel,
+    };
+    use crate::test_helpers::DbFixturesWithModel;
+
+    #[convex_macro::test_runtime]
+    async fn test_record_and_count_reads(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let tablet_id = TabletId(InternalId([1; 16]));
+        let access = |id: u8, last_read_secs: u64| DocumentAccess {
+            tablet_id,
+            internal_id: InternalId([id; 16]),
+            last_read: UnixTimestamp::from_millis(last_read_secs * 1000),
+        };
+        let resolution = Duration::from_secs(60);
+
+        let mut tx = db.begin(Identity::system()).await?;
+        let mut model = DocumentAccessModel::new(&mut tx);
+        assert!(model.record_read(access(2, 1000), resolution).await?);
+        assert!(model.record_read(access(3, 1000), resolution).await?);
+        // Within the resolution of the stored read, so it's not rewritten.
+        assert!(!model.record_read(access(3, 1030), resolution).await?);
+        assert!(model.record_read(access(3, 2000), resolution).await?);
+        db.commit(tx).await?;
+
+        let mut tx = db.begin(Identity::system()).await?;
+        let mut model = DocumentAccessModel::new(&mut tx);
+        assert_eq!(
+            model
+                .count_reads(tablet_id, UnixTimestamp::from_millis(1_500_000))
+                .await?,
+            (2, 1)
+        );
+        assert_eq!(
+            model
+                .count_reads(TabletId(InternalId([4; 16])), UnixTimestamp::from_millis(0))
+                .await?,
+            (0, 0)
+        );
+        Ok(())
+    }
+}
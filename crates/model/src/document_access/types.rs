@@ -0,0 +1,62 @@
+use common::runtime::UnixTimestamp;
+#[cfg(any(test, feature = "testing"))]
+use proptest::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use value::{
+    codegen_convex_serialization,
+    InternalId,
+    TabletId,
+};
+
+/// The approximate time a user document was last read. Reads are sampled, so
+/// a document without a record may still have been read.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct DocumentAccess {
+    pub tablet_id: TabletId,
+    pub internal_id: InternalId,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(
+            strategy = "(0..=i64::MAX as u64 / 1_000_000).prop_map(UnixTimestamp::from_millis)"
+        )
+    )]
+    pub last_read: UnixTimestamp,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedDocumentAccess {
+    table_id: String,
+    document_id: String,
+    last_read_ms: i64,
+}
+
+impl TryFrom<DocumentAccess> for SerializedDocumentAccess {
+    type Error = anyhow::Error;
+
+    fn try_from(access: DocumentAccess) -> anyhow::Result<Self> {
+        Ok(Self {
+            table_id: access.tablet_id.to_string(),
+            document_id: access.internal_id.to_string(),
+            last_read_ms: access.last_read.as_ms_since_epoch()?.try_into()?,
+        })
+    }
+}
+
+impl TryFrom<SerializedDocumentAccess> for DocumentAccess {
+    type Error = anyhow::Error;
+
+    fn try_from(access: SerializedDocumentAccess) -> anyhow::Result<Self> {
+        Ok(Self {
+            tablet_id: access.table_id.parse()?,
+            internal_id: access.document_id.parse()?,
+            last_read: UnixTimestamp::from_millis(access.last_read_ms.try_into()?),
+        })
+    }
+}
+
+codegen_convex_serialization!(DocumentAccess, SerializedDocumentAccess);
@@ -0,0 +1,330 @@
+use std::sync::LazyLock;
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    query::{
+        IndexRange,
+        IndexRangeExpression,
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+    types::IndexName,
+};
+use database::{
+    defaults::system_index,
+    unauthorized_error,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use value::{
+    ConvexValue,
+    DeveloperDocumentId,
+    FieldPath,
+    TableName,
+    TableNamespace,
+};
+
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+use types::{
+    ExportWatermark,
+    ExportWatermarkConfig,
+};
+
+pub static EXPORT_WATERMARK_CONFIGS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_export_watermark_configs"
+        .parse()
+        .expect("Invalid built-in export_watermark_configs table")
+});
+
+pub static EXPORT_WATERMARK_CONFIGS_BY_KEY_FINGERPRINT_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&EXPORT_WATERMARK_CONFIGS_TABLE, "by_key_fingerprint"));
+static KEY_FINGERPRINT_FIELD: LazyLock<FieldPath> = LazyLock::new(|| {
+    "keyFingerprint"
+        .parse()
+        .expect("invalid keyFingerprint field")
+});
+
+pub static EXPORT_WATERMARKS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_export_watermarks"
+        .parse()
+        .expect("Invalid built-in export_watermarks table")
+});
+
+pub static EXPORT_WATERMARKS_BY_EXPORT_ID_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&EXPORT_WATERMARKS_TABLE, "by_export_id"));
+pub static EXPORT_WATERMARKS_BY_TOKEN_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&EXPORT_WATERMARKS_TABLE, "by_token"));
+static EXPORT_ID_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "exportId".parse().expect("invalid exportId field"));
+static TOKEN_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "token".parse().expect("invalid token field"));
+
+pub struct ExportWatermarkConfigsTable;
+impl SystemTable for ExportWatermarkConfigsTable {
+    fn table_name(&self) -> &'static TableName {
+        &EXPORT_WATERMARK_CONFIGS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![SystemIndex {
+            name: EXPORT_WATERMARK_CONFIGS_BY_KEY_FINGERPRINT_INDEX.clone(),
+            fields: vec![KEY_FINGERPRINT_FIELD.clone()].try_into().unwrap(),
+        }]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<ExportWatermarkConfig>::try_from(document).map(|_| ())
+    }
+}
+
+pub struct ExportWatermarksTable;
+impl SystemTable for ExportWatermarksTable {
+    fn table_name(&self) -> &'static TableName {
+        &EXPORT_WATERMARKS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![
+            SystemIndex {
+                name: EXPORT_WATERMARKS_BY_EXPORT_ID_INDEX.clone(),
+                fields: vec![EXPORT_ID_FIELD.clone()].try_into().unwrap(),
+            },
+            SystemIndex {
+                name: EXPORT_WATERMARKS_BY_TOKEN_INDEX.clone(),
+                fields: vec![TOKEN_FIELD.clone()].try_into().unwrap(),
+            },
+        ]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<ExportWatermark>::try_from(document).map(|_| ())
+    }
+}
+
+/// Which admin keys' exports are watermarked, and the watermarks given to
+/// each of their exports.
+pub struct ExportWatermarksModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> ExportWatermarksModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    pub async fn config(
+        &mut self,
+        key_fingerprint: &str,
+    ) -> anyhow::Result<Option<ParsedDocument<ExportWatermarkConfig>>> {
+        let index_range = IndexRange {
+            index_name: EXPORT_WATERMARK_CONFIGS_BY_KEY_FINGERPRINT_INDEX.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                KEY_FINGERPRINT_FIELD.clone(),
+                ConvexValue::try_from(key_fingerprint.to_string())?.into(),
+            )],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        query_stream
+            .expect_at_most_one(self.tx)
+            .await?
+            .map(ParsedDocument::try_from)
+            .transpose()
+    }
+
+    pub async fn list_configs(&mut self) -> anyhow::Result<Vec<ExportWatermarkConfig>> {
+        let query = Query::full_table_scan(EXPORT_WATERMARK_CONFIGS_TABLE.clone(), Order::Asc);
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        let mut configs = vec![];
+        while let Some(document) = query_stream.next(self.tx, None).await? {
+            let config: ParsedDocument<ExportWatermarkConfig> = document.try_into()?;
+            configs.push(config.into_value());
+        }
+        Ok(configs)
+    }
+
+    /// Watermarks exports requested with `config.key_fingerprint`'s key,
+    /// replacing any existing config for it.
+    pub async fn set_config(&mut self, config: ExportWatermarkConfig) -> anyhow::Result<()> {
+        if !(self.tx.identity().is_system() || self.tx.identity().is_admin()) {
+            anyhow::bail!(unauthorized_error("set_export_watermark_config"));
+        }
+        match self.config(&config.key_fingerprint).await? {
+            Some(existing) => {
+                SystemMetadataModel::new_global(self.tx)
+                    .replace(existing.id(), config.try_into()?)
+                    .await?;
+            },
+            None => {
+                SystemMetadataModel::new_global(self.tx)
+                    .insert(&EXPORT_WATERMARK_CONFIGS_TABLE, config.try_into()?)
+                    .await?;
+            },
+        }
+        Ok(())
+    }
+
+    /// Stops watermarking exports requested with the key. Returns whether
+    /// they were being watermarked.
+    pub async fn remove_config(&mut self, key_fingerprint: &str) -> anyhow::Result<bool> {
+        if !(self.tx.identity().is_system() || self.tx.identity().is_admin()) {
+            anyhow::bail!(unauthorized_error("remove_export_watermark_config"));
+        }
+        let Some(existing) = self.config(key_fingerprint).await? else {
+            return Ok(false);
+        };
+        SystemMetadataModel::new_global(self.tx)
+            .delete(existing.id())
+            .await?;
+        Ok(true)
+    }
+
+    pub async fn insert_watermark(&mut self, watermark: ExportWatermark) -> anyhow::Result<()> {
+        SystemMetadataModel::new_global(self.tx)
+            .insert(&EXPORT_WATERMARKS_TABLE, watermark.try_into()?)
+            .await?;
+        Ok(())
+    }
+
+    pub async fn watermark_for_export(
+        &mut self,
+        export_id: DeveloperDocumentId,
+    ) -> anyhow::Result<Option<ExportWatermark>> {
+        self.watermark_by(
+            &EXPORT_WATERMARKS_BY_EXPORT_ID_INDEX,
+            &EXPORT_ID_FIELD,
+            export_id.encode(),
+        )
+        .await
+    }
+
+    /// Finds the export a watermark token was given to.
+    pub async fn trace(&mut self, token: &str) -> anyhow::Result<Option<ExportWatermark>> {
+        self.watermark_by(
+            &EXPORT_WATERMARKS_BY_TOKEN_INDEX,
+            &TOKEN_FIELD,
+            token.to_string(),
+        )
+        .await
+    }
+
+    async fn watermark_by(
+        &mut self,
+        index_name: &IndexName,
+        field: &FieldPath,
+        value: String,
+    ) -> anyhow::Result<Option<ExportWatermark>> {
+        let index_range = IndexRange {
+            index_name: index_name.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                field.clone(),
+                ConvexValue::try_from(value)?.into(),
+            )],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        let Some(document) = query_stream.expect_at_most_one(self.tx).await? else {
+            return Ok(None);
+        };
+        let watermark: ParsedDocument<ExportWatermark> = document.try_into()?;
+        Ok(Some(watermark.into_value()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use database::test_helpers::DbFixtures;
+    use keybroker::Identity;
+    use runtime::testing::TestRuntime;
+    use value::DeveloperDocumentId;
+
+    use super::{
+        types::{
+            ExportWatermark,
+            ExportWatermarkConfig,
+            WatermarkMode,
+        },
+        ExportWatermarksModel,
+    };
+    use crate::test_helpers::DbFixturesWithModel;
+
+    #[convex_macro::test_runtime]
+    async fn test_export_watermarks(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin(Identity::system()).await?;
+        let config = ExportWatermarkConfig {
+            key_fingerprint: "abc".to_string(),
+            label: "analytics team".to_string(),
+            mode: WatermarkMode::Field,
+        };
+        ExportWatermarksModel::new(&mut tx)
+            .set_config(config.clone())
+            .await?;
+        assert_eq!(
+            ExportWatermarksModel::new(&mut tx)
+                .config("abc")
+                .await?
+                .map(|config| config.into_value()),
+            Some(config.clone())
+        );
+        assert_eq!(
+            ExportWatermarksModel::new(&mut tx).list_configs().await?,
+            vec![config]
+        );
+
+        let watermark = ExportWatermark {
+            export_id: DeveloperDocumentId::MIN,
+            key_fingerprint: "abc".to_string(),
+            label: "analytics team".to_string(),
+            mode: WatermarkMode::Field,
+            token: "token".to_string(),
+        };
+        ExportWatermarksModel::new(&mut tx)
+            .insert_watermark(watermark.clone())
+            .await?;
+        // Watermarks outlive the config they came from.
+        assert!(
+            ExportWatermarksModel::new(&mut tx)
+                .remove_config("abc")
+                .await?
+        );
+        assert!(ExportWatermarksModel::new(&mut tx)
+            .config("abc")
+            .await?
+            .is_none());
+        assert_eq!(
+            ExportWatermarksModel::new(&mut tx)
+                .watermark_for_export(DeveloperDocumentId::MIN)
+                .await?,
+            Some(watermark.clone())
+        );
+        assert_eq!(
+            ExportWatermarksModel::new(&mut tx).trace("token").await?,
+            Some(watermark)
+        );
+        assert!(ExportWatermarksModel::new(&mut tx)
+            .trace("other")
+            .await?
+            .is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,267 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    str::FromStr,
+};
+
+use errors::ErrorMetadata;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use value::{
+    codegen_convex_serialization,
+    sha256::Sha256,
+    ConvexObject,
+    ConvexValue,
+    DeveloperDocumentId,
+    Namespace,
+};
+
+/// Field that `WatermarkMode::Field` adds to every exported document.
+pub const WATERMARK_FIELD: &str = "_watermark";
+
+pub const MAX_EXPORT_WATERMARK_LABEL_LEN: usize = 256;
+
+/// How exports requested with an admin key are marked, so a leaked export can
+/// be traced back to the key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub enum WatermarkMode {
+    /// Adds a `_watermark` field holding the export's token to every
+    /// document. Documents with the field can't be imported back until it's
+    /// removed.
+    Field,
+    /// Sets the lowest bit of each top-level floating point field to
+    /// [`noise_bit`] for the export's token. This changes numbers by at most
+    /// one unit in the last place, and survives the watermark field being
+    /// stripped from a dump.
+    Noise,
+}
+
+impl WatermarkMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Field => "field",
+            Self::Noise => "noise",
+        }
+    }
+}
+
+impl FromStr for WatermarkMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "field" => Ok(Self::Field),
+            "noise" => Ok(Self::Noise),
+            _ => anyhow::bail!(ErrorMetadata::bad_request(
+                "InvalidExportWatermark",
+                format!("Invalid watermark mode {s:?}. Expected \"field\" or \"noise\"."),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for WatermarkMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Watermarks exports requested with the admin key whose
+/// `AdminIdentity::key_fingerprint` is `key_fingerprint`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct ExportWatermarkConfig {
+    pub key_fingerprint: String,
+    /// Who the key was issued to, reported when tracing a token.
+    pub label: String,
+    pub mode: WatermarkMode,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedExportWatermarkConfig {
+    key_fingerprint: String,
+    label: String,
+    mode: String,
+}
+
+impl TryFrom<ExportWatermarkConfig> for SerializedExportWatermarkConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(config: ExportWatermarkConfig) -> anyhow::Result<Self> {
+        Ok(Self {
+            key_fingerprint: config.key_fingerprint,
+            label: config.label,
+            mode: config.mode.to_string(),
+        })
+    }
+}
+
+impl TryFrom<SerializedExportWatermarkConfig> for ExportWatermarkConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(config: SerializedExportWatermarkConfig) -> anyhow::Result<Self> {
+        Ok(Self {
+            key_fingerprint: config.key_fingerprint,
+            label: config.label,
+            mode: config.mode.parse()?,
+        })
+    }
+}
+
+codegen_convex_serialization!(ExportWatermarkConfig, SerializedExportWatermarkConfig);
+
+/// The watermark applied to one export, kept so its token can be traced
+/// after the key's config changes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct ExportWatermark {
+    pub export_id: DeveloperDocumentId,
+    pub key_fingerprint: String,
+    pub label: String,
+    pub mode: WatermarkMode,
+    /// Random and unique to the export.
+    pub token: String,
+}
+
+impl ExportWatermark {
+    /// Watermarks a document of a user table in the export.
+    pub fn apply(
+        &self,
+        id: DeveloperDocumentId,
+        object: ConvexObject,
+    ) -> anyhow::Result<ConvexObject> {
+        match self.mode {
+            // Documents can't have extra system fields, so the field is added
+            // when the document is encoded.
+            WatermarkMode::Field => Ok(object),
+            WatermarkMode::Noise => {
+                let fields: BTreeMap<_, _> = BTreeMap::from(object)
+                    .into_iter()
+                    .map(|(field, value)| {
+                        let value = match value {
+                            ConvexValue::Float64(f)
+                                if !field.is_system() && f.is_finite() && f != 0.0 =>
+                            {
+                                let bit = u64::from(noise_bit(&self.token, id, &field));
+                                ConvexValue::Float64(f64::from_bits((f.to_bits() & !1) | bit))
+                            },
+                            value => value,
+                        };
+                        (field, value)
+                    })
+                    .collect();
+                fields.try_into()
+            },
+        }
+    }
+}
+
+/// The lowest bit `WatermarkMode::Noise` gives `field` of document `id` in
+/// the export with `token`. Checking a dump's numbers against each export's
+/// token finds the one where they all match, while for other tokens only
+/// about half do.
+pub fn noise_bit(token: &str, id: DeveloperDocumentId, field: &str) -> bool {
+    let mut hasher = Sha256::new();
+    hasher.update(token.as_bytes());
+    hasher.update(&[0]);
+    hasher.update(id.encode().as_bytes());
+    hasher.update(&[0]);
+    hasher.update(field.as_bytes());
+    hasher.finalize()[0] & 1 == 1
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedExportWatermark {
+    export_id: String,
+    key_fingerprint: String,
+    label: String,
+    mode: String,
+    token: String,
+}
+
+impl TryFrom<ExportWatermark> for SerializedExportWatermark {
+    type Error = anyhow::Error;
+
+    fn try_from(watermark: ExportWatermark) -> anyhow::Result<Self> {
+        Ok(Self {
+            export_id: watermark.export_id.encode(),
+            key_fingerprint: watermark.key_fingerprint,
+            label: watermark.label,
+            mode: watermark.mode.to_string(),
+            token: watermark.token,
+        })
+    }
+}
+
+impl TryFrom<SerializedExportWatermark> for ExportWatermark {
+    type Error = anyhow::Error;
+
+    fn try_from(watermark: SerializedExportWatermark) -> anyhow::Result<Self> {
+        Ok(Self {
+            export_id: DeveloperDocumentId::decode(&watermark.export_id)?,
+            key_fingerprint: watermark.key_fingerprint,
+            label: watermark.label,
+            mode: watermark.mode.parse()?,
+            token: watermark.token,
+        })
+    }
+}
+
+codegen_convex_serialization!(ExportWatermark, SerializedExportWatermark);
+
+#[cfg(test)]
+mod tests {
+    use value::{
+        assert_obj,
+        ConvexValue,
+        DeveloperDocumentId,
+    };
+
+    use super::{
+        noise_bit,
+        ExportWatermark,
+        WatermarkMode,
+    };
+
+    #[test]
+    fn test_noise_watermark() -> anyhow::Result<()> {
+        let id = DeveloperDocumentId::MIN;
+        let watermark = ExportWatermark {
+            export_id: id,
+            key_fingerprint: "fingerprint".to_string(),
+            label: "ci".to_string(),
+            mode: WatermarkMode::Noise,
+            token: "token".to_string(),
+        };
+        let object = assert_obj!(
+            "price" => 19.99,
+            "zero" => 0.0,
+            "count" => 3,
+            "name" => "widget",
+        );
+        let marked = watermark.apply(id, object.clone())?;
+        let Some(ConvexValue::Float64(price)) = marked.get("price") else {
+            panic!("price should still be a float");
+        };
+        assert_eq!(price.to_bits() & 1 == 1, noise_bit("token", id, "price"));
+        assert!((price - 19.99).abs() <= f64::EPSILON * 19.99);
+        assert_eq!(marked.get("zero"), object.get("zero"));
+        assert_eq!(marked.get("count"), object.get("count"));
+        assert_eq!(marked.get("name"), object.get("name"));
+        // The same export always marks a document the same way.
+        assert_eq!(watermark.apply(id, object)?, marked);
+
+        let watermark = ExportWatermark {
+            mode: WatermarkMode::Field,
+            ..watermark
+        };
+        let object = assert_obj!("price" => 19.99);
+        assert_eq!(watermark.apply(id, object.clone())?, object);
+        Ok(())
+    }
+}
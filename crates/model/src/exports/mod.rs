@@ -31,9 +31,11 @@ use database::{
     SystemMetadataModel,
     Transaction,
 };
+use errors::ErrorMetadata;
 use sync_types::Timestamp;
 use types::{
     ExportFormat,
+    ExportProgress,
     ExportRequestor,
 };
 use value::{
@@ -183,6 +185,34 @@ impl<'a, RT: Runtime> ExportsModel<'a, RT> {
         Ok(result)
     }
 
+    /// Completed exports from `requestor`, newest first. Includes expired
+    /// exports that haven't been cleaned up yet.
+    pub async fn list_completed(
+        &mut self,
+        requestor: ExportRequestor,
+    ) -> anyhow::Result<Vec<ParsedDocument<Export>>> {
+        let index_range = IndexRange {
+            index_name: EXPORTS_BY_REQUESTOR.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                EXPORTS_REQUESTOR_FIELD.clone(),
+                ConvexValue::try_from(requestor.to_string())?.into(),
+            )],
+            order: Order::Desc,
+        };
+        let completed_filter = Expression::Eq(
+            Expression::Field(EXPORTS_STATE_FIELD.clone()).into(),
+            Expression::Literal(maybe_val!("completed")).into(),
+        );
+        let query = Query::index_range(index_range).filter(completed_filter);
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        let mut result = vec![];
+        while let Some(doc) = query_stream.next(self.tx, None).await? {
+            let row: ParsedDocument<Export> = doc.try_into()?;
+            result.push(row);
+        }
+        Ok(result)
+    }
+
     pub async fn latest_requested(&mut self) -> anyhow::Result<Option<ParsedDocument<Export>>> {
         self.export_in_state("requested").await
     }
@@ -269,6 +299,55 @@ impl<'a, RT: Runtime> ExportsModel<'a, RT> {
         Ok(())
     }
 
+    /// Deletes a completed or failed export. Returns the completed export's
+    /// object key, which the caller should delete from exports storage after
+    /// committing.
+    pub async fn delete(
+        &mut self,
+        snapshot_id: DeveloperDocumentId,
+    ) -> anyhow::Result<Option<ObjectKey>> {
+        let (id, export) = self
+            .get(snapshot_id)
+            .await?
+            .context(ErrorMetadata::not_found(
+                "ExportNotFound",
+                format!("The requested export {snapshot_id} was not found"),
+            ))?
+            .into_id_and_value();
+        let object_key = match export {
+            Export::Completed { zip_object_key, .. } => Some(zip_object_key),
+            Export::Failed { .. } => None,
+            Export::Requested { .. } | Export::InProgress { .. } => {
+                anyhow::bail!(ErrorMetadata::bad_request(
+                    "ExportInProgress",
+                    format!("The requested export {snapshot_id} hasn't finished yet"),
+                ))
+            },
+        };
+        SystemMetadataModel::new_global(self.tx).delete(id).await?;
+        Ok(object_key)
+    }
+
+    pub async fn update_progress(
+        &mut self,
+        snapshot_id: DeveloperDocumentId,
+        new_progress: ExportProgress,
+    ) -> anyhow::Result<()> {
+        let (id, mut export) = self
+            .get(snapshot_id)
+            .await?
+            .context("Snapshot not found")?
+            .into_id_and_value();
+        let Export::InProgress { progress, .. } = &mut export else {
+            anyhow::bail!("Can only update progress on in-progress exports");
+        };
+        *progress = Some(new_progress);
+        SystemMetadataModel::new_global(self.tx)
+            .replace(id, export.try_into()?)
+            .await?;
+        Ok(())
+    }
+
     pub async fn cleanup_expired(
         &mut self,
         retention_duration: Duration,
@@ -307,7 +386,10 @@ mod tests {
     use anyhow::Context;
     use cmd_util::env::env_config;
     use common::{
-        components::ComponentId,
+        components::{
+            ComponentId,
+            ComponentPath,
+        },
         types::ObjectKey,
     };
     use database::test_helpers::DbFixtures;
@@ -324,6 +406,7 @@ mod tests {
             types::{
                 Export,
                 ExportFormat,
+                ExportProgress,
                 ExportRequestor,
             },
             ExportsModel,
@@ -545,6 +628,51 @@ mod tests {
         Ok(())
     }
 
+    #[convex_macro::test_runtime]
+    async fn test_update_progress(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin_system().await?;
+        let ts = *tx.begin_timestamp();
+        let ts_u64: u64 = ts.into();
+        let mut exports_model = ExportsModel::new(&mut tx);
+
+        let export = Export::requested(
+            ExportFormat::Zip {
+                include_storage: false,
+            },
+            ComponentId::test_user(),
+            ExportRequestor::SnapshotExport,
+            ts_u64 + 1000,
+        );
+        let requested_id = exports_model.insert_export(export.clone()).await?;
+        let new_progress = ExportProgress {
+            component_path: ComponentPath::root(),
+            current_table: Some("messages".parse()?),
+            documents_written: 10,
+            total_documents: 100,
+            bytes_written: 1024,
+        };
+        assert!(exports_model
+            .update_progress(requested_id.developer_id, new_progress.clone())
+            .await
+            .is_err());
+
+        let id = exports_model.insert_export(export.in_progress(ts)?).await?;
+        exports_model
+            .update_progress(id.developer_id, new_progress.clone())
+            .await?;
+        let export = exports_model
+            .get(id.developer_id)
+            .await?
+            .context("Not found")?
+            .into_value();
+        let Export::InProgress { progress, .. } = export else {
+            anyhow::bail!("Export must be in progress");
+        };
+        assert_eq!(progress, Some(new_progress));
+        Ok(())
+    }
+
     #[convex_macro::test_runtime]
     async fn test_cleanup_expired(rt: TestRuntime) -> anyhow::Result<()> {
         let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
@@ -4,8 +4,14 @@ use std::fmt::{
 };
 
 use common::{
-    components::ComponentId,
-    types::ObjectKey,
+    components::{
+        ComponentId,
+        ComponentPath,
+    },
+    types::{
+        ObjectKey,
+        TableName,
+    },
 };
 use serde::{
     Deserialize,
@@ -33,6 +39,8 @@ pub enum Export {
         requestor: ExportRequestor,
         /// Expiration timestamp in nanos
         expiration_ts: u64,
+        /// What this attempt has written so far.
+        progress: Option<ExportProgress>,
     },
     Completed {
         /// Timestamp for the successful (final) attempt at Export.
@@ -75,6 +83,7 @@ enum SerializedExport {
         component: Option<String>,
         requestor: String,
         expiration_ts: i64,
+        progress: Option<SerializedExportProgress>,
     },
     Completed {
         start_ts: u64,
@@ -116,12 +125,14 @@ impl TryFrom<Export> for SerializedExport {
                 component,
                 expiration_ts,
                 requestor,
+                progress,
             } => SerializedExport::InProgress {
                 start_ts: start_ts.into(),
                 format: format.into(),
                 component: component.serialize_to_string(),
                 requestor: requestor.to_string(),
                 expiration_ts: expiration_ts as i64,
+                progress: progress.map(Into::into),
             },
             Export::Completed {
                 start_ts,
@@ -179,12 +190,14 @@ impl TryFrom<SerializedExport> for Export {
                 component,
                 expiration_ts,
                 requestor,
+                progress,
             } => Export::InProgress {
                 start_ts: start_ts.try_into()?,
                 format: format.into(),
                 component: ComponentId::deserialize_from_string(component.as_deref())?,
                 requestor: requestor.parse()?,
                 expiration_ts: expiration_ts as u64,
+                progress: progress.map(TryInto::try_into).transpose()?,
             },
             SerializedExport::Completed {
                 start_ts,
@@ -222,6 +235,61 @@ impl TryFrom<SerializedExport> for Export {
 
 codegen_convex_serialization!(Export, SerializedExport);
 
+/// Running totals for an export in progress, so clients subscribed to the
+/// export can show a live progress bar.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct ExportProgress {
+    pub component_path: ComponentPath,
+    /// The table currently being written.
+    pub current_table: Option<TableName>,
+    pub documents_written: u64,
+    /// Number of documents in all the tables being exported.
+    pub total_documents: u64,
+    /// Size of the documents and files written, before compression.
+    pub bytes_written: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SerializedExportProgress {
+    component_path: Option<String>,
+    current_table: Option<String>,
+    documents_written: i64,
+    total_documents: i64,
+    bytes_written: i64,
+}
+
+impl From<ExportProgress> for SerializedExportProgress {
+    fn from(progress: ExportProgress) -> Self {
+        SerializedExportProgress {
+            component_path: progress.component_path.serialize(),
+            current_table: progress.current_table.map(|table| table.to_string()),
+            documents_written: progress.documents_written as i64,
+            total_documents: progress.total_documents as i64,
+            bytes_written: progress.bytes_written as i64,
+        }
+    }
+}
+
+impl TryFrom<SerializedExportProgress> for ExportProgress {
+    type Error = anyhow::Error;
+
+    fn try_from(progress: SerializedExportProgress) -> anyhow::Result<Self> {
+        Ok(ExportProgress {
+            component_path: ComponentPath::deserialize(progress.component_path.as_deref())?,
+            current_table: progress
+                .current_table
+                .map(|table| table.parse())
+                .transpose()?,
+            documents_written: progress.documents_written as u64,
+            total_documents: progress.total_documents as u64,
+            bytes_written: progress.bytes_written as u64,
+        })
+    }
+}
+
+codegen_convex_serialization!(ExportProgress, SerializedExportProgress);
+
 impl Export {
     pub fn format(&self) -> ExportFormat {
         match self {
@@ -256,6 +324,20 @@ impl Export {
 pub enum ExportFormat {
     /// zip file containing a CleanJsonl for each table, and sidecar type info.
     Zip { include_storage: bool },
+    /// zip file containing a Parquet file for each table, with columns derived
+    /// from the active schema. Meant for loading into analytics engines, and
+    /// can't be imported back into Convex.
+    Parquet { include_storage: bool },
+}
+
+impl ExportFormat {
+    pub fn include_storage(&self) -> bool {
+        match self {
+            ExportFormat::Zip { include_storage } | ExportFormat::Parquet { include_storage } => {
+                *include_storage
+            },
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -264,19 +346,32 @@ pub enum ExportFormat {
 #[serde(rename_all = "snake_case")]
 enum SerializedExportFormat {
     Zip { include_storage: bool },
+    Parquet { include_storage: bool },
 }
 
 impl From<ExportFormat> for SerializedExportFormat {
     fn from(value: ExportFormat) -> Self {
-        let ExportFormat::Zip { include_storage } = value;
-        SerializedExportFormat::Zip { include_storage }
+        match value {
+            ExportFormat::Zip { include_storage } => {
+                SerializedExportFormat::Zip { include_storage }
+            },
+            ExportFormat::Parquet { include_storage } => {
+                SerializedExportFormat::Parquet { include_storage }
+            },
+        }
     }
 }
 
 impl From<SerializedExportFormat> for ExportFormat {
     fn from(value: SerializedExportFormat) -> Self {
-        let SerializedExportFormat::Zip { include_storage } = value;
-        ExportFormat::Zip { include_storage }
+        match value {
+            SerializedExportFormat::Zip { include_storage } => {
+                ExportFormat::Zip { include_storage }
+            },
+            SerializedExportFormat::Parquet { include_storage } => {
+                ExportFormat::Parquet { include_storage }
+            },
+        }
     }
 }
 
@@ -290,6 +385,8 @@ pub enum ExportRequestor {
     SnapshotExport,
     /// The team-level cloud backup feature
     CloudBackup,
+    /// The deployment's own backup schedule
+    ScheduledBackup,
 }
 
 impl ExportRequestor {
@@ -297,6 +394,7 @@ impl ExportRequestor {
         match self {
             Self::SnapshotExport => "snapshot_export",
             Self::CloudBackup => "cloud_backup",
+            Self::ScheduledBackup => "scheduled_backup",
         }
     }
 }
@@ -329,6 +427,7 @@ impl Export {
                 component,
                 requestor,
                 expiration_ts,
+                progress: None,
             }),
             Self::Completed { .. } | Self::InProgress { .. } | Self::Failed { .. } => Err(
                 anyhow::anyhow!("Can only begin an export that is requested"),
@@ -349,6 +448,7 @@ impl Export {
                 requestor,
                 expiration_ts,
                 start_ts: _, // replace start_ts with the actual database TS
+                progress: _,
             } => {
                 anyhow::ensure!(snapshot_ts <= complete_ts);
                 Ok(Self::Completed {
@@ -57,12 +57,17 @@ use value::{
 
 use self::virtual_table::FileStorageDocMapper;
 use crate::{
-    file_storage::types::FileStorageEntry,
+    file_storage::types::{
+        FileScanStatus,
+        FileStorageEntry,
+    },
     SystemIndex,
     SystemTable,
 };
 
+pub mod transforms;
 pub mod types;
+pub mod uploads;
 pub mod virtual_table;
 
 pub type BatchKey = usize;
@@ -328,6 +333,28 @@ impl<'a, RT: Runtime> FileStorageModel<'a, RT> {
         Ok(Some(entry.into_value()))
     }
 
+    /// Record the result of scanning a file, returning false if the file was
+    /// deleted before the scan finished.
+    pub async fn set_scan_status(
+        &mut self,
+        storage_id: FileStorageId,
+        scan_status: FileScanStatus,
+    ) -> anyhow::Result<bool> {
+        if !self.tx.identity().is_system() {
+            anyhow::bail!(unauthorized_error("set_scan_status"))
+        }
+        let Some(entry) = self.get_file(storage_id).await? else {
+            return Ok(false);
+        };
+        let document_id = entry.id();
+        let mut entry = entry.into_value();
+        entry.scan_status = Some(scan_status);
+        SystemMetadataModel::new(self.tx, self.namespace)
+            .replace(document_id, entry.try_into()?)
+            .await?;
+        Ok(true)
+    }
+
     pub async fn get_total_storage_count(&mut self) -> anyhow::Result<u64> {
         TableModel::new(self.tx)
             .count(self.namespace, &FILE_STORAGE_TABLE.clone())
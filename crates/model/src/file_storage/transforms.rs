@@ -0,0 +1,173 @@
+//! Images derived from files in `_storage` by the file-serving route's
+//! transform parameters. Transforms are keyed by the source file's object key,
+//! which never changes, and the transform's canonical parameters.
+use std::sync::LazyLock;
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    query::{
+        IndexRange,
+        IndexRangeExpression,
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+    types::{
+        IndexName,
+        ObjectKey,
+    },
+};
+use database::{
+    defaults::system_index,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use value::{
+    ConvexValue,
+    FieldPath,
+    ResolvedDocumentId,
+    TableName,
+    TableNamespace,
+};
+
+use super::types::FileStorageTransform;
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub static FILE_STORAGE_TRANSFORMS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_file_storage_transforms"
+        .parse()
+        .expect("Invalid built-in file storage transforms table")
+});
+
+pub static FILE_STORAGE_TRANSFORMS_BY_SOURCE_INDEX: LazyLock<IndexName> = LazyLock::new(|| {
+    system_index(
+        &FILE_STORAGE_TRANSFORMS_TABLE,
+        "by_source_key_and_transform",
+    )
+});
+
+static SOURCE_KEY_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "sourceKey".parse().expect("invalid sourceKey field"));
+static TRANSFORM_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "transform".parse().expect("invalid transform field"));
+
+pub struct FileStorageTransformsTable;
+impl SystemTable for FileStorageTransformsTable {
+    fn table_name(&self) -> &'static TableName {
+        &FILE_STORAGE_TRANSFORMS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![SystemIndex {
+            name: FILE_STORAGE_TRANSFORMS_BY_SOURCE_INDEX.clone(),
+            fields: vec![SOURCE_KEY_FIELD.clone(), TRANSFORM_FIELD.clone()]
+                .try_into()
+                .unwrap(),
+        }]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<FileStorageTransform>::try_from(document).map(|_| ())
+    }
+}
+
+pub struct FileStorageTransformModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> FileStorageTransformModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    pub async fn get(
+        &mut self,
+        source_key: &ObjectKey,
+        transform: &str,
+    ) -> anyhow::Result<Option<ParsedDocument<FileStorageTransform>>> {
+        let index_range = IndexRange {
+            index_name: FILE_STORAGE_TRANSFORMS_BY_SOURCE_INDEX.clone(),
+            range: vec![
+                IndexRangeExpression::Eq(
+                    SOURCE_KEY_FIELD.clone(),
+                    ConvexValue::try_from(source_key.to_string())?.into(),
+                ),
+                IndexRangeExpression::Eq(
+                    TRANSFORM_FIELD.clone(),
+                    ConvexValue::try_from(transform.to_string())?.into(),
+                ),
+            ],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        query_stream
+            .expect_at_most_one(self.tx)
+            .await?
+            .map(ParsedDocument::try_from)
+            .transpose()
+    }
+
+    pub async fn insert(
+        &mut self,
+        transform: FileStorageTransform,
+    ) -> anyhow::Result<ResolvedDocumentId> {
+        SystemMetadataModel::new_global(self.tx)
+            .insert(
+                FileStorageTransformsTable.table_name(),
+                transform.try_into()?,
+            )
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::types::ObjectKey;
+    use database::test_helpers::DbFixtures;
+    use runtime::testing::TestRuntime;
+    use value::sha256::Sha256;
+
+    use super::FileStorageTransformModel;
+    use crate::{
+        file_storage::types::FileStorageTransform,
+        test_helpers::DbFixturesWithModel,
+    };
+
+    #[convex_macro::test_runtime]
+    async fn test_get_transform(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin_system().await?;
+        let mut model = FileStorageTransformModel::new(&mut tx);
+        let source_key = ObjectKey::try_from("source".to_string())?;
+        let transform = FileStorageTransform {
+            source_key: source_key.clone(),
+            transform: "w=100,h=auto,fit=contain,format=webp".to_string(),
+            storage_key: ObjectKey::try_from("thumbnail".to_string())?,
+            sha256: Sha256::hash(b"thumbnail"),
+            size: 9,
+            content_type: "image/webp".to_string(),
+        };
+        model.insert(transform.clone()).await?;
+
+        let found = model
+            .get(&source_key, "w=100,h=auto,fit=contain,format=webp")
+            .await?;
+        assert_eq!(found.map(|doc| doc.into_value()), Some(transform));
+        assert!(model
+            .get(&source_key, "w=200,h=auto,fit=contain,format=webp")
+            .await?
+            .is_none());
+        Ok(())
+    }
+}
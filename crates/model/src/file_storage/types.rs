@@ -1,18 +1,35 @@
-use std::collections::BTreeMap;
+use std::{
+    collections::BTreeMap,
+    str::FromStr,
+};
 
 use anyhow::Context;
 use common::{
+    components::ComponentId,
     obj,
     types::{
         ObjectKey,
         StorageUuid,
     },
 };
-use pb::storage::FileStorageEntry as FileStorageEntryProto;
+use errors::ErrorMetadata;
+use pb::storage::{
+    FileStorageEntry as FileStorageEntryProto,
+    FileStorageTag as FileStorageTagProto,
+};
+#[cfg(any(test, feature = "testing"))]
+use proptest::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
 use value::{
+    codegen_convex_serialization,
     sha256::Sha256Digest,
     ConvexObject,
     ConvexValue,
+    FieldName,
+    IdentifierFieldName,
 };
 
 #[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
@@ -24,6 +41,157 @@ pub struct FileStorageEntry {
     pub sha256: Sha256Digest,   // Sha256 of contents
     pub size: i64,              // Size of file in storage
     pub content_type: Option<String>, // Optional ContentType header saved with file
+    pub filename: Option<String>, // Optional filename to serve the file with
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(
+            strategy = "prop::collection::btree_map(\"[a-zA-Z][a-zA-Z0-9_]{0,8}\", \".*\", 0..4)"
+        )
+    )]
+    pub tags: BTreeMap<String, String>, // User-supplied key/value tags
+    /// Set when a file scanner was configured as the file was stored. Files
+    /// without a status were never scanned and are served normally.
+    pub scan_status: Option<FileScanStatus>,
+}
+
+/// Where a file is in the post-upload scan. Only clean files are served.
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileScanStatus {
+    Pending,
+    Clean,
+    Quarantined,
+}
+
+impl FileScanStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Clean => "clean",
+            Self::Quarantined => "quarantined",
+        }
+    }
+}
+
+impl FromStr for FileScanStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "pending" => Ok(Self::Pending),
+            "clean" => Ok(Self::Clean),
+            "quarantined" => Ok(Self::Quarantined),
+            _ => anyhow::bail!("Invalid file scan status {s:?}"),
+        }
+    }
+}
+
+impl FileStorageEntry {
+    /// Whether the file can be served. Files waiting on a scan or that the
+    /// scanner flagged aren't.
+    pub fn check_servable(&self) -> anyhow::Result<()> {
+        match self.scan_status {
+            None | Some(FileScanStatus::Clean) => Ok(()),
+            Some(FileScanStatus::Pending) => anyhow::bail!(ErrorMetadata::forbidden(
+                "FileScanPending",
+                format!(
+                    "File {} is still being scanned and can't be served yet",
+                    self.storage_id
+                ),
+            )),
+            Some(FileScanStatus::Quarantined) => anyhow::bail!(ErrorMetadata::forbidden(
+                "FileQuarantined",
+                format!(
+                    "File {} was quarantined by the file scanner",
+                    self.storage_id
+                ),
+            )),
+        }
+    }
+}
+
+/// The most tags a file in `_storage` can have.
+pub const MAX_FILE_STORAGE_TAGS: usize = 32;
+const MAX_FILE_STORAGE_TAG_KEY_LENGTH: usize = 64;
+const MAX_FILE_STORAGE_TAG_VALUE_LENGTH: usize = 1024;
+const MAX_FILE_STORAGE_FILENAME_LENGTH: usize = 255;
+
+/// Check a filename supplied when storing a file, which is served back in the
+/// file's `Content-Disposition` header.
+pub fn validate_file_storage_filename(filename: &str) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        !filename.is_empty()
+            && filename.len() <= MAX_FILE_STORAGE_FILENAME_LENGTH
+            && !filename
+                .chars()
+                .any(|c| c.is_control() || c == '/' || c == '\\' || c == '"'),
+        ErrorMetadata::bad_request(
+            "InvalidFilename",
+            format!(
+                "Invalid filename {filename:?}. Filenames must be between 1 and \
+                 {MAX_FILE_STORAGE_FILENAME_LENGTH} bytes and can't contain slashes, quotes or \
+                 control characters."
+            ),
+        )
+    );
+    Ok(())
+}
+
+/// Check tags supplied when storing a file. Tag keys are identifiers so that
+/// queries can filter on them, e.g. `q.field("tags.kind")`.
+pub fn validate_file_storage_tags(tags: &BTreeMap<String, String>) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        tags.len() <= MAX_FILE_STORAGE_TAGS,
+        ErrorMetadata::bad_request(
+            "TooManyFileTags",
+            format!(
+                "Files can have at most {MAX_FILE_STORAGE_TAGS} tags, not {}",
+                tags.len()
+            ),
+        )
+    );
+    for (key, value) in tags {
+        anyhow::ensure!(
+            key.len() <= MAX_FILE_STORAGE_TAG_KEY_LENGTH
+                && !key.starts_with('_')
+                && key.parse::<IdentifierFieldName>().is_ok(),
+            ErrorMetadata::bad_request(
+                "InvalidFileTag",
+                format!(
+                    "Invalid tag key {key:?}. Tag keys must be identifiers of at most \
+                     {MAX_FILE_STORAGE_TAG_KEY_LENGTH} characters."
+                ),
+            )
+        );
+        anyhow::ensure!(
+            value.len() <= MAX_FILE_STORAGE_TAG_VALUE_LENGTH,
+            ErrorMetadata::bad_request(
+                "InvalidFileTag",
+                format!(
+                    "The value of tag {key:?} is longer than {MAX_FILE_STORAGE_TAG_VALUE_LENGTH} \
+                     bytes"
+                ),
+            )
+        );
+    }
+    Ok(())
+}
+
+fn tags_to_object(tags: BTreeMap<String, String>) -> anyhow::Result<ConvexObject> {
+    tags.into_iter()
+        .map(|(key, value)| Ok((key.parse()?, ConvexValue::try_from(value)?)))
+        .collect::<anyhow::Result<BTreeMap<FieldName, ConvexValue>>>()?
+        .try_into()
+}
+
+fn tags_from_object(tags: ConvexObject) -> anyhow::Result<BTreeMap<String, String>> {
+    BTreeMap::from(tags)
+        .into_iter()
+        .map(|(key, value)| match value {
+            ConvexValue::String(value) => Ok((key.to_string(), value.to_string())),
+            _ => anyhow::bail!("Non-string value for tag {key}"),
+        })
+        .collect()
 }
 
 impl TryFrom<FileStorageEntry> for ConvexObject {
@@ -36,10 +204,13 @@ impl TryFrom<FileStorageEntry> for ConvexObject {
             sha256,
             size,
             content_type,
+            filename,
+            tags,
+            scan_status,
         }: FileStorageEntry,
     ) -> Result<Self, Self::Error> {
         let storage_key: String = storage_key.into();
-        obj!(
+        let object = obj!(
             "storageId" => storage_id.to_string(),
             "storageKey" => storage_key,
             "sha256" => sha256,
@@ -48,7 +219,23 @@ impl TryFrom<FileStorageEntry> for ConvexObject {
                 None => ConvexValue::Null,
                 Some(ct) => ct.try_into()?,
             },
-        )
+        )?;
+        // Only files stored with a filename, tags or a scanner configured
+        // have the fields, so existing documents don't change shape.
+        let mut fields: BTreeMap<_, _> = object.into();
+        if let Some(filename) = filename {
+            fields.insert("filename".parse()?, filename.try_into()?);
+        }
+        if !tags.is_empty() {
+            fields.insert("tags".parse()?, tags_to_object(tags)?.into());
+        }
+        if let Some(scan_status) = scan_status {
+            fields.insert(
+                "scanStatus".parse()?,
+                scan_status.as_str().to_string().try_into()?,
+            );
+        }
+        fields.try_into()
     }
 }
 
@@ -78,12 +265,30 @@ impl TryFrom<ConvexObject> for FileStorageEntry {
             Some(ConvexValue::String(ct)) => Some(String::from(ct)),
             _ => anyhow::bail!("Invalid 'content_type' in {object_fields:?}"),
         };
+        let filename = match object_fields.remove("filename") {
+            None => None,
+            Some(ConvexValue::String(filename)) => Some(String::from(filename)),
+            _ => anyhow::bail!("Invalid 'filename' in {object_fields:?}"),
+        };
+        let tags = match object_fields.remove("tags") {
+            None => BTreeMap::new(),
+            Some(ConvexValue::Object(tags)) => tags_from_object(tags)?,
+            _ => anyhow::bail!("Invalid 'tags' in {object_fields:?}"),
+        };
+        let scan_status = match object_fields.remove("scanStatus") {
+            None => None,
+            Some(ConvexValue::String(scan_status)) => Some(String::from(scan_status).parse()?),
+            _ => anyhow::bail!("Invalid 'scanStatus' in {object_fields:?}"),
+        };
         Ok(Self {
             storage_id,
             storage_key,
             sha256,
             size,
             content_type,
+            filename,
+            tags,
+            scan_status,
         })
     }
 }
@@ -102,12 +307,26 @@ impl TryFrom<FileStorageEntryProto> for FileStorageEntry {
             .try_into()?;
         let sha256 = entry.sha256.context("Missing `sha256` field")?.try_into()?;
         let size = entry.size.context("Missing `size` field")?;
+        let tags = entry
+            .tags
+            .into_iter()
+            .map(|tag| {
+                Ok((
+                    tag.key.context("Missing `key` field")?,
+                    tag.value.context("Missing `value` field")?,
+                ))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        let scan_status = entry.scan_status.map(|s| s.parse()).transpose()?;
         Ok(FileStorageEntry {
             storage_id,
             storage_key,
             sha256,
             size,
             content_type: entry.content_type,
+            filename: entry.filename,
+            tags,
+            scan_status,
         })
     }
 }
@@ -120,10 +339,188 @@ impl From<FileStorageEntry> for FileStorageEntryProto {
             sha256: Some(entry.sha256.to_vec()),
             size: Some(entry.size),
             content_type: entry.content_type,
+            filename: entry.filename,
+            tags: entry
+                .tags
+                .into_iter()
+                .map(|(key, value)| FileStorageTagProto {
+                    key: Some(key),
+                    value: Some(value),
+                })
+                .collect(),
+            scan_status: entry.scan_status.map(|s| s.as_str().to_string()),
         }
     }
 }
 
+/// A file being uploaded to `_storage` in chunks over several requests, so a
+/// client can resume an interrupted upload from the last chunk that arrived.
+/// Each chunk is stored as its own object, and the chunks are concatenated
+/// into the file when the upload is finished.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct FileStorageUpload {
+    /// The component whose `_storage` the file is stored in.
+    pub component: ComponentId,
+    pub content_type: Option<String>,
+    /// The file's total size, if the client declared it up front.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "proptest::option::of(0..=i64::MAX as u64)")
+    )]
+    pub upload_length: Option<u64>,
+    /// The chunks received so far, in order.
+    pub chunks: Vec<FileStorageUploadChunk>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct FileStorageUploadChunk {
+    pub object_key: ObjectKey,
+    pub sha256: Sha256Digest,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=i64::MAX as u64")
+    )]
+    pub size: u64,
+}
+
+impl FileStorageUpload {
+    /// The number of bytes received so far, which is where the next chunk
+    /// starts.
+    pub fn offset(&self) -> u64 {
+        self.chunks.iter().map(|chunk| chunk.size).sum()
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SerializedFileStorageUpload {
+    component: Option<String>,
+    content_type: Option<String>,
+    upload_length: Option<i64>,
+    chunks: Vec<SerializedFileStorageUploadChunk>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SerializedFileStorageUploadChunk {
+    object_key: String,
+    sha256: String,
+    size: i64,
+}
+
+impl TryFrom<FileStorageUpload> for SerializedFileStorageUpload {
+    type Error = anyhow::Error;
+
+    fn try_from(upload: FileStorageUpload) -> anyhow::Result<Self> {
+        Ok(SerializedFileStorageUpload {
+            component: upload.component.serialize_to_string(),
+            content_type: upload.content_type,
+            upload_length: upload.upload_length.map(i64::try_from).transpose()?,
+            chunks: upload
+                .chunks
+                .into_iter()
+                .map(|chunk| {
+                    anyhow::Ok(SerializedFileStorageUploadChunk {
+                        object_key: chunk.object_key.to_string(),
+                        sha256: chunk.sha256.as_base64(),
+                        size: chunk.size.try_into()?,
+                    })
+                })
+                .try_collect()?,
+        })
+    }
+}
+
+impl TryFrom<SerializedFileStorageUpload> for FileStorageUpload {
+    type Error = anyhow::Error;
+
+    fn try_from(upload: SerializedFileStorageUpload) -> anyhow::Result<Self> {
+        Ok(FileStorageUpload {
+            component: ComponentId::deserialize_from_string(upload.component.as_deref())?,
+            content_type: upload.content_type,
+            upload_length: upload.upload_length.map(u64::try_from).transpose()?,
+            chunks: upload
+                .chunks
+                .into_iter()
+                .map(|chunk| {
+                    anyhow::Ok(FileStorageUploadChunk {
+                        object_key: chunk.object_key.try_into()?,
+                        sha256: Sha256Digest::from_base64(&chunk.sha256)?,
+                        size: chunk.size.try_into()?,
+                    })
+                })
+                .try_collect()?,
+        })
+    }
+}
+
+codegen_convex_serialization!(FileStorageUpload, SerializedFileStorageUpload);
+
+/// An image derived from a file in `_storage` by resizing or re-encoding it,
+/// cached so later requests for the same transform are served directly.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct FileStorageTransform {
+    /// The object key of the original file.
+    pub source_key: ObjectKey,
+    /// The transform's canonical parameters, e.g. `w=100,h=100,fit=cover`.
+    pub transform: String,
+    /// The object key of the transformed image.
+    pub storage_key: ObjectKey,
+    pub sha256: Sha256Digest,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=i64::MAX as u64")
+    )]
+    pub size: u64,
+    pub content_type: String,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SerializedFileStorageTransform {
+    source_key: String,
+    transform: String,
+    storage_key: String,
+    sha256: String,
+    size: i64,
+    content_type: String,
+}
+
+impl TryFrom<FileStorageTransform> for SerializedFileStorageTransform {
+    type Error = anyhow::Error;
+
+    fn try_from(transform: FileStorageTransform) -> anyhow::Result<Self> {
+        Ok(SerializedFileStorageTransform {
+            source_key: transform.source_key.to_string(),
+            transform: transform.transform,
+            storage_key: transform.storage_key.to_string(),
+            sha256: transform.sha256.as_base64(),
+            size: transform.size.try_into()?,
+            content_type: transform.content_type,
+        })
+    }
+}
+
+impl TryFrom<SerializedFileStorageTransform> for FileStorageTransform {
+    type Error = anyhow::Error;
+
+    fn try_from(transform: SerializedFileStorageTransform) -> anyhow::Result<Self> {
+        Ok(FileStorageTransform {
+            source_key: transform.source_key.try_into()?,
+            transform: transform.transform,
+            storage_key: transform.storage_key.try_into()?,
+            sha256: Sha256Digest::from_base64(&transform.sha256)?,
+            size: transform.size.try_into()?,
+            content_type: transform.content_type,
+        })
+    }
+}
+
+codegen_convex_serialization!(FileStorageTransform, SerializedFileStorageTransform);
+
 #[cfg(test)]
 mod tests {
     use cmd_util::env::env_config;
@@ -132,7 +529,28 @@ mod tests {
     use proptest::prelude::*;
     use value::ConvexObject;
 
-    use super::FileStorageEntry;
+    use super::{
+        validate_file_storage_filename,
+        validate_file_storage_tags,
+        FileStorageEntry,
+    };
+
+    #[test]
+    fn test_validate_file_storage_metadata() {
+        assert!(validate_file_storage_filename("avatar.png").is_ok());
+        assert!(validate_file_storage_filename("").is_err());
+        assert!(validate_file_storage_filename("../avatar.png").is_err());
+        assert!(validate_file_storage_filename("a\"b.png").is_err());
+
+        let tags = |tags: &[(&str, &str)]| {
+            tags.iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+        assert!(validate_file_storage_tags(&tags(&[("kind", "avatar"), ("userId", "1")])).is_ok());
+        assert!(validate_file_storage_tags(&tags(&[("not an identifier", "x")])).is_err());
+        assert!(validate_file_storage_tags(&tags(&[("_system", "x")])).is_err());
+    }
 
     proptest! {
         #![proptest_config(
@@ -0,0 +1,241 @@
+//! In-progress resumable uploads to `_storage`. The upload's document tracks
+//! the chunks received so far so an interrupted client can ask for the
+//! offset to resume from.
+use std::sync::LazyLock;
+
+use anyhow::Context;
+use common::{
+    components::ComponentId,
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    runtime::Runtime,
+};
+use database::{
+    SystemMetadataModel,
+    Transaction,
+};
+use errors::ErrorMetadata;
+use value::{
+    ResolvedDocumentId,
+    TableName,
+    TableNamespace,
+};
+
+use super::types::{
+    FileStorageUpload,
+    FileStorageUploadChunk,
+};
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub static FILE_STORAGE_UPLOADS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_file_storage_uploads"
+        .parse()
+        .expect("Invalid built-in file storage uploads table")
+});
+
+/// Upper bound on the number of chunks in a resumable upload, which keeps the
+/// upload's document well under the document size limit.
+pub const MAX_FILE_STORAGE_UPLOAD_CHUNKS: usize = 2048;
+
+pub struct FileStorageUploadsTable;
+impl SystemTable for FileStorageUploadsTable {
+    fn table_name(&self) -> &'static TableName {
+        &FILE_STORAGE_UPLOADS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<FileStorageUpload>::try_from(document).map(|_| ())
+    }
+}
+
+pub struct FileStorageUploadModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> FileStorageUploadModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    pub async fn get(
+        &mut self,
+        id: ResolvedDocumentId,
+    ) -> anyhow::Result<Option<ParsedDocument<FileStorageUpload>>> {
+        anyhow::ensure!(self
+            .tx
+            .table_mapping()
+            .namespace(TableNamespace::Global)
+            .tablet_matches_name(id.tablet_id, FileStorageUploadsTable.table_name()));
+        match self.tx.get(id).await? {
+            None => Ok(None),
+            Some(doc) => Ok(Some(doc.try_into()?)),
+        }
+    }
+
+    /// The upload with `id`, which must be storing a file in `component`.
+    pub async fn must_get(
+        &mut self,
+        id: ResolvedDocumentId,
+        component: ComponentId,
+    ) -> anyhow::Result<ParsedDocument<FileStorageUpload>> {
+        let upload = self
+            .get(id)
+            .await?
+            .filter(|upload| upload.component == component);
+        upload.context(ErrorMetadata::not_found(
+            "FileUploadNotFound",
+            format!("File upload {id} not found"),
+        ))
+    }
+
+    pub async fn start_upload(
+        &mut self,
+        component: ComponentId,
+        content_type: Option<String>,
+        upload_length: Option<u64>,
+    ) -> anyhow::Result<ResolvedDocumentId> {
+        let upload = FileStorageUpload {
+            component,
+            content_type,
+            upload_length,
+            chunks: vec![],
+        };
+        SystemMetadataModel::new_global(self.tx)
+            .insert(FileStorageUploadsTable.table_name(), upload.try_into()?)
+            .await
+    }
+
+    /// Record a chunk that the client sent starting at byte `offset`, which
+    /// must be where the chunks received so far end. Returns the new offset.
+    pub async fn record_chunk(
+        &mut self,
+        id: ResolvedDocumentId,
+        component: ComponentId,
+        offset: u64,
+        chunk: FileStorageUploadChunk,
+    ) -> anyhow::Result<u64> {
+        let mut upload = self.must_get(id, component).await?.into_value();
+        let current_offset = upload.offset();
+        anyhow::ensure!(
+            offset == current_offset,
+            ErrorMetadata::bad_request(
+                "FileUploadOffsetMismatch",
+                format!(
+                    "Chunk starts at offset {offset} but the upload has received {current_offset} \
+                     bytes"
+                ),
+            )
+        );
+        anyhow::ensure!(
+            upload.chunks.len() < MAX_FILE_STORAGE_UPLOAD_CHUNKS,
+            ErrorMetadata::bad_request(
+                "TooManyFileUploadChunks",
+                format!(
+                    "An upload can have at most {MAX_FILE_STORAGE_UPLOAD_CHUNKS} chunks. Send \
+                     larger chunks."
+                ),
+            )
+        );
+        let new_offset = current_offset + chunk.size;
+        if let Some(upload_length) = upload.upload_length {
+            anyhow::ensure!(
+                new_offset <= upload_length,
+                ErrorMetadata::bad_request(
+                    "FileUploadTooLarge",
+                    format!(
+                        "Chunk ends at offset {new_offset} but the upload is only {upload_length} \
+                         bytes"
+                    ),
+                )
+            );
+        }
+        upload.chunks.push(chunk);
+        SystemMetadataModel::new_global(self.tx)
+            .replace(id, upload.try_into()?)
+            .await?;
+        Ok(new_offset)
+    }
+
+    pub async fn delete(&mut self, id: ResolvedDocumentId) -> anyhow::Result<()> {
+        SystemMetadataModel::new_global(self.tx).delete(id).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{
+        components::ComponentId,
+        types::ObjectKey,
+    };
+    use database::test_helpers::DbFixtures;
+    use errors::ErrorMetadataAnyhowExt;
+    use runtime::testing::TestRuntime;
+    use value::sha256::Sha256;
+
+    use super::FileStorageUploadModel;
+    use crate::{
+        file_storage::types::FileStorageUploadChunk,
+        test_helpers::DbFixturesWithModel,
+    };
+
+    fn chunk(object_key: &str, contents: &[u8]) -> anyhow::Result<FileStorageUploadChunk> {
+        Ok(FileStorageUploadChunk {
+            object_key: ObjectKey::try_from(object_key.to_string())?,
+            sha256: Sha256::hash(contents),
+            size: contents.len() as u64,
+        })
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_record_chunks(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin_system().await?;
+        let mut model = FileStorageUploadModel::new(&mut tx);
+        let id = model
+            .start_upload(ComponentId::Root, Some("text/plain".to_string()), Some(10))
+            .await?;
+
+        assert_eq!(
+            model
+                .record_chunk(id, ComponentId::Root, 0, chunk("a", b"hello")?)
+                .await?,
+            5
+        );
+        // A retry of the first chunk doesn't start where the upload ends.
+        let err = model
+            .record_chunk(id, ComponentId::Root, 0, chunk("b", b"hello")?)
+            .await
+            .unwrap_err();
+        assert_eq!(err.short_msg(), "FileUploadOffsetMismatch");
+        // The chunk would run past the declared length.
+        let err = model
+            .record_chunk(id, ComponentId::Root, 5, chunk("c", b"world!")?)
+            .await
+            .unwrap_err();
+        assert!(err.is_bad_request());
+        assert_eq!(
+            model
+                .record_chunk(id, ComponentId::Root, 5, chunk("d", b"world")?)
+                .await?,
+            10
+        );
+
+        let upload = model.must_get(id, ComponentId::Root).await?;
+        assert_eq!(upload.offset(), 10);
+        assert_eq!(upload.chunks.len(), 2);
+
+        model.delete(id).await?;
+        assert!(model.get(id).await?.is_none());
+        Ok(())
+    }
+}
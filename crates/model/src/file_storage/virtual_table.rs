@@ -16,6 +16,8 @@ use common::{
         VirtualSystemMapping,
     },
 };
+#[cfg(any(test, feature = "testing"))]
+use proptest::prelude::*;
 use semver::Version;
 use value::{
     val,
@@ -67,6 +69,9 @@ impl VirtualSystemDocMapper for FileStorageDocMapper {
             sha256,
             size: metadata.size as f64,
             content_type: metadata.content_type,
+            filename: metadata.filename,
+            tags: metadata.tags,
+            scan_status: metadata.scan_status.map(|s| s.as_str().to_string()),
         };
         let mut public_metadata_resolved: ConvexObject = public_metadata.try_into()?;
 
@@ -98,6 +103,15 @@ struct PublicFileMetadata {
     sha256: String,               // Hex-encoded Sha256 of contents
     size: f64,                    // Size of file in storage
     content_type: Option<String>, // Optional ContentType header saved with file
+    filename: Option<String>,     // Only present if the file was stored with one
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(
+            strategy = "prop::collection::btree_map(\"[a-zA-Z][a-zA-Z0-9_]{0,8}\", \".*\", 0..4)"
+        )
+    )]
+    tags: BTreeMap<String, String>, // Only present if the file was stored with tags
+    scan_status: Option<String>,  // Only present if a file scanner is configured
 }
 
 impl TryFrom<PublicFileMetadata> for ConvexObject {
@@ -108,6 +122,9 @@ impl TryFrom<PublicFileMetadata> for ConvexObject {
             sha256,
             size,
             content_type,
+            filename,
+            tags,
+            scan_status,
         }: PublicFileMetadata,
     ) -> Result<Self, Self::Error> {
         let mut obj: BTreeMap<FieldName, ConvexValue> = BTreeMap::new();
@@ -120,6 +137,19 @@ impl TryFrom<PublicFileMetadata> for ConvexObject {
                 Some(ct) => val!(ct),
             },
         );
+        if let Some(filename) = filename {
+            obj.insert("filename".parse()?, filename.try_into()?);
+        }
+        if !tags.is_empty() {
+            let tags: BTreeMap<FieldName, ConvexValue> = tags
+                .into_iter()
+                .map(|(key, value)| Ok((key.parse()?, value.try_into()?)))
+                .collect::<anyhow::Result<_>>()?;
+            obj.insert("tags".parse()?, ConvexObject::try_from(tags)?.into());
+        }
+        if let Some(scan_status) = scan_status {
+            obj.insert("scanStatus".parse()?, scan_status.try_into()?);
+        }
         ConvexObject::try_from(obj)
     }
 }
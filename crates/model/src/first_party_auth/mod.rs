@@ -0,0 +1,370 @@
+use std::sync::LazyLock;
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    query::{
+        IndexRange,
+        IndexRangeExpression,
+        Order,
+        Query,
+    },
+    runtime::{
+        Runtime,
+        UnixTimestamp,
+    },
+    types::IndexName,
+};
+use database::{
+    defaults::system_index,
+    unauthorized_error,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use errors::ErrorMetadata;
+use value::{
+    ConvexValue,
+    DeveloperDocumentId,
+    FieldPath,
+    ResolvedDocumentId,
+    TableName,
+    TableNamespace,
+};
+
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+use types::{
+    AuthAccount,
+    AuthSession,
+};
+
+/// How many expired sessions to clean up each time a new one is created.
+const MAX_EXPIRED_SESSIONS_DELETED: usize = 16;
+
+pub static AUTH_ACCOUNTS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_auth_accounts"
+        .parse()
+        .expect("Invalid built-in auth_accounts table")
+});
+
+pub static AUTH_ACCOUNTS_BY_EMAIL_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&AUTH_ACCOUNTS_TABLE, "by_email"));
+static EMAIL_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "email".parse().expect("invalid email field"));
+
+pub static AUTH_SESSIONS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_auth_sessions"
+        .parse()
+        .expect("Invalid built-in auth_sessions table")
+});
+
+pub static AUTH_SESSIONS_BY_ACCOUNT_ID_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&AUTH_SESSIONS_TABLE, "by_account_id"));
+pub static AUTH_SESSIONS_BY_EXPIRES_AT_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&AUTH_SESSIONS_TABLE, "by_expires_at"));
+static ACCOUNT_ID_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "accountId".parse().expect("invalid accountId field"));
+static EXPIRES_AT_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "expiresAtMs".parse().expect("invalid expiresAtMs field"));
+
+pub struct AuthAccountsTable;
+impl SystemTable for AuthAccountsTable {
+    fn table_name(&self) -> &'static TableName {
+        &AUTH_ACCOUNTS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![SystemIndex {
+            name: AUTH_ACCOUNTS_BY_EMAIL_INDEX.clone(),
+            fields: vec![EMAIL_FIELD.clone()].try_into().unwrap(),
+        }]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<AuthAccount>::try_from(document).map(|_| ())
+    }
+}
+
+pub struct AuthSessionsTable;
+impl SystemTable for AuthSessionsTable {
+    fn table_name(&self) -> &'static TableName {
+        &AUTH_SESSIONS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![
+            SystemIndex {
+                name: AUTH_SESSIONS_BY_ACCOUNT_ID_INDEX.clone(),
+                fields: vec![ACCOUNT_ID_FIELD.clone()].try_into().unwrap(),
+            },
+            SystemIndex {
+                name: AUTH_SESSIONS_BY_EXPIRES_AT_INDEX.clone(),
+                fields: vec![EXPIRES_AT_FIELD.clone()].try_into().unwrap(),
+            },
+        ]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<AuthSession>::try_from(document).map(|_| ())
+    }
+}
+
+/// Accounts and sessions for the deployment's first-party email/password and
+/// magic link auth. Only the system identity can read or write them, since
+/// they hold password hashes.
+pub struct FirstPartyAuthModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> FirstPartyAuthModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    fn check_identity(&self, operation: &'static str) -> anyhow::Result<()> {
+        if !self.tx.identity().is_system() {
+            anyhow::bail!(unauthorized_error(operation));
+        }
+        Ok(())
+    }
+
+    /// Resolves an ID from a session token or session, returning `None` if it
+    /// isn't an ID in `table`.
+    fn resolve_id(&mut self, table: &TableName, id: &str) -> Option<ResolvedDocumentId> {
+        let id = DeveloperDocumentId::decode(id).ok()?;
+        let table = self
+            .tx
+            .table_mapping()
+            .namespace(TableNamespace::Global)
+            .id(table)
+            .ok()?;
+        (id.table() == table.table_number).then(|| ResolvedDocumentId::new(table.tablet_id, id))
+    }
+
+    pub async fn account_by_email(
+        &mut self,
+        email: &str,
+    ) -> anyhow::Result<Option<ParsedDocument<AuthAccount>>> {
+        self.check_identity("account_by_email")?;
+        let index_range = IndexRange {
+            index_name: AUTH_ACCOUNTS_BY_EMAIL_INDEX.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                EMAIL_FIELD.clone(),
+                ConvexValue::try_from(email.to_lowercase())?.into(),
+            )],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        query_stream
+            .expect_at_most_one(self.tx)
+            .await?
+            .map(ParsedDocument::try_from)
+            .transpose()
+    }
+
+    pub async fn account(
+        &mut self,
+        account_id: &str,
+    ) -> anyhow::Result<Option<ParsedDocument<AuthAccount>>> {
+        self.check_identity("account")?;
+        let Some(id) = self.resolve_id(&AUTH_ACCOUNTS_TABLE, account_id) else {
+            return Ok(None);
+        };
+        self.tx
+            .get(id)
+            .await?
+            .map(ParsedDocument::try_from)
+            .transpose()
+    }
+
+    pub async fn insert_account(
+        &mut self,
+        mut account: AuthAccount,
+    ) -> anyhow::Result<ResolvedDocumentId> {
+        self.check_identity("insert_account")?;
+        account.email = account.email.to_lowercase();
+        if self.account_by_email(&account.email).await?.is_some() {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "AuthAccountAlreadyExists",
+                "An account with this email already exists",
+            ));
+        }
+        SystemMetadataModel::new_global(self.tx)
+            .insert(&AUTH_ACCOUNTS_TABLE, account.try_into()?)
+            .await
+    }
+
+    pub async fn replace_account(
+        &mut self,
+        id: ResolvedDocumentId,
+        account: AuthAccount,
+    ) -> anyhow::Result<()> {
+        self.check_identity("replace_account")?;
+        SystemMetadataModel::new_global(self.tx)
+            .replace(id, account.try_into()?)
+            .await?;
+        Ok(())
+    }
+
+    /// Starts a session for the account, cleaning up some of the sessions
+    /// that expired without logging out.
+    pub async fn create_session(
+        &mut self,
+        account_id: ResolvedDocumentId,
+        expires_at: UnixTimestamp,
+        now: UnixTimestamp,
+    ) -> anyhow::Result<ResolvedDocumentId> {
+        self.check_identity("create_session")?;
+        let index_range = IndexRange {
+            index_name: AUTH_SESSIONS_BY_EXPIRES_AT_INDEX.clone(),
+            range: vec![IndexRangeExpression::Lt(
+                EXPIRES_AT_FIELD.clone(),
+                ConvexValue::from(i64::try_from(now.as_ms_since_epoch()?)?),
+            )],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        let mut expired = vec![];
+        while expired.len() < MAX_EXPIRED_SESSIONS_DELETED {
+            let Some(document) = query_stream.next(self.tx, None).await? else {
+                break;
+            };
+            expired.push(document.id());
+        }
+        let mut system_model = SystemMetadataModel::new_global(self.tx);
+        for id in expired {
+            system_model.delete(id).await?;
+        }
+        let session = AuthSession {
+            account_id: DeveloperDocumentId::from(account_id).encode(),
+            expires_at,
+        };
+        system_model
+            .insert(&AUTH_SESSIONS_TABLE, session.try_into()?)
+            .await
+    }
+
+    /// The session a token was issued for, if it hasn't been logged out.
+    pub async fn session(
+        &mut self,
+        session_id: &str,
+    ) -> anyhow::Result<Option<ParsedDocument<AuthSession>>> {
+        self.check_identity("session")?;
+        let Some(id) = self.resolve_id(&AUTH_SESSIONS_TABLE, session_id) else {
+            return Ok(None);
+        };
+        self.tx
+            .get(id)
+            .await?
+            .map(ParsedDocument::try_from)
+            .transpose()
+    }
+
+    /// Ends the session, returning whether it existed.
+    pub async fn delete_session(&mut self, session_id: &str) -> anyhow::Result<bool> {
+        self.check_identity("delete_session")?;
+        let Some(session) = self.session(session_id).await? else {
+            return Ok(false);
+        };
+        SystemMetadataModel::new_global(self.tx)
+            .delete(session.id())
+            .await?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use common::runtime::Runtime;
+    use database::test_helpers::DbFixtures;
+    use errors::ErrorMetadataAnyhowExt;
+    use keybroker::Identity;
+    use runtime::testing::TestRuntime;
+    use value::DeveloperDocumentId;
+
+    use super::{
+        types::AuthAccount,
+        FirstPartyAuthModel,
+    };
+    use crate::test_helpers::DbFixturesWithModel;
+
+    #[convex_macro::test_runtime]
+    async fn test_accounts_and_sessions(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin(Identity::system()).await?;
+        let now = rt.unix_timestamp();
+        let account = AuthAccount {
+            email: "Ada@Example.com".to_string(),
+            password_hash: Some("hash".to_string()),
+            magic_link_token_hash: None,
+            magic_link_expires_at: None,
+            created_at: now,
+        };
+        let account_id = FirstPartyAuthModel::new(&mut tx)
+            .insert_account(account.clone())
+            .await?;
+        let err = FirstPartyAuthModel::new(&mut tx)
+            .insert_account(account)
+            .await
+            .unwrap_err();
+        assert_eq!(err.short_msg(), "AuthAccountAlreadyExists");
+        let stored = FirstPartyAuthModel::new(&mut tx)
+            .account_by_email("ada@example.COM")
+            .await?
+            .unwrap();
+        assert_eq!(stored.email, "ada@example.com");
+
+        let session_id = FirstPartyAuthModel::new(&mut tx)
+            .create_session(account_id, now + Duration::from_secs(60), now)
+            .await?;
+        let session_id = DeveloperDocumentId::from(session_id).encode();
+        let session = FirstPartyAuthModel::new(&mut tx)
+            .session(&session_id)
+            .await?
+            .unwrap();
+        assert_eq!(
+            session.account_id,
+            DeveloperDocumentId::from(account_id).encode()
+        );
+        // Account IDs aren't session IDs.
+        assert!(FirstPartyAuthModel::new(&mut tx)
+            .session(&session.account_id)
+            .await?
+            .is_none());
+
+        assert!(
+            FirstPartyAuthModel::new(&mut tx)
+                .delete_session(&session_id)
+                .await?
+        );
+        assert!(FirstPartyAuthModel::new(&mut tx)
+            .session(&session_id)
+            .await?
+            .is_none());
+
+        let mut tx = db.begin(Identity::Unknown).await?;
+        assert!(FirstPartyAuthModel::new(&mut tx)
+            .account_by_email("ada@example.com")
+            .await
+            .is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,129 @@
+use common::runtime::UnixTimestamp;
+#[cfg(any(test, feature = "testing"))]
+use proptest::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use value::codegen_convex_serialization;
+
+/// A user who signed up with the deployment's first-party auth.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct AuthAccount {
+    /// Lowercased, so accounts are unique regardless of case.
+    pub email: String,
+    /// Argon2 hash in PHC string format. Accounts created with a magic link
+    /// don't have a password.
+    pub password_hash: Option<String>,
+    /// SHA-256 hash of the magic link token that was last sent, if it hasn't
+    /// been used yet.
+    pub magic_link_token_hash: Option<Vec<u8>>,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "proptest::option::of((0..=i64::MAX as u64 / 1_000_000)
+            .prop_map(UnixTimestamp::from_millis))")
+    )]
+    pub magic_link_expires_at: Option<UnixTimestamp>,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(
+            strategy = "(0..=i64::MAX as u64 / 1_000_000).prop_map(UnixTimestamp::from_millis)"
+        )
+    )]
+    pub created_at: UnixTimestamp,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedAuthAccount {
+    email: String,
+    password_hash: Option<String>,
+    #[serde(with = "serde_bytes")]
+    magic_link_token_hash: Option<Vec<u8>>,
+    magic_link_expires_at_ms: Option<i64>,
+    created_at_ms: i64,
+}
+
+impl TryFrom<AuthAccount> for SerializedAuthAccount {
+    type Error = anyhow::Error;
+
+    fn try_from(account: AuthAccount) -> anyhow::Result<Self> {
+        Ok(Self {
+            email: account.email,
+            password_hash: account.password_hash,
+            magic_link_token_hash: account.magic_link_token_hash,
+            magic_link_expires_at_ms: account
+                .magic_link_expires_at
+                .map(|ts| anyhow::Ok(ts.as_ms_since_epoch()?.try_into()?))
+                .transpose()?,
+            created_at_ms: account.created_at.as_ms_since_epoch()?.try_into()?,
+        })
+    }
+}
+
+impl TryFrom<SerializedAuthAccount> for AuthAccount {
+    type Error = anyhow::Error;
+
+    fn try_from(account: SerializedAuthAccount) -> anyhow::Result<Self> {
+        Ok(Self {
+            email: account.email,
+            password_hash: account.password_hash,
+            magic_link_token_hash: account.magic_link_token_hash,
+            magic_link_expires_at: account
+                .magic_link_expires_at_ms
+                .map(|ms| anyhow::Ok(UnixTimestamp::from_millis(ms.try_into()?)))
+                .transpose()?,
+            created_at: UnixTimestamp::from_millis(account.created_at_ms.try_into()?),
+        })
+    }
+}
+
+codegen_convex_serialization!(AuthAccount, SerializedAuthAccount);
+
+/// A login to an [`AuthAccount`]. Session tokens name the session they're for,
+/// so deleting it logs the token out.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct AuthSession {
+    /// ID of the account's `_auth_accounts` document.
+    pub account_id: String,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(
+            strategy = "(0..=i64::MAX as u64 / 1_000_000).prop_map(UnixTimestamp::from_millis)"
+        )
+    )]
+    pub expires_at: UnixTimestamp,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedAuthSession {
+    account_id: String,
+    expires_at_ms: i64,
+}
+
+impl TryFrom<AuthSession> for SerializedAuthSession {
+    type Error = anyhow::Error;
+
+    fn try_from(session: AuthSession) -> anyhow::Result<Self> {
+        Ok(Self {
+            account_id: session.account_id,
+            expires_at_ms: session.expires_at.as_ms_since_epoch()?.try_into()?,
+        })
+    }
+}
+
+impl TryFrom<SerializedAuthSession> for AuthSession {
+    type Error = anyhow::Error;
+
+    fn try_from(session: SerializedAuthSession) -> anyhow::Result<Self> {
+        Ok(Self {
+            account_id: session.account_id,
+            expires_at: UnixTimestamp::from_millis(session.expires_at_ms.try_into()?),
+        })
+    }
+}
+
+codegen_convex_serialization!(AuthSession, SerializedAuthSession);
@@ -0,0 +1,209 @@
+//! Per-function overrides for the deployment's execution timeouts and heap
+//! limits, set by admins and applied when a function's arguments are
+//! validated.
+use std::sync::LazyLock;
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+        CREATION_TIME_FIELD_PATH,
+    },
+    query::{
+        IndexRange,
+        IndexRangeExpression,
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+    types::IndexName,
+};
+use database::{
+    defaults::system_index,
+    require_admin_or_system,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use value::{
+    ConvexValue,
+    FieldPath,
+    TableName,
+    TableNamespace,
+};
+
+use self::types::FunctionLimits;
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+pub static FUNCTION_LIMITS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_function_limits"
+        .parse()
+        .expect("Invalid built-in function_limits table")
+});
+
+pub static FUNCTION_LIMITS_INDEX_BY_FUNCTION: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&FUNCTION_LIMITS_TABLE, "by_function"));
+
+static FUNCTION_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "function".parse().expect("invalid function field"));
+
+pub struct FunctionLimitsTable;
+impl SystemTable for FunctionLimitsTable {
+    fn table_name(&self) -> &'static TableName {
+        &FUNCTION_LIMITS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![SystemIndex {
+            name: FUNCTION_LIMITS_INDEX_BY_FUNCTION.clone(),
+            fields: vec![FUNCTION_FIELD.clone(), CREATION_TIME_FIELD_PATH.clone()]
+                .try_into()
+                .unwrap(),
+        }]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<FunctionLimits>::try_from(document).map(|_| ())
+    }
+}
+
+pub struct FunctionLimitsModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> FunctionLimitsModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    /// All the overrides, ordered by function.
+    pub async fn list(&mut self) -> anyhow::Result<Vec<ParsedDocument<FunctionLimits>>> {
+        require_admin_or_system(self.tx.identity(), "list_function_limits")?;
+        let index_range = IndexRange {
+            index_name: FUNCTION_LIMITS_INDEX_BY_FUNCTION.clone(),
+            range: vec![],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        let mut limits = vec![];
+        while let Some(document) = query_stream.next(self.tx, None).await? {
+            limits.push(document.try_into()?);
+        }
+        Ok(limits)
+    }
+
+    /// The overrides for `function`, which is read while validating every
+    /// call so it doesn't check the identity.
+    pub async fn get(
+        &mut self,
+        function: &str,
+    ) -> anyhow::Result<Option<ParsedDocument<FunctionLimits>>> {
+        let index_range = IndexRange {
+            index_name: FUNCTION_LIMITS_INDEX_BY_FUNCTION.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                FUNCTION_FIELD.clone(),
+                ConvexValue::try_from(function.to_string())?.into(),
+            )],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        query_stream
+            .expect_at_most_one(self.tx)
+            .await?
+            .map(ParsedDocument::try_from)
+            .transpose()
+    }
+
+    /// Replaces the overrides for `limits.function`.
+    pub async fn set(&mut self, limits: FunctionLimits) -> anyhow::Result<()> {
+        require_admin_or_system(self.tx.identity(), "set_function_limits")?;
+        match self.get(&limits.function).await? {
+            Some(existing) => {
+                SystemMetadataModel::new_global(self.tx)
+                    .replace(existing.id(), limits.try_into()?)
+                    .await?;
+            },
+            None => {
+                SystemMetadataModel::new_global(self.tx)
+                    .insert(&FUNCTION_LIMITS_TABLE, limits.try_into()?)
+                    .await?;
+            },
+        }
+        Ok(())
+    }
+
+    /// Removes the overrides for `function`, so it gets the defaults again.
+    pub async fn delete(&mut self, function: &str) -> anyhow::Result<()> {
+        require_admin_or_system(self.tx.identity(), "delete_function_limits")?;
+        if let Some(existing) = self.get(function).await? {
+            SystemMetadataModel::new_global(self.tx)
+                .delete(existing.id())
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use database::test_helpers::DbFixtures;
+    use keybroker::Identity;
+    use runtime::testing::TestRuntime;
+
+    use super::{
+        types::FunctionLimits,
+        FunctionLimitsModel,
+    };
+    use crate::test_helpers::DbFixturesWithModel;
+
+    #[convex_macro::test_runtime]
+    async fn test_function_limits(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin(Identity::system()).await?;
+        let mut model = FunctionLimitsModel::new(&mut tx);
+        let heavy = FunctionLimits::new(
+            "actions:heavy".to_string(),
+            Some(Duration::from_secs(300)),
+            None,
+        )?;
+        model.set(heavy.clone()).await?;
+        let heavier = FunctionLimits {
+            user_timeout: Some(Duration::from_secs(600)),
+            ..heavy
+        };
+        model.set(heavier.clone()).await?;
+        db.commit(tx).await?;
+
+        let mut tx = db.begin(Identity::system()).await?;
+        let mut model = FunctionLimitsModel::new(&mut tx);
+        let listed: Vec<_> = model
+            .list()
+            .await?
+            .into_iter()
+            .map(|limits| limits.into_value())
+            .collect();
+        assert_eq!(listed, vec![heavier]);
+        model.delete("actions:heavy").await?;
+        assert!(model.get("actions:heavy").await?.is_none());
+
+        let mut tx = db.begin(Identity::Unknown).await?;
+        let light = FunctionLimits::new("actions:light".to_string(), None, None)?;
+        assert!(FunctionLimitsModel::new(&mut tx).set(light).await.is_err());
+        Ok(())
+    }
+}
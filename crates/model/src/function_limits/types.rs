@@ -0,0 +1,127 @@
+use std::{
+    cmp,
+    time::Duration,
+};
+
+use common::knobs::{
+    ACTION_MAX_USER_TIMEOUT,
+    DATABASE_UDF_MAX_USER_TIMEOUT,
+    ISOLATE_MAX_FUNCTION_HEAP_SIZE,
+    ISOLATE_MAX_USER_HEAP_SIZE,
+};
+use errors::ErrorMetadata;
+#[cfg(any(test, feature = "testing"))]
+use proptest::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use value::codegen_convex_serialization;
+
+/// Execution limits for a single function that override the deployment's
+/// defaults, so one known-heavy function can run longer or use more memory
+/// without loosening the limits for every other function.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct FunctionLimits {
+    /// The function's path in the root component, like `actions:heavy`.
+    pub function: String,
+    /// Replaces the user timeout for the function's type. Queries and
+    /// mutations are still capped at `DATABASE_UDF_MAX_USER_TIMEOUT` and
+    /// actions at `ACTION_MAX_USER_TIMEOUT`.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(
+            strategy = "proptest::option::of((1..=600_000u64).prop_map(Duration::from_millis))"
+        )
+    )]
+    pub user_timeout: Option<Duration>,
+    /// Replaces `ISOLATE_MAX_USER_HEAP_SIZE` for functions running in V8.
+    /// Node actions aren't affected.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "proptest::option::of(0..=i64::MAX as u64)")
+    )]
+    pub max_user_heap_size: Option<u64>,
+}
+
+fn invalid_function_limits(msg: String) -> ErrorMetadata {
+    ErrorMetadata::bad_request("InvalidFunctionLimits", msg)
+}
+
+impl FunctionLimits {
+    pub fn new(
+        function: String,
+        user_timeout: Option<Duration>,
+        max_user_heap_size: Option<u64>,
+    ) -> anyhow::Result<Self> {
+        if let Some(user_timeout) = user_timeout {
+            let max_user_timeout =
+                cmp::max(*ACTION_MAX_USER_TIMEOUT, *DATABASE_UDF_MAX_USER_TIMEOUT);
+            anyhow::ensure!(
+                !user_timeout.is_zero() && user_timeout <= max_user_timeout,
+                invalid_function_limits(format!(
+                    "A function's timeout must be between 1 and {} milliseconds.",
+                    max_user_timeout.as_millis()
+                ))
+            );
+        }
+        if let Some(max_user_heap_size) = max_user_heap_size {
+            // The heap limit can only be raised while a function runs, so
+            // lower limits can't be enforced.
+            anyhow::ensure!(
+                max_user_heap_size >= *ISOLATE_MAX_USER_HEAP_SIZE as u64
+                    && max_user_heap_size <= *ISOLATE_MAX_FUNCTION_HEAP_SIZE as u64,
+                invalid_function_limits(format!(
+                    "A function's heap size must be between {} and {} bytes.",
+                    *ISOLATE_MAX_USER_HEAP_SIZE, *ISOLATE_MAX_FUNCTION_HEAP_SIZE
+                ))
+            );
+        }
+        Ok(Self {
+            function,
+            user_timeout,
+            max_user_heap_size,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedFunctionLimits {
+    function: String,
+    user_timeout_ms: Option<i64>,
+    max_user_heap_size: Option<i64>,
+}
+
+impl TryFrom<FunctionLimits> for SerializedFunctionLimits {
+    type Error = anyhow::Error;
+
+    fn try_from(limits: FunctionLimits) -> anyhow::Result<Self> {
+        Ok(Self {
+            function: limits.function,
+            user_timeout_ms: limits
+                .user_timeout
+                .map(|timeout| i64::try_from(timeout.as_millis()))
+                .transpose()?,
+            max_user_heap_size: limits.max_user_heap_size.map(i64::try_from).transpose()?,
+        })
+    }
+}
+
+impl TryFrom<SerializedFunctionLimits> for FunctionLimits {
+    type Error = anyhow::Error;
+
+    fn try_from(limits: SerializedFunctionLimits) -> anyhow::Result<Self> {
+        Ok(Self {
+            function: limits.function,
+            user_timeout: limits
+                .user_timeout_ms
+                .map(|ms| anyhow::Ok(Duration::from_millis(u64::try_from(ms)?)))
+                .transpose()?,
+            max_user_heap_size: limits.max_user_heap_size.map(u64::try_from).transpose()?,
+        })
+    }
+}
+
+codegen_convex_serialization!(FunctionLimits, SerializedFunctionLimits);
@@ -71,6 +71,11 @@ pub use database::defaults::{
     SystemTable,
 };
 use database::{
+    defaults::bootstrap_system_tables,
+    materialized_aggregates::{
+        MaterializedAggregateEntriesTable,
+        MaterializedAggregatesTable,
+    },
     ComponentDefinitionsTable,
     ComponentsTable,
     Database,
@@ -106,41 +111,132 @@ use value::{
 };
 
 use crate::{
+    action_checkpoints::ActionCheckpointsTable,
+    admin_keys::{
+        AdminKeySecretsTable,
+        AdminKeysTable,
+    },
+    api_keys::ApiKeysTable,
+    archival::{
+        ArchivalPoliciesTable,
+        ArchivedDocumentsTable,
+        ArchivedSegmentsTable,
+    },
+    audit_log::AuditLogTable,
     auth::AuthTable,
     backend_state::BackendStateModel,
+    backup_schedule::BackupScheduleTable,
+    cors_config::CorsConfigTable,
+    counters::{
+        CounterShardsTable,
+        CountersTable,
+    },
     cron_jobs::{
         CronJobLogsTable,
         CronJobsTable,
     },
+    custom_domains::CustomDomainsTable,
     deployment_audit_log::DeploymentAuditLogsTable,
+    document_access::DocumentAccessTable,
     environment_variables::EnvironmentVariablesTable,
+    export_watermarks::{
+        ExportWatermarkConfigsTable,
+        ExportWatermarksTable,
+    },
     exports::ExportsTable,
     external_packages::ExternalPackagesTable,
-    file_storage::FileStorageTable,
+    file_storage::{
+        transforms::FileStorageTransformsTable,
+        uploads::FileStorageUploadsTable,
+        FileStorageTable,
+    },
+    first_party_auth::{
+        AuthAccountsTable,
+        AuthSessionsTable,
+    },
+    function_limits::FunctionLimitsTable,
+    log_sinks::LogSinksTable,
+    metrics_rollups::MetricsRollupsTable,
     modules::ModulesTable,
+    network_acl::NetworkAclConfigTable,
+    pii_reports::PiiReportsTable,
+    queues::{
+        QueueMessagesTable,
+        QueuesTable,
+    },
+    rate_limit_config::RateLimitConfigTable,
+    rate_limiter::RateLimiterShardsTable,
+    replication::ReplicationStateTable,
+    scheduled_job_dead_letters::ScheduledJobsDeadLetterTable,
     scheduled_jobs::ScheduledJobsTable,
+    scheduler_backoff_policy::SchedulerBackoffPolicyTable,
     session_requests::SessionRequestsTable,
-    snapshot_imports::SnapshotImportsTable,
+    slow_executions::SlowExecutionsTable,
+    snapshot_imports::{
+        SnapshotImportUploadsTable,
+        SnapshotImportsTable,
+    },
     source_packages::SourcePackagesTable,
+    table_compactions::TableCompactionsTable,
     udf_config::UdfConfigTable,
+    usage_meters::UsageMetersTable,
+    usage_quotas::UsageQuotasTable,
+    webauthn::{
+        WebAuthnChallengesTable,
+        WebAuthnCredentialsTable,
+    },
+    workflows::{
+        WorkflowDefinitionsTable,
+        WorkflowsTable,
+    },
 };
 
+pub mod action_checkpoints;
+pub mod admin_keys;
+pub mod api_keys;
+pub mod archival;
+pub mod audit_log;
 pub mod auth;
 pub mod backend_state;
+pub mod backup_schedule;
 pub mod components;
 pub mod config;
+pub mod cors_config;
+pub mod counters;
 pub mod cron_jobs;
+pub mod custom_domains;
 pub mod deployment_audit_log;
+pub mod document_access;
 pub mod environment_variables;
+pub mod export_watermarks;
 pub mod exports;
 pub mod external_packages;
 pub mod file_storage;
+pub mod first_party_auth;
+pub mod function_limits;
+pub mod log_sinks;
+pub mod metrics_rollups;
 pub mod modules;
+pub mod network_acl;
+pub mod operations;
+pub mod pii_reports;
+pub mod queues;
+pub mod rate_limit_config;
+pub mod rate_limiter;
+pub mod replication;
+pub mod scheduled_job_dead_letters;
 pub mod scheduled_jobs;
+pub mod scheduler_backoff_policy;
 pub mod session_requests;
+pub mod slow_executions;
 pub mod snapshot_imports;
 pub mod source_packages;
+pub mod table_compactions;
 pub mod udf_config;
+pub mod usage_meters;
+pub mod usage_quotas;
+pub mod webauthn;
+pub mod workflows;
 
 #[cfg(any(test, feature = "testing"))]
 pub mod test_helpers;
@@ -173,9 +269,52 @@ enum DefaultTableNumber {
     ComponentDefinitionsTable = 31,
     ComponentsTable = 32,
     FunctionHandlesTable = 33,
+    SnapshotImportUploads = 34,
+    ReplicationState = 35,
+    BackupSchedule = 36,
+    FileStorageUploads = 37,
+    FileStorageTransforms = 38,
+    Counters = 39,
+    CounterShards = 40,
+    MetricsRollups = 41,
+    TableCompactions = 42,
+    CorsConfig = 43,
+    CustomDomains = 44,
+    WebAuthnChallenges = 45,
+    WebAuthnCredentials = 46,
+    RateLimitConfig = 47,
+    PiiReports = 48,
+    RateLimiterShards = 49,
+    NetworkAclConfig = 50,
+    ExportWatermarkConfigs = 51,
+    ExportWatermarks = 52,
+    AuditLog = 53,
+    AdminKeys = 54,
+    AdminKeySecrets = 55,
+    DocumentAccess = 56,
+    ArchivalPolicies = 57,
+    ArchivedSegments = 58,
+    ArchivedDocuments = 59,
+    AuthAccounts = 60,
+    AuthSessions = 61,
+    ApiKeys = 62,
+    LogSinks = 63,
+    SlowExecutions = 64,
+    UsageMeters = 65,
+    UsageQuotas = 66,
+    FunctionLimits = 67,
+    ActionCheckpoints = 68,
+    WorkflowDefinitions = 69,
+    Workflows = 70,
+    ScheduledJobsDeadLetter = 71,
+    SchedulerBackoffPolicy = 72,
+    Queues = 73,
+    QueueMessages = 74,
+    MaterializedAggregates = 75,
+    MaterializedAggregateEntries = 76,
     // Keep this number and your user name up to date. The number makes it easy to know
     // what to use next. The username on the same line detects merge conflicts
-    // Next Number - 34 - sujayakar
+    // Next Number - 77 - fadlee
 }
 
 impl From<DefaultTableNumber> for TableNumber {
@@ -211,6 +350,49 @@ impl From<DefaultTableNumber> for &'static dyn SystemTable {
             DefaultTableNumber::ComponentDefinitionsTable => &ComponentDefinitionsTable,
             DefaultTableNumber::ComponentsTable => &ComponentsTable,
             DefaultTableNumber::FunctionHandlesTable => &FunctionHandlesTable,
+            DefaultTableNumber::SnapshotImportUploads => &SnapshotImportUploadsTable,
+            DefaultTableNumber::ReplicationState => &ReplicationStateTable,
+            DefaultTableNumber::BackupSchedule => &BackupScheduleTable,
+            DefaultTableNumber::FileStorageUploads => &FileStorageUploadsTable,
+            DefaultTableNumber::FileStorageTransforms => &FileStorageTransformsTable,
+            DefaultTableNumber::Counters => &CountersTable,
+            DefaultTableNumber::CounterShards => &CounterShardsTable,
+            DefaultTableNumber::MetricsRollups => &MetricsRollupsTable,
+            DefaultTableNumber::TableCompactions => &TableCompactionsTable,
+            DefaultTableNumber::CorsConfig => &CorsConfigTable,
+            DefaultTableNumber::CustomDomains => &CustomDomainsTable,
+            DefaultTableNumber::WebAuthnChallenges => &WebAuthnChallengesTable,
+            DefaultTableNumber::WebAuthnCredentials => &WebAuthnCredentialsTable,
+            DefaultTableNumber::RateLimitConfig => &RateLimitConfigTable,
+            DefaultTableNumber::PiiReports => &PiiReportsTable,
+            DefaultTableNumber::RateLimiterShards => &RateLimiterShardsTable,
+            DefaultTableNumber::NetworkAclConfig => &NetworkAclConfigTable,
+            DefaultTableNumber::ExportWatermarkConfigs => &ExportWatermarkConfigsTable,
+            DefaultTableNumber::ExportWatermarks => &ExportWatermarksTable,
+            DefaultTableNumber::AuditLog => &AuditLogTable,
+            DefaultTableNumber::AdminKeys => &AdminKeysTable,
+            DefaultTableNumber::AdminKeySecrets => &AdminKeySecretsTable,
+            DefaultTableNumber::DocumentAccess => &DocumentAccessTable,
+            DefaultTableNumber::ArchivalPolicies => &ArchivalPoliciesTable,
+            DefaultTableNumber::ArchivedSegments => &ArchivedSegmentsTable,
+            DefaultTableNumber::ArchivedDocuments => &ArchivedDocumentsTable,
+            DefaultTableNumber::AuthAccounts => &AuthAccountsTable,
+            DefaultTableNumber::AuthSessions => &AuthSessionsTable,
+            DefaultTableNumber::ApiKeys => &ApiKeysTable,
+            DefaultTableNumber::LogSinks => &LogSinksTable,
+            DefaultTableNumber::SlowExecutions => &SlowExecutionsTable,
+            DefaultTableNumber::UsageMeters => &UsageMetersTable,
+            DefaultTableNumber::UsageQuotas => &UsageQuotasTable,
+            DefaultTableNumber::FunctionLimits => &FunctionLimitsTable,
+            DefaultTableNumber::ActionCheckpoints => &ActionCheckpointsTable,
+            DefaultTableNumber::WorkflowDefinitions => &WorkflowDefinitionsTable,
+            DefaultTableNumber::Workflows => &WorkflowsTable,
+            DefaultTableNumber::ScheduledJobsDeadLetter => &ScheduledJobsDeadLetterTable,
+            DefaultTableNumber::SchedulerBackoffPolicy => &SchedulerBackoffPolicyTable,
+            DefaultTableNumber::Queues => &QueuesTable,
+            DefaultTableNumber::QueueMessages => &QueueMessagesTable,
+            DefaultTableNumber::MaterializedAggregates => &MaterializedAggregatesTable,
+            DefaultTableNumber::MaterializedAggregateEntries => &MaterializedAggregateEntriesTable,
         }
     }
 }
@@ -393,7 +575,50 @@ pub fn app_system_tables() -> Vec<&'static dyn SystemTable> {
         &BackendStateTable,
         &ExportsTable,
         &SnapshotImportsTable,
+        &SnapshotImportUploadsTable,
         &FunctionHandlesTable,
+        &ReplicationStateTable,
+        &BackupScheduleTable,
+        &FileStorageUploadsTable,
+        &FileStorageTransformsTable,
+        &CountersTable,
+        &CounterShardsTable,
+        &MetricsRollupsTable,
+        &TableCompactionsTable,
+        &CorsConfigTable,
+        &CustomDomainsTable,
+        &WebAuthnChallengesTable,
+        &WebAuthnCredentialsTable,
+        &RateLimitConfigTable,
+        &PiiReportsTable,
+        &RateLimiterShardsTable,
+        &NetworkAclConfigTable,
+        &ExportWatermarkConfigsTable,
+        &ExportWatermarksTable,
+        &AuditLogTable,
+        &AdminKeysTable,
+        &AdminKeySecretsTable,
+        &DocumentAccessTable,
+        &ArchivalPoliciesTable,
+        &ArchivedSegmentsTable,
+        &ArchivedDocumentsTable,
+        &AuthAccountsTable,
+        &AuthSessionsTable,
+        &ApiKeysTable,
+        &LogSinksTable,
+        &SlowExecutionsTable,
+        &UsageMetersTable,
+        &UsageQuotasTable,
+        &FunctionLimitsTable,
+        &ActionCheckpointsTable,
+        &WorkflowDefinitionsTable,
+        &WorkflowsTable,
+        &ScheduledJobsDeadLetterTable,
+        &SchedulerBackoffPolicyTable,
+        &QueuesTable,
+        &QueueMessagesTable,
+        &MaterializedAggregatesTable,
+        &MaterializedAggregateEntriesTable,
     ];
     system_tables.extend(component_system_tables());
     system_tables
@@ -415,7 +640,10 @@ pub fn component_system_tables() -> Vec<&'static dyn SystemTable> {
 
 pub fn virtual_system_mapping() -> VirtualSystemMapping {
     let mut mapping = VirtualSystemMapping::default();
-    for table in app_system_tables() {
+    for table in bootstrap_system_tables()
+        .into_iter()
+        .chain(app_system_tables())
+    {
         if let Some((virtual_table_name, virtual_indexes, mapper)) = table.virtual_table() {
             mapping.add_table(
                 virtual_table_name,
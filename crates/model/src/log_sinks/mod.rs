@@ -0,0 +1,206 @@
+use std::sync::LazyLock;
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    query::{
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+};
+use database::{
+    require_admin_or_system,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use value::{
+    TableName,
+    TableNamespace,
+};
+
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+use types::{
+    LogSink,
+    SinkConfig,
+    SinkStatus,
+    SinkType,
+};
+
+pub static LOG_SINKS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_log_sinks"
+        .parse()
+        .expect("Invalid built-in log_sinks table")
+});
+
+pub struct LogSinksTable;
+impl SystemTable for LogSinksTable {
+    fn table_name(&self) -> &'static TableName {
+        &LOG_SINKS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<LogSink>::try_from(document).map(|_| ())
+    }
+}
+
+/// The deployment's log streaming sinks, which has at most one row per
+/// [`SinkType`].
+pub struct LogSinksModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> LogSinksModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    pub async fn list(&mut self) -> anyhow::Result<Vec<ParsedDocument<LogSink>>> {
+        require_admin_or_system(self.tx.identity(), "list_log_sinks")?;
+        let query = Query::full_table_scan(LOG_SINKS_TABLE.clone(), Order::Asc);
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        let mut sinks = vec![];
+        while let Some(document) = query_stream.next(self.tx, None).await? {
+            sinks.push(document.try_into()?);
+        }
+        Ok(sinks)
+    }
+
+    pub async fn get(
+        &mut self,
+        sink_type: SinkType,
+    ) -> anyhow::Result<Option<ParsedDocument<LogSink>>> {
+        Ok(self
+            .list()
+            .await?
+            .into_iter()
+            .find(|sink| sink.config.sink_type() == sink_type))
+    }
+
+    /// Adds a sink, replacing any existing sink of the same type. The sink is
+    /// pending until the log manager delivers logs to it.
+    pub async fn set(&mut self, config: SinkConfig) -> anyhow::Result<()> {
+        require_admin_or_system(self.tx.identity(), "set_log_sink")?;
+        let sink = LogSink {
+            config,
+            status: SinkStatus::Pending,
+        };
+        match self.get(sink.config.sink_type()).await? {
+            Some(existing) => {
+                SystemMetadataModel::new_global(self.tx)
+                    .replace(existing.id(), sink.try_into()?)
+                    .await?;
+            },
+            None => {
+                SystemMetadataModel::new_global(self.tx)
+                    .insert(&LOG_SINKS_TABLE, sink.try_into()?)
+                    .await?;
+            },
+        }
+        Ok(())
+    }
+
+    /// Records whether logs were delivered to the sink with `config`. Does
+    /// nothing if the sink has since been removed or reconfigured.
+    pub async fn set_status(
+        &mut self,
+        config: &SinkConfig,
+        status: SinkStatus,
+    ) -> anyhow::Result<()> {
+        require_admin_or_system(self.tx.identity(), "set_log_sink_status")?;
+        let Some(existing) = self.get(config.sink_type()).await? else {
+            return Ok(());
+        };
+        if existing.config != *config || existing.status == status {
+            return Ok(());
+        }
+        let (id, mut sink) = existing.into_id_and_value();
+        sink.status = status;
+        SystemMetadataModel::new_global(self.tx)
+            .replace(id, sink.try_into()?)
+            .await?;
+        Ok(())
+    }
+
+    /// Stops streaming logs to the sink of type `sink_type`. Returns whether
+    /// there was one.
+    pub async fn remove(&mut self, sink_type: SinkType) -> anyhow::Result<bool> {
+        require_admin_or_system(self.tx.identity(), "remove_log_sink")?;
+        let Some(existing) = self.get(sink_type).await? else {
+            return Ok(false);
+        };
+        SystemMetadataModel::new_global(self.tx)
+            .delete(existing.id())
+            .await?;
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use database::test_helpers::DbFixtures;
+    use keybroker::Identity;
+    use runtime::testing::TestRuntime;
+
+    use super::{
+        types::{
+            SinkConfig,
+            SinkStatus,
+            SinkType,
+            WebhookConfig,
+        },
+        LogSinksModel,
+    };
+    use crate::test_helpers::DbFixturesWithModel;
+
+    fn webhook(url: &str) -> SinkConfig {
+        SinkConfig::Webhook(WebhookConfig {
+            url: url.to_string(),
+        })
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_log_sinks(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin(Identity::system()).await?;
+        LogSinksModel::new(&mut tx)
+            .set(webhook("https://a.example.com"))
+            .await?;
+        LogSinksModel::new(&mut tx)
+            .set_status(&webhook("https://a.example.com"), SinkStatus::Active)
+            .await?;
+        // Reconfiguring the sink replaces it, and it's pending again.
+        LogSinksModel::new(&mut tx)
+            .set(webhook("https://b.example.com"))
+            .await?;
+        // Status updates for the old config are ignored.
+        LogSinksModel::new(&mut tx)
+            .set_status(&webhook("https://a.example.com"), SinkStatus::Active)
+            .await?;
+        let sinks = LogSinksModel::new(&mut tx).list().await?;
+        assert_eq!(sinks.len(), 1);
+        assert_eq!(sinks[0].config, webhook("https://b.example.com"));
+        assert_eq!(sinks[0].status, SinkStatus::Pending);
+
+        let mut model = LogSinksModel::new(&mut tx);
+        assert!(model.remove(SinkType::Webhook).await?);
+        assert!(!model.remove(SinkType::Webhook).await?);
+
+        let mut tx = db.begin(Identity::Unknown).await?;
+        assert!(LogSinksModel::new(&mut tx).list().await.is_err());
+        Ok(())
+    }
+}
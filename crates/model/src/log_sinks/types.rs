@@ -0,0 +1,308 @@
+use std::{
+    collections::BTreeMap,
+    fmt,
+    str::FromStr,
+};
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use value::codegen_convex_serialization;
+
+/// An external destination that function logs and system events are streamed
+/// to. A deployment has at most one sink of each [`SinkType`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct LogSink {
+    pub config: SinkConfig,
+    pub status: SinkStatus,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub enum SinkStatus {
+    /// The sink hasn't delivered any logs yet.
+    Pending,
+    Active,
+    /// The last batch of logs couldn't be delivered, even after retrying.
+    Failed {
+        reason: String,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub enum SinkType {
+    Datadog,
+    Axiom,
+    Webhook,
+    Syslog,
+}
+
+impl SinkType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Datadog => "datadog",
+            Self::Axiom => "axiom",
+            Self::Webhook => "webhook",
+            Self::Syslog => "syslog",
+        }
+    }
+}
+
+impl fmt::Display for SinkType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for SinkType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "datadog" => Ok(Self::Datadog),
+            "axiom" => Ok(Self::Axiom),
+            "webhook" => Ok(Self::Webhook),
+            "syslog" => Ok(Self::Syslog),
+            _ => anyhow::bail!("Unknown log sink type {s}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub enum SinkConfig {
+    Datadog(DatadogConfig),
+    Axiom(AxiomConfig),
+    Webhook(WebhookConfig),
+    Syslog(SyslogConfig),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct DatadogConfig {
+    /// The Datadog site the account is on, like "datadoghq.com" or
+    /// "datadoghq.eu".
+    pub site: String,
+    pub api_key: String,
+    /// Tags added to every log, like "env:prod".
+    pub tags: Vec<String>,
+    /// The `service` attribute of every log. Defaults to the deployment name.
+    pub service: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct AxiomConfig {
+    pub api_token: String,
+    pub dataset_name: String,
+    /// Attributes added to every log.
+    pub attributes: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct WebhookConfig {
+    /// Batches of logs are POSTed here as a JSON array.
+    pub url: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct SyslogConfig {
+    /// The `host:port` of the syslog server.
+    pub address: String,
+    pub protocol: SyslogProtocol,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+#[serde(rename_all = "camelCase")]
+pub enum SyslogProtocol {
+    Udp,
+    /// Messages are framed with octet counting, as in RFC 6587.
+    Tcp,
+}
+
+const REDACTED: &str = "***";
+
+impl SinkConfig {
+    pub fn sink_type(&self) -> SinkType {
+        match self {
+            Self::Datadog(_) => SinkType::Datadog,
+            Self::Axiom(_) => SinkType::Axiom,
+            Self::Webhook(_) => SinkType::Webhook,
+            Self::Syslog(_) => SinkType::Syslog,
+        }
+    }
+
+    /// The config with its credentials replaced, for showing to admins.
+    pub fn redacted(mut self) -> Self {
+        match &mut self {
+            Self::Datadog(config) => config.api_key = REDACTED.to_string(),
+            Self::Axiom(config) => config.api_token = REDACTED.to_string(),
+            Self::Webhook(_) | Self::Syslog(_) => {},
+        }
+        self
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SerializedSinkConfig {
+    #[serde(rename_all = "camelCase")]
+    Datadog {
+        site: String,
+        api_key: String,
+        #[serde(default)]
+        tags: Vec<String>,
+        service: Option<String>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Axiom {
+        api_token: String,
+        dataset_name: String,
+        /// Attribute names needn't be valid field names, so they're stored as
+        /// a list of pairs.
+        #[serde(default)]
+        attributes: Vec<SerializedAttribute>,
+    },
+    #[serde(rename_all = "camelCase")]
+    Webhook { url: String },
+    #[serde(rename_all = "camelCase")]
+    Syslog {
+        address: String,
+        protocol: SyslogProtocol,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SerializedAttribute {
+    key: String,
+    value: String,
+}
+
+impl From<SinkConfig> for SerializedSinkConfig {
+    fn from(config: SinkConfig) -> Self {
+        match config {
+            SinkConfig::Datadog(DatadogConfig {
+                site,
+                api_key,
+                tags,
+                service,
+            }) => Self::Datadog {
+                site,
+                api_key,
+                tags,
+                service,
+            },
+            SinkConfig::Axiom(AxiomConfig {
+                api_token,
+                dataset_name,
+                attributes,
+            }) => Self::Axiom {
+                api_token,
+                dataset_name,
+                attributes: attributes
+                    .into_iter()
+                    .map(|(key, value)| SerializedAttribute { key, value })
+                    .collect(),
+            },
+            SinkConfig::Webhook(WebhookConfig { url }) => Self::Webhook { url },
+            SinkConfig::Syslog(SyslogConfig { address, protocol }) => {
+                Self::Syslog { address, protocol }
+            },
+        }
+    }
+}
+
+impl From<SerializedSinkConfig> for SinkConfig {
+    fn from(config: SerializedSinkConfig) -> Self {
+        match config {
+            SerializedSinkConfig::Datadog {
+                site,
+                api_key,
+                tags,
+                service,
+            } => Self::Datadog(DatadogConfig {
+                site,
+                api_key,
+                tags,
+                service,
+            }),
+            SerializedSinkConfig::Axiom {
+                api_token,
+                dataset_name,
+                attributes,
+            } => Self::Axiom(AxiomConfig {
+                api_token,
+                dataset_name,
+                attributes: attributes
+                    .into_iter()
+                    .map(|SerializedAttribute { key, value }| (key, value))
+                    .collect(),
+            }),
+            SerializedSinkConfig::Webhook { url } => Self::Webhook(WebhookConfig { url }),
+            SerializedSinkConfig::Syslog { address, protocol } => {
+                Self::Syslog(SyslogConfig { address, protocol })
+            },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "camelCase")]
+pub enum SerializedSinkStatus {
+    Pending,
+    Active,
+    Failed { reason: String },
+}
+
+impl From<SinkStatus> for SerializedSinkStatus {
+    fn from(status: SinkStatus) -> Self {
+        match status {
+            SinkStatus::Pending => Self::Pending,
+            SinkStatus::Active => Self::Active,
+            SinkStatus::Failed { reason } => Self::Failed { reason },
+        }
+    }
+}
+
+impl From<SerializedSinkStatus> for SinkStatus {
+    fn from(status: SerializedSinkStatus) -> Self {
+        match status {
+            SerializedSinkStatus::Pending => Self::Pending,
+            SerializedSinkStatus::Active => Self::Active,
+            SerializedSinkStatus::Failed { reason } => Self::Failed { reason },
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedLogSink {
+    config: SerializedSinkConfig,
+    status: SerializedSinkStatus,
+}
+
+impl From<LogSink> for SerializedLogSink {
+    fn from(sink: LogSink) -> Self {
+        Self {
+            config: sink.config.into(),
+            status: sink.status.into(),
+        }
+    }
+}
+
+impl From<SerializedLogSink> for LogSink {
+    fn from(sink: SerializedLogSink) -> Self {
+        Self {
+            config: sink.config.into(),
+            status: sink.status.into(),
+        }
+    }
+}
+
+codegen_convex_serialization!(LogSink, SerializedLogSink);
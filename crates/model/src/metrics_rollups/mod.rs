@@ -0,0 +1,277 @@
+//! Hourly and daily rollups of per-function and per-table metrics, kept for a
+//! retention period so deployments have trend data without an external
+//! metrics system. Functions can read them from the `_metrics` system table.
+use std::{
+    collections::BTreeMap,
+    sync::{
+        Arc,
+        LazyLock,
+    },
+};
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+        CREATION_TIME_FIELD_PATH,
+    },
+    query::{
+        IndexRange,
+        IndexRangeExpression,
+        Order,
+        Query,
+    },
+    runtime::{
+        Runtime,
+        UnixTimestamp,
+    },
+    types::{
+        GenericIndexName,
+        IndexName,
+    },
+    virtual_system_mapping::VirtualSystemDocMapper,
+};
+use database::{
+    defaults::system_index,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use maplit::btreemap;
+use value::{
+    ConvexValue,
+    FieldPath,
+    TableName,
+    TableNamespace,
+};
+
+use self::{
+    types::{
+        MetricsRollup,
+        RollupGranularity,
+    },
+    virtual_table::MetricsRollupDocMapper,
+};
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+pub mod virtual_table;
+
+pub static METRICS_ROLLUPS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_metrics_rollups"
+        .parse()
+        .expect("invalid built-in metrics rollups table")
+});
+pub static METRICS_ROLLUPS_VIRTUAL_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_metrics"
+        .parse()
+        .expect("_metrics is not a valid virtual table name")
+});
+
+static METRICS_ROLLUPS_INDEX_BY_ID: LazyLock<IndexName> =
+    LazyLock::new(|| GenericIndexName::by_id(METRICS_ROLLUPS_TABLE.clone()));
+static METRICS_ROLLUPS_INDEX_BY_CREATION_TIME: LazyLock<IndexName> =
+    LazyLock::new(|| GenericIndexName::by_creation_time(METRICS_ROLLUPS_TABLE.clone()));
+pub static METRICS_ROLLUPS_INDEX_BY_BUCKET: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&METRICS_ROLLUPS_TABLE, "by_bucket"));
+static METRICS_ROLLUPS_VIRTUAL_INDEX_BY_ID: LazyLock<IndexName> =
+    LazyLock::new(|| GenericIndexName::by_id(METRICS_ROLLUPS_VIRTUAL_TABLE.clone()));
+static METRICS_ROLLUPS_VIRTUAL_INDEX_BY_CREATION_TIME: LazyLock<IndexName> =
+    LazyLock::new(|| GenericIndexName::by_creation_time(METRICS_ROLLUPS_VIRTUAL_TABLE.clone()));
+// The virtual documents keep the indexed fields' names, so the virtual index
+// can be served by the system index.
+static METRICS_ROLLUPS_VIRTUAL_INDEX_BY_BUCKET: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&METRICS_ROLLUPS_VIRTUAL_TABLE, "by_bucket"));
+
+static GRANULARITY_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "granularity".parse().expect("invalid granularity field"));
+static BUCKET_START_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "bucketStart".parse().expect("invalid bucketStart field"));
+static KIND_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "kind".parse().expect("invalid kind field"));
+static NAME_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "name".parse().expect("invalid name field"));
+
+pub struct MetricsRollupsTable;
+impl SystemTable for MetricsRollupsTable {
+    fn table_name(&self) -> &'static TableName {
+        &METRICS_ROLLUPS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![SystemIndex {
+            name: METRICS_ROLLUPS_INDEX_BY_BUCKET.clone(),
+            fields: vec![
+                GRANULARITY_FIELD.clone(),
+                BUCKET_START_FIELD.clone(),
+                KIND_FIELD.clone(),
+                NAME_FIELD.clone(),
+                CREATION_TIME_FIELD_PATH.clone(),
+            ]
+            .try_into()
+            .unwrap(),
+        }]
+    }
+
+    fn virtual_table(
+        &self,
+    ) -> Option<(
+        &'static TableName,
+        BTreeMap<IndexName, IndexName>,
+        Arc<dyn VirtualSystemDocMapper>,
+    )> {
+        Some((
+            &METRICS_ROLLUPS_VIRTUAL_TABLE,
+            btreemap! {
+                METRICS_ROLLUPS_VIRTUAL_INDEX_BY_CREATION_TIME.clone() =>
+                    METRICS_ROLLUPS_INDEX_BY_CREATION_TIME.clone(),
+                METRICS_ROLLUPS_VIRTUAL_INDEX_BY_ID.clone() =>
+                    METRICS_ROLLUPS_INDEX_BY_ID.clone(),
+                METRICS_ROLLUPS_VIRTUAL_INDEX_BY_BUCKET.clone() =>
+                    METRICS_ROLLUPS_INDEX_BY_BUCKET.clone(),
+            },
+            Arc::new(MetricsRollupDocMapper),
+        ))
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<MetricsRollup>::try_from(document).map(|_| ())
+    }
+}
+
+fn bucket_start_value(ts: UnixTimestamp) -> anyhow::Result<ConvexValue> {
+    Ok(ConvexValue::from(ts.as_ms_since_epoch()? as f64))
+}
+
+pub struct MetricsRollupModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> MetricsRollupModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    fn bucket_range(
+        &mut self,
+        granularity: RollupGranularity,
+        start: UnixTimestamp,
+        end: UnixTimestamp,
+    ) -> anyhow::Result<ResolvedQuery<RT>> {
+        let index_range = IndexRange {
+            index_name: METRICS_ROLLUPS_INDEX_BY_BUCKET.clone(),
+            range: vec![
+                IndexRangeExpression::Eq(
+                    GRANULARITY_FIELD.clone(),
+                    ConvexValue::try_from(granularity.as_str().to_string())?.into(),
+                ),
+                IndexRangeExpression::Gte(BUCKET_START_FIELD.clone(), bucket_start_value(start)?),
+                IndexRangeExpression::Lt(BUCKET_START_FIELD.clone(), bucket_start_value(end)?),
+            ],
+            order: Order::Asc,
+        };
+        ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )
+    }
+
+    /// Rollups with `granularity` whose buckets start in `[start, end)`.
+    pub async fn list(
+        &mut self,
+        granularity: RollupGranularity,
+        start: UnixTimestamp,
+        end: UnixTimestamp,
+    ) -> anyhow::Result<Vec<ParsedDocument<MetricsRollup>>> {
+        let mut query_stream = self.bucket_range(granularity, start, end)?;
+        let mut rollups = vec![];
+        while let Some(doc) = query_stream.next(self.tx, None).await? {
+            rollups.push(doc.try_into()?);
+        }
+        Ok(rollups)
+    }
+
+    async fn get(
+        &mut self,
+        rollup: &MetricsRollup,
+    ) -> anyhow::Result<Option<ParsedDocument<MetricsRollup>>> {
+        let index_range = IndexRange {
+            index_name: METRICS_ROLLUPS_INDEX_BY_BUCKET.clone(),
+            range: vec![
+                IndexRangeExpression::Eq(
+                    GRANULARITY_FIELD.clone(),
+                    ConvexValue::try_from(rollup.granularity.as_str().to_string())?.into(),
+                ),
+                IndexRangeExpression::Eq(
+                    BUCKET_START_FIELD.clone(),
+                    bucket_start_value(rollup.bucket_start)?.into(),
+                ),
+                IndexRangeExpression::Eq(
+                    KIND_FIELD.clone(),
+                    ConvexValue::try_from(rollup.kind.as_str().to_string())?.into(),
+                ),
+                IndexRangeExpression::Eq(
+                    NAME_FIELD.clone(),
+                    ConvexValue::try_from(rollup.name.clone())?.into(),
+                ),
+            ],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        query_stream
+            .expect_at_most_one(self.tx)
+            .await?
+            .map(ParsedDocument::try_from)
+            .transpose()
+    }
+
+    /// Add `rollup`'s stats to the stored rollup for the same bucket and
+    /// subject, creating it if there isn't one yet.
+    pub async fn merge(&mut self, rollup: MetricsRollup) -> anyhow::Result<()> {
+        match self.get(&rollup).await? {
+            Some(existing) => {
+                let (id, mut existing) = existing.into_id_and_value();
+                existing.stats.merge(&rollup.stats);
+                SystemMetadataModel::new_global(self.tx)
+                    .replace(id, existing.try_into()?)
+                    .await?;
+            },
+            None => {
+                SystemMetadataModel::new_global(self.tx)
+                    .insert(&METRICS_ROLLUPS_TABLE, rollup.try_into()?)
+                    .await?;
+            },
+        }
+        Ok(())
+    }
+
+    /// Delete up to `limit` rollups with `granularity` whose buckets start
+    /// before `cutoff`, returning how many were deleted.
+    pub async fn delete_before(
+        &mut self,
+        granularity: RollupGranularity,
+        cutoff: UnixTimestamp,
+        limit: usize,
+    ) -> anyhow::Result<usize> {
+        let mut query_stream =
+            self.bucket_range(granularity, UnixTimestamp::from_millis(0), cutoff)?;
+        let mut deleted = 0;
+        while deleted < limit
+            && let Some(doc) = query_stream.next(self.tx, None).await?
+        {
+            SystemMetadataModel::new_global(self.tx)
+                .delete(doc.id())
+                .await?;
+            deleted += 1;
+        }
+        Ok(deleted)
+    }
+}
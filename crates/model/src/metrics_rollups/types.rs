@@ -0,0 +1,353 @@
+use std::{
+    fmt,
+    str::FromStr,
+    time::Duration,
+};
+
+use common::runtime::UnixTimestamp;
+#[cfg(any(test, feature = "testing"))]
+use proptest::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use value::codegen_convex_serialization;
+
+/// How long each rollup's bucket is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub enum RollupGranularity {
+    Hour,
+    Day,
+}
+
+impl RollupGranularity {
+    pub fn duration(&self) -> Duration {
+        match self {
+            Self::Hour => Duration::from_secs(60 * 60),
+            Self::Day => Duration::from_secs(24 * 60 * 60),
+        }
+    }
+
+    /// The start of the bucket containing `ts`. Days start at midnight UTC.
+    pub fn bucket_start(&self, ts: UnixTimestamp) -> UnixTimestamp {
+        let width = self.duration().as_secs();
+        UnixTimestamp::from_millis(ts.as_secs() / width * width * 1000)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Hour => "hour",
+            Self::Day => "day",
+        }
+    }
+}
+
+impl FromStr for RollupGranularity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "hour" => Ok(Self::Hour),
+            "day" => Ok(Self::Day),
+            _ => anyhow::bail!("Invalid rollup granularity {s}"),
+        }
+    }
+}
+
+impl fmt::Display for RollupGranularity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// What a rollup's metrics are about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub enum RollupKind {
+    /// Calls to a function, named by its path or HTTP route.
+    Function,
+    /// Reads and writes to a table by functions.
+    Table,
+}
+
+impl RollupKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Function => "function",
+            Self::Table => "table",
+        }
+    }
+}
+
+impl FromStr for RollupKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "function" => Ok(Self::Function),
+            "table" => Ok(Self::Table),
+            _ => anyhow::bail!("Invalid rollup kind {s}"),
+        }
+    }
+}
+
+/// Execution times bucketed on a log scale, so histograms from different
+/// rollups can be merged and still give approximate percentiles. Bucket `i`
+/// holds times up to 2^(i/4) milliseconds, so percentiles are overestimated by
+/// at most 19%.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct LatencyHistogram {
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "proptest::collection::vec(0..=i64::MAX as u64 / 1024, 0..8)")
+    )]
+    counts: Vec<u64>,
+}
+
+impl LatencyHistogram {
+    /// Times above 2^25 milliseconds, about nine hours, go in the last bucket.
+    const MAX_BUCKET: usize = 100;
+
+    pub fn record(&mut self, latency: Duration) {
+        let ms = latency.as_secs_f64() * 1000.0;
+        let bucket = if ms <= 1.0 {
+            0
+        } else {
+            ((ms.log2() * 4.0).ceil() as usize).min(Self::MAX_BUCKET)
+        };
+        if self.counts.len() <= bucket {
+            self.counts.resize(bucket + 1, 0);
+        }
+        self.counts[bucket] += 1;
+    }
+
+    pub fn merge(&mut self, other: &LatencyHistogram) {
+        if self.counts.len() < other.counts.len() {
+            self.counts.resize(other.counts.len(), 0);
+        }
+        for (count, other_count) in self.counts.iter_mut().zip(&other.counts) {
+            *count += other_count;
+        }
+    }
+
+    /// The upper bound of the bucket containing the `percentile`th percentile,
+    /// for `percentile` between 0 and 1.
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        let total: u64 = self.counts.iter().sum();
+        if total == 0 {
+            return None;
+        }
+        let rank = ((total as f64) * percentile).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (bucket, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let ms = 2f64.powf(bucket as f64 / 4.0);
+                return Some(Duration::from_secs_f64(ms / 1000.0));
+            }
+        }
+        None
+    }
+}
+
+/// Metrics for one function or table over one hour or day. Rollups are
+/// written periodically while their bucket is current, so the current bucket's
+/// rollup is partial.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct MetricsRollup {
+    pub granularity: RollupGranularity,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "(0..=u32::MAX as u64).prop_map(|hours| \
+                             UnixTimestamp::from_millis(hours * 60 * 60 * 1000))")
+    )]
+    pub bucket_start: UnixTimestamp,
+    pub kind: RollupKind,
+    pub name: String,
+    pub stats: RollupStats,
+}
+
+/// Counters that are summed when rollups are merged. Table rollups count the
+/// function calls that touched the table.
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct RollupStats {
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=i64::MAX as u64")
+    )]
+    pub calls: u64,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=i64::MAX as u64")
+    )]
+    pub errors: u64,
+    pub latency: LatencyHistogram,
+    /// Bytes read and written from the database, file storage and vector
+    /// indexes.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=i64::MAX as u64")
+    )]
+    pub bandwidth_bytes: u64,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=i64::MAX as u64")
+    )]
+    pub rows_read: u64,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=i64::MAX as u64")
+    )]
+    pub rows_written: u64,
+}
+
+impl RollupStats {
+    pub fn merge(&mut self, other: &RollupStats) {
+        self.calls += other.calls;
+        self.errors += other.errors;
+        self.latency.merge(&other.latency);
+        self.bandwidth_bytes += other.bandwidth_bytes;
+        self.rows_read += other.rows_read;
+        self.rows_written += other.rows_written;
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedMetricsRollup {
+    granularity: String,
+    /// Milliseconds since the epoch, like `_creationTime`.
+    bucket_start: f64,
+    kind: String,
+    name: String,
+    calls: i64,
+    errors: i64,
+    latency_histogram: Vec<i64>,
+    // The percentiles are derived from the histogram, and only stored so
+    // they're easy to read from functions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    latency_p50_ms: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    latency_p90_ms: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    latency_p99_ms: Option<f64>,
+    bandwidth_bytes: i64,
+    rows_read: i64,
+    rows_written: i64,
+}
+
+impl TryFrom<MetricsRollup> for SerializedMetricsRollup {
+    type Error = anyhow::Error;
+
+    fn try_from(rollup: MetricsRollup) -> anyhow::Result<Self> {
+        let stats = rollup.stats;
+        let percentile_ms = |p| {
+            stats
+                .latency
+                .percentile(p)
+                .map(|latency| latency.as_secs_f64() * 1000.0)
+        };
+        Ok(Self {
+            granularity: rollup.granularity.as_str().to_string(),
+            bucket_start: rollup.bucket_start.as_ms_since_epoch()? as f64,
+            kind: rollup.kind.as_str().to_string(),
+            name: rollup.name,
+            calls: stats.calls.try_into()?,
+            errors: stats.errors.try_into()?,
+            latency_p50_ms: percentile_ms(0.5),
+            latency_p90_ms: percentile_ms(0.9),
+            latency_p99_ms: percentile_ms(0.99),
+            latency_histogram: stats
+                .latency
+                .counts
+                .iter()
+                .map(|count| i64::try_from(*count))
+                .collect::<Result<_, _>>()?,
+            bandwidth_bytes: stats.bandwidth_bytes.try_into()?,
+            rows_read: stats.rows_read.try_into()?,
+            rows_written: stats.rows_written.try_into()?,
+        })
+    }
+}
+
+impl TryFrom<SerializedMetricsRollup> for MetricsRollup {
+    type Error = anyhow::Error;
+
+    fn try_from(rollup: SerializedMetricsRollup) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            rollup.bucket_start >= 0.0 && rollup.bucket_start.fract() == 0.0,
+            "Invalid rollup bucket start {}",
+            rollup.bucket_start
+        );
+        Ok(Self {
+            granularity: rollup.granularity.parse()?,
+            bucket_start: UnixTimestamp::from_millis(rollup.bucket_start as u64),
+            kind: rollup.kind.parse()?,
+            name: rollup.name,
+            stats: RollupStats {
+                calls: rollup.calls.try_into()?,
+                errors: rollup.errors.try_into()?,
+                latency: LatencyHistogram {
+                    counts: rollup
+                        .latency_histogram
+                        .into_iter()
+                        .map(u64::try_from)
+                        .collect::<Result<_, _>>()?,
+                },
+                bandwidth_bytes: rollup.bandwidth_bytes.try_into()?,
+                rows_read: rollup.rows_read.try_into()?,
+                rows_written: rollup.rows_written.try_into()?,
+            },
+        })
+    }
+}
+
+codegen_convex_serialization!(MetricsRollup, SerializedMetricsRollup);
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use common::runtime::UnixTimestamp;
+
+    use super::{
+        LatencyHistogram,
+        RollupGranularity,
+    };
+
+    #[test]
+    fn test_bucket_start() {
+        let ts = UnixTimestamp::from_millis(1_700_000_123_456);
+        assert_eq!(
+            RollupGranularity::Hour.bucket_start(ts),
+            UnixTimestamp::from_millis(1_699_999_200_000)
+        );
+        assert_eq!(
+            RollupGranularity::Day.bucket_start(ts),
+            UnixTimestamp::from_millis(1_699_920_000_000)
+        );
+    }
+
+    #[test]
+    fn test_latency_percentiles() {
+        let mut histogram = LatencyHistogram::default();
+        assert_eq!(histogram.percentile(0.5), None);
+        for _ in 0..90 {
+            histogram.record(Duration::from_millis(10));
+        }
+        let mut other = LatencyHistogram::default();
+        for _ in 0..10 {
+            other.record(Duration::from_secs(2));
+        }
+        histogram.merge(&other);
+
+        let p50 = histogram.percentile(0.5).unwrap();
+        assert!(p50 >= Duration::from_millis(10) && p50 < Duration::from_millis(12));
+        let p99 = histogram.percentile(0.99).unwrap();
+        assert!(p99 >= Duration::from_secs(2) && p99 < Duration::from_millis(2400));
+    }
+}
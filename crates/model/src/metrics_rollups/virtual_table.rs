@@ -0,0 +1,58 @@
+use std::collections::BTreeMap;
+
+use common::{
+    document::{
+        DeveloperDocument,
+        ParsedDocument,
+        ResolvedDocument,
+        CREATION_TIME_FIELD,
+        ID_FIELD,
+    },
+    virtual_system_mapping::{
+        VirtualSystemDocMapper,
+        VirtualSystemMapping,
+    },
+};
+use semver::Version;
+use value::{
+    ConvexObject,
+    ConvexValue,
+    TableMapping,
+};
+
+use super::types::MetricsRollup;
+
+/// Rollups are exposed as stored, so the by_bucket index works on both
+/// tables.
+pub struct MetricsRollupDocMapper;
+
+impl VirtualSystemDocMapper for MetricsRollupDocMapper {
+    fn system_to_virtual_doc(
+        &self,
+        virtual_system_mapping: &VirtualSystemMapping,
+        doc: ResolvedDocument,
+        _table_mapping: &TableMapping,
+        _version: Version,
+    ) -> anyhow::Result<DeveloperDocument> {
+        let rollup: ParsedDocument<MetricsRollup> = doc.clone().try_into()?;
+        let public_rollup: ConvexObject = rollup.into_value().try_into()?;
+
+        let virtual_developer_id =
+            virtual_system_mapping.system_resolved_id_to_virtual_developer_id(doc.id())?;
+
+        let mut fields: BTreeMap<_, _> = public_rollup.into();
+        fields.insert(ID_FIELD.to_owned().into(), virtual_developer_id.into());
+        if let Some(t) = doc.creation_time() {
+            fields.insert(
+                CREATION_TIME_FIELD.to_owned().into(),
+                ConvexValue::from(f64::from(t)),
+            );
+        }
+
+        Ok(DeveloperDocument::new(
+            virtual_developer_id,
+            doc.creation_time(),
+            fields.try_into()?,
+        ))
+    }
+}
@@ -3,6 +3,7 @@ use std::{
     mem,
     ops::Deref,
     str::FromStr,
+    time::Duration,
 };
 
 use async_lru::async_lru::SizedValue;
@@ -255,6 +256,103 @@ pub struct AnalyzedFunction {
     pub args_str: Option<String>,
     // JSON-serialized ReturnsValidator
     pub returns_str: Option<String>,
+
+    /// User execution timeout that overrides the default for this function.
+    /// Only set for internal queries and mutations.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(
+            strategy = "proptest::option::of((1..=60_000u64).prop_map(Duration::from_millis))"
+        )
+    )]
+    pub timeout: Option<Duration>,
+
+    /// Number of isolates to keep warm with this function's module evaluated,
+    /// so calls after an idle period don't pay for compiling it. Only set for
+    /// queries and mutations.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "proptest::option::of(1..=16u32)")
+    )]
+    pub warm_instances: Option<u32>,
+
+    /// Region whose action runners should run this function. Only set for
+    /// Node actions.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "proptest::option::of(\"[a-z][a-z0-9-]{0,15}\")")
+    )]
+    pub region: Option<String>,
+
+    /// JSON-serialized object of identity claims and the value each must
+    /// have for non-admins to call this function. See [`RequiredClaims`].
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "proptest::option::of(\"[a-z]{1,8}\".prop_map(|role| \
+                             serde_json::json!({ \"role\": role }).to_string()))")
+    )]
+    pub required_claims_str: Option<String>,
+}
+
+/// Claims a function's caller must have, from its `requiredClaims`, like
+/// `{ role: "admin" }`. They're checked against the same claims
+/// `ctx.auth.getUserIdentity()` returns. A claim matches if it equals the
+/// required value, or is an array containing it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RequiredClaims(BTreeMap<String, JsonValue>);
+
+impl TryFrom<JsonValue> for RequiredClaims {
+    type Error = anyhow::Error;
+
+    fn try_from(value: JsonValue) -> anyhow::Result<Self> {
+        let JsonValue::Object(claims) = value else {
+            anyhow::bail!("requiredClaims must be an object, like {{ role: \"admin\" }}");
+        };
+        anyhow::ensure!(!claims.is_empty(), "requiredClaims is empty");
+        for (claim, value) in &claims {
+            anyhow::ensure!(
+                matches!(
+                    value,
+                    JsonValue::String(_) | JsonValue::Number(_) | JsonValue::Bool(_)
+                ),
+                "requiredClaims.{claim} must be a string, number or boolean"
+            );
+        }
+        Ok(Self(claims.into_iter().collect()))
+    }
+}
+
+impl From<RequiredClaims> for JsonValue {
+    fn from(claims: RequiredClaims) -> Self {
+        JsonValue::Object(claims.0.into_iter().collect())
+    }
+}
+
+impl RequiredClaims {
+    /// The first required claim that `identity_claims`, the caller's claims
+    /// as returned by `ctx.auth.getUserIdentity()`, doesn't have.
+    pub fn first_missing<'a>(&'a self, identity_claims: &JsonValue) -> Option<&'a str> {
+        self.0
+            .iter()
+            .find(
+                |(claim, required)| match identity_claims.get(claim.as_str()) {
+                    Some(JsonValue::Array(values)) => !values.contains(*required),
+                    Some(value) => value != *required,
+                    None => true,
+                },
+            )
+            .map(|(claim, _)| claim.as_str())
+    }
+}
+
+/// Whether `region` is a valid action region name: lowercase letters, digits
+/// and dashes, starting with a letter, like `eu-west-1`.
+pub fn is_valid_action_region(region: &str) -> bool {
+    region.len() <= 64
+        && region.starts_with(|c: char| c.is_ascii_lowercase())
+        && region
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
 }
 
 impl AnalyzedFunction {
@@ -275,9 +373,42 @@ impl AnalyzedFunction {
             visibility,
             args_str: Some(serde_json::to_string(&args_json)?),
             returns_str: Some(serde_json::to_string(&returns_json)?),
+            timeout: None,
+            warm_instances: None,
+            region: None,
+            required_claims_str: None,
         })
     }
 
+    pub fn with_timeout(self, timeout: Option<Duration>) -> Self {
+        Self { timeout, ..self }
+    }
+
+    pub fn with_warm_instances(self, warm_instances: Option<u32>) -> Self {
+        Self {
+            warm_instances,
+            ..self
+        }
+    }
+
+    pub fn with_region(self, region: Option<String>) -> Self {
+        Self { region, ..self }
+    }
+
+    pub fn with_required_claims(self, required_claims: Option<RequiredClaims>) -> Self {
+        Self {
+            required_claims_str: required_claims.map(|claims| JsonValue::from(claims).to_string()),
+            ..self
+        }
+    }
+
+    pub fn required_claims(&self) -> anyhow::Result<Option<RequiredClaims>> {
+        self.required_claims_str
+            .as_deref()
+            .map(|claims| RequiredClaims::try_from(serde_json::from_str::<JsonValue>(claims)?))
+            .transpose()
+    }
+
     pub fn args(&self) -> anyhow::Result<ArgsValidator> {
         match &self.args_str {
             Some(args) => {
@@ -318,6 +449,10 @@ struct SerializedAnalyzedFunction {
     visibility: Option<Visibility>,
     args: Option<String>,
     returns: Option<String>,
+    timeout_ms: Option<i64>,
+    warm_instances: Option<i64>,
+    region: Option<String>,
+    required_claims: Option<String>,
 }
 
 impl TryFrom<AnalyzedFunction> for SerializedAnalyzedFunction {
@@ -331,6 +466,13 @@ impl TryFrom<AnalyzedFunction> for SerializedAnalyzedFunction {
             visibility: f.visibility,
             args: f.args_str,
             returns: f.returns_str,
+            timeout_ms: f
+                .timeout
+                .map(|timeout| i64::try_from(timeout.as_millis()))
+                .transpose()?,
+            warm_instances: f.warm_instances.map(i64::from),
+            region: f.region,
+            required_claims: f.required_claims_str,
         })
     }
 }
@@ -346,6 +488,13 @@ impl TryFrom<SerializedAnalyzedFunction> for AnalyzedFunction {
             visibility: f.visibility,
             args_str: f.args,
             returns_str: f.returns,
+            timeout: f
+                .timeout_ms
+                .map(|ms| anyhow::Ok(Duration::from_millis(u64::try_from(ms)?)))
+                .transpose()?,
+            warm_instances: f.warm_instances.map(u32::try_from).transpose()?,
+            region: f.region,
+            required_claims_str: f.required_claims,
         })
     }
 }
@@ -614,12 +763,17 @@ impl TryFrom<SerializedMappedModule> for MappedModule {
 
 #[cfg(test)]
 mod tests {
+    use serde_json::json;
     use value::{
         obj,
         ConvexObject,
     };
 
-    use super::AnalyzedFunction;
+    use super::{
+        is_valid_action_region,
+        AnalyzedFunction,
+        RequiredClaims,
+    };
     use crate::modules::function_validators::ArgsValidator;
 
     #[test]
@@ -637,4 +791,32 @@ mod tests {
         assert_eq!(function.args()?, ArgsValidator::Unvalidated);
         Ok(())
     }
+
+    #[test]
+    fn test_action_region_names() {
+        assert!(is_valid_action_region("eu-west-1"));
+        assert!(is_valid_action_region("us"));
+        assert!(!is_valid_action_region(""));
+        assert!(!is_valid_action_region("1-eu"));
+        assert!(!is_valid_action_region("EU-West"));
+        assert!(!is_valid_action_region("eu_west"));
+    }
+
+    #[test]
+    fn test_required_claims() -> anyhow::Result<()> {
+        let required = RequiredClaims::try_from(json!({"role": "admin", "email_verified": true}))?;
+        let identity = json!({"role": "admin", "emailVerified": true, "email_verified": true});
+        assert_eq!(required.first_missing(&identity), None);
+        let identity = json!({"role": ["member", "admin"], "email_verified": true});
+        assert_eq!(required.first_missing(&identity), None);
+        let identity = json!({"role": "member", "email_verified": true});
+        assert_eq!(required.first_missing(&identity), Some("role"));
+        let identity = json!({"role": "admin"});
+        assert_eq!(required.first_missing(&identity), Some("email_verified"));
+
+        assert!(RequiredClaims::try_from(json!({})).is_err());
+        assert!(RequiredClaims::try_from(json!(["admin"])).is_err());
+        assert!(RequiredClaims::try_from(json!({"role": ["admin"]})).is_err());
+        Ok(())
+    }
 }
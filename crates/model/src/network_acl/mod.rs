@@ -0,0 +1,142 @@
+use std::sync::LazyLock;
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    query::{
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+};
+use database::{
+    unauthorized_error,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use value::{
+    TableName,
+    TableNamespace,
+};
+
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+use types::NetworkAclConfig;
+
+pub static NETWORK_ACL_CONFIG_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_network_acl_config"
+        .parse()
+        .expect("Invalid built-in network_acl_config table")
+});
+
+pub struct NetworkAclConfigTable;
+impl SystemTable for NetworkAclConfigTable {
+    fn table_name(&self) -> &'static TableName {
+        &NETWORK_ACL_CONFIG_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<NetworkAclConfig>::try_from(document).map(|_| ())
+    }
+}
+
+/// The deployment's IP and country restrictions for public endpoints, which
+/// has at most one row.
+pub struct NetworkAclConfigModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> NetworkAclConfigModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    pub async fn get(&mut self) -> anyhow::Result<Option<ParsedDocument<NetworkAclConfig>>> {
+        let query = Query::full_table_scan(NETWORK_ACL_CONFIG_TABLE.clone(), Order::Asc);
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        query_stream
+            .expect_at_most_one(self.tx)
+            .await?
+            .map(|document| document.try_into())
+            .transpose()
+    }
+
+    /// Replaces the restrictions, or removes them if `config` is `None`.
+    pub async fn set(&mut self, config: Option<NetworkAclConfig>) -> anyhow::Result<()> {
+        if !(self.tx.identity().is_system() || self.tx.identity().is_admin()) {
+            anyhow::bail!(unauthorized_error("set_network_acl_config"));
+        }
+        let existing = self.get().await?;
+        match (existing, config) {
+            (Some(existing), Some(config)) => {
+                SystemMetadataModel::new_global(self.tx)
+                    .replace(existing.id(), config.try_into()?)
+                    .await?;
+            },
+            (Some(existing), None) => {
+                SystemMetadataModel::new_global(self.tx)
+                    .delete(existing.id())
+                    .await?;
+            },
+            (None, Some(config)) => {
+                SystemMetadataModel::new_global(self.tx)
+                    .insert(&NETWORK_ACL_CONFIG_TABLE, config.try_into()?)
+                    .await?;
+            },
+            (None, None) => {},
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use database::test_helpers::DbFixtures;
+    use keybroker::Identity;
+    use runtime::testing::TestRuntime;
+
+    use super::{
+        types::NetworkAclConfig,
+        NetworkAclConfigModel,
+    };
+    use crate::test_helpers::DbFixturesWithModel;
+
+    #[convex_macro::test_runtime]
+    async fn test_set_network_acl_config(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin(Identity::system()).await?;
+        assert!(NetworkAclConfigModel::new(&mut tx).get().await?.is_none());
+        let config = NetworkAclConfig::new(
+            vec![],
+            vec!["192.0.2.0/24".parse()?],
+            vec!["KP".to_string()],
+        )?;
+        NetworkAclConfigModel::new(&mut tx)
+            .set(Some(config.clone()))
+            .await?;
+        let stored = NetworkAclConfigModel::new(&mut tx)
+            .get()
+            .await?
+            .unwrap()
+            .into_value();
+        assert_eq!(stored, config);
+        NetworkAclConfigModel::new(&mut tx).set(None).await?;
+        assert!(NetworkAclConfigModel::new(&mut tx).get().await?.is_none());
+
+        let mut tx = db.begin(Identity::Unknown).await?;
+        assert!(NetworkAclConfigModel::new(&mut tx).set(None).await.is_err());
+        Ok(())
+    }
+}
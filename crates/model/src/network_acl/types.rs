@@ -0,0 +1,338 @@
+use std::{
+    collections::BTreeSet,
+    fmt,
+    net::{
+        IpAddr,
+        Ipv4Addr,
+        Ipv6Addr,
+    },
+    str::FromStr,
+};
+
+use errors::ErrorMetadata;
+#[cfg(any(test, feature = "testing"))]
+use proptest::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use value::codegen_convex_serialization;
+
+/// The most CIDR ranges a deployment's allow and deny lists can hold between
+/// them, since every request is checked against each of them.
+pub const MAX_NETWORK_ACL_RANGES: usize = 1024;
+
+/// A range of IP addresses, like `10.0.0.0/8` or `2001:db8::/32`. A bare
+/// address is a range of just that address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IpCidr {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpCidr {
+    /// The range of addresses that share the first `prefix_len` bits of
+    /// `addr`.
+    pub fn new(addr: IpAddr, prefix_len: u8) -> anyhow::Result<Self> {
+        let addr = addr.to_canonical();
+        let network = match addr {
+            IpAddr::V4(addr) => {
+                anyhow::ensure!(prefix_len <= 32, "IPv4 prefixes are at most 32 bits");
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(prefix_len))
+                    .unwrap_or(0);
+                IpAddr::V4(Ipv4Addr::from(u32::from(addr) & mask))
+            },
+            IpAddr::V6(addr) => {
+                anyhow::ensure!(prefix_len <= 128, "IPv6 prefixes are at most 128 bits");
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(prefix_len))
+                    .unwrap_or(0);
+                IpAddr::V6(Ipv6Addr::from(u128::from(addr) & mask))
+            },
+        };
+        Ok(Self {
+            network,
+            prefix_len,
+        })
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        IpCidr::new(ip.to_canonical(), self.prefix_len).is_ok_and(|range| range == *self)
+    }
+}
+
+impl FromStr for IpCidr {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let parsed: anyhow::Result<_> = try {
+            match s.split_once('/') {
+                Some((addr, prefix_len)) => {
+                    IpCidr::new(addr.parse::<IpAddr>()?, prefix_len.parse()?)?
+                },
+                None => {
+                    let addr = s.parse::<IpAddr>()?.to_canonical();
+                    let prefix_len = if addr.is_ipv4() { 32 } else { 128 };
+                    IpCidr::new(addr, prefix_len)?
+                },
+            }
+        };
+        parsed.map_err(|e| {
+            ErrorMetadata::bad_request(
+                "InvalidNetworkAclConfig",
+                format!("Invalid IP range {s:?}: {e}. Ranges look like \"10.0.0.0/8\"."),
+            )
+            .into()
+        })
+    }
+}
+
+impl fmt::Display for IpCidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix_len)
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl Arbitrary for IpCidr {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): ()) -> Self::Strategy {
+        let v4 = (any::<Ipv4Addr>(), 0..=32u8).prop_map(|(addr, len)| (IpAddr::V4(addr), len));
+        let v6 = (any::<Ipv6Addr>(), 0..=128u8).prop_map(|(addr, len)| (IpAddr::V6(addr), len));
+        prop_oneof![v4, v6]
+            // IPv4-mapped IPv6 addresses with long prefixes aren't valid ranges.
+            .prop_filter_map("invalid range", |(addr, len)| IpCidr::new(addr, len).ok())
+            .boxed()
+    }
+}
+
+/// Which IP addresses and countries can reach the deployment's public
+/// endpoints.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct NetworkAclConfig {
+    /// If nonempty, only addresses in these ranges are allowed.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "proptest::collection::vec(any::<IpCidr>(), 0..4)")
+    )]
+    pub allow: Vec<IpCidr>,
+    /// Addresses in these ranges are rejected, even if they're also in an
+    /// allowed range.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "proptest::collection::vec(any::<IpCidr>(), 0..4)")
+    )]
+    pub deny: Vec<IpCidr>,
+    /// ISO 3166-1 alpha-2 codes, like `"US"`, of countries to reject requests
+    /// from. Requests whose country the GeoIP provider can't tell are allowed.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "proptest::collection::btree_set(\"[A-Z]{2}\", 0..4)")
+    )]
+    pub blocked_countries: BTreeSet<String>,
+}
+
+impl NetworkAclConfig {
+    pub fn new(
+        allow: Vec<IpCidr>,
+        deny: Vec<IpCidr>,
+        blocked_countries: Vec<String>,
+    ) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            allow.len() + deny.len() <= MAX_NETWORK_ACL_RANGES,
+            ErrorMetadata::bad_request(
+                "InvalidNetworkAclConfig",
+                format!("At most {MAX_NETWORK_ACL_RANGES} IP ranges are allowed"),
+            )
+        );
+        let blocked_countries = blocked_countries
+            .into_iter()
+            .map(|country| {
+                anyhow::ensure!(
+                    country.len() == 2 && country.chars().all(|c| c.is_ascii_alphabetic()),
+                    ErrorMetadata::bad_request(
+                        "InvalidNetworkAclConfig",
+                        format!(
+                            "Invalid country code {country:?}. Countries are two letter ISO \
+                             3166-1 codes, like \"US\"."
+                        ),
+                    )
+                );
+                Ok(country.to_ascii_uppercase())
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self {
+            allow,
+            deny,
+            blocked_countries,
+        })
+    }
+
+    /// Checks a request from `ip`, which the GeoIP provider placed in
+    /// `country` if it could tell.
+    pub fn check(&self, ip: IpAddr, country: Option<&str>) -> Result<(), NetworkAclRejection> {
+        if self.deny.iter().any(|range| range.contains(ip)) {
+            return Err(NetworkAclRejection::Denied);
+        }
+        if !self.allow.is_empty() && !self.allow.iter().any(|range| range.contains(ip)) {
+            return Err(NetworkAclRejection::NotAllowed);
+        }
+        match country {
+            Some(country) if self.blocked_countries.contains(country) => {
+                Err(NetworkAclRejection::CountryBlocked)
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Why a request was rejected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub enum NetworkAclRejection {
+    /// The address is in a denied range.
+    Denied,
+    /// There's an allow list and the address isn't on it.
+    NotAllowed,
+    /// The request came from a blocked country.
+    CountryBlocked,
+}
+
+impl NetworkAclRejection {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Denied => "denied",
+            Self::NotAllowed => "not_allowed",
+            Self::CountryBlocked => "country_blocked",
+        }
+    }
+}
+
+impl FromStr for NetworkAclRejection {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "denied" => Ok(Self::Denied),
+            "not_allowed" => Ok(Self::NotAllowed),
+            "country_blocked" => Ok(Self::CountryBlocked),
+            _ => anyhow::bail!("Invalid network ACL rejection reason {s}"),
+        }
+    }
+}
+
+impl fmt::Display for NetworkAclRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedNetworkAclConfig {
+    allow: Vec<String>,
+    deny: Vec<String>,
+    blocked_countries: Vec<String>,
+}
+
+impl TryFrom<NetworkAclConfig> for SerializedNetworkAclConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(config: NetworkAclConfig) -> anyhow::Result<Self> {
+        Ok(Self {
+            allow: config.allow.iter().map(ToString::to_string).collect(),
+            deny: config.deny.iter().map(ToString::to_string).collect(),
+            blocked_countries: config.blocked_countries.into_iter().collect(),
+        })
+    }
+}
+
+impl TryFrom<SerializedNetworkAclConfig> for NetworkAclConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(config: SerializedNetworkAclConfig) -> anyhow::Result<Self> {
+        Ok(Self {
+            allow: config
+                .allow
+                .iter()
+                .map(|range| range.parse())
+                .collect::<anyhow::Result<_>>()?,
+            deny: config
+                .deny
+                .iter()
+                .map(|range| range.parse())
+                .collect::<anyhow::Result<_>>()?,
+            blocked_countries: config.blocked_countries.into_iter().collect(),
+        })
+    }
+}
+
+codegen_convex_serialization!(NetworkAclConfig, SerializedNetworkAclConfig);
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use errors::ErrorMetadataAnyhowExt;
+
+    use super::{
+        IpCidr,
+        NetworkAclConfig,
+        NetworkAclRejection,
+    };
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_ip_cidr() -> anyhow::Result<()> {
+        let range: IpCidr = "10.1.2.3/8".parse()?;
+        assert_eq!(range.to_string(), "10.0.0.0/8");
+        assert!(range.contains(ip("10.255.0.1")));
+        assert!(!range.contains(ip("11.0.0.1")));
+        // IPv4-mapped IPv6 addresses match IPv4 ranges.
+        assert!(range.contains(ip("::ffff:10.0.0.1")));
+
+        let range: IpCidr = "2001:db8::/32".parse()?;
+        assert!(range.contains(ip("2001:db8:1::1")));
+        assert!(!range.contains(ip("2001:db9::1")));
+
+        assert_eq!("1.2.3.4".parse::<IpCidr>()?.to_string(), "1.2.3.4/32");
+        assert_eq!("0.0.0.0/0".parse::<IpCidr>()?.to_string(), "0.0.0.0/0");
+        assert!("1.2.3.4/33".parse::<IpCidr>().is_err());
+        assert!("example.com".parse::<IpCidr>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_check() -> anyhow::Result<()> {
+        let config = NetworkAclConfig::new(
+            vec!["10.0.0.0/8".parse()?],
+            vec!["10.6.6.0/24".parse()?],
+            vec!["kp".to_string()],
+        )?;
+        assert_eq!(config.check(ip("10.0.0.1"), None), Ok(()));
+        assert_eq!(config.check(ip("10.0.0.1"), Some("US")), Ok(()));
+        assert_eq!(
+            config.check(ip("10.6.6.6"), None),
+            Err(NetworkAclRejection::Denied)
+        );
+        assert_eq!(
+            config.check(ip("192.168.0.1"), None),
+            Err(NetworkAclRejection::NotAllowed)
+        );
+        assert_eq!(
+            config.check(ip("10.0.0.1"), Some("KP")),
+            Err(NetworkAclRejection::CountryBlocked)
+        );
+
+        let err = NetworkAclConfig::new(vec![], vec![], vec!["USA".to_string()]).unwrap_err();
+        assert_eq!(err.short_msg(), "InvalidNetworkAclConfig");
+        Ok(())
+    }
+}
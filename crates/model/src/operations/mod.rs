@@ -0,0 +1,244 @@
+//! A single view over the deployment's long-running admin operations: snapshot
+//! imports, snapshot exports, index builds and table compactions. Each kind
+//! keeps its own state in its own system table, and this module translates that
+//! state into [`Operation`]s so clients can poll or stream them the same way.
+use common::{
+    bootstrap_model::index::{
+        TabletIndexMetadata,
+        INDEX_TABLE,
+    },
+    runtime::Runtime,
+};
+use database::{
+    IndexModel,
+    Transaction,
+};
+use errors::ErrorMetadata;
+use value::{
+    DeveloperDocumentId,
+    TableNamespace,
+};
+
+use self::types::{
+    Operation,
+    OperationKind,
+};
+use crate::{
+    exports::{
+        ExportsModel,
+        EXPORTS_TABLE,
+    },
+    snapshot_imports::{
+        SnapshotImportModel,
+        SNAPSHOT_IMPORTS_TABLE,
+    },
+    table_compactions::{
+        TableCompactionModel,
+        TABLE_COMPACTIONS_TABLE,
+    },
+};
+
+pub mod types;
+
+pub struct OperationsModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> OperationsModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    /// All imports, exports and table compactions, and the builds of indexes
+    /// on user tables that aren't enabled yet, newest first.
+    pub async fn list(&mut self, kind: Option<OperationKind>) -> anyhow::Result<Vec<Operation>> {
+        let included = |k| kind.is_none() || kind == Some(k);
+        let mut operations = vec![];
+        if included(OperationKind::SnapshotImport) {
+            for import in SnapshotImportModel::new(self.tx).list().await? {
+                operations.push(Operation::from(&import));
+            }
+        }
+        if included(OperationKind::SnapshotExport) {
+            for export in ExportsModel::new(self.tx).list().await? {
+                operations.push(Operation::from(&export));
+            }
+        }
+        if included(OperationKind::TableCompaction) {
+            for compaction in TableCompactionModel::new(self.tx).list().await? {
+                operations.push(Operation::from(&compaction));
+            }
+        }
+        if included(OperationKind::IndexBuild) {
+            for index in IndexModel::new(self.tx).get_all_indexes().await? {
+                if index.config.is_enabled() {
+                    continue;
+                }
+                let table = self.tx.table_mapping().tablet_name(*index.name.table())?;
+                if table.is_system() {
+                    continue;
+                }
+                operations.push(Operation::from_index(&index, &table));
+            }
+        }
+        operations.sort_by(|a, b| b.creation_time.cmp(&a.creation_time));
+        Ok(operations)
+    }
+
+    pub async fn get(&mut self, id: DeveloperDocumentId) -> anyhow::Result<Option<Operation>> {
+        let table_mapping = self.tx.table_mapping().namespace(TableNamespace::Global);
+        let Ok(table) = table_mapping.number_to_name()(id.table()) else {
+            return Ok(None);
+        };
+        let resolved_id = id.to_resolved(&table_mapping.number_to_tablet())?;
+        if table == *SNAPSHOT_IMPORTS_TABLE {
+            let import = SnapshotImportModel::new(self.tx).get(resolved_id).await?;
+            Ok(import.as_ref().map(Operation::from))
+        } else if table == *EXPORTS_TABLE {
+            let export = ExportsModel::new(self.tx).get(id).await?;
+            Ok(export.as_ref().map(Operation::from))
+        } else if table == *TABLE_COMPACTIONS_TABLE {
+            let compaction = TableCompactionModel::new(self.tx).get(resolved_id).await?;
+            Ok(compaction.as_ref().map(Operation::from))
+        } else if table == *INDEX_TABLE {
+            let Some(document) = self.tx.get(resolved_id).await? else {
+                return Ok(None);
+            };
+            let index = TabletIndexMetadata::from_document(document)?;
+            let table = self.tx.table_mapping().tablet_name(*index.name.table())?;
+            Ok(Some(Operation::from_index(&index, &table)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    pub async fn cancel(&mut self, id: DeveloperDocumentId) -> anyhow::Result<()> {
+        let operation = self.get(id).await?.ok_or_else(|| {
+            ErrorMetadata::not_found("OperationNotFound", format!("Operation {id} not found"))
+        })?;
+        anyhow::ensure!(
+            operation.cancelable,
+            ErrorMetadata::bad_request(
+                "OperationNotCancelable",
+                format!(
+                    "Operation {id} is {} and can't be canceled",
+                    operation.state
+                )
+            )
+        );
+        match operation.kind {
+            OperationKind::SnapshotImport => {
+                let resolved_id = id.to_resolved(
+                    &self
+                        .tx
+                        .table_mapping()
+                        .namespace(TableNamespace::Global)
+                        .number_to_tablet(),
+                )?;
+                SnapshotImportModel::new(self.tx)
+                    .cancel_import(resolved_id)
+                    .await
+            },
+            OperationKind::TableCompaction => {
+                let resolved_id = id.to_resolved(
+                    &self
+                        .tx
+                        .table_mapping()
+                        .namespace(TableNamespace::Global)
+                        .number_to_tablet(),
+                )?;
+                TableCompactionModel::new(self.tx).cancel(resolved_id).await
+            },
+            OperationKind::SnapshotExport | OperationKind::IndexBuild => {
+                anyhow::bail!("{} operations are never cancelable", operation.kind)
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::components::{
+        ComponentId,
+        ComponentPath,
+    };
+    use database::test_helpers::DbFixtures;
+    use runtime::testing::TestRuntime;
+
+    use super::{
+        types::{
+            OperationKind,
+            OperationState,
+        },
+        OperationsModel,
+    };
+    use crate::{
+        exports::{
+            types::{
+                ExportFormat,
+                ExportRequestor,
+            },
+            ExportsModel,
+        },
+        snapshot_imports::{
+            types::{
+                ImportFormat,
+                ImportMode,
+                ImportRequestor,
+                ImportTableSelection,
+            },
+            SnapshotImportModel,
+        },
+        test_helpers::DbFixturesWithModel,
+    };
+
+    #[convex_macro::test_runtime]
+    async fn test_list_and_cancel(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin_system().await?;
+        let import_id = SnapshotImportModel::new(&mut tx)
+            .start_import(
+                ImportFormat::Zip,
+                ImportMode::Replace,
+                ComponentPath::root(),
+                "objectkey".try_into()?,
+                ImportRequestor::SnapshotImport,
+                ImportTableSelection::default(),
+            )
+            .await?;
+        let export_id = ExportsModel::new(&mut tx)
+            .insert_requested(
+                ExportFormat::Zip {
+                    include_storage: false,
+                },
+                ComponentId::Root,
+                ExportRequestor::SnapshotExport,
+                None,
+            )
+            .await?;
+
+        let mut model = OperationsModel::new(&mut tx);
+        let operations = model.list(None).await?;
+        assert_eq!(operations.len(), 2);
+        assert!(operations
+            .iter()
+            .all(|operation| operation.state == OperationState::Pending));
+        let imports = model.list(Some(OperationKind::SnapshotImport)).await?;
+        assert_eq!(imports.len(), 1);
+        assert_eq!(imports[0].id, import_id.into());
+        assert!(imports[0].cancelable);
+
+        let export = model.get(export_id.into()).await?.unwrap();
+        assert_eq!(export.kind, OperationKind::SnapshotExport);
+        assert!(!export.cancelable);
+        assert!(model.cancel(export_id.into()).await.is_err());
+
+        model.cancel(import_id.into()).await?;
+        let import = model.get(import_id.into()).await?.unwrap();
+        assert_eq!(import.state, OperationState::Canceled);
+        assert!(import.state.is_terminal());
+        assert!(import.errors.is_empty());
+        assert!(model.cancel(import_id.into()).await.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,280 @@
+use common::{
+    bootstrap_model::index::{
+        database_index::DatabaseIndexState,
+        IndexConfig,
+        TabletIndexMetadata,
+    },
+    document::{
+        CreationTime,
+        ParsedDocument,
+    },
+};
+use value::{
+    DeveloperDocumentId,
+    TableName,
+};
+
+use crate::{
+    exports::types::Export,
+    snapshot_imports::{
+        types::{
+            ImportState,
+            SnapshotImport,
+        },
+        IMPORT_CANCELED_MESSAGE,
+    },
+    table_compactions::types::{
+        TableCompaction,
+        TableCompactionState,
+    },
+};
+
+/// The kinds of long-running admin operations. Each is backed by the documents
+/// of an existing system table, so an operation's ID is its document's ID.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, strum::EnumString, strum::Display)]
+#[strum(serialize_all = "camelCase")]
+pub enum OperationKind {
+    SnapshotImport,
+    SnapshotExport,
+    IndexBuild,
+    TableCompaction,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, strum::EnumString, strum::Display)]
+#[strum(serialize_all = "camelCase")]
+pub enum OperationState {
+    /// Waiting to start, e.g. for a worker or for the user to confirm.
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+    Canceled,
+}
+
+impl OperationState {
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, Self::Succeeded | Self::Failed | Self::Canceled)
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct OperationProgress {
+    /// Units of work done so far, e.g. documents written.
+    pub completed: u64,
+    /// Units of work in total, if known up front.
+    pub total: Option<u64>,
+    /// Human readable description of what the operation is doing.
+    pub message: Option<String>,
+}
+
+/// A uniform view of an import, export, index build or table compaction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Operation {
+    pub id: DeveloperDocumentId,
+    pub kind: OperationKind,
+    pub creation_time: Option<CreationTime>,
+    pub state: OperationState,
+    pub progress: OperationProgress,
+    /// Errors the operation has hit. A failed operation has at least one.
+    pub errors: Vec<String>,
+    /// Whether the operation can be canceled in its current state.
+    pub cancelable: bool,
+}
+
+impl From<&ParsedDocument<SnapshotImport>> for Operation {
+    fn from(import: &ParsedDocument<SnapshotImport>) -> Self {
+        let documents_written = import
+            .progress
+            .as_ref()
+            .map_or(0, |progress| progress.documents_written);
+        let (state, progress, errors, cancelable) = match &import.state {
+            ImportState::Uploaded => (
+                OperationState::Pending,
+                OperationProgress::default(),
+                vec![],
+                true,
+            ),
+            ImportState::WaitingForConfirmation { info_message, .. } => (
+                OperationState::Pending,
+                OperationProgress {
+                    message: Some(info_message.clone()),
+                    ..Default::default()
+                },
+                vec![],
+                true,
+            ),
+            ImportState::InProgress {
+                progress_message, ..
+            } => (
+                OperationState::Running,
+                OperationProgress {
+                    completed: documents_written,
+                    total: None,
+                    message: Some(progress_message.clone()),
+                },
+                vec![],
+                false,
+            ),
+            ImportState::Completed {
+                num_rows_written, ..
+            } => (
+                OperationState::Succeeded,
+                OperationProgress {
+                    completed: *num_rows_written as u64,
+                    total: Some(*num_rows_written as u64),
+                    message: None,
+                },
+                vec![],
+                false,
+            ),
+            ImportState::Failed(message) if message == IMPORT_CANCELED_MESSAGE => (
+                OperationState::Canceled,
+                OperationProgress::default(),
+                vec![],
+                false,
+            ),
+            ImportState::Failed(message) => (
+                OperationState::Failed,
+                OperationProgress {
+                    completed: documents_written,
+                    ..Default::default()
+                },
+                vec![message.clone()],
+                false,
+            ),
+        };
+        Self {
+            id: import.developer_id(),
+            kind: OperationKind::SnapshotImport,
+            creation_time: import.creation_time(),
+            state,
+            progress,
+            errors,
+            cancelable,
+        }
+    }
+}
+
+impl From<&ParsedDocument<Export>> for Operation {
+    fn from(export: &ParsedDocument<Export>) -> Self {
+        let (state, progress, errors) = match &**export {
+            Export::Requested { .. } => (
+                OperationState::Pending,
+                OperationProgress::default(),
+                vec![],
+            ),
+            Export::InProgress { progress, .. } => (
+                OperationState::Running,
+                match progress {
+                    Some(progress) => OperationProgress {
+                        completed: progress.documents_written,
+                        total: Some(progress.total_documents),
+                        message: progress
+                            .current_table
+                            .as_ref()
+                            .map(|table| format!("Exporting {table}")),
+                    },
+                    None => OperationProgress::default(),
+                },
+                vec![],
+            ),
+            Export::Completed { .. } => (
+                OperationState::Succeeded,
+                OperationProgress::default(),
+                vec![],
+            ),
+            // The export worker retries until it gives up, so there's no
+            // error message left to report.
+            Export::Failed { .. } => (
+                OperationState::Failed,
+                OperationProgress::default(),
+                vec!["Export failed".to_string()],
+            ),
+        };
+        Self {
+            id: export.developer_id(),
+            kind: OperationKind::SnapshotExport,
+            creation_time: export.creation_time(),
+            state,
+            progress,
+            errors,
+            cancelable: false,
+        }
+    }
+}
+
+impl From<&ParsedDocument<TableCompaction>> for Operation {
+    fn from(compaction: &ParsedDocument<TableCompaction>) -> Self {
+        let table = &compaction.table_name;
+        let progress = compaction
+            .state
+            .progress()
+            .map(|progress| OperationProgress {
+                completed: progress.revisions_scanned,
+                total: None,
+                message: Some(format!(
+                    "Deleted {} document revisions and {} index entries from {table}",
+                    progress.documents_deleted, progress.index_entries_deleted
+                )),
+            })
+            .unwrap_or_default();
+        let (state, errors, cancelable) = match &compaction.state {
+            TableCompactionState::Requested => (OperationState::Pending, vec![], true),
+            TableCompactionState::InProgress { .. } => (OperationState::Running, vec![], true),
+            TableCompactionState::Completed { .. } => (OperationState::Succeeded, vec![], false),
+            TableCompactionState::Failed { error, .. } => {
+                (OperationState::Failed, vec![error.clone()], false)
+            },
+            TableCompactionState::Canceled => (OperationState::Canceled, vec![], false),
+        };
+        Self {
+            id: compaction.developer_id(),
+            kind: OperationKind::TableCompaction,
+            creation_time: compaction.creation_time(),
+            state,
+            progress,
+            errors,
+            cancelable,
+        }
+    }
+}
+
+impl Operation {
+    /// The build of `index` on `table`. Enabled indexes are finished builds.
+    pub fn from_index(index: &ParsedDocument<TabletIndexMetadata>, table: &TableName) -> Self {
+        let name = format!("{table}.{}", index.name.descriptor());
+        let (state, message) = if index.config.is_enabled() {
+            (OperationState::Succeeded, None)
+        } else if index.config.is_backfilling() {
+            let message = match &index.config {
+                IndexConfig::Database {
+                    on_disk_state: DatabaseIndexState::Backfilling(backfill_state),
+                    ..
+                } if backfill_state.retention_started => {
+                    format!("Backfilled {name}, waiting for retention to catch up")
+                },
+                _ => format!("Backfilling {name}"),
+            };
+            (OperationState::Running, Some(message))
+        } else {
+            (
+                OperationState::Succeeded,
+                Some(format!(
+                    "Backfilled {name}, waiting to be enabled by a push"
+                )),
+            )
+        };
+        Self {
+            id: index.developer_id(),
+            kind: OperationKind::IndexBuild,
+            creation_time: index.creation_time(),
+            state,
+            progress: OperationProgress {
+                message,
+                ..Default::default()
+            },
+            errors: vec![],
+            cancelable: false,
+        }
+    }
+}
@@ -0,0 +1,224 @@
+use std::{
+    collections::BTreeSet,
+    sync::LazyLock,
+};
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    query::{
+        IndexRange,
+        IndexRangeExpression,
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+    types::IndexName,
+};
+use database::{
+    defaults::system_index,
+    unauthorized_error,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use value::{
+    ConvexValue,
+    FieldPath,
+    TableName,
+    TableNamespace,
+};
+
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+use types::PiiReport;
+
+pub static PII_REPORTS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_pii_reports"
+        .parse()
+        .expect("Invalid built-in pii_reports table")
+});
+
+pub static PII_REPORTS_BY_TABLE_NAME_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&PII_REPORTS_TABLE, "by_table_name"));
+static TABLE_NAME_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "tableName".parse().expect("invalid tableName field"));
+
+pub struct PiiReportsTable;
+impl SystemTable for PiiReportsTable {
+    fn table_name(&self) -> &'static TableName {
+        &PII_REPORTS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![SystemIndex {
+            name: PII_REPORTS_BY_TABLE_NAME_INDEX.clone(),
+            fields: vec![TABLE_NAME_FIELD.clone()].try_into().unwrap(),
+        }]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<PiiReport>::try_from(document).map(|_| ())
+    }
+}
+
+/// The latest PII scan report for each of the app's tables.
+pub struct PiiReportModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> PiiReportModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    pub async fn reports(&mut self) -> anyhow::Result<Vec<PiiReport>> {
+        if !(self.tx.identity().is_system() || self.tx.identity().is_admin()) {
+            anyhow::bail!(unauthorized_error("pii_reports"));
+        }
+        let index_range = IndexRange {
+            index_name: PII_REPORTS_BY_TABLE_NAME_INDEX.clone(),
+            range: vec![],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        let mut reports = vec![];
+        while let Some(document) = query_stream.next(self.tx, None).await? {
+            reports.push(ParsedDocument::<PiiReport>::try_from(document)?.into_value());
+        }
+        Ok(reports)
+    }
+
+    async fn report(
+        &mut self,
+        table_name: &TableName,
+    ) -> anyhow::Result<Option<ParsedDocument<PiiReport>>> {
+        let index_range = IndexRange {
+            index_name: PII_REPORTS_BY_TABLE_NAME_INDEX.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                TABLE_NAME_FIELD.clone(),
+                ConvexValue::try_from(table_name.to_string())?.into(),
+            )],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        query_stream
+            .expect_at_most_one(self.tx)
+            .await?
+            .map(ParsedDocument::try_from)
+            .transpose()
+    }
+
+    /// Replaces the report for `report.table_name`.
+    pub async fn upsert(&mut self, report: PiiReport) -> anyhow::Result<()> {
+        anyhow::ensure!(self.tx.identity().is_system());
+        match self.report(&report.table_name).await? {
+            Some(existing) => {
+                SystemMetadataModel::new_global(self.tx)
+                    .replace(existing.id(), report.try_into()?)
+                    .await?;
+            },
+            None => {
+                SystemMetadataModel::new_global(self.tx)
+                    .insert(&PII_REPORTS_TABLE, report.try_into()?)
+                    .await?;
+            },
+        }
+        Ok(())
+    }
+
+    /// Deletes the reports for tables that aren't in `tables`, since they
+    /// were deleted or renamed.
+    pub async fn retain(&mut self, tables: &BTreeSet<TableName>) -> anyhow::Result<()> {
+        anyhow::ensure!(self.tx.identity().is_system());
+        let query = Query::full_table_scan(PII_REPORTS_TABLE.clone(), Order::Asc);
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        let mut stale = vec![];
+        while let Some(document) = query_stream.next(self.tx, None).await? {
+            let report = ParsedDocument::<PiiReport>::try_from(document)?;
+            if !tables.contains(&report.table_name) {
+                stale.push(report.id());
+            }
+        }
+        for id in stale {
+            SystemMetadataModel::new_global(self.tx).delete(id).await?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use common::runtime::Runtime;
+    use database::test_helpers::DbFixtures;
+    use keybroker::Identity;
+    use runtime::testing::TestRuntime;
+
+    use super::{
+        types::{
+            PiiField,
+            PiiKind,
+            PiiReport,
+        },
+        PiiReportModel,
+    };
+    use crate::test_helpers::DbFixturesWithModel;
+
+    #[convex_macro::test_runtime]
+    async fn test_pii_reports(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin(Identity::system()).await?;
+        let report = |table_name: &str, matches| -> anyhow::Result<PiiReport> {
+            Ok(PiiReport {
+                table_name: table_name.parse()?,
+                scanned_at: rt.unix_timestamp(),
+                documents_sampled: 10,
+                fields: vec![PiiField {
+                    field_path: "email".to_string(),
+                    kind: PiiKind::Email,
+                    matches,
+                    documents_with_field: 10,
+                }],
+            })
+        };
+        PiiReportModel::new(&mut tx)
+            .upsert(report("users", 3)?)
+            .await?;
+        PiiReportModel::new(&mut tx)
+            .upsert(report("orders", 1)?)
+            .await?;
+        PiiReportModel::new(&mut tx)
+            .upsert(report("users", 9)?)
+            .await?;
+        assert_eq!(
+            PiiReportModel::new(&mut tx).reports().await?,
+            vec![report("orders", 1)?, report("users", 9)?]
+        );
+
+        PiiReportModel::new(&mut tx)
+            .retain(&BTreeSet::from(["users".parse()?]))
+            .await?;
+        assert_eq!(
+            PiiReportModel::new(&mut tx).reports().await?,
+            vec![report("users", 9)?]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+use std::{
+    fmt,
+    str::FromStr,
+};
+
+use common::runtime::UnixTimestamp;
+#[cfg(any(test, feature = "testing"))]
+use proptest::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use value::{
+    codegen_convex_serialization,
+    TableName,
+};
+
+/// A kind of personal data the scanner looks for in string fields.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub enum PiiKind {
+    Email,
+    Phone,
+    CreditCard,
+}
+
+impl PiiKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Email => "email",
+            Self::Phone => "phone",
+            Self::CreditCard => "creditCard",
+        }
+    }
+}
+
+impl FromStr for PiiKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "email" => Ok(Self::Email),
+            "phone" => Ok(Self::Phone),
+            "creditCard" => Ok(Self::CreditCard),
+            _ => anyhow::bail!("Invalid PII kind {s}"),
+        }
+    }
+}
+
+impl fmt::Display for PiiKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The results of the latest scan of a table's documents for fields that
+/// look like they hold personal data.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct PiiReport {
+    pub table_name: TableName,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(
+            strategy = "(0..=i64::MAX as u64 / 1_000_000).prop_map(UnixTimestamp::from_millis)"
+        )
+    )]
+    pub scanned_at: UnixTimestamp,
+    /// How many documents the scan looked at, which is at most
+    /// `PII_SCAN_SAMPLE_SIZE`.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=1_000_000u64")
+    )]
+    pub documents_sampled: u64,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "proptest::collection::vec(any::<PiiField>(), 0..4)")
+    )]
+    pub fields: Vec<PiiField>,
+}
+
+/// A field with values that look like `kind`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct PiiField {
+    /// Dotted path to the field, like `contact.email`. Elements of arrays
+    /// share the array's path.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(regex = "[a-z]{1,8}(\\.[a-z]{1,8})?")
+    )]
+    pub field_path: String,
+    pub kind: PiiKind,
+    /// How many sampled documents had a value of this kind in the field.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=1_000_000u64")
+    )]
+    pub matches: u64,
+    /// How many sampled documents had a string in the field.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=1_000_000u64")
+    )]
+    pub documents_with_field: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedPiiReport {
+    table_name: String,
+    scanned_at_ms: i64,
+    documents_sampled: i64,
+    fields: Vec<SerializedPiiField>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedPiiField {
+    field_path: String,
+    kind: String,
+    matches: i64,
+    documents_with_field: i64,
+}
+
+impl TryFrom<PiiReport> for SerializedPiiReport {
+    type Error = anyhow::Error;
+
+    fn try_from(report: PiiReport) -> anyhow::Result<Self> {
+        Ok(Self {
+            table_name: report.table_name.to_string(),
+            scanned_at_ms: report.scanned_at.as_ms_since_epoch()?.try_into()?,
+            documents_sampled: report.documents_sampled.try_into()?,
+            fields: report
+                .fields
+                .into_iter()
+                .map(|field| {
+                    anyhow::Ok(SerializedPiiField {
+                        field_path: field.field_path,
+                        kind: field.kind.to_string(),
+                        matches: field.matches.try_into()?,
+                        documents_with_field: field.documents_with_field.try_into()?,
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?,
+        })
+    }
+}
+
+impl TryFrom<SerializedPiiReport> for PiiReport {
+    type Error = anyhow::Error;
+
+    fn try_from(report: SerializedPiiReport) -> anyhow::Result<Self> {
+        Ok(Self {
+            table_name: report.table_name.parse()?,
+            scanned_at: UnixTimestamp::from_millis(report.scanned_at_ms.try_into()?),
+            documents_sampled: report.documents_sampled.try_into()?,
+            fields: report
+                .fields
+                .into_iter()
+                .map(|field| {
+                    anyhow::Ok(PiiField {
+                        field_path: field.field_path,
+                        kind: field.kind.parse()?,
+                        matches: field.matches.try_into()?,
+                        documents_with_field: field.documents_with_field.try_into()?,
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?,
+        })
+    }
+}
+
+codegen_convex_serialization!(PiiReport, SerializedPiiReport);
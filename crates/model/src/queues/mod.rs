@@ -0,0 +1,758 @@
+//! Queues: named message queues delivered through the scheduler. Every
+//! message is delivered to each of the queue's consumer groups, one message
+//! at a time per partition key, in the order they were enqueued. A message
+//! moves on to the next one in the same transaction that completes its
+//! delivery, and failed deliveries are retried, so each message is delivered
+//! at least once.
+use std::sync::LazyLock;
+
+use common::{
+    components::{
+        CanonicalizedComponentFunctionPath,
+        ComponentPath,
+    },
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+        CREATION_TIME_FIELD_PATH,
+    },
+    execution_context::{
+        ExecutionContext,
+        ExecutionId,
+    },
+    query::{
+        IndexRange,
+        IndexRangeExpression,
+        Order,
+        Query,
+    },
+    runtime::{
+        Runtime,
+        UnixTimestamp,
+    },
+    types::IndexName,
+    RequestId,
+};
+use database::{
+    defaults::system_index,
+    require_admin_or_system,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use errors::ErrorMetadata;
+use serde_json::{
+    json,
+    Value as JsonValue,
+};
+use sync_types::CanonicalizedUdfPath;
+use value::{
+    ConvexArray,
+    ConvexValue,
+    DeveloperDocumentId,
+    FieldPath,
+    ResolvedDocumentId,
+    TableName,
+    TableNamespace,
+};
+
+use self::types::{
+    QueueConfig,
+    QueueMessage,
+    QueueMessageState,
+    MAX_QUEUE_IDENTIFIER_LEN,
+};
+use crate::{
+    scheduled_jobs::{
+        types::ScheduledJobState,
+        SchedulerModel,
+    },
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+pub static QUEUES_TABLE: LazyLock<TableName> =
+    LazyLock::new(|| "_queues".parse().expect("Invalid built-in queues table"));
+
+pub static QUEUES_INDEX_BY_NAME: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&QUEUES_TABLE, "by_name"));
+
+pub static QUEUE_MESSAGES_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_queue_messages"
+        .parse()
+        .expect("Invalid built-in queue_messages table")
+});
+
+pub static QUEUE_MESSAGES_INDEX_BY_PARTITION: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&QUEUE_MESSAGES_TABLE, "by_partition"));
+
+pub static QUEUE_MESSAGES_INDEX_BY_JOB_ID: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&QUEUE_MESSAGES_TABLE, "by_job_id"));
+
+static NAME_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "name".parse().expect("invalid name field"));
+
+static QUEUE_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "queue".parse().expect("invalid queue field"));
+
+static GROUP_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "group".parse().expect("invalid group field"));
+
+static PARTITION_KEY_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "partitionKey".parse().expect("invalid partitionKey field"));
+
+static SEQUENCE_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "sequence".parse().expect("invalid sequence field"));
+
+static JOB_ID_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "jobId".parse().expect("invalid jobId field"));
+
+pub struct QueuesTable;
+impl SystemTable for QueuesTable {
+    fn table_name(&self) -> &'static TableName {
+        &QUEUES_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![SystemIndex {
+            name: QUEUES_INDEX_BY_NAME.clone(),
+            fields: vec![NAME_FIELD.clone(), CREATION_TIME_FIELD_PATH.clone()]
+                .try_into()
+                .unwrap(),
+        }]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<QueueConfig>::try_from(document).map(|_| ())
+    }
+}
+
+pub struct QueueMessagesTable;
+impl SystemTable for QueueMessagesTable {
+    fn table_name(&self) -> &'static TableName {
+        &QUEUE_MESSAGES_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![
+            SystemIndex {
+                name: QUEUE_MESSAGES_INDEX_BY_PARTITION.clone(),
+                fields: vec![
+                    QUEUE_FIELD.clone(),
+                    GROUP_FIELD.clone(),
+                    PARTITION_KEY_FIELD.clone(),
+                    SEQUENCE_FIELD.clone(),
+                ]
+                .try_into()
+                .unwrap(),
+            },
+            SystemIndex {
+                name: QUEUE_MESSAGES_INDEX_BY_JOB_ID.clone(),
+                fields: vec![JOB_ID_FIELD.clone(), CREATION_TIME_FIELD_PATH.clone()]
+                    .try_into()
+                    .unwrap(),
+            },
+        ]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<QueueMessage>::try_from(document).map(|_| ())
+    }
+}
+
+fn queue_not_found(name: &str) -> ErrorMetadata {
+    ErrorMetadata::not_found("QueueNotFound", format!("There's no queue named {name:?}."))
+}
+
+pub struct QueuesModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> QueuesModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    /// All the queues, ordered by name.
+    pub async fn list(&mut self) -> anyhow::Result<Vec<ParsedDocument<QueueConfig>>> {
+        require_admin_or_system(self.tx.identity(), "list_queues")?;
+        let index_range = IndexRange {
+            index_name: QUEUES_INDEX_BY_NAME.clone(),
+            range: vec![],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        let mut queues = vec![];
+        while let Some(document) = query_stream.next(self.tx, None).await? {
+            queues.push(document.try_into()?);
+        }
+        Ok(queues)
+    }
+
+    async fn get_queue(
+        &mut self,
+        name: &str,
+    ) -> anyhow::Result<Option<ParsedDocument<QueueConfig>>> {
+        let index_range = IndexRange {
+            index_name: QUEUES_INDEX_BY_NAME.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                NAME_FIELD.clone(),
+                ConvexValue::try_from(name.to_string())?.into(),
+            )],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        query_stream
+            .expect_at_most_one(self.tx)
+            .await?
+            .map(ParsedDocument::try_from)
+            .transpose()
+    }
+
+    /// Creates the queue `config.name`, or replaces its consumer groups and
+    /// delivery settings. Messages already enqueued aren't delivered to new
+    /// groups, and messages for groups that are removed are dead-lettered.
+    pub async fn set(&mut self, mut config: QueueConfig) -> anyhow::Result<()> {
+        require_admin_or_system(self.tx.identity(), "set_queue")?;
+        match self.get_queue(&config.name).await? {
+            Some(existing) => {
+                config.next_sequence = existing.next_sequence;
+                SystemMetadataModel::new_global(self.tx)
+                    .replace(existing.id(), config.try_into()?)
+                    .await?;
+            },
+            None => {
+                SystemMetadataModel::new_global(self.tx)
+                    .insert(&QUEUES_TABLE, config.try_into()?)
+                    .await?;
+            },
+        }
+        Ok(())
+    }
+
+    /// Removes a queue so nothing more can be enqueued. Its messages are left
+    /// for [`Self::purge`] to delete.
+    pub async fn delete(&mut self, name: &str) -> anyhow::Result<()> {
+        require_admin_or_system(self.tx.identity(), "delete_queue")?;
+        if let Some(existing) = self.get_queue(name).await? {
+            SystemMetadataModel::new_global(self.tx)
+                .delete(existing.id())
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Adds a message to the end of its partition for each of the queue's
+    /// consumer groups, and returns the message's sequence number. Unlike the
+    /// other methods this is called by user functions, so it doesn't need an
+    /// admin.
+    pub async fn enqueue(
+        &mut self,
+        name: &str,
+        partition_key: String,
+        body: JsonValue,
+    ) -> anyhow::Result<u64> {
+        anyhow::ensure!(
+            partition_key.len() <= MAX_QUEUE_IDENTIFIER_LEN,
+            ErrorMetadata::bad_request(
+                "InvalidQueueMessage",
+                format!("A partition key can be at most {MAX_QUEUE_IDENTIFIER_LEN} bytes long."),
+            )
+        );
+        // Check the body can be passed to the consumer before it's enqueued.
+        ConvexValue::try_from(body.clone()).map_err(|e| {
+            ErrorMetadata::bad_request(
+                "InvalidQueueMessage",
+                format!("A queue message must be a valid Convex value: {e}"),
+            )
+        })?;
+        let Some(existing) = self.get_queue(name).await? else {
+            anyhow::bail!(queue_not_found(name));
+        };
+        // Every enqueue writes the queue's sequence number, so concurrent
+        // enqueues conflict and their messages keep the order they commit in.
+        let (queue_id, mut config) = existing.into_id_and_value();
+        let sequence = config.next_sequence;
+        config.next_sequence += 1;
+        SystemMetadataModel::new_global(self.tx)
+            .replace(queue_id, config.clone().try_into()?)
+            .await?;
+        let body = serde_json::to_string(&body)?;
+        for consumer in &config.consumers {
+            let message = QueueMessage {
+                queue: config.name.clone(),
+                group: consumer.group.clone(),
+                partition_key: partition_key.clone(),
+                sequence,
+                body: body.clone(),
+                state: QueueMessageState::Pending,
+                job_id: None,
+                deliveries: 0,
+                delivered_at: None,
+                last_error: None,
+            };
+            SystemMetadataModel::new_global(self.tx)
+                .insert(&QUEUE_MESSAGES_TABLE, message.try_into()?)
+                .await?;
+            self.deliver_head(&config, &consumer.group, &partition_key)
+                .await?;
+        }
+        Ok(sequence)
+    }
+
+    /// Up to `limit` of the queue's messages, ordered by consumer group,
+    /// partition key and sequence number.
+    pub async fn list_messages(
+        &mut self,
+        name: &str,
+        group: Option<&str>,
+        limit: usize,
+    ) -> anyhow::Result<Vec<ParsedDocument<QueueMessage>>> {
+        require_admin_or_system(self.tx.identity(), "list_queue_messages")?;
+        let mut query_stream = self.messages_query(name, group)?;
+        let mut messages = vec![];
+        while messages.len() < limit
+            && let Some(document) = query_stream.next(self.tx, None).await?
+        {
+            messages.push(document.try_into()?);
+        }
+        Ok(messages)
+    }
+
+    /// Deletes up to `limit` of the queue's messages, canceling their
+    /// deliveries, and returns how many were deleted.
+    pub async fn purge(&mut self, name: &str, limit: usize) -> anyhow::Result<usize> {
+        require_admin_or_system(self.tx.identity(), "purge_queue")?;
+        let mut query_stream = self.messages_query(name, None)?;
+        let mut purged = 0;
+        while purged < limit
+            && let Some(document) = query_stream.next(self.tx, None).await?
+        {
+            let (id, message) =
+                ParsedDocument::<QueueMessage>::try_from(document)?.into_id_and_value();
+            // Deleted first so canceling the delivery doesn't retry it.
+            SystemMetadataModel::new_global(self.tx).delete(id).await?;
+            if let Some(job_id) = message.job_id {
+                self.cancel_delivery(job_id).await?;
+            }
+            purged += 1;
+        }
+        Ok(purged)
+    }
+
+    /// Puts up to `limit` of the queue's dead-lettered messages back in their
+    /// partitions with fresh deliveries, and returns how many were redriven.
+    /// They're delivered ahead of any pending messages that were enqueued
+    /// after them.
+    pub async fn redrive(
+        &mut self,
+        name: &str,
+        group: Option<&str>,
+        limit: usize,
+    ) -> anyhow::Result<usize> {
+        require_admin_or_system(self.tx.identity(), "redrive_queue")?;
+        let Some(config) = self.get_queue(name).await?.map(ParsedDocument::into_value) else {
+            anyhow::bail!(queue_not_found(name));
+        };
+        let mut query_stream = self.messages_query(name, group)?;
+        let mut dead_lettered = vec![];
+        while dead_lettered.len() < limit
+            && let Some(document) = query_stream.next(self.tx, None).await?
+        {
+            let message = ParsedDocument::<QueueMessage>::try_from(document)?;
+            if message.state == QueueMessageState::DeadLettered {
+                dead_lettered.push(message);
+            }
+        }
+        let redriven = dead_lettered.len();
+        for message in dead_lettered {
+            let (id, mut message) = message.into_id_and_value();
+            message.state = QueueMessageState::Pending;
+            message.deliveries = 0;
+            SystemMetadataModel::new_global(self.tx)
+                .replace(id, message.clone().try_into()?)
+                .await?;
+            self.deliver_head(&config, &message.group, &message.partition_key)
+                .await?;
+        }
+        Ok(redriven)
+    }
+
+    /// Moves the message delivered by the scheduled job `job_id`, if any, on
+    /// to the next message in its partition, or retries it if the delivery
+    /// failed. Called in the transaction that completes the job.
+    pub async fn message_finished(
+        &mut self,
+        job_id: ResolvedDocumentId,
+        state: &ScheduledJobState,
+    ) -> anyhow::Result<()> {
+        let index_range = IndexRange {
+            index_name: QUEUE_MESSAGES_INDEX_BY_JOB_ID.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                JOB_ID_FIELD.clone(),
+                ConvexValue::try_from(job_id.developer_id.encode())?.into(),
+            )],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        let Some(document) = query_stream.expect_at_most_one(self.tx).await? else {
+            return Ok(());
+        };
+        let (id, mut message) =
+            ParsedDocument::<QueueMessage>::try_from(document)?.into_id_and_value();
+        let Some(config) = self
+            .get_queue(&message.queue)
+            .await?
+            .map(ParsedDocument::into_value)
+        else {
+            // The queue was deleted while the message was in flight.
+            SystemMetadataModel::new_global(self.tx).delete(id).await?;
+            return Ok(());
+        };
+        let error = match state {
+            ScheduledJobState::Success => {
+                SystemMetadataModel::new_global(self.tx).delete(id).await?;
+                return self
+                    .deliver_head(&config, &message.group, &message.partition_key)
+                    .await;
+            },
+            ScheduledJobState::Failed(error) => error.clone(),
+            ScheduledJobState::Canceled => "Delivery was canceled.".to_string(),
+            ScheduledJobState::Pending | ScheduledJobState::InProgress => return Ok(()),
+        };
+        message.last_error = Some(error);
+        message.job_id = None;
+        let consumer = config.consumer(&message.group);
+        match consumer {
+            Some(consumer) if message.deliveries < config.max_deliveries => {
+                // The message stays hidden until its visibility timeout has
+                // passed since it was last delivered.
+                let now = self.tx.runtime().unix_timestamp();
+                let ts = message
+                    .delivered_at
+                    .map_or(now, |delivered_at| delivered_at + config.visibility_timeout)
+                    .max(now);
+                let function = consumer.function.clone();
+                self.deliver(id, message, function, ts).await
+            },
+            _ => {
+                let group = message.group.clone();
+                let partition_key = message.partition_key.clone();
+                message.state = QueueMessageState::DeadLettered;
+                SystemMetadataModel::new_global(self.tx)
+                    .replace(id, message.try_into()?)
+                    .await?;
+                self.deliver_head(&config, &group, &partition_key).await
+            },
+        }
+    }
+
+    /// Delivers the first message in the partition that isn't dead-lettered,
+    /// unless it's already in flight.
+    async fn deliver_head(
+        &mut self,
+        config: &QueueConfig,
+        group: &str,
+        partition_key: &str,
+    ) -> anyhow::Result<()> {
+        let index_range = IndexRange {
+            index_name: QUEUE_MESSAGES_INDEX_BY_PARTITION.clone(),
+            range: vec![
+                IndexRangeExpression::Eq(
+                    QUEUE_FIELD.clone(),
+                    ConvexValue::try_from(config.name.clone())?.into(),
+                ),
+                IndexRangeExpression::Eq(
+                    GROUP_FIELD.clone(),
+                    ConvexValue::try_from(group.to_string())?.into(),
+                ),
+                IndexRangeExpression::Eq(
+                    PARTITION_KEY_FIELD.clone(),
+                    ConvexValue::try_from(partition_key.to_string())?.into(),
+                ),
+            ],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        while let Some(document) = query_stream.next(self.tx, None).await? {
+            let (id, mut message) =
+                ParsedDocument::<QueueMessage>::try_from(document)?.into_id_and_value();
+            match message.state {
+                QueueMessageState::DeadLettered => continue,
+                QueueMessageState::InFlight => return Ok(()),
+                QueueMessageState::Pending => {},
+            }
+            let Some(consumer) = config.consumer(group) else {
+                message.state = QueueMessageState::DeadLettered;
+                message.last_error = Some(format!("Consumer group {group:?} was removed."));
+                SystemMetadataModel::new_global(self.tx)
+                    .replace(id, message.try_into()?)
+                    .await?;
+                continue;
+            };
+            let function = consumer.function.clone();
+            let now = self.tx.runtime().unix_timestamp();
+            return self.deliver(id, message, function, now).await;
+        }
+        Ok(())
+    }
+
+    /// Schedules `function` to receive the message at `ts`.
+    async fn deliver(
+        &mut self,
+        id: ResolvedDocumentId,
+        mut message: QueueMessage,
+        function: CanonicalizedUdfPath,
+        ts: UnixTimestamp,
+    ) -> anyhow::Result<()> {
+        let body: JsonValue = serde_json::from_str(&message.body)?;
+        let args = json!({
+            "queue": message.queue,
+            "group": message.group,
+            "partitionKey": message.partition_key,
+            "messageId": DeveloperDocumentId::from(id).encode(),
+            "body": body,
+            "attempt": message.deliveries + 1,
+        });
+        let job_id = SchedulerModel::new(self.tx, TableNamespace::root_component())
+            .schedule(
+                CanonicalizedComponentFunctionPath {
+                    component: ComponentPath::root(),
+                    udf_path: function,
+                },
+                ConvexArray::try_from(vec![ConvexValue::try_from(args)?])?,
+                ts,
+                ExecutionContext::new_from_parts(RequestId::new(), ExecutionId::new(), None, true),
+            )
+            .await?;
+        message.state = QueueMessageState::InFlight;
+        message.job_id = Some(job_id.into());
+        message.deliveries += 1;
+        message.delivered_at = Some(ts);
+        SystemMetadataModel::new_global(self.tx)
+            .replace(id, message.try_into()?)
+            .await?;
+        Ok(())
+    }
+
+    async fn cancel_delivery(&mut self, job_id: DeveloperDocumentId) -> anyhow::Result<()> {
+        let namespace = TableNamespace::root_component();
+        let job_id = job_id.to_resolved(
+            self.tx
+                .table_mapping()
+                .namespace(namespace)
+                .number_to_tablet(),
+        )?;
+        SchedulerModel::new(self.tx, namespace).cancel(job_id).await
+    }
+
+    fn messages_query(
+        &mut self,
+        name: &str,
+        group: Option<&str>,
+    ) -> anyhow::Result<ResolvedQuery<RT>> {
+        let mut range = vec![IndexRangeExpression::Eq(
+            QUEUE_FIELD.clone(),
+            ConvexValue::try_from(name.to_string())?.into(),
+        )];
+        if let Some(group) = group {
+            range.push(IndexRangeExpression::Eq(
+                GROUP_FIELD.clone(),
+                ConvexValue::try_from(group.to_string())?.into(),
+            ));
+        }
+        let index_range = IndexRange {
+            index_name: QUEUE_MESSAGES_INDEX_BY_PARTITION.clone(),
+            range,
+            order: Order::Asc,
+        };
+        ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use database::{
+        test_helpers::DbFixtures,
+        Transaction,
+    };
+    use keybroker::Identity;
+    use runtime::testing::TestRuntime;
+    use serde_json::json;
+    use value::TableNamespace;
+
+    use super::{
+        types::{
+            QueueConfig,
+            QueueConsumer,
+            QueueMessageState,
+        },
+        QueuesModel,
+    };
+    use crate::{
+        scheduled_jobs::{
+            types::ScheduledJobState,
+            SchedulerModel,
+        },
+        test_helpers::DbFixturesWithModel,
+    };
+
+    fn consumer(group: &str) -> anyhow::Result<QueueConsumer> {
+        Ok(QueueConsumer {
+            group: group.to_string(),
+            function: format!("emails:{group}").parse()?,
+        })
+    }
+
+    /// Completes the delivery of the first message in flight to `group`.
+    async fn complete_delivery(
+        tx: &mut Transaction<TestRuntime>,
+        group: &str,
+        state: ScheduledJobState,
+    ) -> anyhow::Result<()> {
+        let messages = QueuesModel::new(tx)
+            .list_messages("emails", Some(group), 100)
+            .await?;
+        let job_id = messages
+            .iter()
+            .find(|message| message.state == QueueMessageState::InFlight)
+            .and_then(|message| message.job_id)
+            .unwrap();
+        let namespace = TableNamespace::root_component();
+        let job_id =
+            job_id.to_resolved(tx.table_mapping().namespace(namespace).number_to_tablet())?;
+        SchedulerModel::new(tx, namespace)
+            .complete(job_id, state)
+            .await
+    }
+
+    async fn states(
+        tx: &mut Transaction<TestRuntime>,
+        group: &str,
+    ) -> anyhow::Result<Vec<(u64, QueueMessageState)>> {
+        let messages = QueuesModel::new(tx)
+            .list_messages("emails", Some(group), 100)
+            .await?;
+        Ok(messages
+            .into_iter()
+            .map(|message| (message.sequence, message.state.clone()))
+            .collect())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_queue_delivers_partitions_in_order(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin(Identity::system()).await?;
+        let config = QueueConfig::new(
+            "emails".to_string(),
+            vec![consumer("send")?, consumer("audit")?],
+            None,
+            Some(2),
+        )?;
+        QueuesModel::new(&mut tx).set(config).await?;
+        for (partition_key, n) in [("a", 1), ("a", 2), ("b", 3)] {
+            QueuesModel::new(&mut tx)
+                .enqueue("emails", partition_key.to_string(), json!({"n": n}))
+                .await?;
+        }
+
+        // Each group gets the head of each partition.
+        assert_eq!(
+            states(&mut tx, "send").await?,
+            vec![
+                (0, QueueMessageState::InFlight),
+                (1, QueueMessageState::Pending),
+                (2, QueueMessageState::InFlight),
+            ]
+        );
+
+        // A failed delivery is retried, then dead-lettered, and the partition
+        // moves on.
+        complete_delivery(&mut tx, "send", ScheduledJobState::Failed("oops".into())).await?;
+        assert_eq!(
+            states(&mut tx, "send").await?[0].1,
+            QueueMessageState::InFlight
+        );
+        complete_delivery(&mut tx, "send", ScheduledJobState::Failed("oops".into())).await?;
+        assert_eq!(
+            states(&mut tx, "send").await?,
+            vec![
+                (0, QueueMessageState::DeadLettered),
+                (1, QueueMessageState::InFlight),
+                (2, QueueMessageState::InFlight),
+            ]
+        );
+        // The other group isn't affected.
+        assert_eq!(
+            states(&mut tx, "audit").await?[1],
+            (1, QueueMessageState::Pending)
+        );
+
+        // A successful delivery removes the message.
+        complete_delivery(&mut tx, "send", ScheduledJobState::Success).await?;
+        assert_eq!(states(&mut tx, "send").await?.len(), 2);
+
+        assert_eq!(
+            QueuesModel::new(&mut tx)
+                .redrive("emails", None, 100)
+                .await?,
+            1
+        );
+        assert_eq!(QueuesModel::new(&mut tx).purge("emails", 100).await?, 5);
+        assert!(states(&mut tx, "audit").await?.is_empty());
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_enqueue_requires_queue(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin(Identity::system()).await?;
+        assert!(QueuesModel::new(&mut tx)
+            .enqueue("emails", String::new(), json!(null))
+            .await
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_queue_config_validation() -> anyhow::Result<()> {
+        let config = QueueConfig::new("emails".to_string(), vec![consumer("send")?], None, None)?;
+        assert_eq!(config.visibility_timeout, Duration::from_secs(30));
+        assert!(QueueConfig::new("emails".to_string(), vec![], None, None).is_err());
+        assert!(QueueConfig::new(
+            "emails".to_string(),
+            vec![consumer("send")?, consumer("send")?],
+            None,
+            None
+        )
+        .is_err());
+        assert!(
+            QueueConfig::new("emails".to_string(), vec![consumer("send")?], None, Some(0)).is_err()
+        );
+        Ok(())
+    }
+}
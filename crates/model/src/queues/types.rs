@@ -0,0 +1,278 @@
+use std::{
+    collections::BTreeSet,
+    time::Duration,
+};
+
+use common::runtime::UnixTimestamp;
+use errors::ErrorMetadata;
+#[cfg(any(test, feature = "testing"))]
+use proptest::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use sync_types::CanonicalizedUdfPath;
+use value::{
+    codegen_convex_serialization,
+    DeveloperDocumentId,
+};
+
+/// The most consumer groups a queue can have.
+pub const MAX_QUEUE_CONSUMERS: usize = 16;
+/// The most times a message can be delivered to a consumer group before it's
+/// dead-lettered.
+pub const MAX_QUEUE_DELIVERIES: u32 = 100;
+/// The longest a consumer group waits before redelivering a message whose
+/// delivery failed.
+pub const MAX_QUEUE_VISIBILITY_TIMEOUT: Duration = Duration::from_secs(12 * 60 * 60);
+/// The longest a queue name, consumer group or partition key can be, in
+/// bytes.
+pub const MAX_QUEUE_IDENTIFIER_LEN: usize = 256;
+
+pub const DEFAULT_QUEUE_VISIBILITY_TIMEOUT: Duration = Duration::from_secs(30);
+pub const DEFAULT_QUEUE_MAX_DELIVERIES: u32 = 5;
+
+/// A named queue. Every message enqueued is delivered to each of the
+/// queue's consumer groups, in order within each partition key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct QueueConfig {
+    pub name: String,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "prop::collection::vec(any::<QueueConsumer>(), 1..4)")
+    )]
+    pub consumers: Vec<QueueConsumer>,
+    /// How long after a delivery a message stays hidden from its consumer
+    /// group. A failed delivery is retried once this has passed.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "(1..=43_200_000u64).prop_map(Duration::from_millis)")
+    )]
+    pub visibility_timeout: Duration,
+    /// How many times a message is delivered to a consumer group before it's
+    /// dead-lettered.
+    pub max_deliveries: u32,
+    /// The sequence number of the next message enqueued, so messages keep
+    /// the order they were enqueued in even when they're enqueued by
+    /// concurrent mutations.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=i64::MAX as u64")
+    )]
+    pub next_sequence: u64,
+}
+
+/// A consumer group runs a mutation or action in the root component for
+/// each message, called with a single object holding the `queue`, `group`,
+/// `partitionKey`, `messageId`, `body` and delivery `attempt`. A delivery
+/// succeeds once the function does.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct QueueConsumer {
+    pub group: String,
+    pub function: CanonicalizedUdfPath,
+}
+
+fn invalid_queue(msg: impl Into<String>) -> ErrorMetadata {
+    ErrorMetadata::bad_request("InvalidQueue", msg.into())
+}
+
+pub fn validate_queue_identifier(kind: &str, identifier: &str) -> anyhow::Result<()> {
+    anyhow::ensure!(
+        !identifier.is_empty() && identifier.len() <= MAX_QUEUE_IDENTIFIER_LEN,
+        invalid_queue(format!(
+            "A {kind} must be between 1 and {MAX_QUEUE_IDENTIFIER_LEN} bytes long."
+        ))
+    );
+    Ok(())
+}
+
+impl QueueConfig {
+    pub fn new(
+        name: String,
+        consumers: Vec<QueueConsumer>,
+        visibility_timeout: Option<Duration>,
+        max_deliveries: Option<u32>,
+    ) -> anyhow::Result<Self> {
+        validate_queue_identifier("queue name", &name)?;
+        anyhow::ensure!(
+            !consumers.is_empty() && consumers.len() <= MAX_QUEUE_CONSUMERS,
+            invalid_queue(format!(
+                "A queue must have between 1 and {MAX_QUEUE_CONSUMERS} consumer groups."
+            ))
+        );
+        let mut groups = BTreeSet::new();
+        for consumer in &consumers {
+            validate_queue_identifier("consumer group", &consumer.group)?;
+            anyhow::ensure!(
+                groups.insert(consumer.group.as_str()),
+                invalid_queue(format!(
+                    "Consumer group {:?} is defined more than once.",
+                    consumer.group
+                ))
+            );
+            anyhow::ensure!(
+                !consumer.function.is_system(),
+                invalid_queue(format!(
+                    "Consumer group {:?} can't run the system function {}.",
+                    consumer.group,
+                    String::from(consumer.function.clone())
+                ))
+            );
+        }
+        let visibility_timeout = visibility_timeout.unwrap_or(DEFAULT_QUEUE_VISIBILITY_TIMEOUT);
+        anyhow::ensure!(
+            !visibility_timeout.is_zero() && visibility_timeout <= MAX_QUEUE_VISIBILITY_TIMEOUT,
+            invalid_queue(format!(
+                "A queue's visibility timeout must be between 1 and {} milliseconds.",
+                MAX_QUEUE_VISIBILITY_TIMEOUT.as_millis()
+            ))
+        );
+        let max_deliveries = max_deliveries.unwrap_or(DEFAULT_QUEUE_MAX_DELIVERIES);
+        anyhow::ensure!(
+            (1..=MAX_QUEUE_DELIVERIES).contains(&max_deliveries),
+            invalid_queue(format!(
+                "A queue's max deliveries must be between 1 and {MAX_QUEUE_DELIVERIES}."
+            ))
+        );
+        Ok(Self {
+            name,
+            consumers,
+            visibility_timeout,
+            max_deliveries,
+            next_sequence: 0,
+        })
+    }
+
+    pub fn consumer(&self, group: &str) -> Option<&QueueConsumer> {
+        self.consumers
+            .iter()
+            .find(|consumer| consumer.group == group)
+    }
+}
+
+/// A message waiting to be delivered to one consumer group. Enqueuing a
+/// message adds one of these for each of the queue's groups, and it's
+/// deleted once its delivery succeeds.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct QueueMessage {
+    pub queue: String,
+    pub group: String,
+    pub partition_key: String,
+    /// Orders the message within its partition.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=i64::MAX as u64")
+    )]
+    pub sequence: u64,
+    /// The JSON-encoded message.
+    pub body: String,
+    pub state: QueueMessageState,
+    /// The `_scheduled_jobs` document delivering the message, while it's in
+    /// flight.
+    pub job_id: Option<DeveloperDocumentId>,
+    /// How many times the message has been delivered to the group.
+    pub deliveries: u32,
+    /// When the message was last delivered.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "proptest::option::of((0..=i64::MAX as \
+                             u64).prop_map(UnixTimestamp::from_millis))")
+    )]
+    pub delivered_at: Option<UnixTimestamp>,
+    /// The error from the last delivery that failed.
+    pub last_error: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub enum QueueMessageState {
+    /// Waiting for the messages ahead of it in its partition.
+    Pending,
+    /// Being delivered to the consumer group.
+    InFlight,
+    /// Every delivery failed, so the partition moved on without it.
+    DeadLettered,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedQueueMessage {
+    queue: String,
+    group: String,
+    partition_key: String,
+    sequence: i64,
+    body: String,
+    state: SerializedQueueMessageState,
+    job_id: Option<String>,
+    deliveries: i64,
+    delivered_at_ms: Option<i64>,
+    last_error: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+enum SerializedQueueMessageState {
+    Pending,
+    InFlight,
+    DeadLettered,
+}
+
+impl TryFrom<QueueMessage> for SerializedQueueMessage {
+    type Error = anyhow::Error;
+
+    fn try_from(message: QueueMessage) -> anyhow::Result<Self> {
+        Ok(Self {
+            queue: message.queue,
+            group: message.group,
+            partition_key: message.partition_key,
+            sequence: message.sequence.try_into()?,
+            body: message.body,
+            state: match message.state {
+                QueueMessageState::Pending => SerializedQueueMessageState::Pending,
+                QueueMessageState::InFlight => SerializedQueueMessageState::InFlight,
+                QueueMessageState::DeadLettered => SerializedQueueMessageState::DeadLettered,
+            },
+            job_id: message.job_id.map(|id| id.encode()),
+            deliveries: message.deliveries.into(),
+            delivered_at_ms: message
+                .delivered_at
+                .map(|ts| anyhow::Ok(i64::try_from(ts.as_ms_since_epoch()?)?))
+                .transpose()?,
+            last_error: message.last_error,
+        })
+    }
+}
+
+impl TryFrom<SerializedQueueMessage> for QueueMessage {
+    type Error = anyhow::Error;
+
+    fn try_from(message: SerializedQueueMessage) -> anyhow::Result<Self> {
+        Ok(Self {
+            queue: message.queue,
+            group: message.group,
+            partition_key: message.partition_key,
+            sequence: message.sequence.try_into()?,
+            body: message.body,
+            state: match message.state {
+                SerializedQueueMessageState::Pending => QueueMessageState::Pending,
+                SerializedQueueMessageState::InFlight => QueueMessageState::InFlight,
+                SerializedQueueMessageState::DeadLettered => QueueMessageState::DeadLettered,
+            },
+            job_id: message
+                .job_id
+                .map(|id| DeveloperDocumentId::decode(&id))
+                .transpose()?,
+            deliveries: message.deliveries.try_into()?,
+            delivered_at: message
+                .delivered_at_ms
+                .map(|ms| anyhow::Ok(UnixTimestamp::from_millis(u64::try_from(ms)?)))
+                .transpose()?,
+            last_error: message.last_error,
+        })
+    }
+}
+
+codegen_convex_serialization!(QueueMessage, SerializedQueueMessage);
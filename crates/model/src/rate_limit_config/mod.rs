@@ -0,0 +1,149 @@
+use std::sync::LazyLock;
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    query::{
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+};
+use database::{
+    unauthorized_error,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use value::{
+    TableName,
+    TableNamespace,
+};
+
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+use types::RateLimitConfig;
+
+pub static RATE_LIMIT_CONFIG_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_rate_limit_config"
+        .parse()
+        .expect("Invalid built-in rate_limit_config table")
+});
+
+pub struct RateLimitConfigTable;
+impl SystemTable for RateLimitConfigTable {
+    fn table_name(&self) -> &'static TableName {
+        &RATE_LIMIT_CONFIG_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<RateLimitConfig>::try_from(document).map(|_| ())
+    }
+}
+
+/// The deployment's rate limits for public endpoints, which has at most one
+/// row.
+pub struct RateLimitConfigModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> RateLimitConfigModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    pub async fn get(&mut self) -> anyhow::Result<Option<ParsedDocument<RateLimitConfig>>> {
+        let query = Query::full_table_scan(RATE_LIMIT_CONFIG_TABLE.clone(), Order::Asc);
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        query_stream
+            .expect_at_most_one(self.tx)
+            .await?
+            .map(|document| document.try_into())
+            .transpose()
+    }
+
+    /// Replaces the rate limits, or removes them if `config` is `None`.
+    pub async fn set(&mut self, config: Option<RateLimitConfig>) -> anyhow::Result<()> {
+        if !(self.tx.identity().is_system() || self.tx.identity().is_admin()) {
+            anyhow::bail!(unauthorized_error("set_rate_limit_config"));
+        }
+        let existing = self.get().await?;
+        match (existing, config) {
+            (Some(existing), Some(config)) => {
+                SystemMetadataModel::new_global(self.tx)
+                    .replace(existing.id(), config.try_into()?)
+                    .await?;
+            },
+            (Some(existing), None) => {
+                SystemMetadataModel::new_global(self.tx)
+                    .delete(existing.id())
+                    .await?;
+            },
+            (None, Some(config)) => {
+                SystemMetadataModel::new_global(self.tx)
+                    .insert(&RATE_LIMIT_CONFIG_TABLE, config.try_into()?)
+                    .await?;
+            },
+            (None, None) => {},
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use database::test_helpers::DbFixtures;
+    use keybroker::Identity;
+    use runtime::testing::TestRuntime;
+
+    use super::{
+        types::{
+            RateLimitConfig,
+            RateLimitKey,
+            RateLimitRule,
+        },
+        RateLimitConfigModel,
+    };
+    use crate::test_helpers::DbFixturesWithModel;
+
+    #[convex_macro::test_runtime]
+    async fn test_set_rate_limit_config(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin(Identity::system()).await?;
+        assert!(RateLimitConfigModel::new(&mut tx).get().await?.is_none());
+        let config = RateLimitConfig::new(vec![RateLimitRule {
+            path_prefix: "/api/mutation".to_string(),
+            key: RateLimitKey::Ip,
+            requests: 100,
+            period: Duration::from_secs(60),
+        }])?;
+        RateLimitConfigModel::new(&mut tx)
+            .set(Some(config.clone()))
+            .await?;
+        let stored = RateLimitConfigModel::new(&mut tx)
+            .get()
+            .await?
+            .unwrap()
+            .into_value();
+        assert_eq!(stored, config);
+        RateLimitConfigModel::new(&mut tx).set(None).await?;
+        assert!(RateLimitConfigModel::new(&mut tx).get().await?.is_none());
+
+        let mut tx = db.begin(Identity::Unknown).await?;
+        assert!(RateLimitConfigModel::new(&mut tx).set(None).await.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,220 @@
+use std::{
+    fmt,
+    str::FromStr,
+    time::Duration,
+};
+
+use errors::ErrorMetadata;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use value::codegen_convex_serialization;
+
+/// The most rules a deployment can have, since every request is checked
+/// against each of them.
+pub const MAX_RATE_LIMIT_RULES: usize = 32;
+
+/// A deployment's rate limits for its public endpoints: the query, mutation
+/// and action APIs, the sync websocket, file storage and HTTP actions.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct RateLimitConfig {
+    /// A request must be allowed by every rule whose path prefix it matches.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(
+            strategy = "proptest::collection::vec(proptest::prelude::any::<RateLimitRule>(), 0..4)"
+        )
+    )]
+    pub rules: Vec<RateLimitRule>,
+}
+
+impl RateLimitConfig {
+    pub fn new(rules: Vec<RateLimitRule>) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            rules.len() <= MAX_RATE_LIMIT_RULES,
+            ErrorMetadata::bad_request(
+                "InvalidRateLimitConfig",
+                format!("At most {MAX_RATE_LIMIT_RULES} rate limit rules are allowed"),
+            )
+        );
+        for rule in &rules {
+            anyhow::ensure!(
+                rule.path_prefix.starts_with('/'),
+                ErrorMetadata::bad_request(
+                    "InvalidRateLimitConfig",
+                    format!(
+                        "Invalid path prefix {:?}. Path prefixes look like \"/api/mutation\" or \
+                         \"/http/\".",
+                        rule.path_prefix
+                    ),
+                )
+            );
+            anyhow::ensure!(
+                rule.requests > 0 && !rule.period.is_zero(),
+                ErrorMetadata::bad_request(
+                    "InvalidRateLimitConfig",
+                    format!(
+                        "The rule for {} must allow at least one request per period",
+                        rule.path_prefix
+                    ),
+                )
+            );
+        }
+        Ok(Self { rules })
+    }
+}
+
+/// Allows `requests` requests per `period` to paths starting with
+/// `path_prefix`, for each value of `key`. Requests can use up all of the
+/// period's allowance at once, after which it refills evenly over the period.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct RateLimitRule {
+    pub path_prefix: String,
+    pub key: RateLimitKey,
+    #[cfg_attr(any(test, feature = "testing"), proptest(strategy = "1..=10_000u32"))]
+    pub requests: u32,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(
+            strategy = "proptest::strategy::Strategy::prop_map(1..=86_400_000u64, \
+                        Duration::from_millis)"
+        )
+    )]
+    pub period: Duration,
+}
+
+/// What a rule's allowance is shared between.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub enum RateLimitKey {
+    /// Each client IP address.
+    Ip,
+    /// Each set of credentials in the `Authorization` header. Unauthenticated
+    /// requests are limited by IP address instead.
+    Identity,
+    /// All requests to the path prefix.
+    Route,
+}
+
+impl FromStr for RateLimitKey {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "ip" => Ok(Self::Ip),
+            "identity" => Ok(Self::Identity),
+            "route" => Ok(Self::Route),
+            _ => anyhow::bail!(ErrorMetadata::bad_request(
+                "InvalidRateLimitConfig",
+                format!(
+                    "Invalid rate limit key {s:?}. Expected \"ip\", \"identity\" or \"route\"."
+                ),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for RateLimitKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Ip => write!(f, "ip"),
+            Self::Identity => write!(f, "identity"),
+            Self::Route => write!(f, "route"),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedRateLimitConfig {
+    rules: Vec<SerializedRateLimitRule>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedRateLimitRule {
+    path_prefix: String,
+    key: String,
+    requests: i64,
+    period_ms: i64,
+}
+
+impl TryFrom<RateLimitConfig> for SerializedRateLimitConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(config: RateLimitConfig) -> anyhow::Result<Self> {
+        Ok(Self {
+            rules: config
+                .rules
+                .into_iter()
+                .map(|rule| {
+                    anyhow::Ok(SerializedRateLimitRule {
+                        path_prefix: rule.path_prefix,
+                        key: rule.key.to_string(),
+                        requests: rule.requests.into(),
+                        period_ms: rule.period.as_millis().try_into()?,
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?,
+        })
+    }
+}
+
+impl TryFrom<SerializedRateLimitConfig> for RateLimitConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(config: SerializedRateLimitConfig) -> anyhow::Result<Self> {
+        Ok(Self {
+            rules: config
+                .rules
+                .into_iter()
+                .map(|rule| {
+                    anyhow::Ok(RateLimitRule {
+                        path_prefix: rule.path_prefix,
+                        key: rule.key.parse()?,
+                        requests: rule.requests.try_into()?,
+                        period: Duration::from_millis(rule.period_ms.try_into()?),
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?,
+        })
+    }
+}
+
+codegen_convex_serialization!(RateLimitConfig, SerializedRateLimitConfig);
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use errors::ErrorMetadataAnyhowExt;
+
+    use super::{
+        RateLimitConfig,
+        RateLimitKey,
+        RateLimitRule,
+    };
+
+    fn rule(path_prefix: &str, requests: u32) -> RateLimitRule {
+        RateLimitRule {
+            path_prefix: path_prefix.to_string(),
+            key: RateLimitKey::Ip,
+            requests,
+            period: Duration::from_secs(60),
+        }
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let err = RateLimitConfig::new(vec![rule("api/mutation", 10)]).unwrap_err();
+        assert_eq!(err.short_msg(), "InvalidRateLimitConfig");
+        let err = RateLimitConfig::new(vec![rule("/api/mutation", 0)]).unwrap_err();
+        assert_eq!(err.short_msg(), "InvalidRateLimitConfig");
+        let err = RateLimitConfig::new(vec![rule("/", 10); 33]).unwrap_err();
+        assert_eq!(err.short_msg(), "InvalidRateLimitConfig");
+        assert!(RateLimitConfig::new(vec![rule("/", 10); 32]).is_ok());
+    }
+}
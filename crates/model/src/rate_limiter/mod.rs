@@ -0,0 +1,479 @@
+//! Rate limits that mutations check transactionally, e.g. to allow five
+//! signups per hour from each IP address. A limit's state for each key is
+//! stored in `_rate_limiter_shards`, so checking a limit and the writes it
+//! guards commit or conflict together, and concurrent requests can't both
+//! take the last slot.
+//!
+//! Like sharded counters, a limit can split each key's allowance across
+//! several shards so that hot keys don't serialize every request on one
+//! document.
+use std::{
+    sync::LazyLock,
+    time::Duration,
+};
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    query::{
+        IndexRange,
+        IndexRangeExpression,
+        Order,
+        Query,
+    },
+    runtime::{
+        Runtime,
+        UnixTimestamp,
+    },
+    types::IndexName,
+};
+use database::{
+    defaults::system_index,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use errors::ErrorMetadata;
+use rand::Rng;
+use value::{
+    ConvexValue,
+    FieldPath,
+    TableName,
+    TableNamespace,
+};
+
+use self::types::{
+    RateLimit,
+    RateLimitKind,
+    RateLimiterShard,
+};
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+/// How many expired shards to clean up each time a new one is created.
+const MAX_EXPIRED_SHARDS_DELETED: usize = 16;
+
+pub static RATE_LIMITER_SHARDS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_rate_limiter_shards"
+        .parse()
+        .expect("Invalid built-in rate limiter shards table")
+});
+
+pub static RATE_LIMITER_SHARDS_BY_NAME_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&RATE_LIMITER_SHARDS_TABLE, "by_name_key_and_shard"));
+pub static RATE_LIMITER_SHARDS_BY_EXPIRES_AT_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&RATE_LIMITER_SHARDS_TABLE, "by_expires_at"));
+
+static NAME_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "name".parse().expect("invalid name field"));
+static KEY_FIELD: LazyLock<FieldPath> = LazyLock::new(|| "key".parse().expect("invalid key field"));
+static SHARD_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "shard".parse().expect("invalid shard field"));
+static EXPIRES_AT_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "expiresAtMs".parse().expect("invalid expiresAtMs field"));
+
+pub struct RateLimiterShardsTable;
+impl SystemTable for RateLimiterShardsTable {
+    fn table_name(&self) -> &'static TableName {
+        &RATE_LIMITER_SHARDS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![
+            SystemIndex {
+                name: RATE_LIMITER_SHARDS_BY_NAME_INDEX.clone(),
+                fields: vec![NAME_FIELD.clone(), KEY_FIELD.clone(), SHARD_FIELD.clone()]
+                    .try_into()
+                    .unwrap(),
+            },
+            SystemIndex {
+                name: RATE_LIMITER_SHARDS_BY_EXPIRES_AT_INDEX.clone(),
+                fields: vec![EXPIRES_AT_FIELD.clone()].try_into().unwrap(),
+            },
+        ]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<RateLimiterShard>::try_from(document).map(|_| ())
+    }
+}
+
+pub struct RateLimiterModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> RateLimiterModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    async fn shards(
+        &mut self,
+        name: &str,
+        key: &str,
+        shard: Option<u32>,
+    ) -> anyhow::Result<Vec<ParsedDocument<RateLimiterShard>>> {
+        let mut range = vec![
+            IndexRangeExpression::Eq(
+                NAME_FIELD.clone(),
+                ConvexValue::try_from(name.to_string())?.into(),
+            ),
+            IndexRangeExpression::Eq(
+                KEY_FIELD.clone(),
+                ConvexValue::try_from(key.to_string())?.into(),
+            ),
+        ];
+        if let Some(shard) = shard {
+            range.push(IndexRangeExpression::Eq(
+                SHARD_FIELD.clone(),
+                ConvexValue::from(i64::from(shard)).into(),
+            ));
+        }
+        let index_range = IndexRange {
+            index_name: RATE_LIMITER_SHARDS_BY_NAME_INDEX.clone(),
+            range,
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        let mut shards = vec![];
+        while let Some(doc) = query_stream.next(self.tx, None).await? {
+            shards.push(doc.try_into()?);
+        }
+        Ok(shards)
+    }
+
+    /// Takes `count` requests from `key`'s allowance under the limit `name`,
+    /// or returns how long until they would be allowed. Rejected requests
+    /// don't use up any of the allowance.
+    pub async fn limit(
+        &mut self,
+        name: &str,
+        key: &str,
+        limit: &RateLimit,
+        count: u64,
+        now: UnixTimestamp,
+    ) -> anyhow::Result<Result<(), Duration>> {
+        // The last shard has the smallest share.
+        let max_count = limit.share(
+            match limit.kind {
+                RateLimitKind::FixedWindow | RateLimitKind::SlidingWindow => limit.rate,
+                RateLimitKind::TokenBucket => limit.capacity,
+            },
+            limit.shards - 1,
+        );
+        anyhow::ensure!(
+            (1..=max_count).contains(&count),
+            ErrorMetadata::bad_request(
+                "InvalidRateLimitCount",
+                format!(
+                    "Rate limit {name} can take between 1 and {max_count} requests at once, not \
+                     {count}"
+                ),
+            )
+        );
+        let shard = self.tx.runtime().rng().gen_range(0..limit.shards);
+        let now_ms = now.as_ms_since_epoch()?;
+        match self.shards(name, key, Some(shard)).await?.pop() {
+            Some(existing) => {
+                let (id, mut state) = existing.into_id_and_value();
+                if let Err(retry_after) = apply(limit, &mut state, count, now_ms) {
+                    return Ok(Err(retry_after));
+                }
+                SystemMetadataModel::new_global(self.tx)
+                    .replace(id, state.try_into()?)
+                    .await?;
+            },
+            None => {
+                let mut state = RateLimiterShard {
+                    name: name.to_string(),
+                    key: key.to_string(),
+                    shard,
+                    // Token buckets start full.
+                    value: match limit.kind {
+                        RateLimitKind::FixedWindow | RateLimitKind::SlidingWindow => 0,
+                        RateLimitKind::TokenBucket => limit.share(limit.capacity, shard),
+                    },
+                    previous: 0,
+                    ts: now,
+                    expires_at: now,
+                };
+                if let Err(retry_after) = apply(limit, &mut state, count, now_ms) {
+                    return Ok(Err(retry_after));
+                }
+                self.delete_expired(now_ms).await?;
+                SystemMetadataModel::new_global(self.tx)
+                    .insert(&RATE_LIMITER_SHARDS_TABLE, state.try_into()?)
+                    .await?;
+            },
+        }
+        Ok(Ok(()))
+    }
+
+    /// Resets `key`'s allowance under the limit `name`, as if it had never
+    /// made any requests.
+    pub async fn reset(&mut self, name: &str, key: &str) -> anyhow::Result<()> {
+        for shard in self.shards(name, key, None).await? {
+            SystemMetadataModel::new_global(self.tx)
+                .delete(shard.id())
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Deletes some of the shards whose keys haven't made requests recently
+    /// enough to matter, so one-off keys like IP addresses don't accumulate.
+    async fn delete_expired(&mut self, now_ms: u64) -> anyhow::Result<()> {
+        let index_range = IndexRange {
+            index_name: RATE_LIMITER_SHARDS_BY_EXPIRES_AT_INDEX.clone(),
+            range: vec![IndexRangeExpression::Lt(
+                EXPIRES_AT_FIELD.clone(),
+                ConvexValue::from(i64::try_from(now_ms)?),
+            )],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        let mut expired = vec![];
+        while expired.len() < MAX_EXPIRED_SHARDS_DELETED {
+            let Some(document) = query_stream.next(self.tx, None).await? else {
+                break;
+            };
+            expired.push(document.id());
+        }
+        let mut system_model = SystemMetadataModel::new_global(self.tx);
+        for id in expired {
+            system_model.delete(id).await?;
+        }
+        Ok(())
+    }
+}
+
+fn mul_div(a: u64, b: u64, c: u64) -> u64 {
+    (u128::from(a) * u128::from(b) / u128::from(c))
+        .try_into()
+        .unwrap_or(u64::MAX)
+}
+
+fn mul_div_ceil(a: u64, b: u64, c: u64) -> u64 {
+    (u128::from(a) * u128::from(b))
+        .div_ceil(u128::from(c))
+        .try_into()
+        .unwrap_or(u64::MAX)
+}
+
+/// Takes `count` requests from a shard's allowance at `now_ms`, or returns
+/// how long until they would be allowed without changing the shard.
+fn apply(
+    limit: &RateLimit,
+    state: &mut RateLimiterShard,
+    count: u64,
+    now_ms: u64,
+) -> Result<(), Duration> {
+    let rate = limit.share(limit.rate, state.shard);
+    let period_ms = limit.period.as_millis() as u64;
+    let ts_ms = state.ts.as_ms_since_epoch().unwrap_or(0);
+    let (value, previous, ts_ms, expires_at_ms) = match limit.kind {
+        RateLimitKind::TokenBucket => {
+            let capacity = limit.share(limit.capacity, state.shard);
+            let refilled = mul_div(now_ms.saturating_sub(ts_ms), rate, period_ms);
+            let (tokens, ts_ms) = if state.value.saturating_add(refilled) >= capacity {
+                (capacity, now_ms)
+            } else {
+                // Only count time that made up whole tokens as used.
+                (
+                    state.value + refilled,
+                    ts_ms + mul_div_ceil(refilled, period_ms, rate),
+                )
+            };
+            if tokens < count {
+                let refill_ms = mul_div_ceil(count - tokens, period_ms, rate);
+                return Err(Duration::from_millis(
+                    (ts_ms + refill_ms).saturating_sub(now_ms),
+                ));
+            }
+            let tokens = tokens - count;
+            let expires_at_ms = ts_ms + mul_div_ceil(capacity - tokens, period_ms, rate);
+            (tokens, 0, ts_ms, expires_at_ms)
+        },
+        RateLimitKind::FixedWindow => {
+            let window_start = now_ms - now_ms % period_ms;
+            let used = if ts_ms == window_start {
+                state.value
+            } else {
+                0
+            };
+            if used + count > rate {
+                return Err(Duration::from_millis(window_start + period_ms - now_ms));
+            }
+            (used + count, 0, window_start, window_start + period_ms)
+        },
+        RateLimitKind::SlidingWindow => {
+            let window_start = now_ms - now_ms % period_ms;
+            let (used, previous) = if ts_ms == window_start {
+                (state.value, state.previous)
+            } else if ts_ms + period_ms == window_start {
+                (0, state.value)
+            } else {
+                (0, 0)
+            };
+            let elapsed = now_ms - window_start;
+            let weighted_previous = mul_div_ceil(previous, period_ms - elapsed, period_ms);
+            if weighted_previous + used + count > rate {
+                let retry_at = if used + count > rate {
+                    // Wait for enough of this window to slide out of the next.
+                    window_start + period_ms + mul_div_ceil(used + count - rate, period_ms, used)
+                } else {
+                    let room = rate - used - count;
+                    window_start + period_ms - mul_div(room, period_ms, previous)
+                };
+                return Err(Duration::from_millis(retry_at - now_ms));
+            }
+            (
+                used + count,
+                previous,
+                window_start,
+                window_start + 2 * period_ms,
+            )
+        },
+    };
+    state.value = value;
+    state.previous = previous;
+    state.ts = UnixTimestamp::from_millis(ts_ms);
+    state.expires_at = UnixTimestamp::from_millis(expires_at_ms);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use common::runtime::UnixTimestamp;
+    use database::test_helpers::DbFixtures;
+    use errors::ErrorMetadataAnyhowExt;
+    use runtime::testing::TestRuntime;
+
+    use super::{
+        apply,
+        types::{
+            RateLimit,
+            RateLimitKind,
+            RateLimiterShard,
+        },
+        RateLimiterModel,
+    };
+    use crate::test_helpers::DbFixturesWithModel;
+
+    fn ts(ms: u64) -> UnixTimestamp {
+        UnixTimestamp::from_millis(ms)
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_rate_limiter(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin_system().await?;
+        let mut model = RateLimiterModel::new(&mut tx);
+        let minute = Duration::from_secs(60);
+
+        let signups = RateLimit::new(RateLimitKind::FixedWindow, 2, minute, None, None)?;
+        model
+            .limit("signup", "1.2.3.4", &signups, 1, ts(0))
+            .await??;
+        model
+            .limit("signup", "1.2.3.4", &signups, 1, ts(10_000))
+            .await??;
+        assert_eq!(
+            model
+                .limit("signup", "1.2.3.4", &signups, 1, ts(20_000))
+                .await?,
+            Err(Duration::from_secs(40))
+        );
+        // Other keys have their own allowance.
+        model
+            .limit("signup", "5.6.7.8", &signups, 1, ts(20_000))
+            .await??;
+        model
+            .limit("signup", "1.2.3.4", &signups, 1, ts(60_000))
+            .await??;
+
+        model.reset("signup", "1.2.3.4").await?;
+        model
+            .limit("signup", "1.2.3.4", &signups, 2, ts(60_000))
+            .await??;
+
+        let sends = RateLimit::new(RateLimitKind::TokenBucket, 10, minute, Some(20), None)?;
+        for _ in 0..10 {
+            model.limit("send", "ada", &sends, 2, ts(0)).await??;
+        }
+        assert_eq!(
+            model.limit("send", "ada", &sends, 1, ts(0)).await?,
+            Err(Duration::from_secs(6))
+        );
+        model.limit("send", "ada", &sends, 2, ts(12_000)).await??;
+        let err = model
+            .limit("send", "ada", &sends, 21, ts(12_000))
+            .await
+            .unwrap_err();
+        assert_eq!(err.short_msg(), "InvalidRateLimitCount");
+
+        // Each shard allows its share, so the limit as a whole is never exceeded.
+        let likes = RateLimit::new(RateLimitKind::FixedWindow, 8, minute, None, Some(4))?;
+        let mut allowed = 0;
+        for _ in 0..20 {
+            if model.limit("like", "ada", &likes, 1, ts(0)).await?.is_ok() {
+                allowed += 1;
+            }
+        }
+        assert!((2..=8).contains(&allowed));
+        Ok(())
+    }
+
+    #[test]
+    fn test_sliding_window() -> anyhow::Result<()> {
+        let limit = RateLimit::new(
+            RateLimitKind::SlidingWindow,
+            10,
+            Duration::from_millis(100),
+            None,
+            None,
+        )?;
+        let mut state = RateLimiterShard {
+            name: "api".to_string(),
+            key: "ada".to_string(),
+            shard: 0,
+            value: 0,
+            previous: 0,
+            ts: ts(0),
+            expires_at: ts(0),
+        };
+        for _ in 0..10 {
+            apply(&limit, &mut state, 1, 50).unwrap();
+        }
+        assert_eq!(
+            apply(&limit, &mut state, 1, 60),
+            Err(Duration::from_millis(50))
+        );
+        // Halfway through the next window, half of the previous one counts.
+        apply(&limit, &mut state, 5, 150).unwrap();
+        assert_eq!(
+            apply(&limit, &mut state, 1, 150),
+            Err(Duration::from_millis(10))
+        );
+        apply(&limit, &mut state, 1, 160).unwrap();
+        // Windows more than a period ago don't count.
+        apply(&limit, &mut state, 10, 300).unwrap();
+        Ok(())
+    }
+}
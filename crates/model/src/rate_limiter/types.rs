@@ -0,0 +1,267 @@
+use std::{
+    fmt,
+    str::FromStr,
+    time::Duration,
+};
+
+use common::{
+    knobs::RATE_LIMITER_MAX_SHARDS,
+    runtime::UnixTimestamp,
+};
+use errors::ErrorMetadata;
+#[cfg(any(test, feature = "testing"))]
+use proptest::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use value::codegen_convex_serialization;
+
+/// How a rate limit counts the requests it allows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub enum RateLimitKind {
+    /// Allows `rate` requests in each period, with periods starting at
+    /// multiples of the period since the Unix epoch.
+    FixedWindow,
+    /// Like a fixed window, but counts the previous period's requests,
+    /// weighted by how much of it overlaps the last `period`. This avoids
+    /// allowing twice the rate around the boundary between windows.
+    SlidingWindow,
+    /// Allows bursts of up to `capacity` requests, refilling evenly at `rate`
+    /// requests per period.
+    TokenBucket,
+}
+
+impl RateLimitKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::FixedWindow => "fixedWindow",
+            Self::SlidingWindow => "slidingWindow",
+            Self::TokenBucket => "tokenBucket",
+        }
+    }
+}
+
+impl FromStr for RateLimitKind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "fixedWindow" => Ok(Self::FixedWindow),
+            "slidingWindow" => Ok(Self::SlidingWindow),
+            "tokenBucket" => Ok(Self::TokenBucket),
+            _ => anyhow::bail!(ErrorMetadata::bad_request(
+                "InvalidRateLimit",
+                format!(
+                    "Invalid rate limit kind {s:?}. Expected \"fixedWindow\", \"slidingWindow\" \
+                     or \"tokenBucket\"."
+                ),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for RateLimitKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A limit a mutation checks requests against. Limits aren't stored: each
+/// call passes its limit, and calls for the same name should always pass the
+/// same one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RateLimit {
+    pub kind: RateLimitKind,
+    /// Requests allowed per period.
+    pub rate: u64,
+    pub period: Duration,
+    /// The most requests a token bucket allows at once. This is `rate` for
+    /// windowed limits.
+    pub capacity: u64,
+    /// The number of documents the limit's state is split across for each
+    /// key. Each shard allows its share of the rate, so concurrent requests
+    /// usually don't conflict, at the cost of rejecting some requests early
+    /// when the shards they land on are used up before the others.
+    pub shards: u32,
+}
+
+impl RateLimit {
+    pub fn new(
+        kind: RateLimitKind,
+        rate: u64,
+        period: Duration,
+        capacity: Option<u64>,
+        shards: Option<u32>,
+    ) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            rate > 0 && rate <= i64::MAX as u64,
+            invalid_rate_limit("The rate must be a positive integer")
+        );
+        anyhow::ensure!(
+            period >= Duration::from_millis(1) && period.as_millis() <= i64::MAX as u128,
+            invalid_rate_limit("The period must be at least one millisecond")
+        );
+        let capacity = match (kind, capacity) {
+            (RateLimitKind::TokenBucket, Some(capacity)) => {
+                anyhow::ensure!(
+                    capacity > 0 && capacity <= i64::MAX as u64,
+                    invalid_rate_limit("The capacity must be a positive integer")
+                );
+                capacity
+            },
+            (RateLimitKind::FixedWindow | RateLimitKind::SlidingWindow, Some(_)) => {
+                anyhow::bail!(invalid_rate_limit(format!(
+                    "Only token bucket rate limits have a capacity, not {kind} ones"
+                )))
+            },
+            (_, None) => rate,
+        };
+        let shards = shards.unwrap_or(1);
+        anyhow::ensure!(
+            (1..=*RATE_LIMITER_MAX_SHARDS).contains(&shards),
+            invalid_rate_limit(format!(
+                "A rate limit must have between 1 and {} shards",
+                *RATE_LIMITER_MAX_SHARDS
+            ))
+        );
+        anyhow::ensure!(
+            u64::from(shards) <= rate.min(capacity),
+            invalid_rate_limit("A rate limit can't have more shards than requests it allows")
+        );
+        Ok(Self {
+            kind,
+            rate,
+            period,
+            capacity,
+            shards,
+        })
+    }
+
+    /// The part of `total` that `shard` allows, spreading the remainder over
+    /// the first shards so the shares add up to `total`.
+    pub fn share(&self, total: u64, shard: u32) -> u64 {
+        let shards = u64::from(self.shards);
+        total / shards + u64::from(u64::from(shard) < total % shards)
+    }
+}
+
+fn invalid_rate_limit(msg: impl Into<std::borrow::Cow<'static, str>>) -> ErrorMetadata {
+    ErrorMetadata::bad_request("InvalidRateLimit", msg)
+}
+
+/// The state of one shard of a rate limit for one key. What `value` and
+/// `previous` count depends on the limit's kind:
+/// - For windows, `value` is the requests allowed in the window starting at
+///   `ts`, and `previous` those allowed in the window before it.
+/// - For token buckets, `value` is the tokens left as of `ts`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct RateLimiterShard {
+    pub name: String,
+    pub key: String,
+    pub shard: u32,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=i64::MAX as u64")
+    )]
+    pub value: u64,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=i64::MAX as u64")
+    )]
+    pub previous: u64,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(
+            strategy = "(0..=i64::MAX as u64 / 1_000_000).prop_map(UnixTimestamp::from_millis)"
+        )
+    )]
+    pub ts: UnixTimestamp,
+    /// When the shard's state is the same as not having any, after which it
+    /// can be deleted.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(
+            strategy = "(0..=i64::MAX as u64 / 1_000_000).prop_map(UnixTimestamp::from_millis)"
+        )
+    )]
+    pub expires_at: UnixTimestamp,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedRateLimiterShard {
+    name: String,
+    key: String,
+    shard: i64,
+    value: i64,
+    previous: i64,
+    ts_ms: i64,
+    expires_at_ms: i64,
+}
+
+impl TryFrom<RateLimiterShard> for SerializedRateLimiterShard {
+    type Error = anyhow::Error;
+
+    fn try_from(shard: RateLimiterShard) -> anyhow::Result<Self> {
+        Ok(Self {
+            name: shard.name,
+            key: shard.key,
+            shard: shard.shard.into(),
+            value: shard.value.try_into()?,
+            previous: shard.previous.try_into()?,
+            ts_ms: shard.ts.as_ms_since_epoch()?.try_into()?,
+            expires_at_ms: shard.expires_at.as_ms_since_epoch()?.try_into()?,
+        })
+    }
+}
+
+impl TryFrom<SerializedRateLimiterShard> for RateLimiterShard {
+    type Error = anyhow::Error;
+
+    fn try_from(shard: SerializedRateLimiterShard) -> anyhow::Result<Self> {
+        Ok(Self {
+            name: shard.name,
+            key: shard.key,
+            shard: shard.shard.try_into()?,
+            value: shard.value.try_into()?,
+            previous: shard.previous.try_into()?,
+            ts: UnixTimestamp::from_millis(shard.ts_ms.try_into()?),
+            expires_at: UnixTimestamp::from_millis(shard.expires_at_ms.try_into()?),
+        })
+    }
+}
+
+codegen_convex_serialization!(RateLimiterShard, SerializedRateLimiterShard);
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use errors::ErrorMetadataAnyhowExt;
+
+    use super::{
+        RateLimit,
+        RateLimitKind,
+    };
+
+    #[test]
+    fn test_invalid_rate_limit() {
+        let minute = Duration::from_secs(60);
+        let err = RateLimit::new(RateLimitKind::FixedWindow, 0, minute, None, None).unwrap_err();
+        assert_eq!(err.short_msg(), "InvalidRateLimit");
+        let err =
+            RateLimit::new(RateLimitKind::SlidingWindow, 10, minute, Some(20), None).unwrap_err();
+        assert_eq!(err.short_msg(), "InvalidRateLimit");
+        let err =
+            RateLimit::new(RateLimitKind::TokenBucket, 10, minute, Some(2), Some(4)).unwrap_err();
+        assert_eq!(err.short_msg(), "InvalidRateLimit");
+
+        let limit = RateLimit::new(RateLimitKind::TokenBucket, 10, minute, None, Some(4)).unwrap();
+        assert_eq!(limit.capacity, 10);
+        let shares: Vec<_> = (0..4).map(|shard| limit.share(limit.rate, shard)).collect();
+        assert_eq!(shares, vec![3, 3, 2, 2]);
+    }
+}
@@ -0,0 +1,133 @@
+use std::sync::LazyLock;
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    query::{
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+};
+use database::{
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use sync_types::Timestamp;
+use value::{
+    TableName,
+    TableNamespace,
+};
+
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+use types::ReplicationState;
+
+pub static REPLICATION_STATE_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_replication_state"
+        .parse()
+        .expect("Invalid built-in replication_state table")
+});
+
+pub struct ReplicationStateTable;
+impl SystemTable for ReplicationStateTable {
+    fn table_name(&self) -> &'static TableName {
+        &REPLICATION_STATE_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<ReplicationState>::try_from(document).map(|_| ())
+    }
+}
+
+/// Tracks a follower's replication cursor. Updating the cursor in the same
+/// transaction that applies a leader's commit keeps them consistent across
+/// restarts.
+pub struct ReplicationStateModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> ReplicationStateModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    pub async fn get(&mut self) -> anyhow::Result<Option<ParsedDocument<ReplicationState>>> {
+        let query = Query::full_table_scan(REPLICATION_STATE_TABLE.clone(), Order::Asc);
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        query_stream
+            .expect_at_most_one(self.tx)
+            .await?
+            .map(|document| document.try_into())
+            .transpose()
+    }
+
+    pub async fn set_cursor(&mut self, cursor: Timestamp) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.tx.identity().is_system(),
+            "Only the system can update the replication cursor"
+        );
+        let value = ReplicationState { cursor }.try_into()?;
+        match self.get().await? {
+            Some(existing) => {
+                anyhow::ensure!(
+                    existing.cursor <= cursor,
+                    "Replication cursor moved backwards from {} to {cursor}",
+                    existing.cursor
+                );
+                SystemMetadataModel::new_global(self.tx)
+                    .replace(existing.id(), value)
+                    .await?;
+            },
+            None => {
+                SystemMetadataModel::new_global(self.tx)
+                    .insert(&REPLICATION_STATE_TABLE, value)
+                    .await?;
+            },
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use database::test_helpers::DbFixtures;
+    use keybroker::Identity;
+    use runtime::testing::TestRuntime;
+    use sync_types::Timestamp;
+
+    use super::ReplicationStateModel;
+    use crate::test_helpers::DbFixturesWithModel;
+
+    #[convex_macro::test_runtime]
+    async fn test_set_cursor(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin(Identity::system()).await?;
+        assert!(ReplicationStateModel::new(&mut tx).get().await?.is_none());
+        ReplicationStateModel::new(&mut tx)
+            .set_cursor(Timestamp::must(5))
+            .await?;
+        ReplicationStateModel::new(&mut tx)
+            .set_cursor(Timestamp::must(7))
+            .await?;
+        let state = ReplicationStateModel::new(&mut tx).get().await?.unwrap();
+        assert_eq!(state.cursor, Timestamp::must(7));
+        assert!(ReplicationStateModel::new(&mut tx)
+            .set_cursor(Timestamp::must(6))
+            .await
+            .is_err());
+        Ok(())
+    }
+}
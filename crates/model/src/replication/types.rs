@@ -0,0 +1,41 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use sync_types::Timestamp;
+use value::codegen_convex_serialization;
+
+/// How far a follower deployment has caught up with its leader.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct ReplicationState {
+    /// All of the leader's commits at or before this timestamp have been
+    /// applied.
+    pub cursor: Timestamp,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedReplicationState {
+    cursor: i64,
+}
+
+impl From<ReplicationState> for SerializedReplicationState {
+    fn from(state: ReplicationState) -> Self {
+        SerializedReplicationState {
+            cursor: u64::from(state.cursor) as i64,
+        }
+    }
+}
+
+impl TryFrom<SerializedReplicationState> for ReplicationState {
+    type Error = anyhow::Error;
+
+    fn try_from(state: SerializedReplicationState) -> anyhow::Result<Self> {
+        Ok(ReplicationState {
+            cursor: Timestamp::try_from(state.cursor as u64)?,
+        })
+    }
+}
+
+codegen_convex_serialization!(ReplicationState, SerializedReplicationState);
@@ -0,0 +1,265 @@
+//! Scheduled jobs that ran out of attempts after repeated system errors.
+//! Admins can look at why they failed, run them again, or purge them.
+use std::sync::LazyLock;
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    execution_context::{
+        ExecutionContext,
+        ExecutionId,
+    },
+    query::{
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+    RequestId,
+};
+use database::{
+    require_admin_or_system,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use errors::ErrorMetadata;
+use value::{
+    DeveloperDocumentId,
+    ResolvedDocumentId,
+    TableName,
+    TableNamespace,
+};
+
+use self::types::DeadLetteredJob;
+use crate::{
+    scheduled_jobs::SchedulerModel,
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+pub static SCHEDULED_JOBS_DEAD_LETTER_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_scheduled_jobs_dead_letter"
+        .parse()
+        .expect("Invalid built-in scheduled_jobs_dead_letter table")
+});
+
+pub struct ScheduledJobsDeadLetterTable;
+impl SystemTable for ScheduledJobsDeadLetterTable {
+    fn table_name(&self) -> &'static TableName {
+        &SCHEDULED_JOBS_DEAD_LETTER_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<DeadLetteredJob>::try_from(document).map(|_| ())
+    }
+}
+
+fn dead_lettered_job_not_found(id: DeveloperDocumentId) -> ErrorMetadata {
+    ErrorMetadata::not_found(
+        "DeadLetteredJobNotFound",
+        format!("Dead-lettered job {} doesn't exist.", id.encode()),
+    )
+}
+
+pub struct DeadLetteredJobsModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> DeadLetteredJobsModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    /// Records a job that ran out of attempts. Only called by the scheduler.
+    pub async fn insert(&mut self, job: DeadLetteredJob) -> anyhow::Result<ResolvedDocumentId> {
+        SystemMetadataModel::new_global(self.tx)
+            .insert(&SCHEDULED_JOBS_DEAD_LETTER_TABLE, job.try_into()?)
+            .await
+    }
+
+    /// Up to `limit` of the most recently dead-lettered jobs, newest first.
+    pub async fn list(
+        &mut self,
+        limit: usize,
+    ) -> anyhow::Result<Vec<ParsedDocument<DeadLetteredJob>>> {
+        require_admin_or_system(self.tx.identity(), "list_dead_lettered_jobs")?;
+        let query = Query::full_table_scan(SCHEDULED_JOBS_DEAD_LETTER_TABLE.clone(), Order::Desc);
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        let mut jobs = vec![];
+        while jobs.len() < limit
+            && let Some(document) = query_stream.next(self.tx, None).await?
+        {
+            jobs.push(document.try_into()?);
+        }
+        Ok(jobs)
+    }
+
+    pub async fn get(
+        &mut self,
+        id: DeveloperDocumentId,
+    ) -> anyhow::Result<Option<ParsedDocument<DeadLetteredJob>>> {
+        require_admin_or_system(self.tx.identity(), "get_dead_lettered_job")?;
+        let namespace = self.tx.table_mapping().namespace(TableNamespace::Global);
+        let Ok(id) = id.to_resolved(namespace.number_to_tablet()) else {
+            return Ok(None);
+        };
+        if !namespace.tablet_matches_name(id.tablet_id, &SCHEDULED_JOBS_DEAD_LETTER_TABLE) {
+            return Ok(None);
+        }
+        self.tx
+            .get(id)
+            .await?
+            .map(ParsedDocument::try_from)
+            .transpose()
+    }
+
+    /// Schedules the job to run again now, with a fresh set of attempts, and
+    /// removes it from the dead letter table. Returns the new job's id in the
+    /// `_scheduled_jobs` table of the job's component.
+    pub async fn retry(&mut self, id: DeveloperDocumentId) -> anyhow::Result<ResolvedDocumentId> {
+        require_admin_or_system(self.tx.identity(), "retry_dead_lettered_job")?;
+        let Some(existing) = self.get(id).await? else {
+            anyhow::bail!(dead_lettered_job_not_found(id));
+        };
+        let (id, job) = existing.into_id_and_value();
+        let ts = self.tx.runtime().unix_timestamp();
+        let job_id = SchedulerModel::new(self.tx, job.component.into())
+            .schedule(
+                job.path,
+                job.udf_args,
+                ts,
+                ExecutionContext::new_from_parts(RequestId::new(), ExecutionId::new(), None, true),
+            )
+            .await?;
+        SystemMetadataModel::new_global(self.tx).delete(id).await?;
+        Ok(job_id)
+    }
+
+    /// Removes a dead-lettered job without running it again.
+    pub async fn delete(&mut self, id: DeveloperDocumentId) -> anyhow::Result<()> {
+        require_admin_or_system(self.tx.identity(), "delete_dead_lettered_job")?;
+        let Some(existing) = self.get(id).await? else {
+            anyhow::bail!(dead_lettered_job_not_found(id));
+        };
+        SystemMetadataModel::new_global(self.tx)
+            .delete(existing.id())
+            .await?;
+        Ok(())
+    }
+
+    /// Removes up to `limit` of the oldest dead-lettered jobs and returns how
+    /// many were removed. The caller can assume the table is empty if fewer
+    /// than `limit` were removed.
+    pub async fn purge(&mut self, limit: usize) -> anyhow::Result<usize> {
+        require_admin_or_system(self.tx.identity(), "purge_dead_lettered_jobs")?;
+        let query = Query::full_table_scan(SCHEDULED_JOBS_DEAD_LETTER_TABLE.clone(), Order::Asc);
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        let mut ids = vec![];
+        while ids.len() < limit
+            && let Some(document) = query_stream.next(self.tx, None).await?
+        {
+            ids.push(document.id());
+        }
+        let count = ids.len();
+        for id in ids {
+            SystemMetadataModel::new_global(self.tx).delete(id).await?;
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{
+        components::{
+            CanonicalizedComponentFunctionPath,
+            ComponentPath,
+        },
+        document::ParsedDocument,
+        execution_context::ExecutionContext,
+        runtime::Runtime,
+    };
+    use database::test_helpers::DbFixtures;
+    use keybroker::Identity;
+    use runtime::testing::TestRuntime;
+    use value::{
+        ConvexArray,
+        TableNamespace,
+    };
+
+    use super::DeadLetteredJobsModel;
+    use crate::{
+        scheduled_jobs::{
+            types::{
+                ScheduledJob,
+                ScheduledJobState,
+            },
+            SchedulerModel,
+        },
+        test_helpers::DbFixturesWithModel,
+    };
+
+    #[convex_macro::test_runtime]
+    async fn test_dead_lettered_jobs(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin(Identity::system()).await?;
+        let namespace = TableNamespace::root_component();
+        let path = CanonicalizedComponentFunctionPath {
+            component: ComponentPath::root(),
+            udf_path: "jobs:flaky".parse()?,
+        };
+        for _ in 0..2 {
+            let job_id = SchedulerModel::new(&mut tx, namespace)
+                .schedule(
+                    path.clone(),
+                    ConvexArray::try_from(vec![])?,
+                    rt.unix_timestamp(),
+                    ExecutionContext::new_for_test(),
+                )
+                .await?;
+            let mut job: ScheduledJob =
+                ParsedDocument::<ScheduledJob>::try_from(tx.get(job_id).await?.unwrap())?
+                    .into_value();
+            job.attempts.system_errors += 1;
+            job.attempts
+                .record_error(rt.generate_timestamp()?, "boom".to_string());
+            SchedulerModel::new(&mut tx, namespace)
+                .dead_letter(job_id, job)
+                .await?;
+            let state = SchedulerModel::new(&mut tx, namespace)
+                .check_status(job_id)
+                .await?;
+            assert!(matches!(state, Some(ScheduledJobState::Failed(_))));
+        }
+
+        let dead_lettered = DeadLetteredJobsModel::new(&mut tx).list(10).await?;
+        assert_eq!(dead_lettered.len(), 2);
+        assert_eq!(dead_lettered[0].path, path);
+        assert_eq!(dead_lettered[0].attempts.errors[0].error, "boom");
+
+        let job_id = DeadLetteredJobsModel::new(&mut tx)
+            .retry(dead_lettered[0].id().into())
+            .await?;
+        let state = SchedulerModel::new(&mut tx, namespace)
+            .check_status(job_id)
+            .await?;
+        assert_eq!(state, Some(ScheduledJobState::Pending));
+        assert_eq!(DeadLetteredJobsModel::new(&mut tx).purge(10).await?, 1);
+        assert!(DeadLetteredJobsModel::new(&mut tx)
+            .list(10)
+            .await?
+            .is_empty());
+
+        let mut tx = db.begin(Identity::Unknown).await?;
+        assert!(DeadLetteredJobsModel::new(&mut tx).list(10).await.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+use common::{
+    components::{
+        CanonicalizedComponentFunctionPath,
+        ComponentId,
+        ComponentPath,
+    },
+    types::Timestamp,
+};
+#[cfg(any(test, feature = "testing"))]
+use proptest::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::Value as JsonValue;
+use value::{
+    codegen_convex_serialization,
+    ConvexArray,
+    DeveloperDocumentId,
+};
+
+use crate::scheduled_jobs::types::ScheduledJobAttempts;
+
+/// A scheduled job that kept hitting system errors until it ran out of
+/// attempts. The job itself is marked as failed, and this keeps what's
+/// needed to look into it and run it again.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct DeadLetteredJob {
+    /// The component whose `_scheduled_jobs` table the job was in.
+    pub component: ComponentId,
+    /// The job's document in that table.
+    pub job_id: DeveloperDocumentId,
+    pub path: CanonicalizedComponentFunctionPath,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "proptest::arbitrary::any_with::<ConvexArray>((0..4).into())")
+    )]
+    pub udf_args: ConvexArray,
+    pub original_scheduled_ts: Timestamp,
+    pub attempts: ScheduledJobAttempts,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedDeadLetteredJob {
+    component_id: Option<String>,
+    job_id: String,
+    component: Option<String>,
+    udf_path: String,
+    // Serialize the udf arguments as binary since we restrict what
+    // field names can be used in a `Document`'s top-level object.
+    #[serde(with = "serde_bytes")]
+    udf_args: Vec<u8>,
+    original_scheduled_ts: i64,
+    attempts: ScheduledJobAttempts,
+}
+
+impl TryFrom<DeadLetteredJob> for SerializedDeadLetteredJob {
+    type Error = anyhow::Error;
+
+    fn try_from(job: DeadLetteredJob) -> anyhow::Result<Self> {
+        Ok(Self {
+            component_id: job.component.serialize_to_string(),
+            job_id: job.job_id.encode(),
+            component: Some(String::from(job.path.component)),
+            udf_path: String::from(job.path.udf_path),
+            udf_args: serde_json::to_vec(&JsonValue::from(job.udf_args))?,
+            original_scheduled_ts: job.original_scheduled_ts.into(),
+            attempts: job.attempts,
+        })
+    }
+}
+
+impl TryFrom<SerializedDeadLetteredJob> for DeadLetteredJob {
+    type Error = anyhow::Error;
+
+    fn try_from(job: SerializedDeadLetteredJob) -> anyhow::Result<Self> {
+        let udf_args: JsonValue = serde_json::from_slice(&job.udf_args)?;
+        Ok(Self {
+            component: ComponentId::deserialize_from_string(job.component_id.as_deref())?,
+            job_id: DeveloperDocumentId::decode(&job.job_id)?,
+            path: CanonicalizedComponentFunctionPath {
+                component: job
+                    .component
+                    .map(|p| p.parse())
+                    .transpose()?
+                    .unwrap_or_else(ComponentPath::root),
+                udf_path: job.udf_path.parse()?,
+            },
+            udf_args: udf_args.try_into()?,
+            original_scheduled_ts: job.original_scheduled_ts.try_into()?,
+            attempts: job.attempts,
+        })
+    }
+}
+
+codegen_convex_serialization!(DeadLetteredJob, SerializedDeadLetteredJob);
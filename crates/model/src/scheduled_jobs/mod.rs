@@ -6,9 +6,11 @@ use std::{
     },
 };
 
+use anyhow::Context;
 use common::{
     components::CanonicalizedComponentFunctionPath,
     document::{
+        DeveloperDocument,
         ParsedDocument,
         ResolvedDocument,
     },
@@ -44,6 +46,7 @@ use database::{
 };
 use errors::ErrorMetadata;
 use maplit::btreemap;
+use serde_json::Value as JsonValue;
 use sync_types::Timestamp;
 use value::{
     id_v6::DeveloperDocumentId,
@@ -58,13 +61,27 @@ use value::{
 
 use self::{
     types::{
+        IdempotencyKey,
+        OnScheduleConflict,
         ScheduledJob,
         ScheduledJobAttempts,
+        ScheduledJobFilter,
         ScheduledJobState,
+        ScheduledJobsCursor,
+    },
+    virtual_table::{
+        scheduled_job_to_virtual_doc,
+        ScheduledJobsDocMapper,
     },
-    virtual_table::ScheduledJobsDocMapper,
 };
 use crate::{
+    action_checkpoints::ActionCheckpointsModel,
+    queues::QueuesModel,
+    scheduled_job_dead_letters::{
+        types::DeadLetteredJob,
+        DeadLetteredJobsModel,
+    },
+    workflows::WorkflowsModel,
     SystemIndex,
     SystemTable,
 };
@@ -100,6 +117,8 @@ pub static SCHEDULED_JOBS_INDEX_BY_UDF_PATH: LazyLock<IndexName> =
     LazyLock::new(|| system_index(&SCHEDULED_JOBS_TABLE, "by_udf_path_and_next_event_ts"));
 pub static SCHEDULED_JOBS_INDEX_BY_COMPLETED_TS: LazyLock<IndexName> =
     LazyLock::new(|| system_index(&SCHEDULED_JOBS_TABLE, "by_completed_ts"));
+pub static SCHEDULED_JOBS_INDEX_BY_IDEMPOTENCY_KEY: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&SCHEDULED_JOBS_TABLE, "by_idempotency_key_and_next_ts"));
 pub static NEXT_TS_FIELD: LazyLock<FieldPath> =
     LazyLock::new(|| "nextTs".parse().expect("invalid nextTs field"));
 pub static COMPLETED_TS_FIELD: LazyLock<FieldPath> =
@@ -108,6 +127,11 @@ static UDF_PATH_FIELD: LazyLock<FieldPath> =
     LazyLock::new(|| "udfPath".parse().expect("invalid udfPath field"));
 static COMPONENT_PATH_FIELD: LazyLock<FieldPath> =
     LazyLock::new(|| "component".parse().expect("invalid component field"));
+static IDEMPOTENCY_KEY_FIELD: LazyLock<FieldPath> = LazyLock::new(|| {
+    "idempotencyKey"
+        .parse()
+        .expect("invalid idempotencyKey field")
+});
 
 pub struct ScheduledJobsTable;
 impl SystemTable for ScheduledJobsTable {
@@ -135,6 +159,14 @@ impl SystemTable for ScheduledJobsTable {
                     .try_into()
                     .unwrap(),
             },
+            // By idempotency key and next ts. Used to find the unfinished job with a key
+            // when scheduling another one with it.
+            SystemIndex {
+                name: SCHEDULED_JOBS_INDEX_BY_IDEMPOTENCY_KEY.clone(),
+                fields: vec![IDEMPOTENCY_KEY_FIELD.clone(), NEXT_TS_FIELD.clone()]
+                    .try_into()
+                    .unwrap(),
+            },
         ]
     }
 
@@ -208,6 +240,73 @@ impl<'a, RT: Runtime> SchedulerModel<'a, RT> {
         args: ConvexArray,
         ts: UnixTimestamp,
         context: ExecutionContext,
+    ) -> anyhow::Result<ResolvedDocumentId> {
+        self.insert_job(path, args, ts, context, None).await
+    }
+
+    /// Schedules a job unless one with the same key hasn't finished yet, in
+    /// which case `idempotency_key.on_conflict` decides whether the pending
+    /// job is replaced or kept. Either way, the ID of the job that will run is
+    /// returned.
+    pub async fn schedule_idempotent(
+        &mut self,
+        path: CanonicalizedComponentFunctionPath,
+        args: ConvexArray,
+        ts: UnixTimestamp,
+        context: ExecutionContext,
+        idempotency_key: IdempotencyKey,
+    ) -> anyhow::Result<ResolvedDocumentId> {
+        let existing = self.unfinished_jobs_with_key(&idempotency_key.key).await?;
+        match idempotency_key.on_conflict {
+            OnScheduleConflict::Skip => {
+                if let Some(job) = existing.first() {
+                    return Ok(job.id());
+                }
+            },
+            OnScheduleConflict::Replace => {
+                // Jobs that already started can't be replaced, so they're left
+                // to finish alongside the new one.
+                for job in existing {
+                    if job.state == ScheduledJobState::Pending {
+                        self.cancel(job.id()).await?;
+                    }
+                }
+            },
+        }
+        self.insert_job(path, args, ts, context, Some(idempotency_key.key))
+            .await
+    }
+
+    async fn unfinished_jobs_with_key(
+        &mut self,
+        key: &str,
+    ) -> anyhow::Result<Vec<ParsedDocument<ScheduledJob>>> {
+        let index_query = Query::index_range(IndexRange {
+            index_name: SCHEDULED_JOBS_INDEX_BY_IDEMPOTENCY_KEY.clone(),
+            range: vec![
+                IndexRangeExpression::Eq(
+                    IDEMPOTENCY_KEY_FIELD.clone(),
+                    ConvexValue::try_from(key.to_string())?.into(),
+                ),
+                IndexRangeExpression::Gt(NEXT_TS_FIELD.clone(), ConvexValue::Null),
+            ],
+            order: Order::Asc,
+        });
+        let mut query_stream = ResolvedQuery::new(self.tx, self.namespace, index_query)?;
+        let mut jobs = vec![];
+        while let Some(doc) = query_stream.next(self.tx, None).await? {
+            jobs.push(doc.try_into()?);
+        }
+        Ok(jobs)
+    }
+
+    async fn insert_job(
+        &mut self,
+        path: CanonicalizedComponentFunctionPath,
+        args: ConvexArray,
+        ts: UnixTimestamp,
+        context: ExecutionContext,
+        idempotency_key: Option<String>,
     ) -> anyhow::Result<ResolvedDocumentId> {
         if path.udf_path.is_system()
             && !(self.tx.identity().is_admin() || self.tx.identity().is_system())
@@ -231,7 +330,7 @@ impl<'a, RT: Runtime> SchedulerModel<'a, RT> {
             original_scheduled_ts,
             ScheduledJobAttempts::default(),
         )?;
-        let job = if let Some(parent_scheduled_job) = context.parent_scheduled_job {
+        let mut job = if let Some(parent_scheduled_job) = context.parent_scheduled_job {
             let table_mapping = self.tx.table_mapping();
             let parent_scheduled_job = parent_scheduled_job
                 .to_resolved(&table_mapping.namespace(self.namespace).number_to_tablet())?;
@@ -262,6 +361,7 @@ impl<'a, RT: Runtime> SchedulerModel<'a, RT> {
         } else {
             scheduled_job
         };
+        job.idempotency_key = idempotency_key;
         let id = SystemMetadataModel::new(self.tx, self.namespace)
             .insert_metadata(&SCHEDULED_JOBS_TABLE, job.try_into()?)
             .await?;
@@ -289,6 +389,17 @@ impl<'a, RT: Runtime> SchedulerModel<'a, RT> {
         &mut self,
         id: ResolvedDocumentId,
         state: ScheduledJobState,
+    ) -> anyhow::Result<()> {
+        self.complete_with_output(id, state, None).await
+    }
+
+    /// Like [`Self::complete`], but passes the function's result on to the
+    /// workflow running the job, if any.
+    pub async fn complete_with_output(
+        &mut self,
+        id: ResolvedDocumentId,
+        state: ScheduledJobState,
+        output: Option<JsonValue>,
     ) -> anyhow::Result<()> {
         match state {
             ScheduledJobState::InProgress | ScheduledJobState::Pending => {
@@ -318,7 +429,7 @@ impl<'a, RT: Runtime> SchedulerModel<'a, RT> {
         }
 
         let mut job: ScheduledJob = job.into_value();
-        job.state = state;
+        job.state = state.clone();
         // Remove next_ts and set completed_ts so the scheduler knows that the
         // job has already been processed
         job.next_ts = None;
@@ -326,10 +437,50 @@ impl<'a, RT: Runtime> SchedulerModel<'a, RT> {
         SystemMetadataModel::new(self.tx, self.namespace)
             .replace(id, job.try_into()?)
             .await?;
+        // A completed action is never re-run, so its checkpoint isn't needed.
+        ActionCheckpointsModel::new(self.tx)
+            .delete(id.developer_id)
+            .await?;
+        QueuesModel::new(self.tx)
+            .message_finished(id, &state)
+            .await?;
+        WorkflowsModel::new(self.tx)
+            .step_finished(id, &state, output)
+            .await?;
 
         Ok(())
     }
 
+    /// Fails a job that ran out of attempts and moves it to the dead letter
+    /// table. `job` has the attempts it failed with.
+    pub async fn dead_letter(
+        &mut self,
+        id: ResolvedDocumentId,
+        job: ScheduledJob,
+    ) -> anyhow::Result<()> {
+        let failures = job.attempts.count_failures();
+        let dead_lettered = DeadLetteredJob {
+            component: self.namespace.into(),
+            job_id: id.developer_id,
+            path: job.path.clone(),
+            udf_args: job.udf_args()?,
+            original_scheduled_ts: job.original_scheduled_ts,
+            attempts: job.attempts.clone(),
+        };
+        self.replace(id, job).await?;
+        self.complete(
+            id,
+            ScheduledJobState::Failed(format!(
+                "Scheduled function failed after {failures} attempts because of internal errors"
+            )),
+        )
+        .await?;
+        DeadLetteredJobsModel::new(self.tx)
+            .insert(dead_lettered)
+            .await?;
+        Ok(())
+    }
+
     /// Cancel a scheduled job if it is in Pending or InProgress state.
     /// Otherwise, it has already been completed in another transaction.
     pub async fn cancel(&mut self, id: ResolvedDocumentId) -> anyhow::Result<()> {
@@ -365,49 +516,26 @@ impl<'a, RT: Runtime> SchedulerModel<'a, RT> {
         path: Option<CanonicalizedComponentFunctionPath>,
         limit: usize,
     ) -> anyhow::Result<usize> {
-        let index_query = match path {
-            Some(path) => {
-                let udf_path = path.udf_path;
-                let component_path = path.component;
-                let mut component_path_filter = Expression::Eq(
-                    Expression::Field(COMPONENT_PATH_FIELD.clone()).into(),
-                    Expression::Literal(maybe_val!(String::from(component_path.clone()))).into(),
-                );
-                if component_path.is_root() {
-                    component_path_filter = Expression::Or(vec![
-                        component_path_filter,
-                        Expression::Eq(
-                            Expression::Field(COMPONENT_PATH_FIELD.clone()).into(),
-                            Expression::Literal(maybe_val!(undefined)).into(),
-                        ),
-                    ]);
-                }
-                let range = vec![
-                    IndexRangeExpression::Eq(
-                        UDF_PATH_FIELD.clone(),
-                        ConvexValue::try_from(udf_path.to_string())?.into(),
-                    ),
-                    IndexRangeExpression::Gt(NEXT_TS_FIELD.clone(), value::ConvexValue::Null),
-                ];
-                Query::index_range(IndexRange {
-                    index_name: SCHEDULED_JOBS_INDEX_BY_UDF_PATH.clone(),
-                    range,
-                    order: Order::Asc,
-                })
-                .filter(component_path_filter)
-            },
-            None => {
-                let range = vec![IndexRangeExpression::Gt(
-                    NEXT_TS_FIELD.clone(),
-                    value::ConvexValue::Null,
-                )];
-                Query::index_range(IndexRange {
-                    index_name: SCHEDULED_JOBS_INDEX.clone(),
-                    range,
-                    order: Order::Asc,
-                })
+        self.cancel_matching(
+            ScheduledJobFilter {
+                path,
+                ..Default::default()
             },
-        };
+            limit,
+        )
+        .await
+    }
+
+    /// Cancels up to `limit` of the unfinished jobs matching `filter` and
+    /// returns how many were canceled. Canceled jobs no longer match, so
+    /// calling this again continues where it left off, and the caller can
+    /// assume all have been canceled if the result is less than `limit`.
+    pub async fn cancel_matching(
+        &mut self,
+        filter: ScheduledJobFilter,
+        limit: usize,
+    ) -> anyhow::Result<usize> {
+        let index_query = unfinished_jobs_query(&filter, None)?;
         let mut query_stream = ResolvedQuery::new(self.tx, self.namespace, index_query)?;
         let mut count = 0;
         while count < limit
@@ -419,6 +547,61 @@ impl<'a, RT: Runtime> SchedulerModel<'a, RT> {
         Ok(count)
     }
 
+    /// Up to `limit` of the unfinished jobs matching `filter`, in the order
+    /// they next run, starting after `cursor`. Also returns the cursor for
+    /// the next page, or `None` if there are no more jobs.
+    pub async fn list_matching(
+        &mut self,
+        filter: ScheduledJobFilter,
+        cursor: Option<ScheduledJobsCursor>,
+        limit: usize,
+    ) -> anyhow::Result<(
+        Vec<ParsedDocument<ScheduledJob>>,
+        Option<ScheduledJobsCursor>,
+    )> {
+        let (documents, next_cursor) = self.list_matching_documents(filter, cursor, limit).await?;
+        let jobs = documents
+            .into_iter()
+            .map(ParsedDocument::try_from)
+            .try_collect()?;
+        Ok((jobs, next_cursor))
+    }
+
+    async fn list_matching_documents(
+        &mut self,
+        filter: ScheduledJobFilter,
+        cursor: Option<ScheduledJobsCursor>,
+        limit: usize,
+    ) -> anyhow::Result<(Vec<ResolvedDocument>, Option<ScheduledJobsCursor>)> {
+        let index_query = unfinished_jobs_query(&filter, cursor.as_ref().map(|c| c.next_ts))?;
+        let mut query_stream = ResolvedQuery::new(self.tx, self.namespace, index_query)?;
+        let mut documents = vec![];
+        let mut last = None;
+        let mut has_more = false;
+        while let Some(doc) = query_stream.next(self.tx, None).await? {
+            let job: ParsedDocument<ScheduledJob> = doc.clone().try_into()?;
+            let next_ts = job
+                .next_ts
+                .context("Unfinished scheduled job is missing next_ts")?;
+            let id = DeveloperDocumentId::from(doc.id());
+            // Jobs that run at the same time are ordered by ID, so skip the
+            // ones the previous page already returned.
+            if let Some(cursor) = &cursor
+                && next_ts == cursor.next_ts
+                && id <= cursor.id
+            {
+                continue;
+            }
+            if documents.len() == limit {
+                has_more = true;
+                break;
+            }
+            last = Some(ScheduledJobsCursor { next_ts, id });
+            documents.push(doc);
+        }
+        Ok((documents, if has_more { last } else { None }))
+    }
+
     pub async fn list(&mut self) -> anyhow::Result<Vec<ParsedDocument<ScheduledJob>>> {
         let scheduled_query = Query::full_table_scan(SCHEDULED_JOBS_TABLE.clone(), Order::Asc);
         let mut query_stream = ResolvedQuery::new(self.tx, self.namespace, scheduled_query)?;
@@ -447,6 +630,61 @@ impl<'a, RT: Runtime> SchedulerModel<'a, RT> {
     }
 }
 
+/// A query over the jobs matching `filter` that haven't finished, in the
+/// order they next run, starting at `start_ts` if it's later than the
+/// filter's start.
+fn unfinished_jobs_query(
+    filter: &ScheduledJobFilter,
+    start_ts: Option<Timestamp>,
+) -> anyhow::Result<Query> {
+    let mut next_ts_range = vec![match start_ts.max(filter.start_ts) {
+        Some(ts) => IndexRangeExpression::Gte(NEXT_TS_FIELD.clone(), i64::from(ts).into()),
+        None => IndexRangeExpression::Gt(NEXT_TS_FIELD.clone(), value::ConvexValue::Null),
+    }];
+    if let Some(end_ts) = filter.end_ts {
+        next_ts_range.push(IndexRangeExpression::Lt(
+            NEXT_TS_FIELD.clone(),
+            i64::from(end_ts).into(),
+        ));
+    }
+    let query = match &filter.path {
+        Some(path) => {
+            let udf_path = &path.udf_path;
+            let component_path = &path.component;
+            let mut component_path_filter = Expression::Eq(
+                Expression::Field(COMPONENT_PATH_FIELD.clone()).into(),
+                Expression::Literal(maybe_val!(String::from(component_path.clone()))).into(),
+            );
+            if component_path.is_root() {
+                component_path_filter = Expression::Or(vec![
+                    component_path_filter,
+                    Expression::Eq(
+                        Expression::Field(COMPONENT_PATH_FIELD.clone()).into(),
+                        Expression::Literal(maybe_val!(undefined)).into(),
+                    ),
+                ]);
+            }
+            let mut range = vec![IndexRangeExpression::Eq(
+                UDF_PATH_FIELD.clone(),
+                ConvexValue::try_from(udf_path.to_string())?.into(),
+            )];
+            range.extend(next_ts_range);
+            Query::index_range(IndexRange {
+                index_name: SCHEDULED_JOBS_INDEX_BY_UDF_PATH.clone(),
+                range,
+                order: Order::Asc,
+            })
+            .filter(component_path_filter)
+        },
+        None => Query::index_range(IndexRange {
+            index_name: SCHEDULED_JOBS_INDEX.clone(),
+            range: next_ts_range,
+            order: Order::Asc,
+        }),
+    };
+    Ok(query)
+}
+
 /// Same as SchedulerModel but works with the respective virtual table instead
 /// of the underlying system table.
 pub struct VirtualSchedulerModel<'a, RT: Runtime> {
@@ -474,6 +712,41 @@ impl<'a, RT: Runtime> VirtualSchedulerModel<'a, RT> {
             .system_resolved_id_to_virtual_developer_id(system_id)
     }
 
+    pub async fn schedule_idempotent(
+        &mut self,
+        path: CanonicalizedComponentFunctionPath,
+        args: ConvexArray,
+        ts: UnixTimestamp,
+        context: ExecutionContext,
+        idempotency_key: IdempotencyKey,
+    ) -> anyhow::Result<DeveloperDocumentId> {
+        let system_id = SchedulerModel::new(self.tx, self.namespace)
+            .schedule_idempotent(path, args, ts, context, idempotency_key)
+            .await?;
+        self.tx
+            .virtual_system_mapping()
+            .system_resolved_id_to_virtual_developer_id(system_id)
+    }
+
+    /// Like `SchedulerModel::list_matching`, but returns the jobs as
+    /// `_scheduled_functions` documents.
+    pub async fn list_matching(
+        &mut self,
+        filter: ScheduledJobFilter,
+        cursor: Option<ScheduledJobsCursor>,
+        limit: usize,
+    ) -> anyhow::Result<(Vec<DeveloperDocument>, Option<ScheduledJobsCursor>)> {
+        let (documents, next_cursor) = SchedulerModel::new(self.tx, self.namespace)
+            .list_matching_documents(filter, cursor, limit)
+            .await?;
+        let virtual_system_mapping = self.tx.virtual_system_mapping();
+        let documents = documents
+            .into_iter()
+            .map(|doc| scheduled_job_to_virtual_doc(virtual_system_mapping, doc))
+            .try_collect()?;
+        Ok((documents, next_cursor))
+    }
+
     pub async fn cancel(&mut self, virtual_id: DeveloperDocumentId) -> anyhow::Result<()> {
         let table_mapping = self.tx.table_mapping().clone();
         let system_id = self
@@ -1,3 +1,4 @@
+use anyhow::Context;
 use common::{
     components::{
         CanonicalizedComponentFunctionPath,
@@ -5,6 +6,7 @@ use common::{
     },
     types::Timestamp,
 };
+use errors::ErrorMetadata;
 #[cfg(any(test, feature = "testing"))]
 use proptest::prelude::*;
 use serde::{
@@ -16,6 +18,7 @@ use serde_json::Value as JsonValue;
 use value::{
     codegen_convex_serialization,
     ConvexArray,
+    DeveloperDocumentId,
 };
 
 #[derive(Clone, Debug, PartialEq)]
@@ -48,6 +51,10 @@ pub struct ScheduledJob {
     pub original_scheduled_ts: Timestamp,
 
     pub attempts: ScheduledJobAttempts,
+
+    /// Set if the job was scheduled with an idempotency key, in which case no
+    /// other pending job in the component has the same key.
+    pub idempotency_key: Option<String>,
 }
 
 fn args_to_bytes(args: ConvexArray) -> anyhow::Result<ByteBuf> {
@@ -74,6 +81,7 @@ impl ScheduledJob {
             completed_ts,
             original_scheduled_ts,
             attempts,
+            idempotency_key: None,
         })
     }
 
@@ -97,6 +105,8 @@ struct SerializedScheduledJob {
     completed_ts: Option<i64>,
     original_scheduled_ts: Option<i64>,
     attempts: Option<ScheduledJobAttempts>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    idempotency_key: Option<String>,
 }
 
 impl TryFrom<ScheduledJob> for SerializedScheduledJob {
@@ -112,6 +122,7 @@ impl TryFrom<ScheduledJob> for SerializedScheduledJob {
             completed_ts: job.completed_ts.map(|ts| ts.into()),
             original_scheduled_ts: Some(job.original_scheduled_ts.into()),
             attempts: Some(job.attempts),
+            idempotency_key: job.idempotency_key,
         })
     }
 }
@@ -154,6 +165,7 @@ impl TryFrom<SerializedScheduledJob> for ScheduledJob {
             completed_ts,
             original_scheduled_ts,
             attempts: value.attempts.unwrap_or_default(),
+            idempotency_key: value.idempotency_key,
         })
     }
 }
@@ -164,12 +176,134 @@ impl TryFrom<SerializedScheduledJob> for ScheduledJob {
 pub struct ScheduledJobAttempts {
     pub system_errors: u32,
     pub occ_errors: u32,
+    /// The most recent failed attempts, oldest first, so a job that is
+    /// dead-lettered can show why it kept failing.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "prop::collection::vec(any::<ScheduledJobAttemptError>(), 0..4)")
+    )]
+    pub errors: Vec<ScheduledJobAttemptError>,
 }
 
+/// How many failed attempts a scheduled job remembers.
+pub const MAX_SCHEDULED_JOB_ATTEMPT_ERRORS: usize = 10;
+
 impl ScheduledJobAttempts {
     pub fn count_failures(&self) -> u32 {
         self.system_errors + self.occ_errors
     }
+
+    /// Remembers a failed attempt, forgetting the oldest one once there are
+    /// more than `MAX_SCHEDULED_JOB_ATTEMPT_ERRORS`.
+    pub fn record_error(&mut self, ts: Timestamp, error: String) {
+        self.errors.push(ScheduledJobAttemptError {
+            ts: ts.into(),
+            error,
+        });
+        if self.errors.len() > MAX_SCHEDULED_JOB_ATTEMPT_ERRORS {
+            self.errors.remove(0);
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledJobAttemptError {
+    /// When the attempt failed, in nanoseconds since the epoch.
+    pub ts: i64,
+    pub error: String,
+}
+
+/// The longest idempotency key a scheduled job can have, in bytes.
+pub const MAX_IDEMPOTENCY_KEY_LEN: usize = 1024;
+
+/// Schedules a job at most once per key: scheduling again with the key of a
+/// job that hasn't started yet either replaces that job or does nothing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IdempotencyKey {
+    pub key: String,
+    pub on_conflict: OnScheduleConflict,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OnScheduleConflict {
+    /// Cancel the pending job and schedule the new one, so repeatedly
+    /// rescheduling the key debounces it.
+    Replace,
+    /// Keep the pending job and return its ID.
+    Skip,
+}
+
+impl IdempotencyKey {
+    pub fn new(key: String, on_conflict: Option<&str>) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            !key.is_empty() && key.len() <= MAX_IDEMPOTENCY_KEY_LEN,
+            ErrorMetadata::bad_request(
+                "InvalidIdempotencyKey",
+                format!(
+                    "Idempotency keys must be between 1 and {MAX_IDEMPOTENCY_KEY_LEN} bytes long."
+                ),
+            )
+        );
+        let on_conflict = match on_conflict {
+            None | Some("replace") => OnScheduleConflict::Replace,
+            Some("skip") => OnScheduleConflict::Skip,
+            Some(other) => anyhow::bail!(ErrorMetadata::bad_request(
+                "InvalidIdempotencyKey",
+                format!("{other:?} isn't a valid onConflict, expected \"replace\" or \"skip\"."),
+            )),
+        };
+        Ok(Self { key, on_conflict })
+    }
+}
+
+/// Selects the scheduled jobs that haven't finished yet, for listing or
+/// canceling them in bulk.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ScheduledJobFilter {
+    /// Only jobs that run this function.
+    pub path: Option<CanonicalizedComponentFunctionPath>,
+    /// Only jobs that next run at or after this time.
+    pub start_ts: Option<Timestamp>,
+    /// Only jobs that next run before this time.
+    pub end_ts: Option<Timestamp>,
+}
+
+/// Where a page of scheduled jobs left off: the last job returned and when
+/// it next runs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScheduledJobsCursor {
+    pub next_ts: Timestamp,
+    pub id: DeveloperDocumentId,
+}
+
+impl ScheduledJobsCursor {
+    pub fn encode(&self) -> String {
+        format!("{}:{}", i64::from(self.next_ts), self.id.encode())
+    }
+
+    pub fn decode(cursor: &str) -> anyhow::Result<Self> {
+        let (next_ts, id) = cursor
+            .split_once(':')
+            .context(invalid_scheduled_jobs_cursor(cursor))?;
+        Ok(Self {
+            next_ts: next_ts
+                .parse::<i64>()
+                .ok()
+                .and_then(|ts| Timestamp::try_from(ts).ok())
+                .context(invalid_scheduled_jobs_cursor(cursor))?,
+            id: DeveloperDocumentId::decode(id).context(invalid_scheduled_jobs_cursor(cursor))?,
+        })
+    }
+}
+
+fn invalid_scheduled_jobs_cursor(cursor: &str) -> ErrorMetadata {
+    ErrorMetadata::bad_request(
+        "InvalidScheduledJobsCursor",
+        format!("{cursor:?} isn't a valid cursor for listing scheduled jobs."),
+    )
 }
 
 /// The state machine for scheduled jobs. Note that only actions go through the
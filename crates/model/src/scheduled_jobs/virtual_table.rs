@@ -58,42 +58,51 @@ impl VirtualSystemDocMapper for ScheduledJobsDocMapper {
             anyhow::bail!("System document cannot be converted to a virtual document")
         }
 
-        let job: ParsedDocument<ScheduledJob> = doc.clone().try_into()?;
-        let job: ScheduledJob = job.into_value();
-        let udf_args = job.udf_args()?;
-        let public_job = PublicScheduledJob {
-            // TODO(ENG-6920) include component (job.path.component) in virtual table.
-            name: job.path.udf_path,
-            args: udf_args,
-            state: job.state,
-            scheduled_time: timestamp_to_ms(job.original_scheduled_ts)?,
-            completed_time: match job.completed_ts {
-                Some(ts) => Some(timestamp_to_ms(ts)?),
-                None => None,
-            },
-        };
-        let mut public_job_resolved: ConvexObject = public_job.try_into()?;
+        scheduled_job_to_virtual_doc(virtual_system_mapping, doc)
+    }
+}
 
-        let virtual_developer_id =
-            virtual_system_mapping.system_resolved_id_to_virtual_developer_id(doc.id())?;
+/// Converts a `_scheduled_jobs` document into its `_scheduled_functions`
+/// form.
+pub fn scheduled_job_to_virtual_doc(
+    virtual_system_mapping: &VirtualSystemMapping,
+    doc: ResolvedDocument,
+) -> anyhow::Result<DeveloperDocument> {
+    let job: ParsedDocument<ScheduledJob> = doc.clone().try_into()?;
+    let job: ScheduledJob = job.into_value();
+    let udf_args = job.udf_args()?;
+    let public_job = PublicScheduledJob {
+        // TODO(ENG-6920) include component (job.path.component) in virtual table.
+        name: job.path.udf_path,
+        args: udf_args,
+        state: job.state,
+        scheduled_time: timestamp_to_ms(job.original_scheduled_ts)?,
+        completed_time: match job.completed_ts {
+            Some(ts) => Some(timestamp_to_ms(ts)?),
+            None => None,
+        },
+    };
+    let mut public_job_resolved: ConvexObject = public_job.try_into()?;
 
-        let mut fields: BTreeMap<_, _> = public_job_resolved.into();
-        fields.insert(ID_FIELD.to_owned().into(), virtual_developer_id.into());
-        if let Some(t) = doc.creation_time() {
-            fields.insert(
-                CREATION_TIME_FIELD.to_owned().into(),
-                ConvexValue::from(f64::from(t)),
-            );
-        }
-        public_job_resolved = fields.try_into()?;
+    let virtual_developer_id =
+        virtual_system_mapping.system_resolved_id_to_virtual_developer_id(doc.id())?;
 
-        let public_doc = DeveloperDocument::new(
-            virtual_developer_id,
-            doc.creation_time(),
-            public_job_resolved,
+    let mut fields: BTreeMap<_, _> = public_job_resolved.into();
+    fields.insert(ID_FIELD.to_owned().into(), virtual_developer_id.into());
+    if let Some(t) = doc.creation_time() {
+        fields.insert(
+            CREATION_TIME_FIELD.to_owned().into(),
+            ConvexValue::from(f64::from(t)),
         );
-        Ok(public_doc)
     }
+    public_job_resolved = fields.try_into()?;
+
+    let public_doc = DeveloperDocument::new(
+        virtual_developer_id,
+        doc.creation_time(),
+        public_job_resolved,
+    );
+    Ok(public_doc)
 }
 
 #[derive(Clone, Debug, PartialEq)]
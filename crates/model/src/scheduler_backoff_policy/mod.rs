@@ -0,0 +1,113 @@
+//! The deployment's policy for retrying scheduled jobs that hit system
+//! errors, which admins can set to override the `SCHEDULED_JOB_*` knobs.
+use std::sync::LazyLock;
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    query::{
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+};
+use database::{
+    unauthorized_error,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use value::{
+    TableName,
+    TableNamespace,
+};
+
+use self::types::SchedulerBackoffPolicy;
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+pub static SCHEDULER_BACKOFF_POLICY_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_scheduler_backoff_policy"
+        .parse()
+        .expect("Invalid built-in scheduler_backoff_policy table")
+});
+
+pub struct SchedulerBackoffPolicyTable;
+impl SystemTable for SchedulerBackoffPolicyTable {
+    fn table_name(&self) -> &'static TableName {
+        &SCHEDULER_BACKOFF_POLICY_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<SchedulerBackoffPolicy>::try_from(document).map(|_| ())
+    }
+}
+
+/// The scheduler's retry policy, which has at most one row.
+pub struct SchedulerBackoffPolicyModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> SchedulerBackoffPolicyModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    pub async fn get(&mut self) -> anyhow::Result<Option<ParsedDocument<SchedulerBackoffPolicy>>> {
+        let query = Query::full_table_scan(SCHEDULER_BACKOFF_POLICY_TABLE.clone(), Order::Asc);
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        query_stream
+            .expect_at_most_one(self.tx)
+            .await?
+            .map(|document| document.try_into())
+            .transpose()
+    }
+
+    /// The policy the scheduler follows: the one set by an admin, or else the
+    /// one from the knobs.
+    pub async fn policy(&mut self) -> anyhow::Result<SchedulerBackoffPolicy> {
+        Ok(self
+            .get()
+            .await?
+            .map(|policy| policy.into_value())
+            .unwrap_or_else(SchedulerBackoffPolicy::from_knobs))
+    }
+
+    /// Replaces the policy, or removes it if `policy` is `None` so the knobs
+    /// apply again.
+    pub async fn set(&mut self, policy: Option<SchedulerBackoffPolicy>) -> anyhow::Result<()> {
+        if !(self.tx.identity().is_system() || self.tx.identity().is_admin()) {
+            anyhow::bail!(unauthorized_error("set_scheduler_backoff_policy"));
+        }
+        let existing = self.get().await?;
+        match (existing, policy) {
+            (Some(existing), Some(policy)) => {
+                SystemMetadataModel::new_global(self.tx)
+                    .replace(existing.id(), policy.try_into()?)
+                    .await?;
+            },
+            (Some(existing), None) => {
+                SystemMetadataModel::new_global(self.tx)
+                    .delete(existing.id())
+                    .await?;
+            },
+            (None, Some(policy)) => {
+                SystemMetadataModel::new_global(self.tx)
+                    .insert(&SCHEDULER_BACKOFF_POLICY_TABLE, policy.try_into()?)
+                    .await?;
+            },
+            (None, None) => {},
+        }
+        Ok(())
+    }
+}
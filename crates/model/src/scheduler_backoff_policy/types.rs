@@ -0,0 +1,122 @@
+use std::time::Duration;
+
+use common::knobs::{
+    SCHEDULED_JOB_INITIAL_BACKOFF,
+    SCHEDULED_JOB_MAX_ATTEMPTS,
+    SCHEDULED_JOB_MAX_BACKOFF,
+};
+use errors::ErrorMetadata;
+#[cfg(any(test, feature = "testing"))]
+use proptest::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use value::codegen_convex_serialization;
+
+/// The longest an admin can make the scheduler wait between retries.
+pub const MAX_SCHEDULER_BACKOFF: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How the scheduler retries jobs that hit system errors. Retries back off
+/// exponentially from `initial_backoff` up to `max_backoff`, and a job is
+/// dead-lettered once it has failed `max_attempts` times.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct SchedulerBackoffPolicy {
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "(1..=60_000u64).prop_map(Duration::from_millis)")
+    )]
+    pub initial_backoff: Duration,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "(60_000..=86_400_000u64).prop_map(Duration::from_millis)")
+    )]
+    pub max_backoff: Duration,
+    /// `None` retries forever.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "proptest::option::of(1..=1000u32)")
+    )]
+    pub max_attempts: Option<u32>,
+}
+
+fn invalid_backoff_policy(msg: &str) -> ErrorMetadata {
+    ErrorMetadata::bad_request("InvalidSchedulerBackoffPolicy", msg.to_string())
+}
+
+impl SchedulerBackoffPolicy {
+    pub fn new(
+        initial_backoff: Duration,
+        max_backoff: Duration,
+        max_attempts: Option<u32>,
+    ) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            !initial_backoff.is_zero() && initial_backoff <= max_backoff,
+            invalid_backoff_policy("The initial backoff must be between 0 and the max backoff.")
+        );
+        anyhow::ensure!(
+            max_backoff <= MAX_SCHEDULER_BACKOFF,
+            invalid_backoff_policy("The max backoff can be at most a day.")
+        );
+        anyhow::ensure!(
+            max_attempts != Some(0),
+            invalid_backoff_policy("Jobs must be attempted at least once.")
+        );
+        Ok(Self {
+            initial_backoff,
+            max_backoff,
+            max_attempts,
+        })
+    }
+
+    /// The policy used when an admin hasn't set one.
+    pub fn from_knobs() -> Self {
+        Self {
+            initial_backoff: *SCHEDULED_JOB_INITIAL_BACKOFF,
+            max_backoff: *SCHEDULED_JOB_MAX_BACKOFF,
+            max_attempts: Some(*SCHEDULED_JOB_MAX_ATTEMPTS).filter(|attempts| *attempts > 0),
+        }
+    }
+
+    /// Whether a job that has failed `failures` times should stop being
+    /// retried.
+    pub fn is_exhausted(&self, failures: u32) -> bool {
+        self.max_attempts
+            .is_some_and(|max_attempts| failures >= max_attempts)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedSchedulerBackoffPolicy {
+    initial_backoff_ms: i64,
+    max_backoff_ms: i64,
+    max_attempts: Option<i64>,
+}
+
+impl TryFrom<SchedulerBackoffPolicy> for SerializedSchedulerBackoffPolicy {
+    type Error = anyhow::Error;
+
+    fn try_from(policy: SchedulerBackoffPolicy) -> anyhow::Result<Self> {
+        Ok(Self {
+            initial_backoff_ms: i64::try_from(policy.initial_backoff.as_millis())?,
+            max_backoff_ms: i64::try_from(policy.max_backoff.as_millis())?,
+            max_attempts: policy.max_attempts.map(i64::from),
+        })
+    }
+}
+
+impl TryFrom<SerializedSchedulerBackoffPolicy> for SchedulerBackoffPolicy {
+    type Error = anyhow::Error;
+
+    fn try_from(policy: SerializedSchedulerBackoffPolicy) -> anyhow::Result<Self> {
+        Ok(Self {
+            initial_backoff: Duration::from_millis(u64::try_from(policy.initial_backoff_ms)?),
+            max_backoff: Duration::from_millis(u64::try_from(policy.max_backoff_ms)?),
+            max_attempts: policy.max_attempts.map(u32::try_from).transpose()?,
+        })
+    }
+}
+
+codegen_convex_serialization!(SchedulerBackoffPolicy, SerializedSchedulerBackoffPolicy);
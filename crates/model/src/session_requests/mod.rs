@@ -37,7 +37,6 @@ pub mod types;
 
 use types::{
     SessionRequestIdentifier,
-    SessionRequestOutcome,
     SessionRequestRecord,
 };
 
@@ -60,9 +59,15 @@ static SESSION_ID_FIELD: LazyLock<FieldPath> =
 static REQUEST_ID_FIELD: LazyLock<FieldPath> =
     LazyLock::new(|| "requestId".parse().expect("Invalid built-in field"));
 
+static MUTATION_ID_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "mutationId".parse().expect("Invalid built-in field"));
+
 pub static SESSION_REQUESTS_INDEX: LazyLock<IndexName> =
     LazyLock::new(|| system_index(&SESSION_REQUESTS_TABLE, "by_session_id_and_request_id"));
 
+pub static SESSION_REQUESTS_BY_MUTATION_ID_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&SESSION_REQUESTS_TABLE, "by_mutation_id"));
+
 pub struct SessionRequestsTable;
 impl SystemTable for SessionRequestsTable {
     fn table_name(&self) -> &'static TableName {
@@ -70,12 +75,18 @@ impl SystemTable for SessionRequestsTable {
     }
 
     fn indexes(&self) -> Vec<SystemIndex> {
-        vec![SystemIndex {
-            name: SESSION_REQUESTS_INDEX.clone(),
-            fields: vec![SESSION_ID_FIELD.clone(), REQUEST_ID_FIELD.clone()]
-                .try_into()
-                .unwrap(),
-        }]
+        vec![
+            SystemIndex {
+                name: SESSION_REQUESTS_INDEX.clone(),
+                fields: vec![SESSION_ID_FIELD.clone(), REQUEST_ID_FIELD.clone()]
+                    .try_into()
+                    .unwrap(),
+            },
+            SystemIndex {
+                name: SESSION_REQUESTS_BY_MUTATION_ID_INDEX.clone(),
+                fields: vec![MUTATION_ID_FIELD.clone()].try_into().unwrap(),
+            },
+        ]
     }
 
     fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
@@ -96,7 +107,7 @@ impl<'a, RT: Runtime> SessionRequestModel<'a, RT> {
         &mut self,
         request_identifier: &SessionRequestIdentifier,
         identity: Identity,
-    ) -> anyhow::Result<Option<(Timestamp, SessionRequestOutcome)>> {
+    ) -> anyhow::Result<Option<(Timestamp, SessionRequestRecord)>> {
         // We only expect this function to be called by the framework as part
         // of a mutation UDF. We require passing in a system identity to confirm
         // that the caller isn't letting a user call this directly.
@@ -107,19 +118,29 @@ impl<'a, RT: Runtime> SessionRequestModel<'a, RT> {
         // Query whether this request has been seen by the system already. It's
         // important we scan over the session request index and include it
         // in our read set so we can ensure the request happens exactly once.
-        let index_range_query = IndexRange {
-            index_name: SESSION_REQUESTS_INDEX.clone(),
-            range: vec![
-                IndexRangeExpression::Eq(
-                    SESSION_ID_FIELD.clone(),
-                    ConvexValue::try_from(request_identifier.session_id.to_string())?.into(),
-                ),
-                IndexRangeExpression::Eq(
-                    REQUEST_ID_FIELD.clone(),
-                    ConvexValue::from(request_identifier.request_id as i64).into(),
-                ),
-            ],
-            order: Order::Asc,
+        let index_range_query = match request_identifier.mutation_id {
+            Some(ref mutation_id) => IndexRange {
+                index_name: SESSION_REQUESTS_BY_MUTATION_ID_INDEX.clone(),
+                range: vec![IndexRangeExpression::Eq(
+                    MUTATION_ID_FIELD.clone(),
+                    ConvexValue::try_from(mutation_id.clone())?.into(),
+                )],
+                order: Order::Asc,
+            },
+            None => IndexRange {
+                index_name: SESSION_REQUESTS_INDEX.clone(),
+                range: vec![
+                    IndexRangeExpression::Eq(
+                        SESSION_ID_FIELD.clone(),
+                        ConvexValue::try_from(request_identifier.session_id.to_string())?.into(),
+                    ),
+                    IndexRangeExpression::Eq(
+                        REQUEST_ID_FIELD.clone(),
+                        ConvexValue::from(request_identifier.request_id as i64).into(),
+                    ),
+                ],
+                order: Order::Asc,
+            },
         };
         let query = Query::index_range(index_range_query);
         let (doc, ts): (ParsedDocument<SessionRequestRecord>, Timestamp) = {
@@ -145,9 +166,7 @@ impl<'a, RT: Runtime> SessionRequestModel<'a, RT> {
             (doc.try_into()?, ts)
         };
 
-        let outcome = doc.into_value().outcome;
-
-        Ok(Some((ts, outcome)))
+        Ok(Some((ts, doc.into_value())))
     }
 
     pub async fn record_session_request(
@@ -18,7 +18,12 @@ use common::{
         ConvexValue,
     },
 };
-use value::ConvexObject;
+use value::{
+    sha256::Sha256,
+    ConvexArray,
+    ConvexObject,
+    FieldName,
+};
 
 /// Identifier for a single request in a session
 #[derive(Clone, Debug)]
@@ -29,6 +34,10 @@ use value::ConvexObject;
 pub struct SessionRequestIdentifier {
     pub session_id: SessionId,
     pub request_id: SessionRequestSeqNumber,
+    /// The client-chosen ID of the mutation, if it has one. Requests with a
+    /// mutation ID are deduplicated by it instead of by session and request
+    /// ID, so retries from other sessions are deduplicated too.
+    pub mutation_id: Option<String>,
 }
 
 /// Information for a single session request
@@ -40,6 +49,12 @@ pub struct SessionRequestIdentifier {
 pub struct SessionRequestRecord {
     pub session_id: SessionId,
     pub request_id: SessionRequestSeqNumber,
+    pub mutation_id: Option<String>,
+    /// The mutation that was run and a hash of its arguments, to check that
+    /// retries are of the same call. Missing for records written before
+    /// they were recorded.
+    pub udf_path: Option<String>,
+    pub args_hash: Option<String>,
 
     pub outcome: SessionRequestOutcome,
 
@@ -52,12 +67,23 @@ impl TryFrom<SessionRequestRecord> for ConvexObject {
     type Error = anyhow::Error;
 
     fn try_from(request: SessionRequestRecord) -> anyhow::Result<Self> {
-        obj!(
+        let mut fields: BTreeMap<FieldName, ConvexValue> = obj!(
             "sessionId" => request.session_id.to_string(),
             "requestId" => (request.request_id as i64),
             "outcome" =>  ConvexValue::Object(request.outcome.try_into()?),
             "identity" => request.identity.to_string(),
-        )
+        )?
+        .into();
+        for (field, value) in [
+            ("mutationId", request.mutation_id),
+            ("udfPath", request.udf_path),
+            ("argsHash", request.args_hash),
+        ] {
+            if let Some(value) = value {
+                fields.insert(field.parse()?, value.try_into()?);
+            }
+        }
+        fields.try_into()
     }
 }
 
@@ -75,6 +101,21 @@ impl TryFrom<ConvexObject> for SessionRequestRecord {
             Some(ConvexValue::Int64(i)) => i.try_into()?,
             v => anyhow::bail!("Invalid requestId field for SessionRequest: {:?}", v),
         };
+        let mutation_id = match fields.remove("mutationId") {
+            Some(ConvexValue::String(s)) => Some(s.to_string()),
+            None => None,
+            v => anyhow::bail!("Invalid mutationId field for SessionRequest: {:?}", v),
+        };
+        let udf_path = match fields.remove("udfPath") {
+            Some(ConvexValue::String(s)) => Some(s.to_string()),
+            None => None,
+            v => anyhow::bail!("Invalid udfPath field for SessionRequest: {:?}", v),
+        };
+        let args_hash = match fields.remove("argsHash") {
+            Some(ConvexValue::String(s)) => Some(s.to_string()),
+            None => None,
+            v => anyhow::bail!("Invalid argsHash field for SessionRequest: {:?}", v),
+        };
         let outcome: SessionRequestOutcome = match fields.remove("outcome") {
             Some(ConvexValue::Object(s)) => s.try_into()?,
             v => anyhow::bail!("Invalid result field for SessionRequest: {:?}", v),
@@ -87,12 +128,21 @@ impl TryFrom<ConvexObject> for SessionRequestRecord {
         Ok(SessionRequestRecord {
             session_id,
             request_id,
+            mutation_id,
+            udf_path,
+            args_hash,
             outcome,
             identity,
         })
     }
 }
 
+/// Hashes a mutation's arguments for [`SessionRequestRecord::args_hash`].
+pub fn mutation_args_hash(args: &ConvexArray) -> anyhow::Result<String> {
+    let args = json_serialize(ConvexValue::Array(args.clone()))?;
+    Ok(Sha256::hash(args.as_bytes()).as_hex())
+}
+
 #[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
 pub enum SessionRequestOutcome {
@@ -0,0 +1,176 @@
+//! A capped log of function executions that were slow, or read or wrote many
+//! documents, for finding the functions worth optimizing.
+use std::sync::LazyLock;
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    query::{
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+};
+use database::{
+    require_admin_or_system,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use value::{
+    TableName,
+    TableNamespace,
+};
+
+use self::types::SlowExecution;
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+pub static SLOW_EXECUTIONS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_slow_executions"
+        .parse()
+        .expect("Invalid built-in slow_executions table")
+});
+
+pub struct SlowExecutionsTable;
+impl SystemTable for SlowExecutionsTable {
+    fn table_name(&self) -> &'static TableName {
+        &SLOW_EXECUTIONS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<SlowExecution>::try_from(document).map(|_| ())
+    }
+}
+
+pub struct SlowExecutionsModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> SlowExecutionsModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    pub async fn insert(&mut self, execution: SlowExecution) -> anyhow::Result<()> {
+        require_admin_or_system(self.tx.identity(), "insert_slow_execution")?;
+        SystemMetadataModel::new_global(self.tx)
+            .insert(&SLOW_EXECUTIONS_TABLE, execution.try_into()?)
+            .await?;
+        Ok(())
+    }
+
+    /// Up to `limit` of the most recently recorded slow executions, newest
+    /// first.
+    pub async fn list(
+        &mut self,
+        limit: usize,
+    ) -> anyhow::Result<Vec<ParsedDocument<SlowExecution>>> {
+        require_admin_or_system(self.tx.identity(), "list_slow_executions")?;
+        let query = Query::full_table_scan(SLOW_EXECUTIONS_TABLE.clone(), Order::Desc);
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        let mut executions = vec![];
+        while executions.len() < limit
+            && let Some(document) = query_stream.next(self.tx, None).await?
+        {
+            executions.push(document.try_into()?);
+        }
+        Ok(executions)
+    }
+
+    /// Deletes the oldest slow executions so at most `max_rows` are kept, up
+    /// to `limit` at a time. Returns how many were deleted.
+    pub async fn trim(&mut self, max_rows: usize, limit: usize) -> anyhow::Result<usize> {
+        require_admin_or_system(self.tx.identity(), "trim_slow_executions")?;
+        let count = self
+            .tx
+            .count(TableNamespace::Global, &SLOW_EXECUTIONS_TABLE)
+            .await? as usize;
+        let to_delete = count.saturating_sub(max_rows).min(limit);
+        let query = Query::full_table_scan(SLOW_EXECUTIONS_TABLE.clone(), Order::Asc);
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        let mut deleted = 0;
+        while deleted < to_delete
+            && let Some(document) = query_stream.next(self.tx, None).await?
+        {
+            SystemMetadataModel::new_global(self.tx)
+                .delete(document.id())
+                .await?;
+            deleted += 1;
+        }
+        Ok(deleted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::{
+        runtime::UnixTimestamp,
+        types::UdfType,
+    };
+    use database::test_helpers::DbFixtures;
+    use keybroker::Identity;
+    use runtime::testing::TestRuntime;
+
+    use super::{
+        types::{
+            SlowExecution,
+            SlowExecutionReason,
+        },
+        SlowExecutionsModel,
+    };
+    use crate::test_helpers::DbFixturesWithModel;
+
+    fn slow_execution(function: &str) -> SlowExecution {
+        SlowExecution {
+            udf_type: UdfType::Query,
+            function: function.to_string(),
+            started_at: UnixTimestamp::from_millis(1_700_000_000_000),
+            reasons: vec![SlowExecutionReason::ExecutionTime],
+            failed: false,
+            execution_time_ms: 2500.0,
+            args_size_bytes: Some(16),
+            database_read_bytes: 0,
+            database_write_bytes: 0,
+            syscalls: vec![],
+            tables: vec![],
+        }
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_trim_keeps_newest(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin(Identity::system()).await?;
+        for function in ["a:f", "b:f", "c:f"] {
+            SlowExecutionsModel::new(&mut tx)
+                .insert(slow_execution(function))
+                .await?;
+        }
+        db.commit(tx).await?;
+
+        let mut tx = db.begin(Identity::system()).await?;
+        let deleted = SlowExecutionsModel::new(&mut tx).trim(2, 10).await?;
+        assert_eq!(deleted, 1);
+        let functions: Vec<_> = SlowExecutionsModel::new(&mut tx)
+            .list(10)
+            .await?
+            .into_iter()
+            .map(|execution| execution.into_value().function)
+            .collect();
+        assert_eq!(functions, vec!["c:f", "b:f"]);
+
+        let mut tx = db.begin(Identity::Unknown).await?;
+        assert!(SlowExecutionsModel::new(&mut tx).list(10).await.is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,218 @@
+use std::str::FromStr;
+
+use common::{
+    runtime::UnixTimestamp,
+    types::UdfType,
+};
+#[cfg(any(test, feature = "testing"))]
+use proptest::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use value::codegen_convex_serialization;
+
+/// A function execution that was over one of the slow execution thresholds.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct SlowExecution {
+    pub udf_type: UdfType,
+    /// The function's path, or the route for HTTP actions.
+    pub function: String,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "(0..=i64::MAX as u64 / 1024).prop_map(UnixTimestamp::from_millis)")
+    )]
+    pub started_at: UnixTimestamp,
+    pub reasons: Vec<SlowExecutionReason>,
+    pub failed: bool,
+    #[cfg_attr(any(test, feature = "testing"), proptest(strategy = "0.0..1e9f64"))]
+    pub execution_time_ms: f64,
+    /// Not known for HTTP actions.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "proptest::option::of(0..=i64::MAX as u64)")
+    )]
+    pub args_size_bytes: Option<u64>,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=i64::MAX as u64")
+    )]
+    pub database_read_bytes: u64,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=i64::MAX as u64")
+    )]
+    pub database_write_bytes: u64,
+    /// Where the execution spent its time, by syscall, like "1.0/db/get".
+    pub syscalls: Vec<SyscallTiming>,
+    /// The documents read and written, by table.
+    pub tables: Vec<TableAccess>,
+}
+
+impl SlowExecution {
+    pub fn rows_read(&self) -> u64 {
+        self.tables.iter().map(|table| table.rows_read).sum()
+    }
+
+    pub fn rows_written(&self) -> u64 {
+        self.tables.iter().map(|table| table.rows_written).sum()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub enum SlowExecutionReason {
+    ExecutionTime,
+    RowsRead,
+    RowsWritten,
+}
+
+impl SlowExecutionReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ExecutionTime => "executionTime",
+            Self::RowsRead => "rowsRead",
+            Self::RowsWritten => "rowsWritten",
+        }
+    }
+}
+
+impl FromStr for SlowExecutionReason {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "executionTime" => Ok(Self::ExecutionTime),
+            "rowsRead" => Ok(Self::RowsRead),
+            "rowsWritten" => Ok(Self::RowsWritten),
+            _ => anyhow::bail!("Invalid slow execution reason {s}"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+#[serde(rename_all = "camelCase")]
+pub struct SyscallTiming {
+    pub name: String,
+    pub invocations: u32,
+    #[cfg_attr(any(test, feature = "testing"), proptest(strategy = "0.0..1e9f64"))]
+    pub total_duration_ms: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct TableAccess {
+    pub table: String,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=i64::MAX as u64")
+    )]
+    pub rows_read: u64,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=i64::MAX as u64")
+    )]
+    pub rows_written: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedTableAccess {
+    table: String,
+    rows_read: i64,
+    rows_written: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedSlowExecution {
+    udf_type: String,
+    function: String,
+    /// Milliseconds since the epoch, like `_creationTime`.
+    started_at: f64,
+    reasons: Vec<String>,
+    failed: bool,
+    execution_time_ms: f64,
+    args_size_bytes: Option<i64>,
+    database_read_bytes: i64,
+    database_write_bytes: i64,
+    syscalls: Vec<SyscallTiming>,
+    tables: Vec<SerializedTableAccess>,
+}
+
+impl TryFrom<SlowExecution> for SerializedSlowExecution {
+    type Error = anyhow::Error;
+
+    fn try_from(execution: SlowExecution) -> anyhow::Result<Self> {
+        Ok(Self {
+            udf_type: execution.udf_type.to_lowercase_string().to_string(),
+            function: execution.function,
+            started_at: execution.started_at.as_ms_since_epoch()? as f64,
+            reasons: execution
+                .reasons
+                .iter()
+                .map(|reason| reason.as_str().to_string())
+                .collect(),
+            failed: execution.failed,
+            execution_time_ms: execution.execution_time_ms,
+            args_size_bytes: execution.args_size_bytes.map(i64::try_from).transpose()?,
+            database_read_bytes: execution.database_read_bytes.try_into()?,
+            database_write_bytes: execution.database_write_bytes.try_into()?,
+            syscalls: execution.syscalls,
+            tables: execution
+                .tables
+                .into_iter()
+                .map(|table| {
+                    anyhow::Ok(SerializedTableAccess {
+                        table: table.table,
+                        rows_read: table.rows_read.try_into()?,
+                        rows_written: table.rows_written.try_into()?,
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?,
+        })
+    }
+}
+
+impl TryFrom<SerializedSlowExecution> for SlowExecution {
+    type Error = anyhow::Error;
+
+    fn try_from(execution: SerializedSlowExecution) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            execution.started_at >= 0.0 && execution.started_at.fract() == 0.0,
+            "Invalid slow execution start time {}",
+            execution.started_at
+        );
+        Ok(Self {
+            udf_type: execution.udf_type.parse()?,
+            function: execution.function,
+            started_at: UnixTimestamp::from_millis(execution.started_at as u64),
+            reasons: execution
+                .reasons
+                .iter()
+                .map(|reason| reason.parse())
+                .collect::<anyhow::Result<_>>()?,
+            failed: execution.failed,
+            execution_time_ms: execution.execution_time_ms,
+            args_size_bytes: execution.args_size_bytes.map(u64::try_from).transpose()?,
+            database_read_bytes: execution.database_read_bytes.try_into()?,
+            database_write_bytes: execution.database_write_bytes.try_into()?,
+            syscalls: execution.syscalls,
+            tables: execution
+                .tables
+                .into_iter()
+                .map(|table| {
+                    anyhow::Ok(TableAccess {
+                        table: table.table,
+                        rows_read: table.rows_read.try_into()?,
+                        rows_written: table.rows_written.try_into()?,
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?,
+        })
+    }
+}
+
+codegen_convex_serialization!(SlowExecution, SerializedSlowExecution);
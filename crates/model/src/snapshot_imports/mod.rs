@@ -37,9 +37,13 @@ use value::{
 use self::types::{
     ImportFormat,
     ImportMode,
+    ImportProgress,
     ImportState,
     ImportTableCheckpoint,
+    ImportTableSelection,
     SnapshotImport,
+    SnapshotImportUpload,
+    SnapshotImportUploadChunk,
 };
 use crate::{
     SystemIndex,
@@ -54,6 +58,9 @@ pub static SNAPSHOT_IMPORTS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
         .expect("Invalid built-in snapshot imports table")
 });
 
+/// The failure message of imports that were canceled rather than failing.
+pub const IMPORT_CANCELED_MESSAGE: &str = "Import canceled";
+
 pub struct SnapshotImportsTable;
 impl SystemTable for SnapshotImportsTable {
     fn table_name(&self) -> &'static TableName {
@@ -69,6 +76,31 @@ impl SystemTable for SnapshotImportsTable {
     }
 }
 
+pub static SNAPSHOT_IMPORT_UPLOADS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_snapshot_import_uploads"
+        .parse()
+        .expect("Invalid built-in snapshot import uploads table")
+});
+
+/// Upper bound on the number of chunks in a resumable upload, which keeps the
+/// upload's document well under the document size limit.
+pub const MAX_SNAPSHOT_IMPORT_UPLOAD_CHUNKS: u32 = 2048;
+
+pub struct SnapshotImportUploadsTable;
+impl SystemTable for SnapshotImportUploadsTable {
+    fn table_name(&self) -> &'static TableName {
+        &SNAPSHOT_IMPORT_UPLOADS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<SnapshotImportUpload>::try_from(document).map(|_| ())
+    }
+}
+
 pub struct SnapshotImportModel<'a, RT: Runtime> {
     tx: &'a mut Transaction<RT>,
 }
@@ -111,6 +143,7 @@ impl<'a, RT: Runtime> SnapshotImportModel<'a, RT> {
         component_path: ComponentPath,
         object_key: ObjectKey,
         requestor: ImportRequestor,
+        table_selection: ImportTableSelection,
     ) -> anyhow::Result<ResolvedDocumentId> {
         let snapshot_import = SnapshotImport {
             state: ImportState::Uploaded,
@@ -121,6 +154,9 @@ impl<'a, RT: Runtime> SnapshotImportModel<'a, RT> {
             member_id: self.tx.identity().member_id(),
             checkpoints: None,
             requestor,
+            table_selection,
+            progress: None,
+            id_mapping_object_key: None,
         };
         let id = SystemMetadataModel::new_global(self.tx)
             .insert(
@@ -226,7 +262,8 @@ impl<'a, RT: Runtime> SnapshotImportModel<'a, RT> {
         let current_state = self.must_get_state(id).await?;
         match current_state {
             ImportState::Uploaded | ImportState::WaitingForConfirmation { .. } => {
-                self.fail_import(id, "Import canceled".to_string()).await?
+                self.fail_import(id, IMPORT_CANCELED_MESSAGE.to_string())
+                    .await?
             },
             // TODO: support cancelling imports in progress
             ImportState::InProgress { .. } => anyhow::bail!("Cannot cancel an import in progress"),
@@ -247,12 +284,25 @@ impl<'a, RT: Runtime> SnapshotImportModel<'a, RT> {
         id: ResolvedDocumentId,
         ts: Timestamp,
         num_rows_written: u64,
+        id_mapping_object_key: Option<ObjectKey>,
     ) -> anyhow::Result<()> {
         self.update_state(id, move |_| ImportState::Completed {
             ts,
             num_rows_written: num_rows_written as i64,
         })
-        .await
+        .await?;
+        if let Some(id_mapping_object_key) = id_mapping_object_key {
+            SystemMetadataModel::new_global(self.tx)
+                .patch(
+                    id,
+                    patch_value!(
+                        "id_mapping_object_key" =>
+                            Some(ConvexValue::try_from(id_mapping_object_key.to_string())?)
+                    )?,
+                )
+                .await?;
+        }
+        Ok(())
     }
 
     pub async fn fail_import(
@@ -390,6 +440,20 @@ impl<'a, RT: Runtime> SnapshotImportModel<'a, RT> {
         .await
     }
 
+    pub async fn update_progress(
+        &mut self,
+        id: ResolvedDocumentId,
+        progress: ImportProgress,
+    ) -> anyhow::Result<()> {
+        SystemMetadataModel::new_global(self.tx)
+            .patch(
+                id,
+                patch_value!("progress" => Some(ConvexValue::Object(progress.try_into()?)))?,
+            )
+            .await?;
+        Ok(())
+    }
+
     pub async fn import_in_state(
         &mut self,
         import_state: ImportState,
@@ -414,6 +478,82 @@ impl<'a, RT: Runtime> SnapshotImportModel<'a, RT> {
     }
 }
 
+pub struct SnapshotImportUploadModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> SnapshotImportUploadModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    pub async fn get(
+        &mut self,
+        id: ResolvedDocumentId,
+    ) -> anyhow::Result<Option<ParsedDocument<SnapshotImportUpload>>> {
+        anyhow::ensure!(self
+            .tx
+            .table_mapping()
+            .namespace(TableNamespace::Global)
+            .tablet_matches_name(id.tablet_id, SnapshotImportUploadsTable.table_name()));
+        match self.tx.get(id).await? {
+            None => Ok(None),
+            Some(doc) => Ok(Some(doc.try_into()?)),
+        }
+    }
+
+    pub async fn must_get(
+        &mut self,
+        id: ResolvedDocumentId,
+    ) -> anyhow::Result<ParsedDocument<SnapshotImportUpload>> {
+        self.get(id).await?.context(ErrorMetadata::not_found(
+            "ImportUploadNotFound",
+            format!("import upload {id} not found"),
+        ))
+    }
+
+    pub async fn start_upload(&mut self) -> anyhow::Result<ResolvedDocumentId> {
+        let upload = SnapshotImportUpload {
+            member_id: self.tx.identity().member_id(),
+            chunks: vec![],
+        };
+        SystemMetadataModel::new_global(self.tx)
+            .insert(SnapshotImportUploadsTable.table_name(), upload.try_into()?)
+            .await
+    }
+
+    /// Record an uploaded chunk. Returns the chunk it replaced, whose object
+    /// the caller should delete.
+    pub async fn record_chunk(
+        &mut self,
+        id: ResolvedDocumentId,
+        chunk: SnapshotImportUploadChunk,
+    ) -> anyhow::Result<Option<SnapshotImportUploadChunk>> {
+        anyhow::ensure!(
+            chunk.index < MAX_SNAPSHOT_IMPORT_UPLOAD_CHUNKS,
+            ErrorMetadata::bad_request(
+                "TooManyImportChunks",
+                format!(
+                    "Chunk index {} is too large: an upload can have at most \
+                     {MAX_SNAPSHOT_IMPORT_UPLOAD_CHUNKS} chunks",
+                    chunk.index
+                ),
+            )
+        );
+        let mut upload = self.must_get(id).await?.into_value();
+        let replaced = upload.insert_chunk(chunk);
+        SystemMetadataModel::new_global(self.tx)
+            .replace(id, upload.try_into()?)
+            .await?;
+        Ok(replaced)
+    }
+
+    pub async fn delete(&mut self, id: ResolvedDocumentId) -> anyhow::Result<()> {
+        SystemMetadataModel::new_global(self.tx).delete(id).await?;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use anyhow::Context;
@@ -427,6 +567,7 @@ mod tests {
             types::{
                 ImportFormat,
                 ImportMode,
+                ImportTableSelection,
             },
             SnapshotImportModel,
         },
@@ -446,6 +587,7 @@ mod tests {
                 ComponentPath::root(),
                 "objectkey".try_into()?,
                 ImportRequestor::SnapshotImport,
+                ImportTableSelection::default(),
             )
             .await?;
         let doc = imports_model.get(id).await?.context("Doc missing?")?;
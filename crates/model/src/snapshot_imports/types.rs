@@ -1,3 +1,8 @@
+use std::collections::{
+    BTreeMap,
+    BTreeSet,
+};
+
 use common::{
     components::ComponentPath,
     types::{
@@ -6,6 +11,7 @@ use common::{
         TableName,
     },
 };
+use errors::ErrorMetadata;
 use serde::{
     Deserialize,
     Serialize,
@@ -13,6 +19,8 @@ use serde::{
 use sync_types::Timestamp;
 use value::{
     codegen_convex_serialization,
+    sha256::Sha256Digest,
+    FieldName,
     TabletId,
 };
 
@@ -27,6 +35,12 @@ pub struct SnapshotImport {
     pub member_id: Option<MemberId>,
     pub checkpoints: Option<Vec<ImportTableCheckpoint>>,
     pub requestor: ImportRequestor,
+    pub table_selection: ImportTableSelection,
+    pub progress: Option<ImportProgress>,
+    /// The import's old `_id` to new `_id` mapping for documents that got new
+    /// IDs, as JSON lines in snapshot imports storage. Set when the import
+    /// completes.
+    pub id_mapping_object_key: Option<ObjectKey>,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
@@ -39,6 +53,9 @@ struct SerializedSnapshotImport {
     member_id: Option<i64>,
     checkpoints: Option<Vec<SerializedImportTableCheckpoint>>,
     requestor: SerializedImportRequestor,
+    table_selection: Option<SerializedImportTableSelection>,
+    progress: Option<SerializedImportProgress>,
+    id_mapping_object_key: Option<String>,
 }
 
 impl From<SnapshotImport> for SerializedSnapshotImport {
@@ -54,6 +71,10 @@ impl From<SnapshotImport> for SerializedSnapshotImport {
                 .checkpoints
                 .map(|checkpoints| checkpoints.into_iter().map(Into::into).collect()),
             requestor: import.requestor.into(),
+            table_selection: (!import.table_selection.is_empty())
+                .then(|| import.table_selection.into()),
+            progress: import.progress.map(Into::into),
+            id_mapping_object_key: import.id_mapping_object_key.map(|key| key.to_string()),
         }
     }
 }
@@ -74,16 +95,80 @@ impl TryFrom<SerializedSnapshotImport> for SnapshotImport {
                 .map(|checkpoints| checkpoints.into_iter().map(TryInto::try_into).try_collect())
                 .transpose()?,
             requestor: import.requestor.into(),
+            table_selection: import
+                .table_selection
+                .map(TryInto::try_into)
+                .transpose()?
+                .unwrap_or_default(),
+            progress: import.progress.map(TryInto::try_into).transpose()?,
+            id_mapping_object_key: import
+                .id_mapping_object_key
+                .map(TryInto::try_into)
+                .transpose()?,
         })
     }
 }
 
 codegen_convex_serialization!(SnapshotImport, SerializedSnapshotImport);
 
+/// Running totals for an import that's writing documents, so clients
+/// subscribed to the import can show a live progress bar. Unlike
+/// `ImportTableCheckpoint`s, these cover every table written so far.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct ImportProgress {
+    pub component_path: ComponentPath,
+    /// The table currently being written, as named in the import.
+    pub current_table: Option<TableName>,
+    pub documents_written: u64,
+    /// Size of the documents and files written.
+    pub bytes_written: u64,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+struct SerializedImportProgress {
+    component_path: Option<String>,
+    current_table: Option<String>,
+    documents_written: i64,
+    bytes_written: i64,
+}
+
+impl From<ImportProgress> for SerializedImportProgress {
+    fn from(progress: ImportProgress) -> Self {
+        SerializedImportProgress {
+            component_path: progress.component_path.serialize(),
+            current_table: progress.current_table.map(|table| table.to_string()),
+            documents_written: progress.documents_written as i64,
+            bytes_written: progress.bytes_written as i64,
+        }
+    }
+}
+
+impl TryFrom<SerializedImportProgress> for ImportProgress {
+    type Error = anyhow::Error;
+
+    fn try_from(progress: SerializedImportProgress) -> anyhow::Result<Self> {
+        Ok(ImportProgress {
+            component_path: ComponentPath::deserialize(progress.component_path.as_deref())?,
+            current_table: progress
+                .current_table
+                .map(|table| table.parse())
+                .transpose()?,
+            documents_written: progress.documents_written as u64,
+            bytes_written: progress.bytes_written as u64,
+        })
+    }
+}
+
+codegen_convex_serialization!(ImportProgress, SerializedImportProgress);
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 #[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
 pub enum ImportFormat {
-    Csv(TableName),
+    /// CSV with a header row. Columns with a type are parsed as that type,
+    /// and the remaining columns are parsed as numbers if possible and strings
+    /// otherwise.
+    Csv(TableName, BTreeMap<FieldName, CsvColumnType>),
     JsonLines(TableName),
     JsonArray(TableName),
     Zip,
@@ -93,7 +178,11 @@ pub enum ImportFormat {
 #[serde(tag = "format")]
 pub enum SerializedImportFormat {
     #[serde(rename = "csv")]
-    Csv { table: String },
+    Csv {
+        table: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        column_types: Option<BTreeMap<String, String>>,
+    },
     #[serde(rename = "jsonl")]
     JsonLines { table: String },
     #[serde(rename = "json_array")]
@@ -105,8 +194,14 @@ pub enum SerializedImportFormat {
 impl From<ImportFormat> for SerializedImportFormat {
     fn from(format: ImportFormat) -> SerializedImportFormat {
         match format {
-            ImportFormat::Csv(table) => SerializedImportFormat::Csv {
+            ImportFormat::Csv(table, column_types) => SerializedImportFormat::Csv {
                 table: table.to_string(),
+                column_types: (!column_types.is_empty()).then(|| {
+                    column_types
+                        .into_iter()
+                        .map(|(field, column_type)| (field.to_string(), column_type.to_string()))
+                        .collect()
+                }),
             },
             ImportFormat::JsonLines(table) => SerializedImportFormat::JsonLines {
                 table: table.to_string(),
@@ -124,7 +219,17 @@ impl TryFrom<SerializedImportFormat> for ImportFormat {
 
     fn try_from(format: SerializedImportFormat) -> anyhow::Result<ImportFormat> {
         match format {
-            SerializedImportFormat::Csv { table } => Ok(ImportFormat::Csv(table.parse()?)),
+            SerializedImportFormat::Csv {
+                table,
+                column_types,
+            } => Ok(ImportFormat::Csv(
+                table.parse()?,
+                column_types
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|(field, column_type)| Ok((field.parse()?, column_type.parse()?)))
+                    .collect::<anyhow::Result<_>>()?,
+            )),
             SerializedImportFormat::JsonLines { table } => {
                 Ok(ImportFormat::JsonLines(table.parse()?))
             },
@@ -136,6 +241,25 @@ impl TryFrom<SerializedImportFormat> for ImportFormat {
     }
 }
 
+/// How to parse the cells of a CSV column. Empty cells in non-string columns
+/// are left out of the imported document.
+#[derive(Debug, Deserialize, Clone, Copy, Eq, PartialEq, strum::EnumString, strum::Display)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+#[serde(rename_all = "camelCase")]
+#[strum(serialize_all = "camelCase")]
+pub enum CsvColumnType {
+    /// Keeps the cell as is, even if it looks like a number.
+    String,
+    /// A finite float64, e.g. `1.5` or `-3`.
+    Number,
+    /// `true` or `false`, case-insensitively.
+    Boolean,
+    /// An ISO 8601 date (`2024-01-31`) or datetime (`2024-01-31T12:00:00Z`),
+    /// stored as milliseconds since the Unix epoch. Datetimes without an
+    /// offset are interpreted as UTC.
+    Date,
+}
+
 mod import_format_serde {
     use value::codegen_convex_serialization;
 
@@ -374,6 +498,167 @@ pub enum ImportMode {
     RequireEmpty,
 }
 
+/// Which tables of an import to write, under which names, and whether they
+/// keep their document IDs. Table names are the names in the import, and
+/// apply in every component it contains.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct ImportTableSelection {
+    /// If set, only these tables are imported and the rest are skipped.
+    pub allowlist: Option<BTreeSet<TableName>>,
+    /// Tables to import under a different name, e.g. `users` as
+    /// `users_staging`. Renamed tables get new table numbers and document IDs,
+    /// so they never overwrite the table they were exported from.
+    pub renames: BTreeMap<TableName, TableName>,
+    /// Give the documents in every user table new IDs, like renamed tables,
+    /// so the import can be appended to a deployment that has its own
+    /// documents.
+    pub regenerate_ids: bool,
+    /// Rewrite `Id`s in imported documents that reference documents which got
+    /// new IDs, so references between imported tables keep working.
+    pub rewrite_references: bool,
+}
+
+impl ImportTableSelection {
+    pub fn new(
+        allowlist: Option<BTreeSet<TableName>>,
+        renames: BTreeMap<TableName, TableName>,
+    ) -> anyhow::Result<Self> {
+        let mut targets = BTreeSet::new();
+        for (from, to) in &renames {
+            anyhow::ensure!(
+                !from.is_system() && !to.is_system(),
+                ErrorMetadata::bad_request(
+                    "InvalidTableRename",
+                    format!("Cannot rename {from} to {to}: only user tables can be renamed")
+                )
+            );
+            anyhow::ensure!(
+                targets.insert(to),
+                ErrorMetadata::bad_request(
+                    "InvalidTableRename",
+                    format!("Multiple tables are renamed to {to}")
+                )
+            );
+            if let Some(allowlist) = &allowlist {
+                anyhow::ensure!(
+                    allowlist.contains(from),
+                    ErrorMetadata::bad_request(
+                        "InvalidTableRename",
+                        format!(
+                            "Cannot rename {from} because it isn't one of the tables to import"
+                        )
+                    )
+                );
+            }
+        }
+        Ok(Self {
+            allowlist,
+            renames,
+            regenerate_ids: false,
+            rewrite_references: false,
+        })
+    }
+
+    pub fn with_id_options(mut self, regenerate_ids: bool, rewrite_references: bool) -> Self {
+        self.regenerate_ids = regenerate_ids;
+        self.rewrite_references = rewrite_references;
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.allowlist.is_none()
+            && self.renames.is_empty()
+            && !self.regenerate_ids
+            && !self.rewrite_references
+    }
+
+    /// Whether the table named `table_name` in the import should be imported.
+    pub fn includes(&self, table_name: &TableName) -> bool {
+        self.allowlist
+            .as_ref()
+            .map_or(true, |allowlist| allowlist.contains(table_name))
+    }
+
+    /// The name to import the table named `table_name` in the import as.
+    pub fn target_name<'a>(&'a self, table_name: &'a TableName) -> &'a TableName {
+        self.renames.get(table_name).unwrap_or(table_name)
+    }
+
+    /// Whether documents imported into `target_name`, the name a table is
+    /// imported as, get new IDs instead of the `_id`s in the import. System
+    /// tables like `_storage` always keep their IDs.
+    pub fn regenerates_ids(&self, target_name: &TableName) -> bool {
+        !target_name.is_system()
+            && (self.regenerate_ids || self.renames.values().any(|to| to == target_name))
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+struct SerializedImportTableSelection {
+    allowlist: Option<Vec<String>>,
+    renames: Option<Vec<SerializedImportTableRename>>,
+    regenerate_ids: Option<bool>,
+    rewrite_references: Option<bool>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+struct SerializedImportTableRename {
+    from: String,
+    to: String,
+}
+
+impl From<ImportTableSelection> for SerializedImportTableSelection {
+    fn from(selection: ImportTableSelection) -> Self {
+        SerializedImportTableSelection {
+            allowlist: selection.allowlist.map(|allowlist| {
+                allowlist
+                    .into_iter()
+                    .map(|table_name| table_name.to_string())
+                    .collect()
+            }),
+            renames: (!selection.renames.is_empty()).then(|| {
+                selection
+                    .renames
+                    .into_iter()
+                    .map(|(from, to)| SerializedImportTableRename {
+                        from: from.to_string(),
+                        to: to.to_string(),
+                    })
+                    .collect()
+            }),
+            regenerate_ids: selection.regenerate_ids.then_some(true),
+            rewrite_references: selection.rewrite_references.then_some(true),
+        }
+    }
+}
+
+impl TryFrom<SerializedImportTableSelection> for ImportTableSelection {
+    type Error = anyhow::Error;
+
+    fn try_from(selection: SerializedImportTableSelection) -> anyhow::Result<Self> {
+        Ok(ImportTableSelection {
+            allowlist: selection
+                .allowlist
+                .map(|allowlist| {
+                    allowlist
+                        .into_iter()
+                        .map(|table_name| table_name.parse())
+                        .collect::<anyhow::Result<_>>()
+                })
+                .transpose()?,
+            renames: selection
+                .renames
+                .unwrap_or_default()
+                .into_iter()
+                .map(|rename| Ok((rename.from.parse()?, rename.to.parse()?)))
+                .collect::<anyhow::Result<_>>()?,
+            regenerate_ids: selection.regenerate_ids.unwrap_or(false),
+            rewrite_references: selection.rewrite_references.unwrap_or(false),
+        })
+    }
+}
+
 #[derive(PartialEq, Eq, Debug, Clone)]
 #[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
 pub enum ImportRequestor {
@@ -425,3 +710,113 @@ impl From<SerializedImportRequestor> for ImportRequestor {
 }
 
 codegen_convex_serialization!(ImportRequestor, SerializedImportRequestor);
+
+/// A snapshot import file that's uploaded in chunks over several requests so
+/// that an interrupted upload can be resumed. Each chunk is stored as its own
+/// object, and the chunks are concatenated when the upload is finished.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct SnapshotImportUpload {
+    pub member_id: Option<MemberId>,
+    /// The chunks received so far, sorted by index. Chunks may be uploaded in
+    /// any order, so there can be gaps.
+    pub chunks: Vec<SnapshotImportUploadChunk>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct SnapshotImportUploadChunk {
+    pub index: u32,
+    pub object_key: ObjectKey,
+    pub sha256: Sha256Digest,
+    pub size: i64,
+}
+
+impl SnapshotImportUpload {
+    pub fn chunk(&self, index: u32) -> Option<&SnapshotImportUploadChunk> {
+        self.chunks
+            .binary_search_by_key(&index, |chunk| chunk.index)
+            .ok()
+            .map(|i| &self.chunks[i])
+    }
+
+    /// Record a chunk, replacing any chunk previously uploaded at the same
+    /// index. Returns the replaced chunk.
+    pub fn insert_chunk(
+        &mut self,
+        chunk: SnapshotImportUploadChunk,
+    ) -> Option<SnapshotImportUploadChunk> {
+        match self.chunks.binary_search_by_key(&chunk.index, |c| c.index) {
+            Ok(i) => Some(std::mem::replace(&mut self.chunks[i], chunk)),
+            Err(i) => {
+                self.chunks.insert(i, chunk);
+                None
+            },
+        }
+    }
+
+    /// The indexes in `0..num_chunks` that haven't been uploaded yet.
+    pub fn missing_chunks(&self, num_chunks: u32) -> Vec<u32> {
+        (0..num_chunks)
+            .filter(|index| self.chunk(*index).is_none())
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SerializedSnapshotImportUpload {
+    member_id: Option<i64>,
+    chunks: Vec<SerializedSnapshotImportUploadChunk>,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SerializedSnapshotImportUploadChunk {
+    index: i64,
+    object_key: String,
+    sha256: String,
+    size: i64,
+}
+
+impl From<SnapshotImportUpload> for SerializedSnapshotImportUpload {
+    fn from(upload: SnapshotImportUpload) -> Self {
+        SerializedSnapshotImportUpload {
+            member_id: upload.member_id.map(|member_id| member_id.0 as i64),
+            chunks: upload
+                .chunks
+                .into_iter()
+                .map(|chunk| SerializedSnapshotImportUploadChunk {
+                    index: chunk.index as i64,
+                    object_key: chunk.object_key.to_string(),
+                    sha256: chunk.sha256.as_base64(),
+                    size: chunk.size,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<SerializedSnapshotImportUpload> for SnapshotImportUpload {
+    type Error = anyhow::Error;
+
+    fn try_from(upload: SerializedSnapshotImportUpload) -> anyhow::Result<Self> {
+        Ok(SnapshotImportUpload {
+            member_id: upload.member_id.map(|member_id| MemberId(member_id as u64)),
+            chunks: upload
+                .chunks
+                .into_iter()
+                .map(|chunk| {
+                    anyhow::Ok(SnapshotImportUploadChunk {
+                        index: chunk.index.try_into()?,
+                        object_key: chunk.object_key.try_into()?,
+                        sha256: Sha256Digest::from_base64(&chunk.sha256)?,
+                        size: chunk.size,
+                    })
+                })
+                .try_collect()?,
+        })
+    }
+}
+
+codegen_convex_serialization!(SnapshotImportUpload, SerializedSnapshotImportUpload);
@@ -0,0 +1,253 @@
+use std::sync::LazyLock;
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    query::{
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+};
+use database::{
+    unauthorized_error,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use errors::ErrorMetadata;
+use value::{
+    ResolvedDocumentId,
+    TableName,
+    TableNamespace,
+};
+
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+use types::{
+    TableCompaction,
+    TableCompactionState,
+};
+
+pub static TABLE_COMPACTIONS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_table_compactions"
+        .parse()
+        .expect("Invalid built-in table_compactions table")
+});
+
+pub struct TableCompactionsTable;
+impl SystemTable for TableCompactionsTable {
+    fn table_name(&self) -> &'static TableName {
+        &TABLE_COMPACTIONS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<TableCompaction>::try_from(document).map(|_| ())
+    }
+}
+
+pub struct TableCompactionModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> TableCompactionModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    /// Requests a compaction of `table_name`, which the table compaction
+    /// worker picks up. Each table has at most one unfinished compaction.
+    pub async fn request(
+        &mut self,
+        namespace: TableNamespace,
+        table_name: TableName,
+    ) -> anyhow::Result<ResolvedDocumentId> {
+        if !(self.tx.identity().is_system() || self.tx.identity().is_admin()) {
+            anyhow::bail!(unauthorized_error("compact_table"));
+        }
+        let tablet_id = self
+            .tx
+            .table_mapping()
+            .namespace(namespace)
+            .id_if_exists(&table_name)
+            .filter(|_| !table_name.is_system())
+            .ok_or_else(|| {
+                ErrorMetadata::bad_request("TableNotFound", format!("Table {table_name} not found"))
+            })?;
+        let in_progress =
+            self.list().await?.into_iter().any(|compaction| {
+                compaction.tablet_id == tablet_id && !compaction.state.is_finished()
+            });
+        anyhow::ensure!(
+            !in_progress,
+            ErrorMetadata::bad_request(
+                "TableCompactionInProgress",
+                format!("Table {table_name} is already being compacted"),
+            )
+        );
+        SystemMetadataModel::new_global(self.tx)
+            .insert(
+                &TABLE_COMPACTIONS_TABLE,
+                TableCompaction {
+                    tablet_id,
+                    table_name,
+                    state: TableCompactionState::Requested,
+                }
+                .try_into()?,
+            )
+            .await
+    }
+
+    /// All compactions, oldest first.
+    pub async fn list(&mut self) -> anyhow::Result<Vec<ParsedDocument<TableCompaction>>> {
+        let query = Query::full_table_scan(TABLE_COMPACTIONS_TABLE.clone(), Order::Asc);
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        let mut compactions = vec![];
+        while let Some(document) = query_stream.next(self.tx, None).await? {
+            compactions.push(document.try_into()?);
+        }
+        Ok(compactions)
+    }
+
+    pub async fn get(
+        &mut self,
+        id: ResolvedDocumentId,
+    ) -> anyhow::Result<Option<ParsedDocument<TableCompaction>>> {
+        self.tx
+            .get(id)
+            .await?
+            .map(ParsedDocument::try_from)
+            .transpose()
+    }
+
+    /// The oldest compaction that hasn't finished, which is the next one the
+    /// worker should run.
+    pub async fn next_unfinished(
+        &mut self,
+    ) -> anyhow::Result<Option<ParsedDocument<TableCompaction>>> {
+        Ok(self
+            .list()
+            .await?
+            .into_iter()
+            .find(|compaction| !compaction.state.is_finished()))
+    }
+
+    pub async fn update_state(
+        &mut self,
+        id: ResolvedDocumentId,
+        state: TableCompactionState,
+    ) -> anyhow::Result<()> {
+        let mut compaction = self
+            .get(id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Table compaction {id} not found"))?
+            .into_value();
+        compaction.state = state;
+        SystemMetadataModel::new_global(self.tx)
+            .replace(id, compaction.try_into()?)
+            .await?;
+        Ok(())
+    }
+
+    /// Cancels a compaction that hasn't finished. Whatever it has already
+    /// deleted stays deleted.
+    pub async fn cancel(&mut self, id: ResolvedDocumentId) -> anyhow::Result<()> {
+        let compaction = self.get(id).await?.ok_or_else(|| {
+            ErrorMetadata::not_found(
+                "TableCompactionNotFound",
+                format!("Table compaction {id} not found"),
+            )
+        })?;
+        anyhow::ensure!(
+            !compaction.state.is_finished(),
+            ErrorMetadata::bad_request(
+                "TableCompactionFinished",
+                format!("Table compaction {id} has already finished"),
+            )
+        );
+        self.update_state(id, TableCompactionState::Canceled).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::types::Timestamp;
+    use database::{
+        test_helpers::DbFixtures,
+        UserFacingModel,
+    };
+    use keybroker::Identity;
+    use runtime::testing::TestRuntime;
+    use value::{
+        assert_obj,
+        TableNamespace,
+    };
+
+    use super::{
+        types::{
+            TableCompactionProgress,
+            TableCompactionState,
+        },
+        TableCompactionModel,
+    };
+    use crate::test_helpers::DbFixturesWithModel;
+
+    #[convex_macro::test_runtime]
+    async fn test_request_and_cancel(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin(Identity::system()).await?;
+        UserFacingModel::new_root_for_test(&mut tx)
+            .insert("messages".parse()?, assert_obj!())
+            .await?;
+        assert!(TableCompactionModel::new(&mut tx)
+            .request(TableNamespace::test_user(), "missing".parse()?)
+            .await
+            .is_err());
+
+        let mut model = TableCompactionModel::new(&mut tx);
+        let id = model
+            .request(TableNamespace::test_user(), "messages".parse()?)
+            .await?;
+        // Only one compaction per table at a time.
+        assert!(model
+            .request(TableNamespace::test_user(), "messages".parse()?)
+            .await
+            .is_err());
+        let next = model.next_unfinished().await?.unwrap();
+        assert_eq!(next.id(), id);
+        assert_eq!(next.state, TableCompactionState::Requested);
+
+        let progress = TableCompactionProgress::new(Timestamp::must(100));
+        model
+            .update_state(id, TableCompactionState::InProgress { progress })
+            .await?;
+        assert_eq!(
+            model.get(id).await?.unwrap().state.progress(),
+            Some(&progress)
+        );
+
+        model.cancel(id).await?;
+        assert_eq!(
+            model.get(id).await?.unwrap().state,
+            TableCompactionState::Canceled
+        );
+        assert!(model.next_unfinished().await?.is_none());
+        assert!(model.cancel(id).await.is_err());
+        // Canceling allows compacting the table again.
+        model
+            .request(TableNamespace::test_user(), "messages".parse()?)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,207 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use sync_types::Timestamp;
+use value::{
+    codegen_convex_serialization,
+    TableName,
+    TabletId,
+};
+
+/// A request to compact a table: delete the revisions of its documents (and
+/// their index entries) that are no longer visible at any snapshot still in
+/// retention, including tombstones of deleted documents.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TableCompaction {
+    pub tablet_id: TabletId,
+    /// The table's name when the compaction was requested.
+    pub table_name: TableName,
+    pub state: TableCompactionState,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TableCompactionState {
+    Requested,
+    InProgress {
+        progress: TableCompactionProgress,
+    },
+    Completed {
+        progress: TableCompactionProgress,
+    },
+    Failed {
+        error: String,
+        progress: Option<TableCompactionProgress>,
+    },
+    Canceled,
+}
+
+impl TableCompactionState {
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            Self::Completed { .. } | Self::Failed { .. } | Self::Canceled
+        )
+    }
+
+    pub fn progress(&self) -> Option<&TableCompactionProgress> {
+        match self {
+            Self::Requested | Self::Canceled => None,
+            Self::InProgress { progress } | Self::Completed { progress } => Some(progress),
+            Self::Failed { progress, .. } => progress.as_ref(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TableCompactionProgress {
+    /// The compaction scans the document log up to this timestamp, which was
+    /// the oldest snapshot still in retention when the compaction started.
+    pub target_ts: Timestamp,
+    /// Every revision before this timestamp has been scanned.
+    pub cursor: Timestamp,
+    pub revisions_scanned: u64,
+    pub documents_deleted: u64,
+    pub index_entries_deleted: u64,
+}
+
+impl TableCompactionProgress {
+    pub fn new(target_ts: Timestamp) -> Self {
+        Self {
+            target_ts,
+            cursor: Timestamp::MIN,
+            revisions_scanned: 0,
+            documents_deleted: 0,
+            index_entries_deleted: 0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedTableCompactionProgress {
+    target_ts: i64,
+    cursor: i64,
+    revisions_scanned: i64,
+    documents_deleted: i64,
+    index_entries_deleted: i64,
+}
+
+impl TryFrom<TableCompactionProgress> for SerializedTableCompactionProgress {
+    type Error = anyhow::Error;
+
+    fn try_from(progress: TableCompactionProgress) -> anyhow::Result<Self> {
+        Ok(Self {
+            target_ts: progress.target_ts.into(),
+            cursor: progress.cursor.into(),
+            revisions_scanned: progress.revisions_scanned.try_into()?,
+            documents_deleted: progress.documents_deleted.try_into()?,
+            index_entries_deleted: progress.index_entries_deleted.try_into()?,
+        })
+    }
+}
+
+impl TryFrom<SerializedTableCompactionProgress> for TableCompactionProgress {
+    type Error = anyhow::Error;
+
+    fn try_from(progress: SerializedTableCompactionProgress) -> anyhow::Result<Self> {
+        Ok(Self {
+            target_ts: progress.target_ts.try_into()?,
+            cursor: progress.cursor.try_into()?,
+            revisions_scanned: progress.revisions_scanned.try_into()?,
+            documents_deleted: progress.documents_deleted.try_into()?,
+            index_entries_deleted: progress.index_entries_deleted.try_into()?,
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum SerializedTableCompactionState {
+    Requested,
+    InProgress {
+        progress: SerializedTableCompactionProgress,
+    },
+    Completed {
+        progress: SerializedTableCompactionProgress,
+    },
+    Failed {
+        error: String,
+        progress: Option<SerializedTableCompactionProgress>,
+    },
+    Canceled,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedTableCompaction {
+    table_id: String,
+    table_name: String,
+    state: SerializedTableCompactionState,
+}
+
+impl TryFrom<TableCompaction> for SerializedTableCompaction {
+    type Error = anyhow::Error;
+
+    fn try_from(compaction: TableCompaction) -> anyhow::Result<Self> {
+        let state = match compaction.state {
+            TableCompactionState::Requested => SerializedTableCompactionState::Requested,
+            TableCompactionState::InProgress { progress } => {
+                SerializedTableCompactionState::InProgress {
+                    progress: progress.try_into()?,
+                }
+            },
+            TableCompactionState::Completed { progress } => {
+                SerializedTableCompactionState::Completed {
+                    progress: progress.try_into()?,
+                }
+            },
+            TableCompactionState::Failed { error, progress } => {
+                SerializedTableCompactionState::Failed {
+                    error,
+                    progress: progress.map(TryInto::try_into).transpose()?,
+                }
+            },
+            TableCompactionState::Canceled => SerializedTableCompactionState::Canceled,
+        };
+        Ok(Self {
+            table_id: compaction.tablet_id.to_string(),
+            table_name: compaction.table_name.into(),
+            state,
+        })
+    }
+}
+
+impl TryFrom<SerializedTableCompaction> for TableCompaction {
+    type Error = anyhow::Error;
+
+    fn try_from(compaction: SerializedTableCompaction) -> anyhow::Result<Self> {
+        let state = match compaction.state {
+            SerializedTableCompactionState::Requested => TableCompactionState::Requested,
+            SerializedTableCompactionState::InProgress { progress } => {
+                TableCompactionState::InProgress {
+                    progress: progress.try_into()?,
+                }
+            },
+            SerializedTableCompactionState::Completed { progress } => {
+                TableCompactionState::Completed {
+                    progress: progress.try_into()?,
+                }
+            },
+            SerializedTableCompactionState::Failed { error, progress } => {
+                TableCompactionState::Failed {
+                    error,
+                    progress: progress.map(TryInto::try_into).transpose()?,
+                }
+            },
+            SerializedTableCompactionState::Canceled => TableCompactionState::Canceled,
+        };
+        Ok(Self {
+            tablet_id: compaction.table_id.parse()?,
+            table_name: compaction.table_name.parse()?,
+            state,
+        })
+    }
+}
+
+codegen_convex_serialization!(TableCompaction, SerializedTableCompaction);
@@ -0,0 +1,275 @@
+//! Daily usage counters for each function and for the whole deployment, for
+//! operators metering the deployments they host and enforcing quotas on them.
+use std::sync::LazyLock;
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+        CREATION_TIME_FIELD_PATH,
+    },
+    query::{
+        IndexRange,
+        IndexRangeExpression,
+        Order,
+        Query,
+    },
+    runtime::{
+        Runtime,
+        UnixTimestamp,
+    },
+    types::IndexName,
+};
+use database::{
+    defaults::system_index,
+    require_admin_or_system,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use value::{
+    ConvexValue,
+    FieldPath,
+    TableName,
+    TableNamespace,
+};
+
+use self::types::UsageMeter;
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+pub static USAGE_METERS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_usage_meters"
+        .parse()
+        .expect("Invalid built-in usage_meters table")
+});
+
+pub static USAGE_METERS_INDEX_BY_DAY_AND_FUNCTION: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&USAGE_METERS_TABLE, "by_day_and_function"));
+
+static DAY_FIELD: LazyLock<FieldPath> = LazyLock::new(|| "day".parse().expect("invalid day field"));
+static FUNCTION_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "function".parse().expect("invalid function field"));
+
+pub struct UsageMetersTable;
+impl SystemTable for UsageMetersTable {
+    fn table_name(&self) -> &'static TableName {
+        &USAGE_METERS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![SystemIndex {
+            name: USAGE_METERS_INDEX_BY_DAY_AND_FUNCTION.clone(),
+            fields: vec![
+                DAY_FIELD.clone(),
+                FUNCTION_FIELD.clone(),
+                CREATION_TIME_FIELD_PATH.clone(),
+            ]
+            .try_into()
+            .unwrap(),
+        }]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<UsageMeter>::try_from(document).map(|_| ())
+    }
+}
+
+fn day_value(day: UnixTimestamp) -> anyhow::Result<ConvexValue> {
+    Ok(ConvexValue::from(day.as_ms_since_epoch()? as f64))
+}
+
+fn function_value(function: Option<&str>) -> anyhow::Result<ConvexValue> {
+    Ok(match function {
+        Some(function) => ConvexValue::try_from(function.to_string())?,
+        None => ConvexValue::Null,
+    })
+}
+
+pub struct UsageMetersModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> UsageMetersModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    fn day_range(
+        &mut self,
+        start: UnixTimestamp,
+        end: UnixTimestamp,
+    ) -> anyhow::Result<ResolvedQuery<RT>> {
+        let index_range = IndexRange {
+            index_name: USAGE_METERS_INDEX_BY_DAY_AND_FUNCTION.clone(),
+            range: vec![
+                IndexRangeExpression::Gte(DAY_FIELD.clone(), day_value(start)?),
+                IndexRangeExpression::Lt(DAY_FIELD.clone(), day_value(end)?),
+            ],
+            order: Order::Asc,
+        };
+        ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )
+    }
+
+    /// The meters for days starting in `[start, end)`, oldest first.
+    pub async fn list(
+        &mut self,
+        start: UnixTimestamp,
+        end: UnixTimestamp,
+    ) -> anyhow::Result<Vec<ParsedDocument<UsageMeter>>> {
+        require_admin_or_system(self.tx.identity(), "list_usage_meters")?;
+        let mut query_stream = self.day_range(start, end)?;
+        let mut meters = vec![];
+        while let Some(document) = query_stream.next(self.tx, None).await? {
+            meters.push(document.try_into()?);
+        }
+        Ok(meters)
+    }
+
+    async fn get(
+        &mut self,
+        day: UnixTimestamp,
+        function: Option<&str>,
+    ) -> anyhow::Result<Option<ParsedDocument<UsageMeter>>> {
+        let index_range = IndexRange {
+            index_name: USAGE_METERS_INDEX_BY_DAY_AND_FUNCTION.clone(),
+            range: vec![
+                IndexRangeExpression::Eq(DAY_FIELD.clone(), day_value(day)?.into()),
+                IndexRangeExpression::Eq(FUNCTION_FIELD.clone(), function_value(function)?.into()),
+            ],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        query_stream
+            .expect_at_most_one(self.tx)
+            .await?
+            .map(ParsedDocument::try_from)
+            .transpose()
+    }
+
+    /// Adds `meter`'s counters to the stored meter for the same day and
+    /// function, creating it if there isn't one yet.
+    pub async fn add(&mut self, meter: UsageMeter) -> anyhow::Result<()> {
+        require_admin_or_system(self.tx.identity(), "add_usage_meter")?;
+        match self.get(meter.day, meter.function.as_deref()).await? {
+            Some(existing) => {
+                let (id, mut existing) = existing.into_id_and_value();
+                existing.counters.merge(&meter.counters);
+                SystemMetadataModel::new_global(self.tx)
+                    .replace(id, existing.try_into()?)
+                    .await?;
+            },
+            None => {
+                SystemMetadataModel::new_global(self.tx)
+                    .insert(&USAGE_METERS_TABLE, meter.try_into()?)
+                    .await?;
+            },
+        }
+        Ok(())
+    }
+
+    /// Deletes up to `limit` meters for days before `cutoff`, returning how
+    /// many were deleted.
+    pub async fn delete_before(
+        &mut self,
+        cutoff: UnixTimestamp,
+        limit: usize,
+    ) -> anyhow::Result<usize> {
+        require_admin_or_system(self.tx.identity(), "delete_usage_meters")?;
+        let mut query_stream = self.day_range(UnixTimestamp::from_millis(0), cutoff)?;
+        let mut deleted = 0;
+        while deleted < limit
+            && let Some(document) = query_stream.next(self.tx, None).await?
+        {
+            SystemMetadataModel::new_global(self.tx)
+                .delete(document.id())
+                .await?;
+            deleted += 1;
+        }
+        Ok(deleted)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::runtime::UnixTimestamp;
+    use database::test_helpers::DbFixtures;
+    use keybroker::Identity;
+    use runtime::testing::TestRuntime;
+
+    use super::{
+        types::{
+            UsageCounters,
+            UsageMeter,
+        },
+        UsageMetersModel,
+    };
+    use crate::test_helpers::DbFixturesWithModel;
+
+    const DAY_MS: u64 = 24 * 60 * 60 * 1000;
+
+    fn meter(day: u64, function: Option<&str>, calls: u64) -> UsageMeter {
+        UsageMeter {
+            day: UnixTimestamp::from_millis(day * DAY_MS),
+            function: function.map(|function| function.to_string()),
+            counters: UsageCounters {
+                calls,
+                ..Default::default()
+            },
+        }
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_usage_meters(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin(Identity::system()).await?;
+        let mut model = UsageMetersModel::new(&mut tx);
+        model.add(meter(1, Some("a:f"), 1)).await?;
+        model.add(meter(1, None, 1)).await?;
+        model.add(meter(2, Some("a:f"), 2)).await?;
+        model.add(meter(2, Some("a:f"), 3)).await?;
+        model.add(meter(2, None, 5)).await?;
+        db.commit(tx).await?;
+
+        let mut tx = db.begin(Identity::system()).await?;
+        let mut model = UsageMetersModel::new(&mut tx);
+        let day_two: Vec<_> = model
+            .list(
+                UnixTimestamp::from_millis(2 * DAY_MS),
+                UnixTimestamp::from_millis(3 * DAY_MS),
+            )
+            .await?
+            .into_iter()
+            .map(|meter| meter.into_value())
+            .collect();
+        // The deployment's meter sorts first, since null sorts before strings.
+        assert_eq!(day_two, vec![meter(2, None, 5), meter(2, Some("a:f"), 5)]);
+
+        let deleted = model
+            .delete_before(UnixTimestamp::from_millis(2 * DAY_MS), 10)
+            .await?;
+        assert_eq!(deleted, 2);
+
+        let mut tx = db.begin(Identity::Unknown).await?;
+        assert!(UsageMetersModel::new(&mut tx)
+            .list(
+                UnixTimestamp::from_millis(0),
+                UnixTimestamp::from_millis(3 * DAY_MS)
+            )
+            .await
+            .is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,235 @@
+use std::{
+    fmt,
+    str::FromStr,
+};
+
+use common::runtime::UnixTimestamp;
+use errors::ErrorMetadata;
+#[cfg(any(test, feature = "testing"))]
+use proptest::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use value::codegen_convex_serialization;
+
+/// A day's usage by a function, or by the whole deployment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct UsageMeter {
+    /// Midnight UTC at the start of the day.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "(0..=i64::MAX as u64 / 1024).prop_map(UnixTimestamp::from_millis)")
+    )]
+    pub day: UnixTimestamp,
+    /// The function's path, or the route for HTTP actions. `None` for the
+    /// deployment's total usage.
+    pub function: Option<String>,
+    pub counters: UsageCounters,
+}
+
+/// Usage that's metered, and that quotas can limit.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct UsageCounters {
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=i64::MAX as u64 / 2")
+    )]
+    pub calls: u64,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=i64::MAX as u64 / 2")
+    )]
+    pub database_read_bytes: u64,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=i64::MAX as u64 / 2")
+    )]
+    pub database_write_bytes: u64,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=i64::MAX as u64 / 2")
+    )]
+    pub storage_read_bytes: u64,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=i64::MAX as u64 / 2")
+    )]
+    pub storage_write_bytes: u64,
+    /// How long actions and HTTP actions ran for.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=i64::MAX as u64 / 2")
+    )]
+    pub action_compute_ms: u64,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=i64::MAX as u64 / 2")
+    )]
+    pub vector_searches: u64,
+}
+
+impl UsageCounters {
+    pub fn merge(&mut self, other: &Self) {
+        self.calls += other.calls;
+        self.database_read_bytes += other.database_read_bytes;
+        self.database_write_bytes += other.database_write_bytes;
+        self.storage_read_bytes += other.storage_read_bytes;
+        self.storage_write_bytes += other.storage_write_bytes;
+        self.action_compute_ms += other.action_compute_ms;
+        self.vector_searches += other.vector_searches;
+    }
+
+    pub fn get(&self, metric: UsageMetric) -> u64 {
+        match metric {
+            UsageMetric::Calls => self.calls,
+            UsageMetric::DatabaseBandwidthBytes => {
+                self.database_read_bytes + self.database_write_bytes
+            },
+            UsageMetric::StorageBandwidthBytes => {
+                self.storage_read_bytes + self.storage_write_bytes
+            },
+            UsageMetric::ActionComputeMs => self.action_compute_ms,
+            UsageMetric::VectorSearches => self.vector_searches,
+        }
+    }
+}
+
+/// What a quota limits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub enum UsageMetric {
+    Calls,
+    /// Bytes read from and written to the database.
+    DatabaseBandwidthBytes,
+    /// Bytes read from and written to file storage.
+    StorageBandwidthBytes,
+    ActionComputeMs,
+    VectorSearches,
+}
+
+impl UsageMetric {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Calls => "calls",
+            Self::DatabaseBandwidthBytes => "databaseBandwidthBytes",
+            Self::StorageBandwidthBytes => "storageBandwidthBytes",
+            Self::ActionComputeMs => "actionComputeMs",
+            Self::VectorSearches => "vectorSearches",
+        }
+    }
+}
+
+impl FromStr for UsageMetric {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "calls" => Ok(Self::Calls),
+            "databaseBandwidthBytes" => Ok(Self::DatabaseBandwidthBytes),
+            "storageBandwidthBytes" => Ok(Self::StorageBandwidthBytes),
+            "actionComputeMs" => Ok(Self::ActionComputeMs),
+            "vectorSearches" => Ok(Self::VectorSearches),
+            _ => anyhow::bail!(ErrorMetadata::bad_request(
+                "InvalidUsageMetric",
+                format!(
+                    "Invalid usage metric {s:?}. Expected \"calls\", \
+                     \"databaseBandwidthBytes\", \"storageBandwidthBytes\", \
+                     \"actionComputeMs\" or \"vectorSearches\"."
+                ),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for UsageMetric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedUsageCounters {
+    calls: i64,
+    database_read_bytes: i64,
+    database_write_bytes: i64,
+    storage_read_bytes: i64,
+    storage_write_bytes: i64,
+    action_compute_ms: i64,
+    vector_searches: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedUsageMeter {
+    /// Milliseconds since the epoch.
+    day: f64,
+    function: Option<String>,
+    counters: SerializedUsageCounters,
+}
+
+impl TryFrom<UsageCounters> for SerializedUsageCounters {
+    type Error = anyhow::Error;
+
+    fn try_from(counters: UsageCounters) -> anyhow::Result<Self> {
+        Ok(Self {
+            calls: counters.calls.try_into()?,
+            database_read_bytes: counters.database_read_bytes.try_into()?,
+            database_write_bytes: counters.database_write_bytes.try_into()?,
+            storage_read_bytes: counters.storage_read_bytes.try_into()?,
+            storage_write_bytes: counters.storage_write_bytes.try_into()?,
+            action_compute_ms: counters.action_compute_ms.try_into()?,
+            vector_searches: counters.vector_searches.try_into()?,
+        })
+    }
+}
+
+impl TryFrom<SerializedUsageCounters> for UsageCounters {
+    type Error = anyhow::Error;
+
+    fn try_from(counters: SerializedUsageCounters) -> anyhow::Result<Self> {
+        Ok(Self {
+            calls: counters.calls.try_into()?,
+            database_read_bytes: counters.database_read_bytes.try_into()?,
+            database_write_bytes: counters.database_write_bytes.try_into()?,
+            storage_read_bytes: counters.storage_read_bytes.try_into()?,
+            storage_write_bytes: counters.storage_write_bytes.try_into()?,
+            action_compute_ms: counters.action_compute_ms.try_into()?,
+            vector_searches: counters.vector_searches.try_into()?,
+        })
+    }
+}
+
+impl TryFrom<UsageMeter> for SerializedUsageMeter {
+    type Error = anyhow::Error;
+
+    fn try_from(meter: UsageMeter) -> anyhow::Result<Self> {
+        Ok(Self {
+            day: meter.day.as_ms_since_epoch()? as f64,
+            function: meter.function,
+            counters: meter.counters.try_into()?,
+        })
+    }
+}
+
+impl TryFrom<SerializedUsageMeter> for UsageMeter {
+    type Error = anyhow::Error;
+
+    fn try_from(meter: SerializedUsageMeter) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            meter.day >= 0.0 && meter.day.fract() == 0.0,
+            "Invalid usage meter day {}",
+            meter.day
+        );
+        Ok(Self {
+            day: UnixTimestamp::from_millis(meter.day as u64),
+            function: meter.function,
+            counters: meter.counters.try_into()?,
+        })
+    }
+}
+
+codegen_convex_serialization!(UsageMeter, SerializedUsageMeter);
@@ -0,0 +1,101 @@
+use std::sync::LazyLock;
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    query::{
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+};
+use database::{
+    unauthorized_error,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use value::{
+    TableName,
+    TableNamespace,
+};
+
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+use types::UsageQuotaConfig;
+
+pub static USAGE_QUOTAS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_usage_quotas"
+        .parse()
+        .expect("Invalid built-in usage_quotas table")
+});
+
+pub struct UsageQuotasTable;
+impl SystemTable for UsageQuotasTable {
+    fn table_name(&self) -> &'static TableName {
+        &USAGE_QUOTAS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<UsageQuotaConfig>::try_from(document).map(|_| ())
+    }
+}
+
+/// The deployment's usage quotas, which has at most one row.
+pub struct UsageQuotasModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> UsageQuotasModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    pub async fn get(&mut self) -> anyhow::Result<Option<ParsedDocument<UsageQuotaConfig>>> {
+        let query = Query::full_table_scan(USAGE_QUOTAS_TABLE.clone(), Order::Asc);
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        query_stream
+            .expect_at_most_one(self.tx)
+            .await?
+            .map(|document| document.try_into())
+            .transpose()
+    }
+
+    /// Replaces the quotas, or removes them if `config` is `None`.
+    pub async fn set(&mut self, config: Option<UsageQuotaConfig>) -> anyhow::Result<()> {
+        if !(self.tx.identity().is_system() || self.tx.identity().is_admin()) {
+            anyhow::bail!(unauthorized_error("set_usage_quotas"));
+        }
+        let existing = self.get().await?;
+        match (existing, config) {
+            (Some(existing), Some(config)) => {
+                SystemMetadataModel::new_global(self.tx)
+                    .replace(existing.id(), config.try_into()?)
+                    .await?;
+            },
+            (Some(existing), None) => {
+                SystemMetadataModel::new_global(self.tx)
+                    .delete(existing.id())
+                    .await?;
+            },
+            (None, Some(config)) => {
+                SystemMetadataModel::new_global(self.tx)
+                    .insert(&USAGE_QUOTAS_TABLE, config.try_into()?)
+                    .await?;
+            },
+            (None, None) => {},
+        }
+        Ok(())
+    }
+}
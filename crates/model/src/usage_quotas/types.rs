@@ -0,0 +1,214 @@
+use errors::ErrorMetadata;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use value::codegen_convex_serialization;
+
+use crate::usage_meters::types::UsageMetric;
+
+/// The most quotas a deployment can have, since every function call is
+/// checked against each of them.
+pub const MAX_USAGE_QUOTAS: usize = 64;
+
+/// Daily limits on a deployment's usage, for operators hosting deployments
+/// for their own customers. Days start at midnight UTC.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct UsageQuotaConfig {
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(
+            strategy = "proptest::collection::vec(proptest::prelude::any::<UsageQuota>(), 0..4)"
+        )
+    )]
+    pub quotas: Vec<UsageQuota>,
+}
+
+impl UsageQuotaConfig {
+    pub fn new(quotas: Vec<UsageQuota>) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            quotas.len() <= MAX_USAGE_QUOTAS,
+            ErrorMetadata::bad_request(
+                "InvalidUsageQuotaConfig",
+                format!("At most {MAX_USAGE_QUOTAS} usage quotas are allowed"),
+            )
+        );
+        for quota in &quotas {
+            if let QuotaEnforcement::Throttle { calls_per_minute } = quota.enforcement {
+                anyhow::ensure!(
+                    calls_per_minute > 0,
+                    ErrorMetadata::bad_request(
+                        "InvalidUsageQuotaConfig",
+                        "Throttled quotas must allow at least one call per minute. Use \
+                         \"reject\" to reject every call over the quota.",
+                    )
+                );
+            }
+            if let Some(function) = &quota.function {
+                anyhow::ensure!(
+                    !function.is_empty(),
+                    ErrorMetadata::bad_request(
+                        "InvalidUsageQuotaConfig",
+                        "A quota's function can't be empty. Leave it out to limit the whole \
+                         deployment's usage.",
+                    )
+                );
+            }
+        }
+        Ok(Self { quotas })
+    }
+}
+
+/// Limits a function's usage of `metric` per day to `limit`, or the whole
+/// deployment's if `function` is `None`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct UsageQuota {
+    /// The function's path, like `messages:send`. HTTP actions are only
+    /// limited by quotas on the whole deployment.
+    pub function: Option<String>,
+    pub metric: UsageMetric,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=i64::MAX as u64")
+    )]
+    pub limit: u64,
+    pub enforcement: QuotaEnforcement,
+}
+
+/// What happens to calls once a quota is used up.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub enum QuotaEnforcement {
+    /// Calls fail with `UsageQuotaExceeded` until the day ends.
+    Reject,
+    /// Calls are allowed at up to `calls_per_minute`, and the rest fail with
+    /// `UsageQuotaThrottled`.
+    Throttle { calls_per_minute: u32 },
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedUsageQuotaConfig {
+    quotas: Vec<SerializedUsageQuota>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedUsageQuota {
+    function: Option<String>,
+    metric: String,
+    limit: i64,
+    /// "reject" or "throttle".
+    enforcement: String,
+    /// Only set for throttled quotas.
+    throttled_calls_per_minute: Option<i64>,
+}
+
+impl TryFrom<UsageQuota> for SerializedUsageQuota {
+    type Error = anyhow::Error;
+
+    fn try_from(quota: UsageQuota) -> anyhow::Result<Self> {
+        let (enforcement, throttled_calls_per_minute) = match quota.enforcement {
+            QuotaEnforcement::Reject => ("reject", None),
+            QuotaEnforcement::Throttle { calls_per_minute } => {
+                ("throttle", Some(calls_per_minute.into()))
+            },
+        };
+        Ok(Self {
+            function: quota.function,
+            metric: quota.metric.to_string(),
+            limit: quota.limit.try_into()?,
+            enforcement: enforcement.to_string(),
+            throttled_calls_per_minute,
+        })
+    }
+}
+
+impl TryFrom<SerializedUsageQuota> for UsageQuota {
+    type Error = anyhow::Error;
+
+    fn try_from(quota: SerializedUsageQuota) -> anyhow::Result<Self> {
+        let enforcement = match (quota.enforcement.as_str(), quota.throttled_calls_per_minute) {
+            ("reject", None) => QuotaEnforcement::Reject,
+            ("throttle", Some(calls_per_minute)) => QuotaEnforcement::Throttle {
+                calls_per_minute: calls_per_minute.try_into()?,
+            },
+            (enforcement, _) => anyhow::bail!("Invalid quota enforcement {enforcement}"),
+        };
+        Ok(Self {
+            function: quota.function,
+            metric: quota.metric.parse()?,
+            limit: quota.limit.try_into()?,
+            enforcement,
+        })
+    }
+}
+
+impl TryFrom<UsageQuotaConfig> for SerializedUsageQuotaConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(config: UsageQuotaConfig) -> anyhow::Result<Self> {
+        Ok(Self {
+            quotas: config
+                .quotas
+                .into_iter()
+                .map(SerializedUsageQuota::try_from)
+                .collect::<anyhow::Result<_>>()?,
+        })
+    }
+}
+
+impl TryFrom<SerializedUsageQuotaConfig> for UsageQuotaConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(config: SerializedUsageQuotaConfig) -> anyhow::Result<Self> {
+        Ok(Self {
+            quotas: config
+                .quotas
+                .into_iter()
+                .map(UsageQuota::try_from)
+                .collect::<anyhow::Result<_>>()?,
+        })
+    }
+}
+
+codegen_convex_serialization!(UsageQuotaConfig, SerializedUsageQuotaConfig);
+
+#[cfg(test)]
+mod tests {
+    use errors::ErrorMetadataAnyhowExt;
+
+    use super::{
+        QuotaEnforcement,
+        UsageQuota,
+        UsageQuotaConfig,
+    };
+    use crate::usage_meters::types::UsageMetric;
+
+    fn quota(function: Option<&str>, enforcement: QuotaEnforcement) -> UsageQuota {
+        UsageQuota {
+            function: function.map(|function| function.to_string()),
+            metric: UsageMetric::Calls,
+            limit: 1000,
+            enforcement,
+        }
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let throttled = QuotaEnforcement::Throttle {
+            calls_per_minute: 0,
+        };
+        let err = UsageQuotaConfig::new(vec![quota(None, throttled)]).unwrap_err();
+        assert_eq!(err.short_msg(), "InvalidUsageQuotaConfig");
+        let err = UsageQuotaConfig::new(vec![quota(Some(""), QuotaEnforcement::Reject)])
+            .unwrap_err();
+        assert_eq!(err.short_msg(), "InvalidUsageQuotaConfig");
+        let err =
+            UsageQuotaConfig::new(vec![quota(None, QuotaEnforcement::Reject); 65]).unwrap_err();
+        assert_eq!(err.short_msg(), "InvalidUsageQuotaConfig");
+        assert!(UsageQuotaConfig::new(vec![quota(None, QuotaEnforcement::Reject); 64]).is_ok());
+    }
+}
@@ -0,0 +1,414 @@
+use std::sync::LazyLock;
+
+use common::{
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+    },
+    query::{
+        IndexRange,
+        IndexRangeExpression,
+        Order,
+        Query,
+    },
+    runtime::{
+        Runtime,
+        UnixTimestamp,
+    },
+    types::IndexName,
+};
+use database::{
+    defaults::system_index,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use errors::ErrorMetadata;
+use value::{
+    ConvexValue,
+    FieldPath,
+    ResolvedDocumentId,
+    TableName,
+    TableNamespace,
+};
+
+use crate::{
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+use types::{
+    WebAuthnCeremony,
+    WebAuthnChallenge,
+    WebAuthnCredential,
+};
+
+/// How many expired challenges to clean up each time a new one is issued.
+const MAX_EXPIRED_CHALLENGES_DELETED: usize = 16;
+
+pub static WEBAUTHN_CHALLENGES_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_webauthn_challenges"
+        .parse()
+        .expect("Invalid built-in webauthn_challenges table")
+});
+
+pub static WEBAUTHN_CHALLENGES_BY_CHALLENGE_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&WEBAUTHN_CHALLENGES_TABLE, "by_challenge"));
+pub static WEBAUTHN_CHALLENGES_BY_EXPIRES_AT_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&WEBAUTHN_CHALLENGES_TABLE, "by_expires_at"));
+static CHALLENGE_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "challenge".parse().expect("invalid challenge field"));
+static EXPIRES_AT_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "expiresAtMs".parse().expect("invalid expiresAtMs field"));
+
+pub static WEBAUTHN_CREDENTIALS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_webauthn_credentials"
+        .parse()
+        .expect("Invalid built-in webauthn_credentials table")
+});
+
+pub static WEBAUTHN_CREDENTIALS_BY_CREDENTIAL_ID_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&WEBAUTHN_CREDENTIALS_TABLE, "by_credential_id"));
+pub static WEBAUTHN_CREDENTIALS_BY_USER_ID_INDEX: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&WEBAUTHN_CREDENTIALS_TABLE, "by_user_id"));
+static CREDENTIAL_ID_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "credentialId".parse().expect("invalid credentialId field"));
+static USER_ID_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "userId".parse().expect("invalid userId field"));
+
+pub struct WebAuthnChallengesTable;
+impl SystemTable for WebAuthnChallengesTable {
+    fn table_name(&self) -> &'static TableName {
+        &WEBAUTHN_CHALLENGES_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![
+            SystemIndex {
+                name: WEBAUTHN_CHALLENGES_BY_CHALLENGE_INDEX.clone(),
+                fields: vec![CHALLENGE_FIELD.clone()].try_into().unwrap(),
+            },
+            SystemIndex {
+                name: WEBAUTHN_CHALLENGES_BY_EXPIRES_AT_INDEX.clone(),
+                fields: vec![EXPIRES_AT_FIELD.clone()].try_into().unwrap(),
+            },
+        ]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<WebAuthnChallenge>::try_from(document).map(|_| ())
+    }
+}
+
+pub struct WebAuthnCredentialsTable;
+impl SystemTable for WebAuthnCredentialsTable {
+    fn table_name(&self) -> &'static TableName {
+        &WEBAUTHN_CREDENTIALS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![
+            SystemIndex {
+                name: WEBAUTHN_CREDENTIALS_BY_CREDENTIAL_ID_INDEX.clone(),
+                fields: vec![CREDENTIAL_ID_FIELD.clone()].try_into().unwrap(),
+            },
+            SystemIndex {
+                name: WEBAUTHN_CREDENTIALS_BY_USER_ID_INDEX.clone(),
+                fields: vec![USER_ID_FIELD.clone()].try_into().unwrap(),
+            },
+        ]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<WebAuthnCredential>::try_from(document).map(|_| ())
+    }
+}
+
+/// Challenges and credentials for passkey registration and login. Apps call
+/// into this from mutations through the `webauthn` syscalls, so that a
+/// challenge is consumed in the same transaction that checks it.
+pub struct WebAuthnModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> WebAuthnModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    /// Stores a new challenge, cleaning up some of the ones that expired
+    /// without being used.
+    pub async fn start_ceremony(
+        &mut self,
+        challenge: WebAuthnChallenge,
+        now: UnixTimestamp,
+    ) -> anyhow::Result<ResolvedDocumentId> {
+        let index_range = IndexRange {
+            index_name: WEBAUTHN_CHALLENGES_BY_EXPIRES_AT_INDEX.clone(),
+            range: vec![IndexRangeExpression::Lt(
+                EXPIRES_AT_FIELD.clone(),
+                ConvexValue::from(i64::try_from(now.as_ms_since_epoch()?)?),
+            )],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        let mut expired = vec![];
+        while expired.len() < MAX_EXPIRED_CHALLENGES_DELETED {
+            let Some(document) = query_stream.next(self.tx, None).await? else {
+                break;
+            };
+            expired.push(document.id());
+        }
+        let mut system_model = SystemMetadataModel::new_global(self.tx);
+        for id in expired {
+            system_model.delete(id).await?;
+        }
+        system_model
+            .insert(&WEBAUTHN_CHALLENGES_TABLE, challenge.try_into()?)
+            .await
+    }
+
+    /// Consumes the challenge the client signed, returning it if it was
+    /// issued for `ceremony` and hasn't expired.
+    pub async fn take_challenge(
+        &mut self,
+        challenge: &[u8],
+        ceremony: WebAuthnCeremony,
+        now: UnixTimestamp,
+    ) -> anyhow::Result<WebAuthnChallenge> {
+        let index_range = IndexRange {
+            index_name: WEBAUTHN_CHALLENGES_BY_CHALLENGE_INDEX.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                CHALLENGE_FIELD.clone(),
+                ConvexValue::try_from(challenge.to_vec())?.into(),
+            )],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        let Some(document) = query_stream.expect_at_most_one(self.tx).await? else {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "WebAuthnChallengeNotFound",
+                "The WebAuthn challenge doesn't exist or was already used",
+            ));
+        };
+        let document: ParsedDocument<WebAuthnChallenge> = document.try_into()?;
+        SystemMetadataModel::new_global(self.tx)
+            .delete(document.id())
+            .await?;
+        let challenge = document.into_value();
+        if challenge.ceremony != ceremony {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "WebAuthnChallengeNotFound",
+                format!(
+                    "The WebAuthn challenge was issued for {}",
+                    challenge.ceremony
+                ),
+            ));
+        }
+        if challenge.expires_at < now {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "WebAuthnChallengeExpired",
+                "The WebAuthn challenge has expired",
+            ));
+        }
+        Ok(challenge)
+    }
+
+    pub async fn credential(
+        &mut self,
+        credential_id: &[u8],
+    ) -> anyhow::Result<Option<ParsedDocument<WebAuthnCredential>>> {
+        let index_range = IndexRange {
+            index_name: WEBAUTHN_CREDENTIALS_BY_CREDENTIAL_ID_INDEX.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                CREDENTIAL_ID_FIELD.clone(),
+                ConvexValue::try_from(credential_id.to_vec())?.into(),
+            )],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        query_stream
+            .expect_at_most_one(self.tx)
+            .await?
+            .map(ParsedDocument::try_from)
+            .transpose()
+    }
+
+    pub async fn credentials_for_user(
+        &mut self,
+        user_id: &str,
+    ) -> anyhow::Result<Vec<ParsedDocument<WebAuthnCredential>>> {
+        let index_range = IndexRange {
+            index_name: WEBAUTHN_CREDENTIALS_BY_USER_ID_INDEX.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                USER_ID_FIELD.clone(),
+                ConvexValue::try_from(user_id.to_string())?.into(),
+            )],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        let mut credentials = vec![];
+        while let Some(document) = query_stream.next(self.tx, None).await? {
+            credentials.push(document.try_into()?);
+        }
+        Ok(credentials)
+    }
+
+    pub async fn insert_credential(
+        &mut self,
+        credential: WebAuthnCredential,
+    ) -> anyhow::Result<ResolvedDocumentId> {
+        if self.credential(&credential.credential_id).await?.is_some() {
+            anyhow::bail!(ErrorMetadata::bad_request(
+                "WebAuthnCredentialAlreadyRegistered",
+                "This passkey is already registered",
+            ));
+        }
+        SystemMetadataModel::new_global(self.tx)
+            .insert(&WEBAUTHN_CREDENTIALS_TABLE, credential.try_into()?)
+            .await
+    }
+
+    /// Records a successful login with the credential's new signature counter.
+    pub async fn record_assertion(
+        &mut self,
+        credential: ParsedDocument<WebAuthnCredential>,
+        sign_count: u32,
+        now: UnixTimestamp,
+    ) -> anyhow::Result<()> {
+        let id = credential.id();
+        let mut credential = credential.into_value();
+        credential.sign_count = sign_count;
+        credential.last_used_at = Some(now);
+        SystemMetadataModel::new_global(self.tx)
+            .replace(id, credential.try_into()?)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use common::runtime::Runtime;
+    use database::test_helpers::DbFixtures;
+    use errors::ErrorMetadataAnyhowExt;
+    use keybroker::Identity;
+    use runtime::testing::TestRuntime;
+
+    use super::{
+        types::{
+            WebAuthnCeremony,
+            WebAuthnChallenge,
+            WebAuthnCredential,
+        },
+        WebAuthnModel,
+    };
+    use crate::test_helpers::DbFixturesWithModel;
+
+    #[convex_macro::test_runtime]
+    async fn test_webauthn_challenges(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin(Identity::system()).await?;
+        let now = rt.unix_timestamp();
+        let challenge = WebAuthnChallenge {
+            challenge: vec![1; 32],
+            ceremony: WebAuthnCeremony::Registration,
+            user_id: Some("user1".to_string()),
+            expires_at: now + Duration::from_secs(60),
+        };
+        WebAuthnModel::new(&mut tx)
+            .start_ceremony(challenge.clone(), now)
+            .await?;
+        let err = WebAuthnModel::new(&mut tx)
+            .take_challenge(&[1; 32], WebAuthnCeremony::Authentication, now)
+            .await
+            .unwrap_err();
+        assert_eq!(err.short_msg(), "WebAuthnChallengeNotFound");
+        // The mismatched attempt still consumed the challenge.
+        let err = WebAuthnModel::new(&mut tx)
+            .take_challenge(&[1; 32], WebAuthnCeremony::Registration, now)
+            .await
+            .unwrap_err();
+        assert_eq!(err.short_msg(), "WebAuthnChallengeNotFound");
+
+        WebAuthnModel::new(&mut tx)
+            .start_ceremony(challenge.clone(), now)
+            .await?;
+        let taken = WebAuthnModel::new(&mut tx)
+            .take_challenge(&[1; 32], WebAuthnCeremony::Registration, now)
+            .await?;
+        assert_eq!(taken, challenge);
+
+        let later = now + Duration::from_secs(120);
+        WebAuthnModel::new(&mut tx)
+            .start_ceremony(challenge, now)
+            .await?;
+        let err = WebAuthnModel::new(&mut tx)
+            .take_challenge(&[1; 32], WebAuthnCeremony::Registration, later)
+            .await
+            .unwrap_err();
+        assert_eq!(err.short_msg(), "WebAuthnChallengeExpired");
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_webauthn_credentials(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin(Identity::system()).await?;
+        let credential = WebAuthnCredential {
+            credential_id: vec![7; 16],
+            user_id: "user1".to_string(),
+            public_key: vec![0; 77],
+            sign_count: 0,
+            created_at: rt.unix_timestamp(),
+            last_used_at: None,
+        };
+        WebAuthnModel::new(&mut tx)
+            .insert_credential(credential.clone())
+            .await?;
+        let err = WebAuthnModel::new(&mut tx)
+            .insert_credential(credential.clone())
+            .await
+            .unwrap_err();
+        assert_eq!(err.short_msg(), "WebAuthnCredentialAlreadyRegistered");
+
+        let stored = WebAuthnModel::new(&mut tx)
+            .credential(&[7; 16])
+            .await?
+            .unwrap();
+        WebAuthnModel::new(&mut tx)
+            .record_assertion(stored, 5, rt.unix_timestamp())
+            .await?;
+        let credentials = WebAuthnModel::new(&mut tx)
+            .credentials_for_user("user1")
+            .await?;
+        assert_eq!(credentials.len(), 1);
+        assert_eq!(credentials[0].sign_count, 5);
+        assert!(credentials[0].last_used_at.is_some());
+        assert!(WebAuthnModel::new(&mut tx)
+            .credentials_for_user("user2")
+            .await?
+            .is_empty());
+        Ok(())
+    }
+}
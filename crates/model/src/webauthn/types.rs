@@ -0,0 +1,180 @@
+use std::{
+    fmt,
+    str::FromStr,
+};
+
+use common::runtime::UnixTimestamp;
+#[cfg(any(test, feature = "testing"))]
+use proptest::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use value::codegen_convex_serialization;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub enum WebAuthnCeremony {
+    Registration,
+    Authentication,
+}
+
+impl WebAuthnCeremony {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Registration => "registration",
+            Self::Authentication => "authentication",
+        }
+    }
+}
+
+impl FromStr for WebAuthnCeremony {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "registration" => Ok(Self::Registration),
+            "authentication" => Ok(Self::Authentication),
+            _ => anyhow::bail!("Invalid WebAuthn ceremony {s}"),
+        }
+    }
+}
+
+impl fmt::Display for WebAuthnCeremony {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A challenge issued for a registration or authentication that hasn't
+/// finished yet. Each challenge can only be used once.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct WebAuthnChallenge {
+    pub challenge: Vec<u8>,
+    pub ceremony: WebAuthnCeremony,
+    /// The user registering a credential, or the user authenticating if the
+    /// app asked for a specific one.
+    pub user_id: Option<String>,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(
+            strategy = "(0..=i64::MAX as u64 / 1_000_000).prop_map(UnixTimestamp::from_millis)"
+        )
+    )]
+    pub expires_at: UnixTimestamp,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedWebAuthnChallenge {
+    #[serde(with = "serde_bytes")]
+    challenge: Vec<u8>,
+    ceremony: String,
+    user_id: Option<String>,
+    expires_at_ms: i64,
+}
+
+impl TryFrom<WebAuthnChallenge> for SerializedWebAuthnChallenge {
+    type Error = anyhow::Error;
+
+    fn try_from(challenge: WebAuthnChallenge) -> anyhow::Result<Self> {
+        Ok(Self {
+            challenge: challenge.challenge,
+            ceremony: challenge.ceremony.to_string(),
+            user_id: challenge.user_id,
+            expires_at_ms: challenge.expires_at.as_ms_since_epoch()?.try_into()?,
+        })
+    }
+}
+
+impl TryFrom<SerializedWebAuthnChallenge> for WebAuthnChallenge {
+    type Error = anyhow::Error;
+
+    fn try_from(challenge: SerializedWebAuthnChallenge) -> anyhow::Result<Self> {
+        Ok(Self {
+            challenge: challenge.challenge,
+            ceremony: challenge.ceremony.parse()?,
+            user_id: challenge.user_id,
+            expires_at: UnixTimestamp::from_millis(challenge.expires_at_ms.try_into()?),
+        })
+    }
+}
+
+codegen_convex_serialization!(WebAuthnChallenge, SerializedWebAuthnChallenge);
+
+/// A passkey registered to one of the app's users.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct WebAuthnCredential {
+    pub credential_id: Vec<u8>,
+    /// The app's identifier for the user, chosen when registering.
+    pub user_id: String,
+    /// COSE-encoded public key.
+    pub public_key: Vec<u8>,
+    pub sign_count: u32,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(
+            strategy = "(0..=i64::MAX as u64 / 1_000_000).prop_map(UnixTimestamp::from_millis)"
+        )
+    )]
+    pub created_at: UnixTimestamp,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "proptest::option::of((0..=i64::MAX as u64 / 1_000_000)
+            .prop_map(UnixTimestamp::from_millis))")
+    )]
+    pub last_used_at: Option<UnixTimestamp>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedWebAuthnCredential {
+    #[serde(with = "serde_bytes")]
+    credential_id: Vec<u8>,
+    user_id: String,
+    #[serde(with = "serde_bytes")]
+    public_key: Vec<u8>,
+    sign_count: i64,
+    created_at_ms: i64,
+    last_used_at_ms: Option<i64>,
+}
+
+impl TryFrom<WebAuthnCredential> for SerializedWebAuthnCredential {
+    type Error = anyhow::Error;
+
+    fn try_from(credential: WebAuthnCredential) -> anyhow::Result<Self> {
+        Ok(Self {
+            credential_id: credential.credential_id,
+            user_id: credential.user_id,
+            public_key: credential.public_key,
+            sign_count: credential.sign_count.into(),
+            created_at_ms: credential.created_at.as_ms_since_epoch()?.try_into()?,
+            last_used_at_ms: credential
+                .last_used_at
+                .map(|ts| anyhow::Ok(ts.as_ms_since_epoch()?.try_into()?))
+                .transpose()?,
+        })
+    }
+}
+
+impl TryFrom<SerializedWebAuthnCredential> for WebAuthnCredential {
+    type Error = anyhow::Error;
+
+    fn try_from(credential: SerializedWebAuthnCredential) -> anyhow::Result<Self> {
+        Ok(Self {
+            credential_id: credential.credential_id,
+            user_id: credential.user_id,
+            public_key: credential.public_key,
+            sign_count: credential.sign_count.try_into()?,
+            created_at: UnixTimestamp::from_millis(credential.created_at_ms.try_into()?),
+            last_used_at: credential
+                .last_used_at_ms
+                .map(|ms| anyhow::Ok(UnixTimestamp::from_millis(ms.try_into()?)))
+                .transpose()?,
+        })
+    }
+}
+
+codegen_convex_serialization!(WebAuthnCredential, SerializedWebAuthnCredential);
@@ -0,0 +1,605 @@
+//! Workflows: multi-step orchestrations defined as data and run by the
+//! scheduler. Each step runs as a scheduled job, and the workflow moves to
+//! its next step in the same transaction that completes the job, so every
+//! step's result is recorded exactly once.
+use std::sync::LazyLock;
+
+use common::{
+    components::{
+        CanonicalizedComponentFunctionPath,
+        ComponentPath,
+    },
+    document::{
+        ParsedDocument,
+        ResolvedDocument,
+        CREATION_TIME_FIELD_PATH,
+    },
+    execution_context::{
+        ExecutionContext,
+        ExecutionId,
+    },
+    query::{
+        IndexRange,
+        IndexRangeExpression,
+        Order,
+        Query,
+    },
+    runtime::Runtime,
+    types::IndexName,
+    RequestId,
+};
+use database::{
+    defaults::system_index,
+    require_admin_or_system,
+    ResolvedQuery,
+    SystemMetadataModel,
+    Transaction,
+};
+use errors::ErrorMetadata;
+use serde_json::{
+    json,
+    Value as JsonValue,
+};
+use value::{
+    ConvexArray,
+    ConvexValue,
+    DeveloperDocumentId,
+    FieldPath,
+    ResolvedDocumentId,
+    TableName,
+    TableNamespace,
+};
+
+use self::types::{
+    Workflow,
+    WorkflowDefinition,
+    WorkflowState,
+    MAX_WORKFLOW_STEPS_RUN,
+};
+use crate::{
+    scheduled_jobs::{
+        types::ScheduledJobState,
+        SchedulerModel,
+    },
+    SystemIndex,
+    SystemTable,
+};
+
+pub mod types;
+
+pub static WORKFLOW_DEFINITIONS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_workflow_definitions"
+        .parse()
+        .expect("Invalid built-in workflow_definitions table")
+});
+
+pub static WORKFLOW_DEFINITIONS_INDEX_BY_NAME: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&WORKFLOW_DEFINITIONS_TABLE, "by_name"));
+
+pub static WORKFLOWS_TABLE: LazyLock<TableName> = LazyLock::new(|| {
+    "_workflows"
+        .parse()
+        .expect("Invalid built-in workflows table")
+});
+
+pub static WORKFLOWS_INDEX_BY_JOB_ID: LazyLock<IndexName> =
+    LazyLock::new(|| system_index(&WORKFLOWS_TABLE, "by_job_id"));
+
+static NAME_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "name".parse().expect("invalid name field"));
+
+static JOB_ID_FIELD: LazyLock<FieldPath> =
+    LazyLock::new(|| "jobId".parse().expect("invalid jobId field"));
+
+pub struct WorkflowDefinitionsTable;
+impl SystemTable for WorkflowDefinitionsTable {
+    fn table_name(&self) -> &'static TableName {
+        &WORKFLOW_DEFINITIONS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![SystemIndex {
+            name: WORKFLOW_DEFINITIONS_INDEX_BY_NAME.clone(),
+            fields: vec![NAME_FIELD.clone(), CREATION_TIME_FIELD_PATH.clone()]
+                .try_into()
+                .unwrap(),
+        }]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<WorkflowDefinition>::try_from(document).map(|_| ())
+    }
+}
+
+pub struct WorkflowsTable;
+impl SystemTable for WorkflowsTable {
+    fn table_name(&self) -> &'static TableName {
+        &WORKFLOWS_TABLE
+    }
+
+    fn indexes(&self) -> Vec<SystemIndex> {
+        vec![SystemIndex {
+            name: WORKFLOWS_INDEX_BY_JOB_ID.clone(),
+            fields: vec![JOB_ID_FIELD.clone(), CREATION_TIME_FIELD_PATH.clone()]
+                .try_into()
+                .unwrap(),
+        }]
+    }
+
+    fn validate_document(&self, document: ResolvedDocument) -> anyhow::Result<()> {
+        ParsedDocument::<Workflow>::try_from(document).map(|_| ())
+    }
+}
+
+fn workflow_not_found(id: DeveloperDocumentId) -> ErrorMetadata {
+    ErrorMetadata::not_found(
+        "WorkflowNotFound",
+        format!("Workflow {} doesn't exist.", id.encode()),
+    )
+}
+
+pub struct WorkflowsModel<'a, RT: Runtime> {
+    tx: &'a mut Transaction<RT>,
+}
+
+impl<'a, RT: Runtime> WorkflowsModel<'a, RT> {
+    pub fn new(tx: &'a mut Transaction<RT>) -> Self {
+        Self { tx }
+    }
+
+    /// All the workflow definitions, ordered by name.
+    pub async fn list_definitions(
+        &mut self,
+    ) -> anyhow::Result<Vec<ParsedDocument<WorkflowDefinition>>> {
+        require_admin_or_system(self.tx.identity(), "list_workflow_definitions")?;
+        let index_range = IndexRange {
+            index_name: WORKFLOW_DEFINITIONS_INDEX_BY_NAME.clone(),
+            range: vec![],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        let mut definitions = vec![];
+        while let Some(document) = query_stream.next(self.tx, None).await? {
+            definitions.push(document.try_into()?);
+        }
+        Ok(definitions)
+    }
+
+    async fn get_definition(
+        &mut self,
+        name: &str,
+    ) -> anyhow::Result<Option<ParsedDocument<WorkflowDefinition>>> {
+        let index_range = IndexRange {
+            index_name: WORKFLOW_DEFINITIONS_INDEX_BY_NAME.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                NAME_FIELD.clone(),
+                ConvexValue::try_from(name.to_string())?.into(),
+            )],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        query_stream
+            .expect_at_most_one(self.tx)
+            .await?
+            .map(ParsedDocument::try_from)
+            .transpose()
+    }
+
+    /// Replaces the definition named `definition.name`. Running workflows
+    /// pick up the new definition from their next step.
+    pub async fn set_definition(&mut self, definition: WorkflowDefinition) -> anyhow::Result<()> {
+        require_admin_or_system(self.tx.identity(), "set_workflow_definition")?;
+        match self.get_definition(&definition.name).await? {
+            Some(existing) => {
+                SystemMetadataModel::new_global(self.tx)
+                    .replace(existing.id(), definition.try_into()?)
+                    .await?;
+            },
+            None => {
+                SystemMetadataModel::new_global(self.tx)
+                    .insert(&WORKFLOW_DEFINITIONS_TABLE, definition.try_into()?)
+                    .await?;
+            },
+        }
+        Ok(())
+    }
+
+    /// Removes a definition. Its running workflows fail at their next step.
+    pub async fn delete_definition(&mut self, name: &str) -> anyhow::Result<()> {
+        require_admin_or_system(self.tx.identity(), "delete_workflow_definition")?;
+        if let Some(existing) = self.get_definition(name).await? {
+            SystemMetadataModel::new_global(self.tx)
+                .delete(existing.id())
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Starts a workflow from the first step of the definition `name`.
+    pub async fn start(
+        &mut self,
+        name: &str,
+        input: JsonValue,
+    ) -> anyhow::Result<DeveloperDocumentId> {
+        require_admin_or_system(self.tx.identity(), "start_workflow")?;
+        let Some(definition) = self.get_definition(name).await? else {
+            anyhow::bail!(ErrorMetadata::not_found(
+                "WorkflowDefinitionNotFound",
+                format!("There's no workflow named {name:?}."),
+            ));
+        };
+        let first_step = definition.steps[0].name.clone();
+        let workflow = Workflow {
+            definition: name.to_string(),
+            input: serde_json::to_string(&input)?,
+            state: WorkflowState::Running,
+            current_step: None,
+            attempt: 0,
+            job_id: None,
+            output: None,
+            last_error: None,
+            steps_run: 0,
+        };
+        let id = SystemMetadataModel::new_global(self.tx)
+            .insert(&WORKFLOWS_TABLE, workflow.clone().try_into()?)
+            .await?;
+        self.run_step(id, workflow, &definition, first_step, 1)
+            .await?;
+        Ok(id.into())
+    }
+
+    pub async fn get(
+        &mut self,
+        id: DeveloperDocumentId,
+    ) -> anyhow::Result<Option<ParsedDocument<Workflow>>> {
+        require_admin_or_system(self.tx.identity(), "get_workflow")?;
+        let namespace = self.tx.table_mapping().namespace(TableNamespace::Global);
+        let Ok(id) = id.to_resolved(namespace.number_to_tablet()) else {
+            return Ok(None);
+        };
+        if !namespace.tablet_matches_name(id.tablet_id, &WORKFLOWS_TABLE) {
+            return Ok(None);
+        }
+        self.tx
+            .get(id)
+            .await?
+            .map(ParsedDocument::try_from)
+            .transpose()
+    }
+
+    /// Up to `limit` of the most recently started workflows, newest first.
+    pub async fn list(&mut self, limit: usize) -> anyhow::Result<Vec<ParsedDocument<Workflow>>> {
+        require_admin_or_system(self.tx.identity(), "list_workflows")?;
+        let query = Query::full_table_scan(WORKFLOWS_TABLE.clone(), Order::Desc);
+        let mut query_stream = ResolvedQuery::new(self.tx, TableNamespace::Global, query)?;
+        let mut workflows = vec![];
+        while workflows.len() < limit
+            && let Some(document) = query_stream.next(self.tx, None).await?
+        {
+            workflows.push(document.try_into()?);
+        }
+        Ok(workflows)
+    }
+
+    /// Stops a running workflow and cancels its current step.
+    pub async fn cancel(&mut self, id: DeveloperDocumentId) -> anyhow::Result<()> {
+        let Some(existing) = self.get(id).await? else {
+            anyhow::bail!(workflow_not_found(id));
+        };
+        let (id, workflow) = existing.into_id_and_value();
+        if !workflow.state.is_running() {
+            return Ok(());
+        }
+        let job_id = workflow.job_id;
+        self.finish(id, workflow, WorkflowState::Canceled).await?;
+        if let Some(job_id) = job_id {
+            let namespace = TableNamespace::root_component();
+            let job_id = job_id.to_resolved(
+                self.tx
+                    .table_mapping()
+                    .namespace(namespace)
+                    .number_to_tablet(),
+            )?;
+            SchedulerModel::new(self.tx, namespace)
+                .cancel(job_id)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Moves the workflow running the scheduled job `job_id`, if any, on to
+    /// its next step. Called in the transaction that completes the job, with
+    /// the function's result if it succeeded.
+    pub async fn step_finished(
+        &mut self,
+        job_id: ResolvedDocumentId,
+        state: &ScheduledJobState,
+        output: Option<JsonValue>,
+    ) -> anyhow::Result<()> {
+        let index_range = IndexRange {
+            index_name: WORKFLOWS_INDEX_BY_JOB_ID.clone(),
+            range: vec![IndexRangeExpression::Eq(
+                JOB_ID_FIELD.clone(),
+                ConvexValue::try_from(job_id.developer_id.encode())?.into(),
+            )],
+            order: Order::Asc,
+        };
+        let mut query_stream = ResolvedQuery::new(
+            self.tx,
+            TableNamespace::Global,
+            Query::index_range(index_range),
+        )?;
+        let Some(document) = query_stream.expect_at_most_one(self.tx).await? else {
+            return Ok(());
+        };
+        let (id, mut workflow) =
+            ParsedDocument::<Workflow>::try_from(document)?.into_id_and_value();
+        if !workflow.state.is_running() {
+            return Ok(());
+        }
+        let Some(current_step) = workflow.current_step.clone() else {
+            return Ok(());
+        };
+        let definition = self
+            .get_definition(&workflow.definition)
+            .await?
+            .map(ParsedDocument::into_value);
+        let Some((definition, step)) = definition.and_then(|definition| {
+            let step = definition.step(&current_step)?.clone();
+            Some((definition, step))
+        }) else {
+            let error = format!(
+                "Workflow {:?} no longer has a step named {current_step:?}.",
+                workflow.definition
+            );
+            return self
+                .finish(id, workflow, WorkflowState::Failed(error))
+                .await;
+        };
+        match state {
+            ScheduledJobState::Success => {
+                let output = output.unwrap_or(JsonValue::Null);
+                let next_step = step.next_step(&output).map(str::to_string);
+                workflow.output = Some(serde_json::to_string(&output)?);
+                workflow.last_error = None;
+                match next_step {
+                    Some(next_step) => self.run_step(id, workflow, &definition, next_step, 1).await,
+                    None => self.finish(id, workflow, WorkflowState::Succeeded).await,
+                }
+            },
+            ScheduledJobState::Failed(error) => {
+                workflow.last_error = Some(error.clone());
+                if workflow.attempt < step.max_attempts {
+                    let attempt = workflow.attempt + 1;
+                    self.run_step(id, workflow, &definition, current_step, attempt)
+                        .await
+                } else if let Some(on_failure) = step.on_failure {
+                    self.run_step(id, workflow, &definition, on_failure, 1)
+                        .await
+                } else {
+                    let state = WorkflowState::Failed(error.clone());
+                    self.finish(id, workflow, state).await
+                }
+            },
+            ScheduledJobState::Canceled => self.finish(id, workflow, WorkflowState::Canceled).await,
+            ScheduledJobState::Pending | ScheduledJobState::InProgress => Ok(()),
+        }
+    }
+
+    /// Schedules `attempt` of the step `step_name`, after the step's delay on
+    /// its first attempt or its retry delay after that.
+    async fn run_step(
+        &mut self,
+        id: ResolvedDocumentId,
+        mut workflow: Workflow,
+        definition: &WorkflowDefinition,
+        step_name: String,
+        attempt: u32,
+    ) -> anyhow::Result<()> {
+        if workflow.steps_run >= MAX_WORKFLOW_STEPS_RUN {
+            let error = format!("Workflow ran more than {MAX_WORKFLOW_STEPS_RUN} steps.");
+            return self
+                .finish(id, workflow, WorkflowState::Failed(error))
+                .await;
+        }
+        let step = definition
+            .step(&step_name)
+            .ok_or_else(|| anyhow::anyhow!("Workflow step {step_name:?} not found"))?;
+        let delay = if attempt == 1 {
+            step.delay
+        } else {
+            step.retry_delay
+        };
+        let input: JsonValue = serde_json::from_str(&workflow.input)?;
+        let previous: JsonValue = match &workflow.output {
+            Some(output) => serde_json::from_str(output)?,
+            None => JsonValue::Null,
+        };
+        let args = json!({
+            "workflowId": DeveloperDocumentId::from(id).encode(),
+            "input": input,
+            "previous": previous,
+        });
+        let ts = self.tx.runtime().unix_timestamp() + delay.unwrap_or_default();
+        let job_id = SchedulerModel::new(self.tx, TableNamespace::root_component())
+            .schedule(
+                CanonicalizedComponentFunctionPath {
+                    component: ComponentPath::root(),
+                    udf_path: step.function.clone(),
+                },
+                ConvexArray::try_from(vec![ConvexValue::try_from(args)?])?,
+                ts,
+                ExecutionContext::new_from_parts(RequestId::new(), ExecutionId::new(), None, true),
+            )
+            .await?;
+        workflow.current_step = Some(step_name);
+        workflow.attempt = attempt;
+        workflow.job_id = Some(job_id.into());
+        workflow.steps_run += 1;
+        SystemMetadataModel::new_global(self.tx)
+            .replace(id, workflow.try_into()?)
+            .await?;
+        Ok(())
+    }
+
+    async fn finish(
+        &mut self,
+        id: ResolvedDocumentId,
+        mut workflow: Workflow,
+        state: WorkflowState,
+    ) -> anyhow::Result<()> {
+        workflow.state = state;
+        workflow.current_step = None;
+        workflow.job_id = None;
+        SystemMetadataModel::new_global(self.tx)
+            .replace(id, workflow.try_into()?)
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use database::{
+        test_helpers::DbFixtures,
+        Transaction,
+    };
+    use keybroker::Identity;
+    use runtime::testing::TestRuntime;
+    use serde_json::{
+        json,
+        Value as JsonValue,
+    };
+    use value::{
+        DeveloperDocumentId,
+        TableNamespace,
+    };
+
+    use super::{
+        types::{
+            WorkflowBranch,
+            WorkflowDefinition,
+            WorkflowState,
+            WorkflowStep,
+        },
+        WorkflowsModel,
+    };
+    use crate::{
+        scheduled_jobs::{
+            types::ScheduledJobState,
+            SchedulerModel,
+        },
+        test_helpers::DbFixturesWithModel,
+    };
+
+    fn step(name: &str, max_attempts: u32) -> anyhow::Result<WorkflowStep> {
+        Ok(WorkflowStep {
+            name: name.to_string(),
+            function: format!("backfill:{name}").parse()?,
+            max_attempts,
+            delay: None,
+            retry_delay: None,
+            branches: vec![],
+            next: None,
+            on_failure: None,
+        })
+    }
+
+    /// Completes the job running the workflow's current step.
+    async fn complete_step(
+        tx: &mut Transaction<TestRuntime>,
+        id: DeveloperDocumentId,
+        state: ScheduledJobState,
+        output: Option<JsonValue>,
+    ) -> anyhow::Result<()> {
+        let workflow = WorkflowsModel::new(tx).get(id).await?.unwrap();
+        let namespace = TableNamespace::root_component();
+        let job_id = workflow
+            .job_id
+            .unwrap()
+            .to_resolved(tx.table_mapping().namespace(namespace).number_to_tablet())?;
+        SchedulerModel::new(tx, namespace)
+            .complete_with_output(job_id, state, output)
+            .await
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_workflow_steps(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin(Identity::system()).await?;
+        let fetch = WorkflowStep {
+            branches: vec![WorkflowBranch {
+                when: "\"done\"".to_string(),
+                next: "notify".to_string(),
+            }],
+            next: Some("fetch".to_string()),
+            ..step("fetch", 2)?
+        };
+        let definition =
+            WorkflowDefinition::new("backfill".to_string(), vec![fetch, step("notify", 1)?])?;
+        WorkflowsModel::new(&mut tx)
+            .set_definition(definition)
+            .await?;
+        let id = WorkflowsModel::new(&mut tx)
+            .start("backfill", json!({"cursor": null}))
+            .await?;
+
+        // A failed attempt is retried.
+        complete_step(&mut tx, id, ScheduledJobState::Failed("oops".into()), None).await?;
+        let workflow = WorkflowsModel::new(&mut tx).get(id).await?.unwrap();
+        assert_eq!(workflow.current_step.as_deref(), Some("fetch"));
+        assert_eq!(workflow.attempt, 2);
+
+        // Without a matching branch it goes to `next`.
+        complete_step(&mut tx, id, ScheduledJobState::Success, Some(json!("more"))).await?;
+        let workflow = WorkflowsModel::new(&mut tx).get(id).await?.unwrap();
+        assert_eq!(workflow.current_step.as_deref(), Some("fetch"));
+        assert_eq!(workflow.attempt, 1);
+        assert_eq!(workflow.steps_run, 3);
+
+        complete_step(&mut tx, id, ScheduledJobState::Success, Some(json!("done"))).await?;
+        let workflow = WorkflowsModel::new(&mut tx).get(id).await?.unwrap();
+        assert_eq!(workflow.current_step.as_deref(), Some("notify"));
+
+        complete_step(&mut tx, id, ScheduledJobState::Success, Some(json!(null))).await?;
+        let workflow = WorkflowsModel::new(&mut tx).get(id).await?.unwrap();
+        assert_eq!(workflow.state, WorkflowState::Succeeded);
+        assert_eq!(workflow.job_id, None);
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_workflow_fails_after_last_attempt(rt: TestRuntime) -> anyhow::Result<()> {
+        let DbFixtures { db, .. } = DbFixtures::new_with_model(&rt).await?;
+        let mut tx = db.begin(Identity::system()).await?;
+        let definition = WorkflowDefinition::new("backfill".to_string(), vec![step("fetch", 1)?])?;
+        WorkflowsModel::new(&mut tx)
+            .set_definition(definition)
+            .await?;
+        let id = WorkflowsModel::new(&mut tx)
+            .start("backfill", JsonValue::Null)
+            .await?;
+        complete_step(&mut tx, id, ScheduledJobState::Failed("oops".into()), None).await?;
+        let workflow = WorkflowsModel::new(&mut tx).get(id).await?.unwrap();
+        assert_eq!(workflow.state, WorkflowState::Failed("oops".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_definition_rejects_unknown_steps() -> anyhow::Result<()> {
+        let fetch = WorkflowStep {
+            next: Some("store".to_string()),
+            ..step("fetch", 1)?
+        };
+        assert!(WorkflowDefinition::new("backfill".to_string(), vec![fetch]).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,394 @@
+use std::{
+    collections::BTreeSet,
+    time::Duration,
+};
+
+use errors::ErrorMetadata;
+#[cfg(any(test, feature = "testing"))]
+use proptest::prelude::*;
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::Value as JsonValue;
+use sync_types::CanonicalizedUdfPath;
+use value::{
+    codegen_convex_serialization,
+    DeveloperDocumentId,
+};
+
+/// The most steps a workflow definition can have.
+pub const MAX_WORKFLOW_DEFINITION_STEPS: usize = 100;
+/// The most times a step can be attempted before it fails.
+pub const MAX_WORKFLOW_STEP_ATTEMPTS: u32 = 100;
+/// The most steps a single workflow can run, counting retries and loops, so
+/// a workflow that branches back on itself forever eventually fails.
+pub const MAX_WORKFLOW_STEPS_RUN: u64 = 10_000;
+
+/// A named series of steps. A workflow starts at the first step and moves
+/// between steps as each one succeeds or fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct WorkflowDefinition {
+    pub name: String,
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "prop::collection::vec(any::<WorkflowStep>(), 1..4)")
+    )]
+    pub steps: Vec<WorkflowStep>,
+}
+
+/// A step runs a mutation or action in the root component through the
+/// scheduler. The function is called with a single object holding the
+/// `workflowId`, the workflow's `input` and the `previous` step's result.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct WorkflowStep {
+    pub name: String,
+    pub function: CanonicalizedUdfPath,
+    /// How many times the function is run before the step fails. Actions
+    /// run at most once per attempt, so this is how actions are retried.
+    pub max_attempts: u32,
+    /// How long to wait before the step first runs.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(
+            strategy = "proptest::option::of((1..=86_400_000u64).prop_map(Duration::from_millis))"
+        )
+    )]
+    pub delay: Option<Duration>,
+    /// How long to wait between attempts.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(
+            strategy = "proptest::option::of((1..=86_400_000u64).prop_map(Duration::from_millis))"
+        )
+    )]
+    pub retry_delay: Option<Duration>,
+    /// Checked in order against the function's result. The first branch
+    /// whose value equals the result picks the next step.
+    pub branches: Vec<WorkflowBranch>,
+    /// The step to run after success when no branch matches. The workflow
+    /// succeeds if there isn't one.
+    pub next: Option<String>,
+    /// The step to run once every attempt has failed. The workflow fails if
+    /// there isn't one.
+    pub on_failure: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct WorkflowBranch {
+    /// The JSON-encoded result this branch matches.
+    pub when: String,
+    pub next: String,
+}
+
+fn invalid_workflow(msg: impl Into<String>) -> ErrorMetadata {
+    ErrorMetadata::bad_request("InvalidWorkflowDefinition", msg.into())
+}
+
+impl WorkflowDefinition {
+    pub fn new(name: String, steps: Vec<WorkflowStep>) -> anyhow::Result<Self> {
+        anyhow::ensure!(
+            !name.is_empty(),
+            invalid_workflow("A workflow's name can't be empty.")
+        );
+        anyhow::ensure!(
+            !steps.is_empty() && steps.len() <= MAX_WORKFLOW_DEFINITION_STEPS,
+            invalid_workflow(format!(
+                "A workflow must have between 1 and {MAX_WORKFLOW_DEFINITION_STEPS} steps."
+            ))
+        );
+        let mut names = BTreeSet::new();
+        for step in &steps {
+            anyhow::ensure!(
+                names.insert(step.name.as_str()),
+                invalid_workflow(format!("Step {:?} is defined more than once.", step.name))
+            );
+        }
+        for step in &steps {
+            anyhow::ensure!(
+                !step.function.is_system(),
+                invalid_workflow(format!(
+                    "Step {:?} can't run the system function {}.",
+                    step.name,
+                    String::from(step.function.clone())
+                ))
+            );
+            anyhow::ensure!(
+                (1..=MAX_WORKFLOW_STEP_ATTEMPTS).contains(&step.max_attempts),
+                invalid_workflow(format!(
+                    "Step {:?} must have between 1 and {MAX_WORKFLOW_STEP_ATTEMPTS} attempts.",
+                    step.name
+                ))
+            );
+            for branch in &step.branches {
+                serde_json::from_str::<JsonValue>(&branch.when).map_err(|_| {
+                    invalid_workflow(format!(
+                        "Step {:?} has a branch that doesn't match a JSON value.",
+                        step.name
+                    ))
+                })?;
+            }
+            let targets = step
+                .branches
+                .iter()
+                .map(|branch| &branch.next)
+                .chain(&step.next)
+                .chain(&step.on_failure);
+            for target in targets {
+                anyhow::ensure!(
+                    names.contains(target.as_str()),
+                    invalid_workflow(format!(
+                        "Step {:?} goes to {target:?}, which isn't a step.",
+                        step.name
+                    ))
+                );
+            }
+        }
+        Ok(Self { name, steps })
+    }
+
+    pub fn step(&self, name: &str) -> Option<&WorkflowStep> {
+        self.steps.iter().find(|step| step.name == name)
+    }
+}
+
+impl WorkflowStep {
+    /// The step to run after this one returned `result`, or `None` if the
+    /// workflow is done.
+    pub fn next_step(&self, result: &JsonValue) -> Option<&str> {
+        self.branches
+            .iter()
+            .find(|branch| {
+                serde_json::from_str::<JsonValue>(&branch.when).is_ok_and(|when| &when == result)
+            })
+            .map(|branch| branch.next.as_str())
+            .or(self.next.as_deref())
+    }
+}
+
+/// A run of a [`WorkflowDefinition`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub struct Workflow {
+    pub definition: String,
+    /// The JSON-encoded input the workflow was started with.
+    pub input: String,
+    pub state: WorkflowState,
+    /// The step that's running, until the workflow finishes.
+    pub current_step: Option<String>,
+    /// Which attempt of the current step is running, starting at 1.
+    pub attempt: u32,
+    /// The `_scheduled_jobs` document running the current step.
+    pub job_id: Option<DeveloperDocumentId>,
+    /// The JSON-encoded result of the last step that succeeded.
+    pub output: Option<String>,
+    /// The error from the last attempt that failed.
+    pub last_error: Option<String>,
+    /// How many attempts of any step have run.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(strategy = "0..=i64::MAX as u64")
+    )]
+    pub steps_run: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
+pub enum WorkflowState {
+    Running,
+    Succeeded,
+    Failed(String),
+    Canceled,
+}
+
+impl WorkflowState {
+    pub fn is_running(&self) -> bool {
+        matches!(self, WorkflowState::Running)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedWorkflowDefinition {
+    name: String,
+    steps: Vec<SerializedWorkflowStep>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SerializedWorkflowStep {
+    name: String,
+    function: String,
+    max_attempts: i64,
+    delay_ms: Option<i64>,
+    retry_delay_ms: Option<i64>,
+    branches: Vec<SerializedWorkflowBranch>,
+    next: Option<String>,
+    on_failure: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SerializedWorkflowBranch {
+    when: String,
+    next: String,
+}
+
+fn duration_to_ms(duration: Option<Duration>) -> anyhow::Result<Option<i64>> {
+    duration
+        .map(|duration| i64::try_from(duration.as_millis()))
+        .transpose()
+        .map_err(Into::into)
+}
+
+fn ms_to_duration(ms: Option<i64>) -> anyhow::Result<Option<Duration>> {
+    ms.map(|ms| anyhow::Ok(Duration::from_millis(u64::try_from(ms)?)))
+        .transpose()
+}
+
+impl TryFrom<WorkflowDefinition> for SerializedWorkflowDefinition {
+    type Error = anyhow::Error;
+
+    fn try_from(definition: WorkflowDefinition) -> anyhow::Result<Self> {
+        let steps = definition
+            .steps
+            .into_iter()
+            .map(|step| {
+                anyhow::Ok(SerializedWorkflowStep {
+                    name: step.name,
+                    function: String::from(step.function),
+                    max_attempts: step.max_attempts.into(),
+                    delay_ms: duration_to_ms(step.delay)?,
+                    retry_delay_ms: duration_to_ms(step.retry_delay)?,
+                    branches: step
+                        .branches
+                        .into_iter()
+                        .map(|branch| SerializedWorkflowBranch {
+                            when: branch.when,
+                            next: branch.next,
+                        })
+                        .collect(),
+                    next: step.next,
+                    on_failure: step.on_failure,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self {
+            name: definition.name,
+            steps,
+        })
+    }
+}
+
+impl TryFrom<SerializedWorkflowDefinition> for WorkflowDefinition {
+    type Error = anyhow::Error;
+
+    fn try_from(definition: SerializedWorkflowDefinition) -> anyhow::Result<Self> {
+        let steps = definition
+            .steps
+            .into_iter()
+            .map(|step| {
+                anyhow::Ok(WorkflowStep {
+                    name: step.name,
+                    function: step.function.parse()?,
+                    max_attempts: step.max_attempts.try_into()?,
+                    delay: ms_to_duration(step.delay_ms)?,
+                    retry_delay: ms_to_duration(step.retry_delay_ms)?,
+                    branches: step
+                        .branches
+                        .into_iter()
+                        .map(|branch| WorkflowBranch {
+                            when: branch.when,
+                            next: branch.next,
+                        })
+                        .collect(),
+                    next: step.next,
+                    on_failure: step.on_failure,
+                })
+            })
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self {
+            name: definition.name,
+            steps,
+        })
+    }
+}
+
+codegen_convex_serialization!(WorkflowDefinition, SerializedWorkflowDefinition);
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SerializedWorkflow {
+    definition: String,
+    input: String,
+    state: SerializedWorkflowState,
+    current_step: Option<String>,
+    attempt: i64,
+    job_id: Option<String>,
+    output: Option<String>,
+    last_error: Option<String>,
+    steps_run: i64,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", tag = "type")]
+enum SerializedWorkflowState {
+    Running,
+    Succeeded,
+    Failed { error: String },
+    Canceled,
+}
+
+impl TryFrom<Workflow> for SerializedWorkflow {
+    type Error = anyhow::Error;
+
+    fn try_from(workflow: Workflow) -> anyhow::Result<Self> {
+        Ok(Self {
+            definition: workflow.definition,
+            input: workflow.input,
+            state: match workflow.state {
+                WorkflowState::Running => SerializedWorkflowState::Running,
+                WorkflowState::Succeeded => SerializedWorkflowState::Succeeded,
+                WorkflowState::Failed(error) => SerializedWorkflowState::Failed { error },
+                WorkflowState::Canceled => SerializedWorkflowState::Canceled,
+            },
+            current_step: workflow.current_step,
+            attempt: workflow.attempt.into(),
+            job_id: workflow.job_id.map(|id| id.encode()),
+            output: workflow.output,
+            last_error: workflow.last_error,
+            steps_run: workflow.steps_run.try_into()?,
+        })
+    }
+}
+
+impl TryFrom<SerializedWorkflow> for Workflow {
+    type Error = anyhow::Error;
+
+    fn try_from(workflow: SerializedWorkflow) -> anyhow::Result<Self> {
+        Ok(Self {
+            definition: workflow.definition,
+            input: workflow.input,
+            state: match workflow.state {
+                SerializedWorkflowState::Running => WorkflowState::Running,
+                SerializedWorkflowState::Succeeded => WorkflowState::Succeeded,
+                SerializedWorkflowState::Failed { error } => WorkflowState::Failed(error),
+                SerializedWorkflowState::Canceled => WorkflowState::Canceled,
+            },
+            current_step: workflow.current_step,
+            attempt: workflow.attempt.try_into()?,
+            job_id: workflow
+                .job_id
+                .map(|id| DeveloperDocumentId::decode(&id))
+                .transpose()?,
+            output: workflow.output,
+            last_error: workflow.last_error,
+            steps_run: workflow.steps_run.try_into()?,
+        })
+    }
+}
+
+codegen_convex_serialization!(Workflow, SerializedWorkflow);
@@ -52,10 +52,12 @@ use model::{
         },
         module_versions::{
             invalid_function_name_error,
+            is_valid_action_region,
             AnalyzedFunction,
             AnalyzedModule,
             AnalyzedSourcePosition,
             MappedModule,
+            RequiredClaims,
             SourceMap,
             Visibility,
         },
@@ -208,13 +210,17 @@ impl Actions {
     ) -> anyhow::Result<NodeActionOutcome> {
         let path = request.path_and_args.path().clone();
         let timer = node_executor("execute");
+        // Use the user facing timeout here, which should be less than the
+        // total Node timeout. This allows us to preempt early and give
+        // better error message and logs in the common case.
+        let timeout = request
+            .path_and_args
+            .user_timeout()
+            .unwrap_or(self.user_timeout);
         let request = ExecutorRequest::Execute {
             request,
             backend_address: self.convex_origin.clone(),
-            // Use the user facing timeout here, which should be less than the
-            // total Node timeout. This allows us to preempt early and give
-            // better error message and logs in the common case.
-            timeout: self.user_timeout,
+            timeout,
         };
         let InvokeResponse {
             response,
@@ -436,6 +442,28 @@ impl Actions {
                     None => ReturnsValidator::Unvalidated,
                 };
                 let visibility = f.visibility.clone().map(Visibility::from);
+                if let Some(region) = &f.region
+                    && !is_valid_action_region(region)
+                {
+                    return Ok(Err(JsError::from_message(format!(
+                        "{} defined in {:?} has an invalid region {region:?}. Regions are \
+                         lowercase letters, digits and dashes, like \"eu-west-1\".",
+                        f.name, path,
+                    ))));
+                }
+
+                let required_claims = match f.required_claims.clone() {
+                    Some(claims) => match RequiredClaims::try_from(claims) {
+                        Ok(claims) => Some(claims),
+                        Err(e) => {
+                            return Ok(Err(JsError::from_message(format!(
+                                "{} defined in {:?}: {e}.",
+                                f.name, path,
+                            ))));
+                        },
+                    },
+                    None => None,
+                };
 
                 // Extract source position
                 let pos = if let Some(Some(token)) =
@@ -459,14 +487,11 @@ impl Actions {
                     .name
                     .parse()
                     .map_err(|e| invalid_function_name_error(&e))?;
-                functions.push(AnalyzedFunction::new(
-                    function_name,
-                    pos,
-                    udf_type,
-                    visibility,
-                    args,
-                    returns,
-                )?);
+                functions.push(
+                    AnalyzedFunction::new(function_name, pos, udf_type, visibility, args, returns)?
+                        .with_region(f.region.clone())
+                        .with_required_claims(required_claims),
+                );
             }
 
             // Sort by line number where source position of None compares least
@@ -815,6 +840,8 @@ pub struct AnalyzedNodeFunction {
     visibility: Option<VisibilityJson>,
     args: Option<JsonValue>,
     returns: Option<JsonValue>,
+    region: Option<String>,
+    required_claims: Option<JsonValue>,
 }
 
 #[derive(Debug)]
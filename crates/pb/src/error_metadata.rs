@@ -22,6 +22,7 @@ impl From<ErrorCode> for ErrorCodeProto {
             ErrorCode::NotFound => ErrorCodeProto::TransientNotFound,
             ErrorCode::ClientDisconnect => ErrorCodeProto::ClientDisconnect,
             ErrorCode::RateLimited => ErrorCodeProto::RateLimited,
+            ErrorCode::PayloadTooLarge => ErrorCodeProto::PayloadTooLarge,
             ErrorCode::Overloaded => ErrorCodeProto::Overloaded,
             ErrorCode::RejectedBeforeExecution => ErrorCodeProto::RejectedBeforeExecution,
             ErrorCode::OCC => ErrorCodeProto::Occ,
@@ -44,6 +45,7 @@ impl From<ErrorCodeProto> for ErrorCode {
             ErrorCodeProto::TransientNotFound => ErrorCode::NotFound,
             ErrorCodeProto::ClientDisconnect => ErrorCode::ClientDisconnect,
             ErrorCodeProto::RateLimited => ErrorCode::RateLimited,
+            ErrorCodeProto::PayloadTooLarge => ErrorCode::PayloadTooLarge,
             ErrorCodeProto::Overloaded => ErrorCode::Overloaded,
             ErrorCodeProto::RejectedBeforeExecution => ErrorCode::RejectedBeforeExecution,
             ErrorCodeProto::Occ => ErrorCode::OCC,
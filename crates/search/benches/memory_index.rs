@@ -17,7 +17,10 @@ use std::{
 };
 
 use common::{
-    bootstrap_model::index::text_index::DeveloperTextIndexConfig,
+    bootstrap_model::index::text_index::{
+        DeveloperTextIndexConfig,
+        TextIndexAnalyzer,
+    },
     document::{
         CreationTime,
         ResolvedDocument,
@@ -26,6 +29,7 @@ use common::{
         InternalSearch,
         InternalSearchFilterExpression,
         SearchVersion,
+        TextSearchOptions,
     },
     types::{
         IndexName,
@@ -95,6 +99,7 @@ impl Dataset {
         let config = DeveloperTextIndexConfig {
             search_field: "body".parse()?,
             filter_fields: BTreeSet::new(),
+            analyzer: TextIndexAnalyzer::default(),
         };
 
         let schema = TantivySearchIndexSchema::new(&config);
@@ -137,6 +142,7 @@ impl Dataset {
                 filters: vec![InternalSearchFilterExpression::Search(
                     "body".parse()?,
                     q.query,
+                    TextSearchOptions::default(),
                 )],
             };
             let (compiled_query, _) = schema.compile(&internal_search, SearchVersion::V1)?;
@@ -0,0 +1,223 @@
+use std::{
+    iter::Peekable,
+    str::CharIndices,
+};
+
+use common::bootstrap_model::index::text_index::TextIndexAnalyzer;
+use strum::IntoEnumIterator;
+use tantivy::tokenizer::{
+    BoxTokenStream,
+    Language,
+    LowerCaser,
+    RemoveLongFilter,
+    Stemmer,
+    StopWordFilter,
+    TextAnalyzer,
+    Token,
+    TokenStream,
+    TokenizerManager,
+};
+
+use crate::constants::{
+    convex_en,
+    CONVEX_EN_TOKENIZER,
+    MAX_TEXT_TERM_LENGTH,
+};
+
+/// Builds the analyzer that splits a text index's search field, and queries
+/// against it, into terms.
+pub fn text_analyzer(analyzer: TextIndexAnalyzer) -> TextAnalyzer {
+    let language = match analyzer {
+        TextIndexAnalyzer::Simple => return convex_en(),
+        TextIndexAnalyzer::Cjk => {
+            return TextAnalyzer::from(CjkTokenizer)
+                .filter(RemoveLongFilter::limit(MAX_TEXT_TERM_LENGTH))
+                .filter(LowerCaser);
+        },
+        TextIndexAnalyzer::Danish => Language::Danish,
+        TextIndexAnalyzer::Dutch => Language::Dutch,
+        TextIndexAnalyzer::English => Language::English,
+        TextIndexAnalyzer::Finnish => Language::Finnish,
+        TextIndexAnalyzer::French => Language::French,
+        TextIndexAnalyzer::German => Language::German,
+        TextIndexAnalyzer::Hungarian => Language::Hungarian,
+        TextIndexAnalyzer::Italian => Language::Italian,
+        TextIndexAnalyzer::Norwegian => Language::Norwegian,
+        TextIndexAnalyzer::Portuguese => Language::Portuguese,
+        TextIndexAnalyzer::Russian => Language::Russian,
+        TextIndexAnalyzer::Spanish => Language::Spanish,
+        TextIndexAnalyzer::Swedish => Language::Swedish,
+    };
+    let mut text_analyzer = convex_en();
+    if let Some(stop_words) = StopWordFilter::new(language) {
+        text_analyzer = text_analyzer.filter(stop_words);
+    }
+    text_analyzer.filter(Stemmer::new(language))
+}
+
+/// The name tantivy knows an analyzer's tokenizer by. Indexes built before
+/// analyzers were configurable refer to the default analyzer by
+/// [`CONVEX_EN_TOKENIZER`], so it keeps that name.
+pub fn tokenizer_name(analyzer: TextIndexAnalyzer) -> String {
+    match analyzer {
+        TextIndexAnalyzer::Simple => CONVEX_EN_TOKENIZER.to_string(),
+        analyzer => format!("convex_{analyzer}"),
+    }
+}
+
+/// Registers every analyzer with a tantivy index, so segments built with any
+/// of them can be written and read.
+pub fn register_tokenizers(tokenizers: &TokenizerManager) {
+    for analyzer in TextIndexAnalyzer::iter() {
+        tokenizers.register(&tokenizer_name(analyzer), text_analyzer(analyzer));
+    }
+}
+
+/// Chinese, Japanese and Korean characters, which aren't separated into words
+/// by spaces.
+fn is_cjk(c: char) -> bool {
+    matches!(c,
+        '\u{3040}'..='\u{30FF}' // Hiragana and Katakana
+        | '\u{3400}'..='\u{4DBF}' // CJK Unified Ideographs Extension A
+        | '\u{4E00}'..='\u{9FFF}' // CJK Unified Ideographs
+        | '\u{AC00}'..='\u{D7AF}' // Hangul Syllables
+        | '\u{F900}'..='\u{FAFF}' // CJK Compatibility Ideographs
+        | '\u{20000}'..='\u{2FFFF}' // Supplementary Ideographic Plane
+    )
+}
+
+/// Splits text into words like tantivy's `SimpleTokenizer`, except that runs
+/// of CJK characters become overlapping bigrams: "東京都" is indexed as "東京"
+/// and "京都". A CJK character on its own is a single term.
+#[derive(Clone)]
+struct CjkTokenizer;
+
+impl tantivy::tokenizer::Tokenizer for CjkTokenizer {
+    fn token_stream<'a>(&self, text: &'a str) -> BoxTokenStream<'a> {
+        BoxTokenStream::from(CjkTokenStream {
+            text,
+            chars: text.char_indices().peekable(),
+            in_cjk_run: false,
+            token: Token::default(),
+        })
+    }
+}
+
+struct CjkTokenStream<'a> {
+    text: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    // Whether the last token was a bigram whose second character is the next
+    // one in `chars`.
+    in_cjk_run: bool,
+    token: Token,
+}
+
+impl CjkTokenStream<'_> {
+    /// Where a bigram starting just before the next character would end, if
+    /// the next character is CJK.
+    fn bigram_end(&mut self) -> Option<usize> {
+        match self.chars.peek() {
+            Some(&(offset, c)) if is_cjk(c) => Some(offset + c.len_utf8()),
+            _ => None,
+        }
+    }
+}
+
+impl TokenStream for CjkTokenStream<'_> {
+    fn advance(&mut self) -> bool {
+        while let Some((offset_from, c)) = self.chars.next() {
+            let offset_to = if is_cjk(c) {
+                match self.bigram_end() {
+                    Some(offset_to) => {
+                        self.in_cjk_run = true;
+                        offset_to
+                    },
+                    // The end of a run, which the last bigram already covered.
+                    None if self.in_cjk_run => {
+                        self.in_cjk_run = false;
+                        continue;
+                    },
+                    None => offset_from + c.len_utf8(),
+                }
+            } else if c.is_alphanumeric() {
+                self.in_cjk_run = false;
+                let mut offset_to = offset_from + c.len_utf8();
+                while let Some(&(offset, c)) = self.chars.peek() {
+                    if !c.is_alphanumeric() || is_cjk(c) {
+                        break;
+                    }
+                    offset_to = offset + c.len_utf8();
+                    self.chars.next();
+                }
+                offset_to
+            } else {
+                self.in_cjk_run = false;
+                continue;
+            };
+            self.token.text.clear();
+            self.token.text.push_str(&self.text[offset_from..offset_to]);
+            self.token.offset_from = offset_from;
+            self.token.offset_to = offset_to;
+            self.token.position = self.token.position.wrapping_add(1);
+            return true;
+        }
+        false
+    }
+
+    fn token(&self) -> &Token {
+        &self.token
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.token
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use common::bootstrap_model::index::text_index::TextIndexAnalyzer;
+
+    use super::text_analyzer;
+
+    fn tokenize(analyzer: TextIndexAnalyzer, text: &str) -> Vec<String> {
+        let analyzer = text_analyzer(analyzer);
+        let mut token_stream = analyzer.token_stream(text);
+        let mut tokens = vec![];
+        while let Some(token) = token_stream.next() {
+            tokens.push(token.text.clone());
+        }
+        tokens
+    }
+
+    #[test]
+    fn test_simple_analyzer() {
+        assert_eq!(
+            tokenize(TextIndexAnalyzer::Simple, "The Running foxes"),
+            vec!["the", "running", "foxes"]
+        );
+    }
+
+    #[test]
+    fn test_stemming_analyzer() {
+        assert_eq!(
+            tokenize(TextIndexAnalyzer::English, "The Running foxes"),
+            vec!["run", "fox"]
+        );
+        assert_eq!(
+            tokenize(TextIndexAnalyzer::English, "runs"),
+            tokenize(TextIndexAnalyzer::English, "running")
+        );
+    }
+
+    #[test]
+    fn test_cjk_analyzer() {
+        assert_eq!(
+            tokenize(TextIndexAnalyzer::Cjk, "東京都 in Tokyo, 日"),
+            vec!["東京", "京都", "in", "tokyo", "日"]
+        );
+        assert_eq!(
+            tokenize(TextIndexAnalyzer::Cjk, "Convex是一个数据库"),
+            vec!["convex", "是一", "一个", "个数", "数据", "据库"]
+        );
+    }
+}
@@ -61,8 +61,7 @@ use vector::qdrant_segments::{
 use walkdir::WalkDir;
 
 use crate::{
-    constants::CONVEX_EN_TOKENIZER,
-    convex_en,
+    analyzers::register_tokenizers,
     metrics::{
         self,
     },
@@ -82,9 +81,7 @@ pub async fn index_reader_for_directory<P: AsRef<Path>>(
     let timer = metrics::index_reader_for_directory_timer();
     let directory = directory.as_ref().to_path_buf();
     let index = tokio::task::spawn_blocking(move || Index::open_in_dir(directory)).await??;
-    index
-        .tokenizers()
-        .register(CONVEX_EN_TOKENIZER, convex_en());
+    register_tokenizers(index.tokenizers());
     let reader = index.reader()?;
     timer.finish();
     Ok(reader)
@@ -98,9 +95,7 @@ pub async fn index_writer_for_directory<P: AsRef<Path>>(
     let schema = tantivy_schema.schema.clone();
     let index =
         tokio::task::spawn_blocking(move || Index::create_in_dir(&directory, schema)).await??;
-    index
-        .tokenizers()
-        .register(CONVEX_EN_TOKENIZER, convex_en());
+    register_tokenizers(index.tokenizers());
     Ok(index.writer(*SEARCH_INDEXING_MEMORY_ARENA_BYTES)?)
 }
 
@@ -12,6 +12,7 @@
 #![feature(lint_reasons)]
 
 mod aggregation;
+mod analyzers;
 mod archive;
 mod constants;
 mod convex_query;
@@ -38,10 +39,15 @@ use std::{
 };
 
 use aggregation::PostingListMatchAggregator;
+pub use analyzers::text_analyzer;
+use analyzers::tokenizer_name;
 use anyhow::Context;
 use common::{
     bootstrap_model::index::{
-        text_index::DeveloperTextIndexConfig,
+        text_index::{
+            DeveloperTextIndexConfig,
+            TextIndexAnalyzer,
+        },
         IndexConfig,
     },
     document::ResolvedDocument,
@@ -51,6 +57,8 @@ use common::{
         InternalSearch,
         InternalSearchFilterExpression,
         SearchVersion,
+        TextSearchOptions,
+        MAX_SEARCH_EDIT_DISTANCE,
     },
     runtime::block_in_place,
     types::{
@@ -58,7 +66,6 @@ use common::{
         Timestamp,
     },
 };
-use constants::CONVEX_EN_TOKENIZER;
 pub use constants::{
     convex_en,
     EXACT_SEARCH_MAX_WORD_LENGTH,
@@ -230,6 +237,7 @@ impl TryFrom<&Token> for FieldPosition {
 
 #[derive(Clone)]
 pub struct TantivySearchIndexSchema {
+    analyzer_config: TextIndexAnalyzer,
     analyzer: TextAnalyzer,
 
     internal_id_field: Field,
@@ -254,13 +262,16 @@ impl From<&TantivySearchIndexSchema> for pb::searchlight::SearchIndexConfig {
                 .cloned()
                 .map(|p| p.into())
                 .collect::<Vec<_>>(),
+            analyzer: (!schema.analyzer_config.is_default())
+                .then(|| schema.analyzer_config.to_string()),
         }
     }
 }
 
 impl TantivySearchIndexSchema {
     pub fn new(index_config: &DeveloperTextIndexConfig) -> Self {
-        let analyzer = convex_en();
+        let analyzer_config = index_config.analyzer;
+        let analyzer = text_analyzer(analyzer_config);
 
         let mut schema_builder = Schema::builder();
 
@@ -270,7 +281,7 @@ impl TantivySearchIndexSchema {
 
         let search_field_path = index_config.search_field.clone();
         let index_opts = TextFieldIndexing::default()
-            .set_tokenizer(CONVEX_EN_TOKENIZER)
+            .set_tokenizer(&tokenizer_name(analyzer_config))
             .set_fieldnorms(true)
             .set_index_option(IndexRecordOption::WithFreqsAndPositions);
         let field_opts = TextOptions::default().set_indexing_options(index_opts);
@@ -290,6 +301,7 @@ impl TantivySearchIndexSchema {
         }
         let schema = schema_builder.build();
         Self {
+            analyzer_config,
             analyzer,
             internal_id_field,
             ts_field,
@@ -324,6 +336,7 @@ impl TantivySearchIndexSchema {
         DeveloperTextIndexConfig {
             search_field: self.search_field_path.clone(),
             filter_fields: self.filter_fields.keys().cloned().collect(),
+            analyzer: self.analyzer_config,
         }
     }
 
@@ -672,9 +685,39 @@ impl TantivySearchIndexSchema {
         Ok(result)
     }
 
+    /// The most typos a query word is matched with, based on its length.
+    fn typo_budget(text: &str) -> u8 {
+        let char_count = text.chars().count();
+        if char_count <= EXACT_SEARCH_MAX_WORD_LENGTH {
+            0
+        } else if char_count <= SINGLE_TYPO_SEARCH_MAX_WORD_LENGTH {
+            1
+        } else {
+            2
+        }
+    }
+
     fn compile_tokens_with_typo_tolerance(
         search_field: Field,
         tokens: &Vec<String>,
+    ) -> anyhow::Result<Vec<QueryTerm>> {
+        Self::compile_tokens_with_options(
+            search_field,
+            tokens,
+            TextSearchOptions {
+                prefix: true,
+                max_edit_distance: MAX_SEARCH_EDIT_DISTANCE,
+            },
+        )
+    }
+
+    /// Matches the last token as a prefix if `options.prefix` is set, and
+    /// each token with up to `options.max_edit_distance` typos, capped by its
+    /// [`Self::typo_budget`].
+    fn compile_tokens_with_options(
+        search_field: Field,
+        tokens: &Vec<String>,
+        options: TextSearchOptions,
     ) -> anyhow::Result<Vec<QueryTerm>> {
         let mut res = vec![];
 
@@ -683,15 +726,8 @@ impl TantivySearchIndexSchema {
             let term = Term::from_field_text(search_field, text);
             anyhow::ensure!(term.as_str().is_some(), "Term was not valid UTF8");
 
-            let char_count = text.chars().count();
-            let is_prefix = it.peek().is_none();
-            let num_typos = if char_count <= EXACT_SEARCH_MAX_WORD_LENGTH {
-                0
-            } else if char_count <= SINGLE_TYPO_SEARCH_MAX_WORD_LENGTH {
-                1
-            } else {
-                2
-            };
+            let is_prefix = options.prefix && it.peek().is_none();
+            let num_typos = cmp::min(options.max_edit_distance, Self::typo_budget(text));
 
             if num_typos == 0 && !is_prefix {
                 res.push(QueryTerm::Exact(term))
@@ -713,12 +749,12 @@ impl TantivySearchIndexSchema {
     ) -> anyhow::Result<(CompiledQuery, QueryReads)> {
         let timer = metrics::compile_timer();
 
-        let mut search_text: Option<&str> = None;
+        let mut search_text: Option<(&str, TextSearchOptions)> = None;
         let mut filter_conditions = Vec::new();
         let mut filter_reads = Vec::new();
         for filter in query.filters.iter() {
             match filter {
-                InternalSearchFilterExpression::Search(field_path, text_query) => {
+                InternalSearchFilterExpression::Search(field_path, text_query, options) => {
                     if *field_path != self.search_field_path {
                         anyhow::bail!(ErrorMetadata::bad_request(
                             "IncorrectSearchField",
@@ -741,7 +777,7 @@ impl TantivySearchIndexSchema {
                             )
                         ))
                     }
-                    search_text = Some(text_query)
+                    search_text = Some((text_query.as_str(), *options))
                 },
                 InternalSearchFilterExpression::Eq(field_path, value) => {
                     let Some(field) = self.filter_fields.get(field_path) else {
@@ -762,7 +798,7 @@ impl TantivySearchIndexSchema {
             }
         }
 
-        let Some(search_text) = search_text else {
+        let Some((search_text, options)) = search_text else {
             anyhow::bail!(ErrorMetadata::bad_request(
                 "MissingSearchFilterError",
                 format!(
@@ -787,15 +823,10 @@ impl TantivySearchIndexSchema {
         }
 
         let text_query = match version {
-            SearchVersion::V1 => tokens
-                .iter()
-                .map(|text| {
-                    let term = Term::from_field_text(self.search_field, text);
-                    anyhow::ensure!(term.as_str().is_some(), "Term was not valid UTF8");
-                    Ok(QueryTerm::Exact(term))
-                })
-                .collect::<anyhow::Result<Vec<_>>>()?,
-            // Only the V2 search codepath can generate QueryTerm::Fuzzy
+            // V1 only matches fuzzily when the query asks to.
+            SearchVersion::V1 => {
+                Self::compile_tokens_with_options(self.search_field, &tokens, options)?
+            },
             SearchVersion::V2 => {
                 Self::compile_tokens_with_typo_tolerance(self.search_field, &tokens)?
             },
@@ -807,6 +838,7 @@ impl TantivySearchIndexSchema {
             .map(|t| {
                 anyhow::Ok(TextQueryTermRead::new(
                     self.search_field_path.clone(),
+                    self.analyzer_config,
                     TextQueryTerm::try_from(t)?,
                 ))
             })
@@ -856,7 +888,10 @@ pub enum SearchFileType {
 mod test {
     use std::collections::BTreeSet;
 
-    use common::bootstrap_model::index::text_index::DeveloperTextIndexConfig;
+    use common::bootstrap_model::index::text_index::{
+        DeveloperTextIndexConfig,
+        TextIndexAnalyzer,
+    };
 
     use crate::{
         TantivySearchIndexSchema,
@@ -871,6 +906,7 @@ mod test {
         let schema = TantivySearchIndexSchema::new(&DeveloperTextIndexConfig {
             search_field: "mySearchField".parse()?,
             filter_fields: BTreeSet::new(),
+            analyzer: TextIndexAnalyzer::default(),
         });
         assert_eq!(schema.internal_id_field.field_id(), 0);
         assert_eq!(schema.ts_field.field_id(), 1);
@@ -10,6 +10,7 @@ use std::{
 use anyhow::Context;
 use bitvec::vec::BitVec;
 use common::{
+    bootstrap_model::index::text_index::TextIndexAnalyzer,
     document::{
         CreationTime,
         PackedDocument,
@@ -52,7 +53,6 @@ use value::{
 };
 
 use crate::{
-    convex_en,
     levenshtein_dfa::build_fuzzy_dfa,
     memory_index::{
         art::ART,
@@ -60,6 +60,7 @@ use crate::{
     },
     metrics,
     scoring::term_from_str,
+    text_analyzer,
     EditDistance,
 };
 
@@ -611,12 +612,24 @@ impl QueryResults {
 #[cfg_attr(any(test, feature = "testing"), derive(proptest_derive::Arbitrary))]
 pub struct TextQueryTermRead {
     pub field_path: FieldPath,
+    /// The analyzer the term came from, which documents are split with to
+    /// check if they match it.
+    // Other analyzers can split the terms proptest generates differently.
+    #[cfg_attr(
+        any(test, feature = "testing"),
+        proptest(value = "TextIndexAnalyzer::default()")
+    )]
+    pub analyzer: TextIndexAnalyzer,
     pub term: TextQueryTerm,
 }
 
 impl TextQueryTermRead {
-    pub fn new(field_path: FieldPath, term: TextQueryTerm) -> Self {
-        Self { field_path, term }
+    pub fn new(field_path: FieldPath, analyzer: TextIndexAnalyzer, term: TextQueryTerm) -> Self {
+        Self {
+            field_path,
+            analyzer,
+            term,
+        }
     }
 }
 
@@ -801,7 +814,7 @@ impl HeapSize for QueryReads {
 
 #[derive(Debug, Clone)]
 struct SearchTermTries<T: Clone + Ord> {
-    terms: BTreeMap<FieldPath, Tries<T>>,
+    terms: BTreeMap<(FieldPath, TextIndexAnalyzer), Tries<T>>,
 }
 
 impl<T: Clone + Ord> SearchTermTries<T> {
@@ -811,19 +824,19 @@ impl<T: Clone + Ord> SearchTermTries<T> {
         }
     }
 
-    fn overlaps<'a>(&'a self, document: &'a PackedDocument, analyzer: &'a TextAnalyzer) -> bool {
-        let mut tokens = DocumentTokens::new(analyzer, document);
+    fn overlaps<'a>(&'a self, document: &'a PackedDocument) -> bool {
+        let mut tokens = DocumentTokens::new(document);
         !self.matching_values(&mut tokens).is_empty()
     }
 
     fn matching_values<'a>(&'a self, tokens: &mut DocumentTokens<'a>) -> BTreeSet<T> {
         let mut result = BTreeSet::new();
-        for (path, tries) in self.terms.iter() {
+        for ((path, analyzer), tries) in self.terms.iter() {
             for ((prefix, max_distance), trie) in tries.tries.iter() {
                 // Prefixing is handled by constructing prefix tokens in DocumentTokens (see the
                 // notes there), so we can get away with a symmetric search where the dfa's
                 // prefix is always set to false.
-                tokens.for_each_token(path, *prefix, |token| {
+                tokens.for_each_token(path, *analyzer, *prefix, |token| {
                     let dfa = build_fuzzy_dfa(token, *max_distance, false);
                     for (values, ..) in trie.intersect(dfa, None) {
                         result.extend(values.keys().cloned());
@@ -840,7 +853,7 @@ impl<T: Clone + Ord> SearchTermTries<T> {
             let (token, max_distance, prefix) = text_query.term.fuzzy_params();
             let art = self
                 .terms
-                .entry(path.clone())
+                .entry((path.clone(), text_query.analyzer))
                 .or_insert_with(Tries::new)
                 .tries
                 .entry((prefix, max_distance))
@@ -861,7 +874,7 @@ impl<T: Clone + Ord> SearchTermTries<T> {
             let value = value.clone();
             let tries = self
                 .terms
-                .get_mut(path)
+                .get_mut(&(path.clone(), text_query.analyzer))
                 .unwrap_or_else(|| panic!("Missing tries for {}", path));
             let trie = tries
                 .tries
@@ -933,8 +946,7 @@ impl QueryReads {
                 return true;
             }
         }
-        let analyzer = convex_en();
-        if self.fuzzy_terms.overlaps(document, &analyzer) {
+        if self.fuzzy_terms.overlaps(document) {
             metrics::log_query_reads_outcome(true);
             return true;
         }
@@ -1026,8 +1038,7 @@ impl TextSearchSubscriptions {
     /// reads/subscriptions is significantly larger than the number of
     /// tokens in the document.
     fn add_fuzzy_matches(&self, document: &PackedDocument, matches: &mut BTreeSet<SubscriberId>) {
-        let analyzer = convex_en();
-        let mut tokens = DocumentTokens::new(&analyzer, document);
+        let mut tokens = DocumentTokens::new(document);
         for (_, fuzzy_terms) in self
             .fuzzy_searches
             .iter()
@@ -1068,20 +1079,18 @@ impl FieldTokens {
 
 struct DocumentTokens<'a> {
     doc: &'a PackedDocument,
-    analyzer: &'a TextAnalyzer,
-    tokens: BTreeMap<FieldPath, FieldTokens>,
+    tokens: BTreeMap<(FieldPath, TextIndexAnalyzer), FieldTokens>,
 }
 
 impl<'a> DocumentTokens<'a> {
-    fn new(analyzer: &'a TextAnalyzer, doc: &'a PackedDocument) -> Self {
+    fn new(doc: &'a PackedDocument) -> Self {
         DocumentTokens {
             doc,
-            analyzer,
             tokens: BTreeMap::new(),
         }
     }
 
-    fn calculate(document_text: &ConvexString, analyzer: &'a TextAnalyzer) -> FieldTokens {
+    fn calculate(document_text: &ConvexString, analyzer: &TextAnalyzer) -> FieldTokens {
         // Tokenizing the document is expensive, but so is constructing a prefix for
         // every token. So we always keep track of the list of tokens, but we
         // only construct the prefixes for each token if we have at least one search in
@@ -1096,8 +1105,13 @@ impl<'a> DocumentTokens<'a> {
         FieldTokens { tokens }
     }
 
-    fn for_each_token<'b, F>(&'b mut self, path: &'a FieldPath, prefix: bool, mut for_each: F)
-    where
+    fn for_each_token<'b, F>(
+        &'b mut self,
+        path: &'a FieldPath,
+        analyzer: TextIndexAnalyzer,
+        prefix: bool,
+        mut for_each: F,
+    ) where
         F: FnMut(&String),
     {
         let Some(ConvexValue::String(document_text)) = self.doc.value().get_path(path) else {
@@ -1105,8 +1119,8 @@ impl<'a> DocumentTokens<'a> {
         };
         let document_tokens = self
             .tokens
-            .entry(path.clone())
-            .or_insert(Self::calculate(&document_text, self.analyzer));
+            .entry((path.clone(), analyzer))
+            .or_insert_with(|| Self::calculate(&document_text, &text_analyzer(analyzer)));
 
         if prefix {
             // We're inverting prefix match here by constructing all possible prefixes for
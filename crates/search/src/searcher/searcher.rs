@@ -1440,7 +1440,10 @@ mod tests {
     };
 
     use common::{
-        bootstrap_model::index::text_index::DeveloperTextIndexConfig,
+        bootstrap_model::index::text_index::{
+            DeveloperTextIndexConfig,
+            TextIndexAnalyzer,
+        },
         document::{
             CreationTime,
             ResolvedDocument,
@@ -1527,6 +1530,7 @@ mod tests {
         let schema = TantivySearchIndexSchema::new(&DeveloperTextIndexConfig {
             search_field: field_path.clone(),
             filter_fields: BTreeSet::new(),
+            analyzer: TextIndexAnalyzer::default(),
         });
 
         #[derive(serde::Deserialize)]
@@ -1703,6 +1707,7 @@ mod tests {
         TantivySearchIndexSchema::new(&DeveloperTextIndexConfig {
             search_field: field_path.clone(),
             filter_fields: BTreeSet::new(),
+            analyzer: TextIndexAnalyzer::default(),
         })
     }
 
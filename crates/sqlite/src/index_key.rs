@@ -0,0 +1,182 @@
+//! How index keys are stored in the `index_entries` table.
+//!
+//! Compound indexes over long string fields have long keys, and storing each
+//! one whole in the table's primary key made them most of the database.
+//! Instead, each key is stored as its first [`INDEX_KEY_PREFIX_LEN`] bytes,
+//! its SHA-256, and the rest of the key. Only the prefix and hash are part of
+//! the primary key, so the b-tree's size doesn't grow with key length, and the
+//! rest of the key is zstd compressed when `INDEX_KEY_BLOCK_COMPRESSION` is on
+//! and that makes it smaller.
+//!
+//! Truncating keys to a fixed length preserves their order, so range scans can
+//! filter on the prefix in SQL and then check the full keys.
+use common::{
+    knobs::INDEX_KEY_BLOCK_COMPRESSION,
+    sha256::Sha256,
+};
+
+/// Keys longer than this are split into a prefix of this length and a suffix.
+pub const INDEX_KEY_PREFIX_LEN: usize = 64;
+
+/// Shorter suffixes are stored raw, since zstd's framing outweighs what it
+/// could save.
+const MIN_COMPRESSED_SUFFIX_LEN: usize = 32;
+
+const ZSTD_LEVEL: i32 = 3;
+
+/// How a row's key suffix is encoded. Each row records its own, so rows
+/// written before `INDEX_KEY_BLOCK_COMPRESSION` changed stay readable until
+/// `SqlitePersistence::recompress_index_entries` rewrites them.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SuffixCodec {
+    Raw,
+    Zstd,
+}
+
+impl SuffixCodec {
+    /// The codec new rows are written with.
+    pub fn current() -> Self {
+        if *INDEX_KEY_BLOCK_COMPRESSION {
+            Self::Zstd
+        } else {
+            Self::Raw
+        }
+    }
+
+    pub fn to_sql(self) -> u32 {
+        match self {
+            Self::Raw => 0,
+            Self::Zstd => 1,
+        }
+    }
+
+    pub fn from_sql(codec: u32) -> anyhow::Result<Self> {
+        match codec {
+            0 => Ok(Self::Raw),
+            1 => Ok(Self::Zstd),
+            _ => anyhow::bail!("Unknown index key codec {codec}"),
+        }
+    }
+}
+
+pub struct StoredIndexKey {
+    pub prefix: Vec<u8>,
+    pub sha256: Vec<u8>,
+    /// `None` if the whole key fits in the prefix.
+    pub suffix: Option<Vec<u8>>,
+    pub codec: SuffixCodec,
+}
+
+impl StoredIndexKey {
+    pub fn new(mut key: Vec<u8>, codec: SuffixCodec) -> anyhow::Result<Self> {
+        let sha256 = Sha256::hash(&key).to_vec();
+        let (suffix, codec) = if key.len() > INDEX_KEY_PREFIX_LEN {
+            let (suffix, codec) = encode_suffix(key.split_off(INDEX_KEY_PREFIX_LEN), codec)?;
+            (Some(suffix), codec)
+        } else {
+            (None, SuffixCodec::Raw)
+        };
+        Ok(Self {
+            prefix: key,
+            sha256,
+            suffix,
+            codec,
+        })
+    }
+}
+
+/// Encodes a suffix with `codec`, falling back to storing it raw if that
+/// wouldn't make it smaller.
+pub fn encode_suffix(
+    suffix: Vec<u8>,
+    codec: SuffixCodec,
+) -> anyhow::Result<(Vec<u8>, SuffixCodec)> {
+    match codec {
+        SuffixCodec::Raw => Ok((suffix, SuffixCodec::Raw)),
+        SuffixCodec::Zstd => {
+            if suffix.len() < MIN_COMPRESSED_SUFFIX_LEN {
+                return Ok((suffix, SuffixCodec::Raw));
+            }
+            let compressed = zstd::encode_all(&suffix[..], ZSTD_LEVEL)?;
+            if compressed.len() < suffix.len() {
+                Ok((compressed, SuffixCodec::Zstd))
+            } else {
+                Ok((suffix, SuffixCodec::Raw))
+            }
+        },
+    }
+}
+
+pub fn decode_suffix(suffix: Vec<u8>, codec: SuffixCodec) -> anyhow::Result<Vec<u8>> {
+    match codec {
+        SuffixCodec::Raw => Ok(suffix),
+        SuffixCodec::Zstd => Ok(zstd::decode_all(&suffix[..])?),
+    }
+}
+
+/// Reassembles a key from a row's prefix, suffix and codec.
+pub fn decode_key(
+    mut prefix: Vec<u8>,
+    suffix: Option<Vec<u8>>,
+    codec: u32,
+) -> anyhow::Result<Vec<u8>> {
+    if let Some(suffix) = suffix {
+        prefix.extend(decode_suffix(suffix, SuffixCodec::from_sql(codec)?)?);
+    }
+    Ok(prefix)
+}
+
+/// The prefix of a key that's stored in the primary key. Truncation preserves
+/// order, so a key in `[start, end)` has a prefix in `[prefix(start),
+/// prefix(end)]`.
+pub fn key_prefix(key: &[u8]) -> &[u8] {
+    &key[..key.len().min(INDEX_KEY_PREFIX_LEN)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        decode_key,
+        StoredIndexKey,
+        SuffixCodec,
+        INDEX_KEY_PREFIX_LEN,
+    };
+
+    fn roundtrip(key: Vec<u8>, codec: SuffixCodec) -> anyhow::Result<StoredIndexKey> {
+        let stored = StoredIndexKey::new(key.clone(), codec)?;
+        let decoded = decode_key(
+            stored.prefix.clone(),
+            stored.suffix.clone(),
+            stored.codec.to_sql(),
+        )?;
+        assert_eq!(decoded, key);
+        Ok(stored)
+    }
+
+    #[test]
+    fn test_stored_index_key() -> anyhow::Result<()> {
+        let short = roundtrip(b"short".to_vec(), SuffixCodec::Zstd)?;
+        assert_eq!(short.prefix, b"short");
+        assert!(short.suffix.is_none());
+
+        let long_key = "a long string field ".repeat(50).into_bytes();
+        let compressed = roundtrip(long_key.clone(), SuffixCodec::Zstd)?;
+        assert_eq!(compressed.prefix.len(), INDEX_KEY_PREFIX_LEN);
+        assert_eq!(compressed.codec, SuffixCodec::Zstd);
+        assert!(compressed.suffix.unwrap().len() < long_key.len() - INDEX_KEY_PREFIX_LEN);
+
+        let raw = roundtrip(long_key, SuffixCodec::Raw)?;
+        assert_eq!(raw.codec, SuffixCodec::Raw);
+        assert_eq!(raw.sha256, compressed.sha256);
+
+        // Suffixes that don't compress are stored raw.
+        let incompressible: Vec<u8> = (0..INDEX_KEY_PREFIX_LEN + 40)
+            .map(|i| (i * 97 % 251) as u8)
+            .collect();
+        assert_eq!(
+            roundtrip(incompressible, SuffixCodec::Zstd)?.codec,
+            SuffixCodec::Raw
+        );
+        Ok(())
+    }
+}
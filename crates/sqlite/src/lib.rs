@@ -60,13 +60,23 @@ use futures_async_stream::try_stream;
 use parking_lot::Mutex;
 use rusqlite::{
     params,
-    types::Null,
     Connection,
     Row,
     ToSql,
 };
 use serde_json::Value as JsonValue;
 
+use crate::index_key::{
+    decode_key,
+    decode_suffix,
+    encode_suffix,
+    key_prefix,
+    StoredIndexKey,
+    SuffixCodec,
+};
+
+mod index_key;
+
 // We only have a single Sqlite connection which does not allow async calls, so
 // we can't really make queries concurrent.
 #[derive(Clone)]
@@ -79,19 +89,31 @@ struct Inner {
     connection: Connection,
 }
 
+/// Where [`SqlitePersistence::recompress_index_entries`] left off.
+#[derive(Default)]
+pub struct RecompressionCursor {
+    index_id: Vec<u8>,
+    key_prefix: Vec<u8>,
+    key_sha256: Vec<u8>,
+    ts: u64,
+}
+
 impl SqlitePersistence {
     pub fn new(path: &str, allow_read_only: bool) -> anyhow::Result<Self> {
         let newly_created = !Path::new(path).exists();
-        let connection = Connection::open(path)?;
+        let mut connection = Connection::open(path)?;
         // Execute create tables unconditionally since they are idempotent.
         connection.execute_batch(DOCUMENTS_INIT)?;
-        connection.execute_batch(INDEXES_INIT)?;
+        connection.execute_batch(INDEX_ENTRIES_INIT)?;
         connection.execute_batch(READ_ONLY_INIT)?;
         connection.execute_batch(PERSISTENCE_GLOBALS_INIT)?;
         if !allow_read_only {
             let mut stmt = connection.prepare(CHECK_IS_READ_ONLY)?;
             anyhow::ensure!(stmt.raw_query().next()?.is_none());
         }
+        if connection.prepare(HAS_LEGACY_INDEXES)?.exists([])? {
+            migrate_legacy_indexes(&mut connection)?;
+        }
         Ok(Self {
             inner: Arc::new(Mutex::new(Inner {
                 newly_created,
@@ -100,6 +122,76 @@ impl SqlitePersistence {
         })
     }
 
+    /// Rewrites up to `batch_size` index entries after `cursor` whose key
+    /// suffix isn't encoded the way `INDEX_KEY_BLOCK_COMPRESSION` says, so
+    /// entries written before it changed take up as little space as new
+    /// ones. Returns how many entries were rewritten and where to continue
+    /// from, or `None` once every entry has been checked.
+    pub fn recompress_index_entries(
+        &self,
+        cursor: Option<RecompressionCursor>,
+        batch_size: usize,
+    ) -> anyhow::Result<(usize, Option<RecompressionCursor>)> {
+        let codec = SuffixCodec::current();
+        let cursor = cursor.unwrap_or_default();
+        let mut inner = self.inner.lock();
+        let tx = inner.connection.transaction()?;
+        let mut select_query = tx.prepare_cached(RECOMPRESS_INDEX_ENTRIES)?;
+        let rows = select_query
+            .query_map(
+                params![
+                    cursor.index_id,
+                    cursor.key_prefix,
+                    cursor.key_sha256,
+                    cursor.ts,
+                    codec.to_sql(),
+                    batch_size,
+                ],
+                |row| {
+                    Ok((
+                        row.get::<_, Vec<u8>>(0)?,
+                        row.get::<_, Vec<u8>>(1)?,
+                        row.get::<_, Vec<u8>>(2)?,
+                        row.get::<_, u64>(3)?,
+                        row.get::<_, Vec<u8>>(4)?,
+                        row.get::<_, u32>(5)?,
+                    ))
+                },
+            )?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        drop(select_query);
+
+        let done = rows.len() < batch_size;
+        let mut update_query = tx.prepare_cached(UPDATE_INDEX_SUFFIX)?;
+        let mut rewritten = 0;
+        let mut next_cursor = None;
+        for (index_id, key_prefix, key_sha256, ts, key_suffix, key_codec) in rows {
+            let suffix = decode_suffix(key_suffix, SuffixCodec::from_sql(key_codec)?)?;
+            // Suffixes that don't compress stay raw.
+            let (suffix, suffix_codec) = encode_suffix(suffix, codec)?;
+            if suffix_codec.to_sql() != key_codec {
+                update_query.execute(params![
+                    suffix,
+                    suffix_codec.to_sql(),
+                    &index_id,
+                    &key_prefix,
+                    &key_sha256,
+                    ts,
+                ])?;
+                rewritten += 1;
+            }
+            next_cursor = Some(RecompressionCursor {
+                index_id,
+                key_prefix,
+                key_sha256,
+                ts,
+            });
+        }
+        drop(update_query);
+        tx.commit()?;
+        Ok((rewritten, if done { None } else { next_cursor }))
+    }
+
     #[allow(clippy::needless_lifetimes)]
     #[try_stream(ok = T, error = anyhow::Error)]
     async fn validate_snapshot<T: 'static>(
@@ -134,64 +226,83 @@ impl SqlitePersistence {
 
         let mut params = params![index_id, read_timestamp].to_vec();
 
+        // Filter on key prefixes in SQL, which may match keys just outside the
+        // interval, and then check the full keys.
         let Start::Included(ref start) = interval.start;
-        let start_bytes = &start[..];
+        let start_bytes = key_prefix(&start[..]);
 
         params.push(&start_bytes);
-        let lower = format!(" AND key >= ${}", params.len());
+        let lower = format!(" AND key_prefix >= ${}", params.len());
 
         let end_bytes = match interval.end {
-            End::Excluded(ref t) => Some(&t[..]),
+            End::Excluded(ref t) => Some(key_prefix(&t[..])),
             End::Unbounded => None,
         };
         let upper = match end_bytes {
             Some(ref t) => {
                 params.push(t);
-                format!(" AND key < ${}", params.len())
+                format!(" AND key_prefix <= ${}", params.len())
             },
             None => "".to_owned(),
         };
 
-        let order = match order {
-            Order::Asc => "ASC",
-            Order::Desc => "DESC",
-        };
         let query = format!(
             r#"
-SELECT B.key, B.ts, B.document_id, C.table_id, C.json_value
+SELECT B.key_prefix, B.key_suffix, B.key_codec, B.ts, B.document_id, C.table_id, C.json_value
 FROM (
-    SELECT index_id, key, MAX(ts) as max_ts
-    FROM indexes
+    SELECT index_id, key_prefix, key_sha256, MAX(ts) as max_ts
+    FROM index_entries
     WHERE index_id = $1 AND ts <= $2{lower}{upper}
-    GROUP BY index_id, key
+    GROUP BY index_id, key_prefix, key_sha256
 ) A
-JOIN indexes B
+JOIN index_entries B
 ON B.deleted is FALSE
 AND A.index_id = B.index_id
-AND A.key = B.key
+AND A.key_prefix = B.key_prefix
+AND A.key_sha256 = B.key_sha256
 AND A.max_ts = B.ts
 LEFT JOIN documents C
 ON B.ts = C.ts
 AND B.table_id = c.table_id
 AND B.document_id = C.id
-ORDER BY B.key {order}
 "#,
         );
 
         let connection = &self.inner.lock().connection;
         let mut stmt = connection.prepare(&query)?;
         let row_iter = stmt.query_map(&params[..], |row| {
-            let key = IndexKeyBytes(row.get::<_, Vec<u8>>(0)?);
-            let ts = Timestamp::try_from(row.get::<_, u64>(1)?).expect("timestamp out of bounds");
-            let document_id = row.get::<_, Vec<u8>>(2)?;
-            let table: Option<Vec<u8>> = row.get(3)?;
-            let json_value: Option<String> = row.get(4)?;
+            let key_prefix = row.get::<_, Vec<u8>>(0)?;
+            let key_suffix: Option<Vec<u8>> = row.get(1)?;
+            let key_codec = row.get::<_, u32>(2)?;
+            let ts = Timestamp::try_from(row.get::<_, u64>(3)?).expect("timestamp out of bounds");
+            let document_id = row.get::<_, Vec<u8>>(4)?;
+            let table: Option<Vec<u8>> = row.get(5)?;
+            let json_value: Option<String> = row.get(6)?;
 
-            Ok((key, ts, document_id, table, json_value))
+            Ok((
+                key_prefix,
+                key_suffix,
+                key_codec,
+                ts,
+                document_id,
+                table,
+                json_value,
+            ))
         })?;
-        let mut triples = vec![];
+        let mut rows = vec![];
         for row in row_iter {
-            let (key, ts, document_id, table, json_value) = row?;
+            let (key_prefix, key_suffix, key_codec, ts, document_id, table, json_value) = row?;
+            let key = decode_key(key_prefix, key_suffix, key_codec)?;
+            if interval.contains(&key) {
+                rows.push((IndexKeyBytes(key), ts, document_id, table, json_value));
+            }
+        }
+        match order {
+            Order::Asc => rows.sort_by(|a, b| a.0.cmp(&b.0)),
+            Order::Desc => rows.sort_by(|a, b| b.0.cmp(&a.0)),
+        }
+        let mut triples = vec![];
+        for (key, ts, document_id, table, json_value) in rows {
             let table = table.ok_or_else(|| {
                 anyhow::anyhow!("Dangling index reference for {:?} {:?}", key, ts)
             })?;
@@ -279,31 +390,29 @@ impl Persistence for SqlitePersistence {
         } else {
             tx.prepare_cached(INSERT_INDEX)?
         };
+        let codec = SuffixCodec::current();
         for (ts, update) in indexes {
             let index_id = update.index_id;
-            let key: Vec<u8> = update.key.into_bytes().0;
-            match update.value {
-                DatabaseIndexValue::Deleted => {
-                    insert_index_query.execute(params![
-                        &index_id[..],
-                        &u64::from(ts),
-                        key,
-                        &1,
-                        &Null,
-                        &Null,
-                    ])?;
-                },
-                DatabaseIndexValue::NonClustered(doc_id) => {
-                    insert_index_query.execute(params![
-                        &index_id[..],
-                        &u64::from(ts),
-                        key,
-                        &0,
-                        &doc_id.tablet_id.0[..],
-                        &doc_id.internal_id()[..],
-                    ])?;
-                },
+            let key = StoredIndexKey::new(update.key.into_bytes().0, codec)?;
+            let (deleted, table_id, document_id) = match update.value {
+                DatabaseIndexValue::Deleted => (1, None, None),
+                DatabaseIndexValue::NonClustered(doc_id) => (
+                    0,
+                    Some(doc_id.tablet_id.0[..].to_vec()),
+                    Some(doc_id.internal_id()[..].to_vec()),
+                ),
             };
+            insert_index_query.execute(params![
+                &index_id[..],
+                key.prefix,
+                key.sha256,
+                &u64::from(ts),
+                key.suffix,
+                key.codec.to_sql(),
+                deleted,
+                table_id,
+                document_id,
+            ])?;
         }
         drop(insert_index_query);
 
@@ -345,19 +454,27 @@ impl Persistence for SqlitePersistence {
         let mut walk_indexes = connection.prepare(WALK_INDEXES)?;
         let row_iter = walk_indexes.query_map([], |row| {
             let index_id: Vec<u8> = row.get(0)?;
-            let key: Vec<u8> = row.get(1)?;
-            let ts = Timestamp::try_from(row.get::<_, u64>(2)?).expect("timestamp out of bounds");
-            let deleted = row.get::<_, u32>(3)? != 0;
-            Ok((index_id, key, ts, deleted))
+            let key_prefix: Vec<u8> = row.get(1)?;
+            let key_sha256: Vec<u8> = row.get(2)?;
+            let ts = Timestamp::try_from(row.get::<_, u64>(3)?).expect("timestamp out of bounds");
+            let key_suffix: Option<Vec<u8>> = row.get(4)?;
+            let key_codec = row.get::<_, u32>(5)?;
+            let deleted = row.get::<_, u32>(6)? != 0;
+            Ok((
+                index_id, key_prefix, key_sha256, ts, key_suffix, key_codec, deleted,
+            ))
         })?;
         let rows = row_iter
             .map(|row| {
-                let (index_id, key, ts, deleted) = row?;
+                let (index_id, key_prefix, key_sha256, ts, key_suffix, key_codec, deleted) = row?;
+                let key_suffix = key_suffix
+                    .map(|suffix| decode_suffix(suffix, SuffixCodec::from_sql(key_codec)?))
+                    .transpose()?;
                 let index_row = IndexEntry {
                     index_id: index_id.try_into()?,
-                    key_prefix: key.clone(),
-                    key_suffix: None,
-                    key_sha256: key,
+                    key_prefix,
+                    key_suffix,
+                    key_sha256,
                     ts,
                     deleted,
                 };
@@ -383,13 +500,22 @@ impl Persistence for SqlitePersistence {
 
         for IndexEntry {
             index_id,
-            key_prefix,
+            mut key_prefix,
+            key_suffix,
             ts,
             ..
         } in expired_rows
         {
-            count_deleted +=
-                delete_index_query.execute(params![&index_id[..], &u64::from(ts), key_prefix,])?;
+            // Entries from retention split keys at a different length than we
+            // do, so find the stored key from the whole key.
+            key_prefix.extend(key_suffix.unwrap_or_default());
+            let key = StoredIndexKey::new(key_prefix, SuffixCodec::Raw)?;
+            count_deleted += delete_index_query.execute(params![
+                &index_id[..],
+                &u64::from(ts),
+                key.prefix,
+                key.sha256,
+            ])?;
         }
         drop(delete_index_query);
         tx.commit()?;
@@ -541,6 +667,35 @@ impl PersistenceReader for SqlitePersistence {
     }
 }
 
+/// Moves index entries from the `indexes` table, which stored whole keys,
+/// into `index_entries`. Suffixes are stored raw to keep this quick, and
+/// compressed afterwards by `recompress_index_entries`.
+fn migrate_legacy_indexes(connection: &mut Connection) -> anyhow::Result<()> {
+    let tx = connection.transaction()?;
+    {
+        let mut walk_legacy_indexes = tx.prepare(WALK_LEGACY_INDEXES)?;
+        let mut insert_index_query = tx.prepare(INSERT_INDEX)?;
+        let mut rows = walk_legacy_indexes.query([])?;
+        while let Some(row) = rows.next()? {
+            let key = StoredIndexKey::new(row.get(1)?, SuffixCodec::Raw)?;
+            insert_index_query.execute(params![
+                row.get::<_, Vec<u8>>(0)?,
+                key.prefix,
+                key.sha256,
+                row.get::<_, u64>(2)?,
+                key.suffix,
+                key.codec.to_sql(),
+                row.get::<_, u32>(3)?,
+                row.get::<_, Option<Vec<u8>>>(4)?,
+                row.get::<_, Option<Vec<u8>>>(5)?,
+            ])?;
+        }
+    }
+    tx.execute_batch(DROP_LEGACY_INDEXES)?;
+    tx.commit()?;
+    Ok(())
+}
+
 const DOCUMENTS_INIT: &str = r#"
 CREATE TABLE IF NOT EXISTS documents (
     id BLOB NOT NULL,
@@ -556,20 +711,24 @@ CREATE TABLE IF NOT EXISTS documents (
 CREATE INDEX IF NOT EXISTS documents_by_table_and_id ON documents (table_id, id, ts);
 "#;
 
-const INDEXES_INIT: &str = r#"
-CREATE TABLE IF NOT EXISTS indexes (
+// See `index_key` for how keys are stored.
+const INDEX_ENTRIES_INIT: &str = r#"
+CREATE TABLE IF NOT EXISTS index_entries (
     index_id BLOB NOT NULL,
+    key_prefix BLOB NOT NULL,
+    key_sha256 BLOB NOT NULL,
     ts INTEGER NOT NULL,
 
-    key BLOB NOT NULL,
+    key_suffix BLOB NULL,
+    key_codec INTEGER NOT NULL,
 
     deleted INTEGER NOT NULL,
 
     table_id BLOB NULL,
     document_id BLOB NULL,
 
-    PRIMARY KEY (index_id, key, ts)
-);
+    PRIMARY KEY (index_id, key_prefix, key_sha256, ts)
+) WITHOUT ROWID;
 "#;
 
 const READ_ONLY_INIT: &str = r#"
@@ -622,14 +781,40 @@ const GET_PERSISTENCE_GLOBAL: &str = "SELECT json_value FROM persistence_globals
 
 const INSERT_DOCUMENT: &str = "INSERT INTO documents VALUES (?, ?, ?, ?, ?)";
 const INSERT_OVERWRITE_DOCUMENT: &str = "INSERT OR REPLACE INTO documents VALUES (?, ?, ?, ?, ?)";
-const INSERT_INDEX: &str = "INSERT INTO indexes VALUES (?, ?, ?, ?, ?, ?)";
-const INSERT_OVERWRITE_INDEX: &str = "INSERT OR REPLACE INTO indexes VALUES (?, ?, ?, ?, ?, ?)";
+const INSERT_INDEX: &str = "INSERT INTO index_entries VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)";
+const INSERT_OVERWRITE_INDEX: &str =
+    "INSERT OR REPLACE INTO index_entries VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)";
 const WRITE_PERSISTENCE_GLOBAL: &str = "INSERT OR REPLACE INTO persistence_globals VALUES (?, ?)";
 
-const WALK_INDEXES: &str =
-    "SELECT index_id, key, ts, deleted FROM indexes ORDER BY index_id ASC, key ASC, ts ASC";
+const WALK_INDEXES: &str = r#"
+SELECT index_id, key_prefix, key_sha256, ts, key_suffix, key_codec, deleted
+FROM index_entries
+ORDER BY index_id ASC, key_prefix ASC, key_sha256 ASC, ts ASC
+"#;
 
-const DELETE_INDEX: &str = "DELETE FROM indexes WHERE index_id = ? AND ts <= ? AND key = ?";
+const DELETE_INDEX: &str = r#"
+DELETE FROM index_entries
+WHERE index_id = ? AND ts <= ? AND key_prefix = ? AND key_sha256 = ?
+"#;
+
+const HAS_LEGACY_INDEXES: &str =
+    "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'indexes'";
+const WALK_LEGACY_INDEXES: &str =
+    "SELECT index_id, key, ts, deleted, table_id, document_id FROM indexes";
+const DROP_LEGACY_INDEXES: &str = "DROP TABLE indexes";
+
+const RECOMPRESS_INDEX_ENTRIES: &str = r#"
+SELECT index_id, key_prefix, key_sha256, ts, key_suffix, key_codec
+FROM index_entries
+WHERE (index_id, key_prefix, key_sha256, ts) > (?, ?, ?, ?)
+AND key_suffix IS NOT NULL AND key_codec != ?
+ORDER BY index_id ASC, key_prefix ASC, key_sha256 ASC, ts ASC
+LIMIT ?
+"#;
+const UPDATE_INDEX_SUFFIX: &str = r#"
+UPDATE index_entries SET key_suffix = ?, key_codec = ?
+WHERE index_id = ? AND key_prefix = ? AND key_sha256 = ? AND ts = ?
+"#;
 
 const DELETE_DOCUMENT: &str = "DELETE FROM documents WHERE table_id = ? AND id = ? AND ts <= ?";
 
@@ -647,3 +832,251 @@ WHERE
 ORDER BY ts desc
 LIMIT 1
 "#;
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeSet,
+        sync::Arc,
+    };
+
+    use common::{
+        assert_obj,
+        bootstrap_model::index::{
+            database_index::IndexedFields,
+            INDEX_TABLE,
+        },
+        document::{
+            CreationTime,
+            ResolvedDocument,
+        },
+        index::IndexKey,
+        interval::{
+            End,
+            Interval,
+            Start,
+        },
+        persistence::{
+            ConflictStrategy,
+            NoopRetentionValidator,
+            Persistence,
+        },
+        query::Order,
+        testing::test_id_generator::TestIdGenerator,
+        types::{
+            DatabaseIndexUpdate,
+            DatabaseIndexValue,
+            IndexId,
+            PersistenceVersion,
+            TableName,
+            Timestamp,
+        },
+        value::TabletId,
+    };
+    use futures::TryStreamExt;
+    use rusqlite::{
+        params,
+        Connection,
+    };
+    use tempfile::TempDir;
+
+    use super::{
+        SqlitePersistence,
+        HAS_LEGACY_INDEXES,
+    };
+    use crate::index_key::INDEX_KEY_PREFIX_LEN;
+
+    // The `indexes` table as older versions created it.
+    const LEGACY_INDEXES_INIT: &str = r#"
+CREATE TABLE IF NOT EXISTS indexes (
+    index_id BLOB NOT NULL,
+    ts INTEGER NOT NULL,
+
+    key BLOB NOT NULL,
+    deleted INTEGER NOT NULL,
+
+    table_id BLOB NULL,
+    document_id BLOB NULL,
+
+    PRIMARY KEY (index_id, key, ts)
+);
+"#;
+    const INSERT_LEGACY_INDEX: &str = "INSERT INTO indexes VALUES (?, ?, ?, ?, ?, ?)";
+
+    /// Documents whose index keys are mostly longer than
+    /// `INDEX_KEY_PREFIX_LEN` and share some or all of their stored prefix,
+    /// sorted by key.
+    fn long_key_entries(
+        version: PersistenceVersion,
+    ) -> anyhow::Result<(IndexId, TabletId, Vec<(IndexKey, ResolvedDocument)>)> {
+        let mut id_generator = TestIdGenerator::new();
+        let index_id = id_generator.system_generate(&INDEX_TABLE).internal_id();
+        let table: TableName = str::parse("table")?;
+        let tablet_id = id_generator.user_table_id(&table).tablet_id;
+        let fields: IndexedFields = vec!["value".parse()?].try_into()?;
+
+        let shared = "a".repeat(INDEX_KEY_PREFIX_LEN);
+        let values = [
+            "a".repeat(INDEX_KEY_PREFIX_LEN - 10) + "b",
+            "a".repeat(INDEX_KEY_PREFIX_LEN - 1),
+            shared.clone(),
+            shared.clone() + "a",
+            shared.clone() + "b",
+            shared.clone() + "b" + &"c".repeat(100),
+            shared.clone() + "bc",
+            shared.clone() + "c",
+            "b".to_owned(),
+        ];
+        let mut entries = vec![];
+        for value in values {
+            let doc_id = id_generator.user_generate(&table);
+            let doc =
+                ResolvedDocument::new(doc_id, CreationTime::ONE, assert_obj!("value" => value))?;
+            entries.push((doc.index_key(&fields, version), doc));
+        }
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok((index_id, tablet_id, entries))
+    }
+
+    /// Scans every range between two of `entries`' keys in both orders, at
+    /// `read_ts`, expecting the entries written at `ts`.
+    async fn assert_range_scans(
+        p: &SqlitePersistence,
+        index_id: IndexId,
+        tablet_id: TabletId,
+        ts: Timestamp,
+        read_ts: Timestamp,
+        entries: &[(IndexKey, ResolvedDocument)],
+    ) -> anyhow::Result<()> {
+        for i in 0..entries.len() {
+            for j in i..=entries.len() {
+                let end = match entries.get(j) {
+                    Some((key, _)) => End::Excluded(key.clone().into_bytes().into()),
+                    None => End::Unbounded,
+                };
+                let interval = Interval {
+                    start: Start::Included(entries[i].0.clone().into_bytes().into()),
+                    end,
+                };
+                for order in [Order::Asc, Order::Desc] {
+                    let results: Vec<_> = p
+                        .reader()
+                        .index_scan(
+                            index_id,
+                            tablet_id,
+                            read_ts,
+                            &interval,
+                            order,
+                            100,
+                            Arc::new(NoopRetentionValidator),
+                        )
+                        .try_collect()
+                        .await?;
+                    let mut expected: Vec<_> = entries[i..j]
+                        .iter()
+                        .map(|(key, doc)| (key.clone().into_bytes(), ts, doc.clone()))
+                        .collect();
+                    if let Order::Desc = order {
+                        expected.reverse();
+                    }
+                    assert_eq!(results, expected);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_long_key_range_scans() -> anyhow::Result<()> {
+        let db = TempDir::new()?;
+        let path = db.path().join("convex_local_backend.sqlite3");
+        let p = SqlitePersistence::new(path.to_str().unwrap(), false)?;
+        let (index_id, tablet_id, entries) = long_key_entries(p.reader().version())?;
+        let ts = Timestamp::must(1);
+        p.write(
+            entries
+                .iter()
+                .map(|(_, doc)| (ts, doc.id_with_table_id(), Some(doc.clone())))
+                .collect(),
+            entries
+                .iter()
+                .map(|(key, doc)| {
+                    (
+                        ts,
+                        DatabaseIndexUpdate {
+                            index_id,
+                            key: key.clone(),
+                            value: DatabaseIndexValue::NonClustered(doc.id()),
+                            is_system_index: false,
+                        },
+                    )
+                })
+                .collect(),
+            ConflictStrategy::Error,
+        )
+        .await?;
+        assert_range_scans(&p, index_id, tablet_id, ts, ts, &entries).await
+    }
+
+    #[tokio::test]
+    async fn test_migrate_legacy_indexes() -> anyhow::Result<()> {
+        let db = TempDir::new()?;
+        let path = db.path().join("convex_local_backend.sqlite3");
+        let path = path.to_str().unwrap();
+        let p = SqlitePersistence::new(path, false)?;
+        let (index_id, tablet_id, entries) = long_key_entries(p.reader().version())?;
+        let ts = Timestamp::must(1);
+        p.write(
+            entries
+                .iter()
+                .map(|(_, doc)| (ts, doc.id_with_table_id(), Some(doc.clone())))
+                .collect(),
+            BTreeSet::new(),
+            ConflictStrategy::Error,
+        )
+        .await?;
+        drop(p);
+
+        // Write the index entries the way older versions did, with whole keys,
+        // and delete the first one at a later timestamp.
+        let deleted_ts = Timestamp::must(2);
+        let connection = Connection::open(path)?;
+        connection.execute_batch(LEGACY_INDEXES_INIT)?;
+        for (key, doc) in &entries {
+            connection.execute(
+                INSERT_LEGACY_INDEX,
+                params![
+                    &index_id[..],
+                    u64::from(ts),
+                    key.clone().into_bytes().0,
+                    0,
+                    &doc.id().tablet_id.0[..],
+                    &doc.id().internal_id()[..],
+                ],
+            )?;
+        }
+        connection.execute(
+            INSERT_LEGACY_INDEX,
+            params![
+                &index_id[..],
+                u64::from(deleted_ts),
+                entries[0].0.clone().into_bytes().0,
+                1,
+                None::<Vec<u8>>,
+                None::<Vec<u8>>,
+            ],
+        )?;
+        drop(connection);
+
+        let p = SqlitePersistence::new(path, false)?;
+        assert!(!p
+            .inner
+            .lock()
+            .connection
+            .prepare(HAS_LEGACY_INDEXES)?
+            .exists([])?);
+        assert_range_scans(&p, index_id, tablet_id, ts, ts, &entries).await?;
+        assert_range_scans(&p, index_id, tablet_id, ts, deleted_ts, &entries[1..]).await?;
+        Ok(())
+    }
+}
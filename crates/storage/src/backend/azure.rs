@@ -0,0 +1,428 @@
+//! [`StorageBackend`] for a container in Azure Blob Storage, authenticated
+//! with the storage account's shared key. Multipart uploads are block blobs:
+//! each part is an uncommitted block, and completing the upload commits the
+//! block list. Presigned URLs are service SASs.
+use std::{
+    ops::Range,
+    time::Duration,
+};
+
+use anyhow::Context as _;
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{
+    DateTime,
+    Utc,
+};
+use common::runtime::Runtime;
+use http::{
+    Method,
+    StatusCode,
+    Uri,
+};
+use url::Url;
+
+use super::{
+    check_response,
+    encode_key,
+    hmac_sha256,
+    into_get_stream,
+    parse_content_length,
+    StorageBackend,
+    UploadedPart,
+};
+use crate::{
+    ObjectAttributes,
+    StorageGetStream,
+    UploadId,
+};
+
+/// Blob service version for both requests and SASs.
+const VERSION: &str = "2021-08-06";
+const COPY_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Clone)]
+pub struct AzureBlobBackend<RT: Runtime> {
+    rt: RT,
+    client: reqwest::Client,
+    account: String,
+    container: String,
+    /// Scheme, host, port and account path (for emulators like Azurite) of
+    /// the blob service.
+    endpoint: String,
+    endpoint_path: String,
+    access_key: Vec<u8>,
+}
+
+impl<RT: Runtime> std::fmt::Debug for AzureBlobBackend<RT> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AzureBlobBackend")
+            .field("account", &self.account)
+            .field("container", &self.container)
+            .field("endpoint", &self.endpoint)
+            .finish()
+    }
+}
+
+impl<RT: Runtime> AzureBlobBackend<RT> {
+    /// A backend for `container` in the storage `account`, whose base64
+    /// `access_key` signs requests. `endpoint_url` overrides the account's
+    /// default blob endpoint, e.g. `http://127.0.0.1:10000/devstoreaccount1`
+    /// for Azurite.
+    pub fn new(
+        rt: RT,
+        account: String,
+        container: String,
+        access_key: &str,
+        endpoint_url: Option<Url>,
+    ) -> anyhow::Result<Self> {
+        let endpoint_url = match endpoint_url {
+            Some(endpoint_url) => endpoint_url,
+            None => format!("https://{account}.blob.core.windows.net").parse()?,
+        };
+        let endpoint_path = endpoint_url.path().trim_end_matches('/').to_owned();
+        let host = endpoint_url
+            .host_str()
+            .with_context(|| format!("Endpoint {endpoint_url} has no host"))?;
+        let origin = match endpoint_url.port() {
+            Some(port) => format!("{}://{host}:{port}", endpoint_url.scheme()),
+            None => format!("{}://{host}", endpoint_url.scheme()),
+        };
+        let access_key =
+            base64::decode(access_key).context("Azure storage access key isn't base64")?;
+        Ok(Self {
+            rt,
+            client: reqwest::Client::new(),
+            account,
+            container,
+            endpoint: format!("{origin}{endpoint_path}"),
+            endpoint_path,
+            access_key,
+        })
+    }
+
+    fn blob_path(&self, container: &str, blob: &str) -> String {
+        format!(
+            "{}/{}/{}",
+            self.endpoint_path,
+            encode_key(container),
+            encode_key(blob)
+        )
+    }
+
+    fn blob_url(&self, container: &str, blob: &str) -> String {
+        format!(
+            "{}/{}/{}",
+            self.endpoint,
+            encode_key(container),
+            encode_key(blob)
+        )
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        blob: &str,
+        query: &[(&str, &str)],
+        headers: Vec<(&str, String)>,
+        body: Bytes,
+    ) -> anyhow::Result<reqwest::Response> {
+        let response = self
+            .send_unchecked(method, blob, query, headers, body)
+            .await?;
+        check_response("Azure Blob Storage", response).await
+    }
+
+    /// Sends a request signed with the shared key, without checking its
+    /// status. `headers` must all be lowercase `x-ms-` headers so they're
+    /// signed.
+    async fn send_unchecked(
+        &self,
+        method: Method,
+        blob: &str,
+        query: &[(&str, &str)],
+        headers: Vec<(&str, String)>,
+        body: Bytes,
+    ) -> anyhow::Result<reqwest::Response> {
+        let now = DateTime::<Utc>::from(self.rt.system_time());
+        let mut headers = headers;
+        headers.push((
+            "x-ms-date",
+            now.format("%a, %d %b %Y %H:%M:%S GMT").to_string(),
+        ));
+        headers.push(("x-ms-version", VERSION.to_owned()));
+        headers.sort();
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{name}:{}\n", value.trim()))
+            .collect();
+        let mut canonical_resource =
+            format!("/{}{}", self.account, self.blob_path(&self.container, blob));
+        let mut sorted_query = query.to_vec();
+        sorted_query.sort();
+        for (name, value) in &sorted_query {
+            canonical_resource.push_str(&format!("\n{name}:{value}"));
+        }
+        let content_length = if body.is_empty() {
+            String::new()
+        } else {
+            body.len().to_string()
+        };
+        let string_to_sign = [
+            method.as_str(),
+            "", // Content-Encoding
+            "", // Content-Language
+            &content_length,
+            "", // Content-MD5
+            "", // Content-Type
+            "", // Date, which is in `x-ms-date` instead
+            "", // If-Modified-Since
+            "", // If-Match
+            "", // If-None-Match
+            "", // If-Unmodified-Since
+            "", // Range, which is in `x-ms-range` instead
+            &format!("{canonical_headers}{canonical_resource}"),
+        ]
+        .join("\n");
+        let signature = base64::encode(hmac_sha256(&self.access_key, string_to_sign.as_bytes()));
+        let mut url = self.blob_url(&self.container, blob);
+        if !query.is_empty() {
+            let query = query
+                .iter()
+                .map(|(name, value)| format!("{name}={}", urlencoding::encode(value)))
+                .collect::<Vec<_>>()
+                .join("&");
+            url = format!("{url}?{query}");
+        }
+        let mut request = self.client.request(method, url).header(
+            http::header::AUTHORIZATION,
+            format!("SharedKey {}:{signature}", self.account),
+        );
+        for (name, value) in headers {
+            request = request.header(name, value);
+        }
+        Ok(request.body(body).send().await?)
+    }
+}
+
+/// Block IDs must all be the same length within a blob.
+fn block_id(part_number: u16) -> String {
+    base64::encode(format!("{part_number:05}"))
+}
+
+fn copy_status(response: &reqwest::Response) -> anyhow::Result<String> {
+    Ok(response
+        .headers()
+        .get("x-ms-copy-status")
+        .context("Missing x-ms-copy-status")?
+        .to_str()?
+        .to_owned())
+}
+
+#[async_trait]
+impl<RT: Runtime> StorageBackend for AzureBlobBackend<RT> {
+    fn bucket(&self) -> &str {
+        &self.container
+    }
+
+    async fn put_object(&self, key: &str, body: Bytes) -> anyhow::Result<()> {
+        self.send(
+            Method::PUT,
+            key,
+            &[],
+            vec![("x-ms-blob-type", "BlockBlob".to_owned())],
+            body,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn start_multipart_upload(&self, _key: &str) -> anyhow::Result<UploadId> {
+        // Blocks are staged on the blob itself, so there's no upload to start.
+        Ok(String::new().into())
+    }
+
+    async fn upload_part(
+        &self,
+        key: &str,
+        _upload_id: &UploadId,
+        part_number: u16,
+        body: Bytes,
+    ) -> anyhow::Result<UploadedPart> {
+        let block_id = block_id(part_number);
+        self.send(
+            Method::PUT,
+            key,
+            &[("blockid", &block_id), ("comp", "block")],
+            vec![],
+            body,
+        )
+        .await?;
+        Ok(UploadedPart {
+            part_number,
+            tag: block_id,
+        })
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        key: &str,
+        _upload_id: &UploadId,
+        parts: Vec<UploadedPart>,
+    ) -> anyhow::Result<()> {
+        let blocks: String = parts
+            .into_iter()
+            .map(|part| format!("<Latest>{}</Latest>", part.tag))
+            .collect();
+        let body =
+            format!(r#"<?xml version="1.0" encoding="utf-8"?><BlockList>{blocks}</BlockList>"#);
+        self.send(
+            Method::PUT,
+            key,
+            &[("comp", "blocklist")],
+            vec![],
+            body.into(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn abort_multipart_upload(
+        &self,
+        _key: &str,
+        _upload_id: &UploadId,
+    ) -> anyhow::Result<()> {
+        // Azure garbage collects uncommitted blocks after a week.
+        Ok(())
+    }
+
+    async fn get_range(
+        &self,
+        key: &str,
+        bytes_range: Range<u64>,
+    ) -> anyhow::Result<StorageGetStream> {
+        let range = format!("bytes={}-{}", bytes_range.start, bytes_range.end - 1);
+        let response = self
+            .send(
+                Method::GET,
+                key,
+                &[],
+                vec![("x-ms-range", range)],
+                Bytes::new(),
+            )
+            .await?;
+        Ok(into_get_stream(
+            response,
+            bytes_range.end - bytes_range.start,
+        ))
+    }
+
+    async fn head_object(&self, key: &str) -> anyhow::Result<Option<ObjectAttributes>> {
+        let response = self
+            .send_unchecked(Method::HEAD, key, &[], vec![], Bytes::new())
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = check_response("Azure Blob Storage", response).await?;
+        Ok(Some(ObjectAttributes {
+            size: parse_content_length(&response)?,
+        }))
+    }
+
+    async fn copy_object(
+        &self,
+        source_bucket: &str,
+        source_key: &str,
+        key: &str,
+    ) -> anyhow::Result<()> {
+        let response = self
+            .send(
+                Method::PUT,
+                key,
+                &[],
+                vec![("x-ms-copy-source", self.blob_url(source_bucket, source_key))],
+                Bytes::new(),
+            )
+            .await?;
+        // Copies within a storage account usually finish synchronously, but
+        // may not.
+        let mut status = copy_status(&response)?;
+        while status == "pending" {
+            self.rt.wait(COPY_POLL_INTERVAL).await;
+            let response = self
+                .send(Method::HEAD, key, &[], vec![], Bytes::new())
+                .await?;
+            status = copy_status(&response)?;
+        }
+        anyhow::ensure!(
+            status == "success",
+            "Copying {source_bucket}/{source_key} failed with status {status}"
+        );
+        Ok(())
+    }
+
+    async fn delete_object(&self, key: &str) -> anyhow::Result<()> {
+        self.send(Method::DELETE, key, &[], vec![], Bytes::new())
+            .await?;
+        Ok(())
+    }
+
+    /// Uploads through the URL must set `x-ms-blob-type: BlockBlob`.
+    fn presigned_url(
+        &self,
+        method: Method,
+        key: &str,
+        expires_in: Duration,
+    ) -> anyhow::Result<Uri> {
+        let permissions = match method {
+            Method::GET | Method::HEAD => "r",
+            Method::PUT => "cw",
+            Method::DELETE => "d",
+            _ => anyhow::bail!("Can't presign {method} requests for Azure Blob Storage"),
+        };
+        let expiry = DateTime::<Utc>::from(self.rt.system_time() + expires_in)
+            .format("%Y-%m-%dT%H:%M:%SZ")
+            .to_string();
+        let canonical_resource = format!("/blob/{}/{}/{key}", self.account, self.container);
+        let string_to_sign = [
+            permissions,
+            "", // start
+            &expiry,
+            &canonical_resource,
+            "", // identifier
+            "", // IP
+            "", // protocol
+            VERSION,
+            "b", // resource
+            "",  // snapshot time
+            "",  // encryption scope
+            "",  // Cache-Control
+            "",  // Content-Disposition
+            "",  // Content-Encoding
+            "",  // Content-Language
+            "",  // Content-Type
+        ]
+        .join("\n");
+        let signature = base64::encode(hmac_sha256(&self.access_key, string_to_sign.as_bytes()));
+        let uri = format!(
+            "{}?sv={VERSION}&sr=b&sp={permissions}&se={}&sig={}",
+            self.blob_url(&self.container, key),
+            urlencoding::encode(&expiry),
+            urlencoding::encode(&signature),
+        )
+        .parse()?;
+        Ok(uri)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::block_id;
+
+    #[test]
+    fn test_block_ids_have_same_length() {
+        assert_eq!(block_id(1).len(), block_id(10000).len());
+        assert_ne!(block_id(1), block_id(2));
+    }
+}
@@ -0,0 +1,800 @@
+//! Object store backends for [`Storage`].
+//!
+//! A [`StorageBackend`] is a thin client for one bucket (or container) in an
+//! object store: it reads and writes whole objects by key and knows how to
+//! sign requests. [`BackendStorage`] implements [`Storage`] on top of any
+//! backend, giving each [`StorageUseCase`] its own key prefix, so a
+//! self-hosted deployment can keep its exports, modules, search indexes and
+//! user files in S3, GCS or Azure Blob Storage instead of a local directory.
+use std::{
+    fmt::Debug,
+    ops::Range,
+    path::PathBuf,
+    pin::Pin,
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::Context as _;
+use async_trait::async_trait;
+use bytes::Bytes;
+use common::{
+    runtime::Runtime,
+    types::{
+        FullyQualifiedObjectKey,
+        ObjectKey,
+    },
+};
+use futures::{
+    future::BoxFuture,
+    stream,
+    FutureExt,
+    Stream,
+    StreamExt,
+    TryStreamExt,
+};
+use http::{
+    Method,
+    Uri,
+};
+use ring::hmac;
+use serde_json::{
+    json,
+    Value as JsonValue,
+};
+
+use crate::{
+    BufferedUpload,
+    ClientDrivenUploadPartToken,
+    ClientDrivenUploadToken,
+    LocalDirStorage,
+    ObjectAttributes,
+    Storage,
+    StorageCacheKey,
+    StorageGetStream,
+    StorageUseCase,
+    Upload,
+    UploadId,
+    MAXIMUM_PARALLEL_UPLOADS,
+    MAX_NUM_PARTS,
+    MAX_PART_SIZE,
+};
+
+pub mod azure;
+pub mod s3;
+
+/// S3's minimum size for every part of a multipart upload but the last.
+pub const BACKEND_MIN_PART_SIZE: usize = 5 * (1 << 20);
+
+/// A part of a multipart upload, identified by whatever the backend needs to
+/// stitch the parts back together (an ETag for S3, a block ID for Azure).
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct UploadedPart {
+    pub part_number: u16,
+    pub tag: String,
+}
+
+/// A bucket in an object store. Keys are full object names within the
+/// bucket, including any prefix.
+#[async_trait]
+pub trait StorageBackend: Send + Sync + Debug {
+    /// The bucket (or container) objects are stored in.
+    fn bucket(&self) -> &str;
+
+    async fn put_object(&self, key: &str, body: Bytes) -> anyhow::Result<()>;
+
+    async fn start_multipart_upload(&self, key: &str) -> anyhow::Result<UploadId>;
+    /// Uploads a part of a multipart upload. Part numbers start at 1.
+    async fn upload_part(
+        &self,
+        key: &str,
+        upload_id: &UploadId,
+        part_number: u16,
+        body: Bytes,
+    ) -> anyhow::Result<UploadedPart>;
+    /// Completes a multipart upload from its parts, sorted by part number.
+    async fn complete_multipart_upload(
+        &self,
+        key: &str,
+        upload_id: &UploadId,
+        parts: Vec<UploadedPart>,
+    ) -> anyhow::Result<()>;
+    async fn abort_multipart_upload(&self, key: &str, upload_id: &UploadId) -> anyhow::Result<()>;
+
+    /// Streams the non-empty `bytes_range` of the object.
+    async fn get_range(
+        &self,
+        key: &str,
+        bytes_range: Range<u64>,
+    ) -> anyhow::Result<StorageGetStream>;
+    /// Returns the object's attributes, or `None` if it doesn't exist.
+    async fn head_object(&self, key: &str) -> anyhow::Result<Option<ObjectAttributes>>;
+    /// Copies `source_key` in `source_bucket`, which may be a different bucket
+    /// in the same object store, to `key` in this bucket.
+    async fn copy_object(
+        &self,
+        source_bucket: &str,
+        source_key: &str,
+        key: &str,
+    ) -> anyhow::Result<()>;
+    async fn delete_object(&self, key: &str) -> anyhow::Result<()>;
+
+    /// A URL that lets anyone holding it make a `method` request for the
+    /// object until it expires.
+    fn presigned_url(&self, method: Method, key: &str, expires_in: Duration)
+        -> anyhow::Result<Uri>;
+}
+
+/// Where a deployment keeps its exports, modules, search indexes and user
+/// files.
+#[derive(Clone, Debug)]
+pub enum StorageConfig {
+    /// A directory on the local filesystem, with a subdirectory per use case.
+    LocalDir(PathBuf),
+    /// An object store bucket, with a key prefix per use case under `prefix`.
+    Backend {
+        backend: Arc<dyn StorageBackend>,
+        prefix: String,
+    },
+}
+
+impl StorageConfig {
+    pub fn storage_for_use_case<RT: Runtime>(
+        &self,
+        rt: RT,
+        use_case: StorageUseCase,
+    ) -> anyhow::Result<Arc<dyn Storage>> {
+        let storage: Arc<dyn Storage> = match self {
+            StorageConfig::LocalDir(dir) => Arc::new(LocalDirStorage::for_use_case(
+                rt,
+                &dir.to_string_lossy(),
+                use_case,
+            )?),
+            StorageConfig::Backend { backend, prefix } => Arc::new(BackendStorage::new(
+                rt,
+                backend.clone(),
+                format!("{prefix}{use_case}/"),
+            )),
+        };
+        Ok(storage)
+    }
+}
+
+/// [`Storage`] for objects under `prefix` in a [`StorageBackend`].
+#[derive(Clone)]
+pub struct BackendStorage<RT: Runtime> {
+    rt: RT,
+    backend: Arc<dyn StorageBackend>,
+    prefix: String,
+}
+
+impl<RT: Runtime> std::fmt::Debug for BackendStorage<RT> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BackendStorage")
+            .field("backend", &self.backend)
+            .field("prefix", &self.prefix)
+            .finish()
+    }
+}
+
+impl<RT: Runtime> BackendStorage<RT> {
+    pub fn new(rt: RT, backend: Arc<dyn StorageBackend>, prefix: String) -> Self {
+        Self {
+            rt,
+            backend,
+            prefix,
+        }
+    }
+
+    fn new_object_key(&self) -> anyhow::Result<ObjectKey> {
+        self.rt.new_uuid_v4().to_string().try_into()
+    }
+
+    fn path_for_key(&self, key: &ObjectKey) -> String {
+        format!("{}{}", self.prefix, &**key)
+    }
+}
+
+struct ClientDrivenUpload {
+    object_key: ObjectKey,
+    upload_id: UploadId,
+}
+
+impl TryFrom<ClientDrivenUpload> for ClientDrivenUploadToken {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ClientDrivenUpload) -> Result<Self, Self::Error> {
+        let v = json!({
+            "objectKey": value.object_key.to_string(),
+            "uploadId": value.upload_id.to_string(),
+        });
+        Ok(ClientDrivenUploadToken(serde_json::to_string(&v)?))
+    }
+}
+
+impl TryFrom<ClientDrivenUploadToken> for ClientDrivenUpload {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ClientDrivenUploadToken) -> Result<Self, Self::Error> {
+        let v: JsonValue = serde_json::from_str(&value.0)?;
+        let object_key = v
+            .get("objectKey")
+            .context("missing objectKey")?
+            .as_str()
+            .context("objectKey should be str")?
+            .try_into()?;
+        let upload_id = v
+            .get("uploadId")
+            .context("missing uploadId")?
+            .as_str()
+            .context("uploadId should be str")?
+            .to_owned()
+            .into();
+        Ok(Self {
+            object_key,
+            upload_id,
+        })
+    }
+}
+
+impl TryFrom<UploadedPart> for ClientDrivenUploadPartToken {
+    type Error = anyhow::Error;
+
+    fn try_from(value: UploadedPart) -> Result<Self, Self::Error> {
+        let v = json!({
+            "partNumber": value.part_number,
+            "tag": value.tag,
+        });
+        Ok(ClientDrivenUploadPartToken(serde_json::to_string(&v)?))
+    }
+}
+
+impl TryFrom<ClientDrivenUploadPartToken> for UploadedPart {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ClientDrivenUploadPartToken) -> Result<Self, Self::Error> {
+        let v: JsonValue = serde_json::from_str(&value.0)?;
+        let part_number = v
+            .get("partNumber")
+            .context("missing partNumber")?
+            .as_u64()
+            .context("partNumber should be u64")?
+            .try_into()?;
+        let tag = v
+            .get("tag")
+            .context("missing tag")?
+            .as_str()
+            .context("tag should be str")?
+            .to_owned();
+        Ok(Self { part_number, tag })
+    }
+}
+
+#[async_trait]
+impl<RT: Runtime> Storage for BackendStorage<RT> {
+    async fn start_upload(&self) -> anyhow::Result<Box<BufferedUpload>> {
+        let object_key = self.new_object_key()?;
+        let path = self.path_for_key(&object_key);
+        let upload_id = self.backend.start_multipart_upload(&path).await?;
+        let upload = BackendUpload {
+            backend: self.backend.clone(),
+            path,
+            object_key,
+            upload_id,
+            parts: vec![],
+        };
+        let upload = BufferedUpload::new(upload, BACKEND_MIN_PART_SIZE).await?;
+        Ok(Box::new(upload))
+    }
+
+    async fn start_client_driven_upload(&self) -> anyhow::Result<ClientDrivenUploadToken> {
+        let object_key = self.new_object_key()?;
+        let upload_id = self
+            .backend
+            .start_multipart_upload(&self.path_for_key(&object_key))
+            .await?;
+        ClientDrivenUpload {
+            object_key,
+            upload_id,
+        }
+        .try_into()
+    }
+
+    async fn upload_part(
+        &self,
+        token: ClientDrivenUploadToken,
+        part_number: u16,
+        part: Bytes,
+    ) -> anyhow::Result<ClientDrivenUploadPartToken> {
+        let ClientDrivenUpload {
+            object_key,
+            upload_id,
+        } = token.try_into()?;
+        anyhow::ensure!(part.len() <= MAX_PART_SIZE);
+        self.backend
+            .upload_part(
+                &self.path_for_key(&object_key),
+                &upload_id,
+                part_number,
+                part,
+            )
+            .await?
+            .try_into()
+    }
+
+    async fn finish_client_driven_upload(
+        &self,
+        token: ClientDrivenUploadToken,
+        part_tokens: Vec<ClientDrivenUploadPartToken>,
+    ) -> anyhow::Result<ObjectKey> {
+        let ClientDrivenUpload {
+            object_key,
+            upload_id,
+        } = token.try_into()?;
+        let mut parts = part_tokens
+            .into_iter()
+            .map(UploadedPart::try_from)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        parts.sort();
+        self.backend
+            .complete_multipart_upload(&self.path_for_key(&object_key), &upload_id, parts)
+            .await?;
+        Ok(object_key)
+    }
+
+    async fn signed_url(&self, key: ObjectKey, expires_in: Duration) -> anyhow::Result<Uri> {
+        self.backend
+            .presigned_url(Method::GET, &self.path_for_key(&key), expires_in)
+    }
+
+    async fn presigned_upload_url(&self, expires_in: Duration) -> anyhow::Result<(ObjectKey, Uri)> {
+        let object_key = self.new_object_key()?;
+        let uri =
+            self.backend
+                .presigned_url(Method::PUT, &self.path_for_key(&object_key), expires_in)?;
+        Ok((object_key, uri))
+    }
+
+    async fn get_object_attributes(
+        &self,
+        key: &ObjectKey,
+    ) -> anyhow::Result<Option<ObjectAttributes>> {
+        self.backend.head_object(&self.path_for_key(key)).await
+    }
+
+    fn get_small_range(
+        &self,
+        key: &ObjectKey,
+        bytes_range: std::ops::Range<u64>,
+    ) -> BoxFuture<'static, anyhow::Result<StorageGetStream>> {
+        let backend = self.backend.clone();
+        let path = self.path_for_key(key);
+        async move {
+            // Object stores reject empty ranges.
+            if bytes_range.is_empty() {
+                return Ok(StorageGetStream {
+                    content_length: 0,
+                    stream: stream::empty().boxed(),
+                });
+            }
+            backend.get_range(&path, bytes_range).await
+        }
+        .boxed()
+    }
+
+    async fn copy_object(&self, source: FullyQualifiedObjectKey) -> anyhow::Result<ObjectKey> {
+        let source: String = source.into();
+        let (source_bucket, source_key) = source
+            .split_once('/')
+            .with_context(|| format!("Invalid fully qualified key {source}"))?;
+        let object_key = self.new_object_key()?;
+        self.backend
+            .copy_object(source_bucket, source_key, &self.path_for_key(&object_key))
+            .await?;
+        Ok(object_key)
+    }
+
+    fn storage_type_proto(&self) -> pb::searchlight::StorageType {
+        pb::searchlight::StorageType {
+            storage_type: Some(pb::searchlight::storage_type::StorageType::S3(
+                pb::searchlight::S3Storage {
+                    prefix: self.prefix.clone(),
+                    bucket: self.backend.bucket().to_owned(),
+                },
+            )),
+        }
+    }
+
+    fn cache_key(&self, key: &ObjectKey) -> StorageCacheKey {
+        StorageCacheKey(self.fully_qualified_key(key).into())
+    }
+
+    fn fully_qualified_key(&self, key: &ObjectKey) -> FullyQualifiedObjectKey {
+        format!("{}/{}", self.backend.bucket(), self.path_for_key(key)).into()
+    }
+
+    fn test_only_decompose_fully_qualified_key(
+        &self,
+        key: FullyQualifiedObjectKey,
+    ) -> anyhow::Result<ObjectKey> {
+        let key: String = key.into();
+        let prefix = format!("{}/{}", self.backend.bucket(), self.prefix);
+        key.strip_prefix(&prefix)
+            .with_context(|| format!("{key} doesn't start with {prefix}"))?
+            .to_owned()
+            .try_into()
+    }
+
+    async fn delete_object(&self, key: &ObjectKey) -> anyhow::Result<()> {
+        self.backend.delete_object(&self.path_for_key(key)).await
+    }
+}
+
+pub struct BackendUpload {
+    backend: Arc<dyn StorageBackend>,
+    path: String,
+    object_key: ObjectKey,
+    upload_id: UploadId,
+    parts: Vec<UploadedPart>,
+}
+
+#[async_trait]
+impl Upload for BackendUpload {
+    async fn write(&mut self, data: Bytes) -> anyhow::Result<()> {
+        // `BufferedUpload` flushes whatever is left when it completes, which
+        // may be nothing.
+        if data.is_empty() {
+            return Ok(());
+        }
+        anyhow::ensure!(self.parts.len() < MAX_NUM_PARTS);
+        anyhow::ensure!(data.len() <= MAX_PART_SIZE);
+        let part_number = (self.parts.len() + 1).try_into()?;
+        let part = self
+            .backend
+            .upload_part(&self.path, &self.upload_id, part_number, data)
+            .await?;
+        self.parts.push(part);
+        Ok(())
+    }
+
+    async fn try_write_parallel<'a>(
+        &'a mut self,
+        stream: &mut Pin<Box<dyn Stream<Item = anyhow::Result<Bytes>> + Send + 'a>>,
+    ) -> anyhow::Result<()> {
+        let first_part_number = self.parts.len() + 1;
+        let parts: Vec<_> = stream
+            .try_filter(|data| futures::future::ready(!data.is_empty()))
+            .enumerate()
+            .map(|(i, data)| {
+                let backend = self.backend.clone();
+                let path = self.path.clone();
+                let upload_id = self.upload_id.clone();
+                async move {
+                    let data = data?;
+                    anyhow::ensure!(data.len() <= MAX_PART_SIZE);
+                    let part_number = u16::try_from(first_part_number + i)?;
+                    anyhow::ensure!(part_number as usize <= MAX_NUM_PARTS);
+                    backend
+                        .upload_part(&path, &upload_id, part_number, data)
+                        .await
+                }
+            })
+            .buffered(MAXIMUM_PARALLEL_UPLOADS)
+            .try_collect()
+            .await?;
+        self.parts.extend(parts);
+        Ok(())
+    }
+
+    async fn abort(self: Box<Self>) -> anyhow::Result<()> {
+        self.backend
+            .abort_multipart_upload(&self.path, &self.upload_id)
+            .await
+    }
+
+    async fn complete(self: Box<Self>) -> anyhow::Result<ObjectKey> {
+        let BackendUpload {
+            backend,
+            path,
+            object_key,
+            upload_id,
+            parts,
+        } = *self;
+        // Not every object store accepts empty parts, so upload empty objects
+        // directly.
+        if parts.is_empty() {
+            backend.abort_multipart_upload(&path, &upload_id).await?;
+            backend.put_object(&path, Bytes::new()).await?;
+        } else {
+            backend
+                .complete_multipart_upload(&path, &upload_id, parts)
+                .await?;
+        }
+        Ok(object_key)
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, key);
+    hmac::sign(&key, data).as_ref().to_vec()
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(ring::digest::digest(&ring::digest::SHA256, data))
+}
+
+/// Percent-encodes each segment of an object key, keeping its slashes.
+fn encode_key(key: &str) -> String {
+    key.split('/')
+        .map(|segment| urlencoding::encode(segment).into_owned())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// Returns the response if it succeeded, or an error with its body otherwise.
+async fn check_response(
+    service: &str,
+    response: reqwest::Response,
+) -> anyhow::Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let body = response.text().await.unwrap_or_default();
+    anyhow::bail!("{service} request failed with {status}: {body}")
+}
+
+fn parse_content_length(response: &reqwest::Response) -> anyhow::Result<u64> {
+    let content_length = response
+        .headers()
+        .get(http::header::CONTENT_LENGTH)
+        .context("Missing Content-Length")?
+        .to_str()?
+        .parse()?;
+    Ok(content_length)
+}
+
+fn into_get_stream(response: reqwest::Response, content_length: u64) -> StorageGetStream {
+    StorageGetStream {
+        content_length: content_length as i64,
+        stream: response
+            .bytes_stream()
+            .map_err(|e| futures::io::Error::new(futures::io::ErrorKind::Other, e))
+            .boxed(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::BTreeMap,
+        ops::Range,
+        sync::Arc,
+        time::Duration,
+    };
+
+    use async_trait::async_trait;
+    use bytes::Bytes;
+    use common::runtime::testing::TestRuntime;
+    use futures::{
+        stream,
+        StreamExt,
+    };
+    use http::{
+        Method,
+        Uri,
+    };
+    use parking_lot::Mutex;
+
+    use super::{
+        BackendStorage,
+        StorageBackend,
+        UploadedPart,
+    };
+    use crate::{
+        ObjectAttributes,
+        Storage,
+        StorageExt,
+        StorageGetStream,
+        Upload,
+        UploadExt,
+        UploadId,
+    };
+
+    #[derive(Debug, Default)]
+    struct InMemoryBackend {
+        objects: Mutex<BTreeMap<String, Bytes>>,
+        parts: Mutex<BTreeMap<(String, u16), Bytes>>,
+    }
+
+    #[async_trait]
+    impl StorageBackend for InMemoryBackend {
+        fn bucket(&self) -> &str {
+            "bucket"
+        }
+
+        async fn put_object(&self, key: &str, body: Bytes) -> anyhow::Result<()> {
+            self.objects.lock().insert(key.to_owned(), body);
+            Ok(())
+        }
+
+        async fn start_multipart_upload(&self, key: &str) -> anyhow::Result<UploadId> {
+            Ok(key.to_owned().into())
+        }
+
+        async fn upload_part(
+            &self,
+            key: &str,
+            _upload_id: &UploadId,
+            part_number: u16,
+            body: Bytes,
+        ) -> anyhow::Result<UploadedPart> {
+            self.parts
+                .lock()
+                .insert((key.to_owned(), part_number), body);
+            Ok(UploadedPart {
+                part_number,
+                tag: format!("{key}-{part_number}"),
+            })
+        }
+
+        async fn complete_multipart_upload(
+            &self,
+            key: &str,
+            _upload_id: &UploadId,
+            parts: Vec<UploadedPart>,
+        ) -> anyhow::Result<()> {
+            let mut body = vec![];
+            for part in parts {
+                anyhow::ensure!(part.tag == format!("{key}-{}", part.part_number));
+                let data = self
+                    .parts
+                    .lock()
+                    .remove(&(key.to_owned(), part.part_number))
+                    .unwrap();
+                body.extend_from_slice(&data);
+            }
+            self.put_object(key, body.into()).await
+        }
+
+        async fn abort_multipart_upload(
+            &self,
+            key: &str,
+            _upload_id: &UploadId,
+        ) -> anyhow::Result<()> {
+            self.parts.lock().retain(|(k, _), _| k != key);
+            Ok(())
+        }
+
+        async fn get_range(
+            &self,
+            key: &str,
+            bytes_range: Range<u64>,
+        ) -> anyhow::Result<StorageGetStream> {
+            let body = self.objects.lock().get(key).unwrap().clone();
+            let data = body.slice(bytes_range.start as usize..bytes_range.end as usize);
+            Ok(StorageGetStream {
+                content_length: data.len() as i64,
+                stream: stream::once(async move { Ok(data) }).boxed(),
+            })
+        }
+
+        async fn head_object(&self, key: &str) -> anyhow::Result<Option<ObjectAttributes>> {
+            Ok(self.objects.lock().get(key).map(|body| ObjectAttributes {
+                size: body.len() as u64,
+            }))
+        }
+
+        async fn copy_object(
+            &self,
+            source_bucket: &str,
+            source_key: &str,
+            key: &str,
+        ) -> anyhow::Result<()> {
+            anyhow::ensure!(source_bucket == self.bucket());
+            let body = self.objects.lock().get(source_key).unwrap().clone();
+            self.put_object(key, body).await
+        }
+
+        async fn delete_object(&self, key: &str) -> anyhow::Result<()> {
+            self.objects.lock().remove(key);
+            Ok(())
+        }
+
+        fn presigned_url(
+            &self,
+            method: Method,
+            key: &str,
+            _expires_in: Duration,
+        ) -> anyhow::Result<Uri> {
+            Ok(format!("https://bucket.example.com/{key}?method={method}").parse()?)
+        }
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_upload_and_read(rt: TestRuntime) -> anyhow::Result<()> {
+        let backend = Arc::new(InMemoryBackend::default());
+        let storage: Arc<dyn Storage> = Arc::new(BackendStorage::new(
+            rt,
+            backend.clone(),
+            "prefix/files/".to_owned(),
+        ));
+
+        let mut upload = storage.start_upload().await?;
+        upload
+            .write_parallel(stream::iter(vec![
+                Bytes::from_static(b"hello "),
+                Bytes::from_static(b"world"),
+            ]))
+            .await?;
+        let key = upload.complete().await?;
+        let contents = storage.get(&key).await?.unwrap().collect_as_bytes().await?;
+        assert_eq!(contents, Bytes::from_static(b"hello world"));
+        let range = storage
+            .get_range(
+                &key,
+                (std::ops::Bound::Included(6), std::ops::Bound::Excluded(11)),
+            )
+            .await?
+            .unwrap()
+            .collect_as_bytes()
+            .await?;
+        assert_eq!(range, Bytes::from_static(b"world"));
+
+        let fully_qualified_key = storage.fully_qualified_key(&key);
+        assert_eq!(
+            String::from(fully_qualified_key.clone()),
+            format!("bucket/prefix/files/{}", &*key)
+        );
+        assert_eq!(
+            storage.test_only_decompose_fully_qualified_key(fully_qualified_key.clone())?,
+            key
+        );
+        let copied = storage.copy_object(fully_qualified_key).await?;
+        assert_eq!(
+            storage
+                .get_object_attributes(&copied)
+                .await?
+                .map(|attributes| attributes.size),
+            Some(11)
+        );
+
+        storage.delete_object(&key).await?;
+        assert!(storage.get_object_attributes(&key).await?.is_none());
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_empty_upload(rt: TestRuntime) -> anyhow::Result<()> {
+        let backend = Arc::new(InMemoryBackend::default());
+        let storage = BackendStorage::new(rt, backend.clone(), "files/".to_owned());
+        let upload = storage.start_upload().await?;
+        let key = upload.complete().await?;
+        assert_eq!(
+            backend.objects.lock().get(&format!("files/{}", &*key)),
+            Some(&Bytes::new())
+        );
+        Ok(())
+    }
+
+    #[convex_macro::test_runtime]
+    async fn test_client_driven_upload(rt: TestRuntime) -> anyhow::Result<()> {
+        let backend = Arc::new(InMemoryBackend::default());
+        let storage = BackendStorage::new(rt, backend.clone(), "imports/".to_owned());
+        let token = storage.start_client_driven_upload().await?;
+        let second = storage
+            .upload_part(token.clone(), 2, Bytes::from_static(b"world"))
+            .await?;
+        let first = storage
+            .upload_part(token.clone(), 1, Bytes::from_static(b"hello "))
+            .await?;
+        let key = storage
+            .finish_client_driven_upload(token, vec![second, first])
+            .await?;
+        assert_eq!(
+            backend.objects.lock().get(&format!("imports/{}", &*key)),
+            Some(&Bytes::from_static(b"hello world"))
+        );
+        Ok(())
+    }
+}
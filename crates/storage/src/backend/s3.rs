@@ -0,0 +1,490 @@
+//! [`StorageBackend`] for Amazon S3 and S3-compatible object stores, with
+//! requests signed using AWS Signature Version 4. Google Cloud Storage is
+//! supported through its S3-compatible XML API, authenticated with HMAC keys.
+use std::{
+    ops::Range,
+    time::Duration,
+};
+
+use anyhow::Context as _;
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{
+    DateTime,
+    Utc,
+};
+use common::runtime::Runtime;
+use http::{
+    Method,
+    StatusCode,
+    Uri,
+};
+use url::Url;
+
+use super::{
+    check_response,
+    encode_key,
+    hmac_sha256,
+    into_get_stream,
+    parse_content_length,
+    sha256_hex,
+    StorageBackend,
+    UploadedPart,
+};
+use crate::{
+    ObjectAttributes,
+    StorageGetStream,
+    UploadId,
+};
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+const SERVICE: &str = "s3";
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+/// S3 doesn't accept presigned URLs that last longer than a week.
+const MAX_PRESIGNED_URL_EXPIRATION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+const GCS_ENDPOINT: &str = "https://storage.googleapis.com";
+
+#[derive(Clone)]
+pub struct S3Credentials {
+    pub access_key_id: String,
+    pub secret_access_key: String,
+}
+
+#[derive(Clone)]
+pub struct S3Backend<RT: Runtime> {
+    rt: RT,
+    client: reqwest::Client,
+    bucket: String,
+    region: String,
+    /// Scheme, host and port of the endpoint requests are sent to.
+    origin: String,
+    host: String,
+    /// Path to the bucket on the endpoint, which is empty for virtual-hosted
+    /// style requests.
+    bucket_path: String,
+    credentials: S3Credentials,
+}
+
+impl<RT: Runtime> std::fmt::Debug for S3Backend<RT> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("S3Backend")
+            .field("bucket", &self.bucket)
+            .field("region", &self.region)
+            .field("origin", &self.origin)
+            .finish()
+    }
+}
+
+impl<RT: Runtime> S3Backend<RT> {
+    /// A backend for `bucket` in AWS, or at `endpoint_url` for other
+    /// S3-compatible object stores like MinIO or Cloudflare R2. Custom
+    /// endpoints use path-style requests.
+    pub fn new(
+        rt: RT,
+        bucket: String,
+        region: String,
+        endpoint_url: Option<Url>,
+        credentials: S3Credentials,
+    ) -> anyhow::Result<Self> {
+        let (endpoint_url, bucket_path) = match endpoint_url {
+            None => (
+                format!("https://{bucket}.s3.{region}.amazonaws.com").parse()?,
+                String::new(),
+            ),
+            Some(endpoint_url) => {
+                let bucket_path = format!(
+                    "{}/{}",
+                    endpoint_url.path().trim_end_matches('/'),
+                    encode_key(&bucket)
+                );
+                (endpoint_url, bucket_path)
+            },
+        };
+        let host = endpoint_host(&endpoint_url)?;
+        Ok(Self {
+            rt,
+            client: reqwest::Client::new(),
+            bucket,
+            region,
+            origin: format!("{}://{host}", endpoint_url.scheme()),
+            host,
+            bucket_path,
+            credentials,
+        })
+    }
+
+    /// A backend for a Google Cloud Storage bucket, using its XML API with
+    /// HMAC keys for a service account.
+    pub fn new_gcs(rt: RT, bucket: String, credentials: S3Credentials) -> anyhow::Result<Self> {
+        Self::new(
+            rt,
+            bucket,
+            "auto".to_owned(),
+            Some(GCS_ENDPOINT.parse()?),
+            credentials,
+        )
+    }
+
+    fn canonical_uri(&self, key: &str) -> String {
+        format!("{}/{}", self.bucket_path, encode_key(key))
+    }
+
+    fn credential_scope(&self, now: &DateTime<Utc>) -> String {
+        format!(
+            "{}/{}/{SERVICE}/aws4_request",
+            now.format("%Y%m%d"),
+            self.region
+        )
+    }
+
+    fn signature(&self, now: &DateTime<Utc>, canonical_request: &str) -> String {
+        let string_to_sign = format!(
+            "{ALGORITHM}\n{}\n{}\n{}",
+            amz_date(now),
+            self.credential_scope(now),
+            sha256_hex(canonical_request.as_bytes())
+        );
+        let mut key = hmac_sha256(
+            format!("AWS4{}", self.credentials.secret_access_key).as_bytes(),
+            now.format("%Y%m%d").to_string().as_bytes(),
+        );
+        for part in [self.region.as_str(), SERVICE, "aws4_request"] {
+            key = hmac_sha256(&key, part.as_bytes());
+        }
+        hex::encode(hmac_sha256(&key, string_to_sign.as_bytes()))
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        key: &str,
+        query: &[(&str, &str)],
+        headers: Vec<(&str, String)>,
+        body: Bytes,
+    ) -> anyhow::Result<reqwest::Response> {
+        let response = self
+            .send_unchecked(method, key, query, headers, body)
+            .await?;
+        check_response("S3", response).await
+    }
+
+    /// Sends a request signed with the `Authorization` header, without
+    /// checking its status. All of `headers` are signed, so their names must
+    /// be lowercase.
+    async fn send_unchecked(
+        &self,
+        method: Method,
+        key: &str,
+        query: &[(&str, &str)],
+        headers: Vec<(&str, String)>,
+        body: Bytes,
+    ) -> anyhow::Result<reqwest::Response> {
+        let now = DateTime::<Utc>::from(self.rt.system_time());
+        let payload_hash = sha256_hex(&body);
+        let mut headers = headers;
+        headers.push(("host", self.host.clone()));
+        headers.push(("x-amz-content-sha256", payload_hash.clone()));
+        headers.push(("x-amz-date", amz_date(&now)));
+        headers.sort();
+        let canonical_uri = self.canonical_uri(key);
+        let canonical_query = canonical_query_string(query);
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(name, value)| format!("{name}:{}\n", value.trim()))
+            .collect();
+        let signed_headers = headers
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(";");
+        let canonical_request = [
+            method.as_str(),
+            &canonical_uri,
+            &canonical_query,
+            &canonical_headers,
+            &signed_headers,
+            &payload_hash,
+        ]
+        .join("\n");
+        let authorization = format!(
+            "{ALGORITHM} Credential={}/{}, SignedHeaders={signed_headers}, Signature={}",
+            self.credentials.access_key_id,
+            self.credential_scope(&now),
+            self.signature(&now, &canonical_request)
+        );
+        let mut url = format!("{}{canonical_uri}", self.origin);
+        if !canonical_query.is_empty() {
+            url = format!("{url}?{canonical_query}");
+        }
+        let mut request = self
+            .client
+            .request(method, url)
+            .header(http::header::AUTHORIZATION, authorization);
+        for (name, value) in headers {
+            // reqwest sets the host from the URL.
+            if name != "host" {
+                request = request.header(name, value);
+            }
+        }
+        Ok(request.body(body).send().await?)
+    }
+}
+
+#[async_trait]
+impl<RT: Runtime> StorageBackend for S3Backend<RT> {
+    fn bucket(&self) -> &str {
+        &self.bucket
+    }
+
+    async fn put_object(&self, key: &str, body: Bytes) -> anyhow::Result<()> {
+        self.send(Method::PUT, key, &[], vec![], body).await?;
+        Ok(())
+    }
+
+    async fn start_multipart_upload(&self, key: &str) -> anyhow::Result<UploadId> {
+        let response = self
+            .send(Method::POST, key, &[("uploads", "")], vec![], Bytes::new())
+            .await?;
+        let body = response.text().await?;
+        let upload_id = xml_element(&body, "UploadId")
+            .with_context(|| format!("Missing UploadId in {body}"))?;
+        Ok(upload_id.to_owned().into())
+    }
+
+    async fn upload_part(
+        &self,
+        key: &str,
+        upload_id: &UploadId,
+        part_number: u16,
+        body: Bytes,
+    ) -> anyhow::Result<UploadedPart> {
+        let part_number_str = part_number.to_string();
+        let upload_id = upload_id.to_string();
+        let response = self
+            .send(
+                Method::PUT,
+                key,
+                &[("partNumber", &part_number_str), ("uploadId", &upload_id)],
+                vec![],
+                body,
+            )
+            .await?;
+        let etag = response
+            .headers()
+            .get(http::header::ETAG)
+            .context("Missing ETag for uploaded part")?
+            .to_str()?
+            .to_owned();
+        Ok(UploadedPart {
+            part_number,
+            tag: etag,
+        })
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        key: &str,
+        upload_id: &UploadId,
+        parts: Vec<UploadedPart>,
+    ) -> anyhow::Result<()> {
+        let parts: String = parts
+            .into_iter()
+            .map(|part| {
+                format!(
+                    "<Part><PartNumber>{}</PartNumber><ETag>{}</ETag></Part>",
+                    part.part_number, part.tag
+                )
+            })
+            .collect();
+        let body = format!("<CompleteMultipartUpload>{parts}</CompleteMultipartUpload>");
+        let upload_id = upload_id.to_string();
+        let response = self
+            .send(
+                Method::POST,
+                key,
+                &[("uploadId", &upload_id)],
+                vec![],
+                body.into(),
+            )
+            .await?;
+        check_xml_error(response).await
+    }
+
+    async fn abort_multipart_upload(&self, key: &str, upload_id: &UploadId) -> anyhow::Result<()> {
+        let upload_id = upload_id.to_string();
+        self.send(
+            Method::DELETE,
+            key,
+            &[("uploadId", &upload_id)],
+            vec![],
+            Bytes::new(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn get_range(
+        &self,
+        key: &str,
+        bytes_range: Range<u64>,
+    ) -> anyhow::Result<StorageGetStream> {
+        let range = format!("bytes={}-{}", bytes_range.start, bytes_range.end - 1);
+        let response = self
+            .send(Method::GET, key, &[], vec![("range", range)], Bytes::new())
+            .await?;
+        Ok(into_get_stream(
+            response,
+            bytes_range.end - bytes_range.start,
+        ))
+    }
+
+    async fn head_object(&self, key: &str) -> anyhow::Result<Option<ObjectAttributes>> {
+        let response = self
+            .send_unchecked(Method::HEAD, key, &[], vec![], Bytes::new())
+            .await?;
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let response = check_response("S3", response).await?;
+        Ok(Some(ObjectAttributes {
+            size: parse_content_length(&response)?,
+        }))
+    }
+
+    async fn copy_object(
+        &self,
+        source_bucket: &str,
+        source_key: &str,
+        key: &str,
+    ) -> anyhow::Result<()> {
+        let copy_source = format!("{}/{}", encode_key(source_bucket), encode_key(source_key));
+        let response = self
+            .send(
+                Method::PUT,
+                key,
+                &[],
+                vec![("x-amz-copy-source", copy_source)],
+                Bytes::new(),
+            )
+            .await?;
+        check_xml_error(response).await
+    }
+
+    async fn delete_object(&self, key: &str) -> anyhow::Result<()> {
+        self.send(Method::DELETE, key, &[], vec![], Bytes::new())
+            .await?;
+        Ok(())
+    }
+
+    fn presigned_url(
+        &self,
+        method: Method,
+        key: &str,
+        expires_in: Duration,
+    ) -> anyhow::Result<Uri> {
+        anyhow::ensure!(
+            expires_in <= MAX_PRESIGNED_URL_EXPIRATION,
+            "S3 presigned URLs can't last longer than {MAX_PRESIGNED_URL_EXPIRATION:?}"
+        );
+        let now = DateTime::<Utc>::from(self.rt.system_time());
+        let credential = format!(
+            "{}/{}",
+            self.credentials.access_key_id,
+            self.credential_scope(&now)
+        );
+        let date = amz_date(&now);
+        let expires = expires_in.as_secs().to_string();
+        let canonical_uri = self.canonical_uri(key);
+        let canonical_query = canonical_query_string(&[
+            ("X-Amz-Algorithm", ALGORITHM),
+            ("X-Amz-Credential", &credential),
+            ("X-Amz-Date", &date),
+            ("X-Amz-Expires", &expires),
+            ("X-Amz-SignedHeaders", "host"),
+        ]);
+        let canonical_request = format!(
+            "{method}\n{canonical_uri}\n{canonical_query}\nhost:{}\n\nhost\n{UNSIGNED_PAYLOAD}",
+            self.host
+        );
+        let signature = self.signature(&now, &canonical_request);
+        let uri = format!(
+            "{}{canonical_uri}?{canonical_query}&X-Amz-Signature={signature}",
+            self.origin
+        )
+        .parse()?;
+        Ok(uri)
+    }
+}
+
+fn amz_date(now: &DateTime<Utc>) -> String {
+    now.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+/// The host and, if it isn't the scheme's default, port of `url`.
+fn endpoint_host(url: &Url) -> anyhow::Result<String> {
+    let host = url
+        .host_str()
+        .with_context(|| format!("Endpoint {url} has no host"))?;
+    Ok(match url.port() {
+        Some(port) => format!("{host}:{port}"),
+        None => host.to_owned(),
+    })
+}
+
+/// Percent-encodes and sorts query parameters as SigV4 expects.
+fn canonical_query_string(query: &[(&str, &str)]) -> String {
+    let mut params: Vec<_> = query
+        .iter()
+        .map(|(name, value)| (urlencoding::encode(name), urlencoding::encode(value)))
+        .collect();
+    params.sort();
+    params
+        .into_iter()
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// The text of the first `<element>` in an XML response.
+fn xml_element<'a>(xml: &'a str, element: &str) -> Option<&'a str> {
+    let start_tag = format!("<{element}>");
+    let end_tag = format!("</{element}>");
+    let start = xml.find(&start_tag)? + start_tag.len();
+    let end = start + xml[start..].find(&end_tag)?;
+    Some(&xml[start..end])
+}
+
+/// Some S3 operations return 200 OK with an error in the body.
+async fn check_xml_error(response: reqwest::Response) -> anyhow::Result<()> {
+    let body = response.text().await?;
+    if body.contains("<Error>") {
+        let code = xml_element(&body, "Code").unwrap_or_default();
+        anyhow::bail!("S3 request failed with {code}: {body}");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        canonical_query_string,
+        xml_element,
+    };
+
+    #[test]
+    fn test_canonical_query_string() {
+        assert_eq!(
+            canonical_query_string(&[("uploadId", "a/b="), ("partNumber", "2")]),
+            "partNumber=2&uploadId=a%2Fb%3D"
+        );
+        assert_eq!(canonical_query_string(&[("uploads", "")]), "uploads=");
+    }
+
+    #[test]
+    fn test_xml_element() {
+        let body = "<InitiateMultipartUploadResult><Bucket>b</Bucket><UploadId>abc</UploadId></\
+                    InitiateMultipartUploadResult>";
+        assert_eq!(xml_element(body, "UploadId"), Some("abc"));
+        assert_eq!(xml_element(body, "Key"), None);
+    }
+}
@@ -83,6 +83,8 @@ use value::sha256::{
     Sha256Digest,
 };
 
+pub mod backend;
+
 pub const LOCAL_DIR_MIN_PART_SIZE: usize = 5 * (1 << 20);
 pub const MAX_PART_SIZE: usize = 8 * (1 << 30);
 pub const MAX_NUM_PARTS: usize = 10000;
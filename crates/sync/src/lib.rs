@@ -5,9 +5,20 @@
 #![feature(try_blocks)]
 
 mod metrics;
+mod outgoing;
+mod serialize;
+mod server_args;
+mod sessions;
 mod state;
 pub mod worker;
 
+pub use outgoing::{
+    outgoing_channel,
+    OutgoingReceiver,
+    OutgoingSender,
+};
+pub use serialize::serialize_server_message;
+pub use sessions::RetainedSessions;
 pub use worker::{
     SyncWorker,
     SyncWorkerConfig,
@@ -16,4 +27,6 @@ pub use worker::{
 #[cfg(test)]
 mod tests;
 
-pub type ServerMessage = sync_types::ServerMessage<common::value::ConvexValue>;
+/// Values stay serialized from the query cache to the websocket, so every
+/// subscriber to the same cached result shares a single payload.
+pub type ServerMessage = sync_types::ServerMessage<isolate::JsonPackedValue>;
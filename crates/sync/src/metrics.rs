@@ -43,6 +43,7 @@ pub fn handle_message_timer(message: &ClientMessage) -> StatusTimer {
         ClientMessage::ModifyQuerySet { .. } => "ModifyQuerySet",
         ClientMessage::Mutation { .. } => "Mutation",
         ClientMessage::Event { .. } => "Event",
+        ClientMessage::ModifyAggregateSet { .. } => "ModifyAggregateSet",
     };
     timer.add_label(StaticMetricLabel::new("endpoint", request_name.to_owned()));
     timer
@@ -85,11 +86,51 @@ pub fn log_query_result_dedup(same_value: bool) {
     log_counter(&SYNC_QUERY_RESULT_DEDUP_TOTAL, sample);
 }
 
+register_convex_counter!(
+    SYNC_QUERY_RESULT_PATCHED_TOTAL,
+    "Number of query results sent as patches to the previous result"
+);
+register_convex_counter!(
+    SYNC_QUERY_RESULT_PATCH_BYTES_SAVED_TOTAL,
+    "Bytes saved by sending query results as patches"
+);
+pub fn log_query_result_patched(value_size: usize, patch_size: usize) {
+    log_counter(&SYNC_QUERY_RESULT_PATCHED_TOTAL, 1);
+    log_counter(
+        &SYNC_QUERY_RESULT_PATCH_BYTES_SAVED_TOTAL,
+        value_size.saturating_sub(patch_size) as u64,
+    );
+}
+
 register_convex_counter!(SYNC_EMPTY_TRANSITION_TOTAL, "Number of empty transitions");
 pub fn log_empty_transition() {
     log_counter(&SYNC_EMPTY_TRANSITION_TOTAL, 1);
 }
 
+register_convex_counter!(
+    SYNC_COALESCED_INVALIDATION_TOTAL,
+    "Number of query invalidations delayed to coalesce with later commits"
+);
+pub fn log_coalesced_invalidation() {
+    log_counter(&SYNC_COALESCED_INVALIDATION_TOTAL, 1);
+}
+
+register_convex_counter!(
+    SYNC_COALESCED_TRANSITION_TOTAL,
+    "Number of transitions merged into an earlier one a slow client hadn't been sent yet"
+);
+pub fn log_coalesced_transition() {
+    log_counter(&SYNC_COALESCED_TRANSITION_TOTAL, 1);
+}
+
+register_convex_counter!(
+    SYNC_SEND_BUFFER_FULL_TOTAL,
+    "Number of connections closed because the client fell too far behind"
+);
+pub fn log_send_buffer_full() {
+    log_counter(&SYNC_SEND_BUFFER_FULL_TOTAL, 1);
+}
+
 register_convex_counter!(
     SYNC_CONNECT_TOTAL,
     "Number of new WS connections",
@@ -105,6 +146,16 @@ pub fn log_connect(last_close_reason: String, connection_count: u32) {
     log_distribution(&SYNC_RECONNECT_PREV_CONNECTIONS, connection_count.into());
 }
 
+register_convex_counter!(
+    SYNC_SESSION_RESUME_TOTAL,
+    "Number of reconnects that tried to resume a sync session",
+    &["resumed"]
+);
+pub fn log_session_resume(resumed: bool) {
+    let labels = vec![StaticMetricLabel::new("resumed", resumed.to_string())];
+    log_counter_with_labels(&SYNC_SESSION_RESUME_TOTAL, 1, labels);
+}
+
 register_convex_histogram!(
     SYNC_LINEARIZABILITY_DELAY_SECONDS,
     "How far behind the current backend is behind what the client has observed",
@@ -0,0 +1,373 @@
+//! The buffer between a sync worker and its websocket, holding the messages
+//! the worker has produced but the client hasn't been sent yet.
+//!
+//! Replies to the client's requests go out ahead of query updates, so a slow
+//! client still hears back about its mutations promptly. Transitions for a
+//! client that isn't keeping up are coalesced rather than queued, and the
+//! worker stops computing new ones until the client catches up.
+
+use std::{
+    collections::{
+        BTreeMap,
+        VecDeque,
+    },
+    mem,
+    sync::Arc,
+};
+
+use common::{
+    heap_size::HeapSize,
+    knobs::{
+        SYNC_MAX_SEND_TRANSITION_COUNT,
+        SYNC_MAX_SEND_TRANSITION_SIZE,
+    },
+    value::ConvexValue,
+};
+use isolate::JsonPackedValue;
+use parking_lot::Mutex;
+use sync_types::{
+    patch::apply_patch,
+    LogLinesMessage,
+    QueryId,
+    StateModification,
+};
+use tokio::{
+    sync::{
+        mpsc::error::SendError,
+        Notify,
+    },
+    time::Instant,
+};
+
+use crate::{
+    metrics,
+    ServerMessage,
+};
+
+/// Creates a channel from a sync worker to its websocket that prioritizes
+/// and coalesces messages, and lets the worker hold off on computing updates
+/// while the client is backlogged.
+pub fn outgoing_channel() -> (OutgoingSender, OutgoingReceiver) {
+    let shared = Arc::new(Shared {
+        buffer: Mutex::new(Buffer::default()),
+        message_available: Notify::new(),
+        message_consumed: Notify::new(),
+    });
+    (
+        OutgoingSender {
+            shared: shared.clone(),
+        },
+        OutgoingReceiver { shared },
+    )
+}
+
+struct Shared {
+    buffer: Mutex<Buffer>,
+    message_available: Notify,
+    message_consumed: Notify,
+}
+
+#[derive(Default)]
+struct Buffer {
+    /// Replies to the client's requests and other control messages.
+    responses: VecDeque<Buffered>,
+    /// Aggregate updates, which are small, so they don't wait behind
+    /// transitions.
+    updates: VecDeque<Buffered>,
+    /// Transitions have to be applied in order, so they're sent in order.
+    transitions: VecDeque<Buffered>,
+    /// The total heap size of the buffered messages.
+    size: usize,
+    transitions_size: usize,
+    sender_closed: bool,
+    receiver_closed: bool,
+}
+
+struct Buffered {
+    message: ServerMessage,
+    send_time: Instant,
+    size: usize,
+    /// How many transitions were coalesced into this one.
+    count: usize,
+}
+
+impl Buffer {
+    fn push(&mut self, message: ServerMessage, send_time: Instant) {
+        let message = match message {
+            ServerMessage::Transition { .. } => match self.coalesce_transition(message) {
+                Ok(()) => return,
+                Err(message) => message,
+            },
+            message => message,
+        };
+        let size = message.heap_size();
+        let queue = match &message {
+            ServerMessage::Transition { .. } => {
+                self.transitions_size += size;
+                &mut self.transitions
+            },
+            ServerMessage::AggregateUpdated { .. } | ServerMessage::AggregateFailed { .. } => {
+                &mut self.updates
+            },
+            ServerMessage::MutationResponse { .. }
+            | ServerMessage::ActionResponse { .. }
+            | ServerMessage::AuthError { .. }
+            | ServerMessage::FatalError { .. }
+            | ServerMessage::Ping
+            | ServerMessage::Connected { .. } => &mut self.responses,
+        };
+        queue.push_back(Buffered {
+            message,
+            send_time,
+            size,
+            count: 1,
+        });
+        self.size += size;
+    }
+
+    /// Merges `message` into the last buffered transition, if there is one,
+    /// so the client skips the intermediate version. Returns `message` back
+    /// if it wasn't merged.
+    fn coalesce_transition(&mut self, message: ServerMessage) -> Result<(), ServerMessage> {
+        let Some(pending) = self.transitions.back_mut() else {
+            return Err(message);
+        };
+        coalesce_transitions(&mut pending.message, message)?;
+        let size = pending.message.heap_size();
+        self.size = self.size - pending.size + size;
+        self.transitions_size = self.transitions_size - pending.size + size;
+        pending.size = size;
+        pending.count += 1;
+        metrics::log_coalesced_transition();
+        Ok(())
+    }
+
+    fn pop(&mut self) -> Option<(ServerMessage, Instant)> {
+        let buffered = match self.responses.pop_front() {
+            Some(buffered) => buffered,
+            None => match self.updates.pop_front() {
+                Some(buffered) => buffered,
+                None => {
+                    let buffered = self.transitions.pop_front()?;
+                    self.transitions_size -= buffered.size;
+                    buffered
+                },
+            },
+        };
+        self.size -= buffered.size;
+        Some((buffered.message, buffered.send_time))
+    }
+
+    fn transition_count(&self) -> usize {
+        self.transitions.iter().map(|buffered| buffered.count).sum()
+    }
+}
+
+/// Merges `next` into `pending`, the transition before it, so that `pending`
+/// goes from its own start version straight to `next`'s end version. Returns
+/// `next` back, leaving `pending` as it was, if they can't be merged.
+fn coalesce_transitions(
+    pending: &mut ServerMessage,
+    next: ServerMessage,
+) -> Result<(), ServerMessage> {
+    let (
+        ServerMessage::Transition {
+            end_version,
+            modifications,
+            ..
+        },
+        ServerMessage::Transition {
+            start_version: next_start_version,
+            end_version: next_end_version,
+            modifications: next_modifications,
+        },
+    ) = (&mut *pending, &next)
+    else {
+        return Err(next);
+    };
+    if *end_version != *next_start_version {
+        return Err(next);
+    }
+    let mut merged: BTreeMap<QueryId, StateModification<JsonPackedValue>> = modifications
+        .iter()
+        .map(|modification| (modification_query_id(modification), modification.clone()))
+        .collect();
+    for modification in next_modifications {
+        let query_id = modification_query_id(modification);
+        let modification = match merged.remove(&query_id) {
+            Some(previous) => match coalesce_modifications(previous, modification.clone()) {
+                Ok(modification) => modification,
+                Err(_) => return Err(next),
+            },
+            None => modification.clone(),
+        };
+        merged.insert(query_id, modification);
+    }
+    *end_version = *next_end_version;
+    *modifications = merged.into_values().collect();
+    Ok(())
+}
+
+/// Combines two consecutive modifications of the same query into one. Log
+/// lines from both are kept.
+fn coalesce_modifications(
+    mut previous: StateModification<JsonPackedValue>,
+    next: StateModification<JsonPackedValue>,
+) -> anyhow::Result<StateModification<JsonPackedValue>> {
+    let mut log_lines = log_lines_mut(&mut previous)
+        .map(|log_lines| mem::take(&mut log_lines.0))
+        .unwrap_or_default();
+    let mut merged = match (previous, next) {
+        (
+            StateModification::QueryUpdated { value, .. },
+            StateModification::QueryPatched {
+                query_id,
+                patch,
+                log_lines,
+                journal,
+            },
+        ) => {
+            let mut value = value.json_value();
+            apply_patch(&mut value, &patch)?;
+            StateModification::QueryUpdated {
+                query_id,
+                value: JsonPackedValue::pack(ConvexValue::try_from(value)?),
+                log_lines,
+                journal,
+            }
+        },
+        (
+            StateModification::QueryPatched {
+                patch: mut previous_patch,
+                ..
+            },
+            StateModification::QueryPatched {
+                query_id,
+                patch,
+                log_lines,
+                journal,
+            },
+        ) => {
+            previous_patch.extend(patch);
+            StateModification::QueryPatched {
+                query_id,
+                patch: previous_patch,
+                log_lines,
+                journal,
+            }
+        },
+        (_, StateModification::QueryPatched { query_id, .. }) => {
+            anyhow::bail!("Query {query_id:?} was patched without a previous result")
+        },
+        (_, next) => next,
+    };
+    if let Some(merged_log_lines) = log_lines_mut(&mut merged) {
+        log_lines.append(&mut merged_log_lines.0);
+        merged_log_lines.0 = log_lines;
+    }
+    Ok(merged)
+}
+
+fn log_lines_mut(
+    modification: &mut StateModification<JsonPackedValue>,
+) -> Option<&mut LogLinesMessage> {
+    match modification {
+        StateModification::QueryUpdated { log_lines, .. }
+        | StateModification::QueryPatched { log_lines, .. }
+        | StateModification::QueryFailed { log_lines, .. } => Some(log_lines),
+        StateModification::QueryRemoved { .. } => None,
+    }
+}
+
+fn modification_query_id(modification: &StateModification<JsonPackedValue>) -> QueryId {
+    match modification {
+        StateModification::QueryUpdated { query_id, .. }
+        | StateModification::QueryPatched { query_id, .. }
+        | StateModification::QueryFailed { query_id, .. }
+        | StateModification::QueryRemoved { query_id } => *query_id,
+    }
+}
+
+pub struct OutgoingSender {
+    shared: Arc<Shared>,
+}
+
+impl OutgoingSender {
+    pub fn send(
+        &mut self,
+        msg: (ServerMessage, Instant),
+    ) -> Result<(), SendError<(ServerMessage, Instant)>> {
+        {
+            let mut buffer = self.shared.buffer.lock();
+            if buffer.receiver_closed {
+                return Err(SendError(msg));
+            }
+            buffer.push(msg.0, msg.1);
+        }
+        self.shared.message_available.notify_one();
+        Ok(())
+    }
+
+    /// Whether the client has fallen far enough behind on transitions that
+    /// the worker should wait before computing another one. Whatever changes
+    /// in the meantime goes out together in the next transition.
+    pub fn is_backlogged(&self) -> bool {
+        let buffer = self.shared.buffer.lock();
+        buffer.transition_count() >= *SYNC_MAX_SEND_TRANSITION_COUNT
+            || buffer.transitions_size >= *SYNC_MAX_SEND_TRANSITION_SIZE
+    }
+
+    /// The total heap size of the messages the client hasn't been sent yet.
+    pub fn buffer_size(&self) -> usize {
+        self.shared.buffer.lock().size
+    }
+
+    // Waits until a message has been taken from the buffer. Note that if
+    // multiple messages are taken between calls, this will fire only once.
+    pub async fn message_consumed(&mut self) {
+        self.shared.message_consumed.notified().await;
+    }
+}
+
+impl Drop for OutgoingSender {
+    fn drop(&mut self) {
+        self.shared.buffer.lock().sender_closed = true;
+        self.shared.message_available.notify_one();
+    }
+}
+
+pub struct OutgoingReceiver {
+    shared: Arc<Shared>,
+}
+
+impl OutgoingReceiver {
+    /// Returns the next message to send to the client, or `None` once the
+    /// sender is dropped and the buffer is empty.
+    pub async fn next(&mut self) -> Option<(ServerMessage, Instant)> {
+        loop {
+            {
+                let mut buffer = self.shared.buffer.lock();
+                if let Some(message) = buffer.pop() {
+                    drop(buffer);
+                    self.shared.message_consumed.notify_one();
+                    return Some(message);
+                }
+                if buffer.sender_closed {
+                    return None;
+                }
+            }
+            self.shared.message_available.notified().await;
+        }
+    }
+}
+
+impl Drop for OutgoingReceiver {
+    fn drop(&mut self) {
+        // Nothing buffered will be sent, so free it right away.
+        *self.shared.buffer.lock() = Buffer {
+            receiver_closed: true,
+            ..Buffer::default()
+        };
+        self.shared.message_consumed.notify_one();
+    }
+}
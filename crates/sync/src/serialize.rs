@@ -0,0 +1,79 @@
+use isolate::JsonPackedValue;
+use serde_json::Value as JsonValue;
+use sync_types::StateModification;
+
+use crate::ServerMessage;
+
+/// Serializes a message to send over the websocket.
+///
+/// Query results in a transition are copied in as they were serialized when
+/// the query ran, rather than serialized again for each subscriber. A hot
+/// query's result is computed once by the query cache and shared by every
+/// subscriber that reads it, so each client only serializes its own envelope:
+/// versions, query ids, log lines, and journals.
+pub fn serialize_server_message(message: ServerMessage) -> anyhow::Result<String> {
+    let ServerMessage::Transition {
+        start_version,
+        end_version,
+        modifications,
+    } = message
+    else {
+        return Ok(serde_json::to_string(&JsonValue::from(message))?);
+    };
+    let modifications = modifications
+        .into_iter()
+        .map(serialize_modification)
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(format!(
+        r#"{{"type":"Transition","startVersion":{},"endVersion":{},"modifications":[{}]}}"#,
+        serde_json::to_string(&JsonValue::from(start_version))?,
+        serde_json::to_string(&JsonValue::from(end_version))?,
+        modifications.join(","),
+    ))
+}
+
+fn serialize_modification(
+    modification: StateModification<JsonPackedValue>,
+) -> anyhow::Result<String> {
+    let StateModification::QueryUpdated {
+        query_id,
+        value,
+        log_lines,
+        journal,
+    } = modification
+    else {
+        return Ok(serde_json::to_string(&JsonValue::from(modification))?);
+    };
+    Ok(format!(
+        r#"{{"type":"QueryUpdated","queryId":{},"value":{},"logLines":{},"journal":{}}}"#,
+        serde_json::to_string(&query_id)?,
+        value.as_str(),
+        serde_json::to_string(&log_lines)?,
+        serde_json::to_string(&journal)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use cmd_util::env::env_config;
+    use proptest::prelude::*;
+    use serde_json::Value as JsonValue;
+
+    use super::serialize_server_message;
+    use crate::ServerMessage;
+
+    proptest! {
+        #![proptest_config(
+            ProptestConfig { cases: 256 * env_config("CONVEX_PROPTEST_MULTIPLIER", 1), failure_persistence: None, ..ProptestConfig::default() }
+        )]
+
+        #[test]
+        fn test_serialize_matches_json(message in any::<ServerMessage>()) {
+            let serialized = serialize_server_message(message.clone()).unwrap();
+            assert_eq!(
+                serde_json::from_str::<JsonValue>(&serialized).unwrap(),
+                JsonValue::from(message),
+            );
+        }
+    }
+}
@@ -0,0 +1,166 @@
+//! Query arguments filled in by the server, so clients can subscribe to
+//! queries like "my messages" without passing (and re-subscribing with) the
+//! current user's ID.
+//!
+//! A query's `server_args` maps top-level argument names to the server values
+//! below. Values are resolved from the session's identity each time the query
+//! runs, and are `null` when no user is logged in.
+use std::{
+    collections::BTreeMap,
+    str::FromStr,
+};
+
+use errors::ErrorMetadata;
+use keybroker::Identity;
+use serde_json::Value as JsonValue;
+use sync_types::UserIdentityAttributes;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ServerValue {
+    IdentitySubject,
+    IdentityTokenIdentifier,
+    IdentityIssuer,
+    IdentityEmail,
+    IdentityName,
+}
+
+impl FromStr for ServerValue {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let value = match s {
+            "identity.subject" => Self::IdentitySubject,
+            "identity.tokenIdentifier" => Self::IdentityTokenIdentifier,
+            "identity.issuer" => Self::IdentityIssuer,
+            "identity.email" => Self::IdentityEmail,
+            "identity.name" => Self::IdentityName,
+            _ => anyhow::bail!(ErrorMetadata::bad_request(
+                "InvalidServerArg",
+                format!("{s} isn't a server value that can be used as a query argument"),
+            )),
+        };
+        Ok(value)
+    }
+}
+
+impl ServerValue {
+    fn resolve(self, attributes: Option<&UserIdentityAttributes>) -> JsonValue {
+        let Some(attributes) = attributes else {
+            return JsonValue::Null;
+        };
+        let value = match self {
+            Self::IdentitySubject => attributes.subject.clone(),
+            Self::IdentityTokenIdentifier => Some(attributes.token_identifier.0.clone()),
+            Self::IdentityIssuer => attributes.issuer.clone(),
+            Self::IdentityEmail => attributes.email.clone(),
+            Self::IdentityName => attributes.name.clone(),
+        };
+        value.map_or(JsonValue::Null, JsonValue::String)
+    }
+}
+
+/// A query's parsed `server_args`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ServerArgs(BTreeMap<String, ServerValue>);
+
+impl ServerArgs {
+    pub fn parse(server_args: Option<&BTreeMap<String, String>>) -> anyhow::Result<Self> {
+        let Some(server_args) = server_args else {
+            return Ok(Self::default());
+        };
+        let parsed = server_args
+            .iter()
+            .map(|(name, value)| anyhow::Ok((name.clone(), value.parse()?)))
+            .collect::<anyhow::Result<_>>()?;
+        Ok(Self(parsed))
+    }
+
+    /// Checks that the client's arguments leave room for the server values.
+    /// Clients can't pass an argument the server fills in, so they can't
+    /// impersonate another user.
+    pub fn check_args(&self, args: &[JsonValue]) -> anyhow::Result<()> {
+        if self.0.is_empty() {
+            return Ok(());
+        }
+        let invalid_args = |msg: String| ErrorMetadata::bad_request("InvalidServerArg", msg);
+        let [JsonValue::Object(object)] = args else {
+            anyhow::bail!(invalid_args(
+                "Queries with server arguments must take a single object argument".to_string()
+            ));
+        };
+        if let Some(name) = self.0.keys().find(|name| object.contains_key(*name)) {
+            anyhow::bail!(invalid_args(format!(
+                "Argument {name} is filled in by the server and can't be passed by the client"
+            )));
+        }
+        Ok(())
+    }
+
+    /// Adds the server values for `identity` to the query's arguments.
+    pub fn resolve(
+        &self,
+        mut args: Vec<JsonValue>,
+        identity: &Identity,
+    ) -> anyhow::Result<Vec<JsonValue>> {
+        self.check_args(&args)?;
+        let [JsonValue::Object(object)] = &mut args[..] else {
+            return Ok(args);
+        };
+        let attributes = match identity {
+            Identity::User(user) => Some(&user.attributes),
+            Identity::ActingUser(_, attributes) => Some(attributes),
+            _ => None,
+        };
+        for (name, value) in &self.0 {
+            object.insert(name.clone(), value.resolve(attributes));
+        }
+        Ok(args)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{
+        Duration,
+        SystemTime,
+    };
+
+    use errors::ErrorMetadataAnyhowExt;
+    use keybroker::{
+        Identity,
+        UserIdentity,
+    };
+    use maplit::btreemap;
+    use serde_json::json;
+
+    use super::ServerArgs;
+
+    #[test]
+    fn test_resolve_server_args() -> anyhow::Result<()> {
+        let server_args = ServerArgs::parse(Some(&btreemap! {
+            "userId".to_string() => "identity.subject".to_string(),
+        }))?;
+        let identity = Identity::user(UserIdentity::from_session(
+            "https://example.convex.cloud".to_string(),
+            "user1".to_string(),
+            "ada@example.com".to_string(),
+            SystemTime::now() + Duration::from_secs(60),
+            "token".to_string(),
+        ));
+        let args = server_args.resolve(vec![json!({ "limit": 10 })], &identity)?;
+        assert_eq!(args, vec![json!({ "limit": 10, "userId": "user1" })]);
+        let args = server_args.resolve(vec![json!({})], &Identity::Unknown)?;
+        assert_eq!(args, vec![json!({ "userId": null })]);
+
+        let err = server_args
+            .resolve(vec![json!({ "userId": "user2" })], &identity)
+            .unwrap_err();
+        assert_eq!(err.short_msg(), "InvalidServerArg");
+        let err = ServerArgs::parse(Some(&btreemap! {
+            "flag".to_string() => "env.FLAG".to_string(),
+        }))
+        .unwrap_err();
+        assert_eq!(err.short_msg(), "InvalidServerArg");
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+//! Sync sessions kept around after their websocket closes, so a client that
+//! reconnects shortly after can resume where it left off.
+
+use std::{
+    collections::{
+        BTreeMap,
+        VecDeque,
+    },
+    time::Duration,
+};
+
+use common::knobs::{
+    SYNC_MAX_RETAINED_SESSIONS,
+    SYNC_SESSION_RESUME_GRACE_PERIOD,
+};
+use parking_lot::Mutex;
+use tokio::time::Instant;
+
+use crate::{
+    state::SyncState,
+    ServerMessage,
+};
+
+/// What a closed session leaves behind for the next connection.
+pub(crate) struct RetainedSession {
+    pub state: SyncState,
+    /// The session's most recent mutation responses, which may not have
+    /// reached the client before its websocket closed.
+    pub mutation_responses: VecDeque<ServerMessage>,
+}
+
+/// Closed sessions, keyed by the token the server gave their client. Shared
+/// by all of a backend's sync workers.
+pub struct RetainedSessions {
+    grace_period: Duration,
+    max_sessions: usize,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    sessions: BTreeMap<String, (RetainedSession, Instant)>,
+    /// Tokens in the order they were retained, which is also the order they
+    /// expire in. Tokens of sessions that have since been resumed are skipped
+    /// when they reach the front.
+    expiration_order: VecDeque<(Instant, String)>,
+}
+
+impl Default for RetainedSessions {
+    fn default() -> Self {
+        Self::new(
+            *SYNC_SESSION_RESUME_GRACE_PERIOD,
+            *SYNC_MAX_RETAINED_SESSIONS,
+        )
+    }
+}
+
+impl RetainedSessions {
+    pub fn new(grace_period: Duration, max_sessions: usize) -> Self {
+        Self {
+            grace_period,
+            max_sessions,
+            inner: Mutex::new(Inner::default()),
+        }
+    }
+
+    pub(crate) fn retain(&self, token: String, session: RetainedSession, now: Instant) {
+        if self.grace_period.is_zero() || self.max_sessions == 0 {
+            return;
+        }
+        let expires_at = now + self.grace_period;
+        let mut guard = self.inner.lock();
+        let inner = &mut *guard;
+        inner.sessions.insert(token.clone(), (session, expires_at));
+        inner.expiration_order.push_back((expires_at, token));
+        while let Some((expires_at, token)) = inner.expiration_order.front() {
+            if *expires_at > now && inner.sessions.len() <= self.max_sessions {
+                break;
+            }
+            if inner
+                .sessions
+                .get(token)
+                .is_some_and(|(_, session_expires_at)| session_expires_at == expires_at)
+            {
+                inner.sessions.remove(token);
+            }
+            inner.expiration_order.pop_front();
+        }
+    }
+
+    /// Removes and returns the session retained under `token`, unless it has
+    /// expired. A session can only be resumed once.
+    pub(crate) fn take(&self, token: &str, now: Instant) -> Option<RetainedSession> {
+        let (session, expires_at) = self.inner.lock().sessions.remove(token)?;
+        (now < expires_at).then_some(session)
+    }
+}
@@ -17,7 +17,6 @@ use common::{
         Sha256Digest,
     },
     types::SessionId,
-    value::ConvexValue,
 };
 use errors::ErrorMetadata;
 use futures::{
@@ -31,14 +30,18 @@ use futures::{
     FutureExt,
     StreamExt,
 };
+use isolate::JsonPackedValue;
 use keybroker::Identity;
 use sync_types::{
+    patch::diff_json,
     IdentityVersion,
+    PatchOperation,
     Query,
     QueryId,
     QuerySetModification,
     QuerySetVersion,
     SerializedQueryJournal,
+    SessionResume,
     StateModification,
     StateVersion,
 };
@@ -73,6 +76,11 @@ pub struct SyncedQuery {
     /// when `self.subscription` is no longer valid and the query should be
     /// rerun.
     invalidation_future: Option<AbortHandle>,
+
+    /// The last successful result sent to the client, which the next result
+    /// can be sent as a patch to. Only kept when query patches are enabled,
+    /// and cleared when the query fails.
+    last_value: Option<JsonPackedValue>,
 }
 
 /// The client issues modifications to sync state predicated on a client
@@ -142,6 +150,10 @@ pub struct SyncState {
     pending_identity: Option<Identity>,
     /// These are the query set version and identity according to the client.
     received_client_version: ClientVersion,
+
+    /// Results at least this long are sent as patches, if the client
+    /// supports them. `None` if it doesn't.
+    query_patch_min_size: Option<usize>,
 }
 
 impl SyncState {
@@ -159,9 +171,14 @@ impl SyncState {
             pending_query_updates: vec![],
             pending_identity: None,
             received_client_version: ClientVersion::initial(),
+            query_patch_min_size: None,
         }
     }
 
+    pub fn enable_query_patches(&mut self, min_size: usize) {
+        self.query_patch_min_size = Some(min_size);
+    }
+
     pub fn set_session_id(&mut self, session_id: SessionId) {
         self.session_id = Some(session_id);
     }
@@ -327,6 +344,22 @@ impl SyncState {
         Ok(())
     }
 
+    /// Does the client resuming this session agree with it on the query set,
+    /// identity, and the last transition it was sent?
+    pub fn matches_resume(&self, resume: &SessionResume) -> bool {
+        self.current_version == resume.state_version
+            && self.received_client_version.query_set == resume.query_set_version
+            && self.received_client_version.identity == resume.identity_version
+    }
+
+    /// Drops every query's subscription so the state can outlive the
+    /// subscription client they came from. The queries are rerun when the
+    /// session is resumed, and only results that changed are sent.
+    pub fn suspend(&mut self) {
+        self.take_subscriptions();
+        self.invalidation_futures = FuturesUnordered::new();
+    }
+
     pub fn take_subscriptions(&mut self) -> BTreeMap<QueryId, Box<dyn SubscriptionTrait>> {
         let mut newly_invalidated = BTreeMap::new();
 
@@ -376,17 +409,18 @@ impl SyncState {
     pub fn complete_fetch(
         &mut self,
         query_id: QueryId,
-        result: Result<ConvexValue, RedactedJsError>,
+        result: Result<JsonPackedValue, RedactedJsError>,
         log_lines: RedactedLogLines,
         journal: SerializedQueryJournal,
         subscription: Box<dyn SubscriptionTrait>,
-    ) -> anyhow::Result<Option<StateModification<ConvexValue>>> {
+    ) -> anyhow::Result<Option<StateModification<JsonPackedValue>>> {
         if let Some(query) = self.in_progress_queries.remove(&query_id) {
             let sq = SyncedQuery {
                 query,
                 subscription: None,
                 result_hash: None,
                 invalidation_future: None,
+                last_value: None,
             };
             if self.queries.insert(query_id, sq).is_some() {
                 anyhow::bail!("Duplicate query ID: {}", query_id);
@@ -424,20 +458,38 @@ impl SyncState {
             None
         } else {
             let modification = match result {
-                Ok(value) => StateModification::QueryUpdated {
-                    query_id,
-                    value,
-                    log_lines: log_lines.into(),
-                    journal,
+                Ok(value) => {
+                    let patch = match (self.query_patch_min_size, &query.last_value) {
+                        (Some(min_size), Some(previous)) => query_patch(previous, &value, min_size),
+                        _ => None,
+                    };
+                    if self.query_patch_min_size.is_some() {
+                        query.last_value = Some(value.clone());
+                    }
+                    match patch {
+                        Some(patch) => StateModification::QueryPatched {
+                            query_id,
+                            patch,
+                            log_lines: log_lines.into(),
+                            journal,
+                        },
+                        None => StateModification::QueryUpdated {
+                            query_id,
+                            value,
+                            log_lines: log_lines.into(),
+                            journal,
+                        },
+                    }
                 },
                 Err(error) => {
+                    query.last_value = None;
                     metrics::log_query_failed();
                     StateModification::QueryFailed {
                         query_id,
                         error_message: error.to_string(),
                         log_lines: log_lines.into(),
                         journal,
-                        error_data: error.custom_data_if_any(),
+                        error_data: error.custom_data_if_any().map(JsonPackedValue::pack),
                     }
                 },
             };
@@ -471,8 +523,30 @@ impl SyncState {
     }
 }
 
+/// Returns a patch from `previous` to `value`, if `value` is big enough to be
+/// worth diffing and the patch is smaller than `value`.
+fn query_patch(
+    previous: &JsonPackedValue,
+    value: &JsonPackedValue,
+    min_size: usize,
+) -> Option<Vec<PatchOperation>> {
+    let value_size = value.as_str().len();
+    if value_size < min_size {
+        return None;
+    }
+    let patch = diff_json(&previous.json_value(), &value.json_value());
+    let patch_size = serde_json::to_string(&patch)
+        .expect("Failed to serialize patch")
+        .len();
+    if patch_size >= value_size {
+        return None;
+    }
+    metrics::log_query_result_patched(value_size, patch_size);
+    Some(patch)
+}
+
 fn hash_result(
-    r: &Result<ConvexValue, RedactedJsError>,
+    r: &Result<JsonPackedValue, RedactedJsError>,
     log_lines: &RedactedLogLines,
 ) -> Result<ValueDigest, ErrorDigest> {
     r.as_ref()
@@ -485,11 +559,11 @@ fn hash_result(
         })
 }
 
-fn udf_result_sha256(return_value: &ConvexValue, log_lines: &RedactedLogLines) -> ValueDigest {
+fn udf_result_sha256(return_value: &JsonPackedValue, log_lines: &RedactedLogLines) -> ValueDigest {
     let mut hasher = Sha256::new();
-    return_value
-        .encode_for_hash(&mut hasher)
-        .expect("Failed to create SHA256 digest");
+    // Hash the serialized value so results don't need to be decoded on the
+    // server just to compare them.
+    hasher.update(return_value.as_str().as_bytes());
     hash_log_lines(&mut hasher, log_lines);
 
     hasher.finalize()
@@ -518,6 +592,7 @@ mod tests {
         runtime::UnixTimestamp,
         value::ConvexValue,
     };
+    use isolate::JsonPackedValue;
     use proptest::prelude::*;
 
     use crate::state::udf_result_sha256;
@@ -528,7 +603,7 @@ mod tests {
         )]
 
         #[test]
-        fn test_sha256_deterministic(v in any::<ConvexValue>(), logs in any::<LogLines>()) {
+        fn test_sha256_deterministic(v in any::<JsonPackedValue>(), logs in any::<LogLines>()) {
             let logs = RedactedLogLines::from_log_lines(logs, false);
             let digest = udf_result_sha256(&v, &logs);
             assert_eq!(udf_result_sha256(&v, &logs), digest);
@@ -536,9 +611,9 @@ mod tests {
 
         #[test]
         fn test_sha256_collisions(
-            v1 in any::<ConvexValue>(),
+            v1 in any::<JsonPackedValue>(),
             v1_logs in any::<LogLines>(),
-            v2 in any::<ConvexValue>(),
+            v2 in any::<JsonPackedValue>(),
             v2_logs in any::<LogLines>()
         ) {
             if v1 != v2 {
@@ -551,7 +626,7 @@ mod tests {
 
     #[test]
     fn test_sha256_does_not_collide_with_similar_logs() {
-        let v = ConvexValue::from(42);
+        let v = JsonPackedValue::pack(ConvexValue::from(42));
         let ts = UnixTimestamp::from_millis(1715980547440);
         let v_logs = RedactedLogLines::from_log_lines(
             vec![LogLine::new_developer_log_line(
@@ -1,6 +1,7 @@
 use std::{
     collections::BTreeMap,
     sync::Arc,
+    time::Duration,
 };
 
 use application::{
@@ -40,7 +41,10 @@ use common::{
     RequestId,
 };
 use errors::ErrorMetadataAnyhowExt;
-use isolate::test_helpers::TEST_SOURCE_ISOLATE_ONLY;
+use isolate::{
+    test_helpers::TEST_SOURCE_ISOLATE_ONLY,
+    JsonPackedValue,
+};
 use keybroker::{
     testing::TestUserIdentity,
     Identity,
@@ -60,22 +64,32 @@ use runtime::{
     prod::ProdRuntime,
     testing::TestRuntime,
 };
+use serde_json::json;
 use sync_types::{
+    patch::{
+        apply_patch,
+        PatchOperation,
+        PatchPathSegment,
+        QUERY_PATCH_PROTOCOL_VERSION,
+    },
+    types::SESSION_RESUME_PROTOCOL_VERSION,
     AuthenticationToken,
     ClientMessage,
+    LogLinesMessage,
     Query,
     QueryId,
     QuerySetModification,
+    SessionResume,
     StateModification,
+    StateVersion,
     UserIdentityAttributes,
 };
 use tokio::sync::mpsc;
 
 use crate::{
-    worker::{
-        measurable_unbounded_channel,
-        SingleFlightReceiver,
-    },
+    outgoing_channel,
+    OutgoingReceiver,
+    RetainedSessions,
     ServerMessage,
     SyncWorker,
     SyncWorkerConfig,
@@ -85,6 +99,7 @@ struct SyncTest<RT: Runtime> {
     pub rt: RT,
     pub kb: KeyBroker,
     application: Application<RT>,
+    sessions: Arc<RetainedSessions>,
 }
 
 impl<RT: Runtime> SyncTest<RT> {
@@ -126,26 +141,45 @@ impl<RT: Runtime> SyncTest<RT> {
             rt,
             kb,
             application,
+            sessions: Arc::new(RetainedSessions::default()),
         })
     }
 
     fn new_worker(&self) -> anyhow::Result<TestSyncWorker<RT>> {
         let config = SyncWorkerConfig::default();
-        self.new_worker_with_config(config, None)
+        self.new_worker_with_config(config, None, 0)
     }
 
     fn new_worker_with_config(
         &self,
         config: SyncWorkerConfig,
         max_observed_timestamp: Option<Timestamp>,
+        protocol_version: u32,
+    ) -> anyhow::Result<TestSyncWorker<RT>> {
+        let connect = ClientMessage::Connect {
+            session_id: SessionId::nil(),
+            connection_count: 0,
+            last_close_reason: "InitialConnect".to_string(),
+            max_observed_timestamp,
+            protocol_version,
+            resume: None,
+        };
+        self.new_worker_with_connect(config, connect)
+    }
+
+    fn new_worker_with_connect(
+        &self,
+        config: SyncWorkerConfig,
+        connect: ClientMessage,
     ) -> anyhow::Result<TestSyncWorker<RT>> {
         let worker_failed = Arc::new(Mutex::new(None));
         let (client_tx, client_rx) = mpsc::unbounded_channel();
-        let (server_tx, server_rx) = measurable_unbounded_channel();
+        let (server_tx, server_rx) = outgoing_channel();
 
         let worker_failed_ = worker_failed.clone();
         let api = Arc::new(self.application.clone());
         let rt = self.rt.clone();
+        let sessions = self.sessions.clone();
         let future = async move {
             // TODO(CX-597): The panic in this future currently gets swallowed by
             // `futures::RemoteHandle`.
@@ -157,6 +191,7 @@ impl<RT: Runtime> SyncTest<RT> {
                     destination: RequestDestination::ConvexCloud,
                 },
                 config,
+                sessions,
                 client_rx,
                 server_tx,
             )
@@ -168,15 +203,7 @@ impl<RT: Runtime> SyncTest<RT> {
         };
         let worker_handle = self.rt.spawn("sync_test", future);
 
-        client_tx.send((
-            ClientMessage::Connect {
-                session_id: SessionId::nil(),
-                connection_count: 0,
-                last_close_reason: "InitialConnect".to_string(),
-                max_observed_timestamp,
-            },
-            self.rt.monotonic_now(),
-        ))?;
+        client_tx.send((connect, self.rt.monotonic_now()))?;
 
         Ok(TestSyncWorker {
             rt: self.rt.clone(),
@@ -192,7 +219,7 @@ struct TestSyncWorker<RT: Runtime> {
     rt: RT,
 
     tx: mpsc::UnboundedSender<(ClientMessage, tokio::time::Instant)>,
-    rx: SingleFlightReceiver,
+    rx: OutgoingReceiver,
 
     worker_handle: Box<dyn SpawnHandle>,
     worker_failed: Arc<Mutex<Option<anyhow::Error>>>,
@@ -219,17 +246,43 @@ impl<RT: Runtime> TestSyncWorker<RT> {
         Ok(message)
     }
 
+    /// Authenticates as a test user, since unauthenticated mutation IDs are
+    /// only deduplicated within a session.
+    async fn authenticate_as_user(&mut self, kb: &KeyBroker) -> anyhow::Result<()> {
+        let admin_key = kb.issue_admin_key(MemberId(1));
+        self.send(ClientMessage::Authenticate {
+            token: AuthenticationToken::Admin(
+                admin_key.as_string(),
+                Some(UserIdentityAttributes::test()),
+            ),
+            base_version: 0,
+        })?;
+        must_let!(let ServerMessage::Transition { .. } = self.receive().await?);
+        Ok(())
+    }
+
     async fn mutation(
         &mut self,
         path: &str,
         args: ConvexObject,
         request_id: SessionRequestSeqNumber,
+    ) -> anyhow::Result<(ConvexValue, Timestamp)> {
+        self.mutation_with_id(path, args, request_id, None).await
+    }
+
+    async fn mutation_with_id(
+        &mut self,
+        path: &str,
+        args: ConvexObject,
+        request_id: SessionRequestSeqNumber,
+        mutation_id: Option<&str>,
     ) -> anyhow::Result<(ConvexValue, Timestamp)> {
         self.send(ClientMessage::Mutation {
             request_id,
             udf_path: path.parse()?,
             args: vec![args.into()],
             component_path: None,
+            mutation_id: mutation_id.map(|id| id.to_string()),
         })?;
 
         must_let!(let ServerMessage::MutationResponse {
@@ -241,7 +294,7 @@ impl<RT: Runtime> TestSyncWorker<RT> {
         // Check that the mutation ID on the outgoing message matches to
         // confirm we intercepted the right message
         assert_eq!(request_id, outgoing_mutation_id);
-        Ok((result.unwrap(), ts.unwrap()))
+        Ok((result.unwrap().unpack(), ts.unwrap()))
     }
 
     async fn shutdown(self) -> anyhow::Result<()> {
@@ -292,6 +345,7 @@ async fn test_basic_account(rt: TestRuntime) -> anyhow::Result<()> {
         args: vec![assert_obj!("name" => name1.clone()).into()],
         journal: None,
         component_path: None,
+        server_args: None,
     };
     let msg = ClientMessage::ModifyQuerySet {
         base_version: 0,
@@ -312,7 +366,7 @@ async fn test_basic_account(rt: TestRuntime) -> anyhow::Result<()> {
     assert_eq!(modifications.len(), 1);
     must_let!(let StateModification::QueryUpdated { query_id, value, .. } = &modifications[0]);
     assert_eq!(*query_id, QueryId::new(0));
-    assert_eq!(value, &ConvexValue::from(100.0));
+    assert_eq!(value.unpack(), ConvexValue::from(100.0));
 
     // 3. Mutate a single query and see that it gets updated.
     let (result, _) = sync_worker
@@ -336,7 +390,7 @@ async fn test_basic_account(rt: TestRuntime) -> anyhow::Result<()> {
     assert_eq!(modifications.len(), 1);
     must_let!(let StateModification::QueryUpdated { query_id, value, .. } = &modifications[0]);
     assert_eq!(*query_id, QueryId::new(0));
-    assert_eq!(value, &ConvexValue::from(150.0));
+    assert_eq!(value.unpack(), ConvexValue::from(150.0));
 
     // 4. Add a new query.
     let query = Query {
@@ -345,6 +399,7 @@ async fn test_basic_account(rt: TestRuntime) -> anyhow::Result<()> {
         args: vec![assert_obj!("name" => name2.clone()).into()],
         journal: None,
         component_path: None,
+        server_args: None,
     };
     let msg = ClientMessage::ModifyQuerySet {
         base_version: 1,
@@ -364,7 +419,7 @@ async fn test_basic_account(rt: TestRuntime) -> anyhow::Result<()> {
     assert_eq!(modifications.len(), 1);
     must_let!(let StateModification::QueryUpdated { query_id, value, .. } = &modifications[0]);
     assert_eq!(*query_id, QueryId::new(1));
-    assert_eq!(value, &ConvexValue::from(50.0));
+    assert_eq!(value.unpack(), ConvexValue::from(50.0));
 
     // 5. Do a transfer and see that the two queries get updated atomically.
     let (result, _) = sync_worker
@@ -388,10 +443,10 @@ async fn test_basic_account(rt: TestRuntime) -> anyhow::Result<()> {
     assert_eq!(modifications.len(), 2);
     must_let!(let StateModification::QueryUpdated { query_id, value, .. } = &modifications[0]);
     assert_eq!(*query_id, QueryId::new(0));
-    assert_eq!(value, &ConvexValue::from(125.0));
+    assert_eq!(value.unpack(), ConvexValue::from(125.0));
     must_let!(let StateModification::QueryUpdated { query_id, value, .. } = &modifications[1]);
     assert_eq!(*query_id, QueryId::new(1));
-    assert_eq!(value, &ConvexValue::from(75.0));
+    assert_eq!(value.unpack(), ConvexValue::from(75.0));
 
     // 5. Remove a query.
     let msg = ClientMessage::ModifyQuerySet {
@@ -436,6 +491,7 @@ async fn test_remove_in_progress_query(rt: TestRuntime) -> anyhow::Result<()> {
         args: vec![assert_obj!("name" => name1.clone()).into()],
         journal: None,
         component_path: None,
+        server_args: None,
     };
     let msg = ClientMessage::ModifyQuerySet {
         base_version: 0,
@@ -474,6 +530,7 @@ async fn test_query_failure(rt: TestRuntime) -> anyhow::Result<()> {
         args: vec![assert_obj!("i" => ConvexValue::from(0.0)).into()],
         journal: None,
         component_path: None,
+        server_args: None,
     };
     let query2 = Query {
         query_id: QueryId::new(1),
@@ -481,6 +538,7 @@ async fn test_query_failure(rt: TestRuntime) -> anyhow::Result<()> {
         args: vec![assert_obj!("i" => ConvexValue::from(3.0)).into()],
         journal: None,
         component_path: None,
+        server_args: None,
     };
     let msg = ClientMessage::ModifyQuerySet {
         base_version: 0,
@@ -515,6 +573,7 @@ async fn test_query_failure(rt: TestRuntime) -> anyhow::Result<()> {
         args: vec![],
         journal: None,
         component_path: None,
+        server_args: None,
     };
     let msg = ClientMessage::ModifyQuerySet {
         base_version: end_version.query_set,
@@ -531,7 +590,7 @@ async fn test_query_failure(rt: TestRuntime) -> anyhow::Result<()> {
     assert_eq!(modifications.len(), 1);
     must_let!(let StateModification::QueryUpdated { query_id, value, .. } = &modifications[0]);
     assert_eq!(*query_id, QueryId::new(2));
-    assert_eq!(value, &ConvexValue::try_from("on my list")?);
+    assert_eq!(value.unpack(), ConvexValue::try_from("on my list")?);
 
     // Remove the two failing queries.
     let msg = ClientMessage::ModifyQuerySet {
@@ -717,6 +776,164 @@ async fn test_idempotent_mutations(rt: TestRuntime) -> anyhow::Result<()> {
     Ok(())
 }
 
+#[convex_macro::test_runtime]
+async fn test_idempotent_mutations_by_mutation_id(rt: TestRuntime) -> anyhow::Result<()> {
+    // Retries with the same mutation ID are deduplicated even when they come
+    // from another connection with different request IDs.
+    let test = SyncTest::new(rt).await?;
+    let mut sync_worker = test.new_worker()?;
+    sync_worker.authenticate_as_user(&test.kb).await?;
+
+    let name = ConvexValue::try_from("Alice")?;
+    sync_worker
+        .mutation(
+            "sync:initialize",
+            assert_obj!("name" => name.clone(), "balance" => 0.0),
+            0,
+        )
+        .await?;
+    must_let!(let ServerMessage::Transition { .. } = sync_worker.receive().await?);
+    let (result, _) = sync_worker
+        .mutation_with_id(
+            "sync:deposit",
+            assert_obj!("name" => name.clone(), "balance" => 5.0),
+            1,
+            Some("deposit-1"),
+        )
+        .await?;
+    assert_eq!(result, ConvexValue::try_from("Alice's balance is now 5")?);
+    sync_worker.shutdown().await?;
+
+    let mut sync_worker = test.new_worker()?;
+    sync_worker.authenticate_as_user(&test.kb).await?;
+    let (result, _) = sync_worker
+        .mutation_with_id(
+            "sync:deposit",
+            assert_obj!("name" => name.clone(), "balance" => 5.0),
+            7,
+            Some("deposit-1"),
+        )
+        .await?;
+    assert_eq!(result, ConvexValue::try_from("Alice's balance is now 5")?);
+    must_let!(let ServerMessage::Transition { .. } = sync_worker.receive().await?);
+
+    let (result, _) = sync_worker
+        .mutation_with_id(
+            "sync:deposit",
+            assert_obj!("name" => name.clone(), "balance" => 5.0),
+            8,
+            Some("deposit-2"),
+        )
+        .await?;
+    assert_eq!(result, ConvexValue::try_from("Alice's balance is now 10")?);
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_mutation_id_reused_for_different_call(rt: TestRuntime) -> anyhow::Result<()> {
+    let test = SyncTest::new(rt).await?;
+    let mut sync_worker = test.new_worker()?;
+    sync_worker.authenticate_as_user(&test.kb).await?;
+
+    let name = ConvexValue::try_from("Alice")?;
+    sync_worker
+        .mutation(
+            "sync:initialize",
+            assert_obj!("name" => name.clone(), "balance" => 0.0),
+            0,
+        )
+        .await?;
+    must_let!(let ServerMessage::Transition { .. } = sync_worker.receive().await?);
+    sync_worker
+        .mutation_with_id(
+            "sync:deposit",
+            assert_obj!("name" => name.clone(), "balance" => 5.0),
+            1,
+            Some("deposit-1"),
+        )
+        .await?;
+    must_let!(let ServerMessage::Transition { .. } = sync_worker.receive().await?);
+
+    // Reusing the ID with other arguments fails rather than returning the
+    // first deposit's result.
+    sync_worker.send(ClientMessage::Mutation {
+        request_id: 2,
+        udf_path: "sync:deposit".parse()?,
+        args: vec![assert_obj!("name" => name.clone(), "balance" => 7.0).into()],
+        component_path: None,
+        mutation_id: Some("deposit-1".to_string()),
+    })?;
+    must_let!(let ServerMessage::MutationResponse { request_id, result, .. } =
+        sync_worker.receive().await?);
+    assert_eq!(request_id, 2);
+    must_let!(let Err(error) = result);
+    assert!(error.get_message().contains("different arguments"));
+
+    // As does reusing it for another mutation.
+    sync_worker.send(ClientMessage::Mutation {
+        request_id: 3,
+        udf_path: "sync:initialize".parse()?,
+        args: vec![assert_obj!("name" => name.clone(), "balance" => 5.0).into()],
+        component_path: None,
+        mutation_id: Some("deposit-1".to_string()),
+    })?;
+    must_let!(let ServerMessage::MutationResponse { request_id, result, .. } =
+        sync_worker.receive().await?);
+    assert_eq!(request_id, 3);
+    assert!(result.is_err());
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_unauthenticated_mutation_ids_scoped_to_session(
+    rt: TestRuntime,
+) -> anyhow::Result<()> {
+    // Unauthenticated clients all have the same identity, so one can't get
+    // another's result by picking the same mutation ID.
+    let test = SyncTest::new(rt.clone()).await?;
+    let mut sync_worker = test.new_worker()?;
+
+    let name = ConvexValue::try_from("Alice")?;
+    sync_worker
+        .mutation(
+            "sync:initialize",
+            assert_obj!("name" => name.clone(), "balance" => 0.0),
+            0,
+        )
+        .await?;
+    must_let!(let ServerMessage::Transition { .. } = sync_worker.receive().await?);
+    let (result, _) = sync_worker
+        .mutation_with_id(
+            "sync:deposit",
+            assert_obj!("name" => name.clone(), "balance" => 5.0),
+            1,
+            Some("deposit-1"),
+        )
+        .await?;
+    assert_eq!(result, ConvexValue::try_from("Alice's balance is now 5")?);
+    sync_worker.shutdown().await?;
+
+    let connect = ClientMessage::Connect {
+        session_id: SessionId::new(rt.new_uuid_v4()),
+        connection_count: 0,
+        last_close_reason: "InitialConnect".to_string(),
+        max_observed_timestamp: None,
+        protocol_version: 0,
+        resume: None,
+    };
+    let mut sync_worker = test.new_worker_with_connect(SyncWorkerConfig::default(), connect)?;
+    let (result, _) = sync_worker
+        .mutation_with_id(
+            "sync:deposit",
+            assert_obj!("name" => name.clone(), "balance" => 5.0),
+            1,
+            Some("deposit-1"),
+        )
+        .await?;
+    assert_eq!(result, ConvexValue::try_from("Alice's balance is now 10")?);
+    Ok(())
+}
+
 #[convex_macro::test_runtime]
 async fn test_value_deduplication_success(rt: TestRuntime) -> anyhow::Result<()> {
     let test = SyncTest::new(rt).await?;
@@ -739,6 +956,7 @@ async fn test_value_deduplication_success(rt: TestRuntime) -> anyhow::Result<()>
         args: vec![assert_obj!("throwError" => ConvexValue::from(false)).into()],
         journal: None,
         component_path: None,
+        server_args: None,
     };
     let msg = ClientMessage::ModifyQuerySet {
         base_version: 0,
@@ -752,7 +970,7 @@ async fn test_value_deduplication_success(rt: TestRuntime) -> anyhow::Result<()>
     assert_eq!(modifications.len(), 1, "{modifications:?}");
     must_let!(let StateModification::QueryUpdated { query_id, value, .. } = &modifications[0]);
     assert_eq!(*query_id, QueryId::new(0));
-    assert_eq!(value, &assert_val!("hi"));
+    assert_eq!(value.unpack(), assert_val!("hi"));
 
     // Insert a new value into the "accounts" table, which will invalidate the query
     // but not change its result.
@@ -796,6 +1014,7 @@ async fn test_value_deduplication_failure(rt: TestRuntime) -> anyhow::Result<()>
         args: vec![assert_obj!("throwError" => ConvexValue::from(true)).into()],
         journal: None,
         component_path: None,
+        server_args: None,
     };
     let msg = ClientMessage::ModifyQuerySet {
         base_version: 0,
@@ -877,7 +1096,7 @@ async fn test_udf_cache_out_of_order(rt: TestRuntime) -> anyhow::Result<()> {
             FunctionCaller::SyncWorker(ClientVersion::unknown()),
         )
         .await?;
-    assert_eq!(result1.result?, ConvexValue::from(5.0));
+    assert_eq!(result1.result?.unpack(), ConvexValue::from(5.0));
 
     let result2 = test
         .application
@@ -891,7 +1110,7 @@ async fn test_udf_cache_out_of_order(rt: TestRuntime) -> anyhow::Result<()> {
             FunctionCaller::SyncWorker(ClientVersion::unknown()),
         )
         .await?;
-    assert_eq!(result2.result?, ConvexValue::from(0.0));
+    assert_eq!(result2.result?.unpack(), ConvexValue::from(0.0));
     Ok(())
 }
 
@@ -900,7 +1119,7 @@ async fn test_max_observed_timestamp(rt: ProdRuntime) -> anyhow::Result<()> {
     let test = SyncTest::new(rt).await?;
 
     let config = SyncWorkerConfig::default();
-    let mut sync_worker = test.new_worker_with_config(config, Some(Timestamp::MAX))?;
+    let mut sync_worker = test.new_worker_with_config(config, Some(Timestamp::MAX), 0)?;
     must_let!(let Err(err) = sync_worker.receive().await);
     assert!(
         format!("{err}")
@@ -910,3 +1129,338 @@ async fn test_max_observed_timestamp(rt: ProdRuntime) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[convex_macro::test_runtime]
+async fn test_invalidation_coalesce_window(rt: TestRuntime) -> anyhow::Result<()> {
+    let test = SyncTest::new(rt.clone()).await?;
+    let mut writer = test.new_worker()?;
+    let window = Duration::from_secs(60);
+    let config = SyncWorkerConfig {
+        invalidation_coalesce_window: window,
+        ..Default::default()
+    };
+    let mut reader = test.new_worker_with_config(config, None, 0)?;
+
+    let name = assert_val!("orinoco");
+    writer
+        .mutation(
+            "sync:initialize",
+            assert_obj!("name" => name.clone(), "balance" => 100.0),
+            0,
+        )
+        .await?;
+    must_let!(let ServerMessage::Transition { .. } = writer.receive().await?);
+
+    let subscribed_at = rt.monotonic_now();
+    let query = Query {
+        query_id: QueryId::new(0),
+        udf_path: "sync:accountBalance".parse()?,
+        args: vec![assert_obj!("name" => name.clone()).into()],
+        journal: None,
+        component_path: None,
+        server_args: None,
+    };
+    reader.send(ClientMessage::ModifyQuerySet {
+        base_version: 0,
+        new_version: 1,
+        modifications: vec![QuerySetModification::Add(query)],
+    })?;
+    must_let!(let ServerMessage::Transition { .. } = reader.receive().await?);
+
+    // The deposit invalidates the query right after the reader's last update,
+    // so the rerun waits for the rest of the window.
+    writer
+        .mutation(
+            "sync:deposit",
+            assert_obj!("name" => name, "balance" => 50.0),
+            1,
+        )
+        .await?;
+    let modifications = loop {
+        match reader.receive().await? {
+            ServerMessage::Ping {} => continue,
+            ServerMessage::Transition { modifications, .. } => break modifications,
+            message => anyhow::bail!("Unexpected message {message:?}"),
+        }
+    };
+    assert!(rt.monotonic_now() - subscribed_at >= window);
+    must_let!(let StateModification::QueryUpdated { value, .. } = &modifications[0]);
+    assert_eq!(value.unpack(), ConvexValue::from(150.0));
+
+    writer.shutdown().await?;
+    reader.shutdown().await?;
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_query_patches(rt: TestRuntime) -> anyhow::Result<()> {
+    let test = SyncTest::new(rt).await?;
+    let config = SyncWorkerConfig {
+        query_patch_min_size: 0,
+        ..Default::default()
+    };
+    let mut sync_worker =
+        test.new_worker_with_config(config, None, QUERY_PATCH_PROTOCOL_VERSION)?;
+
+    for (request_id, name) in ["orinoco", "tizoncito", "alice"].into_iter().enumerate() {
+        sync_worker
+            .mutation(
+                "sync:initialize",
+                assert_obj!("name" => name, "balance" => 100.0),
+                request_id as u32,
+            )
+            .await?;
+        must_let!(let ServerMessage::Transition { .. } = sync_worker.receive().await?);
+    }
+
+    let query = Query {
+        query_id: QueryId::new(0),
+        udf_path: "sync:listAccounts".parse()?,
+        args: vec![],
+        journal: None,
+        component_path: None,
+        server_args: None,
+    };
+    sync_worker.send(ClientMessage::ModifyQuerySet {
+        base_version: 0,
+        new_version: 1,
+        modifications: vec![QuerySetModification::Add(query)],
+    })?;
+    must_let!(let ServerMessage::Transition { modifications, .. } = sync_worker.receive().await?);
+    // The client doesn't have a result yet, so the first one is sent whole.
+    must_let!(let StateModification::QueryUpdated { value, .. } = &modifications[0]);
+    let mut result = value.json_value();
+
+    sync_worker
+        .mutation(
+            "sync:deposit",
+            assert_obj!("name" => "tizoncito", "balance" => 5.0),
+            3,
+        )
+        .await?;
+    must_let!(let ServerMessage::Transition { modifications, .. } = sync_worker.receive().await?);
+    must_let!(let StateModification::QueryPatched { patch, .. } = &modifications[0]);
+    // Only the changed balance is sent.
+    assert_eq!(
+        patch,
+        &vec![PatchOperation::Set {
+            path: vec![
+                PatchPathSegment::Index(1),
+                PatchPathSegment::Key("balance".to_string())
+            ],
+            value: json!(105.0),
+        }]
+    );
+    apply_patch(&mut result, patch)?;
+    assert_eq!(result[1]["balance"], json!(105.0));
+
+    sync_worker.shutdown().await?;
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_resume_session(rt: TestRuntime) -> anyhow::Result<()> {
+    let test = SyncTest::new(rt).await?;
+    let connect = |resume| ClientMessage::Connect {
+        session_id: SessionId::nil(),
+        connection_count: 0,
+        last_close_reason: "InitialConnect".to_string(),
+        max_observed_timestamp: None,
+        protocol_version: SESSION_RESUME_PROTOCOL_VERSION,
+        resume,
+    };
+    let mut sync_worker =
+        test.new_worker_with_connect(SyncWorkerConfig::default(), connect(None))?;
+    must_let!(let ServerMessage::Connected {
+        session_token,
+        resumed,
+    } = sync_worker.receive().await?);
+    assert!(!resumed);
+
+    sync_worker
+        .mutation(
+            "sync:initialize",
+            assert_obj!("name" => "orinoco", "balance" => 100.0),
+            0,
+        )
+        .await?;
+    must_let!(let ServerMessage::Transition { .. } = sync_worker.receive().await?);
+    let query = Query {
+        query_id: QueryId::new(0),
+        udf_path: "sync:accountBalance".parse()?,
+        args: vec![assert_obj!("name" => "orinoco").into()],
+        journal: None,
+        component_path: None,
+        server_args: None,
+    };
+    sync_worker.send(ClientMessage::ModifyQuerySet {
+        base_version: 0,
+        new_version: 1,
+        modifications: vec![QuerySetModification::Add(query)],
+    })?;
+    must_let!(let ServerMessage::Transition { end_version, .. } = sync_worker.receive().await?);
+    // The connection drops, so the session is retained.
+    sync_worker.shutdown().await?;
+
+    let resume = SessionResume {
+        session_token,
+        state_version: end_version,
+        query_set_version: 1,
+        identity_version: 0,
+    };
+    let mut sync_worker =
+        test.new_worker_with_connect(SyncWorkerConfig::default(), connect(Some(resume.clone())))?;
+    // The mutation response is replayed in case the old connection lost it.
+    must_let!(let ServerMessage::MutationResponse {
+        request_id,
+        ..
+    } = sync_worker.receive().await?);
+    assert_eq!(request_id, 0);
+    must_let!(let ServerMessage::Connected { resumed, .. } = sync_worker.receive().await?);
+    assert!(resumed);
+    // The query is rerun without the client resending it, and its result
+    // hasn't changed, so it isn't sent again.
+    must_let!(let ServerMessage::Transition {
+        start_version,
+        modifications,
+        ..
+    } = sync_worker.receive().await?);
+    assert_eq!(start_version, end_version);
+    assert!(modifications.is_empty());
+
+    sync_worker
+        .mutation(
+            "sync:deposit",
+            assert_obj!("name" => "orinoco", "balance" => 5.0),
+            1,
+        )
+        .await?;
+    must_let!(let ServerMessage::Transition { modifications, .. } = sync_worker.receive().await?);
+    must_let!(let StateModification::QueryUpdated { query_id, value, .. } = &modifications[0]);
+    assert_eq!(*query_id, QueryId::new(0));
+    assert_eq!(value.unpack(), ConvexValue::from(105.0));
+    sync_worker.shutdown().await?;
+
+    // Each token can only be used once.
+    let mut sync_worker =
+        test.new_worker_with_connect(SyncWorkerConfig::default(), connect(Some(resume)))?;
+    must_let!(let ServerMessage::Connected { resumed, .. } = sync_worker.receive().await?);
+    assert!(!resumed);
+    sync_worker.shutdown().await?;
+
+    Ok(())
+}
+
+#[convex_macro::test_runtime]
+async fn test_outgoing_priority_and_coalescing(rt: TestRuntime) -> anyhow::Result<()> {
+    let (mut tx, mut rx) = outgoing_channel();
+    let version = |ts| StateVersion {
+        query_set: 1,
+        identity: 0,
+        ts: Timestamp::must(ts),
+    };
+    let transition = |start, end, modifications| ServerMessage::Transition {
+        start_version: version(start),
+        end_version: version(end),
+        modifications,
+    };
+    let json = |value: serde_json::Value| -> anyhow::Result<JsonPackedValue> {
+        Ok(JsonPackedValue::pack(value.try_into()?))
+    };
+    let now = rt.monotonic_now();
+
+    tx.send((
+        transition(
+            1,
+            2,
+            vec![
+                StateModification::QueryUpdated {
+                    query_id: QueryId::new(0),
+                    value: json(json!({"balance": 100.0, "name": "orinoco"}))?,
+                    log_lines: LogLinesMessage(vec!["first".to_string()]),
+                    journal: None,
+                },
+                StateModification::QueryRemoved {
+                    query_id: QueryId::new(1),
+                },
+            ],
+        ),
+        now,
+    ))
+    .unwrap();
+    assert!(!tx.is_backlogged());
+    tx.send((
+        transition(
+            2,
+            3,
+            vec![
+                StateModification::QueryPatched {
+                    query_id: QueryId::new(0),
+                    patch: vec![PatchOperation::Set {
+                        path: vec![PatchPathSegment::Key("balance".to_string())],
+                        value: json!(105.0),
+                    }],
+                    log_lines: LogLinesMessage(vec!["second".to_string()]),
+                    journal: None,
+                },
+                StateModification::QueryUpdated {
+                    query_id: QueryId::new(2),
+                    value: json(json!("tizoncito"))?,
+                    log_lines: LogLinesMessage(vec![]),
+                    journal: None,
+                },
+            ],
+        ),
+        now,
+    ))
+    .unwrap();
+    assert!(tx.is_backlogged());
+    tx.send((
+        ServerMessage::MutationResponse {
+            request_id: 0,
+            result: Ok(json(json!(null))?),
+            ts: Some(Timestamp::must(3)),
+            log_lines: LogLinesMessage(vec![]),
+        },
+        now,
+    ))
+    .unwrap();
+
+    // The mutation response goes out ahead of the transitions, which were
+    // coalesced into one.
+    must_let!(let Some((ServerMessage::MutationResponse { .. }, _)) = rx.next().await);
+    must_let!(let Some((ServerMessage::Transition {
+        start_version,
+        end_version,
+        modifications,
+    }, _)) = rx.next().await);
+    assert!(!tx.is_backlogged());
+    assert_eq!(start_version, version(1));
+    assert_eq!(end_version, version(3));
+    assert_eq!(modifications.len(), 3);
+    must_let!(let StateModification::QueryUpdated {
+        query_id,
+        value,
+        log_lines,
+        ..
+    } = &modifications[0]);
+    assert_eq!(*query_id, QueryId::new(0));
+    assert_eq!(
+        value.json_value(),
+        json!({"balance": 105.0, "name": "orinoco"})
+    );
+    assert_eq!(log_lines.0, vec!["first", "second"]);
+    assert_eq!(
+        modifications[1],
+        StateModification::QueryRemoved {
+            query_id: QueryId::new(1)
+        }
+    );
+    must_let!(let StateModification::QueryUpdated { query_id, .. } = &modifications[2]);
+    assert_eq!(*query_id, QueryId::new(2));
+
+    drop(tx);
+    assert!(rx.next().await.is_none());
+
+    Ok(())
+}
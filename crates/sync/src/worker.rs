@@ -1,10 +1,11 @@
 use std::{
-    collections::BTreeMap,
+    collections::{
+        BTreeMap,
+        VecDeque,
+    },
+    mem,
+    pin::Pin,
     sync::{
-        atomic::{
-            AtomicUsize,
-            Ordering,
-        },
         Arc,
         LazyLock,
     },
@@ -33,9 +34,20 @@ use common::{
         ComponentPath,
         ExportPath,
     },
+    errors::report_error,
     http::ResolvedHostname,
-    knobs::SYNC_MAX_SEND_TRANSITION_COUNT,
+    knobs::{
+        SYNC_INVALIDATION_COALESCE_WINDOW,
+        SYNC_MAX_SEND_BUFFER_SIZE,
+        SYNC_QUERY_PATCH_MIN_SIZE,
+    },
     minitrace_helpers::get_sampled_span,
+    paths::FieldPath,
+    query::{
+        IndexRange,
+        IndexRangeExpression,
+        Order,
+    },
     runtime::{
         Runtime,
         WithTimeout,
@@ -48,33 +60,49 @@ use common::{
     version::ClientVersion,
     RequestId,
 };
-use errors::ErrorMetadata;
+use errors::{
+    ErrorMetadata,
+    ErrorMetadataAnyhowExt,
+};
 use futures::{
     future::{
         self,
         BoxFuture,
         Fuse,
+        FusedFuture,
     },
     select_biased,
     stream::{
         self,
+        AbortHandle,
+        Abortable,
+        BoxStream,
         Buffered,
         FuturesUnordered,
+        SelectAll,
     },
     Future,
     FutureExt,
     StreamExt,
 };
+use isolate::JsonPackedValue;
 use keybroker::Identity;
 use maplit::btreemap;
 use minitrace::prelude::*;
 use model::session_requests::types::SessionRequestIdentifier;
 use sync_types::{
+    patch::QUERY_PATCH_PROTOCOL_VERSION,
+    types::SESSION_RESUME_PROTOCOL_VERSION,
+    Aggregate,
+    AggregateId,
+    AggregateSetModification,
     ClientMessage,
     IdentityVersion,
     QueryId,
     QuerySetModification,
     SerializedQueryJournal,
+    SessionId,
+    SessionResume,
     StateModification,
     StateVersion,
     Timestamp,
@@ -82,10 +110,7 @@ use sync_types::{
 };
 use tokio::sync::{
     mpsc,
-    mpsc::error::{
-        SendError,
-        TrySendError,
-    },
+    mpsc::error::TrySendError,
 };
 use tokio_stream::wrappers::ReceiverStream;
 
@@ -96,6 +121,12 @@ use crate::{
         mutation_queue_timer,
         TypedClientEvent,
     },
+    outgoing::OutgoingSender,
+    server_args::ServerArgs,
+    sessions::{
+        RetainedSession,
+        RetainedSessions,
+    },
     state::SyncState,
     ServerMessage,
 };
@@ -110,94 +141,35 @@ pub static SYNC_SESSION_MAX_EXEC_THREADS: LazyLock<usize> =
 const OPERATION_QUEUE_BUFFER_SIZE: usize = 1000;
 const SYNC_WORKER_PROCESS_TIMEOUT: Duration = Duration::from_secs(60);
 
+// The longest client-chosen mutation ID accepted, in bytes.
+const MAX_MUTATION_ID_LEN: usize = 256;
+
+// How many of a session's latest mutation responses are replayed when it's
+// resumed, in case they were lost with the old websocket.
+const MAX_RETAINED_MUTATION_RESPONSES: usize = 64;
+
 #[derive(Clone, Debug)]
 pub struct SyncWorkerConfig {
     pub client_version: ClientVersion,
+    /// Queries invalidated within this long of the last update are rerun
+    /// together at the end of the window, rather than as soon as each commit
+    /// invalidates them. Zero reruns them right away.
+    pub invalidation_coalesce_window: Duration,
+    /// Query results at least this long are sent as patches to clients that
+    /// support them.
+    pub query_patch_min_size: usize,
 }
 
 impl Default for SyncWorkerConfig {
     fn default() -> Self {
         Self {
             client_version: ClientVersion::unknown(),
+            invalidation_coalesce_window: *SYNC_INVALIDATION_COALESCE_WINDOW,
+            query_patch_min_size: *SYNC_QUERY_PATCH_MIN_SIZE,
         }
     }
 }
 
-/// Creates a channel which allows the sender to track the buffer size and
-/// opt-in to slow down if the buffer becomes too large.
-pub fn measurable_unbounded_channel() -> (SingleFlightSender, SingleFlightReceiver) {
-    let buffer_size_bytes = Arc::new(AtomicUsize::new(0));
-    // The channel is used to send/receive "size reduced" notifications.
-    let (size_reduced_tx, size_reduced_rx) = mpsc::channel(1);
-    let (tx, rx) = mpsc::unbounded_channel();
-    (
-        SingleFlightSender {
-            inner: tx,
-            transition_count: buffer_size_bytes.clone(),
-            count_reduced_rx: size_reduced_rx,
-        },
-        SingleFlightReceiver {
-            inner: rx,
-            transition_count: buffer_size_bytes,
-            size_reduced_tx,
-        },
-    )
-}
-
-/// Wrapper around UnboundedSender that counts Transition messages,
-/// allowing single-flighting, i.e. skipping transitions if the client is
-/// backlogged on receiving them.
-pub struct SingleFlightSender {
-    inner: mpsc::UnboundedSender<(ServerMessage, tokio::time::Instant)>,
-
-    transition_count: Arc<AtomicUsize>,
-    count_reduced_rx: mpsc::Receiver<()>,
-}
-
-impl SingleFlightSender {
-    pub fn send(
-        &mut self,
-        msg: (ServerMessage, tokio::time::Instant),
-    ) -> Result<(), SendError<(ServerMessage, tokio::time::Instant)>> {
-        if matches!(&msg.0, ServerMessage::Transition { .. }) {
-            self.transition_count.fetch_add(1, Ordering::SeqCst);
-        }
-        self.inner.send(msg)
-    }
-
-    pub fn transition_count(&self) -> usize {
-        self.transition_count.load(Ordering::SeqCst)
-    }
-
-    // Waits until a single message has been received implying the size of the
-    // buffer have been reduced. Note that if multiple messages are received
-    // between calls, this will fire only once.
-    pub async fn message_consumed(&mut self) {
-        self.count_reduced_rx.recv().await;
-    }
-}
-
-pub struct SingleFlightReceiver {
-    inner: mpsc::UnboundedReceiver<(ServerMessage, tokio::time::Instant)>,
-
-    transition_count: Arc<AtomicUsize>,
-    size_reduced_tx: mpsc::Sender<()>,
-}
-
-impl SingleFlightReceiver {
-    pub async fn next(&mut self) -> Option<(ServerMessage, tokio::time::Instant)> {
-        let result = self.inner.recv().await;
-        if let Some(msg) = &result {
-            if matches!(msg.0, ServerMessage::Transition { .. }) {
-                self.transition_count.fetch_sub(1, Ordering::SeqCst);
-            }
-            // Don't block if channel is full.
-            _ = self.size_reduced_tx.try_send(());
-        }
-        result
-    }
-}
-
 const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
 
 pub struct SyncWorker<RT: Runtime> {
@@ -207,8 +179,15 @@ pub struct SyncWorker<RT: Runtime> {
     state: SyncState,
     host: ResolvedHostname,
 
+    // Sessions closed by other workers, one of which the client may resume.
+    // This worker's session is retained under `session_token` when it's
+    // dropped, if the client supports resumption.
+    sessions: Arc<RetainedSessions>,
+    session_token: Option<String>,
+    mutation_responses: VecDeque<ServerMessage>,
+
     rx: mpsc::UnboundedReceiver<(ClientMessage, tokio::time::Instant)>,
-    tx: SingleFlightSender,
+    tx: OutgoingSender,
 
     // Queue of pending functions or mutations. For time being, we only execute
     // a single one since this is less error prone model for the developer.
@@ -219,15 +198,25 @@ pub struct SyncWorker<RT: Runtime> {
 
     transition_future: Option<Fuse<BoxFuture<'static, anyhow::Result<TransitionState>>>>,
 
+    // Live aggregates the client has subscribed to. Each stream yields an
+    // `AggregateUpdated` message whenever the aggregate's value changes, and
+    // ends after an `AggregateFailed` message or when aborted via its handle.
+    aggregates: SelectAll<Abortable<BoxStream<'static, ServerMessage>>>,
+    aggregate_handles: BTreeMap<AggregateId, AbortHandle>,
+
     // Has an update been scheduled for the future?
     update_scheduled: bool,
+    // When the last update started, and the timer for an update that's
+    // waiting out the rest of the coalescing window after it.
+    last_update_start: Option<tokio::time::Instant>,
+    coalesce_timer: Option<Pin<Box<dyn FusedFuture<Output = ()> + Send>>>,
 
     connect_timer: Option<StatusTimer>,
 }
 
 enum QueryResult {
     Rerun {
-        result: Result<ConvexValue, RedactedJsError>,
+        result: Result<JsonPackedValue, RedactedJsError>,
         log_lines: RedactedLogLines,
         journal: SerializedQueryJournal,
     },
@@ -236,7 +225,7 @@ enum QueryResult {
 
 struct TransitionState {
     udf_results: Vec<(QueryId, QueryResult, Box<dyn SubscriptionTrait>)>,
-    state_modifications: BTreeMap<QueryId, StateModification<ConvexValue>>,
+    state_modifications: BTreeMap<QueryId, StateModification<JsonPackedValue>>,
     current_version: StateVersion,
     new_version: StateVersion,
     timer: StatusTimer,
@@ -248,8 +237,9 @@ impl<RT: Runtime> SyncWorker<RT> {
         rt: RT,
         host: ResolvedHostname,
         config: SyncWorkerConfig,
+        sessions: Arc<RetainedSessions>,
         rx: mpsc::UnboundedReceiver<(ClientMessage, tokio::time::Instant)>,
-        tx: SingleFlightSender,
+        tx: OutgoingSender,
     ) -> Self {
         let (mutation_sender, receiver) = mpsc::channel(OPERATION_QUEUE_BUFFER_SIZE);
         let mutation_futures = ReceiverStream::new(receiver).buffered(1); // Execute at most one operation at a time.
@@ -259,13 +249,20 @@ impl<RT: Runtime> SyncWorker<RT> {
             rt,
             state: SyncState::new(),
             host,
+            sessions,
+            session_token: None,
+            mutation_responses: VecDeque::new(),
             rx,
             tx,
             mutation_futures,
             mutation_sender,
             action_futures: FuturesUnordered::new(),
             transition_future: None,
+            aggregates: SelectAll::new(),
+            aggregate_handles: BTreeMap::new(),
             update_scheduled: false,
+            last_update_start: None,
+            coalesce_timer: None,
             connect_timer: Some(connect_timer()),
         }
     }
@@ -274,12 +271,46 @@ impl<RT: Runtime> SyncWorker<RT> {
         self.update_scheduled = true;
     }
 
+    /// Schedules an update for an invalidated query, unless the last update
+    /// started within the coalescing window, in which case the update waits
+    /// for the window to end so that a burst of commits only reruns the
+    /// query once. Invalidations spaced further apart than the window aren't
+    /// delayed.
+    fn schedule_invalidated_update(&mut self) {
+        if self.coalesce_timer.is_some() {
+            return;
+        }
+        let now = self.rt.monotonic_now();
+        match self.last_update_start {
+            Some(last_update_start)
+                if now < last_update_start + self.config.invalidation_coalesce_window =>
+            {
+                let remaining = last_update_start + self.config.invalidation_coalesce_window - now;
+                metrics::log_coalesced_invalidation();
+                self.coalesce_timer = Some(self.rt.wait(remaining));
+            },
+            _ => self.schedule_update(),
+        }
+    }
+
     /// Run the sync protocol worker, returning `Ok(())` on clean exit and `Err`
     /// if there's an exceptional protocol condition that should shutdown
     /// the WebSocket.
     pub async fn go(&mut self) -> anyhow::Result<()> {
+        let result = self.run().await;
+        if result.is_err() {
+            // Don't let the client resume a session that failed, since it
+            // would likely fail the same way.
+            self.session_token = None;
+        }
+        result
+    }
+
+    async fn run(&mut self) -> anyhow::Result<()> {
         let mut ping_timeout = self.rt.wait(HEARTBEAT_INTERVAL);
         let mut pending = future::pending().boxed().fuse();
+        let mut no_coalesce_timer: Pin<Box<dyn FusedFuture<Output = ()> + Send>> =
+            Box::pin(future::pending().fuse());
 
         // Create a new subscription client for every sync socket. Thus we don't require
         // the subscription client to auto-recover on connection failures.
@@ -312,6 +343,12 @@ impl<RT: Runtime> SyncWorker<RT> {
                         Some(m) => m?,
                         None => panic!("mutation_futures sender dropped prematurely"),
                     };
+                    if self.session_token.is_some() {
+                        if self.mutation_responses.len() == MAX_RETAINED_MUTATION_RESPONSES {
+                            self.mutation_responses.pop_front();
+                        }
+                        self.mutation_responses.push_back(message.clone());
+                    }
                     self.schedule_update();
                     Some(message)
                 },
@@ -319,8 +356,19 @@ impl<RT: Runtime> SyncWorker<RT> {
                     self.schedule_update();
                     Some(result?)
                 },
+                message = self.aggregates.select_next_some() => {
+                    if let ServerMessage::AggregateFailed { aggregate_id, .. } = message {
+                        self.aggregate_handles.remove(&aggregate_id);
+                    }
+                    Some(message)
+                },
                 result = self.state.next_invalidated_query().fuse() => {
                     let _ = result?;
+                    self.schedule_invalidated_update();
+                    None
+                },
+                _ = self.coalesce_timer.as_mut().unwrap_or(&mut no_coalesce_timer) => {
+                    self.coalesce_timer = None;
                     self.schedule_update();
                     None
                 },
@@ -347,12 +395,17 @@ impl<RT: Runtime> SyncWorker<RT> {
                 if self.tx.send((response, self.rt.monotonic_now())).is_err() {
                     break 'top;
                 }
+                if self.tx.buffer_size() > *SYNC_MAX_SEND_BUFFER_SIZE {
+                    metrics::log_send_buffer_full();
+                    anyhow::bail!(ErrorMetadata::overloaded(
+                        "SyncClientTooSlow",
+                        "The client fell too far behind on receiving messages"
+                    ));
+                }
             }
-            // Send update unless the send channel already contains enough transitions,
-            // and unless we are already computing an update.
-            if self.update_scheduled
-                && self.tx.transition_count() < *SYNC_MAX_SEND_TRANSITION_COUNT
-                && self.transition_future.is_none()
+            // Send update unless the client is backlogged on transitions, and
+            // unless we are already computing an update.
+            if self.update_scheduled && !self.tx.is_backlogged() && self.transition_future.is_none()
             {
                 // Always transition to the latest timestamp. In the future,
                 // when we have Sync Worker running on the edge, we can remove this
@@ -379,11 +432,37 @@ impl<RT: Runtime> SyncWorker<RT> {
                     .fuse(),
                 );
                 self.update_scheduled = false;
+                self.last_update_start = Some(self.rt.monotonic_now());
             }
         }
         Ok(())
     }
 
+    /// Picks up the session the client had before reconnecting, if it was
+    /// retained and the client agrees with it on what it was sent. Otherwise
+    /// the client starts a new session and resends its queries.
+    fn resume_session(&mut self, session_id: SessionId, resume: SessionResume) -> bool {
+        let retained = self
+            .sessions
+            .take(&resume.session_token, self.rt.monotonic_now());
+        let resumed = match retained {
+            Some(RetainedSession {
+                state,
+                mutation_responses,
+            }) if state.session_id() == Some(session_id) && state.matches_resume(&resume) => {
+                self.state = state;
+                self.mutation_responses = mutation_responses;
+                // Rerun the session's queries, which lost their subscriptions
+                // when it was retained.
+                self.schedule_update();
+                true
+            },
+            _ => false,
+        };
+        metrics::log_session_resume(resumed);
+        resumed
+    }
+
     pub fn identity_version(&self) -> IdentityVersion {
         self.state.current_version().identity
     }
@@ -413,11 +492,23 @@ impl<RT: Runtime> SyncWorker<RT> {
                 last_close_reason,
                 max_observed_timestamp,
                 connection_count,
+                protocol_version,
+                resume,
             } => {
                 if let Some(timer) = self.connect_timer.take() {
                     timer.finish();
                 }
+                let resumed = match resume {
+                    Some(resume) if protocol_version >= SESSION_RESUME_PROTOCOL_VERSION => {
+                        self.resume_session(session_id, resume)
+                    },
+                    _ => false,
+                };
                 self.state.set_session_id(session_id);
+                if protocol_version >= QUERY_PATCH_PROTOCOL_VERSION {
+                    self.state
+                        .enable_query_patches(self.config.query_patch_min_size);
+                }
                 if let Some(max_observed_timestamp) = max_observed_timestamp {
                     let latest_timestamp = *self
                         .api
@@ -439,7 +530,24 @@ impl<RT: Runtime> SyncWorker<RT> {
                         );
                     }
                 }
-                metrics::log_connect(last_close_reason, connection_count)
+                metrics::log_connect(last_close_reason, connection_count);
+                if protocol_version >= SESSION_RESUME_PROTOCOL_VERSION {
+                    let session_token = self.rt.new_uuid_v4().to_string();
+                    self.session_token = Some(session_token.clone());
+                    // Replay the mutation responses before `Connected` so the
+                    // client only resends mutations that never got one. Send
+                    // errors are ignored since the main loop exits once the
+                    // websocket closes.
+                    let now = self.rt.monotonic_now();
+                    for response in self.mutation_responses.iter().cloned() {
+                        let _ = self.tx.send((response, now));
+                    }
+                    let connected = ServerMessage::Connected {
+                        session_token,
+                        resumed,
+                    };
+                    let _ = self.tx.send((connected, now));
+                }
             },
             ClientMessage::ModifyQuerySet {
                 base_version,
@@ -455,12 +563,26 @@ impl<RT: Runtime> SyncWorker<RT> {
                 udf_path,
                 args,
                 component_path,
+                mutation_id,
             } => {
+                if let Some(ref mutation_id) = mutation_id {
+                    anyhow::ensure!(
+                        !mutation_id.is_empty() && mutation_id.len() <= MAX_MUTATION_ID_LEN,
+                        ErrorMetadata::bad_request(
+                            "InvalidMutationId",
+                            format!(
+                                "Mutation IDs must be between 1 and {MAX_MUTATION_ID_LEN} bytes \
+                                 long"
+                            ),
+                        )
+                    );
+                }
                 let identity = self.state.identity(self.rt.system_time())?;
                 let mutation_identifier =
                     self.state.session_id().map(|id| SessionRequestIdentifier {
                         session_id: id,
                         request_id,
+                        mutation_id,
                     });
                 let server_request_id = match self.state.session_id() {
                     Some(id) => RequestId::new_for_ws_session(id, request_id),
@@ -518,14 +640,16 @@ impl<RT: Runtime> SyncWorker<RT> {
                         let response = match result {
                             Ok(udf_return) => ServerMessage::MutationResponse {
                                 request_id,
-                                result: Ok(udf_return.value),
+                                result: Ok(JsonPackedValue::pack(udf_return.value)),
                                 ts: Some(udf_return.ts),
                                 log_lines: udf_return.log_lines.into(),
                             },
                             Err(RedactedMutationError { error, log_lines }) => {
                                 ServerMessage::MutationResponse {
                                     request_id,
-                                    result: Err(error.into_error_payload()),
+                                    result: Err(error
+                                        .into_error_payload()
+                                        .map_data(JsonPackedValue::pack)),
                                     ts: None,
                                     log_lines: log_lines.into(),
                                 }
@@ -607,13 +731,15 @@ impl<RT: Runtime> SyncWorker<RT> {
                     let response = match result {
                         Ok(udf_return) => ServerMessage::ActionResponse {
                             request_id,
-                            result: Ok(udf_return.value),
+                            result: Ok(JsonPackedValue::pack(udf_return.value)),
                             log_lines: udf_return.log_lines.into(),
                         },
                         Err(RedactedActionError { error, log_lines }) => {
                             ServerMessage::ActionResponse {
                                 request_id,
-                                result: Err(error.into_error_payload()),
+                                result: Err(error
+                                    .into_error_payload()
+                                    .map_data(JsonPackedValue::pack)),
                                 log_lines: log_lines.into(),
                             }
                         },
@@ -653,12 +779,96 @@ impl<RT: Runtime> SyncWorker<RT> {
                     Err(_) => (),
                 }
             },
+            ClientMessage::ModifyAggregateSet { modifications } => {
+                for modification in modifications {
+                    match modification {
+                        AggregateSetModification::Add(aggregate) => {
+                            self.add_aggregate(aggregate)?;
+                        },
+                        AggregateSetModification::Remove { aggregate_id } => {
+                            if let Some(handle) = self.aggregate_handles.remove(&aggregate_id) {
+                                handle.abort();
+                            }
+                        },
+                    }
+                }
+            },
         };
 
         timer.finish();
         Ok(())
     }
 
+    fn add_aggregate(&mut self, aggregate: Aggregate) -> anyhow::Result<()> {
+        let identity = self.state.identity(self.rt.system_time())?;
+        let aggregate_id = aggregate.aggregate_id;
+        let has_sum = aggregate.sum_field.is_some();
+        let api = self.api.clone();
+        let host = self.host.clone();
+        let updates = async move {
+            let (component_path, index_range, sum_field) = Self::parse_aggregate(aggregate)
+                .map_err(|e| {
+                    let msg = e.to_string();
+                    e.context(ErrorMetadata::bad_request("InvalidAggregate", msg))
+                })?;
+            api.subscribe_live_aggregate(
+                &host,
+                RequestId::new(),
+                identity,
+                component_path,
+                index_range,
+                sum_field,
+            )
+            .await
+        }
+        .map(|result| match result {
+            Ok(updates) => updates,
+            Err(e) => stream::once(async { Err(e) }).boxed(),
+        })
+        .flatten_stream()
+        .map(move |result| match result {
+            Ok(update) => ServerMessage::AggregateUpdated {
+                aggregate_id,
+                ts: update.ts,
+                count: update.count,
+                sum: has_sum.then(|| JsonPackedValue::pack(ConvexValue::Float64(update.sum))),
+            },
+            Err(mut e) => {
+                if !e.is_deterministic_user_error() {
+                    report_error(&mut e);
+                }
+                ServerMessage::AggregateFailed {
+                    aggregate_id,
+                    error_message: e.user_facing_message(),
+                }
+            },
+        })
+        .boxed();
+        let (handle, registration) = AbortHandle::new_pair();
+        if let Some(previous) = self.aggregate_handles.insert(aggregate_id, handle) {
+            previous.abort();
+        }
+        self.aggregates.push(Abortable::new(updates, registration));
+        Ok(())
+    }
+
+    fn parse_aggregate(
+        aggregate: Aggregate,
+    ) -> anyhow::Result<(ComponentPath, IndexRange, Option<FieldPath>)> {
+        let component_path = ComponentPath::deserialize(aggregate.component_path.as_deref())?;
+        let index_range = IndexRange {
+            index_name: aggregate.index_name.parse()?,
+            range: aggregate
+                .range
+                .into_iter()
+                .map(IndexRangeExpression::try_from)
+                .collect::<anyhow::Result<_>>()?,
+            order: Order::Asc,
+        };
+        let sum_field = aggregate.sum_field.map(|field| field.parse()).transpose()?;
+        Ok((component_path, index_range, sum_field))
+    }
+
     fn begin_update_queries(
         &mut self,
         new_ts: Timestamp,
@@ -672,15 +882,18 @@ impl<RT: Runtime> SyncWorker<RT> {
 
         let mut identity_version = current_version.identity;
         if let Some(new_identity) = pending_identity {
-            // If the identity version has changed, invalidate all existing tokens.
+            // If the identity version has changed, invalidate all existing tokens,
+            // unless functions would see the same identity. Queries and their server
+            // arguments only see the identity's attributes, which is also what the
+            // query cache is keyed on, so a refreshed token for the same user doesn't
+            // need them to rerun.
             // TODO(CX-737): Don't invalidate queries that don't examine auth state.
-            // TODO(CX-737): Don't invalidate the queries if the User the is the same
-            // only with refreshed token. This is a bit tricky because:
-            // - We need to prove that query does not depend on token issue/expiration time.
-            // - We need to make rpc to backend to compare the properties since Usher can't
-            // validate auth tokens. Alternatively, we make Usher be able to validate tokens
-            // long term.
-            self.state.take_subscriptions();
+            let current_identity = self.state.identity(self.rt.system_time()).ok();
+            if current_identity.map(|identity| identity.cache_key())
+                != Some(new_identity.cache_key())
+            {
+                self.state.take_subscriptions();
+            }
             self.state.insert_identity(new_identity);
             identity_version = new_identity_version;
         }
@@ -700,6 +913,7 @@ impl<RT: Runtime> SyncWorker<RT> {
         for modification in modifications {
             match modification {
                 QuerySetModification::Add(query) => {
+                    ServerArgs::parse(query.server_args.as_ref())?.check_args(&query.args)?;
                     self.state.insert(query)?;
                 },
                 QuerySetModification::Remove { query_id } => {
@@ -755,6 +969,8 @@ impl<RT: Runtime> SyncWorker<RT> {
                         // of a subscription. The sync worker is effectively the owner
                         // of the query so we do not want to re-use the original query request id.
                         let request_id = RequestId::new();
+                        let args = ServerArgs::parse(query.server_args.as_ref())?
+                            .resolve(query.args, &identity_)?;
                         let udf_return = match query.component_path {
                             None => {
                                 api.execute_public_query(
@@ -762,7 +978,7 @@ impl<RT: Runtime> SyncWorker<RT> {
                                     request_id,
                                     identity_,
                                     ExportPath::from(query.udf_path.canonicalize()),
-                                    query.args,
+                                    args,
                                     caller,
                                     ts,
                                     query.journal,
@@ -780,7 +996,7 @@ impl<RT: Runtime> SyncWorker<RT> {
                                     request_id,
                                     identity_,
                                     path,
-                                    query.args,
+                                    args,
                                     caller,
                                     ts,
                                     query.journal,
@@ -877,3 +1093,23 @@ impl<RT: Runtime> SyncWorker<RT> {
         Ok(transition)
     }
 }
+
+impl<RT: Runtime> Drop for SyncWorker<RT> {
+    fn drop(&mut self) {
+        // A transition in progress has already taken the client's pending
+        // modifications from the state, so the session can't be resumed.
+        if self.transition_future.is_some() {
+            return;
+        }
+        if let Some(session_token) = self.session_token.take() {
+            let mut state = mem::replace(&mut self.state, SyncState::new());
+            state.suspend();
+            let session = RetainedSession {
+                state,
+                mutation_responses: mem::take(&mut self.mutation_responses),
+            };
+            self.sessions
+                .retain(session_token, session, self.rt.monotonic_now());
+        }
+    }
+}
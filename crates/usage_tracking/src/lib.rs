@@ -283,6 +283,24 @@ impl UsageCounter {
                 egress: egress_size,
             });
         }
+        for (component_path, ingress_size) in stats.websocket_ingress_size {
+            usage_metrics.push(UsageEvent::WebSocketBandwidth {
+                id: execution_id.to_string(),
+                component_path: component_path.serialize(),
+                udf_id: udf_id.clone(),
+                ingress: ingress_size,
+                egress: 0,
+            });
+        }
+        for (component_path, egress_size) in stats.websocket_egress_size {
+            usage_metrics.push(UsageEvent::WebSocketBandwidth {
+                id: execution_id.to_string(),
+                component_path: component_path.serialize(),
+                udf_id: udf_id.clone(),
+                ingress: 0,
+                egress: egress_size,
+            });
+        }
     }
 }
 
@@ -506,7 +524,7 @@ impl FunctionUsageTracker {
             });
     }
 
-    // Tracks bandwidth usage from vector searches
+    // Tracks bandwidth usage from vector searches, and counts the search.
     //
     // Vector bandwidth is a surcharge on vector related bandwidth usage. As a
     // result it counts against both bandwidth egress and vector egress. It's an
@@ -539,7 +557,31 @@ impl FunctionUsageTracker {
             .mutate_entry_or_default(key.clone(), |count| *count += egress_size);
         state
             .vector_egress_size
-            .mutate_entry_or_default(key, |count| *count += egress_size);
+            .mutate_entry_or_default(key.clone(), |count| *count += egress_size);
+        state
+            .vector_searches
+            .mutate_entry_or_default(key, |count| *count += 1);
+    }
+
+    /// Tracks bytes received from (ingress) and sent to (egress) a websocket
+    /// server by an action.
+    pub fn track_websocket_bandwidth(
+        &self,
+        component_path: ComponentPath,
+        ingress_size: u64,
+        egress_size: u64,
+    ) {
+        let mut state = self.state.lock();
+        if ingress_size > 0 {
+            state
+                .websocket_ingress_size
+                .mutate_entry_or_default(component_path.clone(), |count| *count += ingress_size);
+        }
+        if egress_size > 0 {
+            state
+                .websocket_egress_size
+                .mutate_entry_or_default(component_path, |count| *count += egress_size);
+        }
     }
 }
 
@@ -611,6 +653,10 @@ pub struct FunctionUsageStats {
     pub database_egress_size: WithHeapSize<BTreeMap<(ComponentPath, TableName), u64>>,
     pub vector_ingress_size: WithHeapSize<BTreeMap<(ComponentPath, TableName), u64>>,
     pub vector_egress_size: WithHeapSize<BTreeMap<(ComponentPath, TableName), u64>>,
+    pub websocket_ingress_size: WithHeapSize<BTreeMap<ComponentPath, u64>>,
+    pub websocket_egress_size: WithHeapSize<BTreeMap<ComponentPath, u64>>,
+    /// How many vector searches were run, by table searched.
+    pub vector_searches: WithHeapSize<BTreeMap<(ComponentPath, TableName), u64>>,
 }
 
 impl FunctionUsageStats {
@@ -622,6 +668,7 @@ impl FunctionUsageStats {
             storage_write_bytes: self.storage_ingress_size.values().sum(),
             vector_index_read_bytes: self.vector_egress_size.values().sum(),
             vector_index_write_bytes: self.vector_ingress_size.values().sum(),
+            vector_searches: self.vector_searches.values().sum(),
         }
     }
 
@@ -657,6 +704,19 @@ impl FunctionUsageStats {
             self.vector_egress_size
                 .mutate_entry_or_default(key.clone(), |count| *count += egress_size);
         }
+
+        for (key, ingress_size) in other.websocket_ingress_size {
+            self.websocket_ingress_size
+                .mutate_entry_or_default(key, |count| *count += ingress_size);
+        }
+        for (key, egress_size) in other.websocket_egress_size {
+            self.websocket_egress_size
+                .mutate_entry_or_default(key, |count| *count += egress_size);
+        }
+        for (key, searches) in other.vector_searches {
+            self.vector_searches
+                .mutate_entry_or_default(key, |count| *count += searches);
+        }
     }
 }
 
@@ -712,6 +772,16 @@ mod usage_arbitrary {
                     0..=4,
                 )
                 .prop_map(WithHeapSize::from),
+                proptest::collection::btree_map(any::<ComponentPath>(), 0..=1024u64, 0..=4)
+                    .prop_map(WithHeapSize::from),
+                proptest::collection::btree_map(any::<ComponentPath>(), 0..=1024u64, 0..=4)
+                    .prop_map(WithHeapSize::from),
+                proptest::collection::btree_map(
+                    any::<(ComponentPath, TableName)>(),
+                    0..=1024u64,
+                    0..=4,
+                )
+                .prop_map(WithHeapSize::from),
             );
             strategies
                 .prop_map(
@@ -723,6 +793,9 @@ mod usage_arbitrary {
                         database_egress_size,
                         vector_ingress_size,
                         vector_egress_size,
+                        websocket_ingress_size,
+                        websocket_egress_size,
+                        vector_searches,
                     )| FunctionUsageStats {
                         storage_calls,
                         storage_ingress_size,
@@ -731,6 +804,9 @@ mod usage_arbitrary {
                         database_egress_size,
                         vector_ingress_size,
                         vector_egress_size,
+                        websocket_ingress_size,
+                        websocket_egress_size,
+                        vector_searches,
                     },
                 )
                 .boxed()
@@ -806,6 +882,13 @@ impl From<FunctionUsageStats> for FunctionUsageStatsProto {
             database_egress_size: to_by_tag_count(stats.database_egress_size.into_iter()),
             vector_ingress_size: to_by_tag_count(stats.vector_ingress_size.into_iter()),
             vector_egress_size: to_by_tag_count(stats.vector_egress_size.into_iter()),
+            websocket_ingress_size_by_component: to_by_component_count(
+                stats.websocket_ingress_size.into_iter(),
+            ),
+            websocket_egress_size_by_component: to_by_component_count(
+                stats.websocket_egress_size.into_iter(),
+            ),
+            vector_searches: to_by_tag_count(stats.vector_searches.into_iter()),
         }
     }
 }
@@ -823,6 +906,11 @@ impl TryFrom<FunctionUsageStatsProto> for FunctionUsageStats {
         let database_egress_size = from_by_tag_count(stats.database_egress_size)?.collect();
         let vector_ingress_size = from_by_tag_count(stats.vector_ingress_size)?.collect();
         let vector_egress_size = from_by_tag_count(stats.vector_egress_size)?.collect();
+        let websocket_ingress_size =
+            from_by_component_tag_count(stats.websocket_ingress_size_by_component)?.collect();
+        let websocket_egress_size =
+            from_by_component_tag_count(stats.websocket_egress_size_by_component)?.collect();
+        let vector_searches = from_by_tag_count(stats.vector_searches)?.collect();
 
         Ok(FunctionUsageStats {
             storage_calls,
@@ -832,6 +920,9 @@ impl TryFrom<FunctionUsageStatsProto> for FunctionUsageStats {
             database_egress_size,
             vector_ingress_size,
             vector_egress_size,
+            websocket_ingress_size,
+            websocket_egress_size,
+            vector_searches,
         })
     }
 }
@@ -846,6 +937,7 @@ pub struct AggregatedFunctionUsageStats {
     pub storage_write_bytes: u64,
     pub vector_index_read_bytes: u64,
     pub vector_index_write_bytes: u64,
+    pub vector_searches: u64,
 }
 
 #[cfg(test)]
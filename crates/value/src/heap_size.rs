@@ -38,6 +38,8 @@ use sync_types::{
     ErrorPayload,
     FunctionName,
     LogLinesMessage,
+    PatchOperation,
+    PatchPathSegment,
     ServerMessage,
     SessionId,
     StateModification,
@@ -779,6 +781,20 @@ impl<V: HeapSize> HeapSize for ServerMessage<V> {
             } => error_message.heap_size() + base_version.heap_size(),
             ServerMessage::FatalError { error_message } => error_message.heap_size(),
             ServerMessage::Ping => 0,
+            ServerMessage::Connected {
+                session_token,
+                resumed,
+            } => session_token.heap_size() + resumed.heap_size(),
+            ServerMessage::AggregateUpdated {
+                aggregate_id: _,
+                ts,
+                count,
+                sum,
+            } => ts.heap_size() + count.heap_size() + sum.heap_size(),
+            ServerMessage::AggregateFailed {
+                aggregate_id: _,
+                error_message,
+            } => error_message.heap_size(),
         }
     }
 }
@@ -792,6 +808,12 @@ impl<V: HeapSize> HeapSize for StateModification<V> {
                 log_lines,
                 journal,
             } => value.heap_size() + log_lines.heap_size() + journal.heap_size(),
+            StateModification::QueryPatched {
+                query_id: _,
+                patch,
+                log_lines,
+                journal,
+            } => estimate_vec_size(patch) + log_lines.heap_size() + journal.heap_size(),
             StateModification::QueryFailed {
                 query_id: _,
                 error_message,
@@ -809,6 +831,30 @@ impl<V: HeapSize> HeapSize for StateModification<V> {
     }
 }
 
+impl HeapSize for PatchPathSegment {
+    fn heap_size(&self) -> usize {
+        match self {
+            PatchPathSegment::Key(key) => key.heap_size(),
+            PatchPathSegment::Index(_) => 0,
+        }
+    }
+}
+
+impl HeapSize for PatchOperation {
+    fn heap_size(&self) -> usize {
+        match self {
+            PatchOperation::Set { path, value } => estimate_vec_size(path) + value.heap_size(),
+            PatchOperation::Remove { path } => estimate_vec_size(path),
+            PatchOperation::Splice {
+                path,
+                start: _,
+                delete_count: _,
+                values,
+            } => estimate_vec_size(path) + estimate_vec_size(values),
+        }
+    }
+}
+
 impl HeapSize for StateVersion {
     fn heap_size(&self) -> usize {
         self.query_set.heap_size() + self.identity.heap_size() + self.ts.heap_size()
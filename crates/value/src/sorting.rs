@@ -31,7 +31,10 @@ use byteorder::{
     WriteBytesExt,
 };
 
-use crate::ConvexValue;
+use crate::{
+    id_v6::DeveloperDocumentId,
+    ConvexValue,
+};
 
 const UNDEFINED_TAG: u8 = 0x1;
 
@@ -125,6 +128,21 @@ pub fn values_to_bytes(values: &[Option<ConvexValue>]) -> Vec<u8> {
     out
 }
 
+/// Index keys end with the document's ID, which is encoded as a string of
+/// base32 characters. None of those characters need escaping or can be
+/// mistaken for the string tag, so the ID can be split off the end without
+/// decoding the rest of the key. Returns the key's other values and the ID,
+/// or `None` if `bytes` doesn't end with an ID.
+pub fn split_trailing_id(bytes: &[u8]) -> Option<(&[u8], DeveloperDocumentId)> {
+    let (&TERMINATOR_BYTE, rest) = bytes.split_last()? else {
+        return None;
+    };
+    let tag_position = rest.iter().rposition(|&b| b == STRING_TAG)?;
+    let id = std::str::from_utf8(&rest[tag_position + 1..]).ok()?;
+    let id = DeveloperDocumentId::decode(id).ok()?;
+    Some((&bytes[..tag_position], id))
+}
+
 /// Once a Value or IndexKey has been encoded for sorting, it should not be
 /// necessary to decode the Value or IndexKey again. Therefore this is
 /// test-only.
@@ -575,6 +593,7 @@ mod tests {
         id_v6::DeveloperDocumentId,
         sorting::{
             sorting_decode::bytes_to_values,
+            split_trailing_id,
             TotalOrdF64,
         },
         values_to_bytes,
@@ -591,6 +610,24 @@ mod tests {
         TabletId,
     };
 
+    #[test]
+    fn test_split_trailing_id() -> anyhow::Result<()> {
+        let id = DeveloperDocumentId::new(TableNumber::try_from(1234)?, InternalId::MIN);
+        let values = vec![
+            Some(ConvexValue::from(-1)),
+            Some(ConvexValue::try_from("\x10")?),
+            None,
+        ];
+        let mut values_with_id = values.clone();
+        values_with_id.push(Some(ConvexValue::from(id)));
+        assert_eq!(
+            split_trailing_id(&values_to_bytes(&values_with_id)),
+            Some((&values_to_bytes(&values)[..], id))
+        );
+        assert_eq!(split_trailing_id(&values_to_bytes(&values)), None);
+        Ok(())
+    }
+
     #[test]
     fn test_roundtrip_trophies() -> anyhow::Result<()> {
         // The random portion of this ID starts with the 0xFF byte which
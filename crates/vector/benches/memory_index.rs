@@ -1,8 +1,11 @@
 use std::collections::BTreeMap;
 
-use common::types::{
-    Timestamp,
-    WriteTimestamp,
+use common::{
+    bootstrap_model::index::vector_index::VectorDistanceMetric,
+    types::{
+        Timestamp,
+        WriteTimestamp,
+    },
 };
 use criterion::{
     black_box,
@@ -27,7 +30,10 @@ pub fn criterion_benchmark(c: &mut Criterion) {
 
     let ts = Timestamp::must(1);
 
-    let mut index = MemoryVectorIndex::new(WriteTimestamp::Committed(ts));
+    let mut index = MemoryVectorIndex::new(
+        WriteTimestamp::Committed(ts),
+        VectorDistanceMetric::default(),
+    );
     let mut next_id = 1u128;
 
     for _ in 0..n {
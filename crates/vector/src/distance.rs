@@ -0,0 +1,85 @@
+use common::bootstrap_model::index::vector_index::VectorDistanceMetric;
+use qdrant_segment::{
+    spaces::{
+        metric::Metric,
+        simple::{
+            CosineMetric,
+            DotProductMetric,
+            EuclidMetric,
+        },
+    },
+    types::Distance,
+};
+
+/// The distance qdrant builds an index's segments with.
+pub(crate) fn qdrant_distance(metric: VectorDistanceMetric) -> Distance {
+    match metric {
+        VectorDistanceMetric::Cosine => Distance::Cosine,
+        VectorDistanceMetric::DotProduct => Distance::Dot,
+        VectorDistanceMetric::Euclidean => Distance::Euclid,
+    }
+}
+
+/// Prepares a vector for [`similarity`] the same way qdrant does before
+/// indexing or searching for it. Cosine similarity normalizes vectors, and
+/// the other metrics leave them as they are.
+pub(crate) fn preprocess(metric: VectorDistanceMetric, vector: Vec<f32>) -> Vec<f32> {
+    match metric {
+        VectorDistanceMetric::Cosine => CosineMetric::preprocess(vector),
+        VectorDistanceMetric::DotProduct => DotProductMetric::preprocess(vector),
+        VectorDistanceMetric::Euclidean => EuclidMetric::preprocess(vector),
+    }
+}
+
+/// Scores two preprocessed vectors the same way a qdrant segment does, so
+/// in-memory results can be ranked alongside results from disk.
+pub(crate) fn similarity(metric: VectorDistanceMetric, v1: &[f32], v2: &[f32]) -> f32 {
+    match metric {
+        VectorDistanceMetric::Cosine => CosineMetric::similarity(v1, v2),
+        VectorDistanceMetric::DotProduct => DotProductMetric::similarity(v1, v2),
+        VectorDistanceMetric::Euclidean => EuclidMetric::similarity(v1, v2),
+    }
+}
+
+/// Converts a score from qdrant, or from [`similarity`], into the `_score`
+/// returned from vector searches. Qdrant scores Euclidean matches by their
+/// negated squared distance, which we turn into the negated distance. Both
+/// rank results the same way.
+pub(crate) fn public_score(metric: VectorDistanceMetric, score: f32) -> f32 {
+    match metric {
+        VectorDistanceMetric::Cosine | VectorDistanceMetric::DotProduct => score,
+        VectorDistanceMetric::Euclidean => -score.abs().sqrt(),
+    }
+}
+
+/// The `_score` a vector search returns for a document's vector `v2`, when
+/// searching for `v1`.
+#[cfg(any(test, feature = "testing"))]
+pub fn vector_score(metric: VectorDistanceMetric, v1: &[f32], v2: &[f32]) -> f32 {
+    let v1 = preprocess(metric, v1.to_vec());
+    let v2 = preprocess(metric, v2.to_vec());
+    public_score(metric, similarity(metric, &v1, &v2))
+}
+
+#[cfg(test)]
+mod tests {
+    use common::bootstrap_model::index::vector_index::VectorDistanceMetric;
+
+    use super::vector_score;
+
+    #[test]
+    fn test_vector_scores() {
+        let v1 = [3.0, 4.0];
+        let v2 = [6.0, 8.0];
+        assert!((vector_score(VectorDistanceMetric::Cosine, &v1, &v2) - 1.0).abs() < 1e-6);
+        assert_eq!(
+            vector_score(VectorDistanceMetric::DotProduct, &v1, &v2),
+            50.0
+        );
+        assert_eq!(
+            vector_score(VectorDistanceMetric::Euclidean, &v1, &v2),
+            -5.0
+        );
+        assert_eq!(vector_score(VectorDistanceMetric::Euclidean, &v1, &v1), 0.0);
+    }
+}
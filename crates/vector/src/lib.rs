@@ -21,6 +21,7 @@ use qdrant_segment::data_types::vectors::{
 };
 use value::FieldPath;
 
+mod distance;
 pub mod id_tracker;
 mod memory_index;
 pub mod metrics;
@@ -31,7 +32,10 @@ mod searcher;
 mod vector_index_manager;
 
 #[cfg(any(test, feature = "testing"))]
-pub use self::qdrant_index::cosine_similarity;
+pub use self::{
+    distance::vector_score,
+    qdrant_index::cosine_similarity,
+};
 pub use self::{
     memory_index::MemoryVectorIndex,
     metrics::{
@@ -3,22 +3,26 @@ use std::{
     mem,
 };
 
-use common::types::{
-    Timestamp,
-    WriteTimestamp,
+use common::{
+    bootstrap_model::index::vector_index::VectorDistanceMetric,
+    types::{
+        Timestamp,
+        WriteTimestamp,
+    },
 };
 use imbl::{
     OrdMap,
     OrdSet,
     Vector,
 };
-use qdrant_segment::spaces::{
-    metric::Metric,
-    simple::CosineMetric,
-};
 use value::InternalId;
 
 use crate::{
+    distance::{
+        preprocess,
+        public_score,
+        similarity,
+    },
     qdrant_index::{
         NormalizedQdrantDocument,
         QdrantDocument,
@@ -32,6 +36,8 @@ use crate::{
 
 #[derive(Clone)]
 pub struct MemoryVectorIndex {
+    distance: VectorDistanceMetric,
+
     min_ts: WriteTimestamp,
     max_ts: WriteTimestamp,
 
@@ -45,8 +51,10 @@ pub struct MemoryVectorIndex {
 }
 
 impl MemoryVectorIndex {
-    pub fn new(base_ts: WriteTimestamp) -> Self {
+    pub fn new(base_ts: WriteTimestamp, distance: VectorDistanceMetric) -> Self {
         Self {
+            distance,
+
             min_ts: base_ts,
             max_ts: base_ts,
 
@@ -110,7 +118,7 @@ impl MemoryVectorIndex {
             }
         }
         if let Some(old_value) = old_value {
-            let normalized = NormalizedQdrantDocument::from(old_value);
+            let normalized = NormalizedQdrantDocument::new(old_value, self.distance);
             self.tombstones_size += normalized.size();
             self.tombstones.push_back((ts, normalized));
         }
@@ -119,7 +127,7 @@ impl MemoryVectorIndex {
             self.documents_size -= old_value.document.size();
         }
         if let Some(new_value) = new_value {
-            let normalized = NormalizedQdrantDocument::from(new_value);
+            let normalized = NormalizedQdrantDocument::new(new_value, self.distance);
             self.documents_size += normalized.size();
             let revision = Revision {
                 ts,
@@ -203,14 +211,14 @@ impl MemoryVectorIndex {
             self.min_ts,
         );
         let query_vector = Vec::from(query.vector.clone());
-        let query_vector = CosineMetric::preprocess(query_vector);
+        let query_vector = preprocess(self.distance, query_vector);
         let mut candidates = vec![];
 
         for (&id, revision) in &self.documents {
             if revision.document.matches(query) {
-                let distance = CosineMetric::similarity(&query_vector, &revision.document.vector);
+                let score = similarity(self.distance, &query_vector, &revision.document.vector);
                 candidates.push(VectorSearchQueryResult {
-                    score: distance,
+                    score: public_score(self.distance, score),
                     id,
                     ts: revision.ts,
                 });
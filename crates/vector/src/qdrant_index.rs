@@ -23,7 +23,10 @@ use std::{
 
 use atomic_refcell::AtomicRefCell;
 use common::{
-    bootstrap_model::index::vector_index::DeveloperVectorIndexConfig,
+    bootstrap_model::index::vector_index::{
+        DeveloperVectorIndexConfig,
+        VectorDistanceMetric,
+    },
     document::ResolvedDocument,
     knobs::VECTOR_INDEX_THREADS,
     persistence::DocumentStream,
@@ -51,10 +54,6 @@ use qdrant_segment::{
     entry::entry_point::SegmentEntry,
     json_path::JsonPath,
     segment::Segment,
-    spaces::{
-        metric::Metric,
-        simple::CosineMetric,
-    },
     types::{
         AnyVariants,
         Condition,
@@ -89,6 +88,11 @@ use value::{
 };
 
 use crate::{
+    distance::{
+        preprocess,
+        public_score,
+        qdrant_distance,
+    },
     id_tracker::VectorMemoryIdTracker,
     incorrect_vector_filter_field_error,
     metrics::{
@@ -123,6 +127,7 @@ pub struct QdrantSchema {
     dimension: usize,
     vector_field: FieldPath,
     filter_fields: BTreeSet<FieldPath>,
+    distance: VectorDistanceMetric,
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -137,6 +142,7 @@ impl QdrantSchema {
             dimension: u32::from(index_config.dimensions) as usize,
             vector_field: index_config.vector_field.clone(),
             filter_fields: index_config.filter_fields.clone(),
+            distance: index_config.distance,
         }
     }
 
@@ -333,7 +339,7 @@ impl QdrantSchema {
             let ts = u64::from_le_bytes(ts_bytes[..].try_into()?);
 
             let result = VectorSearchQueryResult {
-                score: qdrant_result.score,
+                score: public_score(self.distance, qdrant_result.score),
                 id: internal_id,
                 ts: WriteTimestamp::Committed(ts.try_into()?),
             };
@@ -357,7 +363,12 @@ impl QdrantSchema {
         // upfront, always set up the more complex directory.
         let memory_dir: PathBuf = tmpdir.path().join("memory");
         let id_tracker = Arc::new(AtomicRefCell::new(VectorMemoryIdTracker::new()));
-        let mutable_config = segment_config(self.dimension, true, *VECTOR_INDEX_THREADS);
+        let mutable_config = segment_config(
+            self.dimension,
+            qdrant_distance(self.distance),
+            true,
+            *VECTOR_INDEX_THREADS,
+        );
         let mut memory_segment = create_mutable_segment(
             &memory_dir,
             id_tracker.clone(),
@@ -463,7 +474,12 @@ impl QdrantSchema {
                 fs::create_dir_all(&indexing_path)?;
                 let disk_path = index_path.join("disk");
                 fs::create_dir_all(&disk_path)?;
-                let disk_config = segment_config(self.dimension, false, *VECTOR_INDEX_THREADS);
+                let disk_config = segment_config(
+                    self.dimension,
+                    qdrant_distance(self.distance),
+                    false,
+                    *VECTOR_INDEX_THREADS,
+                );
                 build_disk_segment(&memory_segment, &indexing_path, &disk_path, disk_config)
             },
         }?;
@@ -524,9 +540,7 @@ impl QdrantDocument {
 
 #[cfg(any(test, feature = "testing"))]
 pub fn cosine_similarity(v1: &[f32], v2: &[f32]) -> f32 {
-    let v1 = CosineMetric::preprocess(v1.to_vec());
-    let v2 = CosineMetric::preprocess(v2.to_vec());
-    CosineMetric::similarity(&v1, &v2)
+    crate::distance::vector_score(VectorDistanceMetric::Cosine, v1, v2)
 }
 
 // NB: For cosine similarity, we need to normalize vectors before indexing them.
@@ -537,14 +551,13 @@ pub struct NormalizedQdrantDocument {
     pub filter_fields: BTreeMap<FieldPath, Vec<u8>>,
 }
 
-impl From<QdrantDocument> for NormalizedQdrantDocument {
-    fn from(value: QdrantDocument) -> Self {
-        let vector = Vec::from(value.vector);
-        let vector = CosineMetric::preprocess(vector);
+impl NormalizedQdrantDocument {
+    pub fn new(document: QdrantDocument, distance: VectorDistanceMetric) -> Self {
+        let vector = preprocess(distance, Vec::from(document.vector));
         Self {
-            internal_id: value.internal_id,
+            internal_id: document.internal_id,
             vector,
-            filter_fields: value.filter_fields,
+            filter_fields: document.filter_fields,
         }
     }
 }
@@ -595,6 +608,7 @@ impl From<QdrantSchema> for proto::VectorIndexConfig {
             dimension: value.dimension as u32,
             vector_field_path: Some(value.vector_field.into()),
             filter_fields: value.filter_fields.into_iter().map(|f| f.into()).collect(),
+            distance: (!value.distance.is_default()).then(|| value.distance.to_string()),
         }
     }
 }
@@ -612,10 +626,16 @@ impl TryFrom<proto::VectorIndexConfig> for QdrantSchema {
             .into_iter()
             .map(|f| f.try_into())
             .collect::<Result<_, _>>()?;
+        let distance = value
+            .distance
+            .map(|distance| VectorDistanceMetric::parse(&distance))
+            .transpose()?
+            .unwrap_or_default();
         Ok(QdrantSchema {
             dimension: value.dimension as usize,
             vector_field,
             filter_fields,
+            distance,
         })
     }
 }
@@ -91,6 +91,7 @@ pub(crate) const DEFAULT_VECTOR_NAME: &str = "default_vector";
 
 pub(crate) fn segment_config(
     dimension: usize,
+    distance: Distance,
     mutable: bool,
     max_indexing_threads: usize,
 ) -> SegmentConfig {
@@ -127,7 +128,7 @@ pub(crate) fn segment_config(
     };
     let vector_data_config = VectorDataConfig {
         size: dimension,
-        distance: Distance::Cosine,
+        distance,
         storage_type: vector_storage_type,
         index,
         quantization_config: None,
@@ -165,7 +166,7 @@ pub fn create_mutable_segment(
     let vector_storage = open_appendable_memmap_vector_storage(
         &vector_storage_path,
         dimension,
-        Distance::Cosine,
+        segment_config.vector_data[DEFAULT_VECTOR_NAME].distance,
         &stopped,
     )?;
     let point_count = id_tracker.borrow().total_point_count();
@@ -260,10 +261,26 @@ pub fn merge_disk_segments_hnsw(
     tmp_path: &Path,
     disk_path: &Path,
 ) -> anyhow::Result<VectorDiskSegmentValues> {
-    let segment_config = segment_config(dimension, false, 4);
+    // Each segment records the distance it was built with, and the merged
+    // segment has to use the same one.
+    let distance = segments
+        .first()
+        .map(|segment| segment_distance(segment))
+        .unwrap_or(Distance::Cosine);
+    anyhow::ensure!(
+        segments
+            .iter()
+            .all(|segment| segment_distance(segment) == distance),
+        "Can't merge vector segments built with different distances"
+    );
+    let segment_config = segment_config(dimension, distance, false, 4);
     merge_disk_segments(segments, tmp_path, disk_path, segment_config)
 }
 
+fn segment_distance(segment: &Segment) -> Distance {
+    segment.segment_config.vector_data[DEFAULT_VECTOR_NAME].distance
+}
+
 pub fn merge_disk_segments(
     segments: Vec<&Segment>,
     tmp_path: &Path,
@@ -573,6 +590,7 @@ mod tests {
         segment::Segment,
         types::{
             Condition,
+            Distance,
             ExtendedPointId,
             FieldCondition,
             Filter,
@@ -650,7 +668,7 @@ mod tests {
     ) -> anyhow::Result<(Segment, Arc<AtomicRefCell<VectorMemoryIdTracker>>)> {
         let memory_path = test_dir.path().join("memory");
         let id_tracker = Arc::new(AtomicRefCell::new(VectorMemoryIdTracker::new()));
-        let mutable_config = segment_config(dimensions, true, 4);
+        let mutable_config = segment_config(dimensions, Distance::Cosine, true, 4);
         let mut memory_segment =
             create_mutable_segment(&memory_path, id_tracker.clone(), dimensions, mutable_config)?;
 
@@ -670,7 +688,7 @@ mod tests {
     ) -> anyhow::Result<(Segment, Arc<AtomicRefCell<VectorMemoryIdTracker>>)> {
         let memory_path = test_dir.path().join("memory");
         let id_tracker = Arc::new(AtomicRefCell::new(VectorMemoryIdTracker::new()));
-        let mutable_config = segment_config(dimensions, true, 4);
+        let mutable_config = segment_config(dimensions, Distance::Cosine, true, 4);
         let mut memory_segment =
             create_mutable_segment(&memory_path, id_tracker.clone(), dimensions, mutable_config)?;
 
@@ -698,7 +716,7 @@ mod tests {
         let disk_path = test_dir.path().join("disk");
         fs::create_dir_all(&disk_path)?;
 
-        let disk_config = segment_config(dimensions, false, 4);
+        let disk_config = segment_config(dimensions, Distance::Cosine, false, 4);
         Ok(build_disk_segment(&memory_segment, &indexing_path, &disk_path, disk_config)?.paths)
     }
 
@@ -766,7 +784,7 @@ mod tests {
         let disk_path = test_dir.path().join("disk");
         fs::create_dir_all(&disk_path)?;
 
-        let disk_config = segment_config(DIMENSIONS, false, 4);
+        let disk_config = segment_config(DIMENSIONS, Distance::Cosine, false, 4);
         Ok(build_disk_segment(memory_segment, &indexing_path, &disk_path, disk_config)?.paths)
     }
 
@@ -1337,7 +1355,7 @@ mod tests {
         let new_paths = create_test_disk_segment(DIMENSIONS, &new_dir, vector.into_iter())?;
         let new_segment = unsafe_load_disk_segment(&new_paths).await?;
 
-        let config = segment_config(DIMENSIONS, false, 4);
+        let config = segment_config(DIMENSIONS, Distance::Cosine, false, 4);
         let merged_dir = tempfile::tempdir()?;
         let result =
             merge_disk_segments_tmpdir(vec![&initial_segment, &new_segment], &merged_dir, config)
@@ -1363,7 +1381,7 @@ mod tests {
         let new_paths = create_test_disk_segment(DIMENSIONS, &new_dir, vectors.into_iter())?;
         let new_segment = unsafe_load_disk_segment(&new_paths).await?;
 
-        let config = segment_config(DIMENSIONS, false, 4);
+        let config = segment_config(DIMENSIONS, Distance::Cosine, false, 4);
         let merged_dir = tempfile::tempdir()?;
         let VectorDiskSegmentValues { paths, .. } =
             merge_disk_segments_tmpdir(vec![&initial_segment, &new_segment], &merged_dir, config)?;
@@ -1402,7 +1420,7 @@ mod tests {
         let new_paths = create_test_disk_segment(DIMENSIONS, &new_dir, vector.clone().into_iter())?;
         let new_segment = unsafe_load_disk_segment(&new_paths).await?;
 
-        let config = segment_config(DIMENSIONS, false, 4);
+        let config = segment_config(DIMENSIONS, Distance::Cosine, false, 4);
         let merged_dir = tempfile::tempdir()?;
         let VectorDiskSegmentValues {
             paths: merged_paths,
@@ -1441,7 +1459,7 @@ mod tests {
             .map(|(segment, ..)| segment)
             .collect();
 
-        let config = segment_config(DIMENSIONS, false, 4);
+        let config = segment_config(DIMENSIONS, Distance::Cosine, false, 4);
         let merged_dir = tempfile::tempdir()?;
         let VectorDiskSegmentValues {
             paths: merged_paths,
@@ -1484,7 +1502,7 @@ mod tests {
             create_test_disk_segment(DIMENSIONS, &other_dir, other_vectors.clone().into_iter())?;
         let other_segment = unsafe_load_disk_segment(&other_paths).await?;
 
-        let config = segment_config(DIMENSIONS, false, 4);
+        let config = segment_config(DIMENSIONS, Distance::Cosine, false, 4);
         let merged_dir = tempfile::tempdir()?;
         let VectorDiskSegmentValues {
             paths: merged_paths,
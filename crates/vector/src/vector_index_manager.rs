@@ -267,7 +267,8 @@ impl VectorIndexManager {
             (None, Some(insertion)) => {
                 let metadata = IndexMetadata::try_from(insertion.value().clone().0)?;
                 if let IndexConfig::Vector {
-                    ref on_disk_state, ..
+                    ref on_disk_state,
+                    ref developer_config,
                 } = metadata.config
                 {
                     let VectorIndexState::Backfilling(state) = on_disk_state else {
@@ -279,7 +280,7 @@ impl VectorIndexManager {
                     self.indexes.insert(
                         insertion.id().internal_id(),
                         index,
-                        MemoryVectorIndex::new(ts),
+                        MemoryVectorIndex::new(ts, developer_config.distance),
                     );
 
                     metrics::log_index_created()
@@ -44,6 +44,9 @@ export class ConvexHttpClient {
   private auth?: string;
   private adminAuth?: string;
   private encodedTsPromise?: Promise<string>;
+  // Commit timestamp of the last mutation, so later queries observe its
+  // writes.
+  private lastMutationTs?: string;
   private debug: boolean;
   private fetchOptions?: FetchOptions;
   private logger: Logger;
@@ -249,7 +252,11 @@ export class ConvexHttpClient {
       path: name,
       format: "convex_encoded_json",
       args,
-      ...(timestamp ? { ts: timestamp } : {}),
+      ...(timestamp
+        ? { ts: timestamp }
+        : this.lastMutationTs
+          ? { minTs: this.lastMutationTs }
+          : {}),
     });
     const endpoint = timestamp
       ? `${this.address}/api/query_at_ts`
@@ -335,6 +342,9 @@ export class ConvexHttpClient {
     }
     switch (respJSON.status) {
       case "success":
+        if (respJSON.ts !== undefined) {
+          this.lastMutationTs = respJSON.ts;
+        }
         return jsonToConvex(respJSON.value);
       case "error":
         if (respJSON.errorData !== undefined) {
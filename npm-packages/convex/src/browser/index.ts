@@ -34,4 +34,5 @@ export { ConvexHttpClient } from "./http_client.js";
 export type { QueryJournal } from "./sync/protocol.js";
 /** @internal */
 export type { UserIdentityAttributes } from "./sync/protocol.js";
+export type { ServerArgs, ServerValue } from "./sync/protocol.js";
 export type { FunctionResult } from "./sync/function_result.js";
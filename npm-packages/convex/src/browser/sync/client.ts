@@ -19,11 +19,15 @@ import {
 } from "./optimistic_updates_impl.js";
 import {
   ActionRequest,
+  ClientMessage,
   MutationRequest,
   QueryId,
   QueryJournal,
   RequestId,
+  ServerArgs,
   ServerMessage,
+  SessionResume,
+  SYNC_PROTOCOL_VERSION,
   TS,
   UserIdentityAttributes,
 } from "./protocol.js";
@@ -130,6 +134,12 @@ export interface SubscribeOptions {
    * @internal
    */
   componentPath?: string;
+
+  /**
+   * Arguments the server fills in, like the logged in user's subject. Queries
+   * using these don't need to be resubscribed to when the user changes.
+   */
+  serverArgs?: ServerArgs;
 }
 
 /**
@@ -145,6 +155,15 @@ export interface MutationOptions {
    * Once the mutation completes, the update will be rolled back.
    */
   optimisticUpdate?: OptimisticUpdate<any>;
+  /**
+   * An ID for this mutation that stays the same when it's retried.
+   *
+   * Retries of a mutation from the same client are already deduplicated. Pass
+   * the same `mutationId` when retrying from a new client, like after a page
+   * reload, and the mutation will run at most once, with every attempt getting
+   * the same result.
+   */
+  mutationId?: string;
 }
 
 /**
@@ -171,6 +190,14 @@ export class BaseConvexClient {
   private readonly debug: boolean;
   private readonly logger: Logger;
   private maxObservedTimestamp: TS | undefined;
+  // The token the server issued for resuming this session after a reconnect.
+  private sessionToken: string | undefined;
+  // Whether we asked the server to resume the session and are waiting for its
+  // `Connected` message to find out if it did. Nothing else is sent until then.
+  private awaitingConnected = false;
+  // Whether the session was resumed, if `Connected` arrived while the socket
+  // was paused. Reconnecting finishes once the socket resumes.
+  private pendingSessionResumed: boolean | undefined;
 
   /**
    * @param address - The url of your Convex deployment, often provided
@@ -235,7 +262,7 @@ export class BaseConvexClient {
       {
         authenticate: (token) => {
           const message = this.state.setAuth(token);
-          this.webSocketManager.sendMessage(message);
+          this.sendMessage(message);
         },
         stopSocket: () => this.webSocketManager.stop(),
         restartSocket: () => this.webSocketManager.restart(),
@@ -292,43 +319,56 @@ export class BaseConvexClient {
         onOpen: (reconnectMetadata: ReconnectMetadata) => {
           // We have a new WebSocket!
           this.mark("convexWebSocketOpen");
+          // A session token can only be used once, so don't try it again if
+          // this socket closes too.
+          const resume: SessionResume | undefined =
+            this.sessionToken !== undefined
+              ? {
+                  sessionToken: this.sessionToken,
+                  stateVersion: this.remoteQuerySet.stateVersion(),
+                  ...this.state.sentVersions(),
+                }
+              : undefined;
+          this.sessionToken = undefined;
+          this.pendingSessionResumed = undefined;
           this.webSocketManager.sendMessage({
             ...reconnectMetadata,
             type: "Connect",
             sessionId: this._sessionId,
             maxObservedTimestamp: this.maxObservedTimestamp,
+            protocolVersion: SYNC_PROTOCOL_VERSION,
+            resume,
           });
 
-          // Throw out our remote query, reissue queries
-          // and outstanding mutations, and reauthenticate.
-          const oldRemoteQueryResults = new Set(
-            this.remoteQuerySet.remoteQueryResults().keys(),
-          );
-          this.remoteQuerySet = new RemoteQuerySet(
-            (queryId) => this.state.queryPath(queryId),
-            this.logger,
-          );
-          const [querySetModification, authModification] = this.state.restart(
-            oldRemoteQueryResults,
-          );
-          if (authModification) {
-            this.webSocketManager.sendMessage(authModification);
-          }
-          this.webSocketManager.sendMessage(querySetModification);
-          for (const message of this.requestManager.restart()) {
-            this.webSocketManager.sendMessage(message);
+          if (resume !== undefined) {
+            // Hold onto our state until the server tells us whether it still
+            // has the session.
+            this.awaitingConnected = true;
+            this.state.pause();
+            return;
           }
+          this.awaitingConnected = false;
+          this.restart();
         },
         onResume: () => {
+          if (this.awaitingConnected) {
+            return;
+          }
+          if (this.pendingSessionResumed !== undefined) {
+            const resumed = this.pendingSessionResumed;
+            this.pendingSessionResumed = undefined;
+            this.finishConnecting(resumed);
+            return;
+          }
           const [querySetModification, authModification] = this.state.resume();
           if (authModification) {
-            this.webSocketManager.sendMessage(authModification);
+            this.sendMessage(authModification);
           }
           if (querySetModification) {
-            this.webSocketManager.sendMessage(querySetModification);
+            this.sendMessage(querySetModification);
           }
           for (const message of this.requestManager.resume()) {
-            this.webSocketManager.sendMessage(message);
+            this.sendMessage(message);
           }
         },
         onMessage: (serverMessage: ServerMessage) => {
@@ -375,6 +415,18 @@ export class BaseConvexClient {
               void this.webSocketManager.terminate();
               throw error;
             }
+            case "Connected": {
+              this.sessionToken = serverMessage.sessionToken;
+              if (this.awaitingConnected) {
+                this.awaitingConnected = false;
+                if (this.webSocketManager.isPaused()) {
+                  this.pendingSessionResumed = serverMessage.resumed;
+                } else {
+                  this.finishConnecting(serverMessage.resumed);
+                }
+              }
+              break;
+            }
             case "Ping":
               break; // do nothing
             default: {
@@ -393,6 +445,69 @@ export class BaseConvexClient {
     this.mark("convexClientConstructed");
   }
 
+  /**
+   * Send a message over the WebSocket, unless we're waiting to hear whether
+   * the server resumed our session.
+   *
+   * @returns Whether the message (might have been) sent.
+   */
+  private sendMessage(message: ClientMessage) {
+    if (this.awaitingConnected) {
+      return false;
+    }
+    return this.webSocketManager.sendMessage(message);
+  }
+
+  private finishConnecting(sessionResumed: boolean) {
+    if (sessionResumed) {
+      this.resumeSession();
+    } else {
+      this.restart();
+    }
+  }
+
+  /**
+   * Throw out our remote query results, reissue queries and outstanding
+   * mutations, and reauthenticate.
+   */
+  private restart() {
+    const oldRemoteQueryResults = new Set(
+      this.remoteQuerySet.remoteQueryResults().keys(),
+    );
+    this.remoteQuerySet = new RemoteQuerySet(
+      (queryId) => this.state.queryPath(queryId),
+      this.logger,
+    );
+    const [querySetModification, authModification] = this.state.restart(
+      oldRemoteQueryResults,
+    );
+    if (authModification) {
+      this.sendMessage(authModification);
+    }
+    this.sendMessage(querySetModification);
+    for (const message of this.requestManager.restart()) {
+      this.sendMessage(message);
+    }
+  }
+
+  /**
+   * Pick up where the previous connection left off after the server resumed
+   * our session, sending only what changed while we were disconnected.
+   */
+  private resumeSession() {
+    const [querySetModification, authModification] =
+      this.state.resumeSession();
+    if (authModification) {
+      this.sendMessage(authModification);
+    }
+    if (querySetModification) {
+      this.sendMessage(querySetModification);
+    }
+    for (const message of this.requestManager.restart(true)) {
+      this.sendMessage(message);
+    }
+  }
+
   /**
    * Return true if there is outstanding work from prior to the time of the most recent restart.
    * This indicates that the client has not proven itself to have gotten past the issue that
@@ -475,12 +590,12 @@ export class BaseConvexClient {
   /** @internal */
   setAdminAuth(value: string, fakeUserIdentity?: UserIdentityAttributes) {
     const message = this.state.setAdminAuth(value, fakeUserIdentity);
-    this.webSocketManager.sendMessage(message);
+    this.sendMessage(message);
   }
 
   clearAuth() {
     const message = this.state.clearAuth();
-    this.webSocketManager.sendMessage(message);
+    this.sendMessage(message);
   }
 
   /**
@@ -509,16 +624,17 @@ export class BaseConvexClient {
       argsObject,
       options?.journal,
       options?.componentPath,
+      options?.serverArgs,
     );
     if (modification !== null) {
-      this.webSocketManager.sendMessage(modification);
+      this.sendMessage(modification);
     }
     return {
       queryToken,
       unsubscribe: () => {
         const modification = unsubscribe();
         if (modification) {
-          this.webSocketManager.sendMessage(modification);
+          this.sendMessage(modification);
         }
       },
     };
@@ -673,8 +789,9 @@ export class BaseConvexClient {
       udfPath,
       componentPath,
       args: [convexToJson(mutationArgs)],
+      mutationId: options?.mutationId,
     };
-    const mightBeSent = this.webSocketManager.sendMessage(message);
+    const mightBeSent = this.sendMessage(message);
     return this.requestManager.request(message, mightBeSent);
   }
 
@@ -721,7 +838,7 @@ export class BaseConvexClient {
       args: [convexToJson(actionArgs)],
     };
 
-    const mightBeSent = this.webSocketManager.sendMessage(message);
+    const mightBeSent = this.sendMessage(message);
     return this.requestManager.request(message, mightBeSent);
   }
 
@@ -776,7 +893,7 @@ export class BaseConvexClient {
   private reportMarks() {
     if (this.debug) {
       const report = getMarksReport(this.sessionId);
-      this.webSocketManager.sendMessage({
+      this.sendMessage({
         type: "Event",
         eventType: "ClientConnect",
         event: report,
@@ -11,6 +11,7 @@ import {
   Transition,
   AdminAuthentication,
   UserIdentityAttributes,
+  ServerArgs,
 } from "./protocol.js";
 import {
   canonicalizeUdfPath,
@@ -25,6 +26,7 @@ type LocalQuery = {
   numSubscribers: number;
   journal?: QueryJournal;
   componentPath?: string;
+  serverArgs?: ServerArgs;
 };
 
 export class LocalSyncState {
@@ -42,6 +44,7 @@ export class LocalSyncState {
   private outstandingAuthOlderThanRestart: boolean;
   private paused: boolean;
   private pendingQuerySetModifications: Map<QueryId, AddQuery | RemoveQuery>;
+  private authChangedWhilePaused: boolean;
 
   constructor() {
     this.nextQueryId = 0;
@@ -53,6 +56,7 @@ export class LocalSyncState {
     this.outstandingAuthOlderThanRestart = false;
     this.paused = false;
     this.pendingQuerySetModifications = new Map();
+    this.authChangedWhilePaused = false;
   }
 
   hasSyncedPastLastReconnect(): boolean {
@@ -71,13 +75,18 @@ export class LocalSyncState {
     args: Record<string, Value>,
     journal?: QueryJournal,
     componentPath?: string,
+    serverArgs?: ServerArgs,
   ): {
     queryToken: QueryToken;
     modification: QuerySetModification | null;
     unsubscribe: () => QuerySetModification | null;
   } {
     const canonicalizedUdfPath = canonicalizeUdfPath(udfPath);
-    const queryToken = serializePathAndArgs(canonicalizedUdfPath, args);
+    const queryToken = serializePathAndArgs(
+      canonicalizedUdfPath,
+      args,
+      serverArgs,
+    );
 
     const existingEntry = this.querySet.get(queryToken);
 
@@ -97,6 +106,7 @@ export class LocalSyncState {
         numSubscribers: 1,
         journal,
         componentPath,
+        serverArgs,
       };
       this.querySet.set(queryToken, query);
       this.queryIdToToken.set(queryId, queryToken);
@@ -111,6 +121,7 @@ export class LocalSyncState {
         args: [convexToJson(args)],
         journal,
         componentPath,
+        serverArgs,
       };
 
       if (this.paused) {
@@ -137,6 +148,7 @@ export class LocalSyncState {
     for (const modification of transition.modifications) {
       switch (modification.type) {
         case "QueryUpdated":
+        case "QueryPatched":
         case "QueryFailed": {
           this.outstandingQueriesOlderThanRestart.delete(modification.queryId);
           const journal = modification.journal;
@@ -186,6 +198,8 @@ export class LocalSyncState {
     const baseVersion = this.identityVersion;
     if (!this.paused) {
       this.identityVersion = baseVersion + 1;
+    } else {
+      this.authChangedWhilePaused = true;
     }
     return {
       type: "Authenticate",
@@ -209,6 +223,8 @@ export class LocalSyncState {
     const baseVersion = this.identityVersion;
     if (!this.paused) {
       this.identityVersion = baseVersion + 1;
+    } else {
+      this.authChangedWhilePaused = true;
     }
     return {
       type: "Authenticate",
@@ -223,6 +239,8 @@ export class LocalSyncState {
     const baseVersion = this.identityVersion;
     if (!this.paused) {
       this.identityVersion = baseVersion + 1;
+    } else {
+      this.authChangedWhilePaused = true;
     }
     return {
       type: "Authenticate",
@@ -282,6 +300,7 @@ export class LocalSyncState {
         args: [convexToJson(localQuery.args)],
         journal: localQuery.journal,
         componentPath: localQuery.componentPath,
+        serverArgs: localQuery.serverArgs,
       };
       modifications.push(add);
 
@@ -316,17 +335,7 @@ export class LocalSyncState {
   }
 
   resume(): [QuerySetModification?, Authenticate?] {
-    const querySet: QuerySetModification | undefined =
-      this.pendingQuerySetModifications.size > 0
-        ? {
-            type: "ModifyQuerySet",
-            baseVersion: this.querySetVersion,
-            newVersion: ++this.querySetVersion,
-            modifications: Array.from(
-              this.pendingQuerySetModifications.values(),
-            ),
-          }
-        : undefined;
+    const querySet = this.pendingQuerySet();
     const authenticate: Authenticate | undefined =
       this.auth !== undefined
         ? {
@@ -341,9 +350,57 @@ export class LocalSyncState {
     return [querySet, authenticate];
   }
 
+  /**
+   * Unpause after the server resumed the session from the previous
+   * connection. The server already has the queries and identity from before
+   * the pause, so only changes made since need to be sent.
+   */
+  resumeSession(): [QuerySetModification?, Authenticate?] {
+    const querySet = this.pendingQuerySet();
+    let authenticate: Authenticate | undefined = undefined;
+    if (this.authChangedWhilePaused) {
+      const baseVersion = this.identityVersion++;
+      authenticate =
+        this.auth !== undefined
+          ? { type: "Authenticate", baseVersion, ...this.auth }
+          : { type: "Authenticate", tokenType: "None", baseVersion };
+    }
+
+    this.unpause();
+
+    return [querySet, authenticate];
+  }
+
+  /**
+   * The versions of the latest query set and identity sent to the server,
+   * which a resumed session must agree on.
+   */
+  sentVersions(): {
+    querySetVersion: QuerySetVersion;
+    identityVersion: IdentityVersion;
+  } {
+    return {
+      querySetVersion: this.querySetVersion,
+      identityVersion: this.identityVersion,
+    };
+  }
+
+  private pendingQuerySet(): QuerySetModification | undefined {
+    if (this.pendingQuerySetModifications.size === 0) {
+      return undefined;
+    }
+    return {
+      type: "ModifyQuerySet",
+      baseVersion: this.querySetVersion,
+      newVersion: ++this.querySetVersion,
+      modifications: Array.from(this.pendingQuerySetModifications.values()),
+    };
+  }
+
   private unpause() {
     this.paused = false;
     this.pendingQuerySetModifications.clear();
+    this.authChangedWhilePaused = false;
   }
 
   private removeSubscriber(
@@ -0,0 +1,44 @@
+import { test, expect } from "vitest";
+
+import { applyPatch } from "./patch.js";
+
+test("applyPatch sets nested values", () => {
+  const value = [
+    { name: "orinoco", balance: 100 },
+    { name: "tizoncito", balance: 100 },
+  ];
+  expect(
+    applyPatch(value, [{ op: "set", path: [1, "balance"], value: 105 }]),
+  ).toEqual([
+    { name: "orinoco", balance: 100 },
+    { name: "tizoncito", balance: 105 },
+  ]);
+});
+
+test("applyPatch replaces the root", () => {
+  expect(applyPatch({ a: 1 }, [{ op: "set", path: [], value: "b" }])).toEqual(
+    "b",
+  );
+});
+
+test("applyPatch removes keys and splices arrays", () => {
+  const value = { list: [1, 2, 3, 4], removed: true };
+  expect(
+    applyPatch(value, [
+      { op: "remove", path: ["removed"] },
+      { op: "splice", path: ["list"], start: 1, deleteCount: 2, values: [5] },
+    ]),
+  ).toEqual({ list: [1, 5, 4] });
+});
+
+test("applyPatch rejects invalid paths", () => {
+  expect(() =>
+    applyPatch({ a: 1 }, [{ op: "set", path: ["b"], value: 2 }]),
+  ).toThrow("Invalid patch path");
+  expect(() =>
+    applyPatch(
+      [1],
+      [{ op: "splice", path: [], start: 1, deleteCount: 1, values: [] }],
+    ),
+  ).toThrow("Invalid patch path");
+});
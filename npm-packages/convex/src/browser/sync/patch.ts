@@ -0,0 +1,104 @@
+import { JSONValue } from "../../values/index.js";
+import { PatchOperation } from "./protocol.js";
+
+/**
+ * Apply a patch from the server to the JSON encoding of a query result.
+ *
+ * `value` is modified in place, unless the patch replaces it entirely, so
+ * the returned value should be used instead.
+ */
+export function applyPatch(
+  value: JSONValue,
+  patch: PatchOperation[],
+): JSONValue {
+  for (const operation of patch) {
+    const { path } = operation;
+    switch (operation.op) {
+      case "set": {
+        if (path.length === 0) {
+          value = operation.value;
+          break;
+        }
+        const parent = valueAt(value, path.slice(0, -1));
+        const last = path[path.length - 1];
+        if (Array.isArray(parent) && typeof last === "number") {
+          if (last >= parent.length) {
+            throw invalidPath(path);
+          }
+          parent[last] = operation.value;
+        } else if (
+          isObject(parent) &&
+          typeof last === "string" &&
+          last in parent
+        ) {
+          parent[last] = operation.value;
+        } else {
+          throw invalidPath(path);
+        }
+        break;
+      }
+      case "remove": {
+        const parent = valueAt(value, path.slice(0, -1));
+        const last = path[path.length - 1];
+        if (
+          !isObject(parent) ||
+          typeof last !== "string" ||
+          !(last in parent)
+        ) {
+          throw invalidPath(path);
+        }
+        delete parent[last];
+        break;
+      }
+      case "splice": {
+        const array = valueAt(value, path);
+        if (
+          !Array.isArray(array) ||
+          operation.start + operation.deleteCount > array.length
+        ) {
+          throw invalidPath(path);
+        }
+        array.splice(
+          operation.start,
+          operation.deleteCount,
+          ...operation.values,
+        );
+        break;
+      }
+      default: {
+        // Enforce that the switch-case is exhaustive.
+        const _: never = operation;
+        throw new Error(`Invalid patch operation ${(operation as any).op}`);
+      }
+    }
+  }
+  return value;
+}
+
+function valueAt(value: JSONValue, path: (string | number)[]): JSONValue {
+  for (const segment of path) {
+    if (Array.isArray(value) && typeof segment === "number") {
+      if (segment >= value.length) {
+        throw invalidPath(path);
+      }
+      value = value[segment];
+    } else if (
+      isObject(value) &&
+      typeof segment === "string" &&
+      segment in value
+    ) {
+      value = value[segment];
+    } else {
+      throw invalidPath(path);
+    }
+  }
+  return value;
+}
+
+function isObject(value: JSONValue): value is { [key: string]: JSONValue } {
+  return typeof value === "object" && value !== null && !Array.isArray(value);
+}
+
+function invalidPath(path: (string | number)[]): Error {
+  return new Error(`Invalid patch path ${JSON.stringify(path)}`);
+}
@@ -24,7 +24,8 @@ export function parseServerMessage(
     case "FatalError":
     case "AuthError":
     case "ActionResponse":
-    case "Ping": {
+    case "Ping":
+    case "Connected": {
       return { ...encoded };
     }
     case "MutationResponse": {
@@ -66,14 +67,24 @@ export function encodeClientMessage(
       return { ...message };
     }
     case "Connect": {
-      if (message.maxObservedTimestamp !== undefined) {
-        return {
-          ...message,
-          maxObservedTimestamp: longToU64(message.maxObservedTimestamp),
-        };
-      } else {
-        return { ...message, maxObservedTimestamp: undefined };
-      }
+      const { maxObservedTimestamp, resume } = message;
+      return {
+        ...message,
+        maxObservedTimestamp:
+          maxObservedTimestamp !== undefined
+            ? longToU64(maxObservedTimestamp)
+            : undefined,
+        resume:
+          resume !== undefined
+            ? {
+                ...resume,
+                stateVersion: {
+                  ...resume.stateVersion,
+                  ts: longToU64(resume.stateVersion.ts),
+                },
+              }
+            : undefined,
+      };
     }
     default: {
       const _exhaustivenessCheck: never = message;
@@ -121,6 +132,21 @@ type Connect = {
   connectionCount: number;
   lastCloseReason: string | null;
   maxObservedTimestamp?: TS;
+  protocolVersion?: number;
+  resume?: SessionResume;
+};
+
+/**
+ * What the client knows about the session from its previous connection. The
+ * server only resumes the session if it agrees on all of it.
+ */
+export type SessionResume = {
+  // From the previous connection's `Connected` message.
+  sessionToken: string;
+  // The end version of the last transition the client received.
+  stateVersion: StateVersion;
+  querySetVersion: QuerySetVersion;
+  identityVersion: IdentityVersion;
 };
 
 export type AddQuery = {
@@ -133,8 +159,30 @@ export type AddQuery = {
    * @internal
    */
   componentPath?: string;
+  serverArgs?: ServerArgs;
 };
 
+/**
+ * A value the server fills in when it runs a query, resolved from the
+ * identity of the logged in user. These are `null` when no one is logged in.
+ *
+ * @public
+ */
+export type ServerValue =
+  | "identity.subject"
+  | "identity.tokenIdentifier"
+  | "identity.issuer"
+  | "identity.email"
+  | "identity.name";
+
+/**
+ * Query arguments the server fills in, from argument name to
+ * {@link ServerValue}.
+ *
+ * @public
+ */
+export type ServerArgs = Record<string, ServerValue>;
+
 export type RemoveQuery = {
   type: "Remove";
   queryId: QueryId;
@@ -155,6 +203,8 @@ export type MutationRequest = {
   // Execute the mutation on a specific component.
   // Only admin auth is allowed to run mutations on non-root components.
   componentPath?: string;
+  // Deduplicates retries of the mutation across sessions.
+  mutationId?: string;
 };
 
 export type ActionRequest = {
@@ -202,8 +252,13 @@ export type ClientMessage =
   | ActionRequest
   | Event;
 
-type EncodedConnect = Omit<Connect, "maxObservedTimestamp"> & {
+type EncodedSessionResume = Omit<SessionResume, "stateVersion"> & {
+  stateVersion: EncodedStateVersion;
+};
+
+type EncodedConnect = Omit<Connect, "maxObservedTimestamp" | "resume"> & {
   maxObservedTimestamp?: EncodedTS;
+  resume?: EncodedSessionResume;
 };
 
 type EncodedClientMessage =
@@ -228,6 +283,27 @@ export type StateVersion = {
 };
 type EncodedStateVersion = Omit<StateVersion, "ts"> & { ts: EncodedTS };
 
+/**
+ * The newest sync protocol version this client understands. Version 1 added
+ * `QueryPatched` modifications, and version 2 added session resumption.
+ */
+export const SYNC_PROTOCOL_VERSION = 2;
+
+/**
+ * A change to the JSON encoding of a query result. `path` is the object keys
+ * and array indices from the root of the result to the changed value.
+ */
+export type PatchOperation =
+  | { op: "set"; path: (string | number)[]; value: JSONValue }
+  | { op: "remove"; path: (string | number)[] }
+  | {
+      op: "splice";
+      path: (string | number)[];
+      start: number;
+      deleteCount: number;
+      values: JSONValue[];
+    };
+
 type StateModification =
   | {
       type: "QueryUpdated";
@@ -237,6 +313,14 @@ type StateModification =
       // Optional because old backend versions don't send this.
       journal?: QueryJournal;
     }
+  | {
+      type: "QueryPatched";
+      queryId: QueryId;
+      // Applies to the query's previous value.
+      patch: PatchOperation[];
+      logLines: LogLines;
+      journal?: QueryJournal;
+    }
   | {
       type: "QueryFailed";
       queryId: QueryId;
@@ -303,6 +387,13 @@ type FatalError = {
 type Ping = {
   type: "Ping";
 };
+export type Connected = {
+  type: "Connected";
+  // Presented in the next connection's `Connect` to resume this session.
+  sessionToken: string;
+  // Whether the session from the previous connection was resumed.
+  resumed: boolean;
+};
 
 export type ServerMessage =
   | Transition
@@ -310,7 +401,8 @@ export type ServerMessage =
   | ActionResponse
   | FatalError
   | AuthError
-  | Ping;
+  | Ping
+  | Connected;
 
 type EncodedTransition = Omit<Transition, "startVersion" | "endVersion"> & {
   startVersion: EncodedStateVersion;
@@ -325,4 +417,5 @@ type EncodedServerMessage =
   | ActionResponse
   | FatalError
   | AuthError
-  | Ping;
+  | Ping
+  | Connected;
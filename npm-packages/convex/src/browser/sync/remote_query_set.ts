@@ -1,22 +1,27 @@
-import { jsonToConvex } from "../../values/index.js";
+import { jsonToConvex, JSONValue } from "../../values/index.js";
 import { Long } from "../long.js";
 import { logForFunction, Logger } from "../logging.js";
 import { QueryId, StateVersion, Transition } from "./protocol.js";
 import { FunctionResult } from "./function_result.js";
+import { applyPatch } from "./patch.js";
 
 /**
- * A represention of the query results we've received on the current WebSocket
- * connection.
+ * A represention of the query results we've received in the current session,
+ * which spans several WebSocket connections if the server resumes it.
  */
 export class RemoteQuerySet {
   private version: StateVersion;
   private readonly remoteQuerySet: Map<QueryId, FunctionResult>;
+  // The JSON encoding of each successful result, which the server's patches
+  // apply to.
+  private readonly remoteQueryJson: Map<QueryId, JSONValue>;
   private readonly queryPath: (queryId: QueryId) => string | null;
   private readonly logger: Logger;
 
   constructor(queryPath: (queryId: QueryId) => string | null, logger: Logger) {
     this.version = { querySet: 0, ts: Long.fromNumber(0), identity: 0 };
     this.remoteQuerySet = new Map();
+    this.remoteQueryJson = new Map();
     this.queryPath = queryPath;
     this.logger = logger;
   }
@@ -41,7 +46,32 @@ export class RemoteQuerySet {
               logForFunction(this.logger, "info", "query", queryPath, line);
             }
           }
-          const value = jsonToConvex(modification.value ?? null);
+          const json = modification.value ?? null;
+          this.remoteQueryJson.set(modification.queryId, json);
+          const value = jsonToConvex(json);
+          this.remoteQuerySet.set(modification.queryId, {
+            success: true,
+            value,
+            logLines: modification.logLines,
+          });
+          break;
+        }
+        case "QueryPatched": {
+          const queryPath = this.queryPath(modification.queryId);
+          if (queryPath) {
+            for (const line of modification.logLines) {
+              logForFunction(this.logger, "info", "query", queryPath, line);
+            }
+          }
+          const previous = this.remoteQueryJson.get(modification.queryId);
+          if (previous === undefined) {
+            throw new Error(
+              `Received a patch for query ${modification.queryId} without a previous result`,
+            );
+          }
+          const json = applyPatch(previous, modification.patch);
+          this.remoteQueryJson.set(modification.queryId, json);
+          const value = jsonToConvex(json);
           this.remoteQuerySet.set(modification.queryId, {
             success: true,
             value,
@@ -56,6 +86,7 @@ export class RemoteQuerySet {
               logForFunction(this.logger, "info", "query", queryPath, line);
             }
           }
+          this.remoteQueryJson.delete(modification.queryId);
           const { errorData } = modification;
           this.remoteQuerySet.set(modification.queryId, {
             success: false,
@@ -68,6 +99,7 @@ export class RemoteQuerySet {
         }
         case "QueryRemoved": {
           this.remoteQuerySet.delete(modification.queryId);
+          this.remoteQueryJson.delete(modification.queryId);
           break;
         }
         default: {
@@ -84,6 +116,10 @@ export class RemoteQuerySet {
     return this.remoteQuerySet;
   }
 
+  stateVersion(): StateVersion {
+    return this.version;
+  }
+
   timestamp(): Long {
     return this.version.ts;
   }
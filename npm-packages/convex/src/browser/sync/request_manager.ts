@@ -155,7 +155,7 @@ export class RequestManager {
     return completeRequests;
   }
 
-  restart(): ClientMessage[] {
+  restart(sessionResumed = false): ClientMessage[] {
     // When we reconnect to the backend, re-request all requests that are safe
     // to be resend.
 
@@ -171,8 +171,11 @@ export class RequestManager {
       if (value.message.type === "Mutation") {
         // This includes ones that have already been completed because we still
         // want to tell the backend to transition the client past the completed
-        // timestamp. This is safe since mutations are idempotent.
-        allMessages.push(value.message);
+        // timestamp. This is safe since mutations are idempotent. A resumed
+        // session already transitions the client past them.
+        if (!sessionResumed || value.status.status !== "Completed") {
+          allMessages.push(value.message);
+        }
       } else {
         // Unlike mutations, actions are not idempotent. When we reconnect to the
         // backend, we don't know if it is safe to resend in-flight actions, so we
@@ -1,4 +1,5 @@
 import { convexToJson, Value } from "../../values/index.js";
+import { ServerArgs } from "./protocol.js";
 
 export function canonicalizeUdfPath(udfPath: string): string {
   const pieces = udfPath.split(":");
@@ -29,9 +30,12 @@ export type QueryToken = string;
 export function serializePathAndArgs(
   udfPath: string,
   args: Record<string, Value>,
+  serverArgs?: ServerArgs,
 ): QueryToken {
   return JSON.stringify({
     udfPath: canonicalizeUdfPath(udfPath),
     args: convexToJson(args),
+    // Only included when set so existing query tokens don't change.
+    ...(serverArgs !== undefined ? { serverArgs } : {}),
   });
 }
@@ -254,6 +254,16 @@ export class WebSocketManager {
     return this.socket.state;
   }
 
+  /**
+   * @returns Whether the socket is paused, so messages can't be sent.
+   */
+  isPaused(): boolean {
+    return (
+      (this.socket.state === "connecting" || this.socket.state === "ready") &&
+      this.socket.paused !== "no"
+    );
+  }
+
   /**
    * @param message - A ClientMessage to send.
    * @returns Whether the message (might have been) sent.
@@ -123,9 +123,17 @@ export const convexExport = new Command("export")
     );
   });
 
+type SnapshotExportProgress = {
+  component_path: string | null;
+  current_table: string | null;
+  documents_written: bigint;
+  total_documents: bigint;
+  bytes_written: bigint;
+};
+
 type SnapshotExportState =
   | { state: "requested" }
-  | { state: "in_progress" }
+  | { state: "in_progress"; progress?: SnapshotExportProgress | null }
   | {
       state: "completed";
       complete_ts: bigint;
@@ -155,8 +163,16 @@ async function waitForStableExportState(
         snapshotExportState = value;
         switch (snapshotExportState.state) {
           case "requested":
+            // Not a stable state.
+            break;
           case "in_progress":
             // Not a stable state.
+            if (snapshotExportState.progress) {
+              changeSpinner(
+                ctx,
+                formatExportProgress(snapshotExportState.progress),
+              );
+            }
             break;
           case "completed":
             onDone();
@@ -172,6 +188,13 @@ async function waitForStableExportState(
   return snapshotExportState!;
 }
 
+function formatExportProgress(progress: SnapshotExportProgress): string {
+  const table = progress.current_table
+    ? ` (${chalk.bold(progress.current_table)}${progress.component_path ? ` in ${progress.component_path}` : ""})`
+    : "";
+  return `Creating snapshot export: ${progress.documents_written.toLocaleString()}/${progress.total_documents.toLocaleString()} documents${table}`;
+}
+
 export async function startSnapshotExport(
   ctx: Context,
   args: {
@@ -191,13 +191,20 @@ export const convexImport = new Command("import")
       ctx: Context,
       state: InProgressImportState,
       checkpointCount: number,
+      progress: ImportProgress | null,
     ) => {
       stopSpinner(ctx);
       while ((state.checkpoint_messages?.length ?? 0) > checkpointCount) {
         logFinishedStep(ctx, state.checkpoint_messages![checkpointCount]);
         checkpointCount += 1;
       }
-      showSpinner(ctx, state.progress_message ?? "Importing");
+      const progressNotice = progress
+        ? ` (${formatSize(Number(progress.bytes_written))} written)`
+        : "";
+      showSpinner(
+        ctx,
+        `${state.progress_message ?? "Importing"}${progressNotice}`,
+      );
       return checkpointCount;
     };
     while (true) {
@@ -331,6 +338,13 @@ async function askToConfirmImportWithExistingImports(
   }
 }
 
+type ImportProgress = {
+  component_path: string | null;
+  current_table: string | null;
+  documents_written: bigint;
+  bytes_written: bigint;
+};
+
 type InProgressImportState = {
   state: "in_progress";
   progress_message?: string | undefined;
@@ -358,6 +372,7 @@ export async function waitForStableImportState(
       ctx: Context,
       state: InProgressImportState,
       checkpointCount: number,
+      progress: ImportProgress | null,
     ) => number;
   },
 ): Promise<SnapshotImportState> {
@@ -391,6 +406,7 @@ export async function waitForStableImportState(
               ctx,
               snapshotImportState,
               checkpointCount,
+              value.progress ?? null,
             );
             return;
         }
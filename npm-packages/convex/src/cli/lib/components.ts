@@ -4,6 +4,7 @@ import {
   changeSpinner,
   logFinishedStep,
   logMessage,
+  logWarning,
 } from "../../bundler/context.js";
 import {
   ProjectConfig,
@@ -304,6 +305,10 @@ async function startComponentsPushAndCodegen(
     logMessage(ctx, "startPush: " + JSON.stringify(startPushResponse, null, 2));
   }
 
+  for (const warning of startPushResponse.argsValidatorWarnings ?? []) {
+    logWarning(ctx, chalk.yellow(warning));
+  }
+
   if (options.codegen) {
     changeSpinner(ctx, "Generating TypeScript bindings...");
     await parentSpan.enterAsync("doFinalComponentCodegen", () =>
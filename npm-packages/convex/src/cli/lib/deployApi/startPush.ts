@@ -41,6 +41,9 @@ export const startPushResponse = looseObject({
   app: checkedComponent,
 
   schemaChange,
+
+  // Functions whose new argument validators reject recently observed calls.
+  argsValidatorWarnings: z.optional(z.array(z.string())),
 });
 export type StartPushResponse = z.infer<typeof startPushResponse>;
 
@@ -4,14 +4,19 @@ import ws from "ws";
 import { ConvexHttpClient } from "../../browser/http_client.js";
 import { BaseConvexClient } from "../../browser/index.js";
 import { PaginationResult, makeFunctionReference } from "../../server/index.js";
-import { Value, convexToJson } from "../../values/value.js";
+import { Value, convexToJson, jsonToConvex } from "../../values/value.js";
 import {
   Context,
   logFinishedStep,
   logMessage,
   logOutput,
 } from "../../bundler/context.js";
-import { waitForever, waitUntilCalled } from "./utils/utils.js";
+import {
+  deploymentFetch,
+  logAndHandleFetchError,
+  waitForever,
+  waitUntilCalled,
+} from "./utils/utils.js";
 
 export async function runFunctionAndLog(
   ctx: Context,
@@ -50,6 +55,50 @@ export async function runFunctionAndLog(
   }
 }
 
+/**
+ * Run a query as it would have run at `at`, which must be within the
+ * deployment's retention window.
+ */
+export async function runQueryAtTsAndLog(
+  ctx: Context,
+  deploymentUrl: string,
+  adminKey: string,
+  functionName: string,
+  args: Value,
+  at: Date,
+  componentPath?: string,
+) {
+  const fetch = deploymentFetch(deploymentUrl, adminKey);
+  let respJSON: any;
+  try {
+    const res = await fetch("/api/run_query_at_ts", {
+      method: "POST",
+      body: JSON.stringify({
+        path: functionName,
+        args: convexToJson(args),
+        componentPath,
+        // Nanoseconds since the epoch.
+        ts: (BigInt(at.getTime()) * BigInt(1000000)).toString(),
+        format: "convex_encoded_json",
+      }),
+    });
+    respJSON = await res.json();
+  } catch (err) {
+    return await logAndHandleFetchError(ctx, err);
+  }
+  if (respJSON.status !== "success") {
+    return await ctx.crash({
+      exitCode: 1,
+      errorType: "invalid filesystem or env vars",
+      printedMessage: `Failed to run function "${functionName}" at ${at.toISOString()}:\n${chalk.red(String(respJSON.errorMessage).trim())}`,
+    });
+  }
+  const result = jsonToConvex(respJSON.value);
+  if (result !== null) {
+    logOutput(ctx, formatValue(result));
+  }
+}
+
 export async function runPaginatedQuery(
   ctx: Context,
   deploymentUrl: string,
@@ -6,7 +6,11 @@ import {
   deploymentSelectionFromOptions,
 } from "./lib/api.js";
 import { actionDescription } from "./lib/command.js";
-import { runFunctionAndLog, subscribeAndLog } from "./lib/run.js";
+import {
+  runFunctionAndLog,
+  runQueryAtTsAndLog,
+  subscribeAndLog,
+} from "./lib/run.js";
 import { ensureHasConvexDependency } from "./lib/utils/utils.js";
 
 export const run = new Command("run")
@@ -24,6 +28,10 @@ export const run = new Command("run")
     "-w, --watch",
     "Watch a query, printing its result if the underlying data changes. Given function must be a query.",
   )
+  .option(
+    "--at <time>",
+    "Run a query as of a past time within the deployment's retention window, like `2024-06-01T12:00:00Z`. Given function must be a query.",
+  )
   .option("--push", "Push code to deployment before running the function.")
   // For backwards compatibility we still support --no-push which is a noop
   .addOption(new Option("--no-push").hideHelp())
@@ -104,6 +112,28 @@ export const run = new Command("run")
       );
     }
 
+    if (options.at !== undefined) {
+      const at = new Date(options.at);
+      if (isNaN(at.getTime()) || options.watch) {
+        return await ctx.crash({
+          exitCode: 1,
+          errorType: "fatal",
+          printedMessage: options.watch
+            ? "`--at` can't be combined with `--watch`."
+            : `Invalid time for \`--at\`: ${options.at}`,
+        });
+      }
+      return await runQueryAtTsAndLog(
+        ctx,
+        deploymentUrl,
+        adminKey,
+        functionName,
+        args,
+        at,
+        options.component,
+      );
+    }
+
     if (options.watch) {
       return await subscribeAndLog(
         ctx,
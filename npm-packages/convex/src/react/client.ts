@@ -3,7 +3,7 @@ import type { OptimisticUpdate, QueryToken } from "../browser/index.js";
 import React, { useContext, useMemo } from "react";
 import { convexToJson, Value } from "../values/index.js";
 import ReactDOM from "react-dom";
-import { QueryJournal } from "../browser/sync/protocol.js";
+import { QueryJournal, ServerArgs } from "../browser/sync/protocol.js";
 import {
   AuthTokenFetcher,
   BaseConvexClientOptions,
@@ -192,6 +192,12 @@ export interface WatchQueryOptions {
    * @internal
    */
   componentPath?: string;
+
+  /**
+   * Arguments the server fills in, like the logged in user's subject. Queries
+   * using these don't need to be resubscribed to when the user changes.
+   */
+  serverArgs?: ServerArgs;
 }
 
 /**
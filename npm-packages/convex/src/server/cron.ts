@@ -83,7 +83,22 @@ export type Interval =
     };
 
 /** @public */
-export type Hourly = {
+export type ScheduleOptions = {
+  /**
+   * An IANA time zone, like `"America/New_York"`. When set, the schedule's
+   * days, hours and minutes are in this time zone instead of UTC, so the job
+   * keeps its local time across daylight saving time changes.
+   */
+  timeZone?: string;
+  /**
+   * Delay every run by the same amount, up to this many seconds (at most a
+   * day), so that jobs on the same schedule don't all start at once.
+   */
+  jitterSeconds?: number;
+};
+
+/** @public */
+export type Hourly = ScheduleOptions & {
   /**
    * Minutes past the hour, 0-59.
    */
@@ -91,19 +106,19 @@ export type Hourly = {
 };
 
 /** @public */
-export type Daily = {
+export type Daily = ScheduleOptions & {
   /**
-   * 0-23, hour of day. Remember, this is UTC.
+   * 0-23, hour of day. Remember, this is UTC unless `timeZone` is set.
    */
   hourUTC: number;
   /**
-   * 0-59, minute of hour. Remember, this is UTC.
+   * 0-59, minute of hour. Remember, this is UTC unless `timeZone` is set.
    */
   minuteUTC: number;
 };
 
 /** @public */
-export type Monthly = {
+export type Monthly = ScheduleOptions & {
   /**
    * 1-31, day of month. Days greater that 28 will not run every month.
    */
@@ -118,7 +133,7 @@ export type Monthly = {
   minuteUTC: number;
 };
 /** @public */
-export type Weekly = {
+export type Weekly = ScheduleOptions & {
   /**
    * "monday", "tuesday", etc.
    */
@@ -153,6 +168,8 @@ export interface CronJob {
   name: string;
   args: JSONValue;
   schedule: Schedule;
+  timeZone?: string;
+  jitterSeconds?: number;
 }
 
 /**
@@ -220,6 +237,25 @@ function validatedMinuteOfHour(n: number) {
   return n;
 }
 
+function validatedTimeZone(s: string) {
+  // The time zone database is on the server, which rejects unknown zones.
+  if (typeof s !== "string" || s.length === 0) {
+    throw new Error(
+      "Time zone must be an IANA time zone like America/New_York",
+    );
+  }
+  return s;
+}
+
+function validatedJitterSeconds(n: number) {
+  if (!Number.isInteger(n) || n < 1 || n > 86400) {
+    throw new Error(
+      "Jitter must be an integer number of seconds from 1 to 86400",
+    );
+  }
+  return n;
+}
+
 function validatedCronString(s: string) {
   return s;
 }
@@ -267,6 +303,17 @@ export class Crons {
     };
   }
 
+  /** @internal */
+  setOptions(cronIdentifier: string, options: ScheduleOptions) {
+    const cron = this.crons[cronIdentifier];
+    if (options.timeZone !== undefined) {
+      cron.timeZone = validatedTimeZone(options.timeZone);
+    }
+    if (options.jitterSeconds !== undefined) {
+      cron.jitterSeconds = validatedJitterSeconds(options.jitterSeconds);
+    }
+  }
+
   /**
    * Schedule a mutation or action to run on an hourly basis.
    *
@@ -342,6 +389,7 @@ export class Crons {
       functionReference,
       ...args,
     );
+    this.setOptions(cronIdentifier, schedule);
   }
 
   /**
@@ -378,6 +426,7 @@ export class Crons {
       functionReference,
       ...args,
     );
+    this.setOptions(cronIdentifier, schedule);
   }
 
   /**
@@ -414,6 +463,7 @@ export class Crons {
       functionReference,
       ...args,
     );
+    this.setOptions(cronIdentifier, schedule);
   }
 
   /**
@@ -455,6 +505,7 @@ export class Crons {
       functionReference,
       ...args,
     );
+    this.setOptions(cronIdentifier, schedule);
   }
 
   /**
@@ -12,15 +12,33 @@ import {
   WithoutSystemFields,
 } from "./system_fields.js";
 
+/**
+ * Options for {@link GenericDatabaseReader.get}.
+ *
+ * @public
+ */
+export type GetOptions = {
+  /**
+   * Whether to look for the document among those moved to file storage by
+   * its table's archival policy if it's no longer in the table.
+   *
+   * Archived documents are fetched from file storage, so reading one is much
+   * slower than a normal read. Defaults to `false`.
+   */
+  includeArchived?: boolean;
+};
+
 interface BaseDatabaseReader<DataModel extends GenericDataModel> {
   /**
    * Fetch a single document from the database by its {@link values.GenericId}.
    *
    * @param id - The {@link values.GenericId} of the document to fetch from the database.
+   * @param options - {@link GetOptions}, e.g. to include archived documents.
    * @returns - The {@link GenericDocument} of the document at the given {@link values.GenericId}, or `null` if it no longer exists.
    */
   get<TableName extends TableNamesInDataModel<DataModel>>(
     id: GenericId<TableName>,
+    options?: GetOptions,
   ): Promise<DocumentByName<DataModel, TableName> | null>;
 
   /**
@@ -71,10 +89,12 @@ export interface BaseTableReader<
    * Fetch a single document from the table by its {@link values.GenericId}.
    *
    * @param id - The {@link values.GenericId} of the document to fetch from the database.
+   * @param options - {@link GetOptions}, e.g. to include archived documents.
    * @returns - The {@link GenericDocument} of the document at the given {@link values.GenericId}, or `null` if it no longer exists.
    */
   get(
     id: GenericId<TableName>,
+    options?: GetOptions,
   ): Promise<DocumentByName<DataModel, TableName> | null>;
 
   /**
@@ -0,0 +1,54 @@
+import { expect, test } from "vitest";
+import { eventStream, formatServerSentEvent } from "./event_stream.js";
+
+test("formatServerSentEvent", () => {
+  expect(formatServerSentEvent({ data: "hello" })).toEqual("data: hello\n\n");
+  expect(
+    formatServerSentEvent({
+      event: "update",
+      id: "7",
+      retry: 1000,
+      data: "line one\nline two\r\nline three",
+    }),
+  ).toEqual(
+    "event: update\nid: 7\nretry: 1000\ndata: line one\ndata: line two\ndata: line three\n\n",
+  );
+  expect(formatServerSentEvent({ data: { count: 1 } })).toEqual(
+    'data: {"count":1}\n\n',
+  );
+  expect(() => formatServerSentEvent({ event: "a\nb", data: "" })).toThrow(
+    /must not contain newlines/,
+  );
+  expect(() => formatServerSentEvent({ retry: -1, data: "" })).toThrow(
+    /non-negative integer/,
+  );
+});
+
+test("eventStream", async () => {
+  const response = eventStream(
+    async (stream) => {
+      stream.comment("start");
+      stream.send({ data: "first" });
+      await Promise.resolve();
+      stream.send({ event: "done", data: 2 });
+    },
+    { status: 201, headers: { "X-Custom": "yes" } },
+  );
+  expect(response.status).toEqual(201);
+  expect(response.headers.get("Content-Type")).toEqual(
+    "text/event-stream; charset=utf-8",
+  );
+  expect(response.headers.get("Cache-Control")).toEqual("no-cache");
+  expect(response.headers.get("X-Custom")).toEqual("yes");
+  expect(await response.text()).toEqual(
+    ": start\n\ndata: first\n\nevent: done\ndata: 2\n\n",
+  );
+});
+
+test("eventStream handler errors cut off the response", async () => {
+  const response = eventStream((stream) => {
+    stream.send({ data: "first" });
+    throw new Error("oops");
+  });
+  await expect(response.text()).rejects.toThrow("oops");
+});
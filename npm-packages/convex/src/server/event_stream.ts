@@ -0,0 +1,168 @@
+/**
+ * A single Server-Sent Event.
+ *
+ * See https://html.spec.whatwg.org/multipage/server-sent-events.html
+ *
+ * @public
+ */
+export type ServerSentEvent = {
+  /**
+   * The event's payload. Values other than strings are sent as JSON.
+   */
+  data: unknown;
+  /**
+   * The event type, which `EventSource` dispatches listeners on. Defaults to
+   * `"message"` on the client.
+   */
+  event?: string;
+  /**
+   * Sets the client's last event ID, which it sends back in the
+   * `Last-Event-ID` header when it reconnects.
+   */
+  id?: string;
+  /**
+   * How many milliseconds the client should wait before reconnecting.
+   */
+  retry?: number;
+};
+
+/**
+ * Writes events to a stream started with {@link eventStream}.
+ *
+ * @public
+ */
+export interface EventStreamWriter {
+  /**
+   * Send an event to the client right away.
+   */
+  send(event: ServerSentEvent): void;
+  /**
+   * Send a comment line, which clients ignore.
+   */
+  comment(text: string): void;
+  /**
+   * Whether the client has disconnected.
+   */
+  readonly closed: boolean;
+}
+
+/**
+ * Format an event in the `text/event-stream` format.
+ *
+ * @public
+ */
+export function formatServerSentEvent(event: ServerSentEvent): string {
+  let formatted = "";
+  if (event.event !== undefined) {
+    formatted += `event: ${singleLineField("event", event.event)}\n`;
+  }
+  if (event.id !== undefined) {
+    const id = singleLineField("id", event.id);
+    if (id.includes("\0")) {
+      throw new Error("Server-Sent Event id must not contain NULL characters");
+    }
+    formatted += `id: ${id}\n`;
+  }
+  if (event.retry !== undefined) {
+    if (!Number.isInteger(event.retry) || event.retry < 0) {
+      throw new Error(
+        `Server-Sent Event retry must be a non-negative integer, not ${event.retry}`,
+      );
+    }
+    formatted += `retry: ${event.retry}\n`;
+  }
+  const data =
+    typeof event.data === "string" ? event.data : JSON.stringify(event.data);
+  for (const line of data.split(/\r\n|\r|\n/)) {
+    formatted += `data: ${line}\n`;
+  }
+  return formatted + "\n";
+}
+
+/**
+ * Respond to an HTTP action with a stream of Server-Sent Events.
+ *
+ * The stream stays open until `handler` returns, and sends each event as soon
+ * as it's written. While no events are being sent, Convex sends keep-alive
+ * comments so proxies don't close the connection, and it ends streams that
+ * stay open longer than the deployment's maximum duration. Clients using
+ * `EventSource` reconnect automatically, so set `id` on events to resume the
+ * stream from the `Last-Event-ID` request header.
+ *
+ * ```js
+ * export const ticks = httpAction(async (ctx, request) => {
+ *   return eventStream(async (stream) => {
+ *     for (let i = 0; i < 10 && !stream.closed; i++) {
+ *       stream.send({ event: "tick", data: { i }, id: `${i}` });
+ *       await new Promise((resolve) => setTimeout(resolve, 1000));
+ *     }
+ *   });
+ * });
+ * ```
+ *
+ * @param handler - Writes events to the stream, which is closed when the
+ * returned promise resolves. If it rejects, the response is cut off.
+ * @param init - Options for the `Response`, like its status or extra headers.
+ * @returns A `Response` with the `text/event-stream` content type.
+ *
+ * @public
+ */
+export function eventStream(
+  handler: (stream: EventStreamWriter) => Promise<void> | void,
+  init?: ResponseInit,
+): Response {
+  const encoder = new TextEncoder();
+  let closed = false;
+  const body = new ReadableStream<Uint8Array>({
+    start(controller) {
+      const write = (text: string) => {
+        if (!closed) {
+          controller.enqueue(encoder.encode(text));
+        }
+      };
+      const writer: EventStreamWriter = {
+        send: (event) => write(formatServerSentEvent(event)),
+        comment: (text) => {
+          for (const line of text.split(/\r\n|\r|\n/)) {
+            write(`: ${line}\n`);
+          }
+          write("\n");
+        },
+        get closed() {
+          return closed;
+        },
+      };
+      void (async () => {
+        try {
+          await handler(writer);
+        } catch (e) {
+          if (!closed) {
+            closed = true;
+            controller.error(e);
+          }
+          return;
+        }
+        if (!closed) {
+          closed = true;
+          controller.close();
+        }
+      })();
+    },
+    cancel() {
+      closed = true;
+    },
+  });
+  const headers = new Headers(init?.headers);
+  headers.set("Content-Type", "text/event-stream; charset=utf-8");
+  if (!headers.has("Cache-Control")) {
+    headers.set("Cache-Control", "no-cache");
+  }
+  return new Response(body, { ...init, headers });
+}
+
+function singleLineField(name: string, value: string): string {
+  if (/[\r\n]/.test(value)) {
+    throw new Error(`Server-Sent Event ${name} must not contain newlines`);
+  }
+  return value;
+}
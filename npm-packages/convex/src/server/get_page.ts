@@ -0,0 +1,114 @@
+import { jsonToConvex, Value } from "../values/index.js";
+import { convexOrUndefinedToJson } from "../values/value.js";
+import { performAsyncSyscall } from "./impl/syscall.js";
+import { GenericMutationCtx, GenericQueryCtx } from "./registration.js";
+
+/**
+ * The values of a document's indexed fields, in index order and ending with
+ * `_creationTime` and `_id`. Fields the document doesn't have are
+ * `undefined`.
+ *
+ * A prefix of an index key, like `["red"]` for an index on
+ * `["color", "size"]`, stands for every document whose key starts with it.
+ *
+ * @public
+ */
+export type IndexKey = (Value | undefined)[];
+
+/**
+ * The result of {@link getPage}.
+ *
+ * @public
+ */
+export type GetPageResult = {
+  page: any[];
+  /**
+   * The index key of each document in `page`. Pass the last one as
+   * `startIndexKey` (or `endIndexKey` in descending order) with
+   * `startInclusive: false` to read the next page.
+   */
+  indexKeys: IndexKey[];
+  /**
+   * Whether there are more documents in the range after this page.
+   */
+  hasMore: boolean;
+};
+
+function indexKeyToJson(key: IndexKey) {
+  return key.map((value) => convexOrUndefinedToJson(value));
+}
+
+/**
+ * Read a page of documents from `index` on `table` between two index keys,
+ * in one call.
+ *
+ * Unlike {@link OrderedQuery.paginate}, pages are bounded by index keys
+ * rather than cursors, so any page can be read without reading the pages
+ * before it, and the bounds can be stored and compared like any other
+ * values. The page is reactive, including `hasMore`.
+ *
+ * @param args.index - The name of an index on `table`, or `"by_creation_time"`
+ * or `"by_id"`.
+ * @param args.startIndexKey - Where to start in index order. Defaults to the
+ * start of the index.
+ * @param args.startInclusive - Whether documents whose key starts with
+ * `startIndexKey` are included. Defaults to `false`.
+ * @param args.endIndexKey - Where to end in index order. Defaults to the end
+ * of the index.
+ * @param args.endInclusive - Whether documents whose key starts with
+ * `endIndexKey` are included. Defaults to `true`.
+ * @param args.order - Defaults to `"asc"`. In `"desc"` order the page starts
+ * from `endIndexKey`.
+ * @param args.maxRows - The most documents to return, up to 1024. Defaults to
+ * 100.
+ * @public
+ */
+export async function getPage(
+  _ctx: GenericQueryCtx<any> | GenericMutationCtx<any>,
+  args: {
+    table: string;
+    index: string;
+    startIndexKey?: IndexKey;
+    startInclusive?: boolean;
+    endIndexKey?: IndexKey;
+    endInclusive?: boolean;
+    order?: "asc" | "desc";
+    maxRows?: number;
+  },
+): Promise<GetPageResult> {
+  const result: { page: any[]; indexKeys: any[][]; hasMore: boolean } =
+    await performAsyncSyscall("1.0/getPage", {
+      table: args.table,
+      index: args.index,
+      start:
+        args.startIndexKey === undefined
+          ? null
+          : {
+              key: indexKeyToJson(args.startIndexKey),
+              inclusive: args.startInclusive ?? false,
+            },
+      end:
+        args.endIndexKey === undefined
+          ? null
+          : {
+              key: indexKeyToJson(args.endIndexKey),
+              inclusive: args.endInclusive ?? true,
+            },
+      order: args.order ?? "asc",
+      maxRows: args.maxRows ?? 100,
+    });
+  return {
+    page: result.page.map((document) => jsonToConvex(document)),
+    indexKeys: result.indexKeys.map((key) =>
+      key.map((value) =>
+        typeof value === "object" &&
+        value !== null &&
+        "$undefined" in value &&
+        Object.keys(value).length === 1
+          ? undefined
+          : jsonToConvex(value),
+      ),
+    ),
+    hasMore: result.hasMore,
+  };
+}
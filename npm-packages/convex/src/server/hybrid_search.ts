@@ -0,0 +1,104 @@
+import { Id } from "../values/value.js";
+import {
+  DocumentByInfo,
+  GenericDataModel,
+  GenericTableInfo,
+  NamedSearchIndex,
+  NamedTableInfo,
+  NamedVectorIndex,
+  SearchIndexNames,
+  TableNamesInDataModel,
+  VectorIndexNames,
+} from "./data_model.js";
+import { SearchFilter, SearchFilterBuilder } from "./search_filter_builder.js";
+import { FilterExpression, VectorFilterBuilder } from "./vector_search.js";
+
+/**
+ * An object with parameters for performing a hybrid search, which combines a
+ * full text search and a vector search over the same table.
+ *
+ * Each search ranks its results, and the rankings are fused with reciprocal
+ * rank fusion: a document scores `weight / (60 + rank)` for each search it
+ * matches, with ranks starting at 1.
+ *
+ * @public
+ */
+export interface HybridSearchQuery<
+  TableInfo extends GenericTableInfo,
+  TextIndexName extends SearchIndexNames<TableInfo>,
+  VectorIndexName extends VectorIndexNames<TableInfo>,
+> {
+  /**
+   * The name of the search index to run the text search against.
+   */
+  textIndex: TextIndexName;
+  /**
+   * The text search to run, like the search filter passed to
+   * {@link QueryInitializer.withSearchIndex}.
+   *
+   * e.g. `textSearch: q => q.search("body", "hello").eq("channel", "#general")`
+   */
+  textSearch: (
+    q: SearchFilterBuilder<
+      DocumentByInfo<TableInfo>,
+      NamedSearchIndex<TableInfo, TextIndexName>
+    >,
+  ) => SearchFilter;
+  /**
+   * The name of the vector index to run the vector search against.
+   */
+  vectorIndex: VectorIndexName;
+  /**
+   * The query vector.
+   *
+   * This must have the same length as the `dimensions` of the vector index.
+   */
+  vector: number[];
+  /**
+   * Optional filter expression for the vector search, like
+   * {@link VectorSearchQuery.filter}.
+   */
+  vectorFilter?: (
+    q: VectorFilterBuilder<
+      DocumentByInfo<TableInfo>,
+      NamedVectorIndex<TableInfo, VectorIndexName>
+    >,
+  ) => FilterExpression<boolean>;
+  /**
+   * The number of results to return. If specified, must be between 1 and 256
+   * inclusive. Each search contributes at most this many candidates.
+   *
+   * @default 10
+   */
+  limit?: number;
+  /**
+   * How much the text search contributes to each result's score. A weight
+   * of `0` skips the text search.
+   *
+   * @default 1
+   */
+  textWeight?: number;
+  /**
+   * How much the vector search contributes to each result's score. A weight
+   * of `0` skips the vector search.
+   *
+   * @default 1
+   */
+  vectorWeight?: number;
+}
+
+export type HybridSearch<
+  DataModel extends GenericDataModel,
+  TableName extends TableNamesInDataModel<DataModel>,
+  TextIndexName extends SearchIndexNames<NamedTableInfo<DataModel, TableName>>,
+  VectorIndexName extends VectorIndexNames<
+    NamedTableInfo<DataModel, TableName>
+  >,
+> = (
+  tableName: TableName,
+  query: HybridSearchQuery<
+    NamedTableInfo<DataModel, TableName>,
+    TextIndexName,
+    VectorIndexName
+  >,
+) => Promise<Array<{ _id: Id<TableName>; _score: number }>>;
@@ -51,5 +51,19 @@ export function setupActionCalls(requestId: string) {
       );
       return jsonToConvex(result);
     },
+    loadCheckpoint: async (): Promise<Value | null> => {
+      const result = await performAsyncSyscall("1.0/actions/loadCheckpoint", {
+        version,
+        requestId,
+      });
+      return jsonToConvex(result);
+    },
+    saveCheckpoint: async (value: Value): Promise<void> => {
+      await performAsyncSyscall("1.0/actions/saveCheckpoint", {
+        value: convexToJson(value),
+        version,
+        requestId,
+      });
+    },
   };
 }
@@ -8,6 +8,7 @@ import { performAsyncSyscall, performSyscall } from "./syscall.js";
 import {
   GenericDatabaseReader,
   GenericDatabaseReaderWithTable,
+  GetOptions,
   GenericDatabaseWriter,
   GenericDatabaseWriterWithTable,
 } from "../database.js";
@@ -17,7 +18,11 @@ import { validateArg } from "./validate.js";
 import { version } from "../../index.js";
 import { patchValueToJson } from "../../values/value.js";
 
-async function get(id: GenericId<string>, isSystem: boolean) {
+async function get(
+  id: GenericId<string>,
+  isSystem: boolean,
+  options?: GetOptions,
+) {
   validateArg(id, 1, "get", "id");
   if (typeof id !== "string") {
     throw new Error(
@@ -32,6 +37,12 @@ async function get(id: GenericId<string>, isSystem: boolean) {
     version,
   };
   const syscallJSON = await performAsyncSyscall("1.0/get", args);
+  if (syscallJSON === null && options?.includeArchived && !isSystem) {
+    const archivedJSON = await performAsyncSyscall("1.0/getArchived", {
+      id: convexToJson(id),
+    });
+    return jsonToConvex(archivedJSON) as GenericDocument;
+  }
 
   return jsonToConvex(syscallJSON) as GenericDocument;
 }
@@ -42,8 +53,8 @@ export function setupReader(): GenericDatabaseReader<GenericDataModel> {
   ): GenericDatabaseReader<GenericDataModel> &
     GenericDatabaseReaderWithTable<GenericDataModel> => {
     return {
-      get: async (id: GenericId<string>) => {
-        return await get(id, isSystem);
+      get: async (id: GenericId<string>, options?: GetOptions) => {
+        return await get(id, isSystem, options);
       },
       query: (tableName: string) => {
         return new TableReader(tableName, isSystem).query();
@@ -153,8 +164,8 @@ class TableReader {
     protected readonly isSystem: boolean,
   ) {}
 
-  async get(id: GenericId<string>) {
-    return get(id, this.isSystem);
+  async get(id: GenericId<string>, options?: GetOptions) {
+    return get(id, this.isSystem, options);
   }
 
   query() {
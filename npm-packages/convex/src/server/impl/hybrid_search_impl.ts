@@ -0,0 +1,61 @@
+import { performAsyncSyscall } from "./syscall.js";
+import { version } from "../../index.js";
+import { GenericDataModel, GenericTableInfo } from "../data_model.js";
+import { HybridSearch, HybridSearchQuery } from "../hybrid_search.js";
+import { SearchFilterBuilderImpl } from "./search_filter_builder_impl.js";
+import {
+  filterBuilderImpl,
+  serializeExpression,
+} from "./vector_search_impl.js";
+import { validateArg } from "./validate.js";
+
+export function setupActionHybridSearch(
+  requestId: string,
+): HybridSearch<GenericDataModel, string, string, string> {
+  return async (
+    tableName: string,
+    query: HybridSearchQuery<GenericTableInfo, string, string>,
+  ) => {
+    validateArg(tableName, 1, "hybridSearch", "tableName");
+    validateArg(query, 2, "hybridSearch", "query");
+    validateArg(query.textIndex, 2, "hybridSearch", "textIndex");
+    validateArg(query.textSearch, 2, "hybridSearch", "textSearch");
+    validateArg(query.vectorIndex, 2, "hybridSearch", "vectorIndex");
+    if (
+      !query.vector ||
+      !Array.isArray(query.vector) ||
+      query.vector.length === 0
+    ) {
+      throw Error("`vector` must be a non-empty Array in hybridSearch");
+    }
+
+    const textSearch = query.textSearch(SearchFilterBuilderImpl.new());
+    const textFilters = (textSearch as SearchFilterBuilderImpl).export();
+    const vectorFilters = query.vectorFilter
+      ? serializeExpression(query.vectorFilter(filterBuilderImpl))
+      : null;
+    const { results } = await performAsyncSyscall("1.0/actions/hybridSearch", {
+      requestId,
+      version,
+      query: {
+        textQuery: {
+          source: {
+            type: "Search",
+            indexName: tableName + "." + query.textIndex,
+            filters: textFilters,
+          },
+          operators: [],
+        },
+        vectorQuery: {
+          indexName: tableName + "." + query.vectorIndex,
+          vector: query.vector,
+          expressions: vectorFilters,
+        },
+        limit: query.limit,
+        textWeight: query.textWeight,
+        vectorWeight: query.vectorWeight,
+      },
+    });
+    return results;
+  };
+}
@@ -1,11 +1,21 @@
-import { Value, JSONValue, jsonToConvex } from "../../values/index.js";
+import {
+  Value,
+  JSONValue,
+  convexToJson,
+  jsonToConvex,
+} from "../../values/index.js";
 import { PaginationResult, PaginationOptions } from "../pagination.js";
 import { performAsyncSyscall, performSyscall } from "./syscall.js";
 import {
   filterBuilderImpl,
   serializeExpression,
 } from "./filter_builder_impl.js";
-import { Query, QueryInitializer } from "../query.js";
+import {
+  AggregateSpec,
+  Query,
+  QueryHints,
+  QueryInitializer,
+} from "../query.js";
 import { ExpressionOrValue, FilterBuilder } from "../filter_builder.js";
 import { GenericTableInfo } from "../data_model.js";
 import {
@@ -34,9 +44,16 @@ type Source =
       filters: ReadonlyArray<SerializedSearchFilter>;
     };
 
+type SerializedQueryHints = {
+  index?: string;
+  disableFilterPushdown?: boolean;
+  scanBudget?: number;
+};
+
 type SerializedQuery = {
   source: Source;
   operators: Array<QueryOperator>;
+  hints?: SerializedQueryHints;
 };
 
 export class QueryInitializerImpl
@@ -121,6 +138,18 @@ export class QueryInitializerImpl
     return this.fullTableScan().limit(n);
   }
 
+  hint(hints: QueryHints<GenericTableInfo>) {
+    return this.fullTableScan().hint(hints);
+  }
+
+  aggregate(spec: AggregateSpec<GenericTableInfo, any>): Promise<any> {
+    return this.fullTableScan().aggregate(spec);
+  }
+
+  withReferences(depth?: number): QueryImpl {
+    return this.fullTableScan().withReferences(depth);
+  }
+
   collect(): Promise<any[]> {
     return this.fullTableScan().collect();
   }
@@ -165,8 +194,13 @@ export class QueryImpl implements Query<GenericTableInfo> {
     | { type: "closed" }
     | { type: "consumed" };
 
-  constructor(query: SerializedQuery) {
+  // How many levels of references to replace with the documents they
+  // reference, or 0 to leave them as ids.
+  private referenceDepth: number;
+
+  constructor(query: SerializedQuery, referenceDepth = 0) {
     this.state = { type: "preparing", query };
+    this.referenceDepth = referenceDepth;
   }
 
   private takeQuery(): SerializedQuery {
@@ -213,7 +247,7 @@ export class QueryImpl implements Query<GenericTableInfo> {
       throw new Error("Queries may only specify order at most once");
     }
     query.source.order = order;
-    return new QueryImpl(query);
+    return new QueryImpl(query, this.referenceDepth);
   }
 
   filter(
@@ -226,14 +260,70 @@ export class QueryImpl implements Query<GenericTableInfo> {
     query.operators.push({
       filter: serializeExpression(predicate(filterBuilderImpl)),
     });
-    return new QueryImpl(query);
+    return new QueryImpl(query, this.referenceDepth);
   }
 
   limit(n: number): any {
     validateArg(n, 1, "limit", "n");
     const query = this.takeQuery();
     query.operators.push({ limit: n });
-    return new QueryImpl(query);
+    return new QueryImpl(query, this.referenceDepth);
+  }
+
+  hint(hints: QueryHints<GenericTableInfo>): any {
+    validateArg(hints, 1, "hint", "hints");
+    if (hints.scanBudget !== undefined) {
+      validateArgIsNonNegativeInteger(
+        hints.scanBudget,
+        1,
+        "hint",
+        "scanBudget",
+      );
+    }
+    const query = this.takeQuery();
+    if (hints.index !== undefined && query.source.type === "Search") {
+      throw new Error("Search queries can not hint an index.");
+    }
+    query.hints = {
+      ...query.hints,
+      ...(hints.index !== undefined ? { index: hints.index } : {}),
+      ...(hints.disableFilterPushdown !== undefined
+        ? { disableFilterPushdown: hints.disableFilterPushdown }
+        : {}),
+      ...(hints.scanBudget !== undefined
+        ? { scanBudget: hints.scanBudget }
+        : {}),
+    };
+    return new QueryImpl(query, this.referenceDepth);
+  }
+
+  withReferences(depth?: number): QueryImpl {
+    const referenceDepth = depth ?? 1;
+    validateArgIsNonNegativeInteger(
+      referenceDepth,
+      1,
+      "withReferences",
+      "depth",
+    );
+    const query = this.takeQuery();
+    if (query.source.type === "Search") {
+      throw new Error(
+        "Queries using `withSearchIndex` can't hydrate references.",
+      );
+    }
+    return new QueryImpl(query, referenceDepth);
+  }
+
+  private async hydrate(documents: any[]): Promise<any[]> {
+    if (this.referenceDepth === 0 || documents.length === 0) {
+      return documents;
+    }
+    const hydrated: JSONValue[] = await performAsyncSyscall("1.0/hydrate", {
+      documents: documents.map((document) => convexToJson(document)),
+      depth: this.referenceDepth,
+      version,
+    });
+    return hydrated.map((document) => jsonToConvex(document));
   }
 
   [Symbol.asyncIterator](): AsyncIterableIterator<any> {
@@ -242,6 +332,15 @@ export class QueryImpl implements Query<GenericTableInfo> {
   }
 
   async next(): Promise<IteratorResult<any>> {
+    const result = await this.nextUnhydrated();
+    if (result.done) {
+      return result;
+    }
+    const [value] = await this.hydrate([result.value]);
+    return { value, done: false };
+  }
+
+  private async nextUnhydrated(): Promise<IteratorResult<any>> {
     if (this.state.type === "closed" || this.state.type === "consumed") {
       throwClosedError(this.state.type);
     }
@@ -293,7 +392,7 @@ export class QueryImpl implements Query<GenericTableInfo> {
         version,
       });
     return {
-      page: page.map((json: string) => jsonToConvex(json)),
+      page: await this.hydrate(page.map((json: string) => jsonToConvex(json))),
       isDone,
       continueCursor,
       splitCursor,
@@ -301,12 +400,77 @@ export class QueryImpl implements Query<GenericTableInfo> {
     };
   }
 
+  async facets(fields: string[]): Promise<any> {
+    validateArg(fields, 1, "facets", "fields");
+    const query = this.takeQuery();
+    if (query.source.type !== "Search") {
+      throw new Error(
+        "Facets can only be counted for queries using `withSearchIndex`.",
+      );
+    }
+    if (query.operators.length > 0) {
+      throw new Error(
+        "Facets can't be counted for a query with filters or a limit.",
+      );
+    }
+    const syscallResult = await performAsyncSyscall("1.0/searchFacets", {
+      query,
+      facets: fields,
+      version,
+    });
+    const facets: Record<string, any> = {};
+    for (const [field, counts] of Object.entries(syscallResult)) {
+      facets[field] = (
+        counts as Array<{ value?: JSONValue; count: number }>
+      ).map(({ value, count }) => ({
+        value: value === undefined ? undefined : jsonToConvex(value),
+        count,
+      }));
+    }
+    return facets;
+  }
+
+  async aggregate(spec: AggregateSpec<GenericTableInfo, any>): Promise<any> {
+    validateArg(spec, 1, "aggregate", "spec");
+    validateArg(spec.aggregates, 1, "aggregate", "aggregates");
+    const query = this.takeQuery();
+    if (query.source.type === "Search") {
+      throw new Error("Queries using `withSearchIndex` can't be aggregated.");
+    }
+    const syscallResult = await performAsyncSyscall("1.0/aggregate", {
+      query,
+      groupBy: spec.groupBy,
+      aggregates: spec.aggregates,
+      version,
+    });
+    return (
+      syscallResult as Array<{
+        group?: JSONValue;
+        values: Record<string, JSONValue>;
+      }>
+    ).map(({ group, values }) => ({
+      group: group === undefined ? undefined : jsonToConvex(group),
+      values: Object.fromEntries(
+        Object.entries(values).map(([name, value]) => [
+          name,
+          jsonToConvex(value),
+        ]),
+      ),
+    }));
+  }
+
   async collect(): Promise<Array<any>> {
     const out: Value[] = [];
-    for await (const item of this) {
-      out.push(item);
+    // Read every document before hydrating so their references are read
+    // together.
+    for (;;) {
+      const { value, done } = await this.nextUnhydrated();
+      if (done) {
+        break;
+      }
+      out.push(value);
     }
-    return out;
+    return await this.hydrate(out);
   }
 
   async take(n: number): Promise<Array<any>> {
@@ -20,9 +20,11 @@ import {
   RegisteredAction,
   RegisteredMutation,
   RegisteredQuery,
+  RequiredClaims,
 } from "../registration.js";
 import { setupActionCalls } from "./actions_impl.js";
 import { setupActionVectorSearch } from "./vector_search_impl.js";
+import { setupActionHybridSearch } from "./hybrid_search_impl.js";
 import { setupAuth } from "./authentication_impl.js";
 import { setupReader, setupWriter } from "./database_impl.js";
 import { QueryImpl, QueryInitializerImpl } from "./query_impl.js";
@@ -129,6 +131,10 @@ type FunctionDefinition =
   | {
       args?: GenericValidator | Record<string, GenericValidator>;
       returns?: GenericValidator | Record<string, GenericValidator>;
+      timeoutMs?: number;
+      warmInstances?: number;
+      region?: string;
+      requiredClaims?: RequiredClaims;
       handler: (ctx: any, args: DefaultFunctionArgs) => any;
     };
 
@@ -145,6 +151,30 @@ function exportArgs(functionDefinition: FunctionDefinition) {
   };
 }
 
+function timeoutMs(functionDefinition: FunctionDefinition) {
+  return typeof functionDefinition === "object"
+    ? functionDefinition.timeoutMs
+    : undefined;
+}
+
+function warmInstances(functionDefinition: FunctionDefinition) {
+  return typeof functionDefinition === "object"
+    ? functionDefinition.warmInstances
+    : undefined;
+}
+
+function region(functionDefinition: FunctionDefinition) {
+  return typeof functionDefinition === "object"
+    ? functionDefinition.region
+    : undefined;
+}
+
+function requiredClaims(functionDefinition: FunctionDefinition) {
+  return typeof functionDefinition === "object"
+    ? functionDefinition.requiredClaims
+    : undefined;
+}
+
 function exportReturns(functionDefinition: FunctionDefinition) {
   return () => {
     let returns: Validator<any, any, any> | undefined;
@@ -193,6 +223,8 @@ export const mutationGeneric: MutationBuilder<any, "public"> = ((
   func.invokeMutation = (argsStr) => invokeMutation(func, argsStr);
   func.exportArgs = exportArgs(functionDefinition);
   func.exportReturns = exportReturns(functionDefinition);
+  func.requiredClaims = requiredClaims(functionDefinition);
+  func.warmInstances = warmInstances(functionDefinition);
   func._handler = handler;
   return func;
 }) as MutationBuilder<any, "public">;
@@ -232,6 +264,9 @@ export const internalMutationGeneric: MutationBuilder<any, "internal"> = ((
   func.invokeMutation = (argsStr) => invokeMutation(func, argsStr);
   func.exportArgs = exportArgs(functionDefinition);
   func.exportReturns = exportReturns(functionDefinition);
+  func.requiredClaims = requiredClaims(functionDefinition);
+  func.timeoutMs = timeoutMs(functionDefinition);
+  func.warmInstances = warmInstances(functionDefinition);
   func._handler = handler;
   return func;
 }) as MutationBuilder<any, "internal">;
@@ -292,6 +327,8 @@ export const queryGeneric: QueryBuilder<any, "public"> = ((
   func.invokeQuery = (argsStr) => invokeQuery(func, argsStr);
   func.exportArgs = exportArgs(functionDefinition);
   func.exportReturns = exportReturns(functionDefinition);
+  func.requiredClaims = requiredClaims(functionDefinition);
+  func.warmInstances = warmInstances(functionDefinition);
   func._handler = handler;
   return func;
 }) as QueryBuilder<any, "public">;
@@ -334,6 +371,9 @@ export const internalQueryGeneric: QueryBuilder<any, "internal"> = ((
   func.invokeQuery = (argsStr) => invokeQuery(func as any, argsStr);
   func.exportArgs = exportArgs(functionDefinition);
   func.exportReturns = exportReturns(functionDefinition);
+  func.requiredClaims = requiredClaims(functionDefinition);
+  func.timeoutMs = timeoutMs(functionDefinition);
+  func.warmInstances = warmInstances(functionDefinition);
   func._handler = handler;
   return func;
 }) as QueryBuilder<any, "internal">;
@@ -349,6 +389,7 @@ async function invokeAction<
     scheduler: setupActionScheduler(requestId),
     storage: setupStorageActionWriter(requestId),
     vectorSearch: setupActionVectorSearch(requestId) as any,
+    hybridSearch: setupActionHybridSearch(requestId) as any,
   };
   const result = await invokeFunction(func, ctx, args as any);
   return JSON.stringify(convexToJson(result === undefined ? null : result));
@@ -388,6 +429,8 @@ export const actionGeneric: ActionBuilder<any, "public"> = ((
     invokeAction(func, requestId, argsStr);
   func.exportArgs = exportArgs(functionDefinition);
   func.exportReturns = exportReturns(functionDefinition);
+  func.requiredClaims = requiredClaims(functionDefinition);
+  func.region = region(functionDefinition);
   func._handler = handler;
   return func;
 }) as ActionBuilder<any, "public">;
@@ -426,6 +469,8 @@ export const internalActionGeneric: ActionBuilder<any, "internal"> = ((
     invokeAction(func, requestId, argsStr);
   func.exportArgs = exportArgs(functionDefinition);
   func.exportReturns = exportReturns(functionDefinition);
+  func.requiredClaims = requiredClaims(functionDefinition);
+  func.region = region(functionDefinition);
   func._handler = handler;
   return func;
 }) as ActionBuilder<any, "internal">;
@@ -443,6 +488,7 @@ async function invokeHttpAction<
     storage: setupStorageActionWriter(requestId),
     scheduler: setupActionScheduler(requestId),
     vectorSearch: setupActionVectorSearch(requestId) as any,
+    hybridSearch: setupActionHybridSearch(requestId) as any,
   };
   return await invokeFunction(func, ctx, [request]);
 }
@@ -2,42 +2,48 @@ import { convexToJson, Value } from "../../values/index.js";
 import { version } from "../../index.js";
 import { performAsyncSyscall } from "./syscall.js";
 import { parseArgs } from "../../common/index.js";
-import { SchedulableFunctionReference, Scheduler } from "../scheduler.js";
+import {
+  IdempotencyKeyOptions,
+  SchedulableFunctionReference,
+  Scheduler,
+} from "../scheduler.js";
 import { Id } from "../../values/value.js";
 import { validateArg } from "./validate.js";
 import { getFunctionAddress } from "../components/paths.js";
 
 export function setupMutationScheduler(): Scheduler {
   return {
-    runAfter: async (
-      delayMs: number,
-      functionReference: SchedulableFunctionReference,
-      args?: Record<string, Value>,
-    ) => {
-      const syscallArgs = runAfterSyscallArgs(delayMs, functionReference, args);
-      return await performAsyncSyscall("1.0/schedule", syscallArgs);
-    },
-    runAt: async (
-      ms_since_epoch_or_date: number | Date,
-      functionReference: SchedulableFunctionReference,
-      args?: Record<string, Value>,
-    ) => {
-      const syscallArgs = runAtSyscallArgs(
-        ms_since_epoch_or_date,
-        functionReference,
-        args,
-      );
-      return await performAsyncSyscall("1.0/schedule", syscallArgs);
-    },
+    ...scheduleFunctions("1.0/schedule", {}),
     cancel: async (id: Id<"_scheduled_functions">) => {
       validateArg(id, 1, "cancel", "id");
       const args = { id: convexToJson(id) };
       await performAsyncSyscall("1.0/cancel_job", args);
     },
+    withIdempotencyKey: (key: string, options?: IdempotencyKeyOptions) =>
+      scheduleFunctions("1.0/schedule", idempotencyKeyArgs(key, options)),
   };
 }
 
 export function setupActionScheduler(requestId: string): Scheduler {
+  return {
+    ...scheduleFunctions("1.0/actions/schedule", { requestId }),
+    cancel: async (id: Id<"_scheduled_functions">) => {
+      validateArg(id, 1, "cancel", "id");
+      const syscallArgs = { id: convexToJson(id) };
+      return await performAsyncSyscall("1.0/actions/cancel_job", syscallArgs);
+    },
+    withIdempotencyKey: (key: string, options?: IdempotencyKeyOptions) =>
+      scheduleFunctions("1.0/actions/schedule", {
+        requestId,
+        ...idempotencyKeyArgs(key, options),
+      }),
+  };
+}
+
+function scheduleFunctions(
+  syscall: string,
+  extraArgs: Record<string, string>,
+): Pick<Scheduler, "runAfter" | "runAt"> {
   return {
     runAfter: async (
       delayMs: number,
@@ -45,10 +51,10 @@ export function setupActionScheduler(requestId: string): Scheduler {
       args?: Record<string, Value>,
     ) => {
       const syscallArgs = {
-        requestId,
+        ...extraArgs,
         ...runAfterSyscallArgs(delayMs, functionReference, args),
       };
-      return await performAsyncSyscall("1.0/actions/schedule", syscallArgs);
+      return await performAsyncSyscall(syscall, syscallArgs);
     },
     runAt: async (
       ms_since_epoch_or_date: number | Date,
@@ -56,19 +62,25 @@ export function setupActionScheduler(requestId: string): Scheduler {
       args?: Record<string, Value>,
     ) => {
       const syscallArgs = {
-        requestId,
+        ...extraArgs,
         ...runAtSyscallArgs(ms_since_epoch_or_date, functionReference, args),
       };
-      return await performAsyncSyscall("1.0/actions/schedule", syscallArgs);
-    },
-    cancel: async (id: Id<"_scheduled_functions">) => {
-      validateArg(id, 1, "cancel", "id");
-      const syscallArgs = { id: convexToJson(id) };
-      return await performAsyncSyscall("1.0/actions/cancel_job", syscallArgs);
+      return await performAsyncSyscall(syscall, syscallArgs);
     },
   };
 }
 
+function idempotencyKeyArgs(key: string, options?: IdempotencyKeyOptions) {
+  if (typeof key !== "string" || key.length === 0) {
+    throw new Error("`key` must be a non-empty string");
+  }
+  const onConflict = options?.onConflict ?? "replace";
+  if (onConflict !== "replace" && onConflict !== "skip") {
+    throw new Error('`onConflict` must be "replace" or "skip"');
+  }
+  return { idempotencyKey: key, onConflict };
+}
+
 function runAfterSyscallArgs(
   delayMs: number,
   functionReference: SchedulableFunctionReference,
@@ -8,6 +8,7 @@ import {
   SearchFilter,
   SearchFilterBuilder,
   SearchFilterFinalizer,
+  TextSearchOptions,
 } from "../search_filter_builder.js";
 import { validateArg } from "./validate.js";
 
@@ -16,6 +17,8 @@ export type SerializedSearchFilter =
       type: "Search";
       fieldPath: string;
       value: string;
+      prefix?: boolean;
+      maxEditDistance?: number;
     }
   | {
       type: "Eq";
@@ -53,6 +56,7 @@ export class SearchFilterBuilderImpl
   search(
     fieldName: string,
     query: string,
+    options?: TextSearchOptions,
   ): SearchFilterFinalizer<GenericDocument, GenericSearchIndexConfig> {
     validateArg(fieldName, 1, "search", "fieldName");
     validateArg(query, 2, "search", "query");
@@ -62,6 +66,10 @@ export class SearchFilterBuilderImpl
         type: "Search",
         fieldPath: fieldName,
         value: query,
+        ...(options?.prefix ? { prefix: true } : {}),
+        ...(options?.maxEditDistance
+          ? { maxEditDistance: options.maxEditDistance }
+          : {}),
       }),
     );
   }
@@ -4,6 +4,8 @@ import {
   FileStorageId,
   StorageReader,
   StorageWriter,
+  StoreFileOptions,
+  UploadUrlOptions,
 } from "../storage.js";
 import { version } from "../../index.js";
 import { performAsyncSyscall, performJsSyscall } from "./syscall.js";
@@ -32,10 +34,11 @@ export function setupStorageReader(requestId: string): StorageReader {
 export function setupStorageWriter(requestId: string): StorageWriter {
   const reader = setupStorageReader(requestId);
   return {
-    generateUploadUrl: async () => {
+    generateUploadUrl: async (options?: UploadUrlOptions) => {
       return await performAsyncSyscall("1.0/storageGenerateUploadUrl", {
         requestId,
         version,
+        ...options,
       });
     },
     delete: async (storageId: FileStorageId) => {
@@ -56,7 +59,7 @@ export function setupStorageActionWriter(
   const writer = setupStorageWriter(requestId);
   return {
     ...writer,
-    store: async (blob: Blob, options?: { sha256?: string }) => {
+    store: async (blob: Blob, options?: StoreFileOptions) => {
       return await performJsSyscall("storage/storeBlob", {
         requestId,
         version,
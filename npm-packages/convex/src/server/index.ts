@@ -98,7 +98,17 @@ export {
 } from "./impl/registration_impl.js";
 export type { IndexRange, IndexRangeBuilder } from "./index_range_builder.js";
 export * from "./pagination.js";
-export type { OrderedQuery, Query, QueryInitializer } from "./query.js";
+export type {
+  AggregateResult,
+  AggregateSpec,
+  Aggregation,
+  OrderedQuery,
+  Query,
+  QueryHints,
+  QueryInitializer,
+  SearchFacets,
+  SearchQuery,
+} from "./query.js";
 export type {
   ArgsArray,
   DefaultFunctionArgs,
@@ -117,6 +127,7 @@ export type {
   RegisteredAction,
   RegisteredMutation,
   RegisteredQuery,
+  RequiredClaims,
   PublicHttpAction,
   UnvalidatedFunction,
   ValidatedFunction,
@@ -127,8 +138,35 @@ export type {
 } from "./registration.js";
 export * from "./search_filter_builder.js";
 export * from "./storage.js";
-export type { Scheduler, SchedulableFunctionReference } from "./scheduler.js";
+export type {
+  IdempotencyKeyOptions,
+  Scheduler,
+  SchedulableFunctionReference,
+} from "./scheduler.js";
 export { cronJobs } from "./cron.js";
+export { seededRandom } from "./seeded_random.js";
+export type { SeededRandomOptions } from "./seeded_random.js";
+export { transactionTimestamp } from "./transaction_timestamp.js";
+export { eventStream, formatServerSentEvent } from "./event_stream.js";
+export type { EventStreamWriter, ServerSentEvent } from "./event_stream.js";
+export {
+  startWebAuthnRegistration,
+  finishWebAuthnRegistration,
+  startWebAuthnAuthentication,
+  finishWebAuthnAuthentication,
+} from "./webauthn.js";
+export type { WebAuthnRelyingParty, WebAuthnResult } from "./webauthn.js";
+export { rateLimit, resetRateLimit } from "./rate_limiter.js";
+export type { RateLimit, RateLimitResult } from "./rate_limiter.js";
+export { enqueue } from "./queues.js";
+export {
+  materializedAggregate,
+  listMaterializedAggregate,
+} from "./materialized_aggregates.js";
+export type { MaterializedAggregateEntry } from "./materialized_aggregates.js";
+export { getPage } from "./get_page.js";
+export type { GetPageResult, IndexKey } from "./get_page.js";
+export { callNativeOp } from "./native_ops.js";
 export type { CronJob, Crons } from "./cron.js";
 export type {
   SystemFields,
@@ -187,7 +225,9 @@ export type { Index, SearchIndex, VectorIndex } from "./schema.js";
 
 export type {
   SearchIndexConfig,
+  SearchIndexAnalyzer,
   VectorIndexConfig,
+  VectorIndexDistance,
   TableDefinition,
   SchemaDefinition,
   DefineSchemaOptions,
@@ -205,6 +245,8 @@ export type {
   FilterExpression,
 } from "./vector_search.js";
 
+export type { HybridSearch, HybridSearchQuery } from "./hybrid_search.js";
+
 /**
  * @public
  */
@@ -0,0 +1,73 @@
+import { convexToJson, jsonToConvex, Value } from "../values/index.js";
+import { performAsyncSyscall } from "./impl/syscall.js";
+import { GenericMutationCtx, GenericQueryCtx } from "./registration.js";
+
+/**
+ * The count of a materialized aggregate's documents with one key, and the sum
+ * of their sum field.
+ *
+ * @public
+ */
+export type MaterializedAggregateEntry = {
+  key: Value;
+  count: number;
+  sum: number;
+};
+
+/**
+ * Read the count and sum of the documents with `key` in the materialized
+ * aggregate `name` on `table`. Leave out `key` for aggregates without a key
+ * field. Keys no documents have count as zero.
+ *
+ * Materialized aggregates are created from the dashboard or the CLI, and are
+ * kept up to date by every write to their table, so this reads a single
+ * entry however many documents it counts. It throws while the aggregate is
+ * still counting the table's existing documents.
+ *
+ * @public
+ */
+export async function materializedAggregate(
+  _ctx: GenericQueryCtx<any> | GenericMutationCtx<any>,
+  args: { table: string; name: string; key?: Value },
+): Promise<{ count: number; sum: number }> {
+  return await performAsyncSyscall("1.0/materializedAggregate/get", {
+    table: args.table,
+    name: args.name,
+    key: convexToJson(args.key ?? null),
+  });
+}
+
+/**
+ * List the entries of the materialized aggregate `name` on `table`, ordered
+ * by their key, count or sum, e.g. for a leaderboard.
+ *
+ * @param args.orderBy - What to order entries by. Defaults to `"key"`.
+ * @param args.order - Defaults to `"asc"`.
+ * @param args.limit - The most entries to return, up to 1000. Defaults to
+ * 100.
+ * @public
+ */
+export async function listMaterializedAggregate(
+  _ctx: GenericQueryCtx<any> | GenericMutationCtx<any>,
+  args: {
+    table: string;
+    name: string;
+    orderBy?: "key" | "count" | "sum";
+    order?: "asc" | "desc";
+    limit?: number;
+  },
+): Promise<MaterializedAggregateEntry[]> {
+  const entries: { key: any; count: number; sum: number }[] =
+    await performAsyncSyscall("1.0/materializedAggregate/list", {
+      table: args.table,
+      name: args.name,
+      orderBy: args.orderBy ?? "key",
+      order: args.order ?? "asc",
+      limit: args.limit ?? 100,
+    });
+  return entries.map(({ key, count, sum }) => ({
+    key: jsonToConvex(key),
+    count,
+    sum,
+  }));
+}
@@ -0,0 +1,27 @@
+import { convexToJson, jsonToConvex, Value } from "../values/index.js";
+import { performAsyncSyscall } from "./impl/syscall.js";
+
+/**
+ * Call a custom native op registered by the build of the Convex backend
+ * running this deployment.
+ *
+ * Ops are only available if the deployment has been granted the op's
+ * capability, and most can only be called from actions. Ops that are
+ * deterministic can also be called from queries and mutations.
+ *
+ * @param name - The name the op was registered with.
+ * @param args - Arguments matching the op's argument validator.
+ * @returns - The op's result.
+ *
+ * @public
+ */
+export async function callNativeOp(
+  name: string,
+  args: Value = {},
+): Promise<Value> {
+  const result = await performAsyncSyscall("1.0/native", {
+    name,
+    args: convexToJson(args),
+  });
+  return jsonToConvex(result);
+}
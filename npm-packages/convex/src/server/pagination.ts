@@ -12,7 +12,8 @@ import { v } from "../values/validator.js";
  *
  * Note: Cursors can only be passed to _exactly_ the same database query that
  * they were generated from. You may not reuse a cursor between different
- * database queries.
+ * database queries. Cursors stay valid when the query's index is rebuilt with
+ * the same fields, and when a snapshot import gives the table a new number.
  *
  * @public
  */
@@ -1,5 +1,8 @@
 import {
   DocumentByInfo,
+  FieldPaths,
+  FieldTypeFromFieldPath,
+  GenericSearchIndexConfig,
   GenericTableInfo,
   IndexNames,
   NamedIndex,
@@ -10,6 +13,31 @@ import { ExpressionOrValue, FilterBuilder } from "./filter_builder.js";
 import { IndexRange, IndexRangeBuilder } from "./index_range_builder.js";
 import { PaginationResult, PaginationOptions } from "./pagination.js";
 import { SearchFilter, SearchFilterBuilder } from "./search_filter_builder.js";
+import { Value } from "../values/index.js";
+
+/**
+ * Hints for planning a query, passed to {@link OrderedQuery.hint}.
+ *
+ * @public
+ */
+export type QueryHints<TableInfo extends GenericTableInfo> = {
+  /**
+   * Walk this index of the table instead of scanning the table in insertion
+   * order. Only valid for queries that don't call
+   * {@link QueryInitializer.withIndex} with a different index.
+   */
+  index?: IndexNames<TableInfo>;
+  /**
+   * By default, `.filter` equality checks on the fields after those in the
+   * index range narrow the range of the index that's read. Set this to read
+   * the whole range and filter it instead.
+   */
+  disableFilterPushdown?: boolean;
+  /**
+   * Throw an error if the query reads more than this many documents.
+   */
+  scanBudget?: number;
+};
 
 /**
  * The {@link QueryInitializer} interface is the entry point for building a {@link Query}
@@ -93,7 +121,7 @@ export interface QueryInitializer<TableInfo extends GenericTableInfo>
         NamedSearchIndex<TableInfo, IndexName>
       >,
     ) => SearchFilter,
-  ): OrderedQuery<TableInfo>;
+  ): SearchQuery<TableInfo, NamedSearchIndex<TableInfo, IndexName>>;
 
   /**
    * The number of documents in the table.
@@ -103,6 +131,109 @@ export interface QueryInitializer<TableInfo extends GenericTableInfo>
   count(): Promise<number>;
 }
 
+/**
+ * The number of search results with each value of a filter field, from most
+ * to least common. Results missing the field are counted with an `undefined`
+ * value.
+ *
+ * @public
+ */
+export type SearchFacets<
+  TableInfo extends GenericTableInfo,
+  FieldPath extends string,
+> = {
+  [Field in FieldPath]: Array<{
+    value: FieldTypeFromFieldPath<DocumentByInfo<TableInfo>, Field>;
+    count: number;
+  }>;
+};
+
+/**
+ * A {@link Query} created with {@link QueryInitializer.withSearchIndex}.
+ *
+ * @public
+ */
+export interface SearchQuery<
+  TableInfo extends GenericTableInfo,
+  SearchIndexConfig extends GenericSearchIndexConfig,
+> extends OrderedQuery<TableInfo> {
+  /**
+   * Count the search results by their value in each of `fields`, such as how
+   * many results there are in each category, in a single call.
+   *
+   * The fields must be `filterFields` of the search index, and the query
+   * can't have been filtered or limited. Like any search query, only the
+   * top 1024 results are counted.
+   *
+   * @param fields - The filter fields to count results by.
+   * @returns - The counts for each field.
+   */
+  facets<FieldPath extends SearchIndexConfig["filterFields"]>(
+    fields: FieldPath[],
+  ): Promise<SearchFacets<TableInfo, FieldPath>>;
+}
+
+/**
+ * A value for {@link Query.aggregate} to compute over each group of documents.
+ *
+ * `"count"` counts the documents, or only those with `field` if it's set.
+ * `"sum"` and `"avg"` skip values that aren't numbers, and `"min"` and `"max"`
+ * compare values with the same ordering as indexes.
+ *
+ * @public
+ */
+export type Aggregation<TableInfo extends GenericTableInfo> =
+  | { op: "count"; field?: FieldPaths<TableInfo> }
+  | { op: "sum" | "avg" | "min" | "max"; field: FieldPaths<TableInfo> };
+
+/**
+ * The aggregations for {@link Query.aggregate} to compute, by name.
+ *
+ * @public
+ */
+export type AggregateSpec<
+  TableInfo extends GenericTableInfo,
+  Aggregates extends Record<string, Aggregation<TableInfo>>,
+> = {
+  /**
+   * Compute the aggregations separately for each value of this field, which
+   * must be one of the fields of the index passed to
+   * {@link QueryInitializer.withIndex}.
+   */
+  groupBy?: FieldPaths<TableInfo>;
+  aggregates: Aggregates;
+};
+
+type AggregateValue<
+  TableInfo extends GenericTableInfo,
+  A extends Aggregation<TableInfo>,
+> = A extends { op: "count" }
+  ? number
+  : A extends { op: "sum" }
+    ? number | bigint
+    : A extends { op: "avg" }
+      ? number | null
+      : A extends { field: infer Field extends string }
+        ? FieldTypeFromFieldPath<DocumentByInfo<TableInfo>, Field> | null
+        : never;
+
+/**
+ * The result of {@link Query.aggregate}: one entry per group, ordered by the
+ * group's value. Documents missing the group-by field are grouped under an
+ * `undefined` value, which is also the `group` of an ungrouped aggregation.
+ *
+ * @public
+ */
+export type AggregateResult<
+  TableInfo extends GenericTableInfo,
+  Aggregates extends Record<string, Aggregation<TableInfo>>,
+> = Array<{
+  group: Value | undefined;
+  values: {
+    [Name in keyof Aggregates]: AggregateValue<TableInfo, Aggregates[Name]>;
+  };
+}>;
+
 /**
  * The {@link Query} interface allows functions to read values out of the database.
  *
@@ -133,6 +264,7 @@ export interface QueryInitializer<TableInfo extends GenericTableInfo>
  * | [`take(n: number)`](#take)                   | Return the first `n` results as an array. |
  * | [`first()`](#first)                          | Return the first result. |
  * | [`unique()`](#unique)                        | Return the only result, and throw if there is more than one result. |
+ * | [`aggregate(...)`](#aggregate)               | Compute counts, sums, averages, minimums and maximums, optionally grouped by an indexed field. |
  *
  * To learn more about how to write queries, see [Querying the Database](https://docs.convex.dev/using/database-queries).
  *
@@ -147,6 +279,22 @@ export interface Query<TableInfo extends GenericTableInfo>
    * @param order - The order to return results in.
    */
   order(order: "asc" | "desc"): OrderedQuery<TableInfo>;
+
+  /**
+   * Compute counts, sums, averages, minimums and maximums over the query's
+   * results on the server, without loading the documents into the function.
+   *
+   * The documents are still read, so aggregations are subject to the same
+   * limits as any other query. Group by a field of the index the query uses
+   * to get the aggregations for each of its values, such as the total of each
+   * customer's orders. There can be at most 1024 groups.
+   *
+   * @param spec - The aggregations to compute, and the field to group by.
+   * @returns - The aggregations computed for each group.
+   */
+  aggregate<Aggregates extends Record<string, Aggregation<TableInfo>>>(
+    spec: AggregateSpec<TableInfo, Aggregates>,
+  ): Promise<AggregateResult<TableInfo, Aggregates>>;
 }
 
 /**
@@ -176,6 +324,34 @@ export interface OrderedQuery<TableInfo extends GenericTableInfo>
    */
   limit(n: number): this;
 
+  /**
+   * Override how the query is planned, for when the default plan reads far
+   * more documents than it needs to on the table's data.
+   *
+   * Calling `hint` more than once merges the hints.
+   *
+   * @param hints - The {@link QueryHints} to plan the query with.
+   * @returns - A new {@link OrderedQuery} with the hints applied.
+   */
+  hint(hints: QueryHints<TableInfo>): this;
+
+  /**
+   * Replace the fields of each result declared as `v.id(...)` in the schema
+   * with the documents they reference, and their references in turn, up to
+   * `depth` levels deep. References to deleted documents become `null`.
+   *
+   * The referenced documents are read on the server in the same transaction,
+   * a level at a time, instead of with a `db.get` for each reference. Only
+   * references declared in the schema are followed, and a query can read at
+   * most 1024 referenced documents.
+   *
+   * @param depth - How many levels of references to follow, up to 4.
+   * Defaults to 1.
+   * @returns - A new {@link OrderedQuery} whose results have their references
+   * replaced.
+   */
+  withReferences(depth?: number): OrderedQuery<any>;
+
   /**
    * Load a page of `n` results and obtain a {@link Cursor} for loading more.
    *
@@ -0,0 +1,31 @@
+import { convexToJson, Value } from "../values/index.js";
+import { performAsyncSyscall } from "./impl/syscall.js";
+import { GenericMutationCtx } from "./registration.js";
+
+/**
+ * Add `body` to the end of the queue `queue`, which is defined with the
+ * deployment's queue API.
+ *
+ * Every message is delivered to each of the queue's consumer groups by
+ * running the group's function with `{ queue, group, partitionKey,
+ * messageId, body, attempt }`. Messages with the same `partitionKey` are
+ * delivered one at a time, in the order they were enqueued, and a message
+ * whose delivery fails is retried until it's dead-lettered. Consumers should
+ * be idempotent, since a message can be delivered more than once.
+ *
+ * The message is only enqueued if the mutation commits.
+ *
+ * @returns The message's sequence number in the queue.
+ * @public
+ */
+export async function enqueue(
+  _ctx: GenericMutationCtx<any>,
+  args: { queue: string; partitionKey?: string; body: Value },
+): Promise<number> {
+  const { sequence } = await performAsyncSyscall("1.0/queue/enqueue", {
+    queue: args.queue,
+    partitionKey: args.partitionKey,
+    body: convexToJson(args.body),
+  });
+  return sequence;
+}
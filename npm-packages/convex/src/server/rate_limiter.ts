@@ -0,0 +1,88 @@
+import { performAsyncSyscall } from "./impl/syscall.js";
+import { GenericMutationCtx } from "./registration.js";
+
+/**
+ * A limit on how often something can happen, checked with {@link rateLimit}.
+ *
+ * Calls for the same `name` should always pass the same limit. To change a
+ * limit, use a new name.
+ *
+ * @public
+ */
+export type RateLimit = {
+  /**
+   * How requests are counted:
+   * - `"fixedWindow"` allows `rate` requests in each `period`, with periods
+   *   starting at multiples of `period` since the Unix epoch.
+   * - `"slidingWindow"` also counts the previous period's requests, weighted
+   *   by how much of it falls within the last `period`.
+   * - `"tokenBucket"` allows bursts of up to `capacity` requests, refilling
+   *   evenly at `rate` requests per `period`.
+   */
+  kind: "fixedWindow" | "slidingWindow" | "tokenBucket";
+  /**
+   * The number of requests allowed per `period`.
+   */
+  rate: number;
+  /**
+   * The period in milliseconds.
+   */
+  period: number;
+  /**
+   * The most requests a token bucket allows at once. Defaults to `rate`.
+   */
+  capacity?: number;
+  /**
+   * The number of shards to split each key's allowance across. More shards
+   * let more mutations check the same key concurrently without conflicting,
+   * but may reject requests early when the shard they land on is used up
+   * before the others. Defaults to 1.
+   */
+  shards?: number;
+};
+
+/**
+ * The result of checking a rate limit.
+ *
+ * @public
+ */
+export type RateLimitResult =
+  | { ok: true; retryAfter: null }
+  | {
+      ok: false;
+      /**
+       * Milliseconds until the request would be allowed.
+       */
+      retryAfter: number;
+    };
+
+/**
+ * Take `count` requests (default 1) from `key`'s allowance under the limit
+ * `name`, e.g. to allow five signups per hour from each IP address.
+ *
+ * The limit's state is read and written in the mutation's transaction, so
+ * concurrent mutations can't both take the last request, and the requests
+ * are given back if the mutation fails. Rejected requests don't use up any
+ * of the allowance.
+ *
+ * @public
+ */
+export async function rateLimit(
+  _ctx: GenericMutationCtx<any>,
+  args: RateLimit & { name: string; key?: string; count?: number },
+): Promise<RateLimitResult> {
+  return await performAsyncSyscall("1.0/rateLimiter/limit", args);
+}
+
+/**
+ * Reset `key`'s allowance under the limit `name`, as if it had never made
+ * any requests.
+ *
+ * @public
+ */
+export async function resetRateLimit(
+  _ctx: GenericMutationCtx<any>,
+  args: { name: string; key?: string },
+): Promise<void> {
+  await performAsyncSyscall("1.0/rateLimiter/reset", args);
+}
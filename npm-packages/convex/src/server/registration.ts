@@ -20,15 +20,17 @@ import {
   ObjectType,
   PropertyValidators,
 } from "../values/validator.js";
-import { Id } from "../values/value.js";
+import { Id, Value } from "../values/value.js";
 import {
   GenericDataModel,
   NamedTableInfo,
+  SearchIndexNames,
   TableNamesInDataModel,
   VectorIndexNames,
 } from "./data_model.js";
 import { Scheduler } from "./scheduler.js";
 import { VectorSearchQuery } from "./vector_search.js";
+import { HybridSearchQuery } from "./hybrid_search.js";
 import { Expand } from "../type_utils.js";
 import { Validator } from "../values/validators.js";
 
@@ -230,6 +232,30 @@ export interface GenericActionCtx<DataModel extends GenericDataModel> {
     ...args: OptionalRestArgs<Action>
   ): Promise<FunctionReturnType<Action>>;
 
+  /**
+   * Load the checkpoint this action last saved with
+   * {@link GenericActionCtx.saveCheckpoint}.
+   *
+   * Only available in actions run by the scheduler, and not in Node.js actions.
+   *
+   * @returns A promise of the saved value, or `null` if this action hasn't
+   * saved a checkpoint yet.
+   */
+  loadCheckpoint(): Promise<Value | null>;
+
+  /**
+   * Save this action's progress. If the action times out or its process
+   * crashes, it's run again with the same arguments and can pick up where it
+   * left off with {@link GenericActionCtx.loadCheckpoint}.
+   *
+   * An action is only run again if it saved a checkpoint since the last time
+   * it was run. Only available in actions run by the scheduler, and not in
+   * Node.js actions.
+   *
+   * @param value - Any Convex value, up to 512KiB once encoded as JSON.
+   */
+  saveCheckpoint(value: Value): Promise<void>;
+
   /**
    * A utility for scheduling Convex functions to run in the future.
    */
@@ -265,6 +291,36 @@ export interface GenericActionCtx<DataModel extends GenericDataModel> {
       VectorSearchQuery<NamedTableInfo<DataModel, TableName>, IndexName>
     >,
   ): Promise<Array<{ _id: Id<TableName>; _score: number }>>;
+
+  /**
+   * Run a full text search and a vector search on the given table, and fuse
+   * their results into a single ranking.
+   *
+   * @param tableName - The name of the table to query.
+   * @param query - A {@link HybridSearchQuery} containing the search index and
+   * text search, the vector index and vector, the number of results to return,
+   * and how to weight each search.
+   * @returns A promise of IDs and fused scores for the best matching
+   * documents, from best to worst match.
+   */
+  hybridSearch<
+    TableName extends TableNamesInDataModel<DataModel>,
+    TextIndexName extends SearchIndexNames<
+      NamedTableInfo<DataModel, TableName>
+    >,
+    VectorIndexName extends VectorIndexNames<
+      NamedTableInfo<DataModel, TableName>
+    >,
+  >(
+    tableName: TableName,
+    query: Expand<
+      HybridSearchQuery<
+        NamedTableInfo<DataModel, TableName>,
+        TextIndexName,
+        VectorIndexName
+      >
+    >,
+  ): Promise<Array<{ _id: Id<TableName>; _score: number }>>;
 }
 
 /**
@@ -336,6 +392,16 @@ type VisibilityProperties<Visiblity extends FunctionVisibility> =
         isInternal: true;
       };
 
+/**
+ * Identity claims a function's caller must have, as set by `requiredClaims`
+ * when defining the function. For example `{ role: "admin" }` requires the
+ * caller's identity to have a `role` claim of `"admin"`, or a `role` array
+ * containing `"admin"`.
+ *
+ * @public
+ */
+export type RequiredClaims = Record<string, string | number | boolean>;
+
 /**
  * A mutation function that is part of this app.
  *
@@ -364,6 +430,15 @@ export type RegisteredMutation<
   /** @internal */
   exportReturns(): string;
 
+  /** @internal */
+  requiredClaims?: RequiredClaims;
+
+  /** @internal */
+  timeoutMs?: number;
+
+  /** @internal */
+  warmInstances?: number;
+
   /** @internal */
   _handler: (ctx: GenericMutationCtx<any>, args: Args) => Returns;
 } & VisibilityProperties<Visibility>;
@@ -396,6 +471,15 @@ export type RegisteredQuery<
   /** @internal */
   exportReturns(): string;
 
+  /** @internal */
+  requiredClaims?: RequiredClaims;
+
+  /** @internal */
+  timeoutMs?: number;
+
+  /** @internal */
+  warmInstances?: number;
+
   /** @internal */
   _handler: (ctx: GenericQueryCtx<any>, args: Args) => Returns;
 } & VisibilityProperties<Visibility>;
@@ -428,6 +512,12 @@ export type RegisteredAction<
   /** @internal */
   exportReturns(): string;
 
+  /** @internal */
+  requiredClaims?: RequiredClaims;
+
+  /** @internal */
+  region?: string;
+
   /** @internal */
   _handler: (ctx: GenericActionCtx<any>, args: Args) => Returns;
 } & VisibilityProperties<Visibility>;
@@ -657,6 +747,34 @@ export type MutationBuilder<
            * ```
            */
           returns?: ReturnsValidator;
+          /**
+           * A longer execution timeout for this function, in milliseconds.
+           *
+           * Only internal queries and mutations can override the default
+           * timeout, up to the deployment's maximum. Use this for heavy
+           * functions that are only run by admins or other functions and
+           * can't easily be split up.
+           */
+          timeoutMs?: number;
+          /**
+           * The number of isolates to keep warm with this function's module
+           * already evaluated, so calls after an idle period don't pay to
+           * compile it.
+           *
+           * The deployment caps how many warm instances a function can ask
+           * for. Use this sparingly, for latency-critical endpoints.
+           */
+          warmInstances?: number;
+          /**
+           * Identity claims that callers must have, like `{ role: "admin" }`.
+           *
+           * Each claim is compared to what `ctx.auth.getUserIdentity()`
+           * returns, and matches if it equals the value or is an array
+           * containing it. Calls from clients without the claims are
+           * rejected before the function runs. Admins, and other functions
+           * calling this one, aren't restricted.
+           */
+          requiredClaims?: RequiredClaims;
           /**
            * The implementation of this function.
            *
@@ -750,6 +868,34 @@ export type MutationBuilderWithTable<
            * ```
            */
           returns?: ReturnsValidator;
+          /**
+           * A longer execution timeout for this function, in milliseconds.
+           *
+           * Only internal queries and mutations can override the default
+           * timeout, up to the deployment's maximum. Use this for heavy
+           * functions that are only run by admins or other functions and
+           * can't easily be split up.
+           */
+          timeoutMs?: number;
+          /**
+           * The number of isolates to keep warm with this function's module
+           * already evaluated, so calls after an idle period don't pay to
+           * compile it.
+           *
+           * The deployment caps how many warm instances a function can ask
+           * for. Use this sparingly, for latency-critical endpoints.
+           */
+          warmInstances?: number;
+          /**
+           * Identity claims that callers must have, like `{ role: "admin" }`.
+           *
+           * Each claim is compared to what `ctx.auth.getUserIdentity()`
+           * returns, and matches if it equals the value or is an array
+           * containing it. Calls from clients without the claims are
+           * rejected before the function runs. Admins, and other functions
+           * calling this one, aren't restricted.
+           */
+          requiredClaims?: RequiredClaims;
           /**
            * The implementation of this function.
            *
@@ -843,6 +989,34 @@ export type QueryBuilder<
            * ```
            */
           returns?: ReturnsValidator;
+          /**
+           * A longer execution timeout for this function, in milliseconds.
+           *
+           * Only internal queries and mutations can override the default
+           * timeout, up to the deployment's maximum. Use this for heavy
+           * functions that are only run by admins or other functions and
+           * can't easily be split up.
+           */
+          timeoutMs?: number;
+          /**
+           * The number of isolates to keep warm with this function's module
+           * already evaluated, so calls after an idle period don't pay to
+           * compile it.
+           *
+           * The deployment caps how many warm instances a function can ask
+           * for. Use this sparingly, for latency-critical endpoints.
+           */
+          warmInstances?: number;
+          /**
+           * Identity claims that callers must have, like `{ role: "admin" }`.
+           *
+           * Each claim is compared to what `ctx.auth.getUserIdentity()`
+           * returns, and matches if it equals the value or is an array
+           * containing it. Calls from clients without the claims are
+           * rejected before the function runs. Admins, and other functions
+           * calling this one, aren't restricted.
+           */
+          requiredClaims?: RequiredClaims;
           /**
            * The implementation of this function.
            *
@@ -932,6 +1106,34 @@ export type QueryBuilderWithTable<
            * ```
            */
           returns?: ReturnsValidator;
+          /**
+           * A longer execution timeout for this function, in milliseconds.
+           *
+           * Only internal queries and mutations can override the default
+           * timeout, up to the deployment's maximum. Use this for heavy
+           * functions that are only run by admins or other functions and
+           * can't easily be split up.
+           */
+          timeoutMs?: number;
+          /**
+           * The number of isolates to keep warm with this function's module
+           * already evaluated, so calls after an idle period don't pay to
+           * compile it.
+           *
+           * The deployment caps how many warm instances a function can ask
+           * for. Use this sparingly, for latency-critical endpoints.
+           */
+          warmInstances?: number;
+          /**
+           * Identity claims that callers must have, like `{ role: "admin" }`.
+           *
+           * Each claim is compared to what `ctx.auth.getUserIdentity()`
+           * returns, and matches if it equals the value or is an array
+           * containing it. Calls from clients without the claims are
+           * rejected before the function runs. Admins, and other functions
+           * calling this one, aren't restricted.
+           */
+          requiredClaims?: RequiredClaims;
           /**
            * The implementation of this function.
            *
@@ -1022,6 +1224,26 @@ export type ActionBuilder<
            * ```
            */
           returns?: ReturnsValidator;
+          /**
+           * The region to run this action in, for example to keep the outbound
+           * requests it makes within a jurisdiction.
+           *
+           * Only Node.js actions (in files with `"use node"`) can set a region.
+           * If the deployment has no action runners in the region, the action
+           * runs on its default runners, unless the deployment is configured to
+           * fail it instead.
+           */
+          region?: string;
+          /**
+           * Identity claims that callers must have, like `{ role: "admin" }`.
+           *
+           * Each claim is compared to what `ctx.auth.getUserIdentity()`
+           * returns, and matches if it equals the value or is an array
+           * containing it. Calls from clients without the claims are
+           * rejected before the function runs. Admins, and other functions
+           * calling this one, aren't restricted.
+           */
+          requiredClaims?: RequiredClaims;
           /**
            * The implementation of this function.
            *
@@ -13,6 +13,22 @@ export type SchedulableFunctionReference = FunctionReference<
   "public" | "internal"
 >;
 
+/**
+ * Options for {@link Scheduler.withIdempotencyKey}.
+ *
+ * @public
+ */
+export type IdempotencyKeyOptions = {
+  /**
+   * What to do if a function scheduled with the same key hasn't started yet.
+   * `"replace"` cancels it and schedules the new one, so scheduling the same
+   * key repeatedly debounces it. `"skip"` keeps it and returns its ID instead.
+   *
+   * Defaults to `"replace"`.
+   */
+  onConflict?: "replace" | "skip";
+};
+
 /**
  * An interface to schedule Convex functions.
  *
@@ -69,4 +85,23 @@ export interface Scheduler {
    * @param id
    */
   cancel(id: Id<"_scheduled_functions">): Promise<void>;
+
+  /**
+   * Returns a scheduler that deduplicates the functions it schedules by
+   * `key`: at most one function scheduled with a key in this component is
+   * pending at a time. Functions that already started aren't affected.
+   *
+   * ```ts
+   * await ctx.scheduler
+   *   .withIdempotencyKey(`reindex:${docId}`)
+   *   .runAfter(5000, internal.search.reindex, { docId });
+   * ```
+   *
+   * @param key - Identifies the scheduled function, up to 1024 bytes.
+   * @param options - What to do if a function with the key is pending.
+   **/
+  withIdempotencyKey(
+    key: string,
+    options?: IdempotencyKeyOptions,
+  ): Pick<Scheduler, "runAfter" | "runAt">;
 }
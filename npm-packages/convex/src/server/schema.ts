@@ -94,8 +94,44 @@ export interface SearchIndexConfig<
    * Additional fields to index for fast filtering when running search queries.
    */
   filterFields?: FilterFields[];
+
+  /**
+   * How the search field is split into words, both when indexing documents
+   * and when running search queries. Defaults to `"simple"`.
+   *
+   * Changing the analyzer rebuilds the index.
+   */
+  analyzer?: SearchIndexAnalyzer;
 }
 
+/**
+ * How a search index splits text into words.
+ *
+ * - `"simple"` splits on whitespace and punctuation and lowercases each word.
+ * - `"cjk"` also splits runs of Chinese, Japanese and Korean characters into
+ *   overlapping pairs of characters.
+ * - A language also drops that language's stop words and reduces each word to
+ *   its stem, so "running" matches "runs".
+ *
+ * @public
+ */
+export type SearchIndexAnalyzer =
+  | "simple"
+  | "cjk"
+  | "danish"
+  | "dutch"
+  | "english"
+  | "finnish"
+  | "french"
+  | "german"
+  | "hungarian"
+  | "italian"
+  | "norwegian"
+  | "portuguese"
+  | "russian"
+  | "spanish"
+  | "swedish";
+
 /**
  * The configuration for a vector index.
  *
@@ -104,6 +140,7 @@ export interface SearchIndexConfig<
 export interface VectorIndexConfig<
   VectorField extends string,
   FilterFields extends string,
+  EmbedFromField extends string = string,
 > {
   /**
    * The field to index for vector search.
@@ -119,8 +156,41 @@ export interface VectorIndexConfig<
    * Additional fields to index for fast filtering when running vector searches.
    */
   filterFields?: FilterFields[];
+
+  /**
+   * How vectors are compared, which should match how the embedding model was
+   * trained. Defaults to `"cosine"`.
+   *
+   * Changing the distance rebuilds the index.
+   */
+  distance?: VectorIndexDistance;
+
+  /**
+   * A string field to generate `vectorField` from. Whenever a document is
+   * inserted or this field changes, the deployment's embedding provider
+   * embeds the text and the vector is written into `vectorField`.
+   *
+   * Writes that set `vectorField` themselves are left alone. Requires the
+   * deployment to be configured with an embedding provider.
+   */
+  embedFrom?: EmbedFromField;
 }
 
+/**
+ * How a vector index compares vectors. Vector searches return the closest
+ * matches first, with a higher `_score` for closer matches.
+ *
+ * - `"cosine"` scores by the cosine of the angle between the vectors, from -1
+ *   to 1.
+ * - `"dotProduct"` scores by the dot product of the vectors, for embedding
+ *   models trained for inner-product retrieval.
+ * - `"euclidean"` scores by the negated Euclidean distance between the
+ *   vectors, so an identical vector scores 0.
+ *
+ * @public
+ */
+export type VectorIndexDistance = "cosine" | "dotProduct" | "euclidean";
+
 /**
  * @internal
  */
@@ -129,6 +199,8 @@ export type VectorIndex = {
   vectorField: string;
   dimensions: number;
   filterFields: string[];
+  distance?: VectorIndexDistance;
+  embedFrom?: string;
 };
 
 /**
@@ -146,6 +218,7 @@ export type SearchIndex = {
   indexDescriptor: string;
   searchField: string;
   filterFields: string[];
+  analyzer?: SearchIndexAnalyzer;
 };
 /**
  * The definition of a table within a schema.
@@ -247,6 +320,9 @@ export class TableDefinition<
       indexDescriptor: name,
       searchField: indexConfig.searchField,
       filterFields: indexConfig.filterFields || [],
+      ...(indexConfig.analyzer !== undefined
+        ? { analyzer: indexConfig.analyzer }
+        : {}),
     });
     return this;
   }
@@ -266,7 +342,13 @@ export class TableDefinition<
     FilterFields extends ExtractFieldPaths<DocumentType> = never,
   >(
     name: IndexName,
-    indexConfig: Expand<VectorIndexConfig<VectorField, FilterFields>>,
+    indexConfig: Expand<
+      VectorIndexConfig<
+        VectorField,
+        FilterFields,
+        ExtractFieldPaths<DocumentType>
+      >
+    >,
   ): TableDefinition<
     DocumentType,
     Indexes,
@@ -288,6 +370,12 @@ export class TableDefinition<
       vectorField: indexConfig.vectorField,
       dimensions: indexConfig.dimensions,
       filterFields: indexConfig.filterFields || [],
+      ...(indexConfig.distance !== undefined
+        ? { distance: indexConfig.distance }
+        : {}),
+      ...(indexConfig.embedFrom !== undefined
+        ? { embedFrom: indexConfig.embedFrom }
+        : {}),
     });
     return this;
   }
@@ -579,7 +667,31 @@ const _systemSchema = defineSchema({
     sha256: v.string(),
     size: v.float64(),
     contentType: v.optional(v.string()),
+    filename: v.optional(v.string()),
+    tags: v.optional(v.record(v.string(), v.string())),
+    scanStatus: v.optional(
+      v.union(
+        v.literal("pending"),
+        v.literal("clean"),
+        v.literal("quarantined"),
+      ),
+    ),
   }),
+  _metrics: defineTable({
+    granularity: v.union(v.literal("hour"), v.literal("day")),
+    bucketStart: v.float64(),
+    kind: v.union(v.literal("function"), v.literal("table")),
+    name: v.string(),
+    calls: v.int64(),
+    errors: v.int64(),
+    latencyHistogram: v.array(v.int64()),
+    latencyP50Ms: v.optional(v.float64()),
+    latencyP90Ms: v.optional(v.float64()),
+    latencyP99Ms: v.optional(v.float64()),
+    bandwidthBytes: v.int64(),
+    rowsRead: v.int64(),
+    rowsWritten: v.int64(),
+  }).index("by_bucket", ["granularity", "bucketStart", "kind", "name"]),
 });
 
 export interface SystemDataModel
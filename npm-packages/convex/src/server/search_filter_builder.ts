@@ -38,13 +38,38 @@ export interface SearchFilterBuilder<
    * @param fieldName - The name of the field to search in. This must be listed
    * as the index's `searchField`.
    * @param query - The query text to search for.
+   * @param options - How loosely words in `query` can match. By default only
+   * whole words that match exactly are found.
    */
   search(
     fieldName: SearchIndexConfig["searchField"],
     query: string,
+    options?: TextSearchOptions,
   ): SearchFilterFinalizer<Document, SearchIndexConfig>;
 }
 
+/**
+ * Options for how {@link SearchFilterBuilder.search} matches the words in its
+ * query.
+ *
+ * @public
+ */
+export type TextSearchOptions = {
+  /**
+   * Also match words that start with the last word of the query, so results
+   * show up while the user is still typing it. Matches like this rank below
+   * exact matches.
+   */
+  prefix?: boolean;
+  /**
+   * The most typos (inserted, deleted or changed characters) allowed in each
+   * word of the query. Short words allow fewer: words of up to four characters
+   * must match exactly, and words of up to eight allow one typo. Matches with
+   * typos rank below exact matches. Defaults to 0.
+   */
+  maxEditDistance?: 0 | 1 | 2;
+};
+
 /**
  * Builder to define equality expressions as part of a search filter.
  *
@@ -0,0 +1,49 @@
+import { performSyscall } from "./impl/syscall.js";
+
+/**
+ * Options for {@link seededRandom}.
+ *
+ * @public
+ */
+export type SeededRandomOptions = {
+  /**
+   * Distinguishes independent random streams within the same function call.
+   * Defaults to the empty string.
+   */
+  key?: string;
+  /**
+   * How many values to generate. At most 8192. Defaults to 1.
+   */
+  count?: number;
+  /**
+   * If set, the values also depend on the function's execution time rounded
+   * down to a multiple of this many milliseconds, so they change once per
+   * period. Cached query results expire as if the query had called
+   * `Date.now()`.
+   */
+  periodMs?: number;
+};
+
+/**
+ * Generate random numbers in `[0, 1)` that are a deterministic function of
+ * the current function's arguments and `options`.
+ *
+ * Unlike `Math.random()`, using these values keeps a query cacheable, which
+ * makes them suited for stable sampling and shuffling like picking a
+ * "featured item". Calling it twice with the same options returns the same
+ * values, so pass a different `key` for independent streams.
+ *
+ * The values are derived from a ChaCha12 stream seeded with a SHA-256 digest
+ * of the Convex JSON encoding of the arguments, `key`, and the time period.
+ * This derivation is stable across Convex versions.
+ *
+ * @public
+ */
+export function seededRandom(options: SeededRandomOptions = {}): number[] {
+  const { values } = performSyscall("1.0/seededRandom", {
+    key: options.key ?? "",
+    count: options.count ?? 1,
+    periodMs: options.periodMs,
+  });
+  return values;
+}
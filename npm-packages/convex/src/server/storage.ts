@@ -32,6 +32,60 @@ export type FileMetadata = {
    * ContentType of the file if it was provided on upload
    */
   contentType: string | null;
+  /**
+   * Filename of the file if it was provided on upload. Files with a filename
+   * are served with a `Content-Disposition` header naming it.
+   */
+  filename: string | null;
+  /**
+   * Tags set on the file when it was uploaded
+   */
+  tags: Record<string, string>;
+};
+
+/**
+ * Options for storing a file with {@link StorageActionWriter.store}.
+ *
+ * @public
+ */
+export type StoreFileOptions = {
+  /**
+   * If provided, this will verify the sha256 checksum matches the contents of the file.
+   */
+  sha256?: string;
+  /**
+   * The file's name, which it's served under.
+   */
+  filename?: string;
+  /**
+   * Key/value tags to set on the file. Tags are stored on the `_storage`
+   * document, so queries can filter files by them, like
+   * `q.eq(q.field("tags.kind"), "avatar")`.
+   */
+  tags?: Record<string, string>;
+};
+
+/**
+ * Limits on what can be uploaded with a URL from
+ * {@link StorageWriter.generateUploadUrl}.
+ *
+ * @public
+ */
+export type UploadUrlOptions = {
+  /**
+   * The largest file, in bytes, that the URL can upload.
+   */
+  maxBytes?: number;
+  /**
+   * The content types the file can have, like `"image/png"` or `"image/*"`.
+   * Uploads must set a matching `Content-Type` header.
+   */
+  allowedContentTypes?: string[];
+  /**
+   * How long the URL works for, in milliseconds. URLs expire after an hour
+   * regardless.
+   */
+  expiresInMs?: number;
 };
 
 /**
@@ -98,11 +152,18 @@ export interface StorageWriter extends StorageReader {
    *
    * Upon a POST request to this URL, the endpoint will return a JSON object containing a newly allocated `Id<"_storage">`.
    *
-   * The POST URL accepts an optional standard HTTP Digest header with a sha256 checksum.
+   * The POST URL accepts an optional standard HTTP Digest header with a sha256 checksum,
+   * an optional `Content-Disposition` header with the file's filename, and an
+   * optional `Convex-Storage-Tags` header with tags to set on the file, URL-encoded
+   * like `kind=avatar&user=abc`.
+   *
+   * Pass `options` to limit what can be uploaded with the URL, which the
+   * upload endpoint enforces.
    *
+   * @param options - {@link UploadUrlOptions} for the upload.
    * @returns - A url that allows file upload via an HTTP POST.
    */
-  generateUploadUrl(): Promise<string>;
+  generateUploadUrl(options?: UploadUrlOptions): Promise<string>;
   /**
    * Delete a file from Convex storage.
    *
@@ -152,6 +213,6 @@ export interface StorageActionWriter extends StorageWriter {
    */
   store(
     blob: Blob,
-    options?: { sha256?: string },
+    options?: StoreFileOptions,
   ): Promise<GenericId<"_storage">>;
 }
@@ -0,0 +1,20 @@
+import { jsonToConvex } from "../values/index.js";
+import { performSyscall } from "./impl/syscall.js";
+
+/**
+ * The timestamp of the snapshot the current query or mutation reads from.
+ *
+ * Timestamps are totally ordered across a deployment, so unlike `Date.now()`
+ * they give a precise position for ordering writes or resuming an external
+ * sync. A mutation's writes are committed at a later timestamp, which is
+ * returned as `ts` in the mutation's response. In queries, this is the
+ * timestamp the result was computed at, which can be earlier than the current
+ * time if the result was served from the cache.
+ *
+ * @returns The timestamp, in nanoseconds since the Unix epoch.
+ * @public
+ */
+export function transactionTimestamp(): bigint {
+  const { ts } = performSyscall("1.0/transactionTimestamp", {});
+  return jsonToConvex(ts) as bigint;
+}
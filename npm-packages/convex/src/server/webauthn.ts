@@ -0,0 +1,111 @@
+import { performAsyncSyscall } from "./impl/syscall.js";
+import { GenericMutationCtx } from "./registration.js";
+
+/**
+ * Where the app's login page is served, which every passkey is scoped to.
+ *
+ * @public
+ */
+export type WebAuthnRelyingParty = {
+  /**
+   * The relying party ID passed to `navigator.credentials`, usually the
+   * app's domain, e.g. `"example.com"`.
+   */
+  rpId: string;
+  /**
+   * The origins the app's login page may be served from, e.g.
+   * `["https://example.com"]`.
+   */
+  origins: string[];
+  /**
+   * Whether to reject authenticators that didn't verify the user with a PIN
+   * or biometric. Defaults to `false`.
+   */
+  requireUserVerification?: boolean;
+};
+
+/**
+ * The result of a successful passkey registration or login.
+ *
+ * @public
+ */
+export type WebAuthnResult = {
+  /**
+   * The user the passkey belongs to.
+   */
+  userId: string;
+  /**
+   * The passkey's credential ID, base64url encoded.
+   */
+  credentialId: string;
+};
+
+/**
+ * Start registering a passkey for `userId`.
+ *
+ * Pass the returned base64url-encoded `challenge` to
+ * `navigator.credentials.create()` in the browser, then send its response to
+ * {@link finishWebAuthnRegistration}. Challenges expire after five minutes.
+ *
+ * @public
+ */
+export async function startWebAuthnRegistration(
+  _ctx: GenericMutationCtx<any>,
+  args: { userId: string },
+): Promise<{ challenge: string }> {
+  return await performAsyncSyscall("1.0/webauthn/startRegistration", args);
+}
+
+/**
+ * Verify the browser's response to `navigator.credentials.create()` and
+ * store the new passkey for the user its challenge was issued to.
+ *
+ * All binary fields are base64url encoded.
+ *
+ * @public
+ */
+export async function finishWebAuthnRegistration(
+  _ctx: GenericMutationCtx<any>,
+  args: WebAuthnRelyingParty & {
+    clientDataJSON: string;
+    attestationObject: string;
+  },
+): Promise<WebAuthnResult> {
+  return await performAsyncSyscall("1.0/webauthn/finishRegistration", args);
+}
+
+/**
+ * Start logging in with a passkey.
+ *
+ * If `userId` is given, only that user's passkeys are accepted and their IDs
+ * are returned as `allowCredentials`. Otherwise any registered passkey can be
+ * used.
+ *
+ * @public
+ */
+export async function startWebAuthnAuthentication(
+  _ctx: GenericMutationCtx<any>,
+  args: { userId?: string },
+): Promise<{ challenge: string; allowCredentials: string[] }> {
+  return await performAsyncSyscall("1.0/webauthn/startAuthentication", args);
+}
+
+/**
+ * Verify the browser's response to `navigator.credentials.get()`, returning
+ * the user who logged in. Throws if the signature or challenge is invalid.
+ *
+ * All binary fields are base64url encoded.
+ *
+ * @public
+ */
+export async function finishWebAuthnAuthentication(
+  _ctx: GenericMutationCtx<any>,
+  args: WebAuthnRelyingParty & {
+    credentialId: string;
+    clientDataJSON: string;
+    authenticatorData: string;
+    signature: string;
+  },
+): Promise<WebAuthnResult> {
+  return await performAsyncSyscall("1.0/webauthn/finishAuthentication", args);
+}
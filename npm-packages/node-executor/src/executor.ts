@@ -400,6 +400,8 @@ export type AnalyzedFunctions = Array<{
   visibility: Visibility | null;
   args: JSONValue | null;
   output: JSONValue | null;
+  region: string | null;
+  requiredClaims: JSONValue | null;
 }>;
 
 async function analyzeModule(filePath: string): Promise<AnalyzedFunctions> {
@@ -414,6 +416,8 @@ async function analyzeModule(filePath: string): Promise<AnalyzedFunctions> {
       visibility: Visibility | null;
       args: JSONValue | null;
       output: JSONValue | null;
+      region: string | null;
+      requiredClaims: JSONValue | null;
     }
   > = new Map();
   for (const [name, value] of Object.entries(module)) {
@@ -476,6 +480,19 @@ async function analyzeModule(filePath: string): Promise<AnalyzedFunctions> {
       }
     }
 
+    // Checked for being a configured region when the function is called, so
+    // pushing doesn't depend on the backend's region configuration.
+    const region =
+      udfType === "action" && typeof (value as any).region === "string"
+        ? (value as any).region
+        : null;
+
+    // Validated by the backend, which reports invalid claims as an error.
+    const requiredClaims =
+      (value as any).requiredClaims !== undefined
+        ? (value as any).requiredClaims
+        : null;
+
     if (isPublic && isInternal) {
       logDebug(`Skipping function marked as both public and internal: ${name}`);
       continue;
@@ -485,6 +502,8 @@ async function analyzeModule(filePath: string): Promise<AnalyzedFunctions> {
         visibility: { kind: "public" },
         args,
         output,
+        region,
+        requiredClaims,
       });
     } else if (isInternal) {
       functions.set(name, {
@@ -492,9 +511,18 @@ async function analyzeModule(filePath: string): Promise<AnalyzedFunctions> {
         visibility: { kind: "internal" },
         args,
         output,
+        region,
+        requiredClaims,
       });
     } else {
-      functions.set(name, { udfType, visibility: null, args, output });
+      functions.set(name, {
+        udfType,
+        visibility: null,
+        args,
+        output,
+        region,
+        requiredClaims,
+      });
     }
   }
   // Do an awful, regex based line match that assumes that moduleConfig.source originates from
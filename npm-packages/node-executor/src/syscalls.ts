@@ -46,6 +46,8 @@ const scheduleSchema = z.object({
   functionHandle: z.optional(z.string()),
   ts: z.number(),
   args: z.any(),
+  idempotencyKey: z.optional(z.string()),
+  onConflict: z.optional(z.string()),
   version: z.string(),
 });
 
@@ -297,6 +299,9 @@ export class SyscallsImpl {
         case "1.0/actions/vectorSearch": {
           return JSON.stringify(await this.syscallVectorSearch(jsonArgs));
         }
+        case "1.0/actions/hybridSearch": {
+          return JSON.stringify(await this.syscallHybridSearch(jsonArgs));
+        }
         case "1.0/schedule":
           throw new Error(
             "The mutation scheduler is being used outside of a Convex mutation. Did" +
@@ -508,6 +513,29 @@ export class SyscallsImpl {
     });
   }
 
+  async syscallHybridSearch(rawArgs: string): Promise<JSONValue> {
+    const hybridSearchSchema = z.object({
+      query: z.any(),
+      version: z.string(),
+    });
+    const hybridSearchReturn = z.object({
+      results: z.array(z.any()),
+    });
+    const operationName = "hybrid search";
+    const hybridSearchArgs = this.validateArgs(
+      rawArgs,
+      hybridSearchSchema,
+      operationName,
+    );
+    return this.actionCallback({
+      version: hybridSearchArgs.version,
+      body: { query: hybridSearchArgs.query },
+      path: "/api/actions/hybrid_search",
+      operationName,
+      responseValidator: hybridSearchReturn,
+    });
+  }
+
   async syscallSchedule(rawArgs: string): Promise<JSONValue> {
     const scheduleReturn = z.object({
       jobId: z.string(),
@@ -526,6 +554,8 @@ export class SyscallsImpl {
         udfPath: scheduleArgs.name,
         udfArgs: scheduleArgs.args,
         scheduledTs: scheduleArgs.ts,
+        idempotencyKey: scheduleArgs.idempotencyKey,
+        onConflict: scheduleArgs.onConflict,
       },
       path: "/api/actions/schedule_job",
       operationName,
@@ -561,24 +591,34 @@ export class SyscallsImpl {
   async syscallStorageGenerateUploadUrl(rawArgs: string): Promise<JSONValue> {
     const storageGenerateUploadUrlArgs = z.object({
       version: z.string(),
+      maxBytes: z.optional(z.number()),
+      allowedContentTypes: z.optional(z.array(z.string())),
+      expiresInMs: z.optional(z.number()),
     });
     const operationName = "generate upload url";
-    const args = this.validateArgs(
+    const { version, ...options } = this.validateArgs(
       rawArgs,
       storageGenerateUploadUrlArgs,
       operationName,
     );
-    return this._storageGenerateUploadUrl(args.version);
+    return this._storageGenerateUploadUrl(version, options);
   }
 
-  async _storageGenerateUploadUrl(version: string): Promise<string> {
+  async _storageGenerateUploadUrl(
+    version: string,
+    options: {
+      maxBytes?: number;
+      allowedContentTypes?: string[];
+      expiresInMs?: number;
+    } = {},
+  ): Promise<string> {
     const storageGenerateUploadUrlReturn = z.object({
       url: z.string(),
     });
     const operationName = "generate upload url";
     const result = await this.actionCallback({
       version,
-      body: {},
+      body: options,
       path: "/api/actions/storage_generate_upload_url",
       operationName,
       responseValidator: storageGenerateUploadUrlReturn,
@@ -660,6 +700,15 @@ export class SyscallsImpl {
     if (options?.sha256 !== undefined) {
       headers["Digest"] = `sha-256=${options.sha256}`;
     }
+    if (options?.filename !== undefined) {
+      headers["Content-Disposition"] =
+        `attachment; filename*=UTF-8''${encodeURIComponent(options.filename)}`;
+    }
+    if (options?.tags !== undefined) {
+      headers["Convex-Storage-Tags"] = new URLSearchParams(
+        options.tags,
+      ).toString();
+    }
 
     const uploadUrl = await this._storageGenerateUploadUrl(args["version"]);
     const response = await fetch(uploadUrl, {
@@ -288,6 +288,18 @@ export default defineSchema({
         state: v.literal("in_progress"),
         start_ts: v.int64(),
         requestor: v.literal("snapshotExport"),
+        progress: v.optional(
+          v.union(
+            v.null(),
+            v.object({
+              component_path: v.union(v.string(), v.null()),
+              current_table: v.union(v.string(), v.null()),
+              documents_written: v.int64(),
+              total_documents: v.int64(),
+              bytes_written: v.int64(),
+            }),
+          ),
+        ),
       }),
       v.object({
         state: v.literal("requested"),
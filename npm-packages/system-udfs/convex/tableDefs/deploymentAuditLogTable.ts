@@ -182,6 +182,31 @@ export const snapshotImport = v.object({
   }),
 });
 
+export const restoreTables = v.object({
+  action: v.literal("restore_tables"),
+  member_id: v.union(v.int64(), v.null()),
+  metadata: v.object({
+    table_names: v.array(v.string()),
+    table_count: v.int64(),
+    restore_ts: v.int64(),
+  }),
+});
+
+export const rejectNetworkRequest = v.object({
+  action: v.literal("reject_network_request"),
+  member_id: v.union(v.int64(), v.null()),
+  metadata: v.object({
+    ip: v.string(),
+    country: v.union(v.string(), v.null()),
+    reason: v.union(
+      v.literal("denied"),
+      v.literal("not_allowed"),
+      v.literal("country_blocked"),
+    ),
+    path: v.string(),
+  }),
+});
+
 const deploymentAuditLogTable = defineTable(
   v.union(
     createEnvironmentVariable,
@@ -194,6 +219,8 @@ const deploymentAuditLogTable = defineTable(
     changeDeploymentState,
     clearTables,
     snapshotImport,
+    restoreTables,
+    rejectNetworkRequest,
   ),
 );
 
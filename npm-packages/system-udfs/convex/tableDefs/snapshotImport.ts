@@ -67,6 +67,18 @@ export const snapshotImportsTable = defineTable({
     ),
   ),
 
+  progress: v.optional(
+    v.union(
+      v.null(),
+      v.object({
+        component_path: v.union(v.string(), v.null()),
+        current_table: v.union(v.string(), v.null()),
+        documents_written: v.int64(),
+        bytes_written: v.int64(),
+      }),
+    ),
+  ),
+
   // This is optional for the moment because historical data hasn’t been
   // backfilled yet.
   requestor: v.optional(
@@ -866,3 +866,5 @@ export function setupEvent(global) {
   global.Event = Event;
   global.EventTarget = EventTarget;
 }
+
+export { Event, EventTarget };
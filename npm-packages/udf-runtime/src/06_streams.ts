@@ -11,8 +11,20 @@ import { performAsyncOp, performOp } from "./syscall";
 
 export { ReadableStream };
 
-export const constructStreamId = (stream: ReadableStream | null): string => {
+export type StreamOptions = {
+  // Don't read more than this many bytes ahead of the stream's Rust reader.
+  maxBufferedBytes?: number;
+};
+
+export const constructStreamId = (
+  stream: ReadableStream | null,
+  options?: StreamOptions,
+): string => {
   const streamId = performOp("stream/create");
+  const maxBufferedBytes = options?.maxBufferedBytes;
+  if (maxBufferedBytes !== undefined) {
+    performOp("stream/limit", streamId, maxBufferedBytes);
+  }
   const reader = stream?.getReader();
   void populateStream();
   return streamId;
@@ -23,6 +35,19 @@ export const constructStreamId = (stream: ReadableStream | null): string => {
       return;
     }
     const { value, done } = await reader.read();
+    if (maxBufferedBytes !== undefined && !done) {
+      try {
+        await performAsyncOp(
+          "stream/reserve",
+          streamId,
+          Math.min(value.byteLength, maxBufferedBytes),
+        );
+      } catch (e) {
+        // The reader went away, so stop reading the stream.
+        void reader.cancel(e);
+        return;
+      }
+    }
     performOp("stream/extend", streamId, value, done);
     if (!done) {
       void populateStream();
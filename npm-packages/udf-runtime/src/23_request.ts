@@ -346,8 +346,16 @@ export const requestFromConvexJson = ({
   return request;
 };
 
+// How far ahead of the HTTP client `fetch` reads a streamed request body, so
+// uploads use constant memory however large they are.
+const MAX_BUFFERED_REQUEST_BODY_BYTES = 1 << 20;
+
 export const convexV8ObjectFromRequest = async (request: Request) => {
-  const streamId = request.body ? constructStreamId(request.body) : null;
+  const streamId = request.body
+    ? constructStreamId(request.body, {
+        maxBufferedBytes: MAX_BUFFERED_REQUEST_BODY_BYTES,
+      })
+    : null;
   const headerPairs = [...request.headers.entries()];
   if (
     request[_contentLength] !== null &&
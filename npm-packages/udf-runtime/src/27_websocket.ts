@@ -0,0 +1,359 @@
+import { Event, EventTarget } from "./02_event";
+import { Blob } from "./09_file.js";
+import { performAsyncOp, performOp } from "./syscall.js";
+
+// Messages are passed to and from Rust as stream chunks, each holding one
+// message after a byte saying what kind it is.
+const FRAME_TEXT = 0;
+const FRAME_BINARY = 1;
+const FRAME_CLOSE = 2;
+
+const CONNECTING = 0;
+const OPEN = 1;
+const CLOSING = 2;
+const CLOSED = 3;
+
+// https://html.spec.whatwg.org/multipage/comms.html#messageevent
+class MessageEvent extends Event {
+  readonly data: any;
+  readonly origin: string;
+  readonly lastEventId: string;
+
+  constructor(
+    type: string,
+    eventInitDict?: EventInit & {
+      data?: any;
+      origin?: string;
+      lastEventId?: string;
+    },
+  ) {
+    super(type, eventInitDict);
+    this.data = eventInitDict?.data ?? null;
+    this.origin = eventInitDict?.origin ?? "";
+    this.lastEventId = eventInitDict?.lastEventId ?? "";
+  }
+}
+
+// https://websockets.spec.whatwg.org/#the-closeevent-interface
+class CloseEvent extends Event {
+  readonly wasClean: boolean;
+  readonly code: number;
+  readonly reason: string;
+
+  constructor(
+    type: string,
+    eventInitDict?: EventInit & {
+      wasClean?: boolean;
+      code?: number;
+      reason?: string;
+    },
+  ) {
+    super(type, eventInitDict);
+    this.wasClean = eventInitDict?.wasClean ?? false;
+    this.code = eventInitDict?.code ?? 0;
+    this.reason = eventInitDict?.reason ?? "";
+  }
+}
+
+type BinaryType = "blob" | "arraybuffer";
+
+// See https://developer.mozilla.org/en-US/docs/Web/API/WebSocket
+// https://websockets.spec.whatwg.org/
+// Connections are only available in actions, and are closed when the action
+// finishes.
+class WebSocket extends EventTarget {
+  static readonly CONNECTING = CONNECTING;
+  static readonly OPEN = OPEN;
+  static readonly CLOSING = CLOSING;
+  static readonly CLOSED = CLOSED;
+  readonly CONNECTING = CONNECTING;
+  readonly OPEN = OPEN;
+  readonly CLOSING = CLOSING;
+  readonly CLOSED = CLOSED;
+
+  readonly url: string;
+  onopen: ((event: Event) => void) | null = null;
+  onmessage: ((event: MessageEvent) => void) | null = null;
+  onerror: ((event: Event) => void) | null = null;
+  onclose: ((event: CloseEvent) => void) | null = null;
+
+  private _readyState = CONNECTING;
+  private _protocol = "";
+  private _binaryType: BinaryType = "blob";
+  private _outgoingStreamId: string;
+  private _outgoingDone = false;
+  // Sends are chained so messages go out in order even when a Blob's contents
+  // have to be read first.
+  private _sent: Promise<void> = Promise.resolve();
+  // Set if `close()` was called before the connection opened.
+  private _pendingClose: Uint8Array | null = null;
+
+  constructor(url: string | URL, protocols?: string | string[]) {
+    super();
+    this.url = parseWebSocketUrl(url);
+    const protocolList =
+      protocols === undefined
+        ? []
+        : typeof protocols === "string"
+          ? [protocols]
+          : [...protocols];
+    if (new Set(protocolList).size !== protocolList.length) {
+      throw new DOMException(
+        "WebSocket protocols must be unique",
+        "SyntaxError",
+      );
+    }
+    this._outgoingStreamId = performOp("stream/create");
+    // Start connecting synchronously so using a WebSocket outside of an action
+    // throws here.
+    const connected = performAsyncOp("websocket/connect", {
+      url: this.url,
+      protocols: protocolList,
+      outgoingStreamId: this._outgoingStreamId,
+    });
+    void this._run(connected);
+  }
+
+  get readyState(): number {
+    return this._readyState;
+  }
+
+  get protocol(): string {
+    return this._protocol;
+  }
+
+  get extensions(): string {
+    return "";
+  }
+
+  get bufferedAmount(): number {
+    // Messages are handed to Rust as soon as they're sent.
+    return 0;
+  }
+
+  get binaryType(): BinaryType {
+    return this._binaryType;
+  }
+
+  set binaryType(value: BinaryType) {
+    if (value === "blob" || value === "arraybuffer") {
+      this._binaryType = value;
+    }
+  }
+
+  send(data: string | ArrayBufferLike | ArrayBufferView | Blob): void {
+    if (this._readyState === CONNECTING) {
+      throw new DOMException(
+        "Failed to execute 'send' on 'WebSocket': Still in CONNECTING state.",
+        "InvalidStateError",
+      );
+    }
+    if (this._readyState !== OPEN) {
+      return;
+    }
+    let frame: Promise<Uint8Array> | Uint8Array;
+    if (typeof data === "string") {
+      frame = encodeFrame(FRAME_TEXT, new TextEncoder().encode(data));
+    } else if (data instanceof Blob) {
+      frame = data
+        .arrayBuffer()
+        .then((buffer) => encodeFrame(FRAME_BINARY, new Uint8Array(buffer)));
+    } else if (ArrayBuffer.isView(data)) {
+      frame = encodeFrame(
+        FRAME_BINARY,
+        new Uint8Array(data.buffer, data.byteOffset, data.byteLength),
+      );
+    } else if (data instanceof ArrayBuffer) {
+      frame = encodeFrame(FRAME_BINARY, new Uint8Array(data));
+    } else {
+      frame = encodeFrame(FRAME_TEXT, new TextEncoder().encode(String(data)));
+    }
+    this._enqueue(frame, false);
+  }
+
+  close(code?: number, reason?: string): void {
+    if (
+      code !== undefined &&
+      code !== 1000 &&
+      !(code >= 3000 && code <= 4999)
+    ) {
+      throw new DOMException(
+        `The close code must be either 1000, or between 3000 and 4999. ${code} is neither.`,
+        "InvalidAccessError",
+      );
+    }
+    const reasonBytes = new TextEncoder().encode(reason ?? "");
+    if (reasonBytes.byteLength > 123) {
+      throw new DOMException(
+        "The close reason must not be greater than 123 UTF-8 bytes.",
+        "SyntaxError",
+      );
+    }
+    if (this._readyState === CLOSING || this._readyState === CLOSED) {
+      return;
+    }
+    let payload = new Uint8Array(0);
+    if (code !== undefined) {
+      payload = new Uint8Array(2 + reasonBytes.byteLength);
+      payload[0] = code >> 8;
+      payload[1] = code & 0xff;
+      payload.set(reasonBytes, 2);
+    }
+    const frame = encodeFrame(FRAME_CLOSE, payload);
+    if (this._readyState === CONNECTING) {
+      this._pendingClose = frame;
+    } else {
+      this._enqueue(frame, true);
+    }
+    this._readyState = CLOSING;
+  }
+
+  private _enqueue(frame: Promise<Uint8Array> | Uint8Array, done: boolean) {
+    this._sent = this._sent.then(async () => {
+      const value = await frame;
+      if (this._outgoingDone) {
+        return;
+      }
+      performOp("stream/extend", this._outgoingStreamId, value, false);
+      if (done) {
+        this._finishSending();
+      }
+    });
+  }
+
+  private _finishSending() {
+    if (!this._outgoingDone) {
+      this._outgoingDone = true;
+      performOp("stream/extend", this._outgoingStreamId, undefined, true);
+    }
+  }
+
+  private async _run(connected: Promise<any>) {
+    let incomingStreamId: string;
+    try {
+      const response = await connected;
+      incomingStreamId = response.incomingStreamId;
+      this._protocol = response.protocol;
+    } catch (e: any) {
+      this._readyState = CLOSED;
+      this._dispatch(errorEvent(e));
+      this._dispatch(
+        new CloseEvent("close", { wasClean: false, code: 1006, reason: "" }),
+      );
+      return;
+    }
+    if (this._pendingClose !== null) {
+      this._enqueue(this._pendingClose, true);
+    } else {
+      this._readyState = OPEN;
+      this._dispatch(new Event("open"));
+    }
+
+    let wasClean = false;
+    let code = 1006;
+    let closeReason = "";
+    try {
+      // eslint-disable-next-line no-constant-condition
+      while (true) {
+        const { value, done } = await performAsyncOp(
+          "stream/readPart",
+          incomingStreamId,
+        );
+        if (done === true) {
+          break;
+        }
+        const kind = value[0];
+        const payload: Uint8Array = value.subarray(1);
+        if (kind === FRAME_CLOSE) {
+          wasClean = true;
+          code = 1005;
+          if (payload.byteLength >= 2) {
+            code = (payload[0] << 8) | payload[1];
+            closeReason = new TextDecoder().decode(payload.subarray(2));
+          }
+          this._readyState = CLOSING;
+          // The close handshake is finished in Rust.
+          this._finishSending();
+        } else if (this._readyState === OPEN) {
+          const data =
+            kind === FRAME_TEXT
+              ? new TextDecoder().decode(payload)
+              : this._binaryType === "arraybuffer"
+                ? payload.slice().buffer
+                : new Blob([payload]);
+          this._dispatch(
+            new MessageEvent("message", {
+              data,
+              origin: new URL(this.url).origin,
+            }),
+          );
+        }
+      }
+    } catch (e: any) {
+      wasClean = false;
+      code = 1006;
+      this._dispatch(errorEvent(e));
+    }
+    this._finishSending();
+    this._readyState = CLOSED;
+    this._dispatch(
+      new CloseEvent("close", { wasClean, code, reason: closeReason }),
+    );
+  }
+
+  private _dispatch(event: Event) {
+    this.dispatchEvent(event);
+    const handler = (this as any)[`on${event.type}`];
+    if (typeof handler === "function") {
+      handler.call(this, event);
+    }
+  }
+}
+
+function parseWebSocketUrl(url: string | URL): string {
+  let parsed: URL;
+  try {
+    parsed = new URL(url.toString());
+  } catch {
+    throw new DOMException(`Invalid WebSocket URL: ${url}`, "SyntaxError");
+  }
+  let href = parsed.href;
+  if (parsed.protocol === "http:") {
+    href = "ws" + href.slice("http".length);
+  } else if (parsed.protocol === "https:") {
+    href = "wss" + href.slice("https".length);
+  } else if (parsed.protocol !== "ws:" && parsed.protocol !== "wss:") {
+    throw new DOMException(
+      `WebSocket URL scheme must be ws or wss, not ${parsed.protocol}`,
+      "SyntaxError",
+    );
+  }
+  if (parsed.hash !== "") {
+    throw new DOMException(
+      "WebSocket URLs cannot have a fragment",
+      "SyntaxError",
+    );
+  }
+  return href;
+}
+
+function encodeFrame(kind: number, payload: Uint8Array): Uint8Array {
+  const frame = new Uint8Array(payload.byteLength + 1);
+  frame[0] = kind;
+  frame.set(payload, 1);
+  return frame;
+}
+
+function errorEvent(e: any): Event {
+  const event = new Event("error");
+  // Not in the spec, but useful since there's no devtools to see why the
+  // connection failed.
+  (event as any).message = e?.message ?? String(e);
+  return event;
+}
+
+export const setupWebSocket = (global: any) => {
+  global.MessageEvent = MessageEvent;
+  global.CloseEvent = CloseEvent;
+  global.WebSocket = WebSocket;
+};
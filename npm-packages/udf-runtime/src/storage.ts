@@ -9,7 +9,11 @@ export const storeBlob = async ({
   options,
 }: {
   blob: Blob;
-  options?: { sha256?: string };
+  options?: {
+    sha256?: string;
+    filename?: string;
+    tags?: Record<string, string>;
+  };
 }) => {
   if (!(blob instanceof Blob)) {
     throw new Error(
@@ -27,6 +31,8 @@ export const storeBlob = async ({
     blob.type,
     blob.size.toString(),
     digestHeader,
+    options?.filename,
+    options?.tags,
   );
   return storageId;
 };
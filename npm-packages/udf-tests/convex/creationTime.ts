@@ -1,3 +1,4 @@
+import { transactionTimestamp } from "convex/server";
 import { query, mutation } from "./_generated/server";
 
 export const createFiveDocuments = mutation(async ({ db }) => {
@@ -9,3 +10,8 @@ export const createFiveDocuments = mutation(async ({ db }) => {
 export const getDocumentsByCreationTime = query(({ db }) => {
   return db.query("table").withIndex("by_creation_time").collect();
 });
+
+export const insertAndReadTimestamp = mutation(async ({ db }) => {
+  await db.insert("table", { count: 0 });
+  return transactionTimestamp();
+});
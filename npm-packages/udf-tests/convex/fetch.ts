@@ -29,6 +29,7 @@ export default action(async () => {
     fetchBodyReader,
     fetchBodyReaderBigBody,
     fetchBodyReaderMultiPartBody,
+    fetchPostBodyLargeReadableStream,
     responseClone,
     fetchMultipartFormDataSuccess,
     fetchMultipartFormBadContentType,
@@ -288,6 +289,31 @@ async function fetchBodyReaderMultiPartBody() {
   assert.strictEqual(chunk3.done, true);
 }
 
+// The request body is larger than what `fetch` buffers, so it can only be
+// sent if reading the stream keeps pace with the upload.
+async function fetchPostBodyLargeReadableStream() {
+  const chunkSize = 64 * 1024;
+  const numChunks = 128;
+  let pulled = 0;
+  const body = new ReadableStream({
+    pull(controller) {
+      if (pulled === numChunks) {
+        controller.close();
+        return;
+      }
+      controller.enqueue(new Uint8Array(chunkSize).fill(pulled % 256));
+      pulled += 1;
+    },
+  });
+  const response = await fetch("http://localhost:4545/count_bytes", {
+    method: "POST",
+    body,
+  });
+  assert(response.ok);
+  assert.strictEqual(await response.text(), String(chunkSize * numChunks));
+  assert.strictEqual(pulled, numChunks);
+}
+
 async function responseClone() {
   const response = await fetch("http://localhost:4545/assets/fixture.json");
   const response1 = response.clone();
@@ -219,3 +219,32 @@ export const boundsOnDifferentFields = query(({ db }) => {
       .collect()
   );
 });
+
+export const aggregateByA = query(({ db }) => {
+  return db
+    .query("myTable")
+    .withIndex("by_a_b")
+    .aggregate({
+      groupBy: "a",
+      aggregates: {
+        count: { op: "count" },
+        withB: { op: "count", field: "b" },
+        sumB: { op: "sum", field: "b" },
+        avgB: { op: "avg", field: "b" },
+        maxB: { op: "max", field: "b" },
+      },
+    });
+});
+
+export const aggregateRange = query(({ db }, { a }: { a: number }) => {
+  return db
+    .query("myTable")
+    .withIndex("by_a_b", (q) => q.eq("a", a))
+    .aggregate({ aggregates: { minB: { op: "min", field: "b" } } });
+});
+
+export const aggregateByNonIndexedField = query(({ db }) => {
+  return db
+    .query("myTable")
+    .aggregate({ groupBy: "b", aggregates: { count: { op: "count" } } });
+});
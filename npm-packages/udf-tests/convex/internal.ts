@@ -25,3 +25,24 @@ export const myInternalMutation = internalMutation(() => {
 export const publicMutation = mutation(() => {
   // intentional noop.
 });
+
+export const slowInternalQuery = internalQuery({
+  timeoutMs: 5000,
+  handler: () => {
+    // intentional noop.
+  },
+});
+
+export const warmQuery = query({
+  warmInstances: 2,
+  handler: () => {
+    // intentional noop.
+  },
+});
+
+export const adminMutation = mutation({
+  requiredClaims: { role: "admin" },
+  handler: () => {
+    // intentional noop.
+  },
+});
@@ -33,9 +33,15 @@ export default defineSchema({
     filterA: v.string(),
     filterB: v.boolean(),
     id: v.string(),
-  }).vectorIndex("vector", {
-    vectorField: "vector",
-    dimensions: 4,
-    filterFields: ["filterA", "filterB"],
-  }),
+    text: v.optional(v.string()),
+  })
+    .vectorIndex("vector", {
+      vectorField: "vector",
+      dimensions: 4,
+      filterFields: ["filterA", "filterB"],
+    })
+    .searchIndex("text", {
+      searchField: "text",
+      filterFields: ["filterA"],
+    }),
 });
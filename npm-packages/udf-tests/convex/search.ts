@@ -38,6 +38,23 @@ export const createDocumentAndSearchForIt = mutation(async ({ db }) => {
     .collect();
 });
 
+export const populateFacets = mutation(async ({ db }) => {
+  await db.insert("messages", { body: "a", filterField: "news" });
+  await db.insert("messages", { body: "a a", filterField: "news" });
+  await db.insert("messages", { body: "a", filterField: "sports" });
+  await db.insert("messages", { body: "a" });
+  await db.insert("messages", { body: "b", filterField: "sports" });
+});
+
+export const searchFacets = query(
+  async ({ db }, { query }: { query: string }) => {
+    return db
+      .query("messages")
+      .withSearchIndex("by_body", (q) => q.search("body", query))
+      .facets(["filterField"]);
+  },
+);
+
 /**
  * UDFs for error cases
  */
@@ -106,3 +123,10 @@ export const insertMany = mutation(
     }
   },
 );
+
+export const facetsOnNonFilterField = query(async ({ db }) => {
+  return db
+    .query("messages")
+    .withSearchIndex("by_body", (q) => q.search("body", "a"))
+    .facets(["body" as any]);
+});
@@ -10,24 +10,28 @@ export const populate = mutation(async ({ db }) => {
       filterA: "A",
       filterB: true,
       id: "doc1",
+      text: "apple banana",
     },
     {
       vector: [1, 2, 3, 4],
       filterA: "B",
       filterB: true,
       id: "doc2",
+      text: "banana",
     },
     {
       vector: [1, 2, 3, 4],
       filterA: "C",
       filterB: false,
       id: "doc3",
+      text: "cherry",
     },
     {
       vector: [1, 2, 3, 4],
       filterA: "Z",
       filterB: true,
       id: "doc4",
+      text: "apple",
     },
   ];
   for (const vectorDoc of vectorDocs) {
@@ -130,3 +134,62 @@ export const noFilter = action({
     return "success";
   },
 });
+
+export const hybridSearch = action({
+  args: {},
+  handler: async (ctx) => {
+    // Every document is an equally good vector match, so the only text match
+    // ranks first.
+    const result = await ctx.hybridSearch("vectorTable", {
+      textIndex: "text",
+      textSearch: (q) => q.search("text", "cherry"),
+      vectorIndex: "vector",
+      vector: [1, 2, 3, 4],
+    });
+    const docs = await ctx.runQuery(api.vector_search.getDocuments, {
+      ids: result.map((r) => r._id),
+    });
+    assert.deepEqual(
+      ["doc1", "doc2", "doc3", "doc4"],
+      docs.map((d) => d.id).sort(),
+    );
+    assert.equal(docs[0].id, "doc3");
+    assert.isAbove(result[0]._score, result[1]._score);
+    return "success";
+  },
+});
+
+export const hybridSearchWithFilters = action({
+  args: {},
+  handler: async (ctx) => {
+    const result = await ctx.hybridSearch("vectorTable", {
+      textIndex: "text",
+      textSearch: (q) => q.search("text", "apple").eq("filterA", "Z"),
+      vectorIndex: "vector",
+      vector: [1, 2, 3, 4],
+      vectorFilter: (q) => q.eq("filterA", "B"),
+      limit: 1,
+      vectorWeight: 2,
+    });
+    const docs = await ctx.runQuery(api.vector_search.getDocuments, {
+      ids: result.map((r) => r._id),
+    });
+    assert.deepEqual(["doc2"], docs.map((d) => d.id));
+    return "success";
+  },
+});
+
+export const hybridSearchInvalidWeights = action({
+  args: {},
+  handler: async (ctx) => {
+    await ctx.hybridSearch("vectorTable", {
+      textIndex: "text",
+      textSearch: (q) => q.search("text", "apple"),
+      vectorIndex: "vector",
+      vector: [1, 2, 3, 4],
+      textWeight: 0,
+      vectorWeight: 0,
+    });
+    return "failure";
+  },
+});
@@ -0,0 +1,62 @@
+import { action, query } from "./_generated/server";
+
+const ECHO_URL = "ws://localhost:4548/echo";
+
+export const fromQuery = query(async () => {
+  new WebSocket(ECHO_URL);
+});
+
+export const echo = action(async () => {
+  const ws = new WebSocket(ECHO_URL, ["chat"]);
+  ws.binaryType = "arraybuffer";
+  await new Promise((resolve, reject) => {
+    ws.onopen = resolve;
+    ws.onerror = reject;
+  });
+  const closed = new Promise<CloseEvent>((resolve) =>
+    ws.addEventListener("close", resolve),
+  );
+  const messages: any[] = [];
+  const received = new Promise((resolve) => {
+    ws.onmessage = (event) => {
+      messages.push(event.data);
+      if (messages.length === 2) {
+        resolve(null);
+      }
+    };
+  });
+  ws.send("hello");
+  ws.send(new Uint8Array([1, 2, 3]));
+  await received;
+  ws.close(1000, "done");
+  const event = await closed;
+  return JSON.stringify({
+    protocol: ws.protocol,
+    text: messages[0],
+    binary: Array.from(new Uint8Array(messages[1])),
+    code: event.code,
+    reason: event.reason,
+    wasClean: event.wasClean,
+    readyState: ws.readyState,
+  });
+});
+
+export const invalidUrl = action(async () => {
+  const errors: string[] = [];
+  for (const url of ["not a url", "ftp://localhost:4548", `${ECHO_URL}#a`]) {
+    try {
+      new WebSocket(url);
+    } catch (e: any) {
+      errors.push(e.name);
+    }
+  }
+  return errors.join(",");
+});
+
+export const connectionRefused = action(async () => {
+  const ws = new WebSocket("ws://localhost:4549");
+  const event = await new Promise<CloseEvent>((resolve) => {
+    ws.onclose = resolve;
+  });
+  return `${event.code} ${event.wasClean}`;
+});